@@ -0,0 +1,208 @@
+//! Audit log command handler
+
+use super::resolve::resolve_entity;
+use crate::{style, AuditCommands};
+use chrono::{DateTime, Utc};
+use pctrl_core::diff::{display_value, FieldChange};
+use pctrl_core::history;
+use pctrl_core::redact::{is_secret_key, redact_secrets, REDACTED};
+use pctrl_core::{humanize, AuditAction, Ensured, EntityType};
+use pctrl_database::Database;
+use serde_json::Value;
+
+pub async fn handle(command: AuditCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        AuditCommands::List {
+            entity_type,
+            name,
+            limit,
+            verbose,
+        } => {
+            let entity_type: Option<EntityType> = entity_type
+                .map(|t| t.parse().map_err(|e: String| anyhow::anyhow!(e)))
+                .transpose()?;
+
+            let entries = match (entity_type, name) {
+                (Some(entity_type), Some(name)) => {
+                    // Removed entities can't be resolved anymore, fall back to the raw ID
+                    let id = resolve_entity(db, entity_type, &name)
+                        .await
+                        .map(|(id, _)| id)
+                        .unwrap_or(name);
+                    db.list_audit_entries(Some((entity_type, &id)), limit)
+                        .await?
+                }
+                _ => {
+                    let mut entries = db.list_audit_entries(None, limit).await?;
+                    if let Some(entity_type) = entity_type {
+                        entries.retain(|e| e.entity_type == entity_type);
+                    }
+                    entries
+                }
+            };
+
+            if entries.is_empty() {
+                outln!("No audit entries.");
+                return Ok(());
+            }
+
+            outln!("Audit log ({}):", entries.len());
+            outln!();
+            for entry in entries {
+                let action = match entry.action {
+                    AuditAction::Created => style::success_text(&entry.action.to_string()),
+                    AuditAction::Updated => style::info_text(&entry.action.to_string()),
+                    AuditAction::Removed => style::error_text(&entry.action.to_string()),
+                };
+                outln!(
+                    "  {} {} {} '{}' {}",
+                    style::dim(&humanize::relative_timestamp(&entry.created_at)),
+                    action,
+                    entry.entity_type,
+                    entry.summary,
+                    style::dim(&format!("by {}", entry.actor))
+                );
+                if verbose {
+                    print_changes(&entry.changes());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `<entity> history-view`: the entity as of `at`, or its timeline
+pub(crate) async fn history_view(
+    db: &Database,
+    entity_type: EntityType,
+    name: &str,
+    at: Option<DateTime<Utc>>,
+    timeline: bool,
+) -> anyhow::Result<()> {
+    let history = db.entity_history(entity_type, name).await?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No {} '{}' in the inventory or audit log",
+            entity_type,
+            name
+        )
+    })?;
+
+    if timeline {
+        let events = history::timeline(&history.entries);
+        if events.is_empty() {
+            outln!("No recorded changes to {} '{}'.", entity_type, history.name);
+            return Ok(());
+        }
+        outln!("History of {} '{}':", entity_type, history.name);
+        outln!();
+        let width = events
+            .iter()
+            .filter_map(|e| e.change.as_ref())
+            .map(|c| c.field.len())
+            .max()
+            .unwrap_or(0);
+        for event in &events {
+            let what = match (&event.change, event.action) {
+                (Some(change), _) => format!(
+                    "{:<width$}  {} → {}",
+                    change.field,
+                    style::error_text(&display_value(&change.old)),
+                    style::success_text(&display_value(&change.new)),
+                    width = width
+                ),
+                (None, AuditAction::Created) => style::success_text("created"),
+                (None, AuditAction::Removed) => style::error_text("removed"),
+                (None, AuditAction::Updated) => style::dim("updated (no field details)"),
+            };
+            outln!(
+                "  {}  {}  {}",
+                style::dim(&short_timestamp(&event.at)),
+                what,
+                style::dim(&format!("by {}", event.actor))
+            );
+        }
+        return Ok(());
+    }
+
+    let Some(at) = at else {
+        anyhow::bail!("Pass --at or --timeline");
+    };
+    let rebuilt = history::reconstruct(history.current.as_ref(), &history.entries, at);
+    let when = at.format("%Y-%m-%d %H:%M UTC");
+    outln!("{} '{}' as of {}", entity_type, history.name, when);
+    outln!();
+    match &rebuilt.state {
+        _ if !rebuilt.existed => outln!("  Didn't exist then."),
+        None => outln!("  Existed, but its fields weren't kept."),
+        Some(state) => {
+            let fields = history::fields(state);
+            let width = fields.iter().map(|(f, _)| f.len()).max().unwrap_or(0);
+            for (field, value) in fields {
+                let leaf = field.rsplit('.').next().unwrap_or(&field);
+                let shown = match value {
+                    Value::Null => display_value(&value),
+                    _ if is_secret_key(leaf) => REDACTED.to_string(),
+                    _ => redact_secrets(&display_value(&value)),
+                };
+                let flag = rebuilt
+                    .uncertain
+                    .get(&field)
+                    .map(|reason| style::warning_text(&format!("  ? {}", reason)))
+                    .unwrap_or_default();
+                outln!("  {:<width$}  {}{}", field, shown, flag, width = width);
+            }
+        }
+    }
+    if !rebuilt.gaps.is_empty() {
+        outln!();
+        for gap in &rebuilt.gaps {
+            outln!("  {}", style::warning_text(&format!("⚠ {}", gap)));
+        }
+    }
+    Ok(())
+}
+
+/// RFC 3339 as "2024-04-01 12:30" (UTC); other text as is
+fn short_timestamp(ts: &str) -> String {
+    DateTime::parse_from_rfc3339(ts)
+        .map(|t| t.with_timezone(&Utc).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| ts.to_string())
+}
+
+/// Changed fields, aligned, old value struck through in red, new in green
+pub(crate) fn print_changes(changes: &[FieldChange]) {
+    let width = changes.iter().map(|c| c.field.len()).max().unwrap_or(0);
+    for change in changes {
+        outln!(
+            "      {:<width$}  {} → {}",
+            change.field,
+            style::error_text(&style::strike(&display_value(&change.old))),
+            style::success_text(&display_value(&change.new)),
+            width = width
+        );
+    }
+}
+
+/// Outcome of an `edit`, with the fields it changed
+pub(crate) fn print_updated(entity: &str, name: &str, changes: &[FieldChange]) {
+    if changes.is_empty() {
+        outln!("No changes.");
+        return;
+    }
+    noteln!("✓ {} '{}' updated", entity, name);
+    print_changes(changes);
+}
+
+/// Value of an `edit` flag for an optional field: empty clears it
+pub(crate) fn clearable(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.trim().is_empty())
+}
+
+/// Outcome of an `add --ensure`, with the fields it changed
+pub(crate) fn print_ensured(entity: &str, name: &str, ensured: &Ensured) {
+    noteln!("✓ {} '{}' {}", entity, name, ensured);
+    if let Ensured::Updated(changes) = ensured {
+        print_changes(changes);
+    }
+}
@@ -0,0 +1,120 @@
+//! Backup and restore command handlers
+
+use pctrl_core::humanize;
+use pctrl_database::backup::{self, EXTENSION};
+use pctrl_database::Database;
+use std::path::{Path, PathBuf};
+
+/// Passphrase for non-interactive runs (cron, scripts)
+const PASSPHRASE_ENV: &str = "PCTRL_BACKUP_PASSPHRASE";
+
+/// Where `pctrl backup` writes by default: backups/ next to the database
+pub(crate) fn backup_dir(db: &Database) -> PathBuf {
+    db.path()
+        .parent()
+        .map(|p| p.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+/// Handle `pctrl backup`
+pub async fn handle_backup(
+    db: &Database,
+    to: Option<PathBuf>,
+    encrypt: bool,
+    keep: Option<u32>,
+) -> anyhow::Result<()> {
+    let extension = if encrypt { EXTENSION } else { "db" };
+    let name = format!(
+        "pctrl-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        extension
+    );
+    let path = match to {
+        Some(to) if to.is_dir() => to.join(name),
+        Some(to) => to,
+        None => {
+            let dir = backup_dir(db);
+            std::fs::create_dir_all(&dir)?;
+            dir.join(name)
+        }
+    };
+
+    if encrypt {
+        let passphrase = new_passphrase()?;
+        let size = db.backup_encrypted_to(&path, &passphrase).await?;
+        noteln!(
+            "✓ Encrypted backup of {} written to {}",
+            humanize::bytes(size),
+            path.display()
+        );
+        outln!("  Without the passphrase it can't be restored.");
+    } else {
+        db.backup_to(&path).await?;
+        noteln!("✓ Backup written to {}", path.display());
+    }
+
+    if let Some(keep) = keep {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let removed = backup::prune_backups(dir, keep as usize)?;
+        if !removed.is_empty() {
+            noteln!(
+                "  Removed {}, kept the newest {}",
+                humanize::count(removed.len() as u64, "older backup", "older backups"),
+                keep
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Handle `pctrl restore`
+pub async fn handle_restore(db: &Database, path: PathBuf) -> anyhow::Result<()> {
+    if !path.is_file() {
+        anyhow::bail!("Backup '{}' not found", path.display());
+    }
+    let passphrase = if backup::is_encrypted_backup(&path)? {
+        Some(passphrase("Passphrase: ")?)
+    } else {
+        None
+    };
+
+    let dir = backup_dir(db);
+    std::fs::create_dir_all(&dir)?;
+    let safety_copy = dir.join(format!(
+        "pctrl-pre-restore-{}.db",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let db_path = db.path();
+    db.close().await;
+    Database::restore_backup(&db_path, &path, passphrase.as_deref(), Some(&safety_copy)).await?;
+    noteln!("✓ Restored {} from {}", db_path.display(), path.display());
+    outln!("  The previous database is in {}", safety_copy.display());
+    Ok(())
+}
+
+fn env_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+pub(crate) fn passphrase(prompt: &str) -> anyhow::Result<String> {
+    match env_passphrase() {
+        Some(passphrase) => Ok(passphrase),
+        None => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+/// Prompt twice, unless the passphrase comes from the environment
+pub(crate) fn new_passphrase() -> anyhow::Result<String> {
+    if let Some(passphrase) = env_passphrase() {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("The passphrase can't be empty");
+    }
+    if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        anyhow::bail!("Passphrases don't match");
+    }
+    Ok(passphrase)
+}
@@ -0,0 +1,133 @@
+//! `pctrl domain sync` / `pctrl domain point`: a domain's Cloudflare zone
+//! and DNS record
+
+use super::propagation::server_addresses;
+use super::resolve::{find_credential, find_domain, find_server};
+use crate::style;
+use pctrl_cloudflare::{Change, CloudflareClient, DnsRecord};
+use pctrl_core::{CredentialData, Domain};
+use pctrl_database::Database;
+
+/// Client for an API token credential
+async fn client(db: &Database, credential: &str) -> anyhow::Result<CloudflareClient> {
+    let cred = find_credential(db, credential).await?;
+    let CredentialData::ApiToken { token, .. } = &cred.data else {
+        anyhow::bail!("Credential '{}' is not an API token", cred.name);
+    };
+    Ok(CloudflareClient::new(token))
+}
+
+/// The domain's zone ID: the stored one, else looked up by name
+async fn zone_id(cloudflare: &CloudflareClient, domain: &Domain) -> anyhow::Result<String> {
+    if let Some(id) = &domain.cloudflare_zone_id {
+        return Ok(id.clone());
+    }
+    let zones = cloudflare.list_zones().await?;
+    match pctrl_cloudflare::zone_for(&zones, &domain.domain) {
+        Some(zone) => Ok(zone.id.clone()),
+        None => anyhow::bail!(
+            "No zone for '{}' in this Cloudflare account (zones: {})",
+            domain.domain,
+            if zones.is_empty() {
+                "none".to_string()
+            } else {
+                zones
+                    .iter()
+                    .map(|z| z.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        ),
+    }
+}
+
+/// Look up the domain's zone and record by name and store their IDs
+pub(crate) async fn sync(db: &Database, domain: &str, credential: &str) -> anyhow::Result<()> {
+    let mut dom = find_domain(db, domain).await?;
+    let cloudflare = client(db, credential).await?;
+
+    // A stored zone may be stale; look it up again
+    dom.cloudflare_zone_id = None;
+    let zone_id = zone_id(&cloudflare, &dom).await?;
+    let records = cloudflare.list_dns_records(&zone_id).await?;
+    let record = pctrl_cloudflare::find_record(&records, &dom.domain, None);
+
+    dom.cloudflare_zone_id = Some(zone_id.clone());
+    dom.cloudflare_record_id = record.map(|r| r.id.clone());
+    db.update_domain(&dom).await?;
+
+    noteln!("✓ Cloudflare IDs of {} updated:", dom.domain);
+    noteln!();
+    outln!("  Zone:   {}", zone_id);
+    match record {
+        Some(record) => outln!("  Record: {} ({})", record.id, describe(record)),
+        None => outln!(
+            "  Record: {}",
+            style::dim("none (no A, AAAA or CNAME record of that name)")
+        ),
+    }
+    Ok(())
+}
+
+fn describe(record: &DnsRecord) -> String {
+    format!(
+        "{} {}{}",
+        record.record_type,
+        record.content,
+        if record.proxied { ", proxied" } else { "" }
+    )
+}
+
+/// Create or update the domain's A/AAAA record so it points at the server
+pub(crate) async fn point(
+    db: &Database,
+    domain: &str,
+    server: &str,
+    credential: &str,
+    proxied: Option<bool>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let mut dom = find_domain(db, domain).await?;
+    let server = find_server(db, server).await?;
+    let addresses = server_addresses(&server)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no address", server.name))?;
+    // IPv4 first: an A record is what most clients need
+    let ip = addresses
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or(addresses.first())
+        .copied()
+        .expect("addresses are not empty");
+
+    let cloudflare = client(db, credential).await?;
+    let zone_id = zone_id(&cloudflare, &dom).await?;
+    let records = cloudflare.list_dns_records(&zone_id).await?;
+    let change = pctrl_cloudflare::plan_point(
+        &records,
+        &dom.domain,
+        dom.cloudflare_record_id.as_deref(),
+        ip,
+        proxied,
+    );
+
+    if dry_run {
+        match &change {
+            Change::Unchanged(_) => outln!("Nothing to change: {}", change),
+            _ => outln!("Would {}", change),
+        }
+        outln!("{}", style::dim("Dry run, nothing changed."));
+        return Ok(());
+    }
+
+    let record = cloudflare.apply(&zone_id, &change).await?;
+    dom.cloudflare_zone_id = Some(zone_id);
+    dom.cloudflare_record_id = Some(record.id);
+    dom.server_id = Some(server.id.clone());
+    db.update_domain(&dom).await?;
+    match change {
+        Change::Unchanged(_) => noteln!("✓ {}", change),
+        _ => noteln!("✓ Cloudflare: {}", change),
+    }
+    Ok(())
+}
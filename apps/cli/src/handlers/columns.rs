@@ -0,0 +1,90 @@
+//! `--columns`, `--filter` and `--sort` of list commands (see
+//! [`pctrl_core::columns`])
+
+use crate::style;
+use pctrl_core::columns::{KnownKeys, ListQuery, ListRow};
+use pctrl_core::table_export::{flatten, ExportTable};
+use pctrl_core::{EntityType, Server};
+use pctrl_database::Database;
+
+/// The query of a server list; `None` when no option was given
+pub(crate) async fn server_query(
+    db: &Database,
+    columns: &[String],
+    filters: &[String],
+    sort: &[String],
+) -> anyhow::Result<Option<ListQuery>> {
+    if columns.is_empty() && filters.is_empty() && sort.is_empty() {
+        return Ok(None);
+    }
+    let known = KnownKeys {
+        meta: db.entity_metadata_keys(EntityType::Server).await?,
+        facts: db.server_fact_keys().await?,
+    };
+    let query = ListQuery::new(ExportTable::Servers, columns, filters, sort, &known)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    Ok(Some(query))
+}
+
+/// Servers as rows, with metadata and facts loaded in one batch each, and
+/// only when the query reads them
+pub(crate) async fn server_rows(
+    db: &Database,
+    servers: &[Server],
+    query: &ListQuery,
+) -> anyhow::Result<Vec<ListRow>> {
+    let ids: Vec<String> = servers.iter().map(|s| s.id.clone()).collect();
+    let mut meta = if query.needs_meta() {
+        db.entity_metadata_for(EntityType::Server, &ids).await?
+    } else {
+        Default::default()
+    };
+    let mut facts = if query.needs_facts() {
+        db.server_facts_for(&ids).await?
+    } else {
+        Default::default()
+    };
+
+    servers
+        .iter()
+        .map(|server| {
+            Ok(ListRow {
+                id: server.id.clone(),
+                fields: flatten(serde_json::to_value(server)?),
+                meta: meta.remove(&server.id).unwrap_or_default(),
+                facts: facts.remove(&server.id).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// The selected columns as an aligned table with a header
+pub(crate) fn print_table(query: &ListQuery, rows: &[ListRow]) {
+    let header: Vec<String> = query.columns.iter().map(|c| c.label()).collect();
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| query.cells(row)).collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |values: &[String]| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    outln!("  {}", style::bold(&line(&header)));
+    for row in &cells {
+        outln!("  {}", line(row));
+    }
+}
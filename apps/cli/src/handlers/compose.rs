@@ -0,0 +1,189 @@
+//! Compose stack command handlers (`pctrl compose`, `pctrl server compose`)
+
+use super::resolve::find_server;
+use crate::{style, ComposeCommands};
+use pctrl_core::compose::{self, ComposeProject, ComposeState, SERVICE_LABEL};
+use pctrl_core::{humanize, Container, ContainerStatus};
+use pctrl_database::Database;
+use std::collections::HashMap;
+
+/// Record the stacks formed by a server's containers as just discovered;
+/// stacks no container belongs to anymore are marked gone, or forgotten
+/// with `prune`
+pub(crate) async fn record(
+    db: &Database,
+    server_id: &str,
+    containers: &[Container],
+    prune: bool,
+) -> anyhow::Result<Vec<ComposeProject>> {
+    let recorded = db.list_compose_projects(Some(server_id)).await?;
+    let stacks = compose::reconcile(&recorded, compose::group(server_id, containers), prune);
+    db.replace_compose_projects(server_id, &stacks).await?;
+    Ok(stacks)
+}
+
+pub async fn handle(command: ComposeCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        ComposeCommands::List { server, json } => {
+            let server = match server {
+                Some(name) => Some(find_server(db, &name).await?),
+                None => None,
+            };
+            let stacks = db
+                .list_compose_projects(server.as_ref().map(|s| s.id.as_str()))
+                .await?;
+            if json {
+                outln!("{}", serde_json::to_string_pretty(&stacks)?);
+                return Ok(());
+            }
+            if stacks.is_empty() {
+                outln!("No compose stacks recorded.");
+                outln!(
+                    "  {}",
+                    style::dim("Record them with: pctrl server compose <server>")
+                );
+                return Ok(());
+            }
+
+            let names: HashMap<String, String> = db
+                .list_servers()
+                .await?
+                .into_iter()
+                .map(|s| (s.id, s.name))
+                .collect();
+            match &server {
+                Some(server) => outln!("Compose stacks on '{}' ({}):", server.name, stacks.len()),
+                None => outln!("Compose stacks ({}):", stacks.len()),
+            }
+            outln!();
+            let server_width = stacks
+                .iter()
+                .map(|s| {
+                    names
+                        .get(&s.server_id)
+                        .map_or(s.server_id.len(), String::len)
+                })
+                .max()
+                .unwrap_or(0);
+            for line in stack_lines(&stacks) {
+                match &server {
+                    Some(_) => outln!("  {}", line.text),
+                    None => outln!(
+                        "  {:<width$}  {}",
+                        names.get(&line.server_id).unwrap_or(&line.server_id),
+                        line.text,
+                        width = server_width
+                    ),
+                }
+            }
+        }
+
+        ComposeCommands::Ps {
+            server,
+            stack,
+            json,
+        } => {
+            let server = find_server(db, &server).await?;
+            let containers = db.list_containers_for_server(&server.id).await?;
+            let members = compose::containers_of(&containers, &stack);
+            if members.is_empty() {
+                let stacks = db.list_compose_projects(Some(&server.id)).await?;
+                match stacks.iter().find(|s| s.name == stack) {
+                    Some(recorded) if recorded.state == ComposeState::Gone => anyhow::bail!(
+                        "Stack '{}' on '{}' is gone; pctrl server compose {} --prune forgets it",
+                        stack,
+                        server.name,
+                        server.name
+                    ),
+                    _ if stacks.is_empty() => anyhow::bail!(
+                        "No compose stacks recorded on '{}'; run pctrl server compose {}",
+                        server.name,
+                        server.name
+                    ),
+                    _ => anyhow::bail!(
+                        "No stack '{}' on '{}' (known: {})",
+                        stack,
+                        server.name,
+                        stacks
+                            .iter()
+                            .map(|s| s.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+            }
+            if json {
+                outln!("{}", serde_json::to_string_pretty(&members)?);
+                return Ok(());
+            }
+
+            outln!(
+                "Stack '{}' on '{}' ({}):",
+                stack,
+                server.name,
+                humanize::count(members.len() as u64, "container", "containers")
+            );
+            outln!();
+            let service = |c: &Container| {
+                compose::labels(c)
+                    .get(SERVICE_LABEL)
+                    .cloned()
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            let name_width = members.iter().map(|c| c.name.len()).max().unwrap_or(0);
+            let service_width = members.iter().map(|c| service(c).len()).max().unwrap_or(0);
+            for container in members {
+                let status = container.status.to_string();
+                let status = match container.status {
+                    ContainerStatus::Running => style::success_text(&status),
+                    ContainerStatus::Exited => style::error_text(&status),
+                    _ => style::warning_text(&status),
+                };
+                outln!(
+                    "  {:<nw$}  {:<sw$}  {:<10}  {}",
+                    container.name,
+                    service(container),
+                    status,
+                    container.image.as_deref().unwrap_or("-"),
+                    nw = name_width,
+                    sw = service_width
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A stack as one line: name, state, running/services, compose file
+pub(crate) struct StackLine {
+    pub server_id: String,
+    pub text: String,
+}
+
+/// Aligned lines for `stacks`
+pub(crate) fn stack_lines(stacks: &[ComposeProject]) -> Vec<StackLine> {
+    let width = stacks.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    stacks
+        .iter()
+        .map(|stack| {
+            let state = format!("{:<7}", stack.state);
+            let state = match stack.state {
+                ComposeState::Up => style::success_text(&state),
+                ComposeState::Partial => style::warning_text(&state),
+                ComposeState::Down => style::error_text(&state),
+                ComposeState::Gone => style::dim(&state),
+            };
+            StackLine {
+                server_id: stack.server_id.clone(),
+                text: format!(
+                    "{:<width$}  {}  {:>5}  {}",
+                    stack.name,
+                    state,
+                    format!("{}/{}", stack.running, stack.services),
+                    style::dim(stack.config_path.as_deref().unwrap_or("-")),
+                    width = width
+                ),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,51 @@
+//! Settings command handler
+
+use crate::{style, ConfigCommands};
+use pctrl_core::settings::{self, SETTINGS};
+use pctrl_database::Database;
+
+pub async fn handle(command: ConfigCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        ConfigCommands::List => {
+            let values = db.list_settings().await?;
+            outln!("Settings:");
+            outln!();
+            for def in SETTINGS {
+                let value = values.iter().find(|(k, _)| k == def.key).map(|(_, v)| v);
+                let shown = match (value, def.default) {
+                    (Some(v), _) => v.clone(),
+                    (None, Some(default)) => style::dim(&format!("{} (default)", default)),
+                    (None, None) => style::dim("-"),
+                };
+                outln!("  {:<14} {}", def.key, shown);
+                outln!("  {:<14} {}", "", style::dim(def.description));
+            }
+        }
+
+        ConfigCommands::Get { key } => {
+            let def =
+                settings::find(&key).ok_or_else(|| anyhow::anyhow!("Unknown setting: {}", key))?;
+            match db.get_setting(&key).await?.as_deref().or(def.default) {
+                Some(value) => outln!("{}", value),
+                None => outln!("{}", style::dim("(unset)")),
+            }
+        }
+
+        ConfigCommands::Set { key, value } => {
+            db.set_setting(&key, &value).await?;
+            noteln!("✓ {} = {}", key, value.trim());
+        }
+
+        ConfigCommands::Unset { key } => {
+            let def =
+                settings::find(&key).ok_or_else(|| anyhow::anyhow!("Unknown setting: {}", key))?;
+            db.unset_setting(&key).await?;
+            match def.default {
+                Some(default) => noteln!("✓ {} reset to default ({})", key, default),
+                None => noteln!("✓ {} unset", key),
+            }
+        }
+    }
+
+    Ok(())
+}
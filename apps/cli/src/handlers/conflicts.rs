@@ -0,0 +1,97 @@
+//! Deciding conflicts at the terminal, for `pctrl merge`, `pctrl import`
+//! and `pctrl snapshot restore`
+
+use crate::style;
+use pctrl_core::conflict::{
+    self, side_by_side, Answer, Choice, Conflict, Decision, InvalidAnswer, Prompt, Prompter, Side,
+};
+use pctrl_core::diff::display_value;
+use std::io::{self, BufRead, Write};
+
+/// Values wider than this are cut in the side-by-side view
+const MAX_VALUE_WIDTH: usize = 36;
+
+/// Ask about every conflict on the terminal; `None` if the user quit
+pub(crate) fn resolve(conflicts: &[Conflict]) -> anyhow::Result<Option<Vec<Decision>>> {
+    if conflicts.is_empty() {
+        return Ok(Some(Vec::new()));
+    }
+    let decisions = conflict::resolve(conflicts, &mut TerminalPrompter)?;
+    outln!();
+    Ok(decisions)
+}
+
+struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn ask(&mut self, prompt: &Prompt<'_>) -> io::Result<Answer> {
+        match prompt {
+            Prompt::Conflict {
+                conflict,
+                position,
+                total,
+            } => {
+                outln!();
+                outln!(
+                    "{} {} {}",
+                    style::dim(&format!("[{}/{}]", position, total)),
+                    conflict.kind,
+                    style::bold(&conflict.name)
+                );
+                for line in side_by_side(conflict, MAX_VALUE_WIDTH).lines() {
+                    outln!("  {}", line);
+                }
+                loop {
+                    let answer = read_answer(&format!(
+                        "Keep [l]ocal, take [i]ncoming, merge [f]ields, [s]kip, [q]uit \
+                         (L/I/S for every {}): ",
+                        conflict.kind
+                    ))?;
+                    let answer = match answer.as_deref() {
+                        None | Some("q") => Answer::Quit,
+                        Some("l") => Answer::Choose(Choice::KeepLocal),
+                        Some("i") => Answer::Choose(Choice::TakeIncoming),
+                        Some("s") => Answer::Choose(Choice::Skip),
+                        Some("L") => Answer::ChooseForKind(Choice::KeepLocal),
+                        Some("I") => Answer::ChooseForKind(Choice::TakeIncoming),
+                        Some("S") => Answer::ChooseForKind(Choice::Skip),
+                        Some("f") | Some("F") => Answer::MergeFields,
+                        Some(_) => continue,
+                    };
+                    return Ok(answer);
+                }
+            }
+            Prompt::Field { field, .. } => loop {
+                let answer = read_answer(&format!(
+                    "  {}: [l]ocal {} / [i]ncoming {}, [q]uit: ",
+                    field.field,
+                    display_value(&field.old),
+                    display_value(&field.new)
+                ))?;
+                let answer = match answer.as_deref().map(str::to_lowercase).as_deref() {
+                    None | Some("q") => Answer::Quit,
+                    Some("l") => Answer::Field(Side::Local),
+                    Some("i") => Answer::Field(Side::Incoming),
+                    Some(_) => continue,
+                };
+                return Ok(answer);
+            },
+        }
+    }
+
+    fn rejected(&mut self, reason: &InvalidAnswer) {
+        outln!("  {}", style::warning_text(&reason.to_string()));
+    }
+}
+
+/// A trimmed line from stdin, case kept (capitals apply to a whole kind);
+/// `None` at end of input
+fn read_answer(prompt: &str) -> io::Result<Option<String>> {
+    out!("{}", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(answer.trim().to_string()))
+}
@@ -0,0 +1,293 @@
+//! Coolify command handler
+
+use super::hints;
+use crate::{style, CoolifyCommands};
+use pctrl_coolify::{CoolifyManager, TokenCheck};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::{hyperlink, CoolifyInstance};
+use pctrl_database::Database;
+
+pub async fn handle(command: CoolifyCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        CoolifyCommands::Instances => {
+            let instances = db.load_config().await?.coolify_instances;
+            if instances.is_empty() {
+                outln!("No Coolify instances configured.");
+                hints::show(db, Event::Empty(Listing::CoolifyInstances)).await?;
+                return Ok(());
+            }
+
+            outln!("Coolify instances ({}):", instances.len());
+            outln!();
+            for instance in instances {
+                outln!(
+                    "  🚀 {} {} {}",
+                    instance.name,
+                    style::dim(&format!("[{}]", instance.id)),
+                    hyperlink::web(&instance.url)
+                );
+            }
+        }
+
+        CoolifyCommands::Add {
+            name,
+            url,
+            token,
+            skip_verify,
+        } => {
+            let instance = CoolifyInstance {
+                id: name.to_lowercase().replace(' ', "-"),
+                name,
+                url: url.trim_end_matches('/').to_string(),
+                api_key: token,
+            };
+            let version = if skip_verify {
+                None
+            } else {
+                let mut coolify = CoolifyManager::new();
+                coolify.add_instance(instance.clone());
+                match coolify.validate_token(&instance.id).await {
+                    Ok(TokenCheck::Valid(version)) => Some(version),
+                    Ok(TokenCheck::Rejected(reason)) => anyhow::bail!(
+                        "{} rejected the API token: {} (--skip-verify to save anyway)",
+                        instance.url,
+                        reason
+                    ),
+                    Err(e) => anyhow::bail!(
+                        "Could not reach {}: {} (--skip-verify to save anyway)",
+                        instance.url,
+                        e
+                    ),
+                }
+            };
+            db.save_coolify_instance(&instance).await?;
+
+            noteln!("✓ Coolify instance added:");
+            noteln!();
+            print_instance(&instance, false);
+            if let Some(version) = version {
+                noteln!("  {} {}", style::dim("Version:"), version);
+            }
+            if !db.is_encrypted() {
+                noteln!();
+                noteln!(
+                    "{}",
+                    style::dim(
+                        "The database has no password, so the API key is stored unencrypted"
+                    )
+                );
+            }
+        }
+
+        CoolifyCommands::Check { id } => {
+            let (coolify, instance) = connect(db, &id).await?;
+            match coolify.validate_token(&instance.id).await {
+                Ok(TokenCheck::Valid(version)) => outln!(
+                    "{} {} {}",
+                    style::success_text("✓"),
+                    instance.name,
+                    style::dim(&format!("Coolify {}", version))
+                ),
+                Ok(TokenCheck::Rejected(reason)) => {
+                    anyhow::bail!("{} rejected the API token: {}", instance.name, reason)
+                }
+                Err(e) => anyhow::bail!("Could not reach {}: {}", instance.name, e),
+            }
+        }
+
+        CoolifyCommands::Show { id, reveal } => {
+            let instance = db
+                .get_coolify_instance(&id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Coolify instance '{}' not found", id))?;
+            outln!();
+            print_instance(&instance, reveal);
+            outln!();
+        }
+
+        CoolifyCommands::Remove { id } => {
+            let instance = db.get_coolify_instance(&id).await?;
+            match instance {
+                Some(instance) if db.remove_coolify_instance(&instance.id).await? => {
+                    noteln!("✓ Coolify instance '{}' removed", instance.name)
+                }
+                _ => outln!("✗ Coolify instance '{}' not found", id),
+            }
+        }
+
+        CoolifyCommands::Apps { id, json } => {
+            let (coolify, instance) = connect(db, &id).await?;
+            let applications = coolify.list_applications(&instance.id).await?;
+            let services = coolify.list_services(&instance.id).await?;
+            if json {
+                let output = serde_json::json!({
+                    "applications": applications,
+                    "services": services,
+                });
+                outln!("{}", serde_json::to_string_pretty(&output)?);
+                return Ok(());
+            }
+
+            outln!(
+                "{}",
+                style::header(&format!(
+                    "Applications on {} ({})",
+                    instance.name,
+                    applications.len()
+                ))
+            );
+            for app in &applications {
+                let url = app
+                    .fqdn
+                    .as_deref()
+                    .and_then(|f| f.split(',').next())
+                    .map(|f| format!("  {}", hyperlink::web(f)))
+                    .unwrap_or_default();
+                outln!(
+                    "  {} {} {} {}{}",
+                    status_dot(app.status.as_deref()),
+                    app.name,
+                    style::dim(&app.uuid),
+                    style::dim(app.status.as_deref().unwrap_or("unknown")),
+                    url
+                );
+            }
+            outln!();
+            outln!(
+                "{}",
+                style::header(&format!(
+                    "Services on {} ({})",
+                    instance.name,
+                    services.len()
+                ))
+            );
+            for service in &services {
+                outln!(
+                    "  {} {} {} {}",
+                    status_dot(service.status.as_deref()),
+                    service.name,
+                    style::dim(&service.uuid),
+                    style::dim(service.service_type.as_deref().unwrap_or(""))
+                );
+            }
+        }
+
+        CoolifyCommands::Deploy { id, uuid, force } => {
+            let (coolify, instance) = connect(db, &id).await?;
+            let queued = coolify.deploy(&instance.id, &uuid, force).await?;
+            if queued.is_empty() {
+                anyhow::bail!("Coolify queued no deployment for '{}'", uuid);
+            }
+            for deployment in queued {
+                noteln!(
+                    "✓ Deployment of {} queued{}: {}",
+                    deployment.resource_uuid,
+                    if force { " (no build cache)" } else { "" },
+                    deployment.deployment_uuid
+                );
+                noteln!(
+                    "{}",
+                    style::dim(&format!(
+                        "  pctrl coolify deployment {} {}",
+                        instance.id, deployment.deployment_uuid
+                    ))
+                );
+            }
+        }
+
+        CoolifyCommands::Deployment {
+            id,
+            deployment,
+            lines,
+            json,
+        } => {
+            let (coolify, instance) = connect(db, &id).await?;
+            let log = coolify
+                .get_deployment_status(&instance.id, &deployment)
+                .await?;
+            if json {
+                outln!("{}", serde_json::to_string_pretty(&log)?);
+                return Ok(());
+            }
+
+            let status = match log.status.as_str() {
+                "finished" => style::success_text(&log.status),
+                "failed" | "cancelled-by-user" => style::error_text(&log.status),
+                _ => style::warning_text(&log.status),
+            };
+            outln!(
+                "  {} {}",
+                style::dim("Application:"),
+                log.application_name.as_deref().unwrap_or("-")
+            );
+            outln!("  {} {}", style::dim("Status:"), status);
+            if let Some(commit) = &log.commit {
+                let message = log.commit_message.as_deref().unwrap_or("").lines().next();
+                outln!(
+                    "  {} {} {}",
+                    style::dim("Commit:"),
+                    commit.chars().take(8).collect::<String>(),
+                    message.unwrap_or_default()
+                );
+            }
+            let log_lines = log.lines();
+            if !log_lines.is_empty() {
+                outln!();
+                for line in &log_lines[log_lines.len().saturating_sub(lines)..] {
+                    for text in line.output.lines() {
+                        if line.stream == "stderr" {
+                            outln!("  {}", style::error_text(text));
+                        } else {
+                            outln!("  {}", text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A client for one stored instance, by ID or name
+async fn connect(db: &Database, id: &str) -> anyhow::Result<(CoolifyManager, CoolifyInstance)> {
+    let instance = db
+        .get_coolify_instance(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Coolify instance '{}' not found", id))?;
+    let mut coolify = CoolifyManager::new();
+    coolify.add_instance(instance.clone());
+    Ok((coolify, instance))
+}
+
+/// Green for running, red for exited, dim for anything else; Coolify
+/// reports e.g. `running:healthy`
+fn status_dot(status: Option<&str>) -> String {
+    match status.and_then(|s| s.split(':').next()) {
+        Some("running") => style::success_text("●"),
+        Some("exited") | Some("degraded") => style::error_text("●"),
+        _ => style::dim("●"),
+    }
+}
+
+fn print_instance(instance: &CoolifyInstance, reveal: bool) {
+    outln!("  {} {}", style::dim("Name:"), instance.name);
+    outln!("  {} {}", style::dim("ID:"), instance.id);
+    outln!("  {} {}", style::dim("URL:"), hyperlink::web(&instance.url));
+    let key = if reveal {
+        instance.api_key.clone()
+    } else {
+        mask(&instance.api_key)
+    };
+    outln!("  {} {}", style::dim("API key:"), key);
+}
+
+/// The first four characters of a key, enough to tell keys apart; short
+/// keys are hidden completely
+fn mask(key: &str) -> String {
+    if key.chars().count() < 12 {
+        return "***".to_string();
+    }
+    let shown: String = key.chars().take(4).collect();
+    format!("{}***", shown)
+}
@@ -1,8 +1,17 @@
 //! Credential command handlers
 
+use super::audit::print_ensured;
+use super::hints;
+use super::references::{self, guard_remove};
+use super::resolve::{find_credential, lookup, name_taken, ref_tag};
 use crate::style;
-use pctrl_core::{Credential, CredentialData, CredentialType};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::{
+    humanize, hyperlink, Credential, CredentialData, CredentialPatch, CredentialType, EntityType,
+};
 use pctrl_database::Database;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 /// Handle credential list command
@@ -10,18 +19,17 @@ pub async fn handle_list(db: &Database) -> anyhow::Result<()> {
     let credentials = db.list_credentials().await?;
 
     if credentials.is_empty() {
-        println!("{}", style::dim("No credentials found."));
-        println!(
-            "{}",
-            style::dim("Add one with: pctrl credential add <name> --type ssh --user root --key ~/.ssh/id_rsa")
-        );
+        outln!("{}", style::dim("No credentials found."));
+        hints::show(db, Event::Empty(Listing::Credentials)).await?;
         return Ok(());
     }
 
-    println!("{}", style::header("Credentials"));
-    println!();
+    outln!("{}", style::header("Credentials"));
+    outln!();
 
+    let refs = db.short_refs(EntityType::Credential).await?;
     for cred in credentials {
+        let inline = db.get_key_material(&cred.id).await?.is_some();
         let type_badge = match cred.credential_type {
             CredentialType::SshKey => style::info_text("[SSH]"),
             CredentialType::SshAgent => style::info_text("[AGENT]"),
@@ -31,33 +39,43 @@ pub async fn handle_list(db: &Database) -> anyhow::Result<()> {
         };
 
         let details = match &cred.data {
+            CredentialData::SshKey {
+                username,
+                port,
+                key_path,
+                ..
+            } if inline => format!("{}@:{} (inline, from {})", username, port, key_path),
             CredentialData::SshKey {
                 username,
                 port,
                 key_path,
                 ..
             } => {
-                format!("{}@:{} ({})", username, port, key_path)
+                format!("{}@:{} ({})", username, port, hyperlink::file(key_path))
             }
             CredentialData::SshAgent { username, port } => {
                 format!("{}@:{} (agent)", username, port)
             }
-            CredentialData::ApiToken { url, .. } => url.as_deref().unwrap_or("no url").to_string(),
-            CredentialData::BasicAuth { username, url, .. } => {
-                format!("{} @ {}", username, url.as_deref().unwrap_or("no url"))
+            CredentialData::ApiToken { url, .. } | CredentialData::OAuth { url, .. } => {
+                url.as_deref().map_or("no url".to_string(), hyperlink::web)
             }
-            CredentialData::OAuth { url, .. } => url.as_deref().unwrap_or("no url").to_string(),
+            CredentialData::BasicAuth { username, url, .. } => format!(
+                "{} @ {}",
+                username,
+                url.as_deref().map_or("no url".to_string(), hyperlink::web)
+            ),
         };
 
-        println!(
-            "  {} {} {}",
+        outln!(
+            "  {} {}{} {}",
             type_badge,
             style::bold(&cred.name),
+            ref_tag(&refs, &cred.id),
             style::dim(&details)
         );
     }
 
-    println!();
+    outln!();
     Ok(())
 }
 
@@ -70,33 +88,68 @@ pub async fn handle_add(
     user: Option<String>,
     port: Option<u16>,
     key: Option<String>,
+    store_inline: bool,
     token: Option<String>,
     password: Option<String>,
     url: Option<String>,
+    ensure: bool,
 ) -> anyhow::Result<()> {
     let credential_type: CredentialType =
         cred_type.parse().map_err(|e: String| anyhow::anyhow!(e))?;
 
+    // Expand ~ to home directory
+    let key = key.map(|key_path| super::expand_home(&key_path).to_string_lossy().to_string());
+    // Read before anything is saved, so a bad file leaves nothing behind
+    let key_material = match &key {
+        Some(path) if store_inline => {
+            if credential_type != CredentialType::SshKey {
+                anyhow::bail!("--store-inline is for SSH key credentials");
+            }
+            Some(read_private_key(Path::new(path))?)
+        }
+        _ => None,
+    };
+
+    if ensure {
+        if let Some(existing) = db.get_credential_by_name(&name).await? {
+            let id = existing.id.clone();
+            // Only the given fields change; nothing is required
+            let patch = CredentialPatch {
+                username: user,
+                port,
+                key_path: key,
+                token,
+                password,
+                url,
+            };
+            let credential = Credential {
+                credential_type,
+                ..existing
+            };
+            let ensured = db.ensure_credential(&credential, &patch).await?;
+            print_ensured("Credential", &name, &ensured);
+            store_key_inline(db, &id, key_material.as_deref()).await?;
+            return Ok(());
+        }
+    }
+
+    let patch = CredentialPatch {
+        username: user.clone(),
+        port,
+        key_path: key.clone(),
+        token: token.clone(),
+        password: password.clone(),
+        url: url.clone(),
+    };
     let data = match credential_type {
         CredentialType::SshKey => {
             let username = user.ok_or_else(|| anyhow::anyhow!("SSH credentials require --user"))?;
             let key_path = key.ok_or_else(|| anyhow::anyhow!("SSH credentials require --key"))?;
 
-            // Expand ~ to home directory
-            let expanded_key_path = if let Some(stripped) = key_path.strip_prefix("~/") {
-                if let Some(home) = dirs::home_dir() {
-                    home.join(stripped).to_string_lossy().to_string()
-                } else {
-                    key_path
-                }
-            } else {
-                key_path
-            };
-
             CredentialData::SshKey {
                 username,
                 port: port.unwrap_or(22),
-                key_path: expanded_key_path,
+                key_path,
                 passphrase: password,
             }
         }
@@ -144,26 +197,65 @@ pub async fn handle_add(
         notes: None,
     };
 
-    db.save_credential(&credential).await?;
-    println!("{} Credential '{}' added.", style::success_text("✓"), name);
+    if ensure {
+        let ensured = db.ensure_credential(&credential, &patch).await?;
+        print_ensured("Credential", &name, &ensured);
+        store_key_inline(db, &credential.id, key_material.as_deref()).await?;
+        return Ok(());
+    }
+
+    db.create_credential(&credential)
+        .await
+        .map_err(|e| name_taken("Credential", e))?;
+    outln!("{} Credential '{}' added.", style::success_text("✓"), name);
+    store_key_inline(db, &credential.id, key_material.as_deref()).await?;
 
     Ok(())
 }
 
+/// The text of a private key file
+fn read_private_key(path: &Path) -> anyhow::Result<String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Can't read key file {}: {}", path.display(), e))?;
+    if !text.contains("PRIVATE KEY-----") {
+        anyhow::bail!("{} doesn't look like a private key", path.display());
+    }
+    Ok(text)
+}
+
+/// Store `key` with the credential, if `--store-inline` read one
+async fn store_key_inline(db: &Database, id: &str, key: Option<&str>) -> anyhow::Result<()> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+    db.set_key_material(id, Some(key)).await?;
+    outln!(
+        "  {}",
+        style::dim("The key is stored inline; the key file isn't needed anymore")
+    );
+    if !db.is_encrypted() {
+        outln!(
+            "  {}",
+            style::warning_text("⚠ The database has no password, so the key is stored unencrypted")
+        );
+    }
+    Ok(())
+}
+
 /// Handle credential show command
 pub async fn handle_show(db: &Database, name: String) -> anyhow::Result<()> {
-    let credential = db
-        .get_credential_by_name(&name)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Credential '{}' not found", name))?;
+    let credential = find_credential(db, &name).await?;
 
-    println!(
+    outln!(
         "{}",
         style::header(&format!("Credential: {}", credential.name))
     );
-    println!();
-    println!("  {} {}", style::dim("ID:"), credential.id);
-    println!("  {} {}", style::dim("Type:"), credential.credential_type);
+    outln!();
+    outln!("  {} {}", style::dim("ID:"), credential.id);
+    if let Some(short_ref) = db.short_ref(EntityType::Credential, &credential.id).await? {
+        outln!("  {} {}", style::dim("Ref:"), short_ref);
+    }
+    outln!("  {} {}", style::dim("Type:"), credential.credential_type);
 
     match &credential.data {
         CredentialData::SshKey {
@@ -172,10 +264,28 @@ pub async fn handle_show(db: &Database, name: String) -> anyhow::Result<()> {
             key_path,
             passphrase,
         } => {
-            println!("  {} {}", style::dim("Username:"), username);
-            println!("  {} {}", style::dim("Port:"), port);
-            println!("  {} {}", style::dim("Key Path:"), key_path);
-            println!(
+            outln!("  {} {}", style::dim("Username:"), username);
+            outln!("  {} {}", style::dim("Port:"), port);
+            if db.get_key_material(&credential.id).await?.is_some() {
+                let storage = if db.is_encrypted() {
+                    "encrypted"
+                } else {
+                    "unencrypted"
+                };
+                outln!(
+                    "  {} inline, {} (from {})",
+                    style::dim("Key:"),
+                    storage,
+                    key_path
+                );
+            } else {
+                outln!(
+                    "  {} {}",
+                    style::dim("Key Path:"),
+                    hyperlink::file(key_path)
+                );
+            }
+            outln!(
                 "  {} {}",
                 style::dim("Passphrase:"),
                 if passphrase.is_some() {
@@ -186,60 +296,72 @@ pub async fn handle_show(db: &Database, name: String) -> anyhow::Result<()> {
             );
         }
         CredentialData::SshAgent { username, port } => {
-            println!("  {} {}", style::dim("Username:"), username);
-            println!("  {} {}", style::dim("Port:"), port);
-            println!("  {} SSH Agent", style::dim("Auth:"));
+            outln!("  {} {}", style::dim("Username:"), username);
+            outln!("  {} {}", style::dim("Port:"), port);
+            outln!("  {} SSH Agent", style::dim("Auth:"));
         }
         CredentialData::ApiToken { token, url } => {
-            println!(
+            outln!(
                 "  {} {}***",
                 style::dim("Token:"),
                 &token[..token.len().min(8)]
             );
             if let Some(u) = url {
-                println!("  {} {}", style::dim("URL:"), u);
+                outln!("  {} {}", style::dim("URL:"), hyperlink::web(u));
             }
         }
         CredentialData::BasicAuth { username, url, .. } => {
-            println!("  {} {}", style::dim("Username:"), username);
-            println!("  {} ***", style::dim("Password:"));
+            outln!("  {} {}", style::dim("Username:"), username);
+            outln!("  {} ***", style::dim("Password:"));
             if let Some(u) = url {
-                println!("  {} {}", style::dim("URL:"), u);
+                outln!("  {} {}", style::dim("URL:"), hyperlink::web(u));
             }
         }
         CredentialData::OAuth {
             url, expires_at, ..
         } => {
-            println!("  {} ***", style::dim("Token:"));
+            outln!("  {} ***", style::dim("Token:"));
             if let Some(u) = url {
-                println!("  {} {}", style::dim("URL:"), u);
+                outln!("  {} {}", style::dim("URL:"), hyperlink::web(u));
             }
             if let Some(exp) = expires_at {
-                println!("  {} {}", style::dim("Expires:"), exp);
+                outln!(
+                    "  {} {} ({})",
+                    style::dim("Expires:"),
+                    exp,
+                    humanize::relative_timestamp(exp)
+                );
             }
         }
     }
 
     if let Some(notes) = &credential.notes {
-        println!("  {} {}", style::dim("Notes:"), notes);
+        outln!("  {} {}", style::dim("Notes:"), notes);
     }
 
-    println!();
+    outln!();
     Ok(())
 }
 
 /// Handle credential remove command
-pub async fn handle_remove(db: &Database, name: String) -> anyhow::Result<()> {
-    let removed = db.remove_credential_by_name(&name).await?;
+pub async fn handle_remove(db: &Database, name: String, force: bool) -> anyhow::Result<()> {
+    let found = lookup(db, Some(EntityType::Credential), &name).await?;
+    let removed = match found {
+        Some(credential) => {
+            guard_remove(db, EntityType::Credential, &credential.id, &name, force).await?;
+            db.remove_credential(&credential.id).await?
+        }
+        None => false,
+    };
 
     if removed {
-        println!(
+        outln!(
             "{} Credential '{}' removed.",
             style::success_text("✓"),
             name
         );
     } else {
-        println!(
+        outln!(
             "{} Credential '{}' not found.",
             style::error_text("✗"),
             name
@@ -248,3 +370,71 @@ pub async fn handle_remove(db: &Database, name: String) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Handle credential deps command
+pub async fn handle_deps(db: &Database, name: String, json: bool) -> anyhow::Result<()> {
+    let credential = find_credential(db, &name).await?;
+
+    references::handle_deps(db, EntityType::Credential, &credential.id, &name, json).await
+}
+
+/// Handle credential export-key command
+pub async fn handle_export_key(
+    db: &Database,
+    name: String,
+    out: PathBuf,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let credential = find_credential(db, &name).await?;
+    let Some(key) = db.get_key_material(&credential.id).await? else {
+        anyhow::bail!(
+            "Credential '{}' has no inline key; add it with --store-inline",
+            credential.name
+        );
+    };
+    if out.exists() {
+        anyhow::bail!("{} already exists", out.display());
+    }
+    if !yes {
+        confirm_export(&credential.name, &out)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&out)
+        .and_then(|mut file| file.write_all(key.as_bytes()))
+        .map_err(|e| anyhow::anyhow!("Can't write {}: {}", out.display(), e))?;
+
+    outln!(
+        "{} Private key of '{}' written to {}",
+        style::success_text("✓"),
+        credential.name,
+        out.display()
+    );
+    Ok(())
+}
+
+fn confirm_export(name: &str, out: &Path) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to write a private key without a terminal; pass --yes");
+    }
+
+    out!(
+        "Write the private key of '{}' to {}? [y/N] ",
+        name,
+        out.display()
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("Aborted");
+    }
+    Ok(())
+}
@@ -1,27 +1,40 @@
 //! Database credentials command handler
 
-use crate::DatabaseCommands;
-use pctrl_core::{DatabaseCredentials, DatabaseType};
-use pctrl_database::Database;
+use super::audit::{clearable, history_view, print_ensured, print_updated};
+use super::hints;
+use super::references::{guard_remove, handle_deps};
+use super::resolve::{find_database, name_taken, ref_tag};
+use crate::{style, DatabaseCommands};
+use chrono::Utc;
+use pctrl_core::diff;
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::lease::{self, SchemaAccess};
+use pctrl_core::{humanize, DatabaseCredentials, DatabasePatch, DatabaseType, EntityType};
+use pctrl_database::{Database, CURRENT_SCHEMA_VERSION};
 
 pub async fn handle(command: DatabaseCommands, db: &Database) -> anyhow::Result<()> {
     match command {
         DatabaseCommands::List => {
             let databases = db.list_database_credentials().await?;
             if databases.is_empty() {
-                println!("No database credentials configured.");
-                println!();
-                println!("Add one with:");
-                println!("  pctrl database add <name> -t <type> -u <user> -P <password>");
+                outln!("No database credentials configured.");
+                hints::show(db, Event::Empty(Listing::Databases)).await?;
             } else {
-                println!("Databases ({}):", databases.len());
-                println!();
+                outln!("Databases ({}):", databases.len());
+                outln!();
+                let refs = db.short_refs(EntityType::Database).await?;
                 for creds in databases {
                     let host_str = creds
                         .host
                         .clone()
                         .unwrap_or_else(|| "localhost".to_string());
-                    println!("  🗄️  {} [{}] - {}", creds.name, creds.db_type, host_str);
+                    outln!(
+                        "  🗄️  {}{} [{}] - {}",
+                        creds.name,
+                        ref_tag(&refs, &creds.id),
+                        creds.db_type,
+                        host_str
+                    );
                 }
             }
         }
@@ -37,12 +50,15 @@ pub async fn handle(command: DatabaseCommands, db: &Database) -> anyhow::Result<
             connection_string,
             server,
             container,
+            ensure,
         } => {
-            let id = name.to_lowercase().replace(' ', "-");
-
-            if db.get_database_credentials_by_name(&name).await?.is_some() {
+            let existing = db.get_database_credentials_by_name(&name).await?;
+            if existing.is_some() && !ensure {
                 anyhow::bail!("Database '{}' already exists.", name);
             }
+            let id = existing
+                .map(|c| c.id)
+                .unwrap_or_else(|| name.to_lowercase().replace(' ', "-"));
 
             let db_type: DatabaseType = db_type.parse().map_err(|e: String| anyhow::anyhow!(e))?;
 
@@ -61,63 +77,110 @@ pub async fn handle(command: DatabaseCommands, db: &Database) -> anyhow::Result<
                 notes: None,
             };
 
-            db.save_database_credentials(&creds).await?;
+            if ensure {
+                let patch = DatabasePatch {
+                    db_type: Some(creds.db_type.clone()),
+                    host: creds.host.clone(),
+                    port: creds.port,
+                    database_name: creds.database_name.clone(),
+                    username: creds.username.clone(),
+                    password: creds.password.clone(),
+                    connection_string: creds.connection_string.clone(),
+                    server_id: server,
+                    container_id: container,
+                };
+                let ensured = db.ensure_database_credentials(&creds, &patch).await?;
+                print_ensured("Database", &name, &ensured);
+                return Ok(());
+            }
+
+            db.create_database_credentials(&creds)
+                .await
+                .map_err(|e| name_taken("Database", e))?;
 
-            println!("✓ Database credentials added:");
-            println!();
-            println!("  Name: {}", name);
-            println!("  ID:   {}", id);
-            println!("  Type: {}", db_type);
+            noteln!("✓ Database credentials added:");
+            noteln!();
+            noteln!("  Name: {}", name);
+            noteln!("  ID:   {}", id);
+            noteln!("  Type: {}", db_type);
             if let Some(s) = &server {
-                println!("  Server: {}", s);
+                noteln!("  Server: {}", s);
             }
             if let Some(c) = &container {
-                println!("  Container: {}", c);
+                noteln!("  Container: {}", c);
+            }
+        }
+
+        DatabaseCommands::Edit {
+            name,
+            host,
+            port,
+            user,
+            password,
+        } => {
+            let before = find_database(db, &name).await?;
+            let mut creds = before.clone();
+            if let Some(host) = host {
+                creds.host = clearable(host);
+            }
+            if let Some(port) = port {
+                creds.port = Some(port);
+            }
+            if let Some(user) = user {
+                creds.username = clearable(user);
+            }
+            if let Some(password) = password {
+                creds.password = Some(password).filter(|p| !p.is_empty());
+            }
+
+            let changes = diff::diff(&before, &creds);
+            if !changes.is_empty() {
+                db.update_database_credentials(&creds).await?;
             }
+            print_updated("Database", &creds.name, &changes);
         }
 
         DatabaseCommands::Show { name } => {
-            let creds = db
-                .get_database_credentials_by_name(&name)
-                .await?
-                .or(db.get_database_credentials(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Database '{}' not found", name))?;
-
-            println!();
-            println!("  🗄️  {}", creds.name);
-            println!("  ─────────────────────────────");
-            println!("  ID:       {}", creds.id);
-            println!("  Type:     {}", creds.db_type);
+            let creds = find_database(db, &name).await?;
+
+            outln!();
+            outln!("  🗄️  {}", creds.name);
+            outln!("  ─────────────────────────────");
+            outln!("  ID:       {}", creds.id);
+            if let Some(short_ref) = db.short_ref(EntityType::Database, &creds.id).await? {
+                outln!("  Ref:      {}", short_ref);
+            }
+            outln!("  Type:     {}", creds.db_type);
             if let Some(h) = &creds.host {
-                println!("  Host:     {}", h);
+                outln!("  Host:     {}", h);
             }
             if let Some(p) = creds.port {
-                println!("  Port:     {}", p);
+                outln!("  Port:     {}", p);
             }
             if let Some(d) = &creds.database_name {
-                println!("  Database: {}", d);
+                outln!("  Database: {}", d);
             }
             if let Some(u) = &creds.username {
-                println!("  User:     {}", u);
+                outln!("  User:     {}", u);
             }
             if creds.password.is_some() {
-                println!("  Password: ********");
+                outln!("  Password: ********");
             }
             if let Some(s) = &creds.server_id {
-                println!("  Server:   {}", s);
+                outln!("  Server:   {}", s);
             }
             if let Some(c) = &creds.container_id {
-                println!("  Container: {}", c);
+                outln!("  Container: {}", c);
             }
-            println!();
+            outln!();
+        }
+
+        DatabaseCommands::HistoryView { name, at, timeline } => {
+            history_view(db, EntityType::Database, &name, at, timeline).await?;
         }
 
         DatabaseCommands::Get { name, field } => {
-            let creds = db
-                .get_database_credentials_by_name(&name)
-                .await?
-                .or(db.get_database_credentials(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Database '{}' not found", name))?;
+            let creds = find_database(db, &name).await?;
 
             let value = match field.to_lowercase().as_str() {
                 "user" | "username" => creds.username.clone(),
@@ -125,7 +188,10 @@ pub async fn handle(command: DatabaseCommands, db: &Database) -> anyhow::Result<
                 "host" => creds.host.clone(),
                 "port" => creds.port.map(|p| p.to_string()),
                 "database" | "db" => creds.database_name.clone(),
-                "url" | "connection_string" => creds.connection_string.clone(),
+                "url" | "connection_string" => creds
+                    .connection_string
+                    .clone()
+                    .or_else(|| creds.connection_url()),
                 _ => anyhow::bail!(
                     "Unknown field: {}. Use: user, pass, host, port, database, url",
                     field
@@ -133,24 +199,158 @@ pub async fn handle(command: DatabaseCommands, db: &Database) -> anyhow::Result<
             };
 
             if let Some(v) = value {
-                println!("{}", v);
+                outln!("{}", v);
             } else {
                 anyhow::bail!("Field '{}' is not set for database '{}'", field, name);
             }
         }
 
-        DatabaseCommands::Remove { name } => {
-            let creds = db
-                .get_database_credentials_by_name(&name)
-                .await?
-                .or(db.get_database_credentials(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Database '{}' not found", name))?;
+        DatabaseCommands::Url { name, no_password } => {
+            let creds = find_database(db, &name).await?;
+            let url = match (&creds.connection_string, no_password) {
+                (Some(stored), false) => Some(stored.clone()),
+                (Some(stored), true) => Some(without_password(stored)),
+                (None, false) => creds.connection_url(),
+                (None, true) => creds.connection_url_without_password(),
+            };
+            match url {
+                Some(url) => outln!("{}", url),
+                None if creds.db_type == DatabaseType::SQLite => {
+                    anyhow::bail!("Database '{}' has no file path", creds.name)
+                }
+                None => anyhow::bail!(
+                    "Database '{}' has no host or connection string; set one with `pctrl db edit {} --host`",
+                    creds.name,
+                    creds.name
+                ),
+            }
+        }
+
+        DatabaseCommands::Remove { name, force } => {
+            let creds = find_database(db, &name).await?;
 
+            guard_remove(db, EntityType::Database, &creds.id, &creds.name, force).await?;
             if db.remove_database_credentials(&creds.id).await? {
-                println!("✓ Database '{}' removed", creds.name);
+                noteln!("✓ Database '{}' removed", creds.name);
             }
         }
+
+        DatabaseCommands::Deps { name, json } => {
+            let creds = find_database(db, &name).await?;
+
+            handle_deps(db, EntityType::Database, &creds.id, &creds.name, json).await?;
+        }
+
+        DatabaseCommands::Info { json } => handle_info(db, json).await?,
     }
 
     Ok(())
 }
+
+/// `pctrl db info`: which pctrl versions wrote the file and who holds the
+/// write lease
+async fn handle_info(db: &Database, json: bool) -> anyhow::Result<()> {
+    let schema = db.schema_version().await?;
+    let writer = db.writer_version().await?;
+    let lease = db.write_lease().await?;
+    let now = Utc::now();
+    let version = env!("CARGO_PKG_VERSION");
+    let read_only = db.newer_schema().is_some();
+
+    if json {
+        let value = serde_json::json!({
+            "path": db.path(),
+            "schema_version": schema,
+            "known_schema_version": CURRENT_SCHEMA_VERSION,
+            "writer_version": writer,
+            "version": version,
+            "read_only": read_only,
+            "lease": lease.as_ref().map(|l| serde_json::json!({
+                "holder": l.holder,
+                "version": l.version,
+                "schema_version": l.schema_version,
+                "acquired_at": l.acquired_at,
+                "heartbeat_at": l.heartbeat_at,
+                "expired": l.is_expired(now),
+            })),
+        });
+        outln!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    outln!();
+    outln!("  🗃️  {}", db.path().display());
+    outln!("  ─────────────────────────────");
+    let schema_note = match lease::schema_access(schema, CURRENT_SCHEMA_VERSION) {
+        SchemaAccess::ReadWrite => String::new(),
+        SchemaAccess::Migrate { .. } => style::dim(" (not migrated yet)"),
+        SchemaAccess::ReadOnly { .. } => style::warning_text(&format!(
+            " (newer than v{}, which this pctrl knows)",
+            CURRENT_SCHEMA_VERSION
+        )),
+    };
+    outln!("  Schema:     v{}{}", schema, schema_note);
+    let writer_note = match &writer {
+        Some(w) if lease::compare_versions(w, version).is_gt() => {
+            style::warning_text(&format!(" (newer than this pctrl, {})", version))
+        }
+        Some(w) if w != version => style::dim(&format!(" (this is {})", version)),
+        _ => String::new(),
+    };
+    outln!(
+        "  Written by: pctrl {}{}",
+        writer.as_deref().unwrap_or("unknown"),
+        writer_note
+    );
+    if read_only {
+        outln!(
+            "  Access:     {}",
+            style::warning_text("read-only, upgrade pctrl to change this database")
+        );
+    } else {
+        outln!("  Access:     read-write");
+    }
+    match lease {
+        None => outln!("  Lease:      {}", style::dim("free")),
+        Some(l) if l.is_expired(now) => outln!(
+            "  Lease:      {} {}",
+            style::dim(&format!(
+                "expired, held by {} (pctrl {})",
+                l.holder, l.version
+            )),
+            style::dim(&format!(
+                "- last heartbeat {}, the next migration takes it over",
+                humanize::relative(l.heartbeat_at)
+            ))
+        ),
+        Some(l) => outln!(
+            "  Lease:      {}",
+            style::warning_text(&format!(
+                "held by {} (pctrl {}, migrating to v{}) since {}, heartbeat {}",
+                l.holder,
+                l.version,
+                l.schema_version,
+                humanize::relative(l.acquired_at),
+                humanize::relative(l.heartbeat_at)
+            ))
+        ),
+    }
+    outln!();
+    Ok(())
+}
+
+/// A stored connection string without the password in its userinfo
+fn without_password(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let Some((userinfo, host)) = authority.rsplit_once('@') else {
+        return url.to_string();
+    };
+    match userinfo.split(':').next() {
+        Some(user) if !user.is_empty() => format!("{}://{}@{}{}", scheme, user, host, path),
+        _ => format!("{}://{}{}", scheme, host, path),
+    }
+}
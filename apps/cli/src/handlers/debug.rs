@@ -0,0 +1,66 @@
+//! Debug command handler (hidden; demo data, database reset, anonymized copies)
+
+use super::hints;
+use crate::{style, DebugCommands};
+use pctrl_core::hints::Event;
+use pctrl_core::humanize;
+use pctrl_database::Database;
+
+pub async fn handle(command: DebugCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        DebugCommands::SeedDemo => {
+            let added = db.seed_demo_data().await?;
+            if added == 0 {
+                outln!("Demo data is already there.");
+            } else {
+                noteln!(
+                    "✓ Added {} of demo data (project \"Demo Shop\")",
+                    humanize::count(added as u64, "row", "rows")
+                );
+                hints::show(db, Event::DemoSeeded).await?;
+            }
+        }
+        DebugCommands::ClearDemo => {
+            let removed = db.clear_demo_data().await?;
+            if removed == 0 {
+                outln!("No demo data found.");
+            } else {
+                noteln!(
+                    "✓ Removed {} of demo data",
+                    humanize::count(removed as u64, "row", "rows")
+                );
+            }
+        }
+        DebugCommands::Reset { confirm } => {
+            db.reset_database(&confirm).await?;
+            noteln!("✓ Database reset; all data was deleted");
+        }
+        DebugCommands::Anonymize { out } => {
+            let reports = db.anonymize_to(&out).await?;
+            for report in reports.iter().filter(|r| r.rows > 0) {
+                let rewritten: Vec<String> = report
+                    .rewritten
+                    .iter()
+                    .map(|(label, n)| format!("{} {}", n, label))
+                    .collect();
+                outln!(
+                    "  {:<24} {:>10}  {}",
+                    report.table,
+                    humanize::count(report.rows, "row", "rows"),
+                    if rewritten.is_empty() {
+                        style::dim("unchanged")
+                    } else {
+                        rewritten.join(", ")
+                    }
+                );
+            }
+            noteln!("✓ Anonymized copy written to {}", out.display());
+            noteln!(
+                "  Secrets are blank and notes cut short; row counts, statuses, links and timestamps are kept."
+            );
+            noteln!("  Look it over before attaching it to a bug report.");
+        }
+    }
+
+    Ok(())
+}
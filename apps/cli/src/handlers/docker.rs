@@ -0,0 +1,805 @@
+//! Docker and container command handlers
+
+use super::compose;
+use super::guard::confirm_live;
+use super::hints;
+use super::resolve::find_server;
+use crate::{style, ContainerCommands, DockerCommands};
+use futures_util::StreamExt;
+use pctrl_core::discovery;
+use pctrl_core::docker_endpoint::{self, DockerEndpoint};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::log_tail::LineBuffer;
+use pctrl_core::network::{reach, Endpoint, Reach};
+use pctrl_core::proxy_labels::{plan_links, proxy_hosts, Labels, ProxyHost};
+use pctrl_core::{
+    humanize, quota, Container, ContainerNetwork, DockerHost, Domain, DomainType, PublishedPort,
+    ResourceType, Server,
+};
+use pctrl_database::Database;
+use pctrl_docker::{ContainerInfo, DockerManager, LogOptions, LogStream};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+/// Local Docker socket, used when no Docker host is configured
+const LOCAL_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+pub async fn handle(command: DockerCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        DockerCommands::Sync {
+            host,
+            create_domains,
+            server,
+        } => {
+            let (docker, host_id) = docker_manager(db, host).await?;
+            let topology = docker.network_topology(&host_id).await?;
+
+            db.replace_network_topology(
+                &host_id,
+                &topology.networks,
+                &topology.memberships,
+                &topology.ports,
+            )
+            .await?;
+
+            noteln!(
+                "✓ Synced '{}': {}, {}, {}",
+                host_id,
+                humanize::count(topology.networks.len() as u64, "network", "networks"),
+                humanize::count(
+                    topology.memberships.len() as u64,
+                    "attachment",
+                    "attachments"
+                ),
+                humanize::count(
+                    topology.ports.len() as u64,
+                    "published port",
+                    "published ports"
+                )
+            );
+
+            let server_id = match server {
+                Some(server) => Some(find_server(db, &server).await?.id),
+                None => match docker.get_host(&host_id) {
+                    Some(host) => host_server(db, host).await?,
+                    None => None,
+                },
+            };
+            match &server_id {
+                Some(server_id) => record_containers(db, &docker, &host_id, server_id).await?,
+                None => noteln!(
+                    "  {}",
+                    style::dim(&format!(
+                        "Containers not recorded for quotas: no server runs '{}' (use --server)",
+                        host_id
+                    ))
+                ),
+            }
+            link_proxy_domains(db, &topology.labels, server_id.as_deref(), create_domains).await?;
+        }
+
+        DockerCommands::Add {
+            name,
+            url,
+            tls_ca,
+            tls_cert,
+            tls_key,
+        } => {
+            let hosts = db.load_config().await?.docker_hosts;
+            if hosts.iter().any(|h| h.name == name) {
+                anyhow::bail!("Docker host '{}' already exists.", name);
+            }
+            let host = DockerHost {
+                id: name.to_lowercase().replace(' ', "-"),
+                name,
+                url,
+                tls_ca,
+                tls_cert,
+                tls_key,
+            };
+            let endpoint = docker_endpoint::validate(&host)?;
+            db.save_docker_host(&host).await?;
+            noteln!("✓ Docker host '{}' added ({})", host.name, endpoint);
+            hints::show(db, Event::DockerHostAdded { id: host.id }).await?;
+        }
+
+        DockerCommands::Check { host } => {
+            let hosts: Vec<DockerHost> = db
+                .load_config()
+                .await?
+                .docker_hosts
+                .into_iter()
+                .filter(|h| host.as_ref().is_none_or(|id| &h.id == id || &h.name == id))
+                .collect();
+            if hosts.is_empty() {
+                match host {
+                    Some(id) => anyhow::bail!("Docker host '{}' not found", id),
+                    None => {
+                        outln!("No Docker hosts configured.");
+                        hints::show(db, Event::Empty(Listing::DockerHosts)).await?;
+                        return Ok(());
+                    }
+                }
+            }
+
+            let mut failed = 0;
+            for host in hosts {
+                let (name, url) = (host.name.clone(), host.url.clone());
+                let mut docker = DockerManager::new();
+                let result = match docker.add_host(host.clone()) {
+                    Ok(()) => docker.health_check(&host.id).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => outln!(
+                        "  {} {:<20} {}",
+                        style::success_text("✓"),
+                        name,
+                        style::dim(&url)
+                    ),
+                    Err(e) => {
+                        failed += 1;
+                        outln!(
+                            "  {} {:<20} {}",
+                            style::error_text("✗"),
+                            name,
+                            style::error_text(&e.to_string())
+                        );
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!(
+                    "{} not reachable",
+                    humanize::count(failed, "Docker host", "Docker hosts")
+                );
+            }
+        }
+
+        DockerCommands::Restart {
+            containers,
+            allow_live,
+        } => lifecycle(db, &containers, Lifecycle::Restart, allow_live).await?,
+
+        DockerCommands::Remove {
+            containers,
+            force,
+            allow_live,
+        } => lifecycle(db, &containers, Lifecycle::Remove { force }, allow_live).await?,
+
+        DockerCommands::Pause {
+            containers,
+            allow_live,
+        } => lifecycle(db, &containers, Lifecycle::Pause, allow_live).await?,
+
+        DockerCommands::Unpause { containers } => {
+            lifecycle(db, &containers, Lifecycle::Unpause, true).await?
+        }
+        DockerCommands::Logs {
+            container,
+            tail,
+            since,
+            timestamps,
+            follow,
+        } => {
+            let (host, name) = match container.split_once('/') {
+                Some((host, name)) => (Some(host.to_string()), name),
+                None => (None, container.as_str()),
+            };
+            let (docker, host_id) = docker_manager(db, host).await?;
+            let options = LogOptions {
+                tail,
+                since: since.map(|t| t.timestamp()),
+                timestamps,
+                follow,
+            };
+            print_logs(&docker, &host_id, name, &options).await?;
+        }
+        DockerCommands::Stats {
+            host,
+            container,
+            json,
+        } => print_stats(db, host, container, json).await?,
+
+        DockerCommands::Networks { host } => {
+            let host_id = match host {
+                Some(id) => id,
+                None => docker_manager(db, None).await?.1,
+            };
+            let networks = db.list_docker_networks(&host_id).await?;
+            if networks.is_empty() {
+                outln!("No networks known for '{}'.", host_id);
+                hints::show(db, Event::NoNetworks { host: host_id }).await?;
+                return Ok(());
+            }
+
+            let memberships: Vec<ContainerNetwork> = db
+                .list_container_networks()
+                .await?
+                .into_iter()
+                .filter(|m| m.host_id == host_id)
+                .collect();
+
+            outln!("Networks on '{}' ({}):", host_id, networks.len());
+            outln!();
+            for network in networks {
+                let driver = network
+                    .driver
+                    .map(|d| style::dim(&format!(" ({})", d)))
+                    .unwrap_or_default();
+                outln!("  {}{}", style::bold(&network.name), driver);
+
+                let members: Vec<&ContainerNetwork> = memberships
+                    .iter()
+                    .filter(|m| m.network == network.name)
+                    .collect();
+                if members.is_empty() {
+                    outln!("    {}", style::dim("no containers"));
+                }
+                for member in members {
+                    outln!(
+                        "    {:<28} {}",
+                        member.container,
+                        style::dim(member.ip_address.as_deref().unwrap_or("-"))
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print resource usage of one container, or of every running one on the
+/// host. Containers are sampled concurrently; each sample takes a second.
+async fn print_stats(
+    db: &Database,
+    host: String,
+    container: Option<String>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let (docker, host_id) = docker_manager(db, Some(host)).await?;
+    let names = match container {
+        Some(name) => vec![name],
+        None => docker
+            .list_containers(&host_id)
+            .await?
+            .into_iter()
+            .filter(|c| c.state == "running")
+            .map(|c| c.name)
+            .collect(),
+    };
+    if names.is_empty() {
+        outln!("No running containers on '{}'.", host_id);
+        return Ok(());
+    }
+
+    let samples = futures_util::future::join_all(
+        names
+            .iter()
+            .map(|name| docker.container_stats(&host_id, name)),
+    )
+    .await;
+
+    if json {
+        let mut rows = Vec::new();
+        for (name, sample) in names.iter().zip(samples) {
+            let mut row = serde_json::to_value(sample?)?;
+            row["container"] = serde_json::Value::String(name.clone());
+            rows.push(row);
+        }
+        outln!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    outln!(
+        "  {:<28} {:>7} {:>21} {:>6} {:>21} {:>21}",
+        "CONTAINER",
+        "CPU %",
+        "MEM USAGE / LIMIT",
+        "MEM %",
+        "NET I/O",
+        "BLOCK I/O"
+    );
+    let mut failed = 0;
+    for (name, sample) in names.iter().zip(samples) {
+        let stats = match sample {
+            Ok(stats) => stats,
+            Err(e) => {
+                failed += 1;
+                outln!("  {:<28} {}", name, style::error_text(&e.to_string()));
+                continue;
+            }
+        };
+        let pair = |a: u64, b: u64| format!("{} / {}", humanize::bytes(a), humanize::bytes(b));
+        outln!(
+            "  {:<28} {:>7} {:>21} {:>6} {:>21} {:>21}",
+            name,
+            format!("{:.1}%", stats.cpu_percent),
+            pair(stats.mem_usage_bytes, stats.mem_limit_bytes),
+            format!("{:.1}%", stats.mem_percent()),
+            pair(stats.net_rx, stats.net_tx),
+            pair(stats.block_read, stats.block_write)
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "Could not read stats of {}",
+            humanize::count(failed, "container", "containers")
+        );
+    }
+    Ok(())
+}
+
+/// Print logs line by line as they arrive, stdout to stdout and stderr to
+/// stderr; Ctrl-C ends a `--follow` cleanly
+async fn print_logs(
+    docker: &DockerManager,
+    host_id: &str,
+    container: &str,
+    options: &LogOptions,
+) -> anyhow::Result<()> {
+    let mut frames = Box::pin(docker.container_logs(host_id, container, options)?);
+    let mut stdout = LineBuffer::default();
+    let mut stderr = LineBuffer::default();
+    loop {
+        tokio::select! {
+            frame = frames.next() => match frame {
+                Some(frame) => {
+                    let frame = frame?;
+                    match frame.stream {
+                        LogStream::Stdout => stdout.push(&frame.text).iter().for_each(|l| outln!("{}", l)),
+                        LogStream::Stderr => stderr.push(&frame.text).iter().for_each(|l| eoutln!("{}", l)),
+                    }
+                }
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    if let Some(line) = stdout.finish() {
+        outln!("{}", line);
+    }
+    if let Some(line) = stderr.finish() {
+        eoutln!("{}", line);
+    }
+    Ok(())
+}
+
+/// A container operation of `docker restart/remove/pause/unpause`
+#[derive(Clone, Copy)]
+enum Lifecycle {
+    Restart,
+    Remove { force: bool },
+    Pause,
+    Unpause,
+}
+
+impl Lifecycle {
+    fn verb(self) -> &'static str {
+        match self {
+            Lifecycle::Restart => "Restart",
+            Lifecycle::Remove { .. } => "Remove",
+            Lifecycle::Pause => "Pause",
+            Lifecycle::Unpause => "Unpause",
+        }
+    }
+
+    fn done(self) -> &'static str {
+        match self {
+            Lifecycle::Restart => "restarted",
+            Lifecycle::Remove { .. } => "removed",
+            Lifecycle::Pause => "paused",
+            Lifecycle::Unpause => "unpaused",
+        }
+    }
+}
+
+/// Apply `action` to each `[host/]name` container, reporting each one;
+/// fails at the end if any of them failed
+async fn lifecycle(
+    db: &Database,
+    containers: &[String],
+    action: Lifecycle,
+    allow_live: bool,
+) -> anyhow::Result<()> {
+    let targets: Vec<(Option<String>, &str)> = containers
+        .iter()
+        .map(|c| match c.split_once('/') {
+            Some((host, name)) => (Some(host.to_string()), name),
+            None => (None, c.as_str()),
+        })
+        .collect();
+
+    let names: Vec<&str> = targets.iter().map(|(_, name)| *name).collect();
+    let live = db
+        .live_projects_for_resource(&ResourceType::Container, &names)
+        .await?;
+    confirm_live(
+        &live,
+        &format!(
+            "{} {}",
+            action.verb(),
+            humanize::count(names.len() as u64, "container", "containers")
+        ),
+        allow_live,
+    )?;
+
+    // One connection per host, so ssh:// hosts tunnel once
+    let mut managers: HashMap<Option<String>, (DockerManager, String)> = HashMap::new();
+    let mut failed = 0;
+    for (host, name) in targets {
+        let manager = match managers.entry(host) {
+            Entry::Occupied(entry) => Ok(&*entry.into_mut()),
+            Entry::Vacant(entry) => docker_manager(db, entry.key().clone())
+                .await
+                .map(|manager| &*entry.insert(manager)),
+        };
+        let result = match manager {
+            Ok((docker, host_id)) => match action {
+                Lifecycle::Restart => docker.restart_container(host_id, name).await,
+                Lifecycle::Remove { force } => docker.remove_container(host_id, name, force).await,
+                Lifecycle::Pause => docker.pause_container(host_id, name).await,
+                Lifecycle::Unpause => docker.unpause_container(host_id, name).await,
+            }
+            .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => outln!("  {} {} {}", style::success_text("✓"), name, action.done()),
+            Err(e) => {
+                failed += 1;
+                outln!(
+                    "  {} {}",
+                    style::error_text("✗"),
+                    style::error_text(&e.to_string())
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{} of {} failed",
+            failed,
+            humanize::count(containers.len() as u64, "container", "containers")
+        );
+    }
+    Ok(())
+}
+
+/// A container a Docker host listed, as recorded on `server_id`
+pub(crate) fn to_container(info: ContainerInfo, server_id: &str) -> Container {
+    Container {
+        id: info.id,
+        name: info.name,
+        image: Some(info.image).filter(|i| !i.is_empty()),
+        server_id: server_id.to_string(),
+        project_id: None,
+        status: discovery::status(&info.state, &info.status),
+        ports: info.ports,
+        env_vars: None,
+        labels: discovery::labels_json(&info.labels),
+        memory_limit_mb: info.memory_limit_mb,
+    }
+}
+
+/// Record the host's containers and their memory limits on `server_id`,
+/// for the server's quotas
+async fn record_containers(
+    db: &Database,
+    docker: &DockerManager,
+    host_id: &str,
+    server_id: &str,
+) -> anyhow::Result<()> {
+    let containers: Vec<Container> = docker
+        .list_containers(host_id)
+        .await?
+        .into_iter()
+        .map(|c| to_container(c, server_id))
+        .collect();
+    db.replace_server_containers(server_id, &containers).await?;
+    compose::record(db, server_id, &containers, false).await?;
+
+    let server = db.get_server(server_id).await?;
+    let running: Vec<&Container> = containers.iter().filter(|c| quota::is_running(c)).collect();
+    noteln!(
+        "✓ Recorded {} on '{}': {}",
+        humanize::count(containers.len() as u64, "container", "containers"),
+        server.as_ref().map_or(server_id, |s| s.name.as_str()),
+        match &server {
+            Some(server) => quota::describe(server, &quota::allocation(running)),
+            None => String::new(),
+        }
+    );
+    Ok(())
+}
+
+pub async fn handle_container(command: ContainerCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        ContainerCommands::Reach { from, to } => {
+            let memberships = db.list_container_networks().await?;
+            let ports = db.list_published_ports().await?;
+
+            let (from_host, from_name) = resolve_container(&from, &memberships, &ports)?;
+            let (to_host, to_name) = resolve_container(&to, &memberships, &ports)?;
+            let endpoint = |host_id, container| Endpoint { host_id, container };
+
+            match reach(
+                endpoint(&from_host, &from_name),
+                endpoint(&to_host, &to_name),
+                &memberships,
+                &ports,
+            ) {
+                Reach::Networks(networks) => outln!(
+                    "{} {} can reach {} over {}",
+                    style::success_text("✓"),
+                    from_name,
+                    to_name,
+                    networks.join(", ")
+                ),
+                Reach::HostPorts(published) => {
+                    outln!(
+                        "{} {} shares no network with {}, only published host ports:",
+                        style::warning_text("⚠"),
+                        from_name,
+                        to_name
+                    );
+                    for port in published {
+                        outln!(
+                            "    {}:{} → {}/{}",
+                            port.host_id,
+                            port.host_port,
+                            port.container_port,
+                            port.protocol
+                        );
+                    }
+                }
+                Reach::Unreachable => outln!(
+                    "{} {} cannot reach {} (no shared network, no published ports)",
+                    style::error_text("✗"),
+                    from_name,
+                    to_name
+                ),
+            }
+        }
+
+        ContainerCommands::Exec {
+            container,
+            command,
+            allow_live,
+        } => {
+            let (host, name) = match container.split_once('/') {
+                Some((host, name)) => (Some(host.to_string()), name.to_string()),
+                None => (None, container.clone()),
+            };
+
+            let live = db
+                .live_projects_for_resource(&ResourceType::Container, &[&name])
+                .await?;
+            confirm_live(
+                &live,
+                &format!("Command in container '{}'", name),
+                allow_live,
+            )?;
+
+            let (docker, host_id) = docker_manager(db, host).await?;
+            noteln!("▶ Executing in {}: {}", name, command);
+            outln!();
+            out!(
+                "{}",
+                docker.exec_in_container(&host_id, &name, &command).await?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve `[host/]container` against the synced topology to (host, name)
+fn resolve_container(
+    input: &str,
+    memberships: &[ContainerNetwork],
+    ports: &[PublishedPort],
+) -> anyhow::Result<(String, String)> {
+    let (host, name) = match input.split_once('/') {
+        Some((host, name)) => (Some(host), name),
+        None => (None, input),
+    };
+
+    let mut hosts: Vec<&str> = memberships
+        .iter()
+        .map(|m| (m.host_id.as_str(), m.container.as_str()))
+        .chain(
+            ports
+                .iter()
+                .map(|p| (p.host_id.as_str(), p.container.as_str())),
+        )
+        .filter(|(h, c)| *c == name && host.is_none_or(|host| host == *h))
+        .map(|(h, _)| h)
+        .collect();
+    hosts.sort_unstable();
+    hosts.dedup();
+
+    match hosts.as_slice() {
+        [] => anyhow::bail!(
+            "Container '{}' not found in synced networks (run 'pctrl docker sync' first)",
+            input
+        ),
+        [host] => Ok((host.to_string(), name.to_string())),
+        _ => anyhow::bail!(
+            "Container '{}' exists on several hosts ({}), use <host>/{}",
+            name,
+            hosts.join(", "),
+            name
+        ),
+    }
+}
+
+/// The server a Docker host runs on: same name or ID, or its address in the
+/// host's URL (`tcp://10.0.0.5:2376`, `ssh://deploy@web-1`)
+pub(crate) async fn host_server(
+    db: &Database,
+    host: &DockerHost,
+) -> anyhow::Result<Option<String>> {
+    let servers = db.list_servers().await?;
+    let endpoint = DockerEndpoint::parse(&host.url).ok();
+    let address = endpoint
+        .as_ref()
+        .and_then(DockerEndpoint::host)
+        .unwrap_or_default();
+    Ok(servers
+        .iter()
+        .find(|s| s.id == host.id || s.name.eq_ignore_ascii_case(&host.name))
+        .or_else(|| {
+            servers
+                .iter()
+                .find(|s| !address.is_empty() && s.host.eq_ignore_ascii_case(address))
+        })
+        .map(|s| s.id.clone()))
+}
+
+/// The configured Docker host a server runs, matched like [`host_server`]:
+/// by ID or name first, then by address
+pub(crate) async fn server_host(
+    db: &Database,
+    server: &Server,
+) -> anyhow::Result<Option<DockerHost>> {
+    let hosts = db.load_config().await?.docker_hosts;
+    let address = |host: &DockerHost| {
+        DockerEndpoint::parse(&host.url)
+            .ok()
+            .and_then(|endpoint| endpoint.host().map(str::to_ascii_lowercase))
+    };
+    let found = hosts
+        .iter()
+        .find(|h| h.id == server.id || h.name.eq_ignore_ascii_case(&server.name))
+        .or_else(|| {
+            hosts
+                .iter()
+                .find(|h| address(h).is_some_and(|a| a.eq_ignore_ascii_case(&server.host)))
+        });
+    Ok(found.cloned())
+}
+
+/// Link domains to the containers whose reverse-proxy labels name them
+async fn link_proxy_domains(
+    db: &Database,
+    labels: &BTreeMap<String, Labels>,
+    server_id: Option<&str>,
+    create: bool,
+) -> anyhow::Result<()> {
+    let containers: BTreeMap<String, Vec<ProxyHost>> = labels
+        .iter()
+        .map(|(container, labels)| (container.clone(), proxy_hosts(labels)))
+        .filter(|(_, hosts)| !hosts.is_empty())
+        .collect();
+    if containers.is_empty() {
+        return Ok(());
+    }
+
+    let domains = db.list_domains().await?;
+    let plan = plan_links(&containers, &domains, server_id, create);
+
+    outln!();
+    outln!("Domains from proxy labels:");
+    for link in &plan.links {
+        let domain = match &link.domain_id {
+            Some(id) => {
+                let mut domain = domains
+                    .iter()
+                    .find(|d| &d.id == id)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Domain '{}' not found", id))?;
+                domain.container_id = Some(link.container.clone());
+                if let Some(server_id) = server_id {
+                    domain.server_id = Some(server_id.to_string());
+                }
+                domain
+            }
+            None => Domain {
+                id: link.hostname.replace('.', "-"),
+                domain: link.hostname.clone(),
+                domain_type: DomainType::default(),
+                ssl: true,
+                ssl_expiry: None,
+                cloudflare_zone_id: None,
+                cloudflare_record_id: None,
+                server_id: server_id.map(str::to_string),
+                container_id: Some(link.container.clone()),
+                notes: Some(format!("From {} labels on {}", link.scheme, link.container)),
+                superseded_by: None,
+            },
+        };
+        db.save_domain(&domain).await?;
+
+        let detail = match (&link.domain_id, &link.previous) {
+            (None, _) => format!("{}, created", link.scheme),
+            (_, Some(previous)) => format!("{}, was {}", link.scheme, previous),
+            _ => link.scheme.to_string(),
+        };
+        outln!(
+            "  {} {} → {} {}",
+            style::success_text("✓"),
+            link.hostname,
+            link.container,
+            style::dim(&format!("({})", detail))
+        );
+    }
+    for (hostname, containers) in &plan.conflicts {
+        outln!(
+            "  {} {} is claimed by {}; not linked",
+            style::warning_text("⚠"),
+            hostname,
+            containers.join(", ")
+        );
+    }
+    if !plan.unchanged.is_empty() {
+        outln!(
+            "  {}",
+            style::dim(&format!(
+                "{} already linked",
+                humanize::count(plan.unchanged.len() as u64, "domain", "domains")
+            ))
+        );
+    }
+    if !plan.unknown.is_empty() {
+        outln!(
+            "  {} {}",
+            style::dim("No domain entry for:"),
+            plan.unknown.join(", ")
+        );
+        noteln!(
+            "  {}",
+            style::dim("Add them with `pctrl docker sync --create-domains`")
+        );
+    }
+    Ok(())
+}
+
+/// Docker manager for the given (or default) host
+pub(crate) async fn docker_manager(
+    db: &Database,
+    host: Option<String>,
+) -> anyhow::Result<(DockerManager, String)> {
+    let hosts = db.load_config().await?.docker_hosts;
+
+    let host = match host {
+        Some(id) => hosts
+            .into_iter()
+            .find(|h| h.id == id || h.name == id)
+            .ok_or_else(|| anyhow::anyhow!("Docker host '{}' not found", id))?,
+        None => hosts.into_iter().next().unwrap_or_else(|| DockerHost {
+            id: "local".to_string(),
+            name: "local".to_string(),
+            url: LOCAL_DOCKER_SOCKET.to_string(),
+            tls_ca: None,
+            tls_cert: None,
+            tls_key: None,
+        }),
+    };
+
+    let host_id = host.id.clone();
+    let mut docker = DockerManager::new();
+    docker.add_host(host)?;
+    Ok((docker, host_id))
+}
@@ -0,0 +1,210 @@
+//! `pctrl doctor`: broken references and how to repair them
+//!
+//! Without flags the plan is only shown. `--fix` applies the repairs the
+//! planner is sure about; `--interactive` walks every finding and asks.
+
+use super::CommandFailed;
+use crate::style;
+use pctrl_core::doctor::{self, Candidate, Choice, Repair, Step};
+use pctrl_database::Database;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+pub async fn handle(db: &Database, fix: bool, interactive: bool) -> anyhow::Result<()> {
+    let steps = doctor::plan(db.integrity_findings().await?);
+    if steps.is_empty() {
+        noteln!("{}", style::success_text("✓ No problems found"));
+        return Ok(());
+    }
+    if interactive {
+        return walk(db, &steps).await;
+    }
+
+    let total = steps.len();
+    let mut open = 0;
+    for step in &steps {
+        print_step(step);
+        match (&step.repair, fix) {
+            (Some(repair), true) => match db.apply_repair(&step.finding, repair).await {
+                Ok(()) => outln!("    {}", style::success_text(&format!("✓ {}", repair))),
+                Err(e) => {
+                    outln!("    {}", style::error_text(&format!("✗ {}", e)));
+                    open += 1;
+                }
+            },
+            (Some(repair), false) => {
+                outln!("    {}", style::dim(&format!("--fix: {}", repair)));
+                open += 1;
+            }
+            (None, _) => open += 1,
+        }
+    }
+
+    outln!();
+    if open == 0 {
+        noteln!(
+            "{}",
+            style::success_text(&format!("✓ Repaired {}", problems(total)))
+        );
+        return Ok(());
+    }
+    if !fix {
+        noteln!(
+            "{}",
+            style::dim("Apply the suggested repairs with --fix, or decide each with --interactive")
+        );
+    } else {
+        noteln!(
+            "{}",
+            style::dim("Decide the rest with: pctrl doctor --interactive")
+        );
+    }
+    Err(CommandFailed::new(1, format!("{} left", problems(open))).into())
+}
+
+fn problems(count: usize) -> String {
+    pctrl_core::humanize::count(count as u64, "problem", "problems")
+}
+
+fn print_step(step: &Step) {
+    outln!(
+        "{} {} {}",
+        style::warning_text("⚠"),
+        style::dim(&format!("[{}]", step.finding.kind)),
+        step.finding.describe()
+    );
+    if let Some(note) = &step.note {
+        outln!("    {}", style::dim(note));
+    }
+}
+
+/// Ask about every finding in plan order
+async fn walk(db: &Database, steps: &[Step]) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("--interactive needs a terminal; use --fix to apply the safe repairs");
+    }
+
+    let mut repaired = 0;
+    for (index, step) in steps.iter().enumerate() {
+        outln!();
+        out!(
+            "{} ",
+            style::dim(&format!("[{}/{}]", index + 1, steps.len()))
+        );
+        print_step(step);
+
+        let choices = step.finding.choices();
+        if choices == [Choice::Skip] {
+            outln!("    {}", style::dim("Nothing to repair here; skipped"));
+            continue;
+        }
+
+        let Some(repair) = ask(db, step, &choices).await? else {
+            continue;
+        };
+        match db.apply_repair(&step.finding, &repair).await {
+            Ok(()) => {
+                outln!("    {}", style::success_text(&format!("✓ {}", repair)));
+                repaired += 1;
+            }
+            Err(e) => outln!("    {}", style::error_text(&format!("✗ {}", e))),
+        }
+    }
+
+    outln!();
+    noteln!("Repaired {} of {}", repaired, problems(steps.len()));
+    Ok(())
+}
+
+/// The user's repair for a step; `None` to skip. Enter takes the suggestion.
+async fn ask(db: &Database, step: &Step, choices: &[Choice]) -> anyhow::Result<Option<Repair>> {
+    let options: Vec<&str> = choices
+        .iter()
+        .map(|c| match c {
+            Choice::Relink => "[r]elink",
+            Choice::Null => "[n]ull",
+            Choice::Delete => "[d]elete link",
+            Choice::Skip => "[s]kip",
+        })
+        .collect();
+    let suggestion = step
+        .repair
+        .as_ref()
+        .map(|r| format!(" (Enter: {})", r))
+        .unwrap_or_default();
+
+    loop {
+        let answer = read_answer(&format!(
+            "    {} or [q]uit{}: ",
+            options.join(", "),
+            suggestion
+        ))?;
+        let choice = match answer.as_str() {
+            "" if step.repair.is_some() => return Ok(step.repair.clone()),
+            "" | "s" => return Ok(None),
+            "q" => anyhow::bail!("Stopped; earlier repairs were kept"),
+            "r" => Choice::Relink,
+            "n" => Choice::Null,
+            "d" => Choice::Delete,
+            _ => continue,
+        };
+        if !choices.contains(&choice) {
+            continue;
+        }
+        match choice {
+            Choice::Relink => {
+                let entities = db.doctor_candidates(step.finding.target).await?;
+                if let Some(candidate) = pick(&entities, &step.finding.target.to_string())? {
+                    return Ok(Some(Repair::Relink(candidate)));
+                }
+            }
+            Choice::Null => return Ok(Some(Repair::Null)),
+            Choice::Delete => return Ok(Some(Repair::Delete)),
+            Choice::Skip => return Ok(None),
+        }
+    }
+}
+
+/// Choose an entity by typing part of its name; empty input goes back
+fn pick(entities: &[Candidate], kind: &str) -> anyhow::Result<Option<Candidate>> {
+    if entities.is_empty() {
+        outln!(
+            "    {}",
+            style::dim(&format!("There is no {} to relink to", kind))
+        );
+        return Ok(None);
+    }
+    loop {
+        let query = read_answer(&format!("    Relink to which {}? ", kind))?;
+        if query.is_empty() {
+            return Ok(None);
+        }
+        let matches = doctor::fuzzy_pick(&query, entities);
+        match matches.as_slice() {
+            [] => outln!("    {}", style::dim("No match")),
+            [only] => return Ok(Some((*only).clone())),
+            several => {
+                for (i, candidate) in several.iter().take(9).enumerate() {
+                    outln!("      {}) {}", i + 1, candidate.name);
+                }
+                let number = read_answer("    Number (Enter to search again): ")?;
+                if let Some(candidate) = number
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| several.get(n.wrapping_sub(1)).filter(|_| n <= 9))
+                {
+                    return Ok(Some((*candidate).clone()));
+                }
+            }
+        }
+    }
+}
+
+fn read_answer(prompt: &str) -> anyhow::Result<String> {
+    out!("{}", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        anyhow::bail!("Aborted");
+    }
+    Ok(answer.trim().to_lowercase())
+}
@@ -1,7 +1,18 @@
 //! Domain command handler
 
-use crate::DomainCommands;
-use pctrl_core::{Domain, DomainType};
+use super::audit::{history_view, print_ensured, print_updated};
+use super::cloudflare;
+use super::hints;
+use super::journal;
+use super::propagation;
+use super::references::{guard_remove, handle_deps};
+use super::resolve::{find_domain, find_server, name_taken, ref_tag};
+use crate::{style, DomainCommands};
+use pctrl_core::diff;
+use pctrl_core::domain_base::BasePlan;
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::throttle::Limits;
+use pctrl_core::{humanize, hyperlink, Domain, DomainPatch, DomainType, EntityType};
 use pctrl_database::Database;
 
 pub async fn handle(command: DomainCommands, db: &Database) -> anyhow::Result<()> {
@@ -9,16 +20,29 @@ pub async fn handle(command: DomainCommands, db: &Database) -> anyhow::Result<()
         DomainCommands::List => {
             let domains = db.list_domains().await?;
             if domains.is_empty() {
-                println!("No domains configured.");
-                println!();
-                println!("Add one with:");
-                println!("  pctrl domain add <domain> [-t type] [-s server]");
+                outln!("No domains configured.");
+                hints::show(db, Event::Empty(Listing::Domains)).await?;
             } else {
-                println!("Domains ({}):", domains.len());
-                println!();
-                for domain in domains {
+                outln!("Domains ({}):", domains.len());
+                outln!();
+                let refs = db.short_refs(EntityType::Domain).await?;
+                for domain in &domains {
                     let ssl_icon = if domain.ssl { "🔒" } else { "🔓" };
-                    println!("  {} {} [{}]", ssl_icon, domain.domain, domain.domain_type);
+                    let superseded = domain.superseded_by.as_ref().map(|id| {
+                        let name = domains
+                            .iter()
+                            .find(|d| &d.id == id)
+                            .map_or(id.as_str(), |d| d.domain.as_str());
+                        format!(" {}", style::dim(&format!("→ superseded by {}", name)))
+                    });
+                    outln!(
+                        "  {} {}{} [{}]{}",
+                        ssl_icon,
+                        hyperlink::web(&domain.domain),
+                        ref_tag(&refs, &domain.id),
+                        domain.domain_type,
+                        superseded.unwrap_or_default()
+                    );
                 }
             }
         }
@@ -31,100 +55,305 @@ pub async fn handle(command: DomainCommands, db: &Database) -> anyhow::Result<()
             ssl_expiry,
             cloudflare_zone,
             cloudflare_record,
+            ensure,
         } => {
-            let id = domain.replace('.', "-").to_lowercase();
-
-            if db.get_domain_by_name(&domain).await?.is_some() {
+            let existing = db.get_domain_by_name(&domain).await?;
+            if existing.is_some() && !ensure {
                 anyhow::bail!("Domain '{}' already exists.", domain);
             }
+            let id = existing
+                .map(|d| d.id)
+                .unwrap_or_else(|| domain.replace('.', "-").to_lowercase());
 
-            let domain_type: DomainType = domain_type.parse().unwrap_or_default();
+            let patched_type: Option<DomainType> =
+                domain_type.map(|t| t.parse().unwrap_or_default());
+            let domain_type = patched_type.clone().unwrap_or_default();
 
             let dom = Domain {
                 id: id.clone(),
                 domain: domain.clone(),
                 domain_type: domain_type.clone(),
-                ssl,
+                ssl: ssl.unwrap_or(true),
                 ssl_expiry: ssl_expiry.clone(),
                 cloudflare_zone_id: cloudflare_zone.clone(),
                 cloudflare_record_id: cloudflare_record.clone(),
                 server_id: server.clone(),
                 container_id: None,
                 notes: None,
+                superseded_by: None,
             };
 
-            db.save_domain(&dom).await?;
+            if ensure {
+                let patch = DomainPatch {
+                    domain_type: patched_type,
+                    ssl,
+                    ssl_expiry,
+                    cloudflare_zone_id: cloudflare_zone,
+                    cloudflare_record_id: cloudflare_record,
+                    server_id: server,
+                };
+                let ensured = db.ensure_domain(&dom, &patch).await?;
+                print_ensured("Domain", &domain, &ensured);
+                return Ok(());
+            }
+
+            db.create_domain(&dom)
+                .await
+                .map_err(|e| name_taken("Domain", e))?;
+            let ssl = dom.ssl;
 
-            println!("✓ Domain added:");
-            println!();
-            println!("  Domain: {}", domain);
-            println!("  ID:     {}", id);
-            println!("  Type:   {}", domain_type);
-            println!("  SSL:    {}", if ssl { "enabled" } else { "disabled" });
+            noteln!("✓ Domain added:");
+            noteln!();
+            noteln!("  Domain: {}", domain);
+            noteln!("  ID:     {}", id);
+            noteln!("  Type:   {}", domain_type);
+            noteln!("  SSL:    {}", if ssl { "enabled" } else { "disabled" });
             if let Some(exp) = &ssl_expiry {
-                println!("  Expiry: {}", exp);
+                noteln!("  Expiry: {}", exp);
             }
             if let Some(s) = &server {
-                println!("  Server: {}", s);
+                noteln!("  Server: {}", s);
             }
             if cloudflare_zone.is_some() || cloudflare_record.is_some() {
-                println!();
-                println!("  Cloudflare:");
+                noteln!();
+                noteln!("  Cloudflare:");
                 if let Some(z) = &cloudflare_zone {
-                    println!("    Zone:   {}", z);
+                    noteln!("    Zone:   {}", z);
                 }
                 if let Some(r) = &cloudflare_record {
-                    println!("    Record: {}", r);
+                    noteln!("    Record: {}", r);
                 }
             }
+            hints::show(db, Event::DomainAdded { domain, server }).await?;
+        }
+
+        DomainCommands::Edit {
+            domain,
+            domain_type,
+            server,
+            no_server,
+            ssl,
+        } => {
+            let before = find_domain(db, &domain).await?;
+            let mut dom = before.clone();
+            if let Some(domain_type) = domain_type {
+                dom.domain_type = domain_type
+                    .parse()
+                    .map_err(|e: String| anyhow::anyhow!(e))?;
+            }
+            if let Some(server) = server {
+                dom.server_id = Some(find_server(db, &server).await?.id);
+            } else if no_server {
+                dom.server_id = None;
+            }
+            if let Some(ssl) = ssl {
+                dom.ssl = ssl;
+            }
+
+            let changes = diff::diff(&before, &dom);
+            if !changes.is_empty() {
+                db.update_domain(&dom).await?;
+            }
+            print_updated("Domain", &dom.domain, &changes);
         }
 
         DomainCommands::Show { domain } => {
-            let dom = db
-                .get_domain_by_name(&domain)
-                .await?
-                .or(db.get_domain(&domain).await?)
-                .ok_or_else(|| anyhow::anyhow!("Domain '{}' not found", domain))?;
+            let dom = find_domain(db, &domain).await?;
 
             let ssl_icon = if dom.ssl { "🔒" } else { "🔓" };
 
-            println!();
-            println!("  {} {}", ssl_icon, dom.domain);
-            println!("  ─────────────────────────────");
-            println!("  ID:     {}", dom.id);
-            println!("  Type:   {}", dom.domain_type);
-            println!("  SSL:    {}", if dom.ssl { "enabled" } else { "disabled" });
+            outln!();
+            outln!("  {} {}", ssl_icon, hyperlink::web(&dom.domain));
+            outln!("  ─────────────────────────────");
+            outln!("  ID:     {}", dom.id);
+            if let Some(short_ref) = db.short_ref(EntityType::Domain, &dom.id).await? {
+                outln!("  Ref:    {}", short_ref);
+            }
+            outln!("  Type:   {}", dom.domain_type);
+            outln!("  SSL:    {}", if dom.ssl { "enabled" } else { "disabled" });
             if let Some(exp) = &dom.ssl_expiry {
-                println!("  Expiry: {}", exp);
+                outln!("  Expiry: {}", exp);
             }
             if let Some(s) = &dom.server_id {
-                println!("  Server: {}", s);
+                outln!("  Server: {}", s);
+            }
+            if let Some(id) = &dom.superseded_by {
+                let name = db.get_domain(id).await?.map_or(id.clone(), |d| d.domain);
+                outln!("  Superseded by: {}", name);
             }
             if dom.cloudflare_zone_id.is_some() || dom.cloudflare_record_id.is_some() {
-                println!();
-                println!("  Cloudflare:");
+                outln!();
+                outln!("  Cloudflare:");
                 if let Some(z) = &dom.cloudflare_zone_id {
-                    println!("    Zone:   {}", z);
+                    outln!("    Zone:   {}", z);
                 }
                 if let Some(r) = &dom.cloudflare_record_id {
-                    println!("    Record: {}", r);
+                    outln!("    Record: {}", r);
                 }
             }
-            println!();
+            outln!();
         }
 
-        DomainCommands::Remove { domain } => {
-            let dom = db
-                .get_domain_by_name(&domain)
-                .await?
-                .or(db.get_domain(&domain).await?)
-                .ok_or_else(|| anyhow::anyhow!("Domain '{}' not found", domain))?;
+        DomainCommands::HistoryView { name, at, timeline } => {
+            history_view(db, EntityType::Domain, &name, at, timeline).await?;
+        }
 
+        DomainCommands::Remove { domain, force } => {
+            let dom = find_domain(db, &domain).await?;
+
+            guard_remove(db, EntityType::Domain, &dom.id, &dom.domain, force).await?;
             if db.remove_domain(&dom.id).await? {
-                println!("✓ Domain '{}' removed", dom.domain);
+                noteln!("✓ Domain '{}' removed", dom.domain);
             }
         }
+
+        DomainCommands::Deps { domain, json } => {
+            let dom = find_domain(db, &domain).await?;
+
+            handle_deps(db, EntityType::Domain, &dom.id, &dom.domain, json).await?;
+        }
+
+        DomainCommands::Log {
+            domain,
+            text,
+            show,
+            limit,
+            amend,
+        } => {
+            let dom = find_domain(db, &domain).await?;
+
+            if show {
+                journal::show(db, EntityType::Domain, &dom.id, &dom.domain, limit).await?;
+            } else {
+                let text = text.unwrap_or_default();
+                journal::append(db, EntityType::Domain, &dom.id, &dom.domain, &text, amend).await?;
+            }
+        }
+
+        DomainCommands::Propagation {
+            domain,
+            all,
+            per_resolver,
+            rate,
+            expect,
+            resolvers,
+            watch,
+            timeout,
+            track,
+            json,
+        } => match domain {
+            Some(domain) if !all => {
+                if per_resolver.is_some() || rate.is_some() {
+                    anyhow::bail!("--per-resolver and --rate only apply with --all");
+                }
+                propagation::handle(db, domain, expect, resolvers, watch, timeout, track, json)
+                    .await?;
+            }
+            _ => {
+                let defaults = Limits::default();
+                let limits = Limits {
+                    per_group: per_resolver.unwrap_or(defaults.per_group),
+                    per_second: rate.unwrap_or(defaults.per_second),
+                    ..defaults
+                };
+                propagation::check_all(db, resolvers, &limits, json).await?;
+            }
+        },
+
+        DomainCommands::Sync { domain, credential } => {
+            cloudflare::sync(db, &domain, &credential).await?
+        }
+
+        DomainCommands::Point {
+            domain,
+            server,
+            credential,
+            proxied,
+            dry_run,
+        } => cloudflare::point(db, &domain, &server, &credential, proxied, dry_run).await?,
+
+        DomainCommands::MigrateBase {
+            old_base,
+            new_base,
+            dry_run,
+        } => migrate_base(db, &old_base, &new_base, dry_run).await?,
     }
 
     Ok(())
 }
+
+/// Plan and apply a base migration
+async fn migrate_base(
+    db: &Database,
+    old_base: &str,
+    new_base: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let domains = db.list_domains().await?;
+    let plan = BasePlan::new(&domains, old_base, new_base).map_err(|e| anyhow::anyhow!(e))?;
+    if plan.renames.is_empty() {
+        outln!("No domains under {}.", plan.old_base);
+        return Ok(());
+    }
+
+    outln!("Renames ({} → {}):", plan.old_base, plan.new_base);
+    outln!();
+    let width = plan
+        .renames
+        .iter()
+        .map(|r| r.domain.domain.len())
+        .max()
+        .unwrap_or(0);
+    for rename in &plan.renames {
+        let line = format!("  {:<width$} → {}", rename.domain.domain, rename.to);
+        match &rename.collision {
+            Some(existing) => outln!(
+                "{} {}",
+                line,
+                style::warning_text(&format!("(skipped: {} already exists)", existing))
+            ),
+            None => outln!("{}", line),
+        }
+    }
+
+    let applicable = plan.applicable().count();
+    if dry_run || applicable == 0 {
+        outln!();
+        outln!(
+            "{}",
+            style::dim(if dry_run {
+                "Dry run, nothing changed."
+            } else {
+                "Nothing to migrate."
+            })
+        );
+        return Ok(());
+    }
+
+    let migration = db.migrate_domain_base(&plan).await?;
+    outln!();
+    noteln!(
+        "✓ {} migrated (old rows kept, marked superseded)",
+        humanize::count(migration.domains as u64, "domain", "domains")
+    );
+    if migration.links > 0 {
+        outln!(
+            "  {} moved to the new names",
+            humanize::count(migration.links, "project link", "project links")
+        );
+    }
+    for change in &migration.health_urls {
+        outln!(
+            "  Health URL of project {}: {} → {}",
+            change.project_id,
+            change.from,
+            change.to
+        );
+    }
+    outln!();
+    outln!(
+        "{}",
+        style::dim("Reverse proxy configs and certificates are not changed; update them for the new names.")
+    );
+    Ok(())
+}
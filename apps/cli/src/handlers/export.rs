@@ -0,0 +1,267 @@
+//! Export command handler
+
+use super::backup::new_passphrase;
+use super::expand_home;
+use crate::{style, ExportCommands};
+use futures_util::StreamExt;
+use pctrl_core::config_export::DocumentFormat;
+use pctrl_core::export::{
+    ansible_inventory, splice_managed_section, ssh_config, ExportHost, GroupBy,
+};
+use pctrl_core::table_export::{self, ExportTable, RowFilter, TableFormat};
+use pctrl_core::{humanize, hyperlink, ResourceType};
+use pctrl_database::backup;
+use pctrl_database::Database;
+use std::io::{BufWriter, Write};
+
+/// Handle `pctrl export [--file]`: the whole setup as one document
+pub async fn handle_document(
+    db: &Database,
+    file: Option<String>,
+    include_secrets: bool,
+    encrypt: bool,
+) -> anyhow::Result<()> {
+    let document = db.config_document(include_secrets).await?;
+    let Some(file) = file else {
+        out!(
+            "{}",
+            document
+                .render(DocumentFormat::Yaml)
+                .map_err(anyhow::Error::msg)?
+        );
+        return Ok(());
+    };
+
+    let path = expand_home(&file);
+    let format = DocumentFormat::for_path(&path);
+    let text = document.render(format).map_err(anyhow::Error::msg)?;
+    if encrypt {
+        let passphrase = new_passphrase()?;
+        let mut out = std::fs::File::create(&path)?;
+        backup::encrypt(&mut text.as_bytes(), &mut out, &passphrase)?;
+    } else {
+        std::fs::write(&path, &text)?;
+    }
+
+    noteln!(
+        "✓ Wrote {} as {}{} to {}",
+        humanize::count(document.count() as u64, "entity", "entities"),
+        format,
+        if encrypt { ", encrypted," } else { "" },
+        hyperlink::folder_of(&path.to_string_lossy())
+    );
+    if include_secrets && !encrypt {
+        outln!(
+            "{}",
+            style::warning_text(
+                "The file holds secrets in plain text; keep it safe or use --encrypt."
+            )
+        );
+    } else if !include_secrets {
+        noteln!(
+            "  {}",
+            style::dim("Credential data and database passwords left out (--include-secrets)")
+        );
+    }
+    Ok(())
+}
+
+pub async fn handle(command: ExportCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        ExportCommands::Ansible { group_by, out } => {
+            let group_by: GroupBy = group_by.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            let hosts = export_hosts(db, Some(group_by)).await?;
+            let inventory = ansible_inventory(&hosts);
+
+            match out {
+                Some(out) => {
+                    let path = expand_home(&out);
+                    std::fs::write(&path, inventory)?;
+                    noteln!(
+                        "✓ Wrote {} to {}",
+                        humanize::count(hosts.len() as u64, "host", "hosts"),
+                        hyperlink::folder_of(&path.to_string_lossy())
+                    );
+                    print_missing_ssh(&hosts);
+                }
+                None => out!("{}", inventory),
+            }
+        }
+
+        ExportCommands::SshConfig { out } => {
+            let hosts = export_hosts(db, None).await?;
+            let section = ssh_config(&hosts);
+
+            match out {
+                Some(out) => {
+                    let path = expand_home(&out);
+                    let existing = match std::fs::read_to_string(&path) {
+                        Ok(content) => content,
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                        Err(e) => return Err(e.into()),
+                    };
+                    let content = splice_managed_section(&existing, &section)
+                        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&path, content)?;
+
+                    let entries = hosts.iter().filter(|h| h.ssh.is_some()).count();
+                    noteln!(
+                        "✓ Wrote {} to {}",
+                        humanize::count(entries as u64, "Host entry", "Host entries"),
+                        hyperlink::folder_of(&path.to_string_lossy())
+                    );
+                    print_missing_ssh(&hosts);
+                    if !path.ends_with(".ssh/config") {
+                        noteln!(
+                            "  {}",
+                            style::dim(&format!(
+                                "Use it from ~/.ssh/config with: Include {}",
+                                path.display()
+                            ))
+                        );
+                    }
+                }
+                None => out!("{}", section),
+            }
+        }
+
+        ExportCommands::Table {
+            name,
+            format,
+            columns,
+            filter,
+            show_secrets,
+            out,
+            list_columns,
+        } => {
+            let table: ExportTable = name.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            if list_columns {
+                for column in table.columns() {
+                    let secret = if column.is_secret() {
+                        style::warning_text(" (--show-secrets)")
+                    } else {
+                        String::new()
+                    };
+                    outln!("  {:<22} {}{}", column.name, column.doc, secret);
+                }
+                return Ok(());
+            }
+            let format: TableFormat = format.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            let filter = RowFilter::new(table, &columns, &filter, show_secrets)
+                .map_err(|e| anyhow::anyhow!(e))?;
+
+            // Data goes out unstyled, row by row
+            let path = out.as_deref().map(expand_home);
+            let mut writer: Box<dyn Write> = match &path {
+                Some(path) => Box::new(BufWriter::new(std::fs::File::create(path)?)),
+                None => Box::new(BufWriter::new(std::io::stdout().lock())),
+            };
+            let rows = write_table(db, table, format, &filter, &mut writer).await?;
+            writer.flush()?;
+            drop(writer);
+
+            if let Some(path) = path {
+                noteln!(
+                    "✓ Wrote {} to {}",
+                    humanize::count(rows, "row", "rows"),
+                    hyperlink::folder_of(&path.to_string_lossy())
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the matching rows of `table` as they are read; returns the count
+async fn write_table(
+    db: &Database,
+    table: ExportTable,
+    format: TableFormat,
+    filter: &RowFilter,
+    writer: &mut dyn Write,
+) -> anyhow::Result<u64> {
+    match format {
+        TableFormat::Csv => writeln!(writer, "{}", table_export::csv_header(filter))?,
+        TableFormat::Json => write!(writer, "[")?,
+        TableFormat::Ndjson => {}
+    }
+
+    let mut rows = db.stream_table(table);
+    let mut count = 0;
+    while let Some(row) = rows.next().await {
+        let row = table_export::flatten(row?);
+        if !filter.matches(&row) {
+            continue;
+        }
+        match format {
+            TableFormat::Csv => writeln!(writer, "{}", table_export::csv_row(filter, &row))?,
+            TableFormat::Json => write!(
+                writer,
+                "{}\n  {}",
+                if count == 0 { "" } else { "," },
+                table_export::json_row(filter, &row)
+            )?,
+            TableFormat::Ndjson => writeln!(writer, "{}", table_export::json_row(filter, &row))?,
+        }
+        count += 1;
+    }
+
+    if format == TableFormat::Json {
+        writeln!(writer, "{}]", if count == 0 { "" } else { "\n" })?;
+    }
+    Ok(count)
+}
+
+/// All servers with their SSH access and, if requested, their groups
+async fn export_hosts(db: &Database, group_by: Option<GroupBy>) -> anyhow::Result<Vec<ExportHost>> {
+    let servers = db.list_servers().await?;
+    let links = match group_by {
+        Some(GroupBy::Project) => db.list_all_project_resources().await?,
+        _ => Vec::new(),
+    };
+    let projects = match group_by {
+        Some(GroupBy::Project) => db.list_projects().await?,
+        _ => Vec::new(),
+    };
+
+    let mut hosts = Vec::new();
+    for server in &servers {
+        let credential = match server.credential_id {
+            Some(ref id) => db.get_credential(id).await?,
+            None => None,
+        };
+        let mut host = ExportHost::new(server, credential.as_ref());
+        host.groups = match group_by {
+            Some(GroupBy::Provider) => server.provider.iter().cloned().collect(),
+            Some(GroupBy::Type) => vec![server.server_type.to_string()],
+            Some(GroupBy::Project) => links
+                .iter()
+                .filter(|l| l.resource_type == ResourceType::Server && l.resource_id == server.id)
+                .filter_map(|l| projects.iter().find(|p| p.id == l.project_id))
+                .map(|p| p.name.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        hosts.push(host);
+    }
+
+    Ok(hosts)
+}
+
+fn print_missing_ssh(hosts: &[ExportHost]) {
+    let missing: Vec<&str> = hosts
+        .iter()
+        .filter(|h| h.ssh.is_none())
+        .map(|h| h.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        outln!(
+            "  {}",
+            style::warning_text(&format!("No SSH credential: {}", missing.join(", ")))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Reports of fan-out commands (`--json`, `--fail-on`, `pctrl last-run`) and
+//! the quota check before they start containers
+
+use super::CommandFailed;
+use crate::style;
+use pctrl_core::fanout::{FailOn, FanoutReport, Outcome};
+use pctrl_core::quota::{self, EXIT_QUOTA_EXCEEDED};
+use pctrl_core::{humanize, Container};
+use pctrl_database::Database;
+
+/// Keep `report` as the command's last run, print it and fail per `fail_on`
+pub(crate) async fn finish(
+    db: &Database,
+    report: &FanoutReport,
+    json: bool,
+    fail_on: FailOn,
+) -> anyhow::Result<()> {
+    db.save_last_run(report).await?;
+    if json {
+        outln!("{}", serde_json::to_string_pretty(report)?);
+    } else {
+        outln!();
+        print_report(report);
+    }
+
+    match fail_on.exit_code(&report.counts) {
+        0 => Ok(()),
+        code => Err(
+            CommandFailed::new(code, format!("{}: {}", report.command, report.summary())).into(),
+        ),
+    }
+}
+
+/// Check the quotas of `server_id` before `names` (containers on it) are
+/// started. Breaches are warnings, or refuse the action with `enforce`.
+/// Containers `docker sync` hasn't recorded yet count as one container
+/// without a memory limit.
+pub(crate) async fn guard_quotas(
+    db: &Database,
+    server_id: &str,
+    names: &[&str],
+    enforce: bool,
+) -> anyhow::Result<()> {
+    let servers = db.list_servers().await?;
+    let containers = db.list_containers_for_server(server_id).await?;
+    let unknown: Vec<Container> = names
+        .iter()
+        .filter(|name| {
+            !containers
+                .iter()
+                .any(|c| c.name == **name || c.id == **name)
+        })
+        .map(|name| Container {
+            id: name.to_string(),
+            name: name.to_string(),
+            image: None,
+            server_id: server_id.to_string(),
+            project_id: None,
+            status: Default::default(),
+            ports: Vec::new(),
+            env_vars: None,
+            labels: None,
+            memory_limit_mb: None,
+        })
+        .collect();
+    let starting: Vec<&Container> = containers
+        .iter()
+        .filter(|c| names.iter().any(|n| c.name == *n || c.id == *n))
+        .chain(&unknown)
+        .collect();
+
+    let breaches = quota::check_start(&servers, &containers, &starting);
+    if breaches.is_empty() {
+        return Ok(());
+    }
+    for breach in &breaches {
+        eoutln!("{}", style::warning_text(&format!("⚠ Quota: {}", breach)));
+    }
+    if enforce {
+        return Err(CommandFailed::new(
+            EXIT_QUOTA_EXCEEDED,
+            "Refused: the action would exceed a server quota (--enforce-quotas)",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `pctrl last-run [<command>...]`; the words form the command path
+pub async fn last_run(db: &Database, command: &[String], json: bool) -> anyhow::Result<()> {
+    if command.is_empty() {
+        let reports = db.list_last_runs().await?;
+        if json {
+            outln!("{}", serde_json::to_string_pretty(&reports)?);
+            return Ok(());
+        }
+        if reports.is_empty() {
+            outln!("No fan-out command has run yet.");
+            return Ok(());
+        }
+        for report in &reports {
+            outln!(
+                "  {:<16} {:<28} {}",
+                report.command,
+                report.summary(),
+                style::dim(&humanize::relative_timestamp(&report.started_at))
+            );
+        }
+        return Ok(());
+    }
+
+    let command = command.join(" ");
+    let Some(report) = db.get_last_run(&command).await? else {
+        anyhow::bail!("'{}' has no recorded run", command);
+    };
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        outln!(
+            "{} {}",
+            style::header(&report.command),
+            style::dim(&format!(
+                "({})",
+                humanize::relative_timestamp(&report.started_at)
+            ))
+        );
+        outln!();
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn print_report(report: &FanoutReport) {
+    let width = report
+        .targets
+        .iter()
+        .map(|t| t.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    for target in &report.targets {
+        let outcome = format!("{:<7}", target.outcome.to_string());
+        let duration = match target.outcome {
+            Outcome::Skipped => "-".to_string(),
+            _ => format!("{}ms", target.duration_ms),
+        };
+        outln!(
+            "  {:<width$}  {}  {:>7}  {}",
+            target.name,
+            match target.outcome {
+                Outcome::Ok => style::success_text(&outcome),
+                Outcome::Failed => style::error_text(&outcome),
+                Outcome::Skipped => style::warning_text(&outcome),
+            },
+            duration,
+            style::dim(target.message.as_deref().unwrap_or(""))
+        );
+    }
+    outln!();
+    let summary = format!("{} ({}ms)", report.summary(), report.duration_ms);
+    if report.counts.failed > 0 {
+        outln!("  {}", style::error_text(&summary));
+    } else {
+        outln!("  {}", style::success_text(&summary));
+    }
+}
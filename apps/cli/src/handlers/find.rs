@@ -0,0 +1,82 @@
+//! `pctrl find` - search every entity by name and details
+
+use super::references::capitalize;
+use super::resolve::ref_tag;
+use crate::style;
+use pctrl_core::search::SearchHit;
+use pctrl_core::EntityType;
+use pctrl_database::Database;
+
+/// Handle `pctrl find <query> [--type <types>]`
+pub async fn handle(
+    db: &Database,
+    query: String,
+    types: Vec<String>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let types: Vec<EntityType> = types
+        .iter()
+        .map(|t| t.parse().map_err(|e: String| anyhow::anyhow!(e)))
+        .collect::<anyhow::Result<_>>()?;
+    let mut hits = db.search(&query).await?;
+    if !types.is_empty() {
+        hits.retain(|h| types.contains(&h.entity_type));
+    }
+
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        outln!("{}", style::dim(&format!("Nothing matches '{}'.", query)));
+        return Ok(());
+    }
+
+    for entity_type in [
+        EntityType::Project,
+        EntityType::Server,
+        EntityType::Domain,
+        EntityType::Database,
+        EntityType::Script,
+        EntityType::Credential,
+    ] {
+        let group: Vec<&SearchHit> = hits
+            .iter()
+            .filter(|h| h.entity_type == entity_type)
+            .collect();
+        if group.is_empty() {
+            continue;
+        }
+        let refs = db.short_refs(entity_type).await?;
+        outln!(
+            "{}",
+            style::header(&format!(
+                "{}s ({})",
+                capitalize(&entity_type.to_string()),
+                group.len()
+            ))
+        );
+        for hit in group {
+            let detail = hit
+                .detail
+                .as_deref()
+                .map(|d| format!("  {}", d))
+                .unwrap_or_default();
+            let projects = if hit.projects.is_empty() {
+                String::new()
+            } else {
+                format!("  [{}]", hit.projects.join(", "))
+            };
+            outln!(
+                "  {}{}{}{}  {}",
+                hit.name,
+                ref_tag(&refs, &hit.id),
+                detail,
+                projects,
+                style::dim(&format!("({})", hit.field))
+            );
+        }
+        outln!();
+    }
+    Ok(())
+}
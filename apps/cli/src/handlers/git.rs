@@ -0,0 +1,372 @@
+//! Git command handler
+
+use super::resolve::find_credential;
+use crate::{style, GitCommands};
+use pctrl_core::{humanize, GitRepo};
+use pctrl_database::Database;
+use pctrl_git::{GitManager, PushAuth, PushProgress, RepoStatus};
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+/// How often the push progress line is redrawn
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+pub async fn handle(command: GitCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        GitCommands::Repos => {
+            let repos = db.list_git_repos().await?;
+            if repos.is_empty() {
+                outln!("No Git repositories configured.");
+                return Ok(());
+            }
+
+            outln!("Git repositories ({}):", repos.len());
+            outln!();
+            for repo in repos {
+                let credential = match &repo.credential_id {
+                    Some(id) => match db.get_credential(id).await? {
+                        Some(credential) => format!("  🔑 {}", credential.name),
+                        None => format!("  🔑 {} (missing)", id),
+                    },
+                    None => String::new(),
+                };
+                outln!(
+                    "  {} {} {}{}",
+                    repo.name,
+                    style::dim(&format!("[{}]", repo.id)),
+                    style::dim(&repo.path),
+                    credential
+                );
+            }
+        }
+
+        GitCommands::Add {
+            name,
+            path,
+            credential,
+        } => {
+            let path = std::fs::canonicalize(&path)
+                .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path, e))?;
+            let credential_id = match credential {
+                Some(credential) => Some(find_credential(db, &credential).await?.id),
+                None => None,
+            };
+            let repo = GitRepo {
+                id: name.to_lowercase().replace(' ', "-"),
+                name,
+                path: path.to_string_lossy().to_string(),
+                remote_url: None,
+                credential_id,
+            };
+            // Refuse paths that aren't a working tree
+            status_of(&repo)?;
+            db.save_git_repo(&repo).await?;
+            noteln!("✓ Git repository '{}' added ({})", repo.name, repo.path);
+        }
+
+        GitCommands::Remove { id } => match db.get_git_repo(&id).await? {
+            Some(repo) if db.remove_git_repo(&repo.id).await? => {
+                noteln!("✓ Git repository '{}' removed", repo.name)
+            }
+            _ => outln!("✗ Git repository '{}' not found", id),
+        },
+
+        GitCommands::Status { repo, all, json } => {
+            let repos = if all {
+                db.list_git_repos().await?
+            } else {
+                vec![find_repo(db, &repo.unwrap_or_default()).await?]
+            };
+            let statuses: Vec<(GitRepo, Result<RepoStatus, String>)> = repos
+                .into_iter()
+                .map(|repo| {
+                    let status = status_of(&repo).map_err(|e| e.to_string());
+                    (repo, status)
+                })
+                .collect();
+            if !all {
+                if let Some((_, Err(e))) = statuses.first() {
+                    anyhow::bail!("{}", e);
+                }
+            }
+
+            if json {
+                let output: Vec<serde_json::Value> = statuses
+                    .iter()
+                    .map(|(repo, status)| match status {
+                        Ok(status) => serde_json::json!({
+                            "id": repo.id,
+                            "name": repo.name,
+                            "path": repo.path,
+                            "status": status,
+                        }),
+                        Err(e) => serde_json::json!({
+                            "id": repo.id,
+                            "name": repo.name,
+                            "path": repo.path,
+                            "error": e,
+                        }),
+                    })
+                    .collect();
+                outln!("{}", serde_json::to_string_pretty(&output)?);
+                return Ok(());
+            }
+            if statuses.is_empty() {
+                outln!("No Git repositories configured.");
+                return Ok(());
+            }
+            print_table(&statuses);
+        }
+
+        GitCommands::Changelog {
+            repo,
+            from,
+            to,
+            conventional,
+        } => {
+            let repo = find_repo(db, &repo).await?;
+            let (version, markdown) = changelog(&repo, from, &to, None, conventional)?;
+            db.save_changelog(&repo.id, &version, &markdown).await?;
+            outln!("{}", markdown.trim_end());
+        }
+
+        GitCommands::Changelogs { repo, json } => {
+            let repo = find_repo(db, &repo).await?;
+            let entries = db.list_changelog(&repo.id).await?;
+            if json {
+                outln!("{}", serde_json::to_string_pretty(&entries)?);
+                return Ok(());
+            }
+            if entries.is_empty() {
+                outln!(
+                    "No changelogs saved for {} (see `pctrl git changelog`).",
+                    repo.name
+                );
+                return Ok(());
+            }
+            let markdown: Vec<&str> = entries.iter().map(|e| e.content.trim_end()).collect();
+            outln!("{}", markdown.join("\n\n"));
+        }
+
+        GitCommands::Release {
+            repo,
+            tag,
+            message,
+            changelog: with_changelog,
+            conventional,
+        } => {
+            let repo = find_repo(db, &repo).await?;
+            let message = match message {
+                Some(message) => message,
+                None => {
+                    let (_, markdown) = changelog(&repo, None, "HEAD", Some(&tag), conventional)?;
+                    db.save_changelog(&repo.id, &tag, &markdown).await?;
+                    markdown
+                }
+            };
+            let mut git = GitManager::new();
+            git.add_repo(repo.clone());
+            git.create_release(&repo.id, &tag, &message)?;
+            noteln!("✓ Tagged {} as {}", repo.name, tag);
+            if with_changelog {
+                noteln!();
+                noteln!("{}", message.trim_end());
+            }
+        }
+
+        GitCommands::Push { repo, tags, branch } => {
+            let repo = find_repo(db, &repo).await?;
+            let auth = match &repo.credential_id {
+                Some(id) => {
+                    let credential = find_credential(db, id).await?;
+                    let key_material = db.get_key_material(&credential.id).await?;
+                    PushAuth::from_credential(&credential, key_material)
+                }
+                None => PushAuth::default(),
+            };
+            let branch = match branch {
+                Some(branch) => Some(branch),
+                None if tags => None,
+                None => Some(status_of(&repo)?.branch.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is on a detached HEAD; name a branch with --branch",
+                        repo.name
+                    )
+                })?),
+            };
+
+            tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+                let repo_id = repo.id.clone();
+                let mut git = GitManager::new();
+                git.add_repo(repo);
+                if let Some(branch) = &branch {
+                    noteln!("Pushing {} to origin", branch);
+                    with_progress(|progress| git.push_branch(&repo_id, branch, &auth, progress))?;
+                    noteln!("✓ Pushed {}", branch);
+                }
+                if tags {
+                    noteln!("Pushing tags to origin");
+                    with_progress(|progress| git.push_tags(&repo_id, &auth, progress))?;
+                    noteln!("✓ Pushed tags");
+                }
+                Ok(())
+            })
+            .await??;
+        }
+    }
+
+    Ok(())
+}
+
+async fn find_repo(db: &Database, id: &str) -> anyhow::Result<GitRepo> {
+    db.get_git_repo(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Git repository '{}' not found", id))
+}
+
+/// The version a changelog is for and its Markdown: the commits after
+/// `from` (default: the previous tag) up to `to`. Without `version`, the
+/// version is the tag on `to`, `to` itself when it isn't HEAD, or
+/// Unreleased.
+fn changelog(
+    repo: &GitRepo,
+    from: Option<String>,
+    to: &str,
+    version: Option<&str>,
+    conventional: bool,
+) -> anyhow::Result<(String, String)> {
+    let mut git = GitManager::new();
+    git.add_repo(repo.clone());
+    let from = match from {
+        Some(from) => Some(from),
+        None => git.previous_tag(&repo.id, to)?,
+    };
+    let commits = git.commits_between(&repo.id, from.as_deref(), to)?;
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => match git.tag_at(&repo.id, to)? {
+            Some(tag) => tag,
+            None if to != "HEAD" => to.to_string(),
+            None => "Unreleased".to_string(),
+        },
+    };
+    // The day of the last commit; today when there's none
+    let date = commits
+        .last()
+        .map(|c| c.date.chars().take(10).collect())
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+    let summaries: Vec<(&str, &str)> = commits
+        .iter()
+        .map(|c| (c.short_hash.as_str(), c.summary.as_str()))
+        .collect();
+    let markdown = pctrl_core::changelog::render(&version, &date, &summaries, conventional);
+    Ok((version, markdown))
+}
+
+/// Run a push, drawing its progress on one rewritten stderr line where
+/// someone watches it
+fn with_progress(
+    push: impl FnOnce(&mut dyn FnMut(PushProgress)) -> pctrl_core::Result<()>,
+) -> anyhow::Result<()> {
+    let show_progress = !style::is_quiet() && io::stderr().is_terminal();
+    let mut drawn: Option<Instant> = None;
+    let mut on_progress = |progress: PushProgress| {
+        let due = drawn.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL);
+        if show_progress && (due || progress.objects == progress.total) {
+            eprint!(
+                "\r\x1b[2K  Pushing objects: {}/{}  {}",
+                progress.objects,
+                progress.total,
+                humanize::bytes(progress.bytes as u64)
+            );
+            drawn = Some(Instant::now());
+        }
+    };
+    let result = push(&mut on_progress);
+    if drawn.is_some() {
+        eprintln!();
+    }
+    Ok(result?)
+}
+
+fn status_of(repo: &GitRepo) -> anyhow::Result<RepoStatus> {
+    let mut git = GitManager::new();
+    git.add_repo(repo.clone());
+    Ok(git.repo_status(&repo.id)?)
+}
+
+/// One line per repository: name, branch, upstream with ahead/behind,
+/// uncommitted changes and the last commit
+fn print_table(statuses: &[(GitRepo, Result<RepoStatus, String>)]) {
+    let width = |text: &dyn Fn(&RepoStatus) -> String| {
+        statuses
+            .iter()
+            .filter_map(|(_, s)| s.as_ref().ok())
+            .map(|s| text(s).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let name_width = statuses
+        .iter()
+        .map(|(r, _)| r.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let head_width = width(&|s| s.head());
+    let upstream_width = width(&upstream);
+    let changes_width = width(&|s| s.changes());
+
+    for (repo, status) in statuses {
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                outln!(
+                    "  {} {:<name_width$}  {}",
+                    style::error_text("✗"),
+                    repo.name,
+                    style::error_text(e)
+                );
+                continue;
+            }
+        };
+        let (mark, changes) = if status.is_dirty() {
+            (
+                style::warning_text("●"),
+                style::warning_text(&format!("{:<changes_width$}", status.changes())),
+            )
+        } else {
+            (
+                style::success_text("✓"),
+                format!("{:<changes_width$}", status.changes()),
+            )
+        };
+        let last_commit = status
+            .last_commit
+            .as_ref()
+            .map(|c| format!("{} {} ({}, {})", c.short_hash, c.summary, c.author, c.date))
+            .unwrap_or_else(|| "no commits yet".to_string());
+        outln!(
+            "  {} {:<name_width$}  {:<head_width$}  {}  {}  {}",
+            mark,
+            repo.name,
+            status.head(),
+            style::dim(&format!("{:<upstream_width$}", upstream(status))),
+            changes,
+            style::dim(&last_commit)
+        );
+    }
+}
+
+/// `origin/main ↑2 ↓1`, or `no upstream`
+fn upstream(status: &RepoStatus) -> String {
+    let Some(upstream) = &status.upstream else {
+        return "no upstream".to_string();
+    };
+    let mut text = upstream.clone();
+    if status.ahead > 0 {
+        text.push_str(&format!(" ↑{}", status.ahead));
+    }
+    if status.behind > 0 {
+        text.push_str(&format!(" ↓{}", status.behind));
+    }
+    text
+}
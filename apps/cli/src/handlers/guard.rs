@@ -0,0 +1,116 @@
+//! Confirmation guards for commands that touch Live projects and for
+//! dangerous scripts outside their run window
+
+use crate::style;
+use chrono::{DateTime, Local, Utc};
+use pctrl_core::run_window::RunWindow;
+use pctrl_core::{humanize, Project, Script};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Ask before `action` runs against Live projects.
+///
+/// Passes without asking for no projects or with `allow_live`; fails
+/// without a terminal to ask on.
+pub(crate) fn confirm_live(
+    projects: &[Project],
+    action: &str,
+    allow_live: bool,
+) -> anyhow::Result<()> {
+    if projects.is_empty() {
+        return Ok(());
+    }
+
+    let names = projects
+        .iter()
+        .map(|p| format!("'{}' ({})", p.name, p.status))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if allow_live {
+        outln!(
+            "{}",
+            style::warning_text(&format!(
+                "⚠  {} on Live project {} (--allow-live)",
+                action, names
+            ))
+        );
+        return Ok(());
+    }
+
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} targets Live project {}; pass --allow-live to run non-interactively",
+            action,
+            names
+        );
+    }
+
+    outln!(
+        "{}",
+        style::warning_text(&format!("⚠  {} targets Live project {}", action, names))
+    );
+    out!("Type 'live' to continue: ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != "live" {
+        anyhow::bail!("Aborted");
+    }
+    outln!();
+    Ok(())
+}
+
+/// Refuse a dangerous script outside its run window, saying when the next
+/// one opens. With `override_window` the user is asked to type the
+/// script's name instead; that fails without a terminal to ask on. A
+/// stored window that doesn't parse counts as closed.
+pub(crate) fn confirm_run_window(
+    script: &Script,
+    override_window: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let Some(expr) = script.run_window.as_deref().filter(|_| script.dangerous) else {
+        return Ok(());
+    };
+    let refusal = match expr.parse::<RunWindow>() {
+        Ok(window) if window.is_open(now, &Local) => return Ok(()),
+        Ok(window) => match window.next_open(now, &Local) {
+            Some(next) => format!(
+                "Script '{}' may only run {}; the next window opens {} (in {})",
+                script.name,
+                window,
+                window.local_text(next, &Local),
+                humanize::duration((next - now).to_std().unwrap_or_default())
+            ),
+            None => format!("Script '{}' may only run {}", script.name, window),
+        },
+        Err(e) => format!(
+            "Script '{}' has an invalid run window '{}' ({}); fix it with \
+             `pctrl script edit {} --run-window ...`",
+            script.name, expr, e, script.name
+        ),
+    };
+
+    if !override_window {
+        anyhow::bail!("{}; pass --override-window to run it anyway", refusal);
+    }
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{}; --override-window needs a terminal to confirm on",
+            refusal
+        );
+    }
+
+    outln!("{}", style::warning_text(&format!("⚠  {}", refusal)));
+    out!("Type the script name to run it now: ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != script.name {
+        anyhow::bail!("Aborted");
+    }
+    outln!();
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Printing next-step hints; the rules are in [`pctrl_core::hints`]
+
+use crate::style;
+use pctrl_core::hints::{self, Context, Event};
+use pctrl_database::Database;
+
+/// Print the hints for `event` as dim lines after a blank one, unless
+/// `--quiet` or the `hints` setting turns them off
+pub(crate) async fn show(db: &Database, event: Event) -> anyhow::Result<()> {
+    if !style::hints_enabled() {
+        return Ok(());
+    }
+    let context = Context {
+        event,
+        projects: db
+            .list_projects()
+            .await?
+            .into_iter()
+            .map(|p| p.name)
+            .collect(),
+        servers: db
+            .list_servers()
+            .await?
+            .into_iter()
+            .map(|s| s.name)
+            .collect(),
+        credentials: db
+            .list_credentials()
+            .await?
+            .into_iter()
+            .map(|c| c.name)
+            .collect(),
+    };
+    let hints = hints::suggest(&context);
+    if !hints.is_empty() {
+        outln!();
+    }
+    for hint in hints {
+        outln!("{}", style::dim(&hint.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Hooks command handler
+
+use crate::{style, HooksCommands};
+use pctrl_core::hooks::{self, HookPayload, HookRun, HookState};
+use pctrl_core::{humanize, hyperlink};
+use pctrl_database::Database;
+use std::io::Read;
+
+pub async fn handle(command: HooksCommands, db: &Database) -> anyhow::Result<()> {
+    let runner = db
+        .hook_runner()
+        .ok_or_else(|| anyhow::anyhow!("Hooks are not enabled"))?;
+
+    match command {
+        HooksCommands::List => {
+            let files = hooks::discover_all(runner.dir());
+            if files.is_empty() {
+                outln!(
+                    "No hooks in {}",
+                    hyperlink::file(&runner.dir().to_string_lossy())
+                );
+                outln!();
+                outln!("Add an executable to run it after an event, e.g.:");
+                outln!("  {}/server.created/10-notify.sh", runner.dir().display());
+                return Ok(());
+            }
+
+            outln!(
+                "Hooks in {} ({}):",
+                hyperlink::file(&runner.dir().to_string_lossy()),
+                files.len()
+            );
+            let mut event = "";
+            for file in &files {
+                if file.event != event {
+                    event = &file.event;
+                    outln!();
+                    if hooks::is_known_event(event) {
+                        outln!("  {}", style::bold(event));
+                    } else {
+                        outln!(
+                            "  {} {}",
+                            style::bold(event),
+                            style::warning_text("(unknown event, never fired)")
+                        );
+                    }
+                }
+
+                let state = match file.state {
+                    HookState::Disabled => style::dim("disabled"),
+                    HookState::NotExecutable => style::warning_text("not executable"),
+                    HookState::Enabled => match db.last_hook_run(&file.event, &file.name).await? {
+                        Some(run) => describe_run(&run),
+                        None => style::dim("never run"),
+                    },
+                };
+                outln!("    {:<28} {}", file.name, state);
+            }
+        }
+
+        HooksCommands::Run { event, sample } => {
+            if !hooks::is_known_event(&event) {
+                outln!(
+                    "{}",
+                    style::warning_text(&format!("⚠  '{}' is not an event pctrl fires", event))
+                );
+            }
+
+            let payload = if sample {
+                HookPayload::sample(&event)
+            } else {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                let mut payload: HookPayload = serde_json::from_str(&input)
+                    .map_err(|e| anyhow::anyhow!("Invalid payload on stdin: {}", e))?;
+                payload.event = event.clone();
+                payload
+            };
+
+            let runs = db.fire_hooks(&payload).await;
+            if runs.is_empty() {
+                outln!("No enabled hooks for '{}'", event);
+                return Ok(());
+            }
+
+            for run in runs {
+                outln!("{} {}", describe_run(&run), run.hook);
+                for line in run.output.lines() {
+                    outln!("    {}", style::dim(line));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One-line result of a hook run
+fn describe_run(run: &HookRun) -> String {
+    let when = humanize::relative_timestamp(&run.ran_at);
+    if run.success {
+        style::success_text(&format!("✓ {} ({}ms)", when, run.duration_ms))
+    } else if run.timed_out {
+        style::error_text(&format!("✗ {} (timed out)", when))
+    } else {
+        let code = run
+            .exit_code
+            .map(|c| format!("exit {}", c))
+            .unwrap_or_else(|| "not started".to_string());
+        style::error_text(&format!("✗ {} ({})", when, code))
+    }
+}
@@ -0,0 +1,44 @@
+//! HTTP requests with retries
+
+use std::time::Duration;
+
+/// Attempts per request, including the first
+const ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubled for every further one
+const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Time a single attempt may take
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Send a request, retrying connection errors, timeouts, 429 and 5xx.
+///
+/// `request` builds a fresh request for every attempt. Other non-success
+/// statuses fail right away, since repeating them won't help.
+pub(crate) async fn send_with_retry(
+    request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, String> {
+    let mut backoff = FIRST_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let error = match request().timeout(REQUEST_TIMEOUT).send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let error = format!("HTTP {}", status);
+                if !(status.is_server_error() || status.as_u16() == 429) {
+                    return Err(error);
+                }
+                error
+            }
+            Err(e) => e.to_string(),
+        };
+
+        if attempt >= ATTEMPTS {
+            return Err(format!("{} (after {} attempts)", error, attempt));
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,447 @@
+//! `pctrl import`: bring in a setup written by `pctrl export`
+//!
+//! The document is compared with this database the way `pctrl merge`
+//! compares two databases, and written through the same steps. `--merge`
+//! keeps this side of every conflict; `--replace` takes the document's and
+//! removes what the document doesn't have. With neither, conflicts are
+//! decided one by one at a terminal and nothing is removed. `pctrl
+//! snapshot restore` writes its snapshot through [`run`] as well.
+//!
+//! A `.toml` file is a hand-written document: its includes are merged and
+//! its variables resolved first ([`pctrl_core::document`]), with `--var`
+//! over every `[vars]`.
+//!
+//! Secret placeholders (`@prompt`, `@env:NAME`, `@file:PATH`) are resolved
+//! before the first write: if any can't be, nothing is imported.
+
+use super::backup::passphrase;
+use super::merge::{apply, link_result, records};
+use super::{conflicts, fanout};
+use crate::style;
+use pctrl_core::config_export::{keep_local_secrets, lacks_secrets, ConfigDocument};
+use pctrl_core::document;
+use pctrl_core::fanout::{FailOn, FanoutReport, Outcome, TargetResult};
+use pctrl_core::merge::{self, Class, IdMap, Record, Resolution, Step};
+use pctrl_core::placeholder::SecretSource;
+use pctrl_core::{EntityType, ProjectResource};
+use pctrl_database::{backup, Database};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::Instant;
+
+/// Order entities are removed in with `--replace`: dependents first
+const REMOVE_ORDER: [EntityType; 6] = [
+    EntityType::Script,
+    EntityType::Domain,
+    EntityType::Database,
+    EntityType::Server,
+    EntityType::Project,
+    EntityType::Credential,
+];
+
+/// How [`run`] writes a document
+pub(crate) struct ImportOptions {
+    /// How conflicts are settled; `None` asks about each at the terminal
+    pub resolution: Option<Resolution>,
+    /// Entity types removed when the document doesn't have them; links go
+    /// too when this isn't empty
+    pub remove: &'static [EntityType],
+    /// Why a credential without data in the document is skipped
+    pub missing_secrets: &'static str,
+    pub dry_run: bool,
+}
+
+/// What [`run`] did
+pub(crate) struct ImportOutcome {
+    pub report: FanoutReport,
+    pub created: usize,
+    pub updated: usize,
+    pub kept: usize,
+    pub removed: usize,
+    pub identical: usize,
+    /// Entities only this database has
+    pub only_local: usize,
+}
+
+impl ImportOutcome {
+    /// "2 created, 1 updated, 0 skipped, 5 unchanged"
+    pub fn summary(&self, removing: bool) -> String {
+        let mut summary = format!(
+            "{} created, {} updated, {} skipped",
+            self.created, self.updated, self.kept
+        );
+        if removing {
+            summary.push_str(&format!(", {} removed", self.removed));
+        }
+        summary.push_str(&format!(", {} unchanged", self.identical));
+        if !removing && self.only_local > 0 {
+            summary.push_str(&format!(", {} only here", self.only_local));
+        }
+        summary
+    }
+}
+
+/// Handle `pctrl import`
+pub(crate) async fn handle(
+    db: &Database,
+    file: &Path,
+    vars: Vec<(String, String)>,
+    merge: bool,
+    replace: bool,
+    dry_run: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let document = read(file, &vars.into_iter().collect())?;
+    let resolution = if merge || replace || dry_run || json || !io::stdin().is_terminal() {
+        Some(if replace {
+            Resolution::TakeOther
+        } else {
+            Resolution::KeepLocal
+        })
+    } else {
+        None
+    };
+    let options = ImportOptions {
+        resolution,
+        remove: if replace { &REMOVE_ORDER } else { &[] },
+        missing_secrets: "no credential data in the file (export --include-secrets)",
+        dry_run,
+    };
+    let outcome = run(db, &document, "import", &options).await?;
+
+    if !json {
+        let verb = if dry_run { "Would import" } else { "Imported" };
+        let mode = if replace { "replace" } else { "merge" };
+        if document.exported_at.is_empty() {
+            outln!(
+                "{} {} into {} ({})",
+                verb,
+                file.display(),
+                db.path().display(),
+                mode
+            );
+        } else {
+            outln!(
+                "{} {} into {} ({}, exported {})",
+                verb,
+                file.display(),
+                db.path().display(),
+                mode,
+                document.exported_at
+            );
+        }
+        noteln!("  {}", style::dim(&outcome.summary(replace)));
+        if outcome.report.targets.is_empty() {
+            outln!("  {}", style::dim("Nothing to import"));
+            db.save_last_run(&outcome.report).await?;
+            return Ok(());
+        }
+    }
+    fanout::finish(db, &outcome.report, json, FailOn::Any).await
+}
+
+/// Write `document` into the database the way `pctrl merge` writes another
+/// database, reporting every step under `command`. Shared by `pctrl import`
+/// and `pctrl snapshot restore`.
+pub(crate) async fn run(
+    db: &Database,
+    document: &ConfigDocument,
+    command: &str,
+    options: &ImportOptions,
+) -> anyhow::Result<ImportOutcome> {
+    let document = &with_secrets(document, options.dry_run)?;
+    let local_records = records(db).await?;
+    let other_records = document.records().map_err(anyhow::Error::msg)?;
+
+    let mut comparison = merge::compare(&local_records, &other_records);
+    if !document.secrets {
+        keep_local_secrets(&mut comparison);
+    }
+    match &options.resolution {
+        None => {
+            let conflicts = comparison.conflicts();
+            let Some(decisions) = conflicts::resolve(&conflicts)? else {
+                anyhow::bail!("Aborted, nothing imported");
+            };
+            comparison.decide(&conflicts, &decisions);
+        }
+        Some(resolution) => {
+            for entry in comparison.conflicts_mut() {
+                entry.resolution = Some(resolution.clone());
+            }
+        }
+    }
+    let local_links = db.list_all_project_resources().await?;
+    let plan = merge::plan(&comparison, &local_links, &document.links);
+
+    let names: HashMap<&str, &str> = local_records
+        .iter()
+        .chain(comparison.entries.iter().filter_map(|e| e.other.as_ref()))
+        .map(|r| (r.id.as_str(), r.name.as_str()))
+        .collect();
+
+    // Credentials that exist after the import; servers can only use those
+    let mut credentials: HashSet<&str> = local_records
+        .iter()
+        .filter(|r| r.entity_type == EntityType::Credential)
+        .map(|r| r.id.as_str())
+        .collect();
+    for step in &plan.steps {
+        if let Step::Import(record) = step {
+            if record.entity_type == EntityType::Credential && !lacks_secrets(record) {
+                credentials.insert(&record.id);
+            }
+        }
+    }
+
+    let dry_run = options.dry_run;
+    let started = Instant::now();
+    let mut report = FanoutReport::new(
+        command,
+        &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    let (mut created, mut updated, mut kept) = (0, 0, 0);
+    for step in &plan.steps {
+        match step {
+            Step::Import(record) if lacks_secrets(record) => {
+                report.push(TargetResult::skipped(
+                    &format!("{}:{}", record.entity_type, record.id),
+                    &format!("{} {}", record.entity_type, record.name),
+                    options.missing_secrets,
+                ));
+                kept += 1;
+            }
+            Step::Import(record) | Step::Update { record, .. } => {
+                let (step, dropped) = without_missing_credential(step, record, &credentials);
+                let mut result = apply(db, &step, dry_run).await;
+                if let (Some(credential), Some(message)) = (dropped, &mut result.message) {
+                    let credential = names
+                        .get(credential.as_str())
+                        .copied()
+                        .unwrap_or(&credential);
+                    message.push_str(&format!("; credential {} not in the file", credential));
+                }
+                if matches!(step, Step::Import(_)) {
+                    created += 1;
+                } else {
+                    updated += 1;
+                }
+                report.push(result);
+            }
+            Step::Keep { .. } | Step::Unresolved { .. } => {
+                kept += 1;
+                report.push(apply(db, step, dry_run).await);
+            }
+        }
+    }
+    for link in &plan.links {
+        report.push(link_result(db, link, &names, dry_run).await);
+    }
+    created += plan.links.len();
+
+    let mut removed = 0;
+    if !options.remove.is_empty() {
+        for link in stale_links(&local_links, &document.links, &comparison.ids) {
+            report.push(unlink(db, link, &names, dry_run).await);
+            removed += 1;
+        }
+        for entity_type in REMOVE_ORDER
+            .into_iter()
+            .filter(|t| options.remove.contains(t))
+        {
+            for entry in &comparison.entries {
+                let (Class::OnlyLocal, Some(record)) = (&entry.class, &entry.local) else {
+                    continue;
+                };
+                if record.entity_type == entity_type {
+                    report.push(remove(db, record, dry_run).await);
+                    removed += 1;
+                }
+            }
+        }
+    }
+    report.duration_ms = started.elapsed().as_millis() as u64;
+
+    Ok(ImportOutcome {
+        report,
+        created,
+        updated,
+        kept,
+        removed,
+        identical: plan.identical,
+        only_local: plan.only_local,
+    })
+}
+
+/// Prompts for `@prompt` secrets; on a dry run nobody is asked
+struct TerminalSecrets {
+    dry_run: bool,
+}
+
+impl SecretSource for TerminalSecrets {
+    fn prompt(&mut self, label: &str) -> Result<String, String> {
+        if self.dry_run {
+            return Ok("(prompted on import)".to_string());
+        }
+        if !io::stdin().is_terminal() {
+            return Err("@prompt needs a terminal".to_string());
+        }
+        rpassword::prompt_password(format!("{}: ", label)).map_err(|e| e.to_string())
+    }
+}
+
+/// `document` with its secret placeholders resolved
+fn with_secrets(document: &ConfigDocument, dry_run: bool) -> anyhow::Result<ConfigDocument> {
+    let mut document = document.clone();
+    let placeholders = document.placeholders().map_err(anyhow::Error::msg)?;
+    if !placeholders.is_empty() {
+        let secrets = placeholders.resolve(&mut TerminalSecrets { dry_run })?;
+        document
+            .fill_secrets(&secrets)
+            .map_err(anyhow::Error::msg)?;
+    }
+    Ok(document)
+}
+
+/// The document in `file`: resolved with `vars` if it is `.toml`,
+/// decrypted first if it is a `.pctrlbak`
+fn read(file: &Path, vars: &BTreeMap<String, String>) -> anyhow::Result<ConfigDocument> {
+    if !file.is_file() {
+        anyhow::bail!("'{}' not found", file.display());
+    }
+    if file
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("toml"))
+    {
+        let table = document::resolve(file, vars, &document::FileSystem)?;
+        return ConfigDocument::from_table(table)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e));
+    }
+    if !vars.is_empty() {
+        anyhow::bail!("--var only applies to .toml documents");
+    }
+    let text = if backup::is_encrypted_backup(file)? {
+        let passphrase = passphrase("Passphrase: ")?;
+        let mut plain = Vec::new();
+        backup::decrypt(&mut std::fs::File::open(file)?, &mut plain, &passphrase)?;
+        String::from_utf8(plain)?
+    } else {
+        std::fs::read_to_string(file)?
+    };
+    ConfigDocument::parse(&text).map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))
+}
+
+/// A server step without its credential if that credential won't exist,
+/// and the credential that was dropped
+fn without_missing_credential(
+    step: &Step,
+    record: &Record,
+    credentials: &HashSet<&str>,
+) -> (Step, Option<String>) {
+    let missing = match record.fields.get("credential_id") {
+        Some(Value::String(id)) if record.entity_type == EntityType::Server => {
+            (!credentials.contains(id.as_str())).then(|| id.clone())
+        }
+        _ => None,
+    };
+    let Some(credential) = missing else {
+        return (step.clone(), None);
+    };
+    let mut record = record.clone();
+    record
+        .fields
+        .insert("credential_id".to_string(), Value::Null);
+    let step = match step {
+        Step::Update { changed, .. } => Step::Update {
+            record,
+            changed: changed.clone(),
+        },
+        _ => Step::Import(record),
+    };
+    (step, Some(credential))
+}
+
+/// This side's links the document doesn't have
+fn stale_links<'a>(
+    local: &'a [ProjectResource],
+    other: &[ProjectResource],
+    ids: &IdMap,
+) -> Vec<&'a ProjectResource> {
+    let key = |l: &ProjectResource| {
+        (
+            l.project_id.clone(),
+            l.resource_type.to_string(),
+            l.resource_id.clone(),
+        )
+    };
+    let wanted: HashSet<_> = merge::remap_links(&[], other, ids)
+        .iter()
+        .map(key)
+        .collect();
+    local.iter().filter(|l| !wanted.contains(&key(l))).collect()
+}
+
+async fn unlink(
+    db: &Database,
+    link: &ProjectResource,
+    names: &HashMap<&str, &str>,
+    dry_run: bool,
+) -> TargetResult {
+    let name_of = |id: &str| names.get(id).copied().unwrap_or(id).to_string();
+    let id = format!("link:{}", link.id);
+    let name = format!(
+        "link {} {} {}",
+        name_of(&link.project_id),
+        link.resource_type,
+        name_of(&link.resource_id)
+    );
+    if dry_run {
+        return TargetResult::skipped(&id, &name, "would unlink");
+    }
+    let started = Instant::now();
+    let (outcome, message) = match db.unlink_project_resource(&link.id).await {
+        Ok(_) => (Outcome::Ok, "unlinked".to_string()),
+        Err(e) => (Outcome::Failed, e.to_string()),
+    };
+    TargetResult {
+        id,
+        name,
+        outcome,
+        duration_ms: started.elapsed().as_millis() as u64,
+        message: Some(message),
+    }
+}
+
+/// Remove an entity the document doesn't have; servers go to the trash
+async fn remove(db: &Database, record: &Record, dry_run: bool) -> TargetResult {
+    let id = format!("{}:{}", record.entity_type, record.id);
+    let name = format!("{} {}", record.entity_type, record.name);
+    if dry_run {
+        return TargetResult::skipped(&id, &name, "would remove");
+    }
+    let started = Instant::now();
+    let result = match record.entity_type {
+        EntityType::Script => db.remove_script(&record.id).await,
+        EntityType::Domain => db.remove_domain(&record.id).await,
+        EntityType::Database => db.remove_database_credentials(&record.id).await,
+        EntityType::Server => db.trash_server(&record.id).await,
+        EntityType::Project => db.remove_project(&record.id).await,
+        EntityType::Credential => db.remove_credential(&record.id).await,
+    };
+    let (outcome, message) = match result {
+        Ok(_) if record.entity_type == EntityType::Server => {
+            (Outcome::Ok, "moved to the trash".to_string())
+        }
+        Ok(_) => (Outcome::Ok, "removed".to_string()),
+        Err(e) => (Outcome::Failed, e.to_string()),
+    };
+    TargetResult {
+        id,
+        name,
+        outcome,
+        duration_ms: started.elapsed().as_millis() as u64,
+        message: Some(message),
+    }
+}
@@ -0,0 +1,102 @@
+//! `pctrl server log` / `project log` / `domain log`: the ops journal
+
+use crate::style;
+use pctrl_core::shell::quote_word;
+use pctrl_core::{current_holder, humanize, EntityType, JournalEntry};
+use pctrl_database::Database;
+
+/// Append an entry, or a correction of entry `amend`
+pub(crate) async fn append(
+    db: &Database,
+    entity_type: EntityType,
+    entity_id: &str,
+    name: &str,
+    text: &str,
+    amend: Option<i64>,
+) -> anyhow::Result<()> {
+    let entry = db
+        .add_journal_entry(entity_type, entity_id, &current_holder(), text, amend)
+        .await?;
+    match entry.amends {
+        Some(original) => noteln!(
+            "✓ Journal entry #{} added to {} '{}' (amends #{})",
+            entry.id,
+            entity_type,
+            name,
+            original
+        ),
+        None => noteln!(
+            "✓ Journal entry #{} added to {} '{}'",
+            entry.id,
+            entity_type,
+            name
+        ),
+    }
+    Ok(())
+}
+
+/// List an entity's latest `limit` entries, newest first
+pub(crate) async fn show(
+    db: &Database,
+    entity_type: EntityType,
+    entity_id: &str,
+    name: &str,
+    limit: i64,
+) -> anyhow::Result<()> {
+    let entries = db
+        .list_journal_entries(entity_type, entity_id, limit)
+        .await?;
+    if entries.is_empty() {
+        outln!("No journal entries for {} '{}'.", entity_type, name);
+        noteln!(
+            "{}",
+            style::dim(&format!(
+                "Add one with: pctrl {} log {} \"...\"",
+                entity_type,
+                quote_word(name)
+            ))
+        );
+        return Ok(());
+    }
+
+    noteln!("Journal of {} '{}':", entity_type, name);
+    noteln!();
+    for entry in &entries {
+        outln!("  {}", heading(entry));
+        for line in entry.text.lines() {
+            outln!("      {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// `#3  2 hours ago  alice@laptop  (amends #1)`
+fn heading(entry: &JournalEntry) -> String {
+    let amends = entry
+        .amends
+        .map(|id| format!("  (amends #{})", id))
+        .unwrap_or_default();
+    format!(
+        "{}  {}",
+        style::bold(&format!("#{}", entry.id)),
+        style::dim(&format!(
+            "{}  {}{}",
+            humanize::relative_timestamp(&entry.created_at),
+            entry.author,
+            amends
+        ))
+    )
+}
+
+/// The latest entry on one line, for `show` views
+pub(crate) fn summary(entry: &JournalEntry) -> String {
+    format!(
+        "{} {}",
+        entry.text.lines().next().unwrap_or_default(),
+        style::dim(&format!(
+            "({}, {})",
+            humanize::relative_timestamp(&entry.created_at),
+            entry.author
+        ))
+    )
+}
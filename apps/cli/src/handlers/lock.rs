@@ -0,0 +1,104 @@
+//! Advisory lock command handlers
+
+use super::resolve::resolve_entity;
+use crate::LockCommands;
+use pctrl_core::{humanize, EntityType};
+use pctrl_database::Database;
+
+/// Handle `pctrl lock ...`
+pub async fn handle_lock(
+    db: &Database,
+    command: Option<LockCommands>,
+    entity_type: Option<String>,
+    name: Option<String>,
+    reason: Option<String>,
+    ttl: chrono::Duration,
+) -> anyhow::Result<()> {
+    if let Some(LockCommands::List) = command {
+        return handle_list(db).await;
+    }
+
+    let (Some(entity_type), Some(name)) = (entity_type, name) else {
+        anyhow::bail!("Usage: pctrl lock <type> <name> [--reason ..] [--ttl 8h] | pctrl lock list");
+    };
+
+    let entity_type: EntityType = entity_type
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!(e))?;
+    let (id, display_name) = resolve_entity(db, entity_type, &name).await?;
+    let holder = pctrl_core::current_holder();
+
+    let lock = db
+        .lock_entity(entity_type, &id, &holder, reason.as_deref(), ttl)
+        .await?;
+
+    outln!("🔒 Locked {} '{}'", entity_type, display_name);
+    outln!("  Holder:  {}", lock.holder);
+    if let Some(r) = &lock.reason {
+        outln!("  Reason:  {}", r);
+    }
+    outln!(
+        "  Expires: {}",
+        humanize::relative_timestamp(&lock.expires_at)
+    );
+
+    Ok(())
+}
+
+/// Handle `pctrl unlock <type> <name>`
+pub async fn handle_unlock(
+    db: &Database,
+    entity_type: String,
+    name: String,
+    force: bool,
+) -> anyhow::Result<()> {
+    let entity_type: EntityType = entity_type
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!(e))?;
+    let (id, display_name) = resolve_entity(db, entity_type, &name).await?;
+
+    let Some(lock) = db.get_entity_lock(entity_type, &id).await? else {
+        outln!("✗ {} '{}' is not locked", entity_type, display_name);
+        return Ok(());
+    };
+
+    if lock.holder != pctrl_core::current_holder() && !force {
+        anyhow::bail!(
+            "{}. Use --force to release someone else's lock.",
+            lock.describe()
+        );
+    }
+
+    db.unlock_entity(entity_type, &id).await?;
+    outln!("🔓 Unlocked {} '{}'", entity_type, display_name);
+
+    Ok(())
+}
+
+async fn handle_list(db: &Database) -> anyhow::Result<()> {
+    let locks = db.list_entity_locks().await?;
+    if locks.is_empty() {
+        outln!("No active locks.");
+        return Ok(());
+    }
+
+    outln!("Locks ({}):", locks.len());
+    outln!();
+    for lock in locks {
+        let reason = lock
+            .reason
+            .as_ref()
+            .map(|r| format!(" - {}", r))
+            .unwrap_or_default();
+        outln!(
+            "  🔒 {} {} [{}]{} (expires {})",
+            lock.entity_type,
+            lock.entity_name,
+            lock.holder,
+            reason,
+            humanize::relative_timestamp(&lock.expires_at)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,340 @@
+//! `pctrl logs follow`: several log streams merged into one
+//!
+//! Every source runs as its own task and sends complete lines over a
+//! channel; the merge loop orders them with [`Reorder`] and prints them
+//! with the source's prefix. A source that fails is reported and the others
+//! keep going. Ctrl-C stops the SSH readers and drops the Docker streams.
+
+use super::docker::docker_manager;
+use super::resolve::find_project;
+use super::resolve::find_server;
+use super::server::create_ssh_manager;
+use crate::{style, LogsCommands};
+use futures_util::StreamExt;
+use pctrl_core::log_tail::{self, LineBuffer, LogLine, Reorder};
+use pctrl_core::{systemd, ResourceType, Server};
+use pctrl_database::Database;
+use pctrl_ssh::SshManager;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinHandle;
+
+/// How often SSH readers check whether to stop
+const STOP_POLL: Duration = Duration::from_millis(250);
+
+pub async fn handle(command: LogsCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        LogsCommands::Follow {
+            containers,
+            services,
+            project,
+            grep,
+            lines,
+        } => {
+            let mut targets = Vec::new();
+            for container in containers {
+                targets.push(container_target(&container));
+            }
+            for service in services {
+                targets.push(service_target(db, &service).await?);
+            }
+            if let Some(project) = project {
+                let project = find_project(db, &project).await?;
+                for link in db.get_project_resources(&project.id).await? {
+                    if link.resource_type == ResourceType::Container {
+                        targets.push(container_target(&link.resource_id));
+                    }
+                }
+                for service in db.list_services_for_project(&project.id).await? {
+                    targets.push(Target {
+                        label: service.name.clone(),
+                        source: Source::Service {
+                            server: Box::new(find_server(db, &service.server_id).await?),
+                            unit: service.unit,
+                        },
+                    });
+                }
+            }
+            // A source named twice (e.g. also linked to the project) once
+            let mut seen = HashSet::new();
+            targets.retain(|t| seen.insert(t.label.clone()));
+            if targets.is_empty() {
+                anyhow::bail!("Nothing to follow: pass --container, --service or --project");
+            }
+
+            follow(db, targets, grep, lines).await
+        }
+    }
+}
+
+/// What a stream reads from
+enum Source {
+    Container { host: Option<String>, name: String },
+    Service { server: Box<Server>, unit: String },
+}
+
+struct Target {
+    label: String,
+    source: Source,
+}
+
+enum Event {
+    Lines(usize, Vec<String>),
+    /// The source's stream ended, with the reason if it failed
+    Ended(usize, Option<String>),
+}
+
+fn container_target(input: &str) -> Target {
+    let (host, name) = match input.split_once('/') {
+        Some((host, name)) => (Some(host.to_string()), name.to_string()),
+        None => (None, input.to_string()),
+    };
+    Target {
+        label: name.clone(),
+        source: Source::Container { host, name },
+    }
+}
+
+/// A stored service by name, else `unit@server`
+async fn service_target(db: &Database, input: &str) -> anyhow::Result<Target> {
+    if let Some(service) = db.get_service(input).await? {
+        return Ok(Target {
+            label: service.name.clone(),
+            source: Source::Service {
+                server: Box::new(find_server(db, &service.server_id).await?),
+                unit: service.unit,
+            },
+        });
+    }
+    match log_tail::split_service_target(input) {
+        (unit, Some(server)) => Ok(Target {
+            label: input.to_string(),
+            source: Source::Service {
+                server: Box::new(find_server(db, server).await?),
+                unit: systemd::normalize_unit(unit).map_err(|e| anyhow::anyhow!(e))?,
+            },
+        }),
+        (_, None) => anyhow::bail!(
+            "Service '{}' not found (give a service name or unit@server)",
+            input
+        ),
+    }
+}
+
+async fn follow(
+    db: &Database,
+    targets: Vec<Target>,
+    grep: Option<String>,
+    lines: u32,
+) -> anyhow::Result<()> {
+    let names: Vec<String> = targets.iter().map(|t| t.label.clone()).collect();
+    let labels = log_tail::label_sources(
+        &names.iter().map(String::as_str).collect::<Vec<_>>(),
+        style::SOURCE_COLORS.len(),
+    );
+    let print = |line: &LogLine| {
+        let label = &labels[line.source];
+        let time = line
+            .timestamp
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S ")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        outln!(
+            "{} {}{}",
+            style::source_text(label.color, &format!("{} │", label.prefix)),
+            style::dim(&time),
+            line.text
+        );
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut tasks: Vec<JoinHandle<()>> = Vec::new();
+    let mut journal = vec![false; targets.len()];
+    let mut running = 0;
+    let mut failed = 0;
+
+    for (index, target) in targets.into_iter().enumerate() {
+        let started = match target.source {
+            Source::Container { host, name } => {
+                start_container(db, host, &name, lines, index, tx.clone()).await
+            }
+            Source::Service { server, unit } => {
+                journal[index] = true;
+                start_service(db, &server, &unit, lines, index, tx.clone(), stop.clone()).await
+            }
+        };
+        match started {
+            Ok(task) => {
+                tasks.push(task);
+                running += 1;
+            }
+            Err(e) => {
+                notice(&labels[index].prefix, &e.to_string());
+                failed += 1;
+            }
+        }
+    }
+    drop(tx);
+
+    if running > 0 {
+        noteln!(
+            "{}",
+            style::dim(&format!("Following {} (Ctrl-C to stop)", names.join(", ")))
+        );
+    }
+
+    let mut reorder = Reorder::new(log_tail::DEFAULT_WINDOW);
+    while running > 0 {
+        let due = reorder.next_due();
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(Event::Lines(source, received)) => {
+                    let now = Instant::now();
+                    for raw in received {
+                        let (timestamp, rest) = log_tail::split_timestamp(&raw);
+                        let text = if journal[source] && timestamp.is_some() {
+                            log_tail::journal_message(rest)
+                        } else {
+                            rest
+                        };
+                        if grep.as_deref().is_some_and(|g| !text.contains(g)) {
+                            continue;
+                        }
+                        reorder.push(
+                            LogLine {
+                                source,
+                                timestamp,
+                                text: text.to_string(),
+                            },
+                            now,
+                        );
+                    }
+                }
+                Some(Event::Ended(source, reason)) => {
+                    reorder.flush().iter().for_each(&print);
+                    match reason {
+                        Some(reason) => {
+                            notice(&labels[source].prefix, &reason);
+                            failed += 1;
+                        }
+                        None => notice(&labels[source].prefix, "stream ended"),
+                    }
+                    running -= 1;
+                }
+                None => break,
+            },
+            _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now).into()), if due.is_some() => {}
+            _ = tokio::signal::ctrl_c() => {
+                outln!();
+                break;
+            }
+        }
+        reorder.ready(Instant::now()).iter().for_each(&print);
+    }
+
+    // Tear down: SSH readers see the flag, Docker streams are dropped
+    stop.store(true, Ordering::Relaxed);
+    tasks.iter().for_each(|t| t.abort());
+    reorder.flush().iter().for_each(&print);
+
+    if failed == names.len() {
+        anyhow::bail!("None of the log streams could be followed");
+    }
+    Ok(())
+}
+
+fn notice(prefix: &str, message: &str) {
+    eoutln!(
+        "{} {} │ {}",
+        style::warning_text("⚠"),
+        prefix.trim_end(),
+        message
+    );
+}
+
+async fn start_container(
+    db: &Database,
+    host: Option<String>,
+    name: &str,
+    lines: u32,
+    index: usize,
+    tx: UnboundedSender<Event>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let (docker, host_id) = docker_manager(db, host).await?;
+    let mut stream = docker.follow_logs(&host_id, name, lines)?;
+    Ok(tokio::spawn(async move {
+        let mut buffer = LineBuffer::default();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    let _ = tx.send(Event::Lines(index, buffer.push(&chunk)));
+                }
+                Err(e) => {
+                    let _ = tx.send(Event::Ended(index, Some(e.to_string())));
+                    return;
+                }
+            }
+        }
+        if let Some(rest) = buffer.finish() {
+            let _ = tx.send(Event::Lines(index, vec![rest]));
+        }
+        let _ = tx.send(Event::Ended(index, None));
+    }))
+}
+
+async fn start_service(
+    db: &Database,
+    server: &Server,
+    unit: &str,
+    lines: u32,
+    index: usize,
+    tx: UnboundedSender<Event>,
+    stop: Arc<AtomicBool>,
+) -> anyhow::Result<JoinHandle<()>> {
+    let cred_id = server
+        .credential_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", server.name))?;
+    let (manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+    let command = systemd::tail_command(unit, lines);
+
+    Ok(tokio::task::spawn_blocking(move || {
+        let mut buffer = LineBuffer::default();
+        let mut last_line = String::new();
+        let result = manager.connect(&conn_id).and_then(|session| {
+            SshManager::follow(
+                &session,
+                &command,
+                &mut |chunk| {
+                    let received = buffer.push(chunk);
+                    if let Some(line) = received.last() {
+                        last_line.clone_from(line);
+                    }
+                    let _ = tx.send(Event::Lines(index, received));
+                },
+                &stop,
+                STOP_POLL,
+            )
+        });
+        if let Some(rest) = buffer.finish() {
+            last_line.clone_from(&rest);
+            let _ = tx.send(Event::Lines(index, vec![rest]));
+        }
+        let reason = match result {
+            Ok(None) | Ok(Some(0)) => None,
+            Ok(Some(code)) if systemd::is_not_systemd(&last_line, code) => {
+                Some("not a systemd host".to_string())
+            }
+            Ok(Some(code)) => Some(format!("journalctl exited with {}", code)),
+            Err(e) => Some(e.to_string()),
+        };
+        let _ = tx.send(Event::Ended(index, reason));
+    }))
+}
@@ -0,0 +1,321 @@
+//! `pctrl merge`: bring another pctrl database's entities into this one
+
+use super::{conflicts, fanout};
+use crate::style;
+use pctrl_core::fanout::{FailOn, FanoutReport, Outcome, TargetResult};
+use pctrl_core::merge::{self, Class, Comparison, Prefer, Record, Step};
+use pctrl_core::{
+    Credential, DatabaseCredentials, Domain, EntityType, Project, ProjectResource, Script,
+    ScriptUpdate, Server,
+};
+use pctrl_database::{db_file, Database};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::Instant;
+
+/// Password of the other database for non-interactive runs
+const PASSWORD_ENV: &str = "PCTRL_MERGE_PASSWORD";
+
+/// Handle `pctrl merge`
+pub(crate) async fn handle(
+    db: &Database,
+    other: &Path,
+    prefer: Option<Prefer>,
+    dry_run: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let path = other.to_string_lossy();
+    let password = if db_file::is_encrypted(other).await {
+        Some(password(&path)?)
+    } else {
+        None
+    };
+    let other_db = Database::open_read_only(&path, password.as_deref()).await?;
+    let other_records = records(&other_db).await?;
+    let other_links = other_db.list_all_project_resources().await?;
+    other_db.close().await;
+
+    let local_records = records(db).await?;
+    let mut comparison = merge::compare(&local_records, &other_records);
+    resolve(&mut comparison, prefer, dry_run)?;
+    let plan = merge::plan(
+        &comparison,
+        &db.list_all_project_resources().await?,
+        &other_links,
+    );
+
+    let names: HashMap<&str, &str> = local_records
+        .iter()
+        .chain(comparison.entries.iter().filter_map(|e| e.other.as_ref()))
+        .map(|r| (r.id.as_str(), r.name.as_str()))
+        .collect();
+
+    let started = Instant::now();
+    let mut report = FanoutReport::new(
+        "merge",
+        &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    for step in &plan.steps {
+        report.push(apply(db, step, dry_run).await);
+    }
+    for link in &plan.links {
+        report.push(link_result(db, link, &names, dry_run).await);
+    }
+    report.duration_ms = started.elapsed().as_millis() as u64;
+
+    if !json {
+        let verb = if dry_run { "Would merge" } else { "Merged" };
+        outln!("{} {} into {}", verb, path, db.path().display());
+        noteln!(
+            "  {}",
+            style::dim(&format!(
+                "{} identical, {} only here",
+                plan.identical, plan.only_local
+            ))
+        );
+        if report.targets.is_empty() {
+            outln!("  {}", style::dim("Nothing to merge"));
+            db.save_last_run(&report).await?;
+            return Ok(());
+        }
+    }
+    fanout::finish(db, &report, json, FailOn::Any).await
+}
+
+fn password(path: &str) -> anyhow::Result<String> {
+    match std::env::var(PASSWORD_ENV).ok().filter(|p| !p.is_empty()) {
+        Some(password) => Ok(password),
+        None => Ok(rpassword::prompt_password(format!(
+            "Password of {}: ",
+            path
+        ))?),
+    }
+}
+
+/// Every entity of a database, with when it last changed
+pub(crate) async fn records(db: &Database) -> anyhow::Result<Vec<Record>> {
+    let changed = db.last_changed().await?;
+    let mut records = Vec::new();
+    add(
+        &mut records,
+        &changed,
+        EntityType::Credential,
+        db.list_credentials().await?,
+    )?;
+    add(
+        &mut records,
+        &changed,
+        EntityType::Server,
+        db.list_servers().await?,
+    )?;
+    add(
+        &mut records,
+        &changed,
+        EntityType::Project,
+        db.list_projects().await?,
+    )?;
+    add(
+        &mut records,
+        &changed,
+        EntityType::Domain,
+        db.list_domains().await?,
+    )?;
+    add(
+        &mut records,
+        &changed,
+        EntityType::Database,
+        db.list_database_credentials().await?,
+    )?;
+    add(
+        &mut records,
+        &changed,
+        EntityType::Script,
+        db.list_scripts().await?,
+    )?;
+    Ok(records)
+}
+
+fn add<T: Serialize>(
+    records: &mut Vec<Record>,
+    changed: &HashMap<(EntityType, String), String>,
+    entity_type: EntityType,
+    entities: Vec<T>,
+) -> anyhow::Result<()> {
+    for entity in &entities {
+        let mut record =
+            Record::from_entity(entity_type, entity, None).map_err(anyhow::Error::msg)?;
+        record.updated_at = changed.get(&(entity_type, record.id.clone())).cloned();
+        records.push(record);
+    }
+    Ok(())
+}
+
+/// Decide the conflicts: by `--prefer`, else one by one at the terminal.
+/// A dry run without either lists them undecided.
+fn resolve(
+    comparison: &mut Comparison,
+    prefer: Option<Prefer>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let conflicts = comparison.count(|c| matches!(c, Class::Conflict(_)));
+    if conflicts == 0 {
+        return Ok(());
+    }
+    if let Some(prefer) = prefer {
+        for entry in comparison.conflicts_mut() {
+            if let (Some(local), Some(other)) = (&entry.local, &entry.other) {
+                entry.resolution = Some(prefer.resolve(local, other));
+            }
+        }
+        return Ok(());
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} conflicting; pass --prefer local|other|newer to merge without a terminal",
+            pctrl_core::humanize::count(conflicts as u64, "entity is", "entities are")
+        );
+    }
+
+    let conflicts = comparison.conflicts();
+    let Some(decisions) = conflicts::resolve(&conflicts)? else {
+        anyhow::bail!("Aborted, nothing merged");
+    };
+    comparison.decide(&conflicts, &decisions);
+    Ok(())
+}
+
+pub(crate) async fn apply(db: &Database, step: &Step, dry_run: bool) -> TargetResult {
+    let (record, message) = match step {
+        Step::Keep { record, changed } => {
+            (record, format!("kept this side's {}", changed.join(", ")))
+        }
+        Step::Unresolved { record, changed } => {
+            (record, format!("conflict: {}", changed.join(", ")))
+        }
+        Step::Import(record) if dry_run => (record, "would import".to_string()),
+        Step::Update { record, changed } if dry_run => (
+            record,
+            format!("would take the other's {}", changed.join(", ")),
+        ),
+        Step::Import(record) | Step::Update { record, .. } => {
+            let done = match step {
+                Step::Update { changed, .. } => {
+                    format!("took the other's {}", changed.join(", "))
+                }
+                _ => "imported".to_string(),
+            };
+            let started = Instant::now();
+            let (outcome, message) = match save(db, record).await {
+                Ok(None) => (Outcome::Ok, done),
+                Ok(Some(note)) => (Outcome::Ok, format!("{}; {}", done, note)),
+                Err(e) => (Outcome::Failed, e.to_string()),
+            };
+            return TargetResult {
+                id: format!("{}:{}", record.entity_type, record.id),
+                name: format!("{} {}", record.entity_type, record.name),
+                outcome,
+                duration_ms: started.elapsed().as_millis() as u64,
+                message: Some(message),
+            };
+        }
+    };
+    TargetResult::skipped(
+        &format!("{}:{}", record.entity_type, record.id),
+        &format!("{} {}", record.entity_type, record.name),
+        &message,
+    )
+}
+
+/// Write a record; a note when part of it waits elsewhere
+async fn save(db: &Database, record: &Record) -> anyhow::Result<Option<String>> {
+    match record.entity_type {
+        EntityType::Credential => db.save_credential(&entity::<Credential>(record)?).await?,
+        EntityType::Server => {
+            // One in the trash comes back
+            let trashed = db.list_trashed_servers().await?;
+            if trashed.iter().any(|s| s.id == record.id) {
+                db.restore_server(&record.id).await?;
+            }
+            db.save_server(&entity::<Server>(record)?).await?
+        }
+        EntityType::Project => db.save_project(&entity::<Project>(record)?).await?,
+        EntityType::Domain => db.save_domain(&entity::<Domain>(record)?).await?,
+        EntityType::Database => {
+            db.save_database_credentials(&entity::<DatabaseCredentials>(record)?)
+                .await?
+        }
+        EntityType::Script => return save_script(db, entity(record)?).await,
+    }
+    Ok(None)
+}
+
+fn entity<T: DeserializeOwned>(record: &Record) -> anyhow::Result<T> {
+    record.to_entity().map_err(anyhow::Error::msg)
+}
+
+/// A new command for an existing script goes through approval like any
+/// other edit; the rest of the script is saved as is
+async fn save_script(db: &Database, script: Script) -> anyhow::Result<Option<String>> {
+    let Some(current) = db.get_script(&script.id).await? else {
+        db.save_script(&script).await?;
+        return Ok(None);
+    };
+    db.save_script(&Script {
+        command: current.command.clone(),
+        dangerous: current.dangerous,
+        ..script.clone()
+    })
+    .await?;
+    match db
+        .update_script_command(
+            &script.id,
+            &script.command,
+            script.dangerous,
+            &pctrl_core::current_holder(),
+        )
+        .await?
+    {
+        ScriptUpdate::Pending(revision) => Ok(Some(format!(
+            "command change awaits approval (pctrl script approve {})",
+            revision.id
+        ))),
+        _ => Ok(None),
+    }
+}
+
+pub(crate) async fn link_result(
+    db: &Database,
+    link: &ProjectResource,
+    names: &HashMap<&str, &str>,
+    dry_run: bool,
+) -> TargetResult {
+    let name_of = |id: &str| names.get(id).copied().unwrap_or(id).to_string();
+    let id = format!("link:{}", link.id);
+    let name = format!(
+        "link {} {} {}",
+        name_of(&link.project_id),
+        link.resource_type,
+        name_of(&link.resource_id)
+    );
+    if dry_run {
+        return TargetResult::skipped(&id, &name, "would link");
+    }
+    let started = Instant::now();
+    let (outcome, message) = match db.link_project_resource(link).await {
+        Ok(()) => (Outcome::Ok, "linked".to_string()),
+        Err(e) => (Outcome::Failed, e.to_string()),
+    };
+    TargetResult {
+        id,
+        name,
+        outcome,
+        duration_ms: started.elapsed().as_millis() as u64,
+        message: Some(message),
+    }
+}
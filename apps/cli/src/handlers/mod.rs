@@ -2,26 +2,203 @@
 //!
 //! Each module handles a specific command group.
 
+mod audit;
+mod backup;
+mod cloudflare;
+mod columns;
+mod compose;
+mod config;
+mod conflicts;
+mod coolify;
 mod credential;
 mod database;
+mod debug;
+mod docker;
+mod doctor;
 mod domain;
-mod project;
+mod export;
+mod fanout;
+mod find;
+mod git;
+mod guard;
+mod hints;
+mod hooks;
+mod http;
+mod import;
+mod journal;
+mod lock;
+mod logs;
+mod merge;
+mod monitor;
+pub(crate) mod preflight;
+pub(crate) mod project;
+mod project_bundle;
+mod project_exec;
+pub(crate) mod project_status;
+pub(crate) mod prompt;
+mod propagation;
+pub(crate) mod references;
+pub(crate) mod resolve;
 mod script;
+mod secret;
 mod server;
+mod service;
+mod ship;
+mod snapshot;
+mod ssh;
+mod stats;
+mod status;
+mod vpn;
+
+pub use server::print_forecast_warnings;
 
 use crate::{Commands, CredentialCommands};
 use pctrl_database::Database;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
+
+/// Error that ends the process with a specific exit code, so scripts can
+/// tell failures apart (e.g. a deploy refused by pre-flight vs. one that failed)
+#[derive(Debug)]
+pub struct CommandFailed {
+    pub code: i32,
+    message: String,
+}
+
+impl CommandFailed {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+/// Path with a leading `~` or `~/` expanded to the home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some("") => "",
+        Some(rest) if rest.starts_with(['/', '\\']) => &rest[1..],
+        _ => return PathBuf::from(path),
+    };
+    match dirs::home_dir() {
+        Some(home) if rest.is_empty() => home,
+        Some(home) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Run a command and record it in the command history and usage stats.
+///
+/// `path` is the subcommand path only; arguments may contain secrets.
+pub async fn run_recorded(command: Commands, db: Arc<Database>, path: &str) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let result = handle_command(command, db.clone()).await;
+    let duration_ms = started.elapsed().as_millis() as i64;
+
+    // Bookkeeping failures must never fail the command itself
+    let error = result.as_ref().err().map(|e| e.to_string());
+    let _ = db
+        .record_command(path, result.is_ok(), error.as_deref())
+        .await;
+    let _ = db.record_usage(path, result.is_ok(), duration_ms).await;
+
+    result
+}
 
 /// Main command dispatcher
 pub async fn handle_command(command: Commands, db: Arc<Database>) -> anyhow::Result<()> {
     match command {
         Commands::Project { command } => project::handle(command, &db).await,
         Commands::Server { command } => server::handle(command, &db).await,
+        Commands::Ssh { command } => ssh::handle(command, &db).await,
         Commands::Domain { command } => domain::handle(command, &db).await,
         Commands::Database { command } => database::handle(command, &db).await,
         Commands::Script { command } => script::handle(command, &db).await,
+        Commands::Service { command } => service::handle(command, &db).await,
+        Commands::Logs { command } => logs::handle(command, &db).await,
         Commands::Credential { command } => handle_credential(command, &db).await,
+        Commands::Identity { command } => secret::handle_identity(command, &db).await,
+        Commands::Secret { command } => secret::handle_secret(command, &db).await,
+        Commands::Docker { command } => docker::handle(command, &db).await,
+        Commands::Container { command } => docker::handle_container(command, &db).await,
+        Commands::Compose { command } => compose::handle(command, &db).await,
+        Commands::Coolify { command } => coolify::handle(command, &db).await,
+        Commands::Git { command } => git::handle(command, &db).await,
+        Commands::Audit { command } => audit::handle(command, &db).await,
+        Commands::Hooks { command } => hooks::handle(command, &db).await,
+        Commands::Stats {
+            command,
+            since,
+            limit,
+        } => stats::handle(&db, command, since, limit).await,
+        Commands::Monitor { command } => monitor::handle(command, &db).await,
+        Commands::Vpn { command } => vpn::handle(command, &db).await,
+        Commands::Status => status::handle(&db).await,
+        Commands::Doctor { fix, interactive } => doctor::handle(&db, fix, interactive).await,
+        Commands::LastRun { command, json } => fanout::last_run(&db, &command, json).await,
+        Commands::PromptSegment { format, init } => {
+            prompt::handle(&db.path(), &format, init.as_deref()).await
+        }
+        Commands::Export {
+            command: Some(command),
+            ..
+        } => export::handle(command, &db).await,
+        Commands::Export {
+            command: None,
+            file,
+            include_secrets,
+            encrypt,
+        } => export::handle_document(&db, file, include_secrets, encrypt).await,
+        Commands::Import {
+            file,
+            vars,
+            merge,
+            replace,
+            dry_run,
+            json,
+        } => import::handle(&db, &file, vars, merge, replace, dry_run, json).await,
+        Commands::Backup { to, encrypt, keep } => {
+            backup::handle_backup(&db, to, encrypt, keep).await
+        }
+        Commands::Restore { path } => backup::handle_restore(&db, path).await,
+        Commands::Merge {
+            other,
+            prefer,
+            dry_run,
+            json,
+        } => merge::handle(&db, &other, prefer, dry_run, json).await,
+        Commands::Config { command } => config::handle(command, &db).await,
+        Commands::Snapshot { command } => snapshot::handle(command, &db).await,
+        Commands::Lock {
+            command,
+            entity_type,
+            name,
+            reason,
+            ttl,
+        } => lock::handle_lock(&db, command, entity_type, name, reason, ttl).await,
+        Commands::Unlock {
+            entity_type,
+            name,
+            force,
+        } => lock::handle_unlock(&db, entity_type, name, force).await,
+        Commands::Find { query, types, json } => find::handle(&db, query, types, json).await,
+        Commands::Resolve {
+            input,
+            entity_type,
+            json,
+        } => resolve::handle(&db, input, entity_type, json).await,
+        Commands::Debug { command } => debug::handle(command, &db).await,
+        Commands::Shell { .. } => anyhow::bail!("Already in a shell"),
     }
 }
 
@@ -35,13 +212,34 @@ async fn handle_credential(command: CredentialCommands, db: &Database) -> anyhow
             user,
             port,
             key,
+            store_inline,
             token,
             password,
             url,
+            ensure,
         } => {
-            credential::handle_add(db, name, cred_type, user, port, key, token, password, url).await
+            credential::handle_add(
+                db,
+                name,
+                cred_type,
+                user,
+                port,
+                key,
+                store_inline,
+                token,
+                password,
+                url,
+                ensure,
+            )
+            .await
         }
         CredentialCommands::Show { name } => credential::handle_show(db, name).await,
-        CredentialCommands::Remove { name } => credential::handle_remove(db, name).await,
+        CredentialCommands::Remove { name, force } => {
+            credential::handle_remove(db, name, force).await
+        }
+        CredentialCommands::Deps { name, json } => credential::handle_deps(db, name, json).await,
+        CredentialCommands::ExportKey { name, out, yes } => {
+            credential::handle_export_key(db, name, out, yes).await
+        }
     }
 }
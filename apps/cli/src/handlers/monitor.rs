@@ -0,0 +1,321 @@
+//! Monitor command handler
+
+use super::fanout::finish;
+use super::http::send_with_retry;
+use super::preflight::probe_server;
+use super::project::print_ended;
+use super::propagation::check_tracked;
+use super::server::disks_filling_up;
+use crate::{style, MonitorCommands};
+use chrono::{DateTime, Utc};
+use pctrl_core::fanout::{fan_out, FailOn, FanoutReport, Outcome, TargetResult};
+use pctrl_core::forecast::WARN_DAYS;
+use pctrl_core::hooks::{HookPayload, MONITOR_CHANGED};
+use pctrl_core::monitor::{liveness, Liveness, MonitorState, DEFAULT_INTERVAL_SECS};
+use pctrl_core::settings::MONITOR_HEARTBEAT_URL;
+use pctrl_core::vpn::Tunnels;
+use pctrl_core::{humanize, hyperlink, Server};
+use pctrl_database::Database;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub async fn handle(command: MonitorCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        MonitorCommands::Run {
+            interval,
+            once,
+            json,
+            fail_on,
+        } => {
+            let interval = match interval {
+                Some(interval) => interval.to_std()?,
+                None => Duration::from_secs(DEFAULT_INTERVAL_SECS),
+            };
+            if interval.as_secs() == 0 {
+                anyhow::bail!("The interval must be at least 1s");
+            }
+            run(db, interval, once, json, fail_on).await
+        }
+        MonitorCommands::Status => status(db).await,
+    }
+}
+
+async fn run(
+    db: &Database,
+    interval: Duration,
+    once: bool,
+    json: bool,
+    fail_on: FailOn,
+) -> anyhow::Result<()> {
+    if !once {
+        outln!(
+            "Monitoring servers every {} (Ctrl+C to stop)",
+            humanize::duration(interval)
+        );
+    }
+
+    let mut last_up: HashMap<String, bool> = HashMap::new();
+    let mut filling: HashSet<String> = HashSet::new();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let report = cycle(db, interval, &mut last_up, &mut filling, json).await?;
+        if once {
+            return finish(db, &report, json, fail_on).await;
+        }
+        db.save_last_run(&report).await?;
+    }
+}
+
+/// Check every server, record transitions for the activity feed, fire
+/// `monitor.changed` on them and on disks that start to look full within
+/// `WARN_DAYS`, record the cycle, send the heartbeat and re-check tracked
+/// DNS propagations. `json` leaves stdout to the report.
+async fn cycle(
+    db: &Database,
+    interval: Duration,
+    last_up: &mut HashMap<String, bool>,
+    filling: &mut HashSet<String>,
+    json: bool,
+) -> anyhow::Result<FanoutReport> {
+    let started = Instant::now();
+    let mut report = FanoutReport::new("monitor run", &now_rfc3339());
+    for ended in db.end_expired_maintenance(Utc::now()).await? {
+        if !json {
+            print_ended(&ended);
+        }
+    }
+    let maintenance = db.maintenance_scope(Utc::now()).await?;
+
+    // Servers behind a VPN that's down are skipped, not counted as down
+    let servers = db.list_servers().await?;
+    let tunnels = Tunnels::detect(&servers).await;
+    let (servers, no_vpn): (Vec<Server>, Vec<Server>) = servers
+        .into_iter()
+        .partition(|s| tunnels.blocked(s).is_none());
+    let results = fan_out(
+        &servers,
+        |s| (s.id.clone(), s.name.clone()),
+        |s| async move {
+            match probe_server(db, s).await {
+                Ok(Ok(())) => (Outcome::Ok, None),
+                Ok(Err(e)) => (Outcome::Failed, Some(e)),
+                Err(e) => (Outcome::Failed, Some(e.to_string())),
+            }
+        },
+    )
+    .await;
+
+    let mut down = Vec::new();
+    let mut paused = Vec::new();
+    for (server, result) in servers.iter().zip(&results) {
+        let error =
+            (result.outcome == Outcome::Failed).then(|| result.message.clone().unwrap_or_default());
+        let up = error.is_none();
+        let project = maintenance.covering(&server.id, &server.name);
+        if !up {
+            match project {
+                Some(project) => paused.push(format!("{} ({})", server.name, project)),
+                None => down.push(server.name.as_str()),
+            }
+        }
+        // The first cycle only establishes the baseline; servers of projects
+        // in maintenance don't alert
+        if last_up
+            .insert(server.id.clone(), up)
+            .is_some_and(|was| was != up)
+            && project.is_none()
+        {
+            db.record_monitor_event(&server.id, &server.name, error.as_deref())
+                .await?;
+            fire_changed(db, server, error.as_deref()).await;
+        }
+    }
+
+    // Forecasts come from the samples `server status` records, so servers
+    // skipped for their VPN count too. Each disk alerts once until its
+    // projection moves out of the window again.
+    let now = Utc::now();
+    let mut full_soon = disks_filling_up(db, &servers, now).await?;
+    full_soon.extend(disks_filling_up(db, &no_vpn, now).await?);
+    filling.retain(|id| full_soon.iter().any(|(s, _)| &s.id == id));
+    let mut disks = Vec::new();
+    for (server, full) in &full_soon {
+        disks.push(format!("{} ({})", server.name, humanize::relative(*full)));
+        if filling.insert(server.id.clone())
+            && maintenance.covering(&server.id, &server.name).is_none()
+        {
+            fire_disk_forecast(db, server, *full).await;
+        }
+    }
+
+    let duration = started.elapsed();
+    report.extend(results);
+    report.extend(
+        no_vpn.iter().map(|s| {
+            TargetResult::skipped(&s.id, &s.name, &tunnels.blocked(s).unwrap_or_default())
+        }),
+    );
+    report.duration_ms = duration.as_millis() as u64;
+
+    let heartbeat_error = match db.get_setting(MONITOR_HEARTBEAT_URL).await? {
+        Some(url) => match send_with_retry(|| reqwest::Client::new().get(&url)).await {
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Monitor heartbeat to {} failed: {}", url, e);
+                Some(e)
+            }
+        },
+        None => None,
+    };
+
+    db.record_monitor_cycle(&MonitorState {
+        last_cycle_at: now_rfc3339(),
+        servers_checked: servers.len() as u32,
+        servers_down: down.len() as u32,
+        duration_ms: duration.as_millis() as u64,
+        interval_secs: interval.as_secs(),
+        heartbeat_error: heartbeat_error.clone(),
+    })
+    .await?;
+
+    if json {
+        check_tracked(db).await?;
+        return Ok(report);
+    }
+
+    let checked = humanize::count(servers.len() as u64, "server", "servers");
+    let line = if down.is_empty() {
+        format!("{} up", checked)
+    } else {
+        format!("{} checked, down: {}", checked, down.join(", "))
+    };
+    outln!(
+        "{} {} {}",
+        style::dim(&Utc::now().format("%H:%M:%S").to_string()),
+        if down.is_empty() {
+            style::success_text(&line)
+        } else {
+            style::error_text(&line)
+        },
+        style::dim(&format!("({}ms)", duration.as_millis()))
+    );
+    if !no_vpn.is_empty() {
+        let skipped: Vec<String> = no_vpn
+            .iter()
+            .map(|s| format!("{} ({})", s.name, s.requires_vpn.as_deref().unwrap_or("")))
+            .collect();
+        outln!(
+            "  {}",
+            style::warning_text(&format!(
+                "VPN not connected, skipped: {}",
+                skipped.join(", ")
+            ))
+        );
+    }
+    if !paused.is_empty() {
+        outln!(
+            "  {}",
+            style::dim(&format!("In maintenance, down: {}", paused.join(", ")))
+        );
+    }
+    if !disks.is_empty() {
+        outln!(
+            "  {}",
+            style::warning_text(&format!(
+                "Disk full in < {} days: {}",
+                WARN_DAYS,
+                disks.join(", ")
+            ))
+        );
+    }
+    if let Some(e) = heartbeat_error {
+        outln!(
+            "  {}",
+            style::warning_text(&format!("Heartbeat failed: {}", e))
+        );
+    }
+    check_tracked(db).await?;
+
+    Ok(report)
+}
+
+fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+async fn fire_changed(db: &Database, server: &Server, error: Option<&str>) {
+    let payload = HookPayload::new(
+        MONITOR_CHANGED,
+        serde_json::json!({
+            "check": "server",
+            "server_id": server.id,
+            "name": server.name,
+            "host": server.host,
+            "up": error.is_none(),
+            "error": error,
+        }),
+    );
+    db.fire_hooks(&payload).await;
+}
+
+async fn fire_disk_forecast(db: &Database, server: &Server, full: DateTime<Utc>) {
+    let payload = HookPayload::new(
+        MONITOR_CHANGED,
+        serde_json::json!({
+            "check": "disk_forecast",
+            "server_id": server.id,
+            "name": server.name,
+            "host": server.host,
+            "within_days": WARN_DAYS,
+            "projected_full": full.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }),
+    );
+    db.fire_hooks(&payload).await;
+}
+
+async fn status(db: &Database) -> anyhow::Result<()> {
+    let state = db.get_monitor_state().await?;
+    let heartbeat_url = db.get_setting(MONITOR_HEARTBEAT_URL).await?;
+
+    outln!("● {}", describe(liveness(state.as_ref(), Utc::now())));
+    let Some(state) = state else {
+        return Ok(());
+    };
+
+    outln!();
+    outln!(
+        "  Last cycle   {} ({} checked, {} down, {}ms)",
+        humanize::relative_timestamp(&state.last_cycle_at),
+        humanize::count(state.servers_checked as u64, "server", "servers"),
+        state.servers_down,
+        state.duration_ms
+    );
+    outln!(
+        "  Interval     {}",
+        humanize::duration(Duration::from_secs(state.interval_secs))
+    );
+    let heartbeat = match (heartbeat_url, &state.heartbeat_error) {
+        (None, _) => style::dim("not configured (pctrl config set monitor_heartbeat_url <url>)"),
+        (Some(url), None) => hyperlink::web(&url),
+        (Some(url), Some(e)) => format!(
+            "{} {}",
+            hyperlink::web(&url),
+            style::warning_text(&format!("(last ping failed: {})", e))
+        ),
+    };
+    outln!("  Heartbeat    {}", heartbeat);
+
+    Ok(())
+}
+
+/// Liveness colored by severity
+pub(crate) fn describe(liveness: Liveness) -> String {
+    let text = liveness.describe();
+    match liveness {
+        Liveness::Alive { .. } => style::success_text(&text),
+        Liveness::Stale { .. } => style::warning_text(&text),
+        Liveness::NeverRan => style::dim(&text),
+    }
+}
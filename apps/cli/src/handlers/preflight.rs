@@ -0,0 +1,300 @@
+//! Pre-flight checks and `project deploy`
+
+use super::vpn::vpn_blocked;
+use super::CommandFailed;
+use crate::style;
+use pctrl_coolify::CoolifyManager;
+use pctrl_core::preflight::{
+    evaluate, CheckKind, CheckResult, PreflightConfig, PreflightReport, Verdict,
+    EXIT_DEPLOY_FAILED, EXIT_PREFLIGHT_REFUSED,
+};
+use pctrl_core::{
+    CredentialData, DatabaseCredentials, DatabaseType, Project, ResourceType, Server,
+};
+use pctrl_database::Database;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Time a single connection test may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run the project's checks and judge them against its settings
+pub(crate) async fn run_preflight(
+    db: &Database,
+    project: &Project,
+) -> anyhow::Result<(PreflightReport, PreflightConfig)> {
+    let config = db.get_preflight_config(&project.id).await?;
+    let checks = collect_checks(db, project, &config).await?;
+    Ok((evaluate(checks, &config), config))
+}
+
+/// Print every check and the verdict
+pub(crate) fn print_report(report: &PreflightReport, config: &PreflightConfig) {
+    outln!("Pre-flight:");
+    if report.checks.is_empty() {
+        outln!(
+            "  {}",
+            style::dim("Nothing to check (no linked servers or databases, no health URL)")
+        );
+    }
+    for check in &report.checks {
+        let label = format!("{:<9}", check.kind.to_string());
+        match &check.error {
+            None => outln!("  {} {} {}", style::success_text("✓"), label, check.target),
+            Some(error) if config.is_blocking(check.kind) => outln!(
+                "  {} {} {}: {}",
+                style::error_text("✗"),
+                label,
+                check.target,
+                error
+            ),
+            Some(error) => outln!(
+                "  {} {} {}: {} {}",
+                style::warning_text("⚠"),
+                label,
+                check.target,
+                error,
+                style::dim("(advisory)")
+            ),
+        }
+    }
+    outln!();
+}
+
+/// `project deploy`: pre-flight, then trigger the project's Coolify deployments
+pub(crate) async fn deploy(
+    db: &Database,
+    project: &Project,
+    skip_preflight: bool,
+) -> anyhow::Result<()> {
+    let targets: Vec<(String, String)> = db
+        .get_project_resources(&project.id)
+        .await?
+        .into_iter()
+        .filter(|r| r.resource_type == ResourceType::Coolify)
+        .map(|r| coolify_target(&r.resource_id))
+        .collect::<anyhow::Result<_>>()?;
+    if targets.is_empty() {
+        anyhow::bail!(
+            "Project '{}' has no Coolify deployment linked (pctrl project link {} coolify <instance>/<uuid>)",
+            project.name,
+            project.name
+        );
+    }
+
+    if skip_preflight {
+        outln!(
+            "{}",
+            style::warning_text("⚠  Pre-flight skipped (--skip-preflight)")
+        );
+    } else {
+        let (report, config) = run_preflight(db, project).await?;
+        print_report(&report, &config);
+        if report.verdict == Verdict::Refused {
+            return Err(CommandFailed::new(
+                EXIT_PREFLIGHT_REFUSED,
+                format!(
+                    "Deploy refused: {} of '{}' failed (--skip-preflight to deploy anyway)",
+                    pctrl_core::humanize::count(
+                        report.blocking_failures(&config).len() as u64,
+                        "blocking check",
+                        "blocking checks"
+                    ),
+                    project.name
+                ),
+            )
+            .into());
+        }
+    }
+
+    let mut coolify = CoolifyManager::new();
+    for instance in db.load_config().await?.coolify_instances {
+        coolify.add_instance(instance);
+    }
+
+    let mut failed = 0;
+    for (instance, uuid) in &targets {
+        out!("  Deploying {} on {}... ", uuid, instance);
+        match coolify.deploy(instance, uuid, false).await {
+            Ok(queued) => {
+                let ids: Vec<&str> = queued.iter().map(|d| d.deployment_uuid.as_str()).collect();
+                outln!(
+                    "{} {}",
+                    style::success_text("✓ queued"),
+                    style::dim(&ids.join(", "))
+                )
+            }
+            Err(e) => {
+                failed += 1;
+                outln!("{}", style::error_text(&format!("✗ {}", e)));
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(CommandFailed::new(
+            EXIT_DEPLOY_FAILED,
+            format!("{} of {} deployments failed", failed, targets.len()),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `<instance>/<uuid>` of a Coolify link: the application or service to
+/// deploy
+fn coolify_target(resource_id: &str) -> anyhow::Result<(String, String)> {
+    resource_id
+        .split_once('/')
+        .filter(|(instance, uuid)| !instance.is_empty() && !uuid.is_empty())
+        .map(|(instance, uuid)| (instance.to_string(), uuid.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("Coolify link '{}' must be <instance>/<uuid>", resource_id))
+}
+
+async fn collect_checks(
+    db: &Database,
+    project: &Project,
+    config: &PreflightConfig,
+) -> anyhow::Result<Vec<CheckResult>> {
+    let mut checks = Vec::new();
+    let bundle = db
+        .resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))?;
+
+    for link in &bundle.links {
+        let id = link.resource_id.as_str();
+        match link.resource_type {
+            ResourceType::Server => checks.push(check_server(db, id, bundle.server(id)).await?),
+            ResourceType::Database => checks.push(check_database(id, bundle.database(id)).await),
+            _ => {}
+        }
+    }
+
+    if let Some(url) = &config.health_url {
+        checks.push(check_health(url).await);
+    }
+
+    Ok(checks)
+}
+
+async fn check_server(
+    db: &Database,
+    id: &str,
+    server: Option<&Server>,
+) -> anyhow::Result<CheckResult> {
+    let Some(server) = server else {
+        return Ok(CheckResult::failed(
+            CheckKind::Server,
+            id,
+            "linked server no longer exists",
+        ));
+    };
+
+    Ok(match probe_server(db, server).await? {
+        Ok(()) => CheckResult::passed(CheckKind::Server, &server.name),
+        Err(e) => CheckResult::failed(CheckKind::Server, &server.name, e),
+    })
+}
+
+/// Connect to a server's SSH port (from its credential, else 22); servers
+/// behind a VPN that's down fail without a connection attempt
+pub(crate) async fn probe_server(
+    db: &Database,
+    server: &Server,
+) -> anyhow::Result<Result<(), String>> {
+    if let Some(reason) = vpn_blocked(server).await {
+        return Ok(Err(reason));
+    }
+    let credential = match &server.credential_id {
+        Some(cred_id) => db.get_credential(cred_id).await?,
+        None => None,
+    };
+    let port = match credential.map(|c| c.data) {
+        Some(CredentialData::SshKey { port, .. } | CredentialData::SshAgent { port, .. }) => port,
+        _ => 22,
+    };
+
+    Ok(connect(&server.host, port).await)
+}
+
+pub(crate) async fn check_database(id: &str, creds: Option<&DatabaseCredentials>) -> CheckResult {
+    let Some(creds) = creds else {
+        return CheckResult::failed(CheckKind::Database, id, "linked database no longer exists");
+    };
+
+    if creds.db_type == DatabaseType::SQLite {
+        return match &creds.database_name {
+            Some(path) if !std::path::Path::new(path).exists() => {
+                CheckResult::failed(CheckKind::Database, &creds.name, "database file not found")
+            }
+            _ => CheckResult::passed(CheckKind::Database, &creds.name),
+        };
+    }
+
+    let address = match (&creds.host, &creds.connection_string) {
+        (Some(host), _) => Some((
+            host.clone(),
+            creds
+                .port
+                .unwrap_or(creds.db_type.default_port().unwrap_or_default()),
+        )),
+        (None, Some(url)) => url_address(url, creds.db_type.default_port().unwrap_or_default()),
+        (None, None) => None,
+    };
+    let Some((host, port)) = address else {
+        return CheckResult::failed(
+            CheckKind::Database,
+            &creds.name,
+            "no host or connection string to test",
+        );
+    };
+
+    match connect(&host, port).await {
+        Ok(()) => CheckResult::passed(CheckKind::Database, &creds.name),
+        Err(e) => CheckResult::failed(CheckKind::Database, &creds.name, e),
+    }
+}
+
+async fn check_health(url: &str) -> CheckResult {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(CHECK_TIMEOUT)
+        .send()
+        .await;
+
+    match response {
+        Ok(r) if r.status().is_success() => CheckResult::passed(CheckKind::Health, url),
+        Ok(r) => CheckResult::failed(CheckKind::Health, url, format!("HTTP {}", r.status())),
+        Err(e) => CheckResult::failed(CheckKind::Health, url, e.to_string()),
+    }
+}
+
+/// Open (and drop) a TCP connection
+async fn connect(host: &str, port: u16) -> Result<(), String> {
+    match tokio::time::timeout(CHECK_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("{}:{} {}", host, port, e)),
+        Err(_) => Err(format!(
+            "{}:{} timed out after {}s",
+            host,
+            port,
+            CHECK_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Host and port of a connection URL like `postgres://user:pw@host:5432/db`
+fn url_address(url: &str, default_port: u16) -> Option<(String, u16)> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?']).next()?;
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, hp)| hp);
+    // Replica set lists: test the first member
+    let host_port = host_port.split(',').next()?;
+
+    match host_port.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => Some((host.to_string(), port.parse().ok()?)),
+        _ if !host_port.is_empty() => Some((host_port.to_string(), default_port)),
+        _ => None,
+    }
+}
@@ -1,36 +1,68 @@
 //! Project command handler
 
-use crate::ProjectCommands;
-use pctrl_core::{Project, ProjectResource, ProjectStatus, ResourceType};
+use super::audit::{clearable, history_view, print_ensured, print_updated};
+use super::docker::{docker_manager, host_server};
+use super::fanout::{finish, guard_quotas};
+use super::guard::confirm_live;
+use super::hints;
+use super::journal;
+use super::preflight::{self, print_report, run_preflight};
+use super::project_bundle;
+use super::project_exec;
+use super::project_status;
+use super::resolve::{find_project, find_server, name_taken, ref_tag};
+use super::service;
+use super::CommandFailed;
+use crate::{style, ProjectCommands};
+use chrono::{Duration as ChronoDuration, Utc};
+use pctrl_core::bundle::ProjectBundle;
+use pctrl_core::diff;
+use pctrl_core::fanout::{fan_out, FailOn, FanoutReport, Outcome, TargetResult};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::maintenance::EndedWindow;
+use pctrl_core::network::{network_edges, NetworkEdge};
+use pctrl_core::preflight::{CheckKind, Verdict, EXIT_PREFLIGHT_REFUSED};
+use pctrl_core::project_archive::BundleOptions;
+use pctrl_core::project_clone::{CloneOptions, ClonePlan};
+use pctrl_core::startup::{phase_status, plan_phases, Direction, PhaseStatus};
+use pctrl_core::{
+    humanize, hyperlink, ship, EntityType, Project, ProjectPatch, ProjectResource, ProjectStatus,
+    ResourceType, Server,
+};
 use pctrl_database::Database;
+use pctrl_docker::DockerManager;
+use std::time::{Duration, Instant};
 
 pub async fn handle(command: ProjectCommands, db: &Database) -> anyhow::Result<()> {
     match command {
         ProjectCommands::List => {
             let projects = db.list_projects().await?;
             if projects.is_empty() {
-                println!("No projects configured.");
-                println!();
-                println!("Add one with:");
-                println!("  pctrl project add <name> [-d description] [-s stack]");
+                outln!("No projects configured.");
+                hints::show(db, Event::Empty(Listing::Projects)).await?;
             } else {
-                println!("Projects ({}):", projects.len());
-                println!();
+                outln!("Projects ({}):", projects.len());
+                outln!();
+                let windows = db.list_maintenance_windows().await?;
+                let refs = db.short_refs(EntityType::Project).await?;
+                let now = Utc::now();
                 for project in projects {
-                    let status_icon = match project.status {
-                        ProjectStatus::Live => "🟢",
-                        ProjectStatus::Staging => "🟡",
-                        ProjectStatus::Dev => "🔵",
-                        ProjectStatus::Archived => "⚫",
-                    };
                     let stack_str = if project.stack.is_empty() {
                         String::new()
                     } else {
                         format!(" [{}]", project.stack.join(", "))
                     };
-                    println!(
-                        "  {} {} - {}{}",
-                        status_icon, project.name, project.status, stack_str
+                    let status = match windows.iter().find(|w| w.project_id == project.id) {
+                        Some(window) => style::warning_text(&window.banner(now)),
+                        None => project.status.to_string(),
+                    };
+                    outln!(
+                        "  {} {}{} - {}{}",
+                        status_icon(&project.status),
+                        project.name,
+                        ref_tag(&refs, &project.id),
+                        status,
+                        stack_str
                     );
                 }
             }
@@ -41,93 +73,226 @@ pub async fn handle(command: ProjectCommands, db: &Database) -> anyhow::Result<(
             description,
             stack,
             status,
+            ensure,
         } => {
-            let id = name.to_lowercase().replace(' ', "-");
-
-            if db.get_project_by_name(&name).await?.is_some() {
+            let existing = db.get_project_by_name(&name).await?;
+            if existing.is_some() && !ensure {
                 anyhow::bail!("Project '{}' already exists.", name);
             }
+            let id = existing
+                .map(|p| p.id)
+                .unwrap_or_else(|| name.to_lowercase().replace(' ', "-"));
 
-            let stack_vec: Vec<String> = stack
-                .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
-                .unwrap_or_default();
-
-            let status: ProjectStatus = status.parse().unwrap_or_default();
+            let stack: Option<Vec<String>> =
+                stack.map(|s| s.split(',').map(|s| s.trim().to_string()).collect());
+            let status: Option<ProjectStatus> = status.map(|s| s.parse().unwrap_or_default());
 
             let project = Project {
                 id: id.clone(),
                 name: name.clone(),
-                description,
-                stack: stack_vec.clone(),
-                status: status.clone(),
+                description: description.clone(),
+                stack: stack.clone().unwrap_or_default(),
+                status: status.clone().unwrap_or_default(),
                 color: None,
                 icon: None,
                 notes: None,
             };
 
-            db.save_project(&project).await?;
+            if ensure {
+                let patch = ProjectPatch {
+                    description,
+                    stack,
+                    status,
+                };
+                let ensured = db.ensure_project(&project, &patch).await?;
+                print_ensured("Project", &name, &ensured);
+                return Ok(());
+            }
+
+            db.create_project(&project)
+                .await
+                .map_err(|e| name_taken("Project", e))?;
+            let (stack_vec, status) = (project.stack, project.status);
 
-            println!("✓ Project added:");
-            println!();
-            println!("  Name:   {}", name);
-            println!("  ID:     {}", id);
-            println!("  Status: {}", status);
+            noteln!("✓ Project added:");
+            noteln!();
+            noteln!("  Name:   {}", name);
+            noteln!("  ID:     {}", id);
+            noteln!("  Status: {}", status);
             if !stack_vec.is_empty() {
-                println!("  Stack:  {}", stack_vec.join(", "));
+                noteln!("  Stack:  {}", stack_vec.join(", "));
             }
         }
 
         ProjectCommands::Show { name } => {
-            let project = db
-                .get_project_by_name(&name)
-                .await?
-                .or(db.get_project(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", name))?;
-
-            let status_icon = match project.status {
-                ProjectStatus::Live => "🟢",
-                ProjectStatus::Staging => "🟡",
-                ProjectStatus::Dev => "🔵",
-                ProjectStatus::Archived => "⚫",
-            };
+            let bundle = find_bundle(db, &name).await?;
+            let project = &bundle.project;
 
-            println!();
-            println!("  {} {}", status_icon, project.name);
-            println!("  ─────────────────────────────");
-            println!("  ID:     {}", project.id);
-            println!("  Status: {}", project.status);
+            outln!();
+            outln!("  {} {}", status_icon(&project.status), project.name);
+            outln!("  ─────────────────────────────");
+            outln!("  ID:     {}", project.id);
+            if let Some(short_ref) = db.short_ref(EntityType::Project, &project.id).await? {
+                outln!("  Ref:    {}", short_ref);
+            }
+            outln!("  Status: {}", project.status);
+            if let Some(window) = db.get_maintenance_window(&project.id).await? {
+                outln!(
+                    "          {}",
+                    style::warning_text(&format!(
+                        "{}, then {}",
+                        window.banner(Utc::now()),
+                        window.previous_status
+                    ))
+                );
+            }
             if !project.stack.is_empty() {
-                println!("  Stack:  {}", project.stack.join(", "));
+                outln!("  Stack:  {}", project.stack.join(", "));
             }
             if let Some(desc) = &project.description {
-                println!("  Desc:   {}", desc);
+                outln!("  Desc:   {}", desc);
             }
 
             // Show linked resources
-            let resources = db.get_project_resources(&project.id).await?;
+            let resources = &bundle.links;
             if !resources.is_empty() {
-                println!();
-                println!("  Resources ({}):", resources.len());
+                let config = db.load_config().await?;
+                outln!();
+                outln!("  Resources ({}):", resources.len());
                 for res in resources {
-                    let role_str = res.role.map(|r| format!(" ({})", r)).unwrap_or_default();
-                    println!(
-                        "    {} {} → {}{}",
-                        res.resource_type, res.resource_id, res.id, role_str
+                    // Coolify links open the instance dashboard, git links the repo folder
+                    let target = match res.resource_type {
+                        ResourceType::Coolify => res
+                            .resource_id
+                            .split_once('/')
+                            .and_then(|(instance, _)| {
+                                config
+                                    .coolify_instances
+                                    .iter()
+                                    .find(|i| i.id == instance || i.name == instance)
+                            })
+                            .map(|i| hyperlink::link(&i.url, &res.resource_id)),
+                        ResourceType::Git => config
+                            .git_repos
+                            .iter()
+                            .find(|r| r.id == res.resource_id)
+                            .map(|r| {
+                                hyperlink::link(
+                                    &hyperlink::file_url(std::path::Path::new(&r.path)),
+                                    &res.resource_id,
+                                )
+                            }),
+                        // Links by ID show the entity's name too
+                        _ => bundle
+                            .name_of(res)
+                            .filter(|name| !name.eq_ignore_ascii_case(&res.resource_id))
+                            .map(|name| format!("{} ({})", name, res.resource_id)),
+                    }
+                    .unwrap_or_else(|| res.resource_id.clone());
+                    let target = if bundle.is_dangling(res) {
+                        format!("{} {}", target, style::warning_text("(missing)"))
+                    } else {
+                        target
+                    };
+                    let role_str = res
+                        .role
+                        .as_ref()
+                        .map(|r| format!(" ({})", r))
+                        .unwrap_or_default();
+                    let order_str = res
+                        .start_order
+                        .map(|o| format!(" [order {}]", o))
+                        .unwrap_or_default();
+                    outln!(
+                        "    {} {} → {}{}{}",
+                        res.resource_type,
+                        target,
+                        res.id,
+                        role_str,
+                        order_str
+                    );
+                }
+            }
+
+            // systemd units, with the state of their last status check
+            let services = db.list_services_for_project(&project.id).await?;
+            if !services.is_empty() {
+                outln!();
+                outln!("  Services ({}):", services.len());
+                for svc in &services {
+                    let server = bundle
+                        .server(&svc.server_id)
+                        .map(|s| s.name.as_str())
+                        .unwrap_or(&svc.server_id);
+                    outln!(
+                        "    {} {} → {}:{}{}",
+                        service::state_icon(svc.last_state.as_deref()),
+                        svc.name,
+                        server,
+                        svc.unit,
+                        service::last_state(svc)
+                    );
+                }
+            }
+
+            let deploy = db.get_deploy_config(&project.id).await?;
+            if let Some(path) = &deploy.path {
+                outln!();
+                outln!(
+                    "  Ship:   {} ({})",
+                    path,
+                    deploy.branch.as_deref().unwrap_or(ship::DEFAULT_BRANCH)
+                );
+                if let Some(run) = db.list_ship_runs(&project.id, 1).await?.first() {
+                    let outcome = match run.failed_phase {
+                        Some(phase) => format!("✗ failed at {}", phase),
+                        None => "✓".to_string(),
+                    };
+                    outln!(
+                        "  Last:   {} {} {}",
+                        outcome,
+                        run.new_commit.as_deref().map(ship::short).unwrap_or("-"),
+                        humanize::relative_timestamp(&run.ran_at)
                     );
                 }
             }
-            println!();
+            outln!();
+        }
+
+        ProjectCommands::HistoryView { name, at, timeline } => {
+            history_view(db, EntityType::Project, &name, at, timeline).await?;
         }
 
         ProjectCommands::Remove { name } => {
-            let project = db
-                .get_project_by_name(&name)
-                .await?
-                .or(db.get_project(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", name))?;
+            let project = find_project(db, &name).await?;
 
             if db.remove_project(&project.id).await? {
-                println!("✓ Project '{}' removed", project.name);
+                noteln!("✓ Project '{}' removed", project.name);
+            }
+        }
+
+        ProjectCommands::Log {
+            name,
+            text,
+            show,
+            limit,
+            amend,
+        } => {
+            let project = find_project(db, &name).await?;
+
+            if show {
+                journal::show(db, EntityType::Project, &project.id, &project.name, limit).await?;
+            } else {
+                let text = text.unwrap_or_default();
+                journal::append(
+                    db,
+                    EntityType::Project,
+                    &project.id,
+                    &project.name,
+                    &text,
+                    amend,
+                )
+                .await?;
             }
         }
 
@@ -136,12 +301,9 @@ pub async fn handle(command: ProjectCommands, db: &Database) -> anyhow::Result<(
             resource_type,
             resource_id,
             role,
+            order,
         } => {
-            let proj = db
-                .get_project_by_name(&project)
-                .await?
-                .or(db.get_project(&project).await?)
-                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))?;
+            let proj = find_project(db, &project).await?;
 
             let res_type: ResourceType = resource_type
                 .parse()
@@ -154,33 +316,765 @@ pub async fn handle(command: ProjectCommands, db: &Database) -> anyhow::Result<(
                 resource_id: resource_id.clone(),
                 role: role.clone(),
                 notes: None,
+                start_order: order,
             };
 
             db.link_project_resource(&link).await?;
 
-            println!(
+            noteln!(
                 "✓ Linked {} '{}' to project '{}'",
-                res_type, resource_id, proj.name
+                res_type,
+                resource_id,
+                proj.name
             );
             if let Some(r) = role {
-                println!("  Role: {}", r);
+                outln!("  Role: {}", r);
+            }
+            if let Some(o) = order {
+                outln!("  Order: {}", o);
+            }
+        }
+
+        ProjectCommands::SetOrder { link_id, order } => {
+            if db.set_resource_start_order(&link_id, order).await? {
+                match order {
+                    Some(o) => noteln!("✓ Start order of '{}' set to {}", link_id, o),
+                    None => noteln!("✓ Start order of '{}' cleared", link_id),
+                }
+            } else {
+                outln!("✗ Link '{}' not found", link_id);
+            }
+        }
+
+        ProjectCommands::Start {
+            project,
+            host,
+            timeout,
+            enforce_quotas,
+            json,
+            fail_on,
+        } => {
+            run_phases(
+                db,
+                &project,
+                host,
+                timeout.to_std()?,
+                Direction::Start,
+                enforce_quotas,
+                json,
+                fail_on,
+            )
+            .await?;
+        }
+
+        ProjectCommands::Stop {
+            project,
+            host,
+            timeout,
+            allow_live,
+            json,
+            fail_on,
+        } => {
+            let proj = find_project(db, &project).await?;
+            if proj.status.is_live() {
+                confirm_live(&[proj], "Stopping containers", allow_live)?;
+            }
+            run_phases(
+                db,
+                &project,
+                host,
+                timeout.to_std()?,
+                Direction::Stop,
+                false,
+                json,
+                fail_on,
+            )
+            .await?;
+        }
+
+        ProjectCommands::Graph { project } => {
+            let bundle = find_bundle(db, &project).await?;
+            let containers: Vec<String> = bundle
+                .links_of(ResourceType::Container)
+                .map(|r| r.resource_id.clone())
+                .collect();
+            let edges = network_edges(&containers, &db.list_container_networks().await?);
+
+            out!("{}", graph_dot(&bundle, &edges));
+        }
+
+        ProjectCommands::Bundle {
+            verify: Some(path), ..
+        } => {
+            project_bundle::verify(&path)?;
+        }
+
+        ProjectCommands::Bundle {
+            project,
+            out,
+            include_secrets,
+            include_artifacts,
+            ..
+        } => {
+            let proj = find_project(db, &project.unwrap_or_default()).await?;
+            let options = BundleOptions {
+                include_secrets,
+                include_artifacts,
+            };
+            project_bundle::bundle(db, &proj, out, options).await?;
+        }
+
+        ProjectCommands::Exec {
+            project,
+            server,
+            all,
+            allow_live,
+            command,
+        } => {
+            let proj = find_project(db, &project).await?;
+            project_exec::exec(db, &proj, server, all, allow_live, &command.join(" ")).await?;
+        }
+
+        ProjectCommands::Status {
+            project,
+            timeout,
+            json,
+            fail_on,
+        } => {
+            let proj = find_project(db, &project).await?;
+            project_status::status(db, &proj, timeout.to_std()?, json, fail_on).await?;
+        }
+
+        ProjectCommands::Preflight { project } => {
+            let proj = find_project(db, &project).await?;
+            let (report, config) = run_preflight(db, &proj).await?;
+            print_report(&report, &config);
+            match report.verdict {
+                Verdict::Clear => outln!("✓ All checks passed"),
+                Verdict::Warnings => outln!("✓ Deploy would proceed (advisory checks failed)"),
+                Verdict::Refused => {
+                    return Err(CommandFailed::new(
+                        EXIT_PREFLIGHT_REFUSED,
+                        "Deploy would be refused",
+                    )
+                    .into())
+                }
+            }
+        }
+
+        ProjectCommands::SetPreflight {
+            project,
+            health_url,
+            no_health_url,
+            advisory,
+            blocking,
+        } => {
+            let proj = find_project(db, &project).await?;
+            let mut config = db.get_preflight_config(&proj.id).await?;
+
+            if no_health_url {
+                config.health_url = None;
+            } else if health_url.is_some() {
+                config.health_url = health_url;
+            }
+            for (kinds, is_advisory) in [(advisory, true), (blocking, false)] {
+                for kind in kinds {
+                    let kind: CheckKind = kind.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+                    config.set_advisory(kind, is_advisory);
+                }
+            }
+            db.save_preflight_config(&proj.id, &config).await?;
+
+            noteln!("✓ Pre-flight of '{}' updated", proj.name);
+            outln!(
+                "  Health URL: {}",
+                config
+                    .health_url
+                    .as_deref()
+                    .map_or("-".to_string(), hyperlink::web)
+            );
+            for kind in CheckKind::ALL {
+                let policy = if config.is_blocking(kind) {
+                    "blocking"
+                } else {
+                    "advisory"
+                };
+                outln!("  {:<10}  {}", kind.to_string(), policy);
+            }
+        }
+
+        ProjectCommands::Deploy {
+            project,
+            skip_preflight,
+        } => {
+            let proj = find_project(db, &project).await?;
+            preflight::deploy(db, &proj, skip_preflight).await?;
+        }
+
+        ProjectCommands::Edit {
+            project,
+            status,
+            description,
+            stack,
+            deploy_path,
+            no_deploy_path,
+            deploy_branch,
+            deploy_server,
+        } => {
+            let proj = find_project(db, &project).await?;
+            if status.is_some() || description.is_some() || stack.is_some() {
+                edit_details(db, &proj, status, description, stack).await?;
+                // Only the details change
+                if deploy_path.is_none()
+                    && !no_deploy_path
+                    && deploy_branch.is_none()
+                    && deploy_server.is_none()
+                {
+                    return Ok(());
+                }
+                outln!();
+            }
+
+            let mut config = db.get_deploy_config(&proj.id).await?;
+
+            if let Some(path) = deploy_path {
+                config.path = Some(path);
+            }
+            if no_deploy_path {
+                config.path = None;
+            }
+            if let Some(branch) = deploy_branch {
+                config.branch = Some(branch);
+            }
+            if let Some(server) = deploy_server {
+                let server = find_server(db, &server).await?;
+                config.server_id = Some(server.id);
+            }
+
+            db.save_deploy_config(&proj.id, &config).await?;
+            noteln!("✓ Deploy settings of '{}' updated", proj.name);
+            outln!("  Path:   {}", config.path.as_deref().unwrap_or("(none)"));
+            outln!(
+                "  Branch: {}",
+                config.branch.as_deref().unwrap_or(ship::DEFAULT_BRANCH)
+            );
+            if let Some(server_id) = &config.server_id {
+                outln!("  Server: {}", server_id);
+            }
+        }
+
+        ProjectCommands::Ship {
+            project,
+            branch,
+            no_build,
+            timeout,
+            allow_live,
+        } => {
+            let proj = find_project(db, &project).await?;
+            if proj.status.is_live() {
+                confirm_live(std::slice::from_ref(&proj), "Shipping", allow_live)?;
+            }
+            super::ship::ship(db, &proj, branch, !no_build, timeout.to_std()?).await?;
+        }
+
+        ProjectCommands::Clone {
+            source,
+            new_name,
+            include_scripts,
+            include_domains_as,
+            link_same_servers,
+            link_same_databases,
+            dry_run,
+        } => {
+            let bundle = find_bundle(db, &source).await?;
+            let options = CloneOptions {
+                include_scripts,
+                domain_suffix: include_domains_as,
+                link_same_servers,
+                link_same_databases,
+            };
+            let plan = ClonePlan::new(
+                &bundle,
+                &db.list_scripts_for_project(&bundle.project.id).await?,
+                &new_name,
+                &options,
+                &db.list_projects().await?,
+                &db.list_scripts().await?,
+                &db.list_domains().await?,
+            )
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+            if !dry_run {
+                db.clone_project(&plan).await?;
+            }
+            print_clone_plan(&bundle, &plan, dry_run);
+        }
+
+        ProjectCommands::Maintenance {
+            project,
+            duration,
+            reason,
+            end,
+        } => {
+            let proj = find_project(db, &project).await?;
+            if end {
+                match db.end_maintenance(&proj.id).await? {
+                    Some(ended) => print_ended(&ended),
+                    None => outln!("'{}' is not in maintenance", proj.name),
+                }
+                return Ok(());
+            }
+
+            let duration: ChronoDuration = duration.unwrap_or_default();
+            if duration <= ChronoDuration::zero() {
+                anyhow::bail!("The maintenance window must be at least 1s");
             }
+            let now = Utc::now();
+            let window = db
+                .start_maintenance(&proj, reason.as_deref(), now, now + duration)
+                .await?;
+            outln!(
+                "🔧 '{}' is in maintenance for {} (until {})",
+                proj.name,
+                humanize::duration(duration.to_std()?),
+                window.expires_at
+            );
+            if let Some(reason) = &window.reason {
+                outln!("  Reason: {}", reason);
+            }
+            outln!(
+                "  Alerts for its servers are paused; the status goes back to {} afterwards",
+                window.previous_status
+            );
         }
 
         ProjectCommands::Unlink { project, link_id } => {
-            let proj = db
-                .get_project_by_name(&project)
-                .await?
-                .or(db.get_project(&project).await?)
-                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))?;
+            let proj = find_project(db, &project).await?;
 
             if db.unlink_project_resource(&link_id).await? {
-                println!("✓ Unlinked resource from project '{}'", proj.name);
+                noteln!("✓ Unlinked resource from project '{}'", proj.name);
             } else {
-                println!("✗ Link '{}' not found", link_id);
+                outln!("✗ Link '{}' not found", link_id);
             }
         }
     }
 
     Ok(())
 }
+
+/// Apply `project edit --status/--description/--stack`
+async fn edit_details(
+    db: &Database,
+    before: &Project,
+    status: Option<String>,
+    description: Option<String>,
+    stack: Option<String>,
+) -> anyhow::Result<()> {
+    let mut project = before.clone();
+    if let Some(status) = status {
+        let status: ProjectStatus = status.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        check_status(&project, &status)?;
+        project.status = status;
+    }
+    if let Some(description) = description {
+        project.description = clearable(description);
+    }
+    if let Some(stack) = stack {
+        project.stack = stack
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    let changes = diff::diff(before, &project);
+    if !changes.is_empty() {
+        db.update_project(&project).await?;
+    }
+    print_updated("Project", &project.name, &changes);
+    Ok(())
+}
+
+/// Maintenance starts and ends through `project maintenance`, which keeps
+/// its window, never through a plain status change
+pub(crate) fn check_status(project: &Project, status: &ProjectStatus) -> anyhow::Result<()> {
+    if *status == ProjectStatus::Maintenance {
+        anyhow::bail!("Use pctrl project maintenance to put a project into maintenance");
+    }
+    if project.status == ProjectStatus::Maintenance && *status != project.status {
+        anyhow::bail!(
+            "'{}' is in maintenance; end it first with pctrl project maintenance {} --end",
+            project.name,
+            project.name
+        );
+    }
+    Ok(())
+}
+
+fn status_icon(status: &ProjectStatus) -> &'static str {
+    match status {
+        ProjectStatus::Live => "🟢",
+        ProjectStatus::Staging => "🟡",
+        ProjectStatus::Dev => "🔵",
+        ProjectStatus::Archived => "⚫",
+        ProjectStatus::Maintenance => "🔧",
+    }
+}
+
+/// Report a maintenance window that ended
+pub(crate) fn print_ended(ended: &EndedWindow) {
+    match &ended.restored {
+        Some(status) => noteln!(
+            "✓ Maintenance of '{}' ended, status back to {}",
+            ended.project_name,
+            status
+        ),
+        None => noteln!(
+            "✓ Maintenance of '{}' ended (status was changed during the window, left as is)",
+            ended.project_name
+        ),
+    }
+}
+
+/// What a clone created (or would create)
+fn print_clone_plan(bundle: &ProjectBundle, plan: &ClonePlan, dry_run: bool) {
+    outln!(
+        "{} '{}' from '{}':",
+        if dry_run {
+            "Would create"
+        } else {
+            "✓ Created"
+        },
+        plan.project.name,
+        bundle.project.name
+    );
+    outln!("  project  {} ({})", plan.project.name, plan.project.id);
+    for cloned in &plan.scripts {
+        outln!("  script   {} → {}", cloned.from, cloned.script.name);
+    }
+    for cloned in &plan.domains {
+        outln!("  domain   {} → {}", cloned.from, cloned.domain.domain);
+    }
+    for link in &plan.links {
+        let role = link
+            .role
+            .as_ref()
+            .map(|r| format!(" ({})", r))
+            .unwrap_or_default();
+        // Shared entities keep their names, copies have new ones
+        let name = match link.resource_type {
+            ResourceType::Domain => plan
+                .domains
+                .iter()
+                .find(|c| c.domain.id == link.resource_id)
+                .map(|c| c.domain.domain.as_str()),
+            ResourceType::Script => None,
+            _ => bundle.name_of(link),
+        }
+        .unwrap_or(&link.resource_id);
+        outln!("  link     {} {}{}", link.resource_type, name, role);
+    }
+    if !plan.skipped.is_empty() {
+        noteln!();
+        noteln!("Not carried over:");
+        for link in &plan.skipped {
+            let name = bundle.name_of(link).unwrap_or(&link.resource_id);
+            noteln!("  {} {}", link.resource_type, name);
+        }
+    }
+}
+
+/// Resolve a project by name or ID, with its links and linked entities
+async fn find_bundle(db: &Database, project: &str) -> anyhow::Result<ProjectBundle> {
+    let project = find_project(db, project).await?;
+    db.resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))
+}
+
+/// The server a project runs on: the configured deploy server, else the
+/// server linked as `production_server`, else its only linked server
+pub(crate) async fn project_server(db: &Database, project: &Project) -> anyhow::Result<Server> {
+    let configured = db.get_deploy_config(&project.id).await?.server_id;
+    let bundle = db
+        .resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))?;
+    let links: Vec<&ProjectResource> = bundle.links_of(ResourceType::Server).collect();
+
+    let server_ref = match configured {
+        Some(server_id) => server_id,
+        None => match links
+            .iter()
+            .find(|l| l.role.as_deref() == Some("production_server"))
+        {
+            Some(link) => link.resource_id.clone(),
+            None => match links.as_slice() {
+                [only] => only.resource_id.clone(),
+                [] => anyhow::bail!(
+                    "Project '{}' has no linked server (pctrl project link {} server <name>)",
+                    project.name,
+                    project.name
+                ),
+                _ => anyhow::bail!(
+                    "Project '{}' has several servers ({}); link one with role production_server or set project edit --deploy-server",
+                    project.name,
+                    links
+                        .iter()
+                        .map(|l| l.resource_id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+        },
+    };
+
+    // The configured deploy server doesn't have to be linked
+    if let Some(server) = bundle.server(&server_ref) {
+        return Ok(server.clone());
+    }
+    find_server(db, &server_ref).await
+}
+
+/// Start or stop a project's containers phase by phase
+#[allow(clippy::too_many_arguments)]
+async fn run_phases(
+    db: &Database,
+    project: &str,
+    host: Option<String>,
+    timeout: Duration,
+    direction: Direction,
+    enforce_quotas: bool,
+    json: bool,
+    fail_on: FailOn,
+) -> anyhow::Result<()> {
+    let proj = find_project(db, project).await?;
+
+    let phases = plan_phases(&db.get_project_resources(&proj.id).await?, direction);
+    if phases.is_empty() {
+        outln!("Project '{}' has no linked containers.", proj.name);
+        hints::show(db, Event::NoContainersLinked { project: proj.name }).await?;
+        return Ok(());
+    }
+
+    let (docker, host_id) = docker_manager(db, host).await?;
+    if direction == Direction::Start {
+        if let Some(server_id) = match docker.get_host(&host_id) {
+            Some(host) => host_server(db, host).await?,
+            None => None,
+        } {
+            let names: Vec<&str> = phases
+                .iter()
+                .flat_map(|p| p.containers.iter().map(String::as_str))
+                .collect();
+            guard_quotas(db, &server_id, &names, enforce_quotas).await?;
+        }
+    }
+    let (verb, command) = match direction {
+        Direction::Start => ("Starting", "project start"),
+        Direction::Stop => ("Stopping", "project stop"),
+    };
+    let started = Instant::now();
+    let mut report = FanoutReport::new(
+        command,
+        &Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    if !json {
+        outln!("{} '{}' ({} phases)", verb, proj.name, phases.len());
+    }
+
+    for (i, phase) in phases.iter().enumerate() {
+        // Later phases depend on the failed one
+        if report.counts.failed > 0 {
+            report.extend(
+                phase
+                    .containers
+                    .iter()
+                    .map(|c| TargetResult::skipped(c, c, "an earlier phase failed")),
+            );
+            continue;
+        }
+
+        if !json {
+            let order = phase
+                .order
+                .map(|o| format!("order {}", o))
+                .unwrap_or_else(|| "unordered".to_string());
+            outln!();
+            outln!(
+                "  Phase {} ({}): {}",
+                i + 1,
+                order,
+                phase.containers.join(", ")
+            );
+        }
+
+        let mut results = fan_out(
+            &phase.containers,
+            |c| (c.clone(), c.clone()),
+            |c| change_state(&docker, &host_id, c, direction),
+        )
+        .await;
+        // Wait until the whole phase is up (or down) before the next one
+        await_phase(&docker, &host_id, &mut results, timeout, direction).await;
+
+        if !json {
+            if results.iter().any(|r| r.outcome == Outcome::Failed) {
+                outln!("  ✗ Phase {} failed", i + 1);
+            } else {
+                outln!("  ✓ Phase {} done", i + 1);
+            }
+        }
+        report.extend(results);
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    finish(db, &report, json, fail_on).await
+}
+
+/// Start or stop one container unless it's already in that state
+async fn change_state(
+    docker: &DockerManager,
+    host_id: &str,
+    container: &str,
+    direction: Direction,
+) -> (Outcome, Option<String>) {
+    let state = match docker.container_state(host_id, container).await {
+        Ok(state) => state,
+        Err(e) => return (Outcome::Failed, Some(e.to_string())),
+    };
+    let changed = match direction {
+        Direction::Start if state.status != "running" => {
+            docker.start_container(host_id, container).await
+        }
+        Direction::Stop if state.status == "running" => {
+            docker.stop_container(host_id, container).await
+        }
+        Direction::Start => return (Outcome::Ok, Some("already running".to_string())),
+        Direction::Stop => return (Outcome::Ok, Some("already stopped".to_string())),
+    };
+    match changed {
+        Ok(()) => (Outcome::Ok, None),
+        Err(e) => (Outcome::Failed, Some(e.to_string())),
+    }
+}
+
+/// Poll the started (or stopped) containers until each is running/healthy
+/// (or down), adding the wait to their durations. Containers that fail or
+/// don't get there within `timeout` are marked failed.
+async fn await_phase(
+    docker: &DockerManager,
+    host_id: &str,
+    results: &mut [TargetResult],
+    timeout: Duration,
+    direction: Direction,
+) {
+    let waiting_since = Instant::now();
+    let deadline = waiting_since + timeout;
+    let mut pending: Vec<usize> = (0..results.len())
+        .filter(|&i| results[i].outcome == Outcome::Ok)
+        .collect();
+
+    while !pending.is_empty() {
+        let mut waiting = Vec::new();
+        for i in pending {
+            let result = &mut results[i];
+            let status = match docker.container_state(host_id, &result.id).await {
+                Ok(state) => phase_status(std::slice::from_ref(&result.id), &[state], direction),
+                Err(e) => PhaseStatus::Failed(e.to_string()),
+            };
+            match status {
+                PhaseStatus::Ready => {
+                    result.duration_ms += waiting_since.elapsed().as_millis() as u64;
+                }
+                PhaseStatus::Failed(reason) => {
+                    result.outcome = Outcome::Failed;
+                    result.message = Some(reason);
+                }
+                PhaseStatus::Waiting => waiting.push(i),
+            }
+        }
+        pending = waiting;
+        if pending.is_empty() {
+            break;
+        }
+
+        if Instant::now() >= deadline {
+            let target = match direction {
+                Direction::Start => "running",
+                Direction::Stop => "stopped",
+            };
+            for i in pending {
+                let result = &mut results[i];
+                result.outcome = Outcome::Failed;
+                result.duration_ms += waiting_since.elapsed().as_millis() as u64;
+                result.message = Some(format!(
+                    "not {} within {}",
+                    target,
+                    humanize::duration(timeout)
+                ));
+            }
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Edge colors for container networks, assigned in order of appearance
+const NETWORK_COLORS: &[&str] = &[
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// Render a project's resources as a Graphviz digraph. Containers sharing a
+/// Docker network are joined by undirected edges colored per network.
+fn graph_dot(bundle: &ProjectBundle, edges: &[NetworkEdge]) -> String {
+    let project = &bundle.project;
+    let node = |kind: &dyn std::fmt::Display, id: &str| format!("\"{}:{}\"", kind, id);
+    let root = node(&"project", &project.id);
+
+    let mut out = format!("digraph \"{}\" {{\n", project.name);
+    out.push_str("  rankdir=LR;\n  node [shape=box];\n");
+    out.push_str(&format!(
+        "  {} [label=\"{}\", shape=folder];\n",
+        root, project.name
+    ));
+
+    for res in &bundle.links {
+        let id = node(&res.resource_type, &res.resource_id);
+        out.push_str(&format!(
+            "  {} [label=\"{}\\n{}\"];\n",
+            id,
+            bundle.name_of(res).unwrap_or(&res.resource_id),
+            res.resource_type
+        ));
+        let label = res
+            .role
+            .as_ref()
+            .map(|r| format!(" [label=\"{}\"]", r))
+            .unwrap_or_default();
+        out.push_str(&format!("  {} -> {}{};\n", root, id, label));
+    }
+
+    let mut networks: Vec<&str> = Vec::new();
+    for edge in edges {
+        let index = match networks.iter().position(|n| *n == edge.network) {
+            Some(i) => i,
+            None => {
+                networks.push(&edge.network);
+                networks.len() - 1
+            }
+        };
+        out.push_str(&format!(
+            "  {} -> {} [dir=none, style=dashed, color=\"{}\", fontcolor=\"{}\", label=\"{}\"];\n",
+            node(&ResourceType::Container, &edge.a),
+            node(&ResourceType::Container, &edge.b),
+            NETWORK_COLORS[index % NETWORK_COLORS.len()],
+            NETWORK_COLORS[index % NETWORK_COLORS.len()],
+            edge.network
+        ));
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,175 @@
+//! `pctrl project bundle`: one archive with everything needed to rebuild a
+//! project (see [`pctrl_core::project_archive`])
+
+use super::backup::backup_dir;
+use super::CommandFailed;
+use crate::style;
+use chrono::Utc;
+use pctrl_core::project_archive::{
+    self, BundleOptions, Manifest, Part, SkippedPart, FORMAT_VERSION, JOURNAL_LIMIT,
+};
+use pctrl_core::project_clone::slug;
+use pctrl_core::{humanize, EntityType, Project};
+use pctrl_database::backup::latest_backup;
+use pctrl_database::bundle_archive::{self, BundleEntry, EntrySource};
+use pctrl_database::Database;
+use std::path::{Path, PathBuf};
+
+/// Render the parts the flags allow and pack them into `out`
+pub(crate) async fn bundle(
+    db: &Database,
+    project: &Project,
+    out: Option<PathBuf>,
+    options: BundleOptions,
+) -> anyhow::Result<()> {
+    let bundle = db
+        .resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))?;
+    let out = out.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}-bundle-{}.tar.gz",
+            slug(&project.name),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    let text = |part: Part, content: String| BundleEntry {
+        part,
+        path: part.path().to_string(),
+        source: EntrySource::Bytes(content.into_bytes()),
+    };
+    for &part in Part::ALL {
+        if let Some(gate) = part.gate().filter(|_| !options.allows(part)) {
+            skipped.push(SkippedPart {
+                part,
+                reason: format!("needs {}", gate),
+            });
+            continue;
+        }
+        match part {
+            Part::Markdown => entries.push(text(part, project_archive::render_markdown(&bundle))),
+            Part::Inventory => entries.push(text(
+                part,
+                project_archive::render_inventory(&bundle, options.include_secrets)
+                    .map_err(|e| anyhow::anyhow!("Can't render the inventory: {}", e))?,
+            )),
+            Part::Proxy => {
+                let routes = project_archive::proxy_routes(&bundle, &db.list_containers().await?);
+                entries.push(text(part, project_archive::render_proxy(&routes)));
+            }
+            Part::Env => entries.push(text(part, project_archive::render_env(&bundle))),
+            Part::Journal => {
+                let mut groups = vec![(
+                    format!("Project {}", project.name),
+                    db.list_journal_entries(EntityType::Project, &project.id, JOURNAL_LIMIT)
+                        .await?,
+                )];
+                for server in &bundle.servers {
+                    groups.push((
+                        format!("Server {}", server.name),
+                        db.list_journal_entries(EntityType::Server, &server.id, JOURNAL_LIMIT)
+                            .await?,
+                    ));
+                }
+                for domain in &bundle.domains {
+                    groups.push((
+                        format!("Domain {}", domain.domain),
+                        db.list_journal_entries(EntityType::Domain, &domain.id, JOURNAL_LIMIT)
+                            .await?,
+                    ));
+                }
+                entries.push(text(part, project_archive::render_journal(&groups)));
+            }
+            Part::Backup => match latest_backup(&backup_dir(db))? {
+                Some(path) => entries.push(BundleEntry {
+                    part,
+                    path: format!(
+                        "{}{}",
+                        part.path(),
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    ),
+                    source: EntrySource::File(path),
+                }),
+                None => skipped.push(SkippedPart {
+                    part,
+                    reason: format!("no backup in {}", backup_dir(db).display()),
+                }),
+            },
+        }
+    }
+
+    let manifest = bundle_archive::write_bundle(
+        &out,
+        &entries,
+        Manifest {
+            format: FORMAT_VERSION,
+            pctrl_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            project: project.name.clone(),
+            include_secrets: options.include_secrets,
+            include_artifacts: options.include_artifacts,
+            files: Vec::new(),
+            skipped,
+        },
+    )?;
+
+    let size = std::fs::metadata(&out).map(|m| m.len()).unwrap_or(0);
+    noteln!(
+        "✓ Bundle of '{}' written to {} ({})",
+        project.name,
+        out.display(),
+        humanize::bytes(size)
+    );
+    for file in &manifest.files {
+        outln!(
+            "  {:<28} {:>10}",
+            file.path,
+            style::dim(&humanize::bytes(file.size))
+        );
+    }
+    for skipped in &manifest.skipped {
+        outln!(
+            "  {}",
+            style::dim(&format!("{} left out: {}", skipped.part, skipped.reason))
+        );
+    }
+    if options.include_secrets {
+        outln!(
+            "{}",
+            style::warning_text("This bundle holds secrets; store it like a password.")
+        );
+    }
+    Ok(())
+}
+
+/// `--verify`: compare every file with the manifest
+pub(crate) fn verify(path: &Path) -> anyhow::Result<()> {
+    let report = bundle_archive::verify_bundle(path)?;
+    let manifest = &report.manifest;
+    outln!(
+        "Bundle of '{}' from {} (pctrl {}, {} files)",
+        manifest.project,
+        humanize::relative_timestamp(&manifest.created_at),
+        manifest.pctrl_version,
+        manifest.files.len()
+    );
+    for file in &report.mismatched {
+        outln!("  {} {}", style::error_text("✗ changed:"), file);
+    }
+    for file in &report.missing {
+        outln!("  {} {}", style::error_text("✗ missing:"), file);
+    }
+    for file in &report.unlisted {
+        outln!("  {} {}", style::warning_text("? not in manifest:"), file);
+    }
+    if !report.is_ok() {
+        return Err(
+            CommandFailed::new(1, format!("{} failed verification", path.display())).into(),
+        );
+    }
+    outln!("{}", style::success_text("✓ All checksums match"));
+    Ok(())
+}
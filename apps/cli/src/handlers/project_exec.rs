@@ -0,0 +1,185 @@
+//! `pctrl project exec`: an ad-hoc command on the server a project runs on
+
+use super::guard::confirm_live;
+use super::project::project_server;
+use super::server::create_ssh_manager;
+use super::vpn::vpn_blocked;
+use super::CommandFailed;
+use crate::style;
+use pctrl_core::log_tail::LineBuffer;
+use pctrl_core::{humanize, Project, Server};
+use pctrl_database::Database;
+use pctrl_ssh::SshManager;
+use std::io::Write;
+
+/// Exit code reported for a server the command never ran on, like `ssh`
+const EXIT_UNREACHABLE: i32 = 255;
+
+/// Run `command` on the project's server, the linked `server` or, with
+/// `all`, every linked server one after the other. The exit code is the
+/// remote one; with `all`, that of the first server that failed.
+pub(crate) async fn exec(
+    db: &Database,
+    project: &Project,
+    server: Option<String>,
+    all: bool,
+    allow_live: bool,
+    command: &str,
+) -> anyhow::Result<()> {
+    let targets = targets(db, project, server, all).await?;
+    if project.status.is_live() {
+        confirm_live(
+            std::slice::from_ref(project),
+            &format!(
+                "Command on {}",
+                humanize::count(targets.len() as u64, "server", "servers")
+            ),
+            allow_live,
+        )?;
+    }
+
+    if !all {
+        let server = &targets[0];
+        noteln!("▶ {} on {}: {}", project.name, server.name, command);
+        let code = run_on(db, server, command, None).await?;
+        if code != 0 {
+            return Err(CommandFailed::new(
+                code,
+                format!("Command exited with {} on '{}'", code, server.name),
+            )
+            .into());
+        }
+        return Ok(());
+    }
+
+    noteln!(
+        "▶ {} on {} servers: {}",
+        project.name,
+        targets.len(),
+        command
+    );
+    let mut failed: Vec<(&str, i32)> = Vec::new();
+    for (index, server) in targets.iter().enumerate() {
+        let prefix = style::source_text(index, &format!("{} │", server.name));
+        let code = match run_on(db, server, command, Some(prefix.clone())).await {
+            Ok(code) => code,
+            Err(e) => {
+                outln!("{} {}", prefix, style::error_text(&e.to_string()));
+                EXIT_UNREACHABLE
+            }
+        };
+        if code != 0 {
+            failed.push((&server.name, code));
+        }
+    }
+
+    let Some(&(_, first_code)) = failed.first() else {
+        noteln!("✓ Ran on {} servers", targets.len());
+        return Ok(());
+    };
+    let summary = failed
+        .iter()
+        .map(|(name, code)| format!("{} ({})", name, code))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(CommandFailed::new(
+        first_code,
+        format!(
+            "Failed on {} of {} servers: {}",
+            failed.len(),
+            targets.len(),
+            summary
+        ),
+    )
+    .into())
+}
+
+/// The servers to run on; `server` has to be one of the project's links
+async fn targets(
+    db: &Database,
+    project: &Project,
+    server: Option<String>,
+    all: bool,
+) -> anyhow::Result<Vec<Server>> {
+    if server.is_none() && !all {
+        return Ok(vec![project_server(db, project).await?]);
+    }
+
+    let bundle = db
+        .resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))?;
+    if bundle.servers.is_empty() {
+        anyhow::bail!(
+            "Project '{}' has no linked server (pctrl project link {} server <name>)",
+            project.name,
+            project.name
+        );
+    }
+    match server {
+        None => Ok(bundle.servers.clone()),
+        Some(reference) => match bundle.server(&reference) {
+            Some(server) => Ok(vec![server.clone()]),
+            None => anyhow::bail!(
+                "Server '{}' is not linked to project '{}' (linked: {})",
+                reference,
+                project.name,
+                bundle
+                    .servers
+                    .iter()
+                    .map(|s| s.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        },
+    }
+}
+
+/// Run `command` on one server, printing its output as it arrives, each
+/// line behind `prefix` if given. Returns the remote exit code.
+async fn run_on(
+    db: &Database,
+    server: &Server,
+    command: &str,
+    prefix: Option<String>,
+) -> anyhow::Result<i32> {
+    if let Some(reason) = vpn_blocked(server).await {
+        anyhow::bail!(
+            "{}; server '{}' is only reachable through it",
+            reason,
+            server.name
+        );
+    }
+    let cred_id = server
+        .credential_id
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", server.name))?;
+    let (manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+
+    let command = command.to_string();
+    let code = tokio::task::spawn_blocking(move || {
+        let session = manager.connect(&conn_id)?;
+        let stream_timeout = manager.options().stream_timeout;
+        let mut buffer = LineBuffer::default();
+        let (_, code) =
+            SshManager::execute_streaming(&session, &command, stream_timeout, &mut |chunk| {
+                match &prefix {
+                    Some(prefix) => {
+                        for line in buffer.push(chunk) {
+                            outln!("{} {}", prefix, line);
+                        }
+                    }
+                    None => {
+                        out!("{}", chunk);
+                        let _ = std::io::stdout().flush();
+                    }
+                }
+            })?;
+        if let (Some(prefix), Some(rest)) = (&prefix, buffer.finish()) {
+            outln!("{} {}", prefix, rest);
+        }
+        Ok::<_, pctrl_core::Error>(code)
+    })
+    .await??;
+    Ok(code)
+}
@@ -0,0 +1,267 @@
+//! `pctrl project status`: check every linked resource at once
+
+use super::docker::{docker_manager, server_host};
+use super::preflight::{check_database, probe_server};
+use super::server::create_ssh_manager;
+use super::vpn::vpn_blocked;
+use super::CommandFailed;
+use crate::style;
+use pctrl_core::bundle::ProjectBundle;
+use pctrl_core::fanout::{fan_out, FailOn, FanoutReport, Outcome};
+use pctrl_core::project_status::{self, Verdict};
+use pctrl_core::{Container, GitRepo, Project, ResourceType, Server};
+use pctrl_database::Database;
+use pctrl_git::GitManager;
+use std::time::{Duration, Instant};
+
+/// A linked resource to check
+struct Target {
+    kind: ResourceType,
+    /// Link target: ID or name
+    id: String,
+    name: String,
+}
+
+/// What the checks look things up in
+struct Inventory {
+    bundle: ProjectBundle,
+    containers: Vec<Container>,
+    repos: Vec<GitRepo>,
+}
+
+/// Check the project's resources, print the table (or JSON) and fail per
+/// `fail_on`
+pub(crate) async fn status(
+    db: &Database,
+    project: &Project,
+    timeout: Duration,
+    json: bool,
+    fail_on: FailOn,
+) -> anyhow::Result<()> {
+    let bundle = db
+        .resolve_project_bundle(&project.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project.name))?;
+    let targets: Vec<Target> = bundle
+        .links
+        .iter()
+        .filter(|l| project_status::CHECKED.contains(&l.resource_type))
+        .map(|l| Target {
+            kind: l.resource_type.clone(),
+            id: l.resource_id.clone(),
+            name: display_name(&bundle, l.resource_type.clone(), &l.resource_id),
+        })
+        .collect();
+    let inventory = Inventory {
+        bundle,
+        containers: db.list_containers().await?,
+        repos: db.load_config().await?.git_repos,
+    };
+
+    let started = Instant::now();
+    let mut report = FanoutReport::new(
+        "project status",
+        &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    report.extend(
+        fan_out(
+            &targets,
+            |t| (format!("{}:{}", t.kind, t.id), t.name.clone()),
+            |t| async {
+                match tokio::time::timeout(timeout, check(db, &inventory, t)).await {
+                    Ok(Ok(verdict)) => verdict,
+                    Ok(Err(e)) => (Outcome::Failed, Some(e.to_string())),
+                    Err(_) => project_status::timed_out(timeout),
+                }
+            },
+        )
+        .await,
+    );
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    db.save_last_run(&report).await?;
+
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_table(project, &targets, &report);
+    }
+
+    match fail_on.exit_code(&report.counts) {
+        0 => Ok(()),
+        code => Err(
+            CommandFailed::new(code, format!("{}: {}", report.command, report.summary())).into(),
+        ),
+    }
+}
+
+/// Name to show for a link: the server or database name, the domain, ...
+fn display_name(bundle: &ProjectBundle, kind: ResourceType, id: &str) -> String {
+    match kind {
+        ResourceType::Server => bundle.server(id).map(|s| s.name.clone()),
+        ResourceType::Database => bundle.database(id).map(|d| d.name.clone()),
+        ResourceType::Domain => bundle.domain(id).map(|d| d.domain.clone()),
+        _ => None,
+    }
+    .unwrap_or_else(|| id.to_string())
+}
+
+async fn check(db: &Database, inventory: &Inventory, target: &Target) -> anyhow::Result<Verdict> {
+    match target.kind {
+        ResourceType::Server => check_server(db, inventory, &target.id).await,
+        ResourceType::Container => check_container(db, inventory, &target.id).await,
+        ResourceType::Database => {
+            let result = check_database(&target.id, inventory.bundle.database(&target.id)).await;
+            Ok(match result.error {
+                None => (Outcome::Ok, None),
+                Some(error) => (Outcome::Failed, Some(error)),
+            })
+        }
+        ResourceType::Domain => check_domain(inventory, &target.id).await,
+        ResourceType::Git => check_git(inventory, &target.id).await,
+        _ => Ok((Outcome::Skipped, Some("not checked".to_string()))),
+    }
+}
+
+async fn check_server(db: &Database, inventory: &Inventory, id: &str) -> anyhow::Result<Verdict> {
+    let Some(server) = inventory.bundle.server(id) else {
+        return Ok(failed("linked server no longer exists"));
+    };
+    server_verdict(db, server).await
+}
+
+/// Log in over SSH; without a credential, only the SSH port can be tried
+pub(crate) async fn server_verdict(db: &Database, server: &Server) -> anyhow::Result<Verdict> {
+    if let Some(reason) = vpn_blocked(server).await {
+        return Ok(failed(&reason));
+    }
+    let Some(cred_id) = &server.credential_id else {
+        return Ok(match probe_server(db, server).await? {
+            Ok(()) => (
+                Outcome::Ok,
+                Some("SSH port open, no credential to log in".to_string()),
+            ),
+            Err(e) => (Outcome::Failed, Some(e)),
+        });
+    };
+
+    let (ssh, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+    Ok(
+        match tokio::task::spawn_blocking(move || ssh.test_connection(&conn_id, None)).await? {
+            Ok(()) => (Outcome::Ok, None),
+            Err(e) => (Outcome::Failed, Some(e.to_string())),
+        },
+    )
+}
+
+/// Ask the Docker host of the container's server, else the default host
+async fn check_container(
+    db: &Database,
+    inventory: &Inventory,
+    id: &str,
+) -> anyhow::Result<Verdict> {
+    let server_id = inventory
+        .containers
+        .iter()
+        .find(|c| c.id == id || c.name == id)
+        .map(|c| c.server_id.clone());
+    let host_id = match server_id {
+        Some(server_id) => match db.get_server(&server_id).await? {
+            Some(server) => server_host(db, &server).await?.map(|h| h.id),
+            None => None,
+        },
+        None => None,
+    };
+    let (docker, host_id) = docker_manager(db, host_id).await?;
+    let state = docker.container_state(&host_id, id).await?;
+    Ok(project_status::container_verdict(
+        &state.status,
+        state.health.as_deref(),
+    ))
+}
+
+async fn check_domain(inventory: &Inventory, id: &str) -> anyhow::Result<Verdict> {
+    let Some(domain) = inventory.bundle.domain(id) else {
+        return Ok(failed("linked domain no longer exists"));
+    };
+    let url = project_status::domain_url(&domain.domain, domain.ssl);
+    Ok(match reqwest::Client::new().head(&url).send().await {
+        Ok(response) => project_status::http_verdict(response.status().as_u16()),
+        Err(e) => (Outcome::Failed, Some(request_error(&e))),
+    })
+}
+
+/// The innermost cause of a request error ("Name or service not known"),
+/// without the URL and wrappers around it
+fn request_error(error: &reqwest::Error) -> String {
+    let mut cause: &dyn std::error::Error = error;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+async fn check_git(inventory: &Inventory, id: &str) -> anyhow::Result<Verdict> {
+    let Some(repo) = inventory
+        .repos
+        .iter()
+        .find(|r| r.id == id || r.name.eq_ignore_ascii_case(id))
+        .cloned()
+    else {
+        return Ok(failed("linked repository no longer exists"));
+    };
+    let repo_id = repo.id.clone();
+    let mut git = GitManager::new();
+    git.add_repo(repo);
+    Ok(
+        match tokio::task::spawn_blocking(move || git.changed_files(&repo_id)).await? {
+            Ok(changed) => project_status::git_verdict(changed),
+            Err(e) => (Outcome::Failed, Some(e.to_string())),
+        },
+    )
+}
+
+fn failed(message: &str) -> Verdict {
+    (Outcome::Failed, Some(message.to_string()))
+}
+
+fn print_table(project: &Project, targets: &[Target], report: &FanoutReport) {
+    outln!("Status of '{}':", project.name);
+    outln!();
+    if targets.is_empty() {
+        outln!(
+            "  {}",
+            style::dim(
+                "Nothing to check (no linked servers, containers, databases, domains or repos)"
+            )
+        );
+        return;
+    }
+
+    let width = targets
+        .iter()
+        .map(|t| t.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (target, result) in targets.iter().zip(&report.targets) {
+        let mark = match result.outcome {
+            Outcome::Ok => style::success_text("✓"),
+            Outcome::Failed => style::error_text("✗"),
+            Outcome::Skipped => style::warning_text("-"),
+        };
+        outln!(
+            "  {} {:<9} {:<width$}  {:>7}  {}",
+            mark,
+            target.kind.to_string(),
+            target.name,
+            format!("{}ms", result.duration_ms),
+            style::dim(result.message.as_deref().unwrap_or(""))
+        );
+    }
+    outln!();
+    let summary = format!("{} ({}ms)", report.summary(), report.duration_ms);
+    if report.counts.failed > 0 {
+        outln!("  {}", style::error_text(&summary));
+    } else {
+        outln!("  {}", style::success_text(&summary));
+    }
+}
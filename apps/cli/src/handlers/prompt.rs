@@ -0,0 +1,37 @@
+//! `pctrl prompt-segment`: health badge for shell prompts
+//!
+//! Runs before the database is opened the normal way (see `main`), so a
+//! prompt never waits for schema setup. It always succeeds: a prompt hook
+//! that fails would break the user's shell.
+
+use crate::style;
+use pctrl_core::prompt::{self, PromptFormat, PromptHealth, PromptShell};
+use pctrl_core::shell::quote_word;
+use pctrl_database::prompt::read_cached_status;
+use std::path::Path;
+
+pub async fn handle(db_path: &Path, format: &str, init: Option<&str>) -> anyhow::Result<()> {
+    if let Some(shell) = init {
+        let shell: PromptShell = shell.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        let mut command = "pctrl".to_string();
+        if db_path != crate::default_db_path() {
+            command.push_str(" --db ");
+            command.push_str(&quote_word(&db_path.to_string_lossy()));
+        }
+        out!("{}", prompt::init_script(shell, &command));
+        return Ok(());
+    }
+
+    let format = match format.parse::<PromptFormat>() {
+        Ok(PromptFormat::Powerline) if !style::use_color() => PromptFormat::Plain,
+        Ok(format) => format,
+        Err(e) => {
+            eoutln!("{}", e);
+            PromptFormat::Plain
+        }
+    };
+    let status = read_cached_status(db_path, prompt::BUDGET).await;
+    let health = PromptHealth::judge(status.servers, status.monitor.as_ref(), chrono::Utc::now());
+    outln!("{}", health.render(format));
+    Ok(())
+}
@@ -0,0 +1,377 @@
+//! `domain propagation`: ask public resolvers whether a DNS change arrived
+
+use super::resolve::find_domain;
+use crate::style;
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::proto::rr::RecordType;
+use hickory_resolver::TokioAsyncResolver;
+use pctrl_core::hooks::{HookPayload, MONITOR_CHANGED};
+use pctrl_core::propagation::{
+    self, AnswerStatus, DnsLookup, DnsRecord, PropagationReport, Target, TrackedPropagation,
+    QUERY_TIMEOUT, WATCH_INTERVAL_SECS,
+};
+use pctrl_core::throttle::Limits;
+use pctrl_core::Server;
+use pctrl_database::Database;
+use std::io::{self, IsTerminal};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Sends UDP queries straight to one resolver, bypassing the system
+/// resolver and any cache
+struct HickoryLookup;
+
+#[async_trait]
+impl DnsLookup for HickoryLookup {
+    async fn lookup(&self, resolver: IpAddr, domain: &str) -> Result<Vec<DnsRecord>, String> {
+        let config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_ips_clear(&[resolver], 53, true),
+        );
+        let mut opts = ResolverOpts::default();
+        opts.timeout = QUERY_TIMEOUT;
+        opts.attempts = 1;
+        opts.cache_size = 0;
+        let client = TokioAsyncResolver::tokio(config, opts);
+
+        let mut records = Vec::new();
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            match client.lookup(domain, record_type).await {
+                Ok(lookup) => records.extend(lookup.record_iter().filter_map(|record| {
+                    Some(DnsRecord {
+                        ip: record.data()?.ip_addr()?,
+                        ttl: record.ttl(),
+                    })
+                })),
+                Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle(
+    db: &Database,
+    domain: String,
+    expect: Vec<IpAddr>,
+    resolvers: Vec<IpAddr>,
+    watch: bool,
+    timeout: chrono::Duration,
+    track: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let expected = if expect.is_empty() {
+        expected_addresses(db, &domain).await?
+    } else {
+        expect
+    };
+    let resolvers = if resolvers.is_empty() {
+        propagation::default_resolvers()
+    } else {
+        resolvers
+    };
+
+    let report = propagation::check(
+        &HickoryLookup,
+        &domain,
+        &resolvers,
+        &expected,
+        QUERY_TIMEOUT,
+    )
+    .await;
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if track && !report.is_complete() {
+        let now = Utc::now();
+        db.track_propagation(&TrackedPropagation {
+            domain: domain.clone(),
+            expected: expected.clone(),
+            resolvers: resolvers.clone(),
+            started_at: now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            expires_at: (now + timeout).to_rfc3339_opts(SecondsFormat::Secs, true),
+        })
+        .await?;
+        if !json {
+            outln!();
+            outln!(
+                "{}",
+                style::dim("Tracking: `pctrl monitor run` reports when it completes")
+            );
+        }
+    }
+
+    if !watch || report.is_complete() {
+        return Ok(());
+    }
+
+    let timeout = timeout.to_std()?;
+    let started = Instant::now();
+    loop {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            anyhow::bail!(
+                "'{}' not fully propagated after {}",
+                domain,
+                pctrl_core::humanize::duration(timeout)
+            );
+        }
+        tokio::time::sleep(remaining.min(Duration::from_secs(WATCH_INTERVAL_SECS))).await;
+
+        let report = propagation::check(
+            &HickoryLookup,
+            &domain,
+            &resolvers,
+            &expected,
+            QUERY_TIMEOUT,
+        )
+        .await;
+        outln!();
+        outln!("{}", style::dim(&Utc::now().format("%H:%M:%S").to_string()));
+        print_report(&report);
+        if report.is_complete() {
+            return Ok(());
+        }
+    }
+}
+
+/// Re-check tracked propagations; called by the monitor on each cycle.
+/// Completed and expired ones are reported, fire `monitor.changed` and
+/// are no longer tracked.
+pub(crate) async fn check_tracked(db: &Database) -> anyhow::Result<()> {
+    let tracked = db.list_tracked_propagations().await?;
+    let targets: Vec<Target> = tracked
+        .iter()
+        .map(|t| Target {
+            domain: t.domain.clone(),
+            expected: t.expected.clone(),
+            resolvers: t.resolvers.clone(),
+        })
+        .collect();
+    let reports = propagation::check_many(
+        &HickoryLookup,
+        &targets,
+        QUERY_TIMEOUT,
+        &Limits::default(),
+        |_| {},
+    )
+    .await;
+
+    for (tracked, report) in tracked.into_iter().zip(reports) {
+        let complete = report.is_complete();
+        if !complete && !tracked.is_expired(Utc::now()) {
+            continue;
+        }
+        db.untrack_propagation(&tracked.domain).await?;
+
+        let line = format!("DNS of {}: {}", tracked.domain, summary(&report));
+        outln!(
+            "  {}",
+            if complete {
+                style::success_text(&line)
+            } else {
+                style::warning_text(&format!("{}, gave up", line))
+            }
+        );
+
+        let payload = HookPayload::new(
+            MONITOR_CHANGED,
+            serde_json::json!({
+                "check": "dns_propagation",
+                "domain": tracked.domain,
+                "expected": tracked.expected,
+                "complete": complete,
+                "percentage": report.percentage(),
+            }),
+        );
+        db.fire_hooks(&payload).await;
+    }
+    Ok(())
+}
+
+/// `domain propagation --all`: every inventory domain whose server address
+/// is known, within `limits` per resolver
+pub async fn check_all(
+    db: &Database,
+    resolvers: Vec<IpAddr>,
+    limits: &Limits,
+    json: bool,
+) -> anyhow::Result<()> {
+    let resolvers = if resolvers.is_empty() {
+        propagation::default_resolvers()
+    } else {
+        resolvers
+    };
+
+    let mut targets = Vec::new();
+    let mut skipped = Vec::new();
+    for domain in db.list_domains().await? {
+        match expected_addresses(db, &domain.domain).await {
+            Ok(expected) => targets.push(Target {
+                domain: domain.domain,
+                expected,
+                resolvers: resolvers.clone(),
+            }),
+            Err(_) => skipped.push(domain.domain),
+        }
+    }
+
+    // Progress on one rewritten stderr line, only where someone watches it
+    let show_progress = !json && !style::is_quiet() && io::stderr().is_terminal();
+    let reports = propagation::check_many(
+        &HickoryLookup,
+        &targets,
+        QUERY_TIMEOUT,
+        limits,
+        |progress| {
+            if show_progress {
+                eprint!("\r\x1b[2K  Checking DNS: {} queries", progress);
+            }
+        },
+    )
+    .await;
+    if show_progress && !targets.is_empty() {
+        eprint!("\r\x1b[2K");
+    }
+
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    if reports.is_empty() {
+        outln!("{}", style::dim("No domains with a linked server to check"));
+    }
+    for report in &reports {
+        let line = summary(report);
+        outln!(
+            "  {:<32} {}",
+            report.domain,
+            if report.is_complete() {
+                style::success_text(&line)
+            } else if report.responding() == 0 {
+                style::error_text(&line)
+            } else {
+                style::warning_text(&line)
+            }
+        );
+    }
+    if !skipped.is_empty() {
+        outln!();
+        outln!(
+            "{}",
+            style::dim(&format!(
+                "Skipped, no server address known: {}",
+                skipped.join(", ")
+            ))
+        );
+    }
+    Ok(())
+}
+
+/// Addresses of the server the domain is linked to
+async fn expected_addresses(db: &Database, domain: &str) -> anyhow::Result<Vec<IpAddr>> {
+    let missing = || {
+        anyhow::anyhow!(
+            "No server address known for '{}'; pass --expect <ip>",
+            domain
+        )
+    };
+    let dom = find_domain(db, domain).await?;
+    let server_ref = dom.server_id.ok_or_else(missing)?;
+    let server = db
+        .get_server(&server_ref)
+        .await?
+        .or(db.get_server_by_name(&server_ref).await?)
+        .ok_or_else(missing)?;
+
+    server_addresses(&server)
+        .await
+        .map_err(|e| anyhow::anyhow!("{}; pass --expect <ip>", e))?
+        .ok_or_else(missing)
+}
+
+/// Addresses of a server: its host if that is an IP, else what the host
+/// name resolves to (`None` when it resolves to nothing)
+pub(crate) async fn server_addresses(server: &Server) -> anyhow::Result<Option<Vec<IpAddr>>> {
+    if let Ok(ip) = server.host.parse::<IpAddr>() {
+        return Ok(Some(vec![ip]));
+    }
+    let addrs: Vec<IpAddr> = tokio::net::lookup_host((server.host.as_str(), 0))
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Can't resolve {} of server '{}' ({})",
+                server.host,
+                server.name,
+                e
+            )
+        })?
+        .map(|addr| addr.ip())
+        .collect();
+    Ok((!addrs.is_empty()).then_some(addrs))
+}
+
+fn print_report(report: &PropagationReport) {
+    let expected: Vec<String> = report.expected.iter().map(IpAddr::to_string).collect();
+    outln!(
+        "{} → {}",
+        style::header(&report.domain),
+        expected.join(", ")
+    );
+    outln!();
+    for answer in &report.answers {
+        let status = answer.status(&report.expected);
+        let ips = match &answer.error {
+            Some(e) => e.clone(),
+            None if answer.records.is_empty() => "-".to_string(),
+            None => answer
+                .records
+                .iter()
+                .map(|r| r.ip.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        let ttl = answer
+            .min_ttl()
+            .map_or_else(|| "-".to_string(), |ttl| format!("{}s", ttl));
+        let status_text = status.to_string();
+        outln!(
+            "  {:<16} {:<40} {:>7}  {}",
+            answer.resolver,
+            ips,
+            ttl,
+            match status {
+                AnswerStatus::Propagated => style::success_text(&status_text),
+                AnswerStatus::Stale | AnswerStatus::NoRecords => style::warning_text(&status_text),
+                AnswerStatus::Failed => style::error_text(&status_text),
+            }
+        );
+    }
+    outln!();
+    outln!("  {}", summary(report));
+}
+
+/// e.g. "67% propagated (2 of 3 responding resolvers)"
+fn summary(report: &PropagationReport) -> String {
+    format!(
+        "{}% propagated ({} of {} responding {})",
+        report.percentage(),
+        report.propagated(),
+        report.responding(),
+        if report.responding() == 1 {
+            "resolver"
+        } else {
+            "resolvers"
+        }
+    )
+}
@@ -0,0 +1,125 @@
+//! `deps` output and the delete guard built on reverse references
+
+use crate::style;
+use pctrl_core::{EntityType, Reference};
+use pctrl_database::Database;
+
+/// Print what references an entity, as a tree grouped by relationship
+pub(crate) async fn handle_deps(
+    db: &Database,
+    entity_type: EntityType,
+    id: &str,
+    name: &str,
+    json: bool,
+) -> anyhow::Result<()> {
+    let references = db.reverse_references(entity_type, id).await?;
+
+    if json {
+        let output = serde_json::json!({
+            "entity_type": entity_type.to_string(),
+            "id": id,
+            "name": name,
+            "references": references,
+        });
+        outln!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    outln!(
+        "{}",
+        style::header(&format!(
+            "Dependents of {} '{}' ({})",
+            entity_type,
+            name,
+            references.len()
+        ))
+    );
+    if references.is_empty() {
+        outln!();
+        outln!("  Nothing references this {}.", entity_type);
+        return Ok(());
+    }
+    print_tree(&references);
+    Ok(())
+}
+
+/// Refuse to remove an entity that's still referenced, unless forced
+pub(crate) async fn guard_remove(
+    db: &Database,
+    entity_type: EntityType,
+    id: &str,
+    name: &str,
+    force: bool,
+) -> anyhow::Result<()> {
+    let references = db.reverse_references(entity_type, id).await?;
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    if force {
+        outln!(
+            "{}",
+            style::warning_text(&format!(
+                "⚠  Removing {} '{}' leaves {} dangling reference(s) (--force)",
+                entity_type,
+                name,
+                references.len()
+            ))
+        );
+        return Ok(());
+    }
+
+    outln!(
+        "{}",
+        style::error_text(&format!(
+            "✗ {} '{}' is still referenced by:",
+            capitalize(&entity_type.to_string()),
+            name
+        ))
+    );
+    print_tree(&references);
+    outln!();
+    anyhow::bail!("Unlink these first, or pass --force to remove anyway")
+}
+
+fn print_tree(references: &[Reference]) {
+    let mut start = 0;
+    while start < references.len() {
+        let relation = &references[start].relation;
+        let end = start
+            + references[start..]
+                .iter()
+                .take_while(|r| &r.relation == relation)
+                .count();
+        let group = &references[start..end];
+
+        outln!();
+        outln!("  {} ({})", style::bold(relation), group.len());
+        for (i, reference) in group.iter().enumerate() {
+            let branch = if i + 1 == group.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            if reference.name == reference.id {
+                outln!("    {} {}", branch, reference.name);
+            } else {
+                outln!(
+                    "    {} {} {}",
+                    branch,
+                    reference.name,
+                    style::dim(&format!("({})", reference.id))
+                );
+            }
+        }
+        start = end;
+    }
+}
+
+pub(crate) fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,177 @@
+//! Resolving user input to entities, and `pctrl resolve`
+//!
+//! Anywhere an entity is named, a short ref (`srv-3fk2`), an ID or a name
+//! is accepted, in that order. Input that names several entities is
+//! rejected with their refs, so one of those can be used instead.
+
+use super::references::capitalize;
+use crate::style;
+use pctrl_core::script_namespace;
+use pctrl_core::short_ref::EntityRef;
+use pctrl_core::{Credential, DatabaseCredentials, Domain, EntityType, Project, Script, Server};
+use pctrl_database::Database;
+use std::collections::HashMap;
+
+/// Handle `pctrl resolve <input> [--type <type>]`
+pub async fn handle(
+    db: &Database,
+    input: String,
+    entity_type: Option<String>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let entity_type: Option<EntityType> = entity_type
+        .map(|t| t.parse().map_err(|e: String| anyhow::anyhow!(e)))
+        .transpose()?;
+    let found = resolve(db, entity_type, &input).await?;
+
+    if json {
+        outln!("{}", serde_json::to_string_pretty(&found)?);
+        return Ok(());
+    }
+    outln!(
+        "{}  {} {} {}",
+        style::bold(&found.short_ref),
+        found.entity_type,
+        found.name,
+        style::dim(&format!("({})", found.id))
+    );
+    Ok(())
+}
+
+/// The one entity `input` names, of a type or of any
+pub async fn resolve(
+    db: &Database,
+    entity_type: Option<EntityType>,
+    input: &str,
+) -> anyhow::Result<EntityRef> {
+    lookup(db, entity_type, input)
+        .await?
+        .ok_or_else(|| match entity_type {
+            Some(t) => anyhow::anyhow!("{} '{}' not found", capitalize(&t.to_string()), input),
+            None => anyhow::anyhow!("Nothing named '{}' found", input),
+        })
+}
+
+/// Like [`resolve`], with `None` when nothing matches; ambiguous input is
+/// still an error
+pub async fn lookup(
+    db: &Database,
+    entity_type: Option<EntityType>,
+    input: &str,
+) -> anyhow::Result<Option<EntityRef>> {
+    let mut found = db.find_entities(entity_type, input).await?;
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(Some(found.remove(0))),
+        _ => {
+            let candidates: Vec<String> = found
+                .iter()
+                .map(|e| format!("{} ({} '{}')", e.short_ref, e.entity_type, e.name))
+                .collect();
+            anyhow::bail!(
+                "'{}' is ambiguous, use a short ref: {}",
+                input,
+                candidates.join(", ")
+            )
+        }
+    }
+}
+
+/// An entity's short ref to put after its name in lists, dimmed
+pub(crate) fn ref_tag(refs: &HashMap<String, String>, id: &str) -> String {
+    refs.get(id)
+        .map(|r| format!(" {}", style::dim(r)))
+        .unwrap_or_default()
+}
+
+/// Resolve an entity ref, ID or name to (id, display name)
+pub async fn resolve_entity(
+    db: &Database,
+    entity_type: EntityType,
+    name: &str,
+) -> anyhow::Result<(String, String)> {
+    let found = resolve(db, Some(entity_type), name).await?;
+    Ok((found.id, found.name))
+}
+
+/// Resolve a project by ref, ID or name
+pub(crate) async fn find_project(db: &Database, input: &str) -> anyhow::Result<Project> {
+    let (id, _) = resolve_entity(db, EntityType::Project, input).await?;
+    db.get_project(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", input))
+}
+
+/// Resolve a server by ref, ID or name
+pub(crate) async fn find_server(db: &Database, input: &str) -> anyhow::Result<Server> {
+    let (id, _) = resolve_entity(db, EntityType::Server, input).await?;
+    db.get_server(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", input))
+}
+
+/// Resolve a domain by ref, ID or name
+pub(crate) async fn find_domain(db: &Database, input: &str) -> anyhow::Result<Domain> {
+    let (id, _) = resolve_entity(db, EntityType::Domain, input).await?;
+    db.get_domain(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Domain '{}' not found", input))
+}
+
+/// Resolve a database by ref, ID or name
+pub(crate) async fn find_database(
+    db: &Database,
+    input: &str,
+) -> anyhow::Result<DatabaseCredentials> {
+    let (id, _) = resolve_entity(db, EntityType::Database, input).await?;
+    db.get_database_credentials(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Database '{}' not found", input))
+}
+
+/// Resolve a script by ref, ID or name, else by the end of its name in
+/// whole namespace segments (`postgres-daily` for `backups/postgres-daily`)
+/// if only one script's name ends that way
+pub(crate) async fn find_script(db: &Database, input: &str) -> anyhow::Result<Script> {
+    if let Some(found) = lookup(db, Some(EntityType::Script), input).await? {
+        return db
+            .get_script(&found.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Script '{}' not found", input));
+    }
+
+    let mut scripts = db.list_scripts().await?;
+    let matches: Vec<String> =
+        script_namespace::suffix_matches(scripts.iter().map(|s| s.name.as_str()), input)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+    match matches.as_slice() {
+        [] => anyhow::bail!("Script '{}' not found", input),
+        [name] => {
+            scripts.retain(|s| &s.name == name);
+            Ok(scripts.remove(0))
+        }
+        _ => anyhow::bail!(
+            "'{}' matches several scripts, give more of the name: {}",
+            input,
+            matches.join(", ")
+        ),
+    }
+}
+
+/// Resolve a credential by ref, ID or name
+pub(crate) async fn find_credential(db: &Database, input: &str) -> anyhow::Result<Credential> {
+    let (id, _) = resolve_entity(db, EntityType::Credential, input).await?;
+    db.get_credential(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Credential '{}' not found", input))
+}
+
+/// A failed create or rename, with a name taken by another `kind` said so
+pub(crate) fn name_taken(kind: &str, err: pctrl_core::Error) -> anyhow::Error {
+    match err {
+        pctrl_core::Error::Conflict(name) => anyhow::anyhow!("{} '{}' already exists.", kind, name),
+        err => err.into(),
+    }
+}
@@ -1,27 +1,88 @@
 //! Script command handler
 
-use crate::ScriptCommands;
-use pctrl_core::{Script, ScriptType};
+use super::audit::{history_view, print_changes, print_ensured, print_updated};
+use super::guard::{confirm_live, confirm_run_window};
+use super::hints;
+use super::resolve::{find_script, find_server, name_taken, ref_tag};
+use crate::{style, ScriptCommands};
+use chrono::{DateTime, Local, Utc};
+use pctrl_core::diff;
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::local_run::LocalRun;
+use pctrl_core::run_window::RunWindow;
+use pctrl_core::script_namespace::{self, TreeRow};
+use pctrl_core::{
+    current_holder, humanize, redact, script_body, shell, EntityType, RevisionStatus, Script,
+    ScriptPatch, ScriptRun, ScriptRunContext, ScriptType, ScriptUpdate,
+};
 use pctrl_database::Database;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
 
 pub async fn handle(command: ScriptCommands, db: &Database) -> anyhow::Result<()> {
     match command {
-        ScriptCommands::List => {
-            let scripts = db.list_scripts().await?;
+        ScriptCommands::List { namespace, flat } => {
+            let namespace = namespace
+                .as_deref()
+                .and_then(script_namespace::normalize_prefix);
+            let scripts = match namespace {
+                Some(namespace) => db.list_scripts_in_namespace(namespace).await?,
+                None => db.list_scripts().await?,
+            };
             if scripts.is_empty() {
-                println!("No scripts configured.");
-                println!();
-                println!("Add one with:");
-                println!("  pctrl script add <name> -c <command>");
-            } else {
-                println!("Scripts ({}):", scripts.len());
-                println!();
-                for script in scripts {
-                    let danger_icon = if script.dangerous { "⚠️ " } else { "" };
-                    println!(
-                        "  📜 {}{} [{}]",
-                        danger_icon, script.name, script.script_type
-                    );
+                match namespace {
+                    Some(namespace) => outln!("No scripts in {}/.", namespace),
+                    None => {
+                        outln!("No scripts configured.");
+                        hints::show(db, Event::Empty(Listing::Scripts)).await?;
+                    }
+                }
+                return Ok(());
+            }
+
+            match namespace {
+                Some(namespace) => outln!("Scripts in {}/ ({}):", namespace, scripts.len()),
+                None => outln!("Scripts ({}):", scripts.len()),
+            }
+            outln!();
+            let refs = db.short_refs(EntityType::Script).await?;
+            let line = |script: &Script, label: &str, indent: usize| {
+                let danger_icon = if script.dangerous { "⚠️ " } else { "" };
+                outln!(
+                    "  {}📜 {}{}{} [{}]",
+                    "  ".repeat(indent),
+                    danger_icon,
+                    label,
+                    ref_tag(&refs, &script.id),
+                    script.script_type
+                );
+            };
+            if flat {
+                for script in &scripts {
+                    line(script, &script.name, 0);
+                }
+                return Ok(());
+            }
+            let names: Vec<&str> = scripts.iter().map(|s| s.name.as_str()).collect();
+            for row in script_namespace::tree(&names, &BTreeSet::new()) {
+                match row {
+                    TreeRow::Namespace {
+                        depth,
+                        label,
+                        scripts,
+                        ..
+                    } => outln!(
+                        "  {}📁 {}/ {}",
+                        "  ".repeat(depth),
+                        label,
+                        style::dim(&format!("({})", scripts))
+                    ),
+                    TreeRow::Script {
+                        depth,
+                        index,
+                        label,
+                    } => line(&scripts[index], label, depth),
                 }
             }
         }
@@ -29,6 +90,7 @@ pub async fn handle(command: ScriptCommands, db: &Database) -> anyhow::Result<()
         ScriptCommands::Add {
             name,
             command,
+            edit,
             description,
             script_type,
             server,
@@ -36,19 +98,38 @@ pub async fn handle(command: ScriptCommands, db: &Database) -> anyhow::Result<()
             docker_host,
             container,
             dangerous,
+            ensure,
         } => {
+            script_namespace::validate_name(&name).map_err(|e| anyhow::anyhow!(e))?;
             let id = name.to_lowercase().replace(' ', "-");
 
-            let script_type: ScriptType = script_type.parse().unwrap_or_default();
+            let command = match command {
+                Some(command) if !edit => Some(command),
+                _ if !edit => None,
+                _ => match edit_in_editor(&name, None)? {
+                    Some(body) => Some(body),
+                    None => {
+                        outln!("✗ Empty script, nothing saved");
+                        return Ok(());
+                    }
+                },
+            };
+            if command.is_none() && db.get_script(&id).await?.is_none() {
+                anyhow::bail!("Script '{}' doesn't exist yet, give its --command", name);
+            }
+
+            let patched_type: Option<ScriptType> =
+                script_type.map(|t| t.parse().unwrap_or_default());
+            let script_type = patched_type.clone().unwrap_or_default();
 
             let script = Script {
                 id: id.clone(),
                 name: name.clone(),
-                description,
-                command: command.clone(),
+                description: description.clone(),
+                command: command.clone().unwrap_or_default(),
                 script_type: script_type.clone(),
                 server_id: server.clone(),
-                project_id: project,
+                project_id: project.clone(),
                 docker_host_id: docker_host.clone(),
                 container_id: container.clone(),
                 dangerous,
@@ -56,125 +137,435 @@ pub async fn handle(command: ScriptCommands, db: &Database) -> anyhow::Result<()
                 last_result: None,
                 exit_code: None,
                 last_output: None,
+                working_dir: None,
+                run_window: None,
+                env: Default::default(),
             };
 
-            db.save_script(&script).await?;
+            if ensure {
+                let patch = ScriptPatch {
+                    description,
+                    command,
+                    script_type: patched_type,
+                    server_id: server,
+                    project_id: project,
+                    docker_host_id: docker_host,
+                    container_id: container,
+                    // Without the flag the stored one is kept
+                    dangerous: dangerous.then_some(true),
+                };
+                let (ensured, pending) =
+                    db.ensure_script(&script, &patch, &current_holder()).await?;
+                print_ensured("Script", &name, &ensured);
+                if let Some(revision) = pending {
+                    outln!(
+                        "⏸ '{}' is dangerous; the command change was saved as revision #{} and needs approval",
+                        name, revision.id
+                    );
+                    outln!("  pctrl script approve {}", revision.id);
+                }
+                return Ok(());
+            }
 
-            println!("✓ Script added:");
-            println!();
-            println!("  Name:    {}", name);
-            println!("  ID:      {}", id);
-            println!("  Type:    {}", script_type);
-            println!("  Command: {}", command);
+            db.create_script(&script)
+                .await
+                .map_err(|e| name_taken("Script", e))?;
+            let command = script.command;
+
+            noteln!("✓ Script added:");
+            noteln!();
+            noteln!("  Name:    {}", name);
+            noteln!("  ID:      {}", id);
+            noteln!("  Type:    {}", script_type);
+            print_command(&command);
             if let Some(s) = server {
-                println!("  Server:  {}", s);
+                noteln!("  Server:  {}", s);
             }
             if let Some(dh) = docker_host {
-                println!("  Docker:  {}", dh);
+                noteln!("  Docker:  {}", dh);
             }
             if let Some(c) = container {
-                println!("  Container: {}", c);
+                noteln!("  Container: {}", c);
             }
             if dangerous {
-                println!("  ⚠️  Marked as dangerous");
+                noteln!("  ⚠️  Marked as dangerous");
             }
         }
 
         ScriptCommands::Show { name } => {
-            let script = db
-                .get_script(&name)
-                .await?
-                .ok_or_else(|| anyhow::anyhow!("Script '{}' not found", name))?;
+            let script = find_script(db, &name).await?;
 
             let danger_icon = if script.dangerous { "⚠️ " } else { "" };
 
-            println!();
-            println!("  📜 {}{}", danger_icon, script.name);
-            println!("  ─────────────────────────────");
-            println!("  ID:      {}", script.id);
-            println!("  Type:    {}", script.script_type);
-            println!("  Command: {}", script.command);
+            outln!();
+            outln!("  📜 {}{}", danger_icon, script.name);
+            outln!("  ─────────────────────────────");
+            outln!("  ID:      {}", script.id);
+            if let Some(short_ref) = db.short_ref(EntityType::Script, &script.id).await? {
+                outln!("  Ref:     {}", short_ref);
+            }
+            outln!("  Type:    {}", script.script_type);
+            print_command(&script.command);
             if let Some(desc) = &script.description {
-                println!("  Desc:    {}", desc);
+                outln!("  Desc:    {}", desc);
             }
             if let Some(server) = &script.server_id {
-                println!("  Server:  {}", server);
+                outln!("  Server:  {}", server);
             }
             if let Some(dh) = &script.docker_host_id {
-                println!("  Docker:  {}", dh);
+                outln!("  Docker:  {}", dh);
             }
             if let Some(c) = &script.container_id {
-                println!("  Container: {}", c);
+                outln!("  Container: {}", c);
             }
             if let Some(project) = &script.project_id {
-                println!("  Project: {}", project);
+                outln!("  Project: {}", project);
             }
+            print_environment(&script);
+            print_run_window(&script);
             if let Some(last_run) = &script.last_run {
-                println!("  Last Run: {}", last_run);
+                outln!("  Last Run: {}", humanize::relative_timestamp(last_run));
             }
             if let Some(result) = &script.last_result {
                 let exit_info = script
                     .exit_code
                     .map(|c| format!(" (exit {})", c))
                     .unwrap_or_default();
-                println!("  Result:  {}{}", result, exit_info);
+                outln!("  Result:  {}{}", result, exit_info);
+            }
+            if let Some(context) = db.get_script_run_context(&script.id).await? {
+                outln!("  Ran in:  {}", context.cwd);
+                outln!("  With:    {}", style::dim(&context.env_names.join(", ")));
             }
             if let Some(output) = &script.last_output {
                 if !output.is_empty() {
-                    println!("  Output:");
+                    outln!("  Output:");
                     for line in output.lines().take(10) {
-                        println!("    {}", line);
+                        outln!("    {}", line);
                     }
                     if output.lines().count() > 10 {
-                        println!("    ... ({} more lines)", output.lines().count() - 10);
+                        outln!("    ... ({} more lines)", output.lines().count() - 10);
                     }
                 }
             }
-            println!();
+            outln!();
+        }
+
+        ScriptCommands::HistoryView { name, at, timeline } => {
+            history_view(db, EntityType::Script, &name, at, timeline).await?;
         }
 
-        ScriptCommands::Run { name, force } => {
-            let script = db
-                .get_script(&name)
+        ScriptCommands::Edit {
+            name,
+            command,
+            edit,
+            dangerous,
+            safe,
+            workdir,
+            no_workdir,
+            env,
+            unset_env,
+            run_window,
+            no_run_window,
+            server,
+        } => {
+            let mut script = find_script(db, &name).await?;
+            let command_changes = command.is_some() || edit || dangerous || safe;
+            let environment_changes =
+                workdir.is_some() || no_workdir || !env.is_empty() || !unset_env.is_empty();
+            let window_changes = run_window.is_some() || no_run_window;
+
+            if let Some(server) = server {
+                script = edit_server(db, script, &server).await?;
+                // Only the server changes
+                if !window_changes && !environment_changes && !command_changes {
+                    return Ok(());
+                }
+                outln!();
+            }
+
+            if window_changes {
+                script = edit_run_window(db, script, run_window).await?;
+                // Only the window changes
+                if !environment_changes && !command_changes {
+                    return Ok(());
+                }
+                outln!();
+            }
+
+            if environment_changes {
+                edit_environment(db, &script, workdir, no_workdir, env, unset_env).await?;
+                // Only the environment changes
+                if !command_changes {
+                    return Ok(());
+                }
+                outln!();
+            }
+
+            let command = match command {
+                Some(command) => command,
+                // Only the flag changes
+                None if (dangerous || safe) && !edit => script.command.clone(),
+                None => match edit_in_editor(&script.name, Some(&script.command))? {
+                    Some(body) => body,
+                    None => {
+                        outln!("✗ Empty script, nothing saved");
+                        return Ok(());
+                    }
+                },
+            };
+            let dangerous = match (dangerous, safe) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => script.dangerous,
+            };
+
+            match db
+                .update_script_command(&script.id, &command, dangerous, &current_holder())
                 .await?
-                .ok_or_else(|| anyhow::anyhow!("Script '{}' not found", name))?;
+            {
+                ScriptUpdate::Unchanged => outln!("No changes."),
+                ScriptUpdate::Applied(script) => {
+                    noteln!("✓ Script '{}' updated", script.name);
+                    outln!();
+                    print_command(&script.command);
+                    if script.dangerous {
+                        outln!("  ⚠️  Marked as dangerous");
+                    }
+                }
+                ScriptUpdate::Pending(revision) => {
+                    outln!(
+                        "⏸ '{}' is dangerous; the change was saved as revision #{} and needs approval",
+                        script.name, revision.id
+                    );
+                    outln!();
+                    print_changes(&revision.changes());
+                    hints::show(
+                        db,
+                        Event::ScriptChangePending {
+                            revision: revision.id,
+                        },
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        ScriptCommands::Pending => {
+            let revisions = db
+                .list_script_revisions(Some(RevisionStatus::Pending))
+                .await?;
+            if revisions.is_empty() {
+                outln!("No pending script changes.");
+                return Ok(());
+            }
+
+            outln!("Pending script changes ({}):", revisions.len());
+            for revision in revisions {
+                outln!();
+                outln!(
+                    "  #{} {} {}",
+                    revision.id,
+                    style::bold(&revision.script_id),
+                    style::dim(&format!(
+                        "by {}, {}",
+                        revision.author,
+                        humanize::relative_timestamp(&revision.created_at)
+                    ))
+                );
+                print_changes(&revision.changes());
+            }
+            hints::show(db, Event::PendingListed).await?;
+        }
+
+        ScriptCommands::Approve { id } => {
+            let script = db.approve_script_revision(id, &current_holder()).await?;
+            noteln!("✓ Revision #{} approved; '{}' updated", id, script.name);
+            outln!();
+            print_command(&script.command);
+        }
+
+        ScriptCommands::Reject { id } => {
+            let revision = db.reject_script_revision(id, &current_holder()).await?;
+            noteln!(
+                "✓ Revision #{} of '{}' rejected",
+                revision.id,
+                revision.script_id
+            );
+        }
+
+        ScriptCommands::Run {
+            name,
+            force,
+            allow_live,
+            inherit_env,
+            override_window,
+        } => {
+            let script = find_script(db, &name).await?;
 
             if script.dangerous && !force {
-                println!("⚠️  This script is marked as dangerous!");
-                println!("    Command: {}", script.command);
-                println!();
-                println!("Use --force to run anyway.");
+                outln!("⚠️  This script is marked as dangerous!");
+                print_command(&script.command);
+                print_run_window(&script);
+                outln!();
+                outln!("Use --force to run anyway.");
                 return Ok(());
             }
+            confirm_run_window(&script, override_window, Utc::now())?;
 
-            println!("Running script '{}'...", script.name);
-            println!("Command: {}", script.command);
-            println!();
+            let live = db.live_projects_for_script_target(&script).await?;
+            confirm_live(&live, &format!("Script '{}'", script.name), allow_live)?;
 
-            let (result, exit_code, output) = match script.script_type {
-                ScriptType::Local => execute_local(&script.command),
-                ScriptType::Ssh => {
-                    println!("⚠️  SSH script execution not yet implemented in v6.");
-                    println!("    Use local scripts for now.");
-                    return Ok(());
-                }
-                ScriptType::Docker => {
-                    println!("⚠️  Docker script execution not yet implemented in v6.");
-                    println!("    Use local scripts for now.");
+            // A missing working directory fails here, before anything runs
+            let local = match script.script_type {
+                ScriptType::Local => Some(plan_local(&script, inherit_env)?),
+                _ => None,
+            };
+
+            outln!("Running script '{}'...", script.name);
+            print_command(&script.command);
+            if let Some(run) = &local {
+                let environment = if inherit_env {
+                    "inherited environment".to_string()
+                } else {
+                    humanize::count(run.env.len() as u64, "variable", "variables")
+                };
+                outln!(
+                    "  {}",
+                    style::dim(&format!("(in {}, {})", run.cwd.display(), environment))
+                );
+            }
+            let pending = db
+                .list_script_revisions(Some(RevisionStatus::Pending))
+                .await?
+                .into_iter()
+                .filter(|r| r.script_id == script.id)
+                .count();
+            if pending > 0 {
+                outln!(
+                    "  {}",
+                    style::dim(&format!(
+                        "({} not applied; see `pctrl script pending`)",
+                        humanize::count(pending as u64, "pending change", "pending changes")
+                    ))
+                );
+            }
+            outln!();
+
+            let context = local.as_ref().map(|run| ScriptRunContext {
+                cwd: run.cwd.display().to_string(),
+                env_names: run.env_names(),
+            });
+            let started_at = Utc::now().to_rfc3339();
+            let (result, exit_code, output) = match (&local, &script.script_type) {
+                (Some(run), _) => execute_local(run, &script.command),
+                (None, ScriptType::Ssh) => execute_ssh(db, &script).await?,
+                (None, _) => {
+                    outln!("⚠️  Docker script execution not yet implemented in v6.");
+                    outln!("    Use local scripts for now.");
                     return Ok(());
                 }
             };
 
             // Update script result in database
-            db.update_script_result(&script.id, result, exit_code, output.as_deref())
+            db.update_script_result(
+                &script.id,
+                result,
+                exit_code,
+                output.as_deref(),
+                context.as_ref(),
+            )
+            .await?;
+            let run = db
+                .add_script_run(
+                    &script.id,
+                    &started_at,
+                    &Utc::now().to_rfc3339(),
+                    exit_code,
+                    output.as_deref(),
+                    &current_holder(),
+                )
                 .await?;
+            db.prune_script_runs(db.script_run_history_limit().await?)
+                .await?;
+            noteln!(
+                "{}",
+                style::dim(&format!(
+                    "Run #{} recorded; see `pctrl script history {}`",
+                    run.id,
+                    shell::quote_word(&script.name)
+                ))
+            );
+        }
+
+        ScriptCommands::History { name, limit } => {
+            let script = find_script(db, &name).await?;
+            let runs = db.list_script_runs(&script.id, limit).await?;
+            if runs.is_empty() {
+                outln!("Script '{}' has not run yet.", script.name);
+                return Ok(());
+            }
+
+            noteln!("Runs of '{}' ({}):", script.name, runs.len());
+            noteln!();
+            outln!(
+                "  {}",
+                style::bold(&format!(
+                    "{:>6}  {:<20}  {:>8}  {:<8}  {}",
+                    "RUN", "STARTED", "DURATION", "EXIT", "BY"
+                ))
+            );
+            for run in &runs {
+                let exit = match run.exit_code {
+                    Some(code) => code.to_string(),
+                    None => "-".to_string(),
+                };
+                let exit = format!("{:<8}", exit);
+                let exit = if run.succeeded() {
+                    style::success_text(&exit)
+                } else {
+                    style::error_text(&exit)
+                };
+                outln!(
+                    "  {:>6}  {:<20}  {:>8}  {}  {}",
+                    format!("#{}", run.id),
+                    humanize::relative_timestamp(&run.started_at),
+                    run_duration(run),
+                    exit,
+                    style::dim(&run.triggered_by)
+                );
+            }
+            noteln!();
+            noteln!(
+                "{}",
+                style::dim("Full output of a run: pctrl script output <run>")
+            );
+        }
+
+        ScriptCommands::Output { run_id } => {
+            let run = db
+                .get_script_run(run_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Run #{} not found", run_id))?;
+            match run.output.as_deref() {
+                Some(output) if !output.is_empty() => {
+                    print!("{}", output);
+                    if !output.ends_with('\n') {
+                        println!();
+                    }
+                }
+                _ => noteln!(
+                    "{}",
+                    style::dim(&format!("Run #{} printed nothing", run.id))
+                ),
+            }
         }
 
         ScriptCommands::Remove { name } => {
             if db.remove_script(&name).await? {
-                println!("✓ Script '{}' removed", name);
+                noteln!("✓ Script '{}' removed", name);
             } else {
-                println!("✗ Script '{}' not found", name);
+                outln!("✗ Script '{}' not found", name);
             }
         }
     }
@@ -182,41 +573,245 @@ pub async fn handle(command: ScriptCommands, db: &Database) -> anyhow::Result<()
     Ok(())
 }
 
+/// Print a command, with line numbers if it spans several lines
+fn print_command(command: &str) {
+    if script_body::is_multiline(command) {
+        outln!("  Command:");
+        for line in script_body::numbered_lines(command) {
+            outln!("    {}", line);
+        }
+    } else {
+        outln!("  Command: {}", command);
+    }
+}
+
+/// Print the working directory and variables of a local script; values of
+/// secret-looking names are hidden
+fn print_environment(script: &Script) {
+    if let Some(dir) = &script.working_dir {
+        outln!("  Workdir: {}", dir);
+    }
+    if !script.env.is_empty() {
+        outln!("  Env:");
+        for (key, value) in &script.env {
+            let value = if redact::is_secret_key(key) {
+                redact::REDACTED
+            } else {
+                value
+            };
+            outln!("    {}={}", key, value);
+        }
+    }
+}
+
+/// Print the run window and whether it's open now
+fn print_run_window(script: &Script) {
+    let Some(expr) = &script.run_window else {
+        return;
+    };
+    let now = Utc::now();
+    let state = match expr.parse::<RunWindow>() {
+        Err(e) => style::error_text(&format!("invalid: {}", e)),
+        Ok(_) if !script.dangerous => style::dim("not enforced, the script isn't dangerous"),
+        Ok(window) if window.is_open(now, &Local) => style::success_text("open now"),
+        Ok(window) => match window.next_open(now, &Local) {
+            Some(next) => style::warning_text(&format!(
+                "closed, opens {}",
+                window.local_text(next, &Local)
+            )),
+            None => style::warning_text("closed"),
+        },
+    };
+    outln!("  Window:  {} ({})", expr, state);
+}
+
+/// Apply `script edit --run-window/--no-run-window`; the window is stored
+/// in its canonical form
+async fn edit_run_window(
+    db: &Database,
+    mut script: Script,
+    run_window: Option<String>,
+) -> anyhow::Result<Script> {
+    script.run_window = match run_window {
+        Some(expr) => {
+            let window: RunWindow = expr
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid --run-window: {}", e))?;
+            Some(window.to_string())
+        }
+        None => None,
+    };
+    db.update_script(&script).await?;
+    match &script.run_window {
+        Some(window) => noteln!("✓ '{}' may only run {}", script.name, window),
+        None => noteln!("✓ '{}' may run at any time", script.name),
+    }
+    if script.run_window.is_some() && !script.dangerous {
+        noteln!(
+            "{}",
+            style::warning_text("Only dangerous scripts are held to their run window")
+        );
+    }
+    Ok(script)
+}
+
+/// Apply `script edit --server`
+async fn edit_server(db: &Database, script: Script, server: &str) -> anyhow::Result<Script> {
+    let server = find_server(db, server).await?;
+    let mut updated = script.clone();
+    updated.server_id = Some(server.id);
+
+    let changes = diff::diff(&script, &updated);
+    if !changes.is_empty() {
+        db.update_script(&updated).await?;
+    }
+    print_updated("Script", &updated.name, &changes);
+    if updated.script_type != ScriptType::Ssh {
+        noteln!(
+            "{}",
+            style::warning_text("Only ssh scripts run on their server")
+        );
+    }
+    Ok(updated)
+}
+
+/// Apply `script edit --workdir/--no-workdir/--env/--unset-env`
+async fn edit_environment(
+    db: &Database,
+    script: &Script,
+    workdir: Option<String>,
+    no_workdir: bool,
+    env: Vec<(String, String)>,
+    unset_env: Vec<String>,
+) -> anyhow::Result<()> {
+    let mut updated = script.clone();
+    if let Some(dir) = workdir {
+        if !dir.starts_with('~') && !Path::new(&dir).is_absolute() {
+            anyhow::bail!("--workdir must be absolute or start with ~, got '{}'", dir);
+        }
+        updated.working_dir = Some(dir);
+    } else if no_workdir {
+        updated.working_dir = None;
+    }
+    for key in &unset_env {
+        if updated.env.remove(key).is_none() {
+            anyhow::bail!("'{}' isn't set for script '{}'", key, script.name);
+        }
+    }
+    updated.env.extend(env);
+
+    db.update_script(&updated).await?;
+    noteln!("✓ Environment of '{}' updated", script.name);
+    outln!();
+    print_environment(&updated);
+    if updated.working_dir.is_none() && updated.env.is_empty() {
+        outln!(
+            "  {}",
+            style::dim("Runs in the current directory with a clean environment")
+        );
+    }
+    if updated.script_type != ScriptType::Local {
+        noteln!();
+        noteln!(
+            "{}",
+            style::warning_text("Only local scripts use the working directory and variables")
+        );
+    }
+    Ok(())
+}
+
+/// Open $VISUAL/$EDITOR on a template and return the saved body
+fn edit_in_editor(name: &str, body: Option<&str>) -> anyhow::Result<Option<String>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // Script bodies often hold secrets: the file is created exclusively,
+    // readable only by the current user, and deleted when dropped
+    let mut file = tempfile::Builder::new()
+        .prefix("pctrl-script-")
+        .suffix(".sh")
+        .tempfile()?;
+    file.write_all(script_body::editor_template(name, body).as_bytes())?;
+    file.flush()?;
+    let path = file.path().to_path_buf();
+
+    // The editor may come with arguments, e.g. EDITOR="code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+
+    let text = std::fs::read_to_string(&path);
+    drop(file);
+
+    let status =
+        status.map_err(|e| anyhow::anyhow!("Failed to start editor '{}': {}", editor, e))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+
+    Ok(script_body::parse_editor_output(&text?))
+}
+
 /// Result type for script execution: (result, exit_code, output)
 type ExecResult = (pctrl_core::ScriptResult, Option<i32>, Option<String>);
 
-fn execute_local(command: &str) -> ExecResult {
-    let shell = if cfg!(windows) { "cmd" } else { "sh" };
-    let args = if cfg!(windows) {
-        vec!["/C", command]
-    } else {
-        vec!["-c", command]
-    };
+/// Where and with which variables a local script runs. Fails before
+/// anything is started when the working directory is missing.
+fn plan_local(script: &Script, inherit_env: bool) -> anyhow::Result<LocalRun> {
+    let working_dir = script.working_dir.as_deref().map(super::expand_home);
+    // Variables that aren't valid UTF-8 can't be passed on by name
+    let host_env = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+    let run = LocalRun::plan(
+        working_dir.as_deref(),
+        &script.env,
+        inherit_env,
+        host_env,
+        &std::env::current_dir()?,
+    )
+    .map_err(anyhow::Error::msg)?;
+    Ok(run)
+}
+
+/// Run locally: `sh -s` with the script on stdin (`cmd /C` on Windows)
+fn execute_local(run: &LocalRun, command: &str) -> ExecResult {
+    let output = run.command(command).spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Some(payload) = run.shell.stdin(command) {
+                stdin.write_all(payload.as_bytes())?;
+            }
+        }
+        child.wait_with_output()
+    });
 
-    match std::process::Command::new(shell).args(&args).output() {
+    match output {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
 
             if !stdout.is_empty() {
-                println!("{}", stdout);
+                outln!("{}", stdout);
             }
             if !stderr.is_empty() {
-                eprintln!("{}", stderr);
+                eoutln!("{}", stderr);
             }
 
             let combined_output = format!("{}{}", stdout, stderr);
             let exit_code = output.status.code();
 
             if output.status.success() {
-                println!("✓ Script completed successfully");
+                noteln!("✓ Script completed successfully");
                 (
                     pctrl_core::ScriptResult::Success,
                     exit_code,
                     Some(combined_output),
                 )
             } else {
-                println!(
+                outln!(
                     "✗ Script failed with exit code: {}",
                     exit_code.unwrap_or(-1)
                 );
@@ -229,8 +824,68 @@ fn execute_local(command: &str) -> ExecResult {
         }
         Err(e) => {
             let error_msg = format!("Failed to execute: {}", e);
-            println!("✗ {}", error_msg);
+            outln!("✗ {}", error_msg);
             (pctrl_core::ScriptResult::Error, None, Some(error_msg))
         }
     }
 }
+
+/// Run on the script's server over SSH, piping the script to `sh -s`
+async fn execute_ssh(db: &Database, script: &Script) -> anyhow::Result<ExecResult> {
+    let server_ref = script
+        .server_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Script '{}' has no server configured", script.name))?;
+    let server = find_server(db, server_ref).await?;
+    let cred_id = server
+        .credential_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", server.name))?;
+
+    let (ssh_manager, conn_id) =
+        super::server::create_ssh_manager(db, cred_id, &server.host).await?;
+    let body = script.command.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        ssh_manager.execute_script_with_password(&conn_id, &body, None)
+    })
+    .await?;
+
+    Ok(match result {
+        Ok((output, exit_code)) => {
+            if !output.is_empty() {
+                out!("{}", output);
+            }
+            if exit_code == 0 {
+                noteln!("✓ Script completed successfully");
+                (
+                    pctrl_core::ScriptResult::Success,
+                    Some(exit_code),
+                    Some(output),
+                )
+            } else {
+                outln!("✗ Script failed with exit code: {}", exit_code);
+                (
+                    pctrl_core::ScriptResult::Error,
+                    Some(exit_code),
+                    Some(output),
+                )
+            }
+        }
+        Err(e) => {
+            let error_msg = format!("Failed to execute: {}", e);
+            outln!("✗ {}", error_msg);
+            (pctrl_core::ScriptResult::Error, None, Some(error_msg))
+        }
+    })
+}
+
+/// How long a run took, e.g. "2m 5s"; "-" when the timestamps don't parse
+fn run_duration(run: &ScriptRun) -> String {
+    let parse = |ts: &str| DateTime::parse_from_rfc3339(ts).ok();
+    match (parse(&run.started_at), parse(&run.finished_at)) {
+        (Some(start), Some(end)) => humanize::duration(std::time::Duration::from_secs(
+            (end - start).num_seconds().max(0) as u64,
+        )),
+        _ => "-".to_string(),
+    }
+}
@@ -0,0 +1,183 @@
+//! Identity and secret sharing handlers (`pctrl identity`, `pctrl secret`)
+
+use super::resolve::lookup;
+use crate::style;
+use crate::{IdentityCommands, SecretCommands};
+use pctrl_core::EntityType;
+use pctrl_database::envelope::{self, Identity, RecipientKey, SharedSecret};
+use pctrl_database::Database;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub async fn handle_identity(command: IdentityCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        IdentityCommands::Init { force } => {
+            if let Some(existing) = db.get_identity().await? {
+                if !force {
+                    anyhow::bail!(
+                        "This installation already has an identity ({}). Pass --force to replace it; secrets sent to the old key can't be opened afterwards.",
+                        existing.public_key()
+                    );
+                }
+            }
+
+            let identity = Identity::generate();
+            db.save_identity(&identity).await?;
+
+            noteln!("✓ Identity created. Share the public key with senders:");
+            noteln!();
+            outln!("{}", identity.public_key());
+            if !db.is_encrypted() {
+                noteln!();
+                noteln!(
+                    "{}",
+                    style::warning_text(
+                        "⚠ The database has no key, so the private key is stored unencrypted like other credentials."
+                    )
+                );
+            }
+        }
+
+        IdentityCommands::Show => {
+            let identity = load_identity(db).await?;
+            outln!("{}", identity.public_key());
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_secret(command: SecretCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        SecretCommands::Send { name, to, out } => {
+            let recipient: RecipientKey = to.parse()?;
+            let secret = find_secret(db, &name).await?;
+            let sealed = envelope::seal(&secret, &recipient)?;
+
+            let path = out.unwrap_or_else(|| default_path(secret.name()));
+            std::fs::write(&path, sealed)
+                .map_err(|e| anyhow::anyhow!("Can't write {}: {}", path.display(), e))?;
+
+            noteln!(
+                "✓ Encrypted {} '{}' for {}",
+                secret.kind(),
+                secret.name(),
+                recipient
+            );
+            outln!("{}", path.display());
+        }
+
+        SecretCommands::Receive { file, force } => {
+            let identity = load_identity(db).await?;
+            let bytes = std::fs::read(&file)
+                .map_err(|e| anyhow::anyhow!("Can't read {}: {}", file.display(), e))?;
+            let secret = envelope::open(&bytes, &identity)?;
+            receive(db, secret, force).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_identity(db: &Database) -> anyhow::Result<Identity> {
+    db.get_identity().await?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "This installation has no identity yet. Create one with: pctrl identity init"
+        )
+    })
+}
+
+/// A database, else a credential, by ref, ID or name
+async fn find_secret(db: &Database, name: &str) -> anyhow::Result<SharedSecret> {
+    if let Some(found) = lookup(db, Some(EntityType::Database), name).await? {
+        if let Some(database) = db.get_database_credentials(&found.id).await? {
+            return Ok(SharedSecret::Database(database));
+        }
+    }
+    if let Some(found) = lookup(db, Some(EntityType::Credential), name).await? {
+        if let Some(credential) = db.get_credential(&found.id).await? {
+            return Ok(SharedSecret::Credential(credential));
+        }
+    }
+    anyhow::bail!("No database or credential named '{}'", name)
+}
+
+/// `<name>.pctrlsecret` in the current directory
+fn default_path(name: &str) -> PathBuf {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    Path::new(&stem).with_extension(envelope::EXTENSION)
+}
+
+/// Import a received entity. An entry with the same name is only replaced
+/// after confirmation and keeps its ID; new entries get a local ID.
+async fn receive(db: &Database, secret: SharedSecret, force: bool) -> anyhow::Result<()> {
+    match secret {
+        SharedSecret::Credential(mut credential) => {
+            let existing = db.get_credential_by_name(&credential.name).await?;
+            if existing.is_some() && !force {
+                confirm_overwrite("Credential", &credential.name)?;
+            }
+            credential.id = existing
+                .map(|c| c.id)
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            db.save_credential(&credential).await?;
+            noteln!("✓ Credential '{}' imported", credential.name);
+        }
+
+        SharedSecret::Database(mut database) => {
+            let existing = db.get_database_credentials_by_name(&database.name).await?;
+            if existing.is_some() && !force {
+                confirm_overwrite("Database", &database.name)?;
+            }
+            database.id = existing
+                .map(|d| d.id)
+                .unwrap_or_else(|| database.name.to_lowercase().replace(' ', "-"));
+
+            // Server and container belong to the sender's inventory
+            if let Some(server) = database.server_id.clone() {
+                if !db.server_exists(&server).await? {
+                    noteln!(
+                        "{}",
+                        style::dim(&format!(
+                            "Server '{}' is unknown here; the database isn't linked to a server",
+                            server
+                        ))
+                    );
+                    database.server_id = None;
+                    database.container_id = None;
+                }
+            }
+
+            db.save_database_credentials(&database).await?;
+            noteln!("✓ Database '{}' imported", database.name);
+        }
+    }
+    Ok(())
+}
+
+fn confirm_overwrite(kind: &str, name: &str) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{} '{}' already exists; pass --force to overwrite it",
+            kind,
+            name
+        );
+    }
+
+    out!("{} '{}' already exists. Overwrite? [y/N] ", kind, name);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("Aborted");
+    }
+    Ok(())
+}
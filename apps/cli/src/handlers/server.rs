@@ -1,9 +1,48 @@
 //! Server command handler
 
-use crate::ServerCommands;
-use pctrl_core::{AuthMethod, CredentialData, Server, ServerSpecs, ServerType, SshConnection};
+use super::audit::{clearable, history_view, print_ensured, print_updated};
+use super::columns;
+use super::compose;
+use super::docker;
+use super::guard::confirm_live;
+use super::hints;
+use super::journal;
+use super::references::{guard_remove, handle_deps};
+use super::resolve::{find_credential, find_server, name_taken, ref_tag};
+use super::ship::SshExecutor;
+use super::vpn::vpn_blocked;
+use super::CommandFailed;
+use crate::{style, ServerCommands};
+use chrono::{DateTime, Utc};
+use crossterm::terminal;
+use pctrl_core::deploy_key;
+use pctrl_core::diff;
+use pctrl_core::discovery;
+use pctrl_core::facts::{self, FactQuery};
+use pctrl_core::forecast::{self, DiskForecast, Trend};
+use pctrl_core::hints::{Event, Failure, Listing};
+use pctrl_core::settings;
+use pctrl_core::shell;
+use pctrl_core::transfer::{Progress, Verified};
+use pctrl_core::{
+    humanize, quota, Container, ContainerStatus, CredentialData, EntityType, ResourceType, Server,
+    ServerFact, ServerPatch, ServerSpecs, ServerType, SshConnection,
+};
 use pctrl_database::Database;
-use pctrl_ssh::SshManager;
+use pctrl_docker::DockerManager;
+use pctrl_providers::{HetznerClient, MatchKind, Provider};
+use pctrl_ssh::{
+    ConnectOptions, OnProgress, PtyRequest, ShellInput, SshManager, TransferOptions, TransferReport,
+};
+use std::io::{self, IsTerminal, Read};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// How often the transfer progress line is redrawn
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Server status information from SSH
 #[derive(Default)]
@@ -12,53 +51,105 @@ struct ServerStatus {
     load: Option<String>,
     memory: Option<String>,
     disk: Option<String>,
+    disk_percent: Option<f64>,
+}
+
+/// One server of `server list`, without the icon
+fn list_line(server: &Server, refs: &std::collections::HashMap<String, String>) -> String {
+    let provider_str = server
+        .provider
+        .as_ref()
+        .map(|p| format!(" ({})", p))
+        .unwrap_or_default();
+    let specs_str = server
+        .specs
+        .as_ref()
+        .map(|s| {
+            format!(
+                " [{} CPU, {} GB RAM, {} GB]",
+                s.cpu_cores.map(|c| c.to_string()).unwrap_or("?".into()),
+                s.ram_gb.map(|r| r.to_string()).unwrap_or("?".into()),
+                s.disk_gb.map(|d| d.to_string()).unwrap_or("?".into())
+            )
+        })
+        .unwrap_or_default();
+    let cred_str = server
+        .credential_id
+        .as_ref()
+        .map(|c| format!(" [🔑 {}]", c))
+        .unwrap_or_default();
+    let vpn_str = server
+        .requires_vpn
+        .as_ref()
+        .map(|v| format!(" [vpn {}]", v))
+        .unwrap_or_default();
+    format!(
+        "{}{} - {} [{}]{}{}{}{}",
+        server.name,
+        ref_tag(refs, &server.id),
+        server.host,
+        server.server_type,
+        provider_str,
+        specs_str,
+        cred_str,
+        vpn_str
+    )
 }
 
 pub async fn handle(command: ServerCommands, db: &Database) -> anyhow::Result<()> {
     match command {
-        ServerCommands::List => {
-            let servers = db.list_servers().await?;
+        ServerCommands::List { trashed: true, .. } => {
+            let servers = db.list_trashed_servers().await?;
             if servers.is_empty() {
-                println!("No servers configured.");
-                println!();
-                println!("Add one with:");
-                println!(
-                    "  pctrl server add <name> <host> [-t type] [-p provider] [-c credential]"
-                );
+                outln!("Trash is empty.");
             } else {
-                println!("Servers ({}):", servers.len());
-                println!();
+                outln!("Trashed servers ({}):", servers.len());
+                outln!();
                 for server in servers {
-                    let provider_str = server
-                        .provider
-                        .map(|p| format!(" ({})", p))
-                        .unwrap_or_default();
-                    let specs_str = server
-                        .specs
-                        .map(|s| {
-                            format!(
-                                " [{} CPU, {} GB RAM, {} GB]",
-                                s.cpu_cores.map(|c| c.to_string()).unwrap_or("?".into()),
-                                s.ram_gb.map(|r| r.to_string()).unwrap_or("?".into()),
-                                s.disk_gb.map(|d| d.to_string()).unwrap_or("?".into())
-                            )
-                        })
-                        .unwrap_or_default();
-                    let cred_str = server
-                        .credential_id
-                        .as_ref()
-                        .map(|c| format!(" [🔑 {}]", c))
-                        .unwrap_or_default();
-                    println!(
-                        "  🖥️  {} - {} [{}]{}{}{}",
-                        server.name,
-                        server.host,
-                        server.server_type,
-                        provider_str,
-                        specs_str,
-                        cred_str
-                    );
+                    outln!("  🗑️  {} - {} ({})", server.name, server.host, server.id);
+                }
+                hints::show(db, Event::TrashListed).await?;
+            }
+        }
+
+        ServerCommands::List {
+            trashed: false,
+            columns,
+            filter,
+            sort,
+        } => {
+            let mut servers = db.list_servers().await?;
+            if servers.is_empty() {
+                outln!("No servers configured.");
+                hints::show(db, Event::Empty(Listing::Servers)).await?;
+                return Ok(());
+            }
+            let Some(query) = columns::server_query(db, &columns, &filter, &sort).await? else {
+                outln!("Servers ({}):", servers.len());
+                outln!();
+                let refs = db.short_refs(EntityType::Server).await?;
+                for server in &servers {
+                    outln!("  🖥️  {}", list_line(server, &refs));
                 }
+                return Ok(());
+            };
+
+            let rows = query.apply(columns::server_rows(db, &servers, &query).await?);
+            if rows.is_empty() {
+                outln!("No servers match.");
+                return Ok(());
+            }
+            outln!("Servers ({}):", rows.len());
+            outln!();
+            if query.columns.is_empty() {
+                let refs = db.short_refs(EntityType::Server).await?;
+                for row in &rows {
+                    if let Some(i) = servers.iter().position(|s| s.id == row.id) {
+                        outln!("  🖥️  {}", list_line(&servers.swap_remove(i), &refs));
+                    }
+                }
+            } else {
+                columns::print_table(&query, &rows);
             }
         }
 
@@ -69,32 +160,33 @@ pub async fn handle(command: ServerCommands, db: &Database) -> anyhow::Result<()
             provider,
             credential,
             location,
+            ensure,
         } => {
-            let id = name.to_lowercase().replace(' ', "-");
-
-            if db.get_server_by_name(&name).await?.is_some() {
+            let existing = db.get_server_by_name(&name).await?;
+            if existing.is_some() && !ensure {
                 anyhow::bail!("Server '{}' already exists.", name);
             }
+            let id = existing
+                .map(|s| s.id)
+                .unwrap_or_else(|| name.to_lowercase().replace(' ', "-"));
 
-            let server_type: ServerType = server_type.parse().unwrap_or_default();
+            let patched_type: Option<ServerType> =
+                server_type.map(|t| t.parse().unwrap_or_default());
+            let server_type = patched_type.clone().unwrap_or_default();
 
             // Resolve credential name to ID and auto-detect specs
             let (resolved_credential_id, specs): (Option<String>, Option<ServerSpecs>) =
                 if let Some(ref cred_input) = credential {
                     // Look up credential by name or ID
-                    let cred = db
-                        .get_credential_by_name(cred_input)
-                        .await?
-                        .or(db.get_credential(cred_input).await?)
-                        .ok_or_else(|| anyhow::anyhow!("Credential '{}' not found", cred_input))?;
+                    let cred = find_credential(db, cred_input).await?;
 
                     let cred_id = cred.id.clone();
 
                     // Auto-detect specs via SSH
-                    println!("🔍 Detecting server specs via SSH...");
+                    noteln!("🔍 Detecting server specs via SSH...");
                     let specs = match detect_specs_via_credential(db, &cred_id, &host).await {
                         Ok(specs) => {
-                            println!(
+                            outln!(
                                 "  ✓ Detected: {} CPU cores, {} GB RAM, {} GB disk",
                                 specs.cpu_cores.map(|c| c.to_string()).unwrap_or("?".into()),
                                 specs.ram_gb.map(|r| r.to_string()).unwrap_or("?".into()),
@@ -103,7 +195,7 @@ pub async fn handle(command: ServerCommands, db: &Database) -> anyhow::Result<()
                             Some(specs)
                         }
                         Err(e) => {
-                            println!("  ⚠ Could not detect specs: {}", e);
+                            outln!("  ⚠ Could not detect specs: {}", e);
                             None
                         }
                     };
@@ -119,95 +211,576 @@ pub async fn handle(command: ServerCommands, db: &Database) -> anyhow::Result<()
                 host: host.clone(),
                 server_type: server_type.clone(),
                 provider: provider.clone(),
-                credential_id: resolved_credential_id,
-                location,
-                specs,
+                credential_id: resolved_credential_id.clone(),
+                location: location.clone(),
+                specs: specs.clone(),
                 notes: None,
+                requires_vpn: None,
+                max_containers: None,
+                max_memory_mb_allocated: None,
             };
 
-            db.save_server(&server).await?;
+            if ensure {
+                let patch = ServerPatch {
+                    host: Some(host),
+                    server_type: patched_type,
+                    provider,
+                    credential_id: resolved_credential_id,
+                    location,
+                    specs,
+                };
+                let ensured = db.ensure_server(&server, &patch).await?;
+                print_ensured("Server", &name, &ensured);
+                return Ok(());
+            }
+
+            db.create_server(&server)
+                .await
+                .map_err(|e| name_taken("Server", e))?;
 
-            println!("✓ Server added:");
-            println!();
-            println!("  Name:       {}", name);
-            println!("  ID:         {}", id);
-            println!("  Host:       {}", host);
-            println!("  Type:       {}", server_type);
+            noteln!("✓ Server added:");
+            noteln!();
+            noteln!("  Name:       {}", name);
+            noteln!("  ID:         {}", id);
+            noteln!("  Host:       {}", host);
+            noteln!("  Type:       {}", server_type);
             if let Some(p) = provider {
-                println!("  Provider:   {}", p);
+                noteln!("  Provider:   {}", p);
             }
-            if let Some(c) = credential {
-                println!("  Credential: {}", c);
+            if let Some(c) = &credential {
+                noteln!("  Credential: {}", c);
             }
+            hints::show(
+                db,
+                Event::ServerAdded {
+                    name,
+                    host,
+                    credential,
+                    specs: server.specs.is_some(),
+                },
+            )
+            .await?;
         }
 
         ServerCommands::Show { name } => {
-            let server = db
-                .get_server_by_name(&name)
-                .await?
-                .or(db.get_server(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
-
-            println!();
-            println!("  🖥️  {}", server.name);
-            println!("  ─────────────────────────────");
-            println!("  ID:         {}", server.id);
-            println!("  Host:       {}", server.host);
-            println!("  Type:       {}", server.server_type);
+            let server = find_server(db, &name).await?;
+
+            outln!();
+            outln!("  🖥️  {}", server.name);
+            outln!("  ─────────────────────────────");
+            outln!("  ID:         {}", server.id);
+            if let Some(short_ref) = db.short_ref(EntityType::Server, &server.id).await? {
+                outln!("  Ref:        {}", short_ref);
+            }
+            outln!("  Host:       {}", server.host);
+            outln!("  Type:       {}", server.server_type);
             if let Some(p) = &server.provider {
-                println!("  Provider:   {}", p);
+                outln!("  Provider:   {}", p);
             }
             if let Some(l) = &server.location {
-                println!("  Location:   {}", l);
+                outln!("  Location:   {}", l);
             }
             if let Some(cred) = &server.credential_id {
-                println!("  Credential: {}", cred);
+                outln!("  Credential: {}", cred);
+            }
+            if let Some(interface) = &server.requires_vpn {
+                let state = match vpn_blocked(&server).await {
+                    Some(_) => style::warning_text("not connected"),
+                    None => style::dim("reachable only through it"),
+                };
+                outln!("  VPN:        {} ({})", interface, state);
+            }
+            let server_facts = facts::to_map(&db.list_server_facts(&server.id).await?);
+            if let Some(os) = facts::os_summary(&server_facts) {
+                outln!("  OS:         {}", os);
+            }
+            if let Some(runtime) = facts::container_runtime(&server_facts) {
+                outln!("  Containers: {}", runtime);
+            }
+            let containers = db.list_containers_for_server(&server.id).await?;
+            let quotas =
+                server.max_containers.is_some() || server.max_memory_mb_allocated.is_some();
+            if quotas || !containers.is_empty() {
+                let allocation =
+                    quota::allocation(containers.iter().filter(|c| quota::is_running(c)));
+                let text = quota::describe(&server, &allocation);
+                let text = if quota::usage(&server, &allocation).iter().any(|u| u.near()) {
+                    style::warning_text(&text)
+                } else {
+                    text
+                };
+                outln!("  Allocated:  {}", text);
+            }
+            if let Some(entry) = db
+                .latest_journal_entry(EntityType::Server, &server.id)
+                .await?
+            {
+                outln!("  Journal:    {}", journal::summary(&entry));
+            }
+            let stacks = db.list_compose_projects(Some(&server.id)).await?;
+            if !stacks.is_empty() {
+                outln!();
+                outln!("  Compose:");
+                for line in compose::stack_lines(&stacks) {
+                    outln!("    {}", line.text);
+                }
             }
             if let Some(specs) = &server.specs {
-                println!();
-                println!("  Specs:");
+                outln!();
+                outln!("  Specs:");
                 if let Some(cpu) = specs.cpu_cores {
-                    println!("    CPU:    {} cores", cpu);
+                    outln!("    CPU:    {} cores", cpu);
                 }
                 if let Some(ram) = specs.ram_gb {
-                    println!("    RAM:    {} GB", ram);
+                    outln!("    RAM:    {} GB", ram);
                 }
                 if let Some(disk) = specs.disk_gb {
-                    println!("    Disk:   {} GB", disk);
+                    outln!("    Disk:   {} GB", disk);
                 }
             }
-            println!();
+            outln!();
         }
 
-        ServerCommands::Remove { name } => {
-            let server = db
-                .get_server_by_name(&name)
-                .await?
-                .or(db.get_server(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
+        ServerCommands::Edit {
+            name,
+            host,
+            provider,
+            location,
+            notes,
+            requires_vpn,
+            no_vpn,
+            max_containers,
+            max_memory_mb,
+            no_quotas,
+        } => {
+            let before = find_server(db, &name).await?;
+            let mut server = before.clone();
+
+            let details_changed =
+                host.is_some() || provider.is_some() || location.is_some() || notes.is_some();
+            let vpn_changed = requires_vpn.is_some() || no_vpn;
+            let quotas_changed = max_containers.is_some() || max_memory_mb.is_some() || no_quotas;
+            if !details_changed && !vpn_changed && !quotas_changed {
+                anyhow::bail!("Nothing to change; see pctrl server edit --help");
+            }
+            if let Some(host) = host {
+                let host = host.trim();
+                if host.is_empty() {
+                    anyhow::bail!("The host is empty");
+                }
+                server.host = host.to_string();
+            }
+            if let Some(provider) = provider {
+                server.provider = clearable(provider);
+            }
+            if let Some(location) = location {
+                server.location = clearable(location);
+            }
+            if let Some(notes) = notes {
+                server.notes = clearable(notes);
+            }
+            match (requires_vpn, no_vpn) {
+                (Some(interface), _) => {
+                    let interface = interface.trim();
+                    if interface.is_empty() {
+                        anyhow::bail!("The VPN interface name is empty; use --no-vpn to clear it");
+                    }
+                    server.requires_vpn = Some(interface.to_string());
+                }
+                (None, true) => server.requires_vpn = None,
+                (None, false) => {}
+            }
+            if no_quotas {
+                server.max_containers = None;
+                server.max_memory_mb_allocated = None;
+            }
+            if let Some(max) = max_containers {
+                server.max_containers = Some(max);
+            }
+            if let Some(max) = max_memory_mb {
+                server.max_memory_mb_allocated = Some(max);
+            }
+
+            let changes = diff::diff(&before, &server);
+            if !changes.is_empty() {
+                db.update_server(&server).await?;
+            }
+            print_updated("Server", &server.name, &changes);
+            if quotas_changed && !changes.is_empty() {
+                let running = db.list_containers_for_server(&server.id).await?;
+                let allocation = quota::allocation(running.iter().filter(|c| quota::is_running(c)));
+                outln!();
+                outln!("  Quotas: {}", quota::describe(&server, &allocation));
+            }
+        }
+
+        ServerCommands::Remove { name, force } => {
+            let server = find_server(db, &name).await?;
 
+            guard_remove(db, EntityType::Server, &server.id, &server.name, force).await?;
             if db.remove_server(&server.id).await? {
-                println!("✓ Server '{}' removed", server.name);
+                noteln!("✓ Server '{}' removed", server.name);
             }
         }
 
-        ServerCommands::Exec { name, command } => {
-            let server = db
-                .get_server_by_name(&name)
-                .await?
-                .or(db.get_server(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
+        ServerCommands::HistoryView { name, at, timeline } => {
+            history_view(db, EntityType::Server, &name, at, timeline).await?;
+        }
+
+        ServerCommands::Deps { name, json } => {
+            let server = find_server(db, &name).await?;
+
+            handle_deps(db, EntityType::Server, &server.id, &server.name, json).await?;
+        }
+
+        ServerCommands::Log {
+            name,
+            text,
+            show,
+            limit,
+            amend,
+        } => {
+            let server = find_server(db, &name).await?;
+
+            if show {
+                journal::show(db, EntityType::Server, &server.id, &server.name, limit).await?;
+            } else {
+                let text = text.unwrap_or_default();
+                journal::append(
+                    db,
+                    EntityType::Server,
+                    &server.id,
+                    &server.name,
+                    &text,
+                    amend,
+                )
+                .await?;
+            }
+        }
+
+        ServerCommands::Forecast { name } => {
+            let server = find_server(db, &name).await?;
+
+            outln!();
+            outln!("  💾 Disk forecast: {}", server.name);
+            outln!("  ─────────────────────────────");
+
+            let now = Utc::now();
+            let Some(forecast) = disk_forecast(db, &server.id, now).await? else {
+                outln!("  No disk samples yet.");
+                outln!();
+                outln!(
+                    "  Samples are recorded by: pctrl server status {}",
+                    server.name
+                );
+                outln!();
+                return Ok(());
+            };
+
+            outln!("  Current: {:.1}%", forecast.current);
+            match forecast.trend {
+                Trend::InsufficientData {
+                    samples,
+                    span_hours,
+                } => {
+                    outln!();
+                    outln!(
+                        "  ⚠ Not enough history for a forecast: {} over {} (need {} over {} days)",
+                        humanize::count(samples as u64, "sample", "samples"),
+                        humanize::duration(std::time::Duration::from_secs(
+                            span_hours.max(0) as u64 * 3600
+                        )),
+                        forecast::MIN_SAMPLES,
+                        forecast::MIN_SPAN_HOURS / 24
+                    );
+                }
+                Trend::Flat => {
+                    outln!("  Growth:  {:+.2}% per week (flat)", forecast.per_week);
+                    outln!();
+                    outln!("  No fill date projected.");
+                }
+                Trend::Shrinking => {
+                    outln!("  Growth:  {:+.2}% per week (shrinking)", forecast.per_week);
+                    outln!();
+                    outln!("  No fill date projected.");
+                }
+                Trend::Growing => {
+                    outln!("  Growth:  {:+.2}% per week", forecast.per_week);
+                    print_projection("90%", forecast.reaches_90);
+                    print_projection("100%", forecast.reaches_100);
+                    if forecast.full_within(forecast::WARN_DAYS, now) {
+                        outln!();
+                        outln!("  ⚠ Disk full in < {} days", forecast::WARN_DAYS);
+                    }
+                }
+            }
+            outln!();
+            outln!("  Linear trend over past samples; bursts and cleanups are not predicted.");
+            outln!();
+        }
+
+        ServerCommands::Facts {
+            query: Some(query), ..
+        } => {
+            let query: FactQuery = query.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            let servers = db.list_servers().await?;
+            let stored = db.list_all_server_facts().await?;
+
+            let matching: Vec<(&Server, String)> = servers
+                .iter()
+                .filter_map(|server| {
+                    let rows: Vec<ServerFact> = stored
+                        .iter()
+                        .filter(|f| f.server_id == server.id)
+                        .cloned()
+                        .collect();
+                    let server_facts = facts::to_map(&rows);
+                    query
+                        .matches(&server_facts)
+                        .then(|| (server, server_facts[&query.key].clone()))
+                })
+                .collect();
+
+            if matching.is_empty() {
+                outln!("No server has {}={}.", query.key, query.value);
+                let collected = servers
+                    .iter()
+                    .filter(|s| stored.iter().any(|f| f.server_id == s.id))
+                    .count();
+                if collected < servers.len() {
+                    outln!(
+                        "  {}",
+                        style::dim(&format!(
+                            "Facts were collected for {} of {} (pctrl server facts <name> --refresh)",
+                            collected,
+                            humanize::count(servers.len() as u64, "server", "servers")
+                        ))
+                    );
+                }
+            } else {
+                outln!(
+                    "Servers with {}={} ({}):",
+                    query.key,
+                    query.value,
+                    matching.len()
+                );
+                outln!();
+                for (server, value) in matching {
+                    outln!("  🖥️  {} - {} ({})", server.name, server.host, value);
+                }
+            }
+        }
+
+        ServerCommands::Facts { name, refresh, .. } => {
+            let name = name.unwrap_or_default();
+            let server = find_server(db, &name).await?;
+
+            let mut stored = db.list_server_facts(&server.id).await?;
+            if refresh || stored.is_empty() {
+                noteln!("🔍 Collecting facts via SSH...");
+                let collected = collect_facts(db, &server).await?;
+                db.replace_server_facts(&server.id, &collected).await?;
+                stored = db.list_server_facts(&server.id).await?;
+            }
+            let server_facts = facts::to_map(&stored);
+
+            outln!();
+            outln!("  🖥️  Facts: {}", server.name);
+            outln!("  ─────────────────────────────");
+            if server_facts.is_empty() {
+                outln!("  No facts reported.");
+            }
+            let width = server_facts.keys().map(|k| k.len()).max().unwrap_or(0);
+            for (key, value) in &server_facts {
+                outln!("  {:<width$}  {}", key, value, width = width);
+            }
+            if let Some(os) = facts::os_summary(&server_facts) {
+                outln!();
+                outln!("  OS:         {}", os);
+            }
+            if let Some(runtime) = facts::container_runtime(&server_facts) {
+                outln!("  Containers: {}", runtime);
+            }
+            let containers = db.list_containers_for_server(&server.id).await?;
+            let quotas =
+                server.max_containers.is_some() || server.max_memory_mb_allocated.is_some();
+            if quotas || !containers.is_empty() {
+                let allocation =
+                    quota::allocation(containers.iter().filter(|c| quota::is_running(c)));
+                let text = quota::describe(&server, &allocation);
+                let text = if quota::usage(&server, &allocation).iter().any(|u| u.near()) {
+                    style::warning_text(&text)
+                } else {
+                    text
+                };
+                outln!("  Allocated:  {}", text);
+            }
+            if let Some(fact) = stored.first() {
+                outln!();
+                outln!(
+                    "  {}",
+                    style::dim(&format!(
+                        "Collected {} (refresh with --refresh)",
+                        humanize::relative_timestamp(&fact.collected_at)
+                    ))
+                );
+            }
+            outln!();
+        }
+
+        ServerCommands::Discover { name, prune } => {
+            let server = find_server(db, &name).await?;
+            discover(db, &server, prune).await?;
+        }
+
+        ServerCommands::Compose { name, prune } => {
+            let server = find_server(db, &name).await?;
+            record_discovery(db, &server, prune).await?;
+            let stacks = db.list_compose_projects(Some(&server.id)).await?;
+            if stacks.is_empty() {
+                outln!("No containers on '{}'.", server.name);
+                return Ok(());
+            }
+            outln!();
+            for line in compose::stack_lines(&stacks) {
+                outln!("  {}", line.text);
+            }
+            outln!();
+            noteln!(
+                "✓ {} on '{}'",
+                humanize::count(
+                    stacks.iter().filter(|s| !s.is_standalone()).count() as u64,
+                    "compose stack",
+                    "compose stacks"
+                ),
+                server.name
+            );
+        }
+
+        ServerCommands::Meta {
+            name,
+            entries,
+            unset,
+        } => {
+            let server = find_server(db, &name).await?;
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    entry
+                        .split_once('=')
+                        .map(|(k, v)| (k.trim(), v.trim()))
+                        .ok_or_else(|| anyhow::anyhow!("Expected key=value, got '{}'", entry))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            for (key, value) in &entries {
+                db.set_entity_metadata(EntityType::Server, &server.id, key, value)
+                    .await?;
+                noteln!("✓ {}: {} = {}", server.name, key, value);
+            }
+            for key in &unset {
+                if db
+                    .remove_entity_metadata(EntityType::Server, &server.id, key)
+                    .await?
+                {
+                    noteln!("✓ {}: {} removed", server.name, key);
+                } else {
+                    noteln!("{}", style::dim(&format!("{} has no {}", server.name, key)));
+                }
+            }
+            if !entries.is_empty() || !unset.is_empty() {
+                return Ok(());
+            }
+
+            let meta = db
+                .list_entity_metadata(EntityType::Server, &server.id)
+                .await?;
+            if meta.is_empty() {
+                outln!("No metadata for server '{}'.", server.name);
+                noteln!(
+                    "{}",
+                    style::dim(&format!(
+                        "Add some with: pctrl server meta {} owner=alice",
+                        shell::quote_word(&server.name)
+                    ))
+                );
+                return Ok(());
+            }
+            let width = meta.keys().map(|k| k.len()).max().unwrap_or(0);
+            for (key, value) in &meta {
+                outln!("  {:<width$}  {}", key, value, width = width);
+            }
+        }
+
+        ServerCommands::Restore { name } => match db.restore_server(&name).await? {
+            Some(server) => noteln!("✓ Server '{}' restored", server.name),
+            None => outln!("✗ No trashed server '{}'", name),
+        },
+
+        ServerCommands::Reconcile {
+            provider,
+            credential,
+            import,
+            trash,
+            refresh,
+        } => {
+            reconcile(db, &provider, &credential, import, trash, refresh).await?;
+        }
+
+        ServerCommands::Exec {
+            name,
+            command,
+            allow_live,
+            forward_agent,
+            with_deploy_key,
+        } => {
+            let server = find_server(db, &name).await?;
+            if let Some(reason) = vpn_blocked(&server).await {
+                anyhow::bail!(
+                    "{}; server '{}' is only reachable through it",
+                    reason,
+                    server.name
+                );
+            }
+
+            let live = db
+                .live_projects_for_resource(&ResourceType::Server, &[&server.id, &server.name])
+                .await?;
+            confirm_live(
+                &live,
+                &format!("Command on server '{}'", server.name),
+                allow_live,
+            )?;
 
             let cred_id = server
                 .credential_id
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", name))?;
 
-            println!("🔌 Connecting to {}...", server.host);
+            noteln!("🔌 Connecting to {}...", server.host);
             let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
 
-            println!("▶ Executing: {}", command);
-            println!();
+            noteln!("▶ Executing: {}", command);
+            outln!();
+
+            if forward_agent {
+                let exit_code = tokio::task::spawn_blocking(move || {
+                    ssh_manager.execute_system(&conn_id, &command, true)
+                })
+                .await??;
+                if exit_code != 0 {
+                    return Err(CommandFailed::new(
+                        exit_code,
+                        format!("Command exited with {}", exit_code),
+                    )
+                    .into());
+                }
+                return Ok(());
+            }
+            if let Some(key_cred) = with_deploy_key {
+                return exec_with_deploy_key(db, &server, ssh_manager, conn_id, &key_cred, command)
+                    .await;
+            }
 
             let output = tokio::task::spawn_blocking(move || {
                 ssh_manager.execute_command(&conn_id, &command)
@@ -215,137 +788,484 @@ pub async fn handle(command: ServerCommands, db: &Database) -> anyhow::Result<()
             .await??;
 
             // Print output
-            print!("{}", output);
+            out!("{}", output);
+        }
+
+        ServerCommands::Shell { name, allow_live } => {
+            let server = find_server(db, &name).await?;
+            if let Some(reason) = vpn_blocked(&server).await {
+                anyhow::bail!(
+                    "{}; server '{}' is only reachable through it",
+                    reason,
+                    server.name
+                );
+            }
+            if !io::stdin().is_terminal() {
+                anyhow::bail!("An interactive shell needs a terminal; use `server exec` instead");
+            }
+
+            let live = db
+                .live_projects_for_resource(&ResourceType::Server, &[&server.id, &server.name])
+                .await?;
+            confirm_live(
+                &live,
+                &format!("Shell on server '{}'", server.name),
+                allow_live,
+            )?;
+
+            let cred_id = server
+                .credential_id
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", name))?;
+
+            noteln!("🔌 Connecting to {}...", server.host);
+            let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+
+            let exit_code =
+                tokio::task::spawn_blocking(move || run_shell(&ssh_manager, &conn_id)).await??;
+            if exit_code != 0 {
+                return Err(CommandFailed::new(
+                    exit_code,
+                    format!("Shell exited with {}", exit_code),
+                )
+                .into());
+            }
+        }
+
+        ServerCommands::Pull {
+            name,
+            remote,
+            local,
+            limit,
+            fresh,
+            no_checksum,
+        } => {
+            let server = find_server(db, &name).await?;
+            let file_name = Path::new(&remote)
+                .file_name()
+                .ok_or_else(|| anyhow::anyhow!("'{}' names no file", remote))?
+                .to_owned();
+            let local = match local.map(PathBuf::from) {
+                Some(dir) if dir.is_dir() => dir.join(file_name),
+                Some(path) => path,
+                None => PathBuf::from(file_name),
+            };
+            let options = TransferOptions {
+                limit,
+                checksum: !no_checksum,
+                fresh,
+            };
+            let what = format!("⬇ {}:{} → {}", server.name, remote, local.display());
+            let report = sftp_transfer(db, &server, what, move |ssh, conn_id, on_progress| {
+                ssh.download(conn_id, &remote, &local, options, on_progress)
+            })
+            .await?;
+            print_transfer_report(&report);
+        }
+
+        ServerCommands::Push {
+            name,
+            local,
+            remote,
+            limit,
+            fresh,
+            no_checksum,
+        } => {
+            let server = find_server(db, &name).await?;
+            let local = PathBuf::from(local);
+            if !local.is_file() {
+                anyhow::bail!("'{}' is not a file", local.display());
+            }
+            let remote = if remote.ends_with('/') {
+                let file_name = local.file_name().unwrap_or_default().to_string_lossy();
+                format!("{}{}", remote, file_name)
+            } else {
+                remote
+            };
+            let options = TransferOptions {
+                limit,
+                checksum: !no_checksum,
+                fresh,
+            };
+            let what = format!("⬆ {} → {}:{}", local.display(), server.name, remote);
+            let report = sftp_transfer(db, &server, what, move |ssh, conn_id, on_progress| {
+                ssh.upload(conn_id, &local, &remote, options, on_progress)
+            })
+            .await?;
+            print_transfer_report(&report);
         }
 
         ServerCommands::Status { name } => {
-            let server = db
-                .get_server_by_name(&name)
-                .await?
-                .or(db.get_server(&name).await?)
-                .ok_or_else(|| anyhow::anyhow!("Server '{}' not found", name))?;
+            let server = find_server(db, &name).await?;
 
-            println!();
-            println!("  🖥️  {} ({})", server.name, server.host);
-            println!("  ─────────────────────────────");
+            outln!();
+            outln!("  🖥️  {} ({})", server.name, server.host);
+            outln!("  ─────────────────────────────");
+
+            // Not a failure: the server can't be reached until the VPN is up
+            if let Some(reason) = vpn_blocked(&server).await {
+                outln!(
+                    "  Status:  {}",
+                    style::warning_text(&format!("◐ {}", reason))
+                );
+                outln!();
+                return Ok(());
+            }
 
             // Check if credential is configured
             let Some(cred_id) = &server.credential_id else {
-                println!("  ⚠ No credential configured");
-                println!();
+                outln!("  ⚠ No credential configured");
+                hints::show(
+                    db,
+                    Event::StatusWithoutCredential {
+                        name: server.name,
+                        host: server.host,
+                    },
+                )
+                .await?;
+                outln!();
                 return Ok(());
             };
 
             // Try to connect and get status
-            print!("  Connecting... ");
-            match create_ssh_manager(db, cred_id, &server.host).await {
-                Ok((ssh_manager, conn_id)) => {
-                    // Run all status commands in a single blocking task
-                    let status_result = tokio::task::spawn_blocking(move || {
-                        let mut results = ServerStatus::default();
-
-                        // Get uptime
-                        if let Ok(output) =
-                            ssh_manager.execute_command(&conn_id, "uptime -p 2>/dev/null || uptime")
-                        {
-                            results.uptime = Some(output.trim().to_string());
-                        }
+            out!("  Connecting... ");
+            let connected = match create_ssh_manager(db, cred_id, &server.host).await {
+                // Run all status commands in a single blocking task
+                Ok((ssh_manager, conn_id)) => tokio::task::spawn_blocking(move || {
+                    // The commands below fail quietly; an unreachable server must not
+                    // look online
+                    ssh_manager.test_connection(&conn_id, None)?;
+                    let mut results = ServerStatus::default();
 
-                        // Get load average
-                        if let Ok(output) = ssh_manager
-                            .execute_command(&conn_id, "cat /proc/loadavg | cut -d' ' -f1-3")
-                        {
-                            results.load = Some(output.trim().to_string());
-                        }
+                    // Get uptime (seconds since boot)
+                    if let Ok(output) =
+                        ssh_manager.execute_command(&conn_id, "cut -d' ' -f1 /proc/uptime")
+                    {
+                        results.uptime = Some(format_uptime(output.trim()));
+                    }
 
-                        // Get memory info
-                        if let Ok(output) = ssh_manager.execute_command(
-                            &conn_id,
-                            "free -h | grep Mem | awk '{print $3 \"/\" $2}'",
-                        ) {
-                            results.memory = Some(output.trim().to_string());
-                        }
+                    // Get load average
+                    if let Ok(output) =
+                        ssh_manager.execute_command(&conn_id, "cat /proc/loadavg | cut -d' ' -f1-3")
+                    {
+                        results.load = Some(output.trim().to_string());
+                    }
 
-                        // Get disk usage
-                        if let Ok(output) = ssh_manager.execute_command(
-                            &conn_id,
-                            "df -h / | tail -1 | awk '{print $3 \"/\" $2 \" (\" $5 \")\"}'",
-                        ) {
-                            results.disk = Some(output.trim().to_string());
-                        }
+                    // Get memory info
+                    if let Ok(output) = ssh_manager
+                        .execute_command(&conn_id, "free -b | awk '/^Mem:/{print $3, $2}'")
+                    {
+                        results.memory = Some(format_usage(output.trim()));
+                    }
 
-                        results
-                    })
-                    .await?;
+                    // Get disk usage
+                    if let Ok(output) = ssh_manager
+                        .execute_command(&conn_id, "df -B1 / | tail -1 | awk '{print $3, $2, $5}'")
+                    {
+                        results.disk = Some(format_usage(output.trim()));
+                        results.disk_percent = usage_percent(output.trim());
+                    }
 
-                    println!("✓");
+                    Ok::<_, pctrl_core::Error>(results)
+                })
+                .await?
+                .map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+            match connected {
+                Ok(status_result) => {
+                    outln!("✓");
+
+                    // Keep a history for `server forecast`
+                    if let Some(percent) = status_result.disk_percent {
+                        db.record_disk_sample(&server.id, percent).await?;
+                    }
 
                     if let Some(uptime) = &status_result.uptime {
                         if !uptime.is_empty() {
-                            println!("  Uptime:  {}", uptime);
+                            outln!("  Uptime:  {}", uptime);
                         }
                     }
                     if let Some(load) = &status_result.load {
                         if !load.is_empty() {
-                            println!("  Load:    {}", load);
+                            outln!("  Load:    {}", load);
                         }
                     }
                     if let Some(memory) = &status_result.memory {
                         if !memory.is_empty() {
-                            println!("  Memory:  {}", memory);
+                            outln!("  Memory:  {}", memory);
                         }
                     }
                     if let Some(disk) = &status_result.disk {
                         if !disk.is_empty() {
-                            println!("  Disk:    {}", disk);
+                            outln!("  Disk:    {}", disk);
                         }
                     }
 
-                    println!();
-                    println!("  Status:  ✓ Online");
+                    outln!();
+                    outln!("  Status:  ✓ Online");
                 }
                 Err(e) => {
-                    println!("✗");
-                    println!("  Status:  ✗ Offline ({})", e);
+                    outln!("✗");
+                    outln!("  Status:  ✗ Offline ({})", e);
+                    hints::show(
+                        db,
+                        Event::StatusFailed {
+                            name: server.name.clone(),
+                            credential: cred_id.clone(),
+                            vpn: server.requires_vpn.is_some(),
+                            failure: Failure::of(&e.to_string()),
+                        },
+                    )
+                    .await?;
+                }
+            }
+            outln!();
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare stored servers with a provider account and apply the chosen fixes
+async fn reconcile(
+    db: &Database,
+    provider: &str,
+    credential: &str,
+    import: bool,
+    trash: bool,
+    refresh: bool,
+) -> anyhow::Result<()> {
+    let provider: Provider = provider.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+
+    let cred = find_credential(db, credential).await?;
+    let CredentialData::ApiToken { token, .. } = &cred.data else {
+        anyhow::bail!("Credential '{}' is not an API token", cred.name);
+    };
+
+    let instances = match provider {
+        Provider::Hetzner => HetznerClient::new(token).list_servers().await?,
+    };
+    let servers = db.list_servers().await?;
+    let report = pctrl_providers::reconcile(&instances, &servers, &provider.to_string());
+
+    outln!(
+        "Reconciling with {} ({}):",
+        provider,
+        humanize::count(instances.len() as u64, "instance", "instances")
+    );
+
+    outln!();
+    outln!("  Matched ({}):", report.matched.len());
+    for m in &report.matched {
+        let by = match m.by {
+            MatchKind::Ip => "ip",
+            MatchKind::Name => "name",
+        };
+        outln!(
+            "    ✓ {} ↔ {} ({}) [by {}]",
+            m.server.name,
+            m.instance.name,
+            m.instance.public_ipv4.as_deref().unwrap_or("-"),
+            by
+        );
+    }
+
+    outln!();
+    outln!("  Unmanaged ({}):", report.unmanaged.len());
+    for instance in &report.unmanaged {
+        outln!(
+            "    + {} ({}) [{}]",
+            instance.name,
+            instance.public_ipv4.as_deref().unwrap_or("-"),
+            instance.status
+        );
+    }
+
+    outln!();
+    outln!("  Stale ({}):", report.stale.len());
+    for server in &report.stale {
+        outln!("    - {} ({})", server.name, server.host);
+    }
+
+    if refresh {
+        outln!();
+        for m in &report.matched {
+            let specs = m.instance.specs().or(m.server.specs.clone());
+            let location = m.instance.location.clone().or(m.server.location.clone());
+            if specs == m.server.specs && location == m.server.location {
+                continue;
+            }
+            let mut server = m.server.clone();
+            server.specs = specs;
+            server.location = location;
+            db.update_server(&server).await?;
+            outln!("  ↻ Refreshed '{}'", server.name);
+        }
+    }
+
+    if import {
+        outln!();
+        for instance in &report.unmanaged {
+            let server = instance.to_server(&provider.to_string());
+            match db.create_server(&server).await {
+                Err(pctrl_core::Error::Conflict(taken)) => {
+                    outln!(
+                        "  ⚠ Skipped '{}': server '{}' already exists",
+                        instance.name,
+                        taken
+                    );
+                    continue;
                 }
+                created => created?,
             }
-            println!();
+            outln!("  ✓ Imported '{}' ({})", server.name, server.host);
         }
     }
 
+    if trash {
+        outln!();
+        for server in &report.stale {
+            if db.trash_server(&server.id).await? {
+                outln!("  🗑️  Trashed '{}'", server.name);
+            }
+        }
+    }
+
+    let applied = import || trash || refresh;
+    if !applied && (!report.unmanaged.is_empty() || !report.stale.is_empty()) {
+        outln!();
+        outln!("Use --import, --trash or --refresh to apply changes.");
+    }
+
     Ok(())
 }
 
+/// Disk forecast for a server, `None` without samples
+async fn disk_forecast(
+    db: &Database,
+    server_id: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<DiskForecast>> {
+    let samples: Vec<(DateTime<Utc>, f64)> = db
+        .list_disk_samples(server_id)
+        .await?
+        .iter()
+        .filter_map(|s| Some((s.time()?, s.used_percent)))
+        .collect();
+
+    Ok(forecast::forecast(&samples, now))
+}
+
+/// Servers whose disk is projected full within `forecast::WARN_DAYS`, with
+/// the projected date
+pub(crate) async fn disks_filling_up<'a>(
+    db: &Database,
+    servers: &'a [Server],
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<(&'a Server, DateTime<Utc>)>> {
+    let mut filling = Vec::new();
+    for server in servers {
+        let Some(forecast) = disk_forecast(db, &server.id, now).await? else {
+            continue;
+        };
+        if let (true, Some(full)) = (
+            forecast.full_within(forecast::WARN_DAYS, now),
+            forecast.reaches_100,
+        ) {
+            filling.push((server, full));
+        }
+    }
+    Ok(filling)
+}
+
+/// Print "disk full in < 30 days" warnings for the status overview
+pub async fn print_forecast_warnings(db: &Database) -> anyhow::Result<()> {
+    let servers = db.list_servers().await?;
+    for (server, full) in disks_filling_up(db, &servers, Utc::now()).await? {
+        outln!(
+            "  {}",
+            style::warning_text(&format!(
+                "⚠ {}: disk full in < {} days (projected {})",
+                server.name,
+                forecast::WARN_DAYS,
+                humanize::relative(full)
+            ))
+        );
+    }
+    Ok(())
+}
+
+/// Print a projected date line of `server forecast`
+fn print_projection(label: &str, at: Option<DateTime<Utc>>) {
+    match at {
+        Some(at) => outln!(
+            "  {:<5}    {} ({})",
+            label,
+            at.format("%Y-%m-%d"),
+            humanize::relative(at)
+        ),
+        None => outln!(
+            "  {:<5}    beyond {} years",
+            label,
+            forecast::MAX_HORIZON_DAYS / 365
+        ),
+    }
+}
+
+/// Used percent from "<used> <total> [percent]" byte counts
+fn usage_percent(output: &str) -> Option<f64> {
+    let mut parts = output.split_whitespace();
+    let used = parts.next()?.parse::<u64>().ok()?;
+    let total = parts.next()?.parse::<u64>().ok()?;
+    (total > 0).then(|| used as f64 * 100.0 / total as f64)
+}
+
+/// Format `/proc/uptime` seconds; other output is shown as-is
+fn format_uptime(output: &str) -> String {
+    match output.parse::<f64>() {
+        Ok(secs) => humanize::duration(std::time::Duration::from_secs(secs as u64)),
+        Err(_) => output.to_string(),
+    }
+}
+
+/// Format "<used> <total> [percent]" byte counts from free/df as "1.4 GB / 3.8 GB (37%)"
+fn format_usage(output: &str) -> String {
+    let parts: Vec<&str> = output.split_whitespace().collect();
+    let used = parts.first().and_then(|v| v.parse::<u64>().ok());
+    let total = parts.get(1).and_then(|v| v.parse::<u64>().ok());
+
+    match (used, total) {
+        (Some(used), Some(total)) => {
+            let percent = parts
+                .get(2)
+                .map(|p| p.to_string())
+                .or_else(|| (total > 0).then(|| format!("{}%", used * 100 / total)))
+                .unwrap_or_default();
+            format!(
+                "{} / {} ({})",
+                humanize::bytes(used),
+                humanize::bytes(total),
+                percent
+            )
+        }
+        _ => output.to_string(),
+    }
+}
+
 /// Create SSH manager from credential
-async fn create_ssh_manager(
+pub(crate) async fn create_ssh_manager(
     db: &Database,
     cred_id: &str,
     host: &str,
 ) -> anyhow::Result<(SshManager, String)> {
     // Load credential from database (by name or ID)
-    let credential = db
-        .get_credential_by_name(cred_id)
-        .await?
-        .or(db.get_credential(cred_id).await?)
-        .ok_or_else(|| anyhow::anyhow!("Credential '{}' not found", cred_id))?;
+    let credential = find_credential(db, cred_id).await?;
 
     // Extract SSH details from credential and create appropriate auth method
-    let (username, port, auth_method) = match &credential.data {
-        CredentialData::SshKey {
-            username,
-            port,
-            key_path,
-            passphrase,
-        } => (
-            username.clone(),
-            *port,
-            AuthMethod::Key {
-                path: key_path.clone(),
-                passphrase: passphrase.clone(),
-            },
-        ),
-        CredentialData::SshAgent { username, port } => (username.clone(), *port, AuthMethod::Agent),
-        _ => anyhow::bail!("Credential '{}' is not an SSH credential", cred_id),
+    let key_material = db.get_key_material(&credential.id).await?;
+    let Some((username, port, auth_method)) = credential.ssh_login(key_material) else {
+        anyhow::bail!("Credential '{}' is not an SSH credential", cred_id);
     };
 
     // Create SSH connection for pctrl-ssh
@@ -359,13 +1279,367 @@ async fn create_ssh_manager(
     };
 
     // Create SSH manager and add connection
-    let mut ssh_manager = SshManager::new();
+    let stream_timeout = db
+        .get_setting(settings::SSH_STREAM_TIMEOUT_SECS)
+        .await?
+        .and_then(|v| v.parse().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+    let mut ssh_manager = SshManager::with_options(ConnectOptions {
+        stream_timeout,
+        ..ConnectOptions::default()
+    });
     let conn_id = ssh_conn.id.clone();
     ssh_manager.add_connection(ssh_conn);
 
     Ok((ssh_manager, conn_id))
 }
 
+/// Restores the terminal when the shell ends, however it ends
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// `server shell`: attach the local terminal in raw mode to a remote shell.
+/// Keystrokes are read on one thread, window size changes are polled on
+/// another; both feed the SSH pump. Returns the shell's exit status.
+fn run_shell(ssh_manager: &SshManager, conn_id: &str) -> anyhow::Result<i32> {
+    let (cols, rows) = terminal::size()?;
+    let pty = PtyRequest {
+        term: std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string()),
+        cols: u32::from(cols),
+        rows: u32::from(rows),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let keys = tx.clone();
+    // Blocks in read until the next keystroke, so it outlives the shell
+    // briefly; it stops with the process
+    std::thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 1024];
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            if keys.send(ShellInput::Data(buf[..n].to_vec())).is_err() {
+                break;
+            }
+        }
+    });
+    let done = Arc::new(AtomicBool::new(false));
+    let resizer_done = Arc::clone(&done);
+    std::thread::spawn(move || {
+        let mut last = (cols, rows);
+        while !resizer_done.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(250));
+            if let Ok(size) = terminal::size() {
+                if size != last {
+                    last = size;
+                    let resize = ShellInput::Resize {
+                        cols: u32::from(size.0),
+                        rows: u32::from(size.1),
+                    };
+                    if tx.send(resize).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let raw_mode = RawMode::enable()?;
+    let result = ssh_manager.interactive_shell(conn_id, None, &pty, &rx, &mut io::stdout());
+    drop(raw_mode);
+    done.store(true, Ordering::Relaxed);
+    Ok(result?)
+}
+
+/// `server exec --with-deploy-key`: run with a temporary git deploy key,
+/// removing keys that earlier interrupted runs left on the server first
+async fn exec_with_deploy_key(
+    db: &Database,
+    server: &Server,
+    ssh_manager: SshManager,
+    conn_id: String,
+    key_cred: &str,
+    command: String,
+) -> anyhow::Result<()> {
+    let credential = find_credential(db, key_cred).await?;
+    let CredentialData::SshKey {
+        key_path,
+        passphrase,
+        ..
+    } = &credential.data
+    else {
+        anyhow::bail!("Credential '{}' is not an SSH key", credential.name);
+    };
+    if passphrase.is_some() {
+        anyhow::bail!(
+            "Deploy key '{}' has a passphrase; git on the server can't unlock it",
+            credential.name
+        );
+    }
+    let private_key = std::fs::read_to_string(key_path)
+        .map_err(|e| anyhow::anyhow!("Can't read {}: {}", key_path, e))?;
+
+    let stale = db.pending_deploy_keys(&server.id).await?;
+    let token = uuid::Uuid::new_v4().to_string();
+    let path = deploy_key::key_path(&token);
+    db.record_pending_deploy_key(&server.id, &path).await?;
+
+    let run = tokio::task::spawn_blocking(move || {
+        let session = ssh_manager.connect(&conn_id)?;
+        let mut executor = SshExecutor {
+            session,
+            stream_timeout: ssh_manager.options().stream_timeout,
+        };
+        deploy_key::run_with_deploy_key(&mut executor, &private_key, &token, &stale, &command)
+    })
+    .await??;
+
+    if !run.stale_removed.is_empty() {
+        db.clear_pending_deploy_keys(&run.stale_removed).await?;
+        outln!(
+            "{}",
+            style::dim(&format!(
+                "Removed {} left on the server by an interrupted run",
+                humanize::count(run.stale_removed.len() as u64, "deploy key", "deploy keys")
+            ))
+        );
+    }
+    if run.cleaned_up {
+        db.clear_pending_deploy_keys(std::slice::from_ref(&path))
+            .await?;
+    } else {
+        eoutln!(
+            "{}",
+            style::warning_text(
+                "⚠ The deploy key couldn't be removed; the next run against this server removes it"
+            )
+        );
+    }
+
+    if !run.output.success() {
+        return Err(CommandFailed::new(
+            run.output.exit_code,
+            format!("Command exited with {}", run.output.exit_code),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Run the fact collection script on a server
+async fn collect_facts(db: &Database, server: &Server) -> anyhow::Result<facts::Facts> {
+    let cred_id = server.credential_id.as_deref().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Server '{}' has no credential; facts are collected via SSH",
+            server.name
+        )
+    })?;
+    let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+    let output = tokio::task::spawn_blocking(move || {
+        ssh_manager.execute_command(&conn_id, facts::COLLECT_SCRIPT)
+    })
+    .await??;
+
+    Ok(facts::parse(&output))
+}
+
+/// Run an SFTP transfer on a server, with a progress line on stderr
+pub(crate) async fn sftp_transfer<F, R>(
+    db: &Database,
+    server: &Server,
+    what: String,
+    run: F,
+) -> anyhow::Result<R>
+where
+    F: FnOnce(&SshManager, &str, &mut OnProgress) -> pctrl_core::Result<R> + Send + 'static,
+    R: Send + 'static,
+{
+    if let Some(reason) = vpn_blocked(server).await {
+        anyhow::bail!(
+            "{}; server '{}' is only reachable through it",
+            reason,
+            server.name
+        );
+    }
+    let cred_id = server
+        .credential_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", server.name))?;
+    let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+
+    noteln!("{}", what);
+    let show_progress = !style::is_quiet() && io::stderr().is_terminal();
+    let report = tokio::task::spawn_blocking(move || {
+        let mut drawn: Option<Instant> = None;
+        let mut on_progress = |progress: &Progress| {
+            let due = drawn.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL);
+            if show_progress && (due || progress.done == progress.total) {
+                eprint!("\r\x1b[2K  {}", progress.line());
+                drawn = Some(Instant::now());
+            }
+            ControlFlow::Continue(())
+        };
+        let result = run(&ssh_manager, &conn_id, &mut on_progress);
+        if drawn.is_some() {
+            eprintln!();
+        }
+        result
+    })
+    .await??;
+    Ok(report)
+}
+
+/// "✓ 2.0 GB transferred, resumed at 1.2 GB; size and sha256 verified"
+fn print_transfer_report(report: &TransferReport) {
+    let resumed = if report.resumed_from > 0 {
+        format!(", resumed at {}", humanize::bytes(report.resumed_from))
+    } else {
+        String::new()
+    };
+    let verified = match report.verified {
+        Verified::Checksum => "size and sha256 verified",
+        Verified::Size => "size verified",
+    };
+    noteln!(
+        "✓ {} transferred{}; {}",
+        humanize::bytes(report.total),
+        resumed,
+        verified
+    );
+}
+
+/// Record the containers on a server, from its Docker host or else via SSH.
+/// Recorded containers that are gone are marked exited, or forgotten with
+/// `prune`.
+async fn discover(db: &Database, server: &Server, prune: bool) -> anyhow::Result<()> {
+    let (discovered, gone) = record_discovery(db, server, prune).await?;
+    if !discovered.is_empty() {
+        let width = discovered.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        outln!();
+        for container in &discovered {
+            let status = container.status.to_string();
+            let status = match container.status {
+                ContainerStatus::Running => style::success_text(&status),
+                ContainerStatus::Exited => style::error_text(&status),
+                _ => style::warning_text(&status),
+            };
+            outln!(
+                "  {:<width$}  {:<10}  {}  {}",
+                container.name,
+                status,
+                container.image.as_deref().unwrap_or("-"),
+                style::dim(&container.ports.join(", ")),
+                width = width
+            );
+        }
+        outln!();
+    }
+    let running = discovered
+        .iter()
+        .filter(|c| c.status == ContainerStatus::Running)
+        .count();
+    let mut summary = format!(
+        "✓ Discovered {} on '{}' ({} running)",
+        humanize::count(discovered.len() as u64, "container", "containers"),
+        server.name,
+        running
+    );
+    if gone > 0 {
+        summary.push_str(&format!(
+            "; {} gone, {}",
+            gone,
+            if prune { "forgotten" } else { "marked exited" }
+        ));
+    }
+    noteln!("{}", summary);
+    Ok(())
+}
+
+/// The server's containers now, recorded with the ones that are gone marked
+/// exited (or forgotten with `prune`), and how many were gone. The compose
+/// stacks they form are recorded too.
+async fn record_discovery(
+    db: &Database,
+    server: &Server,
+    prune: bool,
+) -> anyhow::Result<(Vec<Container>, usize)> {
+    let discovered = match docker::server_host(db, server).await? {
+        Some(host) => {
+            noteln!("🔍 Listing containers on Docker host '{}'...", host.name);
+            let host_id = host.id.clone();
+            let mut manager = DockerManager::new();
+            manager.add_host(host)?;
+            manager
+                .list_containers(&host_id)
+                .await?
+                .into_iter()
+                .map(|c| docker::to_container(c, &server.id))
+                .collect::<Vec<_>>()
+        }
+        None => {
+            let cred_id = server.credential_id.as_deref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Server '{}' has no Docker host or credential to discover containers with",
+                    server.name
+                )
+            })?;
+            if let Some(reason) = vpn_blocked(server).await {
+                anyhow::bail!(
+                    "{}; server '{}' is only reachable through it",
+                    reason,
+                    server.name
+                );
+            }
+            noteln!("🔍 Listing containers via SSH...");
+            let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+            let output = tokio::task::spawn_blocking(move || {
+                ssh_manager.execute_command(&conn_id, discovery::DOCKER_PS)
+            })
+            .await??;
+            discovery::parse_docker_ps(&output, &server.id).map_err(|e| anyhow::anyhow!(e))?
+        }
+    };
+
+    let recorded = db.list_containers_for_server(&server.id).await?;
+    for container in &discovered {
+        db.save_container(container).await?;
+    }
+    let mut gone = 0;
+    for container in discovery::vanished(&recorded, &discovered) {
+        if prune {
+            db.remove_container(&container.id).await?;
+        } else if container.status != ContainerStatus::Exited {
+            let mut exited = container.clone();
+            exited.status = ContainerStatus::Exited;
+            db.save_container(&exited).await?;
+        } else {
+            continue;
+        }
+        gone += 1;
+    }
+    db.upsert_discovery_cache(
+        &server.id,
+        discovery::CONTAINERS,
+        &serde_json::to_string(&discovered)?,
+        chrono::Duration::seconds(discovery::CONTAINERS_TTL_SECS),
+    )
+    .await?;
+    compose::record(db, &server.id, &discovered, prune).await?;
+    Ok((discovered, gone))
+}
+
 /// Detect server specs via SSH credential
 async fn detect_specs_via_credential(
     db: &Database,
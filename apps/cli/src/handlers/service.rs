@@ -0,0 +1,414 @@
+//! `pctrl service`: systemd units on servers, driven over SSH
+
+use super::guard::confirm_live;
+use super::hints;
+use super::resolve::{find_project, find_server};
+use super::server::create_ssh_manager;
+use super::CommandFailed;
+use crate::{style, ServiceCommands};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::systemd::{self, ServiceAction, ServiceState};
+use pctrl_core::{facts, humanize, ResourceType, Server, Service};
+use pctrl_database::Database;
+use pctrl_ssh::SshManager;
+use std::io::Write;
+use std::sync::Arc;
+
+pub async fn handle(command: ServiceCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        ServiceCommands::List => {
+            let services = db.list_services().await?;
+            if services.is_empty() {
+                outln!("No services configured.");
+                hints::show(db, Event::Empty(Listing::Services)).await?;
+                return Ok(());
+            }
+            let servers = db.list_servers().await?;
+            outln!("Services ({}):", services.len());
+            outln!();
+            for service in services {
+                let server = servers
+                    .iter()
+                    .find(|s| s.id == service.server_id)
+                    .map(|s| s.name.as_str())
+                    .unwrap_or(&service.server_id);
+                outln!(
+                    "  {} {} → {}:{}{}",
+                    state_icon(service.last_state.as_deref()),
+                    service.name,
+                    server,
+                    service.unit,
+                    last_state(&service)
+                );
+            }
+        }
+
+        ServiceCommands::Add {
+            name,
+            server,
+            unit,
+            project,
+        } => {
+            let unit = systemd::normalize_unit(&unit).map_err(|e| anyhow::anyhow!(e))?;
+            let server = find_server(db, &server).await?;
+            let project_id = match project {
+                Some(project) => Some(find_project(db, &project).await?.id),
+                None => None,
+            };
+            if let Some(existing) = db.get_service(&name).await? {
+                anyhow::bail!(
+                    "Service '{}' already exists ({} on {})",
+                    existing.name,
+                    existing.unit,
+                    existing.server_id
+                );
+            }
+
+            let service = Service {
+                id: name.to_lowercase().replace(' ', "-"),
+                name,
+                server_id: server.id.clone(),
+                unit,
+                project_id,
+                last_state: None,
+                last_checked: None,
+            };
+            db.save_service(&service).await?;
+
+            noteln!("✓ Service added:");
+            noteln!();
+            noteln!("  Name:    {}", service.name);
+            noteln!("  ID:      {}", service.id);
+            noteln!("  Server:  {}", server.name);
+            noteln!("  Unit:    {}", service.unit);
+            if let Some(project) = &service.project_id {
+                noteln!("  Project: {}", project);
+            }
+            hints::show(db, Event::ServiceAdded { name: service.name }).await?;
+        }
+
+        ServiceCommands::Remove { name } => {
+            let service = find_service(db, &name).await?;
+            if db.remove_service(&service.id).await? {
+                noteln!("✓ Service '{}' removed", service.name);
+            }
+        }
+
+        ServiceCommands::Status { name } => {
+            let service = find_service(db, &name).await?;
+            let remote = Remote::connect(db, &service).await?;
+            let unit = service.unit.clone();
+            let (output, exit_code) = remote
+                .run(move |run| run(&systemd::show_command(&unit)))
+                .await?;
+            let state = read_state(db, &remote.server, &service.unit, &output, exit_code).await?;
+            db.record_service_state(&service.id, &state.summary())
+                .await?;
+            print_state(&service, &remote.server, &state);
+        }
+
+        ServiceCommands::Start { name, allow_live } => {
+            control(db, &name, ServiceAction::Start, allow_live).await?;
+        }
+
+        ServiceCommands::Stop { name, allow_live } => {
+            control(db, &name, ServiceAction::Stop, allow_live).await?;
+        }
+
+        ServiceCommands::Restart { name, allow_live } => {
+            control(db, &name, ServiceAction::Restart, allow_live).await?;
+        }
+
+        ServiceCommands::Logs {
+            name,
+            lines,
+            follow,
+        } => {
+            let service = find_service(db, &name).await?;
+            let remote = Remote::connect(db, &service).await?;
+            let command = systemd::logs_command(&service.unit, lines, follow);
+            if follow {
+                noteln!(
+                    "Following {} on {} (Ctrl-C to stop)",
+                    service.unit,
+                    remote.server.name
+                );
+            }
+
+            let logs = remote.run(move |run| run(&command));
+            let (output, exit_code) = if follow {
+                // The journal never ends on its own; Ctrl-C ends the session
+                tokio::select! {
+                    result = logs => result?,
+                    _ = tokio::signal::ctrl_c() => {
+                        outln!();
+                        return Ok(());
+                    }
+                }
+            } else {
+                logs.await?
+            };
+            if exit_code != 0 {
+                if systemd::is_not_systemd(&output, exit_code) {
+                    return Err(not_systemd(db, &remote.server).await);
+                }
+                return Err(CommandFailed::new(
+                    exit_code,
+                    format!("journalctl exited with {}", exit_code),
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Start, stop or restart a unit, then show its new state
+async fn control(
+    db: &Database,
+    name: &str,
+    action: ServiceAction,
+    allow_live: bool,
+) -> anyhow::Result<()> {
+    let service = find_service(db, name).await?;
+    let remote = Remote::connect(db, &service).await?;
+
+    let mut live = db
+        .live_projects_for_resource(
+            &ResourceType::Server,
+            &[&remote.server.id, &remote.server.name],
+        )
+        .await?;
+    if let Some(project_id) = &service.project_id {
+        if let Some(project) = db
+            .get_project(project_id)
+            .await?
+            .filter(|p| p.status.is_live() && !live.iter().any(|l| l.id == p.id))
+        {
+            live.push(project);
+        }
+    }
+    let verb = match action {
+        ServiceAction::Start => "Starting",
+        ServiceAction::Stop => "Stopping",
+        ServiceAction::Restart => "Restarting",
+    };
+    confirm_live(
+        &live,
+        &format!("{} service '{}'", verb, service.name),
+        allow_live,
+    )?;
+
+    noteln!("▶ {} {} on {}...", verb, service.unit, remote.server.name);
+    let control = systemd::control_command(action, &service.unit, remote.sudo);
+    let show = systemd::show_command(&service.unit);
+    let (control_result, show_result) = remote
+        .run(move |run| {
+            let control = run(&control)?;
+            // No point asking for the state if the host has no systemd
+            if control.1 != 0 && systemd::is_not_systemd(&control.0, control.1) {
+                return Ok((control, None));
+            }
+            Ok((control, Some(run(&show)?)))
+        })
+        .await?;
+
+    let (output, exit_code) = control_result;
+    if exit_code != 0 {
+        if systemd::is_not_systemd(&output, exit_code) {
+            return Err(not_systemd(db, &remote.server).await);
+        }
+        if systemd::is_sudo_refused(&output) {
+            anyhow::bail!(
+                "sudo on '{}' needs a password; allow passwordless sudo for systemctl or use a root credential",
+                remote.server.name
+            );
+        }
+        out!("{}", output);
+        return Err(CommandFailed::new(
+            exit_code,
+            format!(
+                "systemctl {} {} failed ({})",
+                action, service.unit, exit_code
+            ),
+        )
+        .into());
+    }
+
+    if let Some((output, exit_code)) = show_result {
+        let state = read_state(db, &remote.server, &service.unit, &output, exit_code).await?;
+        db.record_service_state(&service.id, &state.summary())
+            .await?;
+        let line = format!(
+            "{} {}: {}",
+            state_icon(Some(&state.summary())),
+            service.name,
+            state.summary()
+        );
+        let line = if state.is_failed() {
+            style::error_text(&line)
+        } else {
+            line
+        };
+        outln!("{}", line);
+    }
+    Ok(())
+}
+
+/// SSH access to a service's server
+struct Remote {
+    server: Server,
+    manager: Arc<SshManager>,
+    conn_id: String,
+    /// Control commands go through `sudo -n`
+    sudo: bool,
+}
+
+impl Remote {
+    async fn connect(db: &Database, service: &Service) -> anyhow::Result<Self> {
+        let server = find_server(db, &service.server_id).await?;
+        let cred_id = server.credential_id.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("Server '{}' has no credential configured", server.name)
+        })?;
+        let (manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+        let sudo = manager
+            .get_connection(&conn_id)
+            .is_some_and(|c| systemd::needs_sudo(&c.username));
+        Ok(Self {
+            server,
+            manager: Arc::new(manager),
+            conn_id,
+            sudo,
+        })
+    }
+
+    /// Open one session and hand `work` a runner for commands on it. Output
+    /// is echoed as it arrives only for `journalctl`, whose output is the
+    /// result.
+    async fn run<T: Send + 'static>(
+        &self,
+        work: impl FnOnce(
+                &mut dyn FnMut(&str) -> pctrl_core::Result<(String, i32)>,
+            ) -> pctrl_core::Result<T>
+            + Send
+            + 'static,
+    ) -> anyhow::Result<T> {
+        let manager = self.manager.clone();
+        let conn_id = self.conn_id.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let session = manager.connect(&conn_id)?;
+            let stream_timeout = manager.options().stream_timeout;
+            work(&mut |command: &str| {
+                let echo = command.starts_with("journalctl");
+                SshManager::execute_streaming(&session, command, stream_timeout, &mut |chunk| {
+                    if echo {
+                        out!("{}", chunk);
+                        let _ = std::io::stdout().flush();
+                    }
+                })
+            })
+        })
+        .await??;
+        Ok(result)
+    }
+}
+
+/// Parse `systemctl show` output, turning hosts without systemd and unknown
+/// units into clear errors
+async fn read_state(
+    db: &Database,
+    server: &Server,
+    unit: &str,
+    output: &str,
+    exit_code: i32,
+) -> anyhow::Result<ServiceState> {
+    if exit_code != 0 && systemd::is_not_systemd(output, exit_code) {
+        return Err(not_systemd(db, server).await);
+    }
+    let state = systemd::parse_show(output).map_err(|e| anyhow::anyhow!(e))?;
+    if !state.exists() {
+        anyhow::bail!("Unit {} not found on '{}'", unit, server.name);
+    }
+    Ok(state)
+}
+
+/// "not a systemd host" error, with the init system from the server's facts
+/// when they were collected
+async fn not_systemd(db: &Database, server: &Server) -> anyhow::Error {
+    let facts = db
+        .list_server_facts(&server.id)
+        .await
+        .map(|f| facts::to_map(&f))
+        .unwrap_or_default();
+    match systemd::not_systemd_reason(&facts) {
+        Some(reason) => anyhow::anyhow!("'{}' is not a systemd host: {}", server.name, reason),
+        None => anyhow::anyhow!("'{}' is not a systemd host", server.name),
+    }
+}
+
+fn print_state(service: &Service, server: &Server, state: &ServiceState) {
+    outln!();
+    outln!(
+        "  {} {} ({} on {})",
+        state_icon(Some(&state.summary())),
+        service.name,
+        service.unit,
+        server.name
+    );
+    outln!("  ─────────────────────────────");
+    if let Some(description) = &state.description {
+        outln!("  Desc:     {}", description);
+    }
+    let summary = if state.is_failed() {
+        style::error_text(&state.summary())
+    } else {
+        state.summary()
+    };
+    outln!("  State:    {}", summary);
+    if let Some(since) = &state.since {
+        outln!("  Since:    {}", since);
+    }
+    if let Some(enabled) = &state.unit_file_state {
+        outln!("  Enabled:  {}", enabled);
+    }
+    if let Some(pid) = state.main_pid {
+        outln!("  PID:      {}", pid);
+    }
+    if let Some(restarts) = state.restarts {
+        let restarts = if restarts > 0 {
+            style::warning_text(&restarts.to_string())
+        } else {
+            restarts.to_string()
+        };
+        outln!("  Restarts: {}", restarts);
+    }
+    outln!();
+}
+
+/// Status dot for a stored or fresh state summary
+pub(crate) fn state_icon(state: Option<&str>) -> &'static str {
+    match state {
+        Some(s) if s.starts_with("active") => "🟢",
+        Some(s) if s.starts_with("failed") => "🔴",
+        Some(s) if s.starts_with("activating") || s.starts_with("deactivating") => "🟡",
+        Some(_) => "⚪",
+        None => "⚫",
+    }
+}
+
+/// " [active (running), 5m ago]" for a service checked before
+pub(crate) fn last_state(service: &Service) -> String {
+    match (&service.last_state, &service.last_checked) {
+        (Some(state), Some(checked)) => {
+            format!(" [{}, {}]", state, humanize::relative_timestamp(checked))
+        }
+        _ => String::new(),
+    }
+}
+
+async fn find_service(db: &Database, name: &str) -> anyhow::Result<Service> {
+    db.get_service(name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", name))
+}
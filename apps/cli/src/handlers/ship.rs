@@ -0,0 +1,204 @@
+//! `project ship`: git pull and docker compose on the project's server
+
+use super::project::project_server;
+use super::server::create_ssh_manager;
+use super::CommandFailed;
+use crate::style;
+use chrono::{SecondsFormat, Utc};
+use pctrl_core::preflight::EXIT_DEPLOY_FAILED;
+use pctrl_core::ship::{
+    self, CommandOutput, ShipEvent, ShipExecutor, ShipOptions, ShipPhase, ShipRun,
+};
+use pctrl_core::{humanize, Project, ResourceType};
+use pctrl_database::Database;
+use pctrl_ssh::{Session, SshManager};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Time between container polls
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Runs the ship commands over one SSH session
+pub(crate) struct SshExecutor {
+    pub(crate) session: Session,
+    /// See [`pctrl_ssh::ConnectOptions::stream_timeout`]
+    pub(crate) stream_timeout: Option<Duration>,
+}
+
+impl ShipExecutor for SshExecutor {
+    fn run(&mut self, command: &str, echo: bool) -> pctrl_core::Result<CommandOutput> {
+        let (output, exit_code) = SshManager::execute_streaming(
+            &self.session,
+            command,
+            self.stream_timeout,
+            &mut |chunk| {
+                if echo {
+                    out!("{}", chunk);
+                    let _ = std::io::stdout().flush();
+                }
+            },
+        )?;
+        Ok(CommandOutput { output, exit_code })
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Ship a project to its server and record the run
+pub(crate) async fn ship(
+    db: &Database,
+    project: &Project,
+    branch: Option<String>,
+    build: bool,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let config = db.get_deploy_config(&project.id).await?;
+    let path = config.path.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Project '{}' has no deploy path (pctrl project edit {} --deploy-path /srv/...)",
+            project.name,
+            project.name
+        )
+    })?;
+    let branch = branch
+        .or(config.branch)
+        .unwrap_or_else(|| ship::DEFAULT_BRANCH.to_string());
+
+    let server = project_server(db, project).await?;
+    let cred_id = server
+        .credential_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Server '{}' has no credential configured", server.name))?;
+    let (ssh_manager, conn_id) = create_ssh_manager(db, cred_id, &server.host).await?;
+
+    let links = db.get_project_resources(&project.id).await?;
+    let containers: Vec<String> = links
+        .iter()
+        .filter(|l| l.resource_type == ResourceType::Container)
+        .map(|l| l.resource_id.clone())
+        .collect();
+
+    noteln!(
+        "🚢 Shipping {} ({}) to {}:{}",
+        project.name,
+        branch,
+        server.name,
+        path
+    );
+    if let Some(repo) = links.iter().find(|l| l.resource_type == ResourceType::Git) {
+        outln!("  {} {}", style::dim("Repo:"), repo.resource_id);
+    }
+
+    let options = ShipOptions {
+        path,
+        branch: branch.clone(),
+        build,
+        containers,
+        health_timeout: timeout,
+        poll_interval: POLL_INTERVAL,
+    };
+    let started = Instant::now();
+    let report = tokio::task::spawn_blocking(move || -> anyhow::Result<ship::ShipReport> {
+        let session = ssh_manager.connect(&conn_id)?;
+        let mut executor = SshExecutor {
+            session,
+            stream_timeout: ssh_manager.options().stream_timeout,
+        };
+        Ok(ship::ship(&mut executor, &options, &mut print_event))
+    })
+    .await??;
+    let elapsed = started.elapsed();
+
+    db.record_ship_run(&ShipRun {
+        project_id: project.id.clone(),
+        server_id: server.id.clone(),
+        branch,
+        old_commit: report.old_commit.clone(),
+        new_commit: report.new_commit.clone(),
+        success: report.success(),
+        failed_phase: report.failure.as_ref().map(|(phase, _)| *phase),
+        error: report.failure.as_ref().map(|(_, reason)| reason.clone()),
+        output: report.logs_tail.clone(),
+        duration_ms: elapsed.as_millis() as i64,
+        ran_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    })
+    .await?;
+
+    outln!();
+    match &report.failure {
+        Some((phase, reason)) => Err(CommandFailed::new(
+            EXIT_DEPLOY_FAILED,
+            format!("Ship of '{}' failed at {}: {}", project.name, phase, reason),
+        )
+        .into()),
+        None => {
+            outln!(
+                "{} Shipped {} {} in {}",
+                style::success_text("✓"),
+                project.name,
+                report.commit_change().unwrap_or_default(),
+                humanize::duration(elapsed)
+            );
+            Ok(())
+        }
+    }
+}
+
+fn print_event(event: ShipEvent) {
+    match event {
+        ShipEvent::PhaseStarted(phase) => {
+            outln!();
+            outln!("{}", style::header(&format!("▶ {}", phase_title(phase))));
+        }
+        ShipEvent::Commits { old, new, count } => {
+            if old == new {
+                outln!("  Already at {}", ship::short(&new));
+            } else {
+                let commits = count
+                    .map(|n| format!(" ({})", humanize::count(n as u64, "commit", "commits")))
+                    .unwrap_or_default();
+                outln!("  {} → {}{}", ship::short(&old), ship::short(&new), commits);
+            }
+        }
+        ShipEvent::ComposeDetected(compose) => {
+            outln!("  {}", style::dim(&format!("Using {}", compose.command())));
+        }
+        ShipEvent::PhaseSkipped(_, reason) => {
+            outln!("  {}", style::dim(&format!("Skipped ({})", reason)));
+        }
+        ShipEvent::Waiting(pending) => {
+            outln!("  {}", style::dim(&format!("Waiting for {}", pending)));
+        }
+        ShipEvent::PhaseFailed(phase, reason) => {
+            outln!(
+                "{}",
+                style::error_text(&format!("✗ {} failed: {}", phase, reason))
+            );
+        }
+        ShipEvent::Logs(tail) => {
+            outln!();
+            outln!(
+                "{}",
+                style::header(&format!(
+                    "Compose logs (last {} lines)",
+                    ship::LOG_TAIL_LINES
+                ))
+            );
+            for line in tail.lines() {
+                outln!("  {}", line);
+            }
+        }
+    }
+}
+
+fn phase_title(phase: ShipPhase) -> &'static str {
+    match phase {
+        ShipPhase::Fetch => "Fetch (git pull)",
+        ShipPhase::Pull => "Pull images (compose pull)",
+        ShipPhase::Build => "Build (compose build)",
+        ShipPhase::Up => "Start (compose up -d)",
+        ShipPhase::Health => "Wait for containers",
+    }
+}
@@ -0,0 +1,276 @@
+//! Inventory snapshot command handler
+//!
+//! A restore shows the snapshot's changes, lets them be decided at the
+//! terminal, then writes the decided snapshot through `pctrl import`'s
+//! path ([`import::run`]), taking the snapshot's side of every difference.
+
+use super::import::{self, ImportOptions};
+use super::{conflicts, fanout, hints};
+use crate::{style, SnapshotCommands};
+use chrono::Utc;
+use pctrl_core::config_export::ConfigDocument;
+use pctrl_core::conflict::{Decision, Side};
+use pctrl_core::fanout::FailOn;
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::merge::Resolution;
+use pctrl_core::snapshot::{
+    diff_inventories, entity_value, format_changes, is_restorable, restore_conflicts, set_entity,
+    ChangeKind, EntityChange,
+};
+use pctrl_core::{humanize, EntityType, Inventory, InventoryCounts};
+use pctrl_database::Database;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// What a restore removes when the snapshot doesn't have it. Never
+/// credentials: snapshots don't contain their secrets, so they couldn't be
+/// brought back.
+const RESTORE_REMOVES: [EntityType; 5] = [
+    EntityType::Script,
+    EntityType::Domain,
+    EntityType::Database,
+    EntityType::Server,
+    EntityType::Project,
+];
+
+pub async fn handle(command: SnapshotCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        SnapshotCommands::Create { name } => {
+            let name =
+                name.unwrap_or_else(|| Utc::now().format("snapshot-%Y%m%d-%H%M%S").to_string());
+            let info = db.create_snapshot(&name).await?;
+            noteln!(
+                "✓ Snapshot '{}' created ({})",
+                info.name,
+                humanize::bytes(info.size_bytes)
+            );
+            outln!("  {}", format_counts(&info.counts));
+            outln!(
+                "  {}",
+                style::dim("Secrets (credential data, database passwords) are not included")
+            );
+        }
+
+        SnapshotCommands::List => {
+            let snapshots = db.list_snapshots().await?;
+            if snapshots.is_empty() {
+                outln!("No snapshots yet.");
+                hints::show(db, Event::Empty(Listing::Snapshots)).await?;
+                return Ok(());
+            }
+
+            outln!("Snapshots ({}):", snapshots.len());
+            outln!();
+            for info in snapshots {
+                outln!(
+                    "  {:<28} {:>10}  {:>9}  {}",
+                    info.name,
+                    humanize::relative_timestamp(&info.created_at),
+                    humanize::bytes(info.size_bytes),
+                    style::dim(&format_counts(&info.counts))
+                );
+            }
+        }
+
+        SnapshotCommands::Diff { from, to } => {
+            let old = load(db, &from).await?;
+            let (new, to_label) = match to {
+                Some(to) => (load(db, &to).await?, format!("'{}'", to)),
+                None => (db.current_inventory().await?, "now".to_string()),
+            };
+
+            let changes = diff_inventories(&old, &new);
+            outln!("Changes from '{}' to {}:", from, to_label);
+            outln!();
+            if changes.is_empty() {
+                outln!("No changes.");
+                return Ok(());
+            }
+            print_changes(&changes);
+            outln!();
+            outln!("{}", summary(&changes));
+        }
+
+        SnapshotCommands::Restore { name, dry_run, yes } => {
+            let mut target = load(db, &name).await?;
+            let current = db.current_inventory().await?;
+            let changes = diff_inventories(&current, &target);
+            if changes.is_empty() {
+                outln!("✓ Inventory already matches '{}'", name);
+                return Ok(());
+            }
+
+            let (mut apply, skipped): (Vec<EntityChange>, Vec<EntityChange>) =
+                changes.into_iter().partition(is_restorable);
+            if !dry_run && !yes && io::stdin().is_terminal() {
+                decide_modified(&current, &mut target, &mut apply)?;
+                if apply.is_empty() {
+                    outln!("Nothing to restore.");
+                    return Ok(());
+                }
+            }
+
+            outln!("Restoring '{}' will:", name);
+            outln!();
+            print_changes(&apply);
+            if !skipped.is_empty() {
+                outln!();
+                outln!(
+                    "{}",
+                    style::warning_text(
+                        "Not restored (snapshots don't contain credential secrets):"
+                    )
+                );
+                print_changes(&skipped);
+            }
+            outln!();
+
+            if dry_run {
+                outln!("{} (dry run, nothing changed)", summary(&apply));
+                return Ok(());
+            }
+            if apply.is_empty() {
+                outln!("Nothing to restore.");
+                return Ok(());
+            }
+            if !yes {
+                confirm(&format!("Apply {}?", summary(&apply)))?;
+            }
+
+            let document = ConfigDocument::from_inventory(
+                target,
+                &Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            );
+            let options = ImportOptions {
+                resolution: Some(Resolution::TakeOther),
+                remove: &RESTORE_REMOVES,
+                missing_secrets: "not restored, snapshots don't contain credential secrets",
+                dry_run: false,
+            };
+            let outcome = import::run(db, &document, "snapshot restore", &options).await?;
+            noteln!("✓ Restored '{}': {}", name, summary(&apply));
+            fanout::finish(db, &outcome.report, false, FailOn::Any).await?;
+        }
+
+        SnapshotCommands::Remove { name } => {
+            if db.remove_snapshot(&name).await? {
+                noteln!("✓ Snapshot '{}' removed", name);
+            } else {
+                outln!("✗ Snapshot '{}' not found", name);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn load(db: &Database, name: &str) -> anyhow::Result<Inventory> {
+    db.load_snapshot(name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Snapshot '{}' not found", name))
+}
+
+/// Diff listing with colored markers
+fn print_changes(changes: &[EntityChange]) {
+    for line in format_changes(changes).lines() {
+        let colored = match line.chars().next() {
+            Some('+') => style::success_text(line),
+            Some('-') => style::error_text(line),
+            Some('~') => style::warning_text(line),
+            _ => line.to_string(),
+        };
+        outln!("  {}", colored);
+    }
+}
+
+/// "2 created, 1 modified"
+fn summary(changes: &[EntityChange]) -> String {
+    let count = |f: fn(&ChangeKind) -> bool| changes.iter().filter(|c| f(&c.change)).count();
+    let parts = [
+        (count(|c| *c == ChangeKind::Created), "created"),
+        (count(|c| *c == ChangeKind::Removed), "removed"),
+        (count(|c| matches!(c, ChangeKind::Modified(_))), "modified"),
+    ];
+
+    parts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{} {}", n, label))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_counts(counts: &InventoryCounts) -> String {
+    [
+        (counts.projects, "project", "projects"),
+        (counts.servers, "server", "servers"),
+        (counts.domains, "domain", "domains"),
+        (counts.databases, "database", "databases"),
+        (counts.scripts, "script", "scripts"),
+        (counts.credentials, "credential", "credentials"),
+        (counts.links, "link", "links"),
+    ]
+    .iter()
+    .map(|(n, singular, plural)| humanize::count(*n as u64, singular, plural))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Ask about every entity the snapshot would change: the ones kept drop
+/// out of `apply` and go back to their current version in `target`,
+/// field-by-field merges are written into `target`
+fn decide_modified(
+    current: &Inventory,
+    target: &mut Inventory,
+    apply: &mut Vec<EntityChange>,
+) -> anyhow::Result<()> {
+    let conflicts = restore_conflicts(current, target, apply);
+    let Some(decisions) = conflicts::resolve(&conflicts)? else {
+        anyhow::bail!("Aborted");
+    };
+    for (conflict, decision) in conflicts.iter().zip(decisions) {
+        let index = apply
+            .iter()
+            .position(|c| c.kind == conflict.kind && c.id == conflict.id);
+        let Some(index) = index else {
+            continue;
+        };
+        match decision {
+            Decision::TakeIncoming => {}
+            Decision::KeepLocal | Decision::Skip => {
+                if let Some(kept) = entity_value(current, &conflict.kind, &conflict.id) {
+                    set_entity(target, &conflict.kind, &conflict.id, kept)
+                        .map_err(anyhow::Error::msg)?;
+                }
+                apply.remove(index);
+            }
+            Decision::Fields(picks) => {
+                set_entity(
+                    target,
+                    &conflict.kind,
+                    &conflict.id,
+                    conflict.merged(&picks),
+                )
+                .map_err(anyhow::Error::msg)?;
+                if let ChangeKind::Modified(fields) = &mut apply[index].change {
+                    fields.retain(|f| picks.get(&f.field) == Some(&Side::Incoming));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn confirm(question: &str) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to restore without a terminal; pass --yes");
+    }
+
+    out!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("Aborted");
+    }
+    Ok(())
+}
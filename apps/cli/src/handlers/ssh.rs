@@ -0,0 +1,92 @@
+//! SSH command handler: files to and from servers
+
+use super::resolve::find_server;
+use super::server::sftp_transfer;
+use crate::SshCommands;
+use pctrl_core::humanize;
+use pctrl_database::Database;
+use pctrl_ssh::{CopyOptions, CopyReport};
+use std::path::{Path, PathBuf};
+
+pub async fn handle(command: SshCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        SshCommands::Put {
+            server,
+            local,
+            remote,
+            recursive,
+            force,
+            mode,
+        } => {
+            let server = find_server(db, &server).await?;
+            let local = PathBuf::from(local);
+            if !local.exists() {
+                anyhow::bail!("'{}' doesn't exist", local.display());
+            }
+            let remote = if remote.ends_with('/') {
+                format!("{}{}", remote, file_name(&local.to_string_lossy())?)
+            } else {
+                remote
+            };
+            let options = CopyOptions {
+                recursive,
+                overwrite: force,
+            };
+            let what = format!("⬆ {} → {}:{}", local.display(), server.name, remote);
+            let report = sftp_transfer(db, &server, what, move |ssh, conn_id, on_progress| {
+                ssh.upload_file(conn_id, &local, &remote, mode, options, on_progress)
+            })
+            .await?;
+            print_report(&report);
+        }
+
+        SshCommands::Get {
+            server,
+            remote,
+            local,
+            recursive,
+            force,
+        } => {
+            let server = find_server(db, &server).await?;
+            let local = match PathBuf::from(local) {
+                dir if dir.is_dir() => dir.join(file_name(&remote)?),
+                path => path,
+            };
+            // No need to connect to refuse this one
+            if local.is_file() && !force {
+                anyhow::bail!("'{}' already exists; --force replaces it", local.display());
+            }
+            let options = CopyOptions {
+                recursive,
+                overwrite: force,
+            };
+            let what = format!("⬇ {}:{} → {}", server.name, remote, local.display());
+            let report = sftp_transfer(db, &server, what, move |ssh, conn_id, on_progress| {
+                ssh.download_file(conn_id, &remote, &local, options, on_progress)
+            })
+            .await?;
+            print_report(&report);
+        }
+    }
+
+    Ok(())
+}
+
+/// The last part of `path`, for copying into a directory
+fn file_name(path: &str) -> anyhow::Result<String> {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("'{}' names no file", path))
+}
+
+/// "✓ 3 files copied (12.4 KB)"
+fn print_report(report: &CopyReport) {
+    let files = if report.files == 1 { "file" } else { "files" };
+    noteln!(
+        "✓ {} {} copied ({})",
+        report.files,
+        files,
+        humanize::bytes(report.bytes)
+    );
+}
@@ -0,0 +1,226 @@
+//! Usage statistics command handler
+
+use crate::{style, StatsCommands};
+use chrono::{DateTime, Utc};
+use pctrl_core::growth::{self, Granularity};
+use pctrl_core::humanize;
+use pctrl_database::Database;
+
+/// Periods a growth chart can show; one bar each
+const MAX_PERIODS: usize = 120;
+
+pub async fn handle(
+    db: &Database,
+    command: Option<StatsCommands>,
+    since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    match command {
+        Some(StatsCommands::Reset) => {
+            let removed = db.reset_usage_stats().await?;
+            noteln!(
+                "✓ Usage statistics reset ({} removed)",
+                humanize::count(removed, "entry", "entries")
+            );
+        }
+        Some(StatsCommands::Disable) => {
+            db.set_usage_stats_enabled(false).await?;
+            noteln!("✓ Usage statistics disabled (existing data kept, 'stats reset' deletes it)");
+        }
+        Some(StatsCommands::Enable) => {
+            db.set_usage_stats_enabled(true).await?;
+            noteln!("✓ Usage statistics enabled");
+        }
+        Some(StatsCommands::Growth {
+            since,
+            granularity,
+            json,
+        }) => growth(db, since, granularity, json).await?,
+        None => show(db, since, limit).await?,
+    }
+
+    Ok(())
+}
+
+async fn show(db: &Database, since: Option<DateTime<Utc>>, limit: usize) -> anyhow::Result<()> {
+    let summary = db.usage_summary(since, limit).await?;
+    let enabled = db.usage_stats_enabled().await?;
+
+    let period = since
+        .map(|s| format!("since {}", humanize::relative(s)))
+        .unwrap_or_else(|| "all time".to_string());
+    outln!(
+        "Usage statistics ({}, {}):",
+        period,
+        humanize::count(summary.total as u64, "command", "commands")
+    );
+    if !enabled {
+        outln!(
+            "{}",
+            style::warning_text("  Collection is disabled ('pctrl stats enable' to resume)")
+        );
+    }
+    outln!();
+
+    if summary.total == 0 {
+        outln!("No usage recorded yet.");
+        return Ok(());
+    }
+
+    outln!("{}", style::bold("Top commands"));
+    outln!(
+        "  {:<28} {:>6} {:>8} {:>8} {:>8}",
+        "COMMAND",
+        "RUNS",
+        "AVG",
+        "P95",
+        "FAILED"
+    );
+    for usage in &summary.commands {
+        let failed = format!("{:.0}%", usage.failure_rate() * 100.0);
+        let failed = if usage.failures > 0 {
+            style::warning_text(&format!("{:>8}", failed))
+        } else {
+            style::dim(&format!("{:>8}", failed))
+        };
+        outln!(
+            "  {:<28} {:>6} {:>8} {:>8} {}",
+            usage.command,
+            usage.count,
+            format_ms(usage.avg_ms),
+            format_ms(usage.p95_ms),
+            failed
+        );
+    }
+
+    outln!();
+    outln!("{}", style::bold("Busiest days"));
+    for (day, count) in &summary.busiest_days {
+        outln!(
+            "  {}  {}",
+            day,
+            humanize::count(*count as u64, "command", "commands")
+        );
+    }
+
+    if !summary.entities.is_empty() {
+        outln!();
+        outln!("{}", style::bold("Most changed"));
+        for entity in &summary.entities {
+            outln!(
+                "  {:<10} {:<28} {}",
+                entity.entity_type.to_string(),
+                entity.summary,
+                style::dim(&humanize::count(entity.changes as u64, "change", "changes"))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn growth(
+    db: &Database,
+    since: DateTime<Utc>,
+    granularity: Granularity,
+    json: bool,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let periods = growth::periods(since, now, granularity).len();
+    if periods > MAX_PERIODS {
+        anyhow::bail!(
+            "{} is too many to chart; use a coarser --granularity or a later --since",
+            humanize::count(periods as u64, "period", "periods")
+        );
+    }
+    let growth = growth::reconstruct(
+        &db.entity_counts().await?,
+        &db.growth_events().await?,
+        since,
+        now,
+        granularity,
+    );
+    let composition = db.composition().await?;
+
+    if json {
+        let output = serde_json::json!({
+            "granularity": growth.granularity,
+            "periods": growth.periods,
+            "series": growth.series,
+            "composition": composition,
+        });
+        outln!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let first = growth
+        .periods
+        .first()
+        .map(|start| granularity.label(*start))
+        .unwrap_or_default();
+    outln!(
+        "Growth since {} (by {}, {}):",
+        first,
+        granularity,
+        humanize::count(periods as u64, "period", "periods")
+    );
+    outln!();
+    outln!(
+        "  {:<12} {:<width$} {:>7} {:>5} {:>7}",
+        "TYPE",
+        "TREND",
+        "THEN",
+        "NOW",
+        "CHANGE",
+        width = periods.max(5)
+    );
+    for series in &growth.series {
+        let change = match series.change() {
+            0 => style::dim(&format!("{:>7}", 0)),
+            n if n > 0 => style::success_text(&format!("{:>7}", format!("+{}", n))),
+            n => style::warning_text(&format!("{:>7}", n)),
+        };
+        outln!(
+            "  {:<12} {:<width$} {:>7} {:>5} {}",
+            format!("{}s", series.entity_type),
+            growth::sparkline(&series.counts),
+            series.counts.first().copied().unwrap_or(0),
+            series.counts.last().copied().unwrap_or(0),
+            change,
+            width = periods.max(5)
+        );
+    }
+
+    outln!();
+    outln!("{}", style::bold("Now"));
+    for (label, groups) in [
+        ("servers", &composition.servers_by_provider),
+        ("projects", &composition.projects_by_status),
+        ("domains", &composition.domains_by_type),
+    ] {
+        let groups: Vec<String> = groups
+            .iter()
+            .map(|share| format!("{} {}", share.name, share.count))
+            .collect();
+        outln!(
+            "  {:<12} {}",
+            label,
+            if groups.is_empty() {
+                style::dim("none")
+            } else {
+                groups.join(", ")
+            }
+        );
+    }
+
+    Ok(())
+}
+
+/// Milliseconds as `850ms` or `1.2s`
+fn format_ms(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
@@ -0,0 +1,88 @@
+//! Status overview handler
+
+use super::monitor::describe;
+use super::vpn::by_interface;
+use crate::style;
+use chrono::Utc;
+use pctrl_core::monitor::liveness;
+use pctrl_core::quota;
+use pctrl_core::vpn::{TunnelState, Tunnels};
+use pctrl_database::Database;
+
+pub async fn handle(db: &Database) -> anyhow::Result<()> {
+    let servers = db.list_servers().await?;
+    let rows = [
+        ("Projects", db.list_projects().await?.len()),
+        ("Servers", servers.len()),
+        ("Domains", db.list_domains().await?.len()),
+        ("Databases", db.list_database_credentials().await?.len()),
+        ("Scripts", db.list_scripts().await?.len()),
+    ];
+
+    outln!("Status:");
+    outln!();
+    for (label, count) in rows {
+        outln!("  {:<12} {}", label, count);
+    }
+    outln!();
+
+    let state = db.get_monitor_state().await?;
+    outln!("  {}", describe(liveness(state.as_ref(), Utc::now())));
+
+    // Servers behind a VPN that's down aren't failures, just unreachable
+    let tunnels = Tunnels::detect(&servers).await;
+    for (interface, names) in by_interface(&servers) {
+        if tunnels.state(interface) == TunnelState::NotConnected {
+            outln!(
+                "  {}",
+                style::warning_text(&format!(
+                    "◐ VPN {} not connected: {}",
+                    interface,
+                    names.join(", ")
+                ))
+            );
+        }
+    }
+
+    // Servers at 90% of a quota or beyond
+    let containers = db.list_containers().await?;
+    for server in &servers {
+        let allocation = quota::allocation(
+            containers
+                .iter()
+                .filter(|c| c.server_id == server.id && quota::is_running(c)),
+        );
+        let near: Vec<String> = quota::usage(server, &allocation)
+            .into_iter()
+            .filter(|u| u.near())
+            .map(|u| u.to_string())
+            .collect();
+        if !near.is_empty() {
+            outln!(
+                "  {}",
+                style::warning_text(&format!(
+                    "▲ {} near quota: {}",
+                    server.name,
+                    near.join(", ")
+                ))
+            );
+        }
+    }
+
+    let windows = db.list_maintenance_windows().await?;
+    if !windows.is_empty() {
+        outln!();
+        for window in windows {
+            let name = db
+                .get_project(&window.project_id)
+                .await?
+                .map_or(window.project_id.clone(), |p| p.name);
+            outln!(
+                "  {}",
+                style::warning_text(&format!("🔧 {}: {}", name, window.banner(Utc::now())))
+            );
+        }
+    }
+
+    Ok(())
+}
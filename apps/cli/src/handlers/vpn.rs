@@ -0,0 +1,81 @@
+//! VPN command handler
+
+use super::hints;
+use crate::{style, VpnCommands};
+use pctrl_core::hints::{Event, Listing};
+use pctrl_core::vpn::{TunnelState, Tunnels};
+use pctrl_core::Server;
+use pctrl_database::Database;
+use std::collections::BTreeMap;
+
+pub async fn handle(command: VpnCommands, db: &Database) -> anyhow::Result<()> {
+    match command {
+        VpnCommands::Status => status(db).await,
+    }
+}
+
+async fn status(db: &Database) -> anyhow::Result<()> {
+    let servers = db.list_servers().await?;
+    let dependents = by_interface(&servers);
+    if dependents.is_empty() {
+        outln!("No server requires a VPN.");
+        hints::show(db, Event::Empty(Listing::VpnServers)).await?;
+        return Ok(());
+    }
+
+    let tunnels = Tunnels::detect(&servers).await;
+    outln!("VPN interfaces ({}):", dependents.len());
+    outln!();
+    for (interface, names) in &dependents {
+        let state = tunnels.state(interface);
+        let text = format!("{} {}", marker(state), state);
+        let state = match state {
+            TunnelState::Connected => style::success_text(&text),
+            TunnelState::NotConnected => style::warning_text(&text),
+            TunnelState::Unknown => style::dim(&text),
+        };
+        outln!("  {:<12} {}", interface, state);
+        outln!("    {}", style::dim(&names.join(", ")));
+    }
+    if dependents
+        .keys()
+        .any(|i| tunnels.state(i) == TunnelState::Unknown)
+    {
+        noteln!();
+        noteln!(
+            "{}",
+            style::dim(
+                "Couldn't read the local network interfaces; these servers are contacted as usual"
+            )
+        );
+    }
+
+    Ok(())
+}
+
+fn marker(state: TunnelState) -> &'static str {
+    match state {
+        TunnelState::Connected => "●",
+        TunnelState::NotConnected => "○",
+        TunnelState::Unknown => "?",
+    }
+}
+
+/// Server names per VPN interface
+pub(crate) fn by_interface(servers: &[Server]) -> BTreeMap<&str, Vec<&str>> {
+    let mut map: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for server in servers {
+        if let Some(interface) = &server.requires_vpn {
+            map.entry(interface).or_default().push(&server.name);
+        }
+    }
+    map
+}
+
+/// Why `server` can't be reached right now ("VPN wg0 not connected"), so
+/// callers skip the connection attempt
+pub(crate) async fn vpn_blocked(server: &Server) -> Option<String> {
+    Tunnels::detect(std::slice::from_ref(server))
+        .await
+        .blocked(server)
+}
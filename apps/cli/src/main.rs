@@ -1,11 +1,20 @@
-use clap::{Parser, Subcommand};
-use pctrl_core::Mode;
+use chrono::{DateTime, Utc};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use pctrl_core::hooks::HookRunner;
+use pctrl_core::hyperlink::{self, HyperlinkMode};
+use pctrl_core::parse;
+use pctrl_core::{settings, Mode};
 use pctrl_database::Database;
+use std::io::IsTerminal;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-mod handlers;
+#[macro_use]
 mod style;
+
+mod handlers;
+mod shell;
 mod tui;
 
 /// Default database path
@@ -16,6 +25,14 @@ fn default_db_path() -> PathBuf {
         .join("pctrl.db")
 }
 
+/// Directory with hook executables (`<config dir>/pctrl/hooks/<event>/`)
+fn hooks_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("pctrl")
+        .join("hooks")
+}
+
 #[derive(Parser)]
 #[command(name = "pctrl")]
 #[command(about = "Mission Control for Self-Hosters & Indie Devs", long_about = None)]
@@ -29,6 +46,26 @@ struct Cli {
     #[arg(long, global = true)]
     db: Option<PathBuf>,
 
+    /// Use a --db file that holds another application's tables (adds pctrl's tables to it)
+    #[arg(long, global = true)]
+    adopt_db: bool,
+
+    /// Write to entities even if someone else holds a lock on them
+    #[arg(long, global = true)]
+    override_lock: bool,
+
+    /// Print exact values (bytes, seconds, timestamps) instead of humanized ones
+    #[arg(long, global = true)]
+    raw: bool,
+
+    /// Only print results and errors (no banners, hints or success lines)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Plain ASCII output without colors or symbols (also set by NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -65,6 +102,12 @@ pub enum Commands {
         command: ServerCommands,
     },
 
+    /// Copy files and directories to and from servers over SFTP
+    Ssh {
+        #[command(subcommand)]
+        command: SshCommands,
+    },
+
     /// Domain management
     Domain {
         #[command(subcommand)]
@@ -84,12 +127,861 @@ pub enum Commands {
         command: ScriptCommands,
     },
 
+    /// systemd services on servers (non-containerized apps)
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommands,
+    },
+
+    /// Logs of containers and services
+    Logs {
+        #[command(subcommand)]
+        command: LogsCommands,
+    },
+
     /// Credential management (SSH keys, API tokens, etc.)
     #[command(alias = "cred")]
     Credential {
         #[command(subcommand)]
         command: CredentialCommands,
     },
+
+    /// This installation's keypair for receiving secrets
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommands,
+    },
+
+    /// Send credentials and databases to other installations, encrypted
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommands,
+    },
+
+    /// Lock an entity against edits by others (advisory)
+    #[command(args_conflicts_with_subcommands = true)]
+    Lock {
+        #[command(subcommand)]
+        command: Option<LockCommands>,
+        /// Entity type: project, server, domain, database, script, credential
+        entity_type: Option<String>,
+        /// Entity name or ID
+        name: Option<String>,
+        /// Why the entity is locked (e.g., "migrating to new DC")
+        #[arg(short, long)]
+        reason: Option<String>,
+        #[arg(
+            long,
+            default_value = "8h",
+            value_parser = parse::duration,
+            help = parse::help("Lock lifetime", parse::DURATION_FORMATS)
+        )]
+        ttl: chrono::Duration,
+    },
+
+    /// Docker hosts: sync and inspect network topology
+    Docker {
+        #[command(subcommand)]
+        command: DockerCommands,
+    },
+
+    /// Containers (from synced Docker hosts)
+    Container {
+        #[command(subcommand)]
+        command: ContainerCommands,
+    },
+
+    /// Docker Compose stacks (from discovered containers)
+    Compose {
+        #[command(subcommand)]
+        command: ComposeCommands,
+    },
+
+    /// Coolify instances
+    Coolify {
+        #[command(subcommand)]
+        command: CoolifyCommands,
+    },
+
+    /// Git repositories
+    Git {
+        #[command(subcommand)]
+        command: GitCommands,
+    },
+
+    /// Audit log of entity changes
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+
+    /// Hook executables run after pctrl events
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+
+    /// Local statistics about your own pctrl usage (never leaves this machine)
+    #[command(args_conflicts_with_subcommands = true)]
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommands>,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            help = parse::help("Only include commands since", parse::AGE_FORMATS)
+        )]
+        since: Option<DateTime<Utc>>,
+        /// Number of entries per list
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Named snapshots of the whole inventory (secrets excluded)
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Server monitoring
+    Monitor {
+        #[command(subcommand)]
+        command: MonitorCommands,
+    },
+
+    /// VPN tunnels servers depend on (`server edit --requires-vpn`)
+    Vpn {
+        #[command(subcommand)]
+        command: VpnCommands,
+    },
+
+    /// Overview of resources and the monitor
+    Status,
+
+    /// Find broken references and case-duplicate names, and repair them
+    Doctor {
+        /// Apply the repairs the check is sure about
+        #[arg(long, conflicts_with = "interactive")]
+        fix: bool,
+        /// Decide each finding: relink, clear, delete the link or skip
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Show the report of the last run of a fan-out command
+    LastRun {
+        /// Command, e.g. `project start` (omit to list all)
+        command: Vec<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compact health badge for shell prompts (cached data only, always exits 0)
+    PromptSegment {
+        /// Output format: powerline, plain, json
+        #[arg(short, long, default_value = "plain")]
+        format: String,
+        /// Print the prompt snippet for a shell instead: zsh, bash, fish
+        #[arg(long, value_name = "SHELL")]
+        init: Option<String>,
+    },
+
+    /// Export the whole setup (YAML/JSON), or servers for other tools
+    /// (Ansible, OpenSSH)
+    #[command(args_conflicts_with_subcommands = true)]
+    Export {
+        #[command(subcommand)]
+        command: Option<ExportCommands>,
+        /// Write the setup to this file (.json for JSON, YAML otherwise)
+        /// instead of stdout
+        #[arg(long)]
+        file: Option<String>,
+        /// Include credential data, database passwords and connection strings
+        #[arg(long)]
+        include_secrets: bool,
+        /// Encrypt the file with a passphrase, like backup --encrypt
+        #[arg(long, requires = "file")]
+        encrypt: bool,
+    },
+
+    /// Bring in a setup written by `pctrl export`
+    Import {
+        /// File from `pctrl export --file` (YAML, JSON or encrypted), or a
+        /// hand-written `.toml` document with includes and variables
+        file: PathBuf,
+        /// Set a variable of a `.toml` document, over its `[vars]`
+        /// (repeatable)
+        #[arg(
+            long = "var",
+            value_name = "KEY=VALUE",
+            value_parser = pctrl_core::document::parse_var
+        )]
+        vars: Vec<(String, String)>,
+        /// Add what is missing and leave entities that differ as they are
+        /// (default without a terminal; at one, each difference is asked
+        /// about)
+        #[arg(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Make this database match the file: differing entities are
+        /// overwritten, ones not in the file removed (servers to the trash)
+        #[arg(long)]
+        replace: bool,
+        /// Show what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a snapshot of the whole database
+    Backup {
+        /// Target file or directory (default: backups/ next to the database)
+        #[arg(long)]
+        to: Option<PathBuf>,
+        /// Encrypt with a passphrase into a .pctrlbak file
+        #[arg(long)]
+        encrypt: bool,
+        /// Afterwards, delete all but the N newest timestamped backups in
+        /// the target directory
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        keep: Option<u32>,
+    },
+
+    /// Replace the database with a backup (plain or .pctrlbak); the current
+    /// one is kept in backups/ first
+    Restore {
+        /// Backup file
+        path: PathBuf,
+    },
+
+    /// Merge another pctrl database into this one
+    Merge {
+        /// The other database file (opened read-only)
+        other: PathBuf,
+        /// Resolve conflicts without asking: local, other, newer
+        #[arg(long)]
+        prefer: Option<pctrl_core::merge::Prefer>,
+        /// Show what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// User settings (e.g., TUI theme)
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Release an advisory lock
+    Unlock {
+        /// Entity type: project, server, domain, database, script, credential
+        entity_type: String,
+        /// Entity name or ID
+        name: String,
+        /// Release a lock held by someone else
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Search every entity by name and details (host, provider, command...)
+    Find {
+        /// Text to look for, case-insensitive
+        query: String,
+        /// Only these types, comma-separated: project, server, domain, database, script, credential
+        #[arg(short = 't', long = "type", value_delimiter = ',')]
+        types: Vec<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the entity a short ref (srv-3fk2), ID or name stands for
+    Resolve {
+        /// Short ref, ID or name
+        input: String,
+        /// Only entities of this type: project, server, domain, database, script, credential
+        #[arg(short = 't', long = "type")]
+        entity_type: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Interactive shell with an optional project context
+    Shell {
+        /// Start with this project selected (same as `use <project>`)
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Demo data, database reset and anonymized copies for screenshots,
+    /// testing and bug reports
+    #[command(hide = true)]
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommands,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// AUDIT COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List recent entity changes
+    List {
+        /// Only show changes to this entity type
+        #[arg(short = 't', long = "type")]
+        entity_type: Option<String>,
+        /// Only show changes to this entity (name or ID, requires --type)
+        #[arg(short, long, requires = "entity_type")]
+        name: Option<String>,
+        /// Maximum number of entries
+        #[arg(short, long, default_value = "20")]
+        limit: i64,
+        /// Show what changed in each update
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// DOCKER COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum DockerCommands {
+    /// Fetch networks, container attachments and published ports of a host
+    Sync {
+        /// Docker host ID (default: first configured host, else local socket)
+        host: Option<String>,
+        /// Add domains found in proxy labels that aren't known yet
+        #[arg(long)]
+        create_domains: bool,
+        /// Server the host runs on, set on linked domains (default: matched by name or address)
+        #[arg(long)]
+        server: Option<String>,
+    },
+    /// List a host's networks with their member containers
+    Networks {
+        /// Docker host ID (default: first configured host, else local socket)
+        host: Option<String>,
+    },
+    /// Add a Docker host
+    ///
+    /// URLs: unix:///var/run/docker.sock, tcp://host:2375, https://host:2376
+    /// (client certificate from --tls-* or ~/.docker) or ssh://user@host
+    /// (tunnelled through the system ssh).
+    Add {
+        /// Host name
+        name: String,
+        /// Docker API URL
+        #[arg(short, long, default_value = "unix:///var/run/docker.sock")]
+        url: String,
+        /// CA certificate file (https://)
+        #[arg(long, requires_all = ["tls_cert", "tls_key"])]
+        tls_ca: Option<String>,
+        /// Client certificate file (https://)
+        #[arg(long, requires_all = ["tls_ca", "tls_key"])]
+        tls_cert: Option<String>,
+        /// Client key file (https://)
+        #[arg(long, requires_all = ["tls_ca", "tls_cert"])]
+        tls_key: Option<String>,
+    },
+    /// Check that Docker hosts answer
+    Check {
+        /// Docker host ID or name (default: all configured hosts)
+        host: Option<String>,
+    },
+    /// Restart containers
+    Restart {
+        /// Containers ([host/]name)
+        #[arg(required = true)]
+        containers: Vec<String>,
+        /// Restart containers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Remove containers; running ones only with --force
+    Remove {
+        /// Containers ([host/]name)
+        #[arg(required = true)]
+        containers: Vec<String>,
+        /// Kill running containers before removing them
+        #[arg(short, long)]
+        force: bool,
+        /// Remove containers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Pause containers
+    Pause {
+        /// Containers ([host/]name)
+        #[arg(required = true)]
+        containers: Vec<String>,
+        /// Pause containers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Resume paused containers
+    Unpause {
+        /// Containers ([host/]name)
+        #[arg(required = true)]
+        containers: Vec<String>,
+    },
+    /// Print a container's logs; stderr lines go to stderr
+    Logs {
+        /// Container ([host/]name)
+        container: String,
+        /// Only the last N lines
+        #[arg(short = 'n', long)]
+        tail: Option<u32>,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            help = parse::help("Only lines since", parse::AGE_FORMATS)
+        )]
+        since: Option<DateTime<Utc>>,
+        /// Start every line with its timestamp
+        #[arg(short, long)]
+        timestamps: bool,
+        /// Keep printing new lines until Ctrl-C
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Show CPU, memory, network and block I/O usage of running containers
+    Stats {
+        /// Docker host ID or name
+        host: String,
+        /// Only this container (default: all running containers)
+        container: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ContainerCommands {
+    /// Check whether one container can reach another
+    Reach {
+        /// Source container ([host/]name)
+        from: String,
+        /// Target container ([host/]name)
+        to: String,
+    },
+    /// Execute a command inside a container
+    Exec {
+        /// Container ([host/]name)
+        container: String,
+        /// Command to execute
+        command: String,
+        /// Run in containers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ComposeCommands {
+    /// List recorded compose stacks and their state
+    List {
+        /// Only the stacks of this server (name or ID)
+        #[arg(long)]
+        server: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the containers of a stack
+    Ps {
+        /// Server name or ID
+        server: String,
+        /// Compose project name, or "(standalone)"
+        stack: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// HOOKS COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum HooksCommands {
+    /// List discovered hooks and their last run
+    List,
+    /// Run the hooks of an event now (payload JSON from stdin, or --sample)
+    Run {
+        /// Event name (e.g., server.created, script.finished)
+        event: String,
+        /// Feed a synthetic payload instead of reading stdin
+        #[arg(long)]
+        sample: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// STATS COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum StatsCommands {
+    /// Delete all recorded usage
+    Reset,
+    /// Stop recording usage
+    Disable,
+    /// Resume recording usage
+    Enable,
+    /// Entity counts over time, from the audit log, and what exists now
+    Growth {
+        #[arg(
+            long,
+            default_value = "1y",
+            value_parser = parse::age,
+            help = parse::help("Start of the chart", parse::AGE_FORMATS)
+        )]
+        since: DateTime<Utc>,
+        /// Period length: day, week, month, year
+        #[arg(short, long, default_value = "month")]
+        granularity: pctrl_core::growth::Granularity,
+        /// Print the series as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// SNAPSHOT COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Snapshot the current inventory
+    Create {
+        /// Snapshot name (default: current date and time)
+        name: Option<String>,
+    },
+    /// List snapshots
+    List,
+    /// Show what changed from snapshot A to snapshot B (or to now)
+    Diff {
+        /// Older snapshot
+        from: String,
+        /// Newer snapshot (default: current inventory)
+        to: Option<String>,
+    },
+    /// Bring the inventory back to a snapshot
+    Restore {
+        /// Snapshot name
+        name: String,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Delete a snapshot
+    Remove {
+        /// Snapshot name
+        name: String,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// MONITOR COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum MonitorCommands {
+    /// Check all servers periodically (SSH port reachable)
+    Run {
+        #[arg(
+            short,
+            long,
+            value_parser = parse::duration,
+            help = parse::help("Time between cycles, default 5m", parse::DURATION_FORMATS)
+        )]
+        interval: Option<chrono::Duration>,
+        /// Run a single cycle and exit
+        #[arg(long)]
+        once: bool,
+        /// Print the per-server report of the cycle as JSON (with --once)
+        #[arg(long, requires = "once")]
+        json: bool,
+        /// When to exit non-zero: any (a server is down), all (every one is down), none
+        #[arg(long, default_value = "any", requires = "once")]
+        fail_on: pctrl_core::fanout::FailOn,
+    },
+    /// Show whether the monitor is still running
+    Status,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// COOLIFY COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum CoolifyCommands {
+    /// List Coolify instances
+    Instances,
+    /// Add a Coolify instance (or replace one with the same ID)
+    Add {
+        /// Display name; the ID is derived from it
+        name: String,
+        /// Base URL, e.g. https://coolify.example.com
+        #[arg(short, long)]
+        url: String,
+        /// API token
+        #[arg(short = 't', long)]
+        token: String,
+        /// Save without checking that the instance accepts the token
+        #[arg(long)]
+        skip_verify: bool,
+    },
+    /// Check that an instance is reachable and accepts its token
+    Check {
+        /// Instance ID or name
+        id: String,
+    },
+    /// Show an instance; the API key is masked
+    Show {
+        /// Instance ID or name
+        id: String,
+        /// Print the full API key
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Remove an instance
+    Remove {
+        /// Instance ID or name
+        id: String,
+    },
+    /// List the applications and services on an instance
+    Apps {
+        /// Instance ID or name
+        id: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Deploy an application or service
+    Deploy {
+        /// Instance ID or name
+        id: String,
+        /// Application or service UUID (see `coolify apps`)
+        uuid: String,
+        /// Rebuild without the build cache
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Show a deployment's status and the end of its build log
+    Deployment {
+        /// Instance ID or name
+        id: String,
+        /// Deployment UUID, as printed by `coolify deploy`
+        deployment: String,
+        /// Log lines to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// GIT COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum GitCommands {
+    /// List Git repositories
+    Repos,
+    /// Add a Git repository (or replace one with the same ID)
+    Add {
+        /// Display name; the ID is derived from it
+        name: String,
+        /// Path to the working tree
+        #[arg(short, long)]
+        path: String,
+        /// Credential for pushing, tried after the SSH agent: an SSH key,
+        /// basic auth or an API token
+        #[arg(short, long)]
+        credential: Option<String>,
+    },
+    /// Remove a repository (the working tree is left alone)
+    Remove {
+        /// Repository ID or name
+        id: String,
+    },
+    /// Branch, ahead/behind, uncommitted changes and last commit
+    Status {
+        /// Repository ID or name
+        #[arg(required_unless_present = "all")]
+        repo: Option<String>,
+        /// Every repository
+        #[arg(long, conflicts_with = "repo")]
+        all: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Push to origin: the current branch, or what the flags name
+    Push {
+        /// Repository ID or name
+        repo: String,
+        /// Push all tags
+        #[arg(long)]
+        tags: bool,
+        /// Push this branch
+        #[arg(short, long)]
+        branch: Option<String>,
+    },
+    /// Markdown changelog of the commits since the previous tag; printed
+    /// and saved for the version
+    Changelog {
+        /// Repository ID or name
+        repo: String,
+        /// Start after this tag or commit (default: the previous tag, or
+        /// the first commit)
+        #[arg(long)]
+        from: Option<String>,
+        /// End at this tag or commit
+        #[arg(long, default_value = "HEAD")]
+        to: String,
+        /// Group commits by Conventional Commits type (feat, fix, chore)
+        #[arg(long)]
+        conventional: bool,
+    },
+    /// Saved changelogs, newest version first
+    Changelogs {
+        /// Repository ID or name
+        repo: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Tag HEAD as a release (push it with `git push --tags`)
+    Release {
+        /// Repository ID or name
+        repo: String,
+        /// Tag name, e.g. v1.3.0
+        tag: String,
+        /// Tag message
+        #[arg(short, long, required_unless_present = "changelog")]
+        message: Option<String>,
+        /// Use the changelog since the previous tag as the message (and
+        /// save it)
+        #[arg(long, conflicts_with = "message")]
+        changelog: bool,
+        /// Group the changelog by Conventional Commits type
+        #[arg(long, requires = "changelog")]
+        conventional: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// VPN COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum VpnCommands {
+    /// List VPN interfaces, whether they're up and which servers need them
+    Status,
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// EXPORT COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum ExportCommands {
+    /// Ansible YAML inventory of all servers
+    Ansible {
+        /// Group hosts by provider, type or project
+        #[arg(short, long, default_value = "provider")]
+        group_by: String,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// OpenSSH Host entries for all servers
+    SshConfig {
+        /// Write to this file, replacing only the pctrl-managed section
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// One table as CSV, JSON or NDJSON (projects, servers, domains,
+    /// databases, scripts, credentials, services, links)
+    Table {
+        /// Table name
+        name: String,
+        /// Output format: csv, json, ndjson
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+        /// Columns to include, comma-separated (default: all but secrets)
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Vec<String>,
+        /// Only rows where a column has this value (key=value, repeatable)
+        #[arg(short = 'w', long = "where")]
+        filter: Vec<String>,
+        /// Include columns holding passwords, tokens and connection strings
+        #[arg(long)]
+        show_secrets: bool,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<String>,
+        /// List the table's columns instead of exporting
+        #[arg(long)]
+        list_columns: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// CONFIG COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// List all settings with their current values
+    List,
+    /// Show one setting
+    Get {
+        /// Setting key (e.g., tui_theme)
+        key: String,
+    },
+    /// Change a setting
+    Set {
+        /// Setting key (e.g., tui_theme)
+        key: String,
+        /// New value
+        value: String,
+    },
+    /// Reset a setting to its default
+    Unset {
+        /// Setting key
+        key: String,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// LOCK COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum LockCommands {
+    /// List active locks
+    List,
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -110,9 +1002,12 @@ pub enum ProjectCommands {
         /// Tech stack (comma-separated, e.g., "rust,tauri,react")
         #[arg(short, long)]
         stack: Option<String>,
-        /// Status: dev, staging, live, archived
-        #[arg(long, default_value = "dev")]
-        status: String,
+        /// Status: dev, staging, live, archived [default: dev]
+        #[arg(long)]
+        status: Option<String>,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
     },
     /// Show project details
     Show {
@@ -124,17 +1019,188 @@ pub enum ProjectCommands {
         /// Project name or ID
         name: String,
     },
+    /// Append to the project's ops journal, or show it
+    Log {
+        /// Project name or ID
+        name: String,
+        /// What happened
+        #[arg(required_unless_present = "show")]
+        text: Option<String>,
+        /// List the journal, newest first
+        #[arg(long, conflicts_with_all = ["text", "amend"])]
+        show: bool,
+        /// Entries to list
+        #[arg(long, default_value_t = 20, requires = "show")]
+        limit: i64,
+        /// Correct an earlier entry (the original stays)
+        #[arg(long, value_name = "ENTRY")]
+        amend: Option<i64>,
+    },
     /// Link a resource to a project
     Link {
         /// Project name or ID
         project: String,
-        /// Resource type: server, container, database, domain, script
+        /// Resource type: server, container, database, domain, coolify, script
         resource_type: String,
-        /// Resource ID
+        /// Resource ID (coolify: <instance>/<application or service UUID>)
         resource_id: String,
         /// Role description (e.g., "production_db", "staging_server")
         #[arg(short, long)]
         role: Option<String>,
+        /// Start order for `project start` (lower starts first)
+        #[arg(long)]
+        order: Option<i32>,
+    },
+    /// Set the start order of a linked resource (omit to clear)
+    SetOrder {
+        /// Resource link ID
+        link_id: String,
+        /// Start order (lower starts first)
+        order: Option<i32>,
+    },
+    /// Start the project's containers in start order
+    Start {
+        /// Project name or ID
+        project: String,
+        /// Docker host ID (default: first configured host, else local socket)
+        #[arg(long)]
+        host: Option<String>,
+        #[arg(
+            long,
+            default_value = "60s",
+            value_parser = parse::duration_or_secs,
+            help = parse::help("How long to wait for each phase to become running/healthy", parse::DURATION_FORMATS)
+        )]
+        timeout: chrono::Duration,
+        /// Refuse to start when a server quota would be exceeded (default: warn)
+        #[arg(long)]
+        enforce_quotas: bool,
+        /// Print the per-container report as JSON
+        #[arg(long)]
+        json: bool,
+        /// When to exit non-zero: any (a container failed), all (every one failed), none
+        #[arg(long, default_value = "any")]
+        fail_on: pctrl_core::fanout::FailOn,
+    },
+    /// Stop the project's containers in reverse start order
+    Stop {
+        /// Project name or ID
+        project: String,
+        /// Docker host ID (default: first configured host, else local socket)
+        #[arg(long)]
+        host: Option<String>,
+        #[arg(
+            long,
+            default_value = "60s",
+            value_parser = parse::duration_or_secs,
+            help = parse::help("How long to wait for each phase to stop", parse::DURATION_FORMATS)
+        )]
+        timeout: chrono::Duration,
+        /// Print the per-container report as JSON
+        #[arg(long)]
+        json: bool,
+        /// When to exit non-zero: any (a container failed), all (every one failed), none
+        #[arg(long, default_value = "any")]
+        fail_on: pctrl_core::fanout::FailOn,
+        /// Stop a Live project without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Print the project's resources as a Graphviz graph (with container networks)
+    Graph {
+        /// Project name or ID
+        project: String,
+    },
+    /// Check the project's servers, containers, databases, domains and git
+    /// repos at once
+    ///
+    /// Exits with 1 when a check failed (see --fail-on).
+    Status {
+        /// Project name or ID
+        project: String,
+        #[arg(
+            long,
+            default_value = "5s",
+            value_parser = parse::duration_or_secs,
+            help = parse::help("How long each check may take", parse::DURATION_FORMATS)
+        )]
+        timeout: chrono::Duration,
+        /// Print the per-resource report as JSON
+        #[arg(long)]
+        json: bool,
+        /// When to exit non-zero: any (a check failed), all (every one failed), none
+        #[arg(long, default_value = "any")]
+        fail_on: pctrl_core::fanout::FailOn,
+    },
+    /// Pack the project's operational state into one .tar.gz for disaster
+    /// recovery: overview, inventory, proxy routes and journal
+    Bundle {
+        /// Project name or ID
+        #[arg(required_unless_present = "verify")]
+        project: Option<String>,
+        /// Output file [default: <project>-bundle-<date>.tar.gz]
+        #[arg(short, long, conflicts_with = "verify")]
+        out: Option<PathBuf>,
+        /// Include secrets: database passwords and the .env render
+        #[arg(long, conflicts_with = "verify")]
+        include_secrets: bool,
+        /// Include the latest verified backup of the whole database
+        #[arg(long, conflicts_with = "verify")]
+        include_artifacts: bool,
+        /// Check a bundle's files against its manifest instead
+        #[arg(long, value_name = "FILE", conflicts_with = "project")]
+        verify: Option<PathBuf>,
+    },
+    /// Run a command on the project's server via SSH
+    Exec {
+        /// Project name or ID
+        project: String,
+        /// Linked server to run on (default: the deploy server, else the
+        /// one linked as production_server, else the only one)
+        #[arg(long, conflicts_with = "all")]
+        server: Option<String>,
+        /// Run on every linked server in turn, output prefixed with its name
+        #[arg(long)]
+        all: bool,
+        /// Run on servers of a Live project without asking
+        #[arg(long)]
+        allow_live: bool,
+        /// Command to run, after `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Run the project's pre-flight checks (servers, databases, health URL)
+    Preflight {
+        /// Project name or ID
+        project: String,
+    },
+    /// Configure the project's pre-flight checks
+    SetPreflight {
+        /// Project name or ID
+        project: String,
+        /// URL that must answer with 2xx before a deploy
+        #[arg(long, conflicts_with = "no_health_url")]
+        health_url: Option<String>,
+        /// Remove the health URL
+        #[arg(long)]
+        no_health_url: bool,
+        /// Checks that only warn instead of blocking: server, database, health
+        #[arg(long, value_delimiter = ',')]
+        advisory: Vec<String>,
+        /// Checks that block the deploy again
+        #[arg(long, value_delimiter = ',')]
+        blocking: Vec<String>,
+    },
+    /// Trigger the project's linked Coolify deployments after pre-flight checks
+    ///
+    /// Exits with 3 when pre-flight refused the deploy (nothing triggered)
+    /// and 4 when a triggered deployment failed.
+    Deploy {
+        /// Project name or ID
+        project: String,
+        /// Deploy even if blocking checks fail
+        #[arg(long)]
+        skip_preflight: bool,
     },
     /// Unlink a resource from a project
     Unlink {
@@ -143,6 +1209,122 @@ pub enum ProjectCommands {
         /// Resource link ID
         link_id: String,
     },
+    /// Put the project into maintenance for a while
+    ///
+    /// Sets the status to maintenance and pauses monitor alerts for the
+    /// project's servers. The previous status comes back when the window
+    /// expires (checked by the monitor and by every pctrl run).
+    Maintenance {
+        /// Project name or ID
+        project: String,
+        #[arg(
+            long = "for",
+            value_name = "DURATION",
+            required_unless_present = "end",
+            conflicts_with = "end",
+            value_parser = parse::duration,
+            help = parse::help("How long the window lasts", parse::DURATION_FORMATS)
+        )]
+        duration: Option<chrono::Duration>,
+        /// What the maintenance is for
+        #[arg(long, conflicts_with = "end")]
+        reason: Option<String>,
+        /// End the window now and restore the previous status
+        #[arg(long)]
+        end: bool,
+    },
+    /// Change a project's details or its deploy settings for `project ship`
+    #[command(visible_alias = "update")]
+    Edit {
+        /// Project name or ID
+        project: String,
+        /// Status: dev, staging, live, archived
+        #[arg(long)]
+        status: Option<String>,
+        /// Description (empty clears it)
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Tech stack, comma-separated (empty clears it)
+        #[arg(long)]
+        stack: Option<String>,
+        /// Git checkout on the server (e.g., /srv/acme)
+        #[arg(long, conflicts_with = "no_deploy_path")]
+        deploy_path: Option<String>,
+        /// Remove the deploy path
+        #[arg(long)]
+        no_deploy_path: bool,
+        /// Branch to ship [default: main]
+        #[arg(long)]
+        deploy_branch: Option<String>,
+        /// Server to ship to (default: the server linked as production_server, else the only one)
+        #[arg(long)]
+        deploy_server: Option<String>,
+    },
+    /// Pull, rebuild and restart the project's compose checkout over SSH
+    ///
+    /// Runs git pull, docker compose pull/build/up -d in the deploy path and
+    /// waits for the linked containers. Exits with 4 when a phase failed.
+    Ship {
+        /// Project name or ID
+        project: String,
+        /// Branch to ship instead of the configured one
+        #[arg(long)]
+        branch: Option<String>,
+        /// Skip `docker compose build`
+        #[arg(long)]
+        no_build: bool,
+        #[arg(
+            long,
+            default_value = "120s",
+            value_parser = parse::duration_or_secs,
+            help = parse::help("How long to wait for the linked containers", parse::DURATION_FORMATS)
+        )]
+        timeout: chrono::Duration,
+        /// Ship a Live project without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Create a new project from an existing one's structure
+    ///
+    /// Copies description, stack, color, icon and notes. Scripts, domains and
+    /// links to the same servers and databases only come along when asked
+    /// for; containers, git repos and Coolify links never do.
+    Clone {
+        /// Project to copy (name or ID)
+        source: String,
+        /// Name of the new project
+        new_name: String,
+        /// Copy the project's scripts (new IDs, same commands)
+        #[arg(long)]
+        include_scripts: bool,
+        /// Copy linked domains with this suffix on the first label (app.example.com → app-<suffix>.example.com)
+        #[arg(long, value_name = "SUFFIX")]
+        include_domains_as: Option<String>,
+        /// Link the same servers
+        #[arg(long)]
+        link_same_servers: bool,
+        /// Link the same database credentials (shared, not copied)
+        #[arg(long)]
+        link_same_databases: bool,
+        /// Show what would be created without creating it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the project as it was at an earlier time, from the audit log
+    HistoryView {
+        /// Project name or ID
+        name: String,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            required_unless_present = "timeline",
+            help = parse::help("Point in time", parse::AGE_FORMATS)
+        )]
+        at: Option<DateTime<Utc>>,
+        /// List every recorded change instead
+        #[arg(long, conflicts_with = "at")]
+        timeline: bool,
+    },
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -152,47 +1334,318 @@ pub enum ProjectCommands {
 #[derive(Subcommand)]
 pub enum ServerCommands {
     /// List all servers
-    List,
+    List {
+        /// Show trashed servers instead
+        #[arg(long)]
+        trashed: bool,
+        /// Columns to show, comma-separated: fields, meta.<key>, fact.<key>
+        /// (e.g., name,host,meta.owner,fact.distro)
+        #[arg(long, value_delimiter = ',', conflicts_with = "trashed")]
+        columns: Vec<String>,
+        /// Only servers where a column has (key=value) or hasn't
+        /// (key!=value) a value; "-" matches a missing value (repeatable)
+        #[arg(long, conflicts_with = "trashed")]
+        filter: Vec<String>,
+        /// Sort by these columns, comma-separated; prefix "-" for descending
+        #[arg(
+            long,
+            value_delimiter = ',',
+            allow_hyphen_values = true,
+            conflicts_with = "trashed"
+        )]
+        sort: Vec<String>,
+    },
     /// Add a new server
     Add {
         /// Server name
         name: String,
-        /// Server host (IP or hostname)
-        host: String,
-        /// Server type: vps, dedicated, local, cloud
-        #[arg(short = 't', long, default_value = "vps")]
-        server_type: String,
-        /// Provider (e.g., hetzner, aws, digitalocean)
-        #[arg(short, long)]
-        provider: Option<String>,
-        /// Credential name/ID for SSH access
-        #[arg(short, long)]
-        credential: Option<String>,
-        /// Location (e.g., "Falkenstein, DE")
-        #[arg(short, long)]
-        location: Option<String>,
+        /// Server host (IP or hostname)
+        host: String,
+        /// Server type: vps, dedicated, local, cloud [default: vps]
+        #[arg(short = 't', long)]
+        server_type: Option<String>,
+        /// Provider (e.g., hetzner, aws, digitalocean)
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Credential name/ID for SSH access
+        #[arg(short, long)]
+        credential: Option<String>,
+        /// Location (e.g., "Falkenstein, DE")
+        #[arg(short, long)]
+        location: Option<String>,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
+    },
+    /// Show server details
+    Show {
+        /// Server name or ID
+        name: String,
+    },
+    /// Change a server's settings
+    #[command(visible_alias = "update")]
+    Edit {
+        /// Server name or ID
+        name: String,
+        /// Server host (IP or hostname)
+        #[arg(long)]
+        host: Option<String>,
+        /// Provider (empty clears it)
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Location (empty clears it)
+        #[arg(short, long)]
+        location: Option<String>,
+        /// Notes (empty clears them)
+        #[arg(long)]
+        notes: Option<String>,
+        /// VPN interface the server is only reachable through (e.g., wg0)
+        #[arg(long, value_name = "INTERFACE", conflicts_with = "no_vpn")]
+        requires_vpn: Option<String>,
+        /// The server is reachable without a VPN
+        #[arg(long)]
+        no_vpn: bool,
+        /// Soft cap on running containers
+        #[arg(long, value_name = "N")]
+        max_containers: Option<u32>,
+        /// Soft cap on the summed memory limits of running containers, in MB
+        #[arg(long, value_name = "MB")]
+        max_memory_mb: Option<u64>,
+        /// Remove both quotas
+        #[arg(long, conflicts_with_all = ["max_containers", "max_memory_mb"])]
+        no_quotas: bool,
+    },
+    /// Remove a server
+    Remove {
+        /// Server name or ID
+        name: String,
+        /// Remove even if other entries still reference it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show what references this server
+    Deps {
+        /// Server name or ID
+        name: String,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Append to the server's ops journal, or show it
+    Log {
+        /// Server name or ID
+        name: String,
+        /// What happened
+        #[arg(required_unless_present = "show")]
+        text: Option<String>,
+        /// List the journal, newest first
+        #[arg(long, conflicts_with_all = ["text", "amend"])]
+        show: bool,
+        /// Entries to list
+        #[arg(long, default_value_t = 20, requires = "show")]
+        limit: i64,
+        /// Correct an earlier entry (the original stays)
+        #[arg(long, value_name = "ENTRY")]
+        amend: Option<i64>,
+    },
+    /// Execute a command on the server via SSH
+    Exec {
+        /// Server name or ID
+        name: String,
+        /// Command to execute
+        command: String,
+        /// Run on servers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+        /// Forward the local SSH agent (runs through the system ssh binary)
+        #[arg(short = 'A', long, conflicts_with = "with_deploy_key")]
+        forward_agent: bool,
+        /// Install this SSH key credential as git deploy key for the
+        /// command's duration (use a read-only key)
+        #[arg(long, value_name = "CREDENTIAL")]
+        with_deploy_key: Option<String>,
+    },
+    /// Open an interactive shell on the server via SSH
+    #[command(alias = "connect")]
+    Shell {
+        /// Server name or ID
+        name: String,
+        /// Open on servers of Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Download a file over SFTP, resuming an interrupted download
+    Pull {
+        /// Server name or ID
+        name: String,
+        /// Remote file
+        remote: String,
+        /// Local file or directory (default: the remote file name, here)
+        local: Option<String>,
+        #[arg(
+            long,
+            value_parser = parse::rate,
+            help = parse::help("Bandwidth cap", parse::RATE_FORMATS)
+        )]
+        limit: Option<u64>,
+        /// Start over instead of resuming a partial download
+        #[arg(long)]
+        fresh: bool,
+        /// Check the size only, not the SHA-256
+        #[arg(long)]
+        no_checksum: bool,
+    },
+    /// Upload a file over SFTP, resuming an interrupted upload
+    Push {
+        /// Server name or ID
+        name: String,
+        /// Local file
+        local: String,
+        /// Remote file, or directory ending in '/'
+        remote: String,
+        #[arg(
+            long,
+            value_parser = parse::rate,
+            help = parse::help("Bandwidth cap", parse::RATE_FORMATS)
+        )]
+        limit: Option<u64>,
+        /// Start over instead of resuming a partial upload
+        #[arg(long)]
+        fresh: bool,
+        /// Check the size only, not the SHA-256
+        #[arg(long)]
+        no_checksum: bool,
+    },
+    /// Check server status (connectivity, uptime)
+    Status {
+        /// Server name or ID
+        name: String,
+    },
+    /// Forecast disk usage from `server status` samples
+    Forecast {
+        /// Server name or ID
+        name: String,
     },
-    /// Show server details
-    Show {
+    /// Record the server's containers, from its Docker host or via SSH
+    Discover {
         /// Server name or ID
         name: String,
+        /// Forget containers and compose stacks that are gone instead of
+        /// marking them
+        #[arg(long)]
+        prune: bool,
     },
-    /// Remove a server
-    Remove {
+    /// Discover the server's containers and show its compose stacks
+    Compose {
         /// Server name or ID
         name: String,
+        /// Forget stacks and containers that are gone
+        #[arg(long)]
+        prune: bool,
     },
-    /// Execute a command on the server via SSH
-    Exec {
+    /// Show collected host facts (distro, kernel, IPs, ports, ...)
+    Facts {
+        /// Server name or ID
+        #[arg(required_unless_present = "query")]
+        name: Option<String>,
+        /// Collect the facts again via SSH
+        #[arg(long)]
+        refresh: bool,
+        /// List servers with a matching fact instead (e.g., "distro=ubuntu")
+        #[arg(short, long, conflicts_with_all = ["name", "refresh"])]
+        query: Option<String>,
+    },
+    /// Show or change a server's metadata, usable as meta.<key> columns
+    Meta {
         /// Server name or ID
         name: String,
-        /// Command to execute
-        command: String,
+        /// Entries to set (key=value)
+        entries: Vec<String>,
+        /// Keys to remove (repeatable)
+        #[arg(long)]
+        unset: Vec<String>,
     },
-    /// Check server status (connectivity, uptime)
-    Status {
+    /// Restore a trashed server
+    Restore {
+        /// Server name or ID
+        name: String,
+    },
+    /// Compare servers against a cloud provider account
+    Reconcile {
+        /// Provider: hetzner
+        #[arg(short, long, default_value = "hetzner")]
+        provider: String,
+        /// API token credential name/ID for the provider
+        #[arg(short, long)]
+        credential: String,
+        /// Create servers for unmanaged instances
+        #[arg(long)]
+        import: bool,
+        /// Move stale servers to the trash
+        #[arg(long)]
+        trash: bool,
+        /// Update specs/location of matched servers from the provider
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Show the server as it was at an earlier time, from the audit log
+    HistoryView {
         /// Server name or ID
         name: String,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            required_unless_present = "timeline",
+            help = parse::help("Point in time", parse::AGE_FORMATS)
+        )]
+        at: Option<DateTime<Utc>>,
+        /// List every recorded change instead
+        #[arg(long, conflicts_with = "at")]
+        timeline: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// SSH COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum SshCommands {
+    /// Upload a file, or a directory with --recursive
+    Put {
+        /// Server name or ID
+        server: String,
+        /// Local file or directory
+        local: String,
+        /// Remote path, or directory ending in '/'
+        remote: String,
+        /// Copy directories with everything in them
+        #[arg(short, long)]
+        recursive: bool,
+        /// Replace files that already exist on the server
+        #[arg(short, long)]
+        force: bool,
+        #[arg(
+            long,
+            value_parser = parse::mode,
+            help = parse::help("Permissions of the uploaded files instead of their own", parse::MODE_FORMATS)
+        )]
+        mode: Option<i32>,
+    },
+    /// Download a file, or a directory with --recursive
+    Get {
+        /// Server name or ID
+        server: String,
+        /// Remote file or directory
+        remote: String,
+        /// Local path, or an existing directory to download into
+        local: String,
+        /// Copy directories with everything in them
+        #[arg(short, long)]
+        recursive: bool,
+        /// Replace files that already exist here
+        #[arg(short, long)]
+        force: bool,
     },
 }
 
@@ -208,15 +1661,15 @@ pub enum DomainCommands {
     Add {
         /// Domain name (e.g., app.example.com)
         domain: String,
-        /// Domain type: production, staging, dev
-        #[arg(short = 't', long, default_value = "production")]
-        domain_type: String,
+        /// Domain type: production, staging, dev [default: production]
+        #[arg(short = 't', long)]
+        domain_type: Option<String>,
         /// Server ID this domain points to
         #[arg(short, long)]
         server: Option<String>,
-        /// SSL enabled
-        #[arg(long, default_value = "true")]
-        ssl: bool,
+        /// SSL enabled: true, false [default: true]
+        #[arg(long)]
+        ssl: Option<bool>,
         /// SSL certificate expiry date (e.g., "2025-12-31")
         #[arg(long)]
         ssl_expiry: Option<String>,
@@ -226,16 +1679,156 @@ pub enum DomainCommands {
         /// Cloudflare DNS Record ID
         #[arg(long)]
         cloudflare_record: Option<String>,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
     },
     /// Show domain details
     Show {
         /// Domain name
         domain: String,
     },
+    /// Change a domain's type, server or SSL
+    #[command(visible_alias = "update")]
+    Edit {
+        /// Domain name
+        domain: String,
+        /// Domain type: production, staging, dev
+        #[arg(short = 't', long)]
+        domain_type: Option<String>,
+        /// Server name or ID this domain points to
+        #[arg(short, long, conflicts_with = "no_server")]
+        server: Option<String>,
+        /// The domain points to no server
+        #[arg(long)]
+        no_server: bool,
+        /// SSL enabled: true, false
+        #[arg(long)]
+        ssl: Option<bool>,
+    },
     /// Remove a domain
     Remove {
         /// Domain name
         domain: String,
+        /// Remove even if other entries still reference it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show what references this domain
+    Deps {
+        /// Domain name
+        domain: String,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Append to the domain's ops journal, or show it
+    Log {
+        /// Domain name
+        domain: String,
+        /// What happened
+        #[arg(required_unless_present = "show")]
+        text: Option<String>,
+        /// List the journal, newest first
+        #[arg(long, conflicts_with_all = ["text", "amend"])]
+        show: bool,
+        /// Entries to list
+        #[arg(long, default_value_t = 20, requires = "show")]
+        limit: i64,
+        /// Correct an earlier entry (the original stays)
+        #[arg(long, value_name = "ENTRY")]
+        amend: Option<i64>,
+    },
+    /// Check how far a DNS change has propagated across public resolvers
+    ///
+    /// Asks each resolver directly for the A/AAAA records and compares them
+    /// with the expected address (default: the linked server's).
+    Propagation {
+        /// Domain name (need not be in the inventory with --expect)
+        #[arg(required_unless_present = "all")]
+        domain: Option<String>,
+        /// Check every inventory domain with a linked server, a few queries
+        /// per resolver at a time
+        #[arg(long, conflicts_with_all = ["domain", "expect", "watch", "track"])]
+        all: bool,
+        /// With --all: queries running at once per resolver [default: 2]
+        #[arg(long)]
+        per_resolver: Option<usize>,
+        /// With --all: queries started per second overall, 0 for no limit
+        /// [default: 10]
+        #[arg(long)]
+        rate: Option<f64>,
+        /// Expected address(es), comma-separated
+        #[arg(long, value_delimiter = ',')]
+        expect: Vec<IpAddr>,
+        /// Resolvers to ask, comma-separated [default: 1.1.1.1,8.8.8.8,9.9.9.9]
+        #[arg(long, value_delimiter = ',')]
+        resolvers: Vec<IpAddr>,
+        /// Re-check every 30s until fully propagated
+        #[arg(long, conflicts_with = "track")]
+        watch: bool,
+        #[arg(
+            long,
+            default_value = "30m",
+            value_parser = parse::duration,
+            help = parse::help("Give up watching or tracking after", parse::DURATION_FORMATS)
+        )]
+        timeout: chrono::Duration,
+        /// Let `pctrl monitor run` keep checking until fully propagated
+        #[arg(long)]
+        track: bool,
+        /// Print JSON
+        #[arg(long, conflicts_with = "watch")]
+        json: bool,
+    },
+    /// Look up the domain's Cloudflare zone and DNS record and store their IDs
+    Sync {
+        /// Domain name
+        domain: String,
+        /// Cloudflare API token credential name/ID
+        #[arg(short, long)]
+        credential: String,
+    },
+    /// Create or update the domain's A/AAAA record on Cloudflare to point at a server
+    Point {
+        /// Domain name
+        domain: String,
+        /// Server name or ID (its host, or what the host resolves to)
+        server: String,
+        /// Cloudflare API token credential name/ID
+        #[arg(short, long)]
+        credential: String,
+        /// Proxy through Cloudflare: true, false [default: keep the record's setting]
+        #[arg(long)]
+        proxied: Option<bool>,
+        /// Only show the planned DNS change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move every domain under one base to another (app.example.com → app.example.io)
+    MigrateBase {
+        /// Current base, e.g. example.com
+        old_base: String,
+        /// New base, e.g. example.io
+        new_base: String,
+        /// Only show the planned renames
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show the domain as it was at an earlier time, from the audit log
+    HistoryView {
+        /// Domain name or ID
+        name: String,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            required_unless_present = "timeline",
+            help = parse::help("Point in time", parse::AGE_FORMATS)
+        )]
+        at: Option<DateTime<Utc>>,
+        /// List every recorded change instead
+        #[arg(long, conflicts_with = "at")]
+        timeline: bool,
     },
 }
 
@@ -278,12 +1871,33 @@ pub enum DatabaseCommands {
         /// Container ID (for dockerized databases)
         #[arg(long)]
         container: Option<String>,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
     },
     /// Show database credentials
     Show {
         /// Database name or ID
         name: String,
     },
+    /// Change a database's connection details
+    #[command(visible_alias = "update")]
+    Edit {
+        /// Database name or ID
+        name: String,
+        /// Database host (empty clears it)
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Database port
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Username (empty clears it)
+        #[arg(short, long)]
+        user: Option<String>,
+        /// Password (empty clears it)
+        #[arg(short = 'P', long)]
+        password: Option<String>,
+    },
     /// Get specific field (user, pass, url)
     Get {
         /// Database name or ID
@@ -291,10 +1905,50 @@ pub enum DatabaseCommands {
         /// Field to get: user, pass, url, host, port, database
         field: String,
     },
+    /// Print the connection URL, built from the fields unless one is stored
+    Url {
+        /// Database name or ID
+        name: String,
+        /// Leave the password out
+        #[arg(long)]
+        no_password: bool,
+    },
     /// Remove database credentials
     Remove {
         /// Database name or ID
         name: String,
+        /// Remove even if other entries still reference it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show what references this database
+    Deps {
+        /// Database name or ID
+        name: String,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the database as it was at an earlier time, from the audit log
+    HistoryView {
+        /// Database name or ID
+        name: String,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            required_unless_present = "timeline",
+            help = parse::help("Point in time", parse::AGE_FORMATS)
+        )]
+        at: Option<DateTime<Utc>>,
+        /// List every recorded change instead
+        #[arg(long, conflicts_with = "at")]
+        timeline: bool,
+    },
+    /// Show pctrl's own database file: schema, versions and the write lease
+    Info {
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -304,21 +1958,30 @@ pub enum DatabaseCommands {
 
 #[derive(Subcommand)]
 pub enum ScriptCommands {
-    /// List all scripts
-    List,
+    /// List scripts as a tree of their namespaces
+    List {
+        /// Only scripts in this namespace and the ones below it (e.g., backups/)
+        namespace: Option<String>,
+        /// One line per script with its full name
+        #[arg(long)]
+        flat: bool,
+    },
     /// Add a new script
     Add {
         /// Script name
         name: String,
         /// Command to execute
-        #[arg(short, long)]
-        command: String,
+        #[arg(short, long, required_unless_present_any = ["edit", "ensure"])]
+        command: Option<String>,
+        /// Write the (multi-line) command in $EDITOR
+        #[arg(long, conflicts_with = "command")]
+        edit: bool,
         /// Script description
         #[arg(short, long)]
         description: Option<String>,
-        /// Script type: ssh, local, docker
-        #[arg(short = 't', long, default_value = "local")]
-        script_type: String,
+        /// Script type: ssh, local, docker [default: local]
+        #[arg(short = 't', long)]
+        script_type: Option<String>,
         /// Server ID to run on (for ssh scripts)
         #[arg(short, long)]
         server: Option<String>,
@@ -334,12 +1997,73 @@ pub enum ScriptCommands {
         /// Mark as dangerous (requires confirmation)
         #[arg(long)]
         dangerous: bool,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
     },
     /// Show script details
     Show {
         /// Script name or ID
         name: String,
     },
+    /// Change a script's command or settings (dangerous scripts may need
+    /// approval for command changes)
+    #[command(visible_alias = "update")]
+    Edit {
+        /// Script name or ID
+        name: String,
+        /// New command (opens $EDITOR when omitted)
+        #[arg(short, long, conflicts_with = "edit")]
+        command: Option<String>,
+        /// Edit the command in $EDITOR
+        #[arg(long)]
+        edit: bool,
+        /// Mark as dangerous
+        #[arg(long, conflicts_with = "safe")]
+        dangerous: bool,
+        /// Remove the dangerous mark
+        #[arg(long)]
+        safe: bool,
+        /// Working directory for local runs (absolute or ~/...)
+        #[arg(long, value_name = "DIR", conflicts_with = "no_workdir")]
+        workdir: Option<String>,
+        /// Run in the current directory again
+        #[arg(long)]
+        no_workdir: bool,
+        /// Set an environment variable for local runs (repeatable)
+        #[arg(long, value_name = "KEY=VALUE", value_parser = pctrl_core::local_run::parse_assignment)]
+        env: Vec<(String, String)>,
+        /// Remove a variable set with --env (repeatable)
+        #[arg(long, value_name = "KEY")]
+        unset_env: Vec<String>,
+        /// Only let the script run at these times if it's dangerous, e.g.
+        /// "Mon-Thu 08:00-18:00" or "Sat,Sun 10:00-12:00 Europe/Berlin"
+        #[arg(long, value_name = "WINDOW", conflicts_with = "no_run_window")]
+        run_window: Option<String>,
+        /// Let the script run at any time again
+        #[arg(long)]
+        no_run_window: bool,
+        /// Server name or ID the script runs on
+        #[arg(short, long)]
+        server: Option<String>,
+    },
+    /// List changes to dangerous scripts awaiting approval
+    Pending,
+    /// Apply a pending change
+    ///
+    /// In two-person mode the approver must not be the author: pctrl
+    /// compares the user names and, on Unix, the real uid of both. That
+    /// keeps two accounts apart, not someone who controls both or can
+    /// write the database file.
+    Approve {
+        /// Revision number (from `script pending`)
+        id: i64,
+    },
+    /// Discard a pending change
+    Reject {
+        /// Revision number (from `script pending`)
+        id: i64,
+    },
     /// Run a script
     Run {
         /// Script name or ID
@@ -347,12 +2071,183 @@ pub enum ScriptCommands {
         /// Force run without confirmation (for dangerous scripts)
         #[arg(short, long)]
         force: bool,
+        /// Run against Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+        /// Pass pctrl's whole environment to a local script
+        #[arg(long)]
+        inherit_env: bool,
+        /// Run a dangerous script outside its run window (asks to type its name)
+        #[arg(long)]
+        override_window: bool,
+    },
+    /// List past runs of a script, newest first
+    History {
+        /// Script name or ID
+        name: String,
+        /// Number of runs to show
+        #[arg(short, long, default_value = "20")]
+        limit: i64,
+    },
+    /// Print the captured output of one run (from `script history`)
+    Output {
+        /// Run number
+        run_id: i64,
     },
     /// Remove a script
     Remove {
         /// Script name or ID
         name: String,
     },
+    /// Show the script as it was at an earlier time, from the audit log
+    HistoryView {
+        /// Script name or ID
+        name: String,
+        #[arg(
+            long,
+            value_parser = parse::age,
+            required_unless_present = "timeline",
+            help = parse::help("Point in time", parse::AGE_FORMATS)
+        )]
+        at: Option<DateTime<Utc>>,
+        /// List every recorded change instead
+        #[arg(long, conflicts_with = "at")]
+        timeline: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// SERVICE COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// List services with their last known state
+    List,
+    /// Add a systemd unit on a server
+    Add {
+        /// Service name
+        name: String,
+        /// Server name or ID the unit runs on
+        #[arg(short, long)]
+        server: String,
+        /// Unit name, e.g. myapp.service (".service" is added if no type is given)
+        #[arg(short, long)]
+        unit: String,
+        /// Project name or ID (optional)
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Remove a service from pctrl (the unit is left alone)
+    Remove {
+        /// Service name or ID
+        name: String,
+    },
+    /// Show the unit's state (systemctl show)
+    Status {
+        /// Service name or ID
+        name: String,
+    },
+    /// Start the unit
+    Start {
+        /// Service name or ID
+        name: String,
+        /// Run against Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Stop the unit
+    Stop {
+        /// Service name or ID
+        name: String,
+        /// Run against Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Restart the unit
+    Restart {
+        /// Service name or ID
+        name: String,
+        /// Run against Live projects without asking
+        #[arg(long)]
+        allow_live: bool,
+    },
+    /// Show the unit's journal
+    Logs {
+        /// Service name or ID
+        name: String,
+        /// Number of lines
+        #[arg(short = 'n', long, default_value = "100")]
+        lines: u32,
+        /// Keep printing new lines until Ctrl-C
+        #[arg(short, long)]
+        follow: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// IDENTITY / SECRET COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum IdentityCommands {
+    /// Generate this installation's keypair
+    Init {
+        /// Replace an existing keypair (secrets sent to the old key can no longer be opened)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the public key to give to senders
+    Show,
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Encrypt a database or credential for another installation
+    Send {
+        /// Database or credential name (or credential ID)
+        name: String,
+        /// Recipient's public key (from their `pctrl identity show`)
+        #[arg(long)]
+        to: String,
+        /// Output file (default: <name>.pctrlsecret)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Decrypt a received secret and import it
+    Receive {
+        /// Secret file
+        file: PathBuf,
+        /// Overwrite an existing entry with the same name without asking
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// LOGS COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum LogsCommands {
+    /// Follow several containers and services in one stream until Ctrl-C
+    Follow {
+        /// Container ([host/]name); repeat for more
+        #[arg(short, long = "container")]
+        containers: Vec<String>,
+        /// Service name, or unit@server; repeat for more
+        #[arg(short, long = "service")]
+        services: Vec<String>,
+        /// Follow every container and service of a project
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Only show lines containing this text
+        #[arg(short, long)]
+        grep: Option<String>,
+        /// Lines of history per source
+        #[arg(short = 'n', long, default_value = "10")]
+        lines: u32,
+    },
 }
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -377,8 +2272,11 @@ pub enum CredentialCommands {
         #[arg(short, long)]
         port: Option<u16>,
         /// SSH key path (for SSH credentials)
-        #[arg(short, long)]
+        #[arg(short, long, visible_alias = "key-file")]
         key: Option<String>,
+        /// Store the key itself, encrypted, instead of its path
+        #[arg(long, requires = "key")]
+        store_inline: bool,
         /// API token (for API/OAuth credentials)
         #[arg(long)]
         token: Option<String>,
@@ -388,6 +2286,9 @@ pub enum CredentialCommands {
         /// URL (for API/OAuth/basic auth)
         #[arg(long)]
         url: Option<String>,
+        /// Create if missing, otherwise update only the given fields
+        #[arg(long)]
+        ensure: bool,
     },
     /// Show credential details
     Show {
@@ -398,29 +2299,160 @@ pub enum CredentialCommands {
     Remove {
         /// Credential name
         name: String,
+        /// Remove even if other entries still reference it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show what references this credential
+    Deps {
+        /// Credential name
+        name: String,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a key stored inline (--store-inline) back to a file
+    ExportKey {
+        /// Credential name
+        name: String,
+        /// File to write; must not exist yet
+        #[arg(short, long)]
+        out: PathBuf,
+        /// Don't ask before writing the private key
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// DEBUG COMMANDS
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Subcommand)]
+pub enum DebugCommands {
+    /// Add a demo project with servers, domains, a script and links
+    SeedDemo,
+    /// Remove the demo data again
+    ClearDemo,
+    /// Delete everything and recreate the schema
+    Reset {
+        /// Must be RESET
+        #[arg(long)]
+        confirm: String,
     },
+    /// Write an anonymized copy of the database to attach to a bug report
+    Anonymize {
+        /// Where to write the copy; must not exist yet
+        #[arg(long, default_value = "anon.db")]
+        out: PathBuf,
+    },
+}
+
+/// Subcommand path of an invocation, e.g. "server add"
+pub(crate) fn command_path(matches: &ArgMatches) -> String {
+    let mut path = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        path.push(name);
+        current = sub;
+    }
+    path.join(" ")
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
-
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     let mode: Mode = cli.mode.into();
+    pctrl_core::humanize::set_raw(cli.raw);
+    let color = !cli.no_color && !style::no_color_env(std::env::var_os("NO_COLOR").as_deref());
+    style::configure(cli.quiet, color);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_ansi(color)
+        .init();
 
     // ─────────────────────────────────────────────────────────────────────────
     // 1. Database initialisieren
     // ─────────────────────────────────────────────────────────────────────────
     let db_path = cli.db.unwrap_or_else(default_db_path);
 
+    // Prompt hooks only read cached data, without schema setup
+    if let Some(Commands::PromptSegment { format, init }) = &cli.command {
+        return handlers::prompt::handle(&db_path, format, init.as_deref()).await;
+    }
+
     // Stelle sicher dass das Verzeichnis existiert
     if let Some(parent) = db_path.parent() {
-        std::fs::create_dir_all(parent)?;
+        std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("Can't create directory {}: {}", parent.display(), e))?;
+    }
+
+    let db_file = db_path.to_str().unwrap_or("pctrl.db");
+    let db = if cli.adopt_db {
+        Database::adopt(db_file, None).await
+    } else {
+        Database::new(db_file, None).await
+    }
+    .map_err(|e| anyhow::anyhow!("Database init failed: {}", e))?;
+
+    db.set_lock_override(cli.override_lock);
+    db.set_hook_runner(HookRunner::new(hooks_dir()));
+
+    if let Some(schema) = db.newer_schema() {
+        eoutln!(
+            "{}",
+            style::warning_text(&format!(
+                "Warning: {} was written by a newer pctrl (schema v{}, this pctrl {} knows v{}); it is opened read-only",
+                db_path.display(),
+                schema,
+                env!("CARGO_PKG_VERSION"),
+                pctrl_database::CURRENT_SCHEMA_VERSION
+            ))
+        );
+    }
+
+    // Windows end even when no monitor is running to notice
+    let ended = if db.newer_schema().is_some() {
+        Ok(Vec::new())
+    } else {
+        db.end_expired_maintenance(Utc::now()).await
+    };
+    match ended {
+        Ok(ended) => {
+            for window in ended {
+                eoutln!(
+                    "{}",
+                    style::dim(&format!(
+                        "Maintenance of '{}' ended{}",
+                        window.project_name,
+                        window
+                            .restored
+                            .map(|s| format!(", status back to {}", s))
+                            .unwrap_or_default()
+                    ))
+                );
+            }
+        }
+        Err(e) => tracing::warn!("Could not end expired maintenance windows: {}", e),
     }
 
-    let db = Database::new(db_path.to_str().unwrap_or("pctrl.db"), None)
-        .await
-        .map_err(|e| anyhow::anyhow!("Database init failed: {}", e))?;
+    let hyperlinks: HyperlinkMode = db
+        .get_setting(settings::HYPERLINKS)
+        .await?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default();
+    hyperlink::set_enabled(
+        color
+            && hyperlink::detect(
+                hyperlinks,
+                std::env::var("TERM").ok().as_deref(),
+                std::io::stdout().is_terminal(),
+            ),
+    );
+
+    style::set_hints(db.get_setting(settings::HINTS).await?.as_deref() != Some("off"));
 
     let db = Arc::new(db);
 
@@ -429,8 +2461,41 @@ async fn main() -> anyhow::Result<()> {
     // ─────────────────────────────────────────────────────────────────────────
 
     // If a subcommand is provided, always use CLI mode to handle it
-    if let Some(command) = cli.command {
-        handlers::handle_command(command, db.clone()).await?;
+    if let Some(Commands::Shell { project }) = cli.command {
+        let history_path = db_path.parent().map(|p| p.join("shell_history"));
+        let flags = shell::ShellFlags {
+            raw: cli.raw,
+            override_lock: cli.override_lock,
+            quiet: cli.quiet,
+            color,
+        };
+        shell::run(db.clone(), project, history_path, flags).await?;
+    } else if let Some(command) = cli.command {
+        let result = handlers::run_recorded(command, db.clone(), &command_path(&matches)).await;
+        if let Some(failed) = result
+            .as_ref()
+            .err()
+            .and_then(|e| e.downcast_ref::<handlers::CommandFailed>())
+        {
+            eoutln!("Error: {}", failed);
+            std::process::exit(failed.code);
+        }
+        // Writes to a read-only file fail in SQLite; say why
+        if let (Some(schema), Err(e)) = (db.newer_schema(), &result) {
+            if format!("{:?}", e).contains("readonly database") {
+                eoutln!(
+                    "Error: this database has schema v{} from a newer pctrl; pctrl {} only reads it. Upgrade pctrl to change it.",
+                    schema,
+                    env!("CARGO_PKG_VERSION")
+                );
+                std::process::exit(1);
+            }
+        }
+        // Printed like a returned error would be, but plain when color is off
+        if let Err(e) = result {
+            eoutln!("Error: {:?}", e);
+            std::process::exit(1);
+        }
     } else {
         // No subcommand - use the specified mode (default: TUI)
         match mode {
@@ -446,7 +2511,7 @@ async fn main() -> anyhow::Result<()> {
                 let scripts = db.list_scripts().await.unwrap_or_default();
                 let credentials = db.list_credentials().await.unwrap_or_default();
 
-                println!("  {}Status{}", style::BOLD, style::RESET);
+                outln!("  {}Status{}", style::BOLD, style::RESET);
                 style::divider();
                 style::kv_count("Projects", projects.len());
                 style::kv_count("Servers", servers.len());
@@ -455,28 +2520,29 @@ async fn main() -> anyhow::Result<()> {
                 style::kv_count("Scripts", scripts.len());
                 style::kv_count("Credentials", credentials.len());
                 style::divider();
-                println!(
+                let _ = handlers::print_forecast_warnings(&db).await;
+                outln!(
                     "  {}Database:{} {}",
                     style::GRAY,
                     style::RESET,
                     style::format_path(&db_path.display().to_string(), 40)
                 );
-                println!();
-                println!(
+                noteln!();
+                noteln!(
                     "  {}Use {}pctrl --help{} for available commands{}",
                     style::DIM,
                     style::CYAN,
                     style::DIM,
                     style::RESET
                 );
-                println!();
+                noteln!();
             }
             Mode::Tui => {
                 tui::run(db.clone()).await?;
             }
             Mode::Gui => {
-                println!("GUI mode requires the desktop application (Tauri)");
-                println!("Run: cd apps/desktop && npm run tauri dev");
+                outln!("GUI mode requires the desktop application (Tauri)");
+                outln!("Run: cd apps/desktop && npm run tauri dev");
             }
         }
     }
@@ -0,0 +1,133 @@
+//! Line editor for the shell, built on rustyline (history, Tab completion)
+//!
+//! rustyline falls back to plain line reading when stdin is not a terminal,
+//! so `echo "server list" | pctrl shell` works too. Secret option values are
+//! redacted before a line reaches the history, and rustyline keeps the
+//! history file readable by its owner only (mode 0600).
+
+use pctrl_core::shell::{complete, quote_word, redact_secrets};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
+use std::io;
+use std::path::PathBuf;
+
+/// Maximum number of history entries kept on disk
+const HISTORY_LIMIT: usize = 1000;
+
+/// Result of reading one line
+pub enum ReadLine {
+    Line(String),
+    /// Ctrl-C: discard the current line
+    Interrupted,
+    /// Ctrl-D on an empty line or end of input
+    Eof,
+}
+
+/// Possible words for Tab completion
+pub trait Candidates {
+    /// Candidates for the word after the complete words in `previous`
+    fn candidates(&self, previous: &[String]) -> Vec<String>;
+}
+
+/// Adapts [`Candidates`] to rustyline's completion hooks
+struct ShellHelper<C>(C);
+
+impl<C: Candidates> Completer for ShellHelper<C> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, matches) = complete(&line[..pos], |previous| self.0.candidates(previous));
+        let only = matches.len() == 1;
+        let pairs = matches
+            .into_iter()
+            .map(|word| Pair {
+                replacement: match only {
+                    true => format!("{} ", quote_word(&word)),
+                    false => quote_word(&word),
+                },
+                display: word,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl<C> Hinter for ShellHelper<C> {
+    type Hint = String;
+}
+
+impl<C> Highlighter for ShellHelper<C> {}
+
+impl<C> Validator for ShellHelper<C> {}
+
+impl<C: Candidates> Helper for ShellHelper<C> {}
+
+pub struct LineEditor<C: Candidates> {
+    editor: Editor<ShellHelper<C>, FileHistory>,
+    history_path: Option<PathBuf>,
+}
+
+impl<C: Candidates> LineEditor<C> {
+    /// Create an editor, loading history from `history_path` if it exists
+    pub fn new(history_path: Option<PathBuf>, candidates: C) -> rustyline::Result<Self> {
+        let config = Config::builder()
+            .max_history_size(HISTORY_LIMIT)?
+            .history_ignore_dups(true)?
+            .completion_type(CompletionType::List)
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(ShellHelper(candidates)));
+
+        if let Some(path) = &history_path {
+            // Not there yet on the first start
+            let _ = editor.load_history(path);
+        }
+
+        Ok(Self {
+            editor,
+            history_path,
+        })
+    }
+
+    /// The completion source, e.g. to refresh names after a command
+    pub fn candidates_mut(&mut self) -> &mut C {
+        &mut self
+            .editor
+            .helper_mut()
+            .expect("helper is set in LineEditor::new")
+            .0
+    }
+
+    /// Add a line to the history with secrets redacted and persist it
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let _ = self.editor.add_history_entry(redact_secrets(line));
+
+        if let Some(path) = &self.history_path {
+            let _ = self.editor.append_history(path);
+        }
+    }
+
+    /// Read a line, completing words on Tab
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Ok(ReadLine::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(ReadLine::Interrupted),
+            Err(ReadlineError::Eof) => Ok(ReadLine::Eof),
+            Err(ReadlineError::Io(e)) => Err(e),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+}
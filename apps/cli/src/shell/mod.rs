@@ -0,0 +1,247 @@
+//! Interactive shell (`pctrl shell`)
+//!
+//! Each line is parsed with the regular clap definitions and dispatched via
+//! `handle_command`, so commands behave exactly as on the command line.
+
+mod editor;
+
+use crate::handlers::resolve::resolve_entity;
+use crate::{command_path, handlers, style, Cli};
+use clap::{CommandFactory, FromArgMatches};
+use editor::{Candidates, LineEditor, ReadLine};
+use pctrl_core::shell::{inject_project, parse_line, ReplLine, PROJECT_RULES};
+use pctrl_core::EntityType;
+use pctrl_database::Database;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Entity names offered by Tab completion
+#[derive(Default)]
+struct EntityNames {
+    projects: Vec<String>,
+    servers: Vec<String>,
+    domains: Vec<String>,
+    databases: Vec<String>,
+    scripts: Vec<String>,
+    services: Vec<String>,
+    credentials: Vec<String>,
+}
+
+impl EntityNames {
+    async fn load(db: &Database) -> Self {
+        Self {
+            projects: names(db.list_projects().await, |p| p.name),
+            servers: names(db.list_servers().await, |s| s.name),
+            domains: names(db.list_domains().await, |d| d.domain),
+            databases: names(db.list_database_credentials().await, |d| d.name),
+            scripts: names(db.list_scripts().await, |s| s.name),
+            services: names(db.list_services().await, |s| s.name),
+            credentials: names(db.list_credentials().await, |c| c.name),
+        }
+    }
+
+    /// Names for a top-level command (or all of them)
+    fn for_command(&self, command: Option<&str>) -> Vec<String> {
+        match command {
+            Some("project" | "p") => self.projects.clone(),
+            Some("server") => self.servers.clone(),
+            Some("domain") => self.domains.clone(),
+            Some("database" | "db") => self.databases.clone(),
+            Some("script") => self.scripts.clone(),
+            Some("service") => self.services.clone(),
+            Some("credential" | "cred") => self.credentials.clone(),
+            _ => [
+                &self.projects,
+                &self.servers,
+                &self.domains,
+                &self.databases,
+                &self.scripts,
+                &self.services,
+                &self.credentials,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+        }
+    }
+}
+
+fn names<T>(list: pctrl_core::Result<Vec<T>>, name: impl Fn(T) -> String) -> Vec<String> {
+    list.map(|l| l.into_iter().map(name).collect())
+        .unwrap_or_default()
+}
+
+/// Global flags the shell was started with (`pctrl --raw shell`)
+#[derive(Clone, Copy)]
+pub struct ShellFlags {
+    pub raw: bool,
+    pub override_lock: bool,
+    pub quiet: bool,
+    pub color: bool,
+}
+
+/// Run the REPL until `exit` or Ctrl-D
+pub async fn run(
+    db: Arc<Database>,
+    project: Option<String>,
+    history_path: Option<PathBuf>,
+    flags: ShellFlags,
+) -> anyhow::Result<()> {
+    let mut context = match project {
+        Some(name) => Some(resolve_project(&db, &name).await?),
+        None => None,
+    };
+    let completion = Completion {
+        root: Cli::command(),
+        entities: EntityNames::load(&db).await,
+    };
+    let mut editor = LineEditor::new(history_path, completion)?;
+
+    outln!("pctrl shell — type 'help' for commands, 'exit' or Ctrl-D to leave");
+    outln!();
+
+    loop {
+        let prompt = match &context {
+            Some((_, name)) => format!("pctrl ({})> ", name),
+            None => "pctrl> ".to_string(),
+        };
+
+        let line = match editor.read_line(&prompt)? {
+            ReadLine::Line(line) => line,
+            ReadLine::Interrupted => continue,
+            ReadLine::Eof => break,
+        };
+        editor.add_history(&line);
+
+        let parsed = match parse_line(&line) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                outln!("{}", style::error_text(&format!("✗ {}", e)));
+                continue;
+            }
+        };
+
+        match parsed {
+            ReplLine::Empty => {}
+            ReplLine::Exit => break,
+            ReplLine::Help => print_help(),
+            ReplLine::ShowContext => match &context {
+                Some((id, name)) => outln!("Current project: {} ({})", name, id),
+                None => outln!("No project selected (use <project>)"),
+            },
+            ReplLine::Use(None) => {
+                context = None;
+                outln!("Project context cleared");
+            }
+            ReplLine::Use(Some(name)) => match resolve_project(&db, &name).await {
+                Ok(project) => {
+                    outln!("Using project '{}'", project.1);
+                    context = Some(project);
+                }
+                Err(e) => outln!("{}", style::error_text(&format!("✗ {}", e))),
+            },
+            ReplLine::Command(args) => {
+                let args = match &context {
+                    Some((id, _)) => inject_project(&args, id, PROJECT_RULES),
+                    None => args,
+                };
+                if let Err(e) = run_command(&db, args, flags).await {
+                    outln!("{}", style::error_text(&format!("✗ {}", e)));
+                }
+                // Names may have changed (add/remove)
+                editor.candidates_mut().entities = EntityNames::load(&db).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse and run one command line like the normal CLI would
+async fn run_command(
+    db: &Arc<Database>,
+    args: Vec<String>,
+    flags: ShellFlags,
+) -> anyhow::Result<()> {
+    let matches = match Cli::command()
+        .no_binary_name(true)
+        .try_get_matches_from(&args)
+    {
+        Ok(matches) => matches,
+        Err(e) => {
+            // Help and version output are not errors
+            e.print()?;
+            return Ok(());
+        }
+    };
+    let cli = Cli::from_arg_matches(&matches)?;
+    let Some(command) = cli.command else {
+        return Ok(());
+    };
+
+    // Per-line global flags apply to this command only
+    pctrl_core::humanize::set_raw(flags.raw || cli.raw);
+    db.set_lock_override(flags.override_lock || cli.override_lock);
+    style::configure(flags.quiet || cli.quiet, flags.color && !cli.no_color);
+
+    let result = handlers::run_recorded(command, db.clone(), &command_path(&matches)).await;
+
+    pctrl_core::humanize::set_raw(flags.raw);
+    db.set_lock_override(flags.override_lock);
+    style::configure(flags.quiet, flags.color);
+
+    result
+}
+
+/// Tab completion from the clap definitions and database names
+struct Completion {
+    root: clap::Command,
+    entities: EntityNames,
+}
+
+impl Candidates for Completion {
+    fn candidates(&self, previous: &[String]) -> Vec<String> {
+        candidates(&self.root, &self.entities, previous)
+    }
+}
+
+/// Completion candidates for the word after `previous`
+fn candidates(root: &clap::Command, entities: &EntityNames, previous: &[String]) -> Vec<String> {
+    const BUILTINS: &[&str] = &["use", "help", "exit"];
+
+    match previous {
+        [] => root
+            .get_subcommands()
+            .map(|c| c.get_name().to_string())
+            .chain(BUILTINS.iter().map(|b| b.to_string()))
+            .collect(),
+        [builtin] if builtin == "use" => entities.projects.clone(),
+        [command] => root
+            .find_subcommand(command)
+            .map(|c| {
+                c.get_subcommands()
+                    .map(|s| s.get_name().to_string())
+                    .collect()
+            })
+            .unwrap_or_else(|| entities.for_command(Some(command))),
+        [command, ..] => entities.for_command(Some(command)),
+    }
+}
+
+/// Resolve a project by ref, ID or name to (id, name)
+async fn resolve_project(db: &Database, name: &str) -> anyhow::Result<(String, String)> {
+    resolve_entity(db, EntityType::Project, name).await
+}
+
+fn print_help() {
+    outln!("Any pctrl command works without the 'pctrl' prefix, e.g. 'server list'.");
+    outln!();
+    outln!("  use <project>   Set the current project (injected into project commands)");
+    outln!("  use -           Clear the current project");
+    outln!("  use             Show the current project");
+    outln!("  help            This help ('<command> --help' for command help)");
+    outln!("  exit            Leave the shell (or Ctrl-D)");
+    outln!();
+    outln!("Tab completes commands and entity names; history is kept between sessions.");
+}
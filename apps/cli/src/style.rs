@@ -1,11 +1,176 @@
 //! CLI Styling - FinanzApp Quality
 //!
 //! Farben, Banner und formatierte Ausgaben für professionellen CLI-Look.
+//!
+//! Everything the CLI prints goes through the macros below, so `--quiet`,
+//! `--no-color` and `NO_COLOR` apply everywhere: [`outln!`] prints a line,
+//! plain ASCII without escape sequences when color is off; [`noteln!`]
+//! prints decoration (banners, hints, success lines) that `--quiet` drops.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static COLOR: AtomicBool = AtomicBool::new(true);
+static HINTS: AtomicBool = AtomicBool::new(true);
+
+/// Print a line, plain when color is off
+macro_rules! outln {
+    () => {
+        println!()
+    };
+    ($($arg:tt)*) => {
+        println!("{}", $crate::style::render(&format!($($arg)*)))
+    };
+}
+
+/// Print without a newline, plain when color is off
+macro_rules! out {
+    ($($arg:tt)*) => {
+        print!("{}", $crate::style::render(&format!($($arg)*)))
+    };
+}
+
+/// Print a line to stderr, plain when color is off
+macro_rules! eoutln {
+    () => {
+        eprintln!()
+    };
+    ($($arg:tt)*) => {
+        eprintln!("{}", $crate::style::render(&format!($($arg)*)))
+    };
+}
+
+/// Print a decorative line (banner, hint, success line) unless `--quiet`
+macro_rules! noteln {
+    ($($arg:tt)*) => {
+        if !$crate::style::is_quiet() {
+            outln!($($arg)*)
+        }
+    };
+}
+
+/// Set the output mode for the rest of the process (or shell line)
+pub fn configure(quiet: bool, color: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Whether decorative output is suppressed (`--quiet`)
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Turn next-step hints on or off (the `hints` setting)
+pub fn set_hints(enabled: bool) {
+    HINTS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether next-step hints are printed: the setting allows them and
+/// `--quiet` isn't given
+pub fn hints_enabled() -> bool {
+    HINTS.load(Ordering::Relaxed) && !is_quiet()
+}
+
+/// Whether ANSI styling and symbols are used
+pub fn use_color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Whether `NO_COLOR` asks for plain output: set and not empty
+/// (<https://no-color.org>)
+pub fn no_color_env(value: Option<&std::ffi::OsStr>) -> bool {
+    value.is_some_and(|v| !v.is_empty())
+}
+
+/// Text as it should be printed: unchanged with color, else [`plain`]
+pub fn render(text: &str) -> Cow<'_, str> {
+    if use_color() {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(plain(text))
+    }
+}
+
+/// `text` without escape sequences (colors, hyperlinks), with symbols
+/// replaced by ASCII markers and decorative emoji dropped
+pub fn plain(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters up to a final byte in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC (hyperlinks): up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            c if c.is_ascii() => out.push(c),
+            c => match ascii_symbol(c) {
+                Some(symbol) => out.push_str(symbol),
+                None => out.push(c),
+            },
+        }
+    }
+    out
+}
+
+/// ASCII stand-in for a symbol; `None` for anything else (names may
+/// contain letters of other scripts, those are kept)
+fn ascii_symbol(c: char) -> Option<&'static str> {
+    let symbol = match c {
+        '✓' | '✔' => "[ok]",
+        '✗' | '✘' => "[!!]",
+        '⚠' => "[!]",
+        'ℹ' => "[i]",
+        '●' | '○' | '•' | '🟢' | '🟡' | '🔵' | '⚫' | '🔧' => "*",
+        '→' => "->",
+        '←' => "<-",
+        '↔' => "<->",
+        '↻' => "~",
+        '▶' => ">",
+        '⏸' => "||",
+        '…' => "...",
+        '—' | '–' | '─' | '━' | '═' => "-",
+        '│' | '║' => "|",
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '╔' | '╗' | '╚' | '╝' => "+",
+        // Sparkline bars, lowest to highest
+        '▁' => "_",
+        '▂' => ".",
+        '▃' => ",",
+        '▄' => "-",
+        '▅' => "=",
+        '▆' => "+",
+        '▇' => "*",
+        '█' => "#",
+        '🔒' => "[locked]",
+        '🔓' => "[open]",
+        '🔑' => "key",
+        // Other emoji (and the variation selector after them) are decoration
+        '\u{fe0f}' | '\u{2600}'..='\u{27bf}' | '\u{1f000}'..='\u{1faff}' => "",
+        _ => return None,
+    };
+    Some(symbol)
+}
 
 // ANSI Color Codes
 pub const RESET: &str = "\x1b[0m";
 pub const BOLD: &str = "\x1b[1m";
 pub const DIM: &str = "\x1b[2m";
+pub const STRIKE: &str = "\x1b[9m";
 
 // Colors (allow dead_code - will be used in CLI handlers)
 #[allow(dead_code)]
@@ -23,7 +188,16 @@ pub const GRAY: &str = "\x1b[90m";
 
 /// Print the pctrl ASCII banner
 pub fn print_banner(version: &str) {
-    println!(
+    if is_quiet() {
+        return;
+    }
+    if !use_color() {
+        outln!();
+        outln!("  pctrl v{} - Mission Control for Self-Hosters", version);
+        outln!();
+        return;
+    }
+    outln!(
         r#"
 {g}  ┌───────────────────────────────────────────────────────────┐
   │                                                           │
@@ -50,59 +224,86 @@ pub fn print_banner(version: &str) {
 /// Print a section header (like FinanzApp's build phases)
 #[allow(dead_code)]
 pub fn section(title: &str) {
-    println!();
-    println!(
+    if is_quiet() {
+        return;
+    }
+    outln!();
+    outln!(
         "{}┌─────────────────────────────────────────┐{}",
-        BLUE, RESET
+        BLUE,
+        RESET
     );
-    println!(
+    outln!(
         "{}│{} {}{}{:<39}{} {}│{}",
-        BLUE, RESET, WHITE, BOLD, title, RESET, BLUE, RESET
+        BLUE,
+        RESET,
+        WHITE,
+        BOLD,
+        title,
+        RESET,
+        BLUE,
+        RESET
     );
-    println!(
+    outln!(
         "{}└─────────────────────────────────────────┘{}",
-        BLUE, RESET
+        BLUE,
+        RESET
     );
 }
 
 /// Print a step indicator [1/4]
 #[allow(dead_code)]
 pub fn step(current: u32, total: u32, text: &str) {
-    println!();
-    println!(
+    if is_quiet() {
+        return;
+    }
+    outln!();
+    outln!(
         "{}[{}/{}]{} {}{}{}",
-        YELLOW, current, total, RESET, BOLD, text, RESET
+        YELLOW,
+        current,
+        total,
+        RESET,
+        BOLD,
+        text,
+        RESET
     );
 }
 
 /// Success message with checkmark
 #[allow(dead_code)]
 pub fn success(msg: &str) {
-    println!("{}✓{} {}", GREEN, RESET, msg);
+    if is_quiet() {
+        return;
+    }
+    outln!("{}✓{} {}", GREEN, RESET, msg);
 }
 
 /// Error message with X
 #[allow(dead_code)]
 pub fn error(msg: &str) {
-    println!("{}✗{} {}", RED, RESET, msg);
+    outln!("{}✗{} {}", RED, RESET, msg);
 }
 
 /// Warning message
 #[allow(dead_code)]
 pub fn warn(msg: &str) {
-    println!("{}⚠{}  {}", YELLOW, RESET, msg);
+    outln!("{}⚠{}  {}", YELLOW, RESET, msg);
 }
 
 /// Info message
 #[allow(dead_code)]
 pub fn info(msg: &str) {
-    println!("{}ℹ{}  {}", CYAN, RESET, msg);
+    if is_quiet() {
+        return;
+    }
+    outln!("{}ℹ{}  {}", CYAN, RESET, msg);
 }
 
 /// Print a key-value pair (for status display)
 #[allow(dead_code)]
 pub fn kv(key: &str, value: &str) {
-    println!("  {}{}:{} {}", GRAY, key, RESET, value);
+    outln!("  {}{}:{} {}", GRAY, key, RESET, value);
 }
 
 /// Print a key-value pair with count (highlighted if > 0)
@@ -112,36 +313,52 @@ pub fn kv_count(key: &str, count: usize) {
     } else {
         ("○", format!("{}0{}", DIM, RESET))
     };
-    println!("  {} {}: {}", icon, key, value);
+    outln!("  {} {}: {}", icon, key, value);
 }
 
 /// Print a horizontal divider
 pub fn divider() {
-    println!("{}─────────────────────────────────────────{}", GRAY, RESET);
+    if is_quiet() {
+        return;
+    }
+    outln!("{}─────────────────────────────────────────{}", GRAY, RESET);
 }
 
 /// Print a boxed summary (like FinanzApp's success banner)
 #[allow(dead_code)]
 pub fn success_box(title: &str, lines: &[&str]) {
-    println!();
-    println!(
+    if is_quiet() {
+        return;
+    }
+    outln!();
+    outln!(
         "{}  ┌───────────────────────────────────────────┐{}",
-        GREEN, RESET
+        GREEN,
+        RESET
     );
-    println!(
+    outln!(
         "{}  │{}  {}{}{:<39}{}  {}│{}",
-        GREEN, RESET, WHITE, BOLD, title, RESET, GREEN, RESET
+        GREEN,
+        RESET,
+        WHITE,
+        BOLD,
+        title,
+        RESET,
+        GREEN,
+        RESET
     );
-    println!(
+    outln!(
         "{}  ├───────────────────────────────────────────┤{}",
-        GREEN, RESET
+        GREEN,
+        RESET
     );
     for line in lines {
-        println!("{}  │{}  {:<41}  {}│{}", GREEN, RESET, line, GREEN, RESET);
+        outln!("{}  │{}  {:<41}  {}│{}", GREEN, RESET, line, GREEN, RESET);
     }
-    println!(
+    outln!(
         "{}  └───────────────────────────────────────────┘{}",
-        GREEN, RESET
+        GREEN,
+        RESET
     );
 }
 
@@ -160,35 +377,57 @@ pub fn format_path(path: &str, max_len: usize) -> String {
 
 /// Return bold text
 pub fn bold(text: &str) -> String {
-    format!("{}{}{}", BOLD, text, RESET)
+    paint(BOLD, text)
+}
+
+/// Return struck-through text
+pub fn strike(text: &str) -> String {
+    paint(STRIKE, text)
 }
 
 /// Return dimmed text
 pub fn dim(text: &str) -> String {
-    format!("{}{}{}", DIM, text, RESET)
+    paint(DIM, text)
 }
 
 /// Return green (success) text
 pub fn success_text(text: &str) -> String {
-    format!("{}{}{}", GREEN, text, RESET)
+    paint(GREEN, text)
 }
 
 /// Return red (error) text
 pub fn error_text(text: &str) -> String {
-    format!("{}{}{}", RED, text, RESET)
+    paint(RED, text)
 }
 
 /// Return yellow (warning) text
 pub fn warning_text(text: &str) -> String {
-    format!("{}{}{}", YELLOW, text, RESET)
+    paint(YELLOW, text)
 }
 
 /// Return cyan (info) text
 pub fn info_text(text: &str) -> String {
-    format!("{}{}{}", CYAN, text, RESET)
+    paint(CYAN, text)
+}
+
+/// Colors that tell log sources apart
+pub const SOURCE_COLORS: &[&str] = &[CYAN, GREEN, MAGENTA, YELLOW, BLUE, RED];
+
+/// Return text in the given source color
+pub fn source_text(color: usize, text: &str) -> String {
+    paint(SOURCE_COLORS[color % SOURCE_COLORS.len()], text)
 }
 
 /// Return a section header
 pub fn header(text: &str) -> String {
-    format!("{}{}{}{}", BOLD, WHITE, text, RESET)
+    paint(&format!("{}{}", BOLD, WHITE), text)
+}
+
+/// `text` wrapped in an ANSI style, or as is when color is off
+fn paint(code: &str, text: &str) -> String {
+    if use_color() {
+        format!("{}{}{}", code, text, RESET)
+    } else {
+        text.to_string()
+    }
 }
@@ -1,64 +1,786 @@
 //! TUI application state
 
-use super::types::{InputForm, InputMode, SelectedPanel};
-use pctrl_core::{DatabaseCredentials, Domain, Project, Script, Server};
+use super::checks::{self, CheckResult, CheckSender, Round};
+use super::notifications::{Notification, Notifications, Notifier};
+use super::theme::{self, Theme};
+use super::types::{
+    ConnectionStatus, InputForm, InputMode, ProjectDetail, SelectedPanel, Selection,
+};
+use chrono::{DateTime, Duration, Utc};
+use pctrl_core::discovery::{self, CachedDiscovery};
+use pctrl_core::maintenance::MaintenanceWindow;
+use pctrl_core::script_namespace::{self, TreeRow};
+use pctrl_core::search::{self, Candidate};
+use pctrl_core::settings::{self, LiveEffect, TUI_ACCENT, TUI_REFRESH_SECS, TUI_THEME};
+use pctrl_core::theme::{parse_color, ColorDepth, Palette, TermColor, ThemeName};
+use pctrl_core::vpn::Tunnels;
+use pctrl_core::{
+    ActivityEntry, ActivityFilter, CoolifyInstance, DatabaseCredentials, DockerHost, Domain,
+    EntityType, GitRepo, Project, Script, Server, Service,
+};
 use pctrl_database::Database;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
 pub struct App {
     pub selected_panel: SelectedPanel,
     pub db: Arc<Database>,
     // v6 entities
     pub projects: Vec<Project>,
+    /// Row of the projects list under the cursor
+    pub project_selected: usize,
+    /// The project opened with Enter, shown instead of the list
+    pub project_detail: Option<ProjectDetail>,
+    /// Open maintenance windows, shown next to their projects
+    pub maintenance: Vec<MaintenanceWindow>,
     pub servers: Vec<Server>,
+    /// Row of the servers list under the cursor
+    pub server_selected: usize,
+    /// Local VPN interfaces, for servers that require one
+    pub tunnels: Tunnels,
+    /// systemd units, shown under their servers
+    pub services: Vec<Service>,
+    /// Last container discovery per server, shown under the server
+    pub discovered: Vec<CachedDiscovery>,
     pub domains: Vec<Domain>,
+    pub domain_selected: usize,
     pub databases: Vec<DatabaseCredentials>,
+    pub database_selected: usize,
     pub scripts: Vec<Script>,
+    /// Shown with their connection on the status panel
+    pub docker_hosts: Vec<DockerHost>,
+    pub coolify: Vec<CoolifyInstance>,
+    pub repos: Vec<GitRepo>,
+    /// Last check of each server and database, by ID; missing until the
+    /// first check
+    pub connections: HashMap<String, ConnectionStatus>,
+    checks: CheckSender,
+    check_results: mpsc::UnboundedReceiver<CheckResult>,
+    /// Row of the scripts tree under the cursor
+    pub script_selected: usize,
+    /// Namespaces folded in the scripts panel, by path
+    pub collapsed_namespaces: BTreeSet<String>,
+    // Activity feed
+    pub activity: Vec<ActivityEntry>,
+    pub activity_filter: ActivityFilter,
+    pub activity_selected: usize,
+    pub activity_expanded: bool,
+    /// No older entries left in the database
+    pub activity_exhausted: bool,
+    pub search_input: String,
+    /// Narrows the selected panel's list to entries matching it, like
+    /// `pctrl find`; cleared when the panel changes
+    pub filter: Option<String>,
+    // Notifications
+    pub notifications: Notifications,
+    pub notification_selected: usize,
+    pub notification_expanded: bool,
+    notifier: Notifier,
+    inbox: mpsc::UnboundedReceiver<Notification>,
     // UI state
     pub input_mode: InputMode,
     pub input_form: InputForm,
     pub loading: bool,
+    /// Time relative times are rendered against, advanced by [`App::tick`]
+    pub now: DateTime<Utc>,
+    pub theme: Theme,
+    /// Custom accent from the `tui_accent` setting
+    pub accent: Option<TermColor>,
+    pub color_depth: ColorDepth,
+    /// Settings as last read, to tell what another process changed
+    settings: Vec<(String, String)>,
+    settings_generation: i64,
+    settings_checked_at: DateTime<Utc>,
+    /// Data reload interval from the `tui_refresh_secs` setting
+    pub refresh_every: Option<Duration>,
+    loaded_at: DateTime<Utc>,
 }
 
+/// Activity entries loaded per page
+const ACTIVITY_PAGE_SIZE: i64 = 50;
+
+/// How often the settings generation is polled, in seconds
+const SETTINGS_POLL_SECS: i64 = 1;
+
 impl App {
     pub fn new(db: Arc<Database>) -> Self {
+        let (notifier, inbox) = mpsc::unbounded_channel();
+        let (checks, check_results) = mpsc::unbounded_channel();
         Self {
             selected_panel: SelectedPanel::Status,
             db,
             projects: Vec::new(),
+            project_selected: 0,
+            project_detail: None,
+            maintenance: Vec::new(),
             servers: Vec::new(),
+            server_selected: 0,
+            tunnels: Tunnels::default(),
+            services: Vec::new(),
+            discovered: Vec::new(),
             domains: Vec::new(),
+            domain_selected: 0,
             databases: Vec::new(),
+            database_selected: 0,
             scripts: Vec::new(),
+            docker_hosts: Vec::new(),
+            coolify: Vec::new(),
+            repos: Vec::new(),
+            connections: HashMap::new(),
+            checks,
+            check_results,
+            script_selected: 0,
+            collapsed_namespaces: BTreeSet::new(),
+            activity: Vec::new(),
+            activity_filter: ActivityFilter::default(),
+            activity_selected: 0,
+            activity_expanded: false,
+            activity_exhausted: false,
+            search_input: String::new(),
+            filter: None,
+            notifications: Notifications::default(),
+            notification_selected: 0,
+            notification_expanded: false,
+            notifier,
+            inbox,
             input_mode: InputMode::Normal,
             input_form: InputForm::default(),
             loading: false,
+            now: Utc::now(),
+            theme: Theme::default(),
+            accent: None,
+            color_depth: theme::terminal_color_depth(),
+            settings: Vec::new(),
+            settings_generation: 0,
+            settings_checked_at: DateTime::<Utc>::MIN_UTC,
+            refresh_every: None,
+            loaded_at: DateTime::<Utc>::MIN_UTC,
+        }
+    }
+
+    /// Advance the clock the UI renders against and take in what
+    /// background work reported since the last tick
+    pub fn tick(&mut self, now: DateTime<Utc>) {
+        self.now = now;
+        while let Ok((id, status)) = self.check_results.try_recv() {
+            self.connections.insert(id, status);
+        }
+        while let Ok(notification) = self.inbox.try_recv() {
+            self.notifications.push(notification, now);
+        }
+    }
+
+    /// Check every server, database, Coolify instance and Git repository in
+    /// the background; false, without starting anything, while the last round is still running
+    pub fn check_connections(&mut self) -> bool {
+        if self.checks_running() {
+            return false;
+        }
+        let total = self.servers.len()
+            + self.databases.len()
+            + self.docker_hosts.len()
+            + self.coolify.len()
+            + self.repos.len();
+        let round = Round::new(total, self.notifier());
+        for server in &self.servers {
+            self.connections
+                .insert(server.id.clone(), ConnectionStatus::Checking);
+            checks::spawn_server(
+                self.db.clone(),
+                server.clone(),
+                self.checks.clone(),
+                round.clone(),
+            );
+        }
+        for database in &self.databases {
+            self.connections
+                .insert(database.id.clone(), ConnectionStatus::Checking);
+            checks::spawn_database(database.clone(), self.checks.clone(), round.clone());
+        }
+        for host in &self.docker_hosts {
+            self.connections
+                .insert(checks::docker_id(&host.id), ConnectionStatus::Checking);
+            checks::spawn_docker(host.clone(), self.checks.clone(), round.clone());
+        }
+        for instance in &self.coolify {
+            self.connections
+                .insert(checks::coolify_id(&instance.id), ConnectionStatus::Checking);
+            checks::spawn_coolify(instance.clone(), self.checks.clone(), round.clone());
+        }
+        for repo in &self.repos {
+            self.connections
+                .insert(checks::git_id(&repo.id), ConnectionStatus::Checking);
+            checks::spawn_git(repo.clone(), self.checks.clone(), round.clone());
+        }
+        true
+    }
+
+    /// Whether a check hasn't reported yet
+    pub fn checks_running(&self) -> bool {
+        self.connections
+            .values()
+            .any(|status| *status == ConnectionStatus::Checking)
+    }
+
+    /// A sender for work that outlives the keypress that started it
+    pub fn notifier(&self) -> Notifier {
+        self.notifier.clone()
+    }
+
+    pub fn notify(&mut self, notification: Notification) {
+        self.notifications.push(notification, self.now);
+    }
+
+    /// Open the notification panel; everything in it counts as read
+    pub fn open_notifications(&mut self) {
+        self.notifications.mark_read();
+        self.notification_selected = 0;
+        self.notification_expanded = false;
+        self.input_mode = InputMode::Notifications;
+    }
+
+    pub fn close_notifications(&mut self) {
+        self.notifications.mark_read();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Act on the selected notification: go to the panel it's about, or
+    /// show its full text
+    pub fn open_notification(&mut self) {
+        let Some(notification) = self.notifications.get(self.notification_selected) else {
+            return;
+        };
+        match notification.panel {
+            Some(panel) => {
+                self.show_panel(panel, false);
+                self.close_notifications();
+            }
+            None => self.notification_expanded = !self.notification_expanded,
+        }
+    }
+
+    /// The loaded list, or `None` after reporting why it couldn't be loaded
+    fn loaded<T, E: Display>(
+        &mut self,
+        what: &str,
+        panel: SelectedPanel,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.notify(
+                    Notification::error(format!("Couldn't load {}", what))
+                        .with_details(e.to_string())
+                        .with_panel(panel),
+                );
+                None
+            }
+        }
+    }
+
+    /// Read the settings and apply the ones the TUI uses
+    pub async fn load_settings(&mut self) {
+        self.settings_generation = self.db.settings_generation().await.unwrap_or(0);
+        self.settings = self.db.list_settings().await.unwrap_or_default();
+        self.apply_settings();
+    }
+
+    /// Between frames: pick up settings changed elsewhere and reload the
+    /// data when `tui_refresh_secs` says it's time
+    pub async fn background(&mut self) {
+        if self.now - self.settings_checked_at >= Duration::seconds(SETTINGS_POLL_SECS) {
+            self.settings_checked_at = self.now;
+            self.check_settings().await;
+        }
+        if let Some(every) = self.refresh_every {
+            if self.now - self.loaded_at >= every {
+                self.load_all().await;
+            }
+        }
+    }
+
+    /// Re-read the settings if their generation moved, apply them and say
+    /// what changed
+    async fn check_settings(&mut self) {
+        let Ok(generation) = self.db.settings_generation().await else {
+            return;
+        };
+        if generation == self.settings_generation {
+            return;
+        }
+        self.settings_generation = generation;
+        let current = match self.db.list_settings().await {
+            Ok(current) => current,
+            Err(e) => {
+                self.notify(
+                    Notification::warning("Couldn't reload settings").with_details(e.to_string()),
+                );
+                return;
+            }
+        };
+        let changes = settings::changes(&self.settings, &current);
+        self.settings = current;
+        if changes.is_empty() {
+            return;
         }
+        self.apply_settings();
+
+        let keys: Vec<&str> = changes.iter().map(|c| c.key).collect();
+        let details: Vec<String> = changes
+            .iter()
+            .map(|change| match change.effect {
+                LiveEffect::Theme | LiveEffect::Refresh => format!("{} (applied)", change),
+                LiveEffect::Commands => format!("{} (used by commands, not the TUI)", change),
+            })
+            .collect();
+        self.notify(
+            Notification::info(format!("Settings changed: {}", keys.join(", ")))
+                .with_details(details.join("\n")),
+        );
+    }
+
+    /// The value in effect: the set one, else the default
+    fn setting(&self, key: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .or_else(|| settings::find(key).and_then(|def| def.default))
+    }
+
+    fn apply_settings(&mut self) {
+        let name = self
+            .setting(TUI_THEME)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        self.accent = self.setting(TUI_ACCENT).and_then(|v| parse_color(v).ok());
+        self.set_theme(name);
+        self.refresh_every = self
+            .setting(TUI_REFRESH_SECS)
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|secs| *secs > 0)
+            .map(Duration::seconds);
+    }
+
+    /// Switch to the next preset and remember it
+    pub async fn cycle_theme(&mut self) {
+        let name = self.theme.name.next();
+        self.set_theme(name);
+        if let Err(e) = self.db.set_setting(TUI_THEME, &name.to_string()).await {
+            self.notify(
+                Notification::warning(format!("Theme {} not saved for next time", name))
+                    .with_details(e.to_string()),
+            );
+            return;
+        }
+        // Our own change, not one to report
+        self.load_settings().await;
+    }
+
+    fn set_theme(&mut self, name: ThemeName) {
+        let palette = Palette::preset(name).with_accent(self.accent, self.color_depth);
+        self.theme = Theme::new(&palette);
     }
 
     pub async fn load_all(&mut self) {
         self.loading = true;
+        self.loaded_at = self.now;
 
         // Load v6 entities
-        if let Ok(projects) = self.db.list_projects().await {
+        let result = self.db.list_projects().await;
+        if let Some(projects) = self.loaded("projects", SelectedPanel::Projects, result) {
             self.projects = projects;
         }
-        if let Ok(servers) = self.db.list_servers().await {
+        let result = self.db.list_maintenance_windows().await;
+        if let Some(windows) = self.loaded("maintenance windows", SelectedPanel::Projects, result) {
+            self.maintenance = windows;
+        }
+        let result = self.db.list_servers().await;
+        if let Some(servers) = self.loaded("servers", SelectedPanel::Servers, result) {
             self.servers = servers;
         }
-        if let Ok(domains) = self.db.list_domains().await {
+        self.tunnels = Tunnels::detect(&self.servers).await;
+        let result = self.db.list_services().await;
+        if let Some(services) = self.loaded("services", SelectedPanel::Servers, result) {
+            self.services = services;
+        }
+        let result = self.db.list_discovery_cache(discovery::CONTAINERS).await;
+        if let Some(discovered) =
+            self.loaded("discovered containers", SelectedPanel::Servers, result)
+        {
+            self.discovered = discovered;
+        }
+        let result = self.db.list_domains().await;
+        if let Some(domains) = self.loaded("domains", SelectedPanel::Domains, result) {
             self.domains = domains;
         }
-        if let Ok(databases) = self.db.list_database_credentials().await {
+        let result = self.db.list_database_credentials().await;
+        if let Some(databases) = self.loaded("databases", SelectedPanel::Databases, result) {
             self.databases = databases;
         }
-        if let Ok(scripts) = self.db.list_scripts().await {
+        let result = self.db.list_scripts().await;
+        if let Some(scripts) = self.loaded("scripts", SelectedPanel::Scripts, result) {
             self.scripts = scripts;
         }
+        let result = self.db.list_docker_hosts().await;
+        if let Some(hosts) = self.loaded("Docker hosts", SelectedPanel::Status, result) {
+            self.docker_hosts = hosts;
+        }
+        let result = self.db.list_coolify_instances().await;
+        if let Some(instances) = self.loaded("Coolify instances", SelectedPanel::Status, result) {
+            self.coolify = instances;
+        }
+        let result = self.db.list_git_repos().await;
+        if let Some(repos) = self.loaded("Git repositories", SelectedPanel::Status, result) {
+            self.repos = repos;
+        }
+        self.reload_activity().await;
+        self.reload_project_detail().await;
 
         self.loading = false;
     }
 
+    /// Switch to a panel; coming from below, the cursor of a list starts on
+    /// its last row
+    pub fn show_panel(&mut self, panel: SelectedPanel, from_below: bool) {
+        self.selected_panel = panel;
+        self.project_detail = None;
+        self.filter = None;
+        self.reset_cursors(from_below);
+    }
+
+    /// Put the cursor of every list on its first or last row
+    pub fn reset_cursors(&mut self, last: bool) {
+        if last {
+            self.script_selected = self.script_rows().len().saturating_sub(1);
+            self.project_selected = self.visible_projects().len().saturating_sub(1);
+            self.server_selected = self.visible_servers().len().saturating_sub(1);
+            self.domain_selected = self.visible_domains().len().saturating_sub(1);
+            self.database_selected = self.visible_databases().len().saturating_sub(1);
+        } else {
+            self.script_selected = 0;
+            self.project_selected = 0;
+            self.server_selected = 0;
+            self.domain_selected = 0;
+            self.database_selected = 0;
+        }
+    }
+
+    /// Move the cursor of the selected panel's list; false when there's no
+    /// list or the cursor is on its first or last row
+    pub fn select_item(&mut self, down: bool) -> bool {
+        match self.selected_panel {
+            // Nothing to move through, but not a reason to leave either
+            SelectedPanel::Projects if self.project_detail.is_some() => true,
+            SelectedPanel::Projects => {
+                let rows = self.visible_projects().len();
+                step(&mut self.project_selected, rows, down)
+            }
+            SelectedPanel::Servers => {
+                let rows = self.visible_servers().len();
+                step(&mut self.server_selected, rows, down)
+            }
+            SelectedPanel::Domains => {
+                let rows = self.visible_domains().len();
+                step(&mut self.domain_selected, rows, down)
+            }
+            SelectedPanel::Databases => {
+                let rows = self.visible_databases().len();
+                step(&mut self.database_selected, rows, down)
+            }
+            SelectedPanel::Scripts => self.select_script(down),
+            SelectedPanel::Status | SelectedPanel::Activity => false,
+        }
+    }
+
+    /// The entry `e` and `d` act on: the row under the cursor of the
+    /// selected panel, or the open project. `None` on a namespace row.
+    pub fn selection(&self) -> Option<Selection> {
+        let (entity_type, id, name) = match self.selected_panel {
+            SelectedPanel::Projects => {
+                let project = self.selected_project()?;
+                (EntityType::Project, &project.id, &project.name)
+            }
+            SelectedPanel::Servers => {
+                let server = self.selected_server()?;
+                (EntityType::Server, &server.id, &server.name)
+            }
+            SelectedPanel::Domains => {
+                let domain = self.selected_domain()?;
+                (EntityType::Domain, &domain.id, &domain.domain)
+            }
+            SelectedPanel::Databases => {
+                let database = self.selected_database()?;
+                (EntityType::Database, &database.id, &database.name)
+            }
+            SelectedPanel::Scripts => {
+                let script = self.selected_script()?;
+                (EntityType::Script, &script.id, &script.name)
+            }
+            SelectedPanel::Status | SelectedPanel::Activity => return None,
+        };
+        Some(Selection {
+            entity_type,
+            id: id.clone(),
+            name: name.clone(),
+        })
+    }
+
+    pub fn selected_server(&self) -> Option<&Server> {
+        let servers = self.visible_servers();
+        servers
+            .get(clamp(self.server_selected, servers.len()))
+            .copied()
+    }
+
+    pub fn selected_domain(&self) -> Option<&Domain> {
+        let domains = self.visible_domains();
+        domains
+            .get(clamp(self.domain_selected, domains.len()))
+            .copied()
+    }
+
+    pub fn selected_database(&self) -> Option<&DatabaseCredentials> {
+        let databases = self.visible_databases();
+        databases
+            .get(clamp(self.database_selected, databases.len()))
+            .copied()
+    }
+
+    /// The script under the cursor of the scripts tree
+    pub fn selected_script(&self) -> Option<&Script> {
+        let rows = self.script_rows();
+        match rows.get(clamp(self.script_selected, rows.len())) {
+            Some(TreeRow::Script { index, .. }) => self.visible_scripts().get(*index).copied(),
+            _ => None,
+        }
+    }
+
+    /// The filter narrowing the selected panel's list: the one being typed
+    /// after `/`, else the applied one
+    pub fn list_filter(&self) -> Option<&str> {
+        if self.input_mode == InputMode::Searching {
+            Some(self.search_input.as_str())
+        } else {
+            self.filter.as_deref()
+        }
+        .filter(|query| !query.trim().is_empty())
+    }
+
+    /// `items` matching the list filter if `panel` is selected, else all
+    fn narrowed<'a, T>(
+        &self,
+        panel: SelectedPanel,
+        items: &'a [T],
+        candidate: fn(&T) -> Candidate,
+    ) -> Vec<&'a T> {
+        match self.list_filter() {
+            Some(query) if self.selected_panel == panel => items
+                .iter()
+                .filter(|item| search::match_kind(query, &candidate(item)).is_some())
+                .collect(),
+            _ => items.iter().collect(),
+        }
+    }
+
+    pub fn visible_projects(&self) -> Vec<&Project> {
+        self.narrowed(SelectedPanel::Projects, &self.projects, Candidate::project)
+    }
+
+    pub fn visible_servers(&self) -> Vec<&Server> {
+        self.narrowed(SelectedPanel::Servers, &self.servers, Candidate::server)
+    }
+
+    pub fn visible_domains(&self) -> Vec<&Domain> {
+        self.narrowed(SelectedPanel::Domains, &self.domains, Candidate::domain)
+    }
+
+    pub fn visible_databases(&self) -> Vec<&DatabaseCredentials> {
+        self.narrowed(
+            SelectedPanel::Databases,
+            &self.databases,
+            Candidate::database,
+        )
+    }
+
+    pub fn visible_scripts(&self) -> Vec<&Script> {
+        self.narrowed(SelectedPanel::Scripts, &self.scripts, Candidate::script)
+    }
+
+    /// The project open in the detail view, else the one under the cursor
+    pub fn selected_project(&self) -> Option<&Project> {
+        match &self.project_detail {
+            Some(detail) => self.projects.iter().find(|p| p.id == detail.project_id),
+            None => {
+                let projects = self.visible_projects();
+                projects
+                    .get(clamp(self.project_selected, projects.len()))
+                    .copied()
+            }
+        }
+    }
+
+    /// Open the project under the cursor with its linked resources
+    pub async fn open_project(&mut self) {
+        let Some(project_id) = self.selected_project().map(|p| p.id.clone()) else {
+            return;
+        };
+        self.project_detail = Some(ProjectDetail {
+            project_id,
+            resources: Vec::new(),
+        });
+        self.reload_project_detail().await;
+    }
+
+    /// Re-read the open project's links; closes the view if the project is
+    /// gone
+    async fn reload_project_detail(&mut self) {
+        let Some(project_id) = self.project_detail.as_ref().map(|d| d.project_id.clone()) else {
+            return;
+        };
+        if !self.projects.iter().any(|p| p.id == project_id) {
+            self.project_detail = None;
+            return;
+        }
+        let result = self.db.get_project_resources(&project_id).await;
+        if let Some(resources) = self.loaded("linked resources", SelectedPanel::Projects, result) {
+            if let Some(detail) = self.project_detail.as_mut() {
+                detail.resources = resources;
+            }
+        }
+    }
+
+    /// Prefill the form with the selected entry and start editing it
+    pub fn edit_selected(&mut self) {
+        let Some(selection) = self.selection() else {
+            return;
+        };
+        let form = match self.selected_panel {
+            SelectedPanel::Projects => self.selected_project().map(|project| InputForm {
+                name: project.name.clone(),
+                description: project.description.clone().unwrap_or_default(),
+                stack: project.stack.join(", "),
+                status: project.status.to_string(),
+                ..InputForm::default()
+            }),
+            SelectedPanel::Servers => self.selected_server().map(|server| InputForm {
+                name: server.name.clone(),
+                host: server.host.clone(),
+                server_type: server.server_type.to_string(),
+                provider: server.provider.clone().unwrap_or_default(),
+                ..InputForm::default()
+            }),
+            SelectedPanel::Domains => self.selected_domain().map(|domain| InputForm {
+                domain: domain.domain.clone(),
+                domain_type: domain.domain_type.to_string(),
+                ssl: domain.ssl.to_string(),
+                ..InputForm::default()
+            }),
+            SelectedPanel::Databases => self.selected_database().map(|database| InputForm {
+                name: database.name.clone(),
+                db_type: database.db_type.to_string(),
+                host: database.host.clone().unwrap_or_default(),
+                port: database.port.map(|p| p.to_string()).unwrap_or_default(),
+                ..InputForm::default()
+            }),
+            SelectedPanel::Scripts => self.selected_script().map(|script| InputForm {
+                name: script.name.clone(),
+                command: script.command.clone(),
+                script_type: script.script_type.to_string(),
+                ..InputForm::default()
+            }),
+            SelectedPanel::Status | SelectedPanel::Activity => None,
+        };
+        let Some(form) = form else {
+            return;
+        };
+        self.input_form = InputForm {
+            editing: Some(selection.id),
+            ..form
+        };
+        self.input_mode = InputMode::Adding;
+    }
+
+    /// Rows of the scripts panel: the namespace tree of the visible
+    /// scripts without the contents of folded namespaces
+    pub fn script_rows(&self) -> Vec<TreeRow<'_>> {
+        let names: Vec<&str> = self
+            .visible_scripts()
+            .into_iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        script_namespace::tree(&names, &self.collapsed_namespaces)
+    }
+
+    /// Move the scripts cursor; false on the first or last row, where the
+    /// key moves on to the next panel instead
+    pub fn select_script(&mut self, down: bool) -> bool {
+        let rows = self.script_rows().len();
+        step(&mut self.script_selected, rows, down)
+    }
+
+    /// Fold (`Some(true)`), unfold or toggle (`None`) the namespace under
+    /// the scripts cursor
+    pub fn fold_namespace(&mut self, fold: Option<bool>) {
+        let path = match self.script_rows().get(self.script_selected) {
+            Some(TreeRow::Namespace {
+                path, collapsed, ..
+            }) if fold != Some(*collapsed) => path.clone(),
+            _ => return,
+        };
+        if !self.collapsed_namespaces.remove(&path) {
+            self.collapsed_namespaces.insert(path);
+        }
+    }
+
+    /// Reload the activity feed from the newest entry (after filter changes)
+    pub async fn reload_activity(&mut self) {
+        self.activity.clear();
+        self.activity_selected = 0;
+        self.activity_expanded = false;
+        self.activity_exhausted = false;
+        self.load_more_activity().await;
+    }
+
+    /// Append the next page of older activity entries
+    pub async fn load_more_activity(&mut self) {
+        if self.activity_exhausted {
+            return;
+        }
+        let cursor = self.activity.last().map(|e| e.cursor());
+        match self
+            .db
+            .activity_feed(cursor.as_ref(), &self.activity_filter, ACTIVITY_PAGE_SIZE)
+            .await
+        {
+            Ok(page) => {
+                self.activity_exhausted = (page.len() as i64) < ACTIVITY_PAGE_SIZE;
+                self.activity.extend(page);
+            }
+            Err(e) => {
+                self.activity_exhausted = true;
+                self.loaded::<(), _>("activity", SelectedPanel::Activity, Err(e));
+            }
+        }
+    }
+
+    /// Move the activity selection, loading older pages when scrolling past the end
+    pub async fn select_activity(&mut self, down: bool) {
+        if down {
+            if self.activity_selected + 1 >= self.activity.len() {
+                self.load_more_activity().await;
+            }
+            if self.activity_selected + 1 < self.activity.len() {
+                self.activity_selected += 1;
+            }
+        } else {
+            self.activity_selected = self.activity_selected.saturating_sub(1);
+        }
+    }
+
     pub fn get_form_fields(&self) -> Vec<(&'static str, &str)> {
         match self.selected_panel {
             SelectedPanel::Projects => vec![
@@ -89,7 +811,7 @@ impl App {
                 ("Command", &self.input_form.command),
                 ("Type", &self.input_form.script_type),
             ],
-            SelectedPanel::Status => vec![],
+            SelectedPanel::Status | SelectedPanel::Activity => vec![],
         }
     }
 
@@ -129,7 +851,7 @@ impl App {
                 2 => Some(&mut self.input_form.script_type),
                 _ => None,
             },
-            SelectedPanel::Status => None,
+            SelectedPanel::Status | SelectedPanel::Activity => None,
         }
     }
 
@@ -140,7 +862,7 @@ impl App {
             SelectedPanel::Domains => 3,
             SelectedPanel::Databases => 4,
             SelectedPanel::Scripts => 3,
-            SelectedPanel::Status => 0,
+            SelectedPanel::Status | SelectedPanel::Activity => 0,
         }
     }
 
@@ -168,3 +890,23 @@ impl App {
         }
     }
 }
+
+/// A cursor kept on one of `rows` rows
+fn clamp(selected: usize, rows: usize) -> usize {
+    selected.min(rows.saturating_sub(1))
+}
+
+/// Move a cursor over `rows` rows one step; false if it's already on the
+/// first or last one
+fn step(selected: &mut usize, rows: usize, down: bool) -> bool {
+    *selected = clamp(*selected, rows);
+    if down && *selected + 1 < rows {
+        *selected += 1;
+        true
+    } else if !down && *selected > 0 {
+        *selected -= 1;
+        true
+    } else {
+        false
+    }
+}
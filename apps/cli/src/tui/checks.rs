@@ -0,0 +1,216 @@
+//! Connection checks that run beside the render loop
+//!
+//! Every server, database, Docker host, Coolify instance and Git
+//! repository gets its own task; each reports over a channel the App drains on every tick, so
+//! the dots change one by one instead of the UI freezing until the slowest
+//! host answers. Failures are also reported through the App's
+//! [`Notifier`], and the last check of a [`Round`] says how the round went.
+
+use super::notifications::{Notification, Notifier};
+use super::types::{ConnectionStatus, SelectedPanel};
+use crate::handlers::preflight::check_database;
+use crate::handlers::project_status::server_verdict;
+use pctrl_coolify::CoolifyManager;
+use pctrl_core::fanout::Outcome;
+use pctrl_core::humanize;
+use pctrl_core::{CoolifyInstance, DatabaseCredentials, DockerHost, GitRepo, Server};
+use pctrl_database::Database;
+use pctrl_docker::DockerManager;
+use pctrl_git::GitManager;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long one check may take before its entry counts as offline
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A finished check: the server or database ID (see [`docker_id`],
+/// [`coolify_id`] and [`git_id`] for the others) and what came of it
+pub type CheckResult = (String, ConnectionStatus);
+
+/// Sending half of the App's check channel
+pub type CheckSender = mpsc::UnboundedSender<CheckResult>;
+
+/// Checks started together
+pub struct Round {
+    remaining: AtomicUsize,
+    total: usize,
+    offline: AtomicUsize,
+    notifier: Notifier,
+}
+
+impl Round {
+    pub fn new(total: usize, notifier: Notifier) -> Arc<Self> {
+        Arc::new(Self {
+            remaining: AtomicUsize::new(total),
+            total,
+            offline: AtomicUsize::new(0),
+            notifier,
+        })
+    }
+
+    /// Count a finished check, reporting it if it failed; the last one
+    /// sends the round's summary
+    fn finish(&self, subject: &Subject, status: &ConnectionStatus) {
+        let failure = match status {
+            ConnectionStatus::Offline(error) => Some(
+                Notification::error(format!("{} is unreachable", subject.name)).with_details(error),
+            ),
+            ConnectionStatus::Attention(note) => Some(
+                Notification::warning(format!("{} needs attention", subject.name))
+                    .with_details(note),
+            ),
+            _ => None,
+        };
+        if let Some(failure) = failure {
+            let _ = self.notifier.send(failure.with_panel(subject.panel));
+        }
+        if matches!(status, ConnectionStatus::Offline(_)) {
+            self.offline.fetch_add(1, Ordering::SeqCst);
+        }
+        if self.remaining.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        let checked = format!(
+            "Checked {}",
+            humanize::count(self.total as u64, "connection", "connections")
+        );
+        let notification = match self.offline.load(Ordering::SeqCst) {
+            0 => Notification::success(format!("{}: all reachable", checked)),
+            offline => Notification::warning(format!("{}: {} unreachable", checked, offline)),
+        };
+        let _ = self.notifier.send(notification);
+    }
+}
+
+/// What a check is about, as its notifications name it
+struct Subject {
+    name: String,
+    /// Panel showing its status
+    panel: SelectedPanel,
+}
+
+impl Subject {
+    fn new(kind: &str, name: &str, panel: SelectedPanel) -> Self {
+        Self {
+            name: format!("{} '{}'", kind, name),
+            panel,
+        }
+    }
+}
+
+/// Log in to the server over SSH, or try its SSH port without a credential
+pub fn spawn_server(db: Arc<Database>, server: Server, results: CheckSender, round: Arc<Round>) {
+    let id = server.id.clone();
+    let subject = Subject::new("Server", &server.name, SelectedPanel::Servers);
+    spawn(id, subject, results, round, async move {
+        match server_verdict(&db, &server).await {
+            Ok((Outcome::Failed, error)) => {
+                ConnectionStatus::Offline(error.unwrap_or_else(|| "unreachable".to_string()))
+            }
+            Ok(_) => ConnectionStatus::Online,
+            Err(e) => ConnectionStatus::Offline(e.to_string()),
+        }
+    });
+}
+
+/// Connect to the database's port; SQLite files only need to exist
+pub fn spawn_database(database: DatabaseCredentials, results: CheckSender, round: Arc<Round>) {
+    let id = database.id.clone();
+    let subject = Subject::new("Database", &database.name, SelectedPanel::Databases);
+    spawn(id, subject, results, round, async move {
+        match check_database(&database.id, Some(&database)).await.error {
+            None => ConnectionStatus::Online,
+            Some(error) => ConnectionStatus::Offline(error),
+        }
+    });
+}
+
+/// Ping the Docker daemon the way the host's URL says
+pub fn spawn_docker(host: DockerHost, results: CheckSender, round: Arc<Round>) {
+    let id = docker_id(&host.id);
+    let subject = Subject::new("Docker host", &host.name, SelectedPanel::Status);
+    spawn(id, subject, results, round, async move {
+        let mut docker = DockerManager::new();
+        let host_id = host.id.clone();
+        if let Err(e) = docker.add_host(host) {
+            return ConnectionStatus::Offline(e.to_string());
+        }
+        match docker.health_check(&host_id).await {
+            Ok(()) => ConnectionStatus::Online,
+            Err(e) => ConnectionStatus::Offline(e.to_string()),
+        }
+    });
+}
+
+/// Key of a Docker host's check
+pub fn docker_id(id: &str) -> String {
+    format!("docker:{}", id)
+}
+
+/// Ask the instance for its version with its token
+pub fn spawn_coolify(instance: CoolifyInstance, results: CheckSender, round: Arc<Round>) {
+    let id = coolify_id(&instance.id);
+    let subject = Subject::new("Coolify", &instance.name, SelectedPanel::Status);
+    spawn(id, subject, results, round, async move {
+        let mut coolify = CoolifyManager::new();
+        let instance_id = instance.id.clone();
+        coolify.add_instance(instance);
+        match coolify.health_check(&instance_id).await {
+            Ok(_) => ConnectionStatus::Online,
+            Err(e) => ConnectionStatus::Offline(e.to_string()),
+        }
+    });
+}
+
+/// Key of a Coolify instance's check, apart from server and database IDs
+pub fn coolify_id(id: &str) -> String {
+    format!("coolify:{}", id)
+}
+
+/// Read the repository's status; uncommitted changes need attention
+pub fn spawn_git(repo: GitRepo, results: CheckSender, round: Arc<Round>) {
+    let id = git_id(&repo.id);
+    let subject = Subject::new("Repository", &repo.name, SelectedPanel::Status);
+    spawn(id, subject, results, round, async move {
+        let repo_id = repo.id.clone();
+        let mut git = GitManager::new();
+        git.add_repo(repo);
+        match tokio::task::spawn_blocking(move || git.repo_status(&repo_id)).await {
+            Ok(Ok(status)) if status.is_dirty() => {
+                ConnectionStatus::Attention(format!("{} · {}", status.head(), status.changes()))
+            }
+            Ok(Ok(_)) => ConnectionStatus::Online,
+            Ok(Err(e)) => ConnectionStatus::Offline(e.to_string()),
+            Err(e) => ConnectionStatus::Offline(e.to_string()),
+        }
+    });
+}
+
+/// Key of a Git repository's check
+pub fn git_id(id: &str) -> String {
+    format!("git:{}", id)
+}
+
+/// Run a check with [`CHECK_TIMEOUT`] and send its result; a closed
+/// channel means the TUI is gone and nobody waits for it. The round is
+/// told first, so its notifications are queued before the dot changes.
+fn spawn(
+    id: String,
+    subject: Subject,
+    results: CheckSender,
+    round: Arc<Round>,
+    check: impl Future<Output = ConnectionStatus> + Send + 'static,
+) {
+    tokio::spawn(async move {
+        let status = tokio::time::timeout(CHECK_TIMEOUT, check)
+            .await
+            .unwrap_or_else(|_| {
+                ConnectionStatus::Offline(format!("timed out after {}s", CHECK_TIMEOUT.as_secs()))
+            });
+        round.finish(&subject, &status);
+        let _ = results.send((id, status));
+    });
+}
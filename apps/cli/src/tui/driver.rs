@@ -0,0 +1,146 @@
+//! Headless TUI driver for tests
+//!
+//! Runs the App against an in-memory database and a [`TestBackend`]: keys
+//! go through the same input handling as the terminal loop, the clock only
+//! moves when the test advances it, and every frame can be inspected as
+//! text or as a styled buffer.
+
+use super::app::App;
+use super::checks::CHECK_TIMEOUT;
+use super::{input, ui};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use pctrl_core::theme::ColorDepth;
+use pctrl_database::Database;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::Terminal;
+use std::sync::Arc;
+
+/// Terminal size frames are rendered at
+pub const WIDTH: u16 = 120;
+pub const HEIGHT: u16 = 40;
+
+pub struct TuiDriver {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+    /// A key asked the TUI to quit
+    pub quit: bool,
+}
+
+impl TuiDriver {
+    /// A fresh in-memory database
+    pub async fn new() -> Self {
+        let db = Database::new("sqlite::memory:", None)
+            .await
+            .expect("in-memory database");
+        Self::with_db(Arc::new(db)).await
+    }
+
+    /// Drive the TUI against an existing database. The clock starts at
+    /// [`start_time`] and colors are rendered in true color.
+    pub async fn with_db(db: Arc<Database>) -> Self {
+        let mut app = App::new(db);
+        app.color_depth = ColorDepth::TrueColor;
+        app.tick(start_time());
+        app.load_settings().await;
+        app.load_all().await;
+
+        let terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("test terminal");
+        Self {
+            app,
+            terminal,
+            quit: false,
+        }
+    }
+
+    pub fn db(&self) -> Arc<Database> {
+        self.app.db.clone()
+    }
+
+    /// Press one key, as the terminal loop would deliver it
+    pub async fn press(&mut self, code: KeyCode) {
+        self.send(KeyEvent::new(code, KeyModifiers::NONE)).await;
+    }
+
+    /// Press several keys in order
+    pub async fn press_all(&mut self, codes: &[KeyCode]) {
+        for code in codes {
+            self.press(*code).await;
+        }
+    }
+
+    /// Type text character by character
+    pub async fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c)).await;
+        }
+    }
+
+    async fn send(&mut self, key: KeyEvent) {
+        assert!(!self.quit, "key sent after the TUI quit");
+        self.quit = input::handle_input(&mut self.app, Event::Key(key))
+            .await
+            .expect("input handling");
+    }
+
+    /// Move the clock forward
+    pub fn advance(&mut self, by: Duration) {
+        self.app.tick(self.app.now + by);
+    }
+
+    /// Move the clock forward and run what the terminal loop does between
+    /// frames
+    pub async fn wait(&mut self, by: Duration) {
+        self.advance(by);
+        self.app.background().await;
+    }
+
+    /// Wait, in real time, until the connection checks have reported,
+    /// and take in their results
+    pub async fn settle_checks(&mut self) {
+        let deadline = std::time::Instant::now() + CHECK_TIMEOUT * 2;
+        while self.app.checks_running() {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "connection checks still running"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            self.app.tick(self.app.now);
+        }
+    }
+
+    /// Reload everything from the database, like pressing `r`
+    pub async fn refresh(&mut self) {
+        self.app.load_all().await;
+    }
+
+    /// Render a frame and return its buffer
+    pub fn render(&mut self) -> Buffer {
+        let app = &self.app;
+        self.terminal
+            .draw(|f| ui::render(f, app))
+            .expect("render")
+            .buffer
+            .clone()
+    }
+
+    /// Render a frame as plain text, one line per row
+    pub fn screen(&mut self) -> String {
+        let buffer = self.render();
+        (0..buffer.area.height)
+            .map(|y| {
+                let row: String = (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect();
+                row.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Where the driver's clock starts
+pub fn start_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+}
@@ -1,11 +1,17 @@
 //! TUI input handling
 
 use super::app::App;
+use super::notifications::Notification;
 use super::types::{InputMode, SelectedPanel};
+use crate::handlers::project::check_status;
+use crate::handlers::references::capitalize;
+use crate::handlers::resolve::name_taken;
 use crossterm::event::{Event, KeyCode, KeyEventKind};
+use pctrl_core::diff;
 use pctrl_core::{
-    DatabaseCredentials, DatabaseType, Domain, DomainType, Project, ProjectStatus, Script,
-    ScriptType, Server, ServerType,
+    current_holder, script_namespace, ActivityKind, DatabaseCredentials, DatabaseType, Domain,
+    DomainType, EntityType, Project, ProjectStatus, Script, ScriptType, ScriptUpdate, Server,
+    ServerType,
 };
 use std::io;
 use uuid::Uuid;
@@ -18,36 +24,167 @@ pub async fn handle_input(app: &mut App, event: Event) -> io::Result<bool> {
         }
 
         match app.input_mode {
+            InputMode::Normal if app.selected_panel == SelectedPanel::Activity => match key.code {
+                KeyCode::Char('q') => return Ok(true),
+                KeyCode::Esc => {
+                    if app.activity_expanded {
+                        app.activity_expanded = false;
+                    } else if app.activity_filter.search.is_some() {
+                        app.activity_filter.search = None;
+                        app.reload_activity().await;
+                    } else {
+                        return Ok(true);
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j') => app.select_activity(true).await,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    if app.activity_selected == 0 {
+                        app.show_panel(prev_panel(app.selected_panel), true);
+                    } else {
+                        app.select_activity(false).await;
+                    }
+                }
+                KeyCode::Tab => app.show_panel(next_panel(app.selected_panel), false),
+                KeyCode::BackTab => app.show_panel(prev_panel(app.selected_panel), false),
+                KeyCode::Enter if !app.activity.is_empty() => {
+                    app.activity_expanded = !app.activity_expanded;
+                }
+                KeyCode::Char('/') => {
+                    app.search_input = app.activity_filter.search.clone().unwrap_or_default();
+                    app.input_mode = InputMode::Searching;
+                }
+                KeyCode::Char(c @ '1'..='4') => {
+                    let idx = c as usize - '1' as usize;
+                    app.activity_filter.toggle(ActivityKind::ALL[idx]);
+                    app.reload_activity().await;
+                }
+                KeyCode::Char('r') => refresh(app).await,
+                KeyCode::Char('T') => app.cycle_theme().await,
+                KeyCode::Char('n') => app.open_notifications(),
+                _ => {}
+            },
             InputMode::Normal => match key.code {
+                KeyCode::Esc if app.project_detail.is_some() => app.project_detail = None,
+                KeyCode::Esc if app.filter.is_some() => {
+                    app.filter = None;
+                    app.reset_cursors(false);
+                }
                 KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
-                KeyCode::Down | KeyCode::Char('j') => {
-                    app.selected_panel = match app.selected_panel {
-                        SelectedPanel::Status => SelectedPanel::Projects,
-                        SelectedPanel::Projects => SelectedPanel::Servers,
-                        SelectedPanel::Servers => SelectedPanel::Domains,
-                        SelectedPanel::Domains => SelectedPanel::Databases,
-                        SelectedPanel::Databases => SelectedPanel::Scripts,
-                        SelectedPanel::Scripts => SelectedPanel::Status,
-                    };
+                // In the projects list and the scripts tree, j/k move the
+                // cursor until it runs off either end
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => {
+                    let moved = key.code != KeyCode::Tab && app.select_item(true);
+                    if !moved {
+                        app.show_panel(next_panel(app.selected_panel), false);
+                    }
+                }
+                KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => {
+                    let moved = key.code != KeyCode::BackTab && app.select_item(false);
+                    if !moved {
+                        // Up enters a list at its last row, BackTab at the top
+                        app.show_panel(
+                            prev_panel(app.selected_panel),
+                            key.code != KeyCode::BackTab,
+                        );
+                    }
+                }
+                KeyCode::Enter
+                    if app.selected_panel == SelectedPanel::Projects
+                        && app.project_detail.is_none() =>
+                {
+                    app.open_project().await;
+                }
+                KeyCode::Char('e') if app.selection().is_some() => app.edit_selected(),
+                KeyCode::Char('d') if app.selection().is_some() => ask_delete(app).await,
+                KeyCode::Enter if app.selected_panel == SelectedPanel::Scripts => {
+                    app.fold_namespace(None);
+                }
+                KeyCode::Left | KeyCode::Char('h')
+                    if app.selected_panel == SelectedPanel::Scripts =>
+                {
+                    app.fold_namespace(Some(true));
+                }
+                KeyCode::Right | KeyCode::Char('l')
+                    if app.selected_panel == SelectedPanel::Scripts =>
+                {
+                    app.fold_namespace(Some(false));
+                }
+                KeyCode::Char('/')
+                    if app.selected_panel != SelectedPanel::Status
+                        && app.project_detail.is_none() =>
+                {
+                    app.search_input = app.filter.clone().unwrap_or_default();
+                    app.input_mode = InputMode::Searching;
+                }
+                KeyCode::Char('a') if app.selected_panel != SelectedPanel::Status => {
+                    app.reset_form();
+                    app.input_mode = InputMode::Adding;
+                }
+                KeyCode::Char('r') => refresh(app).await,
+                KeyCode::Char('T') => app.cycle_theme().await,
+                KeyCode::Char('n') => app.open_notifications(),
+                _ => {}
+            },
+            InputMode::Deleting => match key.code {
+                KeyCode::Char('y') => delete_selected(app).await,
+                KeyCode::Char('n') | KeyCode::Esc => app.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Notifications => match key.code {
+                KeyCode::Char('q') => return Ok(true),
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    if app.notification_expanded {
+                        app.notification_expanded = false;
+                    } else {
+                        app.close_notifications();
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j')
+                    if app.notification_selected + 1 < app.notifications.len() =>
+                {
+                    app.notification_selected += 1;
+                    app.notification_expanded = false;
                 }
                 KeyCode::Up | KeyCode::Char('k') => {
-                    app.selected_panel = match app.selected_panel {
-                        SelectedPanel::Status => SelectedPanel::Scripts,
-                        SelectedPanel::Projects => SelectedPanel::Status,
-                        SelectedPanel::Servers => SelectedPanel::Projects,
-                        SelectedPanel::Domains => SelectedPanel::Servers,
-                        SelectedPanel::Databases => SelectedPanel::Domains,
-                        SelectedPanel::Scripts => SelectedPanel::Databases,
-                    };
+                    app.notification_selected = app.notification_selected.saturating_sub(1);
+                    app.notification_expanded = false;
                 }
-                KeyCode::Char('a') => {
-                    if app.selected_panel != SelectedPanel::Status {
-                        app.reset_form();
-                        app.input_mode = InputMode::Adding;
+                KeyCode::Enter => app.open_notification(),
+                KeyCode::Char('d') => app.notification_expanded = !app.notification_expanded,
+                _ => {}
+            },
+            // Other panels narrow their list as the filter is typed
+            InputMode::Searching if app.selected_panel != SelectedPanel::Activity => {
+                match key.code {
+                    KeyCode::Esc => app.input_mode = InputMode::Normal,
+                    KeyCode::Enter => {
+                        let query = app.search_input.trim().to_string();
+                        app.filter = (!query.is_empty()).then_some(query);
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Backspace => {
+                        app.search_input.pop();
                     }
+                    KeyCode::Char(c) => app.search_input.push(c),
+                    _ => {}
                 }
-                KeyCode::Char('r') => {
-                    app.load_all().await;
+                app.reset_cursors(false);
+            }
+            InputMode::Searching => match key.code {
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                }
+                KeyCode::Enter => {
+                    let query = app.search_input.trim().to_string();
+                    app.activity_filter.search = (!query.is_empty()).then_some(query);
+                    app.input_mode = InputMode::Normal;
+                    app.reload_activity().await;
+                }
+                KeyCode::Backspace => {
+                    app.search_input.pop();
+                }
+                KeyCode::Char(c) => {
+                    app.search_input.push(c);
                 }
                 _ => {}
             },
@@ -73,12 +210,24 @@ pub async fn handle_input(app: &mut App, event: Event) -> io::Result<bool> {
                     }
                 }
                 KeyCode::Enter => {
-                    if let Err(e) = save_new_entry(app).await {
-                        app.input_form.message = Some(format!("Error: {}", e));
-                    } else {
-                        app.input_mode = InputMode::Normal;
-                        app.reset_form();
-                        app.load_all().await;
+                    let saved = match app.input_form.editing.clone() {
+                        Some(id) => save_edited_entry(app, &id)
+                            .await
+                            .map(|updated| format!("Updated {}", updated)),
+                        None => save_new_entry(app)
+                            .await
+                            .map(|added| format!("Added {}", added)),
+                    };
+                    match saved {
+                        Err(e) => app.input_form.message = Some(format!("Error: {}", e)),
+                        Ok(message) => {
+                            app.input_mode = InputMode::Normal;
+                            app.reset_form();
+                            app.load_all().await;
+                            app.notify(
+                                Notification::success(message).with_panel(app.selected_panel),
+                            );
+                        }
                     }
                 }
                 KeyCode::Backspace => {
@@ -98,8 +247,48 @@ pub async fn handle_input(app: &mut App, event: Event) -> io::Result<bool> {
     Ok(false)
 }
 
-/// Save a new entry based on current panel and form data
-async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
+/// `r`: reload from the database and check the connections again; a
+/// round of checks still running isn't started twice
+async fn refresh(app: &mut App) {
+    app.load_all().await;
+    if app.check_connections() {
+        app.notify(Notification::info("Reloaded from the database"));
+    } else {
+        app.notify(Notification::info(
+            "Reloaded from the database; connection checks still running",
+        ));
+    }
+}
+
+/// Panel below the given one in the sidebar (wraps around)
+fn next_panel(panel: SelectedPanel) -> SelectedPanel {
+    match panel {
+        SelectedPanel::Status => SelectedPanel::Projects,
+        SelectedPanel::Projects => SelectedPanel::Servers,
+        SelectedPanel::Servers => SelectedPanel::Domains,
+        SelectedPanel::Domains => SelectedPanel::Databases,
+        SelectedPanel::Databases => SelectedPanel::Scripts,
+        SelectedPanel::Scripts => SelectedPanel::Activity,
+        SelectedPanel::Activity => SelectedPanel::Status,
+    }
+}
+
+/// Panel above the given one in the sidebar (wraps around)
+fn prev_panel(panel: SelectedPanel) -> SelectedPanel {
+    match panel {
+        SelectedPanel::Status => SelectedPanel::Activity,
+        SelectedPanel::Projects => SelectedPanel::Status,
+        SelectedPanel::Servers => SelectedPanel::Projects,
+        SelectedPanel::Domains => SelectedPanel::Servers,
+        SelectedPanel::Databases => SelectedPanel::Domains,
+        SelectedPanel::Scripts => SelectedPanel::Databases,
+        SelectedPanel::Activity => SelectedPanel::Scripts,
+    }
+}
+
+/// Save a new entry based on current panel and form data; returns what
+/// was added, e.g. "project 'shop'"
+async fn save_new_entry(app: &mut App) -> anyhow::Result<String> {
     let id = Uuid::new_v4().to_string();
 
     match app.selected_panel {
@@ -108,17 +297,7 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 anyhow::bail!("Name is required");
             }
 
-            let stack: Vec<String> = if app.input_form.stack.is_empty() {
-                vec![]
-            } else {
-                app.input_form
-                    .stack
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect()
-            };
-
+            let stack = parse_stack(&app.input_form.stack);
             let status: ProjectStatus = app.input_form.status.parse().unwrap_or_default();
 
             let project = Project {
@@ -136,7 +315,11 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 notes: None,
             };
 
-            app.db.save_project(&project).await?;
+            app.db
+                .create_project(&project)
+                .await
+                .map_err(|e| name_taken("Project", e))?;
+            Ok(format!("project '{}'", project.name))
         }
         SelectedPanel::Servers => {
             if app.input_form.name.is_empty() || app.input_form.host.is_empty() {
@@ -163,9 +346,16 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 location: None,
                 specs: None,
                 notes: None,
+                requires_vpn: None,
+                max_containers: None,
+                max_memory_mb_allocated: None,
             };
 
-            app.db.save_server(&server).await?;
+            app.db
+                .create_server(&server)
+                .await
+                .map_err(|e| name_taken("Server", e))?;
+            Ok(format!("server '{}'", server.name))
         }
         SelectedPanel::Domains => {
             if app.input_form.domain.is_empty() {
@@ -178,9 +368,7 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 .parse()
                 .unwrap_or(DomainType::Production);
 
-            let ssl = app.input_form.ssl.to_lowercase() == "true"
-                || app.input_form.ssl.to_lowercase() == "yes"
-                || app.input_form.ssl == "1";
+            let ssl = parse_ssl(&app.input_form.ssl);
 
             let domain = Domain {
                 id,
@@ -193,9 +381,14 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 server_id: None,
                 container_id: None,
                 notes: None,
+                superseded_by: None,
             };
 
-            app.db.save_domain(&domain).await?;
+            app.db
+                .create_domain(&domain)
+                .await
+                .map_err(|e| name_taken("Domain", e))?;
+            Ok(format!("domain '{}'", domain.domain))
         }
         SelectedPanel::Databases => {
             if app.input_form.name.is_empty() {
@@ -243,12 +436,17 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 notes: None,
             };
 
-            app.db.save_database_credentials(&database).await?;
+            app.db
+                .create_database_credentials(&database)
+                .await
+                .map_err(|e| name_taken("Database", e))?;
+            Ok(format!("database '{}'", database.name))
         }
         SelectedPanel::Scripts => {
             if app.input_form.name.is_empty() || app.input_form.command.is_empty() {
                 anyhow::bail!("Name and Command are required");
             }
+            script_namespace::validate_name(&app.input_form.name).map_err(anyhow::Error::msg)?;
 
             let script_type: ScriptType = app
                 .input_form
@@ -271,12 +469,301 @@ async fn save_new_entry(app: &mut App) -> anyhow::Result<()> {
                 last_result: None,
                 exit_code: None,
                 last_output: None,
+                working_dir: None,
+                run_window: None,
+                env: Default::default(),
             };
 
-            app.db.save_script(&script).await?;
+            app.db
+                .create_script(&script)
+                .await
+                .map_err(|e| name_taken("Script", e))?;
+            Ok(format!("script '{}'", script.name))
+        }
+        SelectedPanel::Status | SelectedPanel::Activity => anyhow::bail!("Nothing to add here"),
+    }
+}
+
+/// "rust, postgres" -> ["rust", "postgres"]
+fn parse_stack(stack: &str) -> Vec<String> {
+    stack
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// "true", "yes" or "1" turn SSL on, anything else off
+fn parse_ssl(ssl: &str) -> bool {
+    matches!(ssl.trim().to_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+/// Trimmed, `None` when empty
+fn optional(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Save the entry being edited in the form; returns what was updated, e.g.
+/// "project 'shop'"
+async fn save_edited_entry(app: &mut App, id: &str) -> anyhow::Result<String> {
+    match app.selected_panel {
+        SelectedPanel::Projects => save_edited_project(app, id).await,
+        SelectedPanel::Servers => save_edited_server(app, id).await,
+        SelectedPanel::Domains => save_edited_domain(app, id).await,
+        SelectedPanel::Databases => save_edited_database(app, id).await,
+        SelectedPanel::Scripts => save_edited_script(app, id).await,
+        SelectedPanel::Status | SelectedPanel::Activity => anyhow::bail!("Nothing to edit here"),
+    }
+}
+
+async fn save_edited_project(app: &mut App, id: &str) -> anyhow::Result<String> {
+    let Some(before) = app.projects.iter().find(|p| p.id == id).cloned() else {
+        anyhow::bail!("The project no longer exists");
+    };
+    let form = &app.input_form;
+    if form.name.trim().is_empty() {
+        anyhow::bail!("Name is required");
+    }
+    let status: ProjectStatus = form.status.trim().parse().map_err(anyhow::Error::msg)?;
+    // A project in maintenance keeps that status while other fields change
+    if status != before.status {
+        check_status(&before, &status)?;
+    }
+
+    let project = Project {
+        name: form.name.trim().to_string(),
+        description: optional(&form.description),
+        stack: parse_stack(&form.stack),
+        status,
+        ..before.clone()
+    };
+    if !diff::diff(&before, &project).is_empty() {
+        app.db
+            .update_project(&project)
+            .await
+            .map_err(|e| name_taken("Project", e))?;
+    }
+    Ok(format!("project '{}'", project.name))
+}
+
+async fn save_edited_server(app: &mut App, id: &str) -> anyhow::Result<String> {
+    let Some(before) = app.servers.iter().find(|s| s.id == id).cloned() else {
+        anyhow::bail!("The server no longer exists");
+    };
+    let form = &app.input_form;
+    if form.name.trim().is_empty() || form.host.trim().is_empty() {
+        anyhow::bail!("Name and Host are required");
+    }
+
+    let server = Server {
+        name: form.name.trim().to_string(),
+        host: form.host.trim().to_string(),
+        server_type: form
+            .server_type
+            .trim()
+            .parse()
+            .map_err(anyhow::Error::msg)?,
+        provider: optional(&form.provider),
+        ..before.clone()
+    };
+    if !diff::diff(&before, &server).is_empty() {
+        app.db
+            .update_server(&server)
+            .await
+            .map_err(|e| name_taken("Server", e))?;
+    }
+    Ok(format!("server '{}'", server.name))
+}
+
+async fn save_edited_domain(app: &mut App, id: &str) -> anyhow::Result<String> {
+    let Some(before) = app.domains.iter().find(|d| d.id == id).cloned() else {
+        anyhow::bail!("The domain no longer exists");
+    };
+    let form = &app.input_form;
+    if form.domain.trim().is_empty() {
+        anyhow::bail!("Domain is required");
+    }
+
+    let domain = Domain {
+        domain: form.domain.trim().to_string(),
+        domain_type: form
+            .domain_type
+            .trim()
+            .parse()
+            .map_err(anyhow::Error::msg)?,
+        ssl: parse_ssl(&form.ssl),
+        ..before.clone()
+    };
+    if !diff::diff(&before, &domain).is_empty() {
+        app.db
+            .update_domain(&domain)
+            .await
+            .map_err(|e| name_taken("Domain", e))?;
+    }
+    Ok(format!("domain '{}'", domain.domain))
+}
+
+async fn save_edited_database(app: &mut App, id: &str) -> anyhow::Result<String> {
+    let Some(before) = app.databases.iter().find(|d| d.id == id).cloned() else {
+        anyhow::bail!("The database no longer exists");
+    };
+    let form = &app.input_form;
+    if form.name.trim().is_empty() {
+        anyhow::bail!("Name is required");
+    }
+    let port = match form.port.trim() {
+        "" => None,
+        port => Some(
+            port.parse()
+                .map_err(|_| anyhow::anyhow!("Port must be a number up to 65535"))?,
+        ),
+    };
+
+    let database = DatabaseCredentials {
+        name: form.name.trim().to_string(),
+        db_type: form.db_type.trim().parse().map_err(anyhow::Error::msg)?,
+        host: optional(&form.host),
+        port,
+        ..before.clone()
+    };
+    if !diff::diff(&before, &database).is_empty() {
+        app.db
+            .update_database_credentials(&database)
+            .await
+            .map_err(|e| name_taken("Database", e))?;
+    }
+    Ok(format!("database '{}'", database.name))
+}
+
+/// Name and type are saved directly; the command goes through
+/// `update_script_command`, so a dangerous script's new command waits for
+/// approval like it does from the CLI
+async fn save_edited_script(app: &mut App, id: &str) -> anyhow::Result<String> {
+    let Some(before) = app.scripts.iter().find(|s| s.id == id).cloned() else {
+        anyhow::bail!("The script no longer exists");
+    };
+    let form = &app.input_form;
+    let name = form.name.trim().to_string();
+    if name.is_empty() || form.command.trim().is_empty() {
+        anyhow::bail!("Name and Command are required");
+    }
+    script_namespace::validate_name(&name).map_err(anyhow::Error::msg)?;
+    let command = form.command.clone();
+
+    let script = Script {
+        name: name.clone(),
+        script_type: form
+            .script_type
+            .trim()
+            .parse()
+            .map_err(anyhow::Error::msg)?,
+        ..before.clone()
+    };
+    if !diff::diff(&before, &script).is_empty() {
+        app.db
+            .update_script(&script)
+            .await
+            .map_err(|e| name_taken("Script", e))?;
+    }
+    match app
+        .db
+        .update_script_command(id, &command, before.dangerous, &current_holder())
+        .await?
+    {
+        ScriptUpdate::Pending(revision) => Ok(format!(
+            "script '{}'; the new command awaits approval as revision #{}",
+            name, revision.id
+        )),
+        ScriptUpdate::Applied(_) | ScriptUpdate::Unchanged => Ok(format!("script '{}'", name)),
+    }
+}
+
+/// `d`: ask before deleting the selected entry. Servers, domains and
+/// databases that something still references are refused, as `remove`
+/// does without `--force`.
+async fn ask_delete(app: &mut App) {
+    let Some(selection) = app.selection() else {
+        return;
+    };
+    let command = match selection.entity_type {
+        EntityType::Server => "server",
+        EntityType::Domain => "domain",
+        EntityType::Database => "db",
+        _ => {
+            app.input_mode = InputMode::Deleting;
+            return;
+        }
+    };
+    match app
+        .db
+        .reverse_references(selection.entity_type, &selection.id)
+        .await
+    {
+        Ok(references) if references.is_empty() => app.input_mode = InputMode::Deleting,
+        Ok(references) => {
+            let mut details: Vec<String> = references
+                .iter()
+                .map(|r| format!("{}: {}", r.relation, r.name))
+                .collect();
+            details.push(format!(
+                "Unlink these first, or use pctrl {} remove \"{}\" --force",
+                command, selection.name
+            ));
+            app.notify(
+                Notification::error(format!(
+                    "{} '{}' is still referenced",
+                    capitalize(&selection.entity_type.to_string()),
+                    selection.name
+                ))
+                .with_details(details.join("\n"))
+                .with_panel(app.selected_panel),
+            );
         }
-        SelectedPanel::Status => {}
+        Err(e) => app.notify(
+            Notification::error(format!(
+                "Couldn't check what references '{}'",
+                selection.name
+            ))
+            .with_details(e.to_string())
+            .with_panel(app.selected_panel),
+        ),
     }
+}
 
-    Ok(())
+/// Delete the selected entry after the prompt was confirmed
+async fn delete_selected(app: &mut App) {
+    app.input_mode = InputMode::Normal;
+    let Some(selection) = app.selection() else {
+        return;
+    };
+    let result = match selection.entity_type {
+        EntityType::Project => app.db.remove_project(&selection.id).await,
+        EntityType::Server => app.db.remove_server(&selection.id).await,
+        EntityType::Domain => app.db.remove_domain(&selection.id).await,
+        EntityType::Database => app.db.remove_database_credentials(&selection.id).await,
+        EntityType::Script => app.db.remove_script(&selection.id).await,
+        EntityType::Credential => return,
+    };
+    let panel = app.selected_panel;
+    match result {
+        Ok(_) => {
+            app.project_detail = None;
+            app.load_all().await;
+            app.notify(
+                Notification::success(format!(
+                    "Deleted {} '{}'",
+                    selection.entity_type, selection.name
+                ))
+                .with_panel(panel),
+            );
+        }
+        Err(e) => app.notify(
+            Notification::error(format!(
+                "Couldn't delete {} '{}'",
+                selection.entity_type, selection.name
+            ))
+            .with_details(e.to_string())
+            .with_panel(panel),
+        ),
+    }
 }
@@ -3,11 +3,19 @@
 //! Provides an interactive terminal user interface.
 
 mod app;
+mod checks;
+#[cfg(test)]
+mod driver;
 mod input;
+mod notifications;
+#[cfg(test)]
+mod tests;
+mod theme;
 mod types;
 mod ui;
 
 use app::App;
+use chrono::Utc;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture},
     execute,
@@ -27,7 +35,9 @@ pub async fn run(db: Arc<Database>) -> anyhow::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new(db);
+    app.load_settings().await;
     app.load_all().await;
+    app.check_connections();
 
     let res = run_app(&mut terminal, &mut app).await;
 
@@ -52,6 +62,8 @@ async fn run_app<B: ratatui::backend::Backend>(
     app: &mut App,
 ) -> io::Result<()> {
     loop {
+        app.tick(Utc::now());
+        app.background().await;
         terminal.draw(|f| ui::render(f, app))?;
 
         if event::poll(std::time::Duration::from_millis(100))? {
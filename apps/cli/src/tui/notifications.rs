@@ -0,0 +1,174 @@
+//! TUI notifications: outcomes of work that finishes outside a keypress
+//!
+//! The newest notification shows briefly as a toast; the last
+//! [`CAPACITY`] stay in the notification panel (`n`). Work reports
+//! through a [`Notifier`], which the App drains on every tick.
+
+use super::types::SelectedPanel;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+use std::fmt;
+use tokio::sync::mpsc;
+
+/// Notifications kept for the panel
+pub const CAPACITY: usize = 50;
+
+/// How long a toast stays on screen, in seconds
+pub const TOAST_SECONDS: i64 = 4;
+
+/// Sending half of the App's notification channel
+pub type Notifier = mpsc::UnboundedSender<Notification>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Level::Info => "info",
+            Level::Success => "success",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    /// Full text, e.g. the error behind the message
+    pub details: Option<String>,
+    /// Panel showing the entity the notification is about
+    pub panel: Option<SelectedPanel>,
+    /// When it (last) arrived; set by [`Notifications::push`]
+    pub at: DateTime<Utc>,
+    /// How many identical notifications it stands for
+    pub count: usize,
+    pub read: bool,
+}
+
+impl Notification {
+    pub fn new(level: Level, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+            details: None,
+            panel: None,
+            at: DateTime::<Utc>::MIN_UTC,
+            count: 1,
+            read: false,
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(Level::Info, message)
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(Level::Success, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    pub fn with_panel(mut self, panel: SelectedPanel) -> Self {
+        self.panel = Some(panel);
+        self
+    }
+
+    /// Same outcome, ignoring when and how often it happened
+    fn same_as(&self, other: &Notification) -> bool {
+        self.level == other.level
+            && self.message == other.message
+            && self.details == other.details
+            && self.panel == other.panel
+    }
+}
+
+/// Bounded queue of notifications, newest first
+#[derive(Debug, Default)]
+pub struct Notifications {
+    items: VecDeque<Notification>,
+    /// The toast is shown until then
+    toast_until: Option<DateTime<Utc>>,
+}
+
+impl Notifications {
+    /// Add a notification that arrived at `now`. A repeat of one already
+    /// queued replaces it, counted; it only toasts again once the earlier
+    /// one was read.
+    pub fn push(&mut self, mut notification: Notification, now: DateTime<Utc>) {
+        notification.at = now;
+        notification.read = false;
+        let mut toast = true;
+        if let Some(i) = self.items.iter().position(|n| n.same_as(&notification)) {
+            let earlier = self.items.remove(i).expect("position is in range");
+            notification.count = earlier.count + 1;
+            toast = earlier.read;
+        }
+        self.items.push_front(notification);
+        self.items.truncate(CAPACITY);
+        if toast {
+            self.toast_until = Some(now + Duration::seconds(TOAST_SECONDS));
+        }
+    }
+
+    /// The newest notification, while its toast lasts
+    pub fn toast(&self, now: DateTime<Utc>) -> Option<&Notification> {
+        match self.toast_until {
+            Some(until) if now < until => self.items.front(),
+            _ => None,
+        }
+    }
+
+    pub fn unread(&self) -> usize {
+        self.items.iter().filter(|n| !n.read).count()
+    }
+
+    /// Whether an unread notification is an error
+    pub fn unread_error(&self) -> bool {
+        self.items
+            .iter()
+            .any(|n| !n.read && n.level == Level::Error)
+    }
+
+    /// Mark everything read and hide the toast
+    pub fn mark_read(&mut self) {
+        for n in &mut self.items {
+            n.read = true;
+        }
+        self.toast_until = None;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Notification> {
+        self.items.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Notification> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
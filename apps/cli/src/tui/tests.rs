@@ -0,0 +1,1245 @@
+//! End-to-end TUI tests through [`TuiDriver`]
+
+use super::driver::{start_time, TuiDriver};
+use super::notifications::{Level, Notification, Notifications, CAPACITY, TOAST_SECONDS};
+use super::theme::Theme;
+use super::types::{ConnectionStatus, InputMode, SelectedPanel};
+use chrono::Duration;
+use crossterm::event::KeyCode;
+use pctrl_core::discovery;
+use pctrl_core::settings::{SCRIPT_APPROVAL, SCRIPT_RUN_HISTORY, TUI_REFRESH_SECS, TUI_THEME};
+use pctrl_core::theme::{Palette, ThemeName};
+use pctrl_core::{
+    ActivityKind, DatabaseCredentials, DatabaseType, Domain, DomainType, Project, ProjectResource,
+    ProjectStatus, ResourceType, RevisionStatus, Script, ScriptType, Server, ServerType, Service,
+};
+
+const PANELS: [SelectedPanel; 7] = [
+    SelectedPanel::Status,
+    SelectedPanel::Projects,
+    SelectedPanel::Servers,
+    SelectedPanel::Domains,
+    SelectedPanel::Databases,
+    SelectedPanel::Scripts,
+    SelectedPanel::Activity,
+];
+
+fn project(name: &str) -> Project {
+    Project {
+        id: name.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+fn server(name: &str) -> Server {
+    Server {
+        id: name.to_string(),
+        name: name.to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    }
+}
+
+fn script(name: &str, command: &str) -> Script {
+    Script {
+        id: name.to_string(),
+        name: name.to_string(),
+        description: None,
+        command: command.to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_panel_navigation_order() {
+    let mut tui = TuiDriver::new().await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Status);
+
+    // Down walks the sidebar top to bottom and wraps around
+    for expected in PANELS.iter().skip(1) {
+        tui.press(KeyCode::Down).await;
+        assert_eq!(tui.app.selected_panel, *expected);
+    }
+    tui.press(KeyCode::Tab).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Status);
+
+    // Up walks back, wrapping to the bottom
+    for expected in PANELS.iter().rev() {
+        tui.press(KeyCode::Up).await;
+        assert_eq!(tui.app.selected_panel, *expected);
+    }
+
+    // The sidebar marks the selected panel
+    tui.press(KeyCode::Char('j')).await;
+    assert!(tui.screen().contains("▶ Projects"));
+
+    tui.press(KeyCode::Char('q')).await;
+    assert!(tui.quit);
+}
+
+#[tokio::test]
+async fn test_add_project_persists() {
+    let mut tui = TuiDriver::new().await;
+    tui.press(KeyCode::Down).await;
+    tui.press(KeyCode::Char('a')).await;
+    assert_eq!(tui.app.input_mode, InputMode::Adding);
+    assert!(tui.screen().contains("Add New Project"));
+
+    tui.type_text("shop").await;
+    tui.press(KeyCode::Tab).await;
+    tui.type_text("Online shop").await;
+    tui.press(KeyCode::Tab).await;
+    tui.type_text("rust, postgres").await;
+    tui.press(KeyCode::Tab).await;
+    // Status defaults to dev; replace it
+    tui.press_all(&[KeyCode::Backspace; 3]).await;
+    tui.type_text("live").await;
+    tui.press(KeyCode::Enter).await;
+
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    let stored = tui.db().get_project_by_name("shop").await.unwrap().unwrap();
+    assert_eq!(stored.description.as_deref(), Some("Online shop"));
+    assert_eq!(stored.stack, ["rust", "postgres"]);
+    assert_eq!(stored.status, ProjectStatus::Live);
+
+    // The panel was reloaded from the database
+    assert_eq!(tui.app.projects.len(), 1);
+    let screen = tui.screen();
+    assert!(screen.contains("shop (live) [rust, postgres]"));
+    assert!(screen.contains("Projects (1)"));
+}
+
+#[tokio::test]
+async fn test_add_form_validation_and_cancel() {
+    let mut tui = TuiDriver::new().await;
+    tui.press_all(&[KeyCode::Down, KeyCode::Down, KeyCode::Char('a')])
+        .await;
+    assert!(tui.screen().contains("Add New Server"));
+
+    // Host is missing
+    tui.type_text("web-1").await;
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.input_mode, InputMode::Adding);
+    assert!(tui.screen().contains("Error: Name and Host are required"));
+    assert!(tui.db().list_servers().await.unwrap().is_empty());
+
+    // Esc discards the form without saving
+    tui.press(KeyCode::Esc).await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    assert!(tui.app.input_form.name.is_empty());
+    assert!(tui.db().list_servers().await.unwrap().is_empty());
+
+    // Nothing to add on the status panel
+    tui.press_all(&[KeyCode::Up, KeyCode::Up, KeyCode::Char('a')])
+        .await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+}
+
+#[tokio::test]
+async fn test_activity_filter_and_search() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_project(&project("shop")).await.unwrap();
+    db.save_project(&project("blog")).await.unwrap();
+    tui.refresh().await;
+
+    tui.press(KeyCode::Up).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Activity);
+    assert_eq!(tui.app.activity.len(), 2);
+    assert!(tui.screen().contains("project created: blog"));
+
+    // Toggling the audit kind off hides both entries
+    let audit = ActivityKind::ALL
+        .iter()
+        .position(|k| *k == ActivityKind::Audit)
+        .unwrap();
+    let toggle = KeyCode::Char((b'1' + audit as u8) as char);
+    tui.press(toggle).await;
+    assert!(tui.app.activity.is_empty());
+    assert!(tui.screen().contains("No activity"));
+    tui.press(toggle).await;
+    assert_eq!(tui.app.activity.len(), 2);
+
+    // Search narrows the feed; Esc clears it again
+    tui.press(KeyCode::Char('/')).await;
+    assert_eq!(tui.app.input_mode, InputMode::Searching);
+    tui.type_text("shop").await;
+    assert!(tui.screen().contains("/shop▌"));
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.activity_filter.search.as_deref(), Some("shop"));
+    assert_eq!(tui.app.activity.len(), 1);
+    assert!(tui.app.activity[0].title.contains("shop"));
+
+    tui.press(KeyCode::Esc).await;
+    assert_eq!(tui.app.activity_filter.search, None);
+    assert_eq!(tui.app.activity.len(), 2);
+    assert!(!tui.quit);
+
+    // With nothing left to clear, Esc quits
+    tui.press(KeyCode::Esc).await;
+    assert!(tui.quit);
+}
+
+#[tokio::test]
+async fn test_maintenance_banner_follows_the_clock() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    let shop = project("shop");
+    db.save_project(&shop).await.unwrap();
+    db.start_maintenance(
+        &shop,
+        Some("db migration"),
+        start_time(),
+        start_time() + Duration::hours(2),
+    )
+    .await
+    .unwrap();
+    tui.refresh().await;
+
+    tui.advance(Duration::minutes(40));
+    assert!(tui
+        .screen()
+        .contains("shop: maintenance, 1h 20m left (db migration)"));
+
+    tui.advance(Duration::minutes(70));
+    tui.press(KeyCode::Down).await;
+    assert!(tui
+        .screen()
+        .contains("shop (maintenance, 10m left (db migration))"));
+}
+
+#[tokio::test]
+async fn test_services_listed_under_their_server() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_server(&Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    for (id, unit) in [("api", "myapp.service"), ("worker", "worker.service")] {
+        db.save_service(&Service {
+            id: id.to_string(),
+            name: id.to_string(),
+            server_id: "web-1".to_string(),
+            unit: unit.to_string(),
+            project_id: None,
+            last_state: None,
+            last_checked: None,
+        })
+        .await
+        .unwrap();
+    }
+    db.record_service_state("api", "active (running)")
+        .await
+        .unwrap();
+    tui.refresh().await;
+
+    tui.press(KeyCode::Down).await;
+    tui.press(KeyCode::Down).await;
+    let screen = tui.screen();
+    assert!(screen.contains("web-1 - 10.0.0.1"));
+    assert!(screen.contains("api myapp.service active (running)"));
+    assert!(screen.contains("worker worker.service unchecked"));
+}
+
+#[tokio::test]
+async fn test_discovered_containers_under_their_server() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_server(&Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    let output = concat!(
+        r#"{"ID":"a1","Names":"api","Image":"api:1","State":"running"}"#,
+        "\n",
+        r#"{"ID":"b2","Names":"cron","Image":"cron:1","State":"exited"}"#,
+    );
+    let containers = discovery::parse_docker_ps(output, "web-1").unwrap();
+    db.upsert_discovery_cache(
+        "web-1",
+        discovery::CONTAINERS,
+        &serde_json::to_string(&containers).unwrap(),
+        Duration::minutes(5),
+    )
+    .await
+    .unwrap();
+    tui.refresh().await;
+
+    tui.press(KeyCode::Down).await;
+    tui.press(KeyCode::Down).await;
+    assert!(tui.screen().contains("2 containers, 1 running discovered"));
+}
+
+#[tokio::test]
+async fn test_theme_colors_and_cycling() {
+    let mut tui = TuiDriver::new().await;
+    let initial = tui.app.theme.name;
+
+    let buffer = tui.render();
+    let theme = &tui.app.theme;
+    // Header badge " pctrl " inside the margin and border
+    let badge = buffer.get(3, 2);
+    assert_eq!(badge.symbol(), "p");
+    assert_eq!(badge.bg, theme.accent);
+    assert_eq!(badge.fg, theme.on_accent);
+    // Selected sidebar entry "▶ Status"
+    let selected = buffer.get(4, 5);
+    assert_eq!(selected.symbol(), "S");
+    assert_eq!(selected.fg, theme.accent);
+    assert_eq!(selected.bg, theme.selection_bg);
+
+    // T switches to the next preset, persists it and repaints with it
+    tui.press(KeyCode::Char('T')).await;
+    let name = tui.app.theme.name;
+    assert_ne!(name, initial);
+    assert_eq!(
+        tui.db().get_setting(TUI_THEME).await.unwrap(),
+        Some(name.to_string())
+    );
+    let expected = Theme::new(&Palette::preset(name));
+    assert_eq!(tui.render().get(3, 2).bg, expected.accent);
+
+    // The saved theme is picked up by the next session
+    let next = TuiDriver::with_db(tui.db()).await;
+    assert_eq!(next.app.theme.name, name);
+    assert_ne!(name, ThemeName::default());
+}
+
+#[tokio::test]
+async fn test_settings_changed_elsewhere_apply_live() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    assert_eq!(tui.app.theme.name, ThemeName::Dark);
+
+    // Another terminal runs `pctrl config set tui_theme light`
+    db.set_setting(TUI_THEME, "light").await.unwrap();
+    db.set_setting(SCRIPT_RUN_HISTORY, "20").await.unwrap();
+    tui.wait(Duration::seconds(1)).await;
+    assert_eq!(tui.app.theme.name, ThemeName::Light);
+    let newest = tui.app.notifications.get(0).unwrap();
+    assert_eq!(
+        newest.message,
+        "Settings changed: tui_theme, script_run_history"
+    );
+    let details = newest.details.as_deref().unwrap();
+    assert!(details.contains("tui_theme dark -> light (applied)"));
+    assert!(details.contains("script_run_history 100 -> 20 (used by commands, not the TUI)"));
+    assert!(tui.screen().contains("Settings changed"));
+
+    // Our own theme switch is not reported back
+    tui.press(KeyCode::Char('T')).await;
+    let count = tui.app.notifications.len();
+    tui.wait(Duration::seconds(1)).await;
+    assert_eq!(tui.app.notifications.len(), count);
+}
+
+#[tokio::test]
+async fn test_refresh_interval_reloads_data() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.set_setting(TUI_REFRESH_SECS, "30").await.unwrap();
+    tui.wait(Duration::seconds(1)).await;
+    assert_eq!(tui.app.refresh_every, Some(Duration::seconds(30)));
+
+    db.save_project(&project("shop")).await.unwrap();
+    tui.wait(Duration::seconds(10)).await;
+    assert!(tui.app.projects.is_empty());
+    tui.wait(Duration::seconds(20)).await;
+    assert_eq!(tui.app.projects.len(), 1);
+
+    // 0 turns it off again
+    db.set_setting(TUI_REFRESH_SECS, "0").await.unwrap();
+    tui.wait(Duration::seconds(1)).await;
+    assert_eq!(tui.app.refresh_every, None);
+    db.save_project(&project("blog")).await.unwrap();
+    tui.wait(Duration::seconds(60)).await;
+    assert_eq!(tui.app.projects.len(), 1);
+}
+
+#[test]
+fn test_notifications_dedup_and_bound() {
+    let now = start_time();
+    let mut queue = Notifications::default();
+    let failed = || Notification::error("Couldn't load servers").with_details("disk I/O error");
+
+    queue.push(failed(), now);
+    queue.push(Notification::success("Added project 'shop'"), now);
+    queue.push(failed(), now + Duration::seconds(30));
+    // The repeat moved to the front, counted, without a second unread entry
+    assert_eq!(queue.len(), 2);
+    assert_eq!(queue.unread(), 2);
+    assert!(queue.unread_error());
+    let newest = queue.get(0).unwrap();
+    assert_eq!(newest.count, 2);
+    assert_eq!(newest.at, now + Duration::seconds(30));
+
+    // Different details are a different notification
+    queue.push(failed().with_details("database is locked"), now);
+    assert_eq!(queue.len(), 3);
+
+    queue.mark_read();
+    assert_eq!(queue.unread(), 0);
+    assert!(!queue.unread_error());
+    queue.push(failed(), now);
+    assert_eq!(queue.unread(), 1);
+    assert_eq!(queue.get(0).unwrap().count, 3);
+
+    for i in 0..CAPACITY + 10 {
+        queue.push(Notification::info(format!("check {}", i)), now);
+    }
+    assert_eq!(queue.len(), CAPACITY);
+    assert_eq!(
+        queue.get(0).unwrap().message,
+        format!("check {}", CAPACITY + 9)
+    );
+}
+
+#[test]
+fn test_toast_expires_and_repeats_stay_quiet() {
+    let now = start_time();
+    let mut queue = Notifications::default();
+    queue.push(Notification::warning("VPN down"), now);
+    assert_eq!(queue.toast(now).unwrap().message, "VPN down");
+    let later = now + Duration::seconds(TOAST_SECONDS);
+    assert!(queue.toast(later).is_none());
+
+    // An unread repeat doesn't toast again; once read, it does
+    queue.push(Notification::warning("VPN down"), later);
+    assert!(queue.toast(later).is_none());
+    queue.mark_read();
+    queue.push(Notification::warning("VPN down"), later);
+    assert_eq!(queue.toast(later).unwrap().count, 3);
+}
+
+#[tokio::test]
+async fn test_notification_panel() {
+    let mut tui = TuiDriver::new().await;
+    // Background work reports through the notifier; the next tick takes it in
+    let notifier = tui.app.notifier();
+    notifier
+        .send(
+            Notification::error("Couldn't check web-1")
+                .with_details("connection refused\nafter 3 attempts"),
+        )
+        .unwrap();
+    notifier
+        .send(Notification::error("Couldn't load domains").with_panel(SelectedPanel::Domains))
+        .unwrap();
+    assert!(tui.app.notifications.is_empty());
+    tui.advance(Duration::seconds(1));
+
+    let screen = tui.screen();
+    assert!(screen.contains("● 2 unread (n)"));
+    assert!(screen.contains("Couldn't load domains"));
+    tui.advance(Duration::seconds(TOAST_SECONDS));
+    assert!(!tui.screen().contains("Couldn't load domains"));
+
+    // Opening the panel reads everything; newest first, with timestamps
+    tui.press(KeyCode::Char('n')).await;
+    assert_eq!(tui.app.input_mode, InputMode::Notifications);
+    let screen = tui.screen();
+    assert!(!screen.contains("unread"));
+    assert!(screen.contains("▶ 12:00:01 error    Couldn't load domains"));
+    assert!(screen.contains("12:00:01 error    Couldn't check web-1"));
+
+    // Without a related panel, Enter shows the full text
+    tui.press(KeyCode::Down).await;
+    tui.press(KeyCode::Enter).await;
+    let screen = tui.screen();
+    assert!(screen.contains("connection refused"));
+    assert!(screen.contains("after 3 attempts"));
+    tui.press(KeyCode::Esc).await;
+    assert_eq!(tui.app.input_mode, InputMode::Notifications);
+
+    // With one, it jumps there
+    tui.press(KeyCode::Up).await;
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Domains);
+}
+
+#[tokio::test]
+async fn test_adding_reports_success() {
+    let mut tui = TuiDriver::new().await;
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('a')]).await;
+    tui.type_text("shop").await;
+    tui.press(KeyCode::Enter).await;
+    let newest = tui.app.notifications.get(0).unwrap();
+    assert_eq!(newest.message, "Added project 'shop'");
+    assert_eq!(newest.panel, Some(SelectedPanel::Projects));
+    assert!(tui.screen().contains("success"));
+}
+
+#[tokio::test]
+async fn test_scripts_grouped_by_namespace() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    for name in [
+        "backups/postgres-daily",
+        "backups/redis",
+        "deploy",
+        "backups/db/mysql",
+    ] {
+        db.save_script(&Script {
+            id: name.to_string(),
+            name: name.to_string(),
+            description: None,
+            command: format!("echo {}", name),
+            script_type: ScriptType::Local,
+            server_id: None,
+            project_id: None,
+            docker_host_id: None,
+            container_id: None,
+            dangerous: false,
+            last_run: None,
+            last_result: None,
+            exit_code: None,
+            last_output: None,
+            working_dir: None,
+            run_window: None,
+            env: Default::default(),
+        })
+        .await
+        .unwrap();
+    }
+    tui.refresh().await;
+
+    tui.press_all(&[KeyCode::BackTab, KeyCode::BackTab]).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Scripts);
+    let screen = tui.screen();
+    assert!(screen.contains("▶ ▾ backups/ (3)"), "{}", screen);
+    assert!(screen.contains("▾ db/ (1)"), "{}", screen);
+    assert!(screen.contains("● mysql [local]"), "{}", screen);
+    assert!(screen.contains("● postgres-daily [local]"), "{}", screen);
+    assert!(screen.contains("● deploy [local]"), "{}", screen);
+
+    // Folding hides the namespace's scripts but keeps the count
+    tui.press(KeyCode::Enter).await;
+    let screen = tui.screen();
+    assert!(screen.contains("▶ ▸ backups/ (3)"), "{}", screen);
+    assert!(!screen.contains("postgres-daily"), "{}", screen);
+    assert!(screen.contains("● deploy [local]"), "{}", screen);
+
+    // Down moves through the rows before leaving the panel
+    tui.press(KeyCode::Down).await;
+    assert!(tui.screen().contains("▶ ● deploy"));
+    tui.press(KeyCode::Down).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Activity);
+
+    // Up comes back in at the last row
+    tui.press_all(&[KeyCode::Up, KeyCode::Up, KeyCode::Right])
+        .await;
+    assert!(tui.screen().contains("● postgres-daily [local]"));
+}
+
+#[tokio::test]
+async fn test_project_detail_lists_linked_resources() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_project(&project("blog")).await.unwrap();
+    let mut shop = project("shop");
+    shop.description = Some("Online shop".to_string());
+    db.save_project(&shop).await.unwrap();
+    db.save_server(&Server {
+        id: "srv-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    db.link_project_resource(&ProjectResource {
+        id: "link-1".to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: "srv-1".to_string(),
+        role: Some("production".to_string()),
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+    tui.refresh().await;
+
+    // The cursor moves through the projects before leaving the panel
+    tui.press(KeyCode::Down).await;
+    assert!(tui.screen().contains("▶ ● blog (live)"));
+    tui.press(KeyCode::Down).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Projects);
+    assert!(tui.screen().contains("▶ ● shop (live)"));
+
+    tui.press(KeyCode::Enter).await;
+    let screen = tui.screen();
+    assert!(screen.contains("Projects › shop"), "{}", screen);
+    assert!(screen.contains("Online shop"), "{}", screen);
+    assert!(screen.contains("Linked resources (1)"), "{}", screen);
+    assert!(
+        screen.contains("server    web-1 [production]"),
+        "{}",
+        screen
+    );
+
+    // Esc goes back to the list instead of quitting
+    tui.press(KeyCode::Esc).await;
+    assert!(!tui.quit);
+    assert!(tui.app.project_detail.is_none());
+    assert!(tui.screen().contains("▶ ● shop (live)"));
+
+    // Leaving the panel closes the project
+    tui.press(KeyCode::Enter).await;
+    tui.press(KeyCode::Tab).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Servers);
+    assert!(tui.app.project_detail.is_none());
+}
+
+#[tokio::test]
+async fn test_edit_project_from_the_form() {
+    let mut tui = TuiDriver::new().await;
+    tui.db().save_project(&project("shop")).await.unwrap();
+    tui.refresh().await;
+
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('e')]).await;
+    assert_eq!(tui.app.input_mode, InputMode::Adding);
+    let screen = tui.screen();
+    assert!(screen.contains("Edit Project"), "{}", screen);
+    assert!(screen.contains("live"), "{}", screen);
+
+    // Maintenance has its own command
+    tui.press_all(&[KeyCode::Tab; 3]).await;
+    tui.press_all(&[KeyCode::Backspace; 4]).await;
+    tui.type_text("maintenance").await;
+    tui.press(KeyCode::Enter).await;
+    assert!(tui.screen().contains("pctrl project maintenance"));
+
+    tui.press_all(&[KeyCode::Backspace; 11]).await;
+    tui.type_text("staging").await;
+    tui.press(KeyCode::Tab).await;
+    tui.press_all(&[KeyCode::Backspace; 4]).await;
+    tui.type_text("webshop").await;
+    tui.press(KeyCode::Enter).await;
+
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    let stored = tui.db().get_project("shop").await.unwrap().unwrap();
+    assert_eq!(stored.name, "webshop");
+    assert_eq!(stored.status, ProjectStatus::Staging);
+    assert_eq!(tui.db().list_projects().await.unwrap().len(), 1);
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        "Updated project 'webshop'"
+    );
+    assert!(tui.screen().contains("webshop (staging)"));
+}
+
+#[tokio::test]
+async fn test_delete_project_asks_first() {
+    let mut tui = TuiDriver::new().await;
+    tui.db().save_project(&project("shop")).await.unwrap();
+    tui.refresh().await;
+
+    tui.press_all(&[KeyCode::Down, KeyCode::Enter, KeyCode::Char('d')])
+        .await;
+    assert_eq!(tui.app.input_mode, InputMode::Deleting);
+    assert!(tui
+        .screen()
+        .contains("Delete project 'shop' and its links?"));
+
+    tui.press(KeyCode::Char('n')).await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    assert_eq!(tui.db().list_projects().await.unwrap().len(), 1);
+
+    tui.press_all(&[KeyCode::Char('d'), KeyCode::Char('y')])
+        .await;
+    assert!(tui.db().list_projects().await.unwrap().is_empty());
+    assert!(tui.app.project_detail.is_none());
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        "Deleted project 'shop'"
+    );
+    let screen = tui.screen();
+    assert!(screen.contains("No projects configured"), "{}", screen);
+    assert!(screen.contains("Projects (0)"), "{}", screen);
+
+    // The status totals follow
+    tui.press(KeyCode::Up).await;
+    assert!(tui.screen().contains("No resources configured yet."));
+}
+
+#[tokio::test]
+async fn test_edit_server_from_the_form() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_server(&server("web-1")).await.unwrap();
+    db.save_server(&server("web-2")).await.unwrap();
+    tui.refresh().await;
+
+    // Down moves through the servers before leaving the panel
+    tui.press_all(&[KeyCode::Down, KeyCode::Down]).await;
+    assert!(tui.screen().contains("▶ ● web-1 - 10.0.0.1"));
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('e')]).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Servers);
+    let screen = tui.screen();
+    assert!(screen.contains("Edit Server"), "{}", screen);
+    assert!(screen.contains("web-2"), "{}", screen);
+
+    // The type is checked instead of falling back to the default
+    tui.press_all(&[KeyCode::Tab; 2]).await;
+    tui.press_all(&[KeyCode::Backspace; 3]).await;
+    tui.type_text("mainframe").await;
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.input_mode, InputMode::Adding);
+    assert!(tui.screen().contains("Error: Unknown server type"));
+
+    tui.press_all(&[KeyCode::Backspace; 9]).await;
+    tui.type_text("dedicated").await;
+    tui.press(KeyCode::BackTab).await;
+    tui.press(KeyCode::Backspace).await;
+    tui.type_text("2").await;
+    tui.press(KeyCode::Enter).await;
+
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    let stored = tui.db().get_server("web-2").await.unwrap().unwrap();
+    assert_eq!(stored.host, "10.0.0.2");
+    assert_eq!(stored.server_type, ServerType::Dedicated);
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        "Updated server 'web-2'"
+    );
+    assert!(tui.screen().contains("web-2 - 10.0.0.2 [dedicated]"));
+
+    // Renaming onto another server's name is refused
+    tui.press(KeyCode::Char('e')).await;
+    tui.press_all(&[KeyCode::Backspace; 5]).await;
+    tui.type_text("WEB-1").await;
+    tui.press(KeyCode::Enter).await;
+    let screen = tui.screen();
+    assert!(
+        screen.contains("Error: Server 'web-1' already exists."),
+        "{}",
+        screen
+    );
+}
+
+#[tokio::test]
+async fn test_delete_keeps_referenced_entries() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_server(&server("web-1")).await.unwrap();
+    db.save_server(&server("web-2")).await.unwrap();
+    db.save_domain(&Domain {
+        id: "shop".to_string(),
+        domain: "shop.example.com".to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: Some("web-1".to_string()),
+        container_id: None,
+        notes: None,
+        superseded_by: None,
+    })
+    .await
+    .unwrap();
+    tui.refresh().await;
+
+    // web-1 still serves the domain
+    tui.press_all(&[KeyCode::Down, KeyCode::Down, KeyCode::Char('d')])
+        .await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    let newest = tui.app.notifications.get(0).unwrap();
+    assert_eq!(newest.message, "Server 'web-1' is still referenced");
+    assert!(newest
+        .details
+        .as_deref()
+        .unwrap()
+        .contains("shop.example.com"));
+
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('d')]).await;
+    assert_eq!(tui.app.input_mode, InputMode::Deleting);
+    assert!(tui.screen().contains("Delete server 'web-2'?"));
+    tui.press(KeyCode::Char('y')).await;
+    assert_eq!(tui.db().list_servers().await.unwrap().len(), 1);
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        "Deleted server 'web-2'"
+    );
+    let screen = tui.screen();
+    assert!(screen.contains("Servers (1)"), "{}", screen);
+    assert!(screen.contains("▶ ● web-1"), "{}", screen);
+
+    // Without the domain, the server can go too
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('d'), KeyCode::Char('y')])
+        .await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Domains);
+    assert!(tui.db().list_domains().await.unwrap().is_empty());
+    tui.press_all(&[KeyCode::Up, KeyCode::Char('d'), KeyCode::Char('y')])
+        .await;
+    assert!(tui.db().list_servers().await.unwrap().is_empty());
+    assert!(tui.screen().contains("Servers (0)"));
+}
+
+#[tokio::test]
+async fn test_edit_script_command_waits_for_approval() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_script(&script("backups/nightly", "pg_dump shop"))
+        .await
+        .unwrap();
+    let mut wipe = script("wipe", "rm -rf /srv/cache");
+    wipe.dangerous = true;
+    db.save_script(&wipe).await.unwrap();
+    db.set_setting(SCRIPT_APPROVAL, "on").await.unwrap();
+    tui.refresh().await;
+
+    // Namespace rows have nothing to edit
+    tui.press_all(&[KeyCode::BackTab, KeyCode::BackTab, KeyCode::Char('e')])
+        .await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Scripts);
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('e')]).await;
+    assert!(tui.screen().contains("Edit Script"));
+    tui.press_all(&[KeyCode::Backspace; 7]).await;
+    tui.type_text("weekly").await;
+    tui.press(KeyCode::Tab).await;
+    tui.type_text(" --clean").await;
+    tui.press(KeyCode::Enter).await;
+    let stored = tui
+        .db()
+        .get_script("backups/nightly")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.name, "backups/weekly");
+    assert_eq!(stored.command, "pg_dump shop --clean");
+
+    // A dangerous script's new command becomes a revision
+    tui.press_all(&[KeyCode::Down, KeyCode::Char('e'), KeyCode::Tab])
+        .await;
+    tui.type_text(" /srv/tmp").await;
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    let stored = tui.db().get_script("wipe").await.unwrap().unwrap();
+    assert_eq!(stored.command, "rm -rf /srv/cache");
+    let pending = tui
+        .db()
+        .list_script_revisions(Some(RevisionStatus::Pending))
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].command, "rm -rf /srv/cache /srv/tmp");
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        format!(
+            "Updated script 'wipe'; the new command awaits approval as revision #{}",
+            pending[0].id
+        )
+    );
+
+    tui.press_all(&[KeyCode::Char('d'), KeyCode::Char('y')])
+        .await;
+    assert!(tui.db().get_script("wipe").await.unwrap().is_none());
+    assert!(tui.screen().contains("Scripts (1)"));
+}
+
+fn database(name: &str, db_type: DatabaseType, port: Option<u16>) -> DatabaseCredentials {
+    DatabaseCredentials {
+        id: name.to_string(),
+        name: name.to_string(),
+        db_type,
+        host: port.map(|_| "127.0.0.1".to_string()),
+        port,
+        database_name: Some("/nonexistent/pctrl-test.db".to_string()),
+        username: None,
+        password: None,
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    }
+}
+
+#[tokio::test]
+async fn test_connection_checks_report_one_by_one() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    let listening = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let up = listening.local_addr().unwrap().port();
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let down = closed.local_addr().unwrap().port();
+    drop(closed);
+    db.save_database_credentials(&database("cache", DatabaseType::Redis, Some(up)))
+        .await
+        .unwrap();
+    db.save_database_credentials(&database("gone", DatabaseType::PostgreSQL, Some(down)))
+        .await
+        .unwrap();
+    db.save_database_credentials(&database("local", DatabaseType::SQLite, None))
+        .await
+        .unwrap();
+    let mut vpn_only = server("vpn-only");
+    vpn_only.requires_vpn = Some("pctrl-test-vpn0".to_string());
+    db.save_server(&vpn_only).await.unwrap();
+    tui.refresh().await;
+    assert!(tui.app.connections.is_empty());
+
+    tui.press_all(&[KeyCode::Down; 4]).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Databases);
+    tui.press(KeyCode::Char('r')).await;
+    assert!(tui.app.checks_running());
+    let screen = tui.screen();
+    assert!(screen.contains("cache [redis]"), "{}", screen);
+    assert!(screen.contains("checking…"), "{}", screen);
+
+    // A second r doesn't start the same checks again
+    tui.press(KeyCode::Char('r')).await;
+    assert_eq!(
+        tui.app.notifications.get(0).unwrap().message,
+        "Reloaded from the database; connection checks still running"
+    );
+
+    tui.settle_checks().await;
+    let connections = &tui.app.connections;
+    assert_eq!(connections["cache"], ConnectionStatus::Online);
+    assert!(matches!(connections["gone"], ConnectionStatus::Offline(_)));
+    assert_eq!(
+        connections["local"],
+        ConnectionStatus::Offline("database file not found".to_string())
+    );
+    assert!(matches!(
+        connections["vpn-only"],
+        ConnectionStatus::Offline(_)
+    ));
+    // The last check to finish says how the round went
+    let summary = tui.app.notifications.get(0).unwrap();
+    assert_eq!(summary.level, Level::Warning);
+    assert_eq!(summary.message, "Checked 4 connections: 3 unreachable");
+    // Each failure has its own notification, leading to its panel
+    let failure = tui
+        .app
+        .notifications
+        .iter()
+        .find(|n| n.message == "Database 'local' is unreachable")
+        .unwrap();
+    assert_eq!(failure.level, Level::Error);
+    assert_eq!(failure.details.as_deref(), Some("database file not found"));
+    assert_eq!(failure.panel, Some(SelectedPanel::Databases));
+    assert!(tui
+        .app
+        .notifications
+        .iter()
+        .any(|n| n.message == "Server 'vpn-only' is unreachable"));
+    tui.advance(Duration::seconds(TOAST_SECONDS + 1));
+    let screen = tui.screen();
+    assert!(!screen.contains("checking…"), "{}", screen);
+    assert!(screen.contains("database file not found"), "{}", screen);
+    assert!(
+        screen.contains(&format!("127.0.0.1:{}", down)),
+        "{}",
+        screen
+    );
+
+    // Done, so r checks again
+    tui.press(KeyCode::Char('r')).await;
+    assert!(tui.app.checks_running());
+    tui.settle_checks().await;
+}
+
+#[tokio::test]
+async fn test_slash_filters_the_panel_list() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_server(&Server {
+        provider: Some("Hetzner".to_string()),
+        ..server("web-1")
+    })
+    .await
+    .unwrap();
+    db.save_server(&server("web-2")).await.unwrap();
+    db.save_server(&server("cache")).await.unwrap();
+    tui.refresh().await;
+    tui.press_all(&[KeyCode::Down, KeyCode::Down]).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Servers);
+
+    // The list narrows while typing, by name or details like `pctrl find`
+    tui.press(KeyCode::Char('/')).await;
+    tui.type_text("hetz").await;
+    let screen = tui.screen();
+    assert!(screen.contains("Servers /hetz▌"), "{}", screen);
+    assert!(screen.contains("web-1"), "{}", screen);
+    assert!(!screen.contains("web-2"), "{}", screen);
+    assert!(!screen.contains("cache"), "{}", screen);
+
+    tui.press(KeyCode::Enter).await;
+    assert_eq!(tui.app.filter.as_deref(), Some("hetz"));
+    assert_eq!(tui.app.selected_server().unwrap().name, "web-1");
+    assert!(tui.screen().contains("Servers /hetz "));
+
+    // Esc clears the filter before it would quit
+    tui.press(KeyCode::Esc).await;
+    assert_eq!(tui.app.filter, None);
+    assert!(tui.screen().contains("cache"));
+
+    tui.press(KeyCode::Char('/')).await;
+    tui.type_text("nowhere").await;
+    assert!(tui.screen().contains("Nothing matches 'nowhere'"));
+    // Esc while typing keeps the filter as it was
+    tui.press(KeyCode::Esc).await;
+    assert_eq!(tui.app.input_mode, InputMode::Normal);
+    assert_eq!(tui.app.filter, None);
+
+    tui.press(KeyCode::Char('/')).await;
+    tui.type_text("WEB").await;
+    tui.press_all(&[KeyCode::Enter, KeyCode::Down]).await;
+    assert_eq!(tui.app.selected_server().unwrap().name, "web-2");
+    // Running off the end leaves the panel and its filter behind
+    tui.press(KeyCode::Down).await;
+    assert_eq!(tui.app.selected_panel, SelectedPanel::Domains);
+    assert_eq!(tui.app.filter, None);
+}
+
+#[tokio::test]
+async fn test_slash_filters_scripts_by_command() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    db.save_script(&script("backups/postgres", "pg_dump shop"))
+        .await
+        .unwrap();
+    db.save_script(&script("backups/redis", "redis-cli save"))
+        .await
+        .unwrap();
+    db.save_script(&script("deploy", "rsync dist/ web:/srv"))
+        .await
+        .unwrap();
+    tui.refresh().await;
+    tui.press_all(&[KeyCode::BackTab, KeyCode::BackTab]).await;
+
+    tui.press(KeyCode::Char('/')).await;
+    tui.type_text("pg_dump").await;
+    tui.press(KeyCode::Enter).await;
+    let screen = tui.screen();
+    assert!(screen.contains("backups/ (1)"), "{}", screen);
+    assert!(!screen.contains("redis"), "{}", screen);
+    assert!(!screen.contains("deploy"), "{}", screen);
+
+    tui.press(KeyCode::Down).await;
+    assert_eq!(tui.app.selected_script().unwrap().name, "backups/postgres");
+}
+
+#[tokio::test]
+async fn test_coolify_instances_are_checked_on_the_status_panel() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    // Answers one request for the version
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let up = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).await.unwrap();
+        let body = "4.0.0-beta.360";
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+    });
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let down = format!("http://{}", closed.local_addr().unwrap());
+    drop(closed);
+    for (name, url) in [("prod", up), ("old", down)] {
+        db.save_coolify_instance(&pctrl_core::CoolifyInstance {
+            id: name.to_string(),
+            name: name.to_string(),
+            url,
+            api_key: "token".to_string(),
+        })
+        .await
+        .unwrap();
+    }
+    tui.refresh().await;
+
+    assert!(tui.app.check_connections());
+    tui.settle_checks().await;
+    assert_eq!(
+        tui.app.connections["coolify:prod"],
+        ConnectionStatus::Online
+    );
+    assert!(matches!(
+        tui.app.connections["coolify:old"],
+        ConnectionStatus::Offline(_)
+    ));
+    tui.advance(Duration::seconds(TOAST_SECONDS + 1));
+    let screen = tui.screen();
+    assert!(screen.contains("Coolify"), "{}", screen);
+    assert!(screen.contains("● prod http://127.0.0.1"), "{}", screen);
+    assert!(screen.contains("● old http://127.0.0.1"), "{}", screen);
+}
+
+#[tokio::test]
+async fn test_docker_hosts_are_checked_on_the_status_panel() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    // Answers one ping
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let up = format!("tcp://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).await.unwrap();
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nOK";
+        stream.write_all(response.as_bytes()).await.unwrap();
+    });
+    let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let down = format!("tcp://{}", closed.local_addr().unwrap());
+    drop(closed);
+    for (name, url) in [("local", up), ("old", down)] {
+        db.save_docker_host(&pctrl_core::DockerHost {
+            id: name.to_string(),
+            name: name.to_string(),
+            url,
+            tls_ca: None,
+            tls_cert: None,
+            tls_key: None,
+        })
+        .await
+        .unwrap();
+    }
+    tui.refresh().await;
+
+    assert!(tui.app.check_connections());
+    tui.settle_checks().await;
+    assert_eq!(
+        tui.app.connections["docker:local"],
+        ConnectionStatus::Online
+    );
+    assert!(matches!(
+        tui.app.connections["docker:old"],
+        ConnectionStatus::Offline(_)
+    ));
+    assert!(tui
+        .app
+        .notifications
+        .iter()
+        .any(|n| n.level == Level::Error && n.message == "Docker host 'old' is unreachable"));
+    tui.advance(Duration::seconds(TOAST_SECONDS + 1));
+    let screen = tui.screen();
+    assert!(screen.contains("Docker"), "{}", screen);
+    assert!(screen.contains("● local tcp://127.0.0.1"), "{}", screen);
+    assert!(screen.contains("● old tcp://127.0.0.1"), "{}", screen);
+}
+
+#[tokio::test]
+async fn test_git_repos_with_changes_need_attention_on_the_status_panel() {
+    let mut tui = TuiDriver::new().await;
+    let db = tui.db();
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["clean", "dirty"] {
+        let path = dir.path().join(name);
+        let repo = git2::Repository::init(&path).unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let author = git2::Signature::now("Alice", "alice@example.com").unwrap();
+        repo.commit(Some("HEAD"), &author, &author, "Initial", &tree, &[])
+            .unwrap();
+    }
+    std::fs::write(dir.path().join("dirty").join("notes.txt"), "wip").unwrap();
+    for name in ["clean", "dirty", "gone"] {
+        db.save_git_repo(&pctrl_core::GitRepo {
+            id: name.to_string(),
+            name: name.to_string(),
+            path: dir.path().join(name).to_string_lossy().to_string(),
+            remote_url: None,
+            credential_id: None,
+        })
+        .await
+        .unwrap();
+    }
+    tui.refresh().await;
+
+    assert!(tui.app.check_connections());
+    tui.settle_checks().await;
+    assert_eq!(tui.app.connections["git:clean"], ConnectionStatus::Online);
+    assert!(matches!(
+        &tui.app.connections["git:dirty"],
+        ConnectionStatus::Attention(note) if note.ends_with("1 untracked")
+    ));
+    assert!(tui
+        .app
+        .notifications
+        .iter()
+        .any(|n| n.level == Level::Warning && n.message == "Repository 'dirty' needs attention"));
+    assert!(matches!(
+        tui.app.connections["git:gone"],
+        ConnectionStatus::Offline(_)
+    ));
+    tui.advance(Duration::seconds(TOAST_SECONDS + 1));
+    let screen = tui.screen();
+    assert!(screen.contains("Git"), "{}", screen);
+    assert!(screen.contains("1 untracked"), "{}", screen);
+}
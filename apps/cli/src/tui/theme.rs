@@ -0,0 +1,88 @@
+//! TUI theme: the core palette as ratatui colors
+
+use pctrl_core::theme::{ColorDepth, Palette, TermColor, ThemeName};
+use ratatui::style::{Color, Modifier, Style};
+
+/// Colors by role, ready for rendering
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub name: ThemeName,
+    pub accent: Color,
+    pub on_accent: Color,
+    pub text: Color,
+    pub dim: Color,
+    pub muted: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub info: Color,
+    pub highlight: Color,
+    pub selection_bg: Color,
+}
+
+impl Theme {
+    pub fn new(palette: &Palette) -> Self {
+        Self {
+            name: palette.name,
+            accent: color(palette.accent),
+            on_accent: color(palette.on_accent),
+            text: color(palette.text),
+            dim: color(palette.dim),
+            muted: color(palette.muted),
+            success: color(palette.success),
+            warning: color(palette.warning),
+            error: color(palette.error),
+            info: color(palette.info),
+            highlight: color(palette.highlight),
+            selection_bg: color(palette.selection_bg),
+        }
+    }
+
+    /// The selected item of a list
+    pub fn selected(&self) -> Style {
+        Style::default()
+            .fg(self.accent)
+            .bg(self.selection_bg)
+            .add_modifier(Modifier::BOLD)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(&Palette::preset(ThemeName::default()))
+    }
+}
+
+/// Color depth of the terminal we're running in
+pub fn terminal_color_depth() -> ColorDepth {
+    ColorDepth::detect(
+        std::env::var("COLORTERM").ok().as_deref(),
+        crossterm::style::available_color_count(),
+    )
+}
+
+fn color(color: TermColor) -> Color {
+    match color {
+        TermColor::Reset => Color::Reset,
+        TermColor::Ansi(n) => match n {
+            0 => Color::Black,
+            1 => Color::Red,
+            2 => Color::Green,
+            3 => Color::Yellow,
+            4 => Color::Blue,
+            5 => Color::Magenta,
+            6 => Color::Cyan,
+            7 => Color::Gray,
+            8 => Color::DarkGray,
+            9 => Color::LightRed,
+            10 => Color::LightGreen,
+            11 => Color::LightYellow,
+            12 => Color::LightBlue,
+            13 => Color::LightMagenta,
+            14 => Color::LightCyan,
+            _ => Color::White,
+        },
+        TermColor::Indexed(n) => Color::Indexed(n),
+        TermColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
@@ -1,6 +1,8 @@
 //! TUI type definitions
 
-#[derive(Clone, Copy, PartialEq)]
+use pctrl_core::{EntityType, ProjectResource};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SelectedPanel {
     Status,
     Projects,
@@ -8,12 +10,47 @@ pub enum SelectedPanel {
     Domains,
     Databases,
     Scripts,
+    Activity,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InputMode {
     Normal,
     Adding,
+    /// Typing an activity feed search (`/`)
+    Searching,
+    /// Notification panel open (`n`)
+    Notifications,
+    /// Asking whether to delete the selected entry (`d`)
+    Deleting,
+}
+
+/// Reachability of a server, database, Docker host, Coolify instance or Git
+/// repository, from the checks started at launch and with `r`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    Checking,
+    Online,
+    /// Why it couldn't be reached
+    Offline(String),
+    /// Reachable, with something to look at, e.g. uncommitted changes
+    Attention(String),
+}
+
+/// A project opened with Enter, and the resources linked to it
+#[derive(Debug, Clone)]
+pub struct ProjectDetail {
+    pub project_id: String,
+    pub resources: Vec<ProjectResource>,
+}
+
+/// The entry under the cursor, which `e` and `d` act on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub entity_type: EntityType,
+    pub id: String,
+    /// Name as listed; the domain itself for domains
+    pub name: String,
 }
 
 #[derive(Clone, Default)]
@@ -23,6 +60,8 @@ pub struct InputForm {
     pub description: String,
     pub current_field: usize,
     pub message: Option<String>,
+    /// ID of the entry being edited (`e`); `None` when adding
+    pub editing: Option<String>,
     // Project
     pub stack: String,
     pub status: String,
@@ -1,13 +1,21 @@
 //! TUI UI rendering
 
 use super::app::App;
-use super::types::{InputMode, SelectedPanel};
-use pctrl_core::ProjectStatus;
+use super::checks;
+use super::notifications::{Level, Notification};
+use super::theme::Theme;
+use super::types::{ConnectionStatus, InputMode, ProjectDetail, SelectedPanel};
+use chrono::{DateTime, Utc};
+use pctrl_core::diff::{display_value, parse_details, FieldChange};
+use pctrl_core::script_namespace::TreeRow;
+use pctrl_core::{
+    humanize, quota, ActivityKind, EntityType, ProjectResource, ProjectStatus, ResourceType,
+};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
@@ -22,32 +30,200 @@ pub fn render(f: &mut Frame, app: &App) {
         ])
         .split(f.size());
 
-    render_header(f, chunks[0]);
+    render_header(f, app, chunks[0]);
     render_main(f, app, chunks[1]);
     render_footer(f, app, chunks[2]);
+
+    if app.input_mode == InputMode::Notifications {
+        render_notifications(f, app, chunks[1]);
+    } else if app.input_mode == InputMode::Deleting {
+        render_delete_prompt(f, app, chunks[1]);
+    } else if let Some(toast) = app.notifications.toast(app.now) {
+        render_toast(f, &app.theme, toast, chunks[1]);
+    }
 }
 
-fn render_header(f: &mut Frame, area: Rect) {
+fn render_header(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let header = Paragraph::new(Line::from(vec![
         Span::styled(
             " pctrl ",
             Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
+                .fg(theme.on_accent)
+                .bg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(" "),
         Span::styled(
             "Mission Control for Self-Hosters & Indie Devs",
-            Style::default().fg(Color::White),
+            Style::default().fg(theme.text),
         ),
     ]))
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(Style::default().fg(theme.dim)),
     );
     f.render_widget(header, area);
+
+    let unread = app.notifications.unread();
+    if unread > 0 {
+        let color = if app.notifications.unread_error() {
+            theme.error
+        } else {
+            theme.accent
+        };
+        let counter = Paragraph::new(Line::from(vec![
+            Span::styled(format!("● {} unread", unread), Style::default().fg(color)),
+            Span::styled(" (n) ", Style::default().fg(theme.dim)),
+        ]))
+        .alignment(Alignment::Right);
+        f.render_widget(counter, inner(area));
+    }
+}
+
+/// `area` without its border
+fn inner(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    }
+}
+
+/// Draw `content` in a bordered box over whatever is at `area`
+fn render_overlay(f: &mut Frame, area: Rect, content: Paragraph, title: &str, border: Color) {
+    f.render_widget(Clear, area);
+    f.render_widget(
+        content.block(
+            Block::default()
+                .title(format!(" {} ", title))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border)),
+        ),
+        area,
+    );
+}
+
+fn level_color(theme: &Theme, level: Level) -> Color {
+    match level {
+        Level::Info => theme.info,
+        Level::Success => theme.success,
+        Level::Warning => theme.warning,
+        Level::Error => theme.error,
+    }
+}
+
+/// "Couldn't load servers (×3)"
+fn notification_text(notification: &Notification) -> String {
+    if notification.count > 1 {
+        format!("{} (×{})", notification.message, notification.count)
+    } else {
+        notification.message.clone()
+    }
+}
+
+/// The newest notification in the top right corner of `area`
+fn render_toast(f: &mut Frame, theme: &Theme, toast: &Notification, area: Rect) {
+    let width = (notification_text(toast).chars().count() as u16 + 4)
+        .clamp(24, 60)
+        .min(area.width);
+    let toast_area = Rect {
+        x: area.right() - width,
+        y: area.y,
+        width,
+        height: 3.min(area.height),
+    };
+    let content = Paragraph::new(Line::from(Span::styled(
+        format!(" {}", notification_text(toast)),
+        Style::default().fg(theme.text),
+    )));
+    let color = level_color(theme, toast.level);
+    render_overlay(f, toast_area, content, &toast.level.to_string(), color);
+}
+
+/// Pull-down list of notifications over the top of `area`
+fn render_notifications(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut items: Vec<Line> = Vec::new();
+
+    if app.notifications.is_empty() {
+        items.push(Line::from(Span::styled(
+            "  No notifications",
+            Style::default().fg(theme.dim),
+        )));
+    }
+    for (i, notification) in app.notifications.iter().enumerate() {
+        let is_selected = i == app.notification_selected;
+        let text_style = if is_selected {
+            theme.selected()
+        } else {
+            Style::default().fg(theme.text)
+        };
+        items.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, text_style),
+            Span::styled(
+                notification.at.format("%H:%M:%S").to_string(),
+                Style::default().fg(theme.dim),
+            ),
+            Span::styled(
+                format!(" {:8} ", notification.level),
+                Style::default().fg(level_color(theme, notification.level)),
+            ),
+            Span::styled(notification_text(notification), text_style),
+        ]));
+        if is_selected && app.notification_expanded {
+            let details = notification.details.as_deref().unwrap_or("No details");
+            for line in details.lines() {
+                items.push(Line::from(Span::styled(
+                    format!("      {}", line),
+                    Style::default().fg(theme.muted),
+                )));
+            }
+        }
+    }
+
+    // Keep the selection in view
+    let height = (items.len() as u16 + 2).min(area.height * 2 / 3).max(3);
+    let visible = height.saturating_sub(2) as usize;
+    let offset = (app.notification_selected + 1).saturating_sub(visible);
+    let panel = Rect { height, ..area };
+    let content = Paragraph::new(items)
+        .wrap(Wrap { trim: false })
+        .scroll((offset as u16, 0));
+    render_overlay(f, panel, content, "Notifications", theme.accent);
+}
+
+/// "Delete server 'web-1'?" in the middle of `area`
+fn render_delete_prompt(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let Some(selection) = app.selection() else {
+        return;
+    };
+    let question = match selection.entity_type {
+        EntityType::Project => format!(" Delete project '{}' and its links? ", selection.name),
+        kind => format!(" Delete {} '{}'? ", kind, selection.name),
+    };
+    let width = (question.chars().count() as u16 + 2)
+        .max(30)
+        .min(area.width);
+    let prompt_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height.saturating_sub(4) / 2,
+        width,
+        height: 4.min(area.height),
+    };
+    let content = Paragraph::new(vec![
+        Line::from(Span::styled(question, Style::default().fg(theme.text))),
+        Line::from(vec![
+            Span::styled(" y ", Style::default().fg(theme.error)),
+            Span::raw("Delete  "),
+            Span::styled(" n ", Style::default().fg(theme.accent)),
+            Span::raw("Keep"),
+        ]),
+    ]);
+    render_overlay(f, prompt_area, content, "Delete", theme.error);
 }
 
 fn render_main(f: &mut Frame, app: &App, area: Rect) {
@@ -61,6 +237,7 @@ fn render_main(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_sidebar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let total_count = app.projects.len()
         + app.servers.len()
         + app.domains.len()
@@ -74,25 +251,21 @@ fn render_sidebar(f: &mut Frame, app: &App, area: Rect) {
         ("Domains", app.domains.len(), SelectedPanel::Domains),
         ("Databases", app.databases.len(), SelectedPanel::Databases),
         ("Scripts", app.scripts.len(), SelectedPanel::Scripts),
+        ("Activity", app.activity.len(), SelectedPanel::Activity),
     ]
     .iter()
     .map(|(name, count, panel)| {
         let is_selected = app.selected_panel == *panel;
         let style = if is_selected {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
+            theme.selected()
         } else {
-            Style::default().fg(Color::White)
+            Style::default().fg(theme.text)
         };
         let prefix = if is_selected { "▶ " } else { "  " };
         ListItem::new(Line::from(vec![
             Span::styled(prefix, style),
             Span::styled((*name).to_string(), style),
-            Span::styled(
-                format!(" ({})", count),
-                Style::default().fg(Color::DarkGray),
-            ),
+            Span::styled(format!(" ({})", count), Style::default().fg(theme.dim)),
         ]))
     })
     .collect();
@@ -101,61 +274,82 @@ fn render_sidebar(f: &mut Frame, app: &App, area: Rect) {
         Block::default()
             .title(" Menu ")
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(Style::default().fg(theme.dim)),
     );
     f.render_widget(menu, area);
 }
 
 fn render_content(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut title = match app.selected_panel {
+        SelectedPanel::Status => "Status",
+        SelectedPanel::Projects => "Projects",
+        SelectedPanel::Servers => "Servers",
+        SelectedPanel::Domains => "Domains",
+        SelectedPanel::Databases => "Databases",
+        SelectedPanel::Scripts => "Scripts",
+        SelectedPanel::Activity => "Activity",
+    }
+    .to_string();
+    if let (Some(_), Some(project)) = (&app.project_detail, app.selected_project()) {
+        title = format!("{} › {}", title, project.name);
+    }
+    if app.selected_panel != SelectedPanel::Activity {
+        if app.input_mode == InputMode::Searching {
+            title = format!("{} /{}▌", title, app.search_input);
+        } else if let Some(filter) = &app.filter {
+            title = format!("{} /{}", title, filter);
+        }
+    }
     let content = if app.input_mode == InputMode::Adding {
         render_form(app)
     } else {
         match app.selected_panel {
             SelectedPanel::Status => render_status(app),
-            SelectedPanel::Projects => render_projects(app),
-            SelectedPanel::Servers => render_servers(app),
-            SelectedPanel::Domains => render_domains(app),
-            SelectedPanel::Databases => render_databases(app),
-            SelectedPanel::Scripts => render_scripts(app),
+            SelectedPanel::Projects => match &app.project_detail {
+                Some(detail) => render_project_detail(app, detail),
+                None => render_projects(app, area.height),
+            },
+            SelectedPanel::Servers => render_servers(app, area.height),
+            SelectedPanel::Domains => render_domains(app, area.height),
+            SelectedPanel::Databases => render_databases(app, area.height),
+            SelectedPanel::Scripts => render_scripts(app, area.height),
+            SelectedPanel::Activity => render_activity(app, area.height),
         }
     }
     .block(
         Block::default()
-            .title(format!(
-                " {} ",
-                match app.selected_panel {
-                    SelectedPanel::Status => "Status",
-                    SelectedPanel::Projects => "Projects",
-                    SelectedPanel::Servers => "Servers",
-                    SelectedPanel::Domains => "Domains",
-                    SelectedPanel::Databases => "Databases",
-                    SelectedPanel::Scripts => "Scripts",
-                }
-            ))
+            .title(format!(" {} ", title))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(Style::default().fg(theme.dim)),
     );
     f.render_widget(content, area);
 }
 
 fn render_form(app: &App) -> Paragraph<'static> {
+    let theme = &app.theme;
     let fields = app.get_form_fields();
     let mut items: Vec<Line> = vec![
         Line::from(""),
         Line::from(Span::styled(
             format!(
-                "  Add New {}",
+                "  {} {}",
+                if app.input_form.editing.is_some() {
+                    "Edit"
+                } else {
+                    "Add New"
+                },
                 match app.selected_panel {
                     SelectedPanel::Projects => "Project",
                     SelectedPanel::Servers => "Server",
                     SelectedPanel::Domains => "Domain",
                     SelectedPanel::Databases => "Database",
                     SelectedPanel::Scripts => "Script",
-                    SelectedPanel::Status => "",
+                    SelectedPanel::Status | SelectedPanel::Activity => "",
                 }
             ),
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
@@ -164,18 +358,14 @@ fn render_form(app: &App) -> Paragraph<'static> {
     for (i, (label, value)) in fields.iter().enumerate() {
         let is_active = i == app.input_form.current_field;
         let label_style = if is_active {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
+            theme.selected()
         } else {
-            Style::default().fg(Color::White)
+            Style::default().fg(theme.text)
         };
         let value_style = if is_active {
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD)
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(theme.dim)
         };
         let cursor = if is_active { "▌" } else { "" };
         let prefix = if is_active { "▶ " } else { "  " };
@@ -191,7 +381,7 @@ fn render_form(app: &App) -> Paragraph<'static> {
         items.push(Line::from(""));
         items.push(Line::from(Span::styled(
             format!("  {}", msg),
-            Style::default().fg(Color::Red),
+            Style::default().fg(theme.error),
         )));
     }
 
@@ -199,59 +389,130 @@ fn render_form(app: &App) -> Paragraph<'static> {
 }
 
 fn render_status(app: &App) -> Paragraph<'static> {
+    let theme = &app.theme;
     let mut items: Vec<Line> = vec![
         Line::from(""),
         Line::from(Span::styled(
             "  Overview",
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
     ];
 
     // Resource counts
     items.push(Line::from(vec![
-        Span::styled("  ● ", Style::default().fg(Color::Cyan)),
-        Span::styled("Projects", Style::default().fg(Color::White)),
+        Span::styled("  ● ", Style::default().fg(theme.accent)),
+        Span::styled("Projects", Style::default().fg(theme.text)),
         Span::styled(
             format!("         {}", app.projects.len()),
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         ),
     ]));
     items.push(Line::from(vec![
-        Span::styled("  ● ", Style::default().fg(Color::Green)),
-        Span::styled("Servers", Style::default().fg(Color::White)),
+        Span::styled("  ● ", Style::default().fg(theme.success)),
+        Span::styled("Servers", Style::default().fg(theme.text)),
         Span::styled(
             format!("          {}", app.servers.len()),
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         ),
     ]));
     items.push(Line::from(vec![
-        Span::styled("  ● ", Style::default().fg(Color::Blue)),
-        Span::styled("Domains", Style::default().fg(Color::White)),
+        Span::styled("  ● ", Style::default().fg(theme.info)),
+        Span::styled("Domains", Style::default().fg(theme.text)),
         Span::styled(
             format!("          {}", app.domains.len()),
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         ),
     ]));
     items.push(Line::from(vec![
-        Span::styled("  ● ", Style::default().fg(Color::Magenta)),
-        Span::styled("Databases", Style::default().fg(Color::White)),
+        Span::styled("  ● ", Style::default().fg(theme.highlight)),
+        Span::styled("Databases", Style::default().fg(theme.text)),
         Span::styled(
             format!("        {}", app.databases.len()),
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         ),
     ]));
     items.push(Line::from(vec![
-        Span::styled("  ● ", Style::default().fg(Color::Yellow)),
-        Span::styled("Scripts", Style::default().fg(Color::White)),
+        Span::styled("  ● ", Style::default().fg(theme.warning)),
+        Span::styled("Scripts", Style::default().fg(theme.text)),
         Span::styled(
             format!("          {}", app.scripts.len()),
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         ),
     ]));
 
+    if !app.docker_hosts.is_empty() {
+        items.push(Line::from(""));
+        items.push(Line::from(Span::styled(
+            "  Docker",
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )));
+        for host in &app.docker_hosts {
+            let id = checks::docker_id(&host.id);
+            let (dot, color) = connection_dot(app, &id, theme.dim);
+            let mut spans = vec![
+                Span::raw("  "),
+                Span::styled(dot, Style::default().fg(color)),
+                Span::styled(host.name.clone(), Style::default().fg(theme.text)),
+                Span::styled(format!(" {}", host.url), Style::default().fg(theme.dim)),
+            ];
+            spans.extend(connection_note(app, &id));
+            items.push(Line::from(spans));
+        }
+    }
+
+    if !app.coolify.is_empty() {
+        items.push(Line::from(""));
+        items.push(Line::from(Span::styled(
+            "  Coolify",
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )));
+        for instance in &app.coolify {
+            let id = checks::coolify_id(&instance.id);
+            let (dot, color) = connection_dot(app, &id, theme.dim);
+            let mut spans = vec![
+                Span::raw("  "),
+                Span::styled(dot, Style::default().fg(color)),
+                Span::styled(instance.name.clone(), Style::default().fg(theme.text)),
+                Span::styled(format!(" {}", instance.url), Style::default().fg(theme.dim)),
+            ];
+            spans.extend(connection_note(app, &id));
+            items.push(Line::from(spans));
+        }
+    }
+
+    if !app.repos.is_empty() {
+        items.push(Line::from(""));
+        items.push(Line::from(Span::styled(
+            "  Git",
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )));
+        for repo in &app.repos {
+            let id = checks::git_id(&repo.id);
+            let (dot, color) = connection_dot(app, &id, theme.dim);
+            let mut spans = vec![
+                Span::raw("  "),
+                Span::styled(dot, Style::default().fg(color)),
+                Span::styled(repo.name.clone(), Style::default().fg(theme.text)),
+                Span::styled(format!(" {}", repo.path), Style::default().fg(theme.dim)),
+            ];
+            spans.extend(connection_note(app, &id));
+            items.push(Line::from(spans));
+        }
+    }
+
+    for window in &app.maintenance {
+        let name = app
+            .projects
+            .iter()
+            .find(|p| p.id == window.project_id)
+            .map_or(window.project_id.as_str(), |p| p.name.as_str());
+        items.push(Line::from(Span::styled(
+            format!("  🔧 {}: {}", name, window.banner(app.now)),
+            Style::default().fg(theme.highlight),
+        )));
+    }
+
     let total = app.projects.len()
         + app.servers.len()
         + app.domains.len()
@@ -261,72 +522,94 @@ fn render_status(app: &App) -> Paragraph<'static> {
     items.push(Line::from(""));
     items.push(Line::from(Span::styled(
         "  ─────────────────────────────",
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.dim),
     )));
 
     if total == 0 {
         items.push(Line::from(""));
         items.push(Line::from(Span::styled(
             "  No resources configured yet.",
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
         items.push(Line::from(Span::styled(
             "  Use ↓ to navigate and 'a' to add resources.",
-            Style::default().fg(Color::Yellow),
+            Style::default().fg(theme.warning),
         )));
     } else {
         items.push(Line::from(""));
         items.push(Line::from(vec![
-            Span::styled("  Total: ", Style::default().fg(Color::DarkGray)),
-            Span::styled(format!("{}", total), Style::default().fg(Color::Cyan)),
-            Span::styled(" resources", Style::default().fg(Color::DarkGray)),
+            Span::styled("  Total: ", Style::default().fg(theme.dim)),
+            Span::styled(format!("{}", total), Style::default().fg(theme.accent)),
+            Span::styled(" resources", Style::default().fg(theme.dim)),
         ]));
     }
 
     Paragraph::new(items)
 }
 
-fn render_projects(app: &App) -> Paragraph<'static> {
+fn render_projects(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let projects = app.visible_projects();
     let items: Vec<Line> = if app.projects.is_empty() {
         vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  No projects configured",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'a' to add a project, or use:",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
             Line::from(Span::styled(
                 "  pctrl project add <name>",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
         ]
+    } else if projects.is_empty() {
+        no_matches(app)
     } else {
-        app.projects
-            .iter()
-            .map(|project| {
+        let selected = app.project_selected.min(projects.len() - 1);
+        // Borders take 2 rows
+        let visible = (height as usize).saturating_sub(2).max(1);
+        let offset = (selected + 1).saturating_sub(visible);
+        projects
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, project)| {
+                let is_selected = i == selected;
                 let status_color = match project.status {
-                    ProjectStatus::Dev => Color::Yellow,
-                    ProjectStatus::Staging => Color::Blue,
-                    ProjectStatus::Live => Color::Green,
-                    ProjectStatus::Archived => Color::DarkGray,
+                    ProjectStatus::Dev => theme.warning,
+                    ProjectStatus::Staging => theme.info,
+                    ProjectStatus::Live => theme.success,
+                    ProjectStatus::Archived => theme.dim,
+                    ProjectStatus::Maintenance => theme.highlight,
                 };
                 let stack_str = if project.stack.is_empty() {
                     String::new()
                 } else {
                     format!(" [{}]", project.stack.join(", "))
                 };
+                let status = match app.maintenance.iter().find(|w| w.project_id == project.id) {
+                    Some(window) => window.banner(app.now),
+                    None => project.status.to_string(),
+                };
                 Line::from(vec![
-                    Span::styled("  ● ", Style::default().fg(status_color)),
-                    Span::styled(project.name.clone(), Style::default().fg(Color::Cyan)),
+                    Span::styled(if is_selected { "▶ " } else { "  " }, theme.selected()),
+                    Span::styled("● ", Style::default().fg(status_color)),
                     Span::styled(
-                        format!(" ({})", project.status),
-                        Style::default().fg(status_color),
+                        project.name.clone(),
+                        if is_selected {
+                            theme.selected()
+                        } else {
+                            Style::default().fg(theme.accent)
+                        },
                     ),
-                    Span::styled(stack_str, Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!(" ({})", status), Style::default().fg(status_color)),
+                    Span::styled(stack_str, Style::default().fg(theme.dim)),
                 ])
             })
             .collect()
@@ -334,72 +617,274 @@ fn render_projects(app: &App) -> Paragraph<'static> {
     Paragraph::new(items)
 }
 
-fn render_servers(app: &App) -> Paragraph<'static> {
+/// A project with its details and the resources linked to it
+fn render_project_detail(app: &App, detail: &ProjectDetail) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let Some(project) = app.selected_project() else {
+        return Paragraph::new("");
+    };
+    let status = match app.maintenance.iter().find(|w| w.project_id == project.id) {
+        Some(window) => window.banner(app.now),
+        None => project.status.to_string(),
+    };
+    let mut items: Vec<Line> = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(
+                format!("  {}", project.name),
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" ({})", status), Style::default().fg(theme.dim)),
+        ]),
+    ];
+    if let Some(description) = &project.description {
+        items.push(Line::from(Span::styled(
+            format!("  {}", description),
+            Style::default().fg(theme.text),
+        )));
+    }
+    if !project.stack.is_empty() {
+        items.push(Line::from(vec![
+            Span::styled("  Stack: ", Style::default().fg(theme.dim)),
+            Span::styled(project.stack.join(", "), Style::default().fg(theme.text)),
+        ]));
+    }
+
+    items.push(Line::from(""));
+    items.push(Line::from(Span::styled(
+        format!("  Linked resources ({})", detail.resources.len()),
+        Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+    )));
+    if detail.resources.is_empty() {
+        items.push(Line::from(Span::styled(
+            "  None yet; link one with:",
+            Style::default().fg(theme.dim),
+        )));
+        items.push(Line::from(Span::styled(
+            format!(
+                "  pctrl project link \"{}\" <type> <resource-id>",
+                project.name
+            ),
+            Style::default().fg(theme.warning),
+        )));
+    }
+    for resource in &detail.resources {
+        let mut spans = vec![
+            Span::styled("  ● ", Style::default().fg(theme.info)),
+            Span::styled(
+                format!("{:10}", resource.resource_type.to_string()),
+                Style::default().fg(theme.dim),
+            ),
+            Span::styled(
+                resource_name(app, resource),
+                Style::default().fg(theme.accent),
+            ),
+        ];
+        if let Some(role) = &resource.role {
+            spans.push(Span::styled(
+                format!(" [{}]", role),
+                Style::default().fg(theme.dim),
+            ));
+        }
+        items.push(Line::from(spans));
+    }
+    Paragraph::new(items)
+}
+
+/// Name of a linked resource from the loaded lists; its ID for kinds the
+/// TUI doesn't list or links to something that's gone
+fn resource_name(app: &App, resource: &ProjectResource) -> String {
+    let id = resource.resource_id.as_str();
+    let name = match resource.resource_type {
+        ResourceType::Server => app
+            .servers
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| s.name.clone()),
+        ResourceType::Domain => app
+            .domains
+            .iter()
+            .find(|d| d.id == id)
+            .map(|d| d.domain.clone()),
+        ResourceType::Database => app
+            .databases
+            .iter()
+            .find(|d| d.id == id)
+            .map(|d| d.name.clone()),
+        ResourceType::Script => app
+            .scripts
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| s.name.clone()),
+        _ => None,
+    };
+    name.unwrap_or_else(|| id.to_string())
+}
+
+fn render_servers(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    // First line of the server under the cursor
+    let mut selected_line = 0;
+    let servers = app.visible_servers();
     let items: Vec<Line> = if app.servers.is_empty() {
         vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  No servers configured",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'a' to add a server, or use:",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
             Line::from(Span::styled(
                 "  pctrl server add <name> <host>",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
         ]
+    } else if servers.is_empty() {
+        no_matches(app)
     } else {
-        app.servers
-            .iter()
-            .map(|server| {
-                let type_str = format!(" [{}]", server.server_type);
-                Line::from(vec![
-                    Span::styled("  ● ", Style::default().fg(Color::Green)),
-                    Span::styled(server.name.clone(), Style::default().fg(Color::Cyan)),
-                    Span::raw(" - "),
-                    Span::styled(server.host.clone(), Style::default().fg(Color::White)),
-                    Span::styled(type_str, Style::default().fg(Color::DarkGray)),
-                ])
-            })
-            .collect()
+        let selected = app.server_selected.min(servers.len() - 1);
+        let mut items = Vec::new();
+        for (i, server) in servers.into_iter().enumerate() {
+            let is_selected = i == selected;
+            if is_selected {
+                selected_line = items.len();
+            }
+            let type_str = format!(" [{}]", server.server_type);
+            let no_vpn = app.tunnels.blocked(server);
+            let (dot, color) = match no_vpn {
+                Some(_) => ("● ", theme.warning),
+                None => connection_dot(app, &server.id, theme.dim),
+            };
+            let mut spans = vec![
+                Span::styled(if is_selected { "▶ " } else { "  " }, theme.selected()),
+                Span::styled(dot, Style::default().fg(color)),
+                Span::styled(
+                    server.name.clone(),
+                    if is_selected {
+                        theme.selected()
+                    } else {
+                        Style::default().fg(theme.accent)
+                    },
+                ),
+                Span::raw(" - "),
+                Span::styled(server.host.clone(), Style::default().fg(theme.text)),
+                Span::styled(type_str, Style::default().fg(theme.dim)),
+            ];
+            if let Some(reason) = no_vpn {
+                spans.push(Span::styled(
+                    format!("  {}", reason),
+                    Style::default().fg(theme.warning),
+                ));
+            } else {
+                spans.extend(connection_note(app, &server.id));
+            }
+            items.push(Line::from(spans));
+            // systemd units under their server, with the last checked state
+            for service in app.services.iter().filter(|s| s.server_id == server.id) {
+                let state = service.last_state.as_deref();
+                let color = match state {
+                    Some(s) if s.starts_with("active") => theme.success,
+                    Some(s) if s.starts_with("failed") => theme.error,
+                    Some(_) => theme.warning,
+                    None => theme.dim,
+                };
+                items.push(Line::from(vec![
+                    Span::styled("      ⚙ ", Style::default().fg(color)),
+                    Span::styled(service.name.clone(), Style::default().fg(theme.text)),
+                    Span::styled(
+                        format!(" {} {}", service.unit, state.unwrap_or("unchecked")),
+                        Style::default().fg(theme.dim),
+                    ),
+                ]));
+            }
+            // Containers from the last `server discover`, marked once stale
+            if let Some(cached) = app.discovered.iter().find(|d| d.server_id == server.id) {
+                let containers = cached.containers();
+                let running = containers.iter().filter(|c| quota::is_running(c)).count();
+                let age = DateTime::parse_from_rfc3339(&cached.fetched_at)
+                    .map(|t| humanize::relative_to(t.with_timezone(&Utc), app.now))
+                    .unwrap_or_else(|_| cached.fetched_at.clone());
+                let fresh = cached.is_fresh(app.now);
+                items.push(Line::from(vec![
+                    Span::styled("      📦 ", Style::default().fg(theme.dim)),
+                    Span::styled(
+                        format!(
+                            "{}, {} running",
+                            humanize::count(containers.len() as u64, "container", "containers"),
+                            running
+                        ),
+                        Style::default().fg(theme.text),
+                    ),
+                    Span::styled(
+                        format!(" discovered {}{}", age, if fresh { "" } else { ", stale" }),
+                        Style::default().fg(if fresh { theme.dim } else { theme.warning }),
+                    ),
+                ]));
+            }
+        }
+        items
     };
-    Paragraph::new(items)
+    // Borders take 2 rows
+    let visible = (height as usize).saturating_sub(2).max(1);
+    let offset = (selected_line + 1).saturating_sub(visible);
+    Paragraph::new(items).scroll((offset as u16, 0))
 }
 
-fn render_domains(app: &App) -> Paragraph<'static> {
+fn render_domains(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let domains = app.visible_domains();
     let items: Vec<Line> = if app.domains.is_empty() {
         vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  No domains configured",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'a' to add a domain, or use:",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
             Line::from(Span::styled(
                 "  pctrl domain add <domain>",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
         ]
+    } else if domains.is_empty() {
+        no_matches(app)
     } else {
-        app.domains
-            .iter()
-            .map(|domain| {
+        let selected = app.domain_selected.min(domains.len() - 1);
+        // Borders take 2 rows
+        let visible = (height as usize).saturating_sub(2).max(1);
+        let offset = (selected + 1).saturating_sub(visible);
+        domains
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, domain)| {
+                let is_selected = i == selected;
                 let ssl_icon = if domain.ssl { "🔒" } else { "🔓" };
                 let type_str = format!(" ({})", domain.domain_type);
                 Line::from(vec![
-                    Span::styled("  ", Style::default()),
+                    Span::styled(if is_selected { "▶ " } else { "  " }, theme.selected()),
                     Span::raw(ssl_icon),
                     Span::raw(" "),
-                    Span::styled(domain.domain.clone(), Style::default().fg(Color::Blue)),
-                    Span::styled(type_str, Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        domain.domain.clone(),
+                        if is_selected {
+                            theme.selected()
+                        } else {
+                            Style::default().fg(theme.info)
+                        },
+                    ),
+                    Span::styled(type_str, Style::default().fg(theme.dim)),
                 ])
             })
             .collect()
@@ -407,125 +892,503 @@ fn render_domains(app: &App) -> Paragraph<'static> {
     Paragraph::new(items)
 }
 
-fn render_databases(app: &App) -> Paragraph<'static> {
+fn render_databases(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let databases = app.visible_databases();
     let items: Vec<Line> = if app.databases.is_empty() {
         vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  No databases configured",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'a' to add a database, or use:",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
             Line::from(Span::styled(
                 "  pctrl db add <name> <type> <host>",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
         ]
+    } else if databases.is_empty() {
+        no_matches(app)
     } else {
-        app.databases
-            .iter()
-            .map(|db| {
+        let selected = app.database_selected.min(databases.len() - 1);
+        // Borders take 2 rows
+        let visible = (height as usize).saturating_sub(2).max(1);
+        let offset = (selected + 1).saturating_sub(visible);
+        databases
+            .into_iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, db)| {
+                let is_selected = i == selected;
                 let host_str = db.host.as_deref().unwrap_or("localhost");
                 let port_str = db.port.map(|p| format!(":{}", p)).unwrap_or_default();
-                Line::from(vec![
-                    Span::styled("  ● ", Style::default().fg(Color::Magenta)),
-                    Span::styled(db.name.clone(), Style::default().fg(Color::Cyan)),
+                let (dot, color) = connection_dot(app, &db.id, theme.highlight);
+                let mut spans = vec![
+                    Span::styled(if is_selected { "▶ " } else { "  " }, theme.selected()),
+                    Span::styled(dot, Style::default().fg(color)),
+                    Span::styled(
+                        db.name.clone(),
+                        if is_selected {
+                            theme.selected()
+                        } else {
+                            Style::default().fg(theme.accent)
+                        },
+                    ),
                     Span::styled(
                         format!(" [{}]", db.db_type),
-                        Style::default().fg(Color::Magenta),
+                        Style::default().fg(theme.highlight),
                     ),
                     Span::raw(" - "),
                     Span::styled(
                         format!("{}{}", host_str, port_str),
-                        Style::default().fg(Color::White),
+                        Style::default().fg(theme.text),
                     ),
-                ])
+                ];
+                spans.extend(connection_note(app, &db.id));
+                Line::from(spans)
             })
             .collect()
     };
     Paragraph::new(items)
 }
 
-fn render_scripts(app: &App) -> Paragraph<'static> {
+/// In place of a list the filter left empty
+fn no_matches(app: &App) -> Vec<Line<'static>> {
+    let query = app.list_filter().unwrap_or_default();
+    vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  Nothing matches '{}'", query.trim()),
+            Style::default().fg(app.theme.dim),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Press Esc to clear the filter",
+            Style::default().fg(app.theme.warning),
+        )),
+    ]
+}
+
+/// Dot before a checked entry and its color: a spinner while the check
+/// runs, green, yellow or red once it's done, `unchecked` before the first one
+fn connection_dot(app: &App, id: &str, unchecked: Color) -> (&'static str, Color) {
+    const SPINNER: [&str; 4] = ["◐ ", "◓ ", "◑ ", "◒ "];
+    let theme = &app.theme;
+    match app.connections.get(id) {
+        None => ("● ", unchecked),
+        Some(ConnectionStatus::Checking) => {
+            let frame = app.now.timestamp_subsec_millis() as usize / 250;
+            (SPINNER[frame % SPINNER.len()], theme.dim)
+        }
+        Some(ConnectionStatus::Online) => ("● ", theme.success),
+        Some(ConnectionStatus::Offline(_)) => ("● ", theme.error),
+        Some(ConnectionStatus::Attention(_)) => ("● ", theme.warning),
+    }
+}
+
+/// "checking…", why the entry is offline or what needs attention, after
+/// its line
+fn connection_note(app: &App, id: &str) -> Option<Span<'static>> {
+    let theme = &app.theme;
+    match app.connections.get(id)? {
+        ConnectionStatus::Checking => {
+            Some(Span::styled("  checking…", Style::default().fg(theme.dim)))
+        }
+        ConnectionStatus::Offline(reason) => Some(Span::styled(
+            format!("  {}", reason),
+            Style::default().fg(theme.error),
+        )),
+        ConnectionStatus::Attention(note) => Some(Span::styled(
+            format!("  {}", note),
+            Style::default().fg(theme.warning),
+        )),
+        ConnectionStatus::Online => None,
+    }
+}
+
+fn render_scripts(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let scripts = app.visible_scripts();
     let items: Vec<Line> = if app.scripts.is_empty() {
         vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  No scripts configured",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'a' to add a script, or use:",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
             Line::from(Span::styled(
                 "  pctrl script add <name> <command>",
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(theme.warning),
             )),
         ]
+    } else if scripts.is_empty() {
+        no_matches(app)
     } else {
-        app.scripts
-            .iter()
-            .map(|script| {
-                let type_str = format!(" [{}]", script.script_type);
-                let cmd_preview: String = script.command.chars().take(40).collect();
-                let cmd_display = if script.command.len() > 40 {
-                    format!("{}...", cmd_preview)
-                } else {
-                    cmd_preview
-                };
-                Line::from(vec![
-                    Span::styled("  ● ", Style::default().fg(Color::Yellow)),
-                    Span::styled(script.name.clone(), Style::default().fg(Color::Cyan)),
-                    Span::styled(type_str, Style::default().fg(Color::Yellow)),
-                    Span::raw(" - "),
-                    Span::styled(cmd_display, Style::default().fg(Color::DarkGray)),
-                ])
+        let rows = app.script_rows();
+        let selected = app.script_selected.min(rows.len() - 1);
+        // Borders take 2 rows
+        let visible = (height as usize).saturating_sub(2).max(1);
+        let offset = (selected + 1).saturating_sub(visible);
+        rows.iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(i, row)| {
+                let is_selected = i == selected;
+                let marker = Span::styled(if is_selected { "▶ " } else { "  " }, theme.selected());
+                let indent = Span::raw("  ".repeat(row.depth()));
+                match row {
+                    TreeRow::Namespace {
+                        label,
+                        scripts,
+                        collapsed,
+                        ..
+                    } => Line::from(vec![
+                        marker,
+                        indent,
+                        Span::styled(
+                            if *collapsed { "▸ " } else { "▾ " },
+                            Style::default().fg(theme.dim),
+                        ),
+                        Span::styled(
+                            format!("{}/", label),
+                            if is_selected {
+                                theme.selected()
+                            } else {
+                                Style::default().fg(theme.info)
+                            },
+                        ),
+                        Span::styled(format!(" ({})", scripts), Style::default().fg(theme.dim)),
+                    ]),
+                    TreeRow::Script { index, label, .. } => {
+                        let script = scripts[*index];
+                        let type_str = format!(" [{}]", script.script_type);
+                        let cmd_preview: String = script.command.chars().take(40).collect();
+                        let cmd_display = if script.command.len() > 40 {
+                            format!("{}...", cmd_preview)
+                        } else {
+                            cmd_preview
+                        };
+                        Line::from(vec![
+                            marker,
+                            indent,
+                            Span::styled("● ", Style::default().fg(theme.warning)),
+                            Span::styled(
+                                label.to_string(),
+                                if is_selected {
+                                    theme.selected()
+                                } else {
+                                    Style::default().fg(theme.accent)
+                                },
+                            ),
+                            Span::styled(type_str, Style::default().fg(theme.warning)),
+                            Span::raw(" - "),
+                            Span::styled(cmd_display, Style::default().fg(theme.dim)),
+                        ])
+                    }
+                }
             })
             .collect()
     };
     Paragraph::new(items)
 }
 
+fn activity_color(theme: &Theme, kind: ActivityKind) -> Color {
+    match kind {
+        ActivityKind::Audit => theme.accent,
+        ActivityKind::Script => theme.warning,
+        ActivityKind::Monitor => theme.highlight,
+        ActivityKind::Command => theme.info,
+    }
+}
+
+/// Aligned field changes: old value struck through in red, new in green
+fn change_lines(theme: &Theme, changes: &[FieldChange]) -> Vec<Line<'static>> {
+    let width = changes.iter().map(|c| c.field.len()).max().unwrap_or(0);
+    changes
+        .iter()
+        .map(|change| {
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:<width$}  ", change.field, width = width),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::styled(
+                    display_value(&change.old),
+                    Style::default()
+                        .fg(theme.error)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                ),
+                Span::raw(" → "),
+                Span::styled(
+                    display_value(&change.new),
+                    Style::default().fg(theme.success),
+                ),
+            ])
+        })
+        .collect()
+}
+
+fn render_activity(app: &App, height: u16) -> Paragraph<'static> {
+    let theme = &app.theme;
+    // Filter bar: kind toggles and current search
+    let mut filter_spans = vec![Span::raw("  ")];
+    for (i, kind) in ActivityKind::ALL.iter().enumerate() {
+        let style = if app.activity_filter.includes(*kind) {
+            Style::default().fg(activity_color(theme, *kind))
+        } else {
+            Style::default().fg(theme.dim)
+        };
+        filter_spans.push(Span::styled(format!("[{}] {}  ", i + 1, kind), style));
+    }
+    if app.input_mode == InputMode::Searching {
+        filter_spans.push(Span::styled(
+            format!("/{}▌", app.search_input),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ));
+    } else if let Some(ref search) = app.activity_filter.search {
+        filter_spans.push(Span::styled(
+            format!("/{}", search),
+            Style::default().fg(theme.text),
+        ));
+    }
+
+    let mut items: Vec<Line> = vec![Line::from(filter_spans), Line::from("")];
+
+    if app.activity.is_empty() {
+        items.push(Line::from(Span::styled(
+            "  No activity recorded yet",
+            Style::default().fg(theme.dim),
+        )));
+        return Paragraph::new(items);
+    }
+
+    if app.activity_expanded {
+        let entry = &app.activity[app.activity_selected.min(app.activity.len() - 1)];
+        items.push(Line::from(vec![
+            Span::styled(
+                format!("  {} ", entry.kind),
+                Style::default().fg(activity_color(theme, entry.kind)),
+            ),
+            Span::styled(
+                format_timestamp(&entry.timestamp),
+                Style::default().fg(theme.dim),
+            ),
+        ]));
+        items.push(Line::from(Span::styled(
+            format!("  {}", entry.title),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )));
+        items.push(Line::from(""));
+        match entry.details {
+            Some(ref details) => {
+                for line in details.lines() {
+                    // Audit updates carry their field changes as JSON
+                    let changes = match entry.kind {
+                        ActivityKind::Audit => parse_details(line),
+                        _ => None,
+                    };
+                    match changes {
+                        Some(changes) => items.extend(change_lines(theme, &changes)),
+                        None => items.push(Line::from(Span::styled(
+                            format!("  {}", line),
+                            Style::default().fg(theme.muted),
+                        ))),
+                    }
+                }
+            }
+            None => items.push(Line::from(Span::styled(
+                "  No details",
+                Style::default().fg(theme.dim),
+            ))),
+        }
+        return Paragraph::new(items);
+    }
+
+    // Borders, filter bar and the "more" hint take 5 rows
+    let visible = (height as usize).saturating_sub(5).max(1);
+    let offset = (app.activity_selected + 1).saturating_sub(visible);
+
+    for (i, entry) in app.activity.iter().enumerate().skip(offset).take(visible) {
+        let is_selected = i == app.activity_selected;
+        let title_style = if is_selected {
+            theme.selected()
+        } else {
+            Style::default().fg(theme.text)
+        };
+        items.push(Line::from(vec![
+            Span::styled(if is_selected { "▶ " } else { "  " }, title_style),
+            Span::styled(
+                format_timestamp(&entry.timestamp),
+                Style::default().fg(theme.dim),
+            ),
+            Span::styled(
+                format!(" {:8} ", entry.kind),
+                Style::default().fg(activity_color(theme, entry.kind)),
+            ),
+            Span::styled(entry.title.clone(), title_style),
+        ]));
+    }
+
+    if !app.activity_exhausted {
+        items.push(Line::from(Span::styled(
+            "  ↓ older entries load on scroll",
+            Style::default().fg(theme.dim),
+        )));
+    }
+
+    Paragraph::new(items)
+}
+
+/// "2024-01-02T10:00:00Z" -> "2024-01-02 10:00:00"
+fn format_timestamp(ts: &str) -> String {
+    ts.replacen('T', " ", 1).trim_end_matches('Z').to_string()
+}
+
 fn render_footer(f: &mut Frame, app: &App, area: Rect) {
-    let footer_content = if app.input_mode == InputMode::Adding {
+    let theme = &app.theme;
+    let footer_content = if app.input_mode == InputMode::Notifications {
+        Line::from(vec![
+            Span::styled(" ↑↓ ", Style::default().fg(theme.accent)),
+            Span::raw("Select"),
+            Span::raw("  │  "),
+            Span::styled(" Enter ", Style::default().fg(theme.accent)),
+            Span::raw("Open"),
+            Span::raw("  │  "),
+            Span::styled(" d ", Style::default().fg(theme.accent)),
+            Span::raw("Details"),
+            Span::raw("  │  "),
+            Span::styled(" Esc ", Style::default().fg(theme.accent)),
+            Span::raw("Close"),
+        ])
+    } else if app.input_mode == InputMode::Deleting {
+        Line::from(vec![
+            Span::styled(" y ", Style::default().fg(theme.accent)),
+            Span::raw("Delete"),
+            Span::raw("  │  "),
+            Span::styled(" n/Esc ", Style::default().fg(theme.accent)),
+            Span::raw("Keep"),
+        ])
+    } else if app.input_mode == InputMode::Searching {
         Line::from(vec![
-            Span::styled(" Tab ", Style::default().fg(Color::Cyan)),
+            Span::styled(" Enter ", Style::default().fg(theme.accent)),
+            Span::raw("Apply"),
+            Span::raw("  │  "),
+            Span::styled(" Esc ", Style::default().fg(theme.accent)),
+            Span::raw("Cancel"),
+        ])
+    } else if app.selected_panel == SelectedPanel::Activity {
+        Line::from(vec![
+            Span::styled(" ↑↓ ", Style::default().fg(theme.accent)),
+            Span::raw("Scroll"),
+            Span::raw("  │  "),
+            Span::styled(" Tab ", Style::default().fg(theme.accent)),
+            Span::raw("Panels"),
+            Span::raw("  │  "),
+            Span::styled(" Enter ", Style::default().fg(theme.accent)),
+            Span::raw("Details"),
+            Span::raw("  │  "),
+            Span::styled(" / ", Style::default().fg(theme.accent)),
+            Span::raw("Search"),
+            Span::raw("  │  "),
+            Span::styled(" 1-4 ", Style::default().fg(theme.accent)),
+            Span::raw("Filter"),
+            Span::raw("  │  "),
+            Span::styled(" T ", Style::default().fg(theme.accent)),
+            Span::raw("Theme"),
+            Span::raw("  │  "),
+            Span::styled(" n ", Style::default().fg(theme.accent)),
+            Span::raw("Notifications"),
+            Span::raw("  │  "),
+            Span::styled(" q ", Style::default().fg(theme.accent)),
+            Span::raw("Quit"),
+        ])
+    } else if app.input_mode == InputMode::Adding {
+        Line::from(vec![
+            Span::styled(" Tab ", Style::default().fg(theme.accent)),
             Span::raw("Next"),
             Span::raw("  │  "),
-            Span::styled(" Shift+Tab ", Style::default().fg(Color::Cyan)),
+            Span::styled(" Shift+Tab ", Style::default().fg(theme.accent)),
             Span::raw("Prev"),
             Span::raw("  │  "),
-            Span::styled(" Enter ", Style::default().fg(Color::Cyan)),
+            Span::styled(" Enter ", Style::default().fg(theme.accent)),
             Span::raw("Save"),
             Span::raw("  │  "),
-            Span::styled(" Esc ", Style::default().fg(Color::Cyan)),
+            Span::styled(" Esc ", Style::default().fg(theme.accent)),
             Span::raw("Cancel"),
         ])
     } else {
         let can_add = app.selected_panel != SelectedPanel::Status;
         let mut spans = vec![
-            Span::styled(" ↑↓ ", Style::default().fg(Color::Cyan)),
+            Span::styled(" ↑↓ ", Style::default().fg(theme.accent)),
             Span::raw("Navigate"),
         ];
+        if app.selected_panel == SelectedPanel::Scripts {
+            spans.extend(vec![
+                Span::raw("  │  "),
+                Span::styled(" Enter ", Style::default().fg(theme.accent)),
+                Span::raw("Fold"),
+            ]);
+        }
+        if app.selected_panel == SelectedPanel::Projects && app.selected_project().is_some() {
+            let (key, action) = if app.project_detail.is_some() {
+                (" Esc ", "Back")
+            } else {
+                (" Enter ", "Open")
+            };
+            spans.extend(vec![
+                Span::raw("  │  "),
+                Span::styled(key, Style::default().fg(theme.accent)),
+                Span::raw(action),
+            ]);
+        }
+        if can_add && app.project_detail.is_none() {
+            spans.extend(vec![
+                Span::raw("  │  "),
+                Span::styled(" / ", Style::default().fg(theme.accent)),
+                Span::raw("Filter"),
+            ]);
+        }
+        if app.selection().is_some() {
+            spans.extend(vec![
+                Span::raw("  │  "),
+                Span::styled(" e ", Style::default().fg(theme.accent)),
+                Span::raw("Edit"),
+                Span::raw("  │  "),
+                Span::styled(" d ", Style::default().fg(theme.accent)),
+                Span::raw("Delete"),
+            ]);
+        }
         if can_add {
             spans.extend(vec![
                 Span::raw("  │  "),
-                Span::styled(" a ", Style::default().fg(Color::Cyan)),
+                Span::styled(" a ", Style::default().fg(theme.accent)),
                 Span::raw("Add"),
             ]);
         }
         spans.extend(vec![
             Span::raw("  │  "),
-            Span::styled(" r ", Style::default().fg(Color::Cyan)),
+            Span::styled(" r ", Style::default().fg(theme.accent)),
             Span::raw("Refresh"),
             Span::raw("  │  "),
-            Span::styled(" q ", Style::default().fg(Color::Cyan)),
+            Span::styled(" T ", Style::default().fg(theme.accent)),
+            Span::raw("Theme"),
+            Span::raw("  │  "),
+            Span::styled(" n ", Style::default().fg(theme.accent)),
+            Span::raw("Notifications"),
+            Span::raw("  │  "),
+            Span::styled(" q ", Style::default().fg(theme.accent)),
             Span::raw("Quit"),
         ]);
         Line::from(spans)
@@ -534,7 +1397,7 @@ fn render_footer(f: &mut Frame, app: &App, area: Rect) {
     let footer = Paragraph::new(footer_content).block(
         Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
+            .border_style(Style::default().fg(theme.dim)),
     );
     f.render_widget(footer, area);
 }
@@ -0,0 +1,36 @@
+//! Helpers shared by the CLI tests
+//!
+//! Every test file compiles this module on its own and uses only part of
+//! it.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Run the `pctrl` binary against `db`, without color or a terminal
+pub fn pctrl(db: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .output()
+        .expect("pctrl runs")
+}
+
+/// Run `pctrl`, which must succeed, and return its stdout
+pub fn run(db: &Path, args: &[&str]) -> String {
+    let output = pctrl(db, args);
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
@@ -0,0 +1,368 @@
+//! `pctrl export --file` and `pctrl import` between two databases
+
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn pctrl(db: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .env("PCTRL_BACKUP_PASSPHRASE", "correct horse")
+        .stdin(Stdio::null())
+        .output()
+        .expect("pctrl runs")
+}
+
+fn run(db: &Path, args: &[&str]) -> String {
+    let output = pctrl(db, args);
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// The demo data plus two credentials, one used by a server
+fn source(dir: &tempfile::TempDir) -> std::path::PathBuf {
+    let db = dir.path().join("source.db");
+    run(&db, &["debug", "seed-demo"]);
+    run(
+        &db,
+        &[
+            "credential",
+            "add",
+            "cf",
+            "--type",
+            "api",
+            "--token",
+            "tok-123",
+        ],
+    );
+    run(
+        &db,
+        &[
+            "credential",
+            "add",
+            "deploy",
+            "--type",
+            "ssh",
+            "-u",
+            "root",
+            "-k",
+            "/keys/id",
+        ],
+    );
+    run(
+        &db,
+        &[
+            "server",
+            "add",
+            "extra",
+            "198.51.100.7",
+            "--credential",
+            "deploy",
+        ],
+    );
+    db
+}
+
+/// A document without the line that changes on every export
+fn content(path: &Path) -> String {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|l| !l.contains("exported_at"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_round_trip_into_an_empty_database_is_identical() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = source(&dir);
+
+    for name in ["setup.yaml", "setup.json"] {
+        let exported = dir.path().join(name);
+        let file = exported.to_str().unwrap();
+        run(&source, &["export", "--file", file, "--include-secrets"]);
+
+        let target = dir.path().join(format!("{}.db", name));
+        let report = run(&target, &["import", file]);
+        assert!(report.contains("0 skipped"), "{}", report);
+
+        let again = dir.path().join(format!("again-{}", name));
+        run(
+            &target,
+            &[
+                "export",
+                "--file",
+                again.to_str().unwrap(),
+                "--include-secrets",
+            ],
+        );
+        assert_eq!(content(&exported), content(&again), "{}", name);
+
+        // A second import finds nothing to do
+        let report = run(&target, &["import", file]);
+        assert!(report.contains("0 created, 0 updated"), "{}", report);
+    }
+}
+
+#[test]
+fn test_secrets_are_left_out_unless_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = source(&dir);
+    let exported = dir.path().join("plain.yaml");
+    run(&source, &["export", "--file", exported.to_str().unwrap()]);
+    assert!(!std::fs::read_to_string(&exported)
+        .unwrap()
+        .contains("tok-123"));
+
+    let target = dir.path().join("target.db");
+    let report = run(&target, &["import", exported.to_str().unwrap()]);
+    assert!(report.contains("credential cf"), "{}", report);
+    assert!(report.contains("no credential data in the file"));
+    assert!(report.contains("credential deploy not in the file"));
+    let servers = run(&target, &["server", "list"]);
+    assert!(servers.contains("extra"), "{}", servers);
+}
+
+#[test]
+fn test_encrypted_export_needs_the_passphrase() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = source(&dir);
+    let exported = dir.path().join("setup.yaml");
+    let file = exported.to_str().unwrap();
+    run(
+        &source,
+        &["export", "--file", file, "--include-secrets", "--encrypt"],
+    );
+    assert!(!std::fs::read_to_string(&exported).is_ok_and(|t| t.contains("tok-123")));
+
+    let target = dir.path().join("target.db");
+    let output = Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(&target)
+        .args(["import", file])
+        .env("PCTRL_BACKUP_PASSPHRASE", "wrong")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let report = run(&target, &["import", file]);
+    assert!(report.contains("0 skipped"), "{}", report);
+}
+
+#[test]
+fn test_replace_removes_what_the_file_lacks() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = source(&dir);
+    let exported = dir.path().join("setup.yaml");
+    let file = exported.to_str().unwrap();
+    run(&source, &["export", "--file", file, "--include-secrets"]);
+
+    let target = dir.path().join("target.db");
+    run(&target, &["import", file]);
+    run(&target, &["server", "add", "local-only", "198.51.100.9"]);
+
+    // Merging leaves it alone, a dry run of replace only reports it
+    let report = run(&target, &["import", file]);
+    assert!(report.contains("1 only here"), "{}", report);
+    let report = run(&target, &["import", file, "--replace", "--dry-run"]);
+    assert!(report.contains("would remove"), "{}", report);
+    assert!(run(&target, &["server", "list"]).contains("local-only"));
+
+    let report = run(&target, &["import", file, "--replace"]);
+    assert!(report.contains("1 removed"), "{}", report);
+    assert!(!run(&target, &["server", "list"]).contains("local-only"));
+}
+
+/// An export whose two tokens are placeholders
+fn with_placeholders(dir: &tempfile::TempDir, token_file: &Path) -> std::path::PathBuf {
+    let db = dir.path().join("placeholders.db");
+    run(
+        &db,
+        &[
+            "credential",
+            "add",
+            "cf",
+            "--type",
+            "api",
+            "--token",
+            "tok-1",
+        ],
+    );
+    run(
+        &db,
+        &[
+            "credential",
+            "add",
+            "gh",
+            "--type",
+            "api",
+            "--token",
+            "tok-2",
+        ],
+    );
+    let exported = dir.path().join("placeholders.yaml");
+    run(
+        &db,
+        &[
+            "export",
+            "--file",
+            exported.to_str().unwrap(),
+            "--include-secrets",
+        ],
+    );
+    let text = std::fs::read_to_string(&exported)
+        .unwrap()
+        .replace("tok-1", "'@env:PCTRL_TEST_CF_TOKEN'")
+        .replace("tok-2", &format!("'@file:{}'", token_file.display()));
+    std::fs::write(&exported, text).unwrap();
+    exported
+}
+
+fn import_with_env(db: &Path, file: &Path, env: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .arg("import")
+        .arg(file)
+        .envs(env.iter().copied())
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .output()
+        .expect("pctrl runs")
+}
+
+#[test]
+fn test_placeholders_are_resolved_before_importing() {
+    let dir = tempfile::tempdir().unwrap();
+    let token_file = dir.path().join("gh-token");
+    std::fs::write(&token_file, "tok-from-file\n").unwrap();
+    let exported = with_placeholders(&dir, &token_file);
+
+    let target = dir.path().join("target.db");
+    let output = import_with_env(
+        &target,
+        &exported,
+        &[("PCTRL_TEST_CF_TOKEN", "tok-from-env")],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let again = dir.path().join("again.yaml");
+    run(
+        &target,
+        &[
+            "export",
+            "--file",
+            again.to_str().unwrap(),
+            "--include-secrets",
+        ],
+    );
+    let text = std::fs::read_to_string(&again).unwrap();
+    assert!(text.contains("token: tok-from-env"), "{}", text);
+    assert!(text.contains("token: tok-from-file"), "{}", text);
+}
+
+#[test]
+fn test_unresolved_placeholders_import_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let exported = with_placeholders(&dir, &dir.path().join("missing"));
+
+    let target = dir.path().join("target.db");
+    let output = import_with_env(&target, &exported, &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("2 secret placeholders can't be resolved"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("token for credential 'cf'"), "{}", stderr);
+    assert!(stderr.contains("token for credential 'gh'"), "{}", stderr);
+    assert!(!run(&target, &["credential", "list"]).contains("cf"));
+
+    // A prompt needs a terminal
+    let text = std::fs::read_to_string(&exported)
+        .unwrap()
+        .replace("'@env:PCTRL_TEST_CF_TOKEN'", "'@prompt'");
+    std::fs::write(&exported, text).unwrap();
+    std::fs::write(dir.path().join("missing"), "tok-2").unwrap();
+    let output = import_with_env(&target, &exported, &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("@prompt needs a terminal"));
+}
+
+#[test]
+fn test_toml_documents_resolve_includes_and_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("common.toml"),
+        r#"format = 1
+
+[vars]
+subnet = "10.0.0"
+region = "fsn1"
+
+[[servers]]
+id = "old"
+name = "old"
+host = "${subnet}.9"
+server_type = "Vps"
+"#,
+    )
+    .unwrap();
+    let infra = dir.path().join("infra.toml");
+    std::fs::write(
+        &infra,
+        r#"include = ["common.toml"]
+
+[vars]
+region = "nbg1"
+
+[[projects]]
+id = "shop"
+name = "shop-${stage}"
+stack = ["rust"]
+status = "Dev"
+
+[["servers!replace"]]
+id = "web-1"
+name = "web-1"
+host = "${subnet}.1"
+server_type = "Vps"
+location = "${region}"
+"#,
+    )
+    .unwrap();
+    let file = infra.to_str().unwrap();
+    let target = dir.path().join("target.db");
+
+    // ${stage} has no value yet
+    let output = pctrl(&target, &["import", file]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("infra.toml:8"), "{}", stderr);
+    assert!(stderr.contains("--var stage=..."), "{}", stderr);
+
+    let report = run(&target, &["import", file, "--var", "stage=prod"]);
+    assert!(report.contains("2 created"), "{}", report);
+    assert!(run(&target, &["project", "list"]).contains("shop-prod"));
+    let servers = run(&target, &["server", "list"]);
+    assert!(servers.contains("10.0.0.1"), "{}", servers);
+    assert!(!servers.contains("old"), "{}", servers);
+    let server = run(&target, &["server", "show", "web-1"]);
+    assert!(server.contains("nbg1"), "{}", server);
+
+    // --var is only for TOML documents
+    let exported = dir.path().join("setup.yaml");
+    run(&target, &["export", "--file", exported.to_str().unwrap()]);
+    let output = pctrl(
+        &target,
+        &["import", exported.to_str().unwrap(), "--var", "stage=prod"],
+    );
+    assert!(!output.status.success());
+}
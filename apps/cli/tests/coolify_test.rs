@@ -0,0 +1,49 @@
+//! `coolify add` verifies the instance before saving it
+
+mod common;
+
+use common::{pctrl, stderr, stdout};
+
+/// A URL nothing listens on
+fn closed_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+#[test]
+fn test_add_refuses_an_unreachable_instance_unless_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let url = closed_url();
+
+    let add = pctrl(&db, &["coolify", "add", "Prod", "-u", &url, "-t", "token"]);
+    assert!(!add.status.success());
+    assert!(stderr(&add).contains("Could not reach"), "{}", stderr(&add));
+    assert!(stderr(&add).contains("--skip-verify"), "{}", stderr(&add));
+    let list = pctrl(&db, &["coolify", "instances"]);
+    assert!(stdout(&list).contains("No Coolify instances"), "{:?}", list);
+
+    let add = pctrl(
+        &db,
+        &[
+            "coolify",
+            "add",
+            "Prod",
+            "-u",
+            &url,
+            "-t",
+            "token",
+            "--skip-verify",
+        ],
+    );
+    assert!(add.status.success(), "{:?}", add);
+    assert!(stdout(&pctrl(&db, &["coolify", "instances"])).contains("Prod"));
+
+    let check = pctrl(&db, &["coolify", "check", "prod"]);
+    assert!(!check.status.success());
+    assert!(
+        stderr(&check).contains("Could not reach Prod"),
+        "{}",
+        stderr(&check)
+    );
+}
@@ -0,0 +1,111 @@
+//! `pctrl <entity> update` applying only the given flags
+
+mod common;
+
+use common::{pctrl, stdout};
+
+fn seeded() -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    assert!(pctrl(&db, &["debug", "seed-demo"]).status.success());
+    (dir, db)
+}
+
+#[test]
+fn test_update_prints_only_the_changed_fields() {
+    let (_dir, db) = seeded();
+    let output = pctrl(
+        &db,
+        &[
+            "server",
+            "update",
+            "demo-web",
+            "--host",
+            "203.0.113.20",
+            "--location",
+            "",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let text = stdout(&output);
+    assert!(text.contains("Server 'demo-web' updated"), "{}", text);
+    assert!(text.contains("203.0.113.10 -> 203.0.113.20"), "{}", text);
+    assert!(text.contains("location"), "{}", text);
+    assert!(!text.contains("provider"), "{}", text);
+
+    let show = stdout(&pctrl(&db, &["server", "show", "demo-web"]));
+    assert!(show.contains("203.0.113.20"), "{}", show);
+    assert!(show.contains("Hetzner"), "{}", show);
+
+    let again = pctrl(
+        &db,
+        &["server", "update", "demo-web", "--host", "203.0.113.20"],
+    );
+    assert_eq!(stdout(&again).trim(), "No changes.");
+}
+
+#[test]
+fn test_update_resolves_servers_by_name() {
+    let (_dir, db) = seeded();
+    let output = pctrl(
+        &db,
+        &[
+            "domain",
+            "update",
+            "shop.demo.example.com",
+            "--server",
+            "demo-db",
+            "--ssl",
+            "false",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let text = stdout(&output);
+    assert!(text.contains("server_id"), "{}", text);
+    assert!(text.contains("ssl"), "{}", text);
+
+    let output = pctrl(
+        &db,
+        &["script", "update", "demo-uptime", "--server", "demo-db"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("server_id"), "{:?}", output);
+
+    let output = pctrl(
+        &db,
+        &["domain", "update", "shop.demo.example.com", "-s", "nope"],
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_project_update_leaves_maintenance_to_its_command() {
+    let (_dir, db) = seeded();
+    let output = pctrl(
+        &db,
+        &["project", "update", "Demo Shop", "--status", "maintenance"],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("pctrl project maintenance"),
+        "{:?}",
+        output
+    );
+
+    let output = pctrl(
+        &db,
+        &[
+            "project",
+            "update",
+            "Demo Shop",
+            "--status",
+            "dev",
+            "--stack",
+            "go",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let text = stdout(&output);
+    assert!(text.contains("Live -> Dev"), "{}", text);
+    assert!(text.contains("rust, postgres -> go"), "{}", text);
+}
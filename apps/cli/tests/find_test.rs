@@ -0,0 +1,68 @@
+//! `pctrl find`: search across entity types, grouped, filtered and as JSON
+
+mod common;
+
+use common::{pctrl, stdout};
+
+fn seeded() -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    for args in [
+        &["server", "add", "web-1", "shop.example.com"][..],
+        &["domain", "add", "shop.example.com"],
+        &["script", "add", "deploy", "-c", "rsync dist/ shop:/srv"],
+        &["server", "add", "ci", "10.0.0.9"],
+    ] {
+        let add = pctrl(&db, args);
+        assert!(add.status.success(), "{:?}", add);
+    }
+    (dir, db)
+}
+
+#[test]
+fn test_find_groups_hits_by_type() {
+    let (_dir, db) = seeded();
+
+    let text = stdout(&pctrl(&db, &["find", "SHOP"]));
+    let servers = text.find("Servers (1)").expect(&text);
+    let domains = text.find("Domains (1)").expect(&text);
+    let scripts = text.find("Scripts (1)").expect(&text);
+    assert!(servers < domains && domains < scripts, "{}", text);
+    assert!(text.contains("(host)"), "{}", text);
+    assert!(text.contains("(command)"), "{}", text);
+    assert!(!text.contains("ci"), "{}", text);
+
+    let text = stdout(&pctrl(&db, &["find", "nothing-here"]));
+    assert!(text.contains("Nothing matches 'nothing-here'."), "{}", text);
+}
+
+#[test]
+fn test_find_filters_by_type_and_prints_json() {
+    let (_dir, db) = seeded();
+
+    let output = pctrl(&db, &["find", "shop", "--type", "server,domain", "--json"]);
+    assert!(output.status.success(), "{:?}", output);
+    let hits: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let found: Vec<(&str, &str, &str)> = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| {
+            (
+                h["entity_type"].as_str().unwrap(),
+                h["name"].as_str().unwrap(),
+                h["field"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("Domain", "shop.example.com", "name"),
+            ("Server", "web-1", "host"),
+        ]
+    );
+
+    let output = pctrl(&db, &["find", "shop", "--type", "planet"]);
+    assert!(!output.status.success());
+}
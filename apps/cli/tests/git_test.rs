@@ -0,0 +1,208 @@
+//! `git add` and `git status` against throwaway repositories
+
+mod common;
+
+use common::{pctrl, stderr, stdout};
+use git2::{Repository, RepositoryInitOptions, Signature};
+use std::path::Path;
+
+/// A repository on `main` with one commit
+fn repo_with_commit(dir: &Path) {
+    let mut options = RepositoryInitOptions::new();
+    options.initial_head("main");
+    let repo = Repository::init_opts(dir, &options).unwrap();
+    std::fs::write(dir.join("README.md"), "hi").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    repo.commit(Some("HEAD"), &author, &author, "Initial commit", &tree, &[])
+        .unwrap();
+}
+
+#[test]
+fn test_status_of_clean_and_dirty_repos() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let shop = dir.path().join("shop");
+    let blog = dir.path().join("blog");
+    repo_with_commit(&shop);
+    repo_with_commit(&blog);
+    std::fs::write(blog.join("draft.md"), "wip").unwrap();
+
+    for (name, path) in [("Shop", &shop), ("Blog", &blog)] {
+        let add = pctrl(&db, &["git", "add", name, "-p", path.to_str().unwrap()]);
+        assert!(add.status.success(), "{}", stderr(&add));
+    }
+
+    let one = pctrl(&db, &["git", "status", "shop"]);
+    assert!(one.status.success(), "{}", stderr(&one));
+    let out = stdout(&one);
+    assert!(out.contains("[ok] Shop"), "{}", out);
+    assert!(out.contains("main"), "{}", out);
+    assert!(out.contains("no upstream"), "{}", out);
+    assert!(out.contains("clean"), "{}", out);
+    assert!(out.contains("Initial commit (Alice,"), "{}", out);
+    assert!(!out.contains("Blog"), "{}", out);
+
+    let all = pctrl(&db, &["git", "status", "--all"]);
+    let out = stdout(&all);
+    assert!(out.contains("* Blog"), "{}", out);
+    assert!(out.contains("1 untracked"), "{}", out);
+    assert!(out.contains("[ok] Shop"), "{}", out);
+
+    let json = pctrl(&db, &["git", "status", "blog", "--json"]);
+    let value: serde_json::Value = serde_json::from_str(&stdout(&json)).unwrap();
+    assert_eq!(value[0]["status"]["untracked"], 1);
+    assert_eq!(value[0]["status"]["branch"], "main");
+}
+
+#[test]
+fn test_broken_repos_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let plain = dir.path().join("plain");
+    std::fs::create_dir(&plain).unwrap();
+
+    let add = pctrl(&db, &["git", "add", "Plain", "-p", plain.to_str().unwrap()]);
+    assert!(!add.status.success());
+    assert!(stdout(&pctrl(&db, &["git", "repos"])).contains("No Git repositories"));
+
+    let moved = dir.path().join("moved");
+    repo_with_commit(&moved);
+    let add = pctrl(&db, &["git", "add", "Moved", "-p", moved.to_str().unwrap()]);
+    assert!(add.status.success(), "{}", stderr(&add));
+    std::fs::remove_dir_all(&moved).unwrap();
+
+    let one = pctrl(&db, &["git", "status", "moved"]);
+    assert!(!one.status.success());
+    let all = pctrl(&db, &["git", "status", "--all"]);
+    assert!(all.status.success(), "{}", stderr(&all));
+    assert!(stdout(&all).contains("[!!] Moved"), "{}", stdout(&all));
+
+    let missing = pctrl(&db, &["git", "status", "nope"]);
+    assert!(stderr(&missing).contains("'nope' not found"));
+}
+
+#[test]
+fn test_push_branch_and_tags_to_origin() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let work = dir.path().join("work");
+    repo_with_commit(&work);
+    let origin = Repository::init_bare(dir.path().join("origin.git")).unwrap();
+    let repo = Repository::open(&work).unwrap();
+    repo.remote("origin", dir.path().join("origin.git").to_str().unwrap())
+        .unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    repo.tag("v1.0.0", head.as_object(), &author, "First", false)
+        .unwrap();
+
+    let add = pctrl(&db, &["git", "add", "Work", "-p", work.to_str().unwrap()]);
+    assert!(add.status.success(), "{}", stderr(&add));
+    let push = pctrl(&db, &["git", "push", "work"]);
+    assert!(push.status.success(), "{}", stderr(&push));
+    assert!(stdout(&push).contains("Pushed main"), "{}", stdout(&push));
+    assert!(origin.find_reference("refs/heads/main").is_ok());
+    assert!(origin.find_reference("refs/tags/v1.0.0").is_err());
+
+    let tags = pctrl(&db, &["git", "push", "work", "--tags"]);
+    assert!(tags.status.success(), "{}", stderr(&tags));
+    assert!(origin.find_reference("refs/tags/v1.0.0").is_ok());
+
+    let unknown = pctrl(
+        &db,
+        &[
+            "git",
+            "add",
+            "Work",
+            "-p",
+            work.to_str().unwrap(),
+            "-c",
+            "nope",
+        ],
+    );
+    assert!(!unknown.status.success());
+}
+
+fn commit(repo: &Repository, message: &str) -> git2::Oid {
+    let workdir = repo.workdir().unwrap();
+    std::fs::write(workdir.join("log.txt"), message).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("log.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.commit(Some("HEAD"), &author, &author, message, &tree, &[&parent])
+        .unwrap()
+}
+
+#[test]
+fn test_changelog_since_the_last_tag_and_release() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let work = dir.path().join("work");
+    repo_with_commit(&work);
+    let repo = Repository::open(&work).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    repo.tag("v1.0.0", head.as_object(), &author, "First", false)
+        .unwrap();
+    commit(&repo, "feat(cli): git changelog");
+    commit(&repo, "fix: empty repos");
+    commit(&repo, "Tidy up");
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Alice").unwrap();
+    config.set_str("user.email", "alice@example.com").unwrap();
+    let add = pctrl(&db, &["git", "add", "Work", "-p", work.to_str().unwrap()]);
+    assert!(add.status.success(), "{}", stderr(&add));
+
+    let log = pctrl(&db, &["git", "changelog", "work", "--conventional"]);
+    assert!(log.status.success(), "{}", stderr(&log));
+    let out = stdout(&log);
+    assert!(out.starts_with("## Unreleased ("), "{}", out);
+    assert!(
+        out.contains("### Features\n\n- **cli:** git changelog ("),
+        "{}",
+        out
+    );
+    assert!(out.contains("### Fixes\n\n- empty repos ("), "{}", out);
+    assert!(out.contains("### Other\n\n- Tidy up ("), "{}", out);
+    assert!(!out.contains("Initial commit"), "{}", out);
+
+    let all = stdout(&pctrl(
+        &db,
+        &["git", "changelog", "work", "--from", "HEAD~1"],
+    ));
+    assert!(all.contains("- Tidy up ("), "{}", all);
+    assert!(!all.contains("empty repos"), "{}", all);
+
+    let release = pctrl(&db, &["git", "release", "work", "v1.1.0", "--changelog"]);
+    assert!(release.status.success(), "{}", stderr(&release));
+    let tag = repo
+        .find_reference("refs/tags/v1.1.0")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+    let message = tag.message().unwrap();
+    assert!(message.starts_with("## v1.1.0 ("), "{}", message);
+    assert!(
+        message.contains("- feat(cli): git changelog ("),
+        "{}",
+        message
+    );
+
+    // Tagged now, HEAD's changelog is the release's
+    let tagged = stdout(&pctrl(&db, &["git", "changelog", "work"]));
+    assert!(tagged.starts_with("## v1.1.0 ("), "{}", tagged);
+
+    let saved = stdout(&pctrl(&db, &["git", "changelogs", "work"]));
+    assert!(saved.starts_with("## v1.1.0 ("), "{}", saved);
+    assert!(saved.contains("## Unreleased ("), "{}", saved);
+
+    let no_message = pctrl(&db, &["git", "release", "work", "v1.2.0"]);
+    assert!(!no_message.status.success());
+}
@@ -0,0 +1,64 @@
+//! `pctrl merge` against a second database file
+
+mod common;
+
+use common::{pctrl, run};
+use std::path::PathBuf;
+
+/// Two demo databases; the other one has a new server linked to the
+/// project and caps demo-web's containers
+fn two_dbs(dir: &tempfile::TempDir) -> (PathBuf, PathBuf) {
+    let local = dir.path().join("local.db");
+    let other = dir.path().join("other.db");
+    run(&local, &["debug", "seed-demo"]);
+    run(&other, &["debug", "seed-demo"]);
+    run(&other, &["server", "add", "extra", "203.0.113.9"]);
+    run(&other, &["project", "link", "Demo Shop", "server", "extra"]);
+    run(
+        &other,
+        &["server", "edit", "demo-web", "--max-containers", "4"],
+    );
+    (local, other)
+}
+
+#[test]
+fn test_merge_needs_a_preference_without_a_terminal() {
+    let dir = tempfile::tempdir().unwrap();
+    let (local, other) = two_dbs(&dir);
+    let other = other.to_str().unwrap();
+
+    // A dry run lists the conflict and writes nothing
+    let planned = run(&local, &["merge", other, "--dry-run"]);
+    assert!(planned.contains("server extra"), "{}", planned);
+    assert!(planned.contains("would import"));
+    assert!(planned.contains("conflict: max_containers"));
+    assert!(!run(&local, &["server", "list"]).contains("extra"));
+
+    let refused = pctrl(&local, &["merge", other]);
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--prefer"));
+}
+
+#[test]
+fn test_merge_imports_and_links() {
+    let dir = tempfile::tempdir().unwrap();
+    let (local, other) = two_dbs(&dir);
+    let other = other.to_str().unwrap();
+
+    let merged = run(&local, &["merge", other, "--prefer", "local"]);
+    assert!(merged.contains("imported"), "{}", merged);
+    assert!(merged.contains("kept this side's max_containers"));
+    assert!(merged.contains("link Demo Shop server extra"));
+    assert!(run(&local, &["project", "show", "Demo Shop"]).contains("extra"));
+
+    // Once merged, only the kept conflict is left
+    let again = run(&local, &["merge", other, "--prefer", "other"]);
+    assert!(
+        again.contains("took the other's max_containers"),
+        "{}",
+        again
+    );
+    assert!(!again.contains("imported"));
+    let last = run(&local, &["last-run", "merge", "--json"]);
+    assert!(last.contains("\"ok\": 1"), "{}", last);
+}
@@ -0,0 +1,81 @@
+//! `pctrl monitor run` alerting on disks projected to fill up
+
+use chrono::{Duration, Utc};
+use pctrl_core::{Server, ServerType};
+use pctrl_database::Database;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn pctrl(db: &Path, config: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env("XDG_CONFIG_HOME", config)
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .output()
+        .expect("pctrl runs")
+}
+
+#[tokio::test]
+async fn test_monitor_alerts_on_disk_filling_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("pctrl.db");
+    let db = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    db.save_server(&Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        // Nothing listens there; the probe fails fast
+        host: "127.0.0.1:1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    // +5% a day, 90% now: full in about two days
+    let now = Utc::now();
+    for day in 0..6 {
+        db.record_disk_sample_at(
+            "web-1",
+            65.0 + 5.0 * day as f64,
+            now - Duration::days(5 - day),
+        )
+        .await
+        .unwrap();
+    }
+    drop(db);
+
+    let config = dir.path().join("config");
+    let event_dir = config.join("pctrl/hooks/monitor.changed");
+    std::fs::create_dir_all(&event_dir).unwrap();
+    let out = dir.path().join("payload.json");
+    let hook = event_dir.join("capture");
+    std::fs::write(&hook, format!("#!/bin/sh\ncat > '{}'\n", out.display())).unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = pctrl(&db_path, &config, &["monitor", "run", "--once"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Disk full in < 30 days: web-1"),
+        "{:?}",
+        output
+    );
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    assert_eq!(payload["data"]["check"], "disk_forecast");
+    assert_eq!(payload["data"]["name"], "web-1");
+    assert_eq!(payload["data"]["within_days"], 30);
+}
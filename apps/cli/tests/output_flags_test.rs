@@ -0,0 +1,159 @@
+//! `--quiet`, `--no-color` and `NO_COLOR` across a representative set of
+//! commands, run against the real binary and a throwaway database
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Commands covering lists, details, empty states, the status screen and
+/// an error
+const COMMANDS: &[&[&str]] = &[
+    &["-m", "cli"],
+    &["status"],
+    &["project", "list"],
+    &["project", "show", "Demo Shop"],
+    &["project", "graph", "Demo Shop"],
+    &["server", "list"],
+    &["server", "show", "demo-web"],
+    &["server", "list", "--trashed"],
+    &["server", "deps", "demo-web"],
+    &["domain", "list"],
+    &["domain", "show", "shop.demo.example.com"],
+    &["database", "list"],
+    &["script", "list"],
+    &["credential", "list"],
+    &["snapshot", "list"],
+    &["lock", "list"],
+    &["audit", "list"],
+    &["monitor", "status"],
+    &["server", "show", "does-not-exist"],
+];
+
+fn pctrl(db: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env_remove("NO_COLOR")
+        .env("RUST_BACKTRACE", "0")
+        .env("TERM", "xterm-256color")
+        .envs(env.iter().copied())
+        .output()
+        .expect("pctrl runs")
+}
+
+fn seeded_db(dir: &tempfile::TempDir) -> std::path::PathBuf {
+    let db = dir.path().join("pctrl.db");
+    let seeded = pctrl(&db, &["debug", "seed-demo"], &[]);
+    assert!(seeded.status.success(), "{:?}", seeded);
+    db
+}
+
+fn text(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+fn assert_plain(args: &[&str], output: &Output) {
+    for (stream, bytes) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        if let Some(pos) = bytes.iter().position(|b| *b == 0x1b || !b.is_ascii()) {
+            panic!(
+                "pctrl {}: {} has {:#04x} at byte {}:\n{}",
+                args.join(" "),
+                stream,
+                bytes[pos],
+                pos,
+                String::from_utf8_lossy(bytes)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_no_color_output_is_plain_ascii() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = seeded_db(&dir);
+
+    for (i, args) in COMMANDS.iter().enumerate() {
+        let with_flag: Vec<&str> = [&["--no-color"], *args].concat();
+        let output = pctrl(&db, &with_flag, &[]);
+        // Only the last command is meant to fail
+        assert_eq!(
+            output.status.success(),
+            i + 1 < COMMANDS.len(),
+            "pctrl {}:\n{}",
+            with_flag.join(" "),
+            text(&output)
+        );
+        assert_plain(&with_flag, &output);
+        assert_plain(args, &pctrl(&db, args, &[("NO_COLOR", "1")]));
+        assert_plain(
+            &with_flag,
+            &pctrl(&db, &[&with_flag[..], &["--quiet"]].concat(), &[]),
+        );
+    }
+
+    // Symbols fall back to ASCII markers instead of disappearing
+    let show = text(&pctrl(
+        &db,
+        &["--no-color", "project", "show", "Demo Shop"],
+        &[],
+    ));
+    assert!(show.contains("demo-web (demo-server-web) -> demo-link-web"));
+
+    // An empty NO_COLOR doesn't count
+    let styled = pctrl(&db, &["project", "list"], &[("NO_COLOR", "")]);
+    assert!(!styled.stdout.is_ascii());
+}
+
+#[test]
+fn test_quiet_keeps_results_and_errors_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = seeded_db(&dir);
+
+    let status = text(&pctrl(&db, &["-q", "-m", "cli"], &[]));
+    assert!(!status.contains("Mission Control"));
+    assert!(!status.contains("pctrl --help"));
+    assert!(status.contains("Projects"));
+
+    let empty = text(&pctrl(&db, &["--quiet", "database", "list"], &[]));
+    assert!(empty.contains("No database credentials configured."));
+    assert!(!empty.contains("Add one with"));
+
+    let added = pctrl(&db, &["-q", "project", "add", "blog"], &[]);
+    assert!(added.status.success());
+    assert_eq!(text(&added), "");
+    assert!(text(&pctrl(&db, &["-q", "project", "list"], &[])).contains("blog"));
+
+    let failed = pctrl(&db, &["-q", "server", "show", "does-not-exist"], &[]);
+    assert!(!failed.status.success());
+    assert!(text(&failed).contains("Server 'does-not-exist' not found"));
+
+    // Without --quiet the hints are there
+    let hinted = text(&pctrl(&db, &["database", "list"], &[]));
+    assert!(hinted.contains("Add one with"));
+}
+
+#[test]
+fn test_hints_follow_the_setting() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = seeded_db(&dir);
+
+    // The demo has one project and no credentials
+    let added = text(&pctrl(&db, &["server", "add", "extra", "203.0.113.9"], &[]));
+    assert!(added.contains("Add an SSH credential with: pctrl credential add"));
+    assert!(added.contains("pctrl project link 'Demo Shop' server extra"));
+
+    let set = pctrl(&db, &["config", "set", "hints", "off"], &[]);
+    assert!(set.status.success(), "{:?}", set);
+    let added = text(&pctrl(
+        &db,
+        &["server", "add", "other", "203.0.113.10"],
+        &[],
+    ));
+    assert!(added.contains("Server added"));
+    assert!(!added.contains("pctrl credential add"));
+    assert!(!text(&pctrl(&db, &["database", "list"], &[])).contains("Add one with"));
+}
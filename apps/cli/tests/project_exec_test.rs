@@ -0,0 +1,73 @@
+//! `pctrl project exec` choosing its servers (nothing here reaches SSH)
+
+mod common;
+
+use common::{pctrl, stderr};
+
+#[test]
+fn test_exec_needs_an_unambiguous_linked_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    assert!(pctrl(&db, &["debug", "seed-demo"]).status.success());
+
+    // Two linked servers, neither linked as production_server
+    let output = pctrl(&db, &["project", "exec", "Demo Shop", "--", "uptime"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("several servers"), "{:?}", output);
+
+    let output = pctrl(
+        &db,
+        &[
+            "project",
+            "exec",
+            "Demo Shop",
+            "--server",
+            "nope",
+            "--",
+            "uptime",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("linked: demo-db, demo-web"),
+        "{:?}",
+        output
+    );
+
+    // The demo project is Live; without a terminal that needs --allow-live
+    let output = pctrl(
+        &db,
+        &["project", "exec", "Demo Shop", "--all", "--", "uptime"],
+    );
+    assert!(stderr(&output).contains("--allow-live"), "{:?}", output);
+}
+
+#[test]
+fn test_all_reports_every_server_that_failed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    assert!(pctrl(&db, &["debug", "seed-demo"]).status.success());
+
+    // Demo servers have no credential, so the command runs nowhere
+    let output = pctrl(
+        &db,
+        &[
+            "project",
+            "exec",
+            "Demo Shop",
+            "--all",
+            "--allow-live",
+            "--",
+            "uptime",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(255));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("demo-db | Server 'demo-db'"), "{}", stdout);
+    assert!(
+        stdout.contains("demo-web | Server 'demo-web'"),
+        "{}",
+        stdout
+    );
+    assert!(stderr(&output).contains("Failed on 2 of 2 servers"));
+}
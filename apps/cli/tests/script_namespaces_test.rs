@@ -0,0 +1,95 @@
+//! Namespaced script names: the tree listing, prefix filters and suffix lookup
+
+mod common;
+
+use common::{pctrl, stderr, stdout};
+
+fn with_scripts(names: &[&str]) -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    for name in names {
+        let command = format!("echo {}", name);
+        let add = pctrl(&db, &["script", "add", name, "-c", &command]);
+        assert!(add.status.success(), "{:?}", add);
+    }
+    (dir, db)
+}
+
+#[test]
+fn test_list_shows_a_tree_and_filters_by_namespace() {
+    let (_dir, db) = with_scripts(&[
+        "backups/postgres-daily",
+        "backups/db/mysql",
+        "deploy",
+        "monitoring/uptime",
+    ]);
+
+    let text = stdout(&pctrl(&db, &["script", "list"]));
+    let backups = text.find("backups/ (2)").expect(&text);
+    let mysql = text.find("    mysql").expect(&text);
+    let deploy = text.find("deploy").expect(&text);
+    assert!(backups < mysql && mysql < deploy, "{}", text);
+
+    let text = stdout(&pctrl(&db, &["script", "list", "--flat"]));
+    assert!(text.contains("backups/db/mysql"), "{}", text);
+    assert!(!text.contains("backups/ (2)"), "{}", text);
+
+    let text = stdout(&pctrl(&db, &["script", "list", "backups/"]));
+    assert!(text.contains("postgres-daily"), "{}", text);
+    assert!(text.contains("mysql"), "{}", text);
+    assert!(!text.contains("deploy"), "{}", text);
+    assert!(!text.contains("uptime"), "{}", text);
+
+    let output = pctrl(&db, &["script", "list", "back"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(
+        stdout(&output).contains("No scripts in back/."),
+        "{:?}",
+        output
+    );
+}
+
+#[test]
+fn test_scripts_resolve_by_a_unique_suffix() {
+    let (_dir, db) = with_scripts(&["backups/postgres-daily", "staging/postgres-daily", "deploy"]);
+
+    let output = pctrl(&db, &["script", "show", "postgres-daily"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("backups/postgres-daily, staging/postgres-daily"),
+        "{:?}",
+        output
+    );
+
+    let output = pctrl(&db, &["script", "run", "staging/postgres-daily"]);
+    assert!(
+        stdout(&output).contains("echo staging/postgres-daily"),
+        "{:?}",
+        output
+    );
+
+    assert!(pctrl(&db, &["script", "remove", "staging/postgres-daily"])
+        .status
+        .success());
+    let output = pctrl(&db, &["script", "show", "POSTGRES-DAILY"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(
+        stdout(&output).contains("backups/postgres-daily"),
+        "{:?}",
+        output
+    );
+
+    // Suffixes match whole segments only
+    assert!(!pctrl(&db, &["script", "show", "daily"]).status.success());
+}
+
+#[test]
+fn test_malformed_namespaces_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    for name in ["/backups", "backups/", "backups//daily"] {
+        let output = pctrl(&db, &["script", "add", name, "-c", "true"]);
+        assert!(!output.status.success(), "accepted '{}'", name);
+    }
+    assert!(stdout(&pctrl(&db, &["script", "list"])).contains("No scripts"));
+}
@@ -0,0 +1,87 @@
+//! `pctrl script run` holding dangerous scripts to their run window
+
+mod common;
+
+use chrono::{Datelike, Duration, Utc};
+use common::{pctrl, stderr, stdout};
+
+#[test]
+fn test_dangerous_script_runs_only_inside_its_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let add = pctrl(
+        &db,
+        &[
+            "script",
+            "add",
+            "wipe",
+            "-c",
+            "echo wiped",
+            "-t",
+            "local",
+            "--dangerous",
+        ],
+    );
+    assert!(add.status.success(), "{:?}", add);
+
+    // Two days from now in UTC is never today
+    let closed_day = (Utc::now() + Duration::days(2)).weekday().to_string();
+    let closed = format!("{} UTC", closed_day);
+    assert!(
+        pctrl(&db, &["script", "edit", "wipe", "--run-window", &closed])
+            .status
+            .success()
+    );
+    let show = pctrl(&db, &["script", "show", "wipe"]);
+    assert!(stdout(&show).contains("closed, opens"), "{:?}", show);
+
+    let output = pctrl(&db, &["script", "run", "wipe", "--force"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains(&format!("may only run {} UTC", closed_day)),
+        "{:?}",
+        output
+    );
+    assert!(
+        stderr(&output).contains("--override-window"),
+        "{:?}",
+        output
+    );
+
+    // Overriding asks for the name, which needs a terminal
+    let output = pctrl(
+        &db,
+        &["script", "run", "wipe", "--force", "--override-window"],
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("terminal"), "{:?}", output);
+
+    assert!(pctrl(
+        &db,
+        &["script", "edit", "wipe", "--run-window", "Mon-Sun UTC"]
+    )
+    .status
+    .success());
+    let output = pctrl(&db, &["script", "run", "wipe", "--force"]);
+    assert!(stdout(&output).contains("wiped"), "{:?}", output);
+}
+
+#[test]
+fn test_invalid_windows_are_rejected_when_set() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    assert!(
+        pctrl(&db, &["script", "add", "wipe", "-c", "true", "--dangerous"])
+            .status
+            .success()
+    );
+
+    let output = pctrl(
+        &db,
+        &["script", "edit", "wipe", "--run-window", "Fri 22:00-02:00"],
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("past midnight"), "{:?}", output);
+    let show = pctrl(&db, &["script", "show", "wipe"]);
+    assert!(!stdout(&show).contains("Window:"), "{:?}", show);
+}
@@ -0,0 +1,59 @@
+//! `pctrl shell` with piped input: commands run and history stays private
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn shell(db: &Path, input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pctrl"))
+        .arg("--db")
+        .arg(db)
+        .arg("shell")
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("pctrl runs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_history_redacts_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+
+    let output = shell(
+        &db,
+        "credential add api -t api --token hunter2 --url https://api.example.com\n\
+         credential list\n\
+         exit\n",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("api"), "{}", stdout);
+
+    let history = dir.path().join("shell_history");
+    let text = std::fs::read_to_string(&history).unwrap();
+    assert!(!text.contains("hunter2"), "{}", text);
+    assert!(text.contains("--token '***'"), "{}", text);
+    assert!(text.contains("credential list"), "{}", text);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&history).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,160 @@
+//! `pctrl snapshot restore` writing through the import path
+
+mod common;
+
+use common::pctrl;
+use pctrl_core::snapshot::diff_inventories;
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType, Project,
+    ProjectResource, ProjectStatus, ResourceType, Server, ServerType,
+};
+use pctrl_database::Database;
+use std::path::Path;
+
+async fn open_db(path: &Path) -> Database {
+    Database::new(path.to_str().unwrap(), None).await.unwrap()
+}
+
+async fn seed(db: &Database) {
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec!["rust".to_string()],
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_server(&Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    db.save_database_credentials(&DatabaseCredentials {
+        id: "main".to_string(),
+        name: "main".to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: Some("db.internal".to_string()),
+        port: Some(5432),
+        database_name: Some("app".to_string()),
+        username: Some("app".to_string()),
+        password: Some("hunter2".to_string()),
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_credential(&Credential {
+        id: "deploy".to_string(),
+        name: "deploy".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "tok-secret".to_string(),
+            url: None,
+        },
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.link_project_resource(&ProjectResource {
+        id: "l1".to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: "web-1".to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_restore_round_trip_keeps_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = open_db(&path).await;
+    seed(&db).await;
+    db.create_snapshot("before").await.unwrap();
+
+    // Reorganize
+    let mut project = db.get_project("shop").await.unwrap().unwrap();
+    project.status = ProjectStatus::Live;
+    db.save_project(&project).await.unwrap();
+    db.trash_server("web-1").await.unwrap();
+    db.unlink_project_resource("l1").await.unwrap();
+    let mut database = db.get_database_credentials("main").await.unwrap().unwrap();
+    database.host = Some("db2.internal".to_string());
+    database.password = Some("new-password".to_string());
+    db.save_database_credentials(&database).await.unwrap();
+    drop(db);
+
+    // Without a terminal a restore needs --yes
+    let output = pctrl(&path, &["snapshot", "restore", "before"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("--yes"),
+        "{:?}",
+        output
+    );
+
+    let output = pctrl(&path, &["snapshot", "restore", "before", "--yes"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Restored 'before'"),
+        "{:?}",
+        output
+    );
+
+    let db = open_db(&path).await;
+    let before = db.load_snapshot("before").await.unwrap().unwrap();
+    assert!(diff_inventories(&before, &db.current_inventory().await.unwrap()).is_empty());
+
+    // The current password survives, the host is restored
+    let database = db.get_database_credentials("main").await.unwrap().unwrap();
+    assert_eq!(database.host.as_deref(), Some("db.internal"));
+    assert_eq!(database.password.as_deref(), Some("new-password"));
+
+    // The restore is a recorded run like an import
+    let output = pctrl(&path, &["last-run", "snapshot", "restore"]);
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_restore_never_removes_credentials() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = open_db(&path).await;
+    db.create_snapshot("empty").await.unwrap();
+    seed(&db).await;
+    drop(db);
+
+    let output = pctrl(&path, &["snapshot", "restore", "empty", "--yes"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let db = open_db(&path).await;
+    let inventory = db.current_inventory().await.unwrap();
+    assert!(inventory.projects.is_empty());
+    assert!(inventory.servers.is_empty());
+    assert!(inventory.databases.is_empty());
+    assert!(inventory.links.is_empty());
+    assert_eq!(inventory.credentials.len(), 1);
+    // Trashed, not deleted
+    assert_eq!(db.list_trashed_servers().await.unwrap().len(), 1);
+}
@@ -0,0 +1,79 @@
+//! `ssh put/get` refusals that happen before a connection is made
+
+mod common;
+
+use common::{pctrl, stderr};
+
+#[test]
+fn test_put_and_get_refusals() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let add = pctrl(&db, &["server", "add", "web-1", "198.51.100.7"]);
+    assert!(add.status.success(), "{}", stderr(&add));
+
+    let missing = dir.path().join("missing.conf");
+    let put = pctrl(
+        &db,
+        &[
+            "ssh",
+            "put",
+            "web-1",
+            missing.to_str().unwrap(),
+            "/etc/app/",
+        ],
+    );
+    assert!(!put.status.success());
+    assert!(stderr(&put).contains("doesn't exist"), "{}", stderr(&put));
+
+    // An existing file is only replaced with --force
+    let existing = dir.path().join("app.conf");
+    std::fs::write(&existing, "local").unwrap();
+    let get = pctrl(
+        &db,
+        &[
+            "ssh",
+            "get",
+            "web-1",
+            "/etc/app/app.conf",
+            dir.path().to_str().unwrap(),
+        ],
+    );
+    assert!(!get.status.success());
+    assert!(
+        stderr(&get).contains("--force replaces it"),
+        "{}",
+        stderr(&get)
+    );
+    assert_eq!(std::fs::read_to_string(&existing).unwrap(), "local");
+
+    let bad_mode = pctrl(
+        &db,
+        &[
+            "ssh",
+            "put",
+            "web-1",
+            existing.to_str().unwrap(),
+            "/etc/app/",
+            "--mode",
+            "rw-r--r--",
+        ],
+    );
+    assert!(!bad_mode.status.success());
+    assert!(
+        stderr(&bad_mode).contains("not octal"),
+        "{}",
+        stderr(&bad_mode)
+    );
+
+    let unknown = pctrl(
+        &db,
+        &[
+            "ssh",
+            "get",
+            "nope",
+            "/etc/hosts",
+            dir.path().to_str().unwrap(),
+        ],
+    );
+    assert!(!unknown.status.success());
+}
@@ -1,15 +1,21 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use pctrl_core::search::{Entity, SearchHit};
+use pctrl_core::transfer::Progress;
 use pctrl_core::{
-    AuthMethod, Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType,
-    Domain, DomainType, Project, ProjectStatus, Script, ScriptType, Server, ServerType,
-    SshConnection,
+    current_holder, Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType,
+    Domain, DomainType, EntityType, Project, ProjectStatus, Script, ScriptType, ScriptUpdate,
+    Server, ServerType, SshConnection,
 };
 use pctrl_database::Database;
-use pctrl_ssh::SshManager;
+use pctrl_docker::{ContainerStats, DockerManager, LogOptions};
+use pctrl_ssh::{CopyOptions, SshManager};
 use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::State;
 use tokio::sync::Mutex;
 use uuid::Uuid;
@@ -72,6 +78,45 @@ pub struct ScriptDto {
     pub description: Option<String>,
 }
 
+/// Fields of an edit dialog; unset fields stay as they are, an empty
+/// string clears an optional one
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectUpdateDto {
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub stack: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServerUpdateDto {
+    pub host: Option<String>,
+    pub provider: Option<String>,
+    pub location: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DomainUpdateDto {
+    pub domain_type: Option<String>,
+    pub server_id: Option<String>,
+    pub ssl: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DatabaseUpdateDto {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScriptUpdateDto {
+    pub command: Option<String>,
+    pub dangerous: Option<bool>,
+    pub server_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CredentialDto {
     pub id: Option<String>,
@@ -82,6 +127,22 @@ pub struct CredentialDto {
     pub key_path: Option<String>,
 }
 
+/// A finished `upload_file`/`download_file`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileTransferDto {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Payload of the `file-transfer-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTransferProgressDto {
+    /// Destination of the transfer
+    pub path: String,
+    pub done: u64,
+    pub total: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServerStatusDto {
     pub online: bool,
@@ -117,6 +178,11 @@ fn get_db_path() -> String {
     }
 }
 
+/// An edit dialog's value for an optional field: empty clears it
+fn clearable(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.trim().is_empty())
+}
+
 async fn ensure_db(state: &State<'_, AppState>) -> Result<(), String> {
     let mut db_guard = state.db.lock().await;
     if db_guard.is_none() {
@@ -164,7 +230,48 @@ async fn add_project(state: State<'_, AppState>, data: ProjectDto) -> Result<Pro
         notes: None,
     };
 
-    db.save_project(&project).await.map_err(|e| e.to_string())?;
+    db.create_project(&project)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(project)
+}
+
+#[tauri::command]
+async fn update_project(
+    state: State<'_, AppState>,
+    id: String,
+    data: ProjectUpdateDto,
+) -> Result<Project, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut project = db
+        .get_project(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Project not found")?;
+    if let Some(status) = data.status {
+        let status: ProjectStatus = status.parse()?;
+        if status != project.status
+            && (status == ProjectStatus::Maintenance
+                || project.status == ProjectStatus::Maintenance)
+        {
+            return Err("Maintenance is started and ended with pctrl project maintenance".into());
+        }
+        project.status = status;
+    }
+    if let Some(description) = data.description {
+        project.description = clearable(description);
+    }
+    if let Some(stack) = data.stack {
+        project.stack = stack.into_iter().filter_map(clearable).collect();
+    }
+
+    db.update_project(&project)
+        .await
+        .map_err(|e| e.to_string())?;
 
     Ok(project)
 }
@@ -214,9 +321,45 @@ async fn add_server(
         location: None,
         specs: None,
         notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
     };
 
-    db.save_server(&server).await.map_err(|e| e.to_string())?;
+    db.create_server(&server).await.map_err(|e| e.to_string())?;
+
+    Ok(server)
+}
+
+#[tauri::command]
+async fn update_server(
+    state: State<'_, AppState>,
+    id: String,
+    data: ServerUpdateDto,
+) -> Result<Server, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut server = db
+        .get_server(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Server not found")?;
+    if let Some(host) = data.host {
+        server.host = clearable(host).ok_or("The host is empty")?;
+    }
+    if let Some(provider) = data.provider {
+        server.provider = clearable(provider);
+    }
+    if let Some(location) = data.location {
+        server.location = clearable(location);
+    }
+    if let Some(notes) = data.notes {
+        server.notes = clearable(notes);
+    }
+
+    db.update_server(&server).await.map_err(|e| e.to_string())?;
 
     Ok(server)
 }
@@ -264,9 +407,40 @@ async fn add_domain(state: State<'_, AppState>, data: DomainDto) -> Result<Domai
         server_id: None,
         container_id: None,
         notes: None,
+        superseded_by: None,
     };
 
-    db.save_domain(&domain).await.map_err(|e| e.to_string())?;
+    db.create_domain(&domain).await.map_err(|e| e.to_string())?;
+
+    Ok(domain)
+}
+
+#[tauri::command]
+async fn update_domain(
+    state: State<'_, AppState>,
+    id: String,
+    data: DomainUpdateDto,
+) -> Result<Domain, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut domain = db
+        .get_domain(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Domain not found")?;
+    if let Some(domain_type) = data.domain_type {
+        domain.domain_type = domain_type.parse()?;
+    }
+    if let Some(server_id) = data.server_id {
+        domain.server_id = clearable(server_id);
+    }
+    if let Some(ssl) = data.ssl {
+        domain.ssl = ssl;
+    }
+
+    db.update_domain(&domain).await.map_err(|e| e.to_string())?;
 
     Ok(domain)
 }
@@ -323,7 +497,42 @@ async fn add_database(
         notes: None,
     };
 
-    db.save_database_credentials(&database)
+    db.create_database_credentials(&database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(database)
+}
+
+#[tauri::command]
+async fn update_database(
+    state: State<'_, AppState>,
+    id: String,
+    data: DatabaseUpdateDto,
+) -> Result<DatabaseCredentials, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut database = db
+        .get_database_credentials(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Database not found")?;
+    if let Some(host) = data.host {
+        database.host = clearable(host);
+    }
+    if let Some(port) = data.port {
+        database.port = Some(port);
+    }
+    if let Some(username) = data.username {
+        database.username = clearable(username);
+    }
+    if let Some(password) = data.password {
+        database.password = Some(password).filter(|p| !p.is_empty());
+    }
+
+    db.update_database_credentials(&database)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -357,6 +566,7 @@ async fn add_script(state: State<'_, AppState>, data: ScriptDto) -> Result<Scrip
     ensure_db(&state).await?;
     let db_guard = state.db.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    pctrl_core::script_namespace::validate_name(&data.name)?;
 
     let script_type: ScriptType = data
         .script_type
@@ -379,13 +589,57 @@ async fn add_script(state: State<'_, AppState>, data: ScriptDto) -> Result<Scrip
         last_result: None,
         exit_code: None,
         last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
     };
 
-    db.save_script(&script).await.map_err(|e| e.to_string())?;
+    db.create_script(&script).await.map_err(|e| e.to_string())?;
 
     Ok(script)
 }
 
+/// Command and dangerous changes of a dangerous script may need approval
+/// (`pctrl script approve`); that is reported as an error
+#[tauri::command]
+async fn update_script(
+    state: State<'_, AppState>,
+    id: String,
+    data: ScriptUpdateDto,
+) -> Result<Script, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut script = db
+        .get_script(&id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Script not found")?;
+    if let Some(server_id) = data.server_id {
+        script.server_id = clearable(server_id);
+        db.update_script(&script).await.map_err(|e| e.to_string())?;
+    }
+    if data.command.is_none() && data.dangerous.is_none() {
+        return Ok(script);
+    }
+
+    let command = data.command.unwrap_or_else(|| script.command.clone());
+    let dangerous = data.dangerous.unwrap_or(script.dangerous);
+    match db
+        .update_script_command(&script.id, &command, dangerous, &current_holder())
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        ScriptUpdate::Applied(script) => Ok(script),
+        ScriptUpdate::Unchanged => Ok(script),
+        ScriptUpdate::Pending(revision) => Err(format!(
+            "'{}' is dangerous; the change was saved as revision #{} and needs approval",
+            script.name, revision.id
+        )),
+    }
+}
+
 #[tauri::command]
 async fn delete_script(state: State<'_, AppState>, id: String) -> Result<bool, String> {
     ensure_db(&state).await?;
@@ -446,7 +700,7 @@ async fn add_credential(
         notes: None,
     };
 
-    db.save_credential(&credential)
+    db.create_credential(&credential)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -504,22 +758,13 @@ async fn get_server_status(
         .ok_or("Credential not found")?;
 
     // Create SSH connection
-    let (username, port, auth_method) = match &credential.data {
-        CredentialData::SshKey {
-            username,
-            port,
-            key_path,
-            passphrase,
-        } => (
-            username.clone(),
-            *port,
-            AuthMethod::Key {
-                path: key_path.clone(),
-                passphrase: passphrase.clone(),
-            },
-        ),
-        CredentialData::SshAgent { username, port } => (username.clone(), *port, AuthMethod::Agent),
-        _ => {
+    let key_material = db
+        .get_key_material(&credential.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (username, port, auth_method) = match credential.ssh_login(key_material) {
+        Some(login) => login,
+        None => {
             return Ok(ServerStatusDto {
                 online: false,
                 uptime: None,
@@ -607,65 +852,278 @@ async fn exec_server_command(
     let db_guard = state.db.lock().await;
     let db = db_guard.as_ref().ok_or("Database not initialized")?;
 
-    // Get server
+    let (ssh_manager, conn_id) = server_ssh(db, &server_id).await?;
+
+    // Execute command
+    let output =
+        tokio::task::spawn_blocking(move || ssh_manager.execute_command(&conn_id, &command))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+    Ok(output)
+}
+
+/// An SshManager for a server, through its SSH credential
+async fn server_ssh(db: &Database, server_id: &str) -> Result<(SshManager, String), String> {
     let server = db
-        .get_server(&server_id)
+        .get_server(server_id)
         .await
         .map_err(|e| e.to_string())?
         .ok_or("Server not found")?;
-
     let cred_id = server
         .credential_id
         .as_ref()
         .ok_or("No credential configured")?;
-
-    // Get credential
     let credential = db
         .get_credential(cred_id)
         .await
         .map_err(|e| e.to_string())?
         .ok_or("Credential not found")?;
+    let key_material = db
+        .get_key_material(&credential.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (username, port, auth_method) = credential
+        .ssh_login(key_material)
+        .ok_or("Credential is not SSH type")?;
 
-    // Create SSH connection
-    let (username, port, auth_method) = match &credential.data {
-        CredentialData::SshKey {
-            username,
-            port,
-            key_path,
-            passphrase,
-        } => (
-            username.clone(),
-            *port,
-            AuthMethod::Key {
-                path: key_path.clone(),
-                passphrase: passphrase.clone(),
-            },
-        ),
-        CredentialData::SshAgent { username, port } => (username.clone(), *port, AuthMethod::Agent),
-        _ => return Err("Credential is not SSH type".to_string()),
-    };
-
-    let ssh_conn = SshConnection {
+    let mut ssh_manager = SshManager::new();
+    ssh_manager.add_connection(SshConnection {
         id: credential.id.clone(),
         name: credential.name.clone(),
-        host: server.host.clone(),
+        host: server.host,
         port,
         username,
         auth_method,
+    });
+    Ok((ssh_manager, credential.id))
+}
+
+/// Sends a transfer's progress to `window` as `file-transfer-progress`
+/// events, at most every 100ms and once at the end
+fn progress_events<'a>(
+    window: &'a tauri::Window,
+    path: &'a str,
+) -> impl FnMut(&Progress) -> ControlFlow<()> + 'a {
+    let mut sent: Option<Instant> = None;
+    move |progress| {
+        let due = sent.is_none_or(|at| at.elapsed() >= Duration::from_millis(100));
+        if due || progress.done == progress.total {
+            let payload = FileTransferProgressDto {
+                path: path.to_string(),
+                done: progress.done,
+                total: progress.total,
+            };
+            let _ = window.emit("file-transfer-progress", payload);
+            sent = Some(Instant::now());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Upload a file, or a directory when `recursive`, to a server over SFTP.
+/// Files get `mode` or keep their permissions; existing files are only
+/// replaced when `overwrite`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn upload_file(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    server_id: String,
+    local_path: String,
+    remote_path: String,
+    mode: Option<i32>,
+    recursive: bool,
+    overwrite: bool,
+) -> Result<FileTransferDto, String> {
+    ensure_db(&state).await?;
+    let (ssh_manager, conn_id) = {
+        let db_guard = state.db.lock().await;
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        server_ssh(db, &server_id).await?
     };
 
-    let mut ssh_manager = SshManager::new();
-    ssh_manager.add_connection(ssh_conn);
-    let conn_id = credential.id.clone();
+    let options = CopyOptions {
+        recursive,
+        overwrite,
+    };
+    let report = tokio::task::spawn_blocking(move || {
+        let mut on_progress = progress_events(&window, &remote_path);
+        ssh_manager.upload_file(
+            &conn_id,
+            Path::new(&local_path),
+            &remote_path,
+            mode,
+            options,
+            &mut on_progress,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
 
-    // Execute command
-    let output =
-        tokio::task::spawn_blocking(move || ssh_manager.execute_command(&conn_id, &command))
-            .await
-            .map_err(|e| e.to_string())?
-            .map_err(|e| e.to_string())?;
+    Ok(FileTransferDto {
+        files: report.files,
+        bytes: report.bytes,
+    })
+}
 
-    Ok(output)
+/// Download a file, or a directory when `recursive`, from a server over
+/// SFTP, keeping its permissions
+#[tauri::command]
+async fn download_file(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    server_id: String,
+    remote_path: String,
+    local_path: String,
+    recursive: bool,
+    overwrite: bool,
+) -> Result<FileTransferDto, String> {
+    ensure_db(&state).await?;
+    let (ssh_manager, conn_id) = {
+        let db_guard = state.db.lock().await;
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        server_ssh(db, &server_id).await?
+    };
+
+    let options = CopyOptions {
+        recursive,
+        overwrite,
+    };
+    let report = tokio::task::spawn_blocking(move || {
+        let mut on_progress = progress_events(&window, &local_path);
+        ssh_manager.download_file(
+            &conn_id,
+            &remote_path,
+            Path::new(&local_path),
+            options,
+            &mut on_progress,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    Ok(FileTransferDto {
+        files: report.files,
+        bytes: report.bytes,
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Docker Container Commands
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A DockerManager connected to one configured Docker host
+async fn docker_host(state: &State<'_, AppState>, host_id: &str) -> Result<DockerManager, String> {
+    ensure_db(state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let host = db
+        .load_config()
+        .await
+        .map_err(|e| e.to_string())?
+        .docker_hosts
+        .into_iter()
+        .find(|h| h.id == host_id)
+        .ok_or("Docker host not found")?;
+
+    let mut docker = DockerManager::new();
+    docker.add_host(host).map_err(|e| e.to_string())?;
+    Ok(docker)
+}
+
+#[tauri::command]
+async fn restart_container(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+) -> Result<(), String> {
+    docker_host(&state, &host_id)
+        .await?
+        .restart_container(&host_id, &container_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_container(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+    force: bool,
+) -> Result<(), String> {
+    docker_host(&state, &host_id)
+        .await?
+        .remove_container(&host_id, &container_id, force)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn pause_container(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+) -> Result<(), String> {
+    docker_host(&state, &host_id)
+        .await?
+        .pause_container(&host_id, &container_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn unpause_container(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+) -> Result<(), String> {
+    docker_host(&state, &host_id)
+        .await?
+        .unpause_container(&host_id, &container_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Last `tail` lines of a container's logs; stderr lines start with
+/// `stderr | `
+#[tauri::command]
+async fn get_container_logs(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+    tail: Option<u32>,
+) -> Result<Vec<String>, String> {
+    docker_host(&state, &host_id)
+        .await?
+        .container_log_lines(
+            &host_id,
+            &container_id,
+            &LogOptions {
+                tail: Some(tail.unwrap_or(200)),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Current CPU, memory, network and block I/O usage of a container
+#[tauri::command]
+async fn get_container_stats(
+    state: State<'_, AppState>,
+    host_id: String,
+    container_id: String,
+) -> Result<ContainerStats, String> {
+    docker_host(&state, &host_id)
+        .await?
+        .container_stats(&host_id, &container_id)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -761,23 +1219,13 @@ async fn test_credential_connection(
         .ok_or("Credential not found")?;
 
     // Create SSH connection
-    let (username, port, auth_method) = match &credential.data {
-        CredentialData::SshKey {
-            username,
-            port,
-            key_path,
-            passphrase,
-        } => (
-            username.clone(),
-            *port,
-            AuthMethod::Key {
-                path: key_path.clone(),
-                passphrase: passphrase.clone(),
-            },
-        ),
-        CredentialData::SshAgent { username, port } => (username.clone(), *port, AuthMethod::Agent),
-        _ => return Err("Credential is not SSH type".to_string()),
-    };
+    let key_material = db
+        .get_key_material(&credential.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (username, port, auth_method) = credential
+        .ssh_login(key_material)
+        .ok_or("Credential is not SSH type")?;
 
     let ssh_conn = SshConnection {
         id: credential.id.clone(),
@@ -805,6 +1253,82 @@ async fn test_credential_connection(
     result
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Search Commands
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Ranked entities matching `query`, for the command palette
+#[tauri::command]
+async fn global_search(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.global_search(&query, limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// One entity by type and ID (or name), secrets redacted; for deep links
+#[tauri::command]
+async fn get_entity(
+    state: State<'_, AppState>,
+    entity_type: String,
+    id: String,
+) -> Result<Option<Entity>, String> {
+    let entity_type: EntityType = entity_type.parse()?;
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.get_entity(entity_type, &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Onboarding Commands
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Add the demo project with its servers, domains, script and links;
+/// returns the number of rows added (0 if the demo data is already there)
+#[tauri::command]
+async fn seed_demo_data(state: State<'_, AppState>) -> Result<usize, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.seed_demo_data().await.map_err(|e| e.to_string())
+}
+
+/// Remove exactly the demo rows; returns the number removed
+#[tauri::command]
+async fn clear_demo_data(state: State<'_, AppState>) -> Result<usize, String> {
+    ensure_db(&state).await?;
+    let db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.clear_demo_data().await.map_err(|e| e.to_string())
+}
+
+/// Delete everything; `confirm` must be "RESET"
+#[tauri::command]
+async fn reset_database(state: State<'_, AppState>, confirm: String) -> Result<(), String> {
+    ensure_db(&state).await?;
+    let mut db_guard = state.db.lock().await;
+    let db = db_guard.as_ref().ok_or("Database not initialized")?;
+    db.reset_database(&confirm)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Start over with a fresh pool; the next command reopens it
+    if let Some(db) = db_guard.take() {
+        db.close().await;
+    }
+    drop(db_guard);
+    ensure_db(&state).await
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Main
 // ─────────────────────────────────────────────────────────────────────────────
@@ -818,18 +1342,23 @@ fn main() {
             // v6 Commands
             list_projects,
             add_project,
+            update_project,
             delete_project,
             list_servers,
             add_server,
+            update_server,
             delete_server,
             list_domains,
             add_domain,
+            update_domain,
             delete_domain,
             list_databases,
             add_database,
+            update_database,
             delete_database,
             list_scripts,
             add_script,
+            update_script,
             delete_script,
             // Credential & SSH Commands
             list_credentials,
@@ -837,8 +1366,24 @@ fn main() {
             delete_credential,
             get_server_status,
             exec_server_command,
+            upload_file,
+            download_file,
             test_credential_connection,
             generate_ssh_key,
+            // Docker Commands
+            restart_container,
+            remove_container,
+            pause_container,
+            unpause_container,
+            get_container_logs,
+            get_container_stats,
+            // Search Commands
+            global_search,
+            get_entity,
+            // Onboarding Commands
+            seed_demo_data,
+            clear_demo_data,
+            reset_database,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -466,6 +466,8 @@ function App() {
         return '#4caf50';
       case 'archived':
         return '#666';
+      case 'maintenance':
+        return '#9c27b0';
       default:
         return '#999';
     }
@@ -0,0 +1,235 @@
+//! pctrl-cloudflare - Cloudflare DNS API client
+//!
+//! Finds the zone and record behind a domain and points records at
+//! servers. The token comes from an ApiToken credential and needs the
+//! Zone:Read and DNS:Edit permissions.
+
+mod point;
+
+pub use point::{find_record, plan_point, record_type, zone_for, Change};
+
+use pctrl_core::{Error, Result};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const API_URL: &str = "https://api.cloudflare.com/client/v4";
+
+/// Records per page when listing (the API's maximum for zones is 50)
+const PER_PAGE: u32 = 50;
+
+/// A DNS zone of the account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+/// A DNS record in a zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub id: String,
+    /// A, AAAA, CNAME, ...
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub content: String,
+    /// Seconds; 1 means automatic
+    #[serde(default)]
+    pub ttl: u32,
+    #[serde(default)]
+    pub proxied: bool,
+}
+
+/// A record to create, or what to replace one with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewRecord {
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub name: String,
+    pub content: String,
+    pub ttl: u32,
+    pub proxied: bool,
+}
+
+/// Cloudflare client (token from an ApiToken credential)
+pub struct CloudflareClient {
+    token: String,
+    base_url: String,
+    client: Client,
+}
+
+impl CloudflareClient {
+    pub fn new(token: &str) -> Self {
+        Self::with_base_url(token, API_URL)
+    }
+
+    /// Client against a different API endpoint (e.g. a mock server)
+    pub fn with_base_url(token: &str, base_url: &str) -> Self {
+        Self {
+            token: token.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// All zones the token can see, following pagination
+    pub async fn list_zones(&self) -> Result<Vec<Zone>> {
+        self.list_all("zones").await
+    }
+
+    /// All records of a zone, following pagination
+    pub async fn list_dns_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        self.list_all(&format!("zones/{}/dns_records", zone_id))
+            .await
+    }
+
+    pub async fn create_record(&self, zone_id: &str, record: &NewRecord) -> Result<DnsRecord> {
+        let url = format!("{}/zones/{}/dns_records", self.base_url, zone_id);
+        self.send(self.client.post(url).json(record)).await
+    }
+
+    /// Replace a record (its type may change, e.g. CNAME to A)
+    pub async fn update_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        record: &NewRecord,
+    ) -> Result<DnsRecord> {
+        let url = format!(
+            "{}/zones/{}/dns_records/{}",
+            self.base_url, zone_id, record_id
+        );
+        self.send(self.client.put(url).json(record)).await
+    }
+
+    pub async fn delete_record(&self, zone_id: &str, record_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/zones/{}/dns_records/{}",
+            self.base_url, zone_id, record_id
+        );
+        let _: serde_json::Value = self.send(self.client.delete(url)).await?;
+        Ok(())
+    }
+
+    /// Carry out a planned change; the record as it is afterwards
+    pub async fn apply(&self, zone_id: &str, change: &Change) -> Result<DnsRecord> {
+        match change {
+            Change::Create(new) => self.create_record(zone_id, new).await,
+            Change::Update { record, new } => self.update_record(zone_id, &record.id, new).await,
+            Change::Unchanged(record) => Ok(record.clone()),
+        }
+    }
+
+    async fn list_all<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}/{}?page={}&per_page={}",
+                self.base_url, path, page, PER_PAGE
+            );
+            let (batch, info): (Vec<T>, Option<ResultInfo>) =
+                self.send_paged(self.client.get(url)).await?;
+            items.extend(batch);
+            match info {
+                Some(info) if info.total_pages > page => page += 1,
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        Ok(self.send_paged(request).await?.0)
+    }
+
+    async fn send_paged<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(T, Option<ResultInfo>)> {
+        let response = request
+            .bearer_auth(&self.token)
+            .timeout(std::time::Duration::from_secs(15))
+            .send()
+            .await
+            .map_err(|e| Error::Cloudflare(format!("Request failed: {}", e)))?;
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Cloudflare(format!("Failed to read response: {}", e)))?;
+
+        let envelope: Option<Envelope<T>> = serde_json::from_str(&body).ok();
+        match envelope {
+            Some(Envelope {
+                success: true,
+                result: Some(result),
+                result_info,
+                ..
+            }) if (200..300).contains(&status) => Ok((result, result_info)),
+            Some(envelope) => Err(api_error(status, &envelope.errors, retry_after.as_deref())),
+            None if (200..300).contains(&status) => {
+                Err(Error::Cloudflare("Failed to parse response".to_string()))
+            }
+            None => Err(api_error(status, &[], retry_after.as_deref())),
+        }
+    }
+}
+
+/// Every response comes wrapped like this
+#[derive(Deserialize)]
+struct Envelope<T> {
+    #[serde(default)]
+    success: bool,
+    #[serde(default)]
+    errors: Vec<ApiMessage>,
+    result: Option<T>,
+    result_info: Option<ResultInfo>,
+}
+
+#[derive(Deserialize)]
+struct ApiMessage {
+    code: u32,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ResultInfo {
+    #[serde(default)]
+    total_pages: u32,
+}
+
+/// A failed request as one readable error; rate limits and rejected tokens
+/// say what to do about them
+fn api_error(status: u16, errors: &[ApiMessage], retry_after: Option<&str>) -> Error {
+    let details = errors
+        .iter()
+        .map(|e| format!("{} (code {})", e.message, e.code))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let message = match status {
+        429 => match retry_after {
+            Some(seconds) => format!("Rate limited, retry in {}s", seconds),
+            None => "Rate limited, retry later".to_string(),
+        },
+        401 | 403 => format!(
+            "Authentication failed{}; the API token needs Zone:Read and DNS:Edit",
+            if details.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", details)
+            }
+        ),
+        _ if details.is_empty() => format!("API request failed with status: {}", status),
+        _ => format!("API request failed with status {}: {}", status, details),
+    };
+    Error::Cloudflare(message)
+}
@@ -0,0 +1,158 @@
+//! Which zone and record belong to a domain, and what pointing it at an
+//! address changes
+
+use crate::{DnsRecord, NewRecord, Zone};
+use std::fmt;
+use std::net::IpAddr;
+
+/// Record types a domain can point at a server with; the first ones are
+/// preferred when several exist
+const POINTING: [&str; 3] = ["A", "AAAA", "CNAME"];
+
+/// TTL Cloudflare reads as "automatic"
+const AUTO_TTL: u32 = 1;
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+/// The zone a domain is in: the longest zone name that is the domain or
+/// ends it
+pub fn zone_for<'a>(zones: &'a [Zone], domain: &str) -> Option<&'a Zone> {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    zones
+        .iter()
+        .filter(|zone| {
+            let name = zone.name.trim_end_matches('.').to_ascii_lowercase();
+            domain == name || domain.ends_with(&format!(".{}", name))
+        })
+        .max_by_key(|zone| zone.name.len())
+}
+
+/// The record a domain points with: the one with `record_id` if it is
+/// still there, else an A, AAAA or CNAME record of that name
+pub fn find_record<'a>(
+    records: &'a [DnsRecord],
+    domain: &str,
+    record_id: Option<&str>,
+) -> Option<&'a DnsRecord> {
+    let pointing =
+        |r: &&DnsRecord| same_name(&r.name, domain) && POINTING.contains(&r.record_type.as_str());
+    record_id
+        .and_then(|id| records.iter().find(|r| r.id == id))
+        .or_else(|| {
+            POINTING.iter().find_map(|kind| {
+                records
+                    .iter()
+                    .filter(pointing)
+                    .find(|r| r.record_type == *kind)
+            })
+        })
+}
+
+/// A for IPv4, AAAA for IPv6
+pub fn record_type(ip: IpAddr) -> &'static str {
+    match ip {
+        IpAddr::V4(_) => "A",
+        IpAddr::V6(_) => "AAAA",
+    }
+}
+
+/// What pointing a domain at an address does to its records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Create(NewRecord),
+    /// Replace `record` (a record of the other type is replaced only when
+    /// it is a CNAME, which can't coexist with an A record)
+    Update {
+        record: DnsRecord,
+        new: NewRecord,
+    },
+    Unchanged(DnsRecord),
+}
+
+/// The change that points `domain` at `ip`. An existing record of the same
+/// type (or the one with `record_id`) is updated, a CNAME replaced; the
+/// record's TTL and proxy setting are kept unless `proxied` is given.
+pub fn plan_point(
+    records: &[DnsRecord],
+    domain: &str,
+    record_id: Option<&str>,
+    ip: IpAddr,
+    proxied: Option<bool>,
+) -> Change {
+    let kind = record_type(ip);
+    let named = |r: &&DnsRecord| same_name(&r.name, domain);
+    let existing = record_id
+        .and_then(|id| {
+            records
+                .iter()
+                .filter(named)
+                .find(|r| r.id == id && (r.record_type == kind || r.record_type == "CNAME"))
+        })
+        .or_else(|| records.iter().filter(named).find(|r| r.record_type == kind))
+        .or_else(|| {
+            records
+                .iter()
+                .filter(named)
+                .find(|r| r.record_type == "CNAME")
+        });
+
+    let new = NewRecord {
+        record_type: kind.to_string(),
+        name: domain.trim_end_matches('.').to_string(),
+        content: ip.to_string(),
+        ttl: existing.map_or(AUTO_TTL, |r| r.ttl.max(AUTO_TTL)),
+        proxied: proxied.unwrap_or(existing.is_some_and(|r| r.proxied)),
+    };
+    match existing {
+        None => Change::Create(new),
+        Some(record)
+            if record.record_type == new.record_type
+                && record.content == new.content
+                && record.proxied == new.proxied =>
+        {
+            Change::Unchanged(record.clone())
+        }
+        Some(record) => Change::Update {
+            record: record.clone(),
+            new,
+        },
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let proxy = |proxied: bool| if proxied { " (proxied)" } else { "" };
+        match self {
+            Change::Create(new) => write!(
+                f,
+                "create {} {} → {}{}",
+                new.record_type,
+                new.name,
+                new.content,
+                proxy(new.proxied)
+            ),
+            Change::Update { record, new } => write!(
+                f,
+                "update {} {} {}{} → {} {}{}",
+                record.name,
+                record.record_type,
+                record.content,
+                proxy(record.proxied),
+                new.record_type,
+                new.content,
+                proxy(new.proxied)
+            ),
+            Change::Unchanged(record) => write!(
+                f,
+                "{} {} already points at {}{}",
+                record.record_type,
+                record.name,
+                record.content,
+                proxy(record.proxied)
+            ),
+        }
+    }
+}
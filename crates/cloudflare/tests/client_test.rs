@@ -0,0 +1,176 @@
+//! The client against a local stand-in for the API
+
+use pctrl_cloudflare::{CloudflareClient, NewRecord};
+use pctrl_core::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A canned response: status, extra header lines, body
+type Reply = (u16, &'static str, String);
+
+/// Answer requests with `replies` in turn; the handle yields each request
+/// as "METHOD path" plus its body
+async fn serve(replies: Vec<Reply>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut seen = Vec::new();
+        let mut replies = replies.into_iter();
+        'connections: loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    continue 'connections;
+                }
+                let request = line
+                    .split_whitespace()
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).await.unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+                seen.push(
+                    format!("{} {}", request, String::from_utf8(body).unwrap())
+                        .trim()
+                        .to_string(),
+                );
+
+                let Some((status, headers, body)) = replies.next() else {
+                    break 'connections;
+                };
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                );
+                stream
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+                if replies.len() == 0 {
+                    break 'connections;
+                }
+            }
+        }
+        seen
+    });
+    (url, handle)
+}
+
+fn ok(result: &str, total_pages: u32) -> Reply {
+    (
+        200,
+        "",
+        format!(
+            r#"{{"success":true,"errors":[],"result":{},"result_info":{{"page":1,"total_pages":{}}}}}"#,
+            result, total_pages
+        ),
+    )
+}
+
+#[tokio::test]
+async fn test_list_zones_follows_pages() {
+    let (url, server) = serve(vec![
+        ok(r#"[{"id":"z1","name":"example.com","status":"active"}]"#, 2),
+        ok(r#"[{"id":"z2","name":"example.io","status":"pending"}]"#, 2),
+    ])
+    .await;
+    let zones = CloudflareClient::with_base_url("token", &url)
+        .list_zones()
+        .await
+        .unwrap();
+    let names: Vec<&str> = zones.iter().map(|z| z.name.as_str()).collect();
+    assert_eq!(names, vec!["example.com", "example.io"]);
+    assert_eq!(
+        server.await.unwrap(),
+        vec![
+            "GET /zones?page=1&per_page=50",
+            "GET /zones?page=2&per_page=50"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_create_record_sends_the_record() {
+    let (url, server) = serve(vec![ok(
+        r#"{"id":"r1","type":"A","name":"shop.example.com","content":"203.0.113.5","ttl":1,"proxied":false}"#,
+        0,
+    )])
+    .await;
+    let created = CloudflareClient::with_base_url("token", &url)
+        .create_record(
+            "z1",
+            &NewRecord {
+                record_type: "A".to_string(),
+                name: "shop.example.com".to_string(),
+                content: "203.0.113.5".to_string(),
+                ttl: 1,
+                proxied: false,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.id, "r1");
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("POST /zones/z1/dns_records {"));
+    assert!(requests[0].contains(r#""type":"A""#));
+}
+
+#[tokio::test]
+async fn test_api_errors_map_to_cloudflare_errors() {
+    let (url, _server) = serve(vec![
+        (
+            403,
+            "",
+            r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}],"result":null}"#
+                .to_string(),
+        ),
+        (429, "Retry-After: 30\r\n", "rate limited".to_string()),
+        (
+            400,
+            "",
+            r#"{"success":false,"errors":[{"code":81057,"message":"Record already exists."}],"result":null}"#
+                .to_string(),
+        ),
+    ])
+    .await;
+    let client = CloudflareClient::with_base_url("token", &url);
+
+    let message = |result: pctrl_core::Result<()>| match result {
+        Err(Error::Cloudflare(message)) => message,
+        other => panic!("expected a Cloudflare error, got {:?}", other),
+    };
+    let auth = message(client.list_zones().await.map(|_| ()));
+    assert!(
+        auth.contains("Authentication failed: Authentication error (code 10000)"),
+        "{}",
+        auth
+    );
+    assert!(auth.contains("DNS:Edit"));
+    assert_eq!(
+        message(client.list_dns_records("z1").await.map(|_| ())),
+        "Rate limited, retry in 30s"
+    );
+    assert_eq!(
+        message(client.delete_record("z1", "r1").await),
+        "API request failed with status 400: Record already exists. (code 81057)"
+    );
+}
@@ -0,0 +1,115 @@
+use pctrl_cloudflare::{find_record, plan_point, zone_for, Change, DnsRecord, Zone};
+use std::net::IpAddr;
+
+fn zone(id: &str, name: &str) -> Zone {
+    Zone {
+        id: id.to_string(),
+        name: name.to_string(),
+        status: "active".to_string(),
+    }
+}
+
+fn record(id: &str, kind: &str, name: &str, content: &str) -> DnsRecord {
+    DnsRecord {
+        id: id.to_string(),
+        record_type: kind.to_string(),
+        name: name.to_string(),
+        content: content.to_string(),
+        ttl: 300,
+        proxied: false,
+    }
+}
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_zone_is_the_longest_suffix() {
+    let zones = vec![
+        zone("z1", "example.com"),
+        zone("z2", "shop.example.com"),
+        zone("z3", "ample.com"),
+    ];
+    assert_eq!(zone_for(&zones, "App.Example.com").unwrap().id, "z1");
+    assert_eq!(zone_for(&zones, "api.shop.example.com.").unwrap().id, "z2");
+    assert_eq!(zone_for(&zones, "example.com").unwrap().id, "z1");
+    // Suffixes only count at a label boundary
+    assert!(zone_for(&zones, "sample.org").is_none());
+    assert!(zone_for(&[zone("z3", "ample.com")], "example.com").is_none());
+}
+
+#[test]
+fn test_find_record_prefers_the_stored_id_then_a() {
+    let records = vec![
+        record("r1", "CNAME", "www.example.com", "example.com"),
+        record("r2", "AAAA", "shop.example.com", "2001:db8::1"),
+        record("r3", "A", "shop.example.com", "203.0.113.5"),
+        record("r4", "TXT", "shop.example.com", "v=spf1"),
+    ];
+    assert_eq!(
+        find_record(&records, "shop.example.com", None).unwrap().id,
+        "r3"
+    );
+    assert_eq!(
+        find_record(&records, "shop.example.com", Some("r2"))
+            .unwrap()
+            .id,
+        "r2"
+    );
+    // A stale ID falls back to the name
+    assert_eq!(
+        find_record(&records, "SHOP.example.com", Some("gone"))
+            .unwrap()
+            .id,
+        "r3"
+    );
+    assert!(find_record(&records, "mail.example.com", None).is_none());
+}
+
+#[test]
+fn test_plan_point() {
+    let records = vec![
+        record("r1", "A", "shop.example.com", "198.51.100.1"),
+        record("r2", "CNAME", "www.example.com", "example.com"),
+    ];
+
+    let change = plan_point(&records, "shop.example.com", None, ip("203.0.113.5"), None);
+    let Change::Update { record, new } = &change else {
+        panic!("expected an update, got {:?}", change);
+    };
+    assert_eq!(record.id, "r1");
+    assert_eq!((new.content.as_str(), new.ttl), ("203.0.113.5", 300));
+    assert_eq!(
+        change.to_string(),
+        "update shop.example.com A 198.51.100.1 → A 203.0.113.5"
+    );
+
+    let change = plan_point(&records, "shop.example.com", None, ip("198.51.100.1"), None);
+    assert!(matches!(change, Change::Unchanged(_)));
+    // Turning the proxy on is a change
+    let change = plan_point(
+        &records,
+        "shop.example.com",
+        None,
+        ip("198.51.100.1"),
+        Some(true),
+    );
+    assert!(matches!(change, Change::Update { ref new, .. } if new.proxied));
+
+    // A CNAME is replaced; a missing record created with automatic TTL
+    let change = plan_point(&records, "www.example.com", None, ip("203.0.113.5"), None);
+    assert!(matches!(change, Change::Update { ref record, .. } if record.id == "r2"));
+    let change = plan_point(&records, "new.example.com", None, ip("2001:db8::5"), None);
+    let Change::Create(new) = &change else {
+        panic!("expected a create, got {:?}", change);
+    };
+    assert_eq!(
+        (new.record_type.as_str(), new.ttl, new.proxied),
+        ("AAAA", 1, false)
+    );
+    assert_eq!(
+        change.to_string(),
+        "create AAAA new.example.com → 2001:db8::5"
+    );
+}
@@ -1,14 +1,146 @@
-use pctrl_core::{CoolifyInstance, Result};
-use reqwest::Client;
+//! pctrl-coolify - Coolify v4 API client
+//!
+//! Lists applications and services, starts deployments by resource UUID
+//! (`/api/v1/deploy`) and follows them (`/api/v1/deployments/{uuid}`).
+//! `/api/v1/version` tells a working token from a rejected one.
+//! Response types only name the fields pctrl uses; others are ignored, so
+//! newer Coolify versions don't break parsing.
+
+use pctrl_core::{CoolifyInstance, Error, Result};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-/// Coolify deployment information
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Deployment {
-    pub id: String,
+/// Longest error body quoted in an error message
+const MAX_ERROR_BODY: usize = 300;
+
+/// How long a health check waits for the instance
+const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// What an instance said about its API token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenCheck {
+    /// The token works; the instance's Coolify version
+    Valid(String),
+    /// 401 or 403: the token is wrong, revoked or lacks read access
+    Rejected(String),
+}
+
+/// An application (`/api/v1/applications`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Application {
+    pub uuid: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Comma-separated URLs the application is served at
+    #[serde(default)]
+    pub fqdn: Option<String>,
+    /// e.g. `running:healthy`, `exited:unhealthy`
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub git_repository: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// nixpacks, dockerfile, dockercompose, static...
+    #[serde(default)]
+    pub build_pack: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// A one-click service stack (`/api/v1/services`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Service {
+    pub uuid: String,
     pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The template it was created from, e.g. `plausible`
+    #[serde(default)]
+    pub service_type: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// A deployment started by [`CoolifyManager::deploy`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedDeployment {
+    #[serde(default)]
+    pub message: String,
+    pub resource_uuid: String,
+    /// Follow it with [`CoolifyManager::get_deployment_status`]
+    pub deployment_uuid: String,
+}
+
+/// A deployment and its build log (`/api/v1/deployments/{uuid}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeploymentLog {
+    pub deployment_uuid: String,
+    /// queued, in_progress, finished, failed, cancelled-by-user
     pub status: String,
-    pub url: Option<String>,
+    #[serde(default)]
+    pub application_name: Option<String>,
+    #[serde(default)]
+    pub server_name: Option<String>,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub commit_message: Option<String>,
+    #[serde(default)]
+    pub force_rebuild: bool,
+    #[serde(default)]
+    pub deployment_url: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    /// The build log as Coolify stores it: a JSON array, as a string
+    #[serde(default)]
+    pub logs: Option<String>,
+}
+
+/// One line of a deployment's build log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLine {
+    #[serde(default)]
+    pub output: String,
+    /// stdout or stderr
+    #[serde(default, rename = "type")]
+    pub stream: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    /// Internal steps Coolify doesn't show by default
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl DeploymentLog {
+    /// Still queued or running
+    pub fn is_running(&self) -> bool {
+        matches!(self.status.as_str(), "queued" | "in_progress")
+    }
+
+    /// The lines of the build log Coolify shows, oldest first; empty when
+    /// there's no log or it can't be read
+    pub fn lines(&self) -> Vec<LogLine> {
+        let lines: Vec<LogLine> = self
+            .logs
+            .as_deref()
+            .and_then(|logs| serde_json::from_str(logs).ok())
+            .unwrap_or_default();
+        lines.into_iter().filter(|line| !line.hidden).collect()
+    }
+}
+
+/// Answer of `/api/v1/deploy`
+#[derive(Deserialize)]
+struct DeployResponse {
+    #[serde(default)]
+    deployments: Vec<QueuedDeployment>,
 }
 
 /// Coolify manager
@@ -30,64 +162,53 @@ impl CoolifyManager {
         self.instances.push(instance);
     }
 
-    /// List deployments on an instance
-    pub async fn list_deployments(&self, instance_id: &str) -> Result<Vec<Deployment>> {
-        let instance = self
-            .instances
-            .iter()
-            .find(|i| i.id == instance_id)
-            .ok_or_else(|| pctrl_core::Error::Coolify("Instance not found".to_string()))?;
-
-        let url = format!("{}/api/v1/deployments", instance.url);
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", instance.api_key))
-            .send()
-            .await
-            .map_err(|e| pctrl_core::Error::Coolify(format!("Request failed: {}", e)))?;
+    /// Applications on an instance
+    pub async fn list_applications(&self, instance_id: &str) -> Result<Vec<Application>> {
+        self.get(instance_id, "applications").await
+    }
 
-        if !response.status().is_success() {
-            return Err(pctrl_core::Error::Coolify(format!(
-                "API request failed with status: {}",
-                response.status()
-            )));
-        }
+    /// Service stacks on an instance
+    pub async fn list_services(&self, instance_id: &str) -> Result<Vec<Service>> {
+        self.get(instance_id, "services").await
+    }
 
-        let deployments: Vec<Deployment> = response
-            .json()
+    /// One application by UUID
+    pub async fn get_application(&self, instance_id: &str, uuid: &str) -> Result<Application> {
+        self.get(instance_id, &format!("applications/{}", uuid))
             .await
-            .map_err(|e| pctrl_core::Error::Coolify(format!("Failed to parse response: {}", e)))?;
-
-        Ok(deployments)
     }
 
-    /// Deploy a project
-    pub async fn deploy_project(&self, instance_id: &str, project_id: &str) -> Result<()> {
-        let instance = self
-            .instances
-            .iter()
-            .find(|i| i.id == instance_id)
-            .ok_or_else(|| pctrl_core::Error::Coolify("Instance not found".to_string()))?;
+    /// Deployments running or queued on an instance
+    pub async fn list_deployments(&self, instance_id: &str) -> Result<Vec<DeploymentLog>> {
+        self.get(instance_id, "deployments").await
+    }
 
-        let url = format!("{}/api/v1/deployments", instance.url);
-        let response = self
+    /// Deploy an application or service by UUID; `force_rebuild` builds
+    /// without the cache
+    pub async fn deploy(
+        &self,
+        instance_id: &str,
+        uuid: &str,
+        force_rebuild: bool,
+    ) -> Result<Vec<QueuedDeployment>> {
+        let instance = self.instance(instance_id)?;
+        let url = format!("{}/api/v1/deploy", instance.url);
+        let request = self
             .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", instance.api_key))
-            .json(&serde_json::json!({ "project_id": project_id }))
-            .send()
-            .await
-            .map_err(|e| pctrl_core::Error::Coolify(format!("Request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(pctrl_core::Error::Coolify(format!(
-                "Deployment failed with status: {}",
-                response.status()
-            )));
-        }
+            .get(url)
+            .query(&[("uuid", uuid), ("force", &force_rebuild.to_string())]);
+        let response: DeployResponse = self.send(instance, request).await?;
+        Ok(response.deployments)
+    }
 
-        Ok(())
+    /// A deployment's status and build log
+    pub async fn get_deployment_status(
+        &self,
+        instance_id: &str,
+        deployment_uuid: &str,
+    ) -> Result<DeploymentLog> {
+        self.get(instance_id, &format!("deployments/{}", deployment_uuid))
+            .await
     }
 
     /// List all instances
@@ -100,33 +221,76 @@ impl CoolifyManager {
         self.instances.iter().find(|i| i.id == id)
     }
 
-    /// Health check - verify connection to Coolify instance
-    pub async fn health_check(&self, instance_id: &str) -> Result<()> {
-        let instance = self
-            .instances
-            .iter()
-            .find(|i| i.id == instance_id)
-            .ok_or_else(|| pctrl_core::Error::Coolify("Instance not found".to_string()))?;
+    /// The instance's Coolify version, or an error if it can't be reached
+    /// or rejects the token
+    pub async fn health_check(&self, instance_id: &str) -> Result<String> {
+        match self.validate_token(instance_id).await? {
+            TokenCheck::Valid(version) => Ok(version),
+            TokenCheck::Rejected(reason) => {
+                Err(Error::Coolify(format!("API token rejected: {}", reason)))
+            }
+        }
+    }
 
-        // Try to reach the API endpoint with a timeout
-        let url = format!("{}/api/v1/deployments", instance.url);
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", instance.api_key))
-            .timeout(std::time::Duration::from_secs(5))
-            .send()
-            .await
-            .map_err(|e| pctrl_core::Error::Coolify(format!("Health check failed: {}", e)))?;
+    /// Ask the instance for its version with the stored token; a connection
+    /// failure or another error status is an `Err`, a refused token isn't
+    pub async fn validate_token(&self, instance_id: &str) -> Result<TokenCheck> {
+        let instance = self.instance(instance_id)?;
+        let url = format!("{}/api/v1/version", instance.url);
+        let request = self.client.get(url).timeout(CHECK_TIMEOUT);
+        let (status, body) = self.fetch(instance, request).await?;
+        match status {
+            200..=299 => Ok(TokenCheck::Valid(parse_version(&body))),
+            401 | 403 => Ok(TokenCheck::Rejected(match api_message(&body) {
+                Some(message) => format!("{} ({})", message, status),
+                None => format!("status {}", status),
+            })),
+            _ => Err(api_error(status, &body)),
+        }
+    }
 
-        if !response.status().is_success() {
-            return Err(pctrl_core::Error::Coolify(format!(
-                "Health check failed with status: {}",
-                response.status()
-            )));
+    fn instance(&self, id: &str) -> Result<&CoolifyInstance> {
+        self.get_instance(id)
+            .ok_or_else(|| Error::Coolify("Instance not found".to_string()))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, instance_id: &str, path: &str) -> Result<T> {
+        let instance = self.instance(instance_id)?;
+        let url = format!("{}/api/v1/{}", instance.url, path);
+        self.send(instance, self.client.get(url)).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        instance: &CoolifyInstance,
+        request: RequestBuilder,
+    ) -> Result<T> {
+        let (status, body) = self.fetch(instance, request).await?;
+        if !(200..300).contains(&status) {
+            return Err(api_error(status, &body));
         }
+        serde_json::from_str(&body)
+            .map_err(|e| Error::Coolify(format!("Failed to parse response: {}", e)))
+    }
 
-        Ok(())
+    /// Status and body of an authenticated request
+    async fn fetch(
+        &self,
+        instance: &CoolifyInstance,
+        request: RequestBuilder,
+    ) -> Result<(u16, String)> {
+        let response = request
+            .bearer_auth(&instance.api_key)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .map_err(|e| Error::Coolify(format!("Request failed: {}", e)))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Coolify(format!("Failed to read response: {}", e)))?;
+        Ok((status, body))
     }
 }
 
@@ -135,3 +299,35 @@ impl Default for CoolifyManager {
         Self::new()
     }
 }
+
+/// A non-2xx answer with what Coolify said: its `message` when the body is
+/// JSON, else the body itself, shortened
+fn api_error(status: u16, body: &str) -> Error {
+    let message = api_message(body).unwrap_or_else(|| body.trim().to_string());
+    let message = if message.chars().count() > MAX_ERROR_BODY {
+        let short: String = message.chars().take(MAX_ERROR_BODY).collect();
+        format!("{}…", short)
+    } else {
+        message
+    };
+    if message.is_empty() {
+        Error::Coolify(format!("API request failed with status: {}", status))
+    } else {
+        Error::Coolify(format!(
+            "API request failed with status {}: {}",
+            status, message
+        ))
+    }
+}
+
+/// The `message` of a JSON error body
+fn api_message(body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    json.get("message")?.as_str().map(str::to_string)
+}
+
+/// `/api/v1/version` answers with the bare version, some releases as a
+/// JSON string
+fn parse_version(body: &str) -> String {
+    serde_json::from_str::<String>(body).unwrap_or_else(|_| body.trim().to_string())
+}
@@ -0,0 +1,217 @@
+//! The client against a local stand-in for the Coolify API
+
+use pctrl_coolify::{CoolifyManager, TokenCheck};
+use pctrl_core::{CoolifyInstance, Error};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A canned response: status, extra header lines, body
+type Reply = (u16, &'static str, String);
+
+/// Answer requests with `replies` in turn; the handle yields each request
+/// as "METHOD path" plus its body
+async fn serve(replies: Vec<Reply>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut seen = Vec::new();
+        let mut replies = replies.into_iter();
+        'connections: loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    continue 'connections;
+                }
+                let request = line
+                    .split_whitespace()
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).await.unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+                seen.push(
+                    format!("{} {}", request, String::from_utf8(body).unwrap())
+                        .trim()
+                        .to_string(),
+                );
+
+                let Some((status, headers, body)) = replies.next() else {
+                    break 'connections;
+                };
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                );
+                stream
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+                if replies.len() == 0 {
+                    break 'connections;
+                }
+            }
+        }
+        seen
+    });
+    (url, handle)
+}
+
+fn ok(body: &str) -> Reply {
+    (200, "", body.to_string())
+}
+
+fn manager(url: &str) -> CoolifyManager {
+    let mut coolify = CoolifyManager::new();
+    coolify.add_instance(CoolifyInstance {
+        id: "prod".to_string(),
+        name: "Prod".to_string(),
+        url: url.to_string(),
+        api_key: "token".to_string(),
+    });
+    coolify
+}
+
+#[tokio::test]
+async fn test_list_applications_ignores_unknown_fields() {
+    let (url, server) = serve(vec![
+        ok(r#"[{"id":7,"uuid":"a1b2","name":"shop","fqdn":"https://shop.example.com","status":"running:healthy","git_repository":"acme/shop","git_branch":"main","build_pack":"nixpacks","environment_id":3,"destination":{"id":1}}]"#),
+        ok(r#"[{"uuid":"s9","name":"plausible","service_type":"plausible","server_status":true}]"#),
+    ])
+    .await;
+    let coolify = manager(&url);
+
+    let apps = coolify.list_applications("prod").await.unwrap();
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].uuid, "a1b2");
+    assert_eq!(apps[0].status.as_deref(), Some("running:healthy"));
+    assert_eq!(apps[0].description, None);
+
+    let services = coolify.list_services("prod").await.unwrap();
+    assert_eq!(services[0].service_type.as_deref(), Some("plausible"));
+    assert_eq!(services[0].status, None);
+
+    assert_eq!(
+        server.await.unwrap(),
+        vec!["GET /api/v1/applications", "GET /api/v1/services"]
+    );
+}
+
+#[tokio::test]
+async fn test_deploy_queues_and_reports_status() {
+    let (url, server) = serve(vec![
+        ok(r#"{"deployments":[{"message":"Application shop deployment queued.","resource_uuid":"a1b2","deployment_uuid":"d42"}]}"#),
+        ok(r#"{"id":12,"deployment_uuid":"d42","status":"in_progress","application_name":"shop","force_rebuild":true,"logs":"[{\"command\":null,\"output\":\"Starting build\",\"type\":\"stdout\",\"timestamp\":\"2024-05-01T10:00:00Z\",\"hidden\":false,\"batch\":1},{\"output\":\"docker inspect\",\"type\":\"stdout\",\"hidden\":true}]"}"#),
+    ])
+    .await;
+    let coolify = manager(&url);
+
+    let queued = coolify.deploy("prod", "a1b2", true).await.unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].deployment_uuid, "d42");
+
+    let status = coolify.get_deployment_status("prod", "d42").await.unwrap();
+    assert!(status.is_running());
+    assert!(status.force_rebuild);
+    let lines = status.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].output, "Starting build");
+
+    assert_eq!(
+        server.await.unwrap(),
+        vec![
+            "GET /api/v1/deploy?uuid=a1b2&force=true",
+            "GET /api/v1/deployments/d42"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_errors_include_the_response_body() {
+    let (url, _server) = serve(vec![
+        (
+            404,
+            "",
+            r#"{"message":"Application not found."}"#.to_string(),
+        ),
+        (500, "", "upstream exploded".to_string()),
+        (401, "", String::new()),
+    ])
+    .await;
+    let coolify = manager(&url);
+
+    let message = |result: pctrl_core::Result<()>| match result {
+        Err(Error::Coolify(message)) => message,
+        other => panic!("expected a Coolify error, got {:?}", other),
+    };
+    assert_eq!(
+        message(coolify.get_application("prod", "nope").await.map(|_| ())),
+        "API request failed with status 404: Application not found."
+    );
+    assert_eq!(
+        message(coolify.deploy("prod", "a1b2", false).await.map(|_| ())),
+        "API request failed with status 500: upstream exploded"
+    );
+    assert_eq!(
+        message(coolify.list_services("prod").await.map(|_| ())),
+        "API request failed with status: 401"
+    );
+    assert_eq!(
+        message(coolify.list_applications("staging").await.map(|_| ())),
+        "Instance not found"
+    );
+}
+
+#[tokio::test]
+async fn test_validate_token_tells_rejected_from_unreachable() {
+    let (url, server) = serve(vec![
+        ok("4.0.0-beta.360"),
+        ok(r#""4.0.0-beta.361""#),
+        (401, "", r#"{"message":"Unauthenticated."}"#.to_string()),
+    ])
+    .await;
+    let coolify = manager(&url);
+
+    assert_eq!(
+        coolify.validate_token("prod").await.unwrap(),
+        TokenCheck::Valid("4.0.0-beta.360".to_string())
+    );
+    assert_eq!(
+        coolify.health_check("prod").await.unwrap(),
+        "4.0.0-beta.361"
+    );
+    assert_eq!(
+        coolify.validate_token("prod").await.unwrap(),
+        TokenCheck::Rejected("Unauthenticated. (401)".to_string())
+    );
+    assert_eq!(server.await.unwrap(), vec!["GET /api/v1/version"; 3]);
+
+    // Nothing listens on a port that was just given back
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    match manager(&closed).validate_token("prod").await {
+        Err(Error::Coolify(message)) => {
+            assert!(message.starts_with("Request failed"), "{}", message)
+        }
+        other => panic!("expected a connection error, got {:?}", other),
+    }
+}
@@ -0,0 +1,568 @@
+//! Anonymized database copies for bug reports (`pctrl debug anonymize`)
+//!
+//! [`rule`] decides what happens to each column and [`Faker`] hands out
+//! the stand-ins. Within one [`Faker`] the same original always gets the
+//! same fake, so IDs still join, a server's host matches the host mentioned
+//! in an audit entry, and `www.shop.com` stays a subdomain of whatever
+//! `shop.com` became.
+//!
+//! Free text is scrubbed of URLs, email and IP addresses, and of every
+//! host, name, path and ID the structured columns produced. Those have to
+//! be faked first, so the caller runs all non-text rules before
+//! [`Faker::scrub`].
+
+use regex::{Regex, RegexBuilder};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Domain every fake host name ends in (reserved, never resolves)
+pub const FAKE_DOMAIN: &str = "example.invalid";
+
+/// Characters of a note kept after scrubbing
+pub const NOTE_LEN: usize = 40;
+
+/// Literals shorter than this are not searched for in free text
+const MIN_LITERAL_LEN: usize = 3;
+
+/// What to do with one column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Statuses, types, numbers, timestamps: structure, left alone
+    Keep,
+    /// Entity IDs; UUIDs and hex IDs are kept, others faked
+    Id,
+    /// Host name or IP address
+    Host,
+    Url,
+    /// File system path
+    Path,
+    /// Name of an entity of the given kind, e.g. `server-3`
+    Name(&'static str),
+    /// Blanked
+    Secret,
+    /// Scrubbed and cut to [`NOTE_LEN`]
+    Note,
+    /// Scrubbed
+    Text,
+}
+
+impl Rule {
+    /// Whether the rule needs everything else faked first
+    pub fn is_free_text(self) -> bool {
+        matches!(self, Rule::Note | Rule::Text)
+    }
+
+    /// Label in the summary
+    pub fn label(self) -> &'static str {
+        match self {
+            Rule::Keep => "kept",
+            Rule::Id => "ids",
+            Rule::Host => "hosts",
+            Rule::Url => "urls",
+            Rule::Path => "paths",
+            Rule::Name(_) => "names",
+            Rule::Secret => "secrets",
+            Rule::Note => "notes",
+            Rule::Text => "text",
+        }
+    }
+}
+
+/// Columns that are never faked, in any table
+const KEEP_COLUMNS: &[&str] = &[
+    "status",
+    "server_type",
+    "domain_type",
+    "db_type",
+    "script_type",
+    "credential_type",
+    "resource_type",
+    "entity_type",
+    "auth_method",
+    "data_type",
+    "action",
+    "role",
+    "protocol",
+    "driver",
+    "priority",
+    "provider",
+    "location",
+    "specs",
+    "stack",
+    "color",
+    "icon",
+    "image",
+    "ports",
+    "branch",
+    "old_commit",
+    "new_commit",
+    "failed_phase",
+    "last_result",
+    "last_state",
+    "previous_status",
+    "requires_vpn",
+    "event",
+    "hook",
+    "version",
+    "counts",
+    "key",
+];
+
+/// What happens to `column` of `table`. `declared_type` is the column's
+/// type in the schema; anything not known to be harmless is scrubbed.
+pub fn rule(table: &str, column: &str, declared_type: &str) -> Rule {
+    let declared_type = declared_type.to_ascii_uppercase();
+    match (table, column) {
+        // Salt and schema version
+        ("metadata", _) => return Rule::Keep,
+        // Last runs are keyed by the command path, e.g. "project start"
+        ("last_runs", "command") | ("usage_stats", "command") => return Rule::Keep,
+        // Short refs hash the type and ID; they say nothing
+        (_, "short_ref") => return Rule::Keep,
+
+        ("credentials", "data")
+        | ("credentials", "key_material")
+        | ("databases", "password")
+        | ("databases", "connection_string")
+        | ("coolify_instances", "api_key")
+        | ("identity", "public_key")
+        | ("identity", "secret")
+        | ("snapshots", "data")
+        | ("containers", "env_vars")
+        | ("scripts", "env")
+        | ("scripts", "last_env") => return Rule::Secret,
+
+        ("servers", "host")
+        | ("databases", "host")
+        | ("ssh_connections", "host")
+        | ("container_networks", "ip_address")
+        | ("dns_propagations", "domain")
+        | ("domains", "domain") => return Rule::Host,
+
+        ("docker_hosts", "url")
+        | ("coolify_instances", "url")
+        | ("git_repos", "remote_url")
+        | ("project_preflight", "health_url") => return Rule::Url,
+
+        ("git_repos", "path")
+        | ("project_deploy", "path")
+        | ("pending_deploy_keys", "path")
+        | ("scripts", "working_dir")
+        | ("scripts", "last_cwd")
+        | ("docker_hosts", "tls_ca" | "tls_cert" | "tls_key") => return Rule::Path,
+
+        ("container_networks" | "container_ports", "container") => return Rule::Name("container"),
+        ("container_networks", "network") => return Rule::Name("network"),
+        ("docker_networks", "name") => return Rule::Name("network"),
+        (_, "username" | "actor" | "author" | "decided_by" | "holder") => {
+            return Rule::Name("user")
+        }
+        ("databases", "database_name") => return Rule::Name("db"),
+        (_, "name") => return Rule::Name(name_kind(table)),
+
+        (_, "notes" | "description" | "reason") => return Rule::Note,
+        _ => {}
+    }
+
+    if column == "id" || column.ends_with("_id") || column == "superseded_by" {
+        return Rule::Id;
+    }
+    if column.ends_with("_at") || KEEP_COLUMNS.contains(&column) {
+        return Rule::Keep;
+    }
+    // Numbers, flags and dates carry nothing to hide
+    if declared_type.contains("INT")
+        || declared_type.contains("REAL")
+        || declared_type.contains("BOOL")
+        || declared_type.contains("DATE")
+    {
+        return Rule::Keep;
+    }
+    if declared_type.contains("BLOB") {
+        return Rule::Secret;
+    }
+    Rule::Text
+}
+
+/// Fake name prefix for the `name` column of `table`
+fn name_kind(table: &str) -> &'static str {
+    match table {
+        "projects" => "project",
+        "servers" => "server",
+        "databases" => "database",
+        "containers" => "container",
+        "scripts" => "script",
+        "credentials" => "credential",
+        "services" => "service",
+        "docker_hosts" => "docker-host",
+        "coolify_instances" => "coolify",
+        "git_repos" => "repo",
+        "ssh_connections" => "ssh",
+        "snapshots" => "snapshot",
+        _ => "item",
+    }
+}
+
+/// What a fake stands in for; numbered separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Id,
+    Host,
+    Label,
+    Ipv4,
+    Ipv6,
+    Path,
+    Email,
+    Name(&'static str),
+}
+
+/// Deterministic stand-ins for one anonymization run
+pub struct Faker {
+    fakes: HashMap<(Kind, String), String>,
+    counters: HashMap<Kind, usize>,
+    /// Every fake handed out, so fakes found in text stay as they are
+    issued: HashSet<String>,
+    /// Originals to replace in free text, lowercased
+    literals: BTreeMap<String, String>,
+    /// Those of `literals` that are host names, matched with subdomains
+    host_literals: HashSet<String>,
+    /// Matcher for `literals`, rebuilt after they change
+    literal_re: Option<Regex>,
+    url_re: Regex,
+    email_re: Regex,
+    ipv4_re: Regex,
+    ipv6_re: Regex,
+}
+
+impl Default for Faker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Faker {
+    pub fn new() -> Self {
+        let re = |pattern: &str| Regex::new(pattern).expect("valid pattern");
+        Self {
+            fakes: HashMap::new(),
+            counters: HashMap::new(),
+            issued: HashSet::new(),
+            literals: BTreeMap::new(),
+            host_literals: HashSet::new(),
+            literal_re: None,
+            url_re: re(r#"\b[a-zA-Z][a-zA-Z0-9+.-]*://[^\s"'<>`)\]]+"#),
+            email_re: re(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+            ipv4_re: re(r"\b(?:\d{1,3}\.){3}\d{1,3}\b"),
+            // Three or more colons, so times like 12:00:01 don't match
+            ipv6_re: re(r"(?i)\b(?:[0-9a-f]{0,4}:){3,7}[0-9a-f]{1,4}\b"),
+        }
+    }
+
+    /// The fake for `original`, numbered `format(n)` on first sight
+    fn fake(&mut self, kind: Kind, original: &str, format: impl Fn(usize) -> String) -> String {
+        if let Some(fake) = self.fakes.get(&(kind, original.to_string())) {
+            return fake.clone();
+        }
+        let counter = self.counters.entry(kind).or_insert(0);
+        *counter += 1;
+        let fake = format(*counter);
+        self.fakes
+            .insert((kind, original.to_string()), fake.clone());
+        self.issued.insert(fake.clone());
+        fake
+    }
+
+    /// Remember `original` to replace it in free text too
+    fn literal(&mut self, original: &str, fake: &str) {
+        if original.len() >= MIN_LITERAL_LEN && original != fake {
+            self.literals
+                .insert(original.to_lowercase(), fake.to_string());
+            self.literal_re = None;
+        }
+    }
+
+    /// Remember a host name; in free text its unknown subdomains are
+    /// faked along with it
+    fn host_literal(&mut self, original: &str, fake: &str) {
+        self.literal(original, fake);
+        self.host_literals.insert(original.to_lowercase());
+    }
+
+    /// Fake an entity ID; UUIDs and hex IDs (Docker) say nothing and stay
+    pub fn id(&mut self, id: &str) -> String {
+        let opaque = id.len() >= 12
+            && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+            && id.chars().any(|c| c.is_ascii_digit());
+        if id.is_empty() || opaque || self.issued.contains(id) {
+            return id.to_string();
+        }
+        let fake = self.fake(Kind::Id, id, |n| format!("id-{}", n));
+        self.literal(id, &fake);
+        fake
+    }
+
+    /// Fake a host name or IP address. Subdomains keep their parent's
+    /// fake: `api.shop.com` → `sub-1.host-1.example.invalid`.
+    pub fn host(&mut self, host: &str) -> String {
+        let trimmed = host.trim().trim_end_matches('.');
+        let lower = trimmed.to_lowercase();
+        if lower.is_empty()
+            || lower == "localhost"
+            || self.issued.contains(&lower)
+            || lower.ends_with(&format!(".{}", FAKE_DOMAIN))
+        {
+            return trimmed.to_string();
+        }
+        if let Ok(ip) = lower.parse::<Ipv4Addr>() {
+            return self.ipv4(ip);
+        }
+        if let Ok(ip) = lower
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<Ipv6Addr>()
+        {
+            return self.ipv6(ip);
+        }
+
+        let labels: Vec<&str> = lower.split('.').collect();
+        let fake = if labels.len() < 2 {
+            self.fake(Kind::Host, &lower, |n| format!("host-{}", n))
+        } else {
+            let base = labels[labels.len() - 2..].join(".");
+            let mut fake = self.fake(Kind::Host, &base, |n| format!("host-{}.{}", n, FAKE_DOMAIN));
+            self.host_literal(&base, &fake);
+            for label in labels[..labels.len() - 2].iter().rev() {
+                let sub = self.fake(Kind::Label, label, |n| format!("sub-{}", n));
+                fake = format!("{}.{}", sub, fake);
+            }
+            self.issued.insert(fake.clone());
+            fake
+        };
+        self.host_literal(&lower, &fake);
+        fake
+    }
+
+    /// Loopback stays; others become addresses of the benchmark range
+    /// 198.18.0.0/15, which is never routed
+    fn ipv4(&mut self, ip: Ipv4Addr) -> String {
+        let original = ip.to_string();
+        if ip.is_loopback() || ip.is_unspecified() || self.issued.contains(&original) {
+            return original;
+        }
+        let fake = self.fake(Kind::Ipv4, &original, |n| {
+            Ipv4Addr::from(u32::from(Ipv4Addr::new(198, 18, 0, 0)) + n as u32).to_string()
+        });
+        self.literal(&original, &fake);
+        fake
+    }
+
+    /// Loopback stays; others become documentation addresses (2001:db8::/32)
+    fn ipv6(&mut self, ip: Ipv6Addr) -> String {
+        let original = ip.to_string();
+        if ip.is_loopback() || ip.is_unspecified() || self.issued.contains(&original) {
+            return original;
+        }
+        let fake = self.fake(Kind::Ipv6, &original, |n| {
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, n as u16).to_string()
+        });
+        self.literal(&original, &fake);
+        fake
+    }
+
+    /// Fake the host of a URL, keeping scheme and port. User info, path
+    /// and query are dropped (they hold names and tokens); a path shows as
+    /// `/path`. `git@host:owner/repo.git` remotes keep their shape.
+    pub fn url(&mut self, url: &str) -> String {
+        let Some((scheme, rest)) = url.split_once("://") else {
+            // scp-like git remote: user@host:path
+            if let Some((user_host, path)) = url.split_once(':') {
+                if let Some((user, host)) = user_host.split_once('@') {
+                    let repo = self.path(path);
+                    return format!(
+                        "{}@{}:{}",
+                        user,
+                        self.host(host),
+                        repo.trim_start_matches('/')
+                    );
+                }
+            }
+            return self.path(url);
+        };
+        if matches!(scheme, "unix" | "npipe" | "file") {
+            return format!("{}://{}", scheme, self.path(rest));
+        }
+
+        let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, tail) = rest.split_at(end);
+        let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        let (host, port) = match host_port.strip_prefix('[') {
+            Some(v6) => match v6.split_once(']') {
+                Some((host, port)) => (host, port),
+                None => (v6, ""),
+            },
+            None => match host_port.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => {
+                    (host, &host_port[host.len()..])
+                }
+                _ => (host_port, ""),
+            },
+        };
+        let fake_host = self.host(host);
+        let fake_host = if fake_host.contains(':') {
+            format!("[{}]", fake_host)
+        } else {
+            fake_host
+        };
+        let path = if tail.len() > 1 { "/path" } else { tail };
+        let fake = format!("{}://{}{}{}", scheme, fake_host, port, path);
+        self.issued.insert(fake.clone());
+        fake
+    }
+
+    /// Fake a path, keeping a leading `~` and the file extension
+    pub fn path(&mut self, path: &str) -> String {
+        if path.is_empty() || self.issued.contains(path) {
+            return path.to_string();
+        }
+        let home = if path.starts_with('~') { "~" } else { "" };
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| e.len() <= 8)
+            .map(|e| format!(".{}", e))
+            .unwrap_or_default();
+        let fake = self.fake(Kind::Path, path, |n| {
+            format!("{}/path/to/file-{}{}", home, n, extension)
+        });
+        self.literal(path, &fake);
+        fake
+    }
+
+    /// Fake the name of an entity of `kind`, e.g. `server-2`
+    pub fn name(&mut self, kind: &'static str, name: &str) -> String {
+        if name.is_empty() || self.issued.contains(name) {
+            return name.to_string();
+        }
+        let fake = self.fake(Kind::Name(kind), name, |n| format!("{}-{}", kind, n));
+        self.literal(name, &fake);
+        fake
+    }
+
+    fn email(&mut self, email: &str) -> String {
+        let lower = email.to_lowercase();
+        if self.issued.contains(&lower) {
+            return email.to_string();
+        }
+        let fake = self.fake(Kind::Email, &lower, |n| {
+            format!("user-{}@{}", n, FAKE_DOMAIN)
+        });
+        self.literal(&lower, &fake);
+        fake
+    }
+
+    /// Replace everything known or recognizable in free text: the faked
+    /// originals, then URLs, email and IP addresses
+    pub fn scrub(&mut self, text: &str) -> String {
+        let mut text = self.replace_literals(text);
+        text = replace_all(&self.url_re.clone(), &text, |m| self.url(m));
+        text = replace_all(&self.email_re.clone(), &text, |m| self.email(m));
+        text = replace_all(&self.ipv4_re.clone(), &text, |m| match m.parse() {
+            Ok(ip) => self.ipv4(ip),
+            Err(_) => m.to_string(),
+        });
+        replace_all(&self.ipv6_re.clone(), &text, |m| match m.parse() {
+            Ok(ip) => self.ipv6(ip),
+            Err(_) => m.to_string(),
+        })
+    }
+
+    /// Scrub a note and cut it to [`NOTE_LEN`] characters
+    pub fn note(&mut self, text: &str) -> String {
+        let scrubbed = self.scrub(text);
+        if scrubbed.chars().count() <= NOTE_LEN {
+            return scrubbed;
+        }
+        let mut cut: String = scrubbed.chars().take(NOTE_LEN).collect();
+        cut.push('…');
+        cut
+    }
+
+    /// Apply `rule` to a text value; `None` for [`Rule::Keep`] and
+    /// [`Rule::Secret`], which the caller handles
+    pub fn apply(&mut self, rule: Rule, value: &str) -> Option<String> {
+        Some(match rule {
+            Rule::Keep | Rule::Secret => return None,
+            Rule::Id => self.id(value),
+            Rule::Host => self.host(value),
+            Rule::Url => self.url(value),
+            Rule::Path => self.path(value),
+            Rule::Name(kind) => self.name(kind, value),
+            Rule::Note => self.note(value),
+            Rule::Text => self.scrub(value),
+        })
+    }
+
+    fn replace_literals(&mut self, text: &str) -> String {
+        if self.literals.is_empty() {
+            return text.to_string();
+        }
+        if self.literal_re.is_none() {
+            // Longest first, so "api.shop.com" wins over "shop.com"
+            let mut originals: Vec<&String> = self.literals.keys().collect();
+            originals.sort_by_key(|o| std::cmp::Reverse(o.len()));
+            let alternatives: Vec<String> = originals
+                .iter()
+                .map(|o| {
+                    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                    let subdomains = if self.host_literals.contains(*o) {
+                        r"(?:[a-z0-9-]+\.)*"
+                    } else {
+                        ""
+                    };
+                    format!(
+                        "{}{}{}{}",
+                        if word(o.chars().next()) { r"\b" } else { "" },
+                        subdomains,
+                        regex::escape(o),
+                        if word(o.chars().last()) { r"\b" } else { "" }
+                    )
+                })
+                .collect();
+            self.literal_re = Some(
+                RegexBuilder::new(&alternatives.join("|"))
+                    .case_insensitive(true)
+                    .size_limit(64 << 20)
+                    .build()
+                    .expect("escaped literals"),
+            );
+        }
+        let re = self.literal_re.clone().expect("built above");
+        replace_all(&re, text, |m| match self.literals.get(&m.to_lowercase()) {
+            Some(fake) => fake.clone(),
+            // A subdomain of a known host
+            None => self.host(m),
+        })
+    }
+}
+
+/// `re.replace_all` with a replacement that needs `&mut` access
+fn replace_all(re: &Regex, text: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for m in re.find_iter(text) {
+        out.push_str(&text[last..m.start()]);
+        out.push_str(&replace(m.as_str()));
+        last = m.end();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// What was rewritten in one table of the copy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableReport {
+    pub table: String,
+    pub rows: u64,
+    /// Values changed, by [`Rule::label`]
+    pub rewritten: BTreeMap<&'static str, u64>,
+}
@@ -0,0 +1,92 @@
+//! A project with its links and the entities they point to
+//!
+//! Loading the linked entities one by one costs a query per link. A bundle
+//! is loaded in a fixed number of queries instead: the project, its links,
+//! and one batch per entity type. Links store whatever the user typed, so
+//! the lookups here match an entity's ID or its name.
+
+use crate::{DatabaseCredentials, Domain, Project, ProjectResource, ResourceType, Script, Server};
+
+/// A project and everything its links resolve to
+#[derive(Debug, Clone)]
+pub struct ProjectBundle {
+    pub project: Project,
+    pub links: Vec<ProjectResource>,
+    pub servers: Vec<Server>,
+    pub domains: Vec<Domain>,
+    pub databases: Vec<DatabaseCredentials>,
+    pub scripts: Vec<Script>,
+}
+
+impl ProjectBundle {
+    /// Links of one resource type
+    pub fn links_of(&self, kind: ResourceType) -> impl Iterator<Item = &ProjectResource> {
+        self.links.iter().filter(move |l| l.resource_type == kind)
+    }
+
+    /// Linked server by ID or name
+    pub fn server(&self, reference: &str) -> Option<&Server> {
+        self.servers
+            .iter()
+            .find(|s| s.id == reference || s.name.eq_ignore_ascii_case(reference))
+    }
+
+    /// Linked domain by ID or domain name
+    pub fn domain(&self, reference: &str) -> Option<&Domain> {
+        self.domains
+            .iter()
+            .find(|d| d.id == reference || d.domain.eq_ignore_ascii_case(reference))
+    }
+
+    /// Linked database by ID or name
+    pub fn database(&self, reference: &str) -> Option<&DatabaseCredentials> {
+        self.databases
+            .iter()
+            .find(|d| d.id == reference || d.name.eq_ignore_ascii_case(reference))
+    }
+
+    /// Linked script by ID or name
+    pub fn script(&self, reference: &str) -> Option<&Script> {
+        self.scripts
+            .iter()
+            .find(|s| s.id == reference || s.name.eq_ignore_ascii_case(reference))
+    }
+
+    /// Name of the entity a link points to, if it is stored in pctrl and
+    /// still exists. Containers, git repos and Coolify links have none.
+    pub fn name_of(&self, link: &ProjectResource) -> Option<&str> {
+        let id = link.resource_id.as_str();
+        match link.resource_type {
+            ResourceType::Server => self.server(id).map(|s| s.name.as_str()),
+            ResourceType::Domain => self.domain(id).map(|d| d.domain.as_str()),
+            ResourceType::Database => self.database(id).map(|d| d.name.as_str()),
+            ResourceType::Script => self.script(id).map(|s| s.name.as_str()),
+            ResourceType::Container | ResourceType::Git | ResourceType::Coolify => None,
+        }
+    }
+
+    /// Whether a link points to a server, domain, database or script that
+    /// no longer exists
+    pub fn is_dangling(&self, link: &ProjectResource) -> bool {
+        let stored = matches!(
+            link.resource_type,
+            ResourceType::Server
+                | ResourceType::Domain
+                | ResourceType::Database
+                | ResourceType::Script
+        );
+        stored && self.name_of(link).is_none()
+    }
+}
+
+/// Distinct references of the links of one type, for a batch lookup
+pub fn link_refs(links: &[ProjectResource], kind: ResourceType) -> Vec<&str> {
+    let mut refs: Vec<&str> = links
+        .iter()
+        .filter(|l| l.resource_type == kind)
+        .map(|l| l.resource_id.as_str())
+        .collect();
+    refs.sort_unstable();
+    refs.dedup();
+    refs
+}
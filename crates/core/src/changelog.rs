@@ -0,0 +1,130 @@
+//! Changelogs from commit summaries (`pctrl git changelog`)
+//!
+//! A plain changelog lists the commits as they are. A conventional one
+//! groups them by their Conventional Commits type (`feat(cli)!: ...`):
+//! features, fixes and chores (chore, build, ci, docs, refactor, style,
+//! test, perf); summaries without a known type land under "Other".
+
+/// A group of a conventional changelog, in the order they're rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Features,
+    Fixes,
+    Chores,
+    Other,
+}
+
+impl Section {
+    pub fn title(self) -> &'static str {
+        match self {
+            Section::Features => "Features",
+            Section::Fixes => "Fixes",
+            Section::Chores => "Chores",
+            Section::Other => "Other",
+        }
+    }
+}
+
+/// A commit summary read as a conventional commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub section: Section,
+    pub scope: Option<String>,
+    /// `!` after the type or scope
+    pub breaking: bool,
+    /// The summary after `type(scope):`; the whole summary under Other
+    pub description: String,
+}
+
+/// Read `feat(scope)!: description`; anything else is Other, as is
+pub fn parse(summary: &str) -> Change {
+    let other = Change {
+        section: Section::Other,
+        scope: None,
+        breaking: false,
+        description: summary.trim().to_string(),
+    };
+    let Some((head, description)) = summary.split_once(':') else {
+        return other;
+    };
+    let description = description.trim();
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, scope)) => match scope.strip_suffix(')') {
+            Some(scope) if !scope.is_empty() => (kind, Some(scope.to_string())),
+            _ => return other,
+        },
+        None => (head, None),
+    };
+    let section = match kind.to_ascii_lowercase().as_str() {
+        "feat" | "feature" => Section::Features,
+        "fix" => Section::Fixes,
+        "chore" | "build" | "ci" | "docs" | "refactor" | "style" | "test" | "perf" => {
+            Section::Chores
+        }
+        _ => return other,
+    };
+    if description.is_empty() {
+        return other;
+    }
+    Change {
+        section,
+        scope,
+        breaking,
+        description: description.to_string(),
+    }
+}
+
+/// Markdown for `version`: a `## version (date)` heading, then one bullet
+/// per commit (short hash, summary), oldest first as given; grouped under
+/// `### Features` etc. when `conventional`
+pub fn render(version: &str, date: &str, commits: &[(&str, &str)], conventional: bool) -> String {
+    let mut out = format!("## {} ({})\n", version, date);
+    if commits.is_empty() {
+        out.push_str("\nNo changes.\n");
+        return out;
+    }
+    if !conventional {
+        out.push('\n');
+        for (hash, summary) in commits {
+            out.push_str(&format!("- {} ({})\n", summary.trim(), hash));
+        }
+        return out;
+    }
+
+    let changes: Vec<(Change, &str)> = commits
+        .iter()
+        .map(|(hash, summary)| (parse(summary), *hash))
+        .collect();
+    for section in [
+        Section::Features,
+        Section::Fixes,
+        Section::Chores,
+        Section::Other,
+    ] {
+        let entries: Vec<&(Change, &str)> = changes
+            .iter()
+            .filter(|(change, _)| change.section == section)
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n### {}\n\n", section.title()));
+        for (change, hash) in entries {
+            let breaking = if change.breaking { "**BREAKING** " } else { "" };
+            let scope = change
+                .scope
+                .as_deref()
+                .map(|s| format!("**{}:** ", s))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "- {}{}{} ({})\n",
+                breaking, scope, change.description, hash
+            ));
+        }
+    }
+    out
+}
@@ -0,0 +1,287 @@
+//! Columns of list output (`pctrl server list --columns/--filter/--sort`)
+//!
+//! A column spec is a built-in field (one of the table's export columns,
+//! see [`crate::table_export`]), `meta.<key>` for a metadata entry of the
+//! entity or `fact.<key>` for a collected host fact. Projection, filtering
+//! and sorting all go through [`ColumnExpr`], so a spec means the same in
+//! all three: a missing value prints as [`MISSING`], `--filter key=-`
+//! matches it, and it sorts after every present value.
+
+use crate::table_export::{cell, Column, ExportTable};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How a missing value is printed (and matched by filters)
+pub const MISSING: &str = "-";
+
+const META_PREFIX: &str = "meta.";
+const FACT_PREFIX: &str = "fact.";
+
+/// Where a column's value comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnExpr {
+    Field(&'static Column),
+    Meta(String),
+    Fact(String),
+}
+
+/// Metadata and fact keys that exist, to validate specs against
+#[derive(Debug, Clone, Default)]
+pub struct KnownKeys {
+    pub meta: BTreeSet<String>,
+    pub facts: BTreeSet<String>,
+}
+
+/// One entity with everything a column may read
+#[derive(Debug, Clone, Default)]
+pub struct ListRow {
+    pub id: String,
+    /// The entity flattened like an export row ([`crate::table_export::flatten`])
+    pub fields: Map<String, Value>,
+    pub meta: BTreeMap<String, String>,
+    pub facts: BTreeMap<String, String>,
+}
+
+/// Whether a metadata key is usable in a spec: letters, digits, `_` and `-`
+pub fn valid_meta_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl ColumnExpr {
+    /// Resolve a spec. Fields are matched case-insensitively; secret
+    /// columns and keys not in `known` are errors listing what is there.
+    /// Facts exist for servers only.
+    pub fn parse(table: ExportTable, spec: &str, known: &KnownKeys) -> Result<Self, String> {
+        let spec = spec.trim();
+        let listing = |keys: &BTreeSet<String>| {
+            if keys.is_empty() {
+                "none yet".to_string()
+            } else {
+                keys.iter().cloned().collect::<Vec<_>>().join(", ")
+            }
+        };
+
+        if let Some(key) = spec.strip_prefix(META_PREFIX) {
+            return if known.meta.contains(key) {
+                Ok(ColumnExpr::Meta(key.to_string()))
+            } else {
+                Err(format!(
+                    "No {} has metadata '{}' (keys: {})",
+                    table.name().trim_end_matches('s'),
+                    key,
+                    listing(&known.meta)
+                ))
+            };
+        }
+        if let Some(key) = spec.strip_prefix(FACT_PREFIX) {
+            if table != ExportTable::Servers {
+                return Err(format!(
+                    "Facts are only collected for servers, not {}",
+                    table
+                ));
+            }
+            return if known.facts.contains(key) {
+                Ok(ColumnExpr::Fact(key.to_string()))
+            } else {
+                Err(format!(
+                    "No server has fact '{}' (keys: {})",
+                    key,
+                    listing(&known.facts)
+                ))
+            };
+        }
+
+        match table.column(spec) {
+            Some(column) if column.is_secret() => Err(format!(
+                "Column '{}' holds secrets and can't be listed",
+                column.name
+            )),
+            Some(column) => Ok(ColumnExpr::Field(column)),
+            None => {
+                let names: Vec<&str> = table
+                    .columns()
+                    .iter()
+                    .filter(|c| !c.is_secret())
+                    .map(|c| c.name)
+                    .collect();
+                Err(format!(
+                    "Unknown column '{}' in {} (columns: {}, or meta.<key> / fact.<key>)",
+                    spec,
+                    table,
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+
+    /// Header text: the spec as written in canonical form
+    pub fn label(&self) -> String {
+        match self {
+            ColumnExpr::Field(column) => column.name.to_string(),
+            ColumnExpr::Meta(key) => format!("{}{}", META_PREFIX, key),
+            ColumnExpr::Fact(key) => format!("{}{}", FACT_PREFIX, key),
+        }
+    }
+
+    /// The value in a row; `None` when missing or empty
+    pub fn value(&self, row: &ListRow) -> Option<String> {
+        let value = match self {
+            ColumnExpr::Field(column) => cell(row.fields.get(column.name)),
+            ColumnExpr::Meta(key) => row.meta.get(key).cloned().unwrap_or_default(),
+            ColumnExpr::Fact(key) => row.facts.get(key).cloned().unwrap_or_default(),
+        };
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// Order of two values: numbers numerically, anything else
+/// case-insensitively; missing values last
+pub fn compare_values(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => a.to_lowercase().cmp(&b.to_lowercase()),
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    expr: ColumnExpr,
+    negated: bool,
+    value: String,
+}
+
+#[derive(Debug, Clone)]
+struct SortKey {
+    expr: ColumnExpr,
+    descending: bool,
+}
+
+/// Parsed `--columns`, `--filter` and `--sort` of one list
+#[derive(Debug, Clone)]
+pub struct ListQuery {
+    pub columns: Vec<ColumnExpr>,
+    conditions: Vec<Condition>,
+    sort: Vec<SortKey>,
+}
+
+impl ListQuery {
+    /// Filters are `spec=value` or `spec!=value`, compared
+    /// case-insensitively, and must all hold. Sort keys apply in order; a
+    /// leading `-` sorts descending, but missing values still come last.
+    pub fn new(
+        table: ExportTable,
+        columns: &[String],
+        filters: &[String],
+        sort: &[String],
+        known: &KnownKeys,
+    ) -> Result<Self, String> {
+        let columns = columns
+            .iter()
+            .map(|spec| ColumnExpr::parse(table, spec, known))
+            .collect::<Result<Vec<_>, _>>()?;
+        let conditions = filters
+            .iter()
+            .map(|filter| {
+                let (spec, value, negated) = match filter.split_once("!=") {
+                    Some((spec, value)) => (spec, value, true),
+                    None => {
+                        let (spec, value) = filter.split_once('=').ok_or_else(|| {
+                            format!("Expected key=value or key!=value, got '{}'", filter)
+                        })?;
+                        (spec, value, false)
+                    }
+                };
+                Ok(Condition {
+                    expr: ColumnExpr::parse(table, spec, known)?,
+                    negated,
+                    value: value.trim().to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let sort = sort
+            .iter()
+            .map(|spec| {
+                let spec = spec.trim();
+                let (spec, descending) = match spec.strip_prefix('-') {
+                    Some(rest) => (rest, true),
+                    None => (spec, false),
+                };
+                Ok(SortKey {
+                    expr: ColumnExpr::parse(table, spec, known)?,
+                    descending,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            columns,
+            conditions,
+            sort,
+        })
+    }
+
+    fn exprs(&self) -> impl Iterator<Item = &ColumnExpr> {
+        self.columns
+            .iter()
+            .chain(self.conditions.iter().map(|c| &c.expr))
+            .chain(self.sort.iter().map(|s| &s.expr))
+    }
+
+    /// Whether any spec reads metadata, so it has to be loaded
+    pub fn needs_meta(&self) -> bool {
+        self.exprs().any(|e| matches!(e, ColumnExpr::Meta(_)))
+    }
+
+    /// Whether any spec reads facts, so they have to be loaded
+    pub fn needs_facts(&self) -> bool {
+        self.exprs().any(|e| matches!(e, ColumnExpr::Fact(_)))
+    }
+
+    pub fn matches(&self, row: &ListRow) -> bool {
+        self.conditions.iter().all(|condition| {
+            let value = condition.expr.value(row);
+            let equal = value
+                .as_deref()
+                .unwrap_or(MISSING)
+                .eq_ignore_ascii_case(&condition.value);
+            equal != condition.negated
+        })
+    }
+
+    /// Rows that match, sorted; the sort is stable, so rows equal in every
+    /// sort key keep their incoming order
+    pub fn apply(&self, rows: Vec<ListRow>) -> Vec<ListRow> {
+        let mut rows: Vec<ListRow> = rows.into_iter().filter(|r| self.matches(r)).collect();
+        rows.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .map(|key| {
+                    let (x, y) = (key.expr.value(a), key.expr.value(b));
+                    match (&x, &y, key.descending) {
+                        (Some(_), Some(_), true) => compare_values(y.as_deref(), x.as_deref()),
+                        _ => compare_values(x.as_deref(), y.as_deref()),
+                    }
+                })
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        rows
+    }
+
+    /// The selected values of a row, missing ones as [`MISSING`]
+    pub fn cells(&self, row: &ListRow) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|expr| expr.value(row).unwrap_or_else(|| MISSING.to_string()))
+            .collect()
+    }
+}
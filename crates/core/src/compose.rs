@@ -0,0 +1,201 @@
+//! Docker Compose stacks of a server (`pctrl compose`)
+//!
+//! Compose labels every container it starts with its project and service,
+//! and the project with the files it came from. Grouping a server's
+//! containers by those labels gives its stacks; containers without them
+//! are grouped under [`STANDALONE`]. A stack recorded earlier whose
+//! containers are all gone is kept as [`ComposeState::Gone`] until pruned.
+
+use crate::proxy_labels::Labels;
+use crate::{Container, ContainerStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Compose project a container belongs to
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// Comma-separated compose files the project was started from
+pub const CONFIG_FILES_LABEL: &str = "com.docker.compose.project.config_files";
+
+/// Service of the compose project the container runs
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// Name of the group of containers without compose labels
+pub const STANDALONE: &str = "(standalone)";
+
+/// State of a stack from its services
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComposeState {
+    /// Every service has a running container
+    Up,
+    /// Some services run, some don't
+    Partial,
+    /// No service runs
+    Down,
+    /// No containers left on the server
+    Gone,
+}
+
+impl fmt::Display for ComposeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ComposeState::Up => "up",
+            ComposeState::Partial => "partial",
+            ComposeState::Down => "down",
+            ComposeState::Gone => "gone",
+        })
+    }
+}
+
+impl std::str::FromStr for ComposeState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(ComposeState::Up),
+            "partial" => Ok(ComposeState::Partial),
+            "down" => Ok(ComposeState::Down),
+            "gone" => Ok(ComposeState::Gone),
+            other => Err(format!("Unknown compose state: {}", other)),
+        }
+    }
+}
+
+/// A compose stack on a server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComposeProject {
+    pub server_id: String,
+    /// Compose project name, or [`STANDALONE`]
+    pub name: String,
+    /// First compose file of the project
+    pub config_path: Option<String>,
+    pub services: u32,
+    /// Services with a running container
+    pub running: u32,
+    pub state: ComposeState,
+}
+
+impl ComposeProject {
+    pub fn is_standalone(&self) -> bool {
+        self.name == STANDALONE
+    }
+}
+
+/// Labels of a recorded container
+pub fn labels(container: &Container) -> Labels {
+    container
+        .labels
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+/// The stack a container belongs to: its compose project, else
+/// [`STANDALONE`]
+pub fn stack_of(container: &Container) -> String {
+    labels(container)
+        .get(PROJECT_LABEL)
+        .filter(|project| !project.is_empty())
+        .cloned()
+        .unwrap_or_else(|| STANDALONE.to_string())
+}
+
+/// State of a stack with `running` of `services` services up
+pub fn state(services: u32, running: u32) -> ComposeState {
+    if services == 0 {
+        ComposeState::Gone
+    } else if running == 0 {
+        ComposeState::Down
+    } else if running < services {
+        ComposeState::Partial
+    } else {
+        ComposeState::Up
+    }
+}
+
+/// The stacks of a server's containers, by name with [`STANDALONE`] last.
+/// A service counts once however many replicas it has; a standalone
+/// container counts as a service of its own.
+pub fn group(server_id: &str, containers: &[Container]) -> Vec<ComposeProject> {
+    #[derive(Default)]
+    struct Stack {
+        config_path: Option<String>,
+        services: BTreeSet<String>,
+        running: BTreeSet<String>,
+    }
+
+    let mut stacks: BTreeMap<String, Stack> = BTreeMap::new();
+    for container in containers {
+        let labels = labels(container);
+        let name = stack_of(container);
+        let stack = stacks.entry(name.clone()).or_default();
+        let service = match labels.get(SERVICE_LABEL) {
+            Some(service) if name != STANDALONE => service.clone(),
+            _ => container.name.clone(),
+        };
+        if stack.config_path.is_none() {
+            stack.config_path = labels
+                .get(CONFIG_FILES_LABEL)
+                .and_then(|files| files.split(',').next())
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string);
+        }
+        if container.status == ContainerStatus::Running {
+            stack.running.insert(service.clone());
+        }
+        stack.services.insert(service);
+    }
+
+    let mut projects: Vec<ComposeProject> = stacks
+        .into_iter()
+        .map(|(name, stack)| {
+            let (services, running) = (stack.services.len() as u32, stack.running.len() as u32);
+            ComposeProject {
+                server_id: server_id.to_string(),
+                name,
+                config_path: stack.config_path,
+                services,
+                running,
+                state: state(services, running),
+            }
+        })
+        .collect();
+    // Standalone sorts after every stack name
+    projects.sort_by_key(|p| (p.is_standalone(), p.name.clone()));
+    projects
+}
+
+/// The stacks to record after a discovery: the current ones, plus earlier
+/// stacks no container belongs to anymore as [`ComposeState::Gone`] unless
+/// `prune` drops them. A gone standalone group isn't kept.
+pub fn reconcile(
+    recorded: &[ComposeProject],
+    current: Vec<ComposeProject>,
+    prune: bool,
+) -> Vec<ComposeProject> {
+    let mut stacks = current;
+    if !prune {
+        for stack in recorded {
+            if stack.is_standalone() || stacks.iter().any(|s| s.name == stack.name) {
+                continue;
+            }
+            stacks.push(ComposeProject {
+                running: 0,
+                state: ComposeState::Gone,
+                ..stack.clone()
+            });
+        }
+    }
+    stacks.sort_by_key(|p| (p.is_standalone(), p.name.clone()));
+    stacks
+}
+
+/// The containers of stack `name`, by name
+pub fn containers_of<'a>(containers: &'a [Container], name: &str) -> Vec<&'a Container> {
+    let mut members: Vec<&Container> = containers.iter().filter(|c| stack_of(c) == name).collect();
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    members
+}
@@ -0,0 +1,328 @@
+//! The whole setup as one portable document (`pctrl export --file`,
+//! `pctrl import`)
+//!
+//! A [`ConfigDocument`] holds projects, servers, domains, databases,
+//! scripts, credentials and project links with their IDs, as YAML or JSON.
+//! Hand-written documents may also be TOML with includes and variables
+//! (see [`crate::document`]), read with [`ConfigDocument::from_table`].
+//! Without secrets, credential data and database passwords and connection
+//! strings are left out; importing such a document keeps the secrets of
+//! entities that already exist ([`keep_local_secrets`]). Importing goes
+//! through [`crate::merge`]: the document is the other side. Snapshot
+//! restores go the same way, with the snapshot as a document without
+//! secrets ([`ConfigDocument::from_inventory`]).
+//!
+//! Secret fields may hold placeholders (`@prompt`, `@env:NAME`,
+//! `@file:PATH`, see [`crate::placeholder`]); [`ConfigDocument::placeholders`]
+//! collects them and [`ConfigDocument::fill_secrets`] puts the resolved
+//! values in before anything is compared or written.
+
+use crate::merge::{Class, Comparison, Record};
+use crate::placeholder::{self, Placeholders, ResolvedSecrets};
+use crate::{
+    Credential, CredentialData, CredentialType, DatabaseCredentials, Domain, EntityType, Inventory,
+    Project, ProjectResource, Script, Server,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// Version of the document layout; newer documents are refused
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Fields left out of documents without secrets, by entity type
+const SECRETS: &[(EntityType, &str)] = &[
+    (EntityType::Credential, "data"),
+    (EntityType::Database, "password"),
+    (EntityType::Database, "connection_string"),
+];
+
+/// Fields of credential data that may hold a secret placeholder
+const DATA_SECRETS: &[&str] = &[
+    "passphrase",
+    "token",
+    "password",
+    "access_token",
+    "refresh_token",
+];
+
+/// A credential; `data` is missing when the document has no secrets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedCredential {
+    pub id: String,
+    pub name: String,
+    pub credential_type: CredentialType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<CredentialData>,
+    pub notes: Option<String>,
+}
+
+impl ExportedCredential {
+    pub fn new(credential: Credential, include_secrets: bool) -> Self {
+        Self {
+            id: credential.id,
+            name: credential.name,
+            credential_type: credential.credential_type,
+            data: include_secrets.then_some(credential.data),
+            notes: credential.notes,
+        }
+    }
+}
+
+/// Everything pctrl manages, ready to be moved to another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigDocument {
+    pub format: u32,
+    #[serde(default)]
+    pub pctrl_version: String,
+    /// RFC 3339, UTC; empty for a hand-written document
+    #[serde(default)]
+    pub exported_at: String,
+    /// Whether credential data and database secrets are included
+    #[serde(default)]
+    pub secrets: bool,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub servers: Vec<Server>,
+    #[serde(default)]
+    pub domains: Vec<Domain>,
+    #[serde(default)]
+    pub databases: Vec<DatabaseCredentials>,
+    #[serde(default)]
+    pub scripts: Vec<Script>,
+    #[serde(default)]
+    pub credentials: Vec<ExportedCredential>,
+    #[serde(default)]
+    pub links: Vec<ProjectResource>,
+}
+
+/// How a document is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Yaml,
+    Json,
+}
+
+impl DocumentFormat {
+    /// JSON for a `.json` file, YAML for anything else
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => DocumentFormat::Json,
+            _ => DocumentFormat::Yaml,
+        }
+    }
+}
+
+impl fmt::Display for DocumentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentFormat::Yaml => write!(f, "YAML"),
+            DocumentFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+impl ConfigDocument {
+    /// An inventory (e.g. a snapshot) as a document without secrets
+    pub fn from_inventory(inventory: Inventory, exported_at: &str) -> Self {
+        Self {
+            format: FORMAT_VERSION,
+            pctrl_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: exported_at.to_string(),
+            secrets: false,
+            projects: inventory.projects,
+            servers: inventory.servers,
+            domains: inventory.domains,
+            databases: inventory.databases,
+            scripts: inventory.scripts,
+            credentials: inventory
+                .credentials
+                .into_iter()
+                .map(|c| ExportedCredential {
+                    id: c.id,
+                    name: c.name,
+                    credential_type: c.credential_type,
+                    data: None,
+                    notes: c.notes,
+                })
+                .collect(),
+            links: inventory.links,
+        }
+    }
+
+    pub fn render(&self, format: DocumentFormat) -> Result<String, String> {
+        match format {
+            DocumentFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            DocumentFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Read a document in either format (JSON is read as YAML). The format
+    /// version is checked before anything else, so a newer document gets
+    /// that error rather than a confusing field error.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let value: serde_yaml::Value =
+            serde_yaml::from_str(text).map_err(|e| format!("Not a pctrl export: {}", e))?;
+        check_format(value.get("format").and_then(serde_yaml::Value::as_u64))?;
+        serde_yaml::from_value(value).map_err(|e| format!("Not a pctrl export: {}", e))
+    }
+
+    /// A document resolved from TOML files by [`crate::document::resolve`].
+    /// `pctrl_version`, `exported_at` and `secrets` may be left out.
+    pub fn from_table(table: toml::Table) -> Result<Self, String> {
+        check_format(
+            table
+                .get("format")
+                .and_then(toml::Value::as_integer)
+                .and_then(|f| u64::try_from(f).ok()),
+        )?;
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| format!("Not a pctrl document: {}", e))
+    }
+
+    pub fn count(&self) -> usize {
+        self.projects.len()
+            + self.servers.len()
+            + self.domains.len()
+            + self.databases.len()
+            + self.scripts.len()
+            + self.credentials.len()
+            + self.links.len()
+    }
+
+    /// Collect the secret placeholders, e.g. `password: "@prompt"` of a
+    /// database. Escaped literals (`@@prompt`) lose their first `@`.
+    pub fn placeholders(&mut self) -> Result<Placeholders, String> {
+        let mut placeholders = Placeholders::default();
+        self.each_secret(|entity, field, value| {
+            if let Some(literal) = placeholders.add(entity, field, value) {
+                *value = literal;
+            }
+        })?;
+        Ok(placeholders)
+    }
+
+    /// Escape secrets that would read as placeholders, for a document
+    /// written with secrets
+    pub fn escape_secrets(&mut self) -> Result<(), String> {
+        self.each_secret(|_, _, value| *value = placeholder::escape(value))
+    }
+
+    /// Put resolved secrets in place of their placeholders
+    pub fn fill_secrets(&mut self, secrets: &ResolvedSecrets) -> Result<(), String> {
+        self.each_secret(|entity, field, value| {
+            if let Some(secret) = secrets.get(entity, field) {
+                *value = secret.to_string();
+            }
+        })
+    }
+
+    /// Call `f` with the entity ("credential 'deploy'"), field and value of
+    /// every secret field that is set
+    fn each_secret(&mut self, mut f: impl FnMut(&str, &str, &mut String)) -> Result<(), String> {
+        for credential in &mut self.credentials {
+            let Some(data) = &mut credential.data else {
+                continue;
+            };
+            let entity = format!("credential '{}'", credential.name);
+            let mut value = serde_json::to_value(&*data).map_err(|e| e.to_string())?;
+            if let Some(fields) = value.as_object_mut() {
+                for field in DATA_SECRETS {
+                    if let Some(Value::String(secret)) = fields.get_mut(*field) {
+                        f(&entity, field, secret);
+                    }
+                }
+            }
+            *data = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        }
+        for database in &mut self.databases {
+            let entity = format!("database '{}'", database.name);
+            let fields = [
+                ("password", &mut database.password),
+                ("connection_string", &mut database.connection_string),
+            ];
+            for (field, secret) in fields {
+                if let Some(secret) = secret {
+                    f(&entity, field, secret);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The document's entities as merge records. Credentials without data
+    /// have no `data` field.
+    pub fn records(&self) -> Result<Vec<Record>, String> {
+        fn add<T: Serialize>(
+            records: &mut Vec<Record>,
+            entity_type: EntityType,
+            entities: &[T],
+        ) -> Result<(), String> {
+            for entity in entities {
+                records.push(Record::from_entity(entity_type, entity, None)?);
+            }
+            Ok(())
+        }
+
+        let mut records = Vec::new();
+        add(&mut records, EntityType::Credential, &self.credentials)?;
+        add(&mut records, EntityType::Server, &self.servers)?;
+        add(&mut records, EntityType::Project, &self.projects)?;
+        add(&mut records, EntityType::Domain, &self.domains)?;
+        add(&mut records, EntityType::Database, &self.databases)?;
+        add(&mut records, EntityType::Script, &self.scripts)?;
+        Ok(records)
+    }
+}
+
+/// Refuse documents without a format version or from a newer pctrl
+fn check_format(format: Option<u64>) -> Result<(), String> {
+    let format = format.ok_or("Not a pctrl export: no format version")?;
+    if format > FORMAT_VERSION as u64 {
+        return Err(format!(
+            "Written by a newer pctrl (format {}, this pctrl reads up to {})",
+            format, FORMAT_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a document record lacks its secrets: a credential without data
+pub fn lacks_secrets(record: &Record) -> bool {
+    record.entity_type == EntityType::Credential && !record.fields.contains_key("data")
+}
+
+/// For a document without secrets: give matched records the local
+/// secrets where the document has none, so missing secrets are neither a
+/// conflict nor overwritten. Secrets the document does have (resolved
+/// placeholders) are kept.
+pub fn keep_local_secrets(comparison: &mut Comparison) {
+    for entry in &mut comparison.entries {
+        let (Some(local), Some(other)) = (&entry.local, &mut entry.other) else {
+            continue;
+        };
+        for (_, field) in SECRETS
+            .iter()
+            .filter(|(entity_type, _)| *entity_type == local.entity_type)
+        {
+            if other.fields.get(*field).is_some_and(|v| !v.is_null()) {
+                continue;
+            }
+            match local.fields.get(*field) {
+                Some(value) => other.fields.insert(field.to_string(), value.clone()),
+                None => other.fields.remove(*field),
+            };
+        }
+        let changed = local.differences(other);
+        entry.class = if changed.is_empty() {
+            Class::Identical
+        } else {
+            Class::Conflict(changed)
+        };
+    }
+}
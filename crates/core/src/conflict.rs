@@ -0,0 +1,361 @@
+//! Deciding conflicts one by one (`pctrl merge`, `pctrl import`,
+//! `pctrl snapshot restore`)
+//!
+//! A [`Conflict`] is an entity whose values here and in the incoming data
+//! differ, with the differing fields from [`crate::diff`]. [`Resolver`]
+//! walks a list of them as a state machine: every conflict is kept, taken,
+//! skipped or merged field by field, and a keep, take or skip can be
+//! remembered for the remaining conflicts of the same kind. Front ends
+//! drive it through a [`Prompter`]; each operation turns the resulting
+//! [`Decision`]s into its own plan.
+
+use crate::diff::{diff_json, display_value, FieldChange};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Which side of a conflict a value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Local,
+    Incoming,
+}
+
+/// One entity that differs between here and the incoming data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// "server", "project", ..., "link"
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub local: Value,
+    pub incoming: Value,
+    /// Differing fields, dotted for nested ones; secret values are masked
+    pub fields: Vec<FieldChange>,
+}
+
+impl Conflict {
+    /// Conflict between two versions of an entity; differences in the
+    /// `ignore`d top-level fields don't count
+    pub fn new(
+        kind: &str,
+        id: &str,
+        name: &str,
+        local: Value,
+        incoming: Value,
+        ignore: &[&str],
+    ) -> Self {
+        let fields = diff_json(&local, &incoming)
+            .into_iter()
+            .filter(|change| {
+                let top = change.field.split('.').next().unwrap_or_default();
+                !ignore.contains(&top)
+            })
+            .collect();
+        Self {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            local,
+            incoming,
+            fields,
+        }
+    }
+
+    /// The local entity with the fields picked from the incoming side
+    pub fn merged(&self, picks: &BTreeMap<String, Side>) -> Value {
+        let mut merged = self.local.clone();
+        for (field, side) in picks {
+            if *side == Side::Incoming {
+                let value = lookup(&self.incoming, field)
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                assign(&mut merged, field, value);
+            }
+        }
+        merged
+    }
+}
+
+/// Differing fields as three columns, field, local and incoming, with a
+/// header line; long values are cut to `max_width` characters
+pub fn side_by_side(conflict: &Conflict, max_width: usize) -> String {
+    let cut = |value: &Value| {
+        let text = display_value(value);
+        if text.chars().count() > max_width {
+            let mut cut: String = text.chars().take(max_width.saturating_sub(1)).collect();
+            cut.push('…');
+            cut
+        } else {
+            text
+        }
+    };
+    let rows: Vec<(String, String, String)> = conflict
+        .fields
+        .iter()
+        .map(|f| (f.field.clone(), cut(&f.old), cut(&f.new)))
+        .collect();
+    let width = |column: fn(&(String, String, String)) -> &String, header: &str| {
+        rows.iter()
+            .map(|row| column(row).chars().count())
+            .chain([header.chars().count()])
+            .max()
+            .unwrap_or(0)
+    };
+    let (field_width, local_width) = (width(|r| &r.0, "field"), width(|r| &r.1, "local"));
+
+    let mut out = format!(
+        "{:<fw$}  {:<lw$}  incoming\n",
+        "field",
+        "local",
+        fw = field_width,
+        lw = local_width
+    );
+    for (field, local, incoming) in rows {
+        out.push_str(&format!(
+            "{:<fw$}  {:<lw$}  {}\n",
+            field,
+            local,
+            incoming,
+            fw = field_width,
+            lw = local_width
+        ));
+    }
+    out
+}
+
+/// The value at a dotted path
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// Set the value at a dotted path, creating objects on the way
+fn assign(value: &mut Value, path: &str, new: Value) {
+    let mut current = value;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let object = current.as_object_mut().expect("just made an object");
+        if keys.peek().is_none() {
+            object.insert(key.to_string(), new);
+            return;
+        }
+        current = object.entry(key.to_string()).or_insert(Value::Null);
+    }
+}
+
+/// What happens to one conflict
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    KeepLocal,
+    TakeIncoming,
+    /// The side picked for every differing field
+    Fields(BTreeMap<String, Side>),
+    /// Left undecided; the operation doesn't touch the entity
+    Skip,
+}
+
+/// An answer that settles a conflict as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    KeepLocal,
+    TakeIncoming,
+    Skip,
+}
+
+impl From<Choice> for Decision {
+    fn from(choice: Choice) -> Self {
+        match choice {
+            Choice::KeepLocal => Decision::KeepLocal,
+            Choice::TakeIncoming => Decision::TakeIncoming,
+            Choice::Skip => Decision::Skip,
+        }
+    }
+}
+
+/// Input to the resolver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// Settle this conflict
+    Choose(Choice),
+    /// Settle this and every remaining conflict of its kind
+    ChooseForKind(Choice),
+    /// Go through the differing fields one by one
+    MergeFields,
+    /// The side of the field being asked about
+    Field(Side),
+    Quit,
+}
+
+/// What the resolver asks next
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prompt<'a> {
+    Conflict {
+        conflict: &'a Conflict,
+        /// 1-based, of `total`
+        position: usize,
+        total: usize,
+    },
+    Field {
+        conflict: &'a Conflict,
+        field: &'a FieldChange,
+        /// 1-based, of the conflict's fields
+        position: usize,
+    },
+}
+
+/// An answer that doesn't fit the current prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAnswer(pub String);
+
+impl fmt::Display for InvalidAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Walks conflicts in order: [`Resolver::prompt`] says what to ask,
+/// [`Resolver::answer`] takes the reply
+#[derive(Debug)]
+pub struct Resolver<'a> {
+    conflicts: &'a [Conflict],
+    decisions: Vec<Option<Decision>>,
+    remembered: HashMap<String, Choice>,
+    current: usize,
+    /// Picks so far while merging the current conflict field by field
+    picks: Option<BTreeMap<String, Side>>,
+    quit: bool,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(conflicts: &'a [Conflict]) -> Self {
+        Self {
+            conflicts,
+            decisions: vec![None; conflicts.len()],
+            remembered: HashMap::new(),
+            current: 0,
+            picks: None,
+            quit: false,
+        }
+    }
+
+    /// The next question, or `None` when every conflict is decided or the
+    /// user quit. Conflicts of a kind with a remembered choice are decided
+    /// on the way without asking.
+    pub fn prompt(&mut self) -> Option<Prompt<'a>> {
+        if self.quit {
+            return None;
+        }
+        while let Some(conflict) = self.conflicts.get(self.current) {
+            if let Some(picks) = &self.picks {
+                let field = &conflict.fields[picks.len()];
+                return Some(Prompt::Field {
+                    conflict,
+                    field,
+                    position: picks.len() + 1,
+                });
+            }
+            match self.remembered.get(&conflict.kind) {
+                Some(choice) => self.decide((*choice).into()),
+                None => {
+                    return Some(Prompt::Conflict {
+                        conflict,
+                        position: self.current + 1,
+                        total: self.conflicts.len(),
+                    })
+                }
+            }
+        }
+        None
+    }
+
+    pub fn answer(&mut self, answer: Answer) -> Result<(), InvalidAnswer> {
+        let Some(conflict) = self.conflicts.get(self.current) else {
+            return Err(InvalidAnswer("No conflict left to decide".to_string()));
+        };
+        match (answer, &mut self.picks) {
+            (Answer::Quit, _) => self.quit = true,
+            (Answer::Field(side), Some(picks)) => {
+                picks.insert(conflict.fields[picks.len()].field.clone(), side);
+                if picks.len() == conflict.fields.len() {
+                    let picks = self.picks.take().unwrap_or_default();
+                    self.decide(fields_decision(picks));
+                }
+            }
+            (_, Some(_)) => {
+                return Err(InvalidAnswer(
+                    "Pick local or incoming for this field".to_string(),
+                ))
+            }
+            (Answer::Field(_), None) => {
+                return Err(InvalidAnswer(
+                    "Not merging field by field right now".to_string(),
+                ))
+            }
+            (Answer::Choose(choice), None) => self.decide(choice.into()),
+            (Answer::ChooseForKind(choice), None) => {
+                self.remembered.insert(conflict.kind.clone(), choice);
+                self.decide(choice.into());
+            }
+            (Answer::MergeFields, None) if conflict.fields.is_empty() => {
+                self.decide(Decision::KeepLocal)
+            }
+            (Answer::MergeFields, None) => self.picks = Some(BTreeMap::new()),
+        }
+        Ok(())
+    }
+
+    fn decide(&mut self, decision: Decision) {
+        self.decisions[self.current] = Some(decision);
+        self.current += 1;
+    }
+
+    /// A decision per conflict, in order; `None` if the user quit
+    pub fn finish(self) -> Option<Vec<Decision>> {
+        if self.quit {
+            return None;
+        }
+        Some(
+            self.decisions
+                .into_iter()
+                .map(|d| d.unwrap_or(Decision::Skip))
+                .collect(),
+        )
+    }
+}
+
+/// Field picks that all went one way are a plain keep or take
+fn fields_decision(picks: BTreeMap<String, Side>) -> Decision {
+    if picks.values().all(|side| *side == Side::Local) {
+        Decision::KeepLocal
+    } else if picks.values().all(|side| *side == Side::Incoming) {
+        Decision::TakeIncoming
+    } else {
+        Decision::Fields(picks)
+    }
+}
+
+/// Asks the user; a terminal in the CLI, scripted answers in tests
+pub trait Prompter {
+    fn ask(&mut self, prompt: &Prompt<'_>) -> std::io::Result<Answer>;
+
+    /// Told when an answer didn't fit; the same prompt comes again
+    fn rejected(&mut self, _reason: &InvalidAnswer) {}
+}
+
+/// Decide every conflict through `prompter`; `None` if the user quit
+pub fn resolve(
+    conflicts: &[Conflict],
+    prompter: &mut dyn Prompter,
+) -> std::io::Result<Option<Vec<Decision>>> {
+    let mut resolver = Resolver::new(conflicts);
+    while let Some(prompt) = resolver.prompt() {
+        let answer = prompter.ask(&prompt)?;
+        if let Err(reason) = resolver.answer(answer) {
+            prompter.rejected(&reason);
+        }
+    }
+    Ok(resolver.finish())
+}
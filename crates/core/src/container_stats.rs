@@ -0,0 +1,87 @@
+//! Resource usage of a running container (`pctrl docker stats`)
+//!
+//! Docker reports CPU time as cumulative counters, together with the
+//! counters of the previous sample (`precpu_stats`). The CPU share is the
+//! container's part of the host's CPU time between the two samples, times
+//! the number of CPUs, so a container busy on two cores shows 200%, as in
+//! `docker stats`.
+
+use serde::{Deserialize, Serialize};
+
+/// Resource usage of one container at one moment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerStats {
+    /// Share of one CPU, 0 when there is no previous sample yet
+    pub cpu_percent: f64,
+    /// Memory in use, without the reclaimable page cache
+    pub mem_usage_bytes: u64,
+    /// Memory limit; the host's memory when the container has none
+    pub mem_limit_bytes: u64,
+    /// Bytes received over all networks
+    pub net_rx: u64,
+    /// Bytes sent over all networks
+    pub net_tx: u64,
+    pub block_read: u64,
+    pub block_write: u64,
+}
+
+impl ContainerStats {
+    /// Memory in use as a share of the limit, 0 without a limit
+    pub fn mem_percent(&self) -> f64 {
+        if self.mem_limit_bytes == 0 {
+            return 0.0;
+        }
+        self.mem_usage_bytes as f64 / self.mem_limit_bytes as f64 * 100.0
+    }
+}
+
+/// Cumulative CPU counters of one sample, in nanoseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuSample {
+    /// CPU time the container used
+    pub container: u64,
+    /// CPU time of the whole host; `None` when Docker sent none
+    pub system: Option<u64>,
+}
+
+/// CPU share between the previous and the current sample. Without a
+/// previous sample (a container that just started, or a one-shot read)
+/// or with counters that didn't move, this is 0 rather than NaN.
+pub fn cpu_percent(current: CpuSample, previous: CpuSample, online_cpus: u64) -> f64 {
+    let (Some(system), Some(previous_system)) = (current.system, previous.system) else {
+        return 0.0;
+    };
+    if previous_system == 0 || system <= previous_system || current.container < previous.container {
+        return 0.0;
+    }
+    let container_delta = (current.container - previous.container) as f64;
+    let system_delta = (system - previous_system) as f64;
+    container_delta / system_delta * online_cpus.max(1) as f64 * 100.0
+}
+
+/// Memory in use the way `docker stats` counts it: the usage minus the
+/// inactive page cache (`total_inactive_file` on cgroup v1,
+/// `inactive_file` on v2), which the kernel can reclaim at any time
+pub fn memory_usage(usage: u64, inactive_file: Option<u64>) -> u64 {
+    match inactive_file {
+        Some(cache) if cache < usage => usage - cache,
+        _ => usage,
+    }
+}
+
+/// Bytes read and written from block I/O entries as `(op, bytes)`.
+/// Docker spells the operations `Read` or `read` depending on the cgroup
+/// version.
+pub fn block_io<'a>(entries: impl IntoIterator<Item = (&'a str, u64)>) -> (u64, u64) {
+    entries
+        .into_iter()
+        .fold((0, 0), |(read, write), (op, bytes)| {
+            if op.eq_ignore_ascii_case("read") {
+                (read + bytes, write)
+            } else if op.eq_ignore_ascii_case("write") {
+                (read, write + bytes)
+            } else {
+                (read, write)
+            }
+        })
+}
@@ -0,0 +1,209 @@
+//! Demo data for onboarding and screenshots (`pctrl debug seed-demo`)
+//!
+//! Every demo row has an id starting with [`DEMO_ID_PREFIX`] and notes
+//! starting with [`DEMO_TAG`], so it can be told apart from real data and
+//! removed again without touching anything else. Servers point at
+//! 203.0.113.0/24 (TEST-NET-3) and domains at example.com, which never
+//! reach a real machine.
+
+use crate::{
+    Domain, DomainType, Project, ProjectResource, ProjectStatus, ResourceType, Script, ScriptType,
+    Server, ServerSpecs, ServerType,
+};
+
+/// Marker at the start of every demo row's notes
+pub const DEMO_TAG: &str = "demo";
+
+/// Prefix of every demo row's id
+pub const DEMO_ID_PREFIX: &str = "demo-";
+
+/// Literal the user must type to reset the database
+pub const RESET_CONFIRMATION: &str = "RESET";
+
+/// Whether an id belongs to demo data
+pub fn is_demo_id(id: &str) -> bool {
+    id.starts_with(DEMO_ID_PREFIX)
+}
+
+/// The complete demo dataset
+#[derive(Debug, Clone)]
+pub struct DemoData {
+    pub project: Project,
+    pub servers: Vec<Server>,
+    pub domains: Vec<Domain>,
+    pub scripts: Vec<Script>,
+    pub links: Vec<ProjectResource>,
+}
+
+impl DemoData {
+    pub fn new() -> Self {
+        let notes = |what: &str| Some(format!("{}: {}", DEMO_TAG, what));
+
+        let project = Project {
+            id: "demo-project".to_string(),
+            name: "Demo Shop".to_string(),
+            description: Some("Demo project - remove with `pctrl debug clear-demo`".to_string()),
+            stack: vec!["rust".to_string(), "postgres".to_string()],
+            status: ProjectStatus::Live,
+            color: Some("#7c3aed".to_string()),
+            icon: None,
+            notes: notes("example project, not a real deployment"),
+        };
+
+        let servers = vec![
+            Server {
+                id: "demo-server-web".to_string(),
+                name: "demo-web".to_string(),
+                host: "203.0.113.10".to_string(),
+                server_type: ServerType::Vps,
+                provider: Some("Hetzner".to_string()),
+                credential_id: None,
+                location: Some("Falkenstein".to_string()),
+                specs: Some(ServerSpecs {
+                    cpu_cores: Some(4),
+                    ram_gb: Some(8),
+                    disk_gb: Some(160),
+                }),
+                notes: notes("documentation IP, unreachable"),
+                requires_vpn: None,
+                max_containers: None,
+                max_memory_mb_allocated: None,
+            },
+            Server {
+                id: "demo-server-db".to_string(),
+                name: "demo-db".to_string(),
+                host: "203.0.113.11".to_string(),
+                server_type: ServerType::Dedicated,
+                provider: Some("Hetzner".to_string()),
+                credential_id: None,
+                location: Some("Helsinki".to_string()),
+                specs: Some(ServerSpecs {
+                    cpu_cores: Some(8),
+                    ram_gb: Some(64),
+                    disk_gb: Some(1000),
+                }),
+                notes: notes("documentation IP, unreachable"),
+                requires_vpn: None,
+                max_containers: None,
+                max_memory_mb_allocated: None,
+            },
+        ];
+
+        let domains = vec![
+            Domain {
+                id: "demo-domain-shop".to_string(),
+                domain: "shop.demo.example.com".to_string(),
+                domain_type: DomainType::Production,
+                ssl: true,
+                ssl_expiry: None,
+                cloudflare_zone_id: None,
+                cloudflare_record_id: None,
+                server_id: Some("demo-server-web".to_string()),
+                container_id: None,
+                notes: notes("reserved example domain"),
+                superseded_by: None,
+            },
+            Domain {
+                id: "demo-domain-staging".to_string(),
+                domain: "staging.demo.example.com".to_string(),
+                domain_type: DomainType::Staging,
+                ssl: true,
+                ssl_expiry: None,
+                cloudflare_zone_id: None,
+                cloudflare_record_id: None,
+                server_id: Some("demo-server-web".to_string()),
+                container_id: None,
+                notes: notes("reserved example domain"),
+                superseded_by: None,
+            },
+        ];
+
+        let scripts = vec![Script {
+            id: "demo-script-uptime".to_string(),
+            name: "demo-uptime".to_string(),
+            description: Some("Show how long the demo web server is up".to_string()),
+            command: "uptime".to_string(),
+            script_type: ScriptType::Ssh,
+            server_id: Some("demo-server-web".to_string()),
+            project_id: Some(project.id.clone()),
+            docker_host_id: None,
+            container_id: None,
+            dangerous: false,
+            last_run: None,
+            last_result: None,
+            exit_code: None,
+            last_output: None,
+            working_dir: None,
+            run_window: None,
+            env: Default::default(),
+        }];
+
+        let link = |id: &str, resource_type: ResourceType, resource_id: &str, role: &str| {
+            ProjectResource {
+                id: id.to_string(),
+                project_id: project.id.clone(),
+                resource_type,
+                resource_id: resource_id.to_string(),
+                role: Some(role.to_string()),
+                notes: notes("example link"),
+                start_order: None,
+            }
+        };
+        let links = vec![
+            link(
+                "demo-link-web",
+                ResourceType::Server,
+                "demo-server-web",
+                "web",
+            ),
+            link(
+                "demo-link-db",
+                ResourceType::Server,
+                "demo-server-db",
+                "database",
+            ),
+            link(
+                "demo-link-shop",
+                ResourceType::Domain,
+                "demo-domain-shop",
+                "production",
+            ),
+            link(
+                "demo-link-staging",
+                ResourceType::Domain,
+                "demo-domain-staging",
+                "staging",
+            ),
+            link(
+                "demo-link-uptime",
+                ResourceType::Script,
+                "demo-script-uptime",
+                "health check",
+            ),
+        ];
+
+        Self {
+            project,
+            servers,
+            domains,
+            scripts,
+            links,
+        }
+    }
+
+    /// Ids of every demo row
+    pub fn ids(&self) -> Vec<&str> {
+        std::iter::once(self.project.id.as_str())
+            .chain(self.servers.iter().map(|s| s.id.as_str()))
+            .chain(self.domains.iter().map(|d| d.id.as_str()))
+            .chain(self.scripts.iter().map(|s| s.id.as_str()))
+            .chain(self.links.iter().map(|l| l.id.as_str()))
+            .collect()
+    }
+}
+
+impl Default for DemoData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,132 @@
+//! Temporary deploy keys for remote commands (`server exec --with-deploy-key`)
+//!
+//! The SSH library can't forward the local agent, so commands that need git
+//! access get a read-only deploy key instead. The key is written to a file
+//! only the login user can read, `GIT_SSH_COMMAND` points git at it, and the
+//! file is removed afterwards. Removal runs from a guard, so it also
+//! happens when the command fails or the run bails out early. If the
+//! connection drops mid-run the removal can't reach the server; the caller
+//! remembers the path and passes it as stale on the next connect.
+
+use crate::shell::quote_word;
+use crate::ship::{CommandOutput, ShipExecutor};
+
+/// Directory for deploy keys, relative to the remote home
+pub const KEY_DIR: &str = ".pctrl/deploy-keys";
+
+/// Remote path of the key for a run token
+pub fn key_path(token: &str) -> String {
+    format!("$HOME/{}/{}", KEY_DIR, token)
+}
+
+/// Command writing `private_key` to `path` with mode 600
+pub fn install_command(path: &str, private_key: &str) -> String {
+    let mut key = private_key.trim_end().to_string();
+    key.push('\n');
+    format!(
+        "umask 077 && mkdir -p \"$HOME/{}\" && printf '%s' {} > \"{}\" && chmod 600 \"{}\"",
+        KEY_DIR,
+        quote_word(&key),
+        path,
+        path
+    )
+}
+
+/// `command` with git using only the deploy key
+pub fn wrap_command(path: &str, command: &str) -> String {
+    format!(
+        "GIT_SSH_COMMAND=\"ssh -i {} -o IdentitiesOnly=yes -o StrictHostKeyChecking=accept-new\" sh -c {}",
+        path,
+        quote_word(command)
+    )
+}
+
+/// Command removing key files
+pub fn remove_command(paths: &[String]) -> String {
+    let quoted: Vec<String> = paths.iter().map(|p| format!("\"{}\"", p)).collect();
+    format!("rm -f {}", quoted.join(" "))
+}
+
+/// Outcome of a command run with a deploy key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployKeyRun {
+    pub output: CommandOutput,
+    /// Keys of earlier interrupted runs that were removed first
+    pub stale_removed: Vec<String>,
+    /// Whether this run's key was removed; if not, keep the path as stale
+    pub cleaned_up: bool,
+}
+
+/// A key on the server, removed when dropped unless removal was tried
+struct InstalledKey<'a> {
+    executor: &'a mut dyn ShipExecutor,
+    path: String,
+    /// Whether removal succeeded, once tried
+    removed: Option<bool>,
+}
+
+impl InstalledKey<'_> {
+    fn remove(&mut self) -> bool {
+        *self.removed.get_or_insert_with(|| {
+            self.executor
+                .run(&remove_command(std::slice::from_ref(&self.path)), false)
+                .is_ok_and(|out| out.success())
+        })
+    }
+}
+
+impl Drop for InstalledKey<'_> {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// Run `command` with a temporary deploy key at [`key_path`]`(token)`.
+///
+/// `stale` keys (from runs whose cleanup didn't reach the server) are
+/// removed first. The key is removed again whatever happens to the
+/// command. An error means the key couldn't be installed or the command
+/// couldn't be run at all; treat the key as not cleaned up then.
+pub fn run_with_deploy_key(
+    executor: &mut dyn ShipExecutor,
+    private_key: &str,
+    token: &str,
+    stale: &[String],
+    command: &str,
+) -> crate::Result<DeployKeyRun> {
+    let stale_removed = if stale.is_empty() {
+        Vec::new()
+    } else {
+        let out = executor.run(&remove_command(stale), false)?;
+        if out.success() {
+            stale.to_vec()
+        } else {
+            Vec::new()
+        }
+    };
+
+    let path = key_path(token);
+    let mut key = InstalledKey {
+        executor,
+        path: path.clone(),
+        removed: None,
+    };
+    let install = key
+        .executor
+        .run(&install_command(&path, private_key), false)?;
+    if !install.success() {
+        return Err(crate::Error::Ssh(format!(
+            "Installing the deploy key failed: {}",
+            install.output.trim()
+        )));
+    }
+
+    let output = key.executor.run(&wrap_command(&path, command), true)?;
+    let cleaned_up = key.remove();
+
+    Ok(DeployKeyRun {
+        output,
+        stale_removed,
+        cleaned_up,
+    })
+}
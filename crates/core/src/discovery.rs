@@ -0,0 +1,178 @@
+//! Container discovery (`pctrl server discover`)
+//!
+//! A server's containers come from its Docker host when one is configured,
+//! else from [`DOCKER_PS`] over SSH, parsed by [`parse_docker_ps`]. Every
+//! run is also kept in the discovery cache until it expires, so views can
+//! show the last result at once and refresh it when it's stale.
+
+use crate::proxy_labels::Labels;
+use crate::{Container, ContainerStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One JSON object per container, stopped ones included. Errors go to
+/// stdout too, so a host without Docker isn't taken for one without
+/// containers.
+pub const DOCKER_PS: &str = "docker ps --all --no-trunc --format '{{json .}}' 2>&1";
+
+/// Discovery cache kind of a server's container list
+pub const CONTAINERS: &str = "containers";
+
+/// How long a discovered container list counts as fresh
+pub const CONTAINERS_TTL_SECS: i64 = 300;
+
+/// A discovery result kept for a server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedDiscovery {
+    pub server_id: String,
+    /// What was discovered, e.g. [`CONTAINERS`]
+    pub data_type: String,
+    /// JSON of the result
+    pub data: String,
+    pub fetched_at: String,
+    pub expires_at: String,
+}
+
+impl CachedDiscovery {
+    /// Whether the result is still fresh at `now`; unreadable expiries are stale
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        DateTime::parse_from_rfc3339(&self.expires_at).is_ok_and(|expires| expires > now)
+    }
+
+    /// The containers of a [`CONTAINERS`] result; empty for other kinds
+    pub fn containers(&self) -> Vec<Container> {
+        if self.data_type != CONTAINERS {
+            return Vec::new();
+        }
+        serde_json::from_str(&self.data).unwrap_or_default()
+    }
+}
+
+/// A line of `docker ps --format '{{json .}}'`. `State` is missing before
+/// Docker 20.10; `Status` ("Up 2 hours (Paused)") stands in for it then.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsLine {
+    #[serde(rename = "ID")]
+    id: String,
+    names: String,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    ports: String,
+    #[serde(default)]
+    labels: String,
+}
+
+/// Containers in the output of [`DOCKER_PS`], recorded on `server_id`
+pub fn parse_docker_ps(output: &str, server_id: &str) -> Result<Vec<Container>, String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let ps: PsLine =
+                serde_json::from_str(line).map_err(|_| format!("docker ps failed: {}", line))?;
+            Ok(Container {
+                id: ps.id,
+                name: ps.names.split(',').next().unwrap_or_default().to_string(),
+                image: Some(ps.image).filter(|i| !i.is_empty()),
+                server_id: server_id.to_string(),
+                project_id: None,
+                status: status(&ps.state, &ps.status),
+                ports: ps
+                    .ports
+                    .split(", ")
+                    .filter(|p| !p.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                env_vars: None,
+                labels: labels_json(&parse_labels(&ps.labels)),
+                memory_limit_mb: None,
+            })
+        })
+        .collect()
+}
+
+/// Container status from Docker's state ("running", "created", ...), or
+/// from its status text when the state is empty
+pub fn status(state: &str, status: &str) -> ContainerStatus {
+    match state.to_ascii_lowercase().as_str() {
+        "" => {}
+        "created" => return ContainerStatus::Stopped,
+        "dead" | "removing" => return ContainerStatus::Exited,
+        other => return other.parse().unwrap_or_default(),
+    }
+    if status.starts_with("Up") {
+        if status.ends_with("(Paused)") {
+            ContainerStatus::Paused
+        } else {
+            ContainerStatus::Running
+        }
+    } else if status.starts_with("Restarting") {
+        ContainerStatus::Restarting
+    } else if status.starts_with("Exited") {
+        ContainerStatus::Exited
+    } else if status.starts_with("Created") {
+        ContainerStatus::Stopped
+    } else {
+        ContainerStatus::Unknown
+    }
+}
+
+/// `docker ps` labels ("a=1,b=2"). Values may contain commas; a part
+/// without `=` belongs to the previous value.
+pub fn parse_labels(text: &str) -> Labels {
+    let mut labels = Labels::new();
+    let mut last: Option<String> = None;
+    for part in text.split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('=') {
+            Some((key, value)) => {
+                labels.insert(key.to_string(), value.to_string());
+                last = Some(key.to_string());
+            }
+            None => {
+                if let Some(value) = last.as_ref().and_then(|key| labels.get_mut(key)) {
+                    value.push(',');
+                    value.push_str(part);
+                }
+            }
+        }
+    }
+    labels
+}
+
+/// Labels as stored on a container: a JSON object, `None` without labels
+pub fn labels_json(labels: &Labels) -> Option<String> {
+    if labels.is_empty() {
+        return None;
+    }
+    serde_json::to_string(labels).ok()
+}
+
+/// A port the way `docker ps` shows it: "0.0.0.0:8080->80/tcp", or
+/// "80/tcp" when it isn't published
+pub fn port_text(ip: Option<&str>, public: Option<u16>, private: u16, protocol: &str) -> String {
+    match public {
+        Some(public) => format!(
+            "{}:{}->{}/{}",
+            ip.unwrap_or("0.0.0.0"),
+            public,
+            private,
+            protocol
+        ),
+        None => format!("{}/{}", private, protocol),
+    }
+}
+
+/// Recorded containers a discovery no longer found
+pub fn vanished<'a>(recorded: &'a [Container], discovered: &[Container]) -> Vec<&'a Container> {
+    recorded
+        .iter()
+        .filter(|r| !discovered.iter().any(|d| d.id == r.id))
+        .collect()
+}
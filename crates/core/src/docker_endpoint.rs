@@ -0,0 +1,236 @@
+//! Where a Docker host's API is reached (`docker_hosts.url`)
+//!
+//! - `unix:///var/run/docker.sock`, or just the path: a local socket
+//! - `tcp://host:2375` or `http://host:2375`: plain HTTP
+//! - `https://host:2376`: TLS with the host's client certificate
+//! - `ssh://user@host[:port][/socket]`: the remote socket, tunnelled
+//!   through the system `ssh` (agent and `~/.ssh/config` apply)
+//!
+//! Hosts are checked when they are saved, so a typo in the scheme fails
+//! there instead of at the first `docker sync`.
+
+use crate::{DockerHost, Error, Result};
+use std::fmt;
+
+/// Docker's plain HTTP port
+pub const HTTP_PORT: u16 = 2375;
+
+/// Docker's TLS port
+pub const HTTPS_PORT: u16 = 2376;
+
+/// Socket on the remote side of `ssh://` when the URL has no path
+pub const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+/// A parsed Docker host URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DockerEndpoint {
+    /// Path of a local socket (a named pipe on Windows)
+    Unix(String),
+    Http {
+        host: String,
+        port: u16,
+    },
+    Https {
+        host: String,
+        port: u16,
+    },
+    Ssh {
+        user: Option<String>,
+        host: String,
+        port: Option<u16>,
+        /// Socket path on the remote host
+        socket: String,
+    },
+}
+
+impl DockerEndpoint {
+    /// Parse a host URL; unsupported schemes are an [`Error::Docker`]
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let invalid =
+            |reason: &str| Error::Docker(format!("Invalid Docker host '{}': {}", url, reason));
+
+        let Some((scheme, rest)) = url.split_once("://") else {
+            if url.starts_with('/') || url.starts_with(r"\\.\pipe\") {
+                return Ok(DockerEndpoint::Unix(url.to_string()));
+            }
+            return Err(invalid(
+                "expected unix://, tcp://, http://, https:// or ssh://",
+            ));
+        };
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "unix" | "npipe" if rest.is_empty() => Err(invalid("no socket path")),
+            "unix" | "npipe" => Ok(DockerEndpoint::Unix(rest.to_string())),
+            "tcp" | "http" => {
+                let (host, port) = host_port(rest).map_err(|e| invalid(&e))?;
+                Ok(DockerEndpoint::Http {
+                    host,
+                    port: port.unwrap_or(HTTP_PORT),
+                })
+            }
+            "https" => {
+                let (host, port) = host_port(rest).map_err(|e| invalid(&e))?;
+                Ok(DockerEndpoint::Https {
+                    host,
+                    port: port.unwrap_or(HTTPS_PORT),
+                })
+            }
+            "ssh" => {
+                let (authority, socket) = match rest.find('/') {
+                    Some(i) => (&rest[..i], &rest[i..]),
+                    None => (rest, ""),
+                };
+                let (user, authority) = match authority.rsplit_once('@') {
+                    Some(("", _)) => return Err(invalid("empty user")),
+                    Some((user, host)) => (Some(user.to_string()), host),
+                    None => (None, authority),
+                };
+                let (host, port) = host_port(authority).map_err(|e| invalid(&e))?;
+                Ok(DockerEndpoint::Ssh {
+                    user,
+                    host,
+                    port,
+                    socket: if socket.len() > 1 {
+                        socket.to_string()
+                    } else {
+                        DEFAULT_SOCKET.to_string()
+                    },
+                })
+            }
+            other => Err(invalid(&format!(
+                "unsupported scheme '{}://'; use unix://, tcp://, http://, https:// or ssh://",
+                other
+            ))),
+        }
+    }
+
+    /// `host:port` of an HTTP(S) endpoint, for the Docker client
+    pub fn address(&self) -> Option<String> {
+        match self {
+            DockerEndpoint::Http { host, port } | DockerEndpoint::Https { host, port } => {
+                Some(join_host_port(host, *port))
+            }
+            _ => None,
+        }
+    }
+
+    /// Remote host name or address; `None` for a local socket
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            DockerEndpoint::Unix(_) => None,
+            DockerEndpoint::Http { host, .. }
+            | DockerEndpoint::Https { host, .. }
+            | DockerEndpoint::Ssh { host, .. } => Some(host),
+        }
+    }
+
+    /// Whether the endpoint needs a client certificate
+    pub fn needs_tls(&self) -> bool {
+        matches!(self, DockerEndpoint::Https { .. })
+    }
+}
+
+impl fmt::Display for DockerEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockerEndpoint::Unix(path) => write!(f, "unix://{}", path),
+            DockerEndpoint::Http { host, port } => {
+                write!(f, "tcp://{}", join_host_port(host, *port))
+            }
+            DockerEndpoint::Https { host, port } => {
+                write!(f, "https://{}", join_host_port(host, *port))
+            }
+            DockerEndpoint::Ssh {
+                user,
+                host,
+                port,
+                socket,
+            } => {
+                write!(f, "ssh://")?;
+                if let Some(user) = user {
+                    write!(f, "{}@", user)?;
+                }
+                match port {
+                    Some(port) => write!(f, "{}", join_host_port(host, *port))?,
+                    None if host.contains(':') => write!(f, "[{}]", host)?,
+                    None => write!(f, "{}", host)?,
+                }
+                if socket != DEFAULT_SOCKET {
+                    write!(f, "{}", socket)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Check a host before it's saved: its URL, and that certificate files
+/// are only given for `https://`, all three together. Without them an
+/// `https://` host uses `DOCKER_CERT_PATH` or `~/.docker`.
+pub fn validate(host: &DockerHost) -> Result<DockerEndpoint> {
+    let endpoint = DockerEndpoint::parse(&host.url)?;
+    let given = [&host.tls_ca, &host.tls_cert, &host.tls_key]
+        .iter()
+        .filter(|f| f.is_some())
+        .count();
+    if given > 0 && !endpoint.needs_tls() {
+        return Err(Error::Docker(format!(
+            "Docker host '{}': certificate files only apply to https:// hosts",
+            host.name
+        )));
+    }
+    if given > 0 && given < 3 {
+        return Err(Error::Docker(format!(
+            "Docker host '{}': give the CA, certificate and key files together",
+            host.name
+        )));
+    }
+    Ok(endpoint)
+}
+
+/// Split `host[:port]` (IPv6 in brackets); a trailing `/` is allowed
+fn host_port(authority: &str) -> std::result::Result<(String, Option<u16>), String> {
+    let authority = authority.strip_suffix('/').unwrap_or(authority);
+    if authority.contains('/') {
+        return Err("unexpected path".to_string());
+    }
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, after) = rest
+                .split_once(']')
+                .ok_or_else(|| "unclosed '[' in the address".to_string())?;
+            match after {
+                "" => (host, None),
+                _ => match after.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return Err(format!("unexpected '{}' after the address", after)),
+                },
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err("no host".to_string());
+    }
+    let port = port
+        .map(|p| {
+            p.parse::<u16>()
+                .ok()
+                .filter(|p| *p != 0)
+                .ok_or_else(|| format!("invalid port '{}'", p))
+        })
+        .transpose()?;
+    Ok((host.to_string(), port))
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
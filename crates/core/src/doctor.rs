@@ -0,0 +1,328 @@
+//! Integrity findings and their repair plan (`pctrl doctor`)
+//!
+//! The database reports every reference that points nowhere and every pair
+//! of names that differ only in case. This module classifies them and orders
+//! the repairs so parent-level problems come first: a server's dangling
+//! credential is only nulled when no existing credential looks like the one
+//! it meant, and a name that matches several entities (as case duplicates
+//! do) is never relinked automatically.
+
+use crate::EntityType;
+use std::fmt;
+
+/// What is wrong, in the order the planner handles it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FindingKind {
+    /// Two entities of a type whose names differ only in case
+    CaseDuplicate,
+    /// A required reference (NOT NULL) to an entity that doesn't exist
+    MissingParent,
+    /// An optional reference to an entity that doesn't exist
+    DanglingRef,
+    /// A project link to a resource that doesn't exist
+    OrphanedLink,
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FindingKind::CaseDuplicate => write!(f, "case duplicate"),
+            FindingKind::MissingParent => write!(f, "missing parent"),
+            FindingKind::DanglingRef => write!(f, "dangling reference"),
+            FindingKind::OrphanedLink => write!(f, "orphaned link"),
+        }
+    }
+}
+
+/// An existing entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub id: String,
+    pub name: String,
+}
+
+/// The row holding a broken reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefSite {
+    /// Relationship label, e.g. "servers" or "project links"
+    pub relation: String,
+    pub table: String,
+    pub column: String,
+    /// Primary key column and value of the row
+    pub key: String,
+    pub row: String,
+    /// Display name of the row's entity
+    pub row_name: String,
+    /// Entity the row belongs to, for the audit log
+    pub owner: Option<(EntityType, String)>,
+    /// The column is NOT NULL
+    pub required: bool,
+    /// The row is a link, not an entity of its own
+    pub link: bool,
+}
+
+/// One integrity problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// Type of the missing or duplicated entity
+    pub target: EntityType,
+    /// Where the reference lives; `None` for case duplicates
+    pub site: Option<RefSite>,
+    /// The missing ID or the duplicated name
+    pub value: String,
+    /// Entities the value probably means (for case duplicates: the duplicates)
+    pub candidates: Vec<Candidate>,
+}
+
+impl Finding {
+    /// A reference to `value` that matches no entity of `target`.
+    /// `entities` are all entities of that type, searched for candidates.
+    pub fn dangling(
+        target: EntityType,
+        site: RefSite,
+        value: &str,
+        entities: &[Candidate],
+    ) -> Self {
+        let kind = if site.link {
+            FindingKind::OrphanedLink
+        } else if site.required {
+            FindingKind::MissingParent
+        } else {
+            FindingKind::DanglingRef
+        };
+        Self {
+            kind,
+            target,
+            site: Some(site),
+            value: value.to_string(),
+            candidates: likely_meant(value, entities),
+        }
+    }
+
+    /// Repairs that make sense for this finding, for interactive mode
+    pub fn choices(&self) -> Vec<Choice> {
+        let Some(site) = &self.site else {
+            return vec![Choice::Skip];
+        };
+        let mut choices = vec![Choice::Relink];
+        if !site.required {
+            choices.push(Choice::Null);
+        }
+        // Entities have their own remove commands with reference guards
+        if site.link {
+            choices.push(Choice::Delete);
+        }
+        choices.push(Choice::Skip);
+        choices
+    }
+
+    /// One line, e.g. "domain 'shop.example.com' → server 'web-1' doesn't exist"
+    pub fn describe(&self) -> String {
+        match &self.site {
+            None => format!(
+                "{} names differ only in case: {}",
+                self.target,
+                self.candidates
+                    .iter()
+                    .map(|c| format!("'{}'", c.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Some(site) => format!(
+                "{} '{}' ({}.{}) → {} '{}' doesn't exist",
+                site.relation, site.row_name, site.table, site.column, self.target, self.value
+            ),
+        }
+    }
+}
+
+/// What interactive mode offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    Relink,
+    Null,
+    Delete,
+    Skip,
+}
+
+/// A repair to apply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// Point the reference at an existing entity
+    Relink(Candidate),
+    /// Clear an optional reference
+    Null,
+    /// Delete the link row
+    Delete,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::Relink(c) => write!(f, "relink to '{}'", c.name),
+            Repair::Null => write!(f, "clear the reference"),
+            Repair::Delete => write!(f, "delete the link"),
+        }
+    }
+}
+
+/// A finding with what `--fix` does about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub finding: Finding,
+    /// `None`: needs a decision (`--interactive`)
+    pub repair: Option<Repair>,
+    /// Why there's no repair, or what the repair is based on
+    pub note: Option<String>,
+}
+
+/// Order of entity types, parents first (servers point at credentials,
+/// links at everything)
+fn level(entity_type: EntityType) -> u8 {
+    match entity_type {
+        EntityType::Credential => 0,
+        EntityType::Server => 1,
+        EntityType::Project => 2,
+        EntityType::Database => 3,
+        EntityType::Domain => 4,
+        EntityType::Script => 5,
+    }
+}
+
+/// Order the findings and choose a repair for each: parent-level types
+/// first, and within a level case duplicates, then missing parents, then
+/// dangling references, then orphaned links.
+pub fn plan(mut findings: Vec<Finding>) -> Vec<Step> {
+    findings.sort_by(|a, b| {
+        let site = |f: &Finding| {
+            f.site
+                .as_ref()
+                .map(|s| (s.table.clone(), s.column.clone(), s.row.clone()))
+        };
+        (level(a.target), a.kind, site(a), &a.value).cmp(&(
+            level(b.target),
+            b.kind,
+            site(b),
+            &b.value,
+        ))
+    });
+
+    findings
+        .into_iter()
+        .map(|finding| {
+            let (repair, note) = choose(&finding);
+            Step {
+                finding,
+                repair,
+                note,
+            }
+        })
+        .collect()
+}
+
+fn choose(finding: &Finding) -> (Option<Repair>, Option<String>) {
+    let Some(site) = &finding.site else {
+        return (
+            None,
+            Some(
+                "rename one so names stay unique; references to them aren't relinked until then"
+                    .to_string(),
+            ),
+        );
+    };
+
+    let names = |cs: &[Candidate]| {
+        cs.iter()
+            .map(|c| format!("'{}'", c.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match finding.candidates.as_slice() {
+        [only] => (
+            Some(Repair::Relink(only.clone())),
+            Some(format!(
+                "looks like {} '{}' (renamed or retyped?)",
+                finding.target, only.name
+            )),
+        ),
+        [] if site.link => (Some(Repair::Delete), None),
+        [] if site.required => (
+            None,
+            Some(format!(
+                "{} can't exist without a {}; pick one to relink it to",
+                site.row_name, finding.target
+            )),
+        ),
+        [] => (Some(Repair::Null), None),
+        several => (None, Some(format!("could be any of {}", names(several)))),
+    }
+}
+
+/// Entities a dangling value probably meant: same ID or name ignoring case,
+/// or a name that slugs to the value (IDs made from names keep the old name)
+pub fn likely_meant(value: &str, entities: &[Candidate]) -> Vec<Candidate> {
+    let value = value.to_lowercase();
+    entities
+        .iter()
+        .filter(|e| {
+            e.id.to_lowercase() == value || e.name.to_lowercase() == value || slug(&e.name) == value
+        })
+        .cloned()
+        .collect()
+}
+
+fn slug(name: &str) -> String {
+    name.to_lowercase().replace(' ', "-")
+}
+
+/// Groups of entities whose names differ only in case
+pub fn case_duplicates(target: EntityType, entities: &[Candidate]) -> Vec<Finding> {
+    let mut groups: Vec<(String, Vec<Candidate>)> = Vec::new();
+    for entity in entities {
+        let key = entity.name.to_lowercase();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(entity.clone()),
+            None => groups.push((key, vec![entity.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(key, candidates)| Finding {
+            kind: FindingKind::CaseDuplicate,
+            target,
+            site: None,
+            value: key,
+            candidates,
+        })
+        .collect()
+}
+
+/// Entities matching a typed query, best first: exact name, prefix,
+/// substring, then the query's letters in order ("wb1" finds "web-1")
+pub fn fuzzy_pick<'a>(query: &str, entities: &'a [Candidate]) -> Vec<&'a Candidate> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return entities.iter().collect();
+    }
+    let score = |name: &str| -> Option<u8> {
+        let name = name.to_lowercase();
+        if name == query {
+            Some(0)
+        } else if name.starts_with(&query) {
+            Some(1)
+        } else if name.contains(&query) {
+            Some(2)
+        } else {
+            let mut rest = name.chars();
+            query.chars().all(|q| rest.any(|c| c == q)).then_some(3)
+        }
+    };
+    let mut matches: Vec<(u8, &Candidate)> = entities
+        .iter()
+        .filter_map(|e| score(&e.name).or_else(|| score(&e.id)).map(|s| (s, e)))
+        .collect();
+    matches.sort_by(|a, b| (a.0, &a.1.name).cmp(&(b.0, &b.1.name)));
+    matches.into_iter().map(|(_, e)| e).collect()
+}
@@ -0,0 +1,437 @@
+//! Declarative inventory documents: includes, merging and variables
+//!
+//! A document such as `infra.toml` may pull in others with
+//! `include = ["common.toml"]`, relative to the including file. Included
+//! documents are merged first, in order, and the including one on top, so
+//! later files win; a file included twice counts once, and include cycles
+//! are errors. Merging goes key by key: tables merge, scalars are
+//! overridden, and lists are appended unless the overriding key ends in
+//! `!replace` (`"servers!replace" = [...]`).
+//!
+//! Strings may use `${name}`, taken from the merged `[vars]` tables and
+//! `--var key=value` (which wins over every file), and `${env:NAME}` for
+//! environment variables; `$${` keeps a literal `${`. Variables are
+//! resolved per file before merging, so an unresolved one is reported with
+//! its file and line, and validation sees the resolved values. What comes
+//! out has no `include` or `vars` left: the document to validate and apply.
+//! Secret placeholders (see [`crate::placeholder`]) pass through untouched.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use toml::{Table, Value};
+
+/// Suffix of a key whose list replaces the merged one instead of extending it
+pub const REPLACE_SUFFIX: &str = "!replace";
+
+/// Shown as the file of variables given with `--var`
+const CLI_VARS: &str = "--var";
+
+/// Reads documents and environment variables
+pub trait DocumentSource {
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn env(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// The real files and environment
+pub struct FileSystem;
+
+impl DocumentSource for FileSystem {}
+
+/// Why a document couldn't be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentError {
+    Read {
+        path: PathBuf,
+        reason: String,
+    },
+    Parse {
+        path: PathBuf,
+        reason: String,
+    },
+    /// A malformed `include` or `[vars]`
+    Invalid {
+        path: PathBuf,
+        reason: String,
+    },
+    /// The files from the first to the one including it again
+    IncludeCycle(Vec<PathBuf>),
+    /// `${name}` without a value; `name` is `env:NAME` for the environment
+    Unresolved {
+        path: PathBuf,
+        line: Option<usize>,
+        name: String,
+    },
+    /// Variables whose values refer to each other, back to the first
+    VarCycle(Vec<String>),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Read { path, reason } => {
+                write!(f, "{}: can't read: {}", path.display(), reason)
+            }
+            DocumentError::Parse { path, reason } => {
+                write!(f, "{}: {}", path.display(), reason.trim_end())
+            }
+            DocumentError::Invalid { path, reason } => write!(f, "{}: {}", path.display(), reason),
+            DocumentError::IncludeCycle(chain) => {
+                let chain: Vec<String> = chain.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "include cycle: {}", chain.join(" → "))
+            }
+            DocumentError::Unresolved { path, line, name } => {
+                write!(f, "{}", path.display())?;
+                if let Some(line) = line {
+                    write!(f, ":{}", line)?;
+                }
+                match name.strip_prefix("env:") {
+                    Some(env) => write!(f, ": environment variable {} is not set", env),
+                    None => write!(
+                        f,
+                        ": unresolved variable ${{{}}}; define it in [vars] or pass --var {}=...",
+                        name, name
+                    ),
+                }
+            }
+            DocumentError::VarCycle(names) => {
+                write!(f, "variables refer to each other: {}", names.join(" → "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+/// One file of a document, in merge order
+struct Part {
+    path: PathBuf,
+    text: String,
+    table: Table,
+}
+
+/// A variable's raw value and where it was defined
+struct Var {
+    value: String,
+    /// Index into the parts; `None` for `--var`
+    part: Option<usize>,
+}
+
+/// Load `path` with its includes, resolve variables (`overrides` win over
+/// every `[vars]`) and merge everything into one table
+pub fn resolve(
+    path: &Path,
+    overrides: &BTreeMap<String, String>,
+    source: &dyn DocumentSource,
+) -> Result<Table, DocumentError> {
+    let mut parts = Vec::new();
+    load(
+        &normalize(path),
+        &mut Vec::new(),
+        &mut HashSet::new(),
+        &mut parts,
+        source,
+    )?;
+
+    let mut vars = BTreeMap::new();
+    for (i, part) in parts.iter_mut().enumerate() {
+        let Some(table) = part.table.remove("vars") else {
+            continue;
+        };
+        let Value::Table(table) = table else {
+            return Err(invalid(&part.path, "vars must be a table"));
+        };
+        for (name, value) in table {
+            let value = match value {
+                Value::String(s) => s,
+                Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => value.to_string(),
+                _ => {
+                    return Err(invalid(
+                        &part.path,
+                        &format!("var '{}' must be a string, number or boolean", name),
+                    ))
+                }
+            };
+            vars.insert(
+                name,
+                Var {
+                    value,
+                    part: Some(i),
+                },
+            );
+        }
+    }
+    for (name, value) in overrides {
+        vars.insert(
+            name.clone(),
+            Var {
+                value: value.clone(),
+                part: None,
+            },
+        );
+    }
+
+    let mut resolved = BTreeMap::new();
+    for name in vars.keys() {
+        resolve_var(name, &vars, &parts, source, &mut resolved, &mut Vec::new())?;
+    }
+
+    let mut merged = Table::new();
+    for mut part in parts {
+        interpolate_table(&mut part.table, &|name| lookup(name, &resolved, source))
+            .map_err(|name| unresolved(&part.path, &part.text, name))?;
+        merge(&mut merged, part.table);
+    }
+    Ok(merged)
+}
+
+/// Load a file and, depth first, its includes into `parts`
+fn load(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    loaded: &mut HashSet<PathBuf>,
+    parts: &mut Vec<Part>,
+    source: &dyn DocumentSource,
+) -> Result<(), DocumentError> {
+    if stack.iter().any(|p| p == path) {
+        let mut chain = stack.clone();
+        chain.push(path.to_path_buf());
+        return Err(DocumentError::IncludeCycle(chain));
+    }
+    if !loaded.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+
+    let text = source.read(path).map_err(|e| DocumentError::Read {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    let mut table: Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| DocumentError::Parse {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+
+    let includes = match table.remove("include") {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                _ => Err(invalid(path, "include must be a list of file names")),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err(invalid(path, "include must be a list of file names")),
+    };
+
+    stack.push(path.to_path_buf());
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for include in includes {
+        load(&normalize(&dir.join(include)), stack, loaded, parts, source)?;
+    }
+    stack.pop();
+
+    parts.push(Part {
+        path: path.to_path_buf(),
+        text,
+        table,
+    });
+    Ok(())
+}
+
+/// Resolve one variable, following references to others
+fn resolve_var(
+    name: &str,
+    vars: &BTreeMap<String, Var>,
+    parts: &[Part],
+    source: &dyn DocumentSource,
+    resolved: &mut BTreeMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<(), DocumentError> {
+    if resolved.contains_key(name) {
+        return Ok(());
+    }
+    if let Some(start) = stack.iter().position(|n| n == name) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(name.to_string());
+        return Err(DocumentError::VarCycle(cycle));
+    }
+    let var = &vars[name];
+
+    stack.push(name.to_string());
+    // Resolve what the value refers to first, or report where it's missing
+    for reference in references(&var.value) {
+        if vars.contains_key(&reference) {
+            resolve_var(&reference, vars, parts, source, resolved, stack)?;
+        }
+    }
+    stack.pop();
+
+    let value = interpolate(&var.value, |n| lookup(n, resolved, source)).map_err(|missing| {
+        match var.part {
+            Some(i) => unresolved(&parts[i].path, &parts[i].text, missing),
+            None => DocumentError::Unresolved {
+                path: PathBuf::from(CLI_VARS),
+                line: None,
+                name: missing,
+            },
+        }
+    })?;
+    resolved.insert(name.to_string(), value);
+    Ok(())
+}
+
+fn lookup(
+    name: &str,
+    vars: &BTreeMap<String, String>,
+    source: &dyn DocumentSource,
+) -> Option<String> {
+    match name.strip_prefix("env:") {
+        Some(env) => source.env(env),
+        None => vars.get(name).cloned(),
+    }
+}
+
+fn invalid(path: &Path, reason: &str) -> DocumentError {
+    DocumentError::Invalid {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+fn unresolved(path: &Path, text: &str, name: String) -> DocumentError {
+    let token = format!("${{{}}}", name);
+    DocumentError::Unresolved {
+        path: path.to_path_buf(),
+        line: text.lines().position(|l| l.contains(&token)).map(|i| i + 1),
+        name,
+    }
+}
+
+/// Replace `${name}` in `text` by `lookup(name)`; `$${` stays a literal
+/// `${`. Returns the first name without a value.
+pub fn interpolate(
+    text: &str,
+    mut lookup: impl FnMut(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+        } else if let Some((name, after)) = tail.strip_prefix("${").and_then(|t| t.split_once('}'))
+        {
+            let name = name.trim();
+            out.push_str(&lookup(name).ok_or_else(|| name.to_string())?);
+            rest = after;
+        } else {
+            out.push('$');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Names `text` refers to with `${name}`, in order
+pub fn references(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let _ = interpolate(text, |name| {
+        names.push(name.to_string());
+        Some(String::new())
+    });
+    names
+}
+
+/// Interpolate every string in a table, keys excepted
+fn interpolate_table(
+    table: &mut Table,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    table
+        .iter_mut()
+        .try_for_each(|(_, value)| interpolate_value(value, lookup))
+}
+
+fn interpolate_value(
+    value: &mut Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    match value {
+        Value::String(s) => *s = interpolate(s, lookup)?,
+        Value::Array(items) => items
+            .iter_mut()
+            .try_for_each(|item| interpolate_value(item, lookup))?,
+        Value::Table(table) => interpolate_table(table, lookup)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Merge `overlay` into `base`: tables merge, lists are appended (replaced
+/// for keys ending in [`REPLACE_SUFFIX`]) and anything else is overridden
+pub fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        let value = clean(value);
+        if let Some(key) = key.strip_suffix(REPLACE_SUFFIX) {
+            base.insert(key.to_string(), value);
+            continue;
+        }
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (Some(Value::Array(base)), Value::Array(overlay)) => base.extend(overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Drop [`REPLACE_SUFFIX`] from nested keys that have nothing to replace
+fn clean(value: Value) -> Value {
+    match value {
+        Value::Table(table) => {
+            let mut cleaned = Table::new();
+            merge(&mut cleaned, table);
+            Value::Table(cleaned)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(clean).collect()),
+        other => other,
+    }
+}
+
+/// Parse `key=value` of `--var`
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected key=value, got '{}'", s)),
+    }
+}
+
+/// `a/./b/../c` → `a/c`, without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(out.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
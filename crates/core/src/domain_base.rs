@@ -0,0 +1,192 @@
+//! Moving domains to a new base (`pctrl domain migrate-base`)
+//!
+//! A base matches whole labels only: `example.com` matches `example.com`
+//! and `app.example.com`, but not `myexample.com`. Migrated domains get a
+//! new row; the old row stays and points to it through `superseded_by`.
+//! A rename whose new name (or ID) is already taken is a collision and is
+//! left alone.
+
+use crate::Domain;
+use std::ops::Range;
+
+/// Lowercased base without leading/trailing dots, e.g. ".Example.COM." →
+/// "example.com"
+pub fn normalize_base(base: &str) -> Result<String, String> {
+    let base = base.trim().trim_matches('.').to_lowercase();
+    if base.is_empty() {
+        return Err("The domain base is empty".to_string());
+    }
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if !base.split('.').all(valid_label) {
+        return Err(format!("'{}' is not a valid domain base", base));
+    }
+    Ok(base)
+}
+
+/// `name` moved from base `old` to `new`, if it is `old` or a subdomain of
+/// it. Bases must be normalized.
+pub fn rebase(name: &str, old: &str, new: &str) -> Option<String> {
+    let name = name.trim_end_matches('.').to_lowercase();
+    if name == old {
+        return Some(new.to_string());
+    }
+    let prefix = name.strip_suffix(old)?.strip_suffix('.')?;
+    (!prefix.is_empty()).then(|| format!("{}.{}", prefix, new))
+}
+
+/// Host of an http(s) URL
+pub fn url_host(url: &str) -> Option<&str> {
+    host_range(url).map(|range| &url[range])
+}
+
+/// `url` with its host replaced, if the host is `from` (case-insensitive)
+pub fn replace_url_host(url: &str, from: &str, to: &str) -> Option<String> {
+    let range = host_range(url)?;
+    if !url[range.clone()].eq_ignore_ascii_case(from) {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}",
+        &url[..range.start],
+        to,
+        &url[range.end..]
+    ))
+}
+
+fn host_range(url: &str) -> Option<Range<usize>> {
+    let authority_start = url.find("://")? + 3;
+    let rest = &url[authority_start..];
+    let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+    // Skip user info
+    let start = authority_start + authority.rfind('@').map_or(0, |at| at + 1);
+    let host_port = &url[start..authority_start + authority.len()];
+    let end = start + host_port.find(':').unwrap_or(host_port.len());
+    (end > start).then_some(start..end)
+}
+
+/// ID of a domain row created for `name`, as `domain add` does it
+pub fn domain_id(name: &str) -> String {
+    name.replace('.', "-").to_lowercase()
+}
+
+/// One planned rename
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub domain: Domain,
+    pub to: String,
+    /// Existing domain the new name or ID clashes with
+    pub collision: Option<String>,
+}
+
+impl Rename {
+    /// The row replacing the old domain: same type, SSL, server, container
+    /// and notes; certificate expiry and Cloudflare IDs belong to the old
+    /// name and aren't copied
+    pub fn new_domain(&self) -> Domain {
+        Domain {
+            id: domain_id(&self.to),
+            domain: self.to.clone(),
+            domain_type: self.domain.domain_type.clone(),
+            ssl: self.domain.ssl,
+            ssl_expiry: None,
+            cloudflare_zone_id: None,
+            cloudflare_record_id: None,
+            server_id: self.domain.server_id.clone(),
+            container_id: self.domain.container_id.clone(),
+            notes: self.domain.notes.clone(),
+            superseded_by: None,
+        }
+    }
+}
+
+/// All renames for moving from one base to another
+#[derive(Debug, Clone)]
+pub struct BasePlan {
+    pub old_base: String,
+    pub new_base: String,
+    /// Sorted by old name
+    pub renames: Vec<Rename>,
+}
+
+impl BasePlan {
+    /// Plan the renames of every domain under `old_base`. Domains that were
+    /// already superseded are skipped, so running it again is harmless.
+    pub fn new(domains: &[Domain], old_base: &str, new_base: &str) -> Result<Self, String> {
+        let old_base = normalize_base(old_base)?;
+        let new_base = normalize_base(new_base)?;
+        if old_base == new_base {
+            return Err("The old and new base are the same".to_string());
+        }
+        // Migrated domains would match the old base again on the next run
+        if rebase(&new_base, &old_base, &new_base).is_some() {
+            return Err(format!(
+                "The new base can't be under the old one ({} is under {})",
+                new_base, old_base
+            ));
+        }
+
+        let mut renames: Vec<Rename> = domains
+            .iter()
+            .filter(|d| d.superseded_by.is_none())
+            .filter_map(|d| {
+                let to = rebase(&d.domain, &old_base, &new_base)?;
+                let id = domain_id(&to);
+                let collision = domains
+                    .iter()
+                    .find(|other| other.domain.eq_ignore_ascii_case(&to) || other.id == id)
+                    .map(|other| other.domain.clone());
+                Some(Rename {
+                    domain: d.clone(),
+                    to,
+                    collision,
+                })
+            })
+            .collect();
+        renames.sort_by(|a, b| a.domain.domain.cmp(&b.domain.domain));
+
+        Ok(Self {
+            old_base,
+            new_base,
+            renames,
+        })
+    }
+
+    /// Renames that will be applied
+    pub fn applicable(&self) -> impl Iterator<Item = &Rename> {
+        self.renames.iter().filter(|r| r.collision.is_none())
+    }
+
+    /// Renames skipped because the new name is taken
+    pub fn collisions(&self) -> impl Iterator<Item = &Rename> {
+        self.renames.iter().filter(|r| r.collision.is_some())
+    }
+
+    /// New name for an old domain name or ID, if it is migrated
+    pub fn renamed(&self, old: &str) -> Option<&Rename> {
+        self.applicable()
+            .find(|r| r.domain.id == old || r.domain.domain.eq_ignore_ascii_case(old))
+    }
+}
+
+/// A project health URL pointed at a migrated domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthUrlChange {
+    pub project_id: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// What applying a [`BasePlan`] changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaseMigration {
+    /// Domains migrated (new rows created, old rows superseded)
+    pub domains: usize,
+    /// Project links moved to the new domains
+    pub links: u64,
+    pub health_urls: Vec<HealthUrlChange>,
+}
@@ -0,0 +1,305 @@
+//! Server exports for other tools (`pctrl export`)
+//!
+//! Generators are pure: they take servers with their resolved SSH access and
+//! groups, and return the file content. Servers without SSH access are kept
+//! as comments, so the output shows what is missing.
+
+use crate::{Credential, CredentialData, Server};
+use std::fmt;
+
+/// First line of the section `pctrl export ssh-config` manages
+pub const SSH_CONFIG_BEGIN: &str = "# BEGIN pctrl managed section";
+
+/// Last line of the managed section
+pub const SSH_CONFIG_END: &str = "# END pctrl managed section";
+
+/// SSH login from a server's credential
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshAccess {
+    pub user: String,
+    pub port: u16,
+    /// None when the SSH agent provides the key
+    pub identity_file: Option<String>,
+}
+
+impl SshAccess {
+    /// SSH access of an SSH key or SSH agent credential
+    pub fn from_credential(credential: &Credential) -> Option<Self> {
+        match &credential.data {
+            CredentialData::SshKey {
+                username,
+                port,
+                key_path,
+                ..
+            } => Some(Self {
+                user: username.clone(),
+                port: *port,
+                identity_file: Some(key_path.clone()),
+            }),
+            CredentialData::SshAgent { username, port } => Some(Self {
+                user: username.clone(),
+                port: *port,
+                identity_file: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A server ready for export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportHost {
+    pub name: String,
+    pub host: String,
+    pub ssh: Option<SshAccess>,
+    /// Inventory groups (unsanitized), empty for ungrouped hosts
+    pub groups: Vec<String>,
+}
+
+impl ExportHost {
+    pub fn new(server: &Server, credential: Option<&Credential>) -> Self {
+        Self {
+            name: server.name.clone(),
+            host: server.host.clone(),
+            ssh: credential.and_then(SshAccess::from_credential),
+            groups: Vec::new(),
+        }
+    }
+}
+
+/// How `export ansible` groups hosts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    #[default]
+    Provider,
+    /// Server type (vps, dedicated, ...)
+    Type,
+    /// Linked projects; a server may be in several
+    Project,
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupBy::Provider => write!(f, "provider"),
+            GroupBy::Type => write!(f, "type"),
+            GroupBy::Project => write!(f, "project"),
+        }
+    }
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "provider" => Ok(GroupBy::Provider),
+            "type" => Ok(GroupBy::Type),
+            "project" => Ok(GroupBy::Project),
+            _ => Err(format!(
+                "Unknown grouping: {} (expected provider, type or project)",
+                s
+            )),
+        }
+    }
+}
+
+/// Ansible YAML inventory
+///
+/// Host variables are set once under `all.hosts`; groups only list members.
+pub fn ansible_inventory(hosts: &[ExportHost]) -> String {
+    let mut out = String::from("# Generated by pctrl export ansible\nall:\n");
+    if hosts.is_empty() {
+        out.push_str("  hosts: {}\n");
+        return out;
+    }
+
+    out.push_str("  hosts:\n");
+    for host in hosts {
+        match &host.ssh {
+            Some(ssh) => {
+                out.push_str(&format!("    {}:\n", yaml_scalar(&host.name)));
+                out.push_str(&format!(
+                    "      ansible_host: {}\n",
+                    yaml_scalar(&host.host)
+                ));
+                out.push_str(&format!("      ansible_user: {}\n", yaml_scalar(&ssh.user)));
+                out.push_str(&format!("      ansible_port: {}\n", ssh.port));
+                if let Some(ref key) = ssh.identity_file {
+                    out.push_str(&format!(
+                        "      ansible_ssh_private_key_file: {}\n",
+                        yaml_scalar(key)
+                    ));
+                }
+            }
+            None => {
+                out.push_str(&format!("    # {}: no SSH credential\n", host.name));
+                out.push_str(&format!("    {}:\n", yaml_scalar(&host.name)));
+                out.push_str(&format!(
+                    "      ansible_host: {}\n",
+                    yaml_scalar(&host.host)
+                ));
+            }
+        }
+    }
+
+    // Group name -> members, in order of first appearance
+    let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+    for host in hosts {
+        for group in &host.groups {
+            let group = group_name(group);
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, members)) => {
+                    if !members.contains(&host.name.as_str()) {
+                        members.push(&host.name);
+                    }
+                }
+                None => groups.push((group, vec![&host.name])),
+            }
+        }
+    }
+    if !groups.is_empty() {
+        out.push_str("  children:\n");
+        for (group, members) in groups {
+            out.push_str(&format!("    {}:\n      hosts:\n", group));
+            for member in members {
+                out.push_str(&format!("        {}:\n", yaml_scalar(member)));
+            }
+        }
+    }
+
+    out
+}
+
+/// OpenSSH config section, including the marker lines
+pub fn ssh_config(hosts: &[ExportHost]) -> String {
+    let mut out = format!(
+        "{}\n# Generated by `pctrl export ssh-config`; edits here are overwritten\n",
+        SSH_CONFIG_BEGIN
+    );
+    for host in hosts {
+        out.push('\n');
+        match &host.ssh {
+            Some(ssh) => {
+                out.push_str(&format!("Host {}\n", host_alias(&host.name)));
+                out.push_str(&format!("    HostName {}\n", ssh_value(&host.host)));
+                out.push_str(&format!("    User {}\n", ssh_value(&ssh.user)));
+                out.push_str(&format!("    Port {}\n", ssh.port));
+                if let Some(ref key) = ssh.identity_file {
+                    out.push_str(&format!("    IdentityFile {}\n", ssh_value(key)));
+                }
+            }
+            None => {
+                out.push_str(&format!(
+                    "# {} ({}): no SSH credential\n",
+                    host.name, host.host
+                ));
+            }
+        }
+    }
+    out.push('\n');
+    out.push_str(SSH_CONFIG_END);
+    out.push('\n');
+    out
+}
+
+/// Replace the managed section of an existing ssh config with `section`
+///
+/// Everything outside the markers is kept byte for byte. Without markers the
+/// section is appended. Unbalanced or repeated markers are an error, since
+/// guessing where our section ends could delete the user's own entries.
+pub fn splice_managed_section(existing: &str, section: &str) -> Result<String, String> {
+    let lines: Vec<&str> = existing.split_inclusive('\n').collect();
+    let is_marker = |line: &str, marker: &str| line.trim_end() == marker;
+    let begins: Vec<usize> = (0..lines.len())
+        .filter(|&i| is_marker(lines[i], SSH_CONFIG_BEGIN))
+        .collect();
+    let ends: Vec<usize> = (0..lines.len())
+        .filter(|&i| is_marker(lines[i], SSH_CONFIG_END))
+        .collect();
+
+    match (begins.as_slice(), ends.as_slice()) {
+        ([], []) => {
+            let mut out = existing.to_string();
+            if !out.is_empty() {
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                if !out.ends_with("\n\n") {
+                    out.push('\n');
+                }
+            }
+            out.push_str(section);
+            Ok(out)
+        }
+        ([begin], [end]) if begin < end => {
+            let mut out: String = lines[..*begin].concat();
+            out.push_str(section);
+            let rest = lines[end + 1..].concat();
+            if !rest.is_empty() && !section.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(&rest);
+            Ok(out)
+        }
+        _ => Err(format!(
+            "The file's pctrl markers are unbalanced (found {} '{}' and {} '{}' lines); fix them by hand",
+            begins.len(),
+            SSH_CONFIG_BEGIN,
+            ends.len(),
+            SSH_CONFIG_END
+        )),
+    }
+}
+
+/// Ansible group name: lowercase letters, digits and underscores
+pub fn group_name(s: &str) -> String {
+    let name: String = s
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        None => "_".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", name),
+        Some(_) => name,
+    }
+}
+
+/// SSH `Host` pattern for a server name: no whitespace or wildcards
+fn host_alias(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| match c {
+            c if c.is_whitespace() => '-',
+            '*' | '?' | '!' | ',' | '"' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// SSH config value, quoted if it contains whitespace
+fn ssh_value(s: &str) -> String {
+    if s.chars().any(char::is_whitespace) {
+        format!("\"{}\"", s.replace('"', ""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// YAML scalar, double-quoted unless it's plainly a string
+fn yaml_scalar(s: &str) -> String {
+    const KEYWORDS: [&str; 11] = [
+        "true", "false", "yes", "no", "on", "off", "null", "y", "n", "~", "",
+    ];
+    let plain = s
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'))
+        && s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/')
+        && !KEYWORDS.contains(&s.to_lowercase().as_str());
+    if plain {
+        s.to_string()
+    } else {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
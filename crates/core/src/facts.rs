@@ -0,0 +1,272 @@
+//! Host facts (`pctrl server facts`)
+//!
+//! [`COLLECT_SCRIPT`] runs over SSH and prints `key=value` lines; [`parse`]
+//! turns them into a fact map. Keys the parser doesn't know are kept, so the
+//! script can gain facts without a parser change.
+
+use crate::ServerFact;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+
+/// Facts of one host, keyed by name
+pub type Facts = BTreeMap<String, String>;
+
+pub const HOSTNAME: &str = "hostname";
+pub const KERNEL: &str = "kernel";
+pub const ARCH: &str = "arch";
+/// os-release `ID`, e.g. "ubuntu"
+pub const DISTRO: &str = "distro";
+pub const DISTRO_VERSION: &str = "distro_version";
+/// os-release `ID_LIKE`, e.g. "debian"
+pub const DISTRO_LIKE: &str = "distro_like";
+/// systemd-detect-virt output, e.g. "kvm" or "none"
+pub const VIRTUALIZATION: &str = "virtualization";
+pub const PUBLIC_IPS: &str = "public_ips";
+pub const PRIVATE_IPS: &str = "private_ips";
+pub const DOCKER_VERSION: &str = "docker_version";
+pub const PODMAN_VERSION: &str = "podman_version";
+/// Listening TCP ports, comma-separated
+pub const LISTENING_PORTS: &str = "listening_ports";
+pub const TIMEZONE: &str = "timezone";
+/// Name of PID 1, e.g. "systemd" or "openrc-init"
+pub const INIT: &str = "init";
+
+/// Raw address lines of the script, split into [`PUBLIC_IPS`] and [`PRIVATE_IPS`]
+const IP: &str = "ip";
+
+/// Shell script printing the facts as `key=value` lines.
+///
+/// Repeated keys (one line per port or address) are joined by the parser.
+/// Every probe tolerates missing tools, so minimal images still report what
+/// they can.
+pub const COLLECT_SCRIPT: &str = r#"echo "hostname=$(hostname 2>/dev/null)"
+echo "kernel=$(uname -r 2>/dev/null)"
+echo "arch=$(uname -m 2>/dev/null)"
+if [ -r /etc/os-release ]; then
+  (. /etc/os-release; echo "distro=$ID"; echo "distro_version=$VERSION_ID"; echo "distro_like=$ID_LIKE")
+fi
+echo "virtualization=$(systemd-detect-virt 2>/dev/null)"
+for ip in $(hostname -I 2>/dev/null); do echo "ip=$ip"; done
+command -v docker >/dev/null 2>&1 && echo "docker_version=$(docker --version 2>/dev/null | sed 's/^Docker version \([^,]*\).*/\1/')"
+command -v podman >/dev/null 2>&1 && echo "podman_version=$(podman --version 2>/dev/null | awk '{print $3}')"
+(ss -Htln 2>/dev/null || netstat -tln 2>/dev/null | tail -n +3) | awk '{print $4}' | sed 's/.*://' | sort -un | sed 's/^/listening_ports=/'
+echo "timezone=$(timedatectl show -p Timezone --value 2>/dev/null || cat /etc/timezone 2>/dev/null || date +%Z)"
+echo "init=$(cat /proc/1/comm 2>/dev/null)"
+"#;
+
+/// Parse the collection script's output.
+///
+/// Blank lines, lines without `=`, keys with characters other than
+/// `[a-z0-9_.-]` and empty values are skipped. Values of repeated keys are
+/// joined with commas, without duplicates. Surrounding quotes are removed.
+pub fn parse(output: &str) -> Facts {
+    let mut facts = Facts::new();
+    let mut addresses = Vec::new();
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = unquote(value.trim());
+        let valid_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid_key || value.is_empty() {
+            continue;
+        }
+
+        if key == IP {
+            if let Ok(address) = value.parse::<IpAddr>() {
+                addresses.push(address);
+            }
+            continue;
+        }
+        match facts.get_mut(&key) {
+            Some(existing) => {
+                if !existing.split(',').any(|v| v == value) {
+                    existing.push(',');
+                    existing.push_str(value);
+                }
+            }
+            None => {
+                facts.insert(key, value.to_string());
+            }
+        }
+    }
+
+    let (private, public): (Vec<IpAddr>, Vec<IpAddr>) =
+        addresses.into_iter().partition(|ip| is_private(*ip));
+    for (key, list) in [(PUBLIC_IPS, public), (PRIVATE_IPS, private)] {
+        if !list.is_empty() {
+            let list: Vec<String> = list.iter().map(IpAddr::to_string).collect();
+            facts.insert(key.to_string(), list.join(","));
+        }
+    }
+
+    facts
+}
+
+/// Fact map of stored facts
+pub fn to_map(facts: &[ServerFact]) -> Facts {
+    facts
+        .iter()
+        .map(|f| (f.key.clone(), f.value.clone()))
+        .collect()
+}
+
+/// Whether an address is not reachable from the internet
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                // Carrier-grade NAT, 100.64.0.0/10 (e.g. Tailscale)
+                || (v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                // Unique local fc00::/7 and link-local fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// A `key=value` filter for `server facts --query`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactQuery {
+    pub key: String,
+    pub value: String,
+}
+
+impl std::str::FromStr for FactQuery {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Self {
+                key: key.trim().to_lowercase(),
+                value: value.trim().to_string(),
+            }),
+            _ => Err(format!(
+                "Invalid query: {} (expected key=value, e.g. distro=ubuntu)",
+                s
+            )),
+        }
+    }
+}
+
+impl FactQuery {
+    /// Case-insensitive match; comma-separated facts match any element
+    pub fn matches(&self, facts: &Facts) -> bool {
+        facts.get(&self.key).is_some_and(|value| {
+            value.eq_ignore_ascii_case(&self.value)
+                || value
+                    .split(',')
+                    .any(|v| v.eq_ignore_ascii_case(&self.value))
+        })
+    }
+}
+
+/// Package manager of a host's distribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Yum,
+    Apk,
+    Pacman,
+    Zypper,
+}
+
+impl PackageManager {
+    /// Derive from the distro facts, falling back to `distro_like`
+    pub fn from_facts(facts: &Facts) -> Option<Self> {
+        let distro = facts.get(DISTRO).map(String::as_str).unwrap_or_default();
+        let version = facts
+            .get(DISTRO_VERSION)
+            .and_then(|v| v.split('.').next())
+            .and_then(|v| v.parse::<u32>().ok());
+        let like = facts
+            .get(DISTRO_LIKE)
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        std::iter::once(distro)
+            .chain(like.split_whitespace())
+            .find_map(|id| Self::for_distro(id, version))
+    }
+
+    fn for_distro(id: &str, version: Option<u32>) -> Option<Self> {
+        match id {
+            "debian" | "ubuntu" | "raspbian" | "linuxmint" | "pop" => Some(Self::Apt),
+            "fedora" | "rocky" | "almalinux" | "ol" => Some(Self::Dnf),
+            // RHEL and CentOS switched to dnf with version 8
+            "rhel" | "centos" => match version {
+                Some(v) if v < 8 => Some(Self::Yum),
+                _ => Some(Self::Dnf),
+            },
+            "amzn" => match version {
+                Some(v) if v < 2023 => Some(Self::Yum),
+                _ => Some(Self::Dnf),
+            },
+            "alpine" => Some(Self::Apk),
+            "arch" | "manjaro" => Some(Self::Pacman),
+            "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" | "sles" | "suse" => {
+                Some(Self::Zypper)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageManager::Apt => write!(f, "apt"),
+            PackageManager::Dnf => write!(f, "dnf"),
+            PackageManager::Yum => write!(f, "yum"),
+            PackageManager::Apk => write!(f, "apk"),
+            PackageManager::Pacman => write!(f, "pacman"),
+            PackageManager::Zypper => write!(f, "zypper"),
+        }
+    }
+}
+
+/// Container runtime found on the host, e.g. "docker 24.0.5"
+pub fn container_runtime(facts: &Facts) -> Option<String> {
+    facts
+        .get(DOCKER_VERSION)
+        .map(|v| format!("docker {}", v))
+        .or_else(|| facts.get(PODMAN_VERSION).map(|v| format!("podman {}", v)))
+}
+
+/// One-line OS summary, e.g. "ubuntu 22.04 (apt)"
+pub fn os_summary(facts: &Facts) -> Option<String> {
+    let distro = facts.get(DISTRO)?;
+    let mut summary = distro.clone();
+    if let Some(version) = facts.get(DISTRO_VERSION) {
+        summary.push(' ');
+        summary.push_str(version);
+    }
+    if let Some(pm) = PackageManager::from_facts(facts) {
+        summary.push_str(&format!(" ({})", pm));
+    }
+    Some(summary)
+}
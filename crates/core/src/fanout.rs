@@ -0,0 +1,203 @@
+//! Per-target results of commands that act on many targets
+//!
+//! `project start/stop` and `monitor run --once` work through several
+//! containers or servers. [`fan_out`] runs the targets and times each one;
+//! the results end up in a [`FanoutReport`], which is printed as a table or
+//! JSON, kept as the command's last run (`pctrl last-run`) and turned into
+//! the exit code by the `--fail-on` policy.
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Exit code when the `--fail-on` policy is met
+pub const EXIT_TARGETS_FAILED: i32 = 1;
+
+/// How a single target went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Ok,
+    Failed,
+    /// Not attempted, e.g. after an earlier phase failed
+    Skipped,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Ok => write!(f, "ok"),
+            Outcome::Failed => write!(f, "failed"),
+            Outcome::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Result for one target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetResult {
+    pub id: String,
+    pub name: String,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+    /// Short reason or detail, e.g. "already running"
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl TargetResult {
+    /// A target that wasn't attempted
+    pub fn skipped(id: &str, name: &str, message: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            outcome: Outcome::Skipped,
+            duration_ms: 0,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+/// Number of targets per outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counts {
+    pub ok: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+impl Counts {
+    pub fn total(&self) -> usize {
+        self.ok + self.failed + self.skipped
+    }
+}
+
+/// Results of one run of a fan-out command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FanoutReport {
+    /// Command path, e.g. "project start"
+    pub command: String,
+    /// RFC 3339
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub counts: Counts,
+    pub targets: Vec<TargetResult>,
+}
+
+impl FanoutReport {
+    pub fn new(command: &str, started_at: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            started_at: started_at.to_string(),
+            duration_ms: 0,
+            counts: Counts::default(),
+            targets: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, result: TargetResult) {
+        match result.outcome {
+            Outcome::Ok => self.counts.ok += 1,
+            Outcome::Failed => self.counts.failed += 1,
+            Outcome::Skipped => self.counts.skipped += 1,
+        }
+        self.targets.push(result);
+    }
+
+    pub fn extend(&mut self, results: impl IntoIterator<Item = TargetResult>) {
+        for result in results {
+            self.push(result);
+        }
+    }
+
+    /// One-line summary, e.g. "3 ok, 1 failed, 2 skipped"
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} ok", self.counts.ok)];
+        if self.counts.failed > 0 {
+            parts.push(format!("{} failed", self.counts.failed));
+        }
+        if self.counts.skipped > 0 {
+            parts.push(format!("{} skipped", self.counts.skipped));
+        }
+        parts.join(", ")
+    }
+}
+
+/// When a fan-out command exits with [`EXIT_TARGETS_FAILED`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailOn {
+    /// Any target failed
+    #[default]
+    Any,
+    /// Every attempted target failed
+    All,
+    /// Never; the report tells
+    None,
+}
+
+impl FailOn {
+    pub fn exit_code(self, counts: &Counts) -> i32 {
+        let failed = match self {
+            FailOn::Any => counts.failed > 0,
+            FailOn::All => counts.failed > 0 && counts.ok == 0,
+            FailOn::None => false,
+        };
+        if failed {
+            EXIT_TARGETS_FAILED
+        } else {
+            0
+        }
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(FailOn::Any),
+            "all" => Ok(FailOn::All),
+            "none" => Ok(FailOn::None),
+            _ => Err(format!("Unknown policy '{}', use any, all or none", s)),
+        }
+    }
+}
+
+impl fmt::Display for FailOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailOn::Any => write!(f, "any"),
+            FailOn::All => write!(f, "all"),
+            FailOn::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Run `run` for every target concurrently and time each one. `label`
+/// gives a target's ID and name.
+pub async fn fan_out<'a, T, L, F, Fut>(targets: &'a [T], label: L, run: F) -> Vec<TargetResult>
+where
+    L: Fn(&T) -> (String, String),
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = (Outcome, Option<String>)>,
+{
+    join_all(targets.iter().map(|target| {
+        let (id, name) = label(target);
+        let run = run(target);
+        async move {
+            let started = Instant::now();
+            let (outcome, message) = run.await;
+            TargetResult {
+                id,
+                name,
+                outcome,
+                duration_ms: started.elapsed().as_millis() as u64,
+                message,
+            }
+        }
+    }))
+    .await
+}
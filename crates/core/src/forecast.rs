@@ -0,0 +1,174 @@
+//! Disk usage forecasting from historical samples
+//!
+//! A least-squares line through (time, used %) samples, projected forward to
+//! the 90% and 100% marks. Forecasts are refused when the samples are too few
+//! or cover too short a period, so two readings taken a minute apart can't
+//! predict a full disk next Tuesday.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Minimum number of samples for a forecast (after thinning to one per hour)
+pub const MIN_SAMPLES: usize = 5;
+
+/// Minimum time between the first and last sample
+pub const MIN_SPAN_HOURS: i64 = 72;
+
+/// Growth below this (percentage points per week) counts as flat
+pub const FLAT_PER_WEEK: f64 = 0.1;
+
+/// Projections further out than this are not reported
+pub const MAX_HORIZON_DAYS: i64 = 5 * 365;
+
+/// Warn when the disk is projected to fill within this many days
+pub const WARN_DAYS: i64 = 30;
+
+/// Trend of a disk usage series
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trend {
+    /// Not enough samples, or they cover too little time
+    InsufficientData {
+        samples: usize,
+        span_hours: i64,
+    },
+    Flat,
+    Shrinking,
+    Growing,
+}
+
+/// Forecast for one disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskForecast {
+    /// Latest sampled usage in percent
+    pub current: f64,
+    /// Fitted growth in percentage points per week
+    pub per_week: f64,
+    pub trend: Trend,
+    /// Projected time of reaching 90% (only for growing trends)
+    pub reaches_90: Option<DateTime<Utc>>,
+    /// Projected time of reaching 100% (only for growing trends)
+    pub reaches_100: Option<DateTime<Utc>>,
+}
+
+impl DiskForecast {
+    /// Whether the disk is projected to be full within `days` of `now`
+    pub fn full_within(&self, days: i64, now: DateTime<Utc>) -> bool {
+        self.reaches_100
+            .is_some_and(|full| full <= now + Duration::days(days))
+    }
+}
+
+/// Least-squares fit of `y = slope * x + intercept`.
+///
+/// Returns `None` for fewer than two points or when all x are equal.
+pub fn linear_regression(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (x, y) in points {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x) * (x - mean_x);
+    }
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// Forecast disk usage from `(time, used percent)` samples.
+///
+/// Returns `None` without samples. Samples are thinned to the latest one per
+/// hour first, so a burst of readings can't outweigh the long-term trend.
+pub fn forecast(samples: &[(DateTime<Utc>, f64)], now: DateTime<Utc>) -> Option<DiskForecast> {
+    let samples = thin_hourly(samples);
+    let samples = samples.as_slice();
+    let first = samples.iter().map(|(t, _)| *t).min()?;
+    let (last_time, current) = samples
+        .iter()
+        .max_by_key(|(t, _)| *t)
+        .map(|(t, v)| (*t, *v))?;
+
+    let span_hours = (last_time - first).num_hours();
+    let insufficient = Trend::InsufficientData {
+        samples: samples.len(),
+        span_hours,
+    };
+    if samples.len() < MIN_SAMPLES || span_hours < MIN_SPAN_HOURS {
+        return Some(DiskForecast {
+            current,
+            per_week: 0.0,
+            trend: insufficient,
+            reaches_90: None,
+            reaches_100: None,
+        });
+    }
+
+    // x in days since the first sample keeps the numbers well-conditioned
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|(t, v)| ((*t - first).num_seconds() as f64 / 86_400.0, *v))
+        .collect();
+    let Some((per_day, intercept)) = linear_regression(&points) else {
+        return Some(DiskForecast {
+            current,
+            per_week: 0.0,
+            trend: insufficient,
+            reaches_90: None,
+            reaches_100: None,
+        });
+    };
+
+    let per_week = per_day * 7.0;
+    let trend = if per_week.abs() < FLAT_PER_WEEK {
+        Trend::Flat
+    } else if per_week < 0.0 {
+        Trend::Shrinking
+    } else {
+        Trend::Growing
+    };
+
+    let project = |target: f64| -> Option<DateTime<Utc>> {
+        if trend != Trend::Growing {
+            return None;
+        }
+        if current >= target {
+            return Some(last_time);
+        }
+        let days = (target - intercept) / per_day;
+        let at = first + Duration::seconds((days * 86_400.0) as i64);
+        let at = at.max(now);
+        (at <= now + Duration::days(MAX_HORIZON_DAYS)).then_some(at)
+    };
+
+    Some(DiskForecast {
+        current,
+        per_week,
+        trend,
+        reaches_90: project(90.0),
+        reaches_100: project(100.0),
+    })
+}
+
+/// Keep the latest sample of every hour, sorted by time
+fn thin_hourly(samples: &[(DateTime<Utc>, f64)]) -> Vec<(DateTime<Utc>, f64)> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by_key(|(t, _)| *t);
+
+    let mut thinned: Vec<(DateTime<Utc>, f64)> = Vec::with_capacity(sorted.len());
+    for sample in sorted {
+        let hour = sample.0.timestamp().div_euclid(3600);
+        match thinned.last_mut() {
+            Some(last) if last.0.timestamp().div_euclid(3600) == hour => *last = sample,
+            _ => thinned.push(sample),
+        }
+    }
+    thinned
+}
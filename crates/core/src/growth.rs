@@ -0,0 +1,291 @@
+//! Entity counts over time, reconstructed from the audit log
+//! (`pctrl stats growth`)
+//!
+//! Only the current counts and the audited creations and removals are
+//! known, so the counts are worked out backwards: the count at the end of a
+//! period is today's count minus every change after it. Entities older than
+//! the audit log are covered that way too; they are the
+//! [`Series::baseline`] the audited changes start from.
+//!
+//! Trashing a server is audited as a removal and restoring it as a
+//! creation. Changes that don't change anything (a trashed server that is
+//! then deleted for good is removed twice) are counted once, see
+//! [`changes`].
+
+use crate::{AuditAction, EntityType};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Entity types in the order they are shown
+pub const ENTITY_TYPES: [EntityType; 6] = [
+    EntityType::Project,
+    EntityType::Server,
+    EntityType::Domain,
+    EntityType::Database,
+    EntityType::Script,
+    EntityType::Credential,
+];
+
+/// Bars of [`sparkline`], lowest first
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Length of the periods counts are bucketed into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    /// Monday to Sunday
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl Granularity {
+    /// Start of the period containing `time` (UTC)
+    pub fn start_of(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let date = time.date_naive();
+        let start = match self {
+            Granularity::Day => date,
+            Granularity::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+            Granularity::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+        };
+        start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+    }
+
+    /// Start of the period after the one starting at `start`
+    pub fn next(self, start: DateTime<Utc>) -> DateTime<Utc> {
+        let date = start.date_naive();
+        let next = match self {
+            Granularity::Day => date + Duration::days(1),
+            Granularity::Week => date + Duration::days(7),
+            Granularity::Month => {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(date)
+            }
+            Granularity::Year => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1).unwrap_or(date),
+        };
+        next.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+    }
+
+    /// Short name of the period starting at `start`, e.g. `2025-06`
+    pub fn label(self, start: DateTime<Utc>) -> String {
+        match self {
+            Granularity::Day | Granularity::Week => start.format("%Y-%m-%d").to_string(),
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+            Granularity::Year => "year",
+        })
+    }
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "day" | "daily" => Ok(Granularity::Day),
+            "week" | "weekly" => Ok(Granularity::Week),
+            "month" | "monthly" => Ok(Granularity::Month),
+            "year" | "yearly" => Ok(Granularity::Year),
+            other => Err(format!(
+                "Unknown granularity '{}' (use day, week, month or year)",
+                other
+            )),
+        }
+    }
+}
+
+/// A creation or removal from the audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrowthEvent {
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    pub action: AuditAction,
+    pub at: DateTime<Utc>,
+}
+
+/// A change of one entity type's count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    pub entity_type: EntityType,
+    pub at: DateTime<Utc>,
+    /// +1 or -1
+    pub delta: i64,
+}
+
+/// Count changes of `events`, given oldest first. Updates are skipped, and
+/// so are creations of entities that exist and removals of ones that
+/// don't. An entity first seen being removed was created before the audit
+/// log began.
+pub fn changes(events: &[GrowthEvent]) -> Vec<Change> {
+    let mut exists: HashMap<(EntityType, &str), bool> = HashMap::new();
+    let mut changes = Vec::new();
+    for event in events {
+        let created = match event.action {
+            AuditAction::Created => true,
+            AuditAction::Removed => false,
+            AuditAction::Updated => continue,
+        };
+        let existed = exists
+            .entry((event.entity_type, event.entity_id.as_str()))
+            .or_insert(!created);
+        if *existed != created {
+            changes.push(Change {
+                entity_type: event.entity_type,
+                at: event.at,
+                delta: if created { 1 } else { -1 },
+            });
+            *existed = created;
+        }
+    }
+    changes
+}
+
+/// Start of every period from the one containing `since` to the one
+/// containing `now`
+pub fn periods(
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    granularity: Granularity,
+) -> Vec<DateTime<Utc>> {
+    let mut periods = Vec::new();
+    let mut start = granularity.start_of(since.min(now));
+    while start <= now {
+        periods.push(start);
+        start = granularity.next(start);
+    }
+    periods
+}
+
+/// Counts of one entity type over time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Series {
+    pub entity_type: EntityType,
+    /// Count at the end of each period; the current count for the last
+    pub counts: Vec<u64>,
+    /// Creations in each period
+    pub created: Vec<u64>,
+    /// Removals in each period
+    pub removed: Vec<u64>,
+    /// Count before the first audited change: entities older than the log
+    pub baseline: u64,
+}
+
+impl Series {
+    /// Change from the first period to now
+    pub fn change(&self) -> i64 {
+        match (self.counts.first(), self.counts.last()) {
+            (Some(first), Some(last)) => *last as i64 - *first as i64,
+            _ => 0,
+        }
+    }
+}
+
+/// Entity counts per period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Growth {
+    pub granularity: Granularity,
+    /// Start of each period
+    pub periods: Vec<DateTime<Utc>>,
+    pub series: Vec<Series>,
+}
+
+/// Work out the counts per period from the `current` counts and the audit
+/// `events` (all of them, oldest first). Counts that would go below zero,
+/// because rows were removed without an audit entry, show as zero.
+pub fn reconstruct(
+    current: &[(EntityType, u64)],
+    events: &[GrowthEvent],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    granularity: Granularity,
+) -> Growth {
+    let changes = changes(events);
+    let periods = periods(since, now, granularity);
+    let series = current
+        .iter()
+        .map(|&(entity_type, count)| {
+            let mine: Vec<&Change> = changes
+                .iter()
+                .filter(|c| c.entity_type == entity_type)
+                .collect();
+            let count = count as i64;
+            let net: i64 = mine.iter().map(|c| c.delta).sum();
+            let mut series = Series {
+                entity_type,
+                counts: Vec::with_capacity(periods.len()),
+                created: Vec::with_capacity(periods.len()),
+                removed: Vec::with_capacity(periods.len()),
+                baseline: (count - net).max(0) as u64,
+            };
+            for &start in &periods {
+                let end = granularity.next(start);
+                let later: i64 = mine.iter().filter(|c| c.at >= end).map(|c| c.delta).sum();
+                let within = mine.iter().filter(|c| c.at >= start && c.at < end);
+                let (created, removed) = within.fold((0, 0), |(up, down), c| {
+                    if c.delta > 0 {
+                        (up + 1, down)
+                    } else {
+                        (up, down + 1)
+                    }
+                });
+                series.counts.push((count - later).max(0) as u64);
+                series.created.push(created);
+                series.removed.push(removed);
+            }
+            series
+        })
+        .collect();
+    Growth {
+        granularity,
+        periods,
+        series,
+    }
+}
+
+/// One bar per value, scaled from zero to the largest
+pub fn sparkline(values: &[u64]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    let top = BARS.len() as u64 - 1;
+    values
+        .iter()
+        .map(|&v| BARS[(v * top).checked_div(max).unwrap_or(0) as usize])
+        .collect()
+}
+
+/// Entities of one kind, e.g. servers of one provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Share {
+    pub name: String,
+    pub count: u64,
+}
+
+/// What the current entities are made of; each list most first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Composition {
+    /// Servers per provider ("none" when unset)
+    pub servers_by_provider: Vec<Share>,
+    pub projects_by_status: Vec<Share>,
+    pub domains_by_type: Vec<Share>,
+}
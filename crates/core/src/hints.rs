@@ -0,0 +1,429 @@
+//! Next-step suggestions after a command
+//!
+//! A command describes what it just did as an [`Event`], together with what
+//! the inventory holds ([`Context`]). Every rule in [`RULES`] looks at that
+//! and may propose hints; [`suggest`] ranks them and keeps the best
+//! [`MAX_HINTS`], so the CLI never buries its output under advice.
+
+use std::fmt;
+
+/// Most hints shown after one command
+pub const MAX_HINTS: usize = 2;
+
+/// A suggested next command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    /// What the command does, e.g. "Add one with"
+    pub label: String,
+    pub command: String,
+    /// Higher ranks are shown first
+    pub rank: u8,
+}
+
+impl Hint {
+    fn new(rank: u8, label: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            command: command.into(),
+            rank,
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.label, self.command)
+    }
+}
+
+/// A list that can come back empty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listing {
+    Projects,
+    Servers,
+    Domains,
+    Databases,
+    Scripts,
+    Services,
+    Credentials,
+    Snapshots,
+    CoolifyInstances,
+    DockerHosts,
+    /// Servers that require a VPN
+    VpnServers,
+}
+
+/// Why a connection to a server failed, from the error text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The server's credential is missing or not an SSH credential
+    Credential,
+    /// The server answered but refused the login
+    Auth,
+    /// The server couldn't be reached at all
+    Unreachable,
+}
+
+impl Failure {
+    /// Classify a connection error
+    pub fn of(error: &str) -> Self {
+        let error = error.to_ascii_lowercase();
+        if error.contains("credential") {
+            Failure::Credential
+        } else if error.contains("authentication") || error.contains("agent") {
+            Failure::Auth
+        } else {
+            Failure::Unreachable
+        }
+    }
+}
+
+/// What a command just did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A list command found nothing
+    Empty(Listing),
+    /// The server trash was listed
+    TrashListed,
+    ServerAdded {
+        name: String,
+        host: String,
+        credential: Option<String>,
+        /// Whether the specs were detected
+        specs: bool,
+    },
+    /// `server status` on a server without a credential
+    StatusWithoutCredential {
+        name: String,
+        host: String,
+    },
+    StatusFailed {
+        name: String,
+        credential: String,
+        /// Whether the server is marked as VPN-only
+        vpn: bool,
+        failure: Failure,
+    },
+    DomainAdded {
+        domain: String,
+        server: Option<String>,
+    },
+    DockerHostAdded {
+        id: String,
+    },
+    /// A Docker host without synced networks
+    NoNetworks {
+        host: String,
+    },
+    ServiceAdded {
+        name: String,
+    },
+    /// A project start/stop found no linked containers
+    NoContainersLinked {
+        project: String,
+    },
+    /// A dangerous script's change waits for approval
+    ScriptChangePending {
+        revision: i64,
+    },
+    /// Pending script changes were listed
+    PendingListed,
+    DemoSeeded,
+}
+
+/// An event and what the inventory holds around it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub event: Event,
+    /// Project names
+    pub projects: Vec<String>,
+    /// Server names
+    pub servers: Vec<String>,
+    /// Credential names
+    pub credentials: Vec<String>,
+}
+
+impl Context {
+    /// An event in an empty inventory
+    pub fn new(event: Event) -> Self {
+        Self {
+            event,
+            projects: Vec::new(),
+            servers: Vec::new(),
+            credentials: Vec::new(),
+        }
+    }
+}
+
+/// A rule: the hints it has for a context, if any
+pub type Rule = fn(&Context) -> Vec<Hint>;
+
+/// Every rule, in no particular order; ranks decide what is shown
+pub const RULES: &[Rule] = &[
+    empty_list,
+    follow_up,
+    server_credential,
+    server_specs,
+    link_to_project,
+    domain_server,
+    status_failed,
+];
+
+/// The best hints for a context, at most [`MAX_HINTS`], highest rank
+/// first. Equal ranks keep rule order; a command proposed twice is kept once.
+pub fn suggest(context: &Context) -> Vec<Hint> {
+    let mut hints: Vec<Hint> = RULES.iter().flat_map(|rule| rule(context)).collect();
+    hints.sort_by_key(|h| std::cmp::Reverse(h.rank));
+    let mut shown: Vec<Hint> = Vec::new();
+    for hint in hints {
+        if !shown.iter().any(|h| h.command == hint.command) {
+            shown.push(hint);
+        }
+    }
+    shown.truncate(MAX_HINTS);
+    shown
+}
+
+/// `arg` as one shell word
+fn quote(arg: &str) -> String {
+    shlex::try_quote(arg)
+        .map(|q| q.into_owned())
+        .unwrap_or_else(|_| arg.to_string())
+}
+
+/// The one name in `names`, quoted, or a placeholder
+fn only_or(names: &[String], placeholder: &str) -> String {
+    match names {
+        [name] => quote(name),
+        _ => placeholder.to_string(),
+    }
+}
+
+/// How to fill an empty list
+fn empty_list(context: &Context) -> Vec<Hint> {
+    let Event::Empty(listing) = &context.event else {
+        return Vec::new();
+    };
+    let (label, command) = match listing {
+        Listing::Projects => (
+            "Add one with",
+            "pctrl project add <name> [-d description] [-s stack]",
+        ),
+        Listing::Servers => (
+            "Add one with",
+            "pctrl server add <name> <host> [-t type] [-p provider] [-c credential]",
+        ),
+        Listing::Domains => (
+            "Add one with",
+            "pctrl domain add <domain> [-t type] [-s server]",
+        ),
+        Listing::Databases => (
+            "Add one with",
+            "pctrl database add <name> -t <type> -u <user> -P <password>",
+        ),
+        Listing::Scripts => ("Add one with", "pctrl script add <name> -c <command>"),
+        Listing::Services => (
+            "Add one with",
+            "pctrl service add <name> --server <server> --unit <unit>",
+        ),
+        Listing::Credentials => (
+            "Add one with",
+            "pctrl credential add <name> --type ssh --user root --key ~/.ssh/id_rsa",
+        ),
+        Listing::Snapshots => ("Create one with", "pctrl snapshot create [name]"),
+        Listing::CoolifyInstances => (
+            "Add one with",
+            "pctrl coolify add <name> -u <url> -t <token>",
+        ),
+        Listing::DockerHosts => ("Add one with", "pctrl docker add <name> <url>"),
+        Listing::VpnServers => (
+            "Mark one with",
+            "pctrl server edit <name> --requires-vpn wg0",
+        ),
+    };
+    vec![Hint::new(5, label, command)]
+}
+
+/// The obvious next command after events with a single one
+fn follow_up(context: &Context) -> Vec<Hint> {
+    let hint = match &context.event {
+        Event::TrashListed => Hint::new(5, "Restore with", "pctrl server restore <name>"),
+        Event::DockerHostAdded { id } => Hint::new(
+            5,
+            "Check the connection with",
+            format!("pctrl docker check {}", quote(id)),
+        ),
+        Event::NoNetworks { host } => Hint::new(
+            5,
+            "Fetch them with",
+            format!("pctrl docker sync {}", quote(host)),
+        ),
+        Event::ServiceAdded { name } => Hint::new(
+            5,
+            "Check it with",
+            format!("pctrl service status {}", quote(name)),
+        ),
+        Event::NoContainersLinked { project } => Hint::new(
+            5,
+            "Link one with",
+            format!(
+                "pctrl project link {} container <name> --order 10",
+                quote(project)
+            ),
+        ),
+        Event::ScriptChangePending { revision } => Hint::new(
+            5,
+            "Until approved, runs use the current command. Approve with",
+            format!("pctrl script approve {}", revision),
+        ),
+        Event::PendingListed => Hint::new(
+            5,
+            "Approve or reject with",
+            "pctrl script approve|reject <#>",
+        ),
+        Event::DemoSeeded => Hint::new(5, "Remove it with", "pctrl debug clear-demo"),
+        _ => return Vec::new(),
+    };
+    vec![hint]
+}
+
+/// A server without a credential can't be reached; link one, or add one
+/// when there is none yet
+fn server_credential(context: &Context) -> Vec<Hint> {
+    let (name, host) = match &context.event {
+        Event::ServerAdded {
+            name,
+            host,
+            credential: None,
+            ..
+        }
+        | Event::StatusWithoutCredential { name, host } => (name, host),
+        _ => return Vec::new(),
+    };
+    let hint = if context.credentials.is_empty() {
+        Hint::new(
+            4,
+            "Add an SSH credential with",
+            "pctrl credential add <name> --type ssh --user root --key ~/.ssh/id_rsa",
+        )
+    } else {
+        Hint::new(
+            4,
+            "Link a credential and detect the specs with",
+            format!(
+                "pctrl server add {} {} -c {} --ensure",
+                quote(name),
+                quote(host),
+                only_or(&context.credentials, "<credential>")
+            ),
+        )
+    };
+    vec![hint]
+}
+
+/// Spec detection failed when the server was added; it runs again with
+/// the credential
+fn server_specs(context: &Context) -> Vec<Hint> {
+    match &context.event {
+        Event::ServerAdded {
+            name,
+            host,
+            credential: Some(credential),
+            specs: false,
+        } => vec![Hint::new(
+            3,
+            "Detect the specs again with",
+            format!(
+                "pctrl server add {} {} -c {} --ensure",
+                quote(name),
+                quote(host),
+                quote(credential)
+            ),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// With a single project, a new server or domain most likely belongs to it
+fn link_to_project(context: &Context) -> Vec<Hint> {
+    let (resource_type, id) = match &context.event {
+        Event::ServerAdded { name, .. } => ("server", name),
+        Event::DomainAdded { domain, .. } => ("domain", domain),
+        _ => return Vec::new(),
+    };
+    match context.projects.as_slice() {
+        [project] => vec![Hint::new(
+            2,
+            format!("Link it to {} with", project),
+            format!(
+                "pctrl project link {} {} {}",
+                quote(project),
+                resource_type,
+                quote(id)
+            ),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// A domain without a server can't be checked for propagation or certificates
+fn domain_server(context: &Context) -> Vec<Hint> {
+    match &context.event {
+        Event::DomainAdded {
+            domain,
+            server: None,
+        } if !context.servers.is_empty() => vec![Hint::new(
+            3,
+            "Point it at a server with",
+            format!(
+                "pctrl domain add {} -s {} --ensure",
+                quote(domain),
+                only_or(&context.servers, "<server>")
+            ),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// Where to look after a failed `server status`
+fn status_failed(context: &Context) -> Vec<Hint> {
+    let Event::StatusFailed {
+        name,
+        credential,
+        vpn,
+        failure,
+    } = &context.event
+    else {
+        return Vec::new();
+    };
+    match failure {
+        Failure::Credential => vec![
+            Hint::new(4, "Find broken references with", "pctrl doctor"),
+            Hint::new(
+                3,
+                "Check the credential with",
+                format!("pctrl credential show {}", quote(credential)),
+            ),
+        ],
+        Failure::Auth => vec![Hint::new(
+            4,
+            "Check the credential with",
+            format!("pctrl credential show {}", quote(credential)),
+        )],
+        Failure::Unreachable => {
+            let mut hints = vec![Hint::new(
+                4,
+                "Check the host with",
+                format!("pctrl server show {}", quote(name)),
+            )];
+            if !vpn {
+                hints.push(Hint::new(
+                    3,
+                    "If it is only reachable through a VPN, mark it with",
+                    format!("pctrl server edit {} --requires-vpn wg0", quote(name)),
+                ));
+            }
+            hints
+        }
+    }
+}
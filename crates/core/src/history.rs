@@ -0,0 +1,269 @@
+//! An entity as it was at an earlier time, rebuilt from the audit log
+//!
+//! Updates store their field-level changes (see [`crate::diff`]), so the
+//! current row can be walked back: starting from it, the changes of every
+//! entry after the requested time are reverted, newest first. Creations and
+//! removals toggle whether the entity existed. What can't be known is
+//! reported instead of guessed: changes recorded before field details
+//! existed, edits from before the audit log, a removed entity whose row is
+//! gone, and secrets, which the log only stores masked.
+
+use crate::diff::FieldChange;
+use crate::redact::REDACTED;
+use crate::{AuditAction, AuditEntry};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What the history of one entity is rebuilt from
+#[derive(Debug, Clone)]
+pub struct EntityHistory {
+    pub id: String,
+    pub name: String,
+    /// Its row as serialized JSON, also when in the trash; `None` when gone
+    pub current: Option<Value>,
+    /// All its audit entries
+    pub entries: Vec<AuditEntry>,
+}
+
+/// Why a rebuilt field may be wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uncertainty {
+    /// A secret; the log only has it masked
+    Secret,
+    /// Its value didn't match what the log says it was changed to, so it
+    /// was also changed without an entry
+    Unrecorded,
+}
+
+impl fmt::Display for Uncertainty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Uncertainty::Secret => write!(f, "secret, only recorded masked"),
+            Uncertainty::Unrecorded => write!(f, "also changed without an audit entry"),
+        }
+    }
+}
+
+/// A stretch of history that wasn't recorded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Gap {
+    /// The time is before the entity's first entry, which isn't its
+    /// creation: edits before the audit log aren't known. `first` is the
+    /// time of the first entry, if there is one.
+    BeforeHistory { first: Option<String> },
+    /// An update recorded without field details (before they were stored)
+    Undetailed { at: String },
+    /// The entity was removed for good; its fields before that are gone
+    RemovedRow { at: String },
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Gap::BeforeHistory { first: Some(first) } => write!(
+                f,
+                "history starts {}; edits before that weren't recorded",
+                first
+            ),
+            Gap::BeforeHistory { first: None } => {
+                write!(f, "no recorded history; any field may have changed")
+            }
+            Gap::Undetailed { at } => {
+                write!(f, "the update at {} was recorded without field details", at)
+            }
+            Gap::RemovedRow { at } => {
+                write!(f, "removed at {}; its fields weren't kept", at)
+            }
+        }
+    }
+}
+
+/// An entity as of a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconstruction {
+    /// Whether the entity existed then
+    pub existed: bool,
+    /// Its fields then; `None` when it didn't exist or they aren't known
+    pub state: Option<Value>,
+    /// Fields whose rebuilt value may be wrong, by dotted path
+    pub uncertain: BTreeMap<String, Uncertainty>,
+    pub gaps: Vec<Gap>,
+}
+
+/// Rebuild an entity as of `at`.
+///
+/// `current` is its row as serialized JSON, also when it's in the trash;
+/// `None` when the row is gone. `entries` are all audit entries of the
+/// entity, in any order.
+pub fn reconstruct(
+    current: Option<&Value>,
+    entries: &[AuditEntry],
+    at: DateTime<Utc>,
+) -> Reconstruction {
+    let mut entries: Vec<&AuditEntry> = entries.iter().collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.id));
+
+    let mut state = current.cloned();
+    let mut existed = match entries.first() {
+        Some(newest) => newest.action != AuditAction::Removed && state.is_some(),
+        None => state.is_some(),
+    };
+    let mut uncertain = BTreeMap::new();
+    let mut gaps = Vec::new();
+
+    for entry in entries.iter().filter(|e| !happened_by(e, at)) {
+        match entry.action {
+            // A restore from the trash is recorded as a creation
+            AuditAction::Created => existed = false,
+            AuditAction::Removed => {
+                existed = true;
+                if state.is_none() {
+                    gaps.push(Gap::RemovedRow {
+                        at: entry.created_at.clone(),
+                    });
+                }
+            }
+            AuditAction::Updated => {
+                let changes = entry.changes();
+                if changes.is_empty() {
+                    gaps.push(Gap::Undetailed {
+                        at: entry.created_at.clone(),
+                    });
+                }
+                if let Some(state) = state.as_mut() {
+                    for change in &changes {
+                        if let Some(reason) = revert(state, change) {
+                            uncertain.insert(change.field.clone(), reason);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if existed && !entries.iter().any(|e| happened_by(e, at)) {
+        gaps.push(Gap::BeforeHistory {
+            first: entries.last().map(|e| e.created_at.clone()),
+        });
+    }
+
+    Reconstruction {
+        existed,
+        state: if existed { state } else { None },
+        uncertain: if existed { uncertain } else { BTreeMap::new() },
+        gaps,
+    }
+}
+
+/// Whether `entry` was recorded at or before `at`; entries with an
+/// unreadable time count as later, so they're reverted
+fn happened_by(entry: &AuditEntry, at: DateTime<Utc>) -> bool {
+    DateTime::parse_from_rfc3339(&entry.created_at).is_ok_and(|t| t <= at)
+}
+
+/// Put the old value of `change` back, returning why it may be wrong
+fn revert(state: &mut Value, change: &FieldChange) -> Option<Uncertainty> {
+    let path: Vec<&str> = change.field.split('.').collect();
+    let masked = |v: &Value| v.as_str() == Some(REDACTED);
+    let reason = if masked(&change.old) || masked(&change.new) {
+        Some(Uncertainty::Secret)
+    } else if field(state, &change.field) != &change.new {
+        Some(Uncertainty::Unrecorded)
+    } else {
+        None
+    };
+    set_path(state, &path, change.old.clone());
+    reason
+}
+
+/// Value at a dotted path; `Null` when missing
+pub fn field<'a>(value: &'a Value, path: &str) -> &'a Value {
+    path.split('.')
+        .try_fold(value, |v, key| v.get(key))
+        .unwrap_or(&Value::Null)
+}
+
+/// Set a dotted path, creating objects on the way. A nested object left
+/// with only unset fields becomes unset itself, like it was serialized
+/// before it got its first field.
+fn set_path(value: &mut Value, path: &[&str], new: Value) {
+    let Some((key, rest)) = path.split_first() else {
+        *value = new;
+        return;
+    };
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+    let Some(map) = value.as_object_mut() else {
+        return;
+    };
+    let child = map.entry(key.to_string()).or_insert(Value::Null);
+    set_path(child, rest, new);
+    if !rest.is_empty()
+        && child
+            .as_object()
+            .is_some_and(|m| m.values().all(Value::is_null))
+    {
+        *child = Value::Null;
+    }
+}
+
+/// Leaf fields of an entity by dotted path, sorted
+pub fn fields(value: &Value) -> Vec<(String, Value)> {
+    let mut fields = Vec::new();
+    collect_fields(&mut fields, "", value);
+    fields
+}
+
+fn collect_fields(fields: &mut Vec<(String, Value)>, path: &str, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect_fields(fields, &child_path, child);
+            }
+        }
+        other => fields.push((path.to_string(), other.clone())),
+    }
+}
+
+/// One line of an entity's timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEvent {
+    /// RFC 3339
+    pub at: String,
+    pub action: AuditAction,
+    pub actor: String,
+    /// The changed field, for updates with details
+    pub change: Option<FieldChange>,
+}
+
+/// Condensed history, oldest first: one event per creation or removal and
+/// per changed field of an update
+pub fn timeline(entries: &[AuditEntry]) -> Vec<TimelineEvent> {
+    let mut entries: Vec<&AuditEntry> = entries.iter().collect();
+    entries.sort_by_key(|e| e.id);
+
+    let mut events = Vec::new();
+    for entry in entries {
+        let event = |change| TimelineEvent {
+            at: entry.created_at.clone(),
+            action: entry.action,
+            actor: entry.actor.clone(),
+            change,
+        };
+        let changes = entry.changes();
+        if changes.is_empty() {
+            events.push(event(None));
+        } else {
+            events.extend(changes.into_iter().map(|c| event(Some(c))));
+        }
+    }
+    events
+}
@@ -0,0 +1,385 @@
+//! Hooks: user executables run after pctrl events
+//!
+//! Hooks live in `<hooks dir>/<event>/`, e.g. `hooks/server.created/notify.sh`,
+//! and receive a JSON payload on stdin. They run one after another in file
+//! name order. Files ending in `.disabled`, hidden files and files without an
+//! executable bit are skipped. A failing hook never fails the operation that
+//! fired it.
+
+use crate::redact::{is_secret_key, REDACTED};
+use crate::{AuditAction, EntityType};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+/// Fired after a script run finished
+pub const SCRIPT_FINISHED: &str = "script.finished";
+
+/// Fired when a monitored check changes state
+pub const MONITOR_CHANGED: &str = "monitor.changed";
+
+/// Suffix that switches a hook off without deleting it
+pub const DISABLED_SUFFIX: &str = ".disabled";
+
+/// Default time a hook may run before it is killed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Captured output is cut off after this many bytes
+const OUTPUT_LIMIT: usize = 4096;
+
+const AUDIT_ACTIONS: [AuditAction; 3] = [
+    AuditAction::Created,
+    AuditAction::Updated,
+    AuditAction::Removed,
+];
+
+/// Event name of an entity mutation, e.g. "server.created"
+pub fn entity_event(entity_type: EntityType, action: AuditAction) -> String {
+    format!("{}.{}", entity_type, action)
+}
+
+/// All events pctrl fires
+pub fn all_events() -> Vec<String> {
+    let mut events: Vec<String> = EntityType::ALL
+        .iter()
+        .flat_map(|t| AUDIT_ACTIONS.iter().map(|a| entity_event(*t, *a)))
+        .collect();
+    events.push(SCRIPT_FINISHED.to_string());
+    events.push(MONITOR_CHANGED.to_string());
+    events
+}
+
+/// Whether pctrl fires `event` (catches typos in hook directory names)
+pub fn is_known_event(event: &str) -> bool {
+    all_events().iter().any(|e| e == event)
+}
+
+/// JSON document a hook receives on stdin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookPayload {
+    pub event: String,
+    /// RFC 3339, UTC
+    pub timestamp: String,
+    /// Who triggered the event (user@host)
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Event data: the entity as serialized by pctrl (secrets redacted),
+    /// null after a removal
+    pub data: serde_json::Value,
+    /// Synthetic payload from `pctrl hooks run --sample`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sample: bool,
+}
+
+impl HookPayload {
+    /// Payload for an arbitrary event; secret-looking fields in `data` are redacted
+    pub fn new(event: &str, data: serde_json::Value) -> Self {
+        Self {
+            event: event.to_string(),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            actor: crate::current_holder(),
+            entity_type: None,
+            entity_id: None,
+            summary: None,
+            data: redact_json(data),
+            sample: false,
+        }
+    }
+
+    /// Payload for an entity mutation
+    pub fn entity(
+        entity_type: EntityType,
+        entity_id: &str,
+        action: AuditAction,
+        summary: &str,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            entity_type: Some(entity_type.to_string()),
+            entity_id: Some(entity_id.to_string()),
+            summary: Some(summary.to_string()),
+            ..Self::new(&entity_event(entity_type, action), data)
+        }
+    }
+
+    /// Synthetic payload for testing a hook
+    pub fn sample(event: &str) -> Self {
+        let entity = EntityType::ALL.iter().find(|t| {
+            event
+                .split_once('.')
+                .is_some_and(|(prefix, _)| prefix == t.to_string())
+        });
+
+        let mut payload = match entity {
+            _ if event == SCRIPT_FINISHED => Self {
+                entity_type: Some(EntityType::Script.to_string()),
+                entity_id: Some("sample-id".to_string()),
+                summary: Some("sample".to_string()),
+                ..Self::new(
+                    event,
+                    serde_json::json!({
+                        "id": "sample-id",
+                        "name": "sample",
+                        "last_result": "Success",
+                        "exit_code": 0,
+                    }),
+                )
+            },
+            Some(entity_type) => Self {
+                entity_type: Some(entity_type.to_string()),
+                entity_id: Some("sample-id".to_string()),
+                summary: Some("sample".to_string()),
+                ..Self::new(
+                    event,
+                    serde_json::json!({ "id": "sample-id", "name": "sample" }),
+                )
+            },
+            None => Self::new(event, serde_json::json!({})),
+        };
+        payload.sample = true;
+        payload
+    }
+}
+
+/// Replace values of secret-looking keys (see [`is_secret_key`]) anywhere in `value`
+pub fn redact_json(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    if is_secret_key(&k) && !v.is_null() {
+                        (k, Value::String(REDACTED.to_string()))
+                    } else {
+                        (k, redact_json(v))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_json).collect()),
+        other => other,
+    }
+}
+
+/// Why a hook file does or doesn't run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookState {
+    Enabled,
+    Disabled,
+    NotExecutable,
+}
+
+/// A file in a hook directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookFile {
+    pub event: String,
+    /// File name
+    pub name: String,
+    pub path: PathBuf,
+    pub state: HookState,
+}
+
+/// Files in `<dir>/<event>/`, sorted by name; hidden files and
+/// subdirectories are ignored
+pub fn discover_event(dir: &Path, event: &str) -> Vec<HookFile> {
+    let Ok(entries) = std::fs::read_dir(dir.join(event)) else {
+        return Vec::new();
+    };
+
+    let mut hooks: Vec<HookFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = std::fs::metadata(entry.path()).ok()?;
+            if name.starts_with('.') || !metadata.is_file() {
+                return None;
+            }
+
+            let state = if name.ends_with(DISABLED_SUFFIX) {
+                HookState::Disabled
+            } else if !is_executable(&metadata) {
+                HookState::NotExecutable
+            } else {
+                HookState::Enabled
+            };
+            Some(HookFile {
+                event: event.to_string(),
+                name,
+                path: entry.path(),
+                state,
+            })
+        })
+        .collect();
+
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+    hooks
+}
+
+/// Hook files of every event directory under `dir`, sorted by event and name
+pub fn discover_all(dir: &Path) -> Vec<HookFile> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut events: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    events.sort();
+
+    events
+        .iter()
+        .flat_map(|event| discover_event(dir, event))
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// Outcome of one hook execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookRun {
+    pub event: String,
+    /// Hook file name
+    pub hook: String,
+    pub success: bool,
+    /// None when the hook was killed or couldn't be started
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Combined stdout and stderr (truncated), or the start error
+    pub output: String,
+    pub duration_ms: i64,
+    /// RFC 3339, UTC
+    pub ran_at: String,
+}
+
+/// Runs the enabled hooks of an event
+#[derive(Debug, Clone)]
+pub struct HookRunner {
+    dir: PathBuf,
+    timeout: Duration,
+}
+
+impl HookRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Hooks directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Run all enabled hooks of the payload's event, in order
+    pub async fn run(&self, payload: &HookPayload) -> Vec<HookRun> {
+        let hooks: Vec<HookFile> = discover_event(&self.dir, &payload.event)
+            .into_iter()
+            .filter(|h| h.state == HookState::Enabled)
+            .collect();
+        if hooks.is_empty() {
+            return Vec::new();
+        }
+
+        let input = serde_json::to_vec(payload).unwrap_or_default();
+        let mut runs = Vec::with_capacity(hooks.len());
+        for hook in hooks {
+            runs.push(self.run_one(&hook, &input).await);
+        }
+        runs
+    }
+
+    async fn run_one(&self, hook: &HookFile, input: &[u8]) -> HookRun {
+        let ran_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let started = Instant::now();
+        let run = |success, exit_code, timed_out, output: String| HookRun {
+            event: hook.event.clone(),
+            hook: hook.name.clone(),
+            success,
+            exit_code,
+            timed_out,
+            output: truncate(&output),
+            duration_ms: started.elapsed().as_millis() as i64,
+            ran_at: ran_at.clone(),
+        };
+
+        let child = tokio::process::Command::new(&hook.path)
+            .env("PCTRL_EVENT", &hook.event)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => return run(false, None, false, format!("Failed to start: {}", e)),
+        };
+
+        // The input is written while the output is read, and both count
+        // against the timeout: a hook that never reads stdin can't block
+        // the write. Stdin is closed once written.
+        let stdin = child.stdin.take();
+        let write = async move {
+            if let Some(mut stdin) = stdin {
+                // A hook that ignores its input closes stdin early; that's fine
+                let _ = stdin.write_all(input).await;
+            }
+        };
+        let finished = async {
+            let ((), output) = tokio::join!(write, child.wait_with_output());
+            output
+        };
+
+        match tokio::time::timeout(self.timeout, finished).await {
+            Ok(Ok(output)) => {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                run(output.status.success(), output.status.code(), false, text)
+            }
+            Ok(Err(e)) => run(false, None, false, e.to_string()),
+            Err(_) => run(
+                false,
+                None,
+                true,
+                format!("Killed after {}s", self.timeout.as_secs()),
+            ),
+        }
+    }
+}
+
+fn truncate(output: &str) -> String {
+    if output.len() <= OUTPUT_LIMIT {
+        return output.to_string();
+    }
+    let mut end = OUTPUT_LIMIT;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...[truncated]", &output[..end])
+}
@@ -0,0 +1,129 @@
+//! Human-friendly formatting of sizes, durations and timestamps
+//!
+//! Output is deterministic and locale-independent (always `.` as decimal
+//! separator, English units). With raw mode enabled (`--raw`), values are
+//! printed machine-readable instead: plain byte counts, whole seconds and
+//! RFC 3339 timestamps.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static RAW: AtomicBool = AtomicBool::new(false);
+
+/// Disable humanization for the rest of the process (`--raw`)
+pub fn set_raw(raw: bool) {
+    RAW.store(raw, Ordering::Relaxed);
+}
+
+/// Whether raw (machine-readable) output is enabled
+pub fn is_raw() -> bool {
+    RAW.load(Ordering::Relaxed)
+}
+
+/// Format a byte count: `1023 B`, `1.0 KB`, `1.4 GB` (1024-based, like `df -h`)
+pub fn bytes(n: u64) -> String {
+    if is_raw() {
+        return n.to_string();
+    }
+
+    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // Avoid "1024.0 KB" after rounding
+    if format!("{:.1}", value) == "1024.0" && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format a duration with its two most significant units: `59s`, `1m 1s`, `3d 4h`
+pub fn duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if is_raw() {
+        return format!("{}s", secs);
+    }
+
+    const UNITS: [(u64, &str); 4] = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+    let mut parts = Vec::new();
+    let mut rest = secs;
+    for (size, suffix) in UNITS {
+        if rest >= size || (size == 1 && parts.is_empty()) {
+            parts.push(format!("{}{}", rest / size, suffix));
+            rest %= size;
+        }
+        if parts.len() == 2 || (!parts.is_empty() && rest == 0) {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+/// Format a timestamp relative to now: `3 days ago`, `in 2 weeks`, `just now`
+pub fn relative(time: DateTime<Utc>) -> String {
+    relative_to(time, Utc::now())
+}
+
+/// Format `time` relative to a fixed `now` (deterministic variant of [`relative`])
+pub fn relative_to(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    if is_raw() {
+        return time.to_rfc3339_opts(SecondsFormat::Secs, true);
+    }
+
+    let diff = (time - now).num_seconds();
+    let secs = diff.unsigned_abs();
+    if secs == 0 {
+        return "just now".to_string();
+    }
+
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let amount = match secs {
+        s if s < MINUTE => count(s, "second", "seconds"),
+        s if s < HOUR => count(s / MINUTE, "minute", "minutes"),
+        s if s < DAY => count(s / HOUR, "hour", "hours"),
+        s if s < 2 * WEEK => count(s / DAY, "day", "days"),
+        s if s < 2 * MONTH => count(s / WEEK, "week", "weeks"),
+        s if s < YEAR => count(s / MONTH, "month", "months"),
+        s => count(s / YEAR, "year", "years"),
+    };
+
+    if diff < 0 {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
+/// Format a stored RFC 3339 timestamp relative to now.
+///
+/// Unparseable input is returned unchanged.
+pub fn relative_timestamp(ts: &str) -> String {
+    match DateTime::parse_from_rfc3339(ts) {
+        Ok(time) => relative(time.with_timezone(&Utc)),
+        Err(_) => ts.to_string(),
+    }
+}
+
+/// `1 server`, `2 servers`, `0 servers`
+pub fn count(n: u64, singular: &str, plural: &str) -> String {
+    if n == 1 {
+        format!("{} {}", n, singular)
+    } else {
+        format!("{} {}", n, plural)
+    }
+}
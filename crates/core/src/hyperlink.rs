@@ -0,0 +1,128 @@
+//! Terminal hyperlinks (OSC 8)
+//!
+//! [`link`] wraps a value in the OSC 8 escape sequence, so terminals that
+//! support it make it clickable. Links are off until the CLI enables them
+//! after [`detect`]; disabled, every helper returns the text unchanged and
+//! piped output stays byte-identical.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The `hyperlinks` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HyperlinkMode {
+    /// Links when stdout is a terminal that isn't `dumb`
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for HyperlinkMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" | "on" => Ok(Self::Always),
+            "never" | "off" => Ok(Self::Never),
+            _ => Err(format!(
+                "Invalid hyperlink mode: {} (expected auto, always or never)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for HyperlinkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HyperlinkMode::Auto => write!(f, "auto"),
+            HyperlinkMode::Always => write!(f, "always"),
+            HyperlinkMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Whether to emit links, from the setting, `$TERM` and whether stdout is a
+/// terminal
+pub fn detect(mode: HyperlinkMode, term: Option<&str>, is_terminal: bool) -> bool {
+    match mode {
+        HyperlinkMode::Always => true,
+        HyperlinkMode::Never => false,
+        HyperlinkMode::Auto => is_terminal && !matches!(term, None | Some("") | Some("dumb")),
+    }
+}
+
+/// Enable or disable links for the rest of the process
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether links are emitted
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// `text` linking to `url`, or `text` as is when links are disabled
+pub fn link(url: &str, text: &str) -> String {
+    if !is_enabled() {
+        return text.to_string();
+    }
+    // Control characters would end the sequence early
+    let url: String = url.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// A domain or URL, linking to itself (`https://` for bare domains)
+pub fn web(target: &str) -> String {
+    link(&web_url(target), target)
+}
+
+/// A file path, linking to the file
+pub fn file(path: &str) -> String {
+    link(&file_url(Path::new(path)), path)
+}
+
+/// A file path, linking to the folder it's in
+pub fn folder_of(path: &str) -> String {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => link(&file_url(parent), path),
+        _ => link(&file_url(Path::new(".")), path),
+    }
+}
+
+/// URL of a domain or URL: bare domains get `https://`
+pub fn web_url(target: &str) -> String {
+    if target.contains("://") {
+        target.to_string()
+    } else {
+        format!("https://{}", target)
+    }
+}
+
+/// `file://` URL of a path; relative paths are resolved against the
+/// current directory
+pub fn file_url(path: &Path) -> String {
+    let absolute: PathBuf = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    let mut url = String::from("file://");
+    for byte in absolute.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
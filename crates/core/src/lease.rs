@@ -0,0 +1,127 @@
+//! Coordinating pctrl versions that share one database file
+//!
+//! A database synced between machines may be opened by an older pctrl
+//! after a newer one upgraded its schema. The older one must not write:
+//! rows it doesn't fully understand would be saved back with defaults.
+//! [`schema_access`] decides what a binary may do with a file.
+//!
+//! Migrations take the write lease first: a single row naming the process
+//! that changes the schema, kept alive by heartbeats. Other processes
+//! don't open the file while it is held; a lease whose heartbeat is older
+//! than [`LEASE_TTL_SECS`] belongs to a process that died and is taken
+//! over ([`decide`]).
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Seconds without a heartbeat after which a lease counts as abandoned
+pub const LEASE_TTL_SECS: i64 = 30;
+
+/// What a binary that knows schema `known` may do with a file at `schema`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaAccess {
+    ReadWrite,
+    /// Older schema: migrate (under the write lease) before writing
+    Migrate {
+        from: i32,
+    },
+    /// Newer schema: read, but never write
+    ReadOnly {
+        schema: i32,
+    },
+}
+
+pub fn schema_access(schema: i32, known: i32) -> SchemaAccess {
+    match schema.cmp(&known) {
+        Ordering::Equal => SchemaAccess::ReadWrite,
+        Ordering::Less => SchemaAccess::Migrate { from: schema },
+        Ordering::Greater => SchemaAccess::ReadOnly { schema },
+    }
+}
+
+/// Compare dotted version numbers (`0.10.1` > `0.9.3`); a missing part
+/// counts as 0 and anything after `-` or `+` is ignored
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// The write lease as stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteLease {
+    /// `user@host (pid N)`
+    pub holder: String,
+    /// pctrl version of the holder
+    pub version: String,
+    /// Schema version the holder migrates to
+    pub schema_version: i32,
+    pub acquired_at: DateTime<Utc>,
+    pub heartbeat_at: DateTime<Utc>,
+}
+
+impl WriteLease {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.heartbeat_at + Duration::seconds(LEASE_TTL_SECS)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at()
+    }
+
+    /// Refusal message for a process that found the lease held
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (pctrl {}) is upgrading the database to schema v{}; try again after it finishes (the lease expires at {} without a heartbeat)",
+            self.holder,
+            self.version,
+            self.schema_version,
+            self.expires_at().format("%Y-%m-%d %H:%M:%S UTC")
+        )
+    }
+}
+
+/// What taking the lease does, given the current one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseDecision {
+    /// Nobody holds it
+    Acquire,
+    /// `holder` already holds it; refresh the heartbeat
+    Renew,
+    /// Someone else held it but stopped sending heartbeats
+    Steal(WriteLease),
+    /// Someone else holds it
+    Busy(WriteLease),
+}
+
+pub fn decide(current: Option<&WriteLease>, holder: &str, now: DateTime<Utc>) -> LeaseDecision {
+    match current {
+        None => LeaseDecision::Acquire,
+        Some(lease) if lease.holder == holder => LeaseDecision::Renew,
+        Some(lease) if lease.is_expired(now) => LeaseDecision::Steal(lease.clone()),
+        Some(lease) => LeaseDecision::Busy(lease.clone()),
+    }
+}
+
+/// Lease holder name of this process
+pub fn current_lease_holder() -> String {
+    format!("{} (pid {})", crate::current_holder(), std::process::id())
+}
@@ -2,7 +2,69 @@
 //!
 //! This crate provides the fundamental data structures used throughout pctrl.
 
+pub mod anonymize;
+pub mod bundle;
+pub mod changelog;
+pub mod columns;
+pub mod compose;
+pub mod config_export;
+pub mod conflict;
+pub mod container_stats;
+pub mod demo;
+pub mod deploy_key;
+pub mod diff;
+pub mod discovery;
+pub mod docker_endpoint;
+pub mod doctor;
+pub mod document;
+pub mod domain_base;
+pub mod export;
+pub mod facts;
+pub mod fanout;
+pub mod forecast;
+pub mod growth;
+pub mod hints;
+pub mod history;
+pub mod hooks;
+pub mod humanize;
+pub mod hyperlink;
+pub mod lease;
+pub mod local_run;
+pub mod log_tail;
+pub mod maintenance;
+pub mod merge;
+pub mod monitor;
+pub mod network;
+pub mod parse;
+pub mod placeholder;
+pub mod preflight;
+pub mod project_archive;
+pub mod project_clone;
+pub mod project_status;
+pub mod prompt;
+pub mod propagation;
+pub mod proxy_labels;
+pub mod quota;
+pub mod redact;
+pub mod run_window;
+pub mod script_body;
+pub mod script_namespace;
+pub mod search;
+pub mod settings;
+pub mod shell;
+pub mod ship;
+pub mod short_ref;
+pub mod snapshot;
+pub mod startup;
+pub mod systemd;
+pub mod table_export;
+pub mod theme;
+pub mod throttle;
+pub mod transfer;
 mod types;
+pub mod vpn;
 
 // Re-export all types from the types module
 pub use types::*;
+
+pub use redact::redact_secrets;
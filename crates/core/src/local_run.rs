@@ -0,0 +1,187 @@
+//! Controlled environment for local scripts
+//!
+//! A local script shouldn't behave differently because it was started from
+//! a login shell, cron or the desktop app. It runs in its own working
+//! directory with a clean environment: only the platform basics
+//! ([`BASE_VARS`]) are taken from pctrl's process, plus the variables the
+//! script declares. `--inherit-env` passes everything through instead.
+//! The shell is chosen per platform: `sh -s` with the body on stdin on
+//! Unix, `cmd /D /S /C "<body>"` on Windows.
+
+use crate::script_body;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Variables taken from pctrl's environment even in a clean one
+pub const BASE_VARS: &[&str] = if cfg!(windows) {
+    // cmd and most programs don't work without the system paths
+    &[
+        "PATH",
+        "PATHEXT",
+        "SystemRoot",
+        "ComSpec",
+        "USERPROFILE",
+        "TEMP",
+        "TMP",
+    ]
+} else {
+    &["PATH", "HOME"]
+};
+
+/// Parse a `KEY=value` assignment from `--env`
+pub fn parse_assignment(text: &str) -> Result<(String, String), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' isn't KEY=value", text))?;
+    validate_name(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Environment variable names: letters, digits and `_`, not starting with a digit
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' isn't a valid environment variable name",
+            name
+        ))
+    }
+}
+
+/// The shell a local script runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// `sh -s`, reading the body from stdin
+    Sh,
+    /// `cmd /D /S /C "<body>"`
+    Cmd,
+}
+
+impl Shell {
+    /// The shell of the platform pctrl runs on
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            Shell::Cmd
+        } else {
+            Shell::Sh
+        }
+    }
+
+    pub fn program(self) -> &'static str {
+        match self {
+            Shell::Sh => script_body::STDIN_SHELL.0,
+            Shell::Cmd => "cmd",
+        }
+    }
+
+    /// Arguments after the program. For `cmd` this is one raw command
+    /// line: with `/S`, cmd strips the outer quotes and runs the rest
+    /// verbatim, so the body needs no escaping of its own (and must not
+    /// get the quoting Rust applies to normal arguments on Windows).
+    pub fn command_line(self, body: &str) -> String {
+        match self {
+            Shell::Sh => script_body::STDIN_SHELL.1.join(" "),
+            Shell::Cmd => format!("/D /S /C \"{}\"", body),
+        }
+    }
+
+    /// What to write to the shell's stdin
+    pub fn stdin(self, body: &str) -> Option<String> {
+        match self {
+            Shell::Sh => Some(script_body::stdin_payload(body)),
+            Shell::Cmd => None,
+        }
+    }
+}
+
+/// How a local script is started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalRun {
+    pub shell: Shell,
+    pub cwd: PathBuf,
+    /// The complete environment of the process, sorted by name
+    pub env: Vec<(String, String)>,
+}
+
+impl LocalRun {
+    /// Plan a run. `working_dir` is already expanded (`~`) and must exist;
+    /// without one the script runs in `current_dir`. `host_env` is pctrl's
+    /// own environment.
+    pub fn plan(
+        working_dir: Option<&Path>,
+        declared: &BTreeMap<String, String>,
+        inherit_env: bool,
+        host_env: impl IntoIterator<Item = (String, String)>,
+        current_dir: &Path,
+    ) -> Result<Self, String> {
+        let cwd = match working_dir {
+            Some(dir) if !dir.is_absolute() => {
+                return Err(format!(
+                    "Working directory '{}' must be absolute or start with ~",
+                    dir.display()
+                ))
+            }
+            Some(dir) if !dir.is_dir() => {
+                return Err(format!(
+                    "Working directory '{}' doesn't exist",
+                    dir.display()
+                ))
+            }
+            Some(dir) => dir.to_path_buf(),
+            None => current_dir.to_path_buf(),
+        };
+
+        let mut env: BTreeMap<String, String> = host_env
+            .into_iter()
+            .filter(|(name, _)| {
+                inherit_env || BASE_VARS.iter().any(|b| b.eq_ignore_ascii_case(name))
+            })
+            .collect();
+        env.extend(declared.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        Ok(Self {
+            shell: Shell::native(),
+            cwd,
+            env: env.into_iter().collect(),
+        })
+    }
+
+    /// Names of the variables the script sees, for the run record
+    pub fn env_names(&self) -> Vec<String> {
+        self.env.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// The process, with stdin/stdout/stderr piped
+    pub fn command(&self, body: &str) -> Command {
+        let mut command = Command::new(self.shell.program());
+        match self.shell {
+            Shell::Sh => {
+                command.args(script_body::STDIN_SHELL.1);
+            }
+            Shell::Cmd => {
+                #[cfg(windows)]
+                {
+                    use std::os::windows::process::CommandExt;
+                    command.raw_arg(self.shell.command_line(body));
+                }
+                #[cfg(not(windows))]
+                command.args(["/D", "/S", "/C", body]);
+            }
+        }
+        command
+            .current_dir(&self.cwd)
+            .env_clear()
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+}
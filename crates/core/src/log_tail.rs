@@ -0,0 +1,198 @@
+//! Several log streams merged into one (`pctrl logs follow`)
+//!
+//! Each source (a container's Docker logs, a unit's journal over SSH) gets
+//! a prefix and a color. Lines are held for a short window and released in
+//! timestamp order, so a line that arrived late over a slower connection
+//! still lands between its neighbours. Ordering is best-effort: a line
+//! delayed by more than the window is printed when it arrives.
+
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+
+/// Longest prefix before names are cut
+pub const MAX_PREFIX: usize = 24;
+
+/// Default reordering window
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(300);
+
+/// Prefix and color of one source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLabel {
+    /// Source name padded to the widest name
+    pub prefix: String,
+    /// Index into the caller's palette
+    pub color: usize,
+}
+
+/// Labels for sources in order: names padded to the same width (at most
+/// [`MAX_PREFIX`], longer names are cut with "…") and colors taken from a
+/// palette of `colors` in turn
+pub fn label_sources(names: &[&str], colors: usize) -> Vec<SourceLabel> {
+    let width = names
+        .iter()
+        .map(|n| n.chars().count().min(MAX_PREFIX))
+        .max()
+        .unwrap_or(0);
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let name = if name.chars().count() > MAX_PREFIX {
+                let cut: String = name.chars().take(MAX_PREFIX - 1).collect();
+                format!("{}…", cut)
+            } else {
+                name.to_string()
+            };
+            SourceLabel {
+                prefix: format!("{:<width$}", name, width = width),
+                color: i % colors.max(1),
+            }
+        })
+        .collect()
+}
+
+/// `unit@server` split at the last `@`; template units keep theirs
+/// (`postgresql@14-main@db` is `postgresql@14-main` on `db`)
+pub fn split_service_target(target: &str) -> (&str, Option<&str>) {
+    match target.rsplit_once('@') {
+        Some((unit, server)) if !unit.is_empty() && !server.is_empty() => (unit, Some(server)),
+        _ => (target, None),
+    }
+}
+
+/// Turns chunks of output into complete lines
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    partial: String,
+}
+
+impl LineBuffer {
+    /// Lines completed by `chunk`, without line endings
+    pub fn push(&mut self, chunk: &str) -> Vec<String> {
+        self.partial.push_str(chunk);
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        complete
+            .lines()
+            .map(|l| l.trim_end_matches('\r').to_string())
+            .collect()
+    }
+
+    /// The unfinished last line, if any
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.partial);
+        (!rest.trim().is_empty()).then(|| rest.trim_end_matches('\r').to_string())
+    }
+}
+
+/// Split a leading timestamp off a line. Understands RFC 3339 (Docker's
+/// `--timestamps`) and journalctl's `short-iso-precise` (`+0000` offsets).
+pub fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    let (first, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let parsed = DateTime::parse_from_rfc3339(first)
+        .or_else(|_| DateTime::parse_from_str(first, "%Y-%m-%dT%H:%M:%S%.f%z"));
+    match parsed {
+        Ok(time) => (Some(time.with_timezone(&Utc)), rest),
+        Err(_) => (None, line),
+    }
+}
+
+/// A journal line without its `host ident[pid]: ` origin
+pub fn journal_message(rest: &str) -> &str {
+    let Some((_host, after)) = rest.split_once(' ') else {
+        return rest;
+    };
+    match after.split_once(": ") {
+        Some((ident, message)) if !ident.contains(' ') => message,
+        _ => rest,
+    }
+}
+
+/// A line from one source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// Index of the source
+    pub source: usize,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub text: String,
+}
+
+struct Pending {
+    line: LogLine,
+    /// Sort key: the line's timestamp, else that of the source's previous line
+    key: Option<DateTime<Utc>>,
+    seq: u64,
+    arrived: Instant,
+}
+
+/// Holds lines for a window and releases them in timestamp order
+pub struct Reorder {
+    window: Duration,
+    pending: Vec<Pending>,
+    last_key: Vec<Option<DateTime<Utc>>>,
+    seq: u64,
+}
+
+impl Reorder {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Vec::new(),
+            last_key: Vec::new(),
+            seq: 0,
+        }
+    }
+
+    /// Add a line that arrived at `now`. Lines without a timestamp sort
+    /// right after their source's previous line.
+    pub fn push(&mut self, line: LogLine, now: Instant) {
+        if self.last_key.len() <= line.source {
+            self.last_key.resize(line.source + 1, None);
+        }
+        let key = line.timestamp.or(self.last_key[line.source]);
+        self.last_key[line.source] = key;
+        self.seq += 1;
+        self.pending.push(Pending {
+            line,
+            key,
+            seq: self.seq,
+            arrived: now,
+        });
+    }
+
+    /// Lines that have waited the whole window, oldest first. Newer arrivals
+    /// that sort before a due line go out with it.
+    pub fn ready(&mut self, now: Instant) -> Vec<LogLine> {
+        self.sort();
+        let Some(last_due) = self
+            .pending
+            .iter()
+            .rposition(|p| now.saturating_duration_since(p.arrived) >= self.window)
+        else {
+            return Vec::new();
+        };
+        self.pending.drain(..=last_due).map(|p| p.line).collect()
+    }
+
+    /// Everything still held, oldest first
+    pub fn flush(&mut self) -> Vec<LogLine> {
+        self.sort();
+        self.pending.drain(..).map(|p| p.line).collect()
+    }
+
+    /// When the next held line is due
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.iter().map(|p| p.arrived + self.window).min()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn sort(&mut self) {
+        self.pending.sort_by_key(|p| (p.key, p.seq));
+    }
+}
@@ -0,0 +1,95 @@
+//! Time-boxed maintenance windows (`pctrl project maintenance`)
+//!
+//! Starting a window flips the project to [`ProjectStatus::Maintenance`] and
+//! remembers the status it had. Once the window expires the previous status
+//! is restored: by the monitor on its next cycle, or by any other pctrl
+//! invocation if no monitor is running. While a window is open, monitor
+//! alerts for the project's servers are suppressed.
+
+use crate::{humanize, ProjectStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// An open maintenance window of one project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub project_id: String,
+    /// Status restored when the window ends
+    pub previous_status: ProjectStatus,
+    pub reason: Option<String>,
+    /// RFC 3339, UTC
+    pub started_at: String,
+    /// RFC 3339, UTC
+    pub expires_at: String,
+}
+
+impl MaintenanceWindow {
+    /// Whether the window is over at `now` (unparseable expiries count as over)
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires().is_none_or(|expires| expires <= now)
+    }
+
+    /// Time left at `now`, zero once expired
+    pub fn remaining(&self, now: DateTime<Utc>) -> Duration {
+        self.expires()
+            .and_then(|expires| (expires - now).to_std().ok())
+            .unwrap_or_default()
+    }
+
+    /// One-line banner, e.g. "maintenance, 1h 20m left (db migration)"
+    pub fn banner(&self, now: DateTime<Utc>) -> String {
+        let left = if self.is_expired(now) {
+            "ending".to_string()
+        } else {
+            format!("{} left", humanize::duration(self.remaining(now)))
+        };
+        match &self.reason {
+            Some(reason) => format!("maintenance, {} ({})", left, reason),
+            None => format!("maintenance, {}", left),
+        }
+    }
+
+    fn expires(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.expires_at)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
+/// A window that ended and the status its project went back to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndedWindow {
+    pub project_id: String,
+    pub project_name: String,
+    /// `None` if the project's status was changed during the window and
+    /// was left alone
+    pub restored: Option<ProjectStatus>,
+}
+
+/// Servers whose monitor alerts are suppressed, keyed by server id or name
+/// (project links may use either), with the project in maintenance
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceScope {
+    servers: HashMap<String, String>,
+}
+
+impl MaintenanceScope {
+    /// Add a server reference (id or name) of a project in maintenance
+    pub fn insert(&mut self, server: impl Into<String>, project_name: impl Into<String>) {
+        self.servers.insert(server.into(), project_name.into());
+    }
+
+    /// Project in maintenance that covers the server, if any
+    pub fn covering(&self, server_id: &str, server_name: &str) -> Option<&str> {
+        self.servers
+            .get(server_id)
+            .or_else(|| self.servers.get(server_name))
+            .map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+}
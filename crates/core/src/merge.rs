@@ -0,0 +1,561 @@
+//! Merging another pctrl database into this one (`pctrl merge`)
+//!
+//! Entities of both databases become [`Record`]s: their JSON plus the time
+//! they last changed. [`compare`] matches the other side's records to local
+//! ones by ID, then by normalized name, and classifies each pair. Matched
+//! entities may have different IDs on the two sides, so references between
+//! entities (a server's credential, a script's project) and project links
+//! are rewritten through the resulting [`IdMap`] before anything is
+//! compared or imported. Types are compared in [`ORDER`], so the targets of
+//! a type's references are always mapped before it.
+
+use crate::conflict::{Conflict, Decision};
+use crate::{EntityType, ProjectResource, ResourceType};
+use chrono::DateTime;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Entity types in the order they are compared and written: every type
+/// after the ones its references point to
+pub const ORDER: [EntityType; 6] = [
+    EntityType::Credential,
+    EntityType::Server,
+    EntityType::Project,
+    EntityType::Domain,
+    EntityType::Database,
+    EntityType::Script,
+];
+
+/// Fields holding the ID of another entity, by the type they point to
+const REFERENCES: &[(EntityType, &str, EntityType)] = &[
+    (EntityType::Server, "credential_id", EntityType::Credential),
+    (EntityType::Domain, "server_id", EntityType::Server),
+    (EntityType::Database, "server_id", EntityType::Server),
+    (EntityType::Script, "server_id", EntityType::Server),
+    (EntityType::Script, "project_id", EntityType::Project),
+];
+
+/// Fields that differ between machines without anyone editing the entity:
+/// a script's last run
+const VOLATILE: &[&str] = &["last_run", "last_result", "exit_code", "last_output"];
+
+/// Fields never shown in a conflict: database passwords and connection
+/// strings, a credential's data
+const SECRET: &[&str] = &["password", "connection_string", "data"];
+
+/// An entity of one side
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub entity_type: EntityType,
+    pub id: String,
+    pub name: String,
+    /// The entity as JSON
+    pub fields: Map<String, Value>,
+    /// When it last changed (RFC 3339), if known
+    pub updated_at: Option<String>,
+}
+
+impl Record {
+    /// Record of an entity; its `name` (a domain's `domain`) names it
+    pub fn from_entity<T: Serialize>(
+        entity_type: EntityType,
+        entity: &T,
+        updated_at: Option<String>,
+    ) -> Result<Self, String> {
+        let fields = match serde_json::to_value(entity).map_err(|e| e.to_string())? {
+            Value::Object(fields) => fields,
+            _ => return Err(format!("{} is not an object", entity_type)),
+        };
+        let text = |key: &str| fields.get(key).and_then(Value::as_str).map(str::to_string);
+        let id = text("id").ok_or_else(|| format!("{} without an ID", entity_type))?;
+        let name = text("name")
+            .or_else(|| text("domain"))
+            .unwrap_or_else(|| id.clone());
+        Ok(Self {
+            entity_type,
+            id,
+            name,
+            fields,
+            updated_at,
+        })
+    }
+
+    /// The entity, with this record's ID
+    pub fn to_entity<T: DeserializeOwned>(&self) -> Result<T, String> {
+        let mut fields = self.fields.clone();
+        fields.insert("id".to_string(), Value::String(self.id.clone()));
+        serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+    }
+
+    /// Fields that differ from `other`'s, sorted; IDs and volatile fields
+    /// don't count
+    pub fn differences(&self, other: &Record) -> Vec<String> {
+        let keys: HashSet<&String> = self.fields.keys().chain(other.fields.keys()).collect();
+        let mut changed: Vec<String> = keys
+            .into_iter()
+            .filter(|key| *key != "id" && !VOLATILE.contains(&key.as_str()))
+            .filter(|key| self.fields.get(*key) != other.fields.get(*key))
+            .cloned()
+            .collect();
+        changed.sort();
+        changed
+    }
+
+    /// A field's value for display; secrets are masked
+    pub fn display_field(&self, field: &str) -> String {
+        if SECRET.contains(&field) {
+            return "••••".to_string();
+        }
+        match self.fields.get(field) {
+            None | Some(Value::Null) => "-".to_string(),
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+        }
+    }
+}
+
+/// Name as compared across databases: trimmed, inner whitespace collapsed,
+/// lowercase
+pub fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// IDs of the other database's entities in this one, where they differ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMap(HashMap<(EntityType, String), String>);
+
+impl IdMap {
+    pub fn insert(&mut self, entity_type: EntityType, other_id: &str, local_id: &str) {
+        if other_id != local_id {
+            self.0
+                .insert((entity_type, other_id.to_string()), local_id.to_string());
+        }
+    }
+
+    /// The local ID for an ID of the other database
+    pub fn get<'a>(&'a self, entity_type: EntityType, other_id: &'a str) -> &'a str {
+        self.0
+            .get(&(entity_type, other_id.to_string()))
+            .map_or(other_id, String::as_str)
+    }
+
+    /// Rewrite a record's references to local IDs
+    pub fn remap(&self, record: &mut Record) {
+        for (_, field, target) in REFERENCES
+            .iter()
+            .filter(|(owner, _, _)| *owner == record.entity_type)
+        {
+            if let Some(Value::String(id)) = record.fields.get_mut(*field) {
+                *id = self.get(*target, id).to_string();
+            }
+        }
+    }
+}
+
+/// How a record compares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Class {
+    OnlyLocal,
+    OnlyOther,
+    Identical,
+    /// Same entity, these fields differ
+    Conflict(Vec<String>),
+}
+
+/// What to do with a conflict
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    KeepLocal,
+    TakeOther,
+    /// The local fields with some taken from the other side
+    Merged(Map<String, Value>),
+}
+
+/// A local record, the other side's (references already remapped), or both
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub local: Option<Record>,
+    pub other: Option<Record>,
+    pub class: Class,
+    /// Set for conflicts before planning; unresolved ones are left alone
+    pub resolution: Option<Resolution>,
+}
+
+impl Entry {
+    /// The record that names the entry: the local one when both exist
+    pub fn record(&self) -> &Record {
+        self.local
+            .as_ref()
+            .or(self.other.as_ref())
+            .expect("an entry has a record")
+    }
+
+    /// The entry as a conflict to decide, if it is one
+    pub fn conflict(&self) -> Option<Conflict> {
+        let (Some(local), Some(other), Class::Conflict(_)) =
+            (&self.local, &self.other, &self.class)
+        else {
+            return None;
+        };
+        let mut ignore = vec!["id"];
+        ignore.extend(VOLATILE);
+        let mut conflict = Conflict::new(
+            &local.entity_type.to_string(),
+            &local.id,
+            &local.name,
+            Value::Object(local.fields.clone()),
+            Value::Object(other.fields.clone()),
+            &ignore,
+        );
+        for change in &mut conflict.fields {
+            let top = change.field.split('.').next().unwrap_or_default();
+            if SECRET.contains(&top) {
+                for value in [&mut change.old, &mut change.new] {
+                    if !value.is_null() {
+                        *value = Value::String("••••".to_string());
+                    }
+                }
+            }
+        }
+        Some(conflict)
+    }
+
+    /// Take a decision on [`Entry::conflict`]; a skip leaves it unresolved
+    pub fn decide(&mut self, conflict: &Conflict, decision: &Decision) {
+        self.resolution = match decision {
+            Decision::KeepLocal => Some(Resolution::KeepLocal),
+            Decision::TakeIncoming => Some(Resolution::TakeOther),
+            Decision::Fields(picks) => match conflict.merged(picks) {
+                Value::Object(fields) => Some(Resolution::Merged(fields)),
+                _ => None,
+            },
+            Decision::Skip => None,
+        };
+    }
+}
+
+/// Both databases side by side
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    pub entries: Vec<Entry>,
+    pub ids: IdMap,
+}
+
+impl Comparison {
+    /// Entries of a class
+    pub fn count(&self, matches: impl Fn(&Class) -> bool) -> usize {
+        self.entries.iter().filter(|e| matches(&e.class)).count()
+    }
+
+    pub fn conflicts_mut(&mut self) -> impl Iterator<Item = &mut Entry> {
+        self.entries
+            .iter_mut()
+            .filter(|e| matches!(e.class, Class::Conflict(_)))
+    }
+
+    /// The conflicting entries to decide, in order
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        self.entries.iter().filter_map(Entry::conflict).collect()
+    }
+
+    /// Take a decision per conflict from [`Comparison::conflicts`]
+    pub fn decide(&mut self, conflicts: &[Conflict], decisions: &[Decision]) {
+        for (entry, (conflict, decision)) in
+            self.conflicts_mut().zip(conflicts.iter().zip(decisions))
+        {
+            entry.decide(conflict, decision);
+        }
+    }
+}
+
+/// Match the other side's records to local ones and classify them. A
+/// record matches the local one with its ID, else an unmatched one with the
+/// same normalized name (the same case preferred); each local record is
+/// matched at most once. Entries come in [`ORDER`], each type in the other
+/// side's order, local-only ones last.
+pub fn compare(local: &[Record], other: &[Record]) -> Comparison {
+    let mut comparison = Comparison::default();
+    for entity_type in ORDER {
+        let locals: Vec<&Record> = local
+            .iter()
+            .filter(|r| r.entity_type == entity_type)
+            .collect();
+        let others: Vec<Record> = other
+            .iter()
+            .filter(|r| r.entity_type == entity_type)
+            .map(|r| {
+                let mut record = r.clone();
+                comparison.ids.remap(&mut record);
+                record
+            })
+            .collect();
+
+        // IDs first, so a name can't claim a record another one has by ID
+        let mut matches: Vec<Option<usize>> = others
+            .iter()
+            .map(|r| locals.iter().position(|l| l.id == r.id))
+            .collect();
+        let mut matched = vec![false; locals.len()];
+        for index in matches.iter().flatten() {
+            matched[*index] = true;
+        }
+        for (record, found) in others.iter().zip(matches.iter_mut()) {
+            if found.is_some() {
+                continue;
+            }
+            let name = normalize(&record.name);
+            let candidates: Vec<usize> = (0..locals.len())
+                .filter(|&i| !matched[i] && normalize(&locals[i].name) == name)
+                .collect();
+            *found = candidates
+                .iter()
+                .find(|&&i| locals[i].name == record.name)
+                .or(candidates.first())
+                .copied();
+            if let Some(index) = found {
+                matched[*index] = true;
+            }
+        }
+
+        for (record, found) in others.into_iter().zip(matches) {
+            let Some(found) = found.map(|index| locals[index]) else {
+                comparison.entries.push(Entry {
+                    local: None,
+                    other: Some(record),
+                    class: Class::OnlyOther,
+                    resolution: None,
+                });
+                continue;
+            };
+            comparison.ids.insert(entity_type, &record.id, &found.id);
+            let changed = found.differences(&record);
+            comparison.entries.push(Entry {
+                local: Some(found.clone()),
+                other: Some(record),
+                class: if changed.is_empty() {
+                    Class::Identical
+                } else {
+                    Class::Conflict(changed)
+                },
+                resolution: None,
+            });
+        }
+
+        for (record, _) in locals.iter().zip(&matched).filter(|(_, m)| !**m) {
+            comparison.entries.push(Entry {
+                local: Some((*record).clone()),
+                other: None,
+                class: Class::OnlyLocal,
+                resolution: None,
+            });
+        }
+    }
+    comparison
+}
+
+/// Which side wins conflicts (`--prefer`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefer {
+    Local,
+    Other,
+    /// The side that changed the entity last; local on a tie or when
+    /// neither time is known
+    Newer,
+}
+
+impl Prefer {
+    pub fn resolve(self, local: &Record, other: &Record) -> Resolution {
+        match self {
+            Prefer::Local => Resolution::KeepLocal,
+            Prefer::Other => Resolution::TakeOther,
+            Prefer::Newer => match (
+                timestamp(local.updated_at.as_deref()),
+                timestamp(other.updated_at.as_deref()),
+            ) {
+                (local, Some(other)) if local.is_none_or(|local| other > local) => {
+                    Resolution::TakeOther
+                }
+                _ => Resolution::KeepLocal,
+            },
+        }
+    }
+}
+
+fn timestamp(text: Option<&str>) -> Option<DateTime<chrono::FixedOffset>> {
+    text.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+}
+
+impl FromStr for Prefer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Prefer::Local),
+            "other" => Ok(Prefer::Other),
+            "newer" => Ok(Prefer::Newer),
+            _ => Err(format!(
+                "Unknown preference '{}', use local, other or newer",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Prefer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Prefer::Local => write!(f, "local"),
+            Prefer::Other => write!(f, "other"),
+            Prefer::Newer => write!(f, "newer"),
+        }
+    }
+}
+
+/// One change a merge makes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// Create an entity only the other side has
+    Import(Record),
+    /// Overwrite a local entity with the other side's fields (the record
+    /// has the local ID)
+    Update {
+        record: Record,
+        changed: Vec<String>,
+    },
+    /// A conflict left as it is here
+    Keep {
+        record: Record,
+        changed: Vec<String>,
+    },
+    /// A conflict nobody decided
+    Unresolved {
+        record: Record,
+        changed: Vec<String>,
+    },
+}
+
+/// Everything a merge does, in write order
+#[derive(Debug, Clone, Default)]
+pub struct MergePlan {
+    pub steps: Vec<Step>,
+    /// The other side's links, remapped, that this side lacks
+    pub links: Vec<ProjectResource>,
+    pub identical: usize,
+    pub only_local: usize,
+}
+
+/// The steps for a compared and resolved pair of databases
+pub fn plan(
+    comparison: &Comparison,
+    local_links: &[ProjectResource],
+    other_links: &[ProjectResource],
+) -> MergePlan {
+    let mut plan = MergePlan::default();
+    for entry in &comparison.entries {
+        match (&entry.class, &entry.local, &entry.other) {
+            (Class::OnlyOther, _, Some(other)) => plan.steps.push(Step::Import(other.clone())),
+            (Class::Conflict(changed), Some(local), Some(other)) => {
+                let changed = changed.clone();
+                plan.steps.push(match &entry.resolution {
+                    Some(Resolution::TakeOther) => {
+                        let mut record = Record {
+                            id: local.id.clone(),
+                            ..other.clone()
+                        };
+                        // This side's last run stays
+                        for field in VOLATILE {
+                            match local.fields.get(*field) {
+                                Some(value) => {
+                                    record.fields.insert(field.to_string(), value.clone())
+                                }
+                                None => record.fields.remove(*field),
+                            };
+                        }
+                        Step::Update { record, changed }
+                    }
+                    Some(Resolution::Merged(fields)) => {
+                        let record = Record {
+                            fields: fields.clone(),
+                            ..local.clone()
+                        };
+                        let changed = local.differences(&record);
+                        Step::Update { record, changed }
+                    }
+                    Some(Resolution::KeepLocal) => Step::Keep {
+                        record: local.clone(),
+                        changed,
+                    },
+                    None => Step::Unresolved {
+                        record: local.clone(),
+                        changed,
+                    },
+                });
+            }
+            (Class::Identical, _, _) => plan.identical += 1,
+            _ => plan.only_local += 1,
+        }
+    }
+    plan.links = remap_links(local_links, other_links, &comparison.ids);
+    plan
+}
+
+/// Entity type a link's resource is, where merges map its ID
+fn link_entity(resource_type: &ResourceType) -> Option<EntityType> {
+    match resource_type {
+        ResourceType::Server => Some(EntityType::Server),
+        ResourceType::Database => Some(EntityType::Database),
+        ResourceType::Domain => Some(EntityType::Domain),
+        ResourceType::Script => Some(EntityType::Script),
+        _ => None,
+    }
+}
+
+/// The other side's links with local project and resource IDs, minus the
+/// ones this side already has. A link ID taken here gets a `-merged` suffix.
+pub fn remap_links(
+    local_links: &[ProjectResource],
+    other_links: &[ProjectResource],
+    ids: &IdMap,
+) -> Vec<ProjectResource> {
+    let mut taken: HashSet<String> = local_links.iter().map(|l| l.id.clone()).collect();
+    let mut present: HashSet<(String, String, String)> = local_links
+        .iter()
+        .map(|l| {
+            (
+                l.project_id.clone(),
+                l.resource_type.to_string(),
+                l.resource_id.clone(),
+            )
+        })
+        .collect();
+
+    let mut links = Vec::new();
+    for link in other_links {
+        let mut link = link.clone();
+        link.project_id = ids.get(EntityType::Project, &link.project_id).to_string();
+        if let Some(entity_type) = link_entity(&link.resource_type) {
+            link.resource_id = ids.get(entity_type, &link.resource_id).to_string();
+        }
+        let key = (
+            link.project_id.clone(),
+            link.resource_type.to_string(),
+            link.resource_id.clone(),
+        );
+        if !present.insert(key) {
+            continue;
+        }
+        while taken.contains(&link.id) {
+            link.id = format!("{}-merged", link.id);
+        }
+        taken.insert(link.id.clone());
+        links.push(link);
+    }
+    links
+}
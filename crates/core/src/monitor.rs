@@ -0,0 +1,80 @@
+//! Monitor liveness (`pctrl monitor`)
+//!
+//! Every monitor cycle records its bookkeeping in the database. A monitor
+//! that stopped is noticed by the gap since its last cycle: more than
+//! [`STALE_AFTER_INTERVALS`] intervals means it is probably down.
+
+use crate::humanize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Time between cycles unless `--interval` says otherwise
+pub const DEFAULT_INTERVAL_SECS: u64 = 300;
+
+/// Missed intervals after which the monitor counts as down
+pub const STALE_AFTER_INTERVALS: u64 = 2;
+
+/// Bookkeeping of the last completed monitor cycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorState {
+    pub last_cycle_at: String,
+    pub servers_checked: u32,
+    pub servers_down: u32,
+    pub duration_ms: u64,
+    /// Interval the monitor was running with
+    pub interval_secs: u64,
+    /// Why the last heartbeat could not be sent
+    pub heartbeat_error: Option<String>,
+}
+
+/// Whether the monitor is still running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    /// No cycle was ever recorded
+    NeverRan,
+    Alive {
+        age_secs: u64,
+    },
+    /// The last cycle is too long ago
+    Stale {
+        age_secs: u64,
+    },
+}
+
+/// Judge the monitor's liveness at `now`
+pub fn liveness(state: Option<&MonitorState>, now: DateTime<Utc>) -> Liveness {
+    let Some(state) = state else {
+        return Liveness::NeverRan;
+    };
+    let Ok(last) = DateTime::parse_from_rfc3339(&state.last_cycle_at) else {
+        return Liveness::NeverRan;
+    };
+
+    // Clock skew can put the last cycle in the future; treat it as just now
+    let age_secs = (now - last.with_timezone(&Utc)).num_seconds().max(0) as u64;
+    if age_secs > state.interval_secs.max(1) * STALE_AFTER_INTERVALS {
+        Liveness::Stale { age_secs }
+    } else {
+        Liveness::Alive { age_secs }
+    }
+}
+
+impl Liveness {
+    /// One-line description, e.g. "monitor last ran 4h ago — it may be down"
+    pub fn describe(&self) -> String {
+        match self {
+            Liveness::NeverRan => {
+                "monitor has never run (start it with `pctrl monitor run`)".to_string()
+            }
+            Liveness::Alive { age_secs } => format!(
+                "monitor is running (last cycle {} ago)",
+                humanize::duration(Duration::from_secs(*age_secs))
+            ),
+            Liveness::Stale { age_secs } => format!(
+                "monitor last ran {} ago — it may be down",
+                humanize::duration(Duration::from_secs(*age_secs))
+            ),
+        }
+    }
+}
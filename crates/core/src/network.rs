@@ -0,0 +1,115 @@
+//! Container reachability over synced Docker networks
+//!
+//! Containers on the same host talk directly when they share a network.
+//! Docker's "none" network isolates a container completely, while "host" mode
+//! containers share the host's network stack with each other. Without a shared
+//! network a container is still reachable through ports it publishes on its
+//! host.
+
+use crate::{ContainerNetwork, PublishedPort};
+use std::collections::BTreeSet;
+
+/// Network of containers started with `--network none`
+pub const NONE_NETWORK: &str = "none";
+
+/// A container on a Docker host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endpoint<'a> {
+    pub host_id: &'a str,
+    pub container: &'a str,
+}
+
+/// How one container can reach another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reach {
+    /// Directly over these shared networks (sorted)
+    Networks(Vec<String>),
+    /// Only through ports the target publishes on its host
+    HostPorts(Vec<PublishedPort>),
+    Unreachable,
+}
+
+/// Whether `from` can reach `to`, based on synced memberships and ports
+pub fn reach(
+    from: Endpoint,
+    to: Endpoint,
+    memberships: &[ContainerNetwork],
+    ports: &[PublishedPort],
+) -> Reach {
+    let from_networks = networks_of(from, memberships);
+
+    // Only attached to "none": no network stack to reach anything with
+    if !from_networks.is_empty() && from_networks.iter().all(|n| *n == NONE_NETWORK) {
+        return Reach::Unreachable;
+    }
+
+    if from.host_id == to.host_id {
+        let to_networks = networks_of(to, memberships);
+        let shared: Vec<String> = from_networks
+            .intersection(&to_networks)
+            .filter(|n| **n != NONE_NETWORK)
+            .map(|n| n.to_string())
+            .collect();
+        if !shared.is_empty() {
+            return Reach::Networks(shared);
+        }
+    }
+
+    let published: Vec<PublishedPort> = ports
+        .iter()
+        .filter(|p| p.host_id == to.host_id && p.container == to.container)
+        .cloned()
+        .collect();
+    if published.is_empty() {
+        Reach::Unreachable
+    } else {
+        Reach::HostPorts(published)
+    }
+}
+
+/// Two containers sharing a network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkEdge {
+    pub a: String,
+    pub b: String,
+    pub network: String,
+}
+
+/// Edges between the given containers, one per shared network.
+///
+/// Containers are matched by name on any host; edges only connect containers
+/// on the same host. Order follows `containers`.
+pub fn network_edges(containers: &[String], memberships: &[ContainerNetwork]) -> Vec<NetworkEdge> {
+    let mut edges = Vec::new();
+
+    for (i, a) in containers.iter().enumerate() {
+        for b in &containers[i + 1..] {
+            let shared: BTreeSet<&str> = memberships
+                .iter()
+                .filter(|m| &m.container == a && m.network != NONE_NETWORK)
+                .filter(|m| {
+                    memberships.iter().any(|o| {
+                        &o.container == b && o.host_id == m.host_id && o.network == m.network
+                    })
+                })
+                .map(|m| m.network.as_str())
+                .collect();
+
+            edges.extend(shared.into_iter().map(|network| NetworkEdge {
+                a: a.clone(),
+                b: b.clone(),
+                network: network.to_string(),
+            }));
+        }
+    }
+
+    edges
+}
+
+fn networks_of<'a>(endpoint: Endpoint, memberships: &'a [ContainerNetwork]) -> BTreeSet<&'a str> {
+    memberships
+        .iter()
+        .filter(|m| m.host_id == endpoint.host_id && m.container == endpoint.container)
+        .map(|m| m.network.as_str())
+        .collect()
+}
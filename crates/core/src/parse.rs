@@ -0,0 +1,246 @@
+//! Parsing of human-friendly CLI values: durations, ages, sizes, rates and
+//! file modes
+//!
+//! Every flag that takes a time or a size uses one of these as its clap
+//! `value_parser`, so they all accept the same formats and bad values fail
+//! at argument parsing. Units are required (except for a plain `0`), case
+//! and whitespace don't matter. The help text of those flags comes from
+//! [`help`], so it lists the formats the parser actually accepts.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::fmt;
+
+/// Examples shown for duration flags
+pub const DURATION_FORMATS: &str = "45s, 90m, 2h30m, 1d";
+/// Examples shown for age flags
+pub const AGE_FORMATS: &str = "7d, 2w, 1y, 2024-01-01";
+/// Examples shown for size flags
+pub const SIZE_FORMATS: &str = "512mb, 1.5gb, 2GiB";
+/// Examples shown for rate flags
+pub const RATE_FORMATS: &str = "2MB/s, 500kb/s, 1gb";
+/// Examples shown for file mode flags
+pub const MODE_FORMATS: &str = "644, 0600, 755";
+
+/// A value that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub input: String,
+    pub reason: String,
+    formats: &'static str,
+}
+
+impl ParseError {
+    fn new(input: &str, reason: impl Into<String>, formats: &'static str) -> Self {
+        Self {
+            input: input.to_string(),
+            reason: reason.into(),
+            formats,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in '{}' (use e.g. {})",
+            self.reason,
+            self.input.trim(),
+            self.formats
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Help text of a flag: its description followed by the accepted formats
+pub fn help(description: &str, formats: &str) -> String {
+    format!("{} (e.g. {})", description, formats)
+}
+
+/// Parse a duration like `45s`, `90m`, `2h30m` or `1d`.
+///
+/// Segments go from the largest unit down (`1d12h`, not `12h1d`).
+pub fn duration(input: &str) -> Result<Duration, ParseError> {
+    parse_duration(input, None, DURATION_FORMATS)
+}
+
+/// Like [`duration`], but a plain number counts as seconds. For flags that
+/// took seconds before they took units (`--timeout 60`).
+pub fn duration_or_secs(input: &str) -> Result<Duration, ParseError> {
+    parse_duration(input, Some(1), DURATION_FORMATS)
+}
+
+/// Parse an age like `7d`, `2w`, `1y` (365 days) or a date (`2024-01-01`,
+/// RFC 3339) into the cutoff it describes
+pub fn age(input: &str) -> Result<DateTime<Utc>, ParseError> {
+    age_at(input, Utc::now())
+}
+
+/// [`age`] relative to `now`
+pub fn age_at(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, ParseError> {
+    let trimmed = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if trimmed.contains('-') || trimmed.contains(':') {
+        return Err(ParseError::new(input, "invalid date", AGE_FORMATS));
+    }
+
+    let period = parse_duration(input, None, AGE_FORMATS)?;
+    now.checked_sub_signed(period)
+        .ok_or_else(|| ParseError::new(input, "too far back", AGE_FORMATS))
+}
+
+/// Parse a size like `512mb`, `1.5gb` or `2GiB` into bytes.
+///
+/// `kb`/`mb`/`gb`/`tb` and `kib`/`mib`/`gib`/`tib` are both 1024-based,
+/// like the sizes pctrl prints.
+pub fn size(input: &str) -> Result<u64, ParseError> {
+    let err = |reason: &str| ParseError::new(input, reason, SIZE_FORMATS);
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    let compact = compact.to_lowercase();
+    if compact.is_empty() {
+        return Err(err("empty size"));
+    }
+
+    let split = compact
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(compact.len());
+    let (number, unit) = compact.split_at(split);
+    if number.is_empty() {
+        return Err(err("missing number"));
+    }
+    let value: f64 = number.parse().map_err(|_| err("invalid number"))?;
+
+    let multiplier: u64 = match unit {
+        "" if value == 0.0 => 1,
+        "" => return Err(err("missing unit")),
+        "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(err(&format!("unknown unit '{}'", unit))),
+    };
+
+    let bytes = (value * multiplier as f64).round();
+    if bytes >= u64::MAX as f64 {
+        return Err(err("too large"));
+    }
+    Ok(bytes as u64)
+}
+
+/// Parse a transfer rate like `2MB/s` or `500kb` into bytes per second.
+///
+/// A size, optionally followed by `/s`; zero is refused.
+pub fn rate(input: &str) -> Result<u64, ParseError> {
+    let trimmed = input.trim();
+    let per_second = trimmed
+        .strip_suffix("/s")
+        .or_else(|| trimmed.strip_suffix("/S"))
+        .unwrap_or(trimmed);
+    match size(per_second) {
+        Ok(0) => Err(ParseError::new(input, "zero rate", RATE_FORMATS)),
+        Ok(bytes) => Ok(bytes),
+        Err(e) => Err(ParseError::new(input, e.reason, RATE_FORMATS)),
+    }
+}
+
+/// Parse an octal file mode like `644` or `0600` (`0o600` works too).
+pub fn mode(input: &str) -> Result<i32, ParseError> {
+    let err = |reason: &str| ParseError::new(input, reason, MODE_FORMATS);
+    let trimmed = input.trim();
+    let digits = trimmed.strip_prefix("0o").unwrap_or(trimmed);
+    if digits.is_empty() {
+        return Err(err("empty mode"));
+    }
+    if !digits.chars().all(|c| ('0'..='7').contains(&c)) {
+        return Err(err("not octal"));
+    }
+    match i32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(err("too large")),
+    }
+}
+
+/// Seconds per duration unit, or `None` for an unknown unit
+fn unit_seconds(unit: &str) -> Option<i64> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(3_600),
+        "d" | "day" | "days" => Some(86_400),
+        "w" | "week" | "weeks" => Some(604_800),
+        "y" | "yr" | "year" | "years" => Some(31_536_000),
+        _ => None,
+    }
+}
+
+fn parse_duration(
+    input: &str,
+    bare_unit: Option<i64>,
+    formats: &'static str,
+) -> Result<Duration, ParseError> {
+    let err = |reason: &str| ParseError::new(input, reason, formats);
+    let lower = input.to_lowercase();
+    let mut chars = lower.chars().filter(|c| !c.is_whitespace()).peekable();
+    if chars.peek().is_none() {
+        return Err(err("empty duration"));
+    }
+
+    let mut total: i64 = 0;
+    let mut previous_unit = i64::MAX;
+    while chars.peek().is_some() {
+        let mut number = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            number.push(*c);
+            chars.next();
+        }
+        let mut unit = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+            unit.push(*c);
+            chars.next();
+        }
+
+        if unit.is_empty() {
+            if let Some(c) = chars.peek() {
+                return Err(err(&format!("unexpected '{}'", c)));
+            }
+        }
+        if number.is_empty() {
+            return Err(err("missing number"));
+        }
+        let value: i64 = number.parse().map_err(|_| err("too large"))?;
+
+        let seconds = if unit.is_empty() {
+            // Only a lone number can go without a unit
+            if previous_unit != i64::MAX {
+                return Err(err("missing unit"));
+            }
+            match bare_unit {
+                Some(seconds) => seconds,
+                None if value == 0 => 1,
+                None => return Err(err("missing unit")),
+            }
+        } else {
+            unit_seconds(&unit).ok_or_else(|| err(&format!("unknown unit '{}'", unit)))?
+        };
+
+        if seconds >= previous_unit {
+            return Err(err("units must go from largest to smallest"));
+        }
+        previous_unit = seconds;
+
+        total = value
+            .checked_mul(seconds)
+            .and_then(|s| total.checked_add(s))
+            .filter(|s| *s <= i64::MAX / 1_000)
+            .ok_or_else(|| err("too large"))?;
+    }
+
+    Ok(Duration::seconds(total))
+}
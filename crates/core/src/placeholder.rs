@@ -0,0 +1,240 @@
+//! Secret placeholders in declarative files
+//!
+//! Files that describe the inventory leave secrets out. Instead, a secret
+//! field may hold a placeholder that is resolved when the file is applied:
+//!
+//! - `@prompt` asks on the terminal, naming the entity
+//! - `@env:COOLIFY_TOKEN` reads an environment variable
+//! - `@file:/run/secrets/x` reads a file (one trailing newline is dropped)
+//!
+//! All placeholders of a file are collected before anything is written.
+//! Environment and file placeholders are read first; if any can't be
+//! resolved, every failure is reported at once and nobody gets prompted.
+//! Otherwise the prompts follow in one batch. A literal value that starts
+//! with a placeholder keeps it by doubling the `@` (`@@prompt`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Where a secret comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    Prompt,
+    Env(String),
+    File(PathBuf),
+}
+
+impl Placeholder {
+    /// The placeholder a field value stands for, if any
+    pub fn parse(value: &str) -> Option<Self> {
+        if value == "@prompt" {
+            Some(Placeholder::Prompt)
+        } else if let Some(name) = value.strip_prefix("@env:") {
+            (!name.is_empty()).then(|| Placeholder::Env(name.to_string()))
+        } else if let Some(path) = value.strip_prefix("@file:") {
+            (!path.is_empty()).then(|| Placeholder::File(PathBuf::from(path)))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placeholder::Prompt => write!(f, "@prompt"),
+            Placeholder::Env(name) => write!(f, "@env:{}", name),
+            Placeholder::File(path) => write!(f, "@file:{}", path.display()),
+        }
+    }
+}
+
+/// A literal value as written to a file: doubles the `@` of a value that
+/// would read as a placeholder
+pub fn escape(value: &str) -> String {
+    match Placeholder::parse(value) {
+        Some(_) => format!("@{}", value),
+        None => value.to_string(),
+    }
+}
+
+/// A placeholder found in a file, e.g. the password of credential 'prod-db'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceholderRef {
+    /// e.g. "credential 'prod-db'"
+    pub entity: String,
+    /// e.g. "password"
+    pub field: String,
+    pub placeholder: Placeholder,
+}
+
+impl PlaceholderRef {
+    /// Prompt text, e.g. "password for credential 'prod-db'"
+    pub fn label(&self) -> String {
+        format!("{} for {}", self.field, self.entity)
+    }
+}
+
+/// Reads the secrets placeholders point to
+pub trait SecretSource {
+    /// Ask the user for a secret without echoing it
+    fn prompt(&mut self, label: &str) -> Result<String, String>;
+
+    fn env(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Placeholders of one file, collected before anything is applied
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Placeholders {
+    refs: Vec<PlaceholderRef>,
+}
+
+impl Placeholders {
+    /// Check a secret field of an entity. Returns the literal value, or
+    /// `None` if it is a placeholder, which is kept for [`Self::resolve`].
+    pub fn add(&mut self, entity: &str, field: &str, value: &str) -> Option<String> {
+        if let Some(escaped) = value
+            .strip_prefix('@')
+            .filter(|rest| Placeholder::parse(rest).is_some())
+        {
+            return Some(escaped.to_string());
+        }
+        match Placeholder::parse(value) {
+            Some(placeholder) => {
+                self.refs.push(PlaceholderRef {
+                    entity: entity.to_string(),
+                    field: field.to_string(),
+                    placeholder,
+                });
+                None
+            }
+            None => Some(value.to_string()),
+        }
+    }
+
+    pub fn refs(&self) -> &[PlaceholderRef] {
+        &self.refs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.refs.is_empty()
+    }
+
+    /// Resolve every placeholder: environment and files first, then the
+    /// prompts in one batch, but only if nothing failed before
+    pub fn resolve(&self, source: &mut dyn SecretSource) -> Result<ResolvedSecrets, Unresolved> {
+        let mut resolved = ResolvedSecrets::default();
+        let mut failures = Vec::new();
+
+        for r in &self.refs {
+            let value = match &r.placeholder {
+                Placeholder::Prompt => continue,
+                Placeholder::Env(name) => match source.env(name) {
+                    Some(value) if !value.is_empty() => Ok(value),
+                    Some(_) => Err(format!("{} is empty", name)),
+                    None => Err(format!("{} is not set", name)),
+                },
+                Placeholder::File(path) => match source.read_file(path) {
+                    Ok(content) => match trim_newline(&content) {
+                        "" => Err("file is empty".to_string()),
+                        value => Ok(value.to_string()),
+                    },
+                    Err(e) => Err(e.to_string()),
+                },
+            };
+            match value {
+                Ok(value) => resolved.insert(r, value),
+                Err(reason) => failures.push((r.clone(), reason)),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(Unresolved { failures });
+        }
+
+        for r in self
+            .refs
+            .iter()
+            .filter(|r| r.placeholder == Placeholder::Prompt)
+        {
+            match source.prompt(&r.label()) {
+                Ok(value) if !value.is_empty() => resolved.insert(r, value),
+                Ok(_) => failures.push((r.clone(), "nothing entered".to_string())),
+                Err(reason) => failures.push((r.clone(), reason)),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(Unresolved { failures });
+        }
+        Ok(resolved)
+    }
+}
+
+/// Secret values by entity and field. Debug output leaves the values out.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ResolvedSecrets {
+    values: HashMap<(String, String), String>,
+}
+
+impl ResolvedSecrets {
+    pub fn get(&self, entity: &str, field: &str) -> Option<&str> {
+        self.values
+            .get(&(entity.to_string(), field.to_string()))
+            .map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn insert(&mut self, r: &PlaceholderRef, value: String) {
+        self.values
+            .insert((r.entity.clone(), r.field.clone()), value);
+    }
+}
+
+impl fmt::Debug for ResolvedSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.values.keys()).finish()
+    }
+}
+
+/// Placeholders that couldn't be resolved, with the reason for each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    pub failures: Vec<(PlaceholderRef, String)>,
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} secret placeholder{} can't be resolved, nothing was applied:",
+            self.failures.len(),
+            if self.failures.len() == 1 { "" } else { "s" }
+        )?;
+        for (r, reason) in &self.failures {
+            write!(f, "\n  {} ({}): {}", r.label(), r.placeholder, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Unresolved {}
+
+fn trim_newline(content: &str) -> &str {
+    content
+        .strip_suffix('\n')
+        .map(|c| c.strip_suffix('\r').unwrap_or(c))
+        .unwrap_or(content)
+}
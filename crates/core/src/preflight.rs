@@ -0,0 +1,164 @@
+//! Pre-flight checks before a deploy
+//!
+//! Before `project deploy` triggers anything, the project's servers, linked
+//! databases and health URL are checked. Each failing check either blocks the
+//! deploy or, when the project marks that kind of check as advisory, only
+//! warns. This module holds the policy; collecting the results is up to the
+//! caller.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Exit code when the pre-flight refused the deploy (nothing was triggered)
+pub const EXIT_PREFLIGHT_REFUSED: i32 = 3;
+
+/// Exit code when the deploy was triggered and failed
+pub const EXIT_DEPLOY_FAILED: i32 = 4;
+
+/// Kind of pre-flight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CheckKind {
+    /// A linked server accepts SSH connections
+    Server,
+    /// A linked database accepts connections
+    Database,
+    /// The project's health URL answers with 2xx
+    Health,
+}
+
+impl CheckKind {
+    pub const ALL: [CheckKind; 3] = [CheckKind::Server, CheckKind::Database, CheckKind::Health];
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckKind::Server => write!(f, "server"),
+            CheckKind::Database => write!(f, "database"),
+            CheckKind::Health => write!(f, "health"),
+        }
+    }
+}
+
+impl std::str::FromStr for CheckKind {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "server" => Ok(CheckKind::Server),
+            "database" | "db" => Ok(CheckKind::Database),
+            "health" => Ok(CheckKind::Health),
+            _ => Err(format!(
+                "Unknown check: {} (expected server, database or health)",
+                s
+            )),
+        }
+    }
+}
+
+/// Per-project pre-flight settings
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightConfig {
+    /// URL that must answer with 2xx before a deploy
+    pub health_url: Option<String>,
+    /// Check kinds that only warn instead of blocking (sorted, unique)
+    #[serde(default)]
+    pub advisory: Vec<CheckKind>,
+}
+
+impl PreflightConfig {
+    /// Whether a failing check of `kind` refuses the deploy
+    pub fn is_blocking(&self, kind: CheckKind) -> bool {
+        !self.advisory.contains(&kind)
+    }
+
+    /// Mark `kind` advisory (or blocking again)
+    pub fn set_advisory(&mut self, kind: CheckKind, advisory: bool) {
+        self.advisory.retain(|k| *k != kind);
+        if advisory {
+            self.advisory.push(kind);
+            self.advisory.sort();
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub kind: CheckKind,
+    /// What was checked, e.g. the server name or URL
+    pub target: String,
+    /// Why the check failed, `None` if it passed
+    pub error: Option<String>,
+}
+
+impl CheckResult {
+    pub fn passed(kind: CheckKind, target: impl Into<String>) -> Self {
+        Self {
+            kind,
+            target: target.into(),
+            error: None,
+        }
+    }
+
+    pub fn failed(kind: CheckKind, target: impl Into<String>, error: impl Into<String>) -> Self {
+        Self {
+            kind,
+            target: target.into(),
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn is_passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Overall pre-flight result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every check passed
+    Clear,
+    /// Only advisory checks failed; the deploy goes ahead
+    Warnings,
+    /// At least one blocking check failed
+    Refused,
+}
+
+/// Check results judged against a project's config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightReport {
+    pub checks: Vec<CheckResult>,
+    pub verdict: Verdict,
+}
+
+impl PreflightReport {
+    /// Failed checks that refuse the deploy
+    pub fn blocking_failures<'a>(&'a self, config: &'a PreflightConfig) -> Vec<&'a CheckResult> {
+        self.checks
+            .iter()
+            .filter(|c| !c.is_passed() && config.is_blocking(c.kind))
+            .collect()
+    }
+
+    /// Failed checks that only warn
+    pub fn advisory_failures<'a>(&'a self, config: &'a PreflightConfig) -> Vec<&'a CheckResult> {
+        self.checks
+            .iter()
+            .filter(|c| !c.is_passed() && !config.is_blocking(c.kind))
+            .collect()
+    }
+}
+
+/// Judge check results: any blocking failure refuses the deploy
+pub fn evaluate(checks: Vec<CheckResult>, config: &PreflightConfig) -> PreflightReport {
+    let failed = checks.iter().filter(|c| !c.is_passed());
+    let verdict = if failed.clone().any(|c| config.is_blocking(c.kind)) {
+        Verdict::Refused
+    } else if failed.count() > 0 {
+        Verdict::Warnings
+    } else {
+        Verdict::Clear
+    };
+
+    PreflightReport { checks, verdict }
+}
@@ -0,0 +1,446 @@
+//! Disaster-recovery bundle of one project (`pctrl project bundle`)
+//!
+//! A bundle is a `.tar.gz` holding one file per [`Part`] and a
+//! [`MANIFEST`] that lists every file with its size and SHA-256, so a copy
+//! can be checked long after it was made. Parts that hold secrets or a
+//! whole database copy are gated ([`Gate`]): they are left out, and named
+//! in the manifest as skipped, unless the matching flag was given.
+//!
+//! This module renders the parts; packing and verifying the archive is in
+//! `pctrl_database::bundle_archive`.
+
+use crate::bundle::ProjectBundle;
+use crate::proxy_labels::{proxy_hosts, Labels};
+use crate::redact::{is_secret_key, REDACTED};
+use crate::{Container, DatabaseCredentials, JournalEntry, ProjectResource, Script, Server};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Name of the manifest inside the archive
+pub const MANIFEST: &str = "manifest.json";
+
+/// Version of the bundle layout, raised when it changes incompatibly
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Journal entries per entity in `journal.md`
+pub const JOURNAL_LIMIT: i64 = 50;
+
+/// One part of a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Part {
+    /// Human-readable overview of the project and its links
+    Markdown,
+    /// The project's entities as TOML, secrets removed unless included
+    Inventory,
+    /// Reverse-proxy routes of the project's domains
+    Proxy,
+    /// Connection variables of the linked databases
+    Env,
+    /// Recent journal entries of the project and its servers and domains
+    Journal,
+    /// The latest verified pctrl backup
+    Backup,
+}
+
+/// What a gated part needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    /// `--include-secrets`
+    Secrets,
+    /// `--include-artifacts`
+    Artifacts,
+}
+
+impl fmt::Display for Gate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Gate::Secrets => write!(f, "--include-secrets"),
+            Gate::Artifacts => write!(f, "--include-artifacts"),
+        }
+    }
+}
+
+impl Part {
+    pub const ALL: &'static [Part] = &[
+        Part::Markdown,
+        Part::Inventory,
+        Part::Proxy,
+        Part::Env,
+        Part::Journal,
+        Part::Backup,
+    ];
+
+    /// Path in the archive; the backup keeps its file name under `artifacts/`
+    pub fn path(&self) -> &'static str {
+        match self {
+            Part::Markdown => "project.md",
+            Part::Inventory => "inventory.toml",
+            Part::Proxy => "proxy.toml",
+            Part::Env => ".env",
+            Part::Journal => "journal.md",
+            Part::Backup => "artifacts/",
+        }
+    }
+
+    /// The flag a part needs. The `.env` render is nothing but secrets, and
+    /// a backup is the whole database, every project included.
+    pub fn gate(&self) -> Option<Gate> {
+        match self {
+            Part::Env => Some(Gate::Secrets),
+            Part::Backup => Some(Gate::Artifacts),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::Markdown => write!(f, "markdown"),
+            Part::Inventory => write!(f, "inventory"),
+            Part::Proxy => write!(f, "proxy"),
+            Part::Env => write!(f, "env"),
+            Part::Journal => write!(f, "journal"),
+            Part::Backup => write!(f, "backup"),
+        }
+    }
+}
+
+/// Flags of `pctrl project bundle`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BundleOptions {
+    pub include_secrets: bool,
+    pub include_artifacts: bool,
+}
+
+impl BundleOptions {
+    /// Whether a part may go into the bundle
+    pub fn allows(&self, part: Part) -> bool {
+        match part.gate() {
+            None => true,
+            Some(Gate::Secrets) => self.include_secrets,
+            Some(Gate::Artifacts) => self.include_artifacts,
+        }
+    }
+}
+
+/// A file in the bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub part: Part,
+    pub size: u64,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+}
+
+/// A part left out, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedPart {
+    pub part: Part,
+    pub reason: String,
+}
+
+/// `manifest.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub pctrl_version: String,
+    pub created_at: String,
+    pub project: String,
+    pub include_secrets: bool,
+    pub include_artifacts: bool,
+    pub files: Vec<ManifestFile>,
+    #[serde(default)]
+    pub skipped: Vec<SkippedPart>,
+}
+
+/// A hostname routed to a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProxyRoute {
+    pub hostname: String,
+    pub container: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Scheme whose labels name the hostname (traefik, caddy, nginx-proxy)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    /// The container's reverse-proxy labels
+    pub labels: Labels,
+}
+
+/// Routes of the project's domains that point at a container; `containers`
+/// may hold more than the project's, only those domains name are used
+pub fn proxy_routes(bundle: &ProjectBundle, containers: &[Container]) -> Vec<ProxyRoute> {
+    bundle
+        .domains
+        .iter()
+        .filter_map(|domain| {
+            let target = domain.container_id.as_deref()?;
+            let container = containers
+                .iter()
+                .find(|c| c.id == target || c.name.eq_ignore_ascii_case(target));
+            let labels: Labels = container
+                .and_then(|c| c.labels.as_deref())
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or_default();
+            let scheme = proxy_hosts(&labels)
+                .into_iter()
+                .find(|h| h.hostname.eq_ignore_ascii_case(&domain.domain))
+                .map(|h| h.scheme.to_string());
+            let labels = labels
+                .into_iter()
+                .filter(|(key, _)| is_proxy_label(key))
+                .collect();
+            Some(ProxyRoute {
+                hostname: domain.domain.clone(),
+                container: container.map_or(target, |c| c.name.as_str()).to_string(),
+                server: domain.server_id.as_deref().map(|id| {
+                    bundle
+                        .server(id)
+                        .map_or(id, |s| s.name.as_str())
+                        .to_string()
+                }),
+                scheme,
+                labels,
+            })
+        })
+        .collect()
+}
+
+fn is_proxy_label(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("traefik.")
+        || key == "caddy"
+        || key.starts_with("caddy.")
+        || key.starts_with("caddy_")
+        || matches!(
+            key.as_str(),
+            "virtual_host" | "virtual_port" | "letsencrypt_host"
+        )
+}
+
+/// `proxy.toml`: one `[[routes]]` table per routed domain
+pub fn render_proxy(routes: &[ProxyRoute]) -> String {
+    #[derive(Serialize)]
+    struct Document<'a> {
+        routes: &'a [ProxyRoute],
+    }
+    let body = toml::to_string(&Document { routes }).unwrap_or_default();
+    format!(
+        "# Reverse-proxy routes of the project's domains, with the labels that\n# declare them\n{}",
+        body
+    )
+}
+
+/// `inventory.toml`: the project, its links and the linked entities.
+/// Database passwords and connection strings, and secret-looking script
+/// variables, are only kept with `include_secrets`.
+pub fn render_inventory(bundle: &ProjectBundle, include_secrets: bool) -> Result<String, String> {
+    #[derive(Serialize)]
+    struct Document<'a> {
+        project: &'a crate::Project,
+        links: &'a [ProjectResource],
+        servers: &'a [Server],
+        domains: &'a [crate::Domain],
+        databases: Vec<DatabaseCredentials>,
+        scripts: Vec<Script>,
+    }
+
+    let databases = bundle
+        .databases
+        .iter()
+        .cloned()
+        .map(|mut db| {
+            if !include_secrets {
+                db.password = db.password.map(|_| REDACTED.to_string());
+                db.connection_string = db.connection_string.map(|_| REDACTED.to_string());
+            }
+            db
+        })
+        .collect();
+    let scripts = bundle
+        .scripts
+        .iter()
+        .cloned()
+        .map(|mut script| {
+            if !include_secrets {
+                for (key, value) in script.env.iter_mut() {
+                    if is_secret_key(key) {
+                        *value = REDACTED.to_string();
+                    }
+                }
+            }
+            script
+        })
+        .collect();
+
+    let document = Document {
+        project: &bundle.project,
+        links: &bundle.links,
+        servers: &bundle.servers,
+        domains: &bundle.domains,
+        databases,
+        scripts,
+    };
+    toml::to_string(&document).map_err(|e| e.to_string())
+}
+
+/// `.env`: `<NAME>_URL` and its parts for every linked database
+pub fn render_env(bundle: &ProjectBundle) -> String {
+    let mut out = format!("# Databases of {}\n", bundle.project.name);
+    for db in &bundle.databases {
+        let prefix: String = db
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let vars = [
+            ("URL", db.connection_string.clone()),
+            ("HOST", db.host.clone()),
+            ("PORT", db.port.map(|p| p.to_string())),
+            ("NAME", db.database_name.clone()),
+            ("USER", db.username.clone()),
+            ("PASSWORD", db.password.clone()),
+        ];
+        out.push('\n');
+        for (suffix, value) in vars {
+            if let Some(value) = value {
+                out.push_str(&format!("{}_{}={}\n", prefix, suffix, env_value(&value)));
+            }
+        }
+    }
+    out
+}
+
+/// A value quoted when a shell or dotenv parser would split it
+fn env_value(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.:/@%+,=".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// `project.md`: what the project is and what it consists of
+pub fn render_markdown(bundle: &ProjectBundle) -> String {
+    let project = &bundle.project;
+    let mut out = format!("# {}\n\n", project.name);
+    if let Some(description) = &project.description {
+        out.push_str(&format!("{}\n\n", description));
+    }
+    out.push_str(&format!("- Status: {}\n", project.status));
+    if !project.stack.is_empty() {
+        out.push_str(&format!("- Stack: {}\n", project.stack.join(", ")));
+    }
+    if let Some(notes) = &project.notes {
+        out.push_str(&format!("\n## Notes\n\n{}\n", notes));
+    }
+
+    if !bundle.servers.is_empty() {
+        out.push_str("\n## Servers\n\n| Name | Host | Type | Provider |\n|---|---|---|---|\n");
+        for s in &bundle.servers {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                s.name,
+                s.host,
+                s.server_type,
+                s.provider.as_deref().unwrap_or("")
+            ));
+        }
+    }
+    if !bundle.domains.is_empty() {
+        out.push_str("\n## Domains\n\n| Domain | Type | SSL | Server |\n|---|---|---|---|\n");
+        for d in &bundle.domains {
+            let server = d
+                .server_id
+                .as_deref()
+                .map(|id| bundle.server(id).map_or(id, |s| s.name.as_str()))
+                .unwrap_or("");
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                d.domain,
+                d.domain_type,
+                if d.ssl { "yes" } else { "no" },
+                server
+            ));
+        }
+    }
+    if !bundle.databases.is_empty() {
+        out.push_str("\n## Databases\n\n| Name | Type | Host | Database |\n|---|---|---|---|\n");
+        for db in &bundle.databases {
+            let host = match (&db.host, db.port) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.clone(),
+                _ => String::new(),
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                db.name,
+                db.db_type,
+                host,
+                db.database_name.as_deref().unwrap_or("")
+            ));
+        }
+    }
+    if !bundle.scripts.is_empty() {
+        out.push_str("\n## Scripts\n\n");
+        for script in &bundle.scripts {
+            let description = script
+                .description
+                .as_deref()
+                .map(|d| format!(": {}", d))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "- `{}` ({}){}\n",
+                script.name, script.script_type, description
+            ));
+        }
+    }
+
+    let other: Vec<&ProjectResource> = bundle
+        .links
+        .iter()
+        .filter(|l| bundle.name_of(l).is_none())
+        .collect();
+    if !other.is_empty() {
+        out.push_str("\n## Other Links\n\n");
+        for link in other {
+            out.push_str(&format!("- {}: {}\n", link.resource_type, link.resource_id));
+        }
+    }
+    out
+}
+
+/// `journal.md`: entries grouped by the entity they belong to (a heading
+/// per entity), newest first
+pub fn render_journal(groups: &[(String, Vec<JournalEntry>)]) -> String {
+    let mut out = String::from("# Journal\n");
+    for (heading, entries) in groups {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n", heading));
+        for entry in entries {
+            let amends = entry
+                .amends
+                .map(|id| format!(", amends #{}", id))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "\n### #{} ({}, {}{})\n\n{}\n",
+                entry.id, entry.created_at, entry.author, amends, entry.text
+            ));
+        }
+    }
+    out
+}
@@ -0,0 +1,284 @@
+//! Cloning a project for a new client (`pctrl project clone`)
+//!
+//! A clone copies the project's own fields and, on request, its scripts,
+//! its domains (renamed with a suffix) and its links to the same servers and
+//! databases. Secrets are never copied: database links are only carried
+//! over when asked for, and they point at the same credentials. Links to
+//! containers, git repos and Coolify apps belong to the source's deployment
+//! and are left out.
+
+use crate::bundle::ProjectBundle;
+use crate::domain_base::domain_id;
+use crate::{Domain, Project, ProjectResource, ProjectStatus, ResourceType, Script};
+use std::collections::HashSet;
+
+/// What to copy besides the project itself
+#[derive(Debug, Clone, Default)]
+pub struct CloneOptions {
+    pub include_scripts: bool,
+    /// Create renamed copies of the linked domains: `app.example.com` with
+    /// suffix "acme" becomes `app-acme.example.com`
+    pub domain_suffix: Option<String>,
+    pub link_same_servers: bool,
+    pub link_same_databases: bool,
+}
+
+/// A script copied into the new project
+#[derive(Debug, Clone)]
+pub struct ClonedScript {
+    /// Name of the source script
+    pub from: String,
+    /// ID of the source script
+    pub from_id: String,
+    pub script: Script,
+}
+
+/// A domain created for the new project
+#[derive(Debug, Clone)]
+pub struct ClonedDomain {
+    /// Name of the source domain
+    pub from: String,
+    pub domain: Domain,
+}
+
+/// Everything a clone creates
+#[derive(Debug, Clone)]
+pub struct ClonePlan {
+    pub project: Project,
+    pub scripts: Vec<ClonedScript>,
+    pub domains: Vec<ClonedDomain>,
+    /// Links of the new project, to copied or shared entities
+    pub links: Vec<ProjectResource>,
+    /// Source links not carried over
+    pub skipped: Vec<ProjectResource>,
+}
+
+/// ID for a name, the way `add` commands derive it
+pub fn slug(name: &str) -> String {
+    name.trim().to_lowercase().replace(' ', "-")
+}
+
+/// `name` with `suffix` appended to its first label
+pub fn suffixed_domain(name: &str, suffix: &str) -> String {
+    match name.split_once('.') {
+        Some((first, rest)) => format!("{}-{}.{}", first, suffix, rest),
+        None => format!("{}-{}", name, suffix),
+    }
+}
+
+/// Lowercased suffix, usable inside a domain label
+pub fn normalize_suffix(suffix: &str) -> Result<String, String> {
+    let suffix = suffix.trim().trim_matches(['-', '.']).to_lowercase();
+    let valid = !suffix.is_empty()
+        && suffix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(suffix)
+    } else {
+        Err(format!(
+            "'{}' can't be used as domain suffix (letters, digits and '-')",
+            suffix
+        ))
+    }
+}
+
+impl ClonePlan {
+    /// Plan a clone of `bundle` named `new_name`.
+    ///
+    /// `scripts` are the scripts owned by the source project; scripts it
+    /// only links are copied too. `existing_*` are all stored entries, used
+    /// to reject a taken project name or domain and to give cloned scripts
+    /// free names: a script named after the source project ("shop-backup")
+    /// is renamed for the new one ("acme-backup"), others get its slug as
+    /// prefix, and a taken name gets "-2", "-3", ... appended.
+    pub fn new(
+        bundle: &ProjectBundle,
+        scripts: &[Script],
+        new_name: &str,
+        options: &CloneOptions,
+        existing_projects: &[Project],
+        existing_scripts: &[Script],
+        existing_domains: &[Domain],
+    ) -> Result<Self, String> {
+        let source = &bundle.project;
+        let new_name = new_name.trim();
+        let new_id = slug(new_name);
+        if new_id.is_empty() {
+            return Err("The new project needs a name".to_string());
+        }
+        if let Some(taken) = existing_projects
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(new_name) || p.id == new_id)
+        {
+            return Err(format!("Project '{}' already exists", taken.name));
+        }
+
+        let project = Project {
+            id: new_id.clone(),
+            name: new_name.to_string(),
+            description: source.description.clone(),
+            stack: source.stack.clone(),
+            // A new client starts out in development
+            status: ProjectStatus::Dev,
+            color: source.color.clone(),
+            icon: source.icon.clone(),
+            notes: source.notes.clone(),
+        };
+
+        let mut plan = Self {
+            project,
+            scripts: Vec::new(),
+            domains: Vec::new(),
+            links: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        if options.include_scripts {
+            let mut taken: HashSet<String> = existing_scripts
+                .iter()
+                .flat_map(|s| [s.id.clone(), slug(&s.name)])
+                .collect();
+            let linked = bundle
+                .links_of(ResourceType::Script)
+                .filter_map(|l| bundle.script(&l.resource_id));
+            let mut seen = HashSet::new();
+            for script in scripts.iter().chain(linked) {
+                if !seen.insert(script.id.clone()) {
+                    continue;
+                }
+                let name = free_name(&script_name(&script.name, &source.id, &new_id), &taken);
+                taken.insert(slug(&name));
+                plan.scripts.push(ClonedScript {
+                    from: script.name.clone(),
+                    from_id: script.id.clone(),
+                    script: Script {
+                        id: slug(&name),
+                        name,
+                        project_id: Some(new_id.clone()),
+                        last_run: None,
+                        last_result: None,
+                        exit_code: None,
+                        last_output: None,
+                        ..script.clone()
+                    },
+                });
+            }
+        }
+
+        let suffix = options
+            .domain_suffix
+            .as_deref()
+            .map(normalize_suffix)
+            .transpose()?;
+
+        for link in &bundle.links {
+            let target = match link.resource_type {
+                ResourceType::Server if options.link_same_servers => Some(link.resource_id.clone()),
+                ResourceType::Database if options.link_same_databases => {
+                    Some(link.resource_id.clone())
+                }
+                ResourceType::Script if options.include_scripts => bundle
+                    .script(&link.resource_id)
+                    .and_then(|s| plan.scripts.iter().find(|c| c.from_id == s.id))
+                    .map(|c| c.script.id.clone()),
+                ResourceType::Domain => match (&suffix, bundle.domain(&link.resource_id)) {
+                    (Some(suffix), Some(domain)) => {
+                        Some(plan.clone_domain(domain, suffix, options, existing_domains)?)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            match target {
+                Some(resource_id) => plan.links.push(ProjectResource {
+                    id: format!("{}-link-{}", new_id, plan.links.len() + 1),
+                    project_id: new_id.clone(),
+                    resource_type: link.resource_type.clone(),
+                    resource_id,
+                    role: link.role.clone(),
+                    notes: link.notes.clone(),
+                    start_order: link.start_order,
+                }),
+                None => plan.skipped.push(link.clone()),
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Plan the copy of a linked domain and return its new ID. The copy
+    /// keeps type, SSL and notes; its server only when servers are shared,
+    /// since a new client usually runs elsewhere.
+    fn clone_domain(
+        &mut self,
+        domain: &Domain,
+        suffix: &str,
+        options: &CloneOptions,
+        existing: &[Domain],
+    ) -> Result<String, String> {
+        if let Some(done) = self.domains.iter().find(|c| c.from == domain.domain) {
+            return Ok(done.domain.id.clone());
+        }
+        let name = suffixed_domain(&domain.domain, suffix);
+        let id = domain_id(&name);
+        let taken = existing
+            .iter()
+            .map(|d| (&d.domain, &d.id))
+            .chain(
+                self.domains
+                    .iter()
+                    .map(|c| (&c.domain.domain, &c.domain.id)),
+            )
+            .any(|(other, other_id)| other.eq_ignore_ascii_case(&name) || *other_id == id);
+        if taken {
+            return Err(format!(
+                "Domain '{}' already exists; choose another suffix",
+                name
+            ));
+        }
+        self.domains.push(ClonedDomain {
+            from: domain.domain.clone(),
+            domain: Domain {
+                id: id.clone(),
+                domain: name,
+                domain_type: domain.domain_type.clone(),
+                ssl: domain.ssl,
+                ssl_expiry: None,
+                cloudflare_zone_id: None,
+                cloudflare_record_id: None,
+                server_id: domain
+                    .server_id
+                    .clone()
+                    .filter(|_| options.link_same_servers),
+                container_id: None,
+                notes: domain.notes.clone(),
+                superseded_by: None,
+            },
+        });
+        Ok(id)
+    }
+}
+
+/// Name of a cloned script before collision handling
+fn script_name(name: &str, source_id: &str, new_id: &str) -> String {
+    let prefix = name.get(..source_id.len());
+    let rest = name.get(source_id.len()..).unwrap_or_default();
+    match prefix {
+        Some(prefix) if prefix.eq_ignore_ascii_case(source_id) && rest.starts_with(['-', ' ']) => {
+            format!("{}{}", new_id, rest)
+        }
+        _ => format!("{}-{}", new_id, name),
+    }
+}
+
+/// `name`, or `name-2`, `name-3`, ... whichever slug isn't taken
+fn free_name(name: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(&slug(name)) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", name, n))
+        .find(|candidate| !taken.contains(&slug(candidate)))
+        .expect("some suffix is free")
+}
@@ -0,0 +1,71 @@
+//! Live status of a project's resources (`pctrl project status`)
+//!
+//! Every linked server, container, database, domain and git repo is checked
+//! at once, each within its own timeout, and the results go into a
+//! [`FanoutReport`](crate::fanout::FanoutReport) like those of other
+//! commands that act on many targets. The judgements are here; the probing
+//! is up to the caller.
+
+use crate::fanout::Outcome;
+use crate::ResourceType;
+use std::time::Duration;
+
+/// Resource types `project status` checks; Coolify and script links have
+/// nothing to probe
+pub const CHECKED: [ResourceType; 5] = [
+    ResourceType::Server,
+    ResourceType::Container,
+    ResourceType::Database,
+    ResourceType::Domain,
+    ResourceType::Git,
+];
+
+/// Outcome and detail of one check
+pub type Verdict = (Outcome, Option<String>);
+
+/// URL a domain is probed at: https unless SSL is off
+pub fn domain_url(domain: &str, ssl: bool) -> String {
+    format!("{}://{}/", if ssl { "https" } else { "http" }, domain)
+}
+
+/// A domain is up when it answers below 500; a 404 or 405 to a HEAD
+/// request still means the site is served
+pub fn http_verdict(status: u16) -> Verdict {
+    let outcome = if status < 500 {
+        Outcome::Ok
+    } else {
+        Outcome::Failed
+    };
+    (outcome, Some(format!("HTTP {}", status)))
+}
+
+/// A container is up when it runs, and is healthy if it has a health check
+pub fn container_verdict(status: &str, health: Option<&str>) -> Verdict {
+    match (status, health) {
+        ("running", None | Some("healthy")) => (Outcome::Ok, health.map(str::to_string)),
+        ("running", Some(health)) => (Outcome::Failed, Some(health.to_string())),
+        ("", _) => (Outcome::Failed, Some("unknown state".to_string())),
+        (status, _) => (Outcome::Failed, Some(status.to_string())),
+    }
+}
+
+/// A readable repo is never down; uncommitted changes are only reported
+pub fn git_verdict(changed_files: usize) -> Verdict {
+    let detail = match changed_files {
+        0 => "clean".to_string(),
+        1 => "dirty, 1 changed file".to_string(),
+        n => format!("dirty, {} changed files", n),
+    };
+    (Outcome::Ok, Some(detail))
+}
+
+/// A check that didn't finish in time
+pub fn timed_out(timeout: Duration) -> Verdict {
+    (
+        Outcome::Failed,
+        Some(format!(
+            "timed out after {}",
+            crate::humanize::duration(timeout)
+        )),
+    )
+}
@@ -0,0 +1,181 @@
+//! Health badge for shell prompts (`pctrl prompt-segment`)
+//!
+//! The segment is rendered on every prompt, so it only looks at what the
+//! monitor last recorded and never touches the network. Anything it can't
+//! find out within [`BUDGET`] shows as "?".
+
+use crate::monitor::{liveness, Liveness, MonitorState};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Time the whole segment may take
+pub const BUDGET: Duration = Duration::from_millis(50);
+
+/// How the segment is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromptFormat {
+    /// Colored block with a powerline arrow (needs a powerline font)
+    Powerline,
+    /// `pctrl ✔ 12`
+    #[default]
+    Plain,
+    /// One JSON object, for prompt frameworks
+    Json,
+}
+
+impl FromStr for PromptFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "powerline" => Ok(PromptFormat::Powerline),
+            "plain" => Ok(PromptFormat::Plain),
+            "json" => Ok(PromptFormat::Json),
+            _ => Err(format!("Unknown format '{}' (powerline, plain, json)", s)),
+        }
+    }
+}
+
+/// What the prompt shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptHealth {
+    /// The monitor is running and every server is up
+    Healthy { servers: u32 },
+    /// Servers the last monitor cycle found down
+    Problems { count: u32 },
+    /// No fresh monitor data, or it couldn't be read in time
+    Unknown { servers: Option<u32> },
+}
+
+impl PromptHealth {
+    /// Judge from the server count and the last monitor cycle. A stale
+    /// monitor is unknown: its last result may no longer hold.
+    pub fn judge(servers: Option<u32>, state: Option<&MonitorState>, now: DateTime<Utc>) -> Self {
+        let Some(state) = state else {
+            return PromptHealth::Unknown { servers };
+        };
+        match liveness(Some(state), now) {
+            Liveness::Alive { .. } if state.servers_down > 0 => PromptHealth::Problems {
+                count: state.servers_down,
+            },
+            Liveness::Alive { .. } => PromptHealth::Healthy {
+                servers: servers.unwrap_or(state.servers_checked),
+            },
+            Liveness::NeverRan | Liveness::Stale { .. } => PromptHealth::Unknown { servers },
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            PromptHealth::Healthy { .. } => "✔",
+            PromptHealth::Problems { .. } => "✘",
+            PromptHealth::Unknown { .. } => "?",
+        }
+    }
+
+    fn count(&self) -> Option<u32> {
+        match *self {
+            PromptHealth::Healthy { servers } => Some(servers),
+            PromptHealth::Problems { count } => Some(count),
+            PromptHealth::Unknown { servers } => servers,
+        }
+    }
+
+    /// The segment in `format`, without a trailing newline
+    pub fn render(&self, format: PromptFormat) -> String {
+        match format {
+            PromptFormat::Plain => self.to_string(),
+            PromptFormat::Powerline => {
+                // Background and the arrow's foreground in the same color
+                let color = match self {
+                    PromptHealth::Healthy { .. } => 2,
+                    PromptHealth::Problems { .. } => 1,
+                    PromptHealth::Unknown { .. } => 3,
+                };
+                format!(
+                    "\x1b[30;4{c}m {} \x1b[0;3{c}m\u{e0b0}\x1b[0m",
+                    self,
+                    c = color
+                )
+            }
+            PromptFormat::Json => {
+                let (status, servers, problems) = match *self {
+                    PromptHealth::Healthy { servers } => ("healthy", Some(servers), Some(0)),
+                    PromptHealth::Problems { count } => ("problems", None, Some(count)),
+                    PromptHealth::Unknown { servers } => ("unknown", servers, None),
+                };
+                serde_json::json!({
+                    "status": status,
+                    "servers": servers,
+                    "problems": problems,
+                })
+                .to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for PromptHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pctrl {}", self.symbol())?;
+        if let Some(count) = self.count() {
+            write!(f, " {}", count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shells `--init` has a snippet for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptShell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+impl FromStr for PromptShell {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "zsh" => Ok(PromptShell::Zsh),
+            "bash" => Ok(PromptShell::Bash),
+            "fish" => Ok(PromptShell::Fish),
+            _ => Err(format!("Unknown shell '{}' (zsh, bash, fish)", s)),
+        }
+    }
+}
+
+/// Snippet that adds the segment to `shell`'s prompt. `command` is how to
+/// call pctrl, already quoted (e.g. `pctrl --db ~/work.db`).
+pub fn init_script(shell: PromptShell, command: &str) -> String {
+    match shell {
+        PromptShell::Zsh => format!(
+            r#"# pctrl prompt segment: add `eval "$(pctrl prompt-segment --init zsh)"` to ~/.zshrc
+setopt prompt_subst
+_pctrl_prompt_segment() {{ {command} prompt-segment --format plain 2>/dev/null; }}
+RPROMPT='$(_pctrl_prompt_segment)'${{RPROMPT:+" $RPROMPT"}}
+"#
+        ),
+        PromptShell::Bash => format!(
+            r#"# pctrl prompt segment: add `eval "$(pctrl prompt-segment --init bash)"` to ~/.bashrc
+_pctrl_prompt_segment() {{ {command} prompt-segment --format plain 2>/dev/null; }}
+PS1='$(_pctrl_prompt_segment) '"$PS1"
+"#
+        ),
+        PromptShell::Fish => format!(
+            r#"# pctrl prompt segment: add `pctrl prompt-segment --init fish | source` to ~/.config/fish/config.fish
+if functions -q fish_right_prompt; and not functions -q _pctrl_original_right_prompt
+    functions -c fish_right_prompt _pctrl_original_right_prompt
+end
+function fish_right_prompt
+    {command} prompt-segment --format plain 2>/dev/null
+    if functions -q _pctrl_original_right_prompt
+        echo -n ' '
+        _pctrl_original_right_prompt
+    end
+end
+"#
+        ),
+    }
+}
@@ -0,0 +1,237 @@
+//! DNS propagation checks (`pctrl domain propagation`)
+//!
+//! Asks several public resolvers directly for a domain's A/AAAA records and
+//! compares their answers against the address the domain should point to.
+//! Each resolver gets its own timeout, so one slow resolver only fails its
+//! own row. The percentage counts the resolvers that answered; failed ones
+//! are reported but can't hold a propagation back forever. Checks of many
+//! domains go through [`check_many`], which keeps to per-resolver limits.
+
+use crate::throttle::{self, Limits, Progress};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Resolvers asked when `--resolvers` isn't given (Cloudflare, Google, Quad9)
+pub const DEFAULT_RESOLVERS: &[&str] = &["1.1.1.1", "8.8.8.8", "9.9.9.9"];
+
+/// Time between polls in `--watch` mode and of tracked propagations
+pub const WATCH_INTERVAL_SECS: u64 = 30;
+
+/// How long one resolver may take to answer
+pub const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One address record from a resolver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub ip: IpAddr,
+    /// Seconds the resolver may still cache the record
+    pub ttl: u32,
+}
+
+/// Looks up a domain's A and AAAA records at one resolver
+#[async_trait]
+pub trait DnsLookup: Send + Sync {
+    /// Records at `resolver`; an empty list when the domain has none
+    async fn lookup(&self, resolver: IpAddr, domain: &str) -> Result<Vec<DnsRecord>, String>;
+}
+
+/// What one resolver said
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolverAnswer {
+    pub resolver: IpAddr,
+    pub records: Vec<DnsRecord>,
+    /// Why the resolver gave no answer (timeout, refused, ...)
+    pub error: Option<String>,
+}
+
+/// How a resolver's answer compares to the expected addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerStatus {
+    /// Only expected addresses (of the expected address families)
+    Propagated,
+    /// Other addresses, e.g. the old server's
+    Stale,
+    NoRecords,
+    Failed,
+}
+
+impl fmt::Display for AnswerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswerStatus::Propagated => write!(f, "propagated"),
+            AnswerStatus::Stale => write!(f, "stale"),
+            AnswerStatus::NoRecords => write!(f, "no records"),
+            AnswerStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl ResolverAnswer {
+    /// Compare against the expected addresses. Only address families that
+    /// are expected count: expecting an IPv4 address ignores AAAA records.
+    pub fn status(&self, expected: &[IpAddr]) -> AnswerStatus {
+        if self.error.is_some() {
+            return AnswerStatus::Failed;
+        }
+        let relevant: Vec<IpAddr> = self
+            .records
+            .iter()
+            .map(|r| r.ip)
+            .filter(|ip| expected.iter().any(|e| e.is_ipv4() == ip.is_ipv4()))
+            .collect();
+        if relevant.is_empty() {
+            AnswerStatus::NoRecords
+        } else if relevant.iter().all(|ip| expected.contains(ip)) {
+            AnswerStatus::Propagated
+        } else {
+            AnswerStatus::Stale
+        }
+    }
+
+    /// Lowest TTL of the records, i.e. when this resolver asks again at the latest
+    pub fn min_ttl(&self) -> Option<u32> {
+        self.records.iter().map(|r| r.ttl).min()
+    }
+}
+
+/// Answers of all resolvers for one domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropagationReport {
+    pub domain: String,
+    pub expected: Vec<IpAddr>,
+    pub answers: Vec<ResolverAnswer>,
+}
+
+impl PropagationReport {
+    /// Resolvers that answered (with or without records)
+    pub fn responding(&self) -> usize {
+        self.answers.iter().filter(|a| a.error.is_none()).count()
+    }
+
+    /// Resolvers that return only the expected addresses
+    pub fn propagated(&self) -> usize {
+        self.answers
+            .iter()
+            .filter(|a| a.status(&self.expected) == AnswerStatus::Propagated)
+            .count()
+    }
+
+    /// Share of responding resolvers that propagated, 0 when none responded
+    pub fn percentage(&self) -> u8 {
+        match self.responding() {
+            0 => 0,
+            responding => (self.propagated() * 100 / responding) as u8,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.percentage() == 100
+    }
+}
+
+/// Ask every resolver, each with its own timeout
+pub async fn check(
+    lookup: &dyn DnsLookup,
+    domain: &str,
+    resolvers: &[IpAddr],
+    expected: &[IpAddr],
+    timeout: Duration,
+) -> PropagationReport {
+    let target = Target {
+        domain: domain.to_string(),
+        expected: expected.to_vec(),
+        resolvers: resolvers.to_vec(),
+    };
+    let mut reports = check_many(lookup, &[target], timeout, &Limits::default(), |_| {}).await;
+    reports.remove(0)
+}
+
+/// One domain of a bulk check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub domain: String,
+    pub expected: Vec<IpAddr>,
+    pub resolvers: Vec<IpAddr>,
+}
+
+/// Check many domains without flooding any resolver: the queries are
+/// grouped by resolver and run within `limits` (see [`crate::throttle`]).
+/// `on_progress` counts queries, not domains. The reports keep the order
+/// of `targets`.
+pub async fn check_many(
+    lookup: &dyn DnsLookup,
+    targets: &[Target],
+    timeout: Duration,
+    limits: &Limits,
+    on_progress: impl FnMut(&Progress),
+) -> Vec<PropagationReport> {
+    let queries: Vec<(&str, IpAddr)> = targets
+        .iter()
+        .flat_map(|t| t.resolvers.iter().map(|r| (t.domain.as_str(), *r)))
+        .collect();
+    let mut answers = throttle::run(
+        &queries,
+        |(_, resolver)| resolver.to_string(),
+        limits,
+        |(domain, resolver)| async move {
+            let (records, error) =
+                match tokio::time::timeout(timeout, lookup.lookup(*resolver, domain)).await {
+                    Ok(Ok(records)) => (records, None),
+                    Ok(Err(e)) => (Vec::new(), Some(e)),
+                    Err(_) => (Vec::new(), Some(format!("no answer within {:?}", timeout))),
+                };
+            ResolverAnswer {
+                resolver: *resolver,
+                records,
+                error,
+            }
+        },
+        on_progress,
+    )
+    .await
+    .into_iter();
+
+    targets
+        .iter()
+        .map(|t| PropagationReport {
+            domain: t.domain.clone(),
+            expected: t.expected.clone(),
+            answers: answers.by_ref().take(t.resolvers.len()).collect(),
+        })
+        .collect()
+}
+
+/// [`DEFAULT_RESOLVERS`] as addresses
+pub fn default_resolvers() -> Vec<IpAddr> {
+    DEFAULT_RESOLVERS
+        .iter()
+        .filter_map(|ip| ip.parse().ok())
+        .collect()
+}
+
+/// A propagation the monitor keeps checking until it completes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedPropagation {
+    pub domain: String,
+    pub expected: Vec<IpAddr>,
+    pub resolvers: Vec<IpAddr>,
+    /// RFC 3339, UTC
+    pub started_at: String,
+    /// RFC 3339, UTC; the monitor gives up after this
+    pub expires_at: String,
+}
+
+impl TrackedPropagation {
+    /// Whether the monitor should give up at `now` (unparseable expiries
+    /// count as over)
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        DateTime::parse_from_rfc3339(&self.expires_at)
+            .ok()
+            .is_none_or(|expires| expires.with_timezone(&Utc) <= now)
+    }
+}
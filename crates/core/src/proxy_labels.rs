@@ -0,0 +1,253 @@
+//! Domains from reverse-proxy container labels
+//!
+//! Traefik, caddy-docker-proxy and nginx-proxy read the hostnames a
+//! container serves from its labels, so they already hold the
+//! domain → container mapping. `docker sync` parses them per scheme and
+//! links the matching domains; a hostname claimed by more than one
+//! container is reported as a conflict instead of linked.
+
+use crate::Domain;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Container labels, by key
+pub type Labels = BTreeMap<String, String>;
+
+/// Label scheme a hostname was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProxyScheme {
+    Traefik,
+    Caddy,
+    NginxProxy,
+}
+
+impl fmt::Display for ProxyScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyScheme::Traefik => write!(f, "traefik"),
+            ProxyScheme::Caddy => write!(f, "caddy"),
+            ProxyScheme::NginxProxy => write!(f, "nginx-proxy"),
+        }
+    }
+}
+
+/// A hostname a container serves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHost {
+    pub hostname: String,
+    pub scheme: ProxyScheme,
+}
+
+/// Hostnames from Traefik router rules: `Host(`a`, `b`)` in
+/// `traefik.http.routers.<name>.rule` (v2/v3) and `Host:a,b` in
+/// `traefik.frontend.rule` (v1). Nothing when `traefik.enable=false`.
+pub fn traefik_hosts(labels: &Labels) -> Vec<String> {
+    if labels
+        .get("traefik.enable")
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("false"))
+    {
+        return Vec::new();
+    }
+
+    let mut hosts = Vec::new();
+    for (key, rule) in labels {
+        let router_rule = key
+            .strip_prefix("traefik.http.routers.")
+            .is_some_and(|rest| rest.ends_with(".rule"));
+        if router_rule {
+            hosts.extend(host_matchers(rule));
+        } else if key == "traefik.frontend.rule" {
+            for matcher in rule.split(';') {
+                if let Some(list) = matcher.trim().strip_prefix("Host:") {
+                    hosts.extend(list.split(',').filter_map(normalize));
+                }
+            }
+        }
+    }
+    dedup(hosts)
+}
+
+/// Arguments of every `Host(...)` matcher in a v2/v3 rule
+fn host_matchers(rule: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    let mut rest = rule;
+    while let Some(start) = rest.find("Host(") {
+        // HostRegexp( and HostSNI( don't match: "Host(" needs the paren
+        let preceded_by_word = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric());
+        let args = &rest[start + "Host(".len()..];
+        let Some(end) = args.find(')') else { break };
+        if !preceded_by_word {
+            hosts.extend(
+                args[..end]
+                    .split(',')
+                    .map(|arg| arg.trim().trim_matches(['`', '"', '\'']))
+                    .filter_map(normalize),
+            );
+        }
+        rest = &args[end..];
+    }
+    hosts
+}
+
+/// Site addresses of caddy-docker-proxy: the `caddy` label, or `caddy_0`,
+/// `caddy_1`... for several sites. Addresses may carry a scheme or port;
+/// port-only sites (`:80`) and placeholders are skipped.
+pub fn caddy_hosts(labels: &Labels) -> Vec<String> {
+    let hosts = labels
+        .iter()
+        .filter(|(key, _)| {
+            key.as_str() == "caddy"
+                || key
+                    .strip_prefix("caddy_")
+                    .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .flat_map(|(_, sites)| sites.split([',', ' ']))
+        .filter(|site| !site.contains('{'))
+        .filter_map(|site| {
+            let site = site.trim();
+            let site = site
+                .strip_prefix("https://")
+                .or_else(|| site.strip_prefix("http://"))
+                .unwrap_or(site);
+            let host = site.split('/').next()?;
+            normalize(host.split(':').next()?)
+        })
+        .collect();
+    dedup(hosts)
+}
+
+/// `VIRTUAL_HOST` of nginx-proxy (as label, in any case), comma separated;
+/// regular expressions (`~^app\..*`) are skipped
+pub fn nginx_proxy_hosts(labels: &Labels) -> Vec<String> {
+    let hosts = labels
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("virtual_host"))
+        .flat_map(|(_, hosts)| hosts.split(','))
+        .filter(|host| !host.trim().starts_with('~'))
+        .filter_map(normalize)
+        .collect();
+    dedup(hosts)
+}
+
+/// Hostnames of all schemes; one found by several is reported once, for
+/// the first scheme
+pub fn proxy_hosts(labels: &Labels) -> Vec<ProxyHost> {
+    let mut seen = BTreeSet::new();
+    let schemes = [
+        (ProxyScheme::Traefik, traefik_hosts(labels)),
+        (ProxyScheme::Caddy, caddy_hosts(labels)),
+        (ProxyScheme::NginxProxy, nginx_proxy_hosts(labels)),
+    ];
+    schemes
+        .into_iter()
+        .flat_map(|(scheme, hosts)| {
+            hosts
+                .into_iter()
+                .map(move |hostname| ProxyHost { hostname, scheme })
+        })
+        .filter(|host| seen.insert(host.hostname.clone()))
+        .collect()
+}
+
+/// Lowercase hostname without trailing dot; `None` for anything that isn't one
+fn normalize(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    let valid = host.contains('.')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '*'));
+    valid.then_some(host)
+}
+
+fn dedup(hosts: Vec<String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    hosts
+        .into_iter()
+        .filter(|h| seen.insert(h.clone()))
+        .collect()
+}
+
+/// A domain to point at a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainLink {
+    pub hostname: String,
+    pub container: String,
+    pub scheme: ProxyScheme,
+    /// The existing domain; `None` when it's created (`--create-domains`)
+    pub domain_id: Option<String>,
+    /// Container the domain was linked to before
+    pub previous: Option<String>,
+}
+
+/// What `docker sync` does with the hostnames it found
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkPlan {
+    pub links: Vec<DomainLink>,
+    /// Hostnames already linked as found
+    pub unchanged: Vec<String>,
+    /// Hostnames claimed by several containers, with their names; not linked
+    pub conflicts: Vec<(String, Vec<String>)>,
+    /// Hostnames without a domain entry, when domains aren't created
+    pub unknown: Vec<String>,
+}
+
+/// Plan the links for the hostnames found per container. `server_id` is the
+/// server the Docker host runs on, if known; linked domains get it too.
+pub fn plan_links(
+    containers: &BTreeMap<String, Vec<ProxyHost>>,
+    domains: &[Domain],
+    server_id: Option<&str>,
+    create: bool,
+) -> LinkPlan {
+    let mut claims: BTreeMap<&str, Vec<(&str, ProxyScheme)>> = BTreeMap::new();
+    for (container, hosts) in containers {
+        for host in hosts {
+            claims
+                .entry(&host.hostname)
+                .or_default()
+                .push((container, host.scheme));
+        }
+    }
+
+    let mut plan = LinkPlan::default();
+    for (hostname, claimants) in claims {
+        if claimants.len() > 1 {
+            let names = claimants.iter().map(|(c, _)| c.to_string()).collect();
+            plan.conflicts.push((hostname.to_string(), names));
+            continue;
+        }
+        let (container, scheme) = claimants[0];
+        let domain = domains.iter().find(|d| {
+            d.domain
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(hostname)
+        });
+        match domain {
+            Some(domain)
+                if domain.container_id.as_deref() == Some(container)
+                    && (server_id.is_none() || domain.server_id.as_deref() == server_id) =>
+            {
+                plan.unchanged.push(hostname.to_string());
+            }
+            Some(domain) => plan.links.push(DomainLink {
+                hostname: hostname.to_string(),
+                container: container.to_string(),
+                scheme,
+                domain_id: Some(domain.id.clone()),
+                previous: domain.container_id.clone(),
+            }),
+            None if create => plan.links.push(DomainLink {
+                hostname: hostname.to_string(),
+                container: container.to_string(),
+                scheme,
+                domain_id: None,
+                previous: None,
+            }),
+            None => plan.unknown.push(hostname.to_string()),
+        }
+    }
+    plan
+}
@@ -0,0 +1,222 @@
+//! Soft resource quotas per server
+//!
+//! A server can cap how many containers run on it (`max_containers`) and how
+//! much memory their limits may add up to (`max_memory_mb_allocated`). The
+//! allocation is computed from the containers `docker sync` recorded; a
+//! container without a memory limit counts as 0 MB and is reported as
+//! unlimited. Quotas are soft: actions that would exceed one warn, and only
+//! refuse with `--enforce-quotas`.
+
+use crate::{Container, ContainerStatus, Server};
+use std::fmt;
+
+/// Share of a quota from which a server is reported in `pctrl status`
+pub const NEAR_QUOTA: f64 = 0.9;
+
+/// Exit code when `--enforce-quotas` refuses an action
+pub const EXIT_QUOTA_EXCEEDED: i32 = 5;
+
+/// What the running containers of a server take up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Allocation {
+    pub containers: u64,
+    /// Sum of the memory limits, in MB
+    pub memory_mb: u64,
+    /// Containers without a memory limit (counted as 0 MB)
+    pub unlimited: u64,
+}
+
+impl Allocation {
+    pub fn plus(self, other: Allocation) -> Allocation {
+        Allocation {
+            containers: self.containers + other.containers,
+            memory_mb: self.memory_mb + other.memory_mb,
+            unlimited: self.unlimited + other.unlimited,
+        }
+    }
+}
+
+/// Whether a container takes up its server's resources
+pub fn is_running(container: &Container) -> bool {
+    matches!(
+        container.status,
+        ContainerStatus::Running | ContainerStatus::Restarting | ContainerStatus::Paused
+    )
+}
+
+/// Allocation of `containers`, whatever their state; filter with
+/// [`is_running`] for the current allocation of a server
+pub fn allocation<'a>(containers: impl IntoIterator<Item = &'a Container>) -> Allocation {
+    containers
+        .into_iter()
+        .fold(Allocation::default(), |acc, container| {
+            acc.plus(Allocation {
+                containers: 1,
+                memory_mb: container.memory_limit_mb.unwrap_or(0),
+                unlimited: u64::from(container.memory_limit_mb.is_none()),
+            })
+        })
+}
+
+/// A quota of a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    Containers,
+    Memory,
+}
+
+impl fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaKind::Containers => write!(f, "containers"),
+            QuotaKind::Memory => write!(f, "memory"),
+        }
+    }
+}
+
+/// How much of a quota is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub kind: QuotaKind,
+    /// Containers, or MB
+    pub used: u64,
+    pub max: u64,
+}
+
+impl QuotaUsage {
+    pub fn ratio(&self) -> f64 {
+        if self.max == 0 {
+            return if self.used == 0 { 0.0 } else { f64::INFINITY };
+        }
+        self.used as f64 / self.max as f64
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.used > self.max
+    }
+
+    /// At [`NEAR_QUOTA`] or above
+    pub fn near(&self) -> bool {
+        self.ratio() >= NEAR_QUOTA
+    }
+}
+
+impl fmt::Display for QuotaUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            QuotaKind::Containers => write!(f, "{}/{} containers", self.used, self.max),
+            QuotaKind::Memory => write!(
+                f,
+                "{}/{} GB allocated",
+                gigabytes(self.used),
+                gigabytes(self.max)
+            ),
+        }
+    }
+}
+
+/// The server's quotas with `allocation` applied; empty without quotas
+pub fn usage(server: &Server, allocation: &Allocation) -> Vec<QuotaUsage> {
+    let mut usage = Vec::new();
+    if let Some(max) = server.max_containers {
+        usage.push(QuotaUsage {
+            kind: QuotaKind::Containers,
+            used: allocation.containers,
+            max: u64::from(max),
+        });
+    }
+    if let Some(max) = server.max_memory_mb_allocated {
+        usage.push(QuotaUsage {
+            kind: QuotaKind::Memory,
+            used: allocation.memory_mb,
+            max,
+        });
+    }
+    usage
+}
+
+/// e.g. "7/10 containers, 3.2/4 GB allocated (2 without memory limit)";
+/// values without a quota are shown alone ("7 containers")
+pub fn describe(server: &Server, allocation: &Allocation) -> String {
+    let containers = match server.max_containers {
+        Some(max) => format!("{}/{} containers", allocation.containers, max),
+        None => crate::humanize::count(allocation.containers, "container", "containers"),
+    };
+    let memory = match server.max_memory_mb_allocated {
+        Some(max) => format!(
+            "{}/{} GB allocated",
+            gigabytes(allocation.memory_mb),
+            gigabytes(max)
+        ),
+        None => format!("{} GB allocated", gigabytes(allocation.memory_mb)),
+    };
+    let mut text = format!("{}, {}", containers, memory);
+    if allocation.unlimited > 0 {
+        text.push_str(&format!(" ({} without memory limit)", allocation.unlimited));
+    }
+    text
+}
+
+/// MB as GB with at most one decimal: 4096 → "4", 3277 → "3.2"
+pub fn gigabytes(mb: u64) -> String {
+    let gb = format!("{:.1}", mb as f64 / 1024.0);
+    gb.strip_suffix(".0").map(String::from).unwrap_or(gb)
+}
+
+/// A quota an action would exceed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaBreach {
+    pub server_id: String,
+    pub server_name: String,
+    /// The quota with the action applied
+    pub usage: QuotaUsage,
+}
+
+impl fmt::Display for QuotaBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} would have {}", self.server_name, self.usage)
+    }
+}
+
+/// Quotas exceeded once `starting` runs in addition to what runs now.
+/// `containers` are the synced containers of all servers; the ones in
+/// `starting` that already run don't count twice. Quotas already exceeded
+/// before are reported too, as long as the action adds to them.
+pub fn check_start(
+    servers: &[Server],
+    containers: &[Container],
+    starting: &[&Container],
+) -> Vec<QuotaBreach> {
+    let mut breaches = Vec::new();
+    for server in servers {
+        let adding: Vec<&Container> = starting
+            .iter()
+            .copied()
+            .filter(|c| c.server_id == server.id && !is_running(c))
+            .collect();
+        if adding.is_empty() {
+            continue;
+        }
+        let current = allocation(
+            containers
+                .iter()
+                .filter(|c| c.server_id == server.id && is_running(c)),
+        );
+        let added = allocation(adding);
+        let after = current.plus(added);
+        for quota in usage(server, &after) {
+            let grows = match quota.kind {
+                QuotaKind::Containers => added.containers > 0,
+                QuotaKind::Memory => added.memory_mb > 0,
+            };
+            if quota.exceeded() && grows {
+                breaches.push(QuotaBreach {
+                    server_id: server.id.clone(),
+                    server_name: server.name.clone(),
+                    usage: quota,
+                });
+            }
+        }
+    }
+    breaches
+}
@@ -0,0 +1,304 @@
+//! When dangerous scripts may run (`pctrl script edit --run-window`)
+//!
+//! A window is one or more `;`-separated clauses of weekdays and wall-clock
+//! time ranges, e.g. `Mon-Thu 08:00-18:00; Fri 08:00-12:00`, optionally
+//! followed by a timezone (`Europe/Berlin`, `UTC`); without one the local
+//! timezone applies. Leaving out the days means every day, leaving out the
+//! ranges the whole day. Ranges are wall-clock times: on a DST change a
+//! window opens when the clock shows its start, and a start the clock skips
+//! opens right after the jump.
+
+use chrono::offset::LocalResult;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Minutes in a day; the end of a range that runs to midnight
+const DAY_END: u32 = 24 * 60;
+
+/// A parsed run window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunWindow {
+    clauses: Vec<Clause>,
+    timezone: Option<Tz>,
+}
+
+/// Days (bit 0 is Monday) and the minute ranges open on them
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    days: u8,
+    /// `[start, end)` in minutes after midnight, by start
+    ranges: Vec<(u32, u32)>,
+}
+
+impl RunWindow {
+    /// The explicit timezone, if the expression names one
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone
+    }
+
+    /// Whether `now` is inside the window; `local` is the timezone used
+    /// when the window doesn't name one
+    pub fn is_open<Z: TimeZone>(&self, now: DateTime<Utc>, local: &Z) -> bool {
+        match self.timezone {
+            Some(tz) => self.is_open_in(now, &tz),
+            None => self.is_open_in(now, local),
+        }
+    }
+
+    /// The first instant at or after `now` inside the window (`now` itself
+    /// if it's open); `None` if none opens within two weeks
+    pub fn next_open<Z: TimeZone>(&self, now: DateTime<Utc>, local: &Z) -> Option<DateTime<Utc>> {
+        match self.timezone {
+            Some(tz) => self.next_open_in(now, &tz),
+            None => self.next_open_in(now, local),
+        }
+    }
+
+    /// `at` as the window's wall-clock time, e.g. "Mon 2026-10-19 08:00 CEST"
+    pub fn local_text<Z: TimeZone>(&self, at: DateTime<Utc>, local: &Z) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        const FORMAT: &str = "%a %Y-%m-%d %H:%M %Z";
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).format(FORMAT).to_string(),
+            None => at.with_timezone(local).format(FORMAT).to_string(),
+        }
+    }
+
+    fn is_open_in<Z: TimeZone>(&self, now: DateTime<Utc>, zone: &Z) -> bool {
+        let local = now.with_timezone(zone);
+        let minute = local.hour() * 60 + local.minute();
+        self.ranges_on(local.weekday().num_days_from_monday())
+            .any(|(start, end)| start <= minute && minute < end)
+    }
+
+    fn next_open_in<Z: TimeZone>(&self, now: DateTime<Utc>, zone: &Z) -> Option<DateTime<Utc>> {
+        if self.is_open_in(now, zone) {
+            return Some(now);
+        }
+        let today = now.with_timezone(zone).date_naive();
+        // Every weekday comes up twice within two weeks, so a start skipped
+        // by a DST jump still finds the one a week later
+        (0..=14)
+            .filter_map(|offset| today.checked_add_signed(Duration::days(offset)))
+            .flat_map(|date| {
+                self.ranges_on(date.weekday().num_days_from_monday())
+                    .flat_map(move |range| starts(zone, date, range))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|start| *start >= now)
+            .min()
+    }
+
+    /// Ranges open on a weekday (0 is Monday)
+    fn ranges_on(&self, weekday: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.clauses
+            .iter()
+            .filter(move |clause| clause.days & (1 << weekday) != 0)
+            .flat_map(|clause| clause.ranges.iter().copied())
+    }
+}
+
+/// The instants a range opens on `date`: one normally, two when the clock
+/// shows its start twice, and the first instant after the jump when the
+/// start is skipped (none if the whole range is skipped)
+fn starts<Z: TimeZone>(zone: &Z, date: NaiveDate, (start, end): (u32, u32)) -> Vec<DateTime<Utc>> {
+    for minute in start..end {
+        let Some(time) = NaiveTime::from_hms_opt(minute / 60, minute % 60, 0) else {
+            break;
+        };
+        match zone.from_local_datetime(&date.and_time(time)) {
+            LocalResult::Single(t) => return vec![t.with_timezone(&Utc)],
+            LocalResult::Ambiguous(earliest, latest) => {
+                return vec![earliest.with_timezone(&Utc), latest.with_timezone(&Utc)]
+            }
+            LocalResult::None => continue,
+        }
+    }
+    Vec::new()
+}
+
+impl std::str::FromStr for RunWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err("Empty run window".to_string());
+        }
+        let mut clauses = Vec::new();
+        let mut timezone = None;
+        for text in s.split(';').map(str::trim) {
+            if text.is_empty() {
+                return Err(format!("Empty clause in run window '{}'", s.trim()));
+            }
+            let mut days = None;
+            let mut ranges = None;
+            for token in text.split_whitespace() {
+                if token.starts_with(|c: char| c.is_ascii_digit()) {
+                    if ranges.is_some() {
+                        return Err(format!(
+                            "Give the time ranges of '{}' once, separated by commas",
+                            text
+                        ));
+                    }
+                    ranges = Some(parse_ranges(token)?);
+                } else if let Ok(tz) = token.parse::<Tz>() {
+                    if timezone.replace(tz).is_some() {
+                        return Err("A run window can name only one timezone".to_string());
+                    }
+                } else {
+                    if days.is_some() {
+                        return Err(format!(
+                            "Give the days of '{}' once, separated by commas",
+                            text
+                        ));
+                    }
+                    days = Some(parse_days(token)?);
+                }
+            }
+            if days.is_none() && ranges.is_none() {
+                return Err(format!("'{}' gives no days or times", text));
+            }
+            clauses.push(Clause {
+                days: days.unwrap_or(0b111_1111),
+                ranges: ranges.unwrap_or_else(|| vec![(0, DAY_END)]),
+            });
+        }
+        Ok(Self { clauses, timezone })
+    }
+}
+
+/// `Mon`, `Mon-Thu` (wrapping around the week, e.g. `Fri-Mon`), or several
+/// of those separated by commas
+fn parse_days(token: &str) -> Result<u8, String> {
+    let day = |name: &str| {
+        DAYS.iter()
+            .position(|day| day.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown day or timezone '{}' (days are {})",
+                    name,
+                    DAYS.join(", ")
+                )
+            })
+    };
+    let mut days = 0u8;
+    for part in token.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                let mut current = first;
+                loop {
+                    days |= 1 << current;
+                    if current == last {
+                        break;
+                    }
+                    current = (current + 1) % 7;
+                }
+            }
+            None => days |= 1 << day(part)?,
+        }
+    }
+    Ok(days)
+}
+
+/// `HH:MM-HH:MM`, several separated by commas; `24:00` ends at midnight
+fn parse_ranges(token: &str) -> Result<Vec<(u32, u32)>, String> {
+    let mut ranges = Vec::new();
+    for part in token.split(',') {
+        let (start, end) = part
+            .split_once('-')
+            .ok_or_else(|| format!("Expected a time range like 08:00-18:00, got '{}'", part))?;
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        if start == DAY_END {
+            return Err(format!("'{}' can't start at 24:00", part));
+        }
+        if end <= start {
+            return Err(format!(
+                "'{}' ends before it starts; split windows past midnight, e.g. \
+                 'Fri 22:00-24:00; Sat 00:00-02:00'",
+                part
+            ));
+        }
+        ranges.push((start, end));
+    }
+    ranges.sort_unstable();
+    Ok(ranges)
+}
+
+/// Minutes after midnight of `HH:MM`
+fn parse_time(text: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid time '{}', expected HH:MM", text);
+    let (hours, minutes) = text.split_once(':').ok_or_else(invalid)?;
+    if minutes.len() != 2 {
+        return Err(invalid());
+    }
+    let (hours, minutes): (u32, u32) = (
+        hours.parse().map_err(|_| invalid())?,
+        minutes.parse().map_err(|_| invalid())?,
+    );
+    match (hours, minutes) {
+        (24, 0) => Ok(DAY_END),
+        (0..=23, 0..=59) => Ok(hours * 60 + minutes),
+        _ => Err(invalid()),
+    }
+}
+
+impl fmt::Display for RunWindow {
+    /// Canonical form: days as runs (`Mon-Thu`, `Sat,Sun`), whole days
+    /// without times, the timezone last
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clauses: Vec<String> = self
+            .clauses
+            .iter()
+            .map(|clause| {
+                let mut parts = vec![days_text(clause.days)];
+                if clause.ranges != [(0, DAY_END)] {
+                    let ranges: Vec<String> = clause
+                        .ranges
+                        .iter()
+                        .map(|(start, end)| format!("{}-{}", time_text(*start), time_text(*end)))
+                        .collect();
+                    parts.push(ranges.join(","));
+                }
+                parts.join(" ")
+            })
+            .collect();
+        write!(f, "{}", clauses.join("; "))?;
+        if let Some(tz) = self.timezone {
+            write!(f, " {}", tz.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs of three or more days as `Mon-Wed`, shorter ones listed
+fn days_text(days: u8) -> String {
+    let mut parts = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        if days & (1 << day) == 0 {
+            day += 1;
+            continue;
+        }
+        let first = day;
+        while day + 1 < 7 && days & (1 << (day + 1)) != 0 {
+            day += 1;
+        }
+        match day - first {
+            0 => parts.push(DAYS[first].to_string()),
+            1 => parts.extend([DAYS[first].to_string(), DAYS[day].to_string()]),
+            _ => parts.push(format!("{}-{}", DAYS[first], DAYS[day])),
+        }
+        day += 1;
+    }
+    parts.join(",")
+}
+
+fn time_text(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
@@ -0,0 +1,73 @@
+//! Multi-line script bodies
+//!
+//! Script commands are stored verbatim and executed by piping them to
+//! `sh -s` on stdin, so heredocs and quotes behave exactly like in a local
+//! script file instead of going through argument quoting.
+
+/// Prefix of the instruction lines in the editor template
+pub const TEMPLATE_PREFIX: &str = "# pctrl:";
+
+/// Shell and arguments that read the script from stdin
+pub const STDIN_SHELL: (&str, &[&str]) = ("sh", &["-s"]);
+
+/// Initial editor contents for a new or existing script
+pub fn editor_template(name: &str, body: Option<&str>) -> String {
+    let mut text = format!(
+        "{p} Script '{name}'\n\
+         {p} Lines starting with '{p}' are removed; everything else is stored as-is.\n\
+         {p} The script runs via `sh -s`, so heredocs, quotes and $VARS work as in a file.\n\
+         {p} Save an empty script to abort.\n",
+        p = TEMPLATE_PREFIX,
+        name = name,
+    );
+    if let Some(body) = body {
+        text.push_str(body);
+        text.push('\n');
+    }
+    text
+}
+
+/// Extract the script body from the edited template.
+///
+/// Only the leading instruction block is stripped, and only trailing line
+/// breaks are trimmed. Returns `None` when nothing is left.
+pub fn parse_editor_output(text: &str) -> Option<String> {
+    let mut rest = text;
+    while rest.starts_with(TEMPLATE_PREFIX) {
+        rest = match rest.find('\n') {
+            Some(i) => &rest[i + 1..],
+            None => "",
+        };
+    }
+
+    let body = rest.trim_end_matches(['\n', '\r']);
+    if body.trim().is_empty() {
+        None
+    } else {
+        Some(body.to_string())
+    }
+}
+
+/// Whether a command spans more than one line
+pub fn is_multiline(body: &str) -> bool {
+    body.contains('\n')
+}
+
+/// Body with right-aligned line numbers, for `script show`
+pub fn numbered_lines(body: &str) -> Vec<String> {
+    let width = body.lines().count().to_string().len();
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} │ {}", i + 1, line, width = width))
+        .collect()
+}
+
+/// Bytes written to the shell's stdin (always newline-terminated so the
+/// last line, e.g. a heredoc terminator, is complete)
+pub fn stdin_payload(body: &str) -> String {
+    let mut payload = body.to_string();
+    if !payload.ends_with('\n') {
+        payload.push('\n');
+    }
+    payload
+}
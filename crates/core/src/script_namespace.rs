@@ -0,0 +1,198 @@
+//! Script namespaces (`backups/postgres-daily`)
+//!
+//! A script name may have `/`-separated segments; all but the last one form
+//! its namespace. `script list` groups scripts into a tree by namespace and
+//! filters by one (`script list backups/`), and a script can be named by a
+//! suffix of whole segments (`postgres-daily`) as long as only one script
+//! ends with it. Names without a `/` are top-level scripts, as before.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Separator of namespace segments
+pub const SEPARATOR: char = '/';
+
+/// Check a new script name: segments may not be empty (a leading, trailing
+/// or double slash) or start or end with blanks
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("The script name is empty".to_string());
+    }
+    if !name.contains(SEPARATOR) {
+        return Ok(());
+    }
+    if name.starts_with(SEPARATOR) || name.ends_with(SEPARATOR) {
+        return Err(format!(
+            "'{}' can't start or end with '{}'",
+            name, SEPARATOR
+        ));
+    }
+    for segment in name.split(SEPARATOR) {
+        if segment.is_empty() {
+            return Err(format!("'{}' has an empty namespace segment", name));
+        }
+        if segment.trim() != segment {
+            return Err(format!("'{}' has blanks around a '{}'", name, SEPARATOR));
+        }
+    }
+    Ok(())
+}
+
+/// Namespace of a script name, `None` for a top-level script
+pub fn namespace(name: &str) -> Option<&str> {
+    name.rsplit_once(SEPARATOR).map(|(namespace, _)| namespace)
+}
+
+/// Last segment of a script name
+pub fn leaf(name: &str) -> &str {
+    name.rsplit_once(SEPARATOR).map_or(name, |(_, leaf)| leaf)
+}
+
+/// A namespace filter as typed (`backups/` or `backups`) without its
+/// trailing separators; `None` when nothing is left
+pub fn normalize_prefix(prefix: &str) -> Option<&str> {
+    Some(prefix.trim().trim_end_matches(SEPARATOR)).filter(|p| !p.is_empty())
+}
+
+/// Whether a script is in namespace `prefix` or one below it, ignoring case
+pub fn in_namespace(name: &str, prefix: &str) -> bool {
+    let Some(prefix) = normalize_prefix(prefix) else {
+        return true;
+    };
+    namespace(name).is_some_and(|namespace| {
+        namespace.eq_ignore_ascii_case(prefix)
+            || (namespace.len() > prefix.len()
+                && namespace.is_char_boundary(prefix.len())
+                && namespace[..prefix.len()].eq_ignore_ascii_case(prefix)
+                && namespace[prefix.len()..].starts_with(SEPARATOR))
+    })
+}
+
+/// Names `input` ends, in whole segments and ignoring case: `postgres-daily`
+/// and `backups/postgres-daily` both match `backups/postgres-daily`, `daily`
+/// doesn't
+pub fn suffix_matches<'a, I>(names: I, input: &str) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let input = input.trim();
+    if input.is_empty() {
+        return Vec::new();
+    }
+    names
+        .into_iter()
+        .filter(|name| {
+            name.eq_ignore_ascii_case(input)
+                || (name.len() > input.len()
+                    && name.is_char_boundary(name.len() - input.len())
+                    && name[name.len() - input.len()..].eq_ignore_ascii_case(input)
+                    && name[..name.len() - input.len()].ends_with(SEPARATOR))
+        })
+        .collect()
+}
+
+/// A row of the namespace tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeRow<'a> {
+    /// A namespace heading with the number of scripts in it, nested ones
+    /// included
+    Namespace {
+        depth: usize,
+        /// Full namespace, e.g. "backups/db"
+        path: String,
+        /// Last segment, e.g. "db"
+        label: &'a str,
+        scripts: usize,
+        collapsed: bool,
+    },
+    /// A script, by its index in the names given to [`tree`]
+    Script {
+        depth: usize,
+        index: usize,
+        label: &'a str,
+    },
+}
+
+impl TreeRow<'_> {
+    pub fn depth(&self) -> usize {
+        match self {
+            TreeRow::Namespace { depth, .. } | TreeRow::Script { depth, .. } => *depth,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Node<'a> {
+    /// Namespaces by lowercase segment, with the segment as first seen
+    namespaces: BTreeMap<String, (&'a str, Node<'a>)>,
+    scripts: Vec<(usize, &'a str)>,
+}
+
+impl Node<'_> {
+    fn count(&self) -> usize {
+        self.scripts.len()
+            + self
+                .namespaces
+                .values()
+                .map(|(_, node)| node.count())
+                .sum::<usize>()
+    }
+}
+
+/// Rows of the tree of `names`: at each level namespaces first, then
+/// scripts, both by name ignoring case. Namespaces whose path is in
+/// `collapsed` are listed without their contents. Segments differing only
+/// in case share a namespace.
+pub fn tree<'a>(names: &[&'a str], collapsed: &BTreeSet<String>) -> Vec<TreeRow<'a>> {
+    let mut root = Node::default();
+    for (index, name) in names.iter().enumerate() {
+        let mut node = &mut root;
+        if let Some(namespace) = namespace(name) {
+            for segment in namespace.split(SEPARATOR) {
+                node = &mut node
+                    .namespaces
+                    .entry(segment.to_lowercase())
+                    .or_insert_with(|| (segment, Node::default()))
+                    .1;
+            }
+        }
+        node.scripts.push((index, leaf(name)));
+    }
+
+    let mut rows = Vec::new();
+    push_rows(&mut rows, &root, "", 0, collapsed);
+    rows
+}
+
+fn push_rows<'a>(
+    rows: &mut Vec<TreeRow<'a>>,
+    node: &Node<'a>,
+    parent: &str,
+    depth: usize,
+    collapsed: &BTreeSet<String>,
+) {
+    for (label, child) in node.namespaces.values() {
+        let path = if parent.is_empty() {
+            label.to_string()
+        } else {
+            format!("{}{}{}", parent, SEPARATOR, label)
+        };
+        let is_collapsed = collapsed.contains(&path);
+        rows.push(TreeRow::Namespace {
+            depth,
+            path: path.clone(),
+            label,
+            scripts: child.count(),
+            collapsed: is_collapsed,
+        });
+        if !is_collapsed {
+            push_rows(rows, child, &path, depth + 1, collapsed);
+        }
+    }
+    let mut scripts = node.scripts.clone();
+    scripts.sort_by_key(|(_, label)| label.to_lowercase());
+    rows.extend(scripts.into_iter().map(|(index, label)| TreeRow::Script {
+        depth,
+        index,
+        label,
+    }));
+}
@@ -0,0 +1,330 @@
+//! Global search across entities and a generic entity view
+//!
+//! Search matches the query case-insensitively against each entity's name
+//! and a few detail fields. Hits are ranked by how they matched (exact
+//! name, name prefix, name substring, then detail), then by type and name,
+//! and say which field matched.
+//! [`Entity`] wraps any entity for views that open one by type and ID, e.g.
+//! a desktop deep link; [`Entity::redacted`] masks its secrets first.
+
+use crate::redact::{self, REDACTED};
+use crate::{
+    Credential, CredentialData, DatabaseCredentials, Domain, EntityType, Project, Script, Server,
+};
+use serde::{Deserialize, Serialize};
+
+/// How a hit matched, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// The name is the query
+    Exact,
+    /// The name starts with the query
+    Prefix,
+    /// The query is part of the name
+    Name,
+    /// The query is part of a detail field (host, command, notes...)
+    Detail,
+}
+
+/// An entity to search: what a result row shows plus the text to match
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub entity_type: EntityType,
+    pub id: String,
+    pub name: String,
+    /// Short description for the result row, e.g. the host of a server
+    pub detail: Option<String>,
+    /// Names of the projects the entity belongs to
+    pub projects: Vec<String>,
+    /// More text to match (not shown), each with the name of its field,
+    /// e.g. `("provider", "hetzner")`
+    pub text: Vec<(&'static str, String)>,
+}
+
+impl Candidate {
+    /// A candidate with the name and detail fields every search matches;
+    /// projects and journal text are the caller's to add
+    fn new(
+        entity_type: EntityType,
+        id: &str,
+        name: &str,
+        detail: Option<String>,
+        text: Vec<(&'static str, Option<String>)>,
+    ) -> Self {
+        Candidate {
+            entity_type,
+            id: id.to_string(),
+            name: name.to_string(),
+            detail,
+            projects: Vec::new(),
+            text: text
+                .into_iter()
+                .filter_map(|(field, value)| Some((field, value?)))
+                .collect(),
+        }
+    }
+
+    pub fn project(project: &Project) -> Self {
+        let mut text = vec![("description", project.description.clone())];
+        text.extend(project.stack.iter().map(|s| ("stack", Some(s.clone()))));
+        text.push(("notes", project.notes.clone()));
+        Self::new(
+            EntityType::Project,
+            &project.id,
+            &project.name,
+            project.description.clone(),
+            text,
+        )
+    }
+
+    pub fn server(server: &Server) -> Self {
+        Self::new(
+            EntityType::Server,
+            &server.id,
+            &server.name,
+            Some(server.host.clone()),
+            vec![
+                ("host", Some(server.host.clone())),
+                ("provider", server.provider.clone()),
+                ("location", server.location.clone()),
+                ("notes", server.notes.clone()),
+            ],
+        )
+    }
+
+    pub fn domain(domain: &Domain) -> Self {
+        Self::new(
+            EntityType::Domain,
+            &domain.id,
+            &domain.domain,
+            Some(domain.domain_type.to_string()),
+            vec![
+                ("type", Some(domain.domain_type.to_string())),
+                ("notes", domain.notes.clone()),
+            ],
+        )
+    }
+
+    pub fn database(db: &DatabaseCredentials) -> Self {
+        let detail = match &db.host {
+            Some(host) => format!("{} on {}", db.db_type, host),
+            None => db.db_type.to_string(),
+        };
+        Self::new(
+            EntityType::Database,
+            &db.id,
+            &db.name,
+            Some(detail),
+            vec![
+                ("type", Some(db.db_type.to_string())),
+                ("host", db.host.clone()),
+                ("database", db.database_name.clone()),
+                ("notes", db.notes.clone()),
+            ],
+        )
+    }
+
+    /// Secrets in the command aren't searchable
+    pub fn script(script: &Script) -> Self {
+        Self::new(
+            EntityType::Script,
+            &script.id,
+            &script.name,
+            script.description.clone(),
+            vec![
+                ("description", script.description.clone()),
+                ("command", Some(redact::redact_secrets(&script.command))),
+            ],
+        )
+    }
+
+    pub fn credential(credential: &Credential) -> Self {
+        Self::new(
+            EntityType::Credential,
+            &credential.id,
+            &credential.name,
+            Some(credential.credential_type.to_string()),
+            vec![
+                ("type", Some(credential.credential_type.to_string())),
+                ("notes", credential.notes.clone()),
+            ],
+        )
+    }
+}
+
+/// A search result row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entity_type: EntityType,
+    pub id: String,
+    pub name: String,
+    pub detail: Option<String>,
+    pub projects: Vec<String>,
+    pub matched: MatchKind,
+    /// The field that matched: `name`, `id`, or a detail field like `host`
+    pub field: String,
+}
+
+/// How `candidate` matches `query`; `None` when it doesn't
+pub fn match_kind(query: &str, candidate: &Candidate) -> Option<MatchKind> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    let name = candidate.name.to_lowercase();
+    if name == query || candidate.id.to_lowercase() == query {
+        Some(MatchKind::Exact)
+    } else if name.starts_with(&query) {
+        Some(MatchKind::Prefix)
+    } else if name.contains(&query) {
+        Some(MatchKind::Name)
+    } else if detail_field(&query, candidate).is_some() {
+        Some(MatchKind::Detail)
+    } else {
+        None
+    }
+}
+
+/// The field of `candidate` that matches `query`: `name`, `id`, a named
+/// detail field, or `detail` for the shown detail; `None` when none does
+pub fn matched_field(query: &str, candidate: &Candidate) -> Option<&'static str> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        None
+    } else if candidate.name.to_lowercase().contains(&query) {
+        Some("name")
+    } else if candidate.id.to_lowercase() == query {
+        Some("id")
+    } else {
+        detail_field(&query, candidate)
+    }
+}
+
+/// The first detail field containing the lowercase `query`
+fn detail_field(query: &str, candidate: &Candidate) -> Option<&'static str> {
+    candidate
+        .text
+        .iter()
+        .find(|(_, value)| value.to_lowercase().contains(query))
+        .map(|(field, _)| *field)
+        .or_else(|| {
+            candidate
+                .detail
+                .as_ref()
+                .filter(|d| d.to_lowercase().contains(query))
+                .map(|_| "detail")
+        })
+}
+
+/// The best `limit` hits for `query`
+pub fn rank(query: &str, candidates: Vec<Candidate>, limit: usize) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = candidates
+        .into_iter()
+        .filter_map(|c| {
+            let matched = match_kind(query, &c)?;
+            let field = matched_field(query, &c)?.to_string();
+            Some(SearchHit {
+                entity_type: c.entity_type,
+                id: c.id,
+                name: c.name,
+                detail: c.detail,
+                projects: c.projects,
+                matched,
+                field,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        (a.matched, type_order(a.entity_type), a.name.to_lowercase()).cmp(&(
+            b.matched,
+            type_order(b.entity_type),
+            b.name.to_lowercase(),
+        ))
+    });
+    hits.truncate(limit);
+    hits
+}
+
+fn type_order(entity_type: EntityType) -> u8 {
+    match entity_type {
+        EntityType::Project => 0,
+        EntityType::Server => 1,
+        EntityType::Domain => 2,
+        EntityType::Database => 3,
+        EntityType::Script => 4,
+        EntityType::Credential => 5,
+    }
+}
+
+/// Any entity, tagged with its type like [`EntityType`] serializes:
+/// `{"type": "Server", "data": {...}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Entity {
+    Project(Project),
+    Server(Server),
+    Domain(Domain),
+    Database(DatabaseCredentials),
+    Script(Script),
+    Credential(Credential),
+}
+
+impl Entity {
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            Entity::Project(_) => EntityType::Project,
+            Entity::Server(_) => EntityType::Server,
+            Entity::Domain(_) => EntityType::Domain,
+            Entity::Database(_) => EntityType::Database,
+            Entity::Script(_) => EntityType::Script,
+            Entity::Credential(_) => EntityType::Credential,
+        }
+    }
+
+    /// The entity with passwords, tokens and passphrases replaced by
+    /// [`REDACTED`], and secrets in commands, output and connection strings
+    /// masked
+    pub fn redacted(self) -> Self {
+        let mask = |value: &mut Option<String>| {
+            if value.is_some() {
+                *value = Some(REDACTED.to_string());
+            }
+        };
+        match self {
+            Entity::Database(mut db) => {
+                mask(&mut db.password);
+                db.connection_string = db.connection_string.map(|c| redact::redact_secrets(&c));
+                Entity::Database(db)
+            }
+            Entity::Script(mut script) => {
+                script.command = redact::redact_secrets(&script.command);
+                script.last_output = script.last_output.map(|o| redact::redact_secrets(&o));
+                for (key, value) in script.env.iter_mut() {
+                    if redact::is_secret_key(key) {
+                        *value = REDACTED.to_string();
+                    }
+                }
+                Entity::Script(script)
+            }
+            Entity::Credential(mut credential) => {
+                match &mut credential.data {
+                    CredentialData::SshKey { passphrase, .. } => mask(passphrase),
+                    CredentialData::SshAgent { .. } => {}
+                    CredentialData::ApiToken { token, .. } => *token = REDACTED.to_string(),
+                    CredentialData::BasicAuth { password, .. } => *password = REDACTED.to_string(),
+                    CredentialData::OAuth {
+                        access_token,
+                        refresh_token,
+                        ..
+                    } => {
+                        *access_token = REDACTED.to_string();
+                        mask(refresh_token);
+                    }
+                }
+                Entity::Credential(credential)
+            }
+            entity => entity,
+        }
+    }
+}
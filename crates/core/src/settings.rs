@@ -0,0 +1,217 @@
+//! User settings (`pctrl config`)
+//!
+//! Settings are key/value pairs stored in the database. Only known keys are
+//! accepted, and values are validated when set, so a typo never silently
+//! does nothing.
+
+use crate::hyperlink::HyperlinkMode;
+use crate::theme::{parse_color, ThemeName};
+use crate::ApprovalMode;
+
+/// TUI theme preset: dark or light
+pub const TUI_THEME: &str = "tui_theme";
+
+/// TUI accent color: ANSI name or hex
+pub const TUI_ACCENT: &str = "tui_accent";
+
+/// Seconds between automatic reloads of the TUI's data; 0 turns them off
+pub const TUI_REFRESH_SECS: &str = "tui_refresh_secs";
+
+/// URL the monitor pings after every successful cycle
+pub const MONITOR_HEARTBEAT_URL: &str = "monitor_heartbeat_url";
+
+/// Approval workflow for dangerous script changes: off, on or two-person
+pub const SCRIPT_APPROVAL: &str = "script_approval";
+
+/// Runs kept per script in `script history`
+pub const SCRIPT_RUN_HISTORY: &str = "script_run_history";
+
+/// Clickable links in CLI output: auto, always or never
+pub const HYPERLINKS: &str = "hyperlinks";
+
+/// Next-step suggestions after commands: on or off
+pub const HINTS: &str = "hints";
+
+/// Seconds a streamed remote command may stay silent; 0 waits as long as it runs
+pub const SSH_STREAM_TIMEOUT_SECS: &str = "ssh_stream_timeout_secs";
+
+/// A known setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingDef {
+    pub key: &'static str,
+    pub description: &'static str,
+    /// Value used when the setting is unset
+    pub default: Option<&'static str>,
+}
+
+/// All known settings
+pub const SETTINGS: &[SettingDef] = &[
+    SettingDef {
+        key: TUI_THEME,
+        description: "TUI color theme: dark or light",
+        default: Some("dark"),
+    },
+    SettingDef {
+        key: TUI_ACCENT,
+        description: "TUI accent color: ANSI name (e.g. magenta) or hex (e.g. #7aa2f7)",
+        default: None,
+    },
+    SettingDef {
+        key: TUI_REFRESH_SECS,
+        description: "Reload the TUI's data every N seconds; 0 turns it off",
+        default: Some("0"),
+    },
+    SettingDef {
+        key: MONITOR_HEARTBEAT_URL,
+        description: "Heartbeat URL pinged after every monitor cycle (e.g. healthchecks.io)",
+        default: None,
+    },
+    SettingDef {
+        key: SCRIPT_APPROVAL,
+        description: "Approve edits of dangerous scripts: off, on, or two-person (approver must be another OS user)",
+        default: Some("off"),
+    },
+    SettingDef {
+        key: SCRIPT_RUN_HISTORY,
+        description: "Runs kept per script for `pctrl script history`; older ones are pruned",
+        default: Some("100"),
+    },
+    SettingDef {
+        key: HYPERLINKS,
+        description: "Clickable links (OSC 8) in CLI output: auto (terminals only), always or never",
+        default: Some("auto"),
+    },
+    SettingDef {
+        key: HINTS,
+        description: "Suggest next steps after commands (dim lines, dropped by --quiet): on or off",
+        default: Some("on"),
+    },
+    SettingDef {
+        key: SSH_STREAM_TIMEOUT_SECS,
+        description: "Give up on a remote command (deploys, project exec) after N seconds without output; 0 waits as long as it runs",
+        default: Some("0"),
+    },
+];
+
+/// Definition of a known setting
+pub fn find(key: &str) -> Option<&'static SettingDef> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+/// Check that `value` is valid for `key`
+pub fn validate(key: &str, value: &str) -> Result<(), String> {
+    let Some(def) = find(key) else {
+        return Err(format!(
+            "Unknown setting: {} (known: {})",
+            key,
+            SETTINGS
+                .iter()
+                .map(|s| s.key)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    };
+
+    match def.key {
+        TUI_THEME => value.parse::<ThemeName>().map(|_| ()),
+        TUI_ACCENT => parse_color(value).map(|_| ()),
+        SCRIPT_APPROVAL => value.parse::<ApprovalMode>().map(|_| ()),
+        HYPERLINKS => value.parse::<HyperlinkMode>().map(|_| ()),
+        TUI_REFRESH_SECS | SSH_STREAM_TIMEOUT_SECS => {
+            value.parse::<u32>().map(|_| ()).map_err(|_| {
+                format!(
+                    "Invalid value: {} (expected a number of seconds, 0 for off)",
+                    value
+                )
+            })
+        }
+        SCRIPT_RUN_HISTORY => match value.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(()),
+            _ => Err(format!(
+                "Invalid value: {} (expected a number of runs, at least 1)",
+                value
+            )),
+        },
+        HINTS => match value {
+            "on" | "off" => Ok(()),
+            _ => Err(format!("Invalid value: {} (expected on or off)", value)),
+        },
+        MONITOR_HEARTBEAT_URL => {
+            if value.starts_with("http://") || value.starts_with("https://") {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Invalid URL: {} (expected http:// or https://)",
+                    value
+                ))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// What a running TUI does when a setting changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveEffect {
+    /// Colors are rebuilt
+    Theme,
+    /// The data reload timer is re-armed
+    Refresh,
+    /// Nothing in the TUI uses it; commands read it when they run
+    Commands,
+}
+
+/// How a running TUI picks up a change of `key`
+pub fn live_effect(key: &str) -> LiveEffect {
+    match key {
+        TUI_THEME | TUI_ACCENT => LiveEffect::Theme,
+        TUI_REFRESH_SECS => LiveEffect::Refresh,
+        _ => LiveEffect::Commands,
+    }
+}
+
+/// A known setting whose value differs between two reads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChange {
+    pub key: &'static str,
+    /// Value in effect before: the set one, else the default
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub effect: LiveEffect,
+}
+
+impl std::fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} -> {}",
+            self.key,
+            self.old.as_deref().unwrap_or("-"),
+            self.new.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+/// Known settings whose value in effect differs between two reads of the
+/// set ones, in [`SETTINGS`] order. Setting a value equal to the default is
+/// no change.
+pub fn changes(old: &[(String, String)], new: &[(String, String)]) -> Vec<SettingChange> {
+    let value = |set: &[(String, String)], def: &SettingDef| {
+        set.iter()
+            .find(|(key, _)| key == def.key)
+            .map(|(_, value)| value.clone())
+            .or_else(|| def.default.map(str::to_string))
+    };
+    SETTINGS
+        .iter()
+        .filter_map(|def| {
+            let (old, new) = (value(old, def), value(new, def));
+            (old != new).then(|| SettingChange {
+                key: def.key,
+                old,
+                new,
+                effect: live_effect(def.key),
+            })
+        })
+        .collect()
+}
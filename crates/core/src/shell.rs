@@ -0,0 +1,346 @@
+//! Line handling for `pctrl shell`
+//!
+//! Lines are split like a POSIX shell would, then the current project is
+//! injected into commands that take one before the normal clap definitions
+//! parse them. Explicit arguments always win over the context.
+
+/// A parsed REPL line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplLine {
+    /// Blank line or comment
+    Empty,
+    Exit,
+    Help,
+    /// `use` without arguments: print the current context
+    ShowContext,
+    /// `use <project>` sets, `use -` clears the context
+    Use(Option<String>),
+    /// Anything else: CLI arguments without the program name
+    Command(Vec<String>),
+}
+
+/// Split a line into arguments with shell quoting rules
+pub fn split_line(line: &str) -> Result<Vec<String>, String> {
+    shlex::split(line).ok_or_else(|| "Unterminated quote or trailing backslash".to_string())
+}
+
+/// Parse a REPL line into a built-in or a CLI command
+pub fn parse_line(line: &str) -> Result<ReplLine, String> {
+    let args = split_line(line)?;
+
+    let Some(first) = args.first() else {
+        return Ok(ReplLine::Empty);
+    };
+
+    Ok(match (first.as_str(), args.len()) {
+        ("exit" | "quit", 1) => ReplLine::Exit,
+        ("help" | "?", 1) => ReplLine::Help,
+        ("use", 1) => ReplLine::ShowContext,
+        ("use", 2) if args[1] == "-" => ReplLine::Use(None),
+        ("use", 2) => ReplLine::Use(Some(args[1].clone())),
+        ("use", _) => return Err("Usage: use <project> | use -".to_string()),
+        _ => ReplLine::Command(args),
+    })
+}
+
+/// Where a command takes its project
+#[derive(Debug, Clone, Copy)]
+pub enum ContextTarget {
+    /// An option such as `--project <name>` / `-p <name>`
+    Flag {
+        long: &'static str,
+        short: Option<char>,
+    },
+    /// The first positional argument, when fewer than `required`
+    /// positionals were given. `value_flags` lists options that consume the
+    /// next argument, so their values aren't counted as positionals.
+    Positional {
+        required: usize,
+        value_flags: &'static [&'static str],
+    },
+}
+
+/// A command (by subcommand path) that accepts the current project
+#[derive(Debug, Clone, Copy)]
+pub struct ContextRule {
+    pub path: &'static [&'static str],
+    pub target: ContextTarget,
+}
+
+/// Commands that receive the current project
+pub const PROJECT_RULES: &[ContextRule] = &[
+    ContextRule {
+        path: &["project", "show"],
+        target: ContextTarget::Positional {
+            required: 1,
+            value_flags: &[],
+        },
+    },
+    ContextRule {
+        path: &["project", "remove"],
+        target: ContextTarget::Positional {
+            required: 1,
+            value_flags: &[],
+        },
+    },
+    ContextRule {
+        path: &["project", "link"],
+        target: ContextTarget::Positional {
+            required: 3,
+            value_flags: &["-r", "--role"],
+        },
+    },
+    ContextRule {
+        path: &["project", "unlink"],
+        target: ContextTarget::Positional {
+            required: 2,
+            value_flags: &[],
+        },
+    },
+    ContextRule {
+        path: &["project", "start"],
+        target: ContextTarget::Positional {
+            required: 1,
+            value_flags: &["--host", "--timeout"],
+        },
+    },
+    ContextRule {
+        path: &["project", "stop"],
+        target: ContextTarget::Positional {
+            required: 1,
+            value_flags: &["--host", "--timeout"],
+        },
+    },
+    ContextRule {
+        path: &["project", "graph"],
+        target: ContextTarget::Positional {
+            required: 1,
+            value_flags: &[],
+        },
+    },
+    ContextRule {
+        path: &["script", "add"],
+        target: ContextTarget::Flag {
+            long: "--project",
+            short: Some('p'),
+        },
+    },
+    ContextRule {
+        path: &["service", "add"],
+        target: ContextTarget::Flag {
+            long: "--project",
+            short: Some('p'),
+        },
+    },
+];
+
+/// Top-level command aliases (kept in sync with the clap definitions)
+const COMMAND_ALIASES: &[(&str, &str)] =
+    &[("p", "project"), ("db", "database"), ("cred", "credential")];
+
+/// Global flags that take a value
+const GLOBAL_VALUE_FLAGS: &[&str] = &["--db", "-m", "--mode"];
+
+/// Inject `project` into `args` according to `rules`.
+///
+/// Returns the arguments unchanged when no rule matches, when the project is
+/// already given explicitly, or when help is requested.
+pub fn inject_project(args: &[String], project: &str, rules: &[ContextRule]) -> Vec<String> {
+    let mut out = args.to_vec();
+
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        return out;
+    }
+
+    // Skip global flags before the subcommand
+    let mut start = 0;
+    while start < args.len() && args[start].starts_with('-') {
+        start += if GLOBAL_VALUE_FLAGS.contains(&args[start].as_str()) {
+            2
+        } else {
+            1
+        };
+    }
+
+    let Some(rule) = rules.iter().find(|rule| {
+        rule.path.iter().enumerate().all(|(i, part)| {
+            args.get(start + i)
+                .map(|a| canonical(a) == *part)
+                .unwrap_or(false)
+        })
+    }) else {
+        return out;
+    };
+
+    let rest_start = start + rule.path.len();
+    let rest = &args[rest_start.min(args.len())..];
+
+    match rule.target {
+        ContextTarget::Flag { long, short } => {
+            let given = rest.iter().any(|a| {
+                a == long
+                    || a.starts_with(&format!("{}=", long))
+                    || short
+                        .is_some_and(|s| a.starts_with(&format!("-{}", s)) && !a.starts_with("--"))
+            });
+            if !given {
+                out.push(long.to_string());
+                out.push(project.to_string());
+            }
+        }
+        ContextTarget::Positional {
+            required,
+            value_flags,
+        } => {
+            if count_positionals(rest, value_flags) < required {
+                out.insert(rest_start, project.to_string());
+            }
+        }
+    }
+
+    out
+}
+
+/// Number of positional arguments, skipping options and their values
+fn count_positionals(args: &[String], value_flags: &[&str]) -> usize {
+    let mut count = 0;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            return count + iter.count();
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            if value_flags.contains(&arg.as_str()) {
+                iter.next();
+            }
+        } else {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn canonical(word: &str) -> &str {
+    COMMAND_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == word)
+        .map(|(_, name)| *name)
+        .unwrap_or(word)
+}
+
+/// Options whose value is a secret, optionally only under one top-level command
+const SECRET_FLAGS: &[(Option<&str>, &str)] = &[
+    (None, "--password"),
+    (None, "-P"),
+    (None, "--token"),
+    (None, "--connection-string"),
+    (Some("coolify"), "-t"),
+    (Some("database"), "-c"),
+];
+
+/// Placeholder written in place of a secret value
+pub const REDACTED: &str = "***";
+
+/// Replace the values of secret-bearing options with `***`, so the line can
+/// be kept in the history file. Handles `--password x`, `--password=x` and
+/// `-Px`. Lines without secrets are returned unchanged.
+pub fn redact_secrets(line: &str) -> String {
+    let Ok(args) = split_line(line) else {
+        return line.to_string();
+    };
+    let command = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .map(|a| canonical(a));
+    let secret = |flag: &str| {
+        SECRET_FLAGS
+            .iter()
+            .any(|(scope, f)| *f == flag && scope.is_none_or(|s| command == Some(s)))
+    };
+
+    let mut redacted = false;
+    let mut out = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            out.push(arg.clone());
+            out.extend(iter.by_ref().cloned());
+            break;
+        }
+        if secret(arg) {
+            out.push(arg.clone());
+            if iter.next().is_some() {
+                out.push(REDACTED.to_string());
+                redacted = true;
+            }
+        } else if let Some((flag, _)) = arg.split_once('=').filter(|(f, _)| secret(f)) {
+            out.push(format!("{}={}", flag, REDACTED));
+            redacted = true;
+        } else if let Some(flag) = arg.get(..2).filter(|f| arg.len() > 2 && secret(f)) {
+            out.push(format!("{}{}", flag, REDACTED));
+            redacted = true;
+        } else {
+            out.push(arg.clone());
+        }
+    }
+
+    if !redacted {
+        return line.to_string();
+    }
+    out.iter()
+        .map(|a| quote_word(a))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Complete the word before the cursor.
+///
+/// `candidates` receives the complete words before the one being completed
+/// and returns possible values for it. Returns the byte offset where the
+/// completed word starts and the sorted, deduplicated matches.
+pub fn complete<F>(line: &str, candidates: F) -> (usize, Vec<String>)
+where
+    F: Fn(&[String]) -> Vec<String>,
+{
+    let start = line
+        .rfind(char::is_whitespace)
+        .map(|i| i + line[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    let prefix = &line[start..];
+    let previous = split_line(&line[..start]).unwrap_or_default();
+
+    let mut matches: Vec<String> = candidates(&previous)
+        .into_iter()
+        .filter(|c| c.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+
+    (start, matches)
+}
+
+/// Longest common prefix of the candidates (for partial Tab completion)
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for c in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(c.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    first[..len].to_string()
+}
+
+/// Quote a completion if it contains characters the splitter would break on
+pub fn quote_word(word: &str) -> String {
+    shlex::try_quote(word)
+        .map(|q| q.into_owned())
+        .unwrap_or_else(|_| word.to_string())
+}
@@ -0,0 +1,463 @@
+//! Build-and-deploy of a project checkout over SSH (`project ship`)
+//!
+//! The project's git checkout on its server is updated, then rebuilt and
+//! restarted with docker compose, and the linked containers are waited on
+//! until they're healthy. This module sequences the phases; running the
+//! commands is up to a [`ShipExecutor`], so the CLI runs them over SSH and
+//! tests run them against a stub.
+
+use crate::shell::quote_word;
+use crate::startup::{phase_status, ContainerState, Direction, PhaseStatus};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Branch shipped when the project doesn't configure one
+pub const DEFAULT_BRANCH: &str = "main";
+
+/// Lines of compose logs shown when a phase fails
+pub const LOG_TAIL_LINES: usize = 50;
+
+/// Per-project deploy settings (`project edit --deploy-*`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeployConfig {
+    /// Checkout on the server, e.g. `/srv/acme`
+    pub path: Option<String>,
+    /// Branch to ship (default `main`)
+    pub branch: Option<String>,
+    /// Server to ship to (default: the project's production server)
+    pub server_id: Option<String>,
+}
+
+/// Output of one remote command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Combined stdout and stderr
+    pub output: String,
+    pub exit_code: i32,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Runs commands on the target server
+pub trait ShipExecutor {
+    /// Run `command` in a shell. With `echo`, its output is shown while it
+    /// runs; otherwise it's only returned.
+    fn run(&mut self, command: &str, echo: bool) -> crate::Result<CommandOutput>;
+
+    /// Wait before polling again
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// Step of a ship run, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShipPhase {
+    /// `git fetch` + `git pull` of the branch
+    Fetch,
+    /// `docker compose pull`
+    Pull,
+    /// `docker compose build`
+    Build,
+    /// `docker compose up -d`
+    Up,
+    /// Waiting for the linked containers
+    Health,
+}
+
+impl fmt::Display for ShipPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShipPhase::Fetch => write!(f, "fetch"),
+            ShipPhase::Pull => write!(f, "pull"),
+            ShipPhase::Build => write!(f, "build"),
+            ShipPhase::Up => write!(f, "up"),
+            ShipPhase::Health => write!(f, "health"),
+        }
+    }
+}
+
+impl std::str::FromStr for ShipPhase {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "fetch" => Ok(ShipPhase::Fetch),
+            "pull" => Ok(ShipPhase::Pull),
+            "build" => Ok(ShipPhase::Build),
+            "up" => Ok(ShipPhase::Up),
+            "health" => Ok(ShipPhase::Health),
+            _ => Err(format!("Unknown ship phase: {}", s)),
+        }
+    }
+}
+
+/// Compose CLI found on the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compose {
+    /// The `docker compose` plugin
+    V2,
+    /// The standalone `docker-compose`
+    V1,
+}
+
+impl Compose {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Compose::V2 => "docker compose",
+            Compose::V1 => "docker-compose",
+        }
+    }
+}
+
+/// What to ship
+#[derive(Debug, Clone)]
+pub struct ShipOptions {
+    /// Checkout on the server
+    pub path: String,
+    pub branch: String,
+    /// Run `docker compose build` (off with `--no-build`)
+    pub build: bool,
+    /// Linked containers to wait for
+    pub containers: Vec<String>,
+    /// How long to wait for the containers
+    pub health_timeout: Duration,
+    /// Time between container polls
+    pub poll_interval: Duration,
+}
+
+/// Progress reported while shipping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShipEvent {
+    PhaseStarted(ShipPhase),
+    /// The checkout moved (or stayed) at a commit
+    Commits {
+        old: String,
+        new: String,
+        /// Commits between old and new, if known
+        count: Option<u32>,
+    },
+    ComposeDetected(Compose),
+    PhaseSkipped(ShipPhase, String),
+    /// Containers not ready yet (reported when the list changes)
+    Waiting(String),
+    PhaseFailed(ShipPhase, String),
+    /// Tail of the compose logs after a failure
+    Logs(String),
+}
+
+/// Result of a ship run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShipReport {
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    /// Phases that ran to completion
+    pub completed: Vec<ShipPhase>,
+    /// The phase that failed and why
+    pub failure: Option<(ShipPhase, String)>,
+    pub logs_tail: Option<String>,
+}
+
+impl ShipReport {
+    pub fn success(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// `abc1234 → def5678`, or just the commit when nothing changed
+    pub fn commit_change(&self) -> Option<String> {
+        match (&self.old_commit, &self.new_commit) {
+            (Some(old), Some(new)) if old == new => Some(format!("{} (unchanged)", short(new))),
+            (Some(old), Some(new)) => Some(format!("{} → {}", short(old), short(new))),
+            (None, Some(new)) => Some(short(new).to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// First 7 characters of a commit hash
+pub fn short(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+/// Run all phases in order, stopping at the first failure
+pub fn ship(
+    executor: &mut dyn ShipExecutor,
+    options: &ShipOptions,
+    on_event: &mut dyn FnMut(ShipEvent),
+) -> ShipReport {
+    let mut report = ShipReport::default();
+    let cd = format!("cd {} && ", quote_word(&options.path));
+
+    // Fetch
+    on_event(ShipEvent::PhaseStarted(ShipPhase::Fetch));
+    if let Err(reason) = fetch(executor, options, &cd, &mut report, on_event) {
+        return fail(executor, report, ShipPhase::Fetch, reason, None, on_event);
+    }
+    report.completed.push(ShipPhase::Fetch);
+
+    // Pull, after finding the compose CLI
+    on_event(ShipEvent::PhaseStarted(ShipPhase::Pull));
+    let compose = match detect_compose(executor) {
+        Ok(compose) => compose,
+        Err(reason) => return fail(executor, report, ShipPhase::Pull, reason, None, on_event),
+    };
+    on_event(ShipEvent::ComposeDetected(compose));
+
+    let steps = [
+        (ShipPhase::Pull, "pull"),
+        (ShipPhase::Build, "build"),
+        (ShipPhase::Up, "up -d"),
+    ];
+    for (phase, args) in steps {
+        if phase != ShipPhase::Pull {
+            on_event(ShipEvent::PhaseStarted(phase));
+        }
+        if phase == ShipPhase::Build && !options.build {
+            on_event(ShipEvent::PhaseSkipped(phase, "--no-build".to_string()));
+            continue;
+        }
+        let command = format!("{}{} {}", cd, compose.command(), args);
+        if let Err(reason) = run_checked(executor, &command, true) {
+            return fail(
+                executor,
+                report,
+                phase,
+                reason,
+                Some((compose, &cd)),
+                on_event,
+            );
+        }
+        report.completed.push(phase);
+    }
+
+    // Health
+    on_event(ShipEvent::PhaseStarted(ShipPhase::Health));
+    if options.containers.is_empty() {
+        on_event(ShipEvent::PhaseSkipped(
+            ShipPhase::Health,
+            "no linked containers".to_string(),
+        ));
+        return report;
+    }
+    if let Err(reason) = wait_healthy(executor, options, on_event) {
+        return fail(
+            executor,
+            report,
+            ShipPhase::Health,
+            reason,
+            Some((compose, &cd)),
+            on_event,
+        );
+    }
+    report.completed.push(ShipPhase::Health);
+
+    report
+}
+
+fn fetch(
+    executor: &mut dyn ShipExecutor,
+    options: &ShipOptions,
+    cd: &str,
+    report: &mut ShipReport,
+    on_event: &mut dyn FnMut(ShipEvent),
+) -> Result<(), String> {
+    let head = format!("{}git rev-parse HEAD", cd);
+    let old = run_checked(executor, &head, false)
+        .map_err(|_| format!("{} is not a git checkout", options.path))?;
+    report.old_commit = Some(old.clone());
+
+    let branch = quote_word(&options.branch);
+    run_checked(
+        executor,
+        &format!(
+            "{}git fetch origin {b} && git checkout {b} && git pull --ff-only origin {b}",
+            cd,
+            b = branch
+        ),
+        true,
+    )?;
+
+    let new = run_checked(executor, &head, false)?;
+    report.new_commit = Some(new.clone());
+
+    let count = if old == new {
+        Some(0)
+    } else {
+        run_checked(
+            executor,
+            &format!("{}git rev-list --count {}..{}", cd, old, new),
+            false,
+        )
+        .ok()
+        .and_then(|n| n.parse().ok())
+    };
+    on_event(ShipEvent::Commits { old, new, count });
+    Ok(())
+}
+
+/// Prefer the compose v2 plugin, fall back to `docker-compose`
+pub fn detect_compose(executor: &mut dyn ShipExecutor) -> Result<Compose, String> {
+    for compose in [Compose::V2, Compose::V1] {
+        let probe = format!("{} version", compose.command());
+        if matches!(executor.run(&probe, false), Ok(out) if out.success()) {
+            return Ok(compose);
+        }
+    }
+    Err("Neither `docker compose` nor `docker-compose` is installed".to_string())
+}
+
+/// Poll the containers until all are running (and healthy, with a
+/// healthcheck), one of them fails, or the timeout passes
+fn wait_healthy(
+    executor: &mut dyn ShipExecutor,
+    options: &ShipOptions,
+    on_event: &mut dyn FnMut(ShipEvent),
+) -> Result<(), String> {
+    let names: Vec<String> = options.containers.iter().map(|c| quote_word(c)).collect();
+    let command = format!(
+        "docker inspect --format '{{{{.Name}}}} {{{{.State.Status}}}} {{{{if .State.Health}}}}{{{{.State.Health.Status}}}}{{{{end}}}}' {}",
+        names.join(" ")
+    );
+
+    let mut waited = Duration::ZERO;
+    let mut last_pending: Option<String> = None;
+    loop {
+        // Missing containers make inspect fail but the others are still listed
+        let output = executor
+            .run(&command, false)
+            .map_err(|e| e.to_string())?
+            .output;
+        let states = parse_inspect(&output);
+        let ordered: Option<Vec<ContainerState>> = options
+            .containers
+            .iter()
+            .map(|name| {
+                states
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, s)| s.clone())
+            })
+            .collect();
+
+        let pending = match ordered {
+            Some(ordered) => match phase_status(&options.containers, &ordered, Direction::Start) {
+                PhaseStatus::Ready => return Ok(()),
+                PhaseStatus::Failed(reason) => return Err(reason),
+                PhaseStatus::Waiting => options
+                    .containers
+                    .iter()
+                    .zip(&ordered)
+                    .filter(|(_, s)| {
+                        !(s.status == "running"
+                            && matches!(s.health.as_deref(), None | Some("healthy")))
+                    })
+                    .map(|(n, _)| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            },
+            None => options
+                .containers
+                .iter()
+                .filter(|c| !states.iter().any(|(n, _)| n == *c))
+                .map(|c| format!("{} (not found)", c))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        if waited >= options.health_timeout {
+            return Err(format!(
+                "Not ready after {}s: {}",
+                waited.as_secs(),
+                pending
+            ));
+        }
+        if last_pending.as_ref() != Some(&pending) {
+            on_event(ShipEvent::Waiting(pending.clone()));
+            last_pending = Some(pending);
+        }
+        executor.sleep(options.poll_interval);
+        waited += options.poll_interval;
+    }
+}
+
+/// Parse `docker inspect` lines of `/name status [health]`
+pub fn parse_inspect(output: &str) -> Vec<(String, ContainerState)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?.trim_start_matches('/').to_string();
+            let status = parts.next()?.to_string();
+            let health = parts.next().map(String::from);
+            Some((name, ContainerState { status, health }))
+        })
+        .collect()
+}
+
+fn run_checked(
+    executor: &mut dyn ShipExecutor,
+    command: &str,
+    echo: bool,
+) -> Result<String, String> {
+    let output = executor.run(command, echo).map_err(|e| e.to_string())?;
+    if output.success() {
+        Ok(output.output.trim().to_string())
+    } else {
+        let last_line = output.output.lines().rev().find(|l| !l.trim().is_empty());
+        Err(match last_line {
+            Some(line) => format!("exit code {}: {}", output.exit_code, line.trim()),
+            None => format!("exit code {}", output.exit_code),
+        })
+    }
+}
+
+/// Record a failure, showing the compose logs once compose has run
+fn fail(
+    executor: &mut dyn ShipExecutor,
+    mut report: ShipReport,
+    phase: ShipPhase,
+    reason: String,
+    logs_from: Option<(Compose, &str)>,
+    on_event: &mut dyn FnMut(ShipEvent),
+) -> ShipReport {
+    on_event(ShipEvent::PhaseFailed(phase, reason.clone()));
+    report.failure = Some((phase, reason));
+
+    if let Some((compose, cd)) = logs_from {
+        let command = format!(
+            "{}{} logs --no-color --tail {}",
+            cd,
+            compose.command(),
+            LOG_TAIL_LINES
+        );
+        if let Ok(output) = executor.run(&command, false) {
+            let tail = output.output.trim_end().to_string();
+            if !tail.is_empty() {
+                on_event(ShipEvent::Logs(tail.clone()));
+                report.logs_tail = Some(tail);
+            }
+        }
+    }
+    report
+}
+
+/// A recorded `project ship` run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShipRun {
+    pub project_id: String,
+    pub server_id: String,
+    pub branch: String,
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    pub success: bool,
+    /// Phase that failed, with the reason
+    pub failed_phase: Option<ShipPhase>,
+    pub error: Option<String>,
+    /// Compose logs tail after a failure
+    pub output: Option<String>,
+    pub duration_ms: i64,
+    /// RFC 3339, UTC
+    pub ran_at: String,
+}
@@ -0,0 +1,83 @@
+//! Short references for entities, e.g. `srv-3fk2` or `prj-9x1a`
+//!
+//! IDs are UUIDs or slugs, and slugs collide across entity types (`web` is
+//! often a project and a server). A short ref names one entity of one type:
+//! a prefix per type, a dash and four characters of Crockford's base32
+//! alphabet. The characters come from a hash of the type and ID, so an
+//! entity gets the same ref whenever it is (re)assigned; on a collision the
+//! next attempt is hashed, see [`generate`].
+//!
+//! Inputs resolve by precedence: a ref first, then an ID, then a name.
+//! Several matches on the same level are ambiguous.
+
+use crate::EntityType;
+use serde::{Deserialize, Serialize};
+
+/// Crockford's base32 alphabet in lower case: no i, l, o or u
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+/// Characters after the prefix; attempts past [`WIDE_AFTER`] get six
+const CODE_LEN: usize = 4;
+
+/// Attempts before codes get longer, for when four characters run out
+const WIDE_AFTER: u32 = 64;
+
+/// Ref prefix of an entity type
+pub fn prefix(entity_type: EntityType) -> &'static str {
+    match entity_type {
+        EntityType::Project => "prj",
+        EntityType::Server => "srv",
+        EntityType::Domain => "dom",
+        EntityType::Database => "dbs",
+        EntityType::Script => "scr",
+        EntityType::Credential => "crd",
+    }
+}
+
+/// Candidate ref of an entity. Attempt 0 is tried first; callers move on
+/// to the next attempt while the candidate is taken.
+pub fn generate(entity_type: EntityType, id: &str, attempt: u32) -> String {
+    let mut hash = fnv1a(prefix(entity_type).as_bytes(), 0xcbf2_9ce4_8422_2325);
+    hash = fnv1a(b":", hash);
+    hash = fnv1a(id.as_bytes(), hash);
+    if attempt > 0 {
+        hash = fnv1a(&attempt.to_le_bytes(), hash);
+    }
+    let len = if attempt < WIDE_AFTER {
+        CODE_LEN
+    } else {
+        CODE_LEN + 2
+    };
+    let code: String = (0..len)
+        .map(|i| ALPHABET[((hash >> (i * 5)) & 31) as usize] as char)
+        .collect();
+    format!("{}-{}", prefix(entity_type), code)
+}
+
+/// Entity type of an input shaped like a ref (any case), else `None`
+pub fn parse(input: &str) -> Option<EntityType> {
+    let input = input.trim().to_ascii_lowercase();
+    let (head, code) = input.split_once('-')?;
+    let entity_type = EntityType::ALL.into_iter().find(|t| prefix(*t) == head)?;
+    let valid = (code.len() == CODE_LEN || code.len() == CODE_LEN + 2)
+        && code.bytes().all(|c| ALPHABET.contains(&c));
+    valid.then_some(entity_type)
+}
+
+/// An entity an input resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityRef {
+    pub entity_type: EntityType,
+    pub id: String,
+    /// Name, or the domain name of a domain
+    pub name: String,
+    pub short_ref: String,
+}
+
+fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,247 @@
+//! Inventory snapshots: secret stripping and entity-level diffs
+//!
+//! Entities are matched by ID. An entity only on the new side was created,
+//! one only on the old side was removed, and one on both sides with differing
+//! fields (see [`crate::diff`]) was modified.
+
+use crate::conflict::Conflict;
+use crate::diff::{diff, display_value, FieldChange};
+use crate::{DatabaseCredentials, Inventory};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What happened to an entity between two inventories
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Created,
+    Removed,
+    Modified(Vec<FieldChange>),
+}
+
+/// One created, removed or modified entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityChange {
+    /// "project", "server", ..., "link"
+    pub kind: &'static str,
+    pub id: String,
+    /// Display name (domain name for domains, "project → type id" for links)
+    pub name: String,
+    pub change: ChangeKind,
+}
+
+/// Database credentials as stored in an inventory: password and connection
+/// string (which usually embeds the password) removed
+pub fn without_secrets(mut database: DatabaseCredentials) -> DatabaseCredentials {
+    database.password = None;
+    database.connection_string = None;
+    database
+}
+
+/// Changes that turn `old` into `new`, grouped by kind, sorted by name
+pub fn diff_inventories(old: &Inventory, new: &Inventory) -> Vec<EntityChange> {
+    let mut changes = Vec::new();
+
+    diff_kind(&mut changes, "project", &old.projects, &new.projects, |p| {
+        (&p.id, p.name.clone())
+    });
+    diff_kind(&mut changes, "server", &old.servers, &new.servers, |s| {
+        (&s.id, s.name.clone())
+    });
+    diff_kind(&mut changes, "domain", &old.domains, &new.domains, |d| {
+        (&d.id, d.domain.clone())
+    });
+    diff_kind(
+        &mut changes,
+        "database",
+        &old.databases,
+        &new.databases,
+        |d| (&d.id, d.name.clone()),
+    );
+    diff_kind(&mut changes, "script", &old.scripts, &new.scripts, |s| {
+        (&s.id, s.name.clone())
+    });
+    diff_kind(
+        &mut changes,
+        "credential",
+        &old.credentials,
+        &new.credentials,
+        |c| (&c.id, c.name.clone()),
+    );
+    diff_kind(&mut changes, "link", &old.links, &new.links, |l| {
+        (
+            &l.id,
+            format!("{} → {} {}", l.project_id, l.resource_type, l.resource_id),
+        )
+    });
+
+    changes
+}
+
+fn diff_kind<T: Serialize>(
+    changes: &mut Vec<EntityChange>,
+    kind: &'static str,
+    old: &[T],
+    new: &[T],
+    key: impl Fn(&T) -> (&String, String),
+) {
+    let old_by_id: BTreeMap<&String, &T> = old.iter().map(|e| (key(e).0, e)).collect();
+    let new_by_id: BTreeMap<&String, &T> = new.iter().map(|e| (key(e).0, e)).collect();
+
+    let mut found = Vec::new();
+    for (id, entity) in &old_by_id {
+        let change = match new_by_id.get(id) {
+            None => ChangeKind::Removed,
+            Some(current) => {
+                let fields = diff(*entity, *current);
+                if fields.is_empty() {
+                    continue;
+                }
+                ChangeKind::Modified(fields)
+            }
+        };
+        // Report modified entities under their new name
+        let name = key(new_by_id.get(id).unwrap_or(entity)).1;
+        found.push(EntityChange {
+            kind,
+            id: id.to_string(),
+            name,
+            change,
+        });
+    }
+    for (id, entity) in &new_by_id {
+        if !old_by_id.contains_key(id) {
+            found.push(EntityChange {
+                kind,
+                id: id.to_string(),
+                name: key(entity).1,
+                change: ChangeKind::Created,
+            });
+        }
+    }
+
+    found.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    changes.extend(found);
+}
+
+/// Whether restoring can apply a change. Credentials are never created or
+/// removed by a restore: snapshots don't contain their secrets.
+pub fn is_restorable(change: &EntityChange) -> bool {
+    change.kind != "credential" || matches!(change.change, ChangeKind::Modified(_))
+}
+
+/// Plain-text listing of changes: `+` created, `-` removed, `~` modified with
+/// one indented line per changed field
+pub fn format_changes(changes: &[EntityChange]) -> String {
+    let mut out = String::new();
+
+    for change in changes {
+        let marker = match change.change {
+            ChangeKind::Created => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified(_) => '~',
+        };
+        out.push_str(&format!("{} {:<10} {}\n", marker, change.kind, change.name));
+
+        if let ChangeKind::Modified(fields) = &change.change {
+            let width = fields.iter().map(|f| f.field.len()).max().unwrap_or(0);
+            for field in fields {
+                out.push_str(&format!(
+                    "      {:<width$}  {} → {}\n",
+                    field.field,
+                    display_value(&field.old),
+                    display_value(&field.new),
+                    width = width
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+/// The modified entities among `changes` as conflicts: `current`'s version
+/// is the local side, `target`'s the incoming one
+pub fn restore_conflicts(
+    current: &Inventory,
+    target: &Inventory,
+    changes: &[EntityChange],
+) -> Vec<Conflict> {
+    changes
+        .iter()
+        .filter(|c| matches!(c.change, ChangeKind::Modified(_)))
+        .filter_map(|change| {
+            Some(Conflict::new(
+                change.kind,
+                &change.id,
+                &change.name,
+                entity_value(current, change.kind, &change.id)?,
+                entity_value(target, change.kind, &change.id)?,
+                &["id"],
+            ))
+        })
+        .collect()
+}
+
+/// An entity of an inventory as JSON
+pub fn entity_value(inventory: &Inventory, kind: &str, id: &str) -> Option<Value> {
+    fn find<T: Serialize>(items: &[T], id: &str) -> Option<Value> {
+        items
+            .iter()
+            .filter_map(|item| serde_json::to_value(item).ok())
+            .find(|value| value.get("id").and_then(Value::as_str) == Some(id))
+    }
+
+    match kind {
+        "project" => find(&inventory.projects, id),
+        "server" => find(&inventory.servers, id),
+        "domain" => find(&inventory.domains, id),
+        "database" => find(&inventory.databases, id),
+        "script" => find(&inventory.scripts, id),
+        "credential" => find(&inventory.credentials, id),
+        "link" => find(&inventory.links, id),
+        _ => None,
+    }
+}
+
+/// Replace an entity of an inventory with `value` (e.g. a conflict merged
+/// field by field)
+pub fn set_entity(
+    inventory: &mut Inventory,
+    kind: &str,
+    id: &str,
+    value: Value,
+) -> Result<(), String> {
+    fn replace<T: Serialize + DeserializeOwned>(
+        items: &mut [T],
+        id: &str,
+        value: Value,
+    ) -> Result<(), String> {
+        let entity: T = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        let slot = items.iter_mut().find(|item| {
+            serde_json::to_value(&**item)
+                .ok()
+                .and_then(|v| v.get("id").and_then(Value::as_str).map(|i| i == id))
+                .unwrap_or(false)
+        });
+        match slot {
+            Some(slot) => {
+                *slot = entity;
+                Ok(())
+            }
+            None => Err(format!("No entity '{}'", id)),
+        }
+    }
+
+    match kind {
+        "project" => replace(&mut inventory.projects, id, value),
+        "server" => replace(&mut inventory.servers, id, value),
+        "domain" => replace(&mut inventory.domains, id, value),
+        "database" => replace(&mut inventory.databases, id, value),
+        "script" => replace(&mut inventory.scripts, id, value),
+        "credential" => replace(&mut inventory.credentials, id, value),
+        "link" => replace(&mut inventory.links, id, value),
+        _ => Err(format!("Unknown kind '{}'", kind)),
+    }
+}
@@ -0,0 +1,104 @@
+//! Dependency-ordered project start/stop
+//!
+//! Container links are grouped by their `start_order` into phases. On start
+//! the phases run in ascending order with unordered links last; on stop the
+//! phases run in reverse. A phase is done when all of its containers are
+//! running and, if they define a healthcheck, healthy.
+
+use crate::{ProjectResource, ResourceType};
+
+/// Start or stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Start,
+    Stop,
+}
+
+/// Containers that are started (or stopped) together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    /// Shared start order, `None` for unordered links
+    pub order: Option<i32>,
+    /// Container names/IDs
+    pub containers: Vec<String>,
+}
+
+/// Observed state of one container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerState {
+    /// Docker state, e.g. "running", "exited"
+    pub status: String,
+    /// Healthcheck status ("starting", "healthy", "unhealthy"), `None` without a healthcheck
+    pub health: Option<String>,
+}
+
+/// Whether a phase may proceed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhaseStatus {
+    Ready,
+    Waiting,
+    /// A container ended up in a state it won't recover from by waiting
+    Failed(String),
+}
+
+/// Split a project's container links into execution phases
+pub fn plan_phases(links: &[ProjectResource], direction: Direction) -> Vec<Phase> {
+    let mut containers: Vec<&ProjectResource> = links
+        .iter()
+        .filter(|l| l.resource_type == ResourceType::Container)
+        .collect();
+    // Ordered links first by ascending order, unordered last; stable for ties
+    containers.sort_by_key(|l| (l.start_order.is_none(), l.start_order));
+
+    let mut phases: Vec<Phase> = Vec::new();
+    for link in containers {
+        match phases.last_mut() {
+            Some(phase) if phase.order == link.start_order => {
+                phase.containers.push(link.resource_id.clone())
+            }
+            _ => phases.push(Phase {
+                order: link.start_order,
+                containers: vec![link.resource_id.clone()],
+            }),
+        }
+    }
+
+    if direction == Direction::Stop {
+        phases.reverse();
+    }
+    phases
+}
+
+/// Evaluate a phase from the states of its containers (same order as
+/// `Phase::containers`)
+pub fn phase_status(
+    containers: &[String],
+    states: &[ContainerState],
+    direction: Direction,
+) -> PhaseStatus {
+    let mut waiting = false;
+
+    for (name, state) in containers.iter().zip(states) {
+        match direction {
+            Direction::Start => match (state.status.as_str(), state.health.as_deref()) {
+                ("running", None | Some("healthy")) => {}
+                ("running", Some("unhealthy")) => {
+                    return PhaseStatus::Failed(format!("{} is unhealthy", name))
+                }
+                ("running" | "created" | "restarting", _) => waiting = true,
+                (status, _) => return PhaseStatus::Failed(format!("{} is {}", name, status)),
+            },
+            Direction::Stop => {
+                if matches!(state.status.as_str(), "running" | "restarting" | "removing") {
+                    waiting = true;
+                }
+            }
+        }
+    }
+
+    if waiting || states.len() < containers.len() {
+        PhaseStatus::Waiting
+    } else {
+        PhaseStatus::Ready
+    }
+}
@@ -0,0 +1,226 @@
+//! systemd services on remote servers (`pctrl service`)
+//!
+//! Everything runs as plain `systemctl`/`journalctl` over SSH. State comes
+//! from `systemctl show`, which prints `Key=value` lines and never fails
+//! for unknown units (they report `LoadState=not-found`). Starting and
+//! stopping needs root, so logins other than root go through `sudo -n`:
+//! it fails right away instead of waiting for a password prompt that
+//! nobody can answer.
+
+use crate::facts::{self, Facts};
+use crate::shell::quote_word;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Properties read by [`show_command`]
+pub const SHOW_PROPERTIES: &str = "Description,LoadState,ActiveState,SubState,UnitFileState,MainPID,NRestarts,ActiveEnterTimestamp,InactiveEnterTimestamp";
+
+/// Unit name with a `.service` suffix if it has no unit type yet
+pub fn normalize_unit(unit: &str) -> Result<String, String> {
+    let unit = unit.trim();
+    let valid = !unit.is_empty()
+        && !unit.starts_with('-')
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '.' | '@' | '-' | '\\'));
+    if !valid {
+        return Err(format!("'{}' is not a valid systemd unit name", unit));
+    }
+    const TYPES: &[&str] = &[
+        ".service", ".socket", ".timer", ".target", ".mount", ".path",
+    ];
+    if TYPES.iter().any(|t| unit.ends_with(t)) {
+        Ok(unit.to_string())
+    } else {
+        Ok(format!("{}.service", unit))
+    }
+}
+
+/// `systemctl show` for the properties [`ServiceState`] needs
+pub fn show_command(unit: &str) -> String {
+    format!(
+        "systemctl show --no-pager --property={} -- {}",
+        SHOW_PROPERTIES,
+        quote_word(unit)
+    )
+}
+
+/// A state-changing `systemctl` call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl fmt::Display for ServiceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceAction::Start => write!(f, "start"),
+            ServiceAction::Stop => write!(f, "stop"),
+            ServiceAction::Restart => write!(f, "restart"),
+        }
+    }
+}
+
+impl FromStr for ServiceAction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "start" => Ok(ServiceAction::Start),
+            "stop" => Ok(ServiceAction::Stop),
+            "restart" => Ok(ServiceAction::Restart),
+            _ => Err(format!("Unknown service action: {}", s)),
+        }
+    }
+}
+
+/// `systemctl <action>`, through `sudo -n` unless logged in as root
+pub fn control_command(action: ServiceAction, unit: &str, sudo: bool) -> String {
+    format!(
+        "{}systemctl {} -- {}",
+        if sudo { "sudo -n " } else { "" },
+        action,
+        quote_word(unit)
+    )
+}
+
+/// `journalctl` for the unit's last `lines` lines, following new ones if
+/// `follow`
+pub fn logs_command(unit: &str, lines: u32, follow: bool) -> String {
+    format!(
+        "journalctl --no-pager -u {} -n {}{}",
+        quote_word(unit),
+        lines,
+        if follow { " -f" } else { "" }
+    )
+}
+
+/// [`logs_command`] following the journal, with ISO timestamps precise
+/// enough to merge with other logs (`pctrl logs follow`)
+pub fn tail_command(unit: &str, lines: u32) -> String {
+    format!("{} -o short-iso-precise", logs_command(unit, lines, true))
+}
+
+/// Whether control commands need `sudo` for this login
+pub fn needs_sudo(username: &str) -> bool {
+    username != "root"
+}
+
+/// State of a unit, from `systemctl show`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ServiceState {
+    pub description: Option<String>,
+    /// e.g. "loaded" or "not-found"
+    pub load_state: String,
+    /// e.g. "active", "inactive", "failed"
+    pub active_state: String,
+    /// e.g. "running", "dead", "exited"
+    pub sub_state: String,
+    /// e.g. "enabled" or "disabled"
+    pub unit_file_state: Option<String>,
+    pub main_pid: Option<u32>,
+    /// Automatic restarts since the unit was last started by hand
+    pub restarts: Option<u32>,
+    /// When the unit entered its current active or inactive state, as
+    /// systemd prints it (e.g. "Tue 2026-03-03 09:14:02 UTC")
+    pub since: Option<String>,
+}
+
+impl ServiceState {
+    pub fn exists(&self) -> bool {
+        self.load_state != "not-found"
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.active_state == "active"
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.active_state == "failed"
+    }
+
+    /// e.g. "active (running)"
+    pub fn summary(&self) -> String {
+        if self.sub_state.is_empty() {
+            self.active_state.clone()
+        } else {
+            format!("{} ({})", self.active_state, self.sub_state)
+        }
+    }
+}
+
+/// Parse `systemctl show` output.
+///
+/// Unknown keys are ignored; empty values, `MainPID=0` and systemd's
+/// `[not set]` count as unset. Output without `ActiveState` (an error
+/// message instead of properties) is an error.
+pub fn parse_show(output: &str) -> Result<ServiceState, String> {
+    let values: HashMap<&str, &str> = output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect();
+    let get = |key: &str| {
+        values
+            .get(key)
+            .copied()
+            .filter(|v| !v.is_empty() && *v != "[not set]")
+    };
+
+    let active_state = get("ActiveState")
+        .ok_or_else(|| format!("Unexpected systemctl output: {}", first_line(output)))?
+        .to_string();
+    let since = if active_state == "active" || active_state == "reloading" {
+        get("ActiveEnterTimestamp")
+    } else {
+        get("InactiveEnterTimestamp")
+    };
+
+    Ok(ServiceState {
+        description: get("Description").map(String::from),
+        load_state: get("LoadState").unwrap_or("loaded").to_string(),
+        sub_state: get("SubState").unwrap_or_default().to_string(),
+        unit_file_state: get("UnitFileState").map(String::from),
+        main_pid: get("MainPID")
+            .and_then(|p| p.parse().ok())
+            .filter(|p| *p != 0),
+        restarts: get("NRestarts").and_then(|n| n.parse().ok()),
+        since: since.map(String::from),
+        active_state,
+    })
+}
+
+/// Whether a failed command's output says the host doesn't run systemd
+pub fn is_not_systemd(output: &str, exit_code: i32) -> bool {
+    const SIGNS: &[&str] = &[
+        "System has not been booted with systemd",
+        "systemctl: command not found",
+        "systemctl: not found",
+        "journalctl: command not found",
+        "journalctl: not found",
+        "Failed to connect to bus",
+    ];
+    exit_code == 127 || SIGNS.iter().any(|s| output.contains(s))
+}
+
+/// Why a host isn't a systemd host, from its facts if they are known, e.g.
+/// "PID 1 is openrc-init (alpine)"
+pub fn not_systemd_reason(facts: &Facts) -> Option<String> {
+    let init = facts.get(facts::INIT).filter(|i| i.as_str() != "systemd")?;
+    Some(match facts.get(facts::DISTRO) {
+        Some(distro) => format!("PID 1 is {} ({})", init, distro),
+        None => format!("PID 1 is {}", init),
+    })
+}
+
+/// Whether `sudo -n` refused because it needs a password
+pub fn is_sudo_refused(output: &str) -> bool {
+    output.contains("sudo: a password is required")
+        || output.contains("sudo: a terminal is required")
+}
+
+fn first_line(output: &str) -> &str {
+    output.lines().next().unwrap_or("").trim()
+}
@@ -0,0 +1,424 @@
+//! One table as CSV, JSON or NDJSON (`pctrl export table`)
+//!
+//! Rows are the typed entities (`Server`, `Domain`, ...) serialized with
+//! serde, so column names are their field names. Nested values are
+//! flattened with dots (`specs.cpu_cores`, `data.username`); every table
+//! lists its columns in [`ExportTable::columns`], and the output always has
+//! exactly those columns. Columns holding secrets are left out unless asked
+//! for, like secret keys in JSON output (see [`crate::redact`]).
+
+use crate::redact::is_secret_key;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// A table that can be exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTable {
+    Projects,
+    Servers,
+    Domains,
+    Databases,
+    Scripts,
+    Credentials,
+    Services,
+    Links,
+}
+
+/// A column of an exported table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub doc: &'static str,
+}
+
+impl Column {
+    /// Whether the column holds secrets: its name looks like a secret key,
+    /// or it is a connection string, which usually embeds a password
+    pub fn is_secret(&self) -> bool {
+        let leaf = self.name.rsplit('.').next().unwrap_or(self.name);
+        is_secret_key(leaf) || leaf == "connection_string"
+    }
+}
+
+const fn col(name: &'static str, doc: &'static str) -> Column {
+    Column { name, doc }
+}
+
+const PROJECT_COLUMNS: &[Column] = &[
+    col("id", "Project ID"),
+    col("name", "Project name"),
+    col("description", "Description"),
+    col("stack", "Technologies, as a JSON array"),
+    col("status", "Dev, Staging, Live, Archived or Maintenance"),
+    col("color", "Color"),
+    col("icon", "Icon"),
+    col("notes", "Notes"),
+];
+
+const SERVER_COLUMNS: &[Column] = &[
+    col("id", "Server ID"),
+    col("name", "Server name"),
+    col("host", "Hostname or IP"),
+    col("server_type", "Vps, Dedicated, Local or Cloud"),
+    col("provider", "Hosting provider"),
+    col("credential_id", "Credential used for SSH"),
+    col("location", "Location"),
+    col("specs.cpu_cores", "CPU cores"),
+    col("specs.ram_gb", "RAM in GB"),
+    col("specs.disk_gb", "Disk in GB"),
+    col("notes", "Notes"),
+    col(
+        "requires_vpn",
+        "VPN interface the server is only reachable through",
+    ),
+    col("max_containers", "Quota of running containers"),
+    col(
+        "max_memory_mb_allocated",
+        "Quota of summed container memory limits in MB",
+    ),
+];
+
+const DOMAIN_COLUMNS: &[Column] = &[
+    col("id", "Domain ID"),
+    col("domain", "Domain name"),
+    col("domain_type", "Production, Staging or Dev"),
+    col("ssl", "Whether SSL is enabled"),
+    col("ssl_expiry", "Certificate expiry"),
+    col("cloudflare_zone_id", "Cloudflare zone"),
+    col("cloudflare_record_id", "Cloudflare DNS record"),
+    col("server_id", "Server it points to"),
+    col("container_id", "Container it routes to"),
+    col("notes", "Notes"),
+    col("superseded_by", "Domain that replaced this one"),
+];
+
+const DATABASE_COLUMNS: &[Column] = &[
+    col("id", "Database ID"),
+    col("name", "Database name in pctrl"),
+    col("db_type", "PostgreSQL, MySQL, MongoDB, Redis or SQLite"),
+    col("host", "Host"),
+    col("port", "Port"),
+    col("database_name", "Database name on the server"),
+    col("username", "User"),
+    col("password", "Password"),
+    col("connection_string", "Connection string"),
+    col("server_id", "Server it runs on"),
+    col("container_id", "Container it runs in"),
+    col("notes", "Notes"),
+];
+
+const SCRIPT_COLUMNS: &[Column] = &[
+    col("id", "Script ID"),
+    col("name", "Script name"),
+    col("description", "Description"),
+    col("command", "Command or script body"),
+    col("script_type", "Ssh, Local or Docker"),
+    col("server_id", "Server it runs on"),
+    col("project_id", "Project it belongs to"),
+    col("docker_host_id", "Docker host for docker scripts"),
+    col("container_id", "Container for docker scripts"),
+    col("dangerous", "Whether it asks before running"),
+    col("last_run", "Last run"),
+    col("last_result", "Success or Error"),
+    col("exit_code", "Exit code of the last run"),
+    col("last_output", "Output of the last run (truncated)"),
+    col("working_dir", "Working directory for local scripts"),
+    col("run_window", "When it may run, if dangerous"),
+];
+
+const CREDENTIAL_COLUMNS: &[Column] = &[
+    col("id", "Credential ID"),
+    col("name", "Credential name"),
+    col(
+        "credential_type",
+        "SshKey, SshAgent, ApiToken, BasicAuth or OAuth",
+    ),
+    col("data.username", "User (SSH, basic auth)"),
+    col("data.port", "SSH port"),
+    col("data.key_path", "SSH key file"),
+    col("data.url", "API URL"),
+    col("data.expires_at", "OAuth token expiry"),
+    col("data.passphrase", "SSH key passphrase"),
+    col("data.token", "API token"),
+    col("data.password", "Password"),
+    col("data.access_token", "OAuth access token"),
+    col("data.refresh_token", "OAuth refresh token"),
+    col("notes", "Notes"),
+];
+
+const SERVICE_COLUMNS: &[Column] = &[
+    col("id", "Service ID"),
+    col("name", "Service name"),
+    col("server_id", "Server the unit runs on"),
+    col("unit", "systemd unit"),
+    col("project_id", "Project it belongs to"),
+    col("last_state", "State at the last check"),
+    col("last_checked", "Time of the last check"),
+];
+
+const LINK_COLUMNS: &[Column] = &[
+    col("id", "Link ID"),
+    col("project_id", "Project"),
+    col(
+        "resource_type",
+        "Server, Domain, Database, Container, Script, Git or Coolify",
+    ),
+    col("resource_id", "Linked entity"),
+    col("role", "Role in the project"),
+    col("notes", "Notes"),
+    col("start_order", "Start phase for project start"),
+];
+
+impl ExportTable {
+    pub const ALL: &'static [ExportTable] = &[
+        ExportTable::Projects,
+        ExportTable::Servers,
+        ExportTable::Domains,
+        ExportTable::Databases,
+        ExportTable::Scripts,
+        ExportTable::Credentials,
+        ExportTable::Services,
+        ExportTable::Links,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportTable::Projects => "projects",
+            ExportTable::Servers => "servers",
+            ExportTable::Domains => "domains",
+            ExportTable::Databases => "databases",
+            ExportTable::Scripts => "scripts",
+            ExportTable::Credentials => "credentials",
+            ExportTable::Services => "services",
+            ExportTable::Links => "links",
+        }
+    }
+
+    /// Every column, in output order
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            ExportTable::Projects => PROJECT_COLUMNS,
+            ExportTable::Servers => SERVER_COLUMNS,
+            ExportTable::Domains => DOMAIN_COLUMNS,
+            ExportTable::Databases => DATABASE_COLUMNS,
+            ExportTable::Scripts => SCRIPT_COLUMNS,
+            ExportTable::Credentials => CREDENTIAL_COLUMNS,
+            ExportTable::Services => SERVICE_COLUMNS,
+            ExportTable::Links => LINK_COLUMNS,
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<&'static Column> {
+        self.columns()
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for ExportTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ExportTable {
+    type Err = String;
+
+    /// Plural or singular (`servers`, `server`); links also as
+    /// `project_resources`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let s = match s.as_str() {
+            "project_resources" | "link" => "links",
+            other => other,
+        };
+        ExportTable::ALL
+            .iter()
+            .find(|t| t.name() == s || t.name().strip_suffix('s') == Some(s))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = ExportTable::ALL.iter().map(|t| t.name()).collect();
+                format!("Unknown table '{}' (tables: {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    /// One JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl FromStr for TableFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(TableFormat::Csv),
+            "json" => Ok(TableFormat::Json),
+            "ndjson" | "jsonl" => Ok(TableFormat::Ndjson),
+            _ => Err(format!("Unknown format '{}' (csv, json, ndjson)", s)),
+        }
+    }
+}
+
+/// A serialized entity flattened to `column -> value`; nested objects get
+/// dotted keys, arrays stay whole
+pub fn flatten(value: Value) -> Map<String, Value> {
+    fn walk(prefix: &str, value: Value, out: &mut Map<String, Value>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&key, value, out);
+                }
+            }
+            other => {
+                out.insert(prefix.to_string(), other);
+            }
+        }
+    }
+    let mut out = Map::new();
+    walk("", value, &mut out);
+    out
+}
+
+/// Column selection and `key=value` conditions for the rows of one table
+#[derive(Debug, Clone)]
+pub struct RowFilter {
+    pub columns: Vec<&'static Column>,
+    conditions: Vec<(&'static Column, String)>,
+}
+
+impl RowFilter {
+    /// `columns` empty means every column. Conditions are `key=value` and
+    /// must all hold; values compare as printed in CSV, case-insensitively.
+    /// Secret columns can only be selected or filtered on with
+    /// `show_secrets`, and are left out of "every column" without it.
+    pub fn new(
+        table: ExportTable,
+        columns: &[String],
+        conditions: &[String],
+        show_secrets: bool,
+    ) -> Result<Self, String> {
+        let lookup = |name: &str| {
+            let column = table.column(name.trim()).ok_or_else(|| {
+                let names: Vec<&str> = table.columns().iter().map(|c| c.name).collect();
+                format!(
+                    "Unknown column '{}' in {} (columns: {})",
+                    name.trim(),
+                    table,
+                    names.join(", ")
+                )
+            })?;
+            if column.is_secret() && !show_secrets {
+                return Err(format!(
+                    "Column '{}' holds secrets; pass --show-secrets to use it",
+                    column.name
+                ));
+            }
+            Ok(column)
+        };
+
+        let columns = if columns.is_empty() {
+            table
+                .columns()
+                .iter()
+                .filter(|c| show_secrets || !c.is_secret())
+                .collect()
+        } else {
+            columns
+                .iter()
+                .map(|c| lookup(c))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let conditions = conditions
+            .iter()
+            .map(|condition| {
+                let (key, value) = condition
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected key=value, got '{}'", condition))?;
+                Ok((lookup(key)?, value.trim().to_string()))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            columns,
+            conditions,
+        })
+    }
+
+    pub fn matches(&self, row: &Map<String, Value>) -> bool {
+        self.conditions
+            .iter()
+            .all(|(column, expected)| cell(row.get(column.name)).eq_ignore_ascii_case(expected))
+    }
+
+    /// The selected values of a row, in column order
+    pub fn select<'a>(&self, row: &'a Map<String, Value>) -> Vec<(&'static str, &'a Value)> {
+        self.columns
+            .iter()
+            .map(|c| (c.name, row.get(c.name).unwrap_or(&Value::Null)))
+            .collect()
+    }
+}
+
+/// A value as CSV prints it: strings as is, null empty, arrays as JSON
+pub fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// One CSV field, quoted if it contains a delimiter, quote, line break or
+/// surrounding spaces
+pub fn csv_field(value: &str) -> String {
+    let needs_quotes =
+        value.contains([',', '"', '\n', '\r']) || value.starts_with(' ') || value.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV header line, without line ending
+pub fn csv_header(filter: &RowFilter) -> String {
+    filter
+        .columns
+        .iter()
+        .map(|c| csv_field(c.name))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// CSV line of a row, without line ending
+pub fn csv_row(filter: &RowFilter, row: &Map<String, Value>) -> String {
+    filter
+        .select(row)
+        .into_iter()
+        .map(|(_, value)| csv_field(&cell(Some(value))))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A row as one JSON object, keys in column order
+pub fn json_row(filter: &RowFilter, row: &Map<String, Value>) -> String {
+    let fields: Vec<String> = filter
+        .select(row)
+        .into_iter()
+        .map(|(name, value)| format!("{}:{}", Value::from(name), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
@@ -0,0 +1,316 @@
+//! TUI color themes
+//!
+//! Themes are palettes of terminal colors by role (accent, text, dim, ...).
+//! Presets only use the 16 ANSI colors, so they follow the terminal's own
+//! color scheme. A custom accent may be a hex color; it is mapped to the
+//! nearest color the terminal can show.
+
+use std::fmt;
+
+/// A terminal color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermColor {
+    /// The terminal's default color
+    Reset,
+    /// One of the 16 ANSI colors (0-7 normal, 8-15 bright)
+    Ansi(u8),
+    /// A color of the 256-color palette
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// ANSI color numbers
+pub mod ansi {
+    pub const BLACK: u8 = 0;
+    pub const RED: u8 = 1;
+    pub const GREEN: u8 = 2;
+    pub const YELLOW: u8 = 3;
+    pub const BLUE: u8 = 4;
+    pub const MAGENTA: u8 = 5;
+    pub const CYAN: u8 = 6;
+    pub const GRAY: u8 = 7;
+    pub const DARK_GRAY: u8 = 8;
+    pub const WHITE: u8 = 15;
+}
+
+/// Names accepted for the 16 ANSI colors, by number
+const ANSI_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "gray",
+    "dark-gray",
+    "light-red",
+    "light-green",
+    "light-yellow",
+    "light-blue",
+    "light-magenta",
+    "light-cyan",
+    "white",
+];
+
+/// Typical (xterm) RGB values of the 16 ANSI colors
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel values of the 6x6x6 cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Depth from `$COLORTERM` and the color count the terminal reports
+    /// (crossterm's `available_color_count`, derived from `$TERM`)
+    pub fn detect(colorterm: Option<&str>, color_count: u16) -> Self {
+        match colorterm {
+            Some("truecolor" | "24bit") => ColorDepth::TrueColor,
+            _ if color_count >= 256 => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// Parse `#rrggbb`, `#rgb` (with or without `#`)
+pub fn parse_hex(s: &str) -> Result<(u8, u8, u8), String> {
+    let hex = s.trim().trim_start_matches('#');
+    let invalid = || format!("Invalid hex color: {} (expected #rrggbb)", s);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+    match hex.len() {
+        6 => Ok((
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        // #abc is #aabbcc
+        3 => Ok((
+            channel(&hex[0..1].repeat(2))?,
+            channel(&hex[1..2].repeat(2))?,
+            channel(&hex[2..3].repeat(2))?,
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a color setting: an ANSI color name (`cyan`, `light-blue`) or hex
+pub fn parse_color(s: &str) -> Result<TermColor, String> {
+    let name = s.trim().to_lowercase().replace(['_', ' '], "-");
+    let name = name.replace("bright-", "light-").replace("grey", "gray");
+    if let Some(n) = ANSI_NAMES.iter().position(|n| *n == name) {
+        return Ok(TermColor::Ansi(n as u8));
+    }
+    parse_hex(s)
+        .map(|(r, g, b)| TermColor::Rgb(r, g, b))
+        .map_err(|_| {
+            format!(
+                "Invalid color: {} (expected a name like cyan or light-blue, or #rrggbb)",
+                s
+            )
+        })
+}
+
+/// Closest of the 16 ANSI colors
+pub fn nearest_ansi16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8)
+        .min_by_key(|&n| distance(rgb, ANSI_RGB[n as usize]))
+        .unwrap_or(0)
+}
+
+/// Closest color of the 256-color palette's cube and gray ramp (16-255)
+pub fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // Gray ramp: 232..=255 is 8, 18, ..., 238
+    let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let step = ((avg as i32 - 8 + 5) / 10).clamp(0, 23) as u8;
+    let gray_value = 8 + 10 * step;
+    let gray = 232 + step;
+
+    if distance(rgb, (gray_value, gray_value, gray_value)) < distance(rgb, cube_rgb) {
+        gray
+    } else {
+        cube as u8
+    }
+}
+
+/// `color` as the terminal can show it
+pub fn fit(color: TermColor, depth: ColorDepth) -> TermColor {
+    match (color, depth) {
+        (TermColor::Rgb(..), ColorDepth::TrueColor) => color,
+        (TermColor::Rgb(r, g, b), ColorDepth::Ansi256) => {
+            TermColor::Indexed(nearest_ansi256((r, g, b)))
+        }
+        (TermColor::Rgb(r, g, b), ColorDepth::Ansi16) => TermColor::Ansi(nearest_ansi16((r, g, b))),
+        (TermColor::Indexed(n), ColorDepth::Ansi16) if n >= 16 => {
+            TermColor::Ansi(nearest_ansi16(indexed_rgb(n)))
+        }
+        _ => color,
+    }
+}
+
+/// Approximate RGB of a 256-palette color
+fn indexed_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => ANSI_RGB[n as usize],
+        16..=231 => {
+            let i = (n - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (n - 232);
+            (v, v, v)
+        }
+    }
+}
+
+/// Squared distance, weighted for perceived brightness of each channel
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    2 * d(a.0, b.0) + 4 * d(a.1, b.1) + 3 * d(a.2, b.2)
+}
+
+/// Built-in theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 2] = [ThemeName::Dark, ThemeName::Light];
+
+    /// The theme after this one (for cycling)
+    pub fn next(self) -> Self {
+        match self {
+            ThemeName::Dark => ThemeName::Light,
+            ThemeName::Light => ThemeName::Dark,
+        }
+    }
+}
+
+impl fmt::Display for ThemeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeName::Dark => write!(f, "dark"),
+            ThemeName::Light => write!(f, "light"),
+        }
+    }
+}
+
+impl std::str::FromStr for ThemeName {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dark" => Ok(ThemeName::Dark),
+            "light" => Ok(ThemeName::Light),
+            _ => Err(format!("Unknown theme: {} (expected dark or light)", s)),
+        }
+    }
+}
+
+/// Colors by role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub name: ThemeName,
+    /// Names, key hints, the selected item
+    pub accent: TermColor,
+    /// Text drawn on an accent background
+    pub on_accent: TermColor,
+    pub text: TermColor,
+    /// Borders, counts, hints
+    pub dim: TermColor,
+    /// Secondary text that must stay readable (details)
+    pub muted: TermColor,
+    pub success: TermColor,
+    pub warning: TermColor,
+    pub error: TermColor,
+    pub info: TermColor,
+    pub highlight: TermColor,
+    /// Background of the selected item
+    pub selection_bg: TermColor,
+}
+
+impl Palette {
+    pub fn preset(name: ThemeName) -> Self {
+        use ansi::*;
+        match name {
+            ThemeName::Dark => Self {
+                name,
+                accent: TermColor::Ansi(CYAN),
+                on_accent: TermColor::Ansi(BLACK),
+                text: TermColor::Ansi(WHITE),
+                dim: TermColor::Ansi(DARK_GRAY),
+                muted: TermColor::Ansi(GRAY),
+                success: TermColor::Ansi(GREEN),
+                warning: TermColor::Ansi(YELLOW),
+                error: TermColor::Ansi(RED),
+                info: TermColor::Ansi(BLUE),
+                highlight: TermColor::Ansi(MAGENTA),
+                selection_bg: TermColor::Reset,
+            },
+            ThemeName::Light => Self {
+                name,
+                accent: TermColor::Ansi(BLUE),
+                on_accent: TermColor::Ansi(WHITE),
+                text: TermColor::Ansi(BLACK),
+                dim: TermColor::Ansi(DARK_GRAY),
+                muted: TermColor::Ansi(DARK_GRAY),
+                success: TermColor::Ansi(GREEN),
+                warning: TermColor::Ansi(YELLOW),
+                error: TermColor::Ansi(RED),
+                info: TermColor::Ansi(CYAN),
+                highlight: TermColor::Ansi(MAGENTA),
+                selection_bg: TermColor::Ansi(GRAY),
+            },
+        }
+    }
+
+    /// Preset with an optional custom accent, fitted to the terminal
+    pub fn with_accent(mut self, accent: Option<TermColor>, depth: ColorDepth) -> Self {
+        if let Some(accent) = accent {
+            self.accent = fit(accent, depth);
+        }
+        self
+    }
+}
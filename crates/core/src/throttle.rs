@@ -0,0 +1,211 @@
+//! Courteous scheduling of bulk network checks
+//!
+//! Checking every domain at once in a tight loop can trip the rate limits
+//! of a DNS provider or look like abuse to a single server. [`run`] works
+//! through checks that carry a group key — the resolver or address they
+//! ask — and starts them so that at most [`Limits::per_group`] run at once
+//! per group, starts across all groups are spaced to
+//! [`Limits::per_second`], and two starts in the same group are a small
+//! random pause apart. Groups take turns, so one large group doesn't hold
+//! up the others.
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Checks running at once per group when not configured
+pub const DEFAULT_PER_GROUP: usize = 2;
+
+/// Checks started per second overall when not configured
+pub const DEFAULT_PER_SECOND: f64 = 10.0;
+
+/// Upper bound of the random pause between starts in one group
+pub const DEFAULT_JITTER: Duration = Duration::from_millis(250);
+
+/// How gently [`run`] works through its checks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Checks running at once per group; 0 counts as 1
+    pub per_group: usize,
+    /// Checks started per second across all groups; 0 for no limit
+    pub per_second: f64,
+    /// Upper bound of the random pause between starts in one group
+    pub jitter: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            per_group: DEFAULT_PER_GROUP,
+            per_second: DEFAULT_PER_SECOND,
+            jitter: DEFAULT_JITTER,
+        }
+    }
+}
+
+impl Limits {
+    /// Time between two starts, from the overall rate
+    fn spacing(&self) -> Duration {
+        if self.per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / self.per_second)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    fn jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            self.jitter.mul_f64(rand::random::<f64>())
+        }
+    }
+}
+
+/// How far a [`run`] has got, reported after every finished check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+    /// Since the run started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Estimated time left, from the average time per finished check;
+    /// `None` before the first one finishes and after the last
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.done == 0 || self.done >= self.total {
+            return None;
+        }
+        let left = (self.total - self.done) as f64 / self.done as f64;
+        Some(self.elapsed.mul_f64(left))
+    }
+}
+
+/// e.g. "34/80, ~40s remaining"
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)?;
+        if let Some(remaining) = self.remaining() {
+            write!(
+                f,
+                ", ~{} remaining",
+                crate::humanize::duration(remaining + Duration::from_millis(500))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Group {
+    key: String,
+    queue: VecDeque<usize>,
+    running: usize,
+    /// Earliest next start, after the jittered pause
+    ready_at: Instant,
+}
+
+/// Run `check` on every task within `limits`, grouping them by `group`.
+///
+/// `on_progress` is called after each finished check. The results keep the
+/// order of `tasks`.
+pub async fn run<'a, T, R, Fut>(
+    tasks: &'a [T],
+    group: impl Fn(&T) -> String,
+    limits: &Limits,
+    check: impl Fn(&'a T) -> Fut,
+    mut on_progress: impl FnMut(&Progress),
+) -> Vec<R>
+where
+    Fut: Future<Output = R>,
+{
+    let started = Instant::now();
+    let per_group = limits.per_group.max(1);
+    let spacing = limits.spacing();
+
+    // Groups in order of their first task
+    let mut groups: Vec<Group> = Vec::new();
+    for (i, task) in tasks.iter().enumerate() {
+        let key = group(task);
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(g) => g.queue.push_back(i),
+            None => groups.push(Group {
+                key,
+                queue: VecDeque::from([i]),
+                running: 0,
+                ready_at: started,
+            }),
+        }
+    }
+
+    let mut results: Vec<Option<R>> = tasks.iter().map(|_| None).collect();
+    let mut running = FuturesUnordered::new();
+    let mut next_start = started;
+    let mut turn = 0;
+    let mut done = 0;
+
+    loop {
+        // Start whatever may start now; note when the next one may
+        let wake = 'start: loop {
+            let mut wake: Option<Instant> = None;
+            let now = Instant::now();
+            for step in 0..groups.len() {
+                let g = (turn + step) % groups.len();
+                let group = &mut groups[g];
+                if group.queue.is_empty() || group.running >= per_group {
+                    continue;
+                }
+                let at = group.ready_at.max(next_start);
+                if at > now {
+                    wake = Some(wake.map_or(at, |w| w.min(at)));
+                    continue;
+                }
+                let Some(i) = group.queue.pop_front() else {
+                    continue;
+                };
+                group.running += 1;
+                group.ready_at = now + limits.jitter();
+                next_start = now + spacing;
+                turn = g + 1;
+                let run = check(&tasks[i]);
+                running.push(async move { (i, g, run.await) });
+                continue 'start;
+            }
+            break wake;
+        };
+
+        if running.is_empty() {
+            match wake {
+                Some(at) => {
+                    sleep_until(at).await;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let finished = match wake {
+            Some(at) => tokio::select! {
+                finished = running.next() => finished,
+                _ = sleep_until(at) => None,
+            },
+            None => running.next().await,
+        };
+        if let Some((i, g, result)) = finished {
+            results[i] = Some(result);
+            groups[g].running -= 1;
+            done += 1;
+            on_progress(&Progress {
+                done,
+                total: tasks.len(),
+                elapsed: started.elapsed(),
+            });
+        }
+    }
+
+    results.into_iter().flatten().collect()
+}
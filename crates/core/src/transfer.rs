@@ -0,0 +1,204 @@
+//! Resumable, throttled file transfers (`server pull/push`)
+//!
+//! Transfers write to a `.part` file next to the destination and rename it
+//! once complete, so a file under its final name is always whole. An
+//! interrupted transfer leaves the `.part` behind; the next one continues
+//! at its length ([`resume_offset`]). [`Bucket`] caps the bandwidth and
+//! [`verify`] checks the result by size and, when both ends can tell, by
+//! SHA-256.
+
+use crate::humanize;
+use std::time::{Duration, Instant};
+
+/// Bytes read and written per step of the copy loop
+pub const CHUNK_SIZE: usize = 32 * 1024;
+
+/// Where an unfinished transfer to `path` is written
+pub fn partial_path(path: &str) -> String {
+    format!("{}.part", path)
+}
+
+/// How a transfer starts, given what is already there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// No partial file: copy everything
+    Fresh,
+    /// Continue after this many bytes
+    From(u64),
+    /// The partial file has every byte; only verification is left
+    Complete,
+    /// The partial file is longer than the source, so it belongs to
+    /// another version of the file: start over
+    Restart,
+}
+
+impl Resume {
+    /// Bytes already transferred
+    pub fn offset(self, total: u64) -> u64 {
+        match self {
+            Resume::Fresh | Resume::Restart => 0,
+            Resume::From(offset) => offset,
+            Resume::Complete => total,
+        }
+    }
+}
+
+/// How to continue a transfer of `total` bytes, given the length of the
+/// partial file if there is one
+pub fn resume_offset(partial: Option<u64>, total: u64) -> Resume {
+    match partial {
+        None | Some(0) if total > 0 => Resume::Fresh,
+        None | Some(0) => Resume::Complete,
+        Some(len) if len < total => Resume::From(len),
+        Some(len) if len == total => Resume::Complete,
+        Some(_) => Resume::Restart,
+    }
+}
+
+/// Token bucket for a bandwidth cap. Tokens are bytes, refilled at the
+/// rate and held up to a quarter second's worth, so the cap holds over
+/// short stretches without stalling on every chunk.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    /// A full bucket for `bytes_per_sec`
+    pub fn new(bytes_per_sec: u64, now: Instant) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        let capacity = rate / 4.0;
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last: now,
+        }
+    }
+
+    /// Take `bytes` from the bucket; returns how long to wait before
+    /// sending them. Chunks larger than the bucket go into debt, which
+    /// the wait pays off.
+    pub fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// State of a running transfer, for progress display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes at the destination, including resumed ones
+    pub done: u64,
+    pub total: u64,
+    /// Bytes that were there when this run started
+    pub resumed_from: u64,
+    /// Time since this run started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Bytes per second in this run
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return 0.0;
+        }
+        self.done.saturating_sub(self.resumed_from) as f64 / secs
+    }
+
+    /// Time left at the current rate; `None` before anything moved
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        (rate > 0.0)
+            .then(|| Duration::from_secs_f64(self.total.saturating_sub(self.done) as f64 / rate))
+    }
+
+    /// "[#######-----]  62%  1.2 GB / 2.0 GB  2.0 MB/s  ETA 6m 40s"
+    pub fn line(&self) -> String {
+        const WIDTH: u64 = 20;
+        let done = self.done.min(self.total);
+        let filled = (done * WIDTH).checked_div(self.total).unwrap_or(WIDTH);
+        let percent = (done * 100).checked_div(self.total).unwrap_or(100);
+        let eta = match self.eta() {
+            Some(eta) if self.done < self.total => {
+                format!("  ETA {}", humanize::duration(round_secs(eta)))
+            }
+            _ => String::new(),
+        };
+        format!(
+            "[{}{}] {:>3}%  {} / {}  {}/s{}",
+            "#".repeat(filled as usize),
+            "-".repeat((WIDTH - filled) as usize),
+            percent,
+            humanize::bytes(self.done),
+            humanize::bytes(self.total),
+            humanize::bytes(self.rate() as u64),
+            eta
+        )
+    }
+}
+
+fn round_secs(d: Duration) -> Duration {
+    Duration::from_secs(d.as_secs().max(1))
+}
+
+/// How a finished transfer was checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verified {
+    Size,
+    /// Size and SHA-256
+    Checksum,
+}
+
+/// Check a finished transfer: the sizes must match, and the checksums
+/// too when both are known
+pub fn verify(
+    size: u64,
+    expected_size: u64,
+    sha256: Option<&str>,
+    expected_sha256: Option<&str>,
+) -> Result<Verified, String> {
+    if size != expected_size {
+        return Err(format!(
+            "size mismatch: {} transferred, {} expected",
+            size, expected_size
+        ));
+    }
+    match (sha256, expected_sha256) {
+        (Some(actual), Some(expected)) if actual.eq_ignore_ascii_case(expected) => {
+            Ok(Verified::Checksum)
+        }
+        (Some(actual), Some(expected)) => Err(format!(
+            "checksum mismatch: sha256 {} transferred, {} expected",
+            actual, expected
+        )),
+        _ => Ok(Verified::Size),
+    }
+}
+
+/// Command printing a remote file's SHA-256; prints nothing where
+/// `sha256sum` isn't available
+pub fn sha256sum_command(path: &str) -> String {
+    let quoted = shlex::try_quote(path)
+        .map(|q| q.into_owned())
+        .unwrap_or_else(|_| path.to_string());
+    format!("sha256sum -- {} 2>/dev/null", quoted)
+}
+
+/// The checksum in `sha256sum` output, if it printed one
+pub fn parse_sha256sum(output: &str) -> Option<String> {
+    let hash = output.split_whitespace().next()?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
@@ -0,0 +1,112 @@
+//! Activity feed types (merged audit log, script runs, monitor events, command history)
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Source of an activity feed entry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ActivityKind {
+    Audit,
+    Script,
+    /// Servers going down or coming back up in `pctrl monitor run`
+    Monitor,
+    Command,
+}
+
+impl ActivityKind {
+    /// All kinds, in filter-toggle order (TUI keys 1-4)
+    pub const ALL: [ActivityKind; 4] = [
+        ActivityKind::Audit,
+        ActivityKind::Script,
+        ActivityKind::Monitor,
+        ActivityKind::Command,
+    ];
+}
+
+impl fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityKind::Audit => write!(f, "audit"),
+            ActivityKind::Script => write!(f, "script"),
+            ActivityKind::Monitor => write!(f, "monitor"),
+            ActivityKind::Command => write!(f, "command"),
+        }
+    }
+}
+
+impl std::str::FromStr for ActivityKind {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "audit" => Ok(ActivityKind::Audit),
+            "script" => Ok(ActivityKind::Script),
+            "monitor" => Ok(ActivityKind::Monitor),
+            "command" | "cmd" => Ok(ActivityKind::Command),
+            _ => Err(format!("Unknown activity kind: {}", s)),
+        }
+    }
+}
+
+/// One row of the merged activity feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    /// ID within the source table
+    pub id: String,
+    /// RFC 3339 timestamp (UTC, second precision)
+    pub timestamp: String,
+    pub title: String,
+    /// Full details, already redacted
+    pub details: Option<String>,
+}
+
+impl ActivityEntry {
+    /// Cursor pointing just past this entry
+    pub fn cursor(&self) -> ActivityCursor {
+        ActivityCursor {
+            timestamp: self.timestamp.clone(),
+            kind: self.kind,
+            id: self.id.clone(),
+        }
+    }
+}
+
+/// Keyset pagination cursor: the feed continues with entries strictly older
+/// than (timestamp, kind, id)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityCursor {
+    pub timestamp: String,
+    pub kind: ActivityKind,
+    pub id: String,
+}
+
+/// Filters for the activity feed
+#[derive(Debug, Clone, Default)]
+pub struct ActivityFilter {
+    /// Kinds to include; `None` is all of them, an empty list none
+    pub kinds: Option<Vec<ActivityKind>>,
+    /// Case-insensitive substring match on title and details
+    pub search: Option<String>,
+}
+
+impl ActivityFilter {
+    /// Whether entries of this kind are shown
+    pub fn includes(&self, kind: ActivityKind) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&kind))
+    }
+
+    /// Toggle a kind on or off (starting from "all")
+    pub fn toggle(&mut self, kind: ActivityKind) {
+        let kinds = self.kinds.get_or_insert_with(|| ActivityKind::ALL.to_vec());
+        if let Some(pos) = kinds.iter().position(|k| *k == kind) {
+            kinds.remove(pos);
+        } else {
+            kinds.push(kind);
+        }
+        if kinds.len() == ActivityKind::ALL.len() {
+            self.kinds = None;
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Audit log types
+
+use super::EntityType;
+use crate::diff::FieldChange;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What happened to an entity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AuditAction {
+    Created,
+    Updated,
+    Removed,
+}
+
+impl AuditAction {
+    /// Action for a save, depending on whether the row existed before
+    pub fn for_save(existed: bool) -> Self {
+        if existed {
+            AuditAction::Updated
+        } else {
+            AuditAction::Created
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::Created => write!(f, "created"),
+            AuditAction::Updated => write!(f, "updated"),
+            AuditAction::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+impl std::str::FromStr for AuditAction {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "created" => Ok(AuditAction::Created),
+            "updated" => Ok(AuditAction::Updated),
+            "removed" => Ok(AuditAction::Removed),
+            _ => Err(format!("Unknown audit action: {}", s)),
+        }
+    }
+}
+
+/// One recorded entity mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    pub action: AuditAction,
+    /// Display name of the entity at the time of the change
+    pub summary: String,
+    /// `user@host` that made the change
+    pub actor: String,
+    pub details: Option<String>,
+    /// RFC 3339 timestamp (UTC)
+    pub created_at: String,
+}
+
+impl AuditEntry {
+    /// Field-level changes recorded with an update (empty for other entries)
+    pub fn changes(&self) -> Vec<FieldChange> {
+        self.details
+            .as_deref()
+            .and_then(crate::diff::parse_details)
+            .unwrap_or_default()
+    }
+}
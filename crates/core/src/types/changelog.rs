@@ -0,0 +1,15 @@
+//! Generated changelogs (`pctrl git changelog`)
+
+use serde::{Deserialize, Serialize};
+
+/// The Markdown changelog of one version of a Git repository, as last
+/// generated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub id: i64,
+    pub repo_id: String,
+    /// The tag it leads up to, or `Unreleased`
+    pub version: String,
+    pub content: String,
+    pub created_at: String,
+}
@@ -15,6 +15,9 @@ pub struct Container {
     pub ports: Vec<String>,
     pub env_vars: Option<String>,
     pub labels: Option<String>,
+    /// Memory limit from `docker inspect`; `None` when unlimited
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
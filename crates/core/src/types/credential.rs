@@ -1,5 +1,6 @@
 //! Credential types for secure storage of SSH keys, API tokens, etc.
 
+use super::AuthMethod;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -158,6 +159,36 @@ impl Credential {
         }
     }
 
+    /// Username, port and authentication of an SSH credential.
+    /// `key_material` is the private key stored inline with it, which is
+    /// used instead of the key file.
+    pub fn ssh_login(&self, key_material: Option<String>) -> Option<(String, u16, AuthMethod)> {
+        match &self.data {
+            CredentialData::SshKey {
+                username,
+                port,
+                key_path,
+                passphrase,
+            } => {
+                let auth = match key_material {
+                    Some(key) => AuthMethod::InlineKey {
+                        key,
+                        passphrase: passphrase.clone(),
+                    },
+                    None => AuthMethod::Key {
+                        path: key_path.clone(),
+                        passphrase: passphrase.clone(),
+                    },
+                };
+                Some((username.clone(), *port, auth))
+            }
+            CredentialData::SshAgent { username, port } => {
+                Some((username.clone(), *port, AuthMethod::Agent))
+            }
+            _ => None,
+        }
+    }
+
     /// Get API token if this is an API token credential
     pub fn as_api_token(&self) -> Option<(&str, Option<&str>)> {
         match &self.data {
@@ -16,6 +16,9 @@ pub struct Domain {
     pub server_id: Option<String>,
     pub container_id: Option<String>,
     pub notes: Option<String>,
+    /// ID of the domain that replaced this one (`domain migrate-base`)
+    #[serde(default)]
+    pub superseded_by: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -0,0 +1,66 @@
+//! Generic entity types used by cross-cutting features (locks, ...)
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Kind of a top-level pctrl entity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum EntityType {
+    Project,
+    Server,
+    Domain,
+    Database,
+    Script,
+    Credential,
+}
+
+impl EntityType {
+    /// Every entity type
+    pub const ALL: [EntityType; 6] = [
+        EntityType::Project,
+        EntityType::Server,
+        EntityType::Domain,
+        EntityType::Database,
+        EntityType::Script,
+        EntityType::Credential,
+    ];
+}
+
+impl fmt::Display for EntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityType::Project => write!(f, "project"),
+            EntityType::Server => write!(f, "server"),
+            EntityType::Domain => write!(f, "domain"),
+            EntityType::Database => write!(f, "database"),
+            EntityType::Script => write!(f, "script"),
+            EntityType::Credential => write!(f, "credential"),
+        }
+    }
+}
+
+impl std::str::FromStr for EntityType {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "project" | "p" => Ok(EntityType::Project),
+            "server" => Ok(EntityType::Server),
+            "domain" => Ok(EntityType::Domain),
+            "database" | "db" => Ok(EntityType::Database),
+            "script" => Ok(EntityType::Script),
+            "credential" | "cred" => Ok(EntityType::Credential),
+            _ => Err(format!("Unknown entity type: {}", s)),
+        }
+    }
+}
+
+/// A row pointing at an entity, found by `Database::reverse_references`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Reference {
+    /// Relationship label, e.g. "domains" or "project links"
+    pub relation: String,
+    /// ID of the referencing entity
+    pub id: String,
+    /// Display name of the referencing entity
+    pub name: String,
+}
@@ -21,6 +21,19 @@ pub enum Error {
     #[error("Git error: {0}")]
     Git(String),
 
+    #[error("Provider error: {0}")]
+    Provider(String),
+
+    #[error("Cloudflare error: {0}")]
+    Cloudflare(String),
+
+    #[error("{0}")]
+    Locked(String),
+
+    /// A name already taken, ignoring case; holds the name as stored
+    #[error("'{0}' already exists")]
+    Conflict(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
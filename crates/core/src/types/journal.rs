@@ -0,0 +1,19 @@
+//! Ops journal entries (`pctrl server log`)
+
+use super::EntityType;
+use serde::{Deserialize, Serialize};
+
+/// A dated note on a server, project or domain. Entries are never edited
+/// or deleted; a correction is a new entry that amends the original.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    /// `user@host` that wrote the entry
+    pub author: String,
+    pub text: String,
+    pub created_at: String,
+    /// The entry this one corrects
+    pub amends: Option<i64>,
+}
@@ -24,6 +24,11 @@ pub enum AuthMethod {
         path: String,
         passphrase: Option<String>,
     },
+    /// Private key stored with the credential, as the text of a key file
+    InlineKey {
+        key: String,
+        passphrase: Option<String>,
+    },
     /// SSH Agent (uses system SSH agent for authentication)
     Agent,
 }
@@ -33,7 +38,17 @@ pub enum AuthMethod {
 pub struct DockerHost {
     pub id: String,
     pub name: String,
+    /// See [`crate::docker_endpoint`]
     pub url: String,
+    /// CA certificate file for `https://` hosts
+    #[serde(default)]
+    pub tls_ca: Option<String>,
+    /// Client certificate file for `https://` hosts
+    #[serde(default)]
+    pub tls_cert: Option<String>,
+    /// Client key file for `https://` hosts
+    #[serde(default)]
+    pub tls_key: Option<String>,
 }
 
 /// Coolify instance configuration
@@ -52,4 +67,7 @@ pub struct GitRepo {
     pub name: String,
     pub path: String,
     pub remote_url: Option<String>,
+    /// Credential tried by pushes after the SSH agent
+    #[serde(default)]
+    pub credential_id: Option<String>,
 }
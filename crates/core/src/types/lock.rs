@@ -0,0 +1,69 @@
+//! Advisory entity lock types
+
+use super::EntityType;
+use serde::{Deserialize, Serialize};
+
+/// Default lock lifetime in hours
+pub const DEFAULT_LOCK_HOURS: i64 = 8;
+
+/// Advisory lock on an entity, held by `user@host`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityLock {
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    /// Name of the entity, or its ID once the entity is gone
+    pub entity_name: String,
+    pub holder: String,
+    pub reason: Option<String>,
+    /// RFC 3339 timestamp (UTC)
+    pub created_at: String,
+    /// RFC 3339 timestamp (UTC), lock is ignored afterwards
+    pub expires_at: String,
+}
+
+impl EntityLock {
+    /// Human-readable description used in refusal messages
+    pub fn describe(&self) -> String {
+        let reason = self
+            .reason
+            .as_ref()
+            .map(|r| format!(" ({})", r))
+            .unwrap_or_default();
+        format!(
+            "{} '{}' is locked by {}{} until {}",
+            self.entity_type, self.entity_name, self.holder, reason, self.expires_at
+        )
+    }
+}
+
+/// Real uid of this process, which unlike `$USER` can't be set at will.
+/// None where there is no such thing (Windows).
+pub fn current_uid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid has no preconditions and never fails
+        Some(unsafe { libc::getuid() })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Identity used as lock holder: `user@hostname`
+pub fn current_holder() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}@{}", user, host)
+}
@@ -2,27 +2,56 @@
 //!
 //! This module contains all the data structures used throughout pctrl.
 
+mod activity;
+mod audit;
+mod changelog;
 mod config;
 mod container;
 mod credential;
 mod database;
 mod domain;
+mod entity;
 mod error;
+mod journal;
 mod legacy;
+mod lock;
+mod network;
+mod patch;
 mod project;
 mod resource;
+mod sample;
 mod script;
 mod server;
+mod service;
+mod snapshot;
+mod usage;
 
 // Re-export all types
+pub use activity::{ActivityCursor, ActivityEntry, ActivityFilter, ActivityKind};
+pub use audit::{AuditAction, AuditEntry};
+pub use changelog::ChangelogEntry;
 pub use config::{Config, Mode};
 pub use container::{Container, ContainerStatus};
 pub use credential::{Credential, CredentialData, CredentialType};
 pub use database::{DatabaseCredentials, DatabaseType};
 pub use domain::{Domain, DomainType};
+pub use entity::{EntityType, Reference};
 pub use error::{Error, Result};
+pub use journal::JournalEntry;
 pub use legacy::{AuthMethod, CoolifyInstance, DockerHost, GitRepo, SshConnection};
+pub use lock::{current_holder, current_uid, EntityLock, DEFAULT_LOCK_HOURS};
+pub use network::{ContainerNetwork, DockerNetwork, PublishedPort};
+pub use patch::{
+    CredentialPatch, DatabasePatch, DomainPatch, Ensured, ProjectPatch, ScriptPatch, ServerPatch,
+};
 pub use project::{Project, ProjectStatus};
 pub use resource::{ProjectResource, ResourceType};
-pub use script::{Script, ScriptResult, ScriptType};
-pub use server::{Server, ServerSpecs, ServerType};
+pub use sample::DiskSample;
+pub use script::{
+    ApprovalMode, RevisionStatus, Script, ScriptResult, ScriptRevision, ScriptRun,
+    ScriptRunContext, ScriptType, ScriptUpdate, SCRIPT_RUN_OUTPUT_LIMIT,
+};
+pub use server::{Server, ServerFact, ServerSpecs, ServerType};
+pub use service::Service;
+pub use snapshot::{CredentialInfo, Inventory, InventoryCounts, SnapshotInfo};
+pub use usage::{percentile, CommandUsage, EntityUsage, UsageSummary};
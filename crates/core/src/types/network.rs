@@ -0,0 +1,34 @@
+//! Docker network topology types
+
+use serde::{Deserialize, Serialize};
+
+/// A network on a Docker host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DockerNetwork {
+    pub host_id: String,
+    pub name: String,
+    /// e.g. "bridge", "overlay", "host", "null"
+    pub driver: Option<String>,
+}
+
+/// A container's attachment to a network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerNetwork {
+    pub host_id: String,
+    /// Container name (without the leading slash)
+    pub container: String,
+    pub network: String,
+    /// None for host/none mode or stopped containers
+    pub ip_address: Option<String>,
+}
+
+/// A container port published on the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedPort {
+    pub host_id: String,
+    pub container: String,
+    pub host_port: u16,
+    pub container_port: u16,
+    /// "tcp", "udp" or "sctp"
+    pub protocol: String,
+}
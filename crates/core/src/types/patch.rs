@@ -0,0 +1,151 @@
+//! Partial updates for `add --ensure`
+//!
+//! A patch holds only the fields given on the command line; `None` keeps
+//! the stored value.
+
+use super::credential::CredentialData;
+use super::database::DatabaseType;
+use super::domain::DomainType;
+use super::project::ProjectStatus;
+use super::script::ScriptType;
+use super::server::{ServerSpecs, ServerType};
+use crate::diff::FieldChange;
+use std::fmt;
+
+/// What an ensure did
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ensured {
+    Created,
+    Updated(Vec<FieldChange>),
+    /// The entity already had every given value
+    Unchanged,
+}
+
+impl fmt::Display for Ensured {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ensured::Created => write!(f, "created"),
+            Ensured::Updated(_) => write!(f, "updated"),
+            Ensured::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProjectPatch {
+    pub description: Option<String>,
+    pub stack: Option<Vec<String>>,
+    pub status: Option<ProjectStatus>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerPatch {
+    pub host: Option<String>,
+    pub server_type: Option<ServerType>,
+    pub provider: Option<String>,
+    pub credential_id: Option<String>,
+    pub location: Option<String>,
+    pub specs: Option<ServerSpecs>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DomainPatch {
+    pub domain_type: Option<DomainType>,
+    pub ssl: Option<bool>,
+    pub ssl_expiry: Option<String>,
+    pub cloudflare_zone_id: Option<String>,
+    pub cloudflare_record_id: Option<String>,
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DatabasePatch {
+    pub db_type: Option<DatabaseType>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub database_name: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub connection_string: Option<String>,
+    pub server_id: Option<String>,
+    pub container_id: Option<String>,
+}
+
+/// Command and dangerous flag go through the approval check of `script edit`
+#[derive(Debug, Clone, Default)]
+pub struct ScriptPatch {
+    pub description: Option<String>,
+    pub command: Option<String>,
+    pub script_type: Option<ScriptType>,
+    pub server_id: Option<String>,
+    pub project_id: Option<String>,
+    pub docker_host_id: Option<String>,
+    pub container_id: Option<String>,
+    pub dangerous: Option<bool>,
+}
+
+/// Credential fields; those not used by the credential's type are ignored
+#[derive(Debug, Clone, Default)]
+pub struct CredentialPatch {
+    pub username: Option<String>,
+    pub port: Option<u16>,
+    pub key_path: Option<String>,
+    /// API or OAuth access token
+    pub token: Option<String>,
+    /// Basic auth password or SSH key passphrase
+    pub password: Option<String>,
+    pub url: Option<String>,
+}
+
+impl CredentialPatch {
+    /// Apply the given fields to credential data
+    pub fn apply(&self, data: &mut CredentialData) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+        fn set_opt<T: Clone>(field: &mut Option<T>, value: &Option<T>) {
+            if value.is_some() {
+                *field = value.clone();
+            }
+        }
+
+        match data {
+            CredentialData::SshKey {
+                username,
+                port,
+                key_path,
+                passphrase,
+            } => {
+                set(username, &self.username);
+                set(port, &self.port);
+                set(key_path, &self.key_path);
+                set_opt(passphrase, &self.password);
+            }
+            CredentialData::SshAgent { username, port } => {
+                set(username, &self.username);
+                set(port, &self.port);
+            }
+            CredentialData::ApiToken { token, url } => {
+                set(token, &self.token);
+                set_opt(url, &self.url);
+            }
+            CredentialData::BasicAuth {
+                username,
+                password,
+                url,
+            } => {
+                set(username, &self.username);
+                set(password, &self.password);
+                set_opt(url, &self.url);
+            }
+            CredentialData::OAuth {
+                access_token, url, ..
+            } => {
+                set(access_token, &self.token);
+                set_opt(url, &self.url);
+            }
+        }
+    }
+}
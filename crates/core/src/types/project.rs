@@ -23,6 +23,16 @@ pub enum ProjectStatus {
     Staging,
     Live,
     Archived,
+    /// Temporarily down for planned work (`pctrl project maintenance`)
+    Maintenance,
+}
+
+impl ProjectStatus {
+    /// Whether commands should treat the project as Live. Projects in
+    /// maintenance count too: the window only pauses alerting.
+    pub fn is_live(&self) -> bool {
+        matches!(self, ProjectStatus::Live | ProjectStatus::Maintenance)
+    }
 }
 
 impl fmt::Display for ProjectStatus {
@@ -32,6 +42,7 @@ impl fmt::Display for ProjectStatus {
             ProjectStatus::Staging => write!(f, "staging"),
             ProjectStatus::Live => write!(f, "live"),
             ProjectStatus::Archived => write!(f, "archived"),
+            ProjectStatus::Maintenance => write!(f, "maintenance"),
         }
     }
 }
@@ -44,6 +55,7 @@ impl std::str::FromStr for ProjectStatus {
             "staging" => Ok(ProjectStatus::Staging),
             "live" => Ok(ProjectStatus::Live),
             "archived" => Ok(ProjectStatus::Archived),
+            "maintenance" => Ok(ProjectStatus::Maintenance),
             _ => Err(format!("Unknown project status: {}", s)),
         }
     }
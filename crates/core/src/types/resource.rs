@@ -12,6 +12,9 @@ pub struct ProjectResource {
     pub resource_id: String,
     pub role: Option<String>,
     pub notes: Option<String>,
+    /// Start phase for `project start` (lower first, unset last)
+    #[serde(default)]
+    pub start_order: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -0,0 +1,23 @@
+//! Server metric samples
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Disk usage of a server's root filesystem at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskSample {
+    pub server_id: String,
+    /// Used space in percent (0-100)
+    pub used_percent: f64,
+    /// RFC 3339 timestamp
+    pub taken_at: String,
+}
+
+impl DiskSample {
+    /// Parsed sample time
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.taken_at)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
@@ -1,6 +1,7 @@
 //! Script types
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// Script for automation
@@ -24,6 +25,24 @@ pub struct Script {
     pub exit_code: Option<i32>,
     /// Truncated output from last execution (stdout + stderr)
     pub last_output: Option<String>,
+    /// Working directory for local scripts (may start with `~`)
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Variables local scripts get in their otherwise clean environment
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// When a dangerous script may run, e.g. "Mon-Thu 08:00-18:00" (see
+    /// [`crate::run_window`])
+    #[serde(default)]
+    pub run_window: Option<String>,
+}
+
+/// Where and with which variables the last local run happened, for
+/// debugging (names only: values may be secrets)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScriptRunContext {
+    pub cwd: String,
+    pub env_names: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -70,3 +89,164 @@ impl fmt::Display for ScriptResult {
         }
     }
 }
+
+impl Script {
+    /// Whether changing this script to `command`/`dangerous` needs approval
+    /// (when approvals are on): any command edit of a dangerous script, or
+    /// taking the dangerous flag off. Marking a script dangerous never does.
+    pub fn change_needs_approval(&self, command: &str, dangerous: bool) -> bool {
+        self.dangerous && (command != self.command || !dangerous)
+    }
+}
+
+/// Whether dangerous script changes need approval (`script_approval` setting)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApprovalMode {
+    /// Changes apply right away
+    #[default]
+    Off,
+    /// Changes wait for `pctrl script approve`
+    On,
+    /// Like On, but the approver must be a different OS user than the author
+    TwoPerson,
+}
+
+impl fmt::Display for ApprovalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalMode::Off => write!(f, "off"),
+            ApprovalMode::On => write!(f, "on"),
+            ApprovalMode::TwoPerson => write!(f, "two-person"),
+        }
+    }
+}
+
+impl std::str::FromStr for ApprovalMode {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(ApprovalMode::Off),
+            "on" => Ok(ApprovalMode::On),
+            "two-person" => Ok(ApprovalMode::TwoPerson),
+            _ => Err(format!(
+                "Unknown approval mode: {} (expected off, on or two-person)",
+                s
+            )),
+        }
+    }
+}
+
+/// Bytes of output kept per run in `script_runs`
+pub const SCRIPT_RUN_OUTPUT_LIMIT: usize = 256 * 1024;
+
+/// One execution of a script (`pctrl script history`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptRun {
+    pub id: i64,
+    pub script_id: String,
+    pub started_at: String,
+    pub finished_at: String,
+    /// `None` when the script never ran to an exit (e.g. SSH failed)
+    pub exit_code: Option<i32>,
+    /// stdout + stderr, at most [`SCRIPT_RUN_OUTPUT_LIMIT`] bytes
+    pub output: Option<String>,
+    /// `user@host` that started the run
+    pub triggered_by: String,
+}
+
+impl ScriptRun {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Output cut to `limit` bytes (at a character boundary), with a note
+    /// saying how much was dropped
+    pub fn truncate_output(output: &str, limit: usize) -> String {
+        if output.len() <= limit {
+            return output.to_string();
+        }
+        let mut end = limit;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!(
+            "{}\n...[truncated: {} more bytes not kept]",
+            &output[..end],
+            output.len() - end
+        )
+    }
+}
+
+/// A proposed change to a dangerous script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptRevision {
+    pub id: i64,
+    pub script_id: String,
+    /// Command and flag the change was made against
+    pub base_command: String,
+    pub base_dangerous: bool,
+    pub command: String,
+    pub dangerous: bool,
+    /// `user@host` that proposed the change
+    pub author: String,
+    /// Real uid of the author's process, where the OS has one
+    pub author_uid: Option<u32>,
+    pub created_at: String,
+    pub status: RevisionStatus,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+}
+
+impl ScriptRevision {
+    /// What the revision changes, as field changes
+    pub fn changes(&self) -> Vec<crate::diff::FieldChange> {
+        crate::diff::diff_json(
+            &serde_json::json!({
+                "command": self.base_command,
+                "dangerous": self.base_dangerous,
+            }),
+            &serde_json::json!({
+                "command": self.command,
+                "dangerous": self.dangerous,
+            }),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RevisionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl fmt::Display for RevisionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevisionStatus::Pending => write!(f, "pending"),
+            RevisionStatus::Approved => write!(f, "approved"),
+            RevisionStatus::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+impl std::str::FromStr for RevisionStatus {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(RevisionStatus::Pending),
+            "approved" => Ok(RevisionStatus::Approved),
+            "rejected" => Ok(RevisionStatus::Rejected),
+            _ => Err(format!("Unknown revision status: {}", s)),
+        }
+    }
+}
+
+/// Outcome of a script command edit
+#[derive(Debug, Clone)]
+pub enum ScriptUpdate {
+    Applied(Script),
+    /// Stored for approval; the script itself is unchanged
+    Pending(ScriptRevision),
+    Unchanged,
+}
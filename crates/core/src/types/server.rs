@@ -16,9 +16,18 @@ pub struct Server {
     pub location: Option<String>,
     pub specs: Option<ServerSpecs>,
     pub notes: Option<String>,
+    /// VPN interface the server is only reachable through (e.g. "wg0")
+    #[serde(default)]
+    pub requires_vpn: Option<String>,
+    /// Soft cap on running containers (see `quota`)
+    #[serde(default)]
+    pub max_containers: Option<u32>,
+    /// Soft cap on the summed memory limits of running containers, in MB
+    #[serde(default)]
+    pub max_memory_mb_allocated: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ServerSpecs {
     pub cpu_cores: Option<u8>,
     pub ram_gb: Option<u16>,
@@ -57,3 +66,13 @@ impl std::str::FromStr for ServerType {
         }
     }
 }
+
+/// A fact collected from a server (`server facts`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerFact {
+    pub server_id: String,
+    pub key: String,
+    pub value: String,
+    /// RFC 3339 timestamp of the collection
+    pub collected_at: String,
+}
@@ -0,0 +1,18 @@
+//! systemd service types
+
+use serde::{Deserialize, Serialize};
+
+/// A systemd unit on a server, managed with `pctrl service`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Service {
+    pub id: String,
+    pub name: String,
+    pub server_id: String,
+    /// Unit name including its type, e.g. "myapp.service"
+    pub unit: String,
+    pub project_id: Option<String>,
+    /// Summary of the last `service status`, e.g. "active (running)"
+    pub last_state: Option<String>,
+    /// RFC 3339 timestamp of the last `service status`
+    pub last_checked: Option<String>,
+}
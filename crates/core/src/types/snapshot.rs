@@ -0,0 +1,87 @@
+//! Inventory snapshot types (`pctrl snapshot`)
+
+use super::{
+    Credential, CredentialType, DatabaseCredentials, Domain, Project, ProjectResource, Script,
+    Server,
+};
+use serde::{Deserialize, Serialize};
+
+/// Every entity pctrl manages, without secrets.
+///
+/// Credential data, database passwords and connection strings are never part
+/// of an inventory; credentials are kept as metadata only.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub servers: Vec<Server>,
+    #[serde(default)]
+    pub domains: Vec<Domain>,
+    #[serde(default)]
+    pub databases: Vec<DatabaseCredentials>,
+    #[serde(default)]
+    pub scripts: Vec<Script>,
+    #[serde(default)]
+    pub credentials: Vec<CredentialInfo>,
+    /// Project resource links
+    #[serde(default)]
+    pub links: Vec<ProjectResource>,
+}
+
+impl Inventory {
+    pub fn counts(&self) -> InventoryCounts {
+        InventoryCounts {
+            projects: self.projects.len(),
+            servers: self.servers.len(),
+            domains: self.domains.len(),
+            databases: self.databases.len(),
+            scripts: self.scripts.len(),
+            credentials: self.credentials.len(),
+            links: self.links.len(),
+        }
+    }
+}
+
+/// A credential without its secret data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialInfo {
+    pub id: String,
+    pub name: String,
+    pub credential_type: CredentialType,
+    pub notes: Option<String>,
+}
+
+impl From<&Credential> for CredentialInfo {
+    fn from(credential: &Credential) -> Self {
+        Self {
+            id: credential.id.clone(),
+            name: credential.name.clone(),
+            credential_type: credential.credential_type.clone(),
+            notes: credential.notes.clone(),
+        }
+    }
+}
+
+/// Number of entities per kind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryCounts {
+    pub projects: usize,
+    pub servers: usize,
+    pub domains: usize,
+    pub databases: usize,
+    pub scripts: usize,
+    pub credentials: usize,
+    pub links: usize,
+}
+
+/// A stored snapshot (without its data)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    /// RFC 3339, UTC
+    pub created_at: String,
+    pub counts: InventoryCounts,
+    /// Compressed size
+    pub size_bytes: u64,
+}
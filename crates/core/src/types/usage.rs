@@ -0,0 +1,80 @@
+//! Local usage statistics types (`pctrl stats`)
+
+use super::EntityType;
+use serde::{Deserialize, Serialize};
+
+/// Usage of one command path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandUsage {
+    /// Subcommand path, e.g. "server status"
+    pub command: String,
+    pub count: i64,
+    pub failures: i64,
+    pub avg_ms: i64,
+    pub p50_ms: i64,
+    pub p95_ms: i64,
+}
+
+impl CommandUsage {
+    /// Aggregate from the durations of all invocations
+    pub fn from_durations(command: &str, failures: i64, durations_ms: &[i64]) -> Self {
+        let mut sorted = durations_ms.to_vec();
+        sorted.sort_unstable();
+
+        let count = sorted.len() as i64;
+        let avg_ms = if count > 0 {
+            sorted.iter().sum::<i64>() / count
+        } else {
+            0
+        };
+
+        Self {
+            command: command.to_string(),
+            count,
+            failures,
+            avg_ms,
+            p50_ms: percentile(&sorted, 50.0).unwrap_or(0),
+            p95_ms: percentile(&sorted, 95.0).unwrap_or(0),
+        }
+    }
+
+    /// Failures as a fraction of all invocations
+    pub fn failure_rate(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.count as f64
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice (`p` in 0..=100)
+pub fn percentile(sorted: &[i64], p: f64) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p.clamp(0.0, 100.0) / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// An entity by number of recorded changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityUsage {
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    /// Most recent audit summary (usually the name)
+    pub summary: String,
+    pub changes: i64,
+}
+
+/// Everything `pctrl stats` shows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub total: i64,
+    /// Most used first
+    pub commands: Vec<CommandUsage>,
+    /// (YYYY-MM-DD, invocations), busiest first
+    pub busiest_days: Vec<(String, i64)>,
+    /// Most changed first
+    pub entities: Vec<EntityUsage>,
+}
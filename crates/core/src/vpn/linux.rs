@@ -0,0 +1,41 @@
+//! Linux: `ip -j link show`, or `wg show interfaces` without iproute2
+
+use super::Interface;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Link {
+    ifname: String,
+    #[serde(default)]
+    flags: Vec<String>,
+    #[serde(default)]
+    operstate: Option<String>,
+}
+
+/// Interfaces from `ip -j link show`; `None` if it isn't that JSON.
+/// WireGuard links have no carrier state (`operstate` "UNKNOWN"), so an
+/// interface is up when it's administratively up and not reported down.
+pub fn parse_ip_link(json: &str) -> Option<Vec<Interface>> {
+    let links: Vec<Link> = serde_json::from_str(json).ok()?;
+    Some(
+        links
+            .into_iter()
+            .map(|link| Interface {
+                up: link.flags.iter().any(|f| f == "UP")
+                    && link.operstate.as_deref() != Some("DOWN"),
+                name: link.ifname,
+            })
+            .collect(),
+    )
+}
+
+/// Interfaces from `wg show interfaces`, which lists only running tunnels
+pub fn parse_wg_interfaces(output: &str) -> Vec<Interface> {
+    output
+        .split_whitespace()
+        .map(|name| Interface {
+            name: name.to_string(),
+            up: true,
+        })
+        .collect()
+}
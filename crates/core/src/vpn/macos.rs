@@ -0,0 +1,33 @@
+//! macOS: `ifconfig`, with WireGuard tunnel names mapped to their utun
+
+use super::Interface;
+
+/// Where wireguard-go writes `<tunnel>.name` files holding the utun name
+pub const WIREGUARD_RUN_DIR: &str = "/var/run/wireguard";
+
+/// Interfaces from `ifconfig`: a header line per interface such as
+/// `utun3: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1420`
+pub fn parse_ifconfig(output: &str) -> Vec<Interface> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(": flags=")?;
+            let flags = rest.split_once('<')?.1.split_once('>')?.0;
+            Some(Interface {
+                name: name.to_string(),
+                up: flags.split(',').any(|f| f == "UP"),
+            })
+        })
+        .collect()
+}
+
+/// Make tunnel `name` (e.g. "wg0") stand for the utun it runs on
+pub fn alias(interfaces: &mut Vec<Interface>, name: &str, utun: &str) {
+    if let Some(up) = interfaces.iter().find(|i| i.name == utun).map(|i| i.up) {
+        interfaces.push(Interface {
+            name: name.to_string(),
+            up,
+        });
+    }
+}
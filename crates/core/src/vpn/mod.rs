@@ -0,0 +1,143 @@
+//! Servers only reachable through a VPN (`requires_vpn`)
+//!
+//! Before pctrl connects to such a server it looks at the local network
+//! interfaces: when the named tunnel is down, the server is reported as
+//! "VPN wg0 not connected" instead of offline, and no connection is tried.
+//! Each platform reads its interfaces differently (`ip -j link` or
+//! `wg show` on Linux, `ifconfig` on macOS, GetAdaptersAddresses on
+//! Windows); when that fails the state is unknown and pctrl connects as
+//! usual.
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+#[cfg(target_os = "macos")]
+pub mod macos;
+#[cfg(windows)]
+mod windows;
+
+use crate::Server;
+use std::fmt;
+
+/// A local network interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    pub up: bool,
+}
+
+/// Whether a tunnel is usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunnelState {
+    Connected,
+    NotConnected,
+    /// The interfaces couldn't be read
+    Unknown,
+}
+
+impl fmt::Display for TunnelState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelState::Connected => write!(f, "connected"),
+            TunnelState::NotConnected => write!(f, "not connected"),
+            TunnelState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Snapshot of the local interfaces, read once and asked per server
+#[derive(Debug, Clone, Default)]
+pub struct Tunnels {
+    interfaces: Option<Vec<Interface>>,
+}
+
+impl Tunnels {
+    /// Read the local interfaces; `None` when the platform tools failed
+    pub fn new(interfaces: Option<Vec<Interface>>) -> Self {
+        Self { interfaces }
+    }
+
+    /// Read the local interfaces if any of `servers` needs a VPN
+    pub async fn detect(servers: &[Server]) -> Self {
+        if servers.iter().all(|s| s.requires_vpn.is_none()) {
+            return Self::default();
+        }
+        Self::new(interfaces().await)
+    }
+
+    pub fn state(&self, name: &str) -> TunnelState {
+        match &self.interfaces {
+            None => TunnelState::Unknown,
+            Some(interfaces) => match interfaces.iter().find(|i| i.name == name) {
+                Some(interface) if interface.up => TunnelState::Connected,
+                _ => TunnelState::NotConnected,
+            },
+        }
+    }
+
+    /// Why `server` can't be reached right now, e.g. "VPN wg0 not
+    /// connected"; `None` when it needs no VPN or the state is unknown
+    pub fn blocked(&self, server: &Server) -> Option<String> {
+        let name = server.requires_vpn.as_deref()?;
+        (self.state(name) == TunnelState::NotConnected)
+            .then(|| format!("VPN {} not connected", name))
+    }
+}
+
+/// The local interfaces, or `None` when they can't be read here
+pub async fn interfaces() -> Option<Vec<Interface>> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(out) = run("ip", &["-j", "link", "show"]).await {
+            if let Some(interfaces) = linux::parse_ip_link(&out) {
+                return Some(interfaces);
+            }
+        }
+        // Minimal systems without iproute2; needs root
+        run("wg", &["show", "interfaces"])
+            .await
+            .map(|out| linux::parse_wg_interfaces(&out))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let out = run("ifconfig", &[]).await?;
+        let mut interfaces = macos::parse_ifconfig(&out);
+        // wireguard-go runs tunnels as utunN and records which one
+        let names = std::fs::read_dir(macos::WIREGUARD_RUN_DIR)
+            .into_iter()
+            .flatten();
+        for entry in names.flatten() {
+            let path = entry.path();
+            if let Ok(utun) = std::fs::read_to_string(&path) {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    macos::alias(&mut interfaces, name, utun.trim());
+                }
+            }
+        }
+        Some(interfaces)
+    }
+    #[cfg(windows)]
+    {
+        tokio::task::spawn_blocking(windows::adapters)
+            .await
+            .ok()
+            .flatten()
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        None
+    }
+}
+
+#[cfg(unix)]
+async fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(std::time::Duration::from_secs(2), output).await {
+        Ok(Ok(output)) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        _ => None,
+    }
+}
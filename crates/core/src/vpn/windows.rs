@@ -0,0 +1,59 @@
+//! Windows: GetAdaptersAddresses. WireGuard for Windows names the adapter
+//! after the tunnel, so the friendly name is the one users configure.
+
+use super::Interface;
+use windows_sys::Win32::Foundation::{ERROR_BUFFER_OVERFLOW, NO_ERROR};
+use windows_sys::Win32::NetworkManagement::IpHelper::{
+    GetAdaptersAddresses, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER, GAA_FLAG_SKIP_MULTICAST,
+    IP_ADAPTER_ADDRESSES_LH,
+};
+use windows_sys::Win32::NetworkManagement::Ndis::IfOperStatusUp;
+use windows_sys::Win32::Networking::WinSock::AF_UNSPEC;
+
+pub(super) fn adapters() -> Option<Vec<Interface>> {
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+    let mut size: u32 = 16 * 1024;
+    // The adapter list can grow between the size query and the call
+    for _ in 0..3 {
+        // u64 elements keep the buffer aligned for the structs
+        let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+        let first = buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH;
+        // SAFETY: `buffer` holds `size` bytes and outlives the call
+        let result = unsafe {
+            GetAdaptersAddresses(AF_UNSPEC as u32, flags, std::ptr::null(), first, &mut size)
+        };
+        if result == ERROR_BUFFER_OVERFLOW {
+            continue;
+        }
+        if result != NO_ERROR {
+            return None;
+        }
+
+        let mut interfaces = Vec::new();
+        let mut adapter = first;
+        while !adapter.is_null() {
+            // SAFETY: the list and its strings live in `buffer`
+            let current = unsafe { &*adapter };
+            interfaces.push(Interface {
+                name: unsafe { wide_string(current.FriendlyName) },
+                up: current.OperStatus == IfOperStatusUp,
+            });
+            adapter = current.Next;
+        }
+        return Some(interfaces);
+    }
+    None
+}
+
+/// # Safety
+/// `ptr` is null or a NUL-terminated UTF-16 string
+unsafe fn wide_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    let mut len = 0;
+    while *ptr.add(len) != 0 {
+        len += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+}
@@ -0,0 +1,70 @@
+use pctrl_core::changelog::{parse, render, Section};
+
+#[test]
+fn test_parse_conventional_summaries() {
+    let change = parse("feat(cli): add git status");
+    assert_eq!(change.section, Section::Features);
+    assert_eq!(change.scope.as_deref(), Some("cli"));
+    assert!(!change.breaking);
+    assert_eq!(change.description, "add git status");
+
+    let change = parse("fix!: drop the old config format");
+    assert_eq!(change.section, Section::Fixes);
+    assert_eq!(change.scope, None);
+    assert!(change.breaking);
+
+    for summary in ["docs: typo", "ci: cache deps", "refactor(db): split crud"] {
+        assert_eq!(parse(summary).section, Section::Chores, "{}", summary);
+    }
+}
+
+#[test]
+fn test_other_summaries_are_kept_whole() {
+    for summary in [
+        "Merge branch 'main'",
+        "Update README.md",
+        "wip: halfway there",
+        "feat(): empty scope",
+        "feat:",
+        "Note: this is not a type",
+    ] {
+        let change = parse(summary);
+        assert_eq!(change.section, Section::Other, "{}", summary);
+        assert_eq!(change.description, summary.trim(), "{}", summary);
+    }
+}
+
+#[test]
+fn test_render_groups_by_type() {
+    let commits = [
+        ("a1", "fix: handle empty repos"),
+        ("b2", "feat(cli)!: new output"),
+        ("c3", "Bump version"),
+        ("d4", "feat: changelog"),
+    ];
+    let markdown = render("v1.3.0", "2026-10-16", &commits, true);
+    assert_eq!(
+        markdown,
+        "## v1.3.0 (2026-10-16)\n\
+         \n### Features\n\n\
+         - **BREAKING** **cli:** new output (b2)\n\
+         - changelog (d4)\n\
+         \n### Fixes\n\n\
+         - handle empty repos (a1)\n\
+         \n### Other\n\n\
+         - Bump version (c3)\n"
+    );
+}
+
+#[test]
+fn test_render_plain_and_empty() {
+    let commits = [("a1", "fix: handle empty repos"), ("c3", "Bump version")];
+    assert_eq!(
+        render("Unreleased", "2026-10-16", &commits, false),
+        "## Unreleased (2026-10-16)\n\n- fix: handle empty repos (a1)\n- Bump version (c3)\n"
+    );
+    assert_eq!(
+        render("v1.0.0", "2026-10-16", &[], true),
+        "## v1.0.0 (2026-10-16)\n\nNo changes.\n"
+    );
+}
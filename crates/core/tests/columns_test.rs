@@ -0,0 +1,139 @@
+use pctrl_core::columns::{compare_values, ColumnExpr, KnownKeys, ListQuery, ListRow};
+use pctrl_core::table_export::{flatten, ExportTable};
+use serde_json::json;
+use std::cmp::Ordering;
+
+fn known() -> KnownKeys {
+    KnownKeys {
+        meta: ["owner", "tier"].iter().map(|k| k.to_string()).collect(),
+        facts: ["distro"].iter().map(|k| k.to_string()).collect(),
+    }
+}
+
+fn row(name: &str, owner: Option<&str>, tier: Option<&str>, distro: Option<&str>) -> ListRow {
+    let mut row = ListRow {
+        id: name.to_string(),
+        fields: flatten(json!({ "name": name, "host": format!("{}.example.com", name) })),
+        ..Default::default()
+    };
+    if let Some(owner) = owner {
+        row.meta.insert("owner".into(), owner.into());
+    }
+    if let Some(tier) = tier {
+        row.meta.insert("tier".into(), tier.into());
+    }
+    if let Some(distro) = distro {
+        row.facts.insert("distro".into(), distro.into());
+    }
+    row
+}
+
+fn query(columns: &[&str], filters: &[&str], sort: &[&str]) -> Result<ListQuery, String> {
+    let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    ListQuery::new(
+        ExportTable::Servers,
+        &strings(columns),
+        &strings(filters),
+        &strings(sort),
+        &known(),
+    )
+}
+
+fn names(rows: &[ListRow]) -> Vec<&str> {
+    rows.iter().map(|r| r.id.as_str()).collect()
+}
+
+#[test]
+fn test_specs_resolve_against_known_keys() {
+    let known = known();
+    assert!(matches!(
+        ColumnExpr::parse(ExportTable::Servers, "NAME", &known),
+        Ok(ColumnExpr::Field(c)) if c.name == "name"
+    ));
+    assert_eq!(
+        ColumnExpr::parse(ExportTable::Servers, "meta.owner", &known),
+        Ok(ColumnExpr::Meta("owner".into()))
+    );
+    assert_eq!(
+        ColumnExpr::parse(ExportTable::Servers, "fact.distro", &known)
+            .unwrap()
+            .label(),
+        "fact.distro"
+    );
+
+    let err = ColumnExpr::parse(ExportTable::Servers, "meta.team", &known).unwrap_err();
+    assert!(err.contains("keys: owner, tier"), "{}", err);
+    let err = ColumnExpr::parse(ExportTable::Servers, "fact.kernel", &known).unwrap_err();
+    assert!(err.contains("keys: distro"), "{}", err);
+    let err = ColumnExpr::parse(ExportTable::Domains, "fact.distro", &known).unwrap_err();
+    assert!(err.contains("only collected for servers"), "{}", err);
+    let err = ColumnExpr::parse(ExportTable::Servers, "ram", &known).unwrap_err();
+    assert!(err.contains("specs.ram_gb"), "{}", err);
+    assert!(ColumnExpr::parse(ExportTable::Databases, "password", &known).is_err());
+}
+
+#[test]
+fn test_values_compare_numerically_with_missing_last() {
+    assert_eq!(compare_values(Some("9"), Some("10")), Ordering::Less);
+    assert_eq!(compare_values(Some("b"), Some("A")), Ordering::Greater);
+    assert_eq!(compare_values(None, Some("a")), Ordering::Greater);
+    assert_eq!(compare_values(None, None), Ordering::Equal);
+}
+
+#[test]
+fn test_mixed_source_sort_is_stable() {
+    let rows = vec![
+        row("web-1", Some("alice"), Some("2"), Some("ubuntu")),
+        row("db-1", None, Some("1"), Some("debian")),
+        row("web-2", Some("bob"), Some("2"), None),
+        row("cache", Some("alice"), None, Some("ubuntu")),
+        row("web-3", Some("alice"), Some("2"), Some("ubuntu")),
+    ];
+
+    // Ties keep the incoming order; missing values go last either way
+    let q = query(&[], &[], &["-meta.tier", "fact.distro"]).unwrap();
+    assert_eq!(
+        names(&q.apply(rows.clone())),
+        ["web-1", "web-3", "web-2", "db-1", "cache"]
+    );
+
+    let q = query(&[], &[], &["meta.owner", "-name"]).unwrap();
+    assert_eq!(
+        names(&q.apply(rows.clone())),
+        ["web-3", "web-1", "cache", "web-2", "db-1"]
+    );
+
+    let q = query(&[], &[], &["fact.distro"]).unwrap();
+    assert_eq!(
+        names(&q.apply(rows)),
+        ["db-1", "web-1", "cache", "web-3", "web-2"]
+    );
+}
+
+#[test]
+fn test_filters_and_cells_share_resolution() {
+    let rows = vec![
+        row("web-1", Some("alice"), None, Some("ubuntu")),
+        row("db-1", None, None, Some("Debian")),
+    ];
+
+    let q = query(
+        &["name", "meta.owner", "fact.distro"],
+        &["fact.distro=debian"],
+        &[],
+    )
+    .unwrap();
+    assert!(q.needs_meta() && q.needs_facts());
+    let matched = q.apply(rows.clone());
+    assert_eq!(names(&matched), ["db-1"]);
+    assert_eq!(q.cells(&matched[0]), ["db-1", "-", "Debian"]);
+
+    let q = query(&["name"], &["meta.owner=-"], &[]).unwrap();
+    assert!(!q.needs_facts());
+    assert_eq!(names(&q.apply(rows.clone())), ["db-1"]);
+
+    let q = query(&["name"], &["meta.owner!=-"], &[]).unwrap();
+    assert_eq!(names(&q.apply(rows)), ["web-1"]);
+
+    assert!(query(&["name"], &["owner"], &[]).is_err());
+}
@@ -0,0 +1,100 @@
+use pctrl_core::compose::{self, ComposeProject, ComposeState, STANDALONE};
+use pctrl_core::discovery;
+
+const DOCKER_PS: &str = include_str!("fixtures/docker_ps_compose.txt");
+
+fn stacks() -> Vec<ComposeProject> {
+    let containers = discovery::parse_docker_ps(DOCKER_PS, "vps").unwrap();
+    compose::group("vps", &containers)
+}
+
+fn find<'a>(stacks: &'a [ComposeProject], name: &str) -> &'a ComposeProject {
+    stacks.iter().find(|s| s.name == name).unwrap()
+}
+
+#[test]
+fn test_group_by_compose_project_with_standalone_last() {
+    let stacks = stacks();
+    let names: Vec<&str> = stacks.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["blog", "shop", "wiki", STANDALONE]);
+    assert!(stacks.iter().all(|s| s.server_id == "vps"));
+}
+
+#[test]
+fn test_replicas_count_as_one_service() {
+    let shop = find(&stacks(), "shop").clone();
+    assert_eq!(shop.services, 2);
+    assert_eq!(shop.running, 2);
+    assert_eq!(shop.state, ComposeState::Up);
+}
+
+#[test]
+fn test_config_path_is_the_first_compose_file() {
+    let stacks = stacks();
+    assert_eq!(
+        find(&stacks, "shop").config_path.as_deref(),
+        Some("/srv/shop/compose.yml")
+    );
+    assert_eq!(
+        find(&stacks, "blog").config_path.as_deref(),
+        Some("/srv/blog/docker-compose.yml")
+    );
+    assert_eq!(find(&stacks, "wiki").config_path, None);
+}
+
+#[test]
+fn test_state_from_running_services() {
+    let stacks = stacks();
+    assert_eq!(find(&stacks, "blog").state, ComposeState::Partial);
+    assert_eq!(find(&stacks, "wiki").state, ComposeState::Down);
+
+    let standalone = find(&stacks, STANDALONE);
+    assert_eq!((standalone.services, standalone.running), (2, 1));
+    assert_eq!(standalone.state, ComposeState::Partial);
+
+    assert_eq!(compose::state(0, 0), ComposeState::Gone);
+    assert_eq!(compose::state(3, 3), ComposeState::Up);
+}
+
+#[test]
+fn test_reconcile_marks_missing_stacks_gone_unless_pruned() {
+    let recorded = stacks();
+    let containers = discovery::parse_docker_ps(DOCKER_PS, "vps").unwrap();
+    let remaining: Vec<_> = containers
+        .into_iter()
+        .filter(|c| !matches!(compose::stack_of(c).as_str(), "blog" | STANDALONE))
+        .collect();
+    let current = compose::group("vps", &remaining);
+
+    let kept = compose::reconcile(&recorded, current.clone(), false);
+    let names: Vec<&str> = kept.iter().map(|s| s.name.as_str()).collect();
+    // A standalone group without containers isn't kept
+    assert_eq!(names, ["blog", "shop", "wiki"]);
+    let blog = find(&kept, "blog");
+    assert_eq!(blog.state, ComposeState::Gone);
+    assert_eq!((blog.services, blog.running), (2, 0));
+    assert_eq!(
+        blog.config_path.as_deref(),
+        Some("/srv/blog/docker-compose.yml")
+    );
+
+    let pruned = compose::reconcile(&recorded, current, true);
+    let names: Vec<&str> = pruned.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["shop", "wiki"]);
+}
+
+#[test]
+fn test_containers_of_a_stack() {
+    let containers = discovery::parse_docker_ps(DOCKER_PS, "vps").unwrap();
+    let shop: Vec<&str> = compose::containers_of(&containers, "shop")
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(shop, ["shop-db-1", "shop-web-1", "shop-web-2"]);
+    let standalone: Vec<&str> = compose::containers_of(&containers, STANDALONE)
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(standalone, ["portainer", "watchtower"]);
+    assert!(compose::containers_of(&containers, "nope").is_empty());
+}
@@ -0,0 +1,218 @@
+use pctrl_core::config_export::{
+    keep_local_secrets, lacks_secrets, ConfigDocument, DocumentFormat, ExportedCredential,
+    FORMAT_VERSION,
+};
+use pctrl_core::merge::{self, Class, Record};
+use pctrl_core::placeholder::SecretSource;
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType, EntityType,
+};
+use std::path::Path;
+
+fn credential() -> Credential {
+    Credential {
+        id: "cred-1".to_string(),
+        name: "deploy".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "tok-123".to_string(),
+            url: None,
+        },
+        notes: None,
+    }
+}
+
+fn database(password: Option<&str>) -> DatabaseCredentials {
+    DatabaseCredentials {
+        id: "db-1".to_string(),
+        name: "shop-db".to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: Some("db.internal".to_string()),
+        port: Some(5432),
+        database_name: Some("shop".to_string()),
+        username: Some("shop".to_string()),
+        password: password.map(String::from),
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    }
+}
+
+fn document(secrets: bool) -> ConfigDocument {
+    ConfigDocument {
+        format: FORMAT_VERSION,
+        pctrl_version: "0.1.0".to_string(),
+        exported_at: "2026-04-01T10:00:00Z".to_string(),
+        secrets,
+        projects: Vec::new(),
+        servers: Vec::new(),
+        domains: Vec::new(),
+        databases: vec![database(secrets.then_some("hunter2"))],
+        scripts: Vec::new(),
+        credentials: vec![ExportedCredential::new(credential(), secrets)],
+        links: Vec::new(),
+    }
+}
+
+#[test]
+fn test_documents_round_trip_in_both_formats() {
+    let original = document(true);
+    for format in [DocumentFormat::Yaml, DocumentFormat::Json] {
+        let text = original.render(format).unwrap();
+        let parsed = ConfigDocument::parse(&text).unwrap();
+        assert_eq!(parsed.render(format).unwrap(), text, "{}", format);
+        assert!(text.contains("tok-123"));
+    }
+}
+
+#[test]
+fn test_format_follows_the_extension() {
+    assert_eq!(
+        DocumentFormat::for_path(Path::new("setup.JSON")),
+        DocumentFormat::Json
+    );
+    assert_eq!(
+        DocumentFormat::for_path(Path::new("pctrl-backup.yaml")),
+        DocumentFormat::Yaml
+    );
+    assert_eq!(
+        DocumentFormat::for_path(Path::new("pctrl-backup")),
+        DocumentFormat::Yaml
+    );
+}
+
+#[test]
+fn test_newer_and_foreign_documents_are_refused() {
+    let newer = format!("format: {}\nprojects: []\n", FORMAT_VERSION + 1);
+    let error = ConfigDocument::parse(&newer).unwrap_err();
+    assert!(error.contains("newer pctrl"), "{}", error);
+
+    let error = ConfigDocument::parse("servers: []\n").unwrap_err();
+    assert!(error.contains("no format version"), "{}", error);
+}
+
+#[test]
+fn test_documents_without_secrets_keep_the_local_ones() {
+    let doc = document(false);
+    assert!(!doc
+        .render(DocumentFormat::Yaml)
+        .unwrap()
+        .contains("tok-123"));
+    let other = doc.records().unwrap();
+    assert!(other.iter().any(lacks_secrets));
+
+    let local = vec![
+        Record::from_entity(EntityType::Credential, &credential(), None).unwrap(),
+        Record::from_entity(EntityType::Database, &database(Some("hunter2")), None).unwrap(),
+    ];
+    let mut comparison = merge::compare(&local, &other);
+    assert_eq!(comparison.count(|c| matches!(c, Class::Conflict(_))), 2);
+
+    keep_local_secrets(&mut comparison);
+    assert_eq!(comparison.count(|c| *c == Class::Identical), 2);
+    let credential = comparison.entries[0].other.as_ref().unwrap();
+    assert!(!lacks_secrets(credential));
+}
+
+#[test]
+fn test_placeholders_are_collected_and_filled() {
+    let mut doc = document(true);
+    doc.credentials[0].data = Some(CredentialData::ApiToken {
+        token: "@env:DEPLOY_TOKEN".to_string(),
+        url: None,
+    });
+    doc.databases[0].password = Some("@prompt".to_string());
+    doc.databases[0].connection_string = Some("@@file:literal".to_string());
+
+    let placeholders = doc.placeholders().unwrap();
+    let labels: Vec<String> = placeholders.refs().iter().map(|r| r.label()).collect();
+    assert_eq!(
+        labels,
+        [
+            "token for credential 'deploy'",
+            "password for database 'shop-db'"
+        ]
+    );
+    // The escaped literal loses its first @
+    assert_eq!(
+        doc.databases[0].connection_string.as_deref(),
+        Some("@file:literal")
+    );
+
+    struct Answers;
+    impl SecretSource for Answers {
+        fn prompt(&mut self, _: &str) -> Result<String, String> {
+            Ok("hunter2".to_string())
+        }
+        fn env(&self, _: &str) -> Option<String> {
+            Some("tok-123".to_string())
+        }
+    }
+    let secrets = placeholders.resolve(&mut Answers).unwrap();
+    doc.fill_secrets(&secrets).unwrap();
+    assert!(matches!(
+        &doc.credentials[0].data,
+        Some(CredentialData::ApiToken { token, .. }) if token == "tok-123"
+    ));
+    assert_eq!(doc.databases[0].password.as_deref(), Some("hunter2"));
+}
+
+#[test]
+fn test_secrets_that_read_as_placeholders_are_escaped() {
+    let mut doc = document(true);
+    doc.databases[0].password = Some("@prompt".to_string());
+    doc.escape_secrets().unwrap();
+    assert_eq!(doc.databases[0].password.as_deref(), Some("@@prompt"));
+
+    let mut parsed = ConfigDocument::parse(&doc.render(DocumentFormat::Yaml).unwrap()).unwrap();
+    assert!(parsed.placeholders().unwrap().is_empty());
+    assert_eq!(parsed.databases[0].password.as_deref(), Some("@prompt"));
+}
+
+#[test]
+fn test_resolved_secrets_win_over_local_ones() {
+    let mut doc = document(false);
+    doc.databases[0].password = Some("from-the-file".to_string());
+    let local =
+        vec![Record::from_entity(EntityType::Database, &database(Some("hunter2")), None).unwrap()];
+    let mut comparison = merge::compare(&local, &doc.records().unwrap());
+    keep_local_secrets(&mut comparison);
+    let entry = comparison
+        .entries
+        .iter()
+        .find(|e| e.local.is_some())
+        .unwrap();
+    assert_eq!(
+        entry.other.as_ref().unwrap().fields["password"],
+        "from-the-file"
+    );
+}
+
+#[test]
+fn test_documents_from_toml_tables() {
+    let table: toml::Table = r#"
+format = 1
+
+[[credentials]]
+id = "cred-1"
+name = "deploy"
+credential_type = "ApiToken"
+data = { type = "ApiToken", token = "@env:DEPLOY_TOKEN" }
+"#
+    .parse()
+    .unwrap();
+    let doc = ConfigDocument::from_table(table).unwrap();
+    assert!(!doc.secrets);
+    assert!(doc.exported_at.is_empty());
+    assert_eq!(doc.credentials[0].name, "deploy");
+
+    let newer: toml::Table = "format = 99".parse().unwrap();
+    assert!(ConfigDocument::from_table(newer)
+        .unwrap_err()
+        .contains("newer pctrl"));
+    let foreign: toml::Table = "name = \"x\"".parse().unwrap();
+    assert!(ConfigDocument::from_table(foreign)
+        .unwrap_err()
+        .contains("no format version"));
+}
@@ -0,0 +1,199 @@
+use pctrl_core::conflict::{
+    resolve, side_by_side, Answer, Choice, Conflict, Decision, InvalidAnswer, Prompt, Prompter,
+    Resolver, Side,
+};
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+
+fn server(id: &str, host: &str, port: u16) -> Conflict {
+    Conflict::new(
+        "server",
+        id,
+        id,
+        json!({ "id": id, "host": "10.0.0.1", "port": 22, "specs": { "cpu_cores": 2 } }),
+        json!({ "id": id, "host": host, "port": port, "specs": { "cpu_cores": 4 } }),
+        &["id"],
+    )
+}
+
+fn project(id: &str) -> Conflict {
+    Conflict::new(
+        "project",
+        id,
+        id,
+        json!({ "id": id, "status": "dev" }),
+        json!({ "id": id, "status": "live" }),
+        &["id"],
+    )
+}
+
+/// Answers from a list; records what it was asked
+struct Scripted {
+    answers: VecDeque<Answer>,
+    asked: Vec<String>,
+    rejected: Vec<String>,
+}
+
+impl Scripted {
+    fn new(answers: &[Answer]) -> Self {
+        Self {
+            answers: answers.iter().copied().collect(),
+            asked: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+}
+
+impl Prompter for Scripted {
+    fn ask(&mut self, prompt: &Prompt<'_>) -> std::io::Result<Answer> {
+        self.asked.push(match prompt {
+            Prompt::Conflict { conflict, .. } => conflict.id.clone(),
+            Prompt::Field {
+                conflict, field, ..
+            } => format!("{}.{}", conflict.id, field.field),
+        });
+        Ok(self.answers.pop_front().expect("ran out of answers"))
+    }
+
+    fn rejected(&mut self, reason: &InvalidAnswer) {
+        self.rejected.push(reason.0.clone());
+    }
+}
+
+#[test]
+fn test_conflict_lists_differing_fields_without_ignored_ones() {
+    let conflict = server("web", "10.0.0.2", 22);
+    let fields: Vec<&str> = conflict.fields.iter().map(|f| f.field.as_str()).collect();
+    assert_eq!(fields, ["host", "specs.cpu_cores"]);
+}
+
+#[test]
+fn test_keep_take_and_skip() {
+    let conflicts = [
+        server("a", "10.0.0.2", 22),
+        server("b", "10.0.0.3", 22),
+        project("p"),
+    ];
+    let mut prompter = Scripted::new(&[
+        Answer::Choose(Choice::KeepLocal),
+        Answer::Choose(Choice::TakeIncoming),
+        Answer::Choose(Choice::Skip),
+    ]);
+    let decisions = resolve(&conflicts, &mut prompter).unwrap().unwrap();
+    assert_eq!(
+        decisions,
+        [Decision::KeepLocal, Decision::TakeIncoming, Decision::Skip]
+    );
+    assert_eq!(prompter.asked, ["a", "b", "p"]);
+}
+
+#[test]
+fn test_choice_for_kind_skips_later_prompts_of_that_kind() {
+    let conflicts = [
+        server("a", "10.0.0.2", 22),
+        project("p"),
+        server("b", "10.0.0.3", 22),
+        server("c", "10.0.0.4", 22),
+    ];
+    let mut prompter = Scripted::new(&[
+        Answer::ChooseForKind(Choice::TakeIncoming),
+        Answer::Choose(Choice::KeepLocal),
+    ]);
+    let decisions = resolve(&conflicts, &mut prompter).unwrap().unwrap();
+    assert_eq!(
+        decisions,
+        [
+            Decision::TakeIncoming,
+            Decision::KeepLocal,
+            Decision::TakeIncoming,
+            Decision::TakeIncoming
+        ]
+    );
+    assert_eq!(prompter.asked, ["a", "p"]);
+}
+
+#[test]
+fn test_field_merge_builds_the_merged_entity() {
+    let conflicts = [server("web", "10.0.0.2", 22)];
+    let mut prompter = Scripted::new(&[
+        Answer::MergeFields,
+        Answer::Field(Side::Local),
+        Answer::Field(Side::Incoming),
+    ]);
+    let decisions = resolve(&conflicts, &mut prompter).unwrap().unwrap();
+    assert_eq!(prompter.asked, ["web", "web.host", "web.specs.cpu_cores"]);
+
+    let picks = BTreeMap::from([
+        ("host".to_string(), Side::Local),
+        ("specs.cpu_cores".to_string(), Side::Incoming),
+    ]);
+    assert_eq!(decisions, [Decision::Fields(picks.clone())]);
+    assert_eq!(
+        conflicts[0].merged(&picks),
+        json!({ "id": "web", "host": "10.0.0.1", "port": 22, "specs": { "cpu_cores": 4 } })
+    );
+}
+
+#[test]
+fn test_field_picks_all_one_way_are_a_plain_decision() {
+    let conflicts = [server("web", "10.0.0.2", 22)];
+    let mut prompter = Scripted::new(&[
+        Answer::MergeFields,
+        Answer::Field(Side::Incoming),
+        Answer::Field(Side::Incoming),
+    ]);
+    let decisions = resolve(&conflicts, &mut prompter).unwrap().unwrap();
+    assert_eq!(decisions, [Decision::TakeIncoming]);
+}
+
+#[test]
+fn test_quit_decides_nothing() {
+    let conflicts = [server("a", "10.0.0.2", 22), server("b", "10.0.0.3", 22)];
+    let mut prompter = Scripted::new(&[Answer::Choose(Choice::KeepLocal), Answer::Quit]);
+    assert_eq!(resolve(&conflicts, &mut prompter).unwrap(), None);
+}
+
+#[test]
+fn test_answers_that_dont_fit_are_rejected_and_asked_again() {
+    let conflicts = [server("web", "10.0.0.2", 22)];
+    let mut prompter = Scripted::new(&[
+        Answer::Field(Side::Local),
+        Answer::MergeFields,
+        Answer::Choose(Choice::KeepLocal),
+        Answer::Field(Side::Local),
+        Answer::Field(Side::Local),
+    ]);
+    let decisions = resolve(&conflicts, &mut prompter).unwrap().unwrap();
+    assert_eq!(decisions, [Decision::KeepLocal]);
+    assert_eq!(prompter.rejected.len(), 2);
+    assert_eq!(
+        prompter.asked,
+        ["web", "web", "web.host", "web.host", "web.specs.cpu_cores"]
+    );
+}
+
+#[test]
+fn test_resolver_without_conflicts_is_done() {
+    let mut resolver = Resolver::new(&[]);
+    assert!(resolver.prompt().is_none());
+    assert!(resolver.answer(Answer::Quit).is_err());
+    assert_eq!(resolver.finish(), Some(Vec::new()));
+}
+
+#[test]
+fn test_side_by_side_aligns_columns_and_cuts_long_values() {
+    let conflict = Conflict::new(
+        "server",
+        "web",
+        "web",
+        json!({ "host": "10.0.0.1", "notes": "a very long note about this server" }),
+        json!({ "host": "10.0.0.2", "notes": null }),
+        &[],
+    );
+    assert_eq!(
+        side_by_side(&conflict, 12),
+        "field  local         incoming\n\
+         host   10.0.0.1      10.0.0.2\n\
+         notes  a very long…  -\n"
+    );
+}
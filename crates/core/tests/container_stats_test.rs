@@ -0,0 +1,59 @@
+use pctrl_core::container_stats::{block_io, cpu_percent, memory_usage, ContainerStats, CpuSample};
+
+fn sample(container: u64, system: Option<u64>) -> CpuSample {
+    CpuSample { container, system }
+}
+
+#[test]
+fn test_cpu_percent_from_deltas() {
+    // Half the host's CPU time on a 4-CPU host: two cores busy
+    let percent = cpu_percent(sample(1_500, Some(12_000)), sample(1_000, Some(11_000)), 4);
+    assert!((percent - 200.0).abs() < 1e-9, "{}", percent);
+
+    // Unknown CPU count counts as one
+    let percent = cpu_percent(sample(1_100, Some(2_000)), sample(1_000, Some(1_000)), 0);
+    assert!((percent - 10.0).abs() < 1e-9, "{}", percent);
+}
+
+#[test]
+fn test_cpu_percent_without_previous_sample_is_zero() {
+    // Just started: Docker sends an empty precpu_stats
+    let now = sample(5_000, Some(90_000));
+    assert_eq!(cpu_percent(now, sample(0, None), 2), 0.0);
+    assert_eq!(cpu_percent(now, sample(0, Some(0)), 2), 0.0);
+    assert_eq!(
+        cpu_percent(sample(5_000, None), sample(4_000, Some(1)), 2),
+        0.0
+    );
+    // Counters that didn't move or went backwards (container restarted)
+    assert_eq!(cpu_percent(now, now, 2), 0.0);
+    assert_eq!(cpu_percent(now, sample(6_000, Some(80_000)), 2), 0.0);
+    assert!(!cpu_percent(now, sample(0, Some(90_000)), 2).is_nan());
+}
+
+#[test]
+fn test_memory_and_block_io() {
+    assert_eq!(memory_usage(100 << 20, Some(30 << 20)), 70 << 20);
+    assert_eq!(memory_usage(100 << 20, None), 100 << 20);
+    // A cache larger than the usage doesn't underflow
+    assert_eq!(memory_usage(10, Some(20)), 10);
+
+    let entries = [
+        ("Read", 100),
+        ("Write", 40),
+        ("read", 5),
+        ("write", 1),
+        ("Sync", 999),
+        ("Total", 146),
+    ];
+    assert_eq!(block_io(entries), (105, 41));
+    assert_eq!(block_io([]), (0, 0));
+
+    let stats = ContainerStats {
+        mem_usage_bytes: 256,
+        mem_limit_bytes: 1024,
+        ..Default::default()
+    };
+    assert_eq!(stats.mem_percent(), 25.0);
+    assert_eq!(ContainerStats::default().mem_percent(), 0.0);
+}
@@ -0,0 +1,166 @@
+use pctrl_core::diff::{diff, diff_json, display_value, parse_details, to_details, FieldChange};
+use pctrl_core::{Credential, CredentialData, CredentialType, Server, ServerSpecs, ServerType};
+use serde_json::{json, Value};
+
+fn change(field: &str, old: Value, new: Value) -> FieldChange {
+    FieldChange {
+        field: field.to_string(),
+        old,
+        new,
+    }
+}
+
+#[test]
+fn test_diff_table() {
+    let cases: Vec<(&str, Value, Value, Vec<FieldChange>)> = vec![
+        ("identical", json!({"a": 1}), json!({"a": 1}), vec![]),
+        (
+            "scalar change",
+            json!({"host": "10.0.0.1", "name": "web"}),
+            json!({"host": "10.0.0.2", "name": "web"}),
+            vec![change("host", json!("10.0.0.1"), json!("10.0.0.2"))],
+        ),
+        (
+            "field set and unset",
+            json!({"notes": null, "location": "fsn1"}),
+            json!({"notes": "primary", "location": null}),
+            vec![
+                change("location", json!("fsn1"), Value::Null),
+                change("notes", Value::Null, json!("primary")),
+            ],
+        ),
+        (
+            "nested object",
+            json!({"specs": {"cpu_cores": 2, "ram_gb": 4}}),
+            json!({"specs": {"cpu_cores": 4, "ram_gb": 4}}),
+            vec![change("specs.cpu_cores", json!(2), json!(4))],
+        ),
+        (
+            "nested object appears",
+            json!({"specs": null}),
+            json!({"specs": {"cpu_cores": 2, "ram_gb": null}}),
+            vec![change("specs.cpu_cores", Value::Null, json!(2))],
+        ),
+        (
+            "array compared as a whole",
+            json!({"stack": ["rust", "postgres"]}),
+            json!({"stack": ["rust", "postgres", "redis"]}),
+            vec![change(
+                "stack",
+                json!(["rust", "postgres"]),
+                json!(["rust", "postgres", "redis"]),
+            )],
+        ),
+        (
+            "secret key masked",
+            json!({"password": "old", "user": "app"}),
+            json!({"password": "new", "user": "app"}),
+            vec![change("password", json!("****"), json!("****"))],
+        ),
+        (
+            "secret set from unset keeps null",
+            json!({"api_key": null}),
+            json!({"api_key": "abc"}),
+            vec![change("api_key", Value::Null, json!("****"))],
+        ),
+        (
+            "everything below a secret key masked",
+            json!({"token": {"value": "a"}}),
+            json!({"token": {"value": "b"}}),
+            vec![change("token.value", json!("****"), json!("****"))],
+        ),
+        (
+            "type change",
+            json!({"port": 22}),
+            json!({"port": "22"}),
+            vec![change("port", json!(22), json!("22"))],
+        ),
+    ];
+
+    for (name, old, new, expected) in cases {
+        assert_eq!(diff_json(&old, &new), expected, "case: {}", name);
+    }
+}
+
+#[test]
+fn test_diff_server_specs() {
+    let old = Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    };
+    let new = Server {
+        server_type: ServerType::Dedicated,
+        specs: Some(ServerSpecs {
+            cpu_cores: Some(8),
+            ram_gb: Some(32),
+            disk_gb: None,
+        }),
+        ..old.clone()
+    };
+
+    let fields: Vec<String> = diff(&old, &new).into_iter().map(|c| c.field).collect();
+    assert_eq!(
+        fields,
+        vec!["server_type", "specs.cpu_cores", "specs.ram_gb"]
+    );
+}
+
+#[test]
+fn test_diff_credential_masks_secrets() {
+    let old = Credential {
+        id: "c1".to_string(),
+        name: "deploy".to_string(),
+        credential_type: CredentialType::BasicAuth,
+        data: CredentialData::BasicAuth {
+            username: "admin".to_string(),
+            password: "hunter2".to_string(),
+            url: None,
+        },
+        notes: None,
+    };
+    let new = Credential {
+        data: CredentialData::BasicAuth {
+            username: "root".to_string(),
+            password: "correct horse".to_string(),
+            url: None,
+        },
+        ..old.clone()
+    };
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.len(), 2);
+    let text = serde_json::to_string(&changes).unwrap();
+    assert!(!text.contains("hunter2"));
+    assert!(!text.contains("correct horse"));
+    assert!(text.contains("root"));
+}
+
+#[test]
+fn test_details_round_trip() {
+    assert_eq!(to_details(&[]), None);
+
+    let changes = vec![change("host", json!("a"), json!("b"))];
+    let details = to_details(&changes).unwrap();
+    assert_eq!(parse_details(&details), Some(changes));
+    assert_eq!(parse_details("by alice@laptop"), None);
+}
+
+#[test]
+fn test_display_value() {
+    assert_eq!(display_value(&Value::Null), "-");
+    assert_eq!(display_value(&json!("web")), "web");
+    assert_eq!(display_value(&json!(["rust", "redis"])), "rust, redis");
+    assert_eq!(display_value(&json!([])), "[]");
+    assert_eq!(display_value(&json!(4)), "4");
+    assert_eq!(display_value(&json!(true)), "true");
+}
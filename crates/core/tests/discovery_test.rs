@@ -0,0 +1,100 @@
+use chrono::{Duration, TimeZone, Utc};
+use pctrl_core::discovery::{self, CachedDiscovery};
+use pctrl_core::ContainerStatus;
+
+#[test]
+fn test_parse_docker_ps() {
+    let output = concat!(
+        r#"{"ID":"4f1a","Names":"web,proxy/web","Image":"nginx:1.27","State":"running","Status":"Up 2 hours","Ports":"0.0.0.0:80->80/tcp, :::80->80/tcp","Labels":"com.docker.compose.project=shop,traefik.http.routers.web.rule=Host(`a.example.com`,`b.example.com`)"}"#,
+        "\n\n",
+        r#"{"ID":"9c2e","Names":"cron","Image":"","Status":"Exited (0) 3 days ago","Ports":"","Labels":""}"#,
+        "\n",
+    );
+    let containers = discovery::parse_docker_ps(output, "vps").unwrap();
+    assert_eq!(containers.len(), 2);
+
+    let web = &containers[0];
+    assert_eq!(web.name, "web");
+    assert_eq!(web.server_id, "vps");
+    assert_eq!(web.image.as_deref(), Some("nginx:1.27"));
+    assert_eq!(web.status, ContainerStatus::Running);
+    assert_eq!(web.ports, vec!["0.0.0.0:80->80/tcp", ":::80->80/tcp"]);
+    let labels: pctrl_core::proxy_labels::Labels =
+        serde_json::from_str(web.labels.as_deref().unwrap()).unwrap();
+    assert_eq!(labels["com.docker.compose.project"], "shop");
+    assert_eq!(
+        labels["traefik.http.routers.web.rule"],
+        "Host(`a.example.com`,`b.example.com`)"
+    );
+
+    // Docker before 20.10: no State, status text instead
+    let cron = &containers[1];
+    assert_eq!(cron.status, ContainerStatus::Exited);
+    assert_eq!(cron.image, None);
+    assert!(cron.ports.is_empty());
+    assert_eq!(cron.labels, None);
+
+    assert_eq!(discovery::parse_docker_ps("", "vps").unwrap().len(), 0);
+    let err = discovery::parse_docker_ps("bash: docker: command not found", "vps").unwrap_err();
+    assert!(err.contains("command not found"));
+}
+
+#[test]
+fn test_status_and_ports() {
+    assert_eq!(discovery::status("created", ""), ContainerStatus::Stopped);
+    assert_eq!(discovery::status("dead", ""), ContainerStatus::Exited);
+    assert_eq!(discovery::status("Paused", ""), ContainerStatus::Paused);
+    assert_eq!(
+        discovery::status("", "Up 5 minutes (Paused)"),
+        ContainerStatus::Paused
+    );
+    assert_eq!(
+        discovery::status("", "Restarting (1) 4 seconds ago"),
+        ContainerStatus::Restarting
+    );
+    assert_eq!(discovery::status("", "???"), ContainerStatus::Unknown);
+
+    assert_eq!(
+        discovery::port_text(Some("127.0.0.1"), Some(8080), 80, "tcp"),
+        "127.0.0.1:8080->80/tcp"
+    );
+    assert_eq!(discovery::port_text(None, None, 53, "udp"), "53/udp");
+}
+
+#[test]
+fn test_vanished_and_cache_freshness() {
+    let ps = |ids: &[&str]| {
+        let output: Vec<String> = ids
+            .iter()
+            .map(|id| format!(r#"{{"ID":"{id}","Names":"{id}","State":"running"}}"#))
+            .collect();
+        discovery::parse_docker_ps(&output.join("\n"), "vps").unwrap()
+    };
+    let recorded = ps(&["a", "b", "c"]);
+    let discovered = ps(&["b", "d"]);
+    let gone: Vec<&str> = discovery::vanished(&recorded, &discovered)
+        .iter()
+        .map(|c| c.id.as_str())
+        .collect();
+    assert_eq!(gone, vec!["a", "c"]);
+
+    let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let cached = CachedDiscovery {
+        server_id: "vps".to_string(),
+        data_type: discovery::CONTAINERS.to_string(),
+        data: serde_json::to_string(&discovered).unwrap(),
+        fetched_at: "2024-05-01T11:58:00Z".to_string(),
+        expires_at: "2024-05-01T12:03:00Z".to_string(),
+    };
+    assert!(cached.is_fresh(now));
+    assert!(!cached.is_fresh(now + Duration::minutes(3)));
+    assert_eq!(cached.containers().len(), 2);
+
+    let broken = CachedDiscovery {
+        expires_at: "soon".to_string(),
+        data_type: "ports".to_string(),
+        ..cached
+    };
+    assert!(!broken.is_fresh(now));
+    assert!(broken.containers().is_empty());
+}
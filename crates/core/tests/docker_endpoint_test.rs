@@ -0,0 +1,122 @@
+use pctrl_core::docker_endpoint::{validate, DockerEndpoint};
+use pctrl_core::DockerHost;
+
+fn parse(url: &str) -> DockerEndpoint {
+    DockerEndpoint::parse(url).unwrap()
+}
+
+fn host(url: &str, tls: [Option<&str>; 3]) -> DockerHost {
+    let [ca, cert, key] = tls.map(|f| f.map(String::from));
+    DockerHost {
+        id: "h".into(),
+        name: "build".into(),
+        url: url.into(),
+        tls_ca: ca,
+        tls_cert: cert,
+        tls_key: key,
+    }
+}
+
+#[test]
+fn test_parses_every_scheme() {
+    assert_eq!(
+        parse("/var/run/docker.sock"),
+        DockerEndpoint::Unix("/var/run/docker.sock".into())
+    );
+    assert_eq!(
+        parse("unix:///run/user/1000/docker.sock"),
+        DockerEndpoint::Unix("/run/user/1000/docker.sock".into())
+    );
+    assert_eq!(
+        parse("tcp://1.2.3.4:2375"),
+        DockerEndpoint::Http {
+            host: "1.2.3.4".into(),
+            port: 2375
+        }
+    );
+    assert_eq!(
+        parse("HTTP://docker.internal"),
+        DockerEndpoint::Http {
+            host: "docker.internal".into(),
+            port: 2375
+        }
+    );
+    assert_eq!(
+        parse("https://[2001:db8::1]/"),
+        DockerEndpoint::Https {
+            host: "2001:db8::1".into(),
+            port: 2376
+        }
+    );
+    assert_eq!(
+        parse("ssh://deploy@web-1:2222"),
+        DockerEndpoint::Ssh {
+            user: Some("deploy".into()),
+            host: "web-1".into(),
+            port: Some(2222),
+            socket: "/var/run/docker.sock".into(),
+        }
+    );
+    assert_eq!(
+        parse("ssh://web-1/run/user/1000/docker.sock"),
+        DockerEndpoint::Ssh {
+            user: None,
+            host: "web-1".into(),
+            port: None,
+            socket: "/run/user/1000/docker.sock".into(),
+        }
+    );
+}
+
+#[test]
+fn test_address_host_and_display() {
+    let https = parse("https://[2001:db8::1]:2376");
+    assert_eq!(https.address().as_deref(), Some("[2001:db8::1]:2376"));
+    assert_eq!(https.host(), Some("2001:db8::1"));
+    assert_eq!(https.to_string(), "https://[2001:db8::1]:2376");
+
+    assert_eq!(parse("http://10.0.0.5").to_string(), "tcp://10.0.0.5:2375");
+    assert_eq!(parse("/var/run/docker.sock").host(), None);
+    assert_eq!(parse("/var/run/docker.sock").address(), None);
+
+    for url in ["ssh://deploy@web-1", "ssh://web-1:2222/run/docker.sock"] {
+        assert_eq!(parse(url).to_string(), url);
+    }
+    assert_eq!(parse("ssh://deploy@web-1").host(), Some("web-1"));
+}
+
+#[test]
+fn test_rejects_unsupported_urls() {
+    let err = |url: &str| DockerEndpoint::parse(url).unwrap_err().to_string();
+
+    assert_eq!(
+        err("ftp://host"),
+        "Docker error: Invalid Docker host 'ftp://host': unsupported scheme 'ftp://'; \
+         use unix://, tcp://, http://, https:// or ssh://"
+    );
+    assert!(err("1.2.3.4:2375").contains("expected unix://"));
+    assert!(err("tcp://").contains("no host"));
+    assert!(err("tcp://host:99999").contains("invalid port '99999'"));
+    assert!(err("tcp://host:2375/v1.41").contains("unexpected path"));
+    assert!(err("https://[::1").contains("unclosed '['"));
+    assert!(err("ssh://@host").contains("empty user"));
+    assert!(err("unix://").contains("no socket path"));
+}
+
+#[test]
+fn test_validate_certificate_files() {
+    let files = [Some("ca.pem"), Some("cert.pem"), Some("key.pem")];
+    assert!(validate(&host("https://build:2376", files)).is_ok());
+    // Default files from ~/.docker
+    assert!(validate(&host("https://build:2376", [None; 3])).is_ok());
+
+    let err = validate(&host("https://build:2376", [Some("ca.pem"), None, None]))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("together"), "{}", err);
+
+    let err = validate(&host("tcp://build:2375", files))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("only apply to https://"), "{}", err);
+}
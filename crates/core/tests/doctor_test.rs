@@ -0,0 +1,271 @@
+use pctrl_core::doctor::{
+    case_duplicates, fuzzy_pick, likely_meant, plan, Candidate, Choice, Finding, FindingKind,
+    RefSite, Repair,
+};
+use pctrl_core::EntityType;
+
+fn entity(id: &str, name: &str) -> Candidate {
+    Candidate {
+        id: id.to_string(),
+        name: name.to_string(),
+    }
+}
+
+fn site(table: &str, column: &str, row: &str, required: bool) -> RefSite {
+    RefSite {
+        relation: table.to_string(),
+        table: table.to_string(),
+        column: column.to_string(),
+        key: "id".to_string(),
+        row: row.to_string(),
+        row_name: row.to_string(),
+        owner: None,
+        required,
+        link: table == "project_resources",
+    }
+}
+
+fn servers() -> Vec<Candidate> {
+    vec![entity("web-1", "Web 1"), entity("db-1", "db-1")]
+}
+
+fn credentials() -> Vec<Candidate> {
+    vec![entity("c-7f3a", "Deploy Key")]
+}
+
+#[test]
+fn test_classification() {
+    let optional = Finding::dangling(
+        EntityType::Server,
+        site("domains", "server_id", "shop", false),
+        "gone",
+        &servers(),
+    );
+    assert_eq!(optional.kind, FindingKind::DanglingRef);
+    assert_eq!(
+        optional.choices(),
+        vec![Choice::Relink, Choice::Null, Choice::Skip]
+    );
+
+    let required = Finding::dangling(
+        EntityType::Server,
+        site("containers", "server_id", "nginx", true),
+        "gone",
+        &servers(),
+    );
+    assert_eq!(required.kind, FindingKind::MissingParent);
+    assert_eq!(required.choices(), vec![Choice::Relink, Choice::Skip]);
+
+    let link = Finding::dangling(
+        EntityType::Domain,
+        site("project_resources", "resource_id", "link-1", true),
+        "old.example.com",
+        &[],
+    );
+    assert_eq!(link.kind, FindingKind::OrphanedLink);
+    assert_eq!(
+        link.choices(),
+        vec![Choice::Relink, Choice::Delete, Choice::Skip]
+    );
+
+    let duplicate = &case_duplicates(
+        EntityType::Server,
+        &[entity("a", "Web"), entity("b", "web"), entity("c", "db")],
+    )[0];
+    assert_eq!(duplicate.kind, FindingKind::CaseDuplicate);
+    assert_eq!(duplicate.choices(), vec![Choice::Skip]);
+    assert_eq!(duplicate.candidates.len(), 2);
+}
+
+#[test]
+fn test_likely_meant() {
+    let entities = vec![
+        entity("web-1", "Web 1"),
+        entity("c-7f3a", "Deploy Key"),
+        entity("api", "API Gateway"),
+    ];
+    let ids = |value: &str| -> Vec<String> {
+        likely_meant(value, &entities)
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    };
+
+    assert_eq!(ids("WEB-1"), vec!["web-1"], "ID in another case");
+    assert_eq!(ids("deploy key"), vec!["c-7f3a"], "name typed in a link");
+    assert_eq!(ids("api-gateway"), vec!["api"], "ID made from the old name");
+    assert!(ids("gone").is_empty());
+}
+
+#[test]
+fn test_case_duplicates() {
+    let findings = case_duplicates(
+        EntityType::Project,
+        &[
+            entity("1", "Shop"),
+            entity("2", "Blog"),
+            entity("3", "SHOP"),
+            entity("4", "shop"),
+        ],
+    );
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].value, "shop");
+    let ids: Vec<&str> = findings[0]
+        .candidates
+        .iter()
+        .map(|c| c.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["1", "3", "4"]);
+    assert!(case_duplicates(EntityType::Project, &servers()).is_empty());
+}
+
+#[test]
+fn test_plan_orders_parents_first() {
+    let findings = vec![
+        Finding::dangling(
+            EntityType::Script,
+            site("project_resources", "resource_id", "l-1", true),
+            "backup",
+            &[],
+        ),
+        Finding::dangling(
+            EntityType::Server,
+            site("domains", "server_id", "shop", false),
+            "gone",
+            &servers(),
+        ),
+        Finding::dangling(
+            EntityType::Server,
+            site("containers", "server_id", "nginx", true),
+            "gone",
+            &servers(),
+        ),
+        Finding::dangling(
+            EntityType::Credential,
+            site("servers", "credential_id", "web-1", false),
+            "deploy-key",
+            &credentials(),
+        ),
+    ];
+
+    let order: Vec<(EntityType, FindingKind)> = plan(findings)
+        .iter()
+        .map(|s| (s.finding.target, s.finding.kind))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (EntityType::Credential, FindingKind::DanglingRef),
+            (EntityType::Server, FindingKind::MissingParent),
+            (EntityType::Server, FindingKind::DanglingRef),
+            (EntityType::Script, FindingKind::OrphanedLink),
+        ]
+    );
+}
+
+#[test]
+fn test_plan_repairs() {
+    let steps = plan(vec![
+        // Renamed credential: relink instead of clearing the reference
+        Finding::dangling(
+            EntityType::Credential,
+            site("servers", "credential_id", "web-1", false),
+            "deploy-key",
+            &credentials(),
+        ),
+        Finding::dangling(
+            EntityType::Server,
+            site("domains", "server_id", "shop", false),
+            "gone",
+            &servers(),
+        ),
+        Finding::dangling(
+            EntityType::Server,
+            site("containers", "server_id", "nginx", true),
+            "gone",
+            &servers(),
+        ),
+        Finding::dangling(
+            EntityType::Domain,
+            site("project_resources", "resource_id", "l-1", true),
+            "old.example.com",
+            &[],
+        ),
+    ]);
+
+    assert_eq!(
+        steps[0].repair,
+        Some(Repair::Relink(entity("c-7f3a", "Deploy Key")))
+    );
+    assert!(steps[0].note.as_deref().unwrap().contains("Deploy Key"));
+    // Required reference without a candidate: only a person can decide
+    assert_eq!(steps[1].finding.kind, FindingKind::MissingParent);
+    assert_eq!(steps[1].repair, None);
+    assert_eq!(steps[2].repair, Some(Repair::Null));
+    assert_eq!(steps[3].repair, Some(Repair::Delete));
+}
+
+#[test]
+fn test_plan_never_relinks_ambiguous_names() {
+    let servers = vec![entity("a", "Web"), entity("b", "web"), entity("c", "db")];
+    let mut findings = case_duplicates(EntityType::Server, &servers);
+    findings.push(Finding::dangling(
+        EntityType::Server,
+        site("domains", "server_id", "shop", false),
+        "WEB",
+        &servers,
+    ));
+    findings.push(Finding::dangling(
+        EntityType::Server,
+        site("databases", "server_id", "pg", false),
+        "A",
+        &servers,
+    ));
+    findings.push(Finding::dangling(
+        EntityType::Server,
+        site("scripts", "server_id", "backup", false),
+        "DB",
+        &servers,
+    ));
+
+    let steps = plan(findings);
+    assert_eq!(steps[0].finding.kind, FindingKind::CaseDuplicate);
+    assert_eq!(steps[0].repair, None);
+
+    let shop = steps
+        .iter()
+        .find(|s| s.finding.value == "WEB")
+        .expect("dangling web reference");
+    assert_eq!(shop.repair, None, "Web and web are both candidates");
+    assert!(shop.note.as_deref().unwrap().contains("any of"));
+
+    // An ID in another case is a clear match despite the duplicate names
+    let pg = steps.iter().find(|s| s.finding.value == "A").unwrap();
+    assert_eq!(pg.repair, Some(Repair::Relink(entity("a", "Web"))));
+
+    let backup = steps.iter().find(|s| s.finding.value == "DB").unwrap();
+    assert_eq!(backup.repair, Some(Repair::Relink(entity("c", "db"))));
+}
+
+#[test]
+fn test_fuzzy_pick() {
+    let entities = vec![
+        entity("1", "web-1"),
+        entity("2", "web-10"),
+        entity("3", "staging-web"),
+        entity("4", "db-primary"),
+    ];
+    let names = |query: &str| -> Vec<&str> {
+        fuzzy_pick(query, &entities)
+            .into_iter()
+            .map(|c| c.name.as_str())
+            .collect()
+    };
+
+    assert_eq!(names("web-1"), vec!["web-1", "web-10"]);
+    assert_eq!(names("WEB"), vec!["web-1", "web-10", "staging-web"]);
+    assert_eq!(names("dbp"), vec!["db-primary"]);
+    assert_eq!(names("4"), vec!["db-primary"], "matches IDs too");
+    assert!(names("mail").is_empty());
+    assert_eq!(names("").len(), 4);
+}
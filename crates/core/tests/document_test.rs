@@ -0,0 +1,344 @@
+use pctrl_core::document::{
+    interpolate, merge, parse_var, references, resolve, DocumentError, DocumentSource,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Files and environment in memory
+#[derive(Default)]
+struct Files {
+    files: HashMap<PathBuf, String>,
+    env: HashMap<String, String>,
+}
+
+impl Files {
+    fn new(files: &[(&str, &str)]) -> Self {
+        Self {
+            files: files
+                .iter()
+                .map(|(path, text)| (PathBuf::from(path), text.to_string()))
+                .collect(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+impl DocumentSource for Files {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+    }
+
+    fn env(&self, name: &str) -> Option<String> {
+        self.env.get(name).cloned()
+    }
+}
+
+fn table(text: &str) -> Table {
+    text.parse().unwrap()
+}
+
+fn no_vars() -> BTreeMap<String, String> {
+    BTreeMap::new()
+}
+
+#[test]
+fn test_interpolate() {
+    let vars = |name: &str| match name {
+        "base" => Some("example.com".to_string()),
+        _ => None,
+    };
+    assert_eq!(interpolate("api.${base}", vars).unwrap(), "api.example.com");
+    assert_eq!(
+        interpolate("${ base }/${base}", vars).unwrap(),
+        "example.com/example.com"
+    );
+    // Escaped, lone and unterminated dollars stay
+    assert_eq!(
+        interpolate("$${base} $5 ${base", vars).unwrap(),
+        "${base} $5 ${base"
+    );
+    assert_eq!(interpolate("x ${nope} ${base}", vars).unwrap_err(), "nope");
+
+    assert_eq!(
+        references("${a}-${env:HOME}-$${b}"),
+        vec!["a".to_string(), "env:HOME".to_string()]
+    );
+}
+
+#[test]
+fn test_merge_overrides_scalars_and_merges_tables() {
+    let mut base = table(
+        r#"
+        name = "personal"
+        [settings]
+        theme = "dark"
+        interval = 60
+        "#,
+    );
+    merge(
+        &mut base,
+        table(
+            r#"
+            name = "work"
+            [settings]
+            interval = 30
+            "#,
+        ),
+    );
+    assert_eq!(
+        base,
+        table(
+            r#"
+            name = "work"
+            [settings]
+            theme = "dark"
+            interval = 30
+            "#
+        )
+    );
+}
+
+#[test]
+fn test_merge_appends_lists_unless_replaced() {
+    let mut base = table(
+        r#"
+        tags = ["a"]
+        [[servers]]
+        name = "web"
+        "#,
+    );
+    merge(
+        &mut base,
+        table(
+            r#"
+            tags = ["b"]
+            [[servers]]
+            name = "db"
+            "#,
+        ),
+    );
+    assert_eq!(base["tags"], Value::from(vec!["a", "b"]));
+    assert_eq!(base["servers"].as_array().unwrap().len(), 2);
+
+    merge(
+        &mut base,
+        table(
+            r#"
+            "tags!replace" = ["c"]
+            [["servers!replace"]]
+            name = "only"
+            "#,
+        ),
+    );
+    assert_eq!(base["tags"], Value::from(vec!["c"]));
+    let servers = base["servers"].as_array().unwrap();
+    assert_eq!(servers.len(), 1);
+    assert_eq!(servers[0]["name"].as_str(), Some("only"));
+
+    // Nothing to replace: the marker is still dropped
+    let mut empty = Table::new();
+    merge(&mut empty, table("[settings]\n\"hosts!replace\" = [\"x\"]"));
+    assert_eq!(empty, table("[settings]\nhosts = [\"x\"]"));
+}
+
+#[test]
+fn test_includes_merge_in_order_and_the_including_file_wins() {
+    let files = Files::new(&[
+        (
+            "cfg/infra.toml",
+            r#"
+            include = ["common.toml", "shared/servers.toml"]
+            [vars]
+            host = "work.internal"
+            base = "work.example.com"
+            [[domains]]
+            domain = "app.${base}"
+            "#,
+        ),
+        (
+            "cfg/common.toml",
+            r#"
+            include = ["shared/servers.toml"]
+            owner = "common"
+            [vars]
+            base = "example.com"
+            region = "fsn1"
+            "#,
+        ),
+        (
+            "cfg/shared/servers.toml",
+            r#"
+            owner = "servers"
+            [[servers]]
+            name = "web"
+            host = "${host}"
+            location = "${region}"
+            [[domains]]
+            domain = "www.${base}"
+            "#,
+        ),
+    ]);
+
+    let doc = resolve(Path::new("cfg/./infra.toml"), &no_vars(), &files).unwrap();
+    assert!(!doc.contains_key("include") && !doc.contains_key("vars"));
+    // servers.toml counts once, before common.toml, which includes it
+    assert_eq!(doc["owner"].as_str(), Some("common"));
+    // Variables come from the merged [vars], resolved everywhere
+    assert_eq!(doc["servers"][0]["host"].as_str(), Some("work.internal"));
+    assert_eq!(doc["servers"][0]["location"].as_str(), Some("fsn1"));
+    let domains: Vec<&str> = doc["domains"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["domain"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        domains,
+        vec!["www.work.example.com", "app.work.example.com"]
+    );
+}
+
+#[test]
+fn test_cli_vars_and_environment() {
+    let mut files = Files::new(&[(
+        "infra.toml",
+        r#"
+        [vars]
+        base = "example.com"
+        api = "api.${base}"
+        [settings]
+        url = "https://${api}"
+        token_file = "${env:HOME}/.token"
+        "#,
+    )]);
+    files.env.insert("HOME".into(), "/home/ops".into());
+
+    let doc = resolve(Path::new("infra.toml"), &no_vars(), &files).unwrap();
+    assert_eq!(
+        doc["settings"]["url"].as_str(),
+        Some("https://api.example.com")
+    );
+    assert_eq!(
+        doc["settings"]["token_file"].as_str(),
+        Some("/home/ops/.token")
+    );
+
+    let mut overrides = BTreeMap::new();
+    overrides.insert("base".to_string(), "example.io".to_string());
+    let doc = resolve(Path::new("infra.toml"), &overrides, &files).unwrap();
+    assert_eq!(
+        doc["settings"]["url"].as_str(),
+        Some("https://api.example.io")
+    );
+
+    files.env.clear();
+    let err = resolve(Path::new("infra.toml"), &no_vars(), &files).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "infra.toml:7: environment variable HOME is not set"
+    );
+}
+
+#[test]
+fn test_unresolved_variables_point_at_file_and_line() {
+    let files = Files::new(&[
+        ("infra.toml", "include = [\"common.toml\"]\nname = \"x\"\n"),
+        (
+            "common.toml",
+            "[[servers]]\nname = \"web\"\nhost = \"web.${base_domain}\"\n",
+        ),
+    ]);
+    let err = resolve(Path::new("infra.toml"), &no_vars(), &files).unwrap_err();
+    assert_eq!(
+        err,
+        DocumentError::Unresolved {
+            path: PathBuf::from("common.toml"),
+            line: Some(3),
+            name: "base_domain".to_string(),
+        }
+    );
+    assert!(err
+        .to_string()
+        .starts_with("common.toml:3: unresolved variable ${base_domain}"));
+
+    // In a --var value
+    let mut overrides = BTreeMap::new();
+    overrides.insert("base_domain".to_string(), "${missing}".to_string());
+    let err = resolve(Path::new("infra.toml"), &overrides, &files).unwrap_err();
+    assert!(
+        matches!(err, DocumentError::Unresolved { ref path, line: None, .. } if path == Path::new("--var"))
+    );
+}
+
+#[test]
+fn test_cycles_are_errors() {
+    let files = Files::new(&[
+        ("a.toml", "include = [\"b.toml\"]"),
+        ("b.toml", "include = [\"sub/../a.toml\"]"),
+    ]);
+    let err = resolve(Path::new("a.toml"), &no_vars(), &files).unwrap_err();
+    assert_eq!(
+        err,
+        DocumentError::IncludeCycle(vec!["a.toml".into(), "b.toml".into(), "a.toml".into()])
+    );
+    assert_eq!(err.to_string(), "include cycle: a.toml → b.toml → a.toml");
+
+    let files = Files::new(&[("v.toml", "[vars]\na = \"${b}\"\nb = \"x${a}\"")]);
+    let err = resolve(Path::new("v.toml"), &no_vars(), &files).unwrap_err();
+    assert_eq!(
+        err,
+        DocumentError::VarCycle(vec!["a".into(), "b".into(), "a".into()])
+    );
+}
+
+#[test]
+fn test_malformed_documents() {
+    let files = Files::new(&[
+        ("bad.toml", "name = "),
+        ("include.toml", "include = \"common.toml\""),
+        ("vars.toml", "[vars]\nlist = [1]"),
+        ("missing.toml", "include = [\"nope.toml\"]"),
+    ]);
+    let err = |path: &str| {
+        resolve(Path::new(path), &no_vars(), &files)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(
+        err("bad.toml").starts_with("bad.toml: "),
+        "{}",
+        err("bad.toml")
+    );
+    assert_eq!(
+        err("include.toml"),
+        "include.toml: include must be a list of file names"
+    );
+    assert_eq!(
+        err("vars.toml"),
+        "vars.toml: var 'list' must be a string, number or boolean"
+    );
+    assert_eq!(err("missing.toml"), "nope.toml: can't read: not found");
+}
+
+#[test]
+fn test_parse_var() {
+    assert_eq!(
+        parse_var("base=example.com"),
+        Ok(("base".to_string(), "example.com".to_string()))
+    );
+    assert_eq!(
+        parse_var("url=https://x?a=b"),
+        Ok(("url".to_string(), "https://x?a=b".to_string()))
+    );
+    assert_eq!(
+        parse_var("empty="),
+        Ok(("empty".to_string(), String::new()))
+    );
+    assert!(parse_var("novalue").is_err());
+    assert!(parse_var("=x").is_err());
+}
@@ -0,0 +1,185 @@
+use pctrl_core::domain_base::{normalize_base, rebase, replace_url_host, url_host, BasePlan};
+use pctrl_core::{Domain, DomainType};
+
+fn domain(name: &str) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: Some("2026-06-01".to_string()),
+        cloudflare_zone_id: Some("zone".to_string()),
+        cloudflare_record_id: Some("record".to_string()),
+        server_id: Some("srv-web".to_string()),
+        container_id: Some("shop-app".to_string()),
+        notes: Some("main shop".to_string()),
+        superseded_by: None,
+    }
+}
+
+#[test]
+fn test_rebase_matches_whole_labels_only() {
+    let (old, new) = ("example.com", "example.io");
+    assert_eq!(
+        rebase("app.example.com", old, new).as_deref(),
+        Some("app.example.io")
+    );
+    assert_eq!(
+        rebase("api.eu.example.com", old, new).as_deref(),
+        Some("api.eu.example.io")
+    );
+    assert_eq!(
+        rebase("example.com", old, new).as_deref(),
+        Some("example.io")
+    );
+    assert_eq!(
+        rebase("App.Example.COM.", old, new).as_deref(),
+        Some("app.example.io")
+    );
+
+    // Substring but not a label boundary
+    assert_eq!(rebase("myexample.com", old, new), None);
+    assert_eq!(rebase("app.notexample.com", old, new), None);
+    assert_eq!(rebase("example.com.au", old, new), None);
+    assert_eq!(rebase(".example.com", old, new), None);
+}
+
+#[test]
+fn test_normalize_base() {
+    assert_eq!(normalize_base(" .Example.COM. ").unwrap(), "example.com");
+    assert!(normalize_base("").is_err());
+    assert!(normalize_base("...").is_err());
+    assert!(normalize_base("exa mple.com").is_err());
+    assert!(normalize_base("example..com").is_err());
+    assert!(normalize_base("-example.com").is_err());
+}
+
+#[test]
+fn test_url_host_replacement() {
+    assert_eq!(
+        url_host("https://app.example.com/health"),
+        Some("app.example.com")
+    );
+    assert_eq!(
+        url_host("http://user:pw@app.example.com:8080/x?y#z"),
+        Some("app.example.com")
+    );
+    assert_eq!(url_host("app.example.com/health"), None);
+
+    assert_eq!(
+        replace_url_host(
+            "https://App.Example.com:8443/health?deep=1",
+            "app.example.com",
+            "app.example.io"
+        )
+        .as_deref(),
+        Some("https://app.example.io:8443/health?deep=1")
+    );
+    assert_eq!(
+        replace_url_host(
+            "https://app.example.com",
+            "app.example.com",
+            "app.example.io"
+        )
+        .as_deref(),
+        Some("https://app.example.io")
+    );
+    // Only the host is touched, not paths that mention the name
+    assert_eq!(
+        replace_url_host(
+            "https://status.example.io/app.example.com",
+            "app.example.com",
+            "x"
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_plan_reports_collisions_and_skips_superseded() {
+    let mut old_blog = domain("blog.example.com");
+    old_blog.superseded_by = Some("blog-example-io".to_string());
+    let domains = vec![
+        domain("shop.example.com"),
+        domain("api.example.com"),
+        // Already exists under the new base
+        domain("api.example.io"),
+        old_blog,
+        domain("blog.example.io"),
+        domain("myexample.com"),
+    ];
+
+    let plan = BasePlan::new(&domains, "Example.com", ".example.io").unwrap();
+    assert_eq!(plan.old_base, "example.com");
+    assert_eq!(plan.new_base, "example.io");
+
+    let renames: Vec<(&str, &str, Option<&str>)> = plan
+        .renames
+        .iter()
+        .map(|r| {
+            (
+                r.domain.domain.as_str(),
+                r.to.as_str(),
+                r.collision.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        renames,
+        [
+            ("api.example.com", "api.example.io", Some("api.example.io")),
+            ("shop.example.com", "shop.example.io", None),
+        ]
+    );
+    assert_eq!(plan.applicable().count(), 1);
+    assert_eq!(plan.collisions().count(), 1);
+
+    // Found by old ID or name, but not when it collides
+    assert_eq!(
+        plan.renamed("shop-example-com").unwrap().to,
+        "shop.example.io"
+    );
+    assert_eq!(
+        plan.renamed("SHOP.example.com").unwrap().to,
+        "shop.example.io"
+    );
+    assert!(plan.renamed("api.example.com").is_none());
+}
+
+#[test]
+fn test_plan_id_collision_and_new_row() {
+    // Another domain already uses the ID the new row would get
+    let mut squatter = domain("legacy.example.net");
+    squatter.id = "shop-example-io".to_string();
+    let plan = BasePlan::new(
+        &[domain("shop.example.com"), squatter],
+        "example.com",
+        "example.io",
+    )
+    .unwrap();
+    assert_eq!(
+        plan.renames[0].collision.as_deref(),
+        Some("legacy.example.net")
+    );
+
+    let plan = BasePlan::new(&[domain("shop.example.com")], "example.com", "example.io").unwrap();
+    let new = plan.renames[0].new_domain();
+    assert_eq!(new.id, "shop-example-io");
+    assert_eq!(new.domain, "shop.example.io");
+    assert_eq!(new.server_id.as_deref(), Some("srv-web"));
+    assert_eq!(new.container_id.as_deref(), Some("shop-app"));
+    assert_eq!(new.notes.as_deref(), Some("main shop"));
+    assert!(new.ssl);
+    // Certificate and Cloudflare record belong to the old name
+    assert_eq!(new.ssl_expiry, None);
+    assert_eq!(new.cloudflare_zone_id, None);
+    assert_eq!(new.cloudflare_record_id, None);
+}
+
+#[test]
+fn test_plan_rejects_same_or_nested_base() {
+    assert!(BasePlan::new(&[], "example.com", "EXAMPLE.com").is_err());
+    assert!(BasePlan::new(&[], "example.com", "shop.example.com").is_err());
+    // Moving up a level is fine
+    assert!(BasePlan::new(&[], "shop.example.com", "example.com").is_ok());
+}
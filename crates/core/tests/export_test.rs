@@ -0,0 +1,188 @@
+mod common;
+
+use pctrl_core::export::{
+    ansible_inventory, group_name, splice_managed_section, ssh_config, ExportHost, GroupBy,
+    SshAccess, SSH_CONFIG_BEGIN, SSH_CONFIG_END,
+};
+use pctrl_core::{Credential, CredentialData, CredentialType, Server};
+
+fn server(name: &str, host: &str) -> Server {
+    Server {
+        host: host.to_string(),
+        ..common::server(name)
+    }
+}
+
+fn key(user: &str, port: u16, path: &str) -> Credential {
+    Credential::new_ssh(
+        "key".to_string(),
+        "key".to_string(),
+        user.to_string(),
+        path.to_string(),
+        Some(port),
+        None,
+    )
+}
+
+fn hosts() -> Vec<ExportHost> {
+    let agent = Credential {
+        id: "agent".to_string(),
+        name: "agent".to_string(),
+        credential_type: CredentialType::SshAgent,
+        data: CredentialData::SshAgent {
+            username: "root".to_string(),
+            port: 22,
+        },
+        notes: None,
+    };
+
+    let mut web = ExportHost::new(
+        &server("web-1", "203.0.113.10"),
+        Some(&key("deploy", 2222, "/home/me/.ssh/id_ed25519")),
+    );
+    web.groups = vec!["Hetzner Cloud".to_string(), "shop".to_string()];
+    let mut db = ExportHost::new(&server("db 1", "10.0.0.5"), None);
+    db.groups = vec!["shop".to_string()];
+    let edge = ExportHost::new(&server("edge", "edge.example.com"), Some(&agent));
+
+    vec![web, db, edge]
+}
+
+#[test]
+fn test_ssh_access_from_credential() {
+    assert_eq!(
+        SshAccess::from_credential(&key("deploy", 2222, "/k")),
+        Some(SshAccess {
+            user: "deploy".to_string(),
+            port: 2222,
+            identity_file: Some("/k".to_string()),
+        })
+    );
+
+    let token = Credential::new_api_token("t".into(), "t".into(), "secret".into(), None);
+    assert_eq!(SshAccess::from_credential(&token), None);
+    assert_eq!(ExportHost::new(&server("a", "h"), Some(&token)).ssh, None);
+}
+
+#[test]
+fn test_ansible_inventory() {
+    assert_eq!(
+        ansible_inventory(&hosts()),
+        include_str!("fixtures/ansible_inventory.yml")
+    );
+    assert_eq!(
+        ansible_inventory(&[]),
+        "# Generated by pctrl export ansible\nall:\n  hosts: {}\n"
+    );
+}
+
+#[test]
+fn test_ssh_config() {
+    assert_eq!(
+        ssh_config(&hosts()),
+        include_str!("fixtures/ssh_config.txt")
+    );
+
+    let empty = ssh_config(&[]);
+    assert!(empty.starts_with(SSH_CONFIG_BEGIN));
+    assert!(empty.ends_with(&format!("{}\n", SSH_CONFIG_END)));
+}
+
+#[test]
+fn test_group_names() {
+    assert_eq!(group_name("Hetzner Cloud"), "hetzner_cloud");
+    assert_eq!(group_name("my-shop"), "my_shop");
+    assert_eq!(group_name("2024 launch"), "_2024_launch");
+    assert_eq!(group_name(""), "_");
+    assert_eq!("type".parse::<GroupBy>(), Ok(GroupBy::Type));
+    assert!("tag".parse::<GroupBy>().is_err());
+}
+
+fn section(body: &str) -> String {
+    format!("{}\n{}\n{}\n", SSH_CONFIG_BEGIN, body, SSH_CONFIG_END)
+}
+
+#[test]
+fn test_splice_into_empty_or_unmanaged_file() {
+    let new = section("Host a");
+    assert_eq!(splice_managed_section("", &new).unwrap(), new);
+
+    // Appended after a blank line; the user's content is untouched
+    let user = "Host github.com\n    User git\n";
+    assert_eq!(
+        splice_managed_section(user, &new).unwrap(),
+        format!("{}\n{}", user, new)
+    );
+    assert_eq!(
+        splice_managed_section("Host x", &new).unwrap(),
+        format!("Host x\n\n{}", new)
+    );
+    assert_eq!(
+        splice_managed_section("Host x\n\n", &new).unwrap(),
+        format!("Host x\n\n{}", new)
+    );
+}
+
+#[test]
+fn test_splice_replaces_only_managed_section() {
+    let before = "# my settings\nHost github.com\n    User git\n\n";
+    let after = "\nHost *\n    ServerAliveInterval 60\n";
+    let existing = format!(
+        "{}{}{}",
+        before,
+        section("Host old\n    HostName 1.1.1.1"),
+        after
+    );
+
+    let new = section("Host new\n    HostName 2.2.2.2");
+    let spliced = splice_managed_section(&existing, &new).unwrap();
+    assert_eq!(spliced, format!("{}{}{}", before, new, after));
+
+    // Re-exporting the same section changes nothing
+    assert_eq!(splice_managed_section(&spliced, &new).unwrap(), spliced);
+}
+
+#[test]
+fn test_splice_keeps_crlf_and_missing_final_newline() {
+    let existing = format!(
+        "Host a\r\n{}\r\n# old\r\n{}\r\nHost b",
+        SSH_CONFIG_BEGIN, SSH_CONFIG_END
+    );
+    let new = section("# new");
+    assert_eq!(
+        splice_managed_section(&existing, &new).unwrap(),
+        format!("Host a\r\n{}Host b", new)
+    );
+
+    // Section at the very end, without a trailing newline
+    let existing = format!("Host a\n{}\n# old\n{}", SSH_CONFIG_BEGIN, SSH_CONFIG_END);
+    assert_eq!(
+        splice_managed_section(&existing, &new).unwrap(),
+        format!("Host a\n{}", new)
+    );
+}
+
+#[test]
+fn test_splice_refuses_unbalanced_markers() {
+    let new = section("Host a");
+    let cases = [
+        format!("{}\nHost a\n", SSH_CONFIG_BEGIN),
+        format!("Host a\n{}\n", SSH_CONFIG_END),
+        format!("{}\n{}\n", SSH_CONFIG_END, SSH_CONFIG_BEGIN),
+        format!("{}\n{}\n{}", new, "Host mine\n", new),
+    ];
+    for existing in cases {
+        assert!(
+            splice_managed_section(&existing, &new).is_err(),
+            "should refuse:\n{}",
+            existing
+        );
+    }
+
+    // Markers must be whole lines; mentions in comments don't count
+    let mention = format!("# see {} below\nHost x\n", SSH_CONFIG_BEGIN);
+    assert_eq!(
+        splice_managed_section(&mention, &new).unwrap(),
+        format!("{}\n{}", mention, new)
+    );
+}
@@ -0,0 +1,122 @@
+use pctrl_core::facts::{self, FactQuery, Facts, PackageManager};
+
+const UBUNTU: &str = include_str!("fixtures/facts_ubuntu.txt");
+const ALPINE: &str = include_str!("fixtures/facts_alpine.txt");
+const ROCKY: &str = include_str!("fixtures/facts_rocky.txt");
+
+fn facts_of(pairs: &[(&str, &str)]) -> Facts {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_ubuntu() {
+    let facts = facts::parse(UBUNTU);
+    assert_eq!(facts[facts::HOSTNAME], "web-1");
+    assert_eq!(facts[facts::DISTRO], "ubuntu");
+    assert_eq!(facts[facts::DISTRO_VERSION], "22.04");
+    assert_eq!(facts[facts::VIRTUALIZATION], "kvm");
+    assert_eq!(
+        facts[facts::PUBLIC_IPS],
+        "116.203.45.12,2a01:4f8:c012:3b5c::1"
+    );
+    assert_eq!(facts[facts::PRIVATE_IPS], "10.0.0.2,172.17.0.1");
+    assert_eq!(facts[facts::DOCKER_VERSION], "24.0.7");
+    assert_eq!(facts[facts::LISTENING_PORTS], "22,80,443,5432");
+    assert_eq!(facts[facts::TIMEZONE], "Etc/UTC");
+    assert!(!facts.contains_key("ip"));
+
+    assert_eq!(
+        PackageManager::from_facts(&facts),
+        Some(PackageManager::Apt)
+    );
+    assert_eq!(
+        facts::container_runtime(&facts).as_deref(),
+        Some("docker 24.0.7")
+    );
+    assert_eq!(
+        facts::os_summary(&facts).as_deref(),
+        Some("ubuntu 22.04 (apt)")
+    );
+}
+
+#[test]
+fn test_parse_alpine_skips_empty_values() {
+    let facts = facts::parse(ALPINE);
+    assert_eq!(facts[facts::ARCH], "aarch64");
+    assert!(!facts.contains_key(facts::DISTRO_LIKE));
+    assert!(!facts.contains_key(facts::VIRTUALIZATION));
+    assert!(!facts.contains_key(facts::PUBLIC_IPS));
+    assert_eq!(
+        facts[facts::PRIVATE_IPS],
+        "192.168.1.20,fe80::be24:11ff:fe9a:1"
+    );
+    assert_eq!(
+        PackageManager::from_facts(&facts),
+        Some(PackageManager::Apk)
+    );
+    assert_eq!(facts::container_runtime(&facts), None);
+}
+
+#[test]
+fn test_parse_rocky_keeps_unknown_keys() {
+    let facts = facts::parse(ROCKY);
+    // Quotes from os-release are removed, repeated values deduplicated
+    assert_eq!(facts[facts::DISTRO_LIKE], "rhel centos fedora");
+    assert_eq!(facts[facts::LISTENING_PORTS], "22,3306");
+    // Carrier-grade NAT counts as private
+    assert_eq!(facts[facts::PRIVATE_IPS], "100.101.7.3");
+    assert_eq!(facts[facts::PUBLIC_IPS], "203.0.113.40");
+    assert_eq!(facts["gpu"], "nvidia-a100");
+    assert!(!facts.keys().any(|k| k.contains(' ')));
+
+    assert_eq!(
+        PackageManager::from_facts(&facts),
+        Some(PackageManager::Dnf)
+    );
+    assert_eq!(
+        facts::container_runtime(&facts).as_deref(),
+        Some("podman 4.6.1")
+    );
+}
+
+#[test]
+fn test_package_manager_fallbacks() {
+    let centos7 = facts_of(&[("distro", "centos"), ("distro_version", "7")]);
+    assert_eq!(
+        PackageManager::from_facts(&centos7),
+        Some(PackageManager::Yum)
+    );
+
+    // Unknown derivative, known parent
+    let derivative = facts_of(&[("distro", "elementary"), ("distro_like", "ubuntu debian")]);
+    assert_eq!(
+        PackageManager::from_facts(&derivative),
+        Some(PackageManager::Apt)
+    );
+
+    assert_eq!(
+        PackageManager::from_facts(&facts_of(&[("distro", "nixos")])),
+        None
+    );
+    assert_eq!(PackageManager::from_facts(&Facts::new()), None);
+}
+
+#[test]
+fn test_fact_query() {
+    let facts = facts::parse(UBUNTU);
+
+    let query: FactQuery = "distro=Ubuntu".parse().unwrap();
+    assert!(query.matches(&facts));
+    let query: FactQuery = "listening_ports=443".parse().unwrap();
+    assert!(query.matches(&facts));
+    let query: FactQuery = "listening_ports=44".parse().unwrap();
+    assert!(!query.matches(&facts));
+    let query: FactQuery = "gpu=nvidia-a100".parse().unwrap();
+    assert!(!query.matches(&facts));
+
+    assert!("distro".parse::<FactQuery>().is_err());
+    assert!("=ubuntu".parse::<FactQuery>().is_err());
+}
@@ -0,0 +1,122 @@
+use pctrl_core::fanout::{
+    fan_out, Counts, FailOn, FanoutReport, Outcome, TargetResult, EXIT_TARGETS_FAILED,
+};
+
+fn result(name: &str, outcome: Outcome, message: Option<&str>) -> TargetResult {
+    TargetResult {
+        id: format!("{}-id", name),
+        name: name.to_string(),
+        outcome,
+        duration_ms: 120,
+        message: message.map(String::from),
+    }
+}
+
+fn counts(ok: usize, failed: usize, skipped: usize) -> Counts {
+    Counts {
+        ok,
+        failed,
+        skipped,
+    }
+}
+
+#[test]
+fn test_report_counts_and_summary() {
+    let mut report = FanoutReport::new("project start", "2024-06-01T12:00:00Z");
+    assert_eq!(report.summary(), "0 ok");
+
+    report.push(result("db", Outcome::Ok, Some("already running")));
+    report.push(result("api", Outcome::Failed, Some("api is unhealthy")));
+    report.extend([
+        TargetResult::skipped("web", "web", "an earlier phase failed"),
+        result("cache", Outcome::Ok, None),
+    ]);
+
+    assert_eq!(report.counts, counts(2, 1, 1));
+    assert_eq!(report.counts.total(), 4);
+    assert_eq!(report.summary(), "2 ok, 1 failed, 1 skipped");
+    let names: Vec<&str> = report.targets.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["db", "api", "web", "cache"]);
+    assert_eq!(report.targets[2].duration_ms, 0);
+}
+
+#[test]
+fn test_report_json_shape() {
+    let mut report = FanoutReport::new("monitor run", "2024-06-01T12:00:00Z");
+    report.push(result("web", Outcome::Failed, Some("connection refused")));
+    report.push(TargetResult::skipped("vpn", "vpn", "VPN wg0 not connected"));
+    report.duration_ms = 300;
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["command"], "monitor run");
+    assert_eq!(json["duration_ms"], 300);
+    assert_eq!(json["counts"]["failed"], 1);
+    assert_eq!(json["counts"]["skipped"], 1);
+    assert_eq!(json["targets"][0]["id"], "web-id");
+    assert_eq!(json["targets"][0]["outcome"], "failed");
+    assert_eq!(json["targets"][0]["message"], "connection refused");
+    assert_eq!(json["targets"][1]["outcome"], "skipped");
+
+    let back: FanoutReport = serde_json::from_value(json).unwrap();
+    assert_eq!(back, report);
+}
+
+#[test]
+fn test_fail_on_exit_codes() {
+    let cases = [
+        // (ok, failed, skipped), any, all, none
+        (counts(3, 0, 0), 0, 0, 0),
+        (counts(2, 1, 0), EXIT_TARGETS_FAILED, 0, 0),
+        (counts(0, 2, 0), EXIT_TARGETS_FAILED, EXIT_TARGETS_FAILED, 0),
+        (counts(0, 1, 3), EXIT_TARGETS_FAILED, EXIT_TARGETS_FAILED, 0),
+        (counts(0, 0, 2), 0, 0, 0),
+        (counts(0, 0, 0), 0, 0, 0),
+    ];
+    for (counts, any, all, none) in cases {
+        assert_eq!(FailOn::Any.exit_code(&counts), any, "any {:?}", counts);
+        assert_eq!(FailOn::All.exit_code(&counts), all, "all {:?}", counts);
+        assert_eq!(FailOn::None.exit_code(&counts), none, "none {:?}", counts);
+    }
+}
+
+#[test]
+fn test_fail_on_parse() {
+    assert_eq!("any".parse::<FailOn>().unwrap(), FailOn::Any);
+    assert_eq!("ALL".parse::<FailOn>().unwrap(), FailOn::All);
+    assert_eq!("none".parse::<FailOn>().unwrap(), FailOn::None);
+    assert!("some".parse::<FailOn>().is_err());
+    assert_eq!(FailOn::default(), FailOn::Any);
+    assert_eq!(FailOn::All.to_string(), "all");
+}
+
+#[tokio::test]
+async fn test_fan_out_keeps_target_order() {
+    let targets = vec![("a", 30u64), ("b", 0), ("c", 10)];
+    let results = fan_out(
+        &targets,
+        |(name, _)| (name.to_string(), name.to_uppercase()),
+        |(name, delay)| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(*delay)).await;
+            match *name {
+                "b" => (Outcome::Failed, Some("boom".to_string())),
+                _ => (Outcome::Ok, None),
+            }
+        },
+    )
+    .await;
+
+    let summary: Vec<(&str, &str, Outcome)> = results
+        .iter()
+        .map(|r| (r.id.as_str(), r.name.as_str(), r.outcome))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("a", "A", Outcome::Ok),
+            ("b", "B", Outcome::Failed),
+            ("c", "C", Outcome::Ok)
+        ]
+    );
+    assert_eq!(results[1].message.as_deref(), Some("boom"));
+    assert!(results[0].duration_ms >= 30);
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use pctrl_core::forecast::{forecast, linear_regression, Trend, MIN_SAMPLES};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// One sample per day for `days` days, usage from `f(day)`
+fn daily(days: i64, f: impl Fn(i64) -> f64) -> Vec<(DateTime<Utc>, f64)> {
+    (0..days)
+        .map(|d| (start() + Duration::days(d), f(d)))
+        .collect()
+}
+
+#[test]
+fn test_linear_regression() {
+    let (slope, intercept) = linear_regression(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+    assert!((slope - 2.0).abs() < 1e-9);
+    assert!((intercept - 1.0).abs() < 1e-9);
+
+    assert!(linear_regression(&[(1.0, 1.0)]).is_none());
+    assert!(linear_regression(&[(1.0, 1.0), (1.0, 2.0)]).is_none());
+}
+
+#[test]
+fn test_steady_growth() {
+    // 50% rising 1 point per day: 90% on day 40, 100% on day 50
+    let samples = daily(10, |d| 50.0 + d as f64);
+    let now = start() + Duration::days(9);
+    let f = forecast(&samples, now).unwrap();
+
+    assert_eq!(f.trend, Trend::Growing);
+    assert_eq!(f.current, 59.0);
+    assert!((f.per_week - 7.0).abs() < 1e-9);
+    assert_eq!(f.reaches_90, Some(start() + Duration::days(40)));
+    assert_eq!(f.reaches_100, Some(start() + Duration::days(50)));
+    assert!(!f.full_within(30, now));
+    assert!(f.full_within(45, now));
+}
+
+#[test]
+fn test_noisy_growth() {
+    let noise = [
+        0.8, -0.6, 0.3, -0.9, 0.5, -0.2, 0.7, -0.4, 0.1, -0.3, 0.6, -0.5, 0.2, -0.7,
+    ];
+    let samples = daily(14, |d| 40.0 + 0.5 * d as f64 + noise[d as usize]);
+    let f = forecast(&samples, start() + Duration::days(13)).unwrap();
+
+    assert_eq!(f.trend, Trend::Growing);
+    assert!((f.per_week - 3.5).abs() < 0.7, "per_week = {}", f.per_week);
+    let full = f.reaches_100.unwrap();
+    assert!(full > start() + Duration::days(100) && full < start() + Duration::days(140));
+}
+
+#[test]
+fn test_shrinking_and_constant() {
+    let shrinking = forecast(&daily(10, |d| 80.0 - d as f64), start() + Duration::days(9)).unwrap();
+    assert_eq!(shrinking.trend, Trend::Shrinking);
+    assert_eq!(shrinking.reaches_100, None);
+
+    let constant = forecast(&daily(10, |_| 62.5), start() + Duration::days(9)).unwrap();
+    assert_eq!(constant.trend, Trend::Flat);
+    assert_eq!(constant.per_week, 0.0);
+    assert_eq!(constant.reaches_90, None);
+}
+
+#[test]
+fn test_refuses_to_extrapolate_from_dense_short_series() {
+    // Two readings a minute apart, with a huge jump between them
+    let samples = vec![(start(), 10.0), (start() + Duration::minutes(1), 60.0)];
+    let f = forecast(&samples, start()).unwrap();
+    assert!(matches!(f.trend, Trend::InsufficientData { .. }));
+    assert_eq!(f.reaches_100, None);
+
+    // Many readings within one hour collapse into a single sample
+    let burst: Vec<_> = (0..50)
+        .map(|m| (start() + Duration::minutes(m), 10.0 + m as f64))
+        .collect();
+    let f = forecast(&burst, start()).unwrap();
+    assert_eq!(
+        f.trend,
+        Trend::InsufficientData {
+            samples: 1,
+            span_hours: 0
+        }
+    );
+
+    // Enough samples but only spread over a day
+    let hourly: Vec<_> = (0..24)
+        .map(|h| (start() + Duration::hours(h), 10.0 + h as f64))
+        .collect();
+    assert!(hourly.len() >= MIN_SAMPLES);
+    let f = forecast(&hourly, start()).unwrap();
+    assert!(matches!(f.trend, Trend::InsufficientData { .. }));
+
+    assert!(forecast(&[], start()).is_none());
+}
+
+#[test]
+fn test_already_full_and_far_future() {
+    let full = forecast(&daily(10, |d| 91.0 + d as f64), start() + Duration::days(9)).unwrap();
+    assert_eq!(full.reaches_90, Some(start() + Duration::days(9)));
+
+    // 0.2 points per week from 10%: centuries away, not reported
+    let slow = forecast(
+        &daily(30, |d| 10.0 + d as f64 * 0.03),
+        start() + Duration::days(29),
+    )
+    .unwrap();
+    assert_eq!(slow.trend, Trend::Growing);
+    assert_eq!(slow.reaches_100, None);
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, TimeZone, Utc};
+use pctrl_core::growth::{changes, periods, reconstruct, sparkline, Granularity, GrowthEvent};
+use pctrl_core::{AuditAction, EntityType};
+
+fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+}
+
+fn event(
+    entity_type: EntityType,
+    id: &str,
+    action: AuditAction,
+    time: DateTime<Utc>,
+) -> GrowthEvent {
+    GrowthEvent {
+        entity_type,
+        entity_id: id.to_string(),
+        action,
+        at: time,
+    }
+}
+
+#[test]
+fn test_period_boundaries() {
+    let time = Utc.with_ymd_and_hms(2025, 12, 18, 15, 30, 0).unwrap();
+    let month = Granularity::Month.start_of(time);
+    assert_eq!(month, Utc.with_ymd_and_hms(2025, 12, 1, 0, 0, 0).unwrap());
+    assert_eq!(
+        Granularity::Month.next(month),
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()
+    );
+    // Weeks start on Monday
+    assert_eq!(
+        Granularity::Week.start_of(time),
+        Utc.with_ymd_and_hms(2025, 12, 15, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        Granularity::Year.start_of(time),
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+    );
+    assert_eq!(Granularity::Month.label(month), "2025-12");
+    assert_eq!(Granularity::Day.label(time), "2025-12-18");
+
+    let months = periods(at(2025, 11, 20), at(2026, 2, 3), Granularity::Month);
+    let labels: Vec<String> = months
+        .iter()
+        .map(|p| Granularity::Month.label(*p))
+        .collect();
+    assert_eq!(labels, vec!["2025-11", "2025-12", "2026-01", "2026-02"]);
+    assert_eq!(
+        periods(at(2026, 2, 3), at(2026, 2, 3), Granularity::Day).len(),
+        1
+    );
+
+    assert_eq!("weekly".parse(), Ok(Granularity::Week));
+    assert!("fortnight".parse::<Granularity>().is_err());
+}
+
+#[test]
+fn test_repeated_and_unknown_changes() {
+    use AuditAction::*;
+    let events = vec![
+        // Older than the audit log: first seen being removed
+        event(EntityType::Server, "old", Removed, at(2025, 1, 2)),
+        // Trashed, then purged: removed once
+        event(EntityType::Server, "web", Created, at(2025, 1, 3)),
+        event(EntityType::Server, "web", Removed, at(2025, 1, 4)),
+        event(EntityType::Server, "web", Removed, at(2025, 1, 5)),
+        // Trashed and restored
+        event(EntityType::Server, "db", Created, at(2025, 1, 6)),
+        event(EntityType::Server, "db", Updated, at(2025, 1, 6)),
+        event(EntityType::Server, "db", Removed, at(2025, 1, 7)),
+        event(EntityType::Server, "db", Created, at(2025, 1, 8)),
+        // Same ID, other type
+        event(EntityType::Project, "web", Created, at(2025, 1, 9)),
+    ];
+    let deltas: Vec<(EntityType, i64)> = changes(&events)
+        .iter()
+        .map(|c| (c.entity_type, c.delta))
+        .collect();
+    assert_eq!(
+        deltas,
+        vec![
+            (EntityType::Server, -1),
+            (EntityType::Server, 1),
+            (EntityType::Server, -1),
+            (EntityType::Server, 1),
+            (EntityType::Server, -1),
+            (EntityType::Server, 1),
+            (EntityType::Project, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_reconstruct_counts_backwards_from_now() {
+    use AuditAction::*;
+    let events = vec![
+        event(EntityType::Server, "a", Created, at(2025, 1, 10)),
+        event(EntityType::Server, "b", Created, at(2025, 2, 10)),
+        event(EntityType::Server, "c", Created, at(2025, 2, 11)),
+        // Created before auditing, removed in March
+        event(EntityType::Server, "legacy", Removed, at(2025, 3, 1)),
+        event(EntityType::Domain, "d", Created, at(2025, 3, 5)),
+    ];
+    // Two servers that predate the log, one of them since removed
+    let current = [(EntityType::Server, 4), (EntityType::Domain, 1)];
+    let growth = reconstruct(
+        &current,
+        &events,
+        at(2025, 1, 1),
+        at(2025, 4, 15),
+        Granularity::Month,
+    );
+    assert_eq!(growth.periods.len(), 4);
+
+    let servers = &growth.series[0];
+    assert_eq!(servers.entity_type, EntityType::Server);
+    assert_eq!(servers.baseline, 2);
+    assert_eq!(servers.counts, vec![3, 5, 4, 4]);
+    assert_eq!(servers.created, vec![1, 2, 0, 0]);
+    assert_eq!(servers.removed, vec![0, 0, 1, 0]);
+    assert_eq!(servers.change(), 1);
+
+    let domains = &growth.series[1];
+    assert_eq!(domains.baseline, 0);
+    assert_eq!(domains.counts, vec![0, 0, 1, 1]);
+
+    // Starting later only drops the earlier periods
+    let later = reconstruct(
+        &current,
+        &events,
+        at(2025, 3, 20),
+        at(2025, 4, 15),
+        Granularity::Month,
+    );
+    assert_eq!(later.series[0].counts, vec![4, 4]);
+}
+
+#[test]
+fn test_counts_never_go_negative() {
+    // Audited creations of rows since deleted without an audit entry
+    let events = vec![
+        event(
+            EntityType::Script,
+            "a",
+            AuditAction::Created,
+            at(2025, 5, 1),
+        ),
+        event(
+            EntityType::Script,
+            "b",
+            AuditAction::Created,
+            at(2025, 6, 1),
+        ),
+    ];
+    let growth = reconstruct(
+        &[(EntityType::Script, 1)],
+        &events,
+        at(2025, 4, 1),
+        at(2025, 6, 2),
+        Granularity::Month,
+    );
+    assert_eq!(growth.series[0].counts, vec![0, 0, 1]);
+    assert_eq!(growth.series[0].baseline, 0);
+}
+
+#[test]
+fn test_sparkline() {
+    assert_eq!(sparkline(&[0, 1, 2, 3, 4, 5, 6, 7]), "▁▂▃▄▅▆▇█");
+    assert_eq!(sparkline(&[3, 3]), "██");
+    assert_eq!(sparkline(&[0, 0, 0]), "▁▁▁");
+    assert_eq!(sparkline(&[]), "");
+}
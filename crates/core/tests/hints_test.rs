@@ -0,0 +1,162 @@
+use pctrl_core::hints::{self, Context, Event, Failure, Listing, MAX_HINTS};
+
+fn commands(context: &Context) -> Vec<String> {
+    hints::suggest(context)
+        .into_iter()
+        .map(|h| h.command)
+        .collect()
+}
+
+fn server_added(credential: Option<&str>, specs: bool) -> Event {
+    Event::ServerAdded {
+        name: "web".to_string(),
+        host: "203.0.113.5".to_string(),
+        credential: credential.map(str::to_string),
+        specs,
+    }
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn test_server_added_ranks_credential_then_project() {
+    // Nothing to link yet: add a credential
+    let context = Context::new(server_added(None, false));
+    assert_eq!(
+        commands(&context),
+        vec!["pctrl credential add <name> --type ssh --user root --key ~/.ssh/id_rsa"]
+    );
+
+    // The only credential and project are filled in, names quoted
+    let mut context = Context::new(server_added(None, false));
+    context.credentials = names(&["deploy"]);
+    context.projects = names(&["Demo Shop"]);
+    assert_eq!(
+        commands(&context),
+        vec![
+            "pctrl server add web 203.0.113.5 -c deploy --ensure",
+            "pctrl project link 'Demo Shop' server web",
+        ]
+    );
+    assert_eq!(
+        hints::suggest(&context)[1].to_string(),
+        "Link it to Demo Shop with: pctrl project link 'Demo Shop' server web"
+    );
+
+    // Several candidates: placeholders, and no project guess
+    context.credentials = names(&["deploy", "backup"]);
+    context.projects = names(&["shop", "blog"]);
+    assert_eq!(
+        commands(&context),
+        vec!["pctrl server add web 203.0.113.5 -c <credential> --ensure"]
+    );
+}
+
+#[test]
+fn test_server_added_with_credential() {
+    let mut context = Context::new(server_added(Some("deploy"), true));
+    assert!(hints::suggest(&context).is_empty());
+
+    // Detection failed: run it again, ahead of the project link
+    context.event = server_added(Some("deploy"), false);
+    context.projects = names(&["shop"]);
+    assert_eq!(
+        commands(&context),
+        vec![
+            "pctrl server add web 203.0.113.5 -c deploy --ensure",
+            "pctrl project link shop server web",
+        ]
+    );
+}
+
+#[test]
+fn test_domain_added_without_server() {
+    let event = |server: Option<&str>| Event::DomainAdded {
+        domain: "shop.example.com".to_string(),
+        server: server.map(str::to_string),
+    };
+    let mut context = Context::new(event(None));
+    assert!(hints::suggest(&context).is_empty());
+
+    context.servers = names(&["web"]);
+    assert_eq!(
+        commands(&context),
+        vec!["pctrl domain add shop.example.com -s web --ensure"]
+    );
+
+    context.event = event(Some("web"));
+    context.projects = names(&["shop"]);
+    assert_eq!(
+        commands(&context),
+        vec!["pctrl project link shop domain shop.example.com"]
+    );
+}
+
+#[test]
+fn test_failed_status_points_at_the_cause() {
+    assert_eq!(
+        Failure::of("Credential 'deploy' not found"),
+        Failure::Credential
+    );
+    assert_eq!(
+        Failure::of("SSH error: Public key authentication failed: [-18]"),
+        Failure::Auth
+    );
+    assert_eq!(
+        Failure::of("SSH error: Could not resolve web.internal: failed to lookup"),
+        Failure::Unreachable
+    );
+
+    let event = |vpn, failure| Event::StatusFailed {
+        name: "web".to_string(),
+        credential: "deploy".to_string(),
+        vpn,
+        failure,
+    };
+    let context = Context::new(event(false, Failure::Credential));
+    assert_eq!(
+        commands(&context),
+        vec!["pctrl doctor", "pctrl credential show deploy"]
+    );
+    let context = Context::new(event(false, Failure::Auth));
+    assert_eq!(commands(&context), vec!["pctrl credential show deploy"]);
+    let context = Context::new(event(false, Failure::Unreachable));
+    assert_eq!(
+        commands(&context),
+        vec![
+            "pctrl server show web",
+            "pctrl server edit web --requires-vpn wg0"
+        ]
+    );
+    // Already behind a VPN: don't suggest marking it
+    let context = Context::new(event(true, Failure::Unreachable));
+    assert_eq!(commands(&context), vec!["pctrl server show web"]);
+}
+
+#[test]
+fn test_every_listing_has_one_hint() {
+    for listing in [
+        Listing::Projects,
+        Listing::Servers,
+        Listing::Domains,
+        Listing::Databases,
+        Listing::Scripts,
+        Listing::Services,
+        Listing::Credentials,
+        Listing::Snapshots,
+        Listing::CoolifyInstances,
+        Listing::DockerHosts,
+        Listing::VpnServers,
+    ] {
+        let found = hints::suggest(&Context::new(Event::Empty(listing)));
+        assert_eq!(found.len(), 1, "{:?}", listing);
+        assert!(found[0].command.starts_with("pctrl "));
+        assert!(found.len() <= MAX_HINTS);
+    }
+    let found = hints::suggest(&Context::new(Event::Empty(Listing::Databases)));
+    assert!(found[0]
+        .to_string()
+        .starts_with("Add one with: pctrl database add"));
+}
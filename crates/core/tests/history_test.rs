@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use pctrl_core::diff::{to_details, FieldChange};
+use pctrl_core::history::{fields, reconstruct, timeline, Gap, Uncertainty};
+use pctrl_core::{AuditAction, AuditEntry, EntityType};
+use serde_json::{json, Value};
+
+fn entry(id: i64, action: AuditAction, day: u32, changes: &[(&str, Value, Value)]) -> AuditEntry {
+    let changes: Vec<FieldChange> = changes
+        .iter()
+        .map(|(field, old, new)| FieldChange {
+            field: field.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        })
+        .collect();
+    AuditEntry {
+        id,
+        entity_type: EntityType::Server,
+        entity_id: "vps-1".into(),
+        action,
+        summary: "web".into(),
+        actor: "alice@laptop".into(),
+        details: to_details(&changes),
+        created_at: format!("2024-04-{:02}T12:00:00Z", day),
+    }
+}
+
+fn day(day: u32) -> DateTime<Utc> {
+    format!("2024-04-{:02}T18:00:00Z", day).parse().unwrap()
+}
+
+#[test]
+fn test_reverts_updates_after_the_time() {
+    let current = json!({"name": "web", "host": "10.0.0.3", "notes": "moved"});
+    let entries = vec![
+        entry(1, AuditAction::Created, 1, &[]),
+        entry(
+            2,
+            AuditAction::Updated,
+            5,
+            &[("host", json!("10.0.0.1"), json!("10.0.0.2"))],
+        ),
+        entry(
+            3,
+            AuditAction::Updated,
+            9,
+            &[
+                ("host", json!("10.0.0.2"), json!("10.0.0.3")),
+                ("notes", Value::Null, json!("moved")),
+            ],
+        ),
+    ];
+
+    let at_6 = reconstruct(Some(&current), &entries, day(6));
+    assert!(at_6.existed);
+    assert_eq!(
+        at_6.state,
+        Some(json!({"name": "web", "host": "10.0.0.2", "notes": null}))
+    );
+    assert!(at_6.uncertain.is_empty());
+    assert!(at_6.gaps.is_empty());
+
+    let at_2 = reconstruct(Some(&current), &entries, day(2));
+    assert_eq!(at_2.state.unwrap()["host"], "10.0.0.1");
+
+    // Now: nothing to revert
+    let now = reconstruct(Some(&current), &entries, day(20));
+    assert_eq!(now.state, Some(current));
+}
+
+#[test]
+fn test_creation_and_removal() {
+    let trashed = json!({"name": "web", "host": "10.0.0.2"});
+    let entries = vec![
+        entry(1, AuditAction::Created, 3, &[]),
+        entry(
+            2,
+            AuditAction::Updated,
+            5,
+            &[("host", json!("10.0.0.1"), json!("10.0.0.2"))],
+        ),
+        entry(3, AuditAction::Removed, 8, &[]),
+    ];
+
+    // Before its creation
+    let before = reconstruct(Some(&trashed), &entries, day(1));
+    assert!(!before.existed);
+    assert_eq!(before.state, None);
+    assert!(before.gaps.is_empty());
+
+    // In the trash now, existed before
+    assert!(!reconstruct(Some(&trashed), &entries, day(9)).existed);
+    let alive = reconstruct(Some(&trashed), &entries, day(4));
+    assert!(alive.existed);
+    assert_eq!(alive.state.unwrap()["host"], "10.0.0.1");
+
+    // Removed for good: existed, but the fields are gone
+    let gone = reconstruct(None, &entries, day(6));
+    assert!(gone.existed);
+    assert_eq!(gone.state, None);
+    assert_eq!(
+        gone.gaps,
+        vec![Gap::RemovedRow {
+            at: "2024-04-08T12:00:00Z".into()
+        }]
+    );
+}
+
+#[test]
+fn test_restore_from_trash() {
+    let current = json!({"name": "web"});
+    let entries = vec![
+        entry(1, AuditAction::Created, 1, &[]),
+        entry(2, AuditAction::Removed, 3, &[]),
+        // Restores are recorded as creations
+        entry(3, AuditAction::Created, 5, &[]),
+    ];
+    assert!(reconstruct(Some(&current), &entries, day(2)).existed);
+    assert!(!reconstruct(Some(&current), &entries, day(4)).existed);
+    assert!(reconstruct(Some(&current), &entries, day(6)).existed);
+}
+
+#[test]
+fn test_gaps_in_the_history() {
+    let current = json!({"host": "10.0.0.9", "password": "****"});
+    let entries = vec![
+        // Existed before the audit log: no creation entry
+        entry(1, AuditAction::Updated, 4, &[]),
+        entry(
+            2,
+            AuditAction::Updated,
+            6,
+            &[
+                // The host was changed again without an entry afterwards
+                ("host", json!("10.0.0.1"), json!("10.0.0.2")),
+                ("password", json!("****"), json!("****")),
+            ],
+        ),
+    ];
+
+    let at_5 = reconstruct(Some(&current), &entries, day(5));
+    assert!(at_5.gaps.is_empty());
+    assert_eq!(at_5.state.as_ref().unwrap()["host"], "10.0.0.1");
+    assert_eq!(at_5.uncertain["host"], Uncertainty::Unrecorded);
+    assert_eq!(at_5.uncertain["password"], Uncertainty::Secret);
+
+    let at_2 = reconstruct(Some(&current), &entries, day(2));
+    assert!(at_2.existed);
+    assert_eq!(
+        at_2.gaps,
+        vec![
+            Gap::Undetailed {
+                at: "2024-04-04T12:00:00Z".into()
+            },
+            Gap::BeforeHistory {
+                first: Some("2024-04-04T12:00:00Z".into())
+            },
+        ]
+    );
+
+    // No entries at all
+    let unknown = reconstruct(Some(&current), &[], day(2));
+    assert_eq!(unknown.state, Some(current));
+    assert_eq!(unknown.gaps, vec![Gap::BeforeHistory { first: None }]);
+}
+
+#[test]
+fn test_nested_fields() {
+    let current = json!({"name": "web", "specs": {"cpu_cores": 4, "ram_gb": 8}});
+    let entries = vec![
+        entry(1, AuditAction::Created, 1, &[]),
+        // Specs were detected later: reported per field
+        entry(
+            2,
+            AuditAction::Updated,
+            5,
+            &[
+                ("specs.cpu_cores", Value::Null, json!(4)),
+                ("specs.ram_gb", Value::Null, json!(8)),
+            ],
+        ),
+    ];
+
+    let before = reconstruct(Some(&current), &entries, day(2));
+    assert_eq!(before.state, Some(json!({"name": "web", "specs": null})));
+    assert!(before.uncertain.is_empty());
+
+    assert_eq!(
+        fields(&current),
+        vec![
+            ("name".to_string(), json!("web")),
+            ("specs.cpu_cores".to_string(), json!(4)),
+            ("specs.ram_gb".to_string(), json!(8)),
+        ]
+    );
+}
+
+#[test]
+fn test_timeline_is_oldest_first_per_field() {
+    let entries = vec![
+        entry(
+            3,
+            AuditAction::Updated,
+            9,
+            &[
+                ("host", json!("a"), json!("b")),
+                ("notes", Value::Null, json!("x")),
+            ],
+        ),
+        entry(1, AuditAction::Created, 1, &[]),
+        entry(2, AuditAction::Updated, 5, &[]),
+    ];
+    let events = timeline(&entries);
+    let summary: Vec<(AuditAction, Option<&str>)> = events
+        .iter()
+        .map(|e| (e.action, e.change.as_ref().map(|c| c.field.as_str())))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (AuditAction::Created, None),
+            (AuditAction::Updated, None),
+            (AuditAction::Updated, Some("host")),
+            (AuditAction::Updated, Some("notes")),
+        ]
+    );
+    assert_eq!(events[0].actor, "alice@laptop");
+}
@@ -0,0 +1,236 @@
+use pctrl_core::hooks::{
+    all_events, discover_all, discover_event, entity_event, is_known_event, redact_json,
+    HookPayload, HookRunner, HookState, SCRIPT_FINISHED,
+};
+use pctrl_core::{AuditAction, EntityType};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::Duration;
+
+fn write_hook(dir: &Path, event: &str, name: &str, body: &str, executable: bool) {
+    let event_dir = dir.join(event);
+    std::fs::create_dir_all(&event_dir).unwrap();
+    let path = event_dir.join(name);
+    std::fs::write(&path, body).unwrap();
+    let mode = if executable { 0o755 } else { 0o644 };
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn test_event_names() {
+    assert_eq!(
+        entity_event(EntityType::Server, AuditAction::Created),
+        "server.created"
+    );
+    assert_eq!(
+        entity_event(EntityType::Credential, AuditAction::Removed),
+        "credential.removed"
+    );
+
+    let events = all_events();
+    assert_eq!(events.len(), 6 * 3 + 2);
+    assert!(is_known_event("project.updated"));
+    assert!(is_known_event(SCRIPT_FINISHED));
+    assert!(is_known_event("monitor.changed"));
+    assert!(!is_known_event("server.create"));
+}
+
+#[test]
+fn test_entity_payload() {
+    let data = serde_json::json!({ "id": "web-1", "name": "web-1", "host": "10.0.0.1" });
+    let payload = HookPayload::entity(
+        EntityType::Server,
+        "web-1",
+        AuditAction::Updated,
+        "web-1",
+        data.clone(),
+    );
+
+    assert_eq!(payload.event, "server.updated");
+    assert_eq!(payload.entity_type.as_deref(), Some("server"));
+    assert_eq!(payload.entity_id.as_deref(), Some("web-1"));
+    assert_eq!(payload.data, data);
+    assert!(!payload.sample);
+
+    // Optional fields and the sample flag stay out of the JSON when unset
+    let json =
+        serde_json::to_value(HookPayload::new("monitor.changed", serde_json::json!({}))).unwrap();
+    assert!(json.get("entity_type").is_none());
+    assert!(json.get("sample").is_none());
+}
+
+#[test]
+fn test_payload_redacts_secret_fields() {
+    let data = serde_json::json!({
+        "name": "prod-db",
+        "password": "hunter2",
+        "nested": [{ "api_key": "abc", "url": "https://x" }],
+        "token": null,
+    });
+    let redacted = redact_json(data);
+    assert_eq!(redacted["name"], "prod-db");
+    assert_eq!(redacted["password"], "****");
+    assert_eq!(redacted["nested"][0]["api_key"], "****");
+    assert_eq!(redacted["nested"][0]["url"], "https://x");
+    assert!(redacted["token"].is_null());
+}
+
+#[test]
+fn test_sample_payload() {
+    let entity = HookPayload::sample("domain.removed");
+    assert!(entity.sample);
+    assert_eq!(entity.entity_type.as_deref(), Some("domain"));
+
+    let script = HookPayload::sample(SCRIPT_FINISHED);
+    assert_eq!(script.entity_type.as_deref(), Some("script"));
+    assert_eq!(script.data["exit_code"], 0);
+
+    let other = HookPayload::sample("monitor.changed");
+    assert!(other.entity_type.is_none());
+}
+
+#[test]
+fn test_discovery_order_and_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    write_hook(
+        dir.path(),
+        "server.created",
+        "20-second",
+        "#!/bin/sh\n",
+        true,
+    );
+    write_hook(
+        dir.path(),
+        "server.created",
+        "10-first",
+        "#!/bin/sh\n",
+        true,
+    );
+    write_hook(
+        dir.path(),
+        "server.created",
+        "30-off.disabled",
+        "#!/bin/sh\n",
+        true,
+    );
+    write_hook(
+        dir.path(),
+        "server.created",
+        "40-readme.txt",
+        "notes",
+        false,
+    );
+    write_hook(dir.path(), "server.created", ".hidden", "#!/bin/sh\n", true);
+    write_hook(
+        dir.path(),
+        "project.removed",
+        "cleanup",
+        "#!/bin/sh\n",
+        true,
+    );
+
+    let hooks = discover_event(dir.path(), "server.created");
+    let listed: Vec<(&str, HookState)> = hooks.iter().map(|h| (h.name.as_str(), h.state)).collect();
+    assert_eq!(
+        listed,
+        vec![
+            ("10-first", HookState::Enabled),
+            ("20-second", HookState::Enabled),
+            ("30-off.disabled", HookState::Disabled),
+            ("40-readme.txt", HookState::NotExecutable),
+        ]
+    );
+
+    let all = discover_all(dir.path());
+    assert_eq!(all.len(), 5);
+    assert_eq!(all[0].event, "project.removed");
+
+    assert!(discover_event(dir.path(), "domain.created").is_empty());
+    assert!(discover_all(&dir.path().join("missing")).is_empty());
+}
+
+#[tokio::test]
+async fn test_runner_passes_payload_on_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("received.json");
+    write_hook(
+        dir.path(),
+        "server.created",
+        "10-capture",
+        &format!("#!/bin/sh\ncat > '{}'\necho captured\n", out.display()),
+        true,
+    );
+    write_hook(
+        dir.path(),
+        "server.created",
+        "20-fail",
+        "#!/bin/sh\necho boom >&2\nexit 3\n",
+        true,
+    );
+    write_hook(
+        dir.path(),
+        "server.created",
+        "30-skip.disabled",
+        "#!/bin/sh\nexit 1\n",
+        true,
+    );
+
+    let runner = HookRunner::new(dir.path());
+    let payload = HookPayload::sample("server.created");
+    let runs = runner.run(&payload).await;
+
+    assert_eq!(runs.len(), 2);
+    assert!(runs[0].success);
+    assert_eq!(runs[0].output.trim(), "captured");
+    assert!(!runs[1].success);
+    assert_eq!(runs[1].exit_code, Some(3));
+    assert_eq!(runs[1].output.trim(), "boom");
+
+    let received: HookPayload =
+        serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    assert_eq!(received, payload);
+}
+
+#[tokio::test]
+async fn test_runner_kills_hooks_after_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    write_hook(
+        dir.path(),
+        "script.finished",
+        "slow",
+        "#!/bin/sh\nsleep 5\n",
+        true,
+    );
+
+    let runner = HookRunner::new(dir.path()).with_timeout(Duration::from_millis(200));
+    let runs = runner.run(&HookPayload::sample(SCRIPT_FINISHED)).await;
+
+    assert_eq!(runs.len(), 1);
+    assert!(runs[0].timed_out);
+    assert!(!runs[0].success);
+    assert!(runs[0].duration_ms < 5000);
+}
+
+#[tokio::test]
+async fn test_runner_times_out_hooks_that_never_read_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    write_hook(
+        dir.path(),
+        "script.finished",
+        "deaf",
+        "#!/bin/sh\nsleep 5\n",
+        true,
+    );
+
+    // Far more than a pipe buffer holds
+    let payload = HookPayload::new(
+        SCRIPT_FINISHED,
+        serde_json::json!({ "output": "x".repeat(1024 * 1024) }),
+    );
+    let runner = HookRunner::new(dir.path()).with_timeout(Duration::from_millis(200));
+    let runs = runner.run(&payload).await;
+
+    assert_eq!(runs.len(), 1);
+    assert!(runs[0].timed_out);
+    assert!(runs[0].duration_ms < 5000);
+}
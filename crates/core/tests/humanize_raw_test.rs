@@ -0,0 +1,15 @@
+//! Raw mode is process-global, so it lives in its own test binary
+
+use chrono::{TimeZone, Utc};
+use pctrl_core::humanize::{bytes, duration, relative_to, set_raw};
+use std::time::Duration;
+
+#[test]
+fn test_raw_mode_disables_humanization() {
+    set_raw(true);
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+
+    assert_eq!(bytes(1_503_238_553), "1503238553");
+    assert_eq!(duration(Duration::from_secs(90_061)), "90061s");
+    assert_eq!(relative_to(now, now), "2024-06-01T12:00:00Z");
+}
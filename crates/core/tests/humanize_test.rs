@@ -0,0 +1,79 @@
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use pctrl_core::humanize::{bytes, count, duration, relative_timestamp, relative_to};
+use std::time::Duration;
+
+#[test]
+fn test_bytes_boundaries() {
+    assert_eq!(bytes(0), "0 B");
+    assert_eq!(bytes(1023), "1023 B");
+    assert_eq!(bytes(1024), "1.0 KB");
+    assert_eq!(bytes(1536), "1.5 KB");
+    assert_eq!(bytes(1024 * 1024 - 1), "1.0 MB");
+    assert_eq!(bytes(1_503_238_553), "1.4 GB");
+    assert_eq!(bytes(5 * 1024_u64.pow(4)), "5.0 TB");
+    assert_eq!(bytes(u64::MAX), "16384.0 PB");
+}
+
+#[test]
+fn test_duration_boundaries() {
+    assert_eq!(duration(Duration::from_secs(0)), "0s");
+    assert_eq!(duration(Duration::from_secs(59)), "59s");
+    assert_eq!(duration(Duration::from_secs(60)), "1m");
+    assert_eq!(duration(Duration::from_secs(61)), "1m 1s");
+    assert_eq!(duration(Duration::from_secs(3_600)), "1h");
+    assert_eq!(duration(Duration::from_secs(3_725)), "1h 2m");
+    assert_eq!(duration(Duration::from_secs(90_061)), "1d 1h");
+    assert_eq!(duration(Duration::from_millis(1_999)), "1s");
+}
+
+#[test]
+fn test_relative_boundaries() {
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+
+    assert_eq!(relative_to(now, now), "just now");
+    assert_eq!(
+        relative_to(now - ChronoDuration::seconds(1), now),
+        "1 second ago"
+    );
+    assert_eq!(
+        relative_to(now - ChronoDuration::seconds(59), now),
+        "59 seconds ago"
+    );
+    assert_eq!(
+        relative_to(now - ChronoDuration::seconds(61), now),
+        "1 minute ago"
+    );
+    assert_eq!(
+        relative_to(now - ChronoDuration::hours(5), now),
+        "5 hours ago"
+    );
+    assert_eq!(
+        relative_to(now - ChronoDuration::days(3), now),
+        "3 days ago"
+    );
+    assert_eq!(
+        relative_to(now + ChronoDuration::days(14), now),
+        "in 2 weeks"
+    );
+    assert_eq!(
+        relative_to(now + ChronoDuration::days(90), now),
+        "in 3 months"
+    );
+    assert_eq!(
+        relative_to(now + ChronoDuration::days(3650), now),
+        "in 10 years"
+    );
+}
+
+#[test]
+fn test_relative_timestamp_passes_through_garbage() {
+    assert_eq!(relative_timestamp("not a date"), "not a date");
+    assert!(relative_timestamp("2000-01-01T00:00:00Z").ends_with("years ago"));
+}
+
+#[test]
+fn test_count() {
+    assert_eq!(count(0, "server", "servers"), "0 servers");
+    assert_eq!(count(1, "server", "servers"), "1 server");
+    assert_eq!(count(2, "entry", "entries"), "2 entries");
+}
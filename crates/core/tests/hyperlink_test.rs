@@ -0,0 +1,68 @@
+//! Links are process-global, so they live in their own test binary
+
+use pctrl_core::hyperlink::{self, detect, file_url, web_url, HyperlinkMode};
+use std::path::Path;
+
+#[test]
+fn test_links_forced_on_and_off() {
+    hyperlink::set_enabled(true);
+    assert_eq!(
+        hyperlink::link("https://example.com", "example"),
+        "\x1b]8;;https://example.com\x1b\\example\x1b]8;;\x1b\\"
+    );
+    assert_eq!(
+        hyperlink::web("app.example.com"),
+        "\x1b]8;;https://app.example.com\x1b\\app.example.com\x1b]8;;\x1b\\"
+    );
+    assert_eq!(
+        hyperlink::file("/srv/app/key file"),
+        "\x1b]8;;file:///srv/app/key%20file\x1b\\/srv/app/key file\x1b]8;;\x1b\\"
+    );
+    assert_eq!(
+        hyperlink::folder_of("/tmp/out/hosts.ini"),
+        "\x1b]8;;file:///tmp/out\x1b\\/tmp/out/hosts.ini\x1b]8;;\x1b\\"
+    );
+    // An escape in the URL can't end the sequence early
+    assert_eq!(
+        hyperlink::link("https://x\x1b]8;;evil", "x"),
+        "\x1b]8;;https://x]8;;evil\x1b\\x\x1b]8;;\x1b\\"
+    );
+
+    // Disabled, the output is exactly the text
+    hyperlink::set_enabled(false);
+    assert_eq!(hyperlink::link("https://example.com", "example"), "example");
+    assert_eq!(hyperlink::web("app.example.com"), "app.example.com");
+    assert_eq!(hyperlink::file("/srv/app/key file"), "/srv/app/key file");
+    assert_eq!(
+        hyperlink::folder_of("/tmp/out/hosts.ini"),
+        "/tmp/out/hosts.ini"
+    );
+}
+
+#[test]
+fn test_detect() {
+    use HyperlinkMode::*;
+
+    assert!(detect(Auto, Some("xterm-256color"), true));
+    assert!(!detect(Auto, Some("xterm-256color"), false));
+    assert!(!detect(Auto, Some("dumb"), true));
+    assert!(!detect(Auto, None, true));
+    assert!(detect(Always, Some("dumb"), false));
+    assert!(!detect(Never, Some("xterm-256color"), true));
+
+    assert_eq!("on".parse::<HyperlinkMode>(), Ok(Always));
+    assert_eq!("NEVER".parse::<HyperlinkMode>(), Ok(Never));
+    assert!("sometimes".parse::<HyperlinkMode>().is_err());
+}
+
+#[test]
+fn test_urls() {
+    assert_eq!(web_url("example.com"), "https://example.com");
+    assert_eq!(web_url("http://localhost:8000"), "http://localhost:8000");
+    assert_eq!(
+        file_url(Path::new("/home/me/ü #1")),
+        "file:///home/me/%C3%BC%20%231"
+    );
+    assert!(file_url(Path::new("relative/key")).ends_with("/relative/key"));
+    assert!(file_url(Path::new("relative/key")).starts_with("file:///"));
+}
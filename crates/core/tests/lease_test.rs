@@ -0,0 +1,58 @@
+use chrono::{Duration, TimeZone, Utc};
+use pctrl_core::lease::{
+    compare_versions, decide, schema_access, LeaseDecision, SchemaAccess, WriteLease,
+    LEASE_TTL_SECS,
+};
+use std::cmp::Ordering;
+
+fn lease(holder: &str) -> WriteLease {
+    let at = Utc.with_ymd_and_hms(2026, 5, 4, 12, 0, 0).unwrap();
+    WriteLease {
+        holder: holder.to_string(),
+        version: "0.3.0".to_string(),
+        schema_version: 13,
+        acquired_at: at,
+        heartbeat_at: at,
+    }
+}
+
+#[test]
+fn test_schema_access() {
+    assert_eq!(schema_access(12, 12), SchemaAccess::ReadWrite);
+    assert_eq!(schema_access(9, 12), SchemaAccess::Migrate { from: 9 });
+    assert_eq!(schema_access(14, 12), SchemaAccess::ReadOnly { schema: 14 });
+}
+
+#[test]
+fn test_compare_versions() {
+    assert_eq!(compare_versions("0.10.1", "0.9.3"), Ordering::Greater);
+    assert_eq!(compare_versions("v1.2", "1.2.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.2.0-beta.1", "1.2.0"), Ordering::Equal);
+    assert_eq!(compare_versions("0.1.0", "0.1.1"), Ordering::Less);
+}
+
+#[test]
+fn test_lease_decisions() {
+    let held = lease("alice@laptop (pid 7)");
+    let now = held.heartbeat_at + Duration::seconds(10);
+
+    assert_eq!(decide(None, "bob", now), LeaseDecision::Acquire);
+    assert_eq!(
+        decide(Some(&held), "alice@laptop (pid 7)", now),
+        LeaseDecision::Renew
+    );
+    assert_eq!(
+        decide(Some(&held), "bob", now),
+        LeaseDecision::Busy(held.clone())
+    );
+
+    let later = held.heartbeat_at + Duration::seconds(LEASE_TTL_SECS);
+    assert!(held.is_expired(later));
+    assert_eq!(
+        decide(Some(&held), "bob", later),
+        LeaseDecision::Steal(held.clone())
+    );
+    assert!(held
+        .describe()
+        .starts_with("alice@laptop (pid 7) (pctrl 0.3.0) is upgrading the database to schema v13"));
+}
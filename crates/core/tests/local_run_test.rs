@@ -0,0 +1,150 @@
+use pctrl_core::local_run::{parse_assignment, LocalRun, Shell, BASE_VARS};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+fn host_env() -> Vec<(String, String)> {
+    vec![
+        ("PATH".to_string(), "/usr/bin:/bin".to_string()),
+        ("HOME".to_string(), "/home/me".to_string()),
+        ("SystemRoot".to_string(), "C:\\Windows".to_string()),
+        ("AWS_SECRET_ACCESS_KEY".to_string(), "leaked".to_string()),
+        ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+    ]
+}
+
+#[test]
+fn test_parse_assignment() {
+    assert_eq!(
+        parse_assignment("API_URL=https://x.test/?a=b").unwrap(),
+        ("API_URL".to_string(), "https://x.test/?a=b".to_string())
+    );
+    assert_eq!(
+        parse_assignment("EMPTY=").unwrap(),
+        ("EMPTY".to_string(), String::new())
+    );
+    assert!(parse_assignment("NO_VALUE").is_err());
+    assert!(parse_assignment("1ST=x").is_err());
+    assert!(parse_assignment("WITH-DASH=x").is_err());
+    assert!(parse_assignment("=x").is_err());
+}
+
+#[test]
+fn test_cmd_command_line_keeps_body_verbatim() {
+    let body = r#"echo "a & b" > "C:\out dir\log.txt" && dir %TEMP%"#;
+    assert_eq!(
+        Shell::Cmd.command_line(body),
+        r#"/D /S /C "echo "a & b" > "C:\out dir\log.txt" && dir %TEMP%""#
+    );
+    assert_eq!(Shell::Cmd.program(), "cmd");
+    assert_eq!(Shell::Cmd.stdin(body), None);
+}
+
+#[test]
+fn test_sh_reads_body_from_stdin() {
+    assert_eq!(Shell::Sh.program(), "sh");
+    assert_eq!(Shell::Sh.command_line("echo 'x'"), "-s");
+    let stdin = Shell::Sh.stdin("echo 'x'").unwrap();
+    assert!(stdin.starts_with("echo 'x'"));
+}
+
+#[test]
+fn test_clean_environment_keeps_base_vars_and_declared() {
+    let dir = tempfile::tempdir().unwrap();
+    let declared = BTreeMap::from([
+        ("DEBUG".to_string(), "1".to_string()),
+        ("PATH".to_string(), "/opt/tool/bin".to_string()),
+    ]);
+    let run = LocalRun::plan(
+        Some(dir.path()),
+        &declared,
+        false,
+        host_env(),
+        Path::new("/"),
+    )
+    .unwrap();
+
+    assert_eq!(run.cwd, dir.path());
+    let names = run.env_names();
+    assert!(names.contains(&"DEBUG".to_string()));
+    assert!(!names.contains(&"AWS_SECRET_ACCESS_KEY".to_string()));
+    assert!(!names.contains(&"LANG".to_string()));
+    assert!(names
+        .iter()
+        .all(|n| n == "DEBUG" || BASE_VARS.iter().any(|b| b.eq_ignore_ascii_case(n))));
+    // Declared variables win over the host's
+    let path = run.env.iter().find(|(k, _)| k == "PATH").unwrap();
+    assert_eq!(path.1, "/opt/tool/bin");
+}
+
+#[test]
+fn test_inherit_env_passes_everything() {
+    let declared = BTreeMap::from([("LANG".to_string(), "C".to_string())]);
+    let run = LocalRun::plan(None, &declared, true, host_env(), Path::new("/srv")).unwrap();
+
+    assert_eq!(run.cwd, Path::new("/srv"));
+    assert_eq!(run.env.len(), host_env().len());
+    let lang = run.env.iter().find(|(k, _)| k == "LANG").unwrap();
+    assert_eq!(lang.1, "C");
+}
+
+#[test]
+fn test_missing_working_dir_fails_before_spawning() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("gone");
+    let err = LocalRun::plan(
+        Some(&missing),
+        &BTreeMap::new(),
+        false,
+        host_env(),
+        Path::new("/"),
+    )
+    .unwrap_err();
+    assert!(err.contains("doesn't exist"), "{}", err);
+
+    let err = LocalRun::plan(
+        Some(Path::new("relative/dir")),
+        &BTreeMap::new(),
+        false,
+        host_env(),
+        Path::new("/"),
+    )
+    .unwrap_err();
+    assert!(err.contains("must be absolute"), "{}", err);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_runs_in_working_dir_with_clean_environment() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let declared = BTreeMap::from([("GREETING".to_string(), "hello world".to_string())]);
+    let run = LocalRun::plan(
+        Some(dir.path()),
+        &declared,
+        false,
+        std::env::vars().chain([("PCTRL_LEAK".to_string(), "1".to_string())]),
+        Path::new("/"),
+    )
+    .unwrap();
+
+    let body = "pwd\necho \"$GREETING\"\necho \"leak=${PCTRL_LEAK-unset}\"";
+    let mut child = run.command(body).spawn().unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(run.shell.stdin(body).unwrap().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        Path::new(lines[0]).canonicalize().unwrap(),
+        dir.path().canonicalize().unwrap()
+    );
+    assert_eq!(lines[1], "hello world");
+    assert_eq!(lines[2], "leak=unset");
+}
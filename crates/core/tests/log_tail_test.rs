@@ -0,0 +1,150 @@
+use chrono::{TimeZone, Utc};
+use pctrl_core::log_tail::{self, LineBuffer, LogLine, Reorder};
+use std::time::{Duration, Instant};
+
+fn line(source: usize, second: Option<u32>, text: &str) -> LogLine {
+    LogLine {
+        source,
+        timestamp: second.map(|s| Utc.with_ymd_and_hms(2026, 3, 3, 9, 14, s).unwrap()),
+        text: text.to_string(),
+    }
+}
+
+fn texts(lines: &[LogLine]) -> Vec<&str> {
+    lines.iter().map(|l| l.text.as_str()).collect()
+}
+
+#[test]
+fn test_labels_are_padded_and_cycle_colors() {
+    let labels = log_tail::label_sources(&["web", "proxy", "postgres@db"], 2);
+    let prefixes: Vec<&str> = labels.iter().map(|l| l.prefix.as_str()).collect();
+    assert_eq!(prefixes, ["web        ", "proxy      ", "postgres@db"]);
+    let colors: Vec<usize> = labels.iter().map(|l| l.color).collect();
+    assert_eq!(colors, [0, 1, 0]);
+
+    let long = "a-very-long-container-name-from-compose-1";
+    let labels = log_tail::label_sources(&[long, "web"], 6);
+    assert_eq!(labels[0].prefix.chars().count(), log_tail::MAX_PREFIX);
+    assert!(labels[0].prefix.ends_with('…'));
+    assert_eq!(labels[1].prefix.chars().count(), log_tail::MAX_PREFIX);
+}
+
+#[test]
+fn test_split_service_target() {
+    assert_eq!(
+        log_tail::split_service_target("postgres@db-server"),
+        ("postgres", Some("db-server"))
+    );
+    assert_eq!(
+        log_tail::split_service_target("postgresql@14-main@db"),
+        ("postgresql@14-main", Some("db"))
+    );
+    assert_eq!(log_tail::split_service_target("api"), ("api", None));
+    assert_eq!(log_tail::split_service_target("api@"), ("api@", None));
+}
+
+#[test]
+fn test_line_buffer_joins_chunks() {
+    let mut buffer = LineBuffer::default();
+    assert!(buffer.push("GET /heal").is_empty());
+    assert_eq!(buffer.push("th 200\r\nGET /"), ["GET /health 200"]);
+    assert_eq!(buffer.push("cart 500\n\nx"), ["GET /cart 500", ""]);
+    assert_eq!(buffer.finish().as_deref(), Some("x"));
+    assert_eq!(buffer.finish(), None);
+}
+
+#[test]
+fn test_split_timestamp() {
+    let (time, rest) = log_tail::split_timestamp("2026-03-03T09:14:02.123456789Z GET /health");
+    assert_eq!(
+        time.unwrap().timestamp_nanos_opt(),
+        Some(
+            Utc.with_ymd_and_hms(2026, 3, 3, 9, 14, 2)
+                .unwrap()
+                .timestamp_nanos_opt()
+                .unwrap()
+                + 123_456_789
+        )
+    );
+    assert_eq!(rest, "GET /health");
+
+    // journalctl's short-iso-precise, older versions without the colon
+    let (time, rest) = log_tail::split_timestamp(
+        "2026-03-03T10:14:02.500000+0100 db-1 postgres[812]: checkpoint starting",
+    );
+    assert_eq!(
+        time.unwrap().timestamp_millis(),
+        Utc.with_ymd_and_hms(2026, 3, 3, 9, 14, 2)
+            .unwrap()
+            .timestamp_millis()
+            + 500
+    );
+    assert_eq!(log_tail::journal_message(rest), "checkpoint starting");
+
+    let (time, rest) = log_tail::split_timestamp("-- No entries --");
+    assert_eq!(time, None);
+    assert_eq!(rest, "-- No entries --");
+    assert_eq!(
+        log_tail::journal_message("db-1 no origin here: just text"),
+        "db-1 no origin here: just text"
+    );
+}
+
+#[test]
+fn test_reorder_releases_in_timestamp_order_after_the_window() {
+    let window = Duration::from_millis(300);
+    let mut reorder = Reorder::new(window);
+    let start = Instant::now();
+
+    reorder.push(line(0, Some(2), "web 2"), start);
+    reorder.push(
+        line(1, Some(1), "proxy 1"),
+        start + Duration::from_millis(100),
+    );
+    reorder.push(
+        line(0, Some(3), "web 3"),
+        start + Duration::from_millis(200),
+    );
+    assert!(reorder.ready(start + Duration::from_millis(250)).is_empty());
+    assert_eq!(reorder.next_due(), Some(start + window));
+
+    // "proxy 1" arrived later but is older, so it goes out with "web 2"
+    let out = reorder.ready(start + window);
+    assert_eq!(texts(&out), ["proxy 1", "web 2"]);
+    assert_eq!(texts(&reorder.flush()), ["web 3"]);
+    assert!(reorder.is_empty());
+}
+
+#[test]
+fn test_reorder_keeps_untimed_lines_with_their_source() {
+    let mut reorder = Reorder::new(Duration::ZERO);
+    let now = Instant::now();
+    reorder.push(line(0, Some(5), "web 5"), now);
+    reorder.push(line(0, None, "  at handler (web 5 stack)"), now);
+    reorder.push(line(1, Some(4), "proxy 4"), now);
+    reorder.push(line(1, Some(6), "proxy 6"), now);
+    assert_eq!(
+        texts(&reorder.ready(now)),
+        ["proxy 4", "web 5", "  at handler (web 5 stack)", "proxy 6"]
+    );
+}
+
+#[test]
+fn test_reorder_late_line_is_printed_anyway() {
+    let mut reorder = Reorder::new(Duration::from_millis(100));
+    let start = Instant::now();
+    reorder.push(line(0, Some(9), "web 9"), start);
+    assert_eq!(
+        texts(&reorder.ready(start + Duration::from_millis(100))),
+        ["web 9"]
+    );
+    // Older than what was printed, but still shown
+    reorder.push(
+        line(1, Some(1), "proxy 1"),
+        start + Duration::from_millis(150),
+    );
+    assert_eq!(
+        texts(&reorder.ready(start + Duration::from_millis(250))),
+        ["proxy 1"]
+    );
+}
@@ -0,0 +1,62 @@
+use chrono::{Duration, TimeZone, Utc};
+use pctrl_core::maintenance::{MaintenanceScope, MaintenanceWindow};
+use pctrl_core::ProjectStatus;
+
+fn window(reason: Option<&str>) -> MaintenanceWindow {
+    MaintenanceWindow {
+        project_id: "shop".to_string(),
+        previous_status: ProjectStatus::Live,
+        reason: reason.map(String::from),
+        started_at: "2026-03-01T22:00:00Z".to_string(),
+        expires_at: "2026-03-02T00:00:00Z".to_string(),
+    }
+}
+
+#[test]
+fn test_window_expiry_and_banner() {
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    let window = window(Some("db migration"));
+
+    let during = start + Duration::minutes(40);
+    assert!(!window.is_expired(during));
+    assert_eq!(window.remaining(during).as_secs(), 80 * 60);
+    assert_eq!(
+        window.banner(during),
+        "maintenance, 1h 20m left (db migration)"
+    );
+
+    let end = start + Duration::hours(2);
+    assert!(window.is_expired(end));
+    assert_eq!(window.remaining(end).as_secs(), 0);
+    assert_eq!(window.banner(end), "maintenance, ending (db migration)");
+
+    let broken = MaintenanceWindow {
+        expires_at: "soon".to_string(),
+        ..window
+    };
+    assert!(broken.is_expired(start));
+}
+
+#[test]
+fn test_maintenance_status() {
+    assert_eq!(
+        "Maintenance".parse::<ProjectStatus>(),
+        Ok(ProjectStatus::Maintenance)
+    );
+    assert_eq!(ProjectStatus::Maintenance.to_string(), "maintenance");
+    assert!(ProjectStatus::Maintenance.is_live());
+    assert!(ProjectStatus::Live.is_live());
+    assert!(!ProjectStatus::Staging.is_live());
+}
+
+#[test]
+fn test_scope_matches_id_or_name() {
+    let mut scope = MaintenanceScope::default();
+    assert!(scope.is_empty());
+    scope.insert("srv-1", "shop");
+    scope.insert("db-1", "blog");
+
+    assert_eq!(scope.covering("srv-1", "web-1"), Some("shop"));
+    assert_eq!(scope.covering("srv-2", "db-1"), Some("blog"));
+    assert_eq!(scope.covering("srv-3", "web-3"), None);
+}
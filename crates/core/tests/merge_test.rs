@@ -0,0 +1,303 @@
+mod common;
+
+use pctrl_core::conflict::{Decision, Side};
+use pctrl_core::merge::{self, Class, IdMap, Prefer, Record, Resolution, Step};
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, EntityType, ProjectResource, ResourceType, Script,
+    ScriptType, Server,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+fn record<T: Serialize>(entity_type: EntityType, entity: &T) -> Record {
+    Record::from_entity(entity_type, entity, None).unwrap()
+}
+
+fn server(id: &str, name: &str, credential_id: Option<&str>) -> Record {
+    record(
+        EntityType::Server,
+        &Server {
+            name: name.to_string(),
+            host: "203.0.113.5".to_string(),
+            credential_id: credential_id.map(str::to_string),
+            ..common::server(id)
+        },
+    )
+}
+
+fn credential(id: &str, name: &str) -> Record {
+    record(
+        EntityType::Credential,
+        &Credential {
+            id: id.to_string(),
+            name: name.to_string(),
+            credential_type: CredentialType::ApiToken,
+            data: CredentialData::ApiToken {
+                token: "s3cr3t".to_string(),
+                url: None,
+            },
+            notes: None,
+        },
+    )
+}
+
+fn script(id: &str, last_output: Option<&str>) -> Script {
+    Script {
+        id: id.to_string(),
+        name: "backup".to_string(),
+        description: None,
+        command: "restic backup /srv".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: last_output.map(|_| "2026-10-01T10:00:00Z".to_string()),
+        last_result: None,
+        exit_code: None,
+        last_output: last_output.map(str::to_string),
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn link(id: &str, project_id: &str, resource_id: &str) -> ProjectResource {
+    ProjectResource {
+        id: id.to_string(),
+        project_id: project_id.to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: resource_id.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    }
+}
+
+fn classes(comparison: &merge::Comparison) -> Vec<(String, Class)> {
+    comparison
+        .entries
+        .iter()
+        .map(|e| (e.record().name.clone(), e.class.clone()))
+        .collect()
+}
+
+#[test]
+fn test_records_match_by_id_then_normalized_name() {
+    let local = vec![
+        server("web", "web", None),
+        server("db", "Database Host", None),
+        server("old", "old", None),
+    ];
+    let mut renamed = server("s-1", "  database   host ", None);
+    renamed.fields.insert("host".into(), "203.0.113.9".into());
+    let other = vec![
+        server("web", "web", None),
+        renamed,
+        server("new", "new", None),
+    ];
+
+    let comparison = merge::compare(&local, &other);
+    assert_eq!(
+        classes(&comparison),
+        vec![
+            ("web".to_string(), Class::Identical),
+            (
+                "Database Host".to_string(),
+                Class::Conflict(vec!["host".to_string(), "name".to_string()])
+            ),
+            ("new".to_string(), Class::OnlyOther),
+            ("old".to_string(), Class::OnlyLocal),
+        ]
+    );
+    assert_eq!(comparison.ids.get(EntityType::Server, "s-1"), "db");
+    assert_eq!(comparison.ids.get(EntityType::Server, "new"), "new");
+}
+
+#[test]
+fn test_id_match_wins_over_an_earlier_name_match() {
+    // The other side's "Web" comes first and would take "web" by name
+    let local = vec![server("web", "web", None)];
+    let other = vec![server("web-2", "Web", None), server("web", "web", None)];
+
+    let comparison = merge::compare(&local, &other);
+    assert_eq!(
+        classes(&comparison),
+        vec![
+            ("Web".to_string(), Class::OnlyOther),
+            ("web".to_string(), Class::Identical),
+        ]
+    );
+}
+
+#[test]
+fn test_references_follow_matched_ids() {
+    // Same credential under another ID: the server pointing at it is identical
+    let local = vec![
+        credential("c-1", "deploy"),
+        server("web", "web", Some("c-1")),
+    ];
+    let other = vec![
+        credential("c-9", "deploy"),
+        credential("c-8", "backup"),
+        server("web", "web", Some("c-9")),
+        server("db", "db", Some("c-9")),
+    ];
+
+    let comparison = merge::compare(&local, &other);
+    assert_eq!(comparison.count(|c| *c == Class::Identical), 2);
+    let plan = merge::plan(&comparison, &[], &[]);
+    let imported: Vec<(String, Option<String>)> = plan
+        .steps
+        .iter()
+        .map(|s| match s {
+            Step::Import(r) => (
+                r.id.clone(),
+                r.fields
+                    .get("credential_id")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+            ),
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        imported,
+        vec![
+            ("c-8".to_string(), None),
+            ("db".to_string(), Some("c-1".to_string())),
+        ]
+    );
+    assert_eq!(plan.identical, 2);
+}
+
+#[test]
+fn test_last_runs_are_no_conflict_and_stay_local() {
+    let local = vec![record(EntityType::Script, &script("backup", Some("ok")))];
+    let other = vec![record(EntityType::Script, &script("backup", None))];
+    let comparison = merge::compare(&local, &other);
+    assert_eq!(comparison.entries[0].class, Class::Identical);
+
+    // A real change is taken without the other side's last run
+    let mut changed = script("backup", None);
+    changed.command = "restic backup /srv /etc".to_string();
+    let other = vec![record(EntityType::Script, &changed)];
+    let mut comparison = merge::compare(&local, &other);
+    assert_eq!(
+        comparison.entries[0].class,
+        Class::Conflict(vec!["command".to_string()])
+    );
+    comparison.entries[0].resolution = Some(Resolution::TakeOther);
+    let plan = merge::plan(&comparison, &[], &[]);
+    let Step::Update { record, changed } = &plan.steps[0] else {
+        panic!("expected an update");
+    };
+    assert_eq!(changed, &vec!["command".to_string()]);
+    let merged: Script = record.to_entity().unwrap();
+    assert_eq!(merged.command, "restic backup /srv /etc");
+    assert_eq!(merged.last_output.as_deref(), Some("ok"));
+}
+
+#[test]
+fn test_field_decision_updates_only_the_picked_fields() {
+    let local = server("web", "web", None);
+    let mut other = server("web", "web", None);
+    other.fields.insert("host".into(), "198.51.100.7".into());
+    other.fields.insert("notes".into(), "moved".into());
+
+    let mut comparison = merge::compare(&[local], &[other]);
+    let conflicts = comparison.conflicts();
+    let fields: Vec<&str> = conflicts[0]
+        .fields
+        .iter()
+        .map(|f| f.field.as_str())
+        .collect();
+    assert_eq!(fields, ["host", "notes"]);
+
+    let picks = BTreeMap::from([
+        ("host".to_string(), Side::Local),
+        ("notes".to_string(), Side::Incoming),
+    ]);
+    comparison.decide(&conflicts, &[Decision::Fields(picks)]);
+    let plan = merge::plan(&comparison, &[], &[]);
+    let Step::Update { record, changed } = &plan.steps[0] else {
+        panic!("expected an update");
+    };
+    assert_eq!(changed, &vec!["notes".to_string()]);
+    let merged: Server = record.to_entity().unwrap();
+    assert_eq!(merged.host, "203.0.113.5");
+    assert_eq!(merged.notes.as_deref(), Some("moved"));
+
+    // A skip leaves the conflict unresolved
+    comparison.decide(&conflicts, &[Decision::Skip]);
+    let plan = merge::plan(&comparison, &[], &[]);
+    assert!(matches!(plan.steps[0], Step::Unresolved { .. }));
+}
+
+#[test]
+fn test_prefer_decides_conflicts() {
+    let mut local = server("web", "web", None);
+    let mut other = server("web", "web", None);
+    other.fields.insert("notes".into(), "moved".into());
+
+    assert_eq!(Prefer::Local.resolve(&local, &other), Resolution::KeepLocal);
+    assert_eq!(Prefer::Other.resolve(&local, &other), Resolution::TakeOther);
+
+    // Newer: the later change wins, local on ties or when nothing is known
+    assert_eq!(Prefer::Newer.resolve(&local, &other), Resolution::KeepLocal);
+    other.updated_at = Some("2026-10-02T09:00:00Z".to_string());
+    assert_eq!(Prefer::Newer.resolve(&local, &other), Resolution::TakeOther);
+    local.updated_at = Some("2026-10-02T11:00:00+02:00".to_string());
+    assert_eq!(Prefer::Newer.resolve(&local, &other), Resolution::KeepLocal);
+    local.updated_at = Some("2026-10-01T09:00:00Z".to_string());
+    assert_eq!(Prefer::Newer.resolve(&local, &other), Resolution::TakeOther);
+
+    assert_eq!("NEWER".parse::<Prefer>(), Ok(Prefer::Newer));
+    assert!("theirs".parse::<Prefer>().is_err());
+
+    // Undecided conflicts are listed, not written
+    let mut comparison = merge::compare(&[local.clone()], &[other.clone()]);
+    let plan = merge::plan(&comparison, &[], &[]);
+    assert!(matches!(plan.steps[0], Step::Unresolved { .. }));
+    comparison.entries[0].resolution = Some(Resolution::KeepLocal);
+    let plan = merge::plan(&comparison, &[], &[]);
+    assert!(matches!(&plan.steps[0], Step::Keep { record, .. } if *record == local));
+}
+
+#[test]
+fn test_links_are_remapped_and_deduplicated() {
+    let mut ids = IdMap::default();
+    ids.insert(EntityType::Project, "shop-2", "shop");
+    ids.insert(EntityType::Server, "s-1", "web");
+
+    let local = vec![link("l1", "shop", "web")];
+    let other = vec![
+        // The same link under local IDs: already there
+        link("x", "shop-2", "s-1"),
+        // A new link whose ID is taken here
+        link("l1", "shop-2", "db"),
+        link("l2", "blog", "web"),
+    ];
+    let merged = merge::remap_links(&local, &other, &ids);
+    let shown: Vec<(&str, &str, &str)> = merged
+        .iter()
+        .map(|l| (l.id.as_str(), l.project_id.as_str(), l.resource_id.as_str()))
+        .collect();
+    assert_eq!(
+        shown,
+        vec![("l1-merged", "shop", "db"), ("l2", "blog", "web")]
+    );
+}
+
+#[test]
+fn test_secrets_are_masked_for_display() {
+    let mut record = credential("c-1", "deploy");
+    assert_eq!(record.display_field("data"), "••••");
+    assert_eq!(record.display_field("name"), "deploy");
+
+    record = server("web", "web", None);
+    assert_eq!(record.display_field("host"), "203.0.113.5");
+    assert_eq!(record.display_field("notes"), "-");
+    assert_eq!(merge::normalize("  Demo\tShop "), "demo shop");
+}
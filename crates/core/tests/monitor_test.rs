@@ -0,0 +1,89 @@
+use chrono::{DateTime, Duration, Utc};
+use pctrl_core::monitor::{liveness, Liveness, MonitorState};
+use pctrl_core::settings;
+
+fn state(last_cycle_at: DateTime<Utc>, interval_secs: u64) -> MonitorState {
+    MonitorState {
+        last_cycle_at: last_cycle_at.to_rfc3339(),
+        servers_checked: 3,
+        servers_down: 0,
+        duration_ms: 120,
+        interval_secs,
+        heartbeat_error: None,
+    }
+}
+
+#[test]
+fn test_liveness() {
+    let now = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let ago = |secs: i64| now - Duration::seconds(secs);
+
+    let cases = [
+        (
+            "just ran",
+            state(ago(0), 300),
+            Liveness::Alive { age_secs: 0 },
+        ),
+        (
+            "one interval",
+            state(ago(300), 300),
+            Liveness::Alive { age_secs: 300 },
+        ),
+        (
+            "exactly two intervals",
+            state(ago(600), 300),
+            Liveness::Alive { age_secs: 600 },
+        ),
+        (
+            "past two intervals",
+            state(ago(601), 300),
+            Liveness::Stale { age_secs: 601 },
+        ),
+        (
+            "4h at 5m",
+            state(ago(4 * 3600), 300),
+            Liveness::Stale { age_secs: 14400 },
+        ),
+        (
+            "slow interval",
+            state(ago(4 * 3600), 3 * 3600),
+            Liveness::Alive { age_secs: 14400 },
+        ),
+        (
+            "clock skew",
+            state(now + Duration::seconds(30), 300),
+            Liveness::Alive { age_secs: 0 },
+        ),
+    ];
+    for (name, state, expected) in cases {
+        assert_eq!(liveness(Some(&state), now), expected, "{}", name);
+    }
+
+    assert_eq!(liveness(None, now), Liveness::NeverRan);
+    let mut garbage = state(now, 300);
+    garbage.last_cycle_at = "yesterday".to_string();
+    assert_eq!(liveness(Some(&garbage), now), Liveness::NeverRan);
+}
+
+#[test]
+fn test_liveness_description() {
+    assert_eq!(
+        Liveness::Stale { age_secs: 4 * 3600 }.describe(),
+        "monitor last ran 4h ago — it may be down"
+    );
+    assert_eq!(
+        Liveness::Alive { age_secs: 90 }.describe(),
+        "monitor is running (last cycle 1m 30s ago)"
+    );
+    assert!(Liveness::NeverRan.describe().contains("pctrl monitor run"));
+}
+
+#[test]
+fn test_heartbeat_url_setting() {
+    let key = settings::MONITOR_HEARTBEAT_URL;
+    assert!(settings::validate(key, "https://hc-ping.com/abc").is_ok());
+    assert!(settings::validate(key, "http://localhost:8080/ping").is_ok());
+    assert!(settings::validate(key, "hc-ping.com/abc").is_err());
+}
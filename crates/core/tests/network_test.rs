@@ -0,0 +1,134 @@
+use pctrl_core::network::{network_edges, reach, Endpoint, NetworkEdge, Reach};
+use pctrl_core::{ContainerNetwork, PublishedPort};
+
+fn member(host: &str, container: &str, network: &str) -> ContainerNetwork {
+    ContainerNetwork {
+        host_id: host.to_string(),
+        container: container.to_string(),
+        network: network.to_string(),
+        ip_address: None,
+    }
+}
+
+fn port(host: &str, container: &str, host_port: u16) -> PublishedPort {
+    PublishedPort {
+        host_id: host.to_string(),
+        container: container.to_string(),
+        host_port,
+        container_port: host_port,
+        protocol: "tcp".to_string(),
+    }
+}
+
+fn at<'a>(host_id: &'a str, container: &'a str) -> Endpoint<'a> {
+    Endpoint { host_id, container }
+}
+
+#[test]
+fn test_shared_networks_are_reported_sorted() {
+    let members = vec![
+        member("h1", "app", "frontend"),
+        member("h1", "app", "backend"),
+        member("h1", "redis", "backend"),
+        member("h1", "redis", "frontend"),
+        member("h1", "redis", "cache"),
+    ];
+    assert_eq!(
+        reach(at("h1", "app"), at("h1", "redis"), &members, &[]),
+        Reach::Networks(vec!["backend".to_string(), "frontend".to_string()])
+    );
+}
+
+#[test]
+fn test_published_ports_when_no_network_is_shared() {
+    let members = vec![member("h1", "app", "frontend"), member("h1", "db", "data")];
+    let ports = vec![port("h1", "db", 5432), port("h1", "app", 80)];
+    assert_eq!(
+        reach(at("h1", "app"), at("h1", "db"), &members, &ports),
+        Reach::HostPorts(vec![port("h1", "db", 5432)])
+    );
+    assert_eq!(
+        reach(at("h1", "db"), at("h1", "app"), &members, &ports),
+        Reach::HostPorts(vec![port("h1", "app", 80)])
+    );
+}
+
+#[test]
+fn test_same_network_name_on_other_host_is_not_shared() {
+    let members = vec![
+        member("h1", "app", "backend"),
+        member("h2", "redis", "backend"),
+    ];
+    assert_eq!(
+        reach(at("h1", "app"), at("h2", "redis"), &members, &[]),
+        Reach::Unreachable
+    );
+    let ports = vec![port("h2", "redis", 6379)];
+    assert!(matches!(
+        reach(at("h1", "app"), at("h2", "redis"), &members, &ports),
+        Reach::HostPorts(_)
+    ));
+}
+
+#[test]
+fn test_none_network_is_isolated() {
+    let members = vec![member("h1", "batch", "none"), member("h1", "other", "none")];
+    let ports = vec![port("h1", "other", 8080)];
+    // Sharing "none" is not a connection, and "none" can't use host ports either
+    assert_eq!(
+        reach(at("h1", "batch"), at("h1", "other"), &members, &ports),
+        Reach::Unreachable
+    );
+}
+
+#[test]
+fn test_host_mode_containers_share_the_host_network() {
+    let members = vec![
+        member("h1", "exporter", "host"),
+        member("h1", "agent", "host"),
+    ];
+    assert_eq!(
+        reach(at("h1", "exporter"), at("h1", "agent"), &members, &[]),
+        Reach::Networks(vec!["host".to_string()])
+    );
+}
+
+#[test]
+fn test_container_without_attachments_can_use_ports() {
+    // e.g. --network container:x, which has no attachments of its own
+    let members = vec![member("h1", "db", "data")];
+    let ports = vec![port("h1", "db", 5432)];
+    assert!(matches!(
+        reach(at("h1", "sidecar"), at("h1", "db"), &members, &ports),
+        Reach::HostPorts(_)
+    ));
+}
+
+#[test]
+fn test_network_edges() {
+    let members = vec![
+        member("h1", "app", "backend"),
+        member("h1", "app", "frontend"),
+        member("h1", "redis", "backend"),
+        member("h1", "proxy", "frontend"),
+        member("h1", "batch", "none"),
+        member("h1", "worker", "none"),
+    ];
+    let containers: Vec<String> = ["app", "redis", "proxy", "batch", "worker"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    let edge = |a: &str, b: &str, network: &str| NetworkEdge {
+        a: a.to_string(),
+        b: b.to_string(),
+        network: network.to_string(),
+    };
+    assert_eq!(
+        network_edges(&containers, &members),
+        vec![
+            edge("app", "redis", "backend"),
+            edge("app", "proxy", "frontend")
+        ]
+    );
+}
@@ -0,0 +1,180 @@
+use chrono::{Duration, TimeZone, Utc};
+use pctrl_core::parse::{
+    age, age_at, duration, duration_or_secs, help, mode, rate, size, AGE_FORMATS, DURATION_FORMATS,
+    SIZE_FORMATS,
+};
+
+#[test]
+fn test_durations() {
+    let cases: &[(&str, i64)] = &[
+        ("45s", 45),
+        ("90m", 5_400),
+        ("2h30m", 9_000),
+        ("1d", 86_400),
+        ("2w", 1_209_600),
+        ("1y", 31_536_000),
+        ("1d12h30m15s", 131_415),
+        ("0", 0),
+        ("0s", 0),
+        ("8H", 28_800),
+        ("  30m ", 1_800),
+        ("1h 2m", 3_720),
+        ("2 hours", 7_200),
+        ("10 min", 600),
+    ];
+    for (input, seconds) in cases {
+        assert_eq!(
+            duration(input),
+            Ok(Duration::seconds(*seconds)),
+            "input {:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_invalid_durations() {
+    let cases: &[(&str, &str)] = &[
+        ("", "empty duration"),
+        ("   ", "empty duration"),
+        ("90", "missing unit"),
+        ("2h30", "missing unit"),
+        ("h", "missing number"),
+        ("5x", "unknown unit 'x'"),
+        ("1.5h", "unexpected '.'"),
+        ("-5m", "unexpected '-'"),
+        ("30m2h", "units must go from largest to smallest"),
+        ("1h1h", "units must go from largest to smallest"),
+        ("99999999999999999999s", "too large"),
+        ("9999999999999999w", "too large"),
+    ];
+    for (input, reason) in cases {
+        let err = duration(input).expect_err(input);
+        assert_eq!(err.reason, *reason, "input {:?}", input);
+    }
+
+    // The message names the input and shows examples
+    assert_eq!(
+        duration("90").unwrap_err().to_string(),
+        format!("missing unit in '90' (use e.g. {})", DURATION_FORMATS)
+    );
+}
+
+#[test]
+fn test_plain_numbers_as_seconds() {
+    assert_eq!(duration_or_secs("60"), Ok(Duration::seconds(60)));
+    assert_eq!(duration_or_secs("2m"), Ok(Duration::seconds(120)));
+    assert_eq!(duration_or_secs("0"), Ok(Duration::zero()));
+    assert!(duration_or_secs("1m30").is_err());
+}
+
+#[test]
+fn test_ages() {
+    let now = Utc.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+    let cases: &[(&str, chrono::DateTime<Utc>)] = &[
+        ("7d", now - Duration::days(7)),
+        ("2w", now - Duration::weeks(2)),
+        ("12h", now - Duration::hours(12)),
+        ("1y", now - Duration::days(365)),
+        ("0", now),
+        (
+            "2024-01-01",
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ),
+        (
+            " 2024-01-01T08:30:00+02:00 ",
+            Utc.with_ymd_and_hms(2024, 1, 1, 6, 30, 0).unwrap(),
+        ),
+    ];
+    for (input, cutoff) in cases {
+        assert_eq!(age_at(input, now), Ok(*cutoff), "input {:?}", input);
+    }
+
+    for (input, reason) in [
+        ("2024-13-01", "invalid date"),
+        ("2024-01-01 10:00", "invalid date"),
+        ("7", "missing unit"),
+        ("yesterday", "missing number"),
+    ] {
+        assert_eq!(age_at(input, now).unwrap_err().reason, reason);
+    }
+}
+
+#[test]
+fn test_sizes() {
+    let cases: &[(&str, u64)] = &[
+        ("512mb", 512 << 20),
+        ("2GiB", 2 << 30),
+        ("2gb", 2 << 30),
+        ("1.5g", 3 << 29),
+        ("100k", 102_400),
+        ("10 B", 10),
+        (" 1 TB ", 1 << 40),
+        ("0", 0),
+    ];
+    for (input, bytes) in cases {
+        assert_eq!(size(input), Ok(*bytes), "input {:?}", input);
+    }
+
+    for (input, reason) in [
+        ("", "empty size"),
+        ("512", "missing unit"),
+        ("mb", "missing number"),
+        ("1.2.3mb", "invalid number"),
+        ("5pb", "unknown unit 'pb'"),
+        ("99999999999tb", "too large"),
+    ] {
+        assert_eq!(size(input).unwrap_err().reason, reason, "input {:?}", input);
+    }
+}
+
+#[test]
+fn test_rates() {
+    assert_eq!(rate("2MB/s"), Ok(2 << 20));
+    assert_eq!(rate(" 500kb/S "), Ok(500 << 10));
+    assert_eq!(rate("1gb"), Ok(1 << 30));
+    for (input, reason) in [
+        ("0", "zero rate"),
+        ("0mb/s", "zero rate"),
+        ("2/s", "missing unit"),
+        ("fast", "missing number"),
+    ] {
+        assert_eq!(rate(input).unwrap_err().reason, reason, "input {:?}", input);
+    }
+    assert!(rate("2mbit/s").unwrap_err().to_string().contains("2MB/s"));
+}
+
+#[test]
+fn test_modes() {
+    assert_eq!(mode("644"), Ok(0o644));
+    assert_eq!(mode("0600"), Ok(0o600));
+    assert_eq!(mode(" 0o755 "), Ok(0o755));
+    assert_eq!(mode("4755"), Ok(0o4755));
+    for (input, reason) in [
+        ("", "empty mode"),
+        ("rw-r--r--", "not octal"),
+        ("689", "not octal"),
+        ("17777", "too large"),
+    ] {
+        assert_eq!(mode(input).unwrap_err().reason, reason, "input {:?}", input);
+    }
+    assert!(mode("u+x").unwrap_err().to_string().contains("0600"));
+}
+
+#[test]
+fn test_help_lists_formats() {
+    assert_eq!(
+        help("Lock lifetime", DURATION_FORMATS),
+        "Lock lifetime (e.g. 45s, 90m, 2h30m, 1d)"
+    );
+    // Every example in the help text parses
+    for example in DURATION_FORMATS.split(", ") {
+        assert!(duration(example).is_ok(), "{}", example);
+    }
+    for example in AGE_FORMATS.split(", ") {
+        assert!(age(example).is_ok(), "{}", example);
+    }
+    for example in SIZE_FORMATS.split(", ") {
+        assert!(size(example).is_ok(), "{}", example);
+    }
+}
@@ -0,0 +1,181 @@
+use pctrl_core::placeholder::{Placeholder, Placeholders, SecretSource};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Environment, files and typed answers from memory
+#[derive(Default)]
+struct Fake {
+    env: HashMap<&'static str, &'static str>,
+    files: HashMap<PathBuf, &'static str>,
+    answers: VecDeque<&'static str>,
+    prompted: Vec<String>,
+}
+
+impl SecretSource for Fake {
+    fn prompt(&mut self, label: &str) -> Result<String, String> {
+        self.prompted.push(label.to_string());
+        self.answers
+            .pop_front()
+            .map(String::from)
+            .ok_or_else(|| "no terminal".to_string())
+    }
+
+    fn env(&self, name: &str) -> Option<String> {
+        self.env.get(name).map(|v| v.to_string())
+    }
+
+    fn read_file(&self, path: &Path) -> std::io::Result<String> {
+        self.files
+            .get(path)
+            .map(|c| c.to_string())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No such file"))
+    }
+}
+
+#[test]
+fn test_parse_placeholders() {
+    assert_eq!(Placeholder::parse("@prompt"), Some(Placeholder::Prompt));
+    assert_eq!(
+        Placeholder::parse("@env:COOLIFY_TOKEN"),
+        Some(Placeholder::Env("COOLIFY_TOKEN".to_string()))
+    );
+    assert_eq!(
+        Placeholder::parse("@file:/run/secrets/x"),
+        Some(Placeholder::File(PathBuf::from("/run/secrets/x")))
+    );
+    for literal in [
+        "hunter2",
+        "@prompting",
+        "@env:",
+        "@file:",
+        "@secret",
+        "prompt",
+    ] {
+        assert_eq!(Placeholder::parse(literal), None, "{}", literal);
+    }
+    assert_eq!(
+        Placeholder::parse("@env:COOLIFY_TOKEN")
+            .unwrap()
+            .to_string(),
+        "@env:COOLIFY_TOKEN"
+    );
+}
+
+#[test]
+fn test_add_keeps_literals_and_unescapes() {
+    let mut placeholders = Placeholders::default();
+    assert_eq!(
+        placeholders.add("credential 'a'", "password", "hunter2"),
+        Some("hunter2".to_string())
+    );
+    assert_eq!(
+        placeholders.add("credential 'a'", "password", "@@prompt"),
+        Some("@prompt".to_string())
+    );
+    assert_eq!(
+        placeholders.add("credential 'a'", "password", "@@x"),
+        Some("@@x".to_string())
+    );
+    assert!(placeholders.is_empty());
+
+    assert_eq!(
+        placeholders.add("credential 'a'", "password", "@prompt"),
+        None
+    );
+    assert_eq!(placeholders.refs().len(), 1);
+    assert_eq!(
+        placeholders.refs()[0].label(),
+        "password for credential 'a'"
+    );
+}
+
+#[test]
+fn test_resolve_env_file_then_prompts_in_one_batch() {
+    let mut placeholders = Placeholders::default();
+    placeholders.add("credential 'prod-db'", "password", "@prompt");
+    placeholders.add("credential 'coolify'", "api_key", "@env:COOLIFY_TOKEN");
+    placeholders.add("credential 'deploy'", "passphrase", "@file:/run/secrets/x");
+    placeholders.add("credential 'staging-db'", "password", "@prompt");
+
+    let mut source = Fake {
+        env: HashMap::from([("COOLIFY_TOKEN", "tok-123")]),
+        files: HashMap::from([(PathBuf::from("/run/secrets/x"), "s3cret\r\n")]),
+        answers: VecDeque::from(["pw-prod", "pw-staging"]),
+        ..Fake::default()
+    };
+    let secrets = placeholders.resolve(&mut source).unwrap();
+
+    assert_eq!(secrets.len(), 4);
+    assert_eq!(
+        secrets.get("credential 'coolify'", "api_key"),
+        Some("tok-123")
+    );
+    // One trailing newline is dropped
+    assert_eq!(
+        secrets.get("credential 'deploy'", "passphrase"),
+        Some("s3cret")
+    );
+    assert_eq!(
+        secrets.get("credential 'prod-db'", "password"),
+        Some("pw-prod")
+    );
+    assert_eq!(
+        secrets.get("credential 'staging-db'", "password"),
+        Some("pw-staging")
+    );
+    assert_eq!(
+        source.prompted,
+        [
+            "password for credential 'prod-db'",
+            "password for credential 'staging-db'"
+        ]
+    );
+
+    // Values never show up in debug output
+    let debug = format!("{:?}", secrets);
+    assert!(!debug.contains("pw-prod") && !debug.contains("tok-123"));
+}
+
+#[test]
+fn test_unresolvable_placeholders_are_reported_together_before_prompting() {
+    let mut placeholders = Placeholders::default();
+    placeholders.add("credential 'prod-db'", "password", "@prompt");
+    placeholders.add("credential 'coolify'", "api_key", "@env:COOLIFY_TOKEN");
+    placeholders.add("credential 'hetzner'", "token", "@env:HCLOUD_TOKEN");
+    placeholders.add("credential 'deploy'", "passphrase", "@file:/run/secrets/x");
+
+    let mut source = Fake {
+        env: HashMap::from([("HCLOUD_TOKEN", "")]),
+        answers: VecDeque::from(["pw-prod"]),
+        ..Fake::default()
+    };
+    let err = placeholders.resolve(&mut source).unwrap_err();
+
+    assert_eq!(err.failures.len(), 3);
+    assert!(source.prompted.is_empty());
+    assert_eq!(
+        err.to_string(),
+        "3 secret placeholders can't be resolved, nothing was applied:\n  \
+         api_key for credential 'coolify' (@env:COOLIFY_TOKEN): COOLIFY_TOKEN is not set\n  \
+         token for credential 'hetzner' (@env:HCLOUD_TOKEN): HCLOUD_TOKEN is empty\n  \
+         passphrase for credential 'deploy' (@file:/run/secrets/x): No such file"
+    );
+}
+
+#[test]
+fn test_failed_prompts_are_aggregated() {
+    let mut placeholders = Placeholders::default();
+    placeholders.add("credential 'a'", "password", "@prompt");
+    placeholders.add("credential 'b'", "password", "@prompt");
+    placeholders.add("credential 'c'", "password", "@prompt");
+
+    // Empty answer, then no terminal left
+    let mut source = Fake {
+        answers: VecDeque::from(["ok", ""]),
+        ..Fake::default()
+    };
+    let err = placeholders.resolve(&mut source).unwrap_err();
+    let reasons: Vec<&str> = err.failures.iter().map(|(_, r)| r.as_str()).collect();
+    assert_eq!(reasons, ["nothing entered", "no terminal"]);
+    assert_eq!(source.prompted.len(), 3);
+}
@@ -0,0 +1,113 @@
+use pctrl_core::preflight::{evaluate, CheckKind, CheckResult, PreflightConfig, Verdict};
+
+fn advisory(kinds: &[CheckKind]) -> PreflightConfig {
+    PreflightConfig {
+        health_url: None,
+        advisory: kinds.to_vec(),
+    }
+}
+
+#[test]
+fn test_verdicts() {
+    let db_down = CheckResult::failed(CheckKind::Database, "main-db", "connection refused");
+    let server_up = CheckResult::passed(CheckKind::Server, "web-1");
+    let health_down = CheckResult::failed(CheckKind::Health, "https://app/health", "HTTP 503");
+
+    let cases: Vec<(&str, Vec<CheckResult>, PreflightConfig, Verdict)> = vec![
+        ("no checks", vec![], advisory(&[]), Verdict::Clear),
+        (
+            "all passed",
+            vec![server_up.clone()],
+            advisory(&[]),
+            Verdict::Clear,
+        ),
+        (
+            "blocking failure",
+            vec![server_up.clone(), db_down.clone()],
+            advisory(&[]),
+            Verdict::Refused,
+        ),
+        (
+            "advisory failure",
+            vec![server_up.clone(), health_down.clone()],
+            advisory(&[CheckKind::Health]),
+            Verdict::Warnings,
+        ),
+        (
+            "advisory does not cover other kinds",
+            vec![db_down.clone(), health_down.clone()],
+            advisory(&[CheckKind::Health]),
+            Verdict::Refused,
+        ),
+        (
+            "everything advisory",
+            vec![db_down, health_down],
+            advisory(&CheckKind::ALL),
+            Verdict::Warnings,
+        ),
+    ];
+
+    for (name, checks, config, expected) in cases {
+        assert_eq!(
+            evaluate(checks, &config).verdict,
+            expected,
+            "case: {}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_failures_split_by_policy() {
+    let config = advisory(&[CheckKind::Health]);
+    let report = evaluate(
+        vec![
+            CheckResult::passed(CheckKind::Server, "web-1"),
+            CheckResult::failed(CheckKind::Database, "main-db", "timed out"),
+            CheckResult::failed(CheckKind::Health, "https://app/health", "HTTP 500"),
+        ],
+        &config,
+    );
+
+    let blocking: Vec<&str> = report
+        .blocking_failures(&config)
+        .iter()
+        .map(|c| c.target.as_str())
+        .collect();
+    let advisory: Vec<&str> = report
+        .advisory_failures(&config)
+        .iter()
+        .map(|c| c.target.as_str())
+        .collect();
+    assert_eq!(blocking, vec!["main-db"]);
+    assert_eq!(advisory, vec!["https://app/health"]);
+}
+
+#[test]
+fn test_set_advisory() {
+    let mut config = PreflightConfig::default();
+    assert!(CheckKind::ALL.iter().all(|k| config.is_blocking(*k)));
+
+    config.set_advisory(CheckKind::Health, true);
+    config.set_advisory(CheckKind::Database, true);
+    config.set_advisory(CheckKind::Health, true);
+    assert_eq!(
+        config.advisory,
+        vec![CheckKind::Database, CheckKind::Health]
+    );
+
+    config.set_advisory(CheckKind::Database, false);
+    assert_eq!(config.advisory, vec![CheckKind::Health]);
+    assert!(config.is_blocking(CheckKind::Database));
+}
+
+#[test]
+fn test_check_kind_parse() {
+    assert_eq!("server".parse::<CheckKind>(), Ok(CheckKind::Server));
+    assert_eq!("DB".parse::<CheckKind>(), Ok(CheckKind::Database));
+    assert_eq!("health".parse::<CheckKind>(), Ok(CheckKind::Health));
+    assert!("disk".parse::<CheckKind>().is_err());
+    for kind in CheckKind::ALL {
+        assert_eq!(kind.to_string().parse::<CheckKind>(), Ok(kind));
+    }
+}
@@ -0,0 +1,330 @@
+use pctrl_core::bundle::ProjectBundle;
+use pctrl_core::project_clone::{self, CloneOptions, ClonePlan};
+use pctrl_core::{
+    Domain, DomainType, Project, ProjectResource, ProjectStatus, ResourceType, Script,
+    ScriptResult, ScriptType,
+};
+
+fn project(name: &str) -> Project {
+    Project {
+        id: project_clone::slug(name),
+        name: name.to_string(),
+        description: Some("Online shop".to_string()),
+        stack: vec!["rust".to_string(), "postgres".to_string()],
+        status: ProjectStatus::Live,
+        color: Some("#ff8800".to_string()),
+        icon: None,
+        notes: Some("runbook in wiki".to_string()),
+    }
+}
+
+fn script(name: &str, project_id: Option<&str>) -> Script {
+    Script {
+        id: project_clone::slug(name),
+        name: name.to_string(),
+        description: None,
+        command: format!("run {}", name),
+        script_type: ScriptType::Ssh,
+        server_id: Some("web".to_string()),
+        project_id: project_id.map(String::from),
+        docker_host_id: None,
+        container_id: None,
+        dangerous: true,
+        last_run: Some("2026-03-01T10:00:00Z".to_string()),
+        last_result: Some(ScriptResult::Success),
+        exit_code: Some(0),
+        last_output: Some("ok".to_string()),
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn domain(name: &str) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: Some("2026-06-01".to_string()),
+        cloudflare_zone_id: Some("zone".to_string()),
+        cloudflare_record_id: Some("record".to_string()),
+        server_id: Some("web".to_string()),
+        container_id: Some("shop-app".to_string()),
+        notes: Some("main".to_string()),
+        superseded_by: None,
+    }
+}
+
+fn link(kind: ResourceType, resource_id: &str, role: Option<&str>) -> ProjectResource {
+    ProjectResource {
+        id: format!("shop-{}-{}", kind, resource_id),
+        project_id: "shop".to_string(),
+        resource_type: kind,
+        resource_id: resource_id.to_string(),
+        role: role.map(String::from),
+        notes: None,
+        start_order: Some(1),
+    }
+}
+
+/// "shop" with a server, database, two domains, a container, a git repo and
+/// a linked script owned by another project
+fn shop() -> ProjectBundle {
+    ProjectBundle {
+        project: project("shop"),
+        links: vec![
+            link(ResourceType::Server, "web", Some("production")),
+            link(ResourceType::Database, "shop-db", None),
+            link(ResourceType::Domain, "shop.example.com", Some("production")),
+            link(ResourceType::Domain, "api-shop-example-com", None),
+            link(ResourceType::Container, "shop-app", None),
+            link(ResourceType::Git, "shop-repo", None),
+            link(ResourceType::Script, "uptime", Some("health check")),
+        ],
+        servers: Vec::new(),
+        domains: vec![domain("shop.example.com"), domain("api.shop.example.com")],
+        databases: Vec::new(),
+        scripts: vec![script("uptime", Some("ops"))],
+    }
+}
+
+fn owned() -> Vec<Script> {
+    vec![
+        script("shop-backup", Some("shop")),
+        script("deploy", Some("shop")),
+    ]
+}
+
+fn kinds(links: &[ProjectResource]) -> Vec<String> {
+    links.iter().map(|l| l.resource_type.to_string()).collect()
+}
+
+#[test]
+fn test_clone_copies_only_the_project_by_default() {
+    let bundle = shop();
+    let plan = ClonePlan::new(
+        &bundle,
+        &owned(),
+        "Acme Shop",
+        &CloneOptions::default(),
+        std::slice::from_ref(&bundle.project),
+        &owned(),
+        &bundle.domains,
+    )
+    .unwrap();
+
+    assert_eq!(plan.project.id, "acme-shop");
+    assert_eq!(plan.project.name, "Acme Shop");
+    assert_eq!(plan.project.status, ProjectStatus::Dev);
+    assert_eq!(plan.project.stack, bundle.project.stack);
+    assert_eq!(plan.project.description, bundle.project.description);
+    assert_eq!(plan.project.notes, bundle.project.notes);
+    assert!(plan.scripts.is_empty());
+    assert!(plan.domains.is_empty());
+    assert!(plan.links.is_empty());
+    assert_eq!(plan.skipped.len(), bundle.links.len());
+}
+
+#[test]
+fn test_clone_links_servers_and_databases_only_when_asked() {
+    let bundle = shop();
+    let options = CloneOptions {
+        link_same_servers: true,
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &[]).unwrap();
+    assert_eq!(kinds(&plan.links), vec!["server"]);
+    let server = &plan.links[0];
+    assert_eq!(server.project_id, "acme");
+    assert_eq!(server.resource_id, "web");
+    assert_eq!(server.role.as_deref(), Some("production"));
+    assert_eq!(server.start_order, Some(1));
+    assert_ne!(server.id, bundle.links[0].id);
+
+    let options = CloneOptions {
+        link_same_servers: true,
+        link_same_databases: true,
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &[]).unwrap();
+    assert_eq!(kinds(&plan.links), vec!["server", "database"]);
+    // Deployment-specific links are never carried over
+    assert_eq!(
+        kinds(&plan.skipped),
+        vec!["domain", "domain", "container", "git", "script"]
+    );
+}
+
+#[test]
+fn test_clone_scripts_get_new_ids_and_free_names() {
+    let bundle = shop();
+    // "acme-deploy" is taken, and so is its first fallback
+    let mut existing = owned();
+    existing.push(script("acme-deploy", None));
+    existing.push(script("acme-deploy-2", None));
+    existing.push(bundle.scripts[0].clone());
+    let options = CloneOptions {
+        include_scripts: true,
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &owned(), "acme", &options, &[], &existing, &[]).unwrap();
+
+    let names: Vec<(&str, &str, &str)> = plan
+        .scripts
+        .iter()
+        .map(|c| {
+            (
+                c.from.as_str(),
+                c.script.id.as_str(),
+                c.script.name.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            // Named after the source project: renamed for the new one
+            ("shop-backup", "acme-backup", "acme-backup"),
+            ("deploy", "acme-deploy-3", "acme-deploy-3"),
+            // Only linked, owned by another project: copied too
+            ("uptime", "acme-uptime", "acme-uptime"),
+        ]
+    );
+    for cloned in &plan.scripts {
+        let script = &cloned.script;
+        assert_eq!(script.project_id.as_deref(), Some("acme"));
+        assert_eq!(script.command, format!("run {}", cloned.from));
+        assert!(script.dangerous);
+        assert_eq!(script.server_id.as_deref(), Some("web"));
+        assert!(script.last_run.is_none());
+        assert!(script.last_result.is_none());
+        assert!(script.exit_code.is_none());
+        assert!(script.last_output.is_none());
+    }
+
+    // The script link points at the copy
+    assert_eq!(kinds(&plan.links), vec!["script"]);
+    assert_eq!(plan.links[0].resource_id, "acme-uptime");
+    assert_eq!(plan.links[0].role.as_deref(), Some("health check"));
+}
+
+#[test]
+fn test_clone_scripts_never_collide_with_each_other() {
+    let mut bundle = shop();
+    bundle.links.clear();
+    // Both map to "acme-backup"
+    let scripts = vec![
+        script("backup", Some("shop")),
+        script("shop backup", Some("shop")),
+    ];
+    let options = CloneOptions {
+        include_scripts: true,
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &scripts, "acme", &options, &[], &scripts, &[]).unwrap();
+    let ids: Vec<&str> = plan.scripts.iter().map(|c| c.script.id.as_str()).collect();
+    assert_eq!(ids, vec!["acme-backup", "acme-backup-2"]);
+}
+
+#[test]
+fn test_clone_domains_with_suffix() {
+    let bundle = shop();
+    let options = CloneOptions {
+        domain_suffix: Some("-Acme".to_string()),
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &bundle.domains).unwrap();
+
+    let names: Vec<(&str, &str, &str)> = plan
+        .domains
+        .iter()
+        .map(|c| {
+            (
+                c.from.as_str(),
+                c.domain.domain.as_str(),
+                c.domain.id.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (
+                "shop.example.com",
+                "shop-acme.example.com",
+                "shop-acme-example-com"
+            ),
+            (
+                "api.shop.example.com",
+                "api-acme.shop.example.com",
+                "api-acme-shop-example-com"
+            ),
+        ]
+    );
+    let copy = &plan.domains[0].domain;
+    assert!(copy.ssl);
+    assert_eq!(copy.notes.as_deref(), Some("main"));
+    // Certificates, Cloudflare records and containers belong to the source;
+    // the server only comes along with --link-same-servers
+    assert!(copy.ssl_expiry.is_none());
+    assert!(copy.cloudflare_zone_id.is_none());
+    assert!(copy.container_id.is_none());
+    assert!(copy.server_id.is_none());
+
+    // Links by name and by ID both point at the copies
+    assert_eq!(kinds(&plan.links), vec!["domain", "domain"]);
+    assert_eq!(plan.links[0].resource_id, "shop-acme-example-com");
+    assert_eq!(plan.links[0].role.as_deref(), Some("production"));
+
+    let options = CloneOptions {
+        domain_suffix: Some("acme".to_string()),
+        link_same_servers: true,
+        ..CloneOptions::default()
+    };
+    let plan = ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &[]).unwrap();
+    assert_eq!(plan.domains[0].domain.server_id.as_deref(), Some("web"));
+}
+
+#[test]
+fn test_clone_rejects_taken_names() {
+    let bundle = shop();
+    let err = ClonePlan::new(
+        &bundle,
+        &[],
+        "SHOP",
+        &CloneOptions::default(),
+        std::slice::from_ref(&bundle.project),
+        &[],
+        &[],
+    )
+    .unwrap_err();
+    assert!(err.contains("already exists"));
+
+    let options = CloneOptions {
+        domain_suffix: Some("acme".to_string()),
+        ..CloneOptions::default()
+    };
+    let taken = vec![domain("shop-acme.example.com")];
+    let err = ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &taken).unwrap_err();
+    assert!(err.contains("shop-acme.example.com"));
+
+    let options = CloneOptions {
+        domain_suffix: Some("ac me".to_string()),
+        ..CloneOptions::default()
+    };
+    assert!(ClonePlan::new(&bundle, &[], "acme", &options, &[], &[], &[]).is_err());
+    assert!(ClonePlan::new(&bundle, &[], "  ", &CloneOptions::default(), &[], &[], &[]).is_err());
+}
+
+#[test]
+fn test_suffixed_domain() {
+    assert_eq!(
+        project_clone::suffixed_domain("app.example.com", "b"),
+        "app-b.example.com"
+    );
+    assert_eq!(
+        project_clone::suffixed_domain("localhost", "b"),
+        "localhost-b"
+    );
+}
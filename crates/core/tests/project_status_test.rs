@@ -0,0 +1,57 @@
+use pctrl_core::fanout::Outcome;
+use pctrl_core::project_status;
+use std::time::Duration;
+
+#[test]
+fn test_domain_url_follows_ssl() {
+    assert_eq!(
+        project_status::domain_url("shop.example.com", true),
+        "https://shop.example.com/"
+    );
+    assert_eq!(
+        project_status::domain_url("shop.example.com", false),
+        "http://shop.example.com/"
+    );
+}
+
+#[test]
+fn test_verdicts() {
+    // Any answer below 500 means the site is served
+    assert_eq!(
+        project_status::http_verdict(405),
+        (Outcome::Ok, Some("HTTP 405".to_string()))
+    );
+    assert_eq!(project_status::http_verdict(502).0, Outcome::Failed);
+
+    assert_eq!(
+        project_status::container_verdict("running", None),
+        (Outcome::Ok, None)
+    );
+    assert_eq!(
+        project_status::container_verdict("running", Some("healthy")).0,
+        Outcome::Ok
+    );
+    assert_eq!(
+        project_status::container_verdict("running", Some("unhealthy")),
+        (Outcome::Failed, Some("unhealthy".to_string()))
+    );
+    assert_eq!(
+        project_status::container_verdict("exited", None),
+        (Outcome::Failed, Some("exited".to_string()))
+    );
+
+    // Uncommitted changes are reported, not failed
+    assert_eq!(
+        project_status::git_verdict(0),
+        (Outcome::Ok, Some("clean".to_string()))
+    );
+    assert_eq!(
+        project_status::git_verdict(3),
+        (Outcome::Ok, Some("dirty, 3 changed files".to_string()))
+    );
+
+    assert_eq!(
+        project_status::timed_out(Duration::from_secs(5)),
+        (Outcome::Failed, Some("timed out after 5s".to_string()))
+    );
+}
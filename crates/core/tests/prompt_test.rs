@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, Utc};
+use pctrl_core::monitor::MonitorState;
+use pctrl_core::prompt::{init_script, PromptFormat, PromptHealth, PromptShell};
+
+fn now() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn state(secs_ago: i64, down: u32) -> MonitorState {
+    MonitorState {
+        last_cycle_at: (now() - Duration::seconds(secs_ago)).to_rfc3339(),
+        servers_checked: 3,
+        servers_down: down,
+        duration_ms: 80,
+        interval_secs: 300,
+        heartbeat_error: None,
+    }
+}
+
+#[test]
+fn test_judge() {
+    let cases = [
+        (
+            "healthy",
+            Some(12),
+            Some(state(60, 0)),
+            PromptHealth::Healthy { servers: 12 },
+        ),
+        (
+            "count unreadable",
+            None,
+            Some(state(60, 0)),
+            PromptHealth::Healthy { servers: 3 },
+        ),
+        (
+            "servers down",
+            Some(12),
+            Some(state(60, 2)),
+            PromptHealth::Problems { count: 2 },
+        ),
+        (
+            "stale monitor",
+            Some(12),
+            Some(state(3600, 2)),
+            PromptHealth::Unknown { servers: Some(12) },
+        ),
+        (
+            "never ran",
+            Some(12),
+            None,
+            PromptHealth::Unknown { servers: Some(12) },
+        ),
+        (
+            "nothing readable",
+            None,
+            None,
+            PromptHealth::Unknown { servers: None },
+        ),
+    ];
+    for (name, servers, state, expected) in cases {
+        assert_eq!(
+            PromptHealth::judge(servers, state.as_ref(), now()),
+            expected,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn test_render() {
+    let healthy = PromptHealth::Healthy { servers: 12 };
+    let problems = PromptHealth::Problems { count: 2 };
+    let unknown = PromptHealth::Unknown { servers: None };
+
+    assert_eq!(healthy.render(PromptFormat::Plain), "pctrl ✔ 12");
+    assert_eq!(problems.render(PromptFormat::Plain), "pctrl ✘ 2");
+    assert_eq!(unknown.render(PromptFormat::Plain), "pctrl ?");
+
+    let powerline = problems.render(PromptFormat::Powerline);
+    assert!(powerline.contains(" pctrl ✘ 2 "));
+    assert!(powerline.starts_with("\x1b[30;41m"));
+    assert!(powerline.ends_with("\x1b[0m"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&unknown.render(PromptFormat::Json)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"status": "unknown", "servers": null, "problems": null})
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&healthy.render(PromptFormat::Json)).unwrap();
+    assert_eq!(json["problems"], 0);
+}
+
+#[test]
+fn test_parse_and_init() {
+    assert_eq!("JSON".parse::<PromptFormat>(), Ok(PromptFormat::Json));
+    assert!("fancy".parse::<PromptFormat>().is_err());
+    assert!("tcsh".parse::<PromptShell>().is_err());
+
+    for shell in [PromptShell::Zsh, PromptShell::Bash, PromptShell::Fish] {
+        let script = init_script(shell, "pctrl --db /tmp/work.db");
+        assert!(
+            script.contains("pctrl --db /tmp/work.db prompt-segment --format plain"),
+            "{:?}",
+            shell
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, TimeZone, Utc};
+use pctrl_core::propagation::{
+    check, check_many, AnswerStatus, DnsLookup, DnsRecord, PropagationReport, ResolverAnswer,
+    Target, TrackedPropagation,
+};
+use pctrl_core::throttle::Limits;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const NEW: &str = "203.0.113.10";
+const OLD: &str = "198.51.100.7";
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+/// How one mocked resolver behaves
+#[derive(Clone)]
+enum Reply {
+    Records(Vec<(&'static str, u32)>),
+    Error(&'static str),
+    /// Answers only after this long
+    Slow(Duration),
+}
+
+struct Mock {
+    replies: HashMap<IpAddr, Reply>,
+}
+
+impl Mock {
+    fn new(replies: &[(&str, Reply)]) -> Self {
+        Self {
+            replies: replies
+                .iter()
+                .map(|(r, reply)| (ip(r), reply.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl DnsLookup for Mock {
+    async fn lookup(&self, resolver: IpAddr, _domain: &str) -> Result<Vec<DnsRecord>, String> {
+        match &self.replies[&resolver] {
+            Reply::Records(records) => Ok(records
+                .iter()
+                .map(|(addr, ttl)| DnsRecord {
+                    ip: ip(addr),
+                    ttl: *ttl,
+                })
+                .collect()),
+            Reply::Error(e) => Err(e.to_string()),
+            Reply::Slow(delay) => {
+                tokio::time::sleep(*delay).await;
+                Ok(vec![DnsRecord {
+                    ip: ip(NEW),
+                    ttl: 300,
+                }])
+            }
+        }
+    }
+}
+
+fn answer(records: &[(&str, u32)]) -> ResolverAnswer {
+    ResolverAnswer {
+        resolver: ip("1.1.1.1"),
+        records: records
+            .iter()
+            .map(|(addr, ttl)| DnsRecord {
+                ip: ip(addr),
+                ttl: *ttl,
+            })
+            .collect(),
+        error: None,
+    }
+}
+
+#[tokio::test]
+async fn test_slow_resolver_only_fails_its_own_row() {
+    let mock = Mock::new(&[
+        ("1.1.1.1", Reply::Records(vec![(NEW, 300)])),
+        ("8.8.8.8", Reply::Slow(Duration::from_secs(60))),
+        ("9.9.9.9", Reply::Records(vec![(NEW, 120)])),
+    ]);
+    let resolvers = [ip("1.1.1.1"), ip("8.8.8.8"), ip("9.9.9.9")];
+
+    let started = std::time::Instant::now();
+    let report = check(
+        &mock,
+        "shop.example.com",
+        &resolvers,
+        &[ip(NEW)],
+        Duration::from_millis(100),
+    )
+    .await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Answers keep the order of the resolvers
+    let order: Vec<IpAddr> = report.answers.iter().map(|a| a.resolver).collect();
+    assert_eq!(order, resolvers);
+    assert_eq!(
+        report.answers[1].error.as_deref(),
+        Some("no answer within 100ms")
+    );
+    assert_eq!(
+        report.answers[1].status(&report.expected),
+        AnswerStatus::Failed
+    );
+    assert_eq!(report.answers[2].min_ttl(), Some(120));
+
+    // The failed resolver doesn't count against the percentage
+    assert_eq!(report.responding(), 2);
+    assert_eq!(report.percentage(), 100);
+    assert!(report.is_complete());
+}
+
+#[tokio::test]
+async fn test_percentage_over_responding_resolvers() {
+    let mock = Mock::new(&[
+        ("1.1.1.1", Reply::Records(vec![(NEW, 300)])),
+        ("8.8.8.8", Reply::Records(vec![(OLD, 3600)])),
+        ("9.9.9.9", Reply::Records(vec![(NEW, 60)])),
+        ("208.67.222.222", Reply::Error("connection refused")),
+    ]);
+    let report = check(
+        &mock,
+        "shop.example.com",
+        &[
+            ip("1.1.1.1"),
+            ip("8.8.8.8"),
+            ip("9.9.9.9"),
+            ip("208.67.222.222"),
+        ],
+        &[ip(NEW)],
+        Duration::from_secs(1),
+    )
+    .await;
+
+    let statuses: Vec<AnswerStatus> = report
+        .answers
+        .iter()
+        .map(|a| a.status(&report.expected))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            AnswerStatus::Propagated,
+            AnswerStatus::Stale,
+            AnswerStatus::Propagated,
+            AnswerStatus::Failed
+        ]
+    );
+    assert_eq!(report.propagated(), 2);
+    assert_eq!(report.responding(), 3);
+    assert_eq!(report.percentage(), 66);
+    assert!(!report.is_complete());
+}
+
+#[test]
+fn test_status_compares_expected_address_families_only() {
+    let expected = [ip(NEW)];
+
+    // AAAA records don't matter when only an IPv4 address is expected
+    let dual = answer(&[(NEW, 300), ("2001:db8::1", 300)]);
+    assert_eq!(dual.status(&expected), AnswerStatus::Propagated);
+    let v6_only = answer(&[("2001:db8::1", 300)]);
+    assert_eq!(v6_only.status(&expected), AnswerStatus::NoRecords);
+
+    // Old and new address side by side is still stale
+    let mixed = answer(&[(NEW, 300), (OLD, 300)]);
+    assert_eq!(mixed.status(&expected), AnswerStatus::Stale);
+    assert_eq!(mixed.min_ttl(), Some(300));
+
+    // Expecting both families checks both
+    let both = [ip(NEW), ip("2001:db8::10")];
+    assert_eq!(dual.status(&both), AnswerStatus::Stale);
+    let moved = answer(&[(NEW, 300), ("2001:db8::10", 60)]);
+    assert_eq!(moved.status(&both), AnswerStatus::Propagated);
+    assert_eq!(moved.min_ttl(), Some(60));
+
+    assert_eq!(answer(&[]).status(&expected), AnswerStatus::NoRecords);
+    assert_eq!(answer(&[]).min_ttl(), None);
+}
+
+#[test]
+fn test_no_responding_resolvers_is_zero_percent() {
+    let report = PropagationReport {
+        domain: "shop.example.com".to_string(),
+        expected: vec![ip(NEW)],
+        answers: vec![ResolverAnswer {
+            resolver: ip("1.1.1.1"),
+            records: Vec::new(),
+            error: Some("no answer within 5s".to_string()),
+        }],
+    };
+    assert_eq!(report.responding(), 0);
+    assert_eq!(report.percentage(), 0);
+    assert!(!report.is_complete());
+
+    let empty = PropagationReport {
+        answers: Vec::new(),
+        ..report
+    };
+    assert_eq!(empty.percentage(), 0);
+}
+
+#[test]
+fn test_tracked_propagation_expiry() {
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+    let tracked = TrackedPropagation {
+        domain: "shop.example.com".to_string(),
+        expected: vec![ip(NEW)],
+        resolvers: vec![ip("1.1.1.1")],
+        started_at: "2026-03-01T12:00:00Z".to_string(),
+        expires_at: "2026-03-01T12:30:00Z".to_string(),
+    };
+    assert!(!tracked.is_expired(start + ChronoDuration::minutes(29)));
+    assert!(tracked.is_expired(start + ChronoDuration::minutes(30)));
+
+    let broken = TrackedPropagation {
+        expires_at: "soon".to_string(),
+        ..tracked
+    };
+    assert!(broken.is_expired(start));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_check_many_splits_answers_per_domain() {
+    let mock = Mock::new(&[
+        ("1.1.1.1", Reply::Records(vec![(NEW, 300)])),
+        ("8.8.8.8", Reply::Records(vec![(OLD, 3600)])),
+    ]);
+    let target = |domain: &str, expected: &str, resolvers: &[&str]| Target {
+        domain: domain.to_string(),
+        expected: vec![ip(expected)],
+        resolvers: resolvers.iter().map(|r| ip(r)).collect(),
+    };
+    let targets = [
+        target("shop.example.com", NEW, &["1.1.1.1", "8.8.8.8"]),
+        target("api.example.com", OLD, &["8.8.8.8"]),
+        target("www.example.com", NEW, &["1.1.1.1"]),
+    ];
+
+    let mut progress = Vec::new();
+    let reports = check_many(
+        &mock,
+        &targets,
+        Duration::from_secs(1),
+        &Limits::default(),
+        |p| progress.push(p.to_string()),
+    )
+    .await;
+
+    let summary: Vec<(&str, usize, u8)> = reports
+        .iter()
+        .map(|r| (r.domain.as_str(), r.answers.len(), r.percentage()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("shop.example.com", 2, 50),
+            ("api.example.com", 1, 100),
+            ("www.example.com", 1, 100),
+        ]
+    );
+    assert_eq!(reports[1].answers[0].resolver, ip("8.8.8.8"));
+    assert_eq!(progress.last().map(String::as_str), Some("4/4"));
+}
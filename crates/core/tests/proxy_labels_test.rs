@@ -0,0 +1,138 @@
+use pctrl_core::proxy_labels::{
+    caddy_hosts, nginx_proxy_hosts, plan_links, proxy_hosts, traefik_hosts, Labels, ProxyHost,
+    ProxyScheme,
+};
+use pctrl_core::{Domain, DomainType};
+use std::collections::BTreeMap;
+
+fn fixture() -> BTreeMap<String, Labels> {
+    serde_json::from_str(include_str!("fixtures/proxy_labels.json")).unwrap()
+}
+
+fn labels(container: &str) -> Labels {
+    fixture().remove(container).unwrap()
+}
+
+fn domain(name: &str, container: Option<&str>, server: Option<&str>) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: server.map(str::to_string),
+        container_id: container.map(str::to_string),
+        notes: None,
+        superseded_by: None,
+    }
+}
+
+#[test]
+fn test_traefik_router_rules() {
+    assert_eq!(
+        traefik_hosts(&labels("shop-app")),
+        vec![
+            "api.example.com",
+            "shop.example.com",
+            "www.shop.example.com"
+        ]
+    );
+    assert_eq!(
+        traefik_hosts(&labels("legacy-app")),
+        vec!["old.example.com", "legacy.example.com"]
+    );
+    assert!(traefik_hosts(&labels("hidden")).is_empty());
+    assert!(traefik_hosts(&labels("blog")).is_empty());
+}
+
+#[test]
+fn test_caddy_sites() {
+    assert_eq!(
+        caddy_hosts(&labels("blog")),
+        vec![
+            "blog.example.com",
+            "www.blog.example.com",
+            "blog.example.org"
+        ]
+    );
+    assert!(caddy_hosts(&labels("shop-app")).is_empty());
+}
+
+#[test]
+fn test_nginx_proxy_virtual_host() {
+    assert_eq!(
+        nginx_proxy_hosts(&labels("wiki")),
+        vec!["wiki.example.com", "docs.example.com"]
+    );
+    assert_eq!(
+        nginx_proxy_hosts(&labels("wiki-staging")),
+        vec!["docs.example.com"]
+    );
+}
+
+#[test]
+fn test_proxy_hosts_tags_scheme() {
+    let hosts = proxy_hosts(&labels("wiki"));
+    assert!(hosts.iter().all(|h| h.scheme == ProxyScheme::NginxProxy));
+    assert_eq!(hosts.len(), 2);
+}
+
+#[test]
+fn test_plan_links() {
+    let containers: BTreeMap<String, Vec<ProxyHost>> = fixture()
+        .iter()
+        .map(|(name, labels)| (name.clone(), proxy_hosts(labels)))
+        .collect();
+    let domains = vec![
+        domain("shop.example.com", None, None),
+        domain("API.example.com", Some("shop-app"), Some("web-1")),
+        domain("www.shop.example.com", Some("old-shop"), None),
+        domain("docs.example.com", None, None),
+    ];
+
+    let plan = plan_links(&containers, &domains, Some("web-1"), false);
+
+    let linked: Vec<(&str, &str, Option<&str>)> = plan
+        .links
+        .iter()
+        .map(|l| {
+            (
+                l.hostname.as_str(),
+                l.container.as_str(),
+                l.previous.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        linked,
+        vec![
+            ("shop.example.com", "shop-app", None),
+            ("www.shop.example.com", "shop-app", Some("old-shop")),
+        ]
+    );
+    assert!(plan.links.iter().all(|l| l.domain_id.is_some()));
+    assert_eq!(plan.unchanged, vec!["api.example.com"]);
+    // Two containers claim docs.example.com: flagged, not linked
+    assert_eq!(
+        plan.conflicts,
+        vec![(
+            "docs.example.com".to_string(),
+            vec!["wiki".to_string(), "wiki-staging".to_string()]
+        )]
+    );
+    assert!(plan.unknown.contains(&"blog.example.com".to_string()));
+    assert!(!plan.unknown.contains(&"hidden.example.com".to_string()));
+
+    let created = plan_links(&containers, &domains, None, true);
+    assert!(created.unknown.is_empty());
+    let blog = created
+        .links
+        .iter()
+        .find(|l| l.hostname == "blog.example.com")
+        .unwrap();
+    assert_eq!(blog.domain_id, None);
+    assert_eq!(blog.container, "blog");
+    assert_eq!(blog.scheme, ProxyScheme::Caddy);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use pctrl_core::quota::{self, Allocation, QuotaKind, QuotaUsage};
+use pctrl_core::{Container, ContainerStatus, Server};
+
+fn server(id: &str, max_containers: Option<u32>, max_memory_mb: Option<u64>) -> Server {
+    Server {
+        host: "203.0.113.10".into(),
+        max_containers,
+        max_memory_mb_allocated: max_memory_mb,
+        ..common::server(id)
+    }
+}
+
+fn container(
+    name: &str,
+    server_id: &str,
+    status: ContainerStatus,
+    memory: Option<u64>,
+) -> Container {
+    Container {
+        id: format!("{}-id", name),
+        name: name.into(),
+        image: None,
+        server_id: server_id.into(),
+        project_id: None,
+        status,
+        ports: Vec::new(),
+        env_vars: None,
+        labels: None,
+        memory_limit_mb: memory,
+    }
+}
+
+#[test]
+fn test_allocation_counts_missing_limits_as_unlimited() {
+    let containers = [
+        container("db", "vps", ContainerStatus::Running, Some(1024)),
+        container("api", "vps", ContainerStatus::Running, Some(512)),
+        container("worker", "vps", ContainerStatus::Running, None),
+        container("old", "vps", ContainerStatus::Exited, Some(2048)),
+    ];
+
+    let running = quota::allocation(containers.iter().filter(|c| quota::is_running(c)));
+    assert_eq!(
+        running,
+        Allocation {
+            containers: 3,
+            memory_mb: 1536,
+            unlimited: 1,
+        }
+    );
+    assert_eq!(quota::allocation(&containers).memory_mb, 3584);
+    assert_eq!(quota::allocation([]), Allocation::default());
+}
+
+#[test]
+fn test_describe() {
+    let allocation = Allocation {
+        containers: 7,
+        memory_mb: 3277,
+        unlimited: 0,
+    };
+    assert_eq!(
+        quota::describe(&server("vps", Some(10), Some(4096)), &allocation),
+        "7/10 containers, 3.2/4 GB allocated"
+    );
+    assert_eq!(
+        quota::describe(&server("vps", None, None), &allocation),
+        "7 containers, 3.2 GB allocated"
+    );
+
+    let unlimited = Allocation {
+        containers: 1,
+        memory_mb: 0,
+        unlimited: 1,
+    };
+    assert_eq!(
+        quota::describe(&server("vps", Some(2), None), &unlimited),
+        "1/2 containers, 0 GB allocated (1 without memory limit)"
+    );
+    assert_eq!(quota::gigabytes(512), "0.5");
+    assert_eq!(quota::gigabytes(8192), "8");
+}
+
+#[test]
+fn test_usage_thresholds() {
+    let usage = |used, max| QuotaUsage {
+        kind: QuotaKind::Containers,
+        used,
+        max,
+    };
+    assert!(!usage(8, 10).near());
+    assert!(usage(9, 10).near());
+    assert!(!usage(10, 10).exceeded());
+    assert!(usage(11, 10).exceeded());
+    assert!(usage(1, 0).exceeded());
+    assert!(!usage(0, 0).exceeded());
+
+    let vps = server("vps", Some(10), None);
+    let allocation = Allocation {
+        containers: 9,
+        memory_mb: 9000,
+        unlimited: 0,
+    };
+    // No memory quota, so only the container count is reported
+    assert_eq!(quota::usage(&vps, &allocation), [usage(9, 10)]);
+    assert!(quota::usage(&server("vps", None, None), &allocation).is_empty());
+}
+
+#[test]
+fn test_check_start() {
+    let servers = [
+        server("small", Some(3), Some(2048)),
+        server("big", Some(50), None),
+    ];
+    let containers = [
+        container("db", "small", ContainerStatus::Running, Some(1024)),
+        container("api", "small", ContainerStatus::Running, Some(512)),
+        container("web", "small", ContainerStatus::Exited, Some(256)),
+        container("cache", "small", ContainerStatus::Exited, Some(512)),
+        container("elsewhere", "big", ContainerStatus::Running, Some(8192)),
+    ];
+
+    // 3/3 containers and 1.75/2 GB: within both quotas
+    let web = [&containers[2]];
+    assert!(quota::check_start(&servers, &containers, &web).is_empty());
+
+    // Already running containers don't count twice
+    let restart = [&containers[0], &containers[1], &containers[2]];
+    assert!(quota::check_start(&servers, &containers, &restart).is_empty());
+
+    // 4/3 containers and 2.25/2 GB
+    let both = [&containers[2], &containers[3]];
+    let breaches = quota::check_start(&servers, &containers, &both);
+    let kinds: Vec<(&str, QuotaKind, u64)> = breaches
+        .iter()
+        .map(|b| (b.server_name.as_str(), b.usage.kind, b.usage.used))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("small", QuotaKind::Containers, 4),
+            ("small", QuotaKind::Memory, 2304)
+        ]
+    );
+    assert_eq!(breaches[0].to_string(), "small would have 4/3 containers");
+
+    // An extra container without memory limit doesn't breach the memory quota
+    let unlimited = container("sidecar", "small", ContainerStatus::Unknown, None);
+    let breaches = quota::check_start(&servers, &containers, &[&containers[3], &unlimited]);
+    assert_eq!(breaches.len(), 1);
+    assert_eq!(breaches[0].usage.kind, QuotaKind::Containers);
+}
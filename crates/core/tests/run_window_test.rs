@@ -0,0 +1,173 @@
+use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Europe::Berlin;
+use pctrl_core::run_window::RunWindow;
+
+fn window(expr: &str) -> RunWindow {
+    expr.parse().unwrap()
+}
+
+fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+}
+
+#[test]
+fn test_parse_renders_canonical_form() {
+    let cases = [
+        (
+            "mon-thu 08:00-18:00 Europe/Berlin",
+            "Mon-Thu 08:00-18:00 Europe/Berlin",
+        ),
+        ("Fri-Mon", "Mon,Fri-Sun"),
+        ("sun,SAT", "Sat,Sun"),
+        ("13:00-17:00,09:00-12:00", "Mon-Sun 09:00-12:00,13:00-17:00"),
+        (
+            "Mon-Fri 08:00-18:00;  Sat 10:00-24:00 UTC",
+            "Mon-Fri 08:00-18:00; Sat 10:00-24:00 UTC",
+        ),
+    ];
+    for (expr, canonical) in cases {
+        let parsed = window(expr);
+        assert_eq!(parsed.to_string(), canonical, "{}", expr);
+        assert_eq!(window(canonical), parsed, "{}", canonical);
+    }
+}
+
+#[test]
+fn test_parse_rejects_malformed_windows() {
+    for expr in [
+        "",
+        "Mon;;Tue",
+        "Funday 08:00-18:00",
+        "Mon 18:00-08:00",
+        "Mon 08:00-08:00",
+        "Mon 24:00-24:00",
+        "Mon 08:00-25:00",
+        "Mon 8:0-9:00",
+        "Mon 08:00",
+        "Mon Tue",
+        "Mon 08:00-09:00 10:00-11:00",
+        "Mon UTC Europe/Berlin",
+        "Europe/Berlin",
+    ] {
+        assert!(expr.parse::<RunWindow>().is_err(), "accepted '{}'", expr);
+    }
+}
+
+#[test]
+fn test_open_at_the_start_and_closed_at_the_end() {
+    // 2026-10-15 is a Thursday
+    let w = window("Mon-Thu 08:00-18:00 UTC");
+    assert!(!w.is_open(utc(2026, 10, 15, 7, 59), &Utc));
+    assert!(w.is_open(utc(2026, 10, 15, 8, 0), &Utc));
+    assert!(w.is_open(utc(2026, 10, 15, 17, 59), &Utc));
+    assert!(!w.is_open(utc(2026, 10, 15, 18, 0), &Utc));
+    assert!(!w.is_open(utc(2026, 10, 16, 12, 0), &Utc));
+}
+
+#[test]
+fn test_next_open_skips_the_weekend() {
+    let w = window("Mon-Thu 08:00-18:00 UTC");
+    assert_eq!(
+        w.next_open(utc(2026, 10, 15, 18, 0), &Utc),
+        Some(utc(2026, 10, 19, 8, 0))
+    );
+    // Earlier the same day
+    assert_eq!(
+        w.next_open(utc(2026, 10, 15, 6, 30), &Utc),
+        Some(utc(2026, 10, 15, 8, 0))
+    );
+    let now = utc(2026, 10, 15, 9, 0);
+    assert_eq!(w.next_open(now, &Utc), Some(now));
+}
+
+#[test]
+fn test_next_open_comes_around_to_the_same_weekday() {
+    // Thursday after the window: next Thursday
+    let w = window("Thu 08:00-09:00 UTC");
+    assert_eq!(
+        w.next_open(utc(2026, 10, 15, 9, 0), &Utc),
+        Some(utc(2026, 10, 22, 8, 0))
+    );
+}
+
+#[test]
+fn test_windows_across_midnight_and_around_the_week() {
+    let w = window("Fri 22:00-24:00; Sat 00:00-02:00 UTC");
+    assert!(w.is_open(utc(2026, 10, 16, 23, 59), &Utc));
+    assert!(w.is_open(utc(2026, 10, 17, 0, 0), &Utc));
+    assert!(!w.is_open(utc(2026, 10, 17, 2, 0), &Utc));
+
+    let weekend = window("Sat-Mon UTC");
+    assert!(weekend.is_open(utc(2026, 10, 19, 23, 59), &Utc));
+    assert!(!weekend.is_open(utc(2026, 10, 20, 0, 0), &Utc));
+    assert_eq!(
+        weekend.next_open(utc(2026, 10, 20, 0, 0), &Utc),
+        Some(utc(2026, 10, 24, 0, 0))
+    );
+}
+
+#[test]
+fn test_without_a_timezone_the_local_one_applies() {
+    // 06:30 UTC is 08:30 in Berlin (CEST)
+    let w = window("08:00-18:00");
+    let now = utc(2026, 7, 1, 6, 30);
+    assert!(w.is_open(now, &Berlin));
+    assert!(!w.is_open(now, &Utc));
+    // An explicit timezone wins over the local one
+    assert!(!window("08:00-18:00 UTC").is_open(now, &Berlin));
+}
+
+#[test]
+fn test_spring_forward_opens_right_after_the_skipped_hour() {
+    // 2026-03-29: Berlin clocks jump from 02:00 CET to 03:00 CEST (01:00 UTC)
+    let w = window("Sun 02:30-04:00 Europe/Berlin");
+    assert_eq!(
+        w.next_open(utc(2026, 3, 28, 12, 0), &Utc),
+        Some(utc(2026, 3, 29, 1, 0))
+    );
+    assert!(!w.is_open(utc(2026, 3, 29, 0, 59), &Utc));
+    assert!(w.is_open(utc(2026, 3, 29, 1, 0), &Utc));
+    // 04:00 CEST
+    assert!(!w.is_open(utc(2026, 3, 29, 2, 0), &Utc));
+}
+
+#[test]
+fn test_spring_forward_skips_a_window_inside_the_gap() {
+    // 02:00-02:30 never shows on the clock that day; next Sunday it does
+    let w = window("Sun 02:00-02:30 Europe/Berlin");
+    assert_eq!(
+        w.next_open(utc(2026, 3, 28, 12, 0), &Utc),
+        Some(utc(2026, 4, 5, 0, 0))
+    );
+}
+
+#[test]
+fn test_fall_back_opens_on_both_passes_of_the_repeated_hour() {
+    // 2026-10-25: Berlin clocks go from 03:00 CEST back to 02:00 CET (01:00 UTC)
+    let w = window("Sun 02:30-04:00 Europe/Berlin");
+    // 02:30 CEST
+    assert_eq!(
+        w.next_open(utc(2026, 10, 24, 12, 0), &Utc),
+        Some(utc(2026, 10, 25, 0, 30))
+    );
+    assert!(w.is_open(utc(2026, 10, 25, 0, 45), &Utc));
+    // Back to 02:10 CET: the clock is before the start again
+    let repeated = utc(2026, 10, 25, 1, 10);
+    assert!(!w.is_open(repeated, &Utc));
+    // 02:30 CET
+    assert_eq!(w.next_open(repeated, &Utc), Some(utc(2026, 10, 25, 1, 30)));
+    // 04:00 CET
+    assert!(w.is_open(utc(2026, 10, 25, 2, 59), &Utc));
+    assert!(!w.is_open(utc(2026, 10, 25, 3, 0), &Utc));
+}
+
+#[test]
+fn test_local_text_uses_the_windows_timezone() {
+    let w = window("Mon-Thu 08:00-18:00 Europe/Berlin");
+    let next = w.next_open(utc(2026, 10, 15, 18, 0), &Utc).unwrap();
+    assert_eq!(w.local_text(next, &Utc), "Mon 2026-10-19 08:00 CEST");
+    assert_eq!(
+        window("08:00-18:00").local_text(utc(2026, 10, 19, 8, 0), &Utc),
+        "Mon 2026-10-19 08:00 UTC"
+    );
+}
@@ -0,0 +1,51 @@
+use pctrl_core::settings;
+use pctrl_core::{ApprovalMode, Script, ScriptType};
+
+fn script(command: &str, dangerous: bool) -> Script {
+    Script {
+        id: "s".to_string(),
+        name: "s".to_string(),
+        description: None,
+        command: command.to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+#[test]
+fn test_change_needs_approval() {
+    let dangerous = script("restart", true);
+    assert!(dangerous.change_needs_approval("restart --now", true));
+    assert!(dangerous.change_needs_approval("restart", false));
+    assert!(!dangerous.change_needs_approval("restart", true));
+
+    let safe = script("uptime", false);
+    assert!(!safe.change_needs_approval("uptime -p", false));
+    assert!(!safe.change_needs_approval("uptime", true));
+}
+
+#[test]
+fn test_approval_modes() {
+    assert_eq!("off".parse::<ApprovalMode>(), Ok(ApprovalMode::Off));
+    assert_eq!("On".parse::<ApprovalMode>(), Ok(ApprovalMode::On));
+    assert_eq!(
+        "two-person".parse::<ApprovalMode>(),
+        Ok(ApprovalMode::TwoPerson)
+    );
+    assert_eq!(ApprovalMode::default(), ApprovalMode::Off);
+    assert_eq!(ApprovalMode::TwoPerson.to_string(), "two-person");
+
+    assert!(settings::validate(settings::SCRIPT_APPROVAL, "two-person").is_ok());
+    assert!(settings::validate(settings::SCRIPT_APPROVAL, "yes").is_err());
+}
@@ -0,0 +1,93 @@
+use pctrl_core::redact_secrets;
+use pctrl_core::script_body::{
+    editor_template, is_multiline, numbered_lines, parse_editor_output, stdin_payload, STDIN_SHELL,
+};
+
+const DEPLOY: &str = r#"#!/bin/sh
+set -e
+cd "$APP_DIR" && echo 'single "quoted"' > /tmp/x
+cat <<'EOF' > /etc/motd
+Grüße aus $HOME — 🚀
+EOF
+echo "done: ${RELEASE:-none}""#;
+
+#[test]
+fn test_editor_round_trip_is_verbatim() {
+    let template = editor_template("deploy", Some(DEPLOY));
+    assert_eq!(parse_editor_output(&template).as_deref(), Some(DEPLOY));
+
+    // Editors usually add a trailing newline; CRLF editors add "\r\n"
+    assert_eq!(
+        parse_editor_output(&format!("{}\r\n", template)).as_deref(),
+        Some(DEPLOY)
+    );
+}
+
+#[test]
+fn test_editor_only_strips_leading_instructions() {
+    let text = "# pctrl: header\necho one\n# pctrl: kept in body\n";
+    assert_eq!(
+        parse_editor_output(text).as_deref(),
+        Some("echo one\n# pctrl: kept in body")
+    );
+    assert_eq!(parse_editor_output(&editor_template("x", None)), None);
+    assert_eq!(parse_editor_output("# pctrl: a\n   \n\n"), None);
+}
+
+#[test]
+fn test_numbered_lines() {
+    assert!(is_multiline(DEPLOY));
+    assert!(!is_multiline("uptime"));
+
+    let lines = numbered_lines(DEPLOY);
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "1 │ #!/bin/sh");
+    assert_eq!(lines[4], "5 │ Grüße aus $HOME — 🚀");
+
+    let long: Vec<String> = (1..=10).map(|i| format!("echo {}", i)).collect();
+    let lines = numbered_lines(&long.join("\n"));
+    assert_eq!(lines[0], " 1 │ echo 1");
+    assert_eq!(lines[9], "10 │ echo 10");
+}
+
+#[test]
+fn test_stdin_payload_terminates_last_line() {
+    assert_eq!(stdin_payload("echo hi"), "echo hi\n");
+    assert_eq!(stdin_payload("echo hi\n"), "echo hi\n");
+    assert!(stdin_payload(DEPLOY).starts_with(DEPLOY));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_shell_runs_heredoc_and_quotes() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let script = "X='it''s'\ncat <<EOF\n\"$X\" ünï\nEOF";
+    let (shell, args) = STDIN_SHELL;
+    let mut child = Command::new(shell)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin_payload(script).as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "\"its\" ünï\n");
+}
+
+#[test]
+fn test_redaction_is_line_aware() {
+    let script = "export DB_PASSWORD=s3cret\necho ok\ncat <<EOF\ntoken: abc\nEOF";
+    assert_eq!(
+        redact_secrets(script),
+        "export DB_PASSWORD=****\necho ok\ncat <<EOF\ntoken: ****\nEOF"
+    );
+}
@@ -0,0 +1,156 @@
+use pctrl_core::script_namespace::{
+    in_namespace, leaf, namespace, normalize_prefix, suffix_matches, tree, validate_name, TreeRow,
+};
+use std::collections::BTreeSet;
+
+#[test]
+fn test_validate_name_accepts_plain_and_namespaced_names() {
+    for name in [
+        "deploy",
+        "rotate logs",
+        "backups/postgres-daily",
+        "a/b/c",
+        "ops/weekly report",
+    ] {
+        assert!(validate_name(name).is_ok(), "rejected '{}'", name);
+    }
+}
+
+#[test]
+fn test_validate_name_rejects_empty_segments() {
+    for name in [
+        "",
+        "  ",
+        "/backups",
+        "backups/",
+        "backups//daily",
+        "/",
+        "backups /daily",
+        "backups/ daily",
+    ] {
+        assert!(validate_name(name).is_err(), "accepted '{}'", name);
+    }
+}
+
+#[test]
+fn test_namespace_and_leaf() {
+    assert_eq!(namespace("deploy"), None);
+    assert_eq!(leaf("deploy"), "deploy");
+    assert_eq!(namespace("backups/db/pg"), Some("backups/db"));
+    assert_eq!(leaf("backups/db/pg"), "pg");
+}
+
+#[test]
+fn test_in_namespace_matches_whole_segments() {
+    assert_eq!(normalize_prefix("backups//"), Some("backups"));
+    assert_eq!(normalize_prefix("/"), None);
+
+    assert!(in_namespace("backups/pg", "backups/"));
+    assert!(in_namespace("backups/pg", "Backups"));
+    assert!(in_namespace("backups/db/pg", "backups"));
+    assert!(in_namespace("backups/db/pg", "backups/db/"));
+    assert!(!in_namespace("backups-old/pg", "backups"));
+    assert!(!in_namespace("backups", "backups"));
+    assert!(!in_namespace("backups/pg", "backups/pg"));
+    // An empty filter matches everything
+    assert!(in_namespace("deploy", "/"));
+}
+
+#[test]
+fn test_suffix_matches_whole_segments_ignoring_case() {
+    let names = [
+        "backups/postgres-daily",
+        "backups/mysql-daily",
+        "legacy/backups/postgres-daily",
+        "deploy",
+    ];
+    assert_eq!(
+        suffix_matches(names, "mysql-daily"),
+        vec!["backups/mysql-daily"]
+    );
+    assert_eq!(
+        suffix_matches(names, "Postgres-Daily"),
+        vec!["backups/postgres-daily", "legacy/backups/postgres-daily"]
+    );
+    assert_eq!(
+        suffix_matches(names, "legacy/backups/postgres-daily"),
+        vec!["legacy/backups/postgres-daily"]
+    );
+    assert_eq!(suffix_matches(names, "deploy"), vec!["deploy"]);
+    assert!(suffix_matches(names, "daily").is_empty());
+    assert!(suffix_matches(names, "").is_empty());
+}
+
+fn render(rows: &[TreeRow], names: &[&str]) -> Vec<String> {
+    rows.iter()
+        .map(|row| match row {
+            TreeRow::Namespace {
+                depth,
+                label,
+                scripts,
+                collapsed,
+                ..
+            } => format!(
+                "{}{}/ ({}){}",
+                "  ".repeat(*depth),
+                label,
+                scripts,
+                if *collapsed { " +" } else { "" }
+            ),
+            TreeRow::Script {
+                depth,
+                index,
+                label,
+            } => {
+                assert!(names[*index].ends_with(label));
+                format!("{}{}", "  ".repeat(*depth), label)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_tree_groups_by_namespace() {
+    let names = [
+        "rotate-logs",
+        "deploy/frontend",
+        "backups/postgres-daily",
+        "Backups/db/redis",
+        "backups/mysql-daily",
+        "deploy/api",
+    ];
+    let rows = tree(&names, &BTreeSet::new());
+    assert_eq!(
+        render(&rows, &names),
+        vec![
+            "backups/ (3)",
+            "  db/ (1)",
+            "    redis",
+            "  mysql-daily",
+            "  postgres-daily",
+            "deploy/ (2)",
+            "  api",
+            "  frontend",
+            "rotate-logs",
+        ]
+    );
+}
+
+#[test]
+fn test_tree_of_plain_names_is_a_sorted_list() {
+    let names = ["b", "A", "c"];
+    let rows = tree(&names, &BTreeSet::new());
+    assert_eq!(render(&rows, &names), vec!["A", "b", "c"]);
+    assert!(rows.iter().all(|row| row.depth() == 0));
+}
+
+#[test]
+fn test_collapsed_namespaces_hide_their_contents() {
+    let names = ["backups/db/redis", "backups/pg", "deploy/api"];
+    let collapsed: BTreeSet<String> = ["backups/db".to_string(), "deploy".to_string()].into();
+    let rows = tree(&names, &collapsed);
+    assert_eq!(
+        render(&rows, &names),
+        vec!["backups/ (2)", "  db/ (1) +", "  pg", "deploy/ (1) +"]
+    );
+}
@@ -0,0 +1,173 @@
+use pctrl_core::shell::{
+    common_prefix, complete, inject_project, parse_line, quote_word, redact_secrets, split_line,
+    ReplLine, PROJECT_RULES,
+};
+
+fn args(line: &str) -> Vec<String> {
+    split_line(line).unwrap()
+}
+
+fn inject(line: &str) -> Vec<String> {
+    inject_project(&args(line), "acme", PROJECT_RULES)
+}
+
+#[test]
+fn test_split_line_quoting() {
+    assert_eq!(
+        args(r#"script add deploy -c "cd /app && echo 'hi there'""#),
+        vec![
+            "script",
+            "add",
+            "deploy",
+            "-c",
+            "cd /app && echo 'hi there'"
+        ]
+    );
+    assert_eq!(
+        args(r"server add my\ box 10.0.0.1"),
+        vec!["server", "add", "my box", "10.0.0.1"]
+    );
+    assert_eq!(args("  server   list  "), vec!["server", "list"]);
+    assert_eq!(
+        args("project show 'Größe ✓'"),
+        vec!["project", "show", "Größe ✓"]
+    );
+    assert!(split_line("project show \"unterminated").is_err());
+}
+
+#[test]
+fn test_parse_builtins() {
+    assert_eq!(parse_line("   ").unwrap(), ReplLine::Empty);
+    assert_eq!(parse_line("exit").unwrap(), ReplLine::Exit);
+    assert_eq!(parse_line("use").unwrap(), ReplLine::ShowContext);
+    assert_eq!(parse_line("use -").unwrap(), ReplLine::Use(None));
+    assert_eq!(
+        parse_line("use 'my app'").unwrap(),
+        ReplLine::Use(Some("my app".to_string()))
+    );
+    assert!(parse_line("use a b").is_err());
+    assert_eq!(
+        parse_line("server list").unwrap(),
+        ReplLine::Command(args("server list"))
+    );
+}
+
+#[test]
+fn test_inject_positional_project() {
+    assert_eq!(inject("project show"), args("project show acme"));
+    assert_eq!(inject("p remove"), args("p remove acme"));
+    assert_eq!(
+        inject("project link server web-1 -r prod"),
+        args("project link acme server web-1 -r prod")
+    );
+    assert_eq!(
+        inject("--raw project unlink link-1"),
+        args("--raw project unlink acme link-1")
+    );
+}
+
+#[test]
+fn test_explicit_project_wins() {
+    assert_eq!(inject("project show other"), args("project show other"));
+    assert_eq!(
+        inject("project link other server web-1 --role prod"),
+        args("project link other server web-1 --role prod")
+    );
+    assert_eq!(
+        inject("script add x -c ls --project other"),
+        args("script add x -c ls --project other")
+    );
+    assert_eq!(
+        inject("script add x -c ls --project=other"),
+        args("script add x -c ls --project=other")
+    );
+    assert_eq!(
+        inject("script add x -c ls -p other"),
+        args("script add x -c ls -p other")
+    );
+}
+
+#[test]
+fn test_inject_flag_and_untouched_commands() {
+    assert_eq!(
+        inject("script add x -c 'ls -p'"),
+        vec!["script", "add", "x", "-c", "ls -p", "--project", "acme"]
+    );
+    assert_eq!(inject("server list"), args("server list"));
+    assert_eq!(inject("project list"), args("project list"));
+    assert_eq!(inject("project show --help"), args("project show --help"));
+    assert_eq!(inject(""), Vec::<String>::new());
+}
+
+#[test]
+fn test_complete() {
+    let candidates = |previous: &[String]| -> Vec<String> {
+        match previous {
+            [] => vec![
+                "server".into(),
+                "script".into(),
+                "shell".into(),
+                "project".into(),
+            ],
+            [c] if c == "server" => vec!["list".into(), "show".into()],
+            _ => vec!["web-1".into(), "web-2".into(), "db box".into()],
+        }
+    };
+
+    assert_eq!(
+        complete("s", candidates),
+        (0, vec!["script".into(), "server".into(), "shell".into()])
+    );
+    assert_eq!(complete("server l", candidates), (7, vec!["list".into()]));
+    assert_eq!(
+        complete("server show we", candidates),
+        (12, vec!["web-1".into(), "web-2".into()])
+    );
+    assert_eq!(
+        complete("server show x", candidates).1,
+        Vec::<String>::new()
+    );
+
+    assert_eq!(common_prefix(&["web-1".into(), "web-2".into()]), "web-");
+    assert_eq!(common_prefix(&["script".into(), "server".into()]), "s");
+    assert_eq!(quote_word("db box"), "'db box'");
+    assert_eq!(quote_word("web-1"), "web-1");
+}
+
+#[test]
+fn test_redact_secrets() {
+    assert_eq!(
+        redact_secrets("cred add api -t api --token abc123 --url https://x"),
+        "cred add api -t api --token '***' --url https://x"
+    );
+    assert_eq!(
+        redact_secrets("db add main postgres -P 's3cret pw' -u app"),
+        "db add main postgres -P '***' -u app"
+    );
+    assert_eq!(
+        redact_secrets("credential add box -t basic --password=hunter2"),
+        "credential add box -t basic '--password=***'"
+    );
+    assert_eq!(
+        redact_secrets("db add main postgres -Phunter2"),
+        "db add main postgres '-P***'"
+    );
+    assert_eq!(
+        redact_secrets("coolify add prod -u https://c.example.com -t tok"),
+        "coolify add prod -u https://c.example.com -t '***'"
+    );
+    assert_eq!(
+        redact_secrets("database add main postgres -c postgres://u:pw@h/db"),
+        "database add main postgres -c '***'"
+    );
+
+    // Same short flags mean something else elsewhere
+    let untouched = "script add deploy -c 'echo hi' -t bash";
+    assert_eq!(redact_secrets(untouched), untouched);
+    assert_eq!(redact_secrets("server list"), "server list");
+    assert_eq!(
+        redact_secrets("script run x -- --token y"),
+        "script run x -- --token y"
+    );
+    assert_eq!(redact_secrets("bad \"quote"), "bad \"quote");
+}
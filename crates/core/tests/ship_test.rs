@@ -0,0 +1,380 @@
+use pctrl_core::ship::{
+    parse_inspect, ship, CommandOutput, Compose, ShipEvent, ShipExecutor, ShipOptions, ShipPhase,
+};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const OLD: &str = "1111111aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const NEW: &str = "2222222bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+/// Answers commands by the first matching substring; repeated patterns
+/// answer in turn, the last answer sticks
+struct Stub {
+    answers: Vec<(&'static str, VecDeque<(i32, String)>)>,
+    commands: Vec<String>,
+    slept: Duration,
+}
+
+impl Stub {
+    fn new() -> Self {
+        Self {
+            answers: Vec::new(),
+            commands: Vec::new(),
+            slept: Duration::ZERO,
+        }
+    }
+
+    fn on(mut self, pattern: &'static str, exit_code: i32, output: &str) -> Self {
+        match self.answers.iter_mut().find(|(p, _)| *p == pattern) {
+            Some((_, queue)) => queue.push_back((exit_code, output.to_string())),
+            None => self
+                .answers
+                .push((pattern, VecDeque::from([(exit_code, output.to_string())]))),
+        }
+        self
+    }
+
+    /// A server where git and compose v2 work and HEAD moves OLD → NEW
+    fn happy() -> Self {
+        Self::new()
+            .on("git rev-parse HEAD", 0, OLD)
+            .on("git rev-parse HEAD", 0, NEW)
+            .on("git fetch", 0, "Updating 1111111..2222222\n")
+            .on("git rev-list --count", 0, "3\n")
+            .on(
+                "docker compose version",
+                0,
+                "Docker Compose version v2.24.0",
+            )
+            .on("compose pull", 0, "")
+            .on("compose build", 0, "")
+            .on("compose up -d", 0, "")
+            .on("compose logs", 0, "web-1 | panic: boom\n")
+            .on(
+                "docker inspect",
+                0,
+                "/web running healthy\n/worker running\n",
+            )
+    }
+
+    fn ran(&self, pattern: &str) -> bool {
+        self.commands.iter().any(|c| c.contains(pattern))
+    }
+}
+
+impl ShipExecutor for Stub {
+    fn run(&mut self, command: &str, _echo: bool) -> pctrl_core::Result<CommandOutput> {
+        self.commands.push(command.to_string());
+        let (exit_code, output) = self
+            .answers
+            .iter_mut()
+            .find(|(pattern, _)| command.contains(pattern))
+            .map(|(_, queue)| {
+                if queue.len() > 1 {
+                    queue.pop_front().unwrap()
+                } else {
+                    queue[0].clone()
+                }
+            })
+            .unwrap_or((127, "command not found".to_string()));
+        Ok(CommandOutput { output, exit_code })
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.slept += duration;
+    }
+}
+
+fn options() -> ShipOptions {
+    ShipOptions {
+        path: "/srv/my app".to_string(),
+        branch: "main".to_string(),
+        build: true,
+        containers: vec!["web".to_string(), "worker".to_string()],
+        health_timeout: Duration::from_secs(60),
+        poll_interval: Duration::from_secs(2),
+    }
+}
+
+fn run(stub: &mut Stub, options: &ShipOptions) -> (pctrl_core::ship::ShipReport, Vec<ShipEvent>) {
+    let mut events = Vec::new();
+    let report = ship(stub, options, &mut |e| events.push(e));
+    (report, events)
+}
+
+#[test]
+fn test_successful_ship_runs_every_phase() {
+    let mut stub = Stub::happy();
+    let (report, events) = run(&mut stub, &options());
+
+    assert!(report.success());
+    assert_eq!(
+        report.completed,
+        [
+            ShipPhase::Fetch,
+            ShipPhase::Pull,
+            ShipPhase::Build,
+            ShipPhase::Up,
+            ShipPhase::Health
+        ]
+    );
+    assert_eq!(report.commit_change().unwrap(), "1111111 → 2222222");
+    assert!(events.contains(&ShipEvent::Commits {
+        old: OLD.to_string(),
+        new: NEW.to_string(),
+        count: Some(3),
+    }));
+    assert!(events.contains(&ShipEvent::ComposeDetected(Compose::V2)));
+    assert!(report.logs_tail.is_none());
+
+    // Paths are quoted, the branch is pulled fast-forward only
+    assert!(stub.ran("cd '/srv/my app' && git fetch origin main && git checkout main && git pull --ff-only origin main"));
+    assert!(stub.ran("cd '/srv/my app' && docker compose up -d"));
+    assert!(!stub.ran("compose logs"));
+}
+
+#[test]
+fn test_unchanged_checkout_and_no_build() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "git rev-parse HEAD")
+        .unwrap()
+        .1 = VecDeque::from([(0, OLD.to_string())]);
+
+    let (report, events) = run(
+        &mut stub,
+        &ShipOptions {
+            build: false,
+            ..options()
+        },
+    );
+
+    assert!(report.success());
+    assert_eq!(report.commit_change().unwrap(), "1111111 (unchanged)");
+    assert!(events.contains(&ShipEvent::Commits {
+        old: OLD.to_string(),
+        new: OLD.to_string(),
+        count: Some(0),
+    }));
+    assert!(events.contains(&ShipEvent::PhaseSkipped(
+        ShipPhase::Build,
+        "--no-build".to_string()
+    )));
+    assert!(!stub.ran("compose build"));
+    assert!(!stub.ran("rev-list"));
+}
+
+#[test]
+fn test_falls_back_to_compose_v1() {
+    let mut stub = Stub::happy()
+        .on("docker-compose version", 0, "docker-compose version 1.29.2")
+        .on("docker-compose pull", 0, "");
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "docker compose version")
+        .unwrap()
+        .1 = VecDeque::from([(1, "docker: 'compose' is not a docker command.".to_string())]);
+
+    let (report, events) = run(&mut stub, &options());
+    assert!(events.contains(&ShipEvent::ComposeDetected(Compose::V1)));
+    assert!(stub.ran("docker-compose up -d"));
+    assert!(report.success());
+}
+
+#[test]
+fn test_missing_compose_fails_without_logs() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "docker compose version")
+        .unwrap()
+        .1 = VecDeque::from([(1, String::new())]);
+
+    let (report, _) = run(&mut stub, &options());
+    let (phase, reason) = report.failure.unwrap();
+    assert_eq!(phase, ShipPhase::Pull);
+    assert!(reason.contains("docker-compose"));
+    assert_eq!(report.completed, [ShipPhase::Fetch]);
+    assert!(!stub.ran("compose logs"));
+}
+
+#[test]
+fn test_fetch_failure_stops_before_compose() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "git fetch")
+        .unwrap()
+        .1 = VecDeque::from([(
+        1,
+        "fatal: Not possible to fast-forward, aborting.\n".to_string(),
+    )]);
+
+    let (report, events) = run(&mut stub, &options());
+    assert_eq!(
+        report.failure,
+        Some((
+            ShipPhase::Fetch,
+            "exit code 1: fatal: Not possible to fast-forward, aborting.".to_string()
+        ))
+    );
+    assert!(report.completed.is_empty());
+    assert_eq!(report.old_commit.as_deref(), Some(OLD));
+    assert!(report.new_commit.is_none());
+    assert!(!stub.ran("compose"));
+    assert!(matches!(
+        events.last(),
+        Some(ShipEvent::PhaseFailed(ShipPhase::Fetch, _))
+    ));
+}
+
+#[test]
+fn test_not_a_checkout() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "git rev-parse HEAD")
+        .unwrap()
+        .1 = VecDeque::from([(128, "fatal: not a git repository".to_string())]);
+
+    let (report, _) = run(&mut stub, &options());
+    assert_eq!(
+        report.failure,
+        Some((
+            ShipPhase::Fetch,
+            "/srv/my app is not a git checkout".to_string()
+        ))
+    );
+    assert!(!stub.ran("git fetch"));
+}
+
+#[test]
+fn test_build_failure_shows_compose_logs() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "compose build")
+        .unwrap()
+        .1 = VecDeque::from([(
+        17,
+        "Step 4/9 : RUN cargo build\nerror: linker failed\n".to_string(),
+    )]);
+
+    let (report, events) = run(&mut stub, &options());
+    assert_eq!(
+        report.failure,
+        Some((
+            ShipPhase::Build,
+            "exit code 17: error: linker failed".to_string()
+        ))
+    );
+    assert_eq!(report.completed, [ShipPhase::Fetch, ShipPhase::Pull]);
+    assert!(!stub.ran("up -d"));
+    assert!(stub.ran("cd '/srv/my app' && docker compose logs --no-color --tail 50"));
+    assert_eq!(report.logs_tail.as_deref(), Some("web-1 | panic: boom"));
+    assert_eq!(
+        events.last(),
+        Some(&ShipEvent::Logs("web-1 | panic: boom".to_string()))
+    );
+}
+
+#[test]
+fn test_health_waits_for_starting_containers() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "docker inspect")
+        .unwrap()
+        .1 = VecDeque::from([
+        (1, "/worker running\nError: No such object: web".to_string()),
+        (0, "/web running starting\n/worker running\n".to_string()),
+        (0, "/web running starting\n/worker running\n".to_string()),
+        (0, "/web running healthy\n/worker running\n".to_string()),
+    ]);
+
+    let (report, events) = run(&mut stub, &options());
+    assert!(report.success());
+    assert_eq!(stub.slept, Duration::from_secs(6));
+
+    // Only changes of the pending list are reported
+    let waiting: Vec<&ShipEvent> = events
+        .iter()
+        .filter(|e| matches!(e, ShipEvent::Waiting(_)))
+        .collect();
+    assert_eq!(
+        waiting,
+        [
+            &ShipEvent::Waiting("web (not found)".to_string()),
+            &ShipEvent::Waiting("web".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_unhealthy_container_fails_with_logs() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "docker inspect")
+        .unwrap()
+        .1 = VecDeque::from([(0, "/web running unhealthy\n/worker exited\n".to_string())]);
+
+    let (report, _) = run(&mut stub, &options());
+    assert_eq!(
+        report.failure,
+        Some((ShipPhase::Health, "web is unhealthy".to_string()))
+    );
+    assert!(report.logs_tail.is_some());
+}
+
+#[test]
+fn test_health_times_out() {
+    let mut stub = Stub::happy();
+    stub.answers
+        .iter_mut()
+        .find(|(p, _)| *p == "docker inspect")
+        .unwrap()
+        .1 = VecDeque::from([(0, "/web running starting\n/worker running\n".to_string())]);
+
+    let (report, _) = run(
+        &mut stub,
+        &ShipOptions {
+            health_timeout: Duration::from_secs(10),
+            ..options()
+        },
+    );
+    assert_eq!(
+        report.failure,
+        Some((ShipPhase::Health, "Not ready after 10s: web".to_string()))
+    );
+    assert_eq!(stub.slept, Duration::from_secs(10));
+}
+
+#[test]
+fn test_no_containers_skips_health() {
+    let mut stub = Stub::happy();
+    let (report, events) = run(
+        &mut stub,
+        &ShipOptions {
+            containers: Vec::new(),
+            ..options()
+        },
+    );
+    assert!(report.success());
+    assert!(!stub.ran("docker inspect"));
+    assert!(events.contains(&ShipEvent::PhaseSkipped(
+        ShipPhase::Health,
+        "no linked containers".to_string()
+    )));
+}
+
+#[test]
+fn test_parse_inspect() {
+    let states = parse_inspect("/web running healthy\n/db exited\n\ngarbage\n");
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0].0, "web");
+    assert_eq!(states[0].1.health.as_deref(), Some("healthy"));
+    assert_eq!(states[1].1.status, "exited");
+    assert_eq!(states[1].1.health, None);
+}
@@ -0,0 +1,38 @@
+use pctrl_core::short_ref::{generate, parse, prefix};
+use pctrl_core::EntityType;
+
+#[test]
+fn test_generate_is_stable_per_type_and_id() {
+    let web = generate(EntityType::Server, "web", 0);
+    assert!(web.starts_with("srv-"), "{}", web);
+    assert_eq!(web.len(), 8);
+    assert_eq!(generate(EntityType::Server, "web", 0), web);
+    // Same slug, other type: another prefix and code
+    let project = generate(EntityType::Project, "web", 0);
+    assert!(project.starts_with("prj-"));
+    assert_ne!(project[4..], web[4..]);
+    // Later attempts differ, and get longer once four characters run out
+    assert_ne!(generate(EntityType::Server, "web", 1), web);
+    assert_eq!(generate(EntityType::Server, "web", 64).len(), 10);
+
+    for entity_type in EntityType::ALL {
+        let short_ref = generate(entity_type, "6f1c2b9e-4d7a-4c1e-9b1a-2f9d3c4e5a6b", 0);
+        assert!(short_ref.starts_with(prefix(entity_type)));
+        assert_eq!(parse(&short_ref), Some(entity_type));
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse("srv-3fk2"), Some(EntityType::Server));
+    assert_eq!(parse(" PRJ-9X1A "), Some(EntityType::Project));
+    assert_eq!(parse("dom-77qc"), Some(EntityType::Domain));
+    assert_eq!(parse("crd-0a1b2c"), Some(EntityType::Credential));
+    // Not refs: unknown prefix, letters outside the alphabet, wrong length
+    assert_eq!(parse("web-3fk2"), None);
+    assert_eq!(parse("srv-3fki"), None);
+    assert_eq!(parse("srv-3fk"), None);
+    assert_eq!(parse("srv-3fk2a"), None);
+    assert_eq!(parse("srv"), None);
+    assert_eq!(parse("web"), None);
+}
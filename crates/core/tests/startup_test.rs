@@ -0,0 +1,146 @@
+use pctrl_core::startup::{
+    phase_status, plan_phases, ContainerState, Direction, Phase, PhaseStatus,
+};
+use pctrl_core::{ProjectResource, ResourceType};
+
+fn link(resource_type: ResourceType, name: &str, order: Option<i32>) -> ProjectResource {
+    ProjectResource {
+        id: format!("link-{}", name),
+        project_id: "acme".to_string(),
+        resource_type,
+        resource_id: name.to_string(),
+        role: None,
+        notes: None,
+        start_order: order,
+    }
+}
+
+fn container(name: &str, order: Option<i32>) -> ProjectResource {
+    link(ResourceType::Container, name, order)
+}
+
+fn phase(order: Option<i32>, containers: &[&str]) -> Phase {
+    Phase {
+        order,
+        containers: containers.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+fn state(status: &str, health: Option<&str>) -> ContainerState {
+    ContainerState {
+        status: status.to_string(),
+        health: health.map(String::from),
+    }
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|c| c.to_string()).collect()
+}
+
+#[test]
+fn test_start_phases_grouped_by_order() {
+    let links = vec![
+        container("web", Some(20)),
+        container("worker", None),
+        container("postgres", Some(10)),
+        link(ResourceType::Domain, "acme.com", Some(1)),
+        container("redis", Some(10)),
+        container("api", Some(20)),
+    ];
+
+    assert_eq!(
+        plan_phases(&links, Direction::Start),
+        vec![
+            phase(Some(10), &["postgres", "redis"]),
+            phase(Some(20), &["web", "api"]),
+            phase(None, &["worker"]),
+        ]
+    );
+}
+
+#[test]
+fn test_stop_reverses_phases() {
+    let links = vec![
+        container("postgres", Some(10)),
+        container("web", Some(20)),
+        container("worker", None),
+    ];
+
+    assert_eq!(
+        plan_phases(&links, Direction::Stop),
+        vec![
+            phase(None, &["worker"]),
+            phase(Some(20), &["web"]),
+            phase(Some(10), &["postgres"]),
+        ]
+    );
+    assert!(plan_phases(&[], Direction::Start).is_empty());
+}
+
+#[test]
+fn test_start_waits_for_health() {
+    let c = names(&["db", "cache"]);
+
+    assert_eq!(
+        phase_status(
+            &c,
+            &[state("running", None), state("running", Some("healthy"))],
+            Direction::Start
+        ),
+        PhaseStatus::Ready
+    );
+    assert_eq!(
+        phase_status(
+            &c,
+            &[state("running", Some("starting")), state("running", None)],
+            Direction::Start
+        ),
+        PhaseStatus::Waiting
+    );
+    assert_eq!(
+        phase_status(
+            &c,
+            &[state("restarting", None), state("running", None)],
+            Direction::Start
+        ),
+        PhaseStatus::Waiting
+    );
+}
+
+#[test]
+fn test_start_fails_on_dead_containers() {
+    let c = names(&["db", "cache"]);
+
+    assert_eq!(
+        phase_status(
+            &c,
+            &[state("running", Some("unhealthy")), state("running", None)],
+            Direction::Start
+        ),
+        PhaseStatus::Failed("db is unhealthy".to_string())
+    );
+    assert_eq!(
+        phase_status(
+            &c,
+            &[state("running", None), state("exited", None)],
+            Direction::Start
+        ),
+        PhaseStatus::Failed("cache is exited".to_string())
+    );
+}
+
+#[test]
+fn test_stop_waits_until_not_running() {
+    let c = names(&["web"]);
+
+    assert_eq!(
+        phase_status(&c, &[state("running", None)], Direction::Stop),
+        PhaseStatus::Waiting
+    );
+    assert_eq!(
+        phase_status(&c, &[state("exited", None)], Direction::Stop),
+        PhaseStatus::Ready
+    );
+    // Missing states are never treated as done
+    assert_eq!(phase_status(&c, &[], Direction::Stop), PhaseStatus::Waiting);
+}
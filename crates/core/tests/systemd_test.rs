@@ -0,0 +1,141 @@
+use pctrl_core::facts;
+use pctrl_core::systemd::{self, ServiceAction};
+
+const RUNNING: &str = include_str!("fixtures/systemd_show_running.txt");
+const FAILED: &str = include_str!("fixtures/systemd_show_failed.txt");
+const NOT_FOUND: &str = include_str!("fixtures/systemd_show_not_found.txt");
+
+#[test]
+fn test_parse_show_running() {
+    let state = systemd::parse_show(RUNNING).unwrap();
+    assert!(state.exists());
+    assert!(state.is_running());
+    assert_eq!(state.summary(), "active (running)");
+    assert_eq!(state.description.as_deref(), Some("My App API"));
+    assert_eq!(state.unit_file_state.as_deref(), Some("enabled"));
+    assert_eq!(state.main_pid, Some(48211));
+    assert_eq!(state.restarts, Some(3));
+    assert_eq!(state.since.as_deref(), Some("Tue 2026-03-03 09:14:02 UTC"));
+}
+
+#[test]
+fn test_parse_show_failed_uses_inactive_timestamp() {
+    let state = systemd::parse_show(FAILED).unwrap();
+    assert!(state.is_failed());
+    assert!(!state.is_running());
+    assert_eq!(state.summary(), "failed (failed)");
+    // MainPID=0 means no main process
+    assert_eq!(state.main_pid, None);
+    assert_eq!(state.restarts, Some(5));
+    assert_eq!(state.since.as_deref(), Some("Mon 2026-03-02 23:00:09 UTC"));
+}
+
+#[test]
+fn test_parse_show_not_found() {
+    let state = systemd::parse_show(NOT_FOUND).unwrap();
+    assert!(!state.exists());
+    assert_eq!(state.unit_file_state, None);
+    assert_eq!(state.since, None);
+}
+
+#[test]
+fn test_parse_show_rejects_error_output() {
+    let err = systemd::parse_show(
+        "System has not been booted with systemd as init system (PID 1). Can't operate.\n",
+    )
+    .unwrap_err();
+    assert!(err.contains("System has not been booted"));
+    assert!(systemd::parse_show("").is_err());
+}
+
+#[test]
+fn test_parse_show_crlf_and_not_set() {
+    let state = systemd::parse_show(
+        "ActiveState=activating\r\nSubState=start\r\nActiveEnterTimestamp=[not set]\r\n",
+    )
+    .unwrap();
+    assert_eq!(state.summary(), "activating (start)");
+    assert_eq!(state.load_state, "loaded");
+    assert_eq!(state.since, None);
+}
+
+#[test]
+fn test_normalize_unit() {
+    assert_eq!(systemd::normalize_unit("myapp").unwrap(), "myapp.service");
+    assert_eq!(
+        systemd::normalize_unit("myapp.service").unwrap(),
+        "myapp.service"
+    );
+    assert_eq!(
+        systemd::normalize_unit("backup.timer").unwrap(),
+        "backup.timer"
+    );
+    assert_eq!(
+        systemd::normalize_unit("worker@2").unwrap(),
+        "worker@2.service"
+    );
+    assert!(systemd::normalize_unit("").is_err());
+    assert!(systemd::normalize_unit("--now").is_err());
+    assert!(systemd::normalize_unit("app; rm -rf /").is_err());
+}
+
+#[test]
+fn test_commands() {
+    assert_eq!(
+        systemd::show_command("myapp.service"),
+        format!(
+            "systemctl show --no-pager --property={} -- myapp.service",
+            systemd::SHOW_PROPERTIES
+        )
+    );
+    assert_eq!(
+        systemd::control_command(ServiceAction::Restart, "myapp.service", true),
+        "sudo -n systemctl restart -- myapp.service"
+    );
+    assert_eq!(
+        systemd::control_command(ServiceAction::Stop, "myapp.service", false),
+        "systemctl stop -- myapp.service"
+    );
+    assert_eq!(
+        systemd::logs_command("myapp.service", 50, true),
+        "journalctl --no-pager -u myapp.service -n 50 -f"
+    );
+    assert!(systemd::needs_sudo("deploy"));
+    assert!(!systemd::needs_sudo("root"));
+}
+
+#[test]
+fn test_not_systemd_detection() {
+    assert!(systemd::is_not_systemd(
+        "System has not been booted with systemd as init system (PID 1). Can't operate.",
+        1
+    ));
+    assert!(systemd::is_not_systemd("sh: systemctl: not found", 127));
+    assert!(systemd::is_not_systemd("", 127));
+    assert!(!systemd::is_not_systemd(
+        "Job for myapp.service failed because the control process exited with error code.",
+        1
+    ));
+    assert!(systemd::is_sudo_refused("sudo: a password is required"));
+}
+
+#[test]
+fn test_not_systemd_reason_from_facts() {
+    let alpine = facts::parse(include_str!("fixtures/facts_alpine.txt"));
+    assert_eq!(
+        systemd::not_systemd_reason(&alpine).as_deref(),
+        Some("PID 1 is openrc-init (alpine)")
+    );
+    let ubuntu = facts::parse(include_str!("fixtures/facts_ubuntu.txt"));
+    assert_eq!(systemd::not_systemd_reason(&ubuntu), None);
+    assert_eq!(systemd::not_systemd_reason(&facts::Facts::new()), None);
+}
+
+#[test]
+fn test_action_parse() {
+    assert_eq!(
+        "Restart".parse::<ServiceAction>(),
+        Ok(ServiceAction::Restart)
+    );
+    assert!("reload".parse::<ServiceAction>().is_err());
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use pctrl_core::table_export::{self, ExportTable, RowFilter, TableFormat};
+use pctrl_core::{Credential, CredentialData, CredentialType, Server, ServerSpecs};
+use serde_json::json;
+
+fn server() -> Server {
+    Server {
+        host: "203.0.113.10".to_string(),
+        provider: Some("Hetzner".to_string()),
+        specs: Some(ServerSpecs {
+            cpu_cores: Some(4),
+            ram_gb: Some(8),
+            disk_gb: None,
+        }),
+        notes: Some("shop, \"main\" box\nsecond line".to_string()),
+        ..common::server("web-1")
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_csv_escaping() {
+    assert_eq!(table_export::csv_field("plain"), "plain");
+    assert_eq!(table_export::csv_field("a,b"), "\"a,b\"");
+    assert_eq!(table_export::csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(table_export::csv_field("one\ntwo"), "\"one\ntwo\"");
+    assert_eq!(table_export::csv_field("crlf\r\n"), "\"crlf\r\n\"");
+    assert_eq!(table_export::csv_field(" padded"), "\" padded\"");
+    assert_eq!(table_export::csv_field(""), "");
+}
+
+#[test]
+fn test_csv_rows_flatten_nested_values() {
+    let filter = RowFilter::new(ExportTable::Servers, &[], &[], false).unwrap();
+    let row = table_export::flatten(serde_json::to_value(server()).unwrap());
+    assert_eq!(
+        table_export::csv_header(&filter),
+        "id,name,host,server_type,provider,credential_id,location,specs.cpu_cores,specs.ram_gb,specs.disk_gb,notes,requires_vpn,max_containers,max_memory_mb_allocated"
+    );
+    assert_eq!(
+        table_export::csv_row(&filter, &row),
+        "web-1,web-1,203.0.113.10,Vps,Hetzner,,,4,8,,\"shop, \"\"main\"\" box\nsecond line\",,,"
+    );
+}
+
+#[test]
+fn test_json_rows_keep_column_order() {
+    let filter = RowFilter::new(
+        ExportTable::Servers,
+        &strings(&["name", "specs.ram_gb", "notes"]),
+        &[],
+        false,
+    )
+    .unwrap();
+    let row = table_export::flatten(serde_json::to_value(server()).unwrap());
+    let line = table_export::json_row(&filter, &row);
+    assert_eq!(
+        line,
+        r#"{"name":"web-1","specs.ram_gb":8,"notes":"shop, \"main\" box\nsecond line"}"#
+    );
+    // Still valid JSON for jq
+    let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(parsed["notes"], "shop, \"main\" box\nsecond line");
+}
+
+#[test]
+fn test_secrets_are_left_out_by_default() {
+    let credential = Credential {
+        id: "c1".to_string(),
+        name: "hetzner".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "s3cr3t".to_string(),
+            url: Some("https://api.hetzner.cloud".to_string()),
+        },
+        notes: None,
+    };
+    let row = table_export::flatten(serde_json::to_value(&credential).unwrap());
+
+    let filter = RowFilter::new(ExportTable::Credentials, &[], &[], false).unwrap();
+    assert!(filter.columns.iter().all(|c| !c.is_secret()));
+    let csv = table_export::csv_row(&filter, &row);
+    assert!(!csv.contains("s3cr3t"));
+    assert!(csv.contains("https://api.hetzner.cloud"));
+
+    // Asking for a secret column, or filtering on one, needs --show-secrets
+    for (columns, conditions) in [
+        (strings(&["name", "data.token"]), Vec::new()),
+        (Vec::new(), strings(&["data.token=s3cr3t"])),
+    ] {
+        let err =
+            RowFilter::new(ExportTable::Credentials, &columns, &conditions, false).unwrap_err();
+        assert!(err.contains("--show-secrets"), "{}", err);
+    }
+
+    let filter = RowFilter::new(ExportTable::Credentials, &[], &[], true).unwrap();
+    assert!(table_export::csv_row(&filter, &row).contains("s3cr3t"));
+
+    let databases = RowFilter::new(ExportTable::Databases, &[], &[], false).unwrap();
+    let names: Vec<&str> = databases.columns.iter().map(|c| c.name).collect();
+    assert!(!names.contains(&"password"));
+    assert!(!names.contains(&"connection_string"));
+    assert!(names.contains(&"username"));
+}
+
+#[test]
+fn test_where_filter() {
+    let row = table_export::flatten(serde_json::to_value(server()).unwrap());
+    let matches = |conditions: &[&str]| {
+        RowFilter::new(ExportTable::Servers, &[], &strings(conditions), false)
+            .unwrap()
+            .matches(&row)
+    };
+    assert!(matches(&["provider=hetzner"]));
+    assert!(matches(&["provider=Hetzner", "specs.cpu_cores=4"]));
+    assert!(matches(&["location="]));
+    assert!(!matches(&["provider=hetzner", "server_type=Dedicated"]));
+
+    assert!(RowFilter::new(ExportTable::Servers, &[], &strings(&["provider"]), false).is_err());
+    let err = RowFilter::new(ExportTable::Servers, &strings(&["nope"]), &[], false).unwrap_err();
+    assert!(err.contains("Unknown column 'nope'"), "{}", err);
+}
+
+#[test]
+fn test_documented_columns_match_the_types() {
+    // Every documented server column is a field of the serialized type
+    let row = table_export::flatten(serde_json::to_value(server()).unwrap());
+    for column in ExportTable::Servers.columns() {
+        assert!(row.contains_key(column.name), "{}", column.name);
+    }
+    assert_eq!(row.len(), ExportTable::Servers.columns().len());
+
+    let flat = table_export::flatten(json!({"a": {"b": {"c": 1}}, "list": [1, 2]}));
+    assert_eq!(flat["a.b.c"], json!(1));
+    assert_eq!(flat["list"], json!([1, 2]));
+}
+
+#[test]
+fn test_table_and_format_names() {
+    assert_eq!("servers".parse::<ExportTable>(), Ok(ExportTable::Servers));
+    assert_eq!("Domain".parse::<ExportTable>(), Ok(ExportTable::Domains));
+    assert_eq!(
+        "project_resources".parse::<ExportTable>(),
+        Ok(ExportTable::Links)
+    );
+    assert!("containers".parse::<ExportTable>().is_err());
+    assert_eq!("ndjson".parse::<TableFormat>(), Ok(TableFormat::Ndjson));
+    assert_eq!("JSON".parse::<TableFormat>(), Ok(TableFormat::Json));
+    assert!("xml".parse::<TableFormat>().is_err());
+}
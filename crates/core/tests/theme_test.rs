@@ -0,0 +1,193 @@
+use pctrl_core::settings;
+use pctrl_core::theme::{
+    fit, nearest_ansi16, nearest_ansi256, parse_color, parse_hex, ColorDepth, Palette, TermColor,
+    ThemeName,
+};
+
+#[test]
+fn test_parse_hex() {
+    assert_eq!(parse_hex("#7aa2f7"), Ok((0x7a, 0xa2, 0xf7)));
+    assert_eq!(parse_hex("7AA2F7"), Ok((0x7a, 0xa2, 0xf7)));
+    assert_eq!(parse_hex("#f0a"), Ok((0xff, 0x00, 0xaa)));
+
+    for invalid in ["", "#", "#12345", "#1234567", "#gggggg", "#+1+2+3", "#ÿÿ"] {
+        assert!(
+            parse_hex(invalid).is_err(),
+            "{:?} should be rejected",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_parse_color() {
+    let cases = [
+        ("cyan", TermColor::Ansi(6)),
+        ("Magenta", TermColor::Ansi(5)),
+        ("light-blue", TermColor::Ansi(12)),
+        ("bright_blue", TermColor::Ansi(12)),
+        ("dark grey", TermColor::Ansi(8)),
+        ("white", TermColor::Ansi(15)),
+        ("#ff8800", TermColor::Rgb(0xff, 0x88, 0x00)),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse_color(input), Ok(expected), "{}", input);
+    }
+    assert!(parse_color("chartreuse").is_err());
+}
+
+#[test]
+fn test_nearest_colors() {
+    assert_eq!(nearest_ansi16((255, 0, 0)), 9);
+    assert_eq!(nearest_ansi16((0, 0, 0)), 0);
+    assert_eq!(nearest_ansi16((250, 250, 250)), 15);
+    assert_eq!(nearest_ansi16((0, 190, 200)), 6);
+
+    assert_eq!(nearest_ansi256((255, 0, 0)), 196);
+    assert_eq!(nearest_ansi256((0, 0, 0)), 16);
+    assert_eq!(nearest_ansi256((255, 255, 255)), 231);
+    // Grays use the gray ramp, which is finer than the cube
+    assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+    assert_eq!(nearest_ansi256((0x7a, 0xa2, 0xf7)), 111);
+}
+
+#[test]
+fn test_fit_to_depth() {
+    let orange = TermColor::Rgb(0xff, 0x87, 0x00);
+    assert_eq!(fit(orange, ColorDepth::TrueColor), orange);
+    assert_eq!(fit(orange, ColorDepth::Ansi256), TermColor::Indexed(208));
+    assert_eq!(fit(orange, ColorDepth::Ansi16), TermColor::Ansi(3));
+
+    // 256-palette colors fall back on 16-color terminals
+    assert_eq!(
+        fit(TermColor::Indexed(196), ColorDepth::Ansi16),
+        TermColor::Ansi(9)
+    );
+    assert_eq!(
+        fit(TermColor::Indexed(3), ColorDepth::Ansi16),
+        TermColor::Indexed(3)
+    );
+
+    // ANSI colors and the default color fit everywhere
+    for depth in [
+        ColorDepth::Ansi16,
+        ColorDepth::Ansi256,
+        ColorDepth::TrueColor,
+    ] {
+        assert_eq!(fit(TermColor::Ansi(5), depth), TermColor::Ansi(5));
+        assert_eq!(fit(TermColor::Reset, depth), TermColor::Reset);
+    }
+}
+
+#[test]
+fn test_detect_color_depth() {
+    assert_eq!(
+        ColorDepth::detect(Some("truecolor"), 8),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::detect(Some("24bit"), 256),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(ColorDepth::detect(None, 256), ColorDepth::Ansi256);
+    assert_eq!(ColorDepth::detect(Some("yes"), 256), ColorDepth::Ansi256);
+    assert_eq!(ColorDepth::detect(None, 8), ColorDepth::Ansi16);
+}
+
+#[test]
+fn test_presets_keep_selection_visible() {
+    for name in ThemeName::ALL {
+        let palette = Palette::preset(name);
+        assert_eq!(palette.name, name);
+        assert_ne!(palette.text, palette.accent, "{}", name);
+        assert_ne!(palette.on_accent, palette.accent, "{}", name);
+        assert_ne!(palette.dim, palette.text, "{}", name);
+        assert_ne!(palette.selection_bg, palette.accent, "{}", name);
+    }
+
+    // Light backgrounds get dark text
+    assert_eq!(Palette::preset(ThemeName::Light).text, TermColor::Ansi(0));
+    assert_eq!(Palette::preset(ThemeName::Dark).text, TermColor::Ansi(15));
+}
+
+#[test]
+fn test_custom_accent() {
+    let dark = Palette::preset(ThemeName::Dark);
+    assert_eq!(dark.with_accent(None, ColorDepth::TrueColor), dark);
+
+    let accent = Some(TermColor::Rgb(0xff, 0x87, 0x00));
+    let custom = dark.with_accent(accent, ColorDepth::Ansi256);
+    assert_eq!(custom.accent, TermColor::Indexed(208));
+    assert_eq!(custom.text, dark.text);
+    assert_eq!(
+        dark.with_accent(accent, ColorDepth::TrueColor).accent,
+        TermColor::Rgb(0xff, 0x87, 0x00)
+    );
+}
+
+#[test]
+fn test_theme_names() {
+    assert_eq!("Light".parse::<ThemeName>(), Ok(ThemeName::Light));
+    assert!("solarized".parse::<ThemeName>().is_err());
+    assert_eq!(ThemeName::Dark.next(), ThemeName::Light);
+    assert_eq!(ThemeName::Light.next(), ThemeName::Dark);
+    assert_eq!(ThemeName::default(), ThemeName::Dark);
+}
+
+#[test]
+fn test_validate_settings() {
+    assert!(settings::validate(settings::TUI_THEME, "light").is_ok());
+    assert!(settings::validate(settings::TUI_THEME, "blue").is_err());
+    assert!(settings::validate(settings::TUI_ACCENT, "#7aa2f7").is_ok());
+    assert!(settings::validate(settings::TUI_ACCENT, "#7aa2").is_err());
+
+    assert!(settings::validate(settings::TUI_REFRESH_SECS, "0").is_ok());
+    assert!(settings::validate(settings::TUI_REFRESH_SECS, "-5").is_err());
+    assert!(settings::validate(settings::SSH_STREAM_TIMEOUT_SECS, "600").is_ok());
+    assert!(settings::validate(settings::SSH_STREAM_TIMEOUT_SECS, "10m").is_err());
+
+    let err = settings::validate("tui_colour", "x").unwrap_err();
+    assert!(err.contains("Unknown setting"));
+    assert!(err.contains(settings::TUI_THEME));
+}
+
+#[test]
+fn test_setting_changes_compare_values_in_effect() {
+    let set = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+    let old = set(&[(settings::TUI_THEME, "dark"), (settings::HINTS, "off")]);
+    let new = set(&[
+        (settings::TUI_ACCENT, "magenta"),
+        (settings::HINTS, "off"),
+        (settings::TUI_REFRESH_SECS, "30"),
+        ("retired_setting", "x"),
+    ]);
+
+    // Unsetting tui_theme falls back to its default, dark: no change
+    let changes = settings::changes(&old, &new);
+    let summary: Vec<(String, settings::LiveEffect)> =
+        changes.iter().map(|c| (c.to_string(), c.effect)).collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                "tui_accent - -> magenta".to_string(),
+                settings::LiveEffect::Theme
+            ),
+            (
+                "tui_refresh_secs 0 -> 30".to_string(),
+                settings::LiveEffect::Refresh
+            ),
+        ]
+    );
+
+    assert!(settings::changes(&new, &new).is_empty());
+    assert_eq!(
+        settings::live_effect(settings::SCRIPT_APPROVAL),
+        settings::LiveEffect::Commands
+    );
+}
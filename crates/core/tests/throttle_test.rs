@@ -0,0 +1,139 @@
+use pctrl_core::throttle::{run, Limits, Progress};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+fn limits(per_group: usize, per_second: f64) -> Limits {
+    Limits {
+        per_group,
+        per_second,
+        jitter: Duration::ZERO,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_caps_concurrency_per_group() {
+    let tasks: Vec<(&str, u32)> = (0..6)
+        .map(|i| ("a", i))
+        .chain((0..2).map(|i| ("b", i)))
+        .collect();
+    let running: Mutex<HashMap<&str, usize>> = Mutex::default();
+    let peak: Mutex<HashMap<&str, usize>> = Mutex::default();
+    let mut reported = Vec::new();
+
+    let started = Instant::now();
+    let results = run(
+        &tasks,
+        |(group, _)| group.to_string(),
+        &limits(2, 0.0),
+        |(group, i)| {
+            let (running, peak) = (&running, &peak);
+            async move {
+                {
+                    let mut running = running.lock().unwrap();
+                    let now = running.entry(group).or_default();
+                    *now += 1;
+                    let mut peak = peak.lock().unwrap();
+                    let max = peak.entry(group).or_default();
+                    *max = (*max).max(*now);
+                }
+                sleep(Duration::from_secs(1)).await;
+                *running.lock().unwrap().get_mut(group).unwrap() -= 1;
+                format!("{}{}", group, i)
+            }
+        },
+        |progress| reported.push(progress.done),
+    )
+    .await;
+
+    let peak = peak.into_inner().unwrap();
+    assert_eq!(peak["a"], 2);
+    assert_eq!(peak["b"], 2);
+    // Six checks two at a time; "b" runs alongside
+    assert_eq!(started.elapsed(), Duration::from_secs(3));
+    assert_eq!(
+        results,
+        vec!["a0", "a1", "a2", "a3", "a4", "a5", "b0", "b1"]
+    );
+    assert_eq!(reported, (1..=8).collect::<Vec<_>>());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_spaces_starts_to_the_rate() {
+    let tasks: Vec<usize> = (0..10).collect();
+    let starts: Mutex<Vec<Instant>> = Mutex::default();
+
+    let started = Instant::now();
+    run(
+        &tasks,
+        |i| i.to_string(),
+        &limits(2, 5.0),
+        |_| {
+            let starts = &starts;
+            async move { starts.lock().unwrap().push(Instant::now()) }
+        },
+        |_| {},
+    )
+    .await;
+
+    let starts = starts.into_inner().unwrap();
+    assert_eq!(starts.len(), 10);
+    assert_eq!(starts[0], started);
+    for pair in starts.windows(2) {
+        assert_eq!(pair[1] - pair[0], Duration::from_millis(200));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_jitters_starts_within_a_group() {
+    let tasks = ["a", "a", "a", "b"];
+    let starts: Mutex<Vec<(&str, Instant)>> = Mutex::default();
+
+    let started = Instant::now();
+    run(
+        &tasks,
+        |group| group.to_string(),
+        &Limits {
+            per_group: 5,
+            per_second: 0.0,
+            jitter: Duration::from_millis(100),
+        },
+        |group| {
+            let starts = &starts;
+            async move { starts.lock().unwrap().push((*group, Instant::now())) }
+        },
+        |_| {},
+    )
+    .await;
+
+    let starts = starts.into_inner().unwrap();
+    let a: Vec<Instant> = starts
+        .iter()
+        .filter(|(g, _)| *g == "a")
+        .map(|(_, t)| *t)
+        .collect();
+    for pair in a.windows(2) {
+        assert!(pair[1] - pair[0] <= Duration::from_millis(100));
+    }
+    // Another group doesn't wait for the pause
+    assert!(starts.contains(&("b", started)));
+}
+
+#[test]
+fn test_progress_estimates_the_rest() {
+    let progress = Progress {
+        done: 40,
+        total: 80,
+        elapsed: Duration::from_secs(40),
+    };
+    assert_eq!(progress.remaining(), Some(Duration::from_secs(40)));
+    assert_eq!(progress.to_string(), "40/80, ~40s remaining");
+
+    let done = Progress {
+        done: 80,
+        ..progress
+    };
+    assert_eq!(done.remaining(), None);
+    assert_eq!(done.to_string(), "80/80");
+}
@@ -0,0 +1,105 @@
+use pctrl_core::transfer::{self, Bucket, Progress, Resume, Verified};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_resume_offset() {
+    assert_eq!(transfer::resume_offset(None, 100), Resume::Fresh);
+    assert_eq!(transfer::resume_offset(Some(0), 100), Resume::Fresh);
+    assert_eq!(transfer::resume_offset(Some(80), 100), Resume::From(80));
+    assert_eq!(transfer::resume_offset(Some(100), 100), Resume::Complete);
+    // A partial file of a larger, older version
+    assert_eq!(transfer::resume_offset(Some(120), 100), Resume::Restart);
+    assert_eq!(transfer::resume_offset(None, 0), Resume::Complete);
+
+    assert_eq!(Resume::From(80).offset(100), 80);
+    assert_eq!(Resume::Restart.offset(100), 0);
+    assert_eq!(Resume::Complete.offset(100), 100);
+    assert_eq!(
+        transfer::partial_path("/srv/db.tar.gz"),
+        "/srv/db.tar.gz.part"
+    );
+}
+
+#[test]
+fn test_bucket_caps_the_rate() {
+    let start = Instant::now();
+    let mut bucket = Bucket::new(1000, start);
+
+    // A quarter second's worth goes out at once
+    assert_eq!(bucket.take(250, start), Duration::ZERO);
+    // Then each byte waits for its refill
+    assert_eq!(bucket.take(500, start), Duration::from_millis(500));
+    // Time passing pays the debt; the wait was kept
+    let later = start + Duration::from_millis(500);
+    assert_eq!(bucket.take(100, later), Duration::from_millis(100));
+
+    // Idle time refills up to the burst only
+    let idle = later + Duration::from_secs(60);
+    assert_eq!(bucket.take(250, idle), Duration::ZERO);
+    assert_eq!(bucket.take(1, idle), Duration::from_millis(1));
+
+    // Over a long transfer, bytes / waited time stays at the cap
+    let mut bucket = Bucket::new(2 << 20, start);
+    let mut now = start;
+    for _ in 0..640 {
+        now += bucket.take(32 * 1024, now);
+    }
+    let rate = (640.0 * 32.0 * 1024.0) / (now - start).as_secs_f64();
+    assert!((rate - (2 << 20) as f64).abs() / ((2 << 20) as f64) < 0.05);
+}
+
+#[test]
+fn test_verify() {
+    let sha = "a".repeat(64);
+    assert_eq!(transfer::verify(10, 10, None, None), Ok(Verified::Size));
+    assert_eq!(
+        transfer::verify(10, 10, Some(&sha), None),
+        Ok(Verified::Size)
+    );
+    assert_eq!(
+        transfer::verify(10, 10, Some(&sha), Some(&sha.to_uppercase())),
+        Ok(Verified::Checksum)
+    );
+    let err = transfer::verify(9, 10, Some(&sha), Some(&sha)).unwrap_err();
+    assert!(err.contains("size mismatch"));
+    let err = transfer::verify(10, 10, Some(&sha), Some(&"b".repeat(64))).unwrap_err();
+    assert!(err.contains("checksum mismatch"));
+
+    let output = format!("{}  /var/backups/db.tar.gz\n", "0f".repeat(32));
+    assert_eq!(transfer::parse_sha256sum(&output), Some("0f".repeat(32)));
+    assert_eq!(transfer::parse_sha256sum(""), None);
+    assert_eq!(transfer::parse_sha256sum("sha256sum: not found"), None);
+    assert_eq!(
+        transfer::sha256sum_command("/srv/my backup.tar"),
+        "sha256sum -- '/srv/my backup.tar' 2>/dev/null"
+    );
+}
+
+#[test]
+fn test_progress_line() {
+    let progress = Progress {
+        done: 3 << 20,
+        total: 4 << 20,
+        resumed_from: 1 << 20,
+        elapsed: Duration::from_secs(2),
+    };
+    // Only this run's bytes count for the rate
+    assert_eq!(progress.rate(), (1 << 20) as f64);
+    assert_eq!(progress.eta(), Some(Duration::from_secs(1)));
+    assert_eq!(
+        progress.line(),
+        "[###############-----]  75%  3.0 MB / 4.0 MB  1.0 MB/s  ETA 1s"
+    );
+
+    let starting = Progress {
+        elapsed: Duration::ZERO,
+        ..progress
+    };
+    assert_eq!(starting.eta(), None);
+    let done = Progress {
+        done: 4 << 20,
+        ..progress
+    };
+    assert!(done.line().starts_with("[####################] 100%"));
+    assert!(!done.line().contains("ETA"));
+}
@@ -0,0 +1,37 @@
+use pctrl_core::{percentile, CommandUsage};
+
+#[test]
+fn test_percentile_nearest_rank() {
+    let sorted: Vec<i64> = (1..=100).collect();
+    assert_eq!(percentile(&sorted, 50.0), Some(50));
+    assert_eq!(percentile(&sorted, 95.0), Some(95));
+    assert_eq!(percentile(&sorted, 100.0), Some(100));
+    assert_eq!(percentile(&sorted, 0.0), Some(1));
+}
+
+#[test]
+fn test_percentile_small_and_empty() {
+    assert_eq!(percentile(&[], 50.0), None);
+    assert_eq!(percentile(&[7], 95.0), Some(7));
+    assert_eq!(percentile(&[10, 20, 30], 50.0), Some(20));
+    assert_eq!(percentile(&[10, 20, 30], 95.0), Some(30));
+}
+
+#[test]
+fn test_from_durations_sorts_and_averages() {
+    let usage = CommandUsage::from_durations("server status", 1, &[400, 100, 200, 300]);
+    assert_eq!(usage.count, 4);
+    assert_eq!(usage.avg_ms, 250);
+    assert_eq!(usage.p50_ms, 200);
+    assert_eq!(usage.p95_ms, 400);
+    assert!((usage.failure_rate() - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn test_from_durations_empty() {
+    let usage = CommandUsage::from_durations("status", 0, &[]);
+    assert_eq!(usage.count, 0);
+    assert_eq!(usage.avg_ms, 0);
+    assert_eq!(usage.p95_ms, 0);
+    assert_eq!(usage.failure_rate(), 0.0);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use pctrl_core::vpn::{Interface, TunnelState, Tunnels};
+use pctrl_core::Server;
+
+fn server(requires_vpn: Option<&str>) -> Server {
+    Server {
+        host: "10.8.0.10".into(),
+        requires_vpn: requires_vpn.map(String::from),
+        ..common::server("web")
+    }
+}
+
+fn interface(name: &str, up: bool) -> Interface {
+    Interface {
+        name: name.to_string(),
+        up,
+    }
+}
+
+#[test]
+fn test_tunnel_state() {
+    let tunnels = Tunnels::new(Some(vec![interface("wg0", true), interface("wg1", false)]));
+    assert_eq!(tunnels.state("wg0"), TunnelState::Connected);
+    assert_eq!(tunnels.state("wg1"), TunnelState::NotConnected);
+    assert_eq!(tunnels.state("wg2"), TunnelState::NotConnected, "missing");
+
+    assert_eq!(tunnels.blocked(&server(Some("wg0"))), None);
+    assert_eq!(
+        tunnels.blocked(&server(Some("wg2"))).as_deref(),
+        Some("VPN wg2 not connected")
+    );
+    assert_eq!(tunnels.blocked(&server(None)), None);
+}
+
+#[test]
+fn test_unknown_state_never_blocks() {
+    let tunnels = Tunnels::new(None);
+    assert_eq!(tunnels.state("wg0"), TunnelState::Unknown);
+    assert_eq!(tunnels.blocked(&server(Some("wg0"))), None);
+}
+
+#[tokio::test]
+async fn test_detect_skips_servers_without_vpn() {
+    let tunnels = Tunnels::detect(&[server(None)]).await;
+    assert_eq!(tunnels.state("wg0"), TunnelState::Unknown);
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::interface;
+    use pctrl_core::vpn::linux::{parse_ip_link, parse_wg_interfaces};
+
+    #[test]
+    fn test_parse_ip_link() {
+        let interfaces = parse_ip_link(include_str!("fixtures/ip_link.json")).unwrap();
+        assert_eq!(
+            interfaces,
+            vec![
+                interface("lo", true),
+                interface("enp3s0", true),
+                // Up, but no carrier
+                interface("wlp4s0", false),
+                // WireGuard reports no operstate of its own
+                interface("wg0", true),
+                interface("wg-office", false),
+            ]
+        );
+        assert_eq!(parse_ip_link("Object \"link\" is unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_wg_interfaces() {
+        let interfaces = parse_wg_interfaces(include_str!("fixtures/wg_show_interfaces.txt"));
+        assert_eq!(
+            interfaces,
+            vec![interface("wg0", true), interface("wg-home", true)]
+        );
+        assert!(parse_wg_interfaces("\n").is_empty());
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::interface;
+    use pctrl_core::vpn::macos::{alias, parse_ifconfig};
+
+    #[test]
+    fn test_parse_ifconfig() {
+        let mut interfaces = parse_ifconfig(include_str!("fixtures/ifconfig_macos.txt"));
+        assert_eq!(
+            interfaces,
+            vec![
+                interface("lo0", true),
+                interface("en0", true),
+                interface("bridge0", false),
+                interface("utun3", true),
+            ]
+        );
+
+        alias(&mut interfaces, "wg0", "utun3");
+        alias(&mut interfaces, "office", "utun9");
+        assert_eq!(interfaces.last(), Some(&interface("wg0", true)));
+        assert!(!interfaces.iter().any(|i| i.name == "office"));
+    }
+}
@@ -0,0 +1,602 @@
+//! Encrypted backups (`pctrl backup --encrypt`)
+//!
+//! A `.pctrlbak` file is a header followed by AES-256-GCM encrypted frames.
+//! The key is derived from a passphrase with Argon2, like the column
+//! encryption.
+//!
+//! Header (big-endian, [`HEADER_LEN`] bytes):
+//!
+//! | bytes | field                                                    |
+//! |-------|----------------------------------------------------------|
+//! | 8     | magic `PCTRLBAK`                                         |
+//! | 1     | format version                                           |
+//! | 4     | plaintext chunk size                                     |
+//! | 16    | Argon2 salt                                              |
+//! | 8     | nonce prefix                                             |
+//! | 16    | key check: GCM tag of nothing under the last nonce       |
+//! | 32    | SHA-256 of the plaintext                                 |
+//!
+//! Each frame is a flag byte (1 for the last frame), the ciphertext length
+//! (4 bytes) and the ciphertext. The nonce is the prefix plus the frame
+//! counter, and flag and length are authenticated, so reordered, dropped or
+//! cut-off frames all fail. Every frame also authenticates the header up to
+//! the checksum (which is only known at the end and checked then), so a
+//! changed chunk size fails like a changed frame. Chunk sizes above [`MAX_CHUNK_SIZE`] are refused before anything
+//! is allocated.
+
+use crate::db_file::{self, DbFileKind};
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+use crate::Database;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// File extension of encrypted backups
+pub const EXTENSION: &str = "pctrlbak";
+
+pub const MAGIC: &[u8; 8] = b"PCTRLBAK";
+
+/// Header format, the only one read
+pub const FORMAT_VERSION: u8 = 2;
+
+/// Plaintext bytes per frame
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size a backup may declare
+pub const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
+pub const HEADER_LEN: usize = 8 + 1 + 4 + 16 + 8 + 16 + 32;
+
+const TAG_LEN: usize = 16;
+
+/// Counter of the key check nonce, never used for a frame
+const KEY_CHECK_COUNTER: u32 = u32::MAX;
+
+/// Why a backup can't be read
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("Not an encrypted pctrl backup")]
+    NotABackup,
+
+    #[error("Unsupported backup format version {0} (this pctrl reads version {FORMAT_VERSION})")]
+    UnsupportedVersion(u8),
+
+    #[error("Backup header declares chunks of {0} bytes, more than the {MAX_CHUNK_SIZE} allowed; it is damaged or was modified")]
+    ChunkTooLarge(u32),
+
+    #[error("Wrong passphrase (or a damaged header)")]
+    WrongPassphrase,
+
+    #[error("Backup is truncated")]
+    Truncated,
+
+    #[error("Backup was modified: frame {0} failed authentication")]
+    Tampered(u32),
+
+    #[error("Backup has data after its last frame")]
+    TrailingData,
+
+    #[error("Checksum mismatch: the decrypted data doesn't match the header")]
+    ChecksumMismatch,
+
+    #[error("Encryption failed: {0}")]
+    Crypto(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl From<BackupError> for pctrl_core::Error {
+    fn from(e: BackupError) -> Self {
+        match e {
+            BackupError::Io(e) => pctrl_core::Error::Io(e),
+            other => pctrl_core::Error::Config(other.to_string()),
+        }
+    }
+}
+
+/// The fixed-size header of a `.pctrlbak` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub chunk_size: u32,
+    pub salt: [u8; 16],
+    pub nonce_prefix: [u8; 8],
+    pub key_check: [u8; 16],
+    pub sha256: [u8; 32],
+}
+
+impl Header {
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..8].copy_from_slice(MAGIC);
+        bytes[8] = self.version;
+        bytes[9..13].copy_from_slice(&self.chunk_size.to_be_bytes());
+        bytes[13..29].copy_from_slice(&self.salt);
+        bytes[29..37].copy_from_slice(&self.nonce_prefix);
+        bytes[37..53].copy_from_slice(&self.key_check);
+        bytes[53..].copy_from_slice(&self.sha256);
+        bytes
+    }
+
+    /// Parse a header; a short or foreign file is [`BackupError::NotABackup`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BackupError> {
+        if bytes.len() < 9 || &bytes[..8] != MAGIC {
+            return Err(BackupError::NotABackup);
+        }
+        if bytes[8] != FORMAT_VERSION {
+            return Err(BackupError::UnsupportedVersion(bytes[8]));
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(BackupError::Truncated);
+        }
+
+        let mut header = Header {
+            version: bytes[8],
+            chunk_size: u32::from_be_bytes(bytes[9..13].try_into().unwrap()),
+            salt: [0; 16],
+            nonce_prefix: [0; 8],
+            key_check: [0; 16],
+            sha256: [0; 32],
+        };
+        header.salt.copy_from_slice(&bytes[13..29]);
+        header.nonce_prefix.copy_from_slice(&bytes[29..37]);
+        header.key_check.copy_from_slice(&bytes[37..53]);
+        header.sha256.copy_from_slice(&bytes[53..HEADER_LEN]);
+        Ok(header)
+    }
+
+    /// Read the header from the start of a backup
+    pub fn read(reader: &mut impl Read) -> Result<Self, BackupError> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        reader.take(HEADER_LEN as u64).read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Whether a file starts with the backup magic
+pub fn is_encrypted_backup(path: &Path) -> io::Result<bool> {
+    let mut magic = Vec::with_capacity(MAGIC.len());
+    std::fs::File::open(path)?
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    Ok(magic == MAGIC)
+}
+
+/// The newest backup in `dir` that reads as one: a SQLite file, or a
+/// `.pctrlbak` with a valid header (its content needs the passphrase)
+pub fn latest_backup(dir: &Path) -> io::Result<Option<std::path::PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut candidates = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && is_backup_file(&path) {
+            candidates.push((metadata.modified()?, path));
+        }
+    }
+    candidates.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(candidates.into_iter().map(|(_, path)| path).next())
+}
+
+fn is_backup_file(path: &Path) -> bool {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut start = Vec::with_capacity(HEADER_LEN);
+    if (&mut file)
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut start)
+        .is_err()
+    {
+        return false;
+    }
+    start.starts_with(SQLITE_MAGIC) || Header::from_bytes(&start).is_ok()
+}
+
+fn nonce(prefix: &[u8; 8], counter: u32) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(prefix);
+    nonce[8..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, BackupError> {
+    let key =
+        Database::derive_key(passphrase, salt).map_err(|e| BackupError::Crypto(e.to_string()))?;
+    Ok(Aes256Gcm::new(&key.into()))
+}
+
+fn key_check(cipher: &Aes256Gcm, prefix: &[u8; 8]) -> Result<[u8; 16], BackupError> {
+    let nonce = nonce(prefix, KEY_CHECK_COUNTER);
+    let tag = cipher
+        .encrypt(Nonce::from_slice(&nonce), &[][..])
+        .map_err(|e| BackupError::Crypto(e.to_string()))?;
+    tag.try_into()
+        .map_err(|_| BackupError::Crypto("unexpected tag length".to_string()))
+}
+
+/// Flag and length of a frame, as written before its ciphertext
+fn frame_prefix(last: bool, len: u32) -> [u8; 5] {
+    let mut prefix = [0u8; 5];
+    prefix[0] = last as u8;
+    prefix[1..].copy_from_slice(&len.to_be_bytes());
+    prefix
+}
+
+/// What a frame authenticates besides its ciphertext: the header without
+/// its checksum, then flag and length
+fn frame_aad(header: &Header, prefix: &[u8; 5]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(HEADER_LEN + prefix.len());
+    aad.extend_from_slice(&header.to_bytes()[..HEADER_LEN - 32]);
+    aad.extend_from_slice(prefix);
+    aad
+}
+
+/// Read until `buf` is full or the input ends; returns the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encrypt `reader` into `writer` chunk by chunk; returns the plaintext size.
+///
+/// The checksum is only known at the end, so the header is written first
+/// and completed by seeking back.
+pub fn encrypt(
+    reader: &mut impl Read,
+    writer: &mut (impl Write + Seek),
+    passphrase: &str,
+) -> Result<u64, BackupError> {
+    encrypt_chunked(reader, writer, passphrase, CHUNK_SIZE)
+}
+
+/// [`encrypt`] with a custom chunk size
+pub fn encrypt_chunked(
+    reader: &mut impl Read,
+    writer: &mut (impl Write + Seek),
+    passphrase: &str,
+    chunk_size: usize,
+) -> Result<u64, BackupError> {
+    if chunk_size > MAX_CHUNK_SIZE as usize {
+        return Err(BackupError::ChunkTooLarge(chunk_size as u32));
+    }
+    let mut header = Header {
+        version: FORMAT_VERSION,
+        chunk_size: chunk_size as u32,
+        salt: [0; 16],
+        nonce_prefix: [0; 8],
+        key_check: [0; 16],
+        sha256: [0; 32],
+    };
+    rand::rngs::OsRng.fill_bytes(&mut header.salt);
+    rand::rngs::OsRng.fill_bytes(&mut header.nonce_prefix);
+    let cipher = cipher(passphrase, &header.salt)?;
+    header.key_check = key_check(&cipher, &header.nonce_prefix)?;
+
+    let start = writer.stream_position()?;
+    writer.write_all(&header.to_bytes())?;
+
+    let mut hasher = Sha256::new();
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    let mut current = vec![0u8; chunk_size];
+    let mut next = vec![0u8; chunk_size];
+    let mut len = read_full(reader, &mut current)?;
+    loop {
+        // Look ahead one chunk to know whether this one is the last
+        let next_len = if len == chunk_size {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        if counter == KEY_CHECK_COUNTER {
+            return Err(BackupError::Crypto("backup too large".to_string()));
+        }
+
+        let plain = &current[..len];
+        hasher.update(plain);
+        total += len as u64;
+        let prefix = frame_prefix(last, (len + TAG_LEN) as u32);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce(&header.nonce_prefix, counter)),
+                Payload {
+                    msg: plain,
+                    aad: &frame_aad(&header, &prefix),
+                },
+            )
+            .map_err(|e| BackupError::Crypto(e.to_string()))?;
+        writer.write_all(&prefix)?;
+        writer.write_all(&ciphertext)?;
+
+        if last {
+            break;
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+        counter += 1;
+    }
+
+    header.sha256 = hasher.finalize().into();
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(start))?;
+    writer.write_all(&header.to_bytes())?;
+    writer.seek(SeekFrom::Start(end))?;
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Decrypt a backup into `writer`; returns the plaintext size.
+///
+/// The checksum can only be verified at the end, so `writer` should be a
+/// temporary file that is discarded on error.
+pub fn decrypt(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    passphrase: &str,
+) -> Result<u64, BackupError> {
+    let header = Header::read(reader)?;
+    if header.chunk_size > MAX_CHUNK_SIZE {
+        return Err(BackupError::ChunkTooLarge(header.chunk_size));
+    }
+    let cipher = cipher(passphrase, &header.salt)?;
+    if key_check(&cipher, &header.nonce_prefix)? != header.key_check {
+        return Err(BackupError::WrongPassphrase);
+    }
+
+    let max_frame = header.chunk_size as usize + TAG_LEN;
+    let mut hasher = Sha256::new();
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    let mut frame = vec![0u8; max_frame];
+    loop {
+        let mut prefix = [0u8; 5];
+        if read_full(reader, &mut prefix)? < prefix.len() {
+            return Err(BackupError::Truncated);
+        }
+        let last = match prefix[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(BackupError::Tampered(counter)),
+        };
+        let len = u32::from_be_bytes(prefix[1..].try_into().unwrap()) as usize;
+        if !(TAG_LEN..=max_frame).contains(&len) {
+            return Err(BackupError::Tampered(counter));
+        }
+        if read_full(reader, &mut frame[..len])? < len {
+            return Err(BackupError::Truncated);
+        }
+
+        let plain = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce(&header.nonce_prefix, counter)),
+                Payload {
+                    msg: &frame[..len],
+                    aad: &frame_aad(&header, &prefix),
+                },
+            )
+            .map_err(|_| BackupError::Tampered(counter))?;
+        hasher.update(&plain);
+        writer.write_all(&plain)?;
+        total += plain.len() as u64;
+
+        if last {
+            break;
+        }
+        counter = counter
+            .checked_add(1)
+            .filter(|c| *c != KEY_CHECK_COUNTER)
+            .ok_or(BackupError::Tampered(counter))?;
+    }
+
+    if reader.read(&mut [0u8; 1])? != 0 {
+        return Err(BackupError::TrailingData);
+    }
+    let sha256: [u8; 32] = hasher.finalize().into();
+    if sha256 != header.sha256 {
+        return Err(BackupError::ChecksumMismatch);
+    }
+    writer.flush()?;
+    Ok(total)
+}
+
+/// First bytes of every SQLite database file
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// `<path><suffix>`, next to `path`
+fn sibling(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+impl Database {
+    /// Write a consistent snapshot of the database to `path` (`VACUUM INTO`)
+    pub async fn backup_to(&self, path: &Path) -> pctrl_core::Result<()> {
+        if path.exists() {
+            return Err(pctrl_core::Error::Config(format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Write an encrypted snapshot to `path`; returns the plaintext size.
+    ///
+    /// The plain snapshot is staged next to the database, never next to the
+    /// target, and removed afterwards.
+    pub async fn backup_encrypted_to(
+        &self,
+        path: &Path,
+        passphrase: &str,
+    ) -> pctrl_core::Result<u64> {
+        if path.exists() {
+            return Err(pctrl_core::Error::Config(format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+        let staged = sibling(&self.path(), ".backup-tmp");
+        let _ = std::fs::remove_file(&staged);
+        self.backup_to(&staged).await?;
+
+        let result = (|| -> Result<u64, BackupError> {
+            let mut reader = io::BufReader::new(std::fs::File::open(&staged)?);
+            let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+            encrypt(&mut reader, &mut writer, passphrase)
+        })();
+        let _ = std::fs::remove_file(&staged);
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(result?)
+    }
+
+    /// Replace the database file at `db_path` with a backup.
+    ///
+    /// Encrypted backups need the passphrase and are fully decrypted first.
+    /// Nothing is replaced unless the backup is a pctrl database this
+    /// version can open and passes SQLite's integrity check. The current
+    /// file is copied to `safety_copy` before it is replaced. The database
+    /// must be closed.
+    pub async fn restore_backup(
+        db_path: &Path,
+        backup: &Path,
+        passphrase: Option<&str>,
+        safety_copy: Option<&Path>,
+    ) -> pctrl_core::Result<()> {
+        let staged = sibling(db_path, ".restore-tmp");
+        let result = async {
+            if is_encrypted_backup(backup)? {
+                let passphrase = passphrase.ok_or_else(|| {
+                    pctrl_core::Error::Config(format!(
+                        "{} is encrypted: a passphrase is required",
+                        backup.display()
+                    ))
+                })?;
+                let mut reader = io::BufReader::new(std::fs::File::open(backup)?);
+                let mut writer = io::BufWriter::new(std::fs::File::create(&staged)?);
+                decrypt(&mut reader, &mut writer, passphrase)?;
+            } else {
+                std::fs::copy(backup, &staged)?;
+            }
+            check_restorable(&staged, backup).await
+        }
+        .await;
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+
+        if let Some(safety_copy) = safety_copy.filter(|_| db_path.exists()) {
+            if let Err(e) = std::fs::copy(db_path, safety_copy) {
+                let _ = std::fs::remove_file(&staged);
+                return Err(e.into());
+            }
+        }
+        // A journal left behind would be applied to the restored file
+        for suffix in ["-journal", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(sibling(db_path, suffix));
+        }
+        std::fs::rename(&staged, db_path)?;
+        Ok(())
+    }
+}
+
+/// Refuse a staged restore that isn't a healthy pctrl database this
+/// version can open; `backup` is the file named in errors
+async fn check_restorable(staged: &Path, backup: &Path) -> pctrl_core::Result<()> {
+    let not_pctrl = |why: &str| {
+        pctrl_core::Error::Config(format!(
+            "{} is not a pctrl database backup ({})",
+            backup.display(),
+            why
+        ))
+    };
+    let mut magic = Vec::with_capacity(SQLITE_MAGIC.len());
+    std::fs::File::open(staged)?
+        .take(SQLITE_MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    if magic != SQLITE_MAGIC {
+        return Err(not_pctrl("not a SQLite file"));
+    }
+    match db_file::inspect(staged).await? {
+        DbFileKind::Pctrl(_) => {}
+        DbFileKind::New => return Err(not_pctrl("no tables")),
+        DbFileKind::Foreign(tables) => {
+            return Err(not_pctrl(&format!("tables: {}", tables.join(", "))))
+        }
+        DbFileKind::NotSqlite => return Err(not_pctrl("not a SQLite file")),
+        DbFileKind::NewerPctrl(version) => {
+            return Err(pctrl_core::Error::Config(format!(
+                "{} was written by a newer pctrl (schema v{}, this version knows v{})",
+                backup.display(),
+                version,
+                CURRENT_SCHEMA_VERSION
+            )))
+        }
+    }
+    db_file::integrity_check(staged).await
+}
+
+/// Whether `name` is one `pctrl backup` gave a backup it named itself
+/// (`pctrl-YYYYmmdd-HHMMSS.db` or `.pctrlbak`)
+pub fn is_timestamped_backup(name: &str) -> bool {
+    let Some(stem) = name
+        .strip_suffix(".db")
+        .or_else(|| name.strip_suffix(&format!(".{}", EXTENSION)))
+    else {
+        return false;
+    };
+    let Some(stamp) = stem.strip_prefix("pctrl-") else {
+        return false;
+    };
+    chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").is_ok()
+}
+
+/// Delete all but the `keep` newest timestamped backups in `dir` (see
+/// [`is_timestamped_backup`]); other files are left alone. Returns the
+/// deleted paths.
+pub fn prune_backups(dir: &Path, keep: usize) -> io::Result<Vec<std::path::PathBuf>> {
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_file() && is_timestamped_backup(&name) {
+            backups.push((name, entry.path()));
+        }
+    }
+    // The timestamp in the name sorts chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for (_, path) in backups.into_iter().take(excess) {
+        std::fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
@@ -0,0 +1,218 @@
+//! Packing and verifying project bundles (`pctrl project bundle`; the
+//! parts are rendered by [`pctrl_core::project_archive`])
+//!
+//! Files are staged in a temporary directory next to the output, hashed,
+//! and streamed into `<out>.partial` as gzip-compressed tar, with
+//! `manifest.json` first. The partial file is renamed into place only when
+//! everything was written; on any error it is removed, and the staging
+//! directory goes with its guard.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pctrl_core::project_archive::{Manifest, ManifestFile, Part, MANIFEST};
+use pctrl_core::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Prefix of the staging directory, next to the output
+pub const STAGING_PREFIX: &str = ".pctrl-bundle-";
+
+/// Content of one bundle file
+#[derive(Debug, Clone)]
+pub enum EntrySource {
+    Bytes(Vec<u8>),
+    /// Copied from disk when staged (e.g. a backup)
+    File(PathBuf),
+}
+
+/// One file to put into a bundle
+#[derive(Debug, Clone)]
+pub struct BundleEntry {
+    pub part: Part,
+    /// Relative path inside the archive
+    pub path: String,
+    pub source: EntrySource,
+}
+
+/// What `verify_bundle` found
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    pub manifest: Manifest,
+    /// Files whose size or checksum differ from the manifest
+    pub mismatched: Vec<String>,
+    /// Files in the manifest but not in the archive
+    pub missing: Vec<String>,
+    /// Files in the archive but not in the manifest
+    pub unlisted: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.unlisted.is_empty()
+    }
+}
+
+/// Write the bundle to `out`; `manifest` supplies everything but `files`,
+/// which is filled from the entries. Refuses to overwrite `out`.
+pub fn write_bundle(out: &Path, entries: &[BundleEntry], manifest: Manifest) -> Result<Manifest> {
+    if out.exists() {
+        return Err(Error::Config(format!("{} already exists", out.display())));
+    }
+    let parent = match out.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let staging = tempfile::Builder::new()
+        .prefix(STAGING_PREFIX)
+        .tempdir_in(&parent)?;
+
+    let mut manifest = manifest;
+    manifest.files = entries
+        .iter()
+        .map(|entry| stage(staging.path(), entry))
+        .collect::<Result<Vec<_>>>()?;
+    let manifest_json =
+        serde_json::to_vec_pretty(&manifest).map_err(|e| Error::Config(e.to_string()))?;
+
+    let partial = {
+        let mut name = out.as_os_str().to_owned();
+        name.push(".partial");
+        PathBuf::from(name)
+    };
+    let packed = pack(&partial, staging.path(), &manifest_json, &manifest.files);
+    if let Err(e) = packed.and_then(|()| Ok(std::fs::rename(&partial, out)?)) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    Ok(manifest)
+}
+
+/// Copy an entry into the staging directory and describe it
+fn stage(staging: &Path, entry: &BundleEntry) -> Result<ManifestFile> {
+    let relative = safe_path(&entry.path)?;
+    let target = staging.join(&relative);
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    match &entry.source {
+        EntrySource::Bytes(bytes) => std::fs::write(&target, bytes)?,
+        EntrySource::File(source) => {
+            std::fs::copy(source, &target)
+                .map_err(|e| Error::Config(format!("Can't read {}: {}", source.display(), e)))?;
+        }
+    }
+    let (size, sha256) = checksum(std::fs::File::open(&target)?)?;
+    Ok(ManifestFile {
+        path: entry.path.clone(),
+        part: entry.part,
+        size,
+        sha256,
+    })
+}
+
+fn pack(partial: &Path, staging: &Path, manifest: &[u8], files: &[ManifestFile]) -> Result<()> {
+    let file = std::fs::File::create(partial)?;
+    let mut tar = tar::Builder::new(GzEncoder::new(
+        io::BufWriter::new(file),
+        Compression::default(),
+    ));
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, MANIFEST, manifest)?;
+    for file in files {
+        tar.append_path_with_name(staging.join(&file.path), &file.path)?;
+    }
+
+    let mut writer = tar.into_inner()?.finish()?;
+    writer.flush()?;
+    writer
+        .into_inner()
+        .map_err(|e| Error::Io(e.into_error()))?
+        .sync_all()?;
+    Ok(())
+}
+
+/// Check every file of a bundle against its manifest, streaming the
+/// archive once
+pub fn verify_bundle(path: &Path) -> Result<VerifyReport> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(io::BufReader::new(file)));
+
+    let mut manifest: Option<Manifest> = None;
+    let mut found: BTreeMap<String, (u64, String)> = BTreeMap::new();
+    let invalid =
+        |e: io::Error| Error::Config(format!("{} is not a pctrl bundle: {}", path.display(), e));
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .map_err(invalid)?
+            .to_string_lossy()
+            .into_owned();
+        if name == MANIFEST {
+            let mut json = Vec::new();
+            entry.read_to_end(&mut json).map_err(invalid)?;
+            manifest = Some(serde_json::from_slice(&json).map_err(|e| {
+                Error::Config(format!("{}: unreadable manifest: {}", path.display(), e))
+            })?);
+        } else {
+            found.insert(name, checksum(&mut entry).map_err(invalid)?);
+        }
+    }
+    let manifest =
+        manifest.ok_or_else(|| Error::Config(format!("{} has no {}", path.display(), MANIFEST)))?;
+
+    let mut report = VerifyReport {
+        manifest,
+        mismatched: Vec::new(),
+        missing: Vec::new(),
+        unlisted: Vec::new(),
+    };
+    for listed in &report.manifest.files {
+        match found.remove(&listed.path) {
+            Some((size, sha256)) if size == listed.size && sha256 == listed.sha256 => {}
+            Some(_) => report.mismatched.push(listed.path.clone()),
+            None => report.missing.push(listed.path.clone()),
+        }
+    }
+    report.unlisted = found.into_keys().collect();
+    Ok(report)
+}
+
+/// Size and hex SHA-256 of everything `reader` yields
+fn checksum(mut reader: impl Read) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+/// An archive path that stays inside the bundle
+fn safe_path(path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let normal = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if !normal || path.is_empty() || path == MANIFEST {
+        return Err(Error::Config(format!("Invalid bundle path '{}'", path)));
+    }
+    Ok(relative.to_path_buf())
+}
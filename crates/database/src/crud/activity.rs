@@ -0,0 +1,138 @@
+//! Activity feed and command history
+//!
+//! The feed merges audit entries, script runs, monitor events and command
+//! history into one
+//! reverse-chronological stream with keyset pagination over
+//! (timestamp, kind, id), so pages stay stable while new rows are added.
+
+use super::{escape_like, now_timestamp};
+use crate::Database;
+use pctrl_core::{ActivityCursor, ActivityEntry, ActivityFilter, ActivityKind, Result};
+
+/// Union of all activity sources, normalized to (kind, id, ts, title, details).
+/// Timestamps are second-precision UTC so they compare as text across sources.
+const FEED_SQL: &str = r#"
+SELECT kind, id, ts, title, details FROM (
+    SELECT 'audit' AS kind, printf('%012d', id) AS id, created_at AS ts,
+           entity_type || ' ' || action || ': ' || summary AS title,
+           'by ' || actor || COALESCE(char(10) || details, '') AS details
+    FROM audit_log
+    UNION ALL
+    SELECT 'script', printf('%012d', r.id), strftime('%Y-%m-%dT%H:%M:%SZ', r.finished_at),
+           'ran ' || COALESCE(s.name, r.script_id) || ' (' || CASE
+               WHEN r.exit_code IS NULL THEN 'unknown'
+               WHEN r.exit_code = 0 THEN 'success'
+               ELSE 'exit ' || r.exit_code
+           END || ')',
+           r.output
+    FROM script_runs r LEFT JOIN scripts s ON s.id = r.script_id
+    UNION ALL
+    SELECT 'monitor', printf('%012d', id), created_at,
+           'server ' || server_name || CASE WHEN up THEN ' up' ELSE ' down' END,
+           error
+    FROM monitor_events
+    UNION ALL
+    SELECT 'command', printf('%012d', id), created_at,
+           'pctrl ' || command || CASE WHEN success THEN '' ELSE ' (failed)' END,
+           error
+    FROM command_history
+)
+"#;
+
+impl Database {
+    /// Record a CLI invocation (subcommand path only, never arguments)
+    pub async fn record_command(
+        &self,
+        command: &str,
+        success: bool,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO command_history (command, success, error, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(command)
+        .bind(success)
+        .bind(error)
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Load one page of the merged activity feed, newest first.
+    ///
+    /// Pass the cursor of the last entry of the previous page to continue.
+    /// Details are redacted before they leave the database layer.
+    pub async fn activity_feed(
+        &self,
+        cursor: Option<&ActivityCursor>,
+        filter: &ActivityFilter,
+        limit: i64,
+    ) -> Result<Vec<ActivityEntry>> {
+        let kinds: Vec<String> = ActivityKind::ALL
+            .iter()
+            .filter(|k| filter.includes(**k))
+            .map(|k| k.to_string())
+            .collect();
+        if kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut sql = String::from(FEED_SQL);
+        sql.push_str(" WHERE kind IN (");
+        sql.push_str(&vec!["?"; kinds.len()].join(", "));
+        sql.push(')');
+
+        let search = filter
+            .search
+            .as_ref()
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| format!("%{}%", escape_like(s.trim())));
+        if search.is_some() {
+            sql.push_str(" AND (title LIKE ? ESCAPE '\\' OR details LIKE ? ESCAPE '\\')");
+        }
+        if cursor.is_some() {
+            sql.push_str(" AND (ts, kind, id) < (?, ?, ?)");
+        }
+        sql.push_str(" ORDER BY ts DESC, kind DESC, id DESC LIMIT ?");
+
+        let mut query = sqlx::query_as::<_, ActivityRow>(&sql);
+        for kind in &kinds {
+            query = query.bind(kind);
+        }
+        if let Some(pattern) = &search {
+            query = query.bind(pattern).bind(pattern);
+        }
+        if let Some(c) = cursor {
+            query = query
+                .bind(&c.timestamp)
+                .bind(c.kind.to_string())
+                .bind(&c.id);
+        }
+        let rows = query
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().filter_map(Self::row_to_activity).collect())
+    }
+
+    /// Helper to convert a row tuple to ActivityEntry
+    fn row_to_activity(row: ActivityRow) -> Option<ActivityEntry> {
+        let (kind, id, timestamp, title, details) = row;
+
+        Some(ActivityEntry {
+            kind: kind.parse().ok()?,
+            id,
+            timestamp,
+            title: pctrl_core::redact_secrets(&title),
+            details: details.map(|d| pctrl_core::redact_secrets(&d)),
+        })
+    }
+}
+
+/// Type alias for activity row tuple
+type ActivityRow = (String, String, String, String, Option<String>);
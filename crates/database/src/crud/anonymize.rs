@@ -0,0 +1,153 @@
+//! Anonymized copies of the database (`pctrl debug anonymize`)
+
+use crate::Database;
+use pctrl_core::anonymize::{rule, Faker, Rule, TableReport};
+use pctrl_core::{Error, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{Connection, SqliteConnection};
+use std::path::Path;
+
+impl Database {
+    /// Copy the database to `path` and anonymize the copy: hosts, names,
+    /// paths and non-UUID IDs replaced with consistent fakes, secrets
+    /// blanked, notes cut short and free text scrubbed. Row counts,
+    /// statuses, links and timestamps stay. The original is not touched;
+    /// a failed run leaves no file behind.
+    pub async fn anonymize_to(&self, path: &Path) -> Result<Vec<TableReport>> {
+        self.backup_to(path).await?;
+        let result = anonymize_file(path).await;
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+}
+
+fn db_error(e: sqlx::Error) -> Error {
+    Error::Database(e.to_string())
+}
+
+async fn anonymize_file(path: &Path) -> Result<Vec<TableReport>> {
+    // Faked IDs are rewritten one column at a time, so references point
+    // nowhere in between
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .foreign_keys(false)
+        .journal_mode(SqliteJournalMode::Delete);
+    let mut conn = SqliteConnection::connect_with(&options)
+        .await
+        .map_err(db_error)?;
+    // Overwrite replaced values instead of leaving them in free pages
+    sqlx::query("PRAGMA secure_delete = ON")
+        .execute(&mut conn)
+        .await
+        .map_err(db_error)?;
+
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(db_error)?;
+
+    // (report index, quoted table, quoted column, rule)
+    let mut plan: Vec<(usize, String, String, Rule)> = Vec::new();
+    let mut reports: Vec<TableReport> = Vec::new();
+    for (table,) in tables {
+        let columns: Vec<(String, String)> =
+            sqlx::query_as("SELECT name, type FROM pragma_table_info(?) ORDER BY cid")
+                .bind(&table)
+                .fetch_all(&mut conn)
+                .await
+                .map_err(db_error)?;
+        for (column, declared_type) in columns {
+            let rule = rule(&table, &column, &declared_type);
+            if rule != Rule::Keep {
+                plan.push((reports.len(), quote(&table), quote(&column), rule));
+            }
+        }
+        let (rows,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", quote(&table)))
+            .fetch_one(&mut conn)
+            .await
+            .map_err(db_error)?;
+        reports.push(TableReport {
+            table,
+            rows: rows as u64,
+            ..Default::default()
+        });
+    }
+
+    let mut faker = Faker::new();
+    let mut tx = conn.begin().await.map_err(db_error)?;
+    // Free text last: it's scrubbed of everything the other columns faked
+    for free_text in [false, true] {
+        for (report, table, column, rule) in &plan {
+            if rule.is_free_text() != free_text {
+                continue;
+            }
+            let changed = match rule {
+                Rule::Secret => blank(&mut tx, table, column).await?,
+                _ => rewrite(&mut tx, &mut faker, table, column, *rule).await?,
+            };
+            if changed > 0 {
+                *reports[*report].rewritten.entry(rule.label()).or_default() += changed;
+            }
+        }
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    // Rebuild the file so no page holds an original value
+    sqlx::query("VACUUM")
+        .execute(&mut conn)
+        .await
+        .map_err(db_error)?;
+    conn.close().await.map_err(db_error)?;
+    Ok(reports)
+}
+
+/// Empty every non-empty text or blob value of a column
+async fn blank(conn: &mut SqliteConnection, table: &str, column: &str) -> Result<u64> {
+    let result = sqlx::query(&format!(
+        "UPDATE {table} SET {column} = CASE typeof({column}) WHEN 'blob' THEN x'' ELSE '' END \
+         WHERE typeof({column}) IN ('text', 'blob') AND length({column}) > 0"
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    Ok(result.rows_affected())
+}
+
+/// Replace each text value of a column with what `rule` makes of it
+async fn rewrite(
+    conn: &mut SqliteConnection,
+    faker: &mut Faker,
+    table: &str,
+    column: &str,
+    rule: Rule,
+) -> Result<u64> {
+    let values: Vec<(i64, String)> = sqlx::query_as(&format!(
+        "SELECT rowid, {column} FROM {table} WHERE typeof({column}) = 'text' ORDER BY rowid"
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut changed = 0;
+    for (rowid, value) in values {
+        let Some(fake) = faker.apply(rule, &value).filter(|fake| *fake != value) else {
+            continue;
+        };
+        sqlx::query(&format!("UPDATE {table} SET {column} = ? WHERE rowid = ?"))
+            .bind(fake)
+            .bind(rowid)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -0,0 +1,189 @@
+//! Audit log operations
+//!
+//! Entries are written from the entity save/remove methods, so every
+//! mutation is recorded regardless of which frontend made it.
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::diff::{self, FieldChange};
+use pctrl_core::history::EntityHistory;
+use pctrl_core::search::Entity;
+use pctrl_core::{AuditAction, AuditEntry, EntityType, Result};
+use std::collections::HashMap;
+
+impl Database {
+    /// Record an entity mutation
+    pub(crate) async fn record_audit(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        action: AuditAction,
+        summary: &str,
+    ) -> Result<()> {
+        self.record_audit_with_changes(entity_type, entity_id, action, summary, &[])
+            .await
+    }
+
+    /// Record an entity mutation with its field-level changes as details
+    pub(crate) async fn record_audit_with_changes(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        action: AuditAction,
+        summary: &str,
+        changes: &[FieldChange],
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (entity_type, entity_id, action, summary, actor, details, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .bind(action.to_string())
+        .bind(summary)
+        .bind(pctrl_core::current_holder())
+        .bind(diff::to_details(changes))
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        self.fire_entity_hooks(entity_type, entity_id, action, summary)
+            .await;
+        Ok(())
+    }
+
+    /// List audit entries, newest first, optionally for a single entity
+    pub async fn list_audit_entries(
+        &self,
+        entity: Option<(EntityType, &str)>,
+        limit: i64,
+    ) -> Result<Vec<AuditEntry>> {
+        let rows: Vec<AuditRow> = match entity {
+            Some((entity_type, entity_id)) => sqlx::query_as(
+                "SELECT id, entity_type, entity_id, action, summary, actor, details, created_at FROM audit_log
+                 WHERE entity_type = ? AND entity_id = ? ORDER BY id DESC LIMIT ?",
+            )
+            .bind(entity_type.to_string())
+            .bind(entity_id)
+            .bind(limit),
+            None => sqlx::query_as(
+                "SELECT id, entity_type, entity_id, action, summary, actor, details, created_at FROM audit_log
+                 ORDER BY id DESC LIMIT ?",
+            )
+            .bind(limit),
+        }
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().filter_map(Self::row_to_audit).collect())
+    }
+
+    /// When each entity last changed, by type and ID: the time of its
+    /// newest audit entry
+    pub async fn last_changed(&self) -> Result<HashMap<(EntityType, String), String>> {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT entity_type, entity_id, MAX(created_at) FROM audit_log
+             GROUP BY entity_type, entity_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(entity_type, id, at)| Some(((entity_type.parse().ok()?, id), at)))
+            .collect())
+    }
+
+    /// An entity's row and all its audit entries, by ID or name. Trashed
+    /// servers are found too; entities removed for good by the ID or name
+    /// in their entries.
+    pub async fn entity_history(
+        &self,
+        entity_type: EntityType,
+        id_or_name: &str,
+    ) -> Result<Option<EntityHistory>> {
+        let mut found = self.find_entity(entity_type, id_or_name).await?;
+        if found.is_none() && entity_type == EntityType::Server {
+            found = self
+                .list_trashed_servers()
+                .await?
+                .into_iter()
+                .find(|s| s.id == id_or_name || s.name.eq_ignore_ascii_case(id_or_name))
+                .map(Entity::Server);
+        }
+
+        let (id, name, current) = match found {
+            Some(entity) => {
+                let (id, name) = match &entity {
+                    Entity::Project(p) => (p.id.clone(), p.name.clone()),
+                    Entity::Server(s) => (s.id.clone(), s.name.clone()),
+                    Entity::Domain(d) => (d.id.clone(), d.domain.clone()),
+                    Entity::Database(d) => (d.id.clone(), d.name.clone()),
+                    Entity::Script(s) => (s.id.clone(), s.name.clone()),
+                    Entity::Credential(c) => (c.id.clone(), c.name.clone()),
+                };
+                let value = serde_json::to_value(&entity)
+                    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+                (id, name, value.get("data").cloned())
+            }
+            None => {
+                let row: Option<(String, String)> = sqlx::query_as(
+                    "SELECT entity_id, summary FROM audit_log
+                     WHERE entity_type = ? AND (entity_id = ? OR summary = ? COLLATE NOCASE)
+                     ORDER BY id DESC LIMIT 1",
+                )
+                .bind(entity_type.to_string())
+                .bind(id_or_name)
+                .bind(id_or_name)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+                let Some((id, name)) = row else {
+                    return Ok(None);
+                };
+                (id, name, None)
+            }
+        };
+
+        let entries = self
+            .list_audit_entries(Some((entity_type, &id)), i64::MAX)
+            .await?;
+        Ok(Some(EntityHistory {
+            id,
+            name,
+            current,
+            entries,
+        }))
+    }
+
+    /// Helper to convert a row tuple to AuditEntry
+    fn row_to_audit(row: AuditRow) -> Option<AuditEntry> {
+        let (id, entity_type, entity_id, action, summary, actor, details, created_at) = row;
+
+        Some(AuditEntry {
+            id,
+            entity_type: entity_type.parse().ok()?,
+            entity_id,
+            action: action.parse().ok()?,
+            summary,
+            actor,
+            details,
+            created_at,
+        })
+    }
+}
+
+/// Type alias for audit row tuple
+type AuditRow = (
+    i64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+);
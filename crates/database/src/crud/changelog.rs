@@ -0,0 +1,75 @@
+//! Changelogs generated from a Git repository's history
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::{ChangelogEntry, Result};
+
+/// changelog row
+type ChangelogRow = (i64, String, String, String, String);
+
+impl Database {
+    /// Store the changelog of a repository's version, replacing the one
+    /// generated before
+    pub async fn save_changelog(
+        &self,
+        repo_id: &str,
+        version: &str,
+        content: &str,
+    ) -> Result<ChangelogEntry> {
+        let created_at = now_timestamp();
+        sqlx::query(
+            "INSERT INTO changelog (repo_id, version, content, created_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT (repo_id, version)
+             DO UPDATE SET content = excluded.content, created_at = excluded.created_at",
+        )
+        .bind(repo_id)
+        .bind(version)
+        .bind(content)
+        .bind(&created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let (id,): (i64,) =
+            sqlx::query_as("SELECT id FROM changelog WHERE repo_id = ? AND version = ?")
+                .bind(repo_id)
+                .bind(version)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(ChangelogEntry {
+            id,
+            repo_id: repo_id.to_string(),
+            version: version.to_string(),
+            content: content.to_string(),
+            created_at,
+        })
+    }
+
+    /// A repository's changelogs, newest version first; regenerating one
+    /// keeps its place
+    pub async fn list_changelog(&self, repo_id: &str) -> Result<Vec<ChangelogEntry>> {
+        let rows: Vec<ChangelogRow> = sqlx::query_as(
+            "SELECT id, repo_id, version, content, CAST(created_at AS TEXT) FROM changelog
+             WHERE repo_id = ? ORDER BY id DESC",
+        )
+        .bind(repo_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(id, repo_id, version, content, created_at)| ChangelogEntry {
+                    id,
+                    repo_id,
+                    version,
+                    content,
+                    created_at,
+                },
+            )
+            .collect())
+    }
+}
@@ -0,0 +1,82 @@
+//! Compose stacks per server (`pctrl compose`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::compose::{self, ComposeProject, ComposeState};
+use pctrl_core::Result;
+
+/// compose_projects row: server_id, name, config_path, services, running, state
+type ComposeRow = (String, String, Option<String>, i64, i64, String);
+
+const COMPOSE_COLUMNS: &str = "server_id, name, config_path, services, running, state";
+
+impl Database {
+    /// Replace the stacks recorded for `server_id` with `stacks`
+    pub async fn replace_compose_projects(
+        &self,
+        server_id: &str,
+        stacks: &[ComposeProject],
+    ) -> Result<()> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let updated_at = now_timestamp();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+
+        sqlx::query("DELETE FROM compose_projects WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        for stack in stacks {
+            sqlx::query(
+                "INSERT INTO compose_projects (server_id, name, config_path, services, running, state, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(server_id)
+            .bind(&stack.name)
+            .bind(&stack.config_path)
+            .bind(stack.services as i64)
+            .bind(stack.running as i64)
+            .bind(stack.state.to_string())
+            .bind(&updated_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Recorded stacks, of one server or all, by server and name with the
+    /// standalone group last
+    pub async fn list_compose_projects(
+        &self,
+        server_id: Option<&str>,
+    ) -> Result<Vec<ComposeProject>> {
+        let rows: Vec<ComposeRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM compose_projects WHERE ? IS NULL OR server_id = ?
+             ORDER BY server_id, name = ?, name",
+            COMPOSE_COLUMNS
+        ))
+        .bind(server_id)
+        .bind(server_id)
+        .bind(compose::STANDALONE)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_compose).collect())
+    }
+}
+
+fn row_to_compose(row: ComposeRow) -> ComposeProject {
+    let (server_id, name, config_path, services, running, state) = row;
+    ComposeProject {
+        server_id,
+        name,
+        config_path,
+        services: services as u32,
+        running: running as u32,
+        state: state.parse().unwrap_or(ComposeState::Gone),
+    }
+}
@@ -25,9 +25,9 @@ impl Database {
 
         // Load all entity types
         config.ssh_connections = self.load_ssh_connections().await?;
-        config.docker_hosts = self.load_docker_hosts().await?;
-        config.coolify_instances = self.load_coolify_instances().await?;
-        config.git_repos = self.load_git_repos().await?;
+        config.docker_hosts = self.list_docker_hosts().await?;
+        config.coolify_instances = self.list_coolify_instances().await?;
+        config.git_repos = self.list_git_repos().await?;
 
         Ok(config)
     }
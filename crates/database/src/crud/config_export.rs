@@ -0,0 +1,42 @@
+//! The whole setup as a [`ConfigDocument`] (`pctrl export --file`)
+
+use crate::Database;
+use pctrl_core::config_export::{ConfigDocument, ExportedCredential, FORMAT_VERSION};
+use pctrl_core::snapshot::without_secrets;
+use pctrl_core::Result;
+
+impl Database {
+    /// Every project, server, domain, database, script, credential and
+    /// link; credential data and database secrets only with
+    /// `include_secrets`
+    pub async fn config_document(&self, include_secrets: bool) -> Result<ConfigDocument> {
+        let mut databases = self.list_database_credentials().await?;
+        if !include_secrets {
+            databases = databases.into_iter().map(without_secrets).collect();
+        }
+        let mut document = ConfigDocument {
+            format: FORMAT_VERSION,
+            pctrl_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            secrets: include_secrets,
+            projects: self.list_projects().await?,
+            servers: self.list_servers().await?,
+            domains: self.list_domains().await?,
+            databases,
+            scripts: self.list_scripts().await?,
+            credentials: self
+                .list_credentials()
+                .await?
+                .into_iter()
+                .map(|c| ExportedCredential::new(c, include_secrets))
+                .collect(),
+            links: self.list_all_project_resources().await?,
+        };
+        if include_secrets {
+            document
+                .escape_secrets()
+                .map_err(pctrl_core::Error::Config)?;
+        }
+        Ok(document)
+    }
+}
@@ -0,0 +1,153 @@
+//! Containers recorded by `docker sync` and `server discover`
+
+use crate::Database;
+use pctrl_core::{Container, Result};
+use sqlx::sqlite::SqliteConnection;
+
+/// containers row: id, name, image, server_id, project_id, status, ports,
+/// env_vars, labels, memory_limit_mb
+type ContainerRow = (
+    String,
+    String,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+);
+
+const CONTAINER_COLUMNS: &str =
+    "id, name, image, server_id, project_id, status, ports, env_vars, labels, memory_limit_mb";
+
+impl Database {
+    /// Replace the containers recorded for `server_id` with `containers`.
+    /// Rows that are still there keep their project; the others are removed.
+    pub async fn replace_server_containers(
+        &self,
+        server_id: &str,
+        containers: &[Container],
+    ) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let existing: Vec<(String,)> =
+            sqlx::query_as("SELECT id FROM containers WHERE server_id = ?")
+                .bind(server_id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id,) in existing {
+            if !containers.iter().any(|c| c.id == id) {
+                sqlx::query("DELETE FROM containers WHERE id = ?")
+                    .bind(&id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            }
+        }
+
+        for container in containers {
+            upsert(&mut tx, server_id, container).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Recorded containers of all servers, by server and name
+    pub async fn list_containers(&self) -> Result<Vec<Container>> {
+        let rows: Vec<ContainerRow> = sqlx::query_as(&format!(
+            "SELECT {CONTAINER_COLUMNS} FROM containers ORDER BY server_id, name"
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_container).collect())
+    }
+
+    /// Record one container, keeping its project if it was recorded before
+    pub async fn save_container(&self, container: &Container) -> Result<()> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        upsert(&mut conn, &container.server_id, container).await
+    }
+
+    /// Forget a recorded container
+    pub async fn remove_container(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM containers WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Recorded containers of one server, by name
+    pub async fn list_containers_for_server(&self, server_id: &str) -> Result<Vec<Container>> {
+        let rows: Vec<ContainerRow> = sqlx::query_as(&format!(
+            "SELECT {CONTAINER_COLUMNS} FROM containers WHERE server_id = ? ORDER BY name"
+        ))
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_container).collect())
+    }
+}
+
+/// Insert or update a container row; the project link is left alone
+async fn upsert(conn: &mut SqliteConnection, server_id: &str, container: &Container) -> Result<()> {
+    let ports = serde_json::to_string(&container.ports).unwrap_or_default();
+    sqlx::query(
+        "INSERT INTO containers (id, name, image, server_id, status, ports, labels, memory_limit_mb, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, image = excluded.image, server_id = excluded.server_id,
+            status = excluded.status, ports = excluded.ports, labels = excluded.labels,
+            memory_limit_mb = excluded.memory_limit_mb, updated_at = excluded.updated_at",
+    )
+    .bind(&container.id)
+    .bind(&container.name)
+    .bind(&container.image)
+    .bind(server_id)
+    .bind(container.status.to_string())
+    .bind(ports)
+    .bind(&container.labels)
+    .bind(container.memory_limit_mb.map(|mb| mb as i64))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    Ok(())
+}
+
+fn row_to_container(
+    (id, name, image, server_id, project_id, status, ports, env_vars, labels, memory_limit_mb): ContainerRow,
+) -> Container {
+    Container {
+        id,
+        name,
+        image,
+        server_id,
+        project_id,
+        status: status.and_then(|s| s.parse().ok()).unwrap_or_default(),
+        ports: ports
+            .and_then(|p| serde_json::from_str(&p).ok())
+            .unwrap_or_default(),
+        env_vars,
+        labels,
+        memory_limit_mb: memory_limit_mb.map(|mb| mb as u64),
+    }
+}
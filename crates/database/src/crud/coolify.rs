@@ -1,23 +1,25 @@
 //! Coolify Instance CRUD operations
 
 use crate::Database;
-use pctrl_core::Result;
-use sqlx::Row;
+use pctrl_core::{CoolifyInstance, Result};
+
+/// coolify_instances row; `sealed` says whether the API key is encrypted
+type CoolifyRow = (String, String, String, String, bool);
 
 impl Database {
-    /// Add or update a Coolify instance
-    pub async fn save_coolify_instance(
-        &self,
-        instance: &pctrl_core::CoolifyInstance,
-    ) -> Result<()> {
+    /// Add or update a Coolify instance; the API key is encrypted when the
+    /// database has a key
+    pub async fn save_coolify_instance(&self, instance: &CoolifyInstance) -> Result<()> {
+        let api_key = self.seal_secret(Some(&instance.api_key))?;
         sqlx::query(
-            "INSERT OR REPLACE INTO coolify_instances (id, name, url, api_key)
-             VALUES (?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO coolify_instances (id, name, url, api_key, sealed)
+             VALUES (?, ?, ?, ?, ?)",
         )
         .bind(&instance.id)
         .bind(&instance.name)
         .bind(&instance.url)
-        .bind(&instance.api_key)
+        .bind(api_key)
+        .bind(self.is_encrypted())
         .execute(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
@@ -48,23 +50,67 @@ impl Database {
         Ok(row.map(|(count,)| count > 0).unwrap_or(false))
     }
 
-    /// Load all Coolify instances
-    pub(crate) async fn load_coolify_instances(&self) -> Result<Vec<pctrl_core::CoolifyInstance>> {
-        let rows = sqlx::query("SELECT id, name, url, api_key FROM coolify_instances")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    /// Get a Coolify instance by ID or name (case-insensitive)
+    pub async fn get_coolify_instance(&self, id_or_name: &str) -> Result<Option<CoolifyInstance>> {
+        let row: Option<CoolifyRow> = sqlx::query_as(
+            "SELECT id, name, url, api_key, sealed FROM coolify_instances WHERE id = ? OR LOWER(name) = LOWER(?)
+             ORDER BY id = ? DESC LIMIT 1",
+        )
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        row.map(|row| self.row_to_coolify_instance(row)).transpose()
+    }
+
+    /// All Coolify instances, by name
+    pub async fn list_coolify_instances(&self) -> Result<Vec<CoolifyInstance>> {
+        let rows: Vec<CoolifyRow> = sqlx::query_as(
+            "SELECT id, name, url, api_key, sealed FROM coolify_instances ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        let instances = rows
-            .into_iter()
-            .map(|row| pctrl_core::CoolifyInstance {
-                id: row.get("id"),
-                name: row.get("name"),
-                url: row.get("url"),
-                api_key: row.get("api_key"),
-            })
-            .collect();
+        rows.into_iter()
+            .map(|row| self.row_to_coolify_instance(row))
+            .collect()
+    }
+
+    /// Encrypt API keys still stored in plain text. Only called once the
+    /// password has been checked against what is already encrypted; keys
+    /// already sealed are skipped.
+    pub(crate) async fn seal_plaintext_coolify_keys(&self) -> Result<()> {
+        if !self.is_encrypted() {
+            return Ok(());
+        }
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, api_key FROM coolify_instances WHERE NOT sealed")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id, api_key) in rows {
+            sqlx::query("UPDATE coolify_instances SET api_key = ?, sealed = 1 WHERE id = ?")
+                .bind(self.seal_secret(Some(&api_key))?)
+                .bind(&id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        Ok(())
+    }
 
-        Ok(instances)
+    /// Decrypt a row and convert it to a CoolifyInstance
+    fn row_to_coolify_instance(&self, row: CoolifyRow) -> Result<CoolifyInstance> {
+        let (id, name, url, api_key, sealed) = row;
+        Ok(CoolifyInstance {
+            id,
+            name,
+            url,
+            api_key: self.open_secret(Some(api_key), sealed)?.unwrap_or_default(),
+        })
     }
 }
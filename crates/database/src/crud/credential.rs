@@ -1,11 +1,31 @@
 //! CRUD operations for credentials
 
+use super::names;
 use crate::Database;
-use pctrl_core::{Credential, CredentialData, CredentialType, Result};
+use pctrl_core::diff::diff;
+use pctrl_core::{AuditAction, Credential, CredentialData, CredentialType, EntityType, Result};
+
+pub(super) type CredentialRow = (String, String, String, Vec<u8>, Option<String>);
 
 impl Database {
     /// Save a credential (insert or update)
     pub async fn save_credential(&self, credential: &Credential) -> Result<()> {
+        self.insert_credential(credential, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_credential(&self, credential: &Credential, creating: bool) -> Result<()> {
+        self.check_lock(EntityType::Credential, &credential.id)
+            .await?;
+        self.check_name(
+            names::CREDENTIALS,
+            &credential.id,
+            &credential.name,
+            creating,
+        )
+        .await?;
+        let previous = self.get_credential(&credential.id).await?;
+
         // Serialize the credential data to JSON (will be encrypted)
         let data_json = serde_json::to_string(&credential.data)
             .map_err(|e| pctrl_core::Error::Database(format!("Failed to serialize data: {}", e)))?;
@@ -31,47 +51,57 @@ impl Database {
         .bind(&credential.notes)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&credential.name))?;
+        self.assign_short_ref(EntityType::Credential, &credential.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, credential))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Credential,
+            &credential.id,
+            AuditAction::for_save(previous.is_some()),
+            &credential.name,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add a credential; fails with [`pctrl_core::Error::Conflict`] when its ID
+    /// or its name (ignoring case) is taken
+    pub async fn create_credential(&self, credential: &Credential) -> Result<()> {
+        self.insert_credential(credential, true).await
+    }
+
+    /// Change an existing credential; fails with [`pctrl_core::Error::Conflict`]
+    /// when another credential has its name (ignoring case)
+    pub async fn update_credential(&self, credential: &Credential) -> Result<()> {
+        self.check_exists(names::CREDENTIALS, &credential.id)
+            .await?;
+        self.save_credential(credential).await
+    }
+
     /// List all credentials
     pub async fn list_credentials(&self) -> Result<Vec<Credential>> {
-        let rows: Vec<(String, String, String, Vec<u8>, Option<String>)> = sqlx::query_as(
+        let rows: Vec<CredentialRow> = sqlx::query_as(
             "SELECT id, name, credential_type, data, notes FROM credentials ORDER BY name",
         )
         .fetch_all(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        let mut credentials = Vec::new();
-        for (id, name, cred_type, encrypted_data, notes) in rows {
-            // Decrypt the data
-            let decrypted = self.decrypt(&encrypted_data)?;
-            let data_json = String::from_utf8(decrypted)
-                .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))?;
-
-            let data: CredentialData = serde_json::from_str(&data_json)
-                .map_err(|e| pctrl_core::Error::Database(format!("Failed to parse data: {}", e)))?;
-
-            let credential_type: CredentialType = cred_type.parse().unwrap_or_default();
-
-            credentials.push(Credential {
-                id,
-                name,
-                credential_type,
-                data,
-                notes,
-            });
-        }
-
-        Ok(credentials)
+        rows.into_iter()
+            .map(|row| self.row_to_credential(row))
+            .collect()
     }
 
     /// Get a credential by ID
     pub async fn get_credential(&self, id: &str) -> Result<Option<Credential>> {
-        let row: Option<(String, String, String, Vec<u8>, Option<String>)> = sqlx::query_as(
+        let row: Option<CredentialRow> = sqlx::query_as(
             "SELECT id, name, credential_type, data, notes FROM credentials WHERE id = ?",
         )
         .bind(id)
@@ -79,33 +109,12 @@ impl Database {
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        match row {
-            Some((id, name, cred_type, encrypted_data, notes)) => {
-                let decrypted = self.decrypt(&encrypted_data)?;
-                let data_json = String::from_utf8(decrypted)
-                    .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))?;
-
-                let data: CredentialData = serde_json::from_str(&data_json).map_err(|e| {
-                    pctrl_core::Error::Database(format!("Failed to parse data: {}", e))
-                })?;
-
-                let credential_type: CredentialType = cred_type.parse().unwrap_or_default();
-
-                Ok(Some(Credential {
-                    id,
-                    name,
-                    credential_type,
-                    data,
-                    notes,
-                }))
-            }
-            None => Ok(None),
-        }
+        row.map(|row| self.row_to_credential(row)).transpose()
     }
 
     /// Get a credential by name
     pub async fn get_credential_by_name(&self, name: &str) -> Result<Option<Credential>> {
-        let row: Option<(String, String, String, Vec<u8>, Option<String>)> = sqlx::query_as(
+        let row: Option<CredentialRow> = sqlx::query_as(
             "SELECT id, name, credential_type, data, notes FROM credentials WHERE name = ?",
         )
         .bind(name)
@@ -113,49 +122,100 @@ impl Database {
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        match row {
-            Some((id, name, cred_type, encrypted_data, notes)) => {
-                let decrypted = self.decrypt(&encrypted_data)?;
-                let data_json = String::from_utf8(decrypted)
-                    .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))?;
-
-                let data: CredentialData = serde_json::from_str(&data_json).map_err(|e| {
-                    pctrl_core::Error::Database(format!("Failed to parse data: {}", e))
-                })?;
-
-                let credential_type: CredentialType = cred_type.parse().unwrap_or_default();
-
-                Ok(Some(Credential {
-                    id,
-                    name,
-                    credential_type,
-                    data,
-                    notes,
-                }))
-            }
-            None => Ok(None),
-        }
+        row.map(|row| self.row_to_credential(row)).transpose()
     }
 
     /// Remove a credential by ID
     pub async fn remove_credential(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Credential, id).await?;
+        let previous = self.get_credential(id).await?;
+
         let result = sqlx::query("DELETE FROM credentials WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let removed = result.rows_affected() > 0;
+        if removed {
+            let summary = previous.map(|e| e.name).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Credential, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
     }
 
     /// Remove a credential by name
     pub async fn remove_credential_by_name(&self, name: &str) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM credentials WHERE name = ?")
-            .bind(name)
+        match self.get_credential_by_name(name).await? {
+            Some(credential) => self.remove_credential(&credential.id).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Store the private key of an SSH credential with it, encrypted like
+    /// its other secrets; `None` goes back to the key file
+    pub async fn set_key_material(&self, id: &str, key: Option<&str>) -> Result<()> {
+        self.check_lock(EntityType::Credential, id).await?;
+        self.check_exists(names::CREDENTIALS, id).await?;
+        let encrypted = key.map(|key| self.encrypt(key.as_bytes())).transpose()?;
+
+        sqlx::query("UPDATE credentials SET key_material = ? WHERE id = ?")
+            .bind(&encrypted)
+            .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let name = self
+            .get_credential(id)
+            .await?
+            .map_or_else(|| id.to_string(), |c| c.name);
+        let summary = match key {
+            Some(_) => format!("{}: key stored inline", name),
+            None => format!("{}: inline key removed", name),
+        };
+        self.record_audit(EntityType::Credential, id, AuditAction::Updated, &summary)
+            .await?;
+        Ok(())
+    }
+
+    /// The private key stored inline with a credential, if any
+    pub async fn get_key_material(&self, id: &str) -> Result<Option<String>> {
+        let row: Option<(Option<Vec<u8>>,)> =
+            sqlx::query_as("SELECT key_material FROM credentials WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        row.and_then(|(key,)| key)
+            .map(|key| {
+                String::from_utf8(self.decrypt(&key)?)
+                    .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))
+            })
+            .transpose()
+    }
+
+    /// Decrypt and parse a credential row
+    pub(super) fn row_to_credential(&self, row: CredentialRow) -> Result<Credential> {
+        let (id, name, cred_type, encrypted_data, notes) = row;
+        let decrypted = self.decrypt(&encrypted_data)?;
+        let data_json = String::from_utf8(decrypted)
+            .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))?;
+
+        let data: CredentialData = serde_json::from_str(&data_json)
+            .map_err(|e| pctrl_core::Error::Database(format!("Failed to parse data: {}", e)))?;
+
+        let credential_type: CredentialType = cred_type.parse().unwrap_or_default();
+
+        Ok(Credential {
+            id,
+            name,
+            credential_type,
+            data,
+            notes,
+        })
     }
 }
@@ -1,7 +1,9 @@
 //! Database Credentials CRUD operations
 
+use super::{names, placeholders};
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::diff::diff;
+use pctrl_core::{AuditAction, EntityType, Result};
 
 impl Database {
     /// Save database credentials
@@ -9,9 +11,25 @@ impl Database {
         &self,
         db_creds: &pctrl_core::DatabaseCredentials,
     ) -> Result<()> {
+        self.insert_database_credentials(db_creds, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_database_credentials(
+        &self,
+        db_creds: &pctrl_core::DatabaseCredentials,
+        creating: bool,
+    ) -> Result<()> {
+        self.check_lock(EntityType::Database, &db_creds.id).await?;
+        self.check_name(names::DATABASES, &db_creds.id, &db_creds.name, creating)
+            .await?;
+        let previous = self.get_database_credentials(&db_creds.id).await?;
+        let password = self.seal_secret(db_creds.password.as_deref())?;
+        let connection_string = self.seal_secret(db_creds.connection_string.as_deref())?;
+
         sqlx::query(
-            "INSERT OR REPLACE INTO databases (id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO databases (id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed, short_ref)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT short_ref FROM databases WHERE id = ?))",
         )
         .bind(&db_creds.id)
         .bind(&db_creds.name)
@@ -20,45 +38,69 @@ impl Database {
         .bind(db_creds.port.map(|p| p as i64))
         .bind(&db_creds.database_name)
         .bind(&db_creds.username)
-        .bind(&db_creds.password)
-        .bind(&db_creds.connection_string)
+        .bind(password)
+        .bind(connection_string)
         .bind(&db_creds.server_id)
         .bind(&db_creds.container_id)
         .bind(&db_creds.notes)
+        .bind(self.is_encrypted())
+        .bind(&db_creds.id)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&db_creds.name))?;
+        self.assign_short_ref(EntityType::Database, &db_creds.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, db_creds))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Database,
+            &db_creds.id,
+            AuditAction::for_save(previous.is_some()),
+            &db_creds.name,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add database credentials; fails with [`pctrl_core::Error::Conflict`]
+    /// when their ID or their name (ignoring case) is taken
+    pub async fn create_database_credentials(
+        &self,
+        db_creds: &pctrl_core::DatabaseCredentials,
+    ) -> Result<()> {
+        self.insert_database_credentials(db_creds, true).await
+    }
+
+    /// Change an existing database; fails with [`pctrl_core::Error::Conflict`]
+    /// when another database has its name (ignoring case)
+    pub async fn update_database_credentials(
+        &self,
+        db_creds: &pctrl_core::DatabaseCredentials,
+    ) -> Result<()> {
+        self.check_exists(names::DATABASES, &db_creds.id).await?;
+        self.save_database_credentials(db_creds).await
+    }
+
     /// Get database credentials by ID
     pub async fn get_database_credentials(
         &self,
         id: &str,
     ) -> Result<Option<pctrl_core::DatabaseCredentials>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<i64>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes FROM databases WHERE id = ?",
+        let row: Option<DatabaseRow> = sqlx::query_as(
+            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed FROM databases WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(row.map(Self::row_to_database_credentials))
+        row.map(|row| self.row_to_database_credentials(row))
+            .transpose()
     }
 
     /// Get database credentials by name (case-insensitive)
@@ -66,86 +108,129 @@ impl Database {
         &self,
         name: &str,
     ) -> Result<Option<pctrl_core::DatabaseCredentials>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<i64>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes FROM databases WHERE LOWER(name) = LOWER(?)",
+        let row: Option<DatabaseRow> = sqlx::query_as(
+            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed FROM databases WHERE LOWER(name) = LOWER(?)",
         )
         .bind(name)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(row.map(Self::row_to_database_credentials))
+        row.map(|row| self.row_to_database_credentials(row))
+            .transpose()
     }
 
     /// List all database credentials
     pub async fn list_database_credentials(&self) -> Result<Vec<pctrl_core::DatabaseCredentials>> {
-        let rows: Vec<(
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<i64>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes FROM databases ORDER BY name",
+        let rows: Vec<DatabaseRow> = sqlx::query_as(
+            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed FROM databases ORDER BY name",
         )
         .fetch_all(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(Self::row_to_database_credentials)
-            .collect())
+        rows.into_iter()
+            .map(|row| self.row_to_database_credentials(row))
+            .collect()
+    }
+
+    /// Database credentials by ID or name (case-insensitive), in one query;
+    /// links store either. Unknown references are left out.
+    pub async fn get_database_credentials_by_ids(
+        &self,
+        refs: &[&str],
+    ) -> Result<Vec<pctrl_core::DatabaseCredentials>> {
+        if refs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list = placeholders(refs.len());
+        let sql = format!(
+            "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed FROM databases
+             WHERE id IN ({list}) OR LOWER(name) IN ({list}) ORDER BY name"
+        );
+        let mut query = sqlx::query_as::<_, DatabaseRow>(&sql);
+        for r in refs {
+            query = query.bind(*r);
+        }
+        for r in refs {
+            query = query.bind(r.to_ascii_lowercase());
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|row| self.row_to_database_credentials(row))
+            .collect()
     }
 
     /// Remove database credentials by ID
     pub async fn remove_database_credentials(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Database, id).await?;
+        let previous = self.get_database_credentials(id).await?;
+
         let result = sqlx::query("DELETE FROM databases WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let removed = result.rows_affected() > 0;
+        if removed {
+            let summary = previous.map(|e| e.name).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Database, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
     }
 
-    /// Helper to convert a row tuple to DatabaseCredentials
-    fn row_to_database_credentials(
-        row: (
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<i64>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        ),
-    ) -> pctrl_core::DatabaseCredentials {
+    /// Encrypt passwords and connection strings still stored in plain text.
+    /// Only called once the password has been checked against what is
+    /// already encrypted; rows already sealed are skipped.
+    pub(crate) async fn seal_plaintext_database_secrets(&self) -> Result<()> {
+        if self.cipher.is_none() {
+            return Ok(());
+        }
+        let rows: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT id, password, connection_string FROM databases WHERE NOT sealed",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id, password, connection_string) in rows {
+            self.seal_database_row(&id, password.as_deref(), connection_string.as_deref())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Store a row's plain text password and connection string encrypted
+    /// and mark it sealed
+    pub(super) async fn seal_database_row(
+        &self,
+        id: &str,
+        password: Option<&str>,
+        connection_string: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE databases SET password = ?, connection_string = ?, sealed = 1 WHERE id = ?",
+        )
+        .bind(self.seal_secret(password)?)
+        .bind(self.seal_secret(connection_string)?)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Decrypt a row and convert it to DatabaseCredentials
+    pub(super) fn row_to_database_credentials(
+        &self,
+        row: DatabaseRow,
+    ) -> Result<pctrl_core::DatabaseCredentials> {
         let (
             id,
             name,
@@ -159,10 +244,11 @@ impl Database {
             server_id,
             container_id,
             notes,
+            sealed,
         ) = row;
         let db_type = db_type.parse().unwrap_or_default();
 
-        pctrl_core::DatabaseCredentials {
+        Ok(pctrl_core::DatabaseCredentials {
             id,
             name,
             db_type,
@@ -170,11 +256,29 @@ impl Database {
             port: port.map(|p| p as u16),
             database_name,
             username,
-            password,
-            connection_string,
+            password: self.open_secret(password, sealed)?,
+            connection_string: self.open_secret(connection_string, sealed)?,
             server_id,
             container_id,
             notes,
-        }
+        })
     }
 }
+
+/// databases row; `sealed` says whether password and connection string
+/// are encrypted
+pub(super) type DatabaseRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<i64>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    bool,
+);
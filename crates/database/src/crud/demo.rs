@@ -0,0 +1,94 @@
+//! Demo data operations
+//!
+//! Demo rows go through the normal save methods, so they look exactly like
+//! data entered by hand. Clearing also drops their audit entries, leaving the
+//! database as it was before seeding.
+
+use crate::Database;
+use pctrl_core::demo::DemoData;
+use pctrl_core::Result;
+
+impl Database {
+    /// Add the demo dataset, skipping rows that already exist.
+    ///
+    /// Returns the number of rows added; seeding twice adds nothing.
+    pub async fn seed_demo_data(&self) -> Result<usize> {
+        let demo = DemoData::new();
+        let mut added = 0;
+
+        if self.get_project(&demo.project.id).await?.is_none() {
+            self.save_project(&demo.project).await?;
+            added += 1;
+        }
+        for server in &demo.servers {
+            if self.get_server(&server.id).await?.is_none() {
+                self.save_server(server).await?;
+                added += 1;
+            }
+        }
+        for domain in &demo.domains {
+            if self.get_domain(&domain.id).await?.is_none() {
+                self.save_domain(domain).await?;
+                added += 1;
+            }
+        }
+        for script in &demo.scripts {
+            if self.get_script(&script.id).await?.is_none() {
+                self.save_script(script).await?;
+                added += 1;
+            }
+        }
+        let linked = self.get_project_resources(&demo.project.id).await?;
+        for link in &demo.links {
+            if !linked.iter().any(|l| l.id == link.id) {
+                self.link_project_resource(link).await?;
+                added += 1;
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Remove exactly the demo rows and their audit entries.
+    ///
+    /// Returns the number of demo rows removed.
+    pub async fn clear_demo_data(&self) -> Result<usize> {
+        let demo = DemoData::new();
+        let mut removed = 0;
+
+        // Dependents first: links and scripts reference servers and the project
+        for link in &demo.links {
+            if self.unlink_project_resource(&link.id).await? {
+                removed += 1;
+            }
+        }
+        for script in &demo.scripts {
+            if self.remove_script(&script.id).await? {
+                removed += 1;
+            }
+        }
+        for domain in &demo.domains {
+            if self.remove_domain(&domain.id).await? {
+                removed += 1;
+            }
+        }
+        for server in &demo.servers {
+            if self.remove_server(&server.id).await? {
+                removed += 1;
+            }
+        }
+        if self.remove_project(&demo.project.id).await? {
+            removed += 1;
+        }
+
+        for id in demo.ids() {
+            sqlx::query("DELETE FROM audit_log WHERE entity_id = ?")
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+
+        Ok(removed)
+    }
+}
@@ -0,0 +1,48 @@
+//! Deploy keys awaiting removal (`server exec --with-deploy-key`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::Result;
+
+impl Database {
+    /// Remember a key before it is installed, until its removal is confirmed
+    pub async fn record_pending_deploy_key(&self, server_id: &str, path: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO pending_deploy_keys (path, server_id, created_at)
+             VALUES (?, ?, ?)",
+        )
+        .bind(path)
+        .bind(server_id)
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Keys that may still be on a server, oldest first
+    pub async fn pending_deploy_keys(&self, server_id: &str) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT path FROM pending_deploy_keys WHERE server_id = ? ORDER BY created_at, path",
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(|(path,)| path).collect())
+    }
+
+    /// Forget keys that were removed from the server
+    pub async fn clear_pending_deploy_keys(&self, paths: &[String]) -> Result<()> {
+        for path in paths {
+            sqlx::query("DELETE FROM pending_deploy_keys WHERE path = ?")
+                .bind(path)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! Discovery cache (filled by `server discover`)
+
+use super::{format_timestamp, now_timestamp};
+use crate::Database;
+use chrono::{Duration, Utc};
+use pctrl_core::discovery::CachedDiscovery;
+use pctrl_core::Result;
+
+/// discovery_cache row: server_id, data_type, data, fetched_at, expires_at
+type CacheRow = (String, String, String, String, String);
+
+impl Database {
+    /// Keep a discovery result for a server, replacing the previous one of
+    /// that kind; it counts as fresh for `ttl`
+    pub async fn upsert_discovery_cache(
+        &self,
+        server_id: &str,
+        data_type: &str,
+        data: &str,
+        ttl: Duration,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO discovery_cache (id, server_id, data_type, data, fetched_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(format!("{}/{}", server_id, data_type))
+        .bind(server_id)
+        .bind(data_type)
+        .bind(data)
+        .bind(now_timestamp())
+        .bind(format_timestamp(Utc::now() + ttl))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The last discovery result of a kind for a server, fresh or not
+    pub async fn get_discovery_cache(
+        &self,
+        server_id: &str,
+        data_type: &str,
+    ) -> Result<Option<CachedDiscovery>> {
+        let row: Option<CacheRow> = sqlx::query_as(
+            "SELECT server_id, data_type, data, fetched_at, expires_at FROM discovery_cache
+             WHERE server_id = ? AND data_type = ?",
+        )
+        .bind(server_id)
+        .bind(data_type)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(row.map(row_to_cached))
+    }
+
+    /// The last discovery results of a kind, one per server
+    pub async fn list_discovery_cache(&self, data_type: &str) -> Result<Vec<CachedDiscovery>> {
+        let rows: Vec<CacheRow> = sqlx::query_as(
+            "SELECT server_id, data_type, data, fetched_at, expires_at FROM discovery_cache
+             WHERE data_type = ? ORDER BY server_id",
+        )
+        .bind(data_type)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(rows.into_iter().map(row_to_cached).collect())
+    }
+}
+
+fn row_to_cached(
+    (server_id, data_type, data, fetched_at, expires_at): CacheRow,
+) -> CachedDiscovery {
+    CachedDiscovery {
+        server_id,
+        data_type,
+        data,
+        fetched_at,
+        expires_at,
+    }
+}
@@ -1,19 +1,23 @@
 //! Docker Host CRUD operations
 
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::{docker_endpoint, Result};
 use sqlx::Row;
 
 impl Database {
-    /// Add or update a Docker host
+    /// Add or update a Docker host; an unsupported URL is rejected here
     pub async fn save_docker_host(&self, host: &pctrl_core::DockerHost) -> Result<()> {
+        docker_endpoint::validate(host)?;
         sqlx::query(
-            "INSERT OR REPLACE INTO docker_hosts (id, name, url)
-             VALUES (?, ?, ?)",
+            "INSERT OR REPLACE INTO docker_hosts (id, name, url, tls_ca, tls_cert, tls_key)
+             VALUES (?, ?, ?, ?, ?, ?)",
         )
         .bind(&host.id)
         .bind(&host.name)
         .bind(&host.url)
+        .bind(&host.tls_ca)
+        .bind(&host.tls_cert)
+        .bind(&host.tls_key)
         .execute(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
@@ -43,12 +47,14 @@ impl Database {
         Ok(row.map(|(count,)| count > 0).unwrap_or(false))
     }
 
-    /// Load all Docker hosts
-    pub(crate) async fn load_docker_hosts(&self) -> Result<Vec<pctrl_core::DockerHost>> {
-        let rows = sqlx::query("SELECT id, name, url FROM docker_hosts")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    /// List all Docker hosts
+    pub async fn list_docker_hosts(&self) -> Result<Vec<pctrl_core::DockerHost>> {
+        let rows = sqlx::query(
+            "SELECT id, name, url, tls_ca, tls_cert, tls_key FROM docker_hosts ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
         let hosts = rows
             .into_iter()
@@ -56,6 +62,9 @@ impl Database {
                 id: row.get("id"),
                 name: row.get("name"),
                 url: row.get("url"),
+                tls_ca: row.get("tls_ca"),
+                tls_cert: row.get("tls_cert"),
+                tls_key: row.get("tls_key"),
             })
             .collect();
 
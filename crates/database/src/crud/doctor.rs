@@ -0,0 +1,221 @@
+//! Reference integrity checks and repairs (`pctrl doctor`)
+//!
+//! Broken references are found through `RELATIONSHIPS`, the same table
+//! `deps` uses; classifying and ordering them is `pctrl_core::doctor`.
+
+use super::references::{entity_table, RELATIONSHIPS};
+use crate::Database;
+use pctrl_core::diff::FieldChange;
+use pctrl_core::doctor::{self, Candidate, Finding, RefSite, Repair};
+use pctrl_core::{AuditAction, EntityType, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Entity types in the order they're checked
+const ENTITY_TYPES: [EntityType; 6] = [
+    EntityType::Credential,
+    EntityType::Server,
+    EntityType::Project,
+    EntityType::Database,
+    EntityType::Domain,
+    EntityType::Script,
+];
+
+impl Database {
+    /// Every reference to a missing entity and every set of names that
+    /// differ only in case, unordered (see [`doctor::plan`])
+    pub async fn integrity_findings(&self) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+        let mut entities = HashMap::new();
+        for entity_type in ENTITY_TYPES {
+            let list = self.doctor_candidates(entity_type).await?;
+            findings.extend(doctor::case_duplicates(entity_type, &list));
+            entities.insert(entity_type, list);
+        }
+
+        for rel in RELATIONSHIPS {
+            let (target_table, name_column) = entity_table(rel.target);
+            let reference = format!("{}.{}", rel.table, rel.column);
+            let exists = if rel.by_name {
+                format!(
+                    "e.id = {reference} OR e.{name} = {reference}",
+                    reference = reference,
+                    name = name_column
+                )
+            } else {
+                format!("e.id = {}", reference)
+            };
+            let filter = rel
+                .filter
+                .map(|f| format!(" AND {}", f))
+                .unwrap_or_default();
+            let sql = format!(
+                "SELECT {key}, {id}, {name}, {column} FROM {table}
+                 WHERE {column} IS NOT NULL AND {column} != ''{filter}
+                 AND NOT EXISTS (SELECT 1 FROM {target} e WHERE {exists})
+                 ORDER BY 1",
+                key = rel.key,
+                id = rel.id,
+                name = rel.name,
+                column = rel.column,
+                table = rel.table,
+                filter = filter,
+                target = target_table,
+                exists = exists,
+            );
+            let rows: Vec<(String, String, String, String)> = sqlx::query_as(&sql)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            if rows.is_empty() {
+                continue;
+            }
+
+            let required = self.is_not_null(rel.table, rel.column).await?;
+            for (key, owner_id, row_name, value) in rows {
+                let site = RefSite {
+                    relation: rel.label.to_string(),
+                    table: rel.table.to_string(),
+                    column: rel.column.to_string(),
+                    key: rel.key.to_string(),
+                    row: key,
+                    row_name,
+                    owner: rel.owner.map(|t| (t, owner_id)),
+                    required,
+                    link: rel.table == "project_resources",
+                };
+                findings.push(Finding::dangling(
+                    rel.target,
+                    site,
+                    &value,
+                    &entities[&rel.target],
+                ));
+            }
+        }
+
+        Ok(findings)
+    }
+
+    /// All entities of a type, as relink targets
+    pub async fn doctor_candidates(&self, entity_type: EntityType) -> Result<Vec<Candidate>> {
+        let (table, name_column) = entity_table(entity_type);
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, {} FROM {} ORDER BY 2",
+            name_column, table
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name)| Candidate { id, name })
+            .collect())
+    }
+
+    /// Apply a repair to a broken reference and record it in the audit log.
+    /// Fails if the row changed since the finding was made.
+    pub async fn apply_repair(&self, finding: &Finding, repair: &Repair) -> Result<()> {
+        let site = finding.site.as_ref().ok_or_else(|| {
+            pctrl_core::Error::Config("Case duplicates can't be repaired automatically".into())
+        })?;
+
+        let (result, new) = match repair {
+            Repair::Relink(candidate) => (
+                sqlx::query(&format!(
+                    "UPDATE {table} SET {column} = ? WHERE {key} = ? AND {column} = ?",
+                    table = site.table,
+                    column = site.column,
+                    key = site.key
+                ))
+                .bind(&candidate.id)
+                .bind(&site.row)
+                .bind(&finding.value)
+                .execute(&self.pool)
+                .await,
+                Value::String(candidate.id.clone()),
+            ),
+            Repair::Null if site.required => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "{}.{} is required and can't be cleared",
+                    site.table, site.column
+                )));
+            }
+            Repair::Null => (
+                sqlx::query(&format!(
+                    "UPDATE {table} SET {column} = NULL WHERE {key} = ? AND {column} = ?",
+                    table = site.table,
+                    column = site.column,
+                    key = site.key
+                ))
+                .bind(&site.row)
+                .bind(&finding.value)
+                .execute(&self.pool)
+                .await,
+                Value::Null,
+            ),
+            Repair::Delete if !site.link => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "Only links can be deleted here; remove {} '{}' with its own command",
+                    site.relation, site.row_name
+                )));
+            }
+            Repair::Delete => (
+                sqlx::query(&format!(
+                    "DELETE FROM {table} WHERE {key} = ? AND {column} = ?",
+                    table = site.table,
+                    column = site.column,
+                    key = site.key
+                ))
+                .bind(&site.row)
+                .bind(&finding.value)
+                .execute(&self.pool)
+                .await,
+                Value::Null,
+            ),
+        };
+        let result = result.map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Err(pctrl_core::Error::Config(format!(
+                "{} '{}' changed since the check; run doctor again",
+                site.relation, site.row_name
+            )));
+        }
+
+        // Rows that aren't entities (containers) are recorded on the missing one
+        let (entity_type, entity_id) = site
+            .owner
+            .clone()
+            .unwrap_or_else(|| (finding.target, finding.value.clone()));
+        let field = if site.link {
+            format!("links.{}", finding.target)
+        } else {
+            site.column.clone()
+        };
+        self.record_audit_with_changes(
+            entity_type,
+            &entity_id,
+            AuditAction::Updated,
+            &format!(
+                "doctor: {} (was {} '{}')",
+                repair, finding.target, finding.value
+            ),
+            &[FieldChange {
+                field,
+                old: Value::String(finding.value.clone()),
+                new,
+            }],
+        )
+        .await
+    }
+
+    async fn is_not_null(&self, table: &str, column: &str) -> Result<bool> {
+        let columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+            sqlx::query_as(&format!("PRAGMA table_info({})", table))
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(columns
+            .iter()
+            .any(|(_, name, _, not_null, _, _)| name == column && *not_null != 0))
+    }
+}
@@ -1,14 +1,42 @@
 //! Domain CRUD operations
 
+use super::names;
+use super::placeholders;
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::diff::diff;
+use pctrl_core::{AuditAction, EntityType, Result};
+
+/// domains row
+type DomainRow = (
+    String,
+    String,
+    String,
+    bool,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
 
 impl Database {
     /// Save a domain
     pub async fn save_domain(&self, domain: &pctrl_core::Domain) -> Result<()> {
+        self.insert_domain(domain, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_domain(&self, domain: &pctrl_core::Domain, creating: bool) -> Result<()> {
+        self.check_lock(EntityType::Domain, &domain.id).await?;
+        self.check_name(names::DOMAINS, &domain.id, &domain.domain, creating)
+            .await?;
+        let previous = self.get_domain(&domain.id).await?;
+
         sqlx::query(
-            "INSERT OR REPLACE INTO domains (id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO domains (id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by, short_ref)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT short_ref FROM domains WHERE id = ?))",
         )
         .bind(&domain.id)
         .bind(&domain.domain)
@@ -20,28 +48,47 @@ impl Database {
         .bind(&domain.server_id)
         .bind(&domain.container_id)
         .bind(&domain.notes)
+        .bind(&domain.superseded_by)
+        .bind(&domain.id)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&domain.domain))?;
+        self.assign_short_ref(EntityType::Domain, &domain.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, domain))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Domain,
+            &domain.id,
+            AuditAction::for_save(previous.is_some()),
+            &domain.domain,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add a domain; fails with [`pctrl_core::Error::Conflict`] when its ID
+    /// or its name (ignoring case) is taken
+    pub async fn create_domain(&self, domain: &pctrl_core::Domain) -> Result<()> {
+        self.insert_domain(domain, true).await
+    }
+
+    /// Change an existing domain; fails with [`pctrl_core::Error::Conflict`]
+    /// when another domain has its name (ignoring case)
+    pub async fn update_domain(&self, domain: &pctrl_core::Domain) -> Result<()> {
+        self.check_exists(names::DOMAINS, &domain.id).await?;
+        self.save_domain(domain).await
+    }
+
     /// Get a domain by ID
     pub async fn get_domain(&self, id: &str) -> Result<Option<pctrl_core::Domain>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            bool,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes FROM domains WHERE id = ?",
+        let row: Option<DomainRow> = sqlx::query_as(
+            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by FROM domains WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -56,19 +103,8 @@ impl Database {
         &self,
         domain_name: &str,
     ) -> Result<Option<pctrl_core::Domain>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            bool,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes FROM domains WHERE LOWER(domain) = LOWER(?)",
+        let row: Option<DomainRow> = sqlx::query_as(
+            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by FROM domains WHERE LOWER(domain) = LOWER(?)",
         )
         .bind(domain_name)
         .fetch_optional(&self.pool)
@@ -80,19 +116,8 @@ impl Database {
 
     /// List all domains
     pub async fn list_domains(&self) -> Result<Vec<pctrl_core::Domain>> {
-        let rows: Vec<(
-            String,
-            String,
-            String,
-            bool,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes FROM domains ORDER BY domain",
+        let rows: Vec<DomainRow> = sqlx::query_as(
+            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by FROM domains ORDER BY domain",
         )
         .fetch_all(&self.pool)
         .await
@@ -101,32 +126,55 @@ impl Database {
         Ok(rows.into_iter().map(Self::row_to_domain).collect())
     }
 
+    /// Domains by ID or domain name (case-insensitive), in one query; links
+    /// store either. Unknown references are left out.
+    pub async fn get_domains_by_ids(&self, refs: &[&str]) -> Result<Vec<pctrl_core::Domain>> {
+        if refs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list = placeholders(refs.len());
+        let sql = format!(
+            "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by FROM domains
+             WHERE id IN ({list}) OR LOWER(domain) IN ({list}) ORDER BY domain"
+        );
+        let mut query = sqlx::query_as::<_, DomainRow>(&sql);
+        for r in refs {
+            query = query.bind(*r);
+        }
+        for r in refs {
+            query = query.bind(r.to_ascii_lowercase());
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_domain).collect())
+    }
+
     /// Remove a domain by ID
     pub async fn remove_domain(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Domain, id).await?;
+        let previous = self.get_domain(id).await?;
+
         let result = sqlx::query("DELETE FROM domains WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let removed = result.rows_affected() > 0;
+        if removed {
+            let summary = previous.map(|e| e.domain).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Domain, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
     }
 
     /// Helper to convert a row tuple to Domain
-    fn row_to_domain(
-        row: (
-            String,
-            String,
-            String,
-            bool,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        ),
-    ) -> pctrl_core::Domain {
+    pub(super) fn row_to_domain(row: DomainRow) -> pctrl_core::Domain {
         let (
             id,
             domain,
@@ -138,6 +186,7 @@ impl Database {
             server_id,
             container_id,
             notes,
+            superseded_by,
         ) = row;
         let domain_type = domain_type.parse().unwrap_or_default();
 
@@ -152,6 +201,7 @@ impl Database {
             server_id,
             container_id,
             notes,
+            superseded_by,
         }
     }
 }
@@ -0,0 +1,130 @@
+//! Domain base migration (`pctrl domain migrate-base`)
+
+use crate::Database;
+use pctrl_core::diff::diff;
+use pctrl_core::domain_base::{
+    replace_url_host, url_host, BaseMigration, BasePlan, HealthUrlChange,
+};
+use pctrl_core::{AuditAction, Domain, EntityType, Result};
+
+impl Database {
+    /// Apply a base migration in one transaction: create the new domains,
+    /// mark the old ones superseded, and move project links and health URLs
+    /// that referenced the old names. Collisions in the plan are skipped.
+    pub async fn migrate_domain_base(&self, plan: &BasePlan) -> Result<BaseMigration> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        for rename in plan.applicable() {
+            self.check_lock(EntityType::Domain, &rename.domain.id)
+                .await?;
+        }
+
+        let health_rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT project_id, health_url FROM project_preflight WHERE health_url IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        let health_urls: Vec<HealthUrlChange> = health_rows
+            .into_iter()
+            .filter_map(|(project_id, url)| {
+                let rename = plan.renamed(url_host(&url)?)?;
+                let to = replace_url_host(&url, &rename.domain.domain, &rename.to)?;
+                Some(HealthUrlChange {
+                    project_id,
+                    from: url,
+                    to,
+                })
+            })
+            .collect();
+
+        let mut migration = BaseMigration::default();
+        let mut audits: Vec<(Domain, Domain)> = Vec::new();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+
+        for rename in plan.applicable() {
+            let new = rename.new_domain();
+            sqlx::query(
+                "INSERT INTO domains (id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&new.id)
+            .bind(&new.domain)
+            .bind(new.domain_type.to_string())
+            .bind(new.ssl)
+            .bind(&new.ssl_expiry)
+            .bind(&new.cloudflare_zone_id)
+            .bind(&new.cloudflare_record_id)
+            .bind(&new.server_id)
+            .bind(&new.container_id)
+            .bind(&new.notes)
+            .bind(&new.superseded_by)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+            sqlx::query("UPDATE domains SET superseded_by = ? WHERE id = ?")
+                .bind(&new.id)
+                .bind(&rename.domain.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+
+            // Links may use the domain's ID or its name
+            for (from, to) in [
+                (&rename.domain.id, &new.id),
+                (&rename.domain.domain, &new.domain),
+            ] {
+                let moved = sqlx::query(
+                    "UPDATE project_resources SET resource_id = ?
+                     WHERE resource_type = 'domain' AND LOWER(resource_id) = LOWER(?)",
+                )
+                .bind(to)
+                .bind(from)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+                migration.links += moved.rows_affected();
+            }
+
+            migration.domains += 1;
+            audits.push((rename.domain.clone(), new));
+        }
+
+        for change in &health_urls {
+            sqlx::query("UPDATE project_preflight SET health_url = ? WHERE project_id = ?")
+                .bind(&change.to)
+                .bind(&change.project_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+        }
+
+        tx.commit().await.map_err(db_err)?;
+
+        for (old, new) in audits {
+            self.assign_short_ref(EntityType::Domain, &new.id).await?;
+            self.record_audit(
+                EntityType::Domain,
+                &new.id,
+                AuditAction::Created,
+                &new.domain,
+            )
+            .await?;
+            let superseded = Domain {
+                superseded_by: Some(new.id.clone()),
+                ..old.clone()
+            };
+            self.record_audit_with_changes(
+                EntityType::Domain,
+                &old.id,
+                AuditAction::Updated,
+                &old.domain,
+                &diff(&old, &superseded),
+            )
+            .await?;
+        }
+
+        migration.health_urls = health_urls;
+        Ok(migration)
+    }
+}
@@ -0,0 +1,623 @@
+//! Idempotent creates (`add --ensure`)
+//!
+//! An ensure never selects before it inserts. `INSERT ... ON CONFLICT DO
+//! NOTHING` claims the creation, so of two concurrent ensures exactly one
+//! reports "created". If the row exists, `INSERT ... ON CONFLICT(id) DO
+//! UPDATE` sets only the patched columns (`COALESCE(?, column)`), so
+//! concurrent ensures merge instead of overwriting each other.
+//!
+//! The entity passed in is what gets created; the patch holds the fields the
+//! caller gave and is what gets applied to an existing row.
+
+use crate::Database;
+use pctrl_core::diff::diff;
+use pctrl_core::{
+    AuditAction, Credential, CredentialData, CredentialPatch, DatabaseCredentials, DatabasePatch,
+    Domain, DomainPatch, Ensured, EntityType, Project, ProjectPatch, Result, Script, ScriptPatch,
+    ScriptRevision, ScriptUpdate, Server, ServerPatch,
+};
+use serde::Serialize;
+use sqlx::sqlite::{Sqlite, SqliteArguments};
+
+type Query<'q> = sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>;
+
+const PROJECT_COLUMNS: [&str; 8] = [
+    "id",
+    "name",
+    "description",
+    "stack",
+    "status",
+    "color",
+    "icon",
+    "notes",
+];
+
+const SERVER_COLUMNS: [&str; 9] = [
+    "id",
+    "name",
+    "host",
+    "server_type",
+    "provider",
+    "credential_id",
+    "location",
+    "specs",
+    "notes",
+];
+
+const DOMAIN_COLUMNS: [&str; 10] = [
+    "id",
+    "domain",
+    "domain_type",
+    "ssl",
+    "ssl_expiry",
+    "cloudflare_zone_id",
+    "cloudflare_record_id",
+    "server_id",
+    "container_id",
+    "notes",
+];
+
+/// `sealed` says whether the password and connection string are encrypted
+const DATABASE_COLUMNS: [&str; 13] = [
+    "id",
+    "name",
+    "db_type",
+    "host",
+    "port",
+    "database_name",
+    "username",
+    "password",
+    "connection_string",
+    "server_id",
+    "container_id",
+    "notes",
+    "sealed",
+];
+
+/// Run results are never part of a create
+const SCRIPT_COLUMNS: [&str; 10] = [
+    "id",
+    "name",
+    "description",
+    "command",
+    "script_type",
+    "server_id",
+    "project_id",
+    "docker_host_id",
+    "container_id",
+    "dangerous",
+];
+
+/// The insert of an ensure: without `patched` it does nothing on conflict,
+/// with it it sets those columns to the given value or keeps them
+fn ensure_sql(table: &str, columns: &[&str], patched: Option<&[&str]>) -> String {
+    let conflict = match patched {
+        Some(patched) => format!(
+            "ON CONFLICT(id) DO UPDATE SET {}",
+            patched
+                .iter()
+                .map(|c| format!("{c} = COALESCE(?, {c})"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => "ON CONFLICT DO NOTHING".to_string(),
+    };
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) {}",
+        table,
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", "),
+        conflict
+    )
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| pctrl_core::Error::Database(e.to_string()))
+}
+
+fn project_values<'q>(sql: &'q str, project: &Project) -> Result<Query<'q>> {
+    Ok(sqlx::query(sql)
+        .bind(project.id.clone())
+        .bind(project.name.clone())
+        .bind(project.description.clone())
+        .bind(to_json(&project.stack)?)
+        .bind(project.status.to_string())
+        .bind(project.color.clone())
+        .bind(project.icon.clone())
+        .bind(project.notes.clone()))
+}
+
+fn server_values<'q>(sql: &'q str, server: &Server) -> Result<Query<'q>> {
+    Ok(sqlx::query(sql)
+        .bind(server.id.clone())
+        .bind(server.name.clone())
+        .bind(server.host.clone())
+        .bind(server.server_type.to_string())
+        .bind(server.provider.clone())
+        .bind(server.credential_id.clone())
+        .bind(server.location.clone())
+        .bind(server.specs.as_ref().map(to_json).transpose()?)
+        .bind(server.notes.clone()))
+}
+
+fn domain_values<'q>(sql: &'q str, domain: &Domain) -> Query<'q> {
+    sqlx::query(sql)
+        .bind(domain.id.clone())
+        .bind(domain.domain.clone())
+        .bind(domain.domain_type.to_string())
+        .bind(domain.ssl)
+        .bind(domain.ssl_expiry.clone())
+        .bind(domain.cloudflare_zone_id.clone())
+        .bind(domain.cloudflare_record_id.clone())
+        .bind(domain.server_id.clone())
+        .bind(domain.container_id.clone())
+        .bind(domain.notes.clone())
+}
+
+fn database_values<'q>(sql: &'q str, creds: &DatabaseCredentials, sealed: bool) -> Query<'q> {
+    sqlx::query(sql)
+        .bind(creds.id.clone())
+        .bind(creds.name.clone())
+        .bind(creds.db_type.to_string())
+        .bind(creds.host.clone())
+        .bind(creds.port.map(|p| p as i64))
+        .bind(creds.database_name.clone())
+        .bind(creds.username.clone())
+        .bind(creds.password.clone())
+        .bind(creds.connection_string.clone())
+        .bind(creds.server_id.clone())
+        .bind(creds.container_id.clone())
+        .bind(creds.notes.clone())
+        .bind(sealed)
+}
+
+fn script_values<'q>(sql: &'q str, script: &Script) -> Query<'q> {
+    sqlx::query(sql)
+        .bind(script.id.clone())
+        .bind(script.name.clone())
+        .bind(script.description.clone())
+        .bind(script.command.clone())
+        .bind(script.script_type.to_string())
+        .bind(script.server_id.clone())
+        .bind(script.project_id.clone())
+        .bind(script.docker_host_id.clone())
+        .bind(script.container_id.clone())
+        .bind(script.dangerous)
+}
+
+impl Database {
+    /// Create a project, or set the patched fields of the existing one
+    pub async fn ensure_project(&self, project: &Project, patch: &ProjectPatch) -> Result<Ensured> {
+        self.check_lock(EntityType::Project, &project.id).await?;
+        let claim = ensure_sql("projects", &PROJECT_COLUMNS, None);
+        if self.claim(project_values(&claim, project)?).await? {
+            return self
+                .ensured_created(EntityType::Project, &project.id, &project.name)
+                .await;
+        }
+
+        let previous = self.get_project(&project.id).await?;
+        let previous = self.require_existing(previous, &project.name)?;
+        let merge = ensure_sql(
+            "projects",
+            &PROJECT_COLUMNS,
+            Some(&["description", "stack", "status"]),
+        );
+        self.merge(
+            project_values(&merge, project)?
+                .bind(patch.description.clone())
+                .bind(patch.stack.as_ref().map(to_json).transpose()?)
+                .bind(patch.status.as_ref().map(|s| s.to_string())),
+        )
+        .await?;
+        let current = self.get_project(&project.id).await?;
+
+        self.ensured_updated(
+            EntityType::Project,
+            &project.id,
+            &project.name,
+            &previous,
+            current,
+        )
+        .await
+    }
+
+    /// Create a server, or set the patched fields of the existing one
+    pub async fn ensure_server(&self, server: &Server, patch: &ServerPatch) -> Result<Ensured> {
+        self.check_lock(EntityType::Server, &server.id).await?;
+        let claim = ensure_sql("servers", &SERVER_COLUMNS, None);
+        if self.claim(server_values(&claim, server)?).await? {
+            return self
+                .ensured_created(EntityType::Server, &server.id, &server.name)
+                .await;
+        }
+
+        // A trashed server has the row but isn't visible
+        let previous = self.get_server(&server.id).await?;
+        let previous = self.require_existing(previous, &server.name)?;
+        let merge = ensure_sql(
+            "servers",
+            &SERVER_COLUMNS,
+            Some(&[
+                "host",
+                "server_type",
+                "provider",
+                "credential_id",
+                "location",
+                "specs",
+            ]),
+        );
+        self.merge(
+            server_values(&merge, server)?
+                .bind(patch.host.clone())
+                .bind(patch.server_type.as_ref().map(|t| t.to_string()))
+                .bind(patch.provider.clone())
+                .bind(patch.credential_id.clone())
+                .bind(patch.location.clone())
+                .bind(patch.specs.as_ref().map(to_json).transpose()?),
+        )
+        .await?;
+        let current = self.get_server(&server.id).await?;
+
+        self.ensured_updated(
+            EntityType::Server,
+            &server.id,
+            &server.name,
+            &previous,
+            current,
+        )
+        .await
+    }
+
+    /// Create a domain, or set the patched fields of the existing one
+    pub async fn ensure_domain(&self, domain: &Domain, patch: &DomainPatch) -> Result<Ensured> {
+        self.check_lock(EntityType::Domain, &domain.id).await?;
+        let claim = ensure_sql("domains", &DOMAIN_COLUMNS, None);
+        if self.claim(domain_values(&claim, domain)).await? {
+            return self
+                .ensured_created(EntityType::Domain, &domain.id, &domain.domain)
+                .await;
+        }
+
+        let previous = self.get_domain(&domain.id).await?;
+        let previous = self.require_existing(previous, &domain.domain)?;
+        let merge = ensure_sql(
+            "domains",
+            &DOMAIN_COLUMNS,
+            Some(&[
+                "domain_type",
+                "ssl",
+                "ssl_expiry",
+                "cloudflare_zone_id",
+                "cloudflare_record_id",
+                "server_id",
+            ]),
+        );
+        self.merge(
+            domain_values(&merge, domain)
+                .bind(patch.domain_type.as_ref().map(|t| t.to_string()))
+                .bind(patch.ssl)
+                .bind(patch.ssl_expiry.clone())
+                .bind(patch.cloudflare_zone_id.clone())
+                .bind(patch.cloudflare_record_id.clone())
+                .bind(patch.server_id.clone()),
+        )
+        .await?;
+        let current = self.get_domain(&domain.id).await?;
+
+        self.ensured_updated(
+            EntityType::Domain,
+            &domain.id,
+            &domain.domain,
+            &previous,
+            current,
+        )
+        .await
+    }
+
+    /// Create database credentials, or set the patched fields of existing ones
+    pub async fn ensure_database_credentials(
+        &self,
+        creds: &DatabaseCredentials,
+        patch: &DatabasePatch,
+    ) -> Result<Ensured> {
+        self.check_lock(EntityType::Database, &creds.id).await?;
+        // Stored with the password and connection string encrypted
+        let sealed = DatabaseCredentials {
+            password: self.seal_secret(creds.password.as_deref())?,
+            connection_string: self.seal_secret(creds.connection_string.as_deref())?,
+            ..creds.clone()
+        };
+        let claim = ensure_sql("databases", &DATABASE_COLUMNS, None);
+        if self
+            .claim(database_values(&claim, &sealed, self.is_encrypted()))
+            .await?
+        {
+            return self
+                .ensured_created(EntityType::Database, &creds.id, &creds.name)
+                .await;
+        }
+
+        let previous = self.get_database_credentials(&creds.id).await?;
+        let previous = self.require_existing(previous, &creds.name)?;
+        // The patched secrets are written encrypted, so the row's others
+        // have to be as well
+        if self.is_encrypted() {
+            self.seal_database_row(
+                &creds.id,
+                previous.password.as_deref(),
+                previous.connection_string.as_deref(),
+            )
+            .await?;
+        }
+        let merge = ensure_sql(
+            "databases",
+            &DATABASE_COLUMNS,
+            Some(&[
+                "db_type",
+                "host",
+                "port",
+                "database_name",
+                "username",
+                "password",
+                "connection_string",
+                "server_id",
+                "container_id",
+            ]),
+        );
+        self.merge(
+            database_values(&merge, &sealed, self.is_encrypted())
+                .bind(patch.db_type.as_ref().map(|t| t.to_string()))
+                .bind(patch.host.clone())
+                .bind(patch.port.map(|p| p as i64))
+                .bind(patch.database_name.clone())
+                .bind(patch.username.clone())
+                .bind(self.seal_secret(patch.password.as_deref())?)
+                .bind(self.seal_secret(patch.connection_string.as_deref())?)
+                .bind(patch.server_id.clone())
+                .bind(patch.container_id.clone()),
+        )
+        .await?;
+        let current = self.get_database_credentials(&creds.id).await?;
+
+        self.ensured_updated(
+            EntityType::Database,
+            &creds.id,
+            &creds.name,
+            &previous,
+            current,
+        )
+        .await
+    }
+
+    /// Create a script, or set the patched fields of the existing one.
+    ///
+    /// A new command or dangerous flag goes through the same approval check
+    /// as `script edit`; a change held for approval is returned.
+    pub async fn ensure_script(
+        &self,
+        script: &Script,
+        patch: &ScriptPatch,
+        author: &str,
+    ) -> Result<(Ensured, Option<ScriptRevision>)> {
+        self.check_lock(EntityType::Script, &script.id).await?;
+        let claim = ensure_sql("scripts", &SCRIPT_COLUMNS, None);
+        if self.claim(script_values(&claim, script)).await? {
+            let ensured = self
+                .ensured_created(EntityType::Script, &script.id, &script.name)
+                .await?;
+            return Ok((ensured, None));
+        }
+
+        let previous = self.get_script(&script.id).await?;
+        let previous = self.require_existing(previous, &script.name)?;
+        let merge = ensure_sql(
+            "scripts",
+            &SCRIPT_COLUMNS,
+            Some(&[
+                "description",
+                "script_type",
+                "server_id",
+                "project_id",
+                "docker_host_id",
+                "container_id",
+            ]),
+        );
+        self.merge(
+            script_values(&merge, script)
+                .bind(patch.description.clone())
+                .bind(patch.script_type.as_ref().map(|t| t.to_string()))
+                .bind(patch.server_id.clone())
+                .bind(patch.project_id.clone())
+                .bind(patch.docker_host_id.clone())
+                .bind(patch.container_id.clone()),
+        )
+        .await?;
+        let merged = self.get_script(&script.id).await?;
+        self.ensured_updated(
+            EntityType::Script,
+            &script.id,
+            &script.name,
+            &previous,
+            merged.clone(),
+        )
+        .await?;
+
+        let mut pending = None;
+        if let (Some(merged), true) = (merged, patch.command.is_some() || patch.dangerous.is_some())
+        {
+            let command = patch.command.as_deref().unwrap_or(&merged.command);
+            let dangerous = patch.dangerous.unwrap_or(merged.dangerous);
+            if let ScriptUpdate::Pending(revision) = self
+                .update_script_command(&script.id, command, dangerous, author)
+                .await?
+            {
+                pending = Some(revision);
+            }
+        }
+
+        let changes = match self.get_script(&script.id).await? {
+            Some(current) => diff(&previous, &current),
+            None => Vec::new(),
+        };
+        let ensured = if changes.is_empty() {
+            Ensured::Unchanged
+        } else {
+            Ensured::Updated(changes)
+        };
+        Ok((ensured, pending))
+    }
+
+    /// Create a credential, or set the patched fields of the one with the
+    /// same name.
+    ///
+    /// The data is encrypted, so the merge happens in Rust inside an
+    /// immediate transaction, which keeps concurrent merges serialized.
+    pub async fn ensure_credential(
+        &self,
+        credential: &Credential,
+        patch: &CredentialPatch,
+    ) -> Result<Ensured> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let data = self.encrypt(to_json(&credential.data)?.as_bytes())?;
+
+        // Names are unique, so this also does nothing for an existing name
+        let claimed = sqlx::query(
+            "INSERT INTO credentials (id, name, credential_type, data, notes)
+             VALUES (?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+        )
+        .bind(&credential.id)
+        .bind(&credential.name)
+        .bind(credential.credential_type.to_string())
+        .bind(&data)
+        .bind(&credential.notes)
+        .execute(&self.pool)
+        .await
+        .map_err(db_err)?
+        .rows_affected()
+            > 0;
+        if claimed {
+            return self
+                .ensured_created(EntityType::Credential, &credential.id, &credential.name)
+                .await;
+        }
+
+        let mut conn = self.pool.acquire().await.map_err(db_err)?;
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *conn)
+            .await
+            .map_err(db_err)?;
+        let merged = async {
+            let row: Option<(String, Vec<u8>)> =
+                sqlx::query_as("SELECT id, data FROM credentials WHERE name = ?")
+                    .bind(&credential.name)
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(db_err)?;
+            let (id, encrypted) = self.require_existing(row, &credential.name)?;
+            let previous = self
+                .get_credential(&id)
+                .await?
+                .ok_or_else(|| pctrl_core::Error::Database("Credential vanished".to_string()))?;
+            if previous.credential_type != credential.credential_type {
+                return Err(pctrl_core::Error::Config(format!(
+                    "Credential '{}' is of type {}, not {}",
+                    credential.name, previous.credential_type, credential.credential_type
+                )));
+            }
+
+            let mut data: CredentialData = serde_json::from_slice(&self.decrypt(&encrypted)?)
+                .map_err(|e| pctrl_core::Error::Database(format!("Failed to parse data: {}", e)))?;
+            patch.apply(&mut data);
+            sqlx::query("UPDATE credentials SET data = ? WHERE id = ?")
+                .bind(self.encrypt(to_json(&data)?.as_bytes())?)
+                .bind(&id)
+                .execute(&mut *conn)
+                .await
+                .map_err(db_err)?;
+
+            let mut current = previous.clone();
+            current.data = data;
+            Ok((previous, current))
+        }
+        .await;
+
+        let end = if merged.is_ok() { "COMMIT" } else { "ROLLBACK" };
+        sqlx::query(end).execute(&mut *conn).await.map_err(db_err)?;
+        drop(conn);
+
+        let (previous, current) = merged?;
+        self.check_lock(EntityType::Credential, &previous.id)
+            .await?;
+        let id = previous.id.clone();
+        self.ensured_updated(
+            EntityType::Credential,
+            &id,
+            &credential.name,
+            &previous,
+            Some(current),
+        )
+        .await
+    }
+
+    /// Run the claiming insert; true if it created the row
+    async fn claim(&self, query: Query<'_>) -> Result<bool> {
+        let result = query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Run the merging upsert
+    async fn merge(&self, query: Query<'_>) -> Result<()> {
+        query
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The existing row an ensure collided with; missing if it is trashed or
+    /// another row has the same unique name
+    fn require_existing<T>(&self, existing: Option<T>, name: &str) -> Result<T> {
+        existing.ok_or_else(|| {
+            pctrl_core::Error::Config(format!(
+                "'{}' conflicts with an entry that can't be updated (is it in the trash?)",
+                name
+            ))
+        })
+    }
+
+    async fn ensured_created(
+        &self,
+        entity_type: EntityType,
+        id: &str,
+        summary: &str,
+    ) -> Result<Ensured> {
+        self.assign_short_ref(entity_type, id).await?;
+        self.record_audit(entity_type, id, AuditAction::Created, summary)
+            .await?;
+        Ok(Ensured::Created)
+    }
+
+    async fn ensured_updated<T: Serialize>(
+        &self,
+        entity_type: EntityType,
+        id: &str,
+        summary: &str,
+        previous: &T,
+        current: Option<T>,
+    ) -> Result<Ensured> {
+        let changes = current
+            .as_ref()
+            .map(|c| diff(previous, c))
+            .unwrap_or_default();
+        if changes.is_empty() {
+            return Ok(Ensured::Unchanged);
+        }
+        self.record_audit_with_changes(entity_type, id, AuditAction::Updated, summary, &changes)
+            .await?;
+        Ok(Ensured::Updated(changes))
+    }
+}
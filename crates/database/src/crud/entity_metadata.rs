@@ -0,0 +1,125 @@
+//! Free-form metadata of entities (`pctrl server meta`), readable as
+//! `meta.<key>` columns in list output
+
+use super::placeholders;
+use crate::Database;
+use pctrl_core::columns::valid_meta_key;
+use pctrl_core::{EntityType, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Ids per `IN (...)` query, well below SQLite's bound parameter limit
+const BATCH: usize = 500;
+
+impl Database {
+    /// Set one metadata entry, replacing its value
+    pub async fn set_entity_metadata(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<()> {
+        if !valid_meta_key(key) {
+            return Err(pctrl_core::Error::Config(format!(
+                "Invalid metadata key '{}' (letters, digits, _ and - only)",
+                key
+            )));
+        }
+        self.check_lock(entity_type, entity_id).await?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO entity_metadata (entity_type, entity_id, key, value) VALUES (?, ?, ?, ?)",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Remove one metadata entry; false if it wasn't set
+    pub async fn remove_entity_metadata(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        key: &str,
+    ) -> Result<bool> {
+        self.check_lock(entity_type, entity_id).await?;
+
+        let result = sqlx::query(
+            "DELETE FROM entity_metadata WHERE entity_type = ? AND entity_id = ? AND key = ?",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .bind(key)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Metadata of one entity, sorted by key
+    pub async fn list_entity_metadata(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+    ) -> Result<BTreeMap<String, String>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT key, value FROM entity_metadata WHERE entity_type = ? AND entity_id = ?",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Metadata of many entities at once, keyed by entity id; entities
+    /// without metadata are left out
+    pub async fn entity_metadata_for(
+        &self,
+        entity_type: EntityType,
+        entity_ids: &[String],
+    ) -> Result<HashMap<String, BTreeMap<String, String>>> {
+        let mut out: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+        for batch in entity_ids.chunks(BATCH) {
+            let sql = format!(
+                "SELECT entity_id, key, value FROM entity_metadata
+                 WHERE entity_type = ? AND entity_id IN ({})",
+                placeholders(batch.len())
+            );
+            let mut query =
+                sqlx::query_as::<_, (String, String, String)>(&sql).bind(entity_type.to_string());
+            for id in batch {
+                query = query.bind(id);
+            }
+            let rows = query
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            for (id, key, value) in rows {
+                out.entry(id).or_default().insert(key, value);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Every metadata key used by some entity of a type
+    pub async fn entity_metadata_keys(&self, entity_type: EntityType) -> Result<BTreeSet<String>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT DISTINCT key FROM entity_metadata WHERE entity_type = ?")
+                .bind(entity_type.to_string())
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(|(key,)| key).collect())
+    }
+}
@@ -0,0 +1,111 @@
+//! Host fact operations (filled by `server facts`)
+
+use super::{now_timestamp, placeholders};
+use crate::Database;
+use pctrl_core::facts::Facts;
+use pctrl_core::{Result, ServerFact};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+impl Database {
+    /// Replace the stored facts of a server with a new collection
+    pub async fn replace_server_facts(&self, server_id: &str, facts: &Facts) -> Result<()> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let collected_at = now_timestamp();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+
+        sqlx::query("DELETE FROM server_facts WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+
+        for (key, value) in facts {
+            sqlx::query(
+                "INSERT INTO server_facts (server_id, key, value, collected_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(server_id)
+            .bind(key)
+            .bind(value)
+            .bind(&collected_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Facts of a server, sorted by key
+    pub async fn list_server_facts(&self, server_id: &str) -> Result<Vec<ServerFact>> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT server_id, key, value, collected_at FROM server_facts
+             WHERE server_id = ? ORDER BY key",
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_fact).collect())
+    }
+
+    /// Facts of all servers, grouped by server
+    pub async fn list_all_server_facts(&self) -> Result<Vec<ServerFact>> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT server_id, key, value, collected_at FROM server_facts ORDER BY server_id, key",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_fact).collect())
+    }
+
+    /// Facts of many servers at once, keyed by server id; servers without
+    /// facts are left out
+    pub async fn server_facts_for(
+        &self,
+        server_ids: &[String],
+    ) -> Result<HashMap<String, BTreeMap<String, String>>> {
+        let mut out: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+        for batch in server_ids.chunks(500) {
+            let sql = format!(
+                "SELECT server_id, key, value FROM server_facts WHERE server_id IN ({})",
+                placeholders(batch.len())
+            );
+            let mut query = sqlx::query_as::<_, (String, String, String)>(&sql);
+            for id in batch {
+                query = query.bind(id);
+            }
+            let rows = query
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            for (id, key, value) in rows {
+                out.entry(id).or_default().insert(key, value);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Every fact key collected for some server
+    pub async fn server_fact_keys(&self) -> Result<BTreeSet<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT key FROM server_facts")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(|(key,)| key).collect())
+    }
+
+    fn row_to_fact(row: (String, String, String, String)) -> ServerFact {
+        let (server_id, key, value, collected_at) = row;
+        ServerFact {
+            server_id,
+            key,
+            value,
+            collected_at,
+        }
+    }
+}
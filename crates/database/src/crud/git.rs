@@ -8,13 +8,14 @@ impl Database {
     /// Add or update a Git repository
     pub async fn save_git_repo(&self, repo: &pctrl_core::GitRepo) -> Result<()> {
         sqlx::query(
-            "INSERT OR REPLACE INTO git_repos (id, name, path, remote_url)
-             VALUES (?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO git_repos (id, name, path, remote_url, credential_id)
+             VALUES (?, ?, ?, ?, ?)",
         )
         .bind(&repo.id)
         .bind(&repo.name)
         .bind(&repo.path)
         .bind(&repo.remote_url)
+        .bind(&repo.credential_id)
         .execute(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
@@ -44,23 +45,41 @@ impl Database {
         Ok(row.map(|(count,)| count > 0).unwrap_or(false))
     }
 
-    /// Load all Git repositories
-    pub(crate) async fn load_git_repos(&self) -> Result<Vec<pctrl_core::GitRepo>> {
-        let rows = sqlx::query("SELECT id, name, path, remote_url FROM git_repos")
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    /// Get a Git repository by ID or name (case-insensitive)
+    pub async fn get_git_repo(&self, id_or_name: &str) -> Result<Option<pctrl_core::GitRepo>> {
+        let row = sqlx::query(
+            "SELECT id, name, path, remote_url, credential_id FROM git_repos WHERE id = ? OR LOWER(name) = LOWER(?)
+             ORDER BY id = ? DESC LIMIT 1",
+        )
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_git_repo))
+    }
 
-        let repos = rows
-            .into_iter()
-            .map(|row| pctrl_core::GitRepo {
-                id: row.get("id"),
-                name: row.get("name"),
-                path: row.get("path"),
-                remote_url: row.get("remote_url"),
-            })
-            .collect();
+    /// All Git repositories, by name
+    pub async fn list_git_repos(&self) -> Result<Vec<pctrl_core::GitRepo>> {
+        let rows = sqlx::query(
+            "SELECT id, name, path, remote_url, credential_id FROM git_repos ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_git_repo).collect())
+    }
+}
 
-        Ok(repos)
+fn row_to_git_repo(row: sqlx::sqlite::SqliteRow) -> pctrl_core::GitRepo {
+    pctrl_core::GitRepo {
+        id: row.get("id"),
+        name: row.get("name"),
+        path: row.get("path"),
+        remote_url: row.get("remote_url"),
+        credential_id: row.get("credential_id"),
     }
 }
@@ -0,0 +1,88 @@
+//! Inputs of `pctrl stats growth`: current counts, audited creations and
+//! removals, and the composition of what exists now
+
+use crate::Database;
+use chrono::{DateTime, Utc};
+use pctrl_core::growth::{Composition, GrowthEvent, Share, ENTITY_TYPES};
+use pctrl_core::{EntityType, Result};
+
+impl Database {
+    /// Current number of entities per type, trashed servers excluded
+    pub async fn entity_counts(&self) -> Result<Vec<(EntityType, u64)>> {
+        let mut counts = Vec::with_capacity(ENTITY_TYPES.len());
+        for entity_type in ENTITY_TYPES {
+            let query = match entity_type {
+                EntityType::Project => "SELECT COUNT(*) FROM projects",
+                EntityType::Server => "SELECT COUNT(*) FROM servers WHERE deleted_at IS NULL",
+                EntityType::Domain => "SELECT COUNT(*) FROM domains",
+                EntityType::Database => "SELECT COUNT(*) FROM databases",
+                EntityType::Script => "SELECT COUNT(*) FROM scripts",
+                EntityType::Credential => "SELECT COUNT(*) FROM credentials",
+            };
+            let (count,): (i64,) = sqlx::query_as(query)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            counts.push((entity_type, count as u64));
+        }
+        Ok(counts)
+    }
+
+    /// Every audited creation and removal, oldest first
+    pub async fn growth_events(&self) -> Result<Vec<GrowthEvent>> {
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT entity_type, entity_id, action, created_at FROM audit_log
+             WHERE action IN ('created', 'removed') ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(entity_type, entity_id, action, created_at)| {
+                Some(GrowthEvent {
+                    entity_type: entity_type.parse().ok()?,
+                    entity_id,
+                    action: action.parse().ok()?,
+                    at: DateTime::parse_from_rfc3339(&created_at)
+                        .ok()?
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect())
+    }
+
+    /// Servers by provider, projects by status and domains by type
+    pub async fn composition(&self) -> Result<Composition> {
+        Ok(Composition {
+            servers_by_provider: self
+                .group_counts(
+                    "SELECT LOWER(COALESCE(NULLIF(TRIM(provider), ''), 'none')), COUNT(*) FROM servers
+                     WHERE deleted_at IS NULL GROUP BY 1",
+                )
+                .await?,
+            projects_by_status: self
+                .group_counts("SELECT COALESCE(status, 'dev'), COUNT(*) FROM projects GROUP BY 1")
+                .await?,
+            domains_by_type: self
+                .group_counts("SELECT COALESCE(domain_type, 'production'), COUNT(*) FROM domains GROUP BY 1")
+                .await?,
+        })
+    }
+
+    /// `(name, count)` rows of a grouping query, most first
+    async fn group_counts(&self, query: &str) -> Result<Vec<Share>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(&format!("{} ORDER BY 2 DESC, 1", query))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|(name, count)| Share {
+                name,
+                count: count as u64,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,142 @@
+//! Hook execution and run history
+
+use crate::Database;
+use pctrl_core::hooks::{HookPayload, HookRun, HookRunner};
+use pctrl_core::{AuditAction, EntityType, Result};
+
+impl Database {
+    /// Enable hooks for this connection (frontends call this once at startup)
+    pub fn set_hook_runner(&self, runner: HookRunner) {
+        let _ = self.hooks.set(runner);
+    }
+
+    /// The configured hook runner, if any
+    pub fn hook_runner(&self) -> Option<&HookRunner> {
+        self.hooks.get()
+    }
+
+    /// Run the hooks of an event and record the results.
+    ///
+    /// Never fails: hook and bookkeeping errors are only logged, so a broken
+    /// hook can't break the operation that fired it.
+    pub async fn fire_hooks(&self, payload: &HookPayload) -> Vec<HookRun> {
+        let Some(runner) = self.hooks.get() else {
+            return Vec::new();
+        };
+
+        let runs = runner.run(payload).await;
+        for run in &runs {
+            if !run.success {
+                tracing::warn!("Hook {}/{} failed: {}", run.event, run.hook, run.output);
+            }
+            if let Err(e) = self.record_hook_run(run).await {
+                tracing::warn!("Failed to record hook run: {}", e);
+            }
+        }
+        runs
+    }
+
+    /// Fire the hooks of an entity mutation with the entity as payload data
+    pub(crate) async fn fire_entity_hooks(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        action: AuditAction,
+        summary: &str,
+    ) {
+        if self.hooks.get().is_none() {
+            return;
+        }
+
+        let data = match action {
+            AuditAction::Removed => serde_json::Value::Null,
+            _ => self
+                .entity_json(entity_type, entity_id)
+                .await
+                .unwrap_or_default(),
+        };
+        let payload = HookPayload::entity(entity_type, entity_id, action, summary, data);
+        self.fire_hooks(&payload).await;
+    }
+
+    /// Most recent run of a hook
+    pub async fn last_hook_run(&self, event: &str, hook: &str) -> Result<Option<HookRun>> {
+        let row: Option<HookRunRow> = sqlx::query_as(
+            "SELECT event, hook, success, exit_code, timed_out, output, duration_ms, created_at
+             FROM hook_runs WHERE event = ? AND hook = ? ORDER BY id DESC LIMIT 1",
+        )
+        .bind(event)
+        .bind(hook)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(
+            |(event, hook, success, exit_code, timed_out, output, duration_ms, ran_at)| HookRun {
+                event,
+                hook,
+                success,
+                exit_code,
+                timed_out,
+                output: output.unwrap_or_default(),
+                duration_ms,
+                ran_at,
+            },
+        ))
+    }
+
+    async fn record_hook_run(&self, run: &HookRun) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO hook_runs
+             (event, hook, success, exit_code, timed_out, output, duration_ms, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&run.event)
+        .bind(&run.hook)
+        .bind(run.success)
+        .bind(run.exit_code)
+        .bind(run.timed_out)
+        .bind(&run.output)
+        .bind(run.duration_ms)
+        .bind(&run.ran_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// An entity as JSON, the way it is serialized everywhere else.
+    /// Credentials leave out their secret data entirely.
+    async fn entity_json(&self, entity_type: EntityType, id: &str) -> Result<serde_json::Value> {
+        let value = match entity_type {
+            EntityType::Project => serde_json::to_value(self.get_project(id).await?),
+            EntityType::Server => serde_json::to_value(self.get_server(id).await?),
+            EntityType::Domain => serde_json::to_value(self.get_domain(id).await?),
+            EntityType::Database => serde_json::to_value(self.get_database_credentials(id).await?),
+            EntityType::Script => serde_json::to_value(self.get_script(id).await?),
+            EntityType::Credential => {
+                serde_json::to_value(self.get_credential(id).await?).map(|mut value| {
+                    if let Some(object) = value.as_object_mut() {
+                        object.remove("data");
+                    }
+                    value
+                })
+            }
+        };
+
+        value.map_err(|e| pctrl_core::Error::Database(e.to_string()))
+    }
+}
+
+/// Type alias for hook run row tuple
+type HookRunRow = (
+    String,
+    String,
+    bool,
+    Option<i32>,
+    bool,
+    Option<String>,
+    i64,
+    String,
+);
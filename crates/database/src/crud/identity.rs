@@ -0,0 +1,40 @@
+//! Identity for sharing secrets (`pctrl identity`)
+
+use super::now_timestamp;
+use crate::envelope::Identity;
+use crate::Database;
+use pctrl_core::Result;
+
+impl Database {
+    /// Store the identity, replacing an existing one. The private key is
+    /// encrypted with the database key, like credential data.
+    pub async fn save_identity(&self, identity: &Identity) -> Result<()> {
+        let secret = self.encrypt(&identity.to_bytes())?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO identity (id, public_key, secret, created_at) VALUES (1, ?, ?, ?)",
+        )
+        .bind(identity.public_key().to_string())
+        .bind(&secret)
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The stored identity, if `identity init` ran
+    pub async fn get_identity(&self) -> Result<Option<Identity>> {
+        let row: Option<(Vec<u8>,)> = sqlx::query_as("SELECT secret FROM identity WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let Some((secret,)) = row else {
+            return Ok(None);
+        };
+
+        let bytes: [u8; 32] = self.decrypt(&secret)?.try_into().map_err(|_| {
+            pctrl_core::Error::Database("Stored identity key is damaged".to_string())
+        })?;
+        Ok(Some(Identity::from_bytes(bytes)))
+    }
+}
@@ -0,0 +1,152 @@
+//! Ops journal (`pctrl server log`, `project log`, `domain log`)
+//!
+//! Entries are only ever inserted: there is no update or delete, and a
+//! correction is a new entry whose `amends` points at the one it corrects.
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::{EntityType, JournalEntry, Result};
+
+/// journal_entries row
+type JournalRow = (i64, String, String, String, String, String, Option<i64>);
+
+const JOURNAL_COLUMNS: &str = "id, entity_type, entity_id, author, text, created_at, amends";
+
+impl Database {
+    /// Append an entry to an entity's journal; `amends` must be an entry of
+    /// the same entity
+    pub async fn add_journal_entry(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        author: &str,
+        text: &str,
+        amends: Option<i64>,
+    ) -> Result<JournalEntry> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(pctrl_core::Error::Config(
+                "Journal entry is empty".to_string(),
+            ));
+        }
+        if let Some(id) = amends {
+            match self.get_journal_entry(id).await? {
+                Some(original)
+                    if original.entity_type == entity_type && original.entity_id == entity_id => {}
+                Some(original) => {
+                    return Err(pctrl_core::Error::Config(format!(
+                        "Journal entry #{} belongs to {} '{}'",
+                        id, original.entity_type, original.entity_id
+                    )))
+                }
+                None => {
+                    return Err(pctrl_core::Error::Config(format!(
+                        "Journal entry #{} not found",
+                        id
+                    )))
+                }
+            }
+        }
+
+        let created_at = now_timestamp();
+        let result = sqlx::query(
+            "INSERT INTO journal_entries (entity_type, entity_id, author, text, created_at, amends)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .bind(author)
+        .bind(text)
+        .bind(&created_at)
+        .bind(amends)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(JournalEntry {
+            id: result.last_insert_rowid(),
+            entity_type,
+            entity_id: entity_id.to_string(),
+            author: author.to_string(),
+            text: text.to_string(),
+            created_at,
+            amends,
+        })
+    }
+
+    pub async fn get_journal_entry(&self, id: i64) -> Result<Option<JournalEntry>> {
+        let sql = format!(
+            "SELECT {} FROM journal_entries WHERE id = ?",
+            JOURNAL_COLUMNS
+        );
+        let row: Option<JournalRow> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.and_then(row_to_entry))
+    }
+
+    /// An entity's latest `limit` entries, newest first
+    pub async fn list_journal_entries(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        limit: i64,
+    ) -> Result<Vec<JournalEntry>> {
+        let sql = format!(
+            "SELECT {} FROM journal_entries WHERE entity_type = ? AND entity_id = ?
+             ORDER BY id DESC LIMIT ?",
+            JOURNAL_COLUMNS
+        );
+        let rows: Vec<JournalRow> = sqlx::query_as(&sql)
+            .bind(entity_type.to_string())
+            .bind(entity_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().filter_map(row_to_entry).collect())
+    }
+
+    /// An entity's latest entry
+    pub async fn latest_journal_entry(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+    ) -> Result<Option<JournalEntry>> {
+        Ok(self
+            .list_journal_entries(entity_type, entity_id, 1)
+            .await?
+            .pop())
+    }
+
+    /// Every entry, oldest first
+    pub async fn list_all_journal_entries(&self) -> Result<Vec<JournalEntry>> {
+        let sql = format!(
+            "SELECT {} FROM journal_entries ORDER BY id",
+            JOURNAL_COLUMNS
+        );
+        let rows: Vec<JournalRow> = sqlx::query_as(&sql)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().filter_map(row_to_entry).collect())
+    }
+}
+
+fn row_to_entry(row: JournalRow) -> Option<JournalEntry> {
+    let (id, entity_type, entity_id, author, text, created_at, amends) = row;
+    Some(JournalEntry {
+        id,
+        entity_type: entity_type.parse().ok()?,
+        entity_id,
+        author,
+        text,
+        created_at,
+        amends,
+    })
+}
@@ -0,0 +1,56 @@
+//! Last-run reports of fan-out commands
+
+use crate::Database;
+use pctrl_core::fanout::FanoutReport;
+use pctrl_core::Result;
+
+use super::now_timestamp;
+
+impl Database {
+    /// Keep `report` as the last run of its command, replacing the previous one
+    pub async fn save_last_run(&self, report: &FanoutReport) -> Result<()> {
+        let json = serde_json::to_string(report)
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO last_runs (command, report, finished_at) VALUES (?, ?, ?)",
+        )
+        .bind(&report.command)
+        .bind(json)
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The last run of `command` (e.g. "project start"), if it ever ran
+    pub async fn get_last_run(&self, command: &str) -> Result<Option<FanoutReport>> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT report FROM last_runs WHERE command = ?")
+                .bind(command)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        row.map(|(json,)| {
+            serde_json::from_str(&json).map_err(|e| pctrl_core::Error::Database(e.to_string()))
+        })
+        .transpose()
+    }
+
+    /// Commands with a stored last run, by name
+    pub async fn list_last_runs(&self) -> Result<Vec<FanoutReport>> {
+        let rows: Vec<(String,)> = sqlx::query_as("SELECT report FROM last_runs ORDER BY command")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|(json,)| {
+                serde_json::from_str(&json).map_err(|e| pctrl_core::Error::Database(e.to_string()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,181 @@
+//! The write lease taken while migrating the schema (see
+//! [`pctrl_core::lease`])
+//!
+//! Taking the lease is one conditional upsert, so two processes racing for
+//! it can't both win: the row is only overwritten by its holder or once its
+//! heartbeat is older than the TTL.
+
+use super::format_timestamp;
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+use crate::Database;
+use chrono::{DateTime, Duration, Utc};
+use pctrl_core::lease::{self, LeaseDecision, WriteLease, LEASE_TTL_SECS};
+use pctrl_core::{Error, Result};
+
+/// write_lease row
+type LeaseRow = (String, String, i64, String, String);
+
+/// Version of this pctrl, as recorded in the lease and in `metadata`
+pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+impl Database {
+    /// The current write lease, expired or not; `None` in a file from
+    /// before leases
+    pub async fn write_lease(&self) -> Result<Option<WriteLease>> {
+        let exists: Option<(i64,)> = sqlx::query_as(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'write_lease'",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+        if exists.is_none() {
+            return Ok(None);
+        }
+
+        let row: Option<LeaseRow> = sqlx::query_as(
+            "SELECT holder, version, schema_version, acquired_at, heartbeat_at FROM write_lease WHERE id = 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(row.and_then(row_to_lease))
+    }
+
+    /// Take the write lease for `holder`, or renew it. A lease without a
+    /// heartbeat for [`LEASE_TTL_SECS`] is taken over; one that is alive
+    /// is an [`Error::Locked`].
+    pub async fn acquire_write_lease(
+        &self,
+        holder: &str,
+        now: DateTime<Utc>,
+    ) -> Result<LeaseDecision> {
+        let decision = lease::decide(self.write_lease().await?.as_ref(), holder, now);
+        if let LeaseDecision::Busy(current) = &decision {
+            return Err(Error::Locked(current.describe()));
+        }
+
+        let now_text = format_timestamp(now);
+        let cutoff = format_timestamp(now - Duration::seconds(LEASE_TTL_SECS));
+        let taken = sqlx::query(
+            "INSERT INTO write_lease (id, holder, version, schema_version, acquired_at, heartbeat_at)
+             VALUES (1, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                 acquired_at = CASE WHEN holder = excluded.holder THEN acquired_at ELSE excluded.acquired_at END,
+                 holder = excluded.holder,
+                 version = excluded.version,
+                 schema_version = excluded.schema_version,
+                 heartbeat_at = excluded.heartbeat_at
+             WHERE holder = excluded.holder OR heartbeat_at <= ?",
+        )
+        .bind(holder)
+        .bind(VERSION)
+        .bind(CURRENT_SCHEMA_VERSION)
+        .bind(&now_text)
+        .bind(&now_text)
+        .bind(&cutoff)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?
+        .rows_affected();
+
+        if taken == 0 {
+            // Someone else took it between the read and the write
+            return match self.write_lease().await? {
+                Some(current) => Err(Error::Locked(current.describe())),
+                None => Err(Error::Database("The write lease vanished".to_string())),
+            };
+        }
+        Ok(decision)
+    }
+
+    /// Refresh `holder`'s heartbeat; false if it no longer holds the lease
+    pub async fn heartbeat_write_lease(&self, holder: &str, now: DateTime<Utc>) -> Result<bool> {
+        heartbeat(&self.pool, holder, now).await
+    }
+
+    /// Give the lease up; false if `holder` didn't hold it
+    pub async fn release_write_lease(&self, holder: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM write_lease WHERE id = 1 AND holder = ?")
+            .bind(holder)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Schema version recorded in the file
+    pub async fn schema_version(&self) -> Result<i32> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT CAST(value AS TEXT) FROM metadata WHERE key = 'schema_version'")
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(row.and_then(|(v,)| v.parse().ok()).unwrap_or(1))
+    }
+
+    /// Version of the pctrl that last opened the file for writing
+    pub async fn writer_version(&self) -> Result<Option<String>> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT CAST(value AS TEXT) FROM metadata WHERE key = 'pctrl_version'")
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(row.map(|(v,)| v))
+    }
+
+    /// Note this pctrl's version as the file's last writer
+    pub(crate) async fn record_writer_version(&self) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES ('pctrl_version', ?)")
+            .bind(VERSION)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Refuse to open while another process holds a live lease
+    pub(crate) async fn check_write_lease(&self, holder: &str) -> Result<()> {
+        match self.write_lease().await? {
+            Some(current) if current.holder != holder && !current.is_expired(Utc::now()) => {
+                Err(Error::Locked(current.describe()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) async fn heartbeat<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    holder: &str,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let result = sqlx::query("UPDATE write_lease SET heartbeat_at = ? WHERE id = 1 AND holder = ?")
+        .bind(format_timestamp(now))
+        .bind(holder)
+        .execute(executor)
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+fn row_to_lease(row: LeaseRow) -> Option<WriteLease> {
+    let (holder, version, schema_version, acquired_at, heartbeat_at) = row;
+    let parse = |ts: &str| {
+        DateTime::parse_from_rfc3339(ts)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    };
+    Some(WriteLease {
+        holder,
+        version,
+        schema_version: schema_version as i32,
+        acquired_at: parse(&acquired_at)?,
+        heartbeat_at: parse(&heartbeat_at)?,
+    })
+}
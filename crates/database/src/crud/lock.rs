@@ -0,0 +1,196 @@
+//! Advisory entity lock operations
+//!
+//! Locks are purely advisory: they protect against two people editing the
+//! same record of a synced database file, not against concurrent writers.
+
+use super::references::entity_table;
+use super::{format_timestamp, now_timestamp};
+use crate::Database;
+use chrono::{Duration, Utc};
+use pctrl_core::{EntityLock, EntityType, Result};
+
+impl Database {
+    /// Lock an entity for `ttl`. The holder's own lock is renewed and an
+    /// expired one replaced; another holder's active lock is refused with
+    /// [`Error::Locked`](pctrl_core::Error::Locked). Checking and taking the
+    /// lock is one statement, so two callers can't both get it.
+    pub async fn lock_entity(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        holder: &str,
+        reason: Option<&str>,
+        ttl: Duration,
+    ) -> Result<EntityLock> {
+        let now = Utc::now();
+        let lock = EntityLock {
+            entity_type,
+            entity_id: entity_id.to_string(),
+            entity_name: self.entity_name(entity_type, entity_id).await?,
+            holder: holder.to_string(),
+            reason: reason.map(|r| r.to_string()),
+            created_at: format_timestamp(now),
+            expires_at: format_timestamp(now + ttl),
+        };
+
+        let result = sqlx::query(
+            "INSERT INTO entity_locks (entity_type, entity_id, holder, reason, created_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT (entity_type, entity_id) DO UPDATE SET
+                 holder = excluded.holder, reason = excluded.reason,
+                 created_at = excluded.created_at, expires_at = excluded.expires_at
+             WHERE entity_locks.holder = excluded.holder
+                OR entity_locks.expires_at <= excluded.created_at",
+        )
+        .bind(lock.entity_type.to_string())
+        .bind(&lock.entity_id)
+        .bind(&lock.holder)
+        .bind(&lock.reason)
+        .bind(&lock.created_at)
+        .bind(&lock.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            let held = match self.get_entity_lock(entity_type, entity_id).await? {
+                Some(current) => current.describe(),
+                None => format!("{} '{}' is locked", entity_type, lock.entity_name),
+            };
+            return Err(pctrl_core::Error::Locked(held));
+        }
+
+        Ok(lock)
+    }
+
+    /// Get the active lock on an entity (expired locks are ignored)
+    pub async fn get_entity_lock(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+    ) -> Result<Option<EntityLock>> {
+        self.prune_expired_locks().await?;
+
+        let row: Option<LockRow> = sqlx::query_as(
+            "SELECT entity_type, entity_id, holder, reason, created_at, expires_at FROM entity_locks WHERE entity_type = ? AND entity_id = ?",
+        )
+        .bind(entity_type.to_string())
+        .bind(entity_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        match row {
+            Some(row) => self.row_to_lock(row).await,
+            None => Ok(None),
+        }
+    }
+
+    /// List all active locks
+    pub async fn list_entity_locks(&self) -> Result<Vec<EntityLock>> {
+        self.prune_expired_locks().await?;
+
+        let rows: Vec<LockRow> = sqlx::query_as(
+            "SELECT entity_type, entity_id, holder, reason, created_at, expires_at FROM entity_locks ORDER BY expires_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let mut locks = Vec::with_capacity(rows.len());
+        for row in rows {
+            locks.extend(self.row_to_lock(row).await?);
+        }
+        Ok(locks)
+    }
+
+    /// Release a lock. Returns false if no active lock existed.
+    pub async fn unlock_entity(&self, entity_type: EntityType, entity_id: &str) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM entity_locks WHERE entity_type = ? AND entity_id = ?")
+                .bind(entity_type.to_string())
+                .bind(entity_id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Allow writes to entities locked by someone else (`--override-lock`)
+    pub fn set_lock_override(&self, enabled: bool) {
+        self.lock_override
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Refuse a write if the entity is locked by another holder.
+    ///
+    /// Called from every mutating CRUD method so no handler can forget it.
+    pub(crate) async fn check_lock(&self, entity_type: EntityType, entity_id: &str) -> Result<()> {
+        if self
+            .lock_override
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return Ok(());
+        }
+
+        if let Some(lock) = self.get_entity_lock(entity_type, entity_id).await? {
+            if lock.holder != pctrl_core::current_holder() {
+                return Err(pctrl_core::Error::Locked(format!(
+                    "{} (use --override-lock to write anyway)",
+                    lock.describe()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lazily delete expired locks
+    async fn prune_expired_locks(&self) -> Result<()> {
+        sqlx::query("DELETE FROM entity_locks WHERE expires_at <= ?")
+            .bind(now_timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Name of a locked entity, for messages; its ID once it's gone
+    async fn entity_name(&self, entity_type: EntityType, entity_id: &str) -> Result<String> {
+        let (table, name_column) = entity_table(entity_type);
+        let row: Option<(String,)> = sqlx::query_as(&format!(
+            "SELECT {} FROM {} WHERE id = ?",
+            name_column, table
+        ))
+        .bind(entity_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map_or_else(|| entity_id.to_string(), |(name,)| name))
+    }
+
+    /// Helper to convert a row tuple to EntityLock; `None` for an unknown
+    /// entity type
+    async fn row_to_lock(&self, row: LockRow) -> Result<Option<EntityLock>> {
+        let (entity_type, entity_id, holder, reason, created_at, expires_at) = row;
+        let Ok(entity_type) = entity_type.parse() else {
+            return Ok(None);
+        };
+
+        Ok(Some(EntityLock {
+            entity_type,
+            entity_name: self.entity_name(entity_type, &entity_id).await?,
+            entity_id,
+            holder,
+            reason,
+            created_at,
+            expires_at,
+        }))
+    }
+}
+
+/// Type alias for lock row tuple
+type LockRow = (String, String, String, Option<String>, String, String);
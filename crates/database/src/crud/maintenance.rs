@@ -0,0 +1,213 @@
+//! Maintenance windows (`pctrl project maintenance`)
+
+use super::format_timestamp;
+use crate::Database;
+use chrono::{DateTime, Utc};
+use pctrl_core::diff::diff;
+use pctrl_core::maintenance::{EndedWindow, MaintenanceScope, MaintenanceWindow};
+use pctrl_core::{AuditAction, EntityType, Project, ProjectStatus, Result};
+
+/// maintenance_windows row
+type WindowRow = (String, String, Option<String>, String, String);
+
+const WINDOW_COLUMNS: &str = "project_id, previous_status, reason, started_at, expires_at";
+
+impl Database {
+    /// Put a project into maintenance until `expires_at`.
+    ///
+    /// Starting a window while one is open extends it and keeps the status
+    /// that was saved when the first one started.
+    pub async fn start_maintenance(
+        &self,
+        project: &Project,
+        reason: Option<&str>,
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<MaintenanceWindow> {
+        self.check_lock(EntityType::Project, &project.id).await?;
+
+        let previous_status = match self.get_maintenance_window(&project.id).await? {
+            Some(open) => open.previous_status,
+            None if project.status == ProjectStatus::Maintenance => ProjectStatus::default(),
+            None => project.status.clone(),
+        };
+        let window = MaintenanceWindow {
+            project_id: project.id.clone(),
+            previous_status,
+            reason: reason.map(String::from),
+            started_at: format_timestamp(now),
+            expires_at: format_timestamp(expires_at),
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO maintenance_windows
+             (project_id, previous_status, reason, started_at, expires_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&window.project_id)
+        .bind(window.previous_status.to_string())
+        .bind(&window.reason)
+        .bind(&window.started_at)
+        .bind(&window.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        self.set_project_status(project, ProjectStatus::Maintenance)
+            .await?;
+        Ok(window)
+    }
+
+    /// The open maintenance window of a project
+    pub async fn get_maintenance_window(
+        &self,
+        project_id: &str,
+    ) -> Result<Option<MaintenanceWindow>> {
+        let row: Option<WindowRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM maintenance_windows WHERE project_id = ?",
+            WINDOW_COLUMNS
+        ))
+        .bind(project_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_window))
+    }
+
+    /// All open maintenance windows, soonest to expire first
+    pub async fn list_maintenance_windows(&self) -> Result<Vec<MaintenanceWindow>> {
+        let rows: Vec<WindowRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM maintenance_windows ORDER BY expires_at, project_id",
+            WINDOW_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_window).collect())
+    }
+
+    /// End a project's maintenance window now, whether or not it expired
+    pub async fn end_maintenance(&self, project_id: &str) -> Result<Option<EndedWindow>> {
+        self.check_lock(EntityType::Project, project_id).await?;
+        match self.get_maintenance_window(project_id).await? {
+            Some(window) => self.close_window(&window).await,
+            None => Ok(None),
+        }
+    }
+
+    /// End every window that expired by `now` and restore the projects'
+    /// previous status. Called by the monitor on each cycle and by every
+    /// pctrl invocation, so windows end even without a running monitor.
+    pub async fn end_expired_maintenance(&self, now: DateTime<Utc>) -> Result<Vec<EndedWindow>> {
+        let mut ended = Vec::new();
+        for window in self.list_maintenance_windows().await? {
+            if window.is_expired(now) {
+                ended.extend(self.close_window(&window).await?);
+            }
+        }
+        Ok(ended)
+    }
+
+    /// Servers of the projects in maintenance at `now`: servers linked to
+    /// the project and the server it ships to
+    pub async fn maintenance_scope(&self, now: DateTime<Utc>) -> Result<MaintenanceScope> {
+        let mut scope = MaintenanceScope::default();
+        for window in self.list_maintenance_windows().await? {
+            if window.is_expired(now) {
+                continue;
+            }
+            let Some(project) = self.get_project(&window.project_id).await? else {
+                continue;
+            };
+
+            let rows: Vec<(String,)> = sqlx::query_as(
+                "SELECT resource_id FROM project_resources
+                 WHERE project_id = ? AND resource_type = 'server'
+                 UNION
+                 SELECT server_id FROM project_deploy
+                 WHERE project_id = ? AND server_id IS NOT NULL",
+            )
+            .bind(&project.id)
+            .bind(&project.id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+            for (server,) in rows {
+                scope.insert(server, project.name.clone());
+            }
+        }
+        Ok(scope)
+    }
+
+    /// Delete a window and restore the project's status, unless it was
+    /// changed by hand during the window
+    async fn close_window(&self, window: &MaintenanceWindow) -> Result<Option<EndedWindow>> {
+        sqlx::query("DELETE FROM maintenance_windows WHERE project_id = ?")
+            .bind(&window.project_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let Some(project) = self.get_project(&window.project_id).await? else {
+            return Ok(None);
+        };
+        let restored = if project.status == ProjectStatus::Maintenance {
+            self.set_project_status(&project, window.previous_status.clone())
+                .await?;
+            Some(window.previous_status.clone())
+        } else {
+            None
+        };
+
+        tracing::info!(
+            "Maintenance of '{}' ended, status {}",
+            project.name,
+            restored
+                .as_ref()
+                .map_or("left unchanged".to_string(), |s| format!("back to {}", s))
+        );
+        Ok(Some(EndedWindow {
+            project_id: project.id,
+            project_name: project.name,
+            restored,
+        }))
+    }
+
+    /// Change only a project's status. Skips the lock check: windows end
+    /// on their own, whoever holds the project.
+    async fn set_project_status(&self, project: &Project, status: ProjectStatus) -> Result<()> {
+        sqlx::query("UPDATE projects SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(status.to_string())
+            .bind(&project.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let updated = Project {
+            status,
+            ..project.clone()
+        };
+        self.record_audit_with_changes(
+            EntityType::Project,
+            &project.id,
+            AuditAction::Updated,
+            &project.name,
+            &diff(project, &updated),
+        )
+        .await
+    }
+}
+
+fn row_to_window(row: WindowRow) -> MaintenanceWindow {
+    let (project_id, previous_status, reason, started_at, expires_at) = row;
+    MaintenanceWindow {
+        project_id,
+        previous_status: previous_status.parse().unwrap_or_default(),
+        reason,
+        started_at,
+        expires_at,
+    }
+}
@@ -2,15 +2,81 @@
 //!
 //! Each module implements methods for the Database struct.
 
+mod activity;
+mod anonymize;
+mod audit;
+mod changelog;
+mod compose;
 mod config;
+mod config_export;
+mod container;
 mod coolify;
 mod credential;
 mod database_creds;
+mod demo;
+mod deploy_key;
+mod discovery;
 mod docker;
+mod doctor;
 mod domain;
+mod domain_base;
+mod ensure;
+mod entity_metadata;
+mod facts;
 mod git;
+mod growth;
+mod hooks;
+mod identity;
+mod journal;
+mod last_run;
+pub(crate) mod lease;
+mod lock;
+mod maintenance;
+pub(crate) mod monitor;
+mod names;
+mod network;
+mod preflight;
 mod project;
+mod project_clone;
 mod project_resources;
+mod propagation;
+mod references;
+mod sample;
 mod script;
+mod script_revision;
+mod script_run;
+mod search;
 mod server;
+mod service;
+mod settings;
+mod ship;
+pub(crate) mod short_ref;
+mod snapshot;
 mod ssh;
+mod table_export;
+mod usage;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// Format a timestamp the way bookkeeping tables (locks, audit log, history)
+/// store it: UTC, second precision, fixed width so it compares as text
+pub(crate) fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Current time as a bookkeeping timestamp
+pub(crate) fn now_timestamp() -> String {
+    format_timestamp(Utc::now())
+}
+
+/// Escape LIKE wildcards so the text matches literally (with `ESCAPE '\'`)
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// `?, ?, ?` for an `IN (...)` clause with `count` values
+pub(crate) fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
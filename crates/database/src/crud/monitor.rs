@@ -0,0 +1,82 @@
+//! Monitor bookkeeping (`pctrl monitor`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::monitor::MonitorState;
+use pctrl_core::Result;
+
+/// monitor_state row without the id
+pub(crate) type MonitorRow = (String, i64, i64, i64, i64, Option<String>);
+
+impl Database {
+    /// Record a completed monitor cycle, replacing the previous one
+    pub async fn record_monitor_cycle(&self, state: &MonitorState) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO monitor_state
+             (id, last_cycle_at, servers_checked, servers_down, duration_ms, interval_secs, heartbeat_error)
+             VALUES (1, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&state.last_cycle_at)
+        .bind(state.servers_checked as i64)
+        .bind(state.servers_down as i64)
+        .bind(state.duration_ms as i64)
+        .bind(state.interval_secs as i64)
+        .bind(&state.heartbeat_error)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Record a server going down (with the error) or coming back up, for
+    /// the activity feed
+    pub async fn record_monitor_event(
+        &self,
+        server_id: &str,
+        server_name: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO monitor_events (server_id, server_name, up, error, created_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(server_name)
+        .bind(error.is_none())
+        .bind(error)
+        .bind(now_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The last completed monitor cycle, if the monitor ever ran
+    pub async fn get_monitor_state(&self) -> Result<Option<MonitorState>> {
+        let row: Option<MonitorRow> = sqlx::query_as(MONITOR_STATE_SQL)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_monitor_state))
+    }
+}
+
+pub(crate) const MONITOR_STATE_SQL: &str =
+    "SELECT last_cycle_at, servers_checked, servers_down, duration_ms, interval_secs, heartbeat_error
+     FROM monitor_state WHERE id = 1";
+
+pub(crate) fn row_to_monitor_state(
+    (last_cycle_at, servers_checked, servers_down, duration_ms, interval_secs, heartbeat_error): MonitorRow,
+) -> MonitorState {
+    MonitorState {
+        last_cycle_at,
+        servers_checked: servers_checked as u32,
+        servers_down: servers_down as u32,
+        duration_ms: duration_ms as u64,
+        interval_secs: interval_secs as u64,
+        heartbeat_error,
+    }
+}
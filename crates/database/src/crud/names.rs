@@ -0,0 +1,122 @@
+//! Names that are unique regardless of case
+//!
+//! Projects, servers, domains, databases, scripts and credentials are looked
+//! up by name ignoring case, so "Blog" and "blog" can't both exist. Saves
+//! refuse a name another row holds, `create_*` also refuses an existing
+//! ID, and `update_*` needs the row to exist. The unique indexes from
+//! schema v14 back this up for writers that bypass these checks.
+
+use crate::Database;
+use pctrl_core::{Error, Result};
+
+/// A table whose names are unique ignoring case
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Names {
+    /// "Project", ... for messages
+    pub kind: &'static str,
+    pub table: &'static str,
+    pub column: &'static str,
+    /// Rows whose names count; a trashed server's doesn't
+    pub live: &'static str,
+}
+
+pub(crate) const PROJECTS: Names = Names {
+    kind: "Project",
+    table: "projects",
+    column: "name",
+    live: "1",
+};
+
+pub(crate) const SERVERS: Names = Names {
+    kind: "Server",
+    table: "servers",
+    column: "name",
+    live: "deleted_at IS NULL",
+};
+
+pub(crate) const DOMAINS: Names = Names {
+    kind: "Domain",
+    table: "domains",
+    column: "domain",
+    live: "1",
+};
+
+pub(crate) const DATABASES: Names = Names {
+    kind: "Database",
+    table: "databases",
+    column: "name",
+    live: "1",
+};
+
+pub(crate) const SCRIPTS: Names = Names {
+    kind: "Script",
+    table: "scripts",
+    column: "name",
+    live: "1",
+};
+
+pub(crate) const CREDENTIALS: Names = Names {
+    kind: "Credential",
+    table: "credentials",
+    column: "name",
+    live: "1",
+};
+
+impl Database {
+    /// Fail with [`Error::Conflict`] when a row other than `id` holds `name`
+    /// ignoring case; when `creating`, a row with `id` itself (trashed or
+    /// not) conflicts too
+    pub(crate) async fn check_name(
+        &self,
+        names: Names,
+        id: &str,
+        name: &str,
+        creating: bool,
+    ) -> Result<()> {
+        let sql = format!(
+            "SELECT {column} FROM {table}
+             WHERE ({column} = ? COLLATE NOCASE AND {live} AND id != ?) OR (? AND id = ?)
+             LIMIT 1",
+            column = names.column,
+            table = names.table,
+            live = names.live
+        );
+        let taken: Option<(String,)> = sqlx::query_as(&sql)
+            .bind(name)
+            .bind(id)
+            .bind(creating)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        match taken {
+            Some((taken,)) => Err(Error::Conflict(taken)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail unless a (live) row with `id` exists, before an update
+    pub(crate) async fn check_exists(&self, names: Names, id: &str) -> Result<()> {
+        let sql = format!(
+            "SELECT 1 FROM {} WHERE id = ? AND {}",
+            names.table, names.live
+        );
+        let found: Option<(i64,)> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        match found {
+            Some(_) => Ok(()),
+            None => Err(Error::Config(format!("{} '{}' not found", names.kind, id))),
+        }
+    }
+}
+
+/// A failed write of `name`: a unique index violation is a conflict
+pub(crate) fn write_error(name: &str) -> impl Fn(sqlx::Error) -> Error + '_ {
+    move |e| match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => Error::Conflict(name.to_string()),
+        _ => Error::Database(e.to_string()),
+    }
+}
@@ -0,0 +1,142 @@
+//! Docker network topology operations (filled by `docker sync`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::{ContainerNetwork, DockerNetwork, PublishedPort, Result};
+
+impl Database {
+    /// Replace the stored networks, memberships and published ports of a host
+    pub async fn replace_network_topology(
+        &self,
+        host_id: &str,
+        networks: &[DockerNetwork],
+        memberships: &[ContainerNetwork],
+        ports: &[PublishedPort],
+    ) -> Result<()> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let synced_at = now_timestamp();
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+
+        for table in ["docker_networks", "container_networks", "container_ports"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE host_id = ?", table))
+                .bind(host_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+        }
+
+        for network in networks {
+            sqlx::query(
+                "INSERT OR REPLACE INTO docker_networks (host_id, name, driver, synced_at)
+                 VALUES (?, ?, ?, ?)",
+            )
+            .bind(host_id)
+            .bind(&network.name)
+            .bind(&network.driver)
+            .bind(&synced_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        for member in memberships {
+            sqlx::query(
+                "INSERT OR REPLACE INTO container_networks
+                 (host_id, container, network, ip_address, synced_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(host_id)
+            .bind(&member.container)
+            .bind(&member.network)
+            .bind(&member.ip_address)
+            .bind(&synced_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        for port in ports {
+            sqlx::query(
+                "INSERT INTO container_ports
+                 (host_id, container, host_port, container_port, protocol) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(host_id)
+            .bind(&port.container)
+            .bind(port.host_port)
+            .bind(port.container_port)
+            .bind(&port.protocol)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        tx.commit().await.map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Networks of a host, sorted by name
+    pub async fn list_docker_networks(&self, host_id: &str) -> Result<Vec<DockerNetwork>> {
+        let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            "SELECT host_id, name, driver FROM docker_networks WHERE host_id = ? ORDER BY name",
+        )
+        .bind(host_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(host_id, name, driver)| DockerNetwork {
+                host_id,
+                name,
+                driver,
+            })
+            .collect())
+    }
+
+    /// Network memberships of all hosts
+    pub async fn list_container_networks(&self) -> Result<Vec<ContainerNetwork>> {
+        let rows: Vec<(String, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT host_id, container, network, ip_address FROM container_networks
+             ORDER BY host_id, network, container",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(host_id, container, network, ip_address)| ContainerNetwork {
+                    host_id,
+                    container,
+                    network,
+                    ip_address,
+                },
+            )
+            .collect())
+    }
+
+    /// Published ports of all hosts
+    pub async fn list_published_ports(&self) -> Result<Vec<PublishedPort>> {
+        let rows: Vec<(String, String, i64, i64, String)> = sqlx::query_as(
+            "SELECT host_id, container, host_port, container_port, protocol FROM container_ports
+             ORDER BY host_id, container, host_port",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(host_id, container, host_port, container_port, protocol)| PublishedPort {
+                    host_id,
+                    container,
+                    host_port: host_port as u16,
+                    container_port: container_port as u16,
+                    protocol,
+                },
+            )
+            .collect())
+    }
+}
@@ -0,0 +1,64 @@
+//! Per-project pre-flight settings for `project deploy`
+
+use crate::Database;
+use pctrl_core::preflight::{CheckKind, PreflightConfig};
+use pctrl_core::{EntityType, Result};
+
+impl Database {
+    /// Pre-flight settings of a project (defaults if none were saved)
+    pub async fn get_preflight_config(&self, project_id: &str) -> Result<PreflightConfig> {
+        let row: Option<(Option<String>, String)> = sqlx::query_as(
+            "SELECT health_url, advisory FROM project_preflight WHERE project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let Some((health_url, advisory)) = row else {
+            return Ok(PreflightConfig::default());
+        };
+
+        // Unknown kinds (from a newer pctrl) are ignored
+        let mut advisory: Vec<CheckKind> = advisory
+            .split(',')
+            .filter_map(|k| k.trim().parse().ok())
+            .collect();
+        advisory.sort();
+        advisory.dedup();
+
+        Ok(PreflightConfig {
+            health_url,
+            advisory,
+        })
+    }
+
+    /// Save a project's pre-flight settings
+    pub async fn save_preflight_config(
+        &self,
+        project_id: &str,
+        config: &PreflightConfig,
+    ) -> Result<()> {
+        self.check_lock(EntityType::Project, project_id).await?;
+
+        let advisory = config
+            .advisory
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO project_preflight (project_id, health_url, advisory)
+             VALUES (?, ?, ?)",
+        )
+        .bind(project_id)
+        .bind(&config.health_url)
+        .bind(advisory)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
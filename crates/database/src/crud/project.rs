@@ -1,17 +1,29 @@
 //! Project CRUD operations
 
+use super::names;
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::diff::diff;
+use pctrl_core::{AuditAction, EntityType, Result};
 
 impl Database {
     /// Save a project
     pub async fn save_project(&self, project: &pctrl_core::Project) -> Result<()> {
+        self.insert_project(project, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_project(&self, project: &pctrl_core::Project, creating: bool) -> Result<()> {
+        self.check_lock(EntityType::Project, &project.id).await?;
+        self.check_name(names::PROJECTS, &project.id, &project.name, creating)
+            .await?;
+        let previous = self.get_project(&project.id).await?;
+
         let stack = serde_json::to_string(&project.stack)
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
         sqlx::query(
-            "INSERT OR REPLACE INTO projects (id, name, description, stack, status, color, icon, notes, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+            "INSERT OR REPLACE INTO projects (id, name, description, stack, status, color, icon, notes, updated_at, short_ref)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP, (SELECT short_ref FROM projects WHERE id = ?))",
         )
         .bind(&project.id)
         .bind(&project.name)
@@ -21,13 +33,42 @@ impl Database {
         .bind(&project.color)
         .bind(&project.icon)
         .bind(&project.notes)
+        .bind(&project.id)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&project.name))?;
+        self.assign_short_ref(EntityType::Project, &project.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, project))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Project,
+            &project.id,
+            AuditAction::for_save(previous.is_some()),
+            &project.name,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add a project; fails with [`pctrl_core::Error::Conflict`] when its ID
+    /// or its name (ignoring case) is taken
+    pub async fn create_project(&self, project: &pctrl_core::Project) -> Result<()> {
+        self.insert_project(project, true).await
+    }
+
+    /// Change an existing project; fails with [`pctrl_core::Error::Conflict`]
+    /// when another project has its name (ignoring case)
+    pub async fn update_project(&self, project: &pctrl_core::Project) -> Result<()> {
+        self.check_exists(names::PROJECTS, &project.id).await?;
+        self.save_project(project).await
+    }
+
     /// Get a project by ID
     pub async fn get_project(&self, id: &str) -> Result<Option<pctrl_core::Project>> {
         let row: Option<(
@@ -95,6 +136,9 @@ impl Database {
 
     /// Remove a project by ID
     pub async fn remove_project(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Project, id).await?;
+        let previous = self.get_project(id).await?;
+
         // Also remove all project_resources for this project
         sqlx::query("DELETE FROM project_resources WHERE project_id = ?")
             .bind(id)
@@ -102,13 +146,38 @@ impl Database {
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
+        sqlx::query("DELETE FROM project_preflight WHERE project_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        sqlx::query("DELETE FROM project_deploy WHERE project_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        sqlx::query("DELETE FROM maintenance_windows WHERE project_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
         let result = sqlx::query("DELETE FROM projects WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let removed = result.rows_affected() > 0;
+        if removed {
+            let summary = previous.map(|e| e.name).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Project, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
     }
 
     /// Check if a project exists
@@ -123,7 +192,7 @@ impl Database {
     }
 
     /// Helper to convert a row tuple to Project
-    fn row_to_project(
+    pub(super) fn row_to_project(
         row: (
             String,
             String,
@@ -0,0 +1,133 @@
+//! Project cloning (`pctrl project clone`)
+
+use crate::Database;
+use pctrl_core::project_clone::ClonePlan;
+use pctrl_core::script_namespace;
+use pctrl_core::{AuditAction, EntityType, Result};
+
+impl Database {
+    /// Create everything in a clone plan in one transaction: the project,
+    /// its copied scripts and domains, and its links
+    pub async fn clone_project(&self, plan: &ClonePlan) -> Result<()> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let project = &plan.project;
+        let stack = serde_json::to_string(&project.stack)
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+
+        sqlx::query(
+            "INSERT INTO projects (id, name, description, stack, status, color, icon, notes)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&project.id)
+        .bind(&project.name)
+        .bind(&project.description)
+        .bind(&stack)
+        .bind(project.status.to_string())
+        .bind(&project.color)
+        .bind(&project.icon)
+        .bind(&project.notes)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+
+        for cloned in &plan.scripts {
+            let script = &cloned.script;
+            sqlx::query(
+                "INSERT INTO scripts (id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, working_dir, env, run_window, namespace)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&script.id)
+            .bind(&script.name)
+            .bind(&script.description)
+            .bind(&script.command)
+            .bind(script.script_type.to_string())
+            .bind(&script.server_id)
+            .bind(&script.project_id)
+            .bind(&script.docker_host_id)
+            .bind(&script.container_id)
+            .bind(script.dangerous)
+            .bind(&script.working_dir)
+            .bind((!script.env.is_empty()).then(|| serde_json::to_string(&script.env).unwrap_or_default()))
+            .bind(&script.run_window)
+            .bind(script_namespace::namespace(&script.name))
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        for cloned in &plan.domains {
+            let domain = &cloned.domain;
+            sqlx::query(
+                "INSERT INTO domains (id, domain, domain_type, ssl, server_id, notes)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&domain.id)
+            .bind(&domain.domain)
+            .bind(domain.domain_type.to_string())
+            .bind(domain.ssl)
+            .bind(&domain.server_id)
+            .bind(&domain.notes)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        for link in &plan.links {
+            sqlx::query(
+                "INSERT INTO project_resources (id, project_id, resource_type, resource_id, role, notes, start_order)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&link.id)
+            .bind(&link.project_id)
+            .bind(link.resource_type.to_string())
+            .bind(&link.resource_id)
+            .bind(&link.role)
+            .bind(&link.notes)
+            .bind(link.start_order)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+
+        tx.commit().await.map_err(db_err)?;
+
+        self.assign_short_ref(EntityType::Project, &project.id)
+            .await?;
+        for cloned in &plan.scripts {
+            self.assign_short_ref(EntityType::Script, &cloned.script.id)
+                .await?;
+        }
+        for cloned in &plan.domains {
+            self.assign_short_ref(EntityType::Domain, &cloned.domain.id)
+                .await?;
+        }
+        self.record_audit(
+            EntityType::Project,
+            &project.id,
+            AuditAction::Created,
+            &project.name,
+        )
+        .await?;
+        for cloned in &plan.scripts {
+            self.record_audit(
+                EntityType::Script,
+                &cloned.script.id,
+                AuditAction::Created,
+                &cloned.script.name,
+            )
+            .await?;
+        }
+        for cloned in &plan.domains {
+            self.record_audit(
+                EntityType::Domain,
+                &cloned.domain.id,
+                AuditAction::Created,
+                &cloned.domain.domain,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Project Resource linking operations
 
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::bundle::{link_refs, ProjectBundle};
+use pctrl_core::{EntityType, Project, ResourceType, Result, Script};
 
 impl Database {
     /// Link a resource to a project
@@ -9,9 +10,13 @@ impl Database {
         &self,
         resource: &pctrl_core::ProjectResource,
     ) -> Result<()> {
+        self.check_lock(EntityType::Project, &resource.project_id)
+            .await?;
+        // Replacing a link may take it from another project
+        self.check_link_lock(&resource.id).await?;
         sqlx::query(
-            "INSERT OR REPLACE INTO project_resources (id, project_id, resource_type, resource_id, role, notes)
-             VALUES (?, ?, ?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO project_resources (id, project_id, resource_type, resource_id, role, notes, start_order)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&resource.id)
         .bind(&resource.project_id)
@@ -19,6 +24,7 @@ impl Database {
         .bind(&resource.resource_id)
         .bind(&resource.role)
         .bind(&resource.notes)
+        .bind(resource.start_order)
         .execute(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
@@ -31,40 +37,94 @@ impl Database {
         &self,
         project_id: &str,
     ) -> Result<Vec<pctrl_core::ProjectResource>> {
-        let rows: Vec<(String, String, String, String, Option<String>, Option<String>)> =
-            sqlx::query_as(
-                "SELECT id, project_id, resource_type, resource_id, role, notes FROM project_resources WHERE project_id = ?",
-            )
-            .bind(project_id)
-            .fetch_all(&self.pool)
+        let rows: Vec<ResourceRow> = sqlx::query_as(
+            "SELECT id, project_id, resource_type, resource_id, role, notes, start_order FROM project_resources WHERE project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_resource).collect())
+    }
+
+    /// Get the resource links of all projects
+    pub async fn list_all_project_resources(&self) -> Result<Vec<pctrl_core::ProjectResource>> {
+        let rows: Vec<ResourceRow> = sqlx::query_as(
+            "SELECT id, project_id, resource_type, resource_id, role, notes, start_order FROM project_resources ORDER BY project_id, id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_resource).collect())
+    }
+
+    /// A project with its links and the servers, domains, databases and
+    /// scripts they point to. Takes one query for the project, one for the
+    /// links and one per linked entity type, however many links there are.
+    pub async fn resolve_project_bundle(&self, project_id: &str) -> Result<Option<ProjectBundle>> {
+        let Some(project) = self.get_project(project_id).await? else {
+            return Ok(None);
+        };
+        let links = self.get_project_resources(&project.id).await?;
+
+        let servers = self
+            .get_servers_by_ids(&link_refs(&links, ResourceType::Server))
+            .await?;
+        let domains = self
+            .get_domains_by_ids(&link_refs(&links, ResourceType::Domain))
+            .await?;
+        let databases = self
+            .get_database_credentials_by_ids(&link_refs(&links, ResourceType::Database))
+            .await?;
+        let scripts = self
+            .get_scripts_by_ids(&link_refs(&links, ResourceType::Script))
+            .await?;
+
+        Ok(Some(ProjectBundle {
+            project,
+            links,
+            servers,
+            domains,
+            databases,
+            scripts,
+        }))
+    }
+
+    pub(super) fn row_to_resource(row: ResourceRow) -> pctrl_core::ProjectResource {
+        let (id, project_id, resource_type, resource_id, role, notes, start_order) = row;
+        let resource_type = resource_type
+            .parse()
+            .unwrap_or(pctrl_core::ResourceType::Server);
+
+        pctrl_core::ProjectResource {
+            id,
+            project_id,
+            resource_type,
+            resource_id,
+            role,
+            notes,
+            start_order,
+        }
+    }
+
+    /// Set (or clear) the start order of a link. Returns false if the link doesn't exist.
+    pub async fn set_resource_start_order(&self, id: &str, order: Option<i32>) -> Result<bool> {
+        self.check_link_lock(id).await?;
+        let result = sqlx::query("UPDATE project_resources SET start_order = ? WHERE id = ?")
+            .bind(order)
+            .bind(id)
+            .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        let resources = rows
-            .into_iter()
-            .map(
-                |(id, project_id, resource_type, resource_id, role, notes)| {
-                    let resource_type = resource_type
-                        .parse()
-                        .unwrap_or(pctrl_core::ResourceType::Server);
-
-                    pctrl_core::ProjectResource {
-                        id,
-                        project_id,
-                        resource_type,
-                        resource_id,
-                        role,
-                        notes,
-                    }
-                },
-            )
-            .collect();
-
-        Ok(resources)
+        Ok(result.rows_affected() > 0)
     }
 
     /// Unlink a resource from a project
     pub async fn unlink_project_resource(&self, id: &str) -> Result<bool> {
+        self.check_link_lock(id).await?;
         let result = sqlx::query("DELETE FROM project_resources WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
@@ -74,6 +134,22 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Refuse changing a link whose project someone else has locked; an
+    /// unknown link passes
+    async fn check_link_lock(&self, id: &str) -> Result<()> {
+        let row: Option<(String,)> =
+            sqlx::query_as("SELECT project_id FROM project_resources WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        match row {
+            Some((project_id,)) => self.check_lock(EntityType::Project, &project_id).await,
+            None => Ok(()),
+        }
+    }
+
     /// Get projects that have a specific resource linked
     pub async fn get_projects_for_resource(
         &self,
@@ -91,4 +167,77 @@ impl Database {
 
         Ok(rows.into_iter().map(|(id,)| id).collect())
     }
+
+    /// Live projects a resource is linked to.
+    ///
+    /// `ids` lists every identifier the resource may have been linked by
+    /// (links store whatever the user typed, ID or name).
+    pub async fn live_projects_for_resource(
+        &self,
+        resource_type: &ResourceType,
+        ids: &[&str],
+    ) -> Result<Vec<Project>> {
+        let mut projects = Vec::new();
+        for id in ids {
+            for project_id in self.get_projects_for_resource(resource_type, id).await? {
+                if let Some(project) = self.get_project(&project_id).await? {
+                    projects.push(project);
+                }
+            }
+        }
+        Ok(live_only(projects))
+    }
+
+    /// Live projects a script run could affect.
+    ///
+    /// Follows the script's own project, its server (to every project the
+    /// server is linked to) and its container. A server hosting both a Live
+    /// and a Dev project counts as Live.
+    pub async fn live_projects_for_script_target(&self, script: &Script) -> Result<Vec<Project>> {
+        let mut projects = Vec::new();
+
+        if let Some(project_id) = &script.project_id {
+            if let Some(project) = self.get_project(project_id).await? {
+                projects.push(project);
+            }
+        }
+
+        if let Some(server_id) = &script.server_id {
+            let name = self.get_server(server_id).await?.map(|s| s.name);
+            let mut ids = vec![server_id.as_str()];
+            ids.extend(name.as_deref());
+            projects.extend(
+                self.live_projects_for_resource(&ResourceType::Server, &ids)
+                    .await?,
+            );
+        }
+
+        if let Some(container) = &script.container_id {
+            projects.extend(
+                self.live_projects_for_resource(&ResourceType::Container, &[container])
+                    .await?,
+            );
+        }
+
+        Ok(live_only(projects))
+    }
+}
+
+/// Keep Live projects (and those in maintenance), deduplicated and sorted by name
+fn live_only(mut projects: Vec<Project>) -> Vec<Project> {
+    projects.retain(|p| p.status.is_live());
+    projects.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    projects.dedup_by(|a, b| a.id == b.id);
+    projects
 }
+
+/// Type alias for project resource row tuple
+type ResourceRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<i32>,
+);
@@ -0,0 +1,74 @@
+//! Tracked DNS propagations (`pctrl domain propagation --track`)
+
+use crate::Database;
+use pctrl_core::propagation::TrackedPropagation;
+use pctrl_core::Result;
+use std::net::IpAddr;
+
+type PropagationRow = (String, String, String, String, String);
+
+impl Database {
+    /// Let the monitor check a propagation, replacing an earlier one of the domain
+    pub async fn track_propagation(&self, tracked: &TrackedPropagation) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO dns_propagations (domain, expected, resolvers, started_at, expires_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&tracked.domain)
+        .bind(join_ips(&tracked.expected))
+        .bind(join_ips(&tracked.resolvers))
+        .bind(&tracked.started_at)
+        .bind(&tracked.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Propagations the monitor is tracking, oldest first
+    pub async fn list_tracked_propagations(&self) -> Result<Vec<TrackedPropagation>> {
+        let rows: Vec<PropagationRow> = sqlx::query_as(
+            "SELECT domain, expected, resolvers, started_at, expires_at
+             FROM dns_propagations ORDER BY started_at, domain",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(domain, expected, resolvers, started_at, expires_at)| TrackedPropagation {
+                    domain,
+                    expected: split_ips(&expected),
+                    resolvers: split_ips(&resolvers),
+                    started_at,
+                    expires_at,
+                },
+            )
+            .collect())
+    }
+
+    /// Stop tracking a domain's propagation
+    pub async fn untrack_propagation(&self, domain: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM dns_propagations WHERE domain = ?")
+            .bind(domain)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn join_ips(ips: &[IpAddr]) -> String {
+    ips.iter()
+        .map(IpAddr::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_ips(value: &str) -> Vec<IpAddr> {
+    value.split(',').filter_map(|ip| ip.parse().ok()).collect()
+}
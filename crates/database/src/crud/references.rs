@@ -0,0 +1,261 @@
+//! Reverse references: which rows point at an entity
+//!
+//! Every relationship lives in [`RELATIONSHIPS`]; a new table referencing an
+//! entity only needs a row there to show up in `deps`, the delete guards and
+//! `pctrl doctor`.
+
+use crate::Database;
+use pctrl_core::{EntityType, Reference, Result};
+
+/// A column referencing an entity of type `target`
+pub(super) struct Relationship {
+    pub(super) target: EntityType,
+    /// Label the references are grouped under
+    pub(super) label: &'static str,
+    pub(super) table: &'static str,
+    pub(super) column: &'static str,
+    /// Expressions for the referencing entity's ID and display name
+    pub(super) id: &'static str,
+    pub(super) name: &'static str,
+    /// Extra condition on the referencing rows
+    pub(super) filter: Option<&'static str>,
+    /// The column may hold the entity's name instead of its ID
+    /// (project links store whatever was typed)
+    pub(super) by_name: bool,
+    /// Primary key of the referencing rows and the type of entity `id` is
+    /// (`None` for rows that aren't an entity type), for `pctrl doctor`
+    pub(super) key: &'static str,
+    pub(super) owner: Option<EntityType>,
+}
+
+const PROJECT_NAME: &str =
+    "COALESCE((SELECT name FROM projects WHERE projects.id = project_id), project_id)";
+
+pub(super) const RELATIONSHIPS: &[Relationship] = &[
+    // Server
+    Relationship {
+        target: EntityType::Server,
+        label: "project links",
+        table: "project_resources",
+        column: "resource_id",
+        id: "project_id",
+        name: PROJECT_NAME,
+        filter: Some("resource_type = 'server'"),
+        by_name: true,
+        key: "id",
+        owner: Some(EntityType::Project),
+    },
+    Relationship {
+        target: EntityType::Server,
+        label: "project deploys",
+        table: "project_deploy",
+        column: "server_id",
+        id: "project_id",
+        name: PROJECT_NAME,
+        filter: None,
+        by_name: false,
+        key: "project_id",
+        owner: Some(EntityType::Project),
+    },
+    Relationship {
+        target: EntityType::Server,
+        label: "domains",
+        table: "domains",
+        column: "server_id",
+        id: "id",
+        name: "domain",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: Some(EntityType::Domain),
+    },
+    Relationship {
+        target: EntityType::Server,
+        label: "databases",
+        table: "databases",
+        column: "server_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: Some(EntityType::Database),
+    },
+    Relationship {
+        target: EntityType::Server,
+        label: "containers",
+        table: "containers",
+        column: "server_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: None,
+    },
+    Relationship {
+        target: EntityType::Server,
+        label: "scripts",
+        table: "scripts",
+        column: "server_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: Some(EntityType::Script),
+    },
+    // Domain
+    Relationship {
+        target: EntityType::Domain,
+        label: "project links",
+        table: "project_resources",
+        column: "resource_id",
+        id: "project_id",
+        name: PROJECT_NAME,
+        filter: Some("resource_type = 'domain'"),
+        by_name: true,
+        key: "id",
+        owner: Some(EntityType::Project),
+    },
+    // Database
+    Relationship {
+        target: EntityType::Database,
+        label: "project links",
+        table: "project_resources",
+        column: "resource_id",
+        id: "project_id",
+        name: PROJECT_NAME,
+        filter: Some("resource_type = 'database'"),
+        by_name: true,
+        key: "id",
+        owner: Some(EntityType::Project),
+    },
+    // Script
+    Relationship {
+        target: EntityType::Script,
+        label: "project links",
+        table: "project_resources",
+        column: "resource_id",
+        id: "project_id",
+        name: PROJECT_NAME,
+        filter: Some("resource_type = 'script'"),
+        by_name: true,
+        key: "id",
+        owner: Some(EntityType::Project),
+    },
+    // Credential
+    Relationship {
+        target: EntityType::Credential,
+        label: "servers",
+        table: "servers",
+        column: "credential_id",
+        id: "id",
+        name: "name || CASE WHEN deleted_at IS NULL THEN '' ELSE ' (trashed)' END",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: Some(EntityType::Server),
+    },
+    Relationship {
+        target: EntityType::Credential,
+        label: "git repos",
+        table: "git_repos",
+        column: "credential_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: None,
+    },
+    // Project
+    Relationship {
+        target: EntityType::Project,
+        label: "containers",
+        table: "containers",
+        column: "project_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: None,
+    },
+    Relationship {
+        target: EntityType::Project,
+        label: "scripts",
+        table: "scripts",
+        column: "project_id",
+        id: "id",
+        name: "name",
+        filter: None,
+        by_name: false,
+        key: "id",
+        owner: Some(EntityType::Script),
+    },
+];
+
+/// Table and name column of each entity type
+pub(super) fn entity_table(entity_type: EntityType) -> (&'static str, &'static str) {
+    match entity_type {
+        EntityType::Project => ("projects", "name"),
+        EntityType::Server => ("servers", "name"),
+        EntityType::Domain => ("domains", "domain"),
+        EntityType::Database => ("databases", "name"),
+        EntityType::Script => ("scripts", "name"),
+        EntityType::Credential => ("credentials", "name"),
+    }
+}
+
+impl Database {
+    /// Everything referencing an entity, grouped by relationship in the
+    /// order of [`RELATIONSHIPS`]
+    pub async fn reverse_references(
+        &self,
+        entity_type: EntityType,
+        id: &str,
+    ) -> Result<Vec<Reference>> {
+        let (table, name_column) = entity_table(entity_type);
+        let name: Option<(String,)> = sqlx::query_as(&format!(
+            "SELECT {} FROM {} WHERE id = ?",
+            name_column, table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let name = name.map(|(n,)| n).unwrap_or_else(|| id.to_string());
+
+        let mut references = Vec::new();
+        for rel in RELATIONSHIPS.iter().filter(|r| r.target == entity_type) {
+            let filter = rel
+                .filter
+                .map(|f| format!(" AND {}", f))
+                .unwrap_or_default();
+            let sql = format!(
+                "SELECT DISTINCT {id}, {name} FROM {table} WHERE {column} IN (?, ?){filter} ORDER BY 2",
+                id = rel.id,
+                name = rel.name,
+                table = rel.table,
+                column = rel.column,
+                filter = filter,
+            );
+            let key = if rel.by_name { name.as_str() } else { id };
+            let rows: Vec<(String, String)> = sqlx::query_as(&sql)
+                .bind(id)
+                .bind(key)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+            references.extend(rows.into_iter().map(|(id, name)| Reference {
+                relation: rel.label.to_string(),
+                id,
+                name,
+            }));
+        }
+
+        Ok(references)
+    }
+}
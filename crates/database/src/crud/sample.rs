@@ -0,0 +1,65 @@
+//! Server metric sample operations
+
+use super::{format_timestamp, now_timestamp};
+use crate::Database;
+use chrono::{DateTime, Utc};
+use pctrl_core::{DiskSample, Result};
+
+impl Database {
+    /// Record the current disk usage of a server
+    pub async fn record_disk_sample(&self, server_id: &str, used_percent: f64) -> Result<()> {
+        self.insert_disk_sample(server_id, used_percent, now_timestamp())
+            .await
+    }
+
+    /// Record a disk usage sample taken at a specific time (imports, tests)
+    pub async fn record_disk_sample_at(
+        &self,
+        server_id: &str,
+        used_percent: f64,
+        taken_at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.insert_disk_sample(server_id, used_percent, format_timestamp(taken_at))
+            .await
+    }
+
+    /// Disk samples of a server, oldest first
+    pub async fn list_disk_samples(&self, server_id: &str) -> Result<Vec<DiskSample>> {
+        let rows: Vec<(String, f64, String)> = sqlx::query_as(
+            "SELECT server_id, disk_used_percent, created_at FROM server_samples
+             WHERE server_id = ? AND disk_used_percent IS NOT NULL ORDER BY created_at",
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(server_id, used_percent, taken_at)| DiskSample {
+                server_id,
+                used_percent,
+                taken_at,
+            })
+            .collect())
+    }
+
+    async fn insert_disk_sample(
+        &self,
+        server_id: &str,
+        used_percent: f64,
+        taken_at: String,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO server_samples (server_id, disk_used_percent, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(used_percent)
+        .bind(taken_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -1,16 +1,36 @@
 //! Script CRUD operations
 
+use super::{escape_like, names, placeholders};
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::diff::diff;
+use pctrl_core::hooks::{HookPayload, SCRIPT_FINISHED};
+use pctrl_core::script_namespace;
+use pctrl_core::{AuditAction, EntityType, Result, ScriptRunContext};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
 
 impl Database {
     /// Save a script
     pub async fn save_script(&self, script: &pctrl_core::Script) -> Result<()> {
+        self.insert_script(script, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_script(&self, script: &pctrl_core::Script, creating: bool) -> Result<()> {
+        self.check_lock(EntityType::Script, &script.id).await?;
+        self.check_name(names::SCRIPTS, &script.id, &script.name, creating)
+            .await?;
+        let previous = self.get_script(&script.id).await?;
+
         let last_result = script.last_result.as_ref().map(|r| r.to_string());
+        let env = (!script.env.is_empty())
+            .then(|| serde_json::to_string(&script.env).unwrap_or_default());
 
         sqlx::query(
-            "INSERT OR REPLACE INTO scripts (id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO scripts (id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window, namespace, last_cwd, last_env, short_ref)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                     (SELECT last_cwd FROM scripts WHERE id = ?), (SELECT last_env FROM scripts WHERE id = ?),
+                     (SELECT short_ref FROM scripts WHERE id = ?))",
         )
         .bind(&script.id)
         .bind(&script.name)
@@ -26,17 +46,52 @@ impl Database {
         .bind(&last_result)
         .bind(script.exit_code)
         .bind(&script.last_output)
+        .bind(&script.working_dir)
+        .bind(&env)
+        .bind(&script.run_window)
+        .bind(script_namespace::namespace(&script.name))
+        .bind(&script.id)
+        .bind(&script.id)
+        .bind(&script.id)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&script.name))?;
+        self.assign_short_ref(EntityType::Script, &script.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, script))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Script,
+            &script.id,
+            AuditAction::for_save(previous.is_some()),
+            &script.name,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add a script; fails with [`pctrl_core::Error::Conflict`] when its ID
+    /// or its name (ignoring case) is taken
+    pub async fn create_script(&self, script: &pctrl_core::Script) -> Result<()> {
+        self.insert_script(script, true).await
+    }
+
+    /// Change an existing script; fails with [`pctrl_core::Error::Conflict`]
+    /// when another script has its name (ignoring case)
+    pub async fn update_script(&self, script: &pctrl_core::Script) -> Result<()> {
+        self.check_exists(names::SCRIPTS, &script.id).await?;
+        self.save_script(script).await
+    }
+
     /// Get a script by ID
     pub async fn get_script(&self, id: &str) -> Result<Option<pctrl_core::Script>> {
-        let row: Option<ScriptRow> = sqlx::query_as(
-            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output FROM scripts WHERE id = ?",
+        let row = sqlx::query(
+            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts WHERE id = ?",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -46,11 +101,57 @@ impl Database {
         Ok(row.map(Self::row_to_script))
     }
 
+    /// Scripts by ID or name (case-insensitive), in one query; links store
+    /// either. Unknown references are left out.
+    pub async fn get_scripts_by_ids(&self, refs: &[&str]) -> Result<Vec<pctrl_core::Script>> {
+        if refs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list = placeholders(refs.len());
+        let sql = format!(
+            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts
+             WHERE id IN ({list}) OR LOWER(name) IN ({list}) ORDER BY name"
+        );
+        let mut query = sqlx::query(&sql);
+        for r in refs {
+            query = query.bind(*r);
+        }
+        for r in refs {
+            query = query.bind(r.to_ascii_lowercase());
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_script).collect())
+    }
+
     /// List all scripts
     pub async fn list_scripts(&self) -> Result<Vec<pctrl_core::Script>> {
-        let rows: Vec<ScriptRow> = sqlx::query_as(
-            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output FROM scripts ORDER BY name",
+        let rows = sqlx::query(
+            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_script).collect())
+    }
+
+    /// Scripts in namespace `prefix` (`backups` or `backups/`) or one below
+    /// it, ignoring case
+    pub async fn list_scripts_in_namespace(&self, prefix: &str) -> Result<Vec<pctrl_core::Script>> {
+        let Some(prefix) = script_namespace::normalize_prefix(prefix) else {
+            return self.list_scripts().await;
+        };
+        let below = format!("{}{}%", escape_like(prefix), script_namespace::SEPARATOR);
+        let rows = sqlx::query(
+            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts
+             WHERE namespace = ? COLLATE NOCASE OR namespace LIKE ? ESCAPE '\\' ORDER BY name",
         )
+        .bind(prefix)
+        .bind(below)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
@@ -63,8 +164,8 @@ impl Database {
         &self,
         project_id: &str,
     ) -> Result<Vec<pctrl_core::Script>> {
-        let rows: Vec<ScriptRow> = sqlx::query_as(
-            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output FROM scripts WHERE project_id = ? ORDER BY name",
+        let rows = sqlx::query(
+            "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts WHERE project_id = ? ORDER BY name",
         )
         .bind(project_id)
         .fetch_all(&self.pool)
@@ -76,22 +177,45 @@ impl Database {
 
     /// Remove a script by ID
     pub async fn remove_script(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Script, id).await?;
+        let previous = self.get_script(id).await?;
+
         let result = sqlx::query("DELETE FROM scripts WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        let removed = result.rows_affected() > 0;
+        if removed {
+            sqlx::query("DELETE FROM script_runs WHERE script_id = ?")
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+            sqlx::query("DELETE FROM script_revisions WHERE script_id = ? AND status = 'pending'")
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+            let summary = previous.map(|e| e.name).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Script, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
     }
 
-    /// Update script execution result
+    /// Update script execution result; `context` is where a local script
+    /// ran and which variables it saw
     pub async fn update_script_result(
         &self,
         id: &str,
         result: pctrl_core::ScriptResult,
         exit_code: Option<i32>,
         output: Option<&str>,
+        context: Option<&ScriptRunContext>,
     ) -> Result<()> {
         let now = chrono::Utc::now().to_rfc3339();
         let result_str = result.to_string();
@@ -105,79 +229,86 @@ impl Database {
         });
 
         sqlx::query(
-            "UPDATE scripts SET last_run = ?, last_result = ?, exit_code = ?, last_output = ? WHERE id = ?",
+            "UPDATE scripts SET last_run = ?, last_result = ?, exit_code = ?, last_output = ?, last_cwd = ?, last_env = ? WHERE id = ?",
         )
         .bind(&now)
         .bind(&result_str)
         .bind(exit_code)
         .bind(&truncated_output)
+        .bind(context.map(|c| c.cwd.clone()))
+        .bind(context.map(|c| serde_json::to_string(&c.env_names).unwrap_or_default()))
         .bind(id)
         .execute(&self.pool)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
+        if let Some(script) = self.get_script(id).await? {
+            let payload = HookPayload {
+                entity_type: Some(EntityType::Script.to_string()),
+                entity_id: Some(script.id.clone()),
+                summary: Some(script.name.clone()),
+                ..HookPayload::new(
+                    SCRIPT_FINISHED,
+                    serde_json::to_value(&script).unwrap_or_default(),
+                )
+            };
+            self.fire_hooks(&payload).await;
+        }
+
         Ok(())
     }
 
-    /// Helper to convert a row tuple to Script
-    fn row_to_script(row: ScriptRow) -> pctrl_core::Script {
-        let (
-            id,
-            name,
-            description,
-            command,
-            script_type,
-            server_id,
-            project_id,
-            docker_host_id,
-            container_id,
-            dangerous,
-            last_run,
-            last_result,
-            exit_code,
-            last_output,
-        ) = row;
+    /// Where the last local run happened and which variables it saw
+    pub async fn get_script_run_context(&self, id: &str) -> Result<Option<ScriptRunContext>> {
+        let row: Option<(Option<String>, Option<String>)> =
+            sqlx::query_as("SELECT last_cwd, last_env FROM scripts WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        let script_type = script_type.parse().unwrap_or_default();
+        Ok(row.and_then(|(cwd, env)| {
+            Some(ScriptRunContext {
+                cwd: cwd?,
+                env_names: env
+                    .and_then(|e| serde_json::from_str(&e).ok())
+                    .unwrap_or_default(),
+            })
+        }))
+    }
+
+    /// Helper to convert a scripts row to Script
+    pub(super) fn row_to_script(row: SqliteRow) -> pctrl_core::Script {
+        let script_type: String = row.get("script_type");
+        let last_result: Option<String> = row.get("last_result");
         let last_result = last_result.and_then(|r| match r.as_str() {
             "success" => Some(pctrl_core::ScriptResult::Success),
             "error" => Some(pctrl_core::ScriptResult::Error),
             _ => None,
         });
+        let env: Option<String> = row.get("env");
+        let env = env
+            .and_then(|e| serde_json::from_str(&e).ok())
+            .unwrap_or_default();
 
         pctrl_core::Script {
-            id,
-            name,
-            description,
-            command,
-            script_type,
-            server_id,
-            project_id,
-            docker_host_id,
-            container_id,
-            dangerous,
-            last_run,
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            command: row.get("command"),
+            script_type: script_type.parse().unwrap_or_default(),
+            server_id: row.get("server_id"),
+            project_id: row.get("project_id"),
+            docker_host_id: row.get("docker_host_id"),
+            container_id: row.get("container_id"),
+            dangerous: row.get("dangerous"),
+            last_run: row.get("last_run"),
             last_result,
-            exit_code,
-            last_output,
+            exit_code: row.get("exit_code"),
+            last_output: row.get("last_output"),
+            working_dir: row.get("working_dir"),
+            env,
+            run_window: row.get("run_window"),
         }
     }
 }
-
-/// Type alias for script row tuple
-type ScriptRow = (
-    String,
-    String,
-    Option<String>,
-    String,
-    String,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    bool,
-    Option<String>,
-    Option<String>,
-    Option<i32>,
-    Option<String>,
-);
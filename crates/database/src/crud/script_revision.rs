@@ -0,0 +1,262 @@
+//! Approval workflow for dangerous scripts (`script_approval` setting)
+//!
+//! While approvals are on, command edits of dangerous scripts (and taking
+//! the flag off) are stored as pending revisions instead of being applied.
+//! The `scripts` table only ever holds approved content, so running a script
+//! can't pick up an unapproved change.
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::settings::SCRIPT_APPROVAL;
+use pctrl_core::{
+    current_uid, ApprovalMode, EntityType, Result, RevisionStatus, Script, ScriptRevision,
+    ScriptUpdate,
+};
+
+/// script_revisions row
+type RevisionRow = (
+    i64,
+    String,
+    String,
+    bool,
+    String,
+    bool,
+    String,
+    Option<i64>,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+);
+
+const REVISION_COLUMNS: &str = "id, script_id, base_command, base_dangerous, command, dangerous, author, author_uid, created_at, status, decided_by, decided_at";
+
+impl Database {
+    /// Current `script_approval` mode (off when unset)
+    pub async fn script_approval_mode(&self) -> Result<ApprovalMode> {
+        Ok(self
+            .get_setting(SCRIPT_APPROVAL)
+            .await?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default())
+    }
+
+    /// Change a script's command and dangerous flag, or propose the change
+    /// for approval when the script is dangerous and approvals are on
+    pub async fn update_script_command(
+        &self,
+        script_id: &str,
+        command: &str,
+        dangerous: bool,
+        author: &str,
+    ) -> Result<ScriptUpdate> {
+        let mut script = self.require_script(script_id).await?;
+        if script.command == command && script.dangerous == dangerous {
+            return Ok(ScriptUpdate::Unchanged);
+        }
+
+        if self.script_approval_mode().await? != ApprovalMode::Off
+            && script.change_needs_approval(command, dangerous)
+        {
+            self.check_lock(EntityType::Script, &script.id).await?;
+            let revision = self
+                .insert_revision(&script, command, dangerous, author)
+                .await?;
+            return Ok(ScriptUpdate::Pending(revision));
+        }
+
+        script.command = command.to_string();
+        script.dangerous = dangerous;
+        self.save_script(&script).await?;
+        Ok(ScriptUpdate::Applied(script))
+    }
+
+    /// Revisions, oldest first; all of them when `status` is None
+    pub async fn list_script_revisions(
+        &self,
+        status: Option<RevisionStatus>,
+    ) -> Result<Vec<ScriptRevision>> {
+        let sql = format!(
+            "SELECT {} FROM script_revisions WHERE ? IS NULL OR status = ? ORDER BY id",
+            REVISION_COLUMNS
+        );
+        let status = status.map(|s| s.to_string());
+        let rows: Vec<RevisionRow> = sqlx::query_as(&sql)
+            .bind(&status)
+            .bind(&status)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_revision).collect())
+    }
+
+    pub async fn get_script_revision(&self, id: i64) -> Result<Option<ScriptRevision>> {
+        let sql = format!(
+            "SELECT {} FROM script_revisions WHERE id = ?",
+            REVISION_COLUMNS
+        );
+        let row: Option<RevisionRow> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_revision))
+    }
+
+    /// Apply a pending revision to its script.
+    ///
+    /// Fails if the script changed since the revision was proposed, and in
+    /// two-person mode if `approver` is the same OS user as the author, by
+    /// name or by the real uid of this process.
+    pub async fn approve_script_revision(&self, id: i64, approver: &str) -> Result<Script> {
+        let revision = self.require_pending_revision(id).await?;
+        if self.script_approval_mode().await? == ApprovalMode::TwoPerson
+            && (os_user(approver) == os_user(&revision.author)
+                || revision
+                    .author_uid
+                    .is_some_and(|uid| Some(uid) == current_uid()))
+        {
+            return Err(pctrl_core::Error::Config(format!(
+                "Revision #{} was proposed by {}; another user must approve it",
+                id, revision.author
+            )));
+        }
+
+        let mut script = self.require_script(&revision.script_id).await?;
+        if script.command != revision.base_command || script.dangerous != revision.base_dangerous {
+            return Err(pctrl_core::Error::Config(format!(
+                "Script '{}' changed since revision #{} was proposed; reject it and edit again",
+                script.name, id
+            )));
+        }
+
+        script.command = revision.command.clone();
+        script.dangerous = revision.dangerous;
+        self.save_script(&script).await?;
+        self.decide_revision(id, RevisionStatus::Approved, approver)
+            .await?;
+        Ok(script)
+    }
+
+    /// Discard a pending revision
+    pub async fn reject_script_revision(&self, id: i64, by: &str) -> Result<ScriptRevision> {
+        let revision = self.require_pending_revision(id).await?;
+        self.decide_revision(id, RevisionStatus::Rejected, by)
+            .await?;
+        Ok(revision)
+    }
+
+    async fn insert_revision(
+        &self,
+        script: &Script,
+        command: &str,
+        dangerous: bool,
+        author: &str,
+    ) -> Result<ScriptRevision> {
+        let created_at = now_timestamp();
+        let author_uid = current_uid();
+        let result = sqlx::query(
+            "INSERT INTO script_revisions (script_id, base_command, base_dangerous, command, dangerous, author, author_uid, created_at, status)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'pending')",
+        )
+        .bind(&script.id)
+        .bind(&script.command)
+        .bind(script.dangerous)
+        .bind(command)
+        .bind(dangerous)
+        .bind(author)
+        .bind(author_uid)
+        .bind(&created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(ScriptRevision {
+            id: result.last_insert_rowid(),
+            script_id: script.id.clone(),
+            base_command: script.command.clone(),
+            base_dangerous: script.dangerous,
+            command: command.to_string(),
+            dangerous,
+            author: author.to_string(),
+            author_uid,
+            created_at,
+            status: RevisionStatus::Pending,
+            decided_by: None,
+            decided_at: None,
+        })
+    }
+
+    async fn decide_revision(&self, id: i64, status: RevisionStatus, by: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE script_revisions SET status = ?, decided_by = ?, decided_at = ? WHERE id = ?",
+        )
+        .bind(status.to_string())
+        .bind(by)
+        .bind(now_timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn require_script(&self, id: &str) -> Result<Script> {
+        self.get_script(id)
+            .await?
+            .ok_or_else(|| pctrl_core::Error::Config(format!("Script '{}' not found", id)))
+    }
+
+    async fn require_pending_revision(&self, id: i64) -> Result<ScriptRevision> {
+        match self.get_script_revision(id).await? {
+            Some(revision) if revision.status == RevisionStatus::Pending => Ok(revision),
+            Some(revision) => Err(pctrl_core::Error::Config(format!(
+                "Revision #{} is already {}",
+                id, revision.status
+            ))),
+            None => Err(pctrl_core::Error::Config(format!(
+                "Revision #{} not found",
+                id
+            ))),
+        }
+    }
+}
+
+/// OS user of a `user@host` holder string
+fn os_user(holder: &str) -> &str {
+    holder.split('@').next().unwrap_or(holder)
+}
+
+fn row_to_revision(row: RevisionRow) -> ScriptRevision {
+    let (
+        id,
+        script_id,
+        base_command,
+        base_dangerous,
+        command,
+        dangerous,
+        author,
+        author_uid,
+        created_at,
+        status,
+        decided_by,
+        decided_at,
+    ) = row;
+    ScriptRevision {
+        id,
+        script_id,
+        base_command,
+        base_dangerous,
+        command,
+        dangerous,
+        author,
+        author_uid: author_uid.and_then(|uid| u32::try_from(uid).ok()),
+        created_at,
+        status: status.parse().unwrap_or(RevisionStatus::Pending),
+        decided_by,
+        decided_at,
+    }
+}
@@ -0,0 +1,131 @@
+//! Run history of scripts (`pctrl script history`, `script output`)
+//!
+//! Every execution adds a row; `scripts.last_*` still holds the latest run
+//! for list views. Output is capped at [`SCRIPT_RUN_OUTPUT_LIMIT`] so a
+//! chatty script doesn't grow the file without bound.
+
+use crate::Database;
+use pctrl_core::settings::SCRIPT_RUN_HISTORY;
+use pctrl_core::{Result, ScriptRun, SCRIPT_RUN_OUTPUT_LIMIT};
+
+/// script_runs row
+type RunRow = (
+    i64,
+    String,
+    String,
+    String,
+    Option<i32>,
+    Option<String>,
+    String,
+);
+
+const RUN_COLUMNS: &str = "id, script_id, started_at, finished_at, exit_code, output, triggered_by";
+
+/// Runs kept per script when `script_run_history` is unset
+const DEFAULT_KEEP: u32 = 100;
+
+impl Database {
+    /// Record a finished run; output beyond the limit is cut with a note
+    pub async fn add_script_run(
+        &self,
+        script_id: &str,
+        started_at: &str,
+        finished_at: &str,
+        exit_code: Option<i32>,
+        output: Option<&str>,
+        triggered_by: &str,
+    ) -> Result<ScriptRun> {
+        let output = output.map(|o| ScriptRun::truncate_output(o, SCRIPT_RUN_OUTPUT_LIMIT));
+        let result = sqlx::query(
+            "INSERT INTO script_runs (script_id, started_at, finished_at, exit_code, output, triggered_by)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(script_id)
+        .bind(started_at)
+        .bind(finished_at)
+        .bind(exit_code)
+        .bind(&output)
+        .bind(triggered_by)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(ScriptRun {
+            id: result.last_insert_rowid(),
+            script_id: script_id.to_string(),
+            started_at: started_at.to_string(),
+            finished_at: finished_at.to_string(),
+            exit_code,
+            output,
+            triggered_by: triggered_by.to_string(),
+        })
+    }
+
+    pub async fn get_script_run(&self, id: i64) -> Result<Option<ScriptRun>> {
+        let sql = format!("SELECT {} FROM script_runs WHERE id = ?", RUN_COLUMNS);
+        let row: Option<RunRow> = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_run))
+    }
+
+    /// A script's latest `limit` runs, newest first
+    pub async fn list_script_runs(&self, script_id: &str, limit: i64) -> Result<Vec<ScriptRun>> {
+        let sql = format!(
+            "SELECT {} FROM script_runs WHERE script_id = ? ORDER BY id DESC LIMIT ?",
+            RUN_COLUMNS
+        );
+        let rows: Vec<RunRow> = sqlx::query_as(&sql)
+            .bind(script_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_run).collect())
+    }
+
+    /// Delete all but each script's latest `keep_last_n` runs; returns how
+    /// many were deleted
+    pub async fn prune_script_runs(&self, keep_last_n: u32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM script_runs WHERE id IN (
+                 SELECT id FROM (
+                     SELECT id, ROW_NUMBER() OVER (PARTITION BY script_id ORDER BY id DESC) AS n
+                     FROM script_runs
+                 ) WHERE n > ?
+             )",
+        )
+        .bind(keep_last_n)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Runs kept per script (`script_run_history` setting)
+    pub async fn script_run_history_limit(&self) -> Result<u32> {
+        Ok(self
+            .get_setting(SCRIPT_RUN_HISTORY)
+            .await?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_KEEP))
+    }
+}
+
+fn row_to_run(row: RunRow) -> ScriptRun {
+    let (id, script_id, started_at, finished_at, exit_code, output, triggered_by) = row;
+    ScriptRun {
+        id,
+        script_id,
+        started_at,
+        finished_at,
+        exit_code,
+        output,
+        triggered_by,
+    }
+}
@@ -0,0 +1,171 @@
+//! Global search and entity lookup by type (desktop command palette)
+
+use crate::Database;
+use pctrl_core::search::{self, Candidate, Entity, SearchHit};
+use pctrl_core::{EntityType, ResourceType, Result};
+use std::collections::HashMap;
+
+impl Database {
+    /// The best `limit` entities matching `query` by name or details, with
+    /// the projects they belong to
+    pub async fn global_search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let mut hits = self.search(query).await?;
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    /// Every entity matching `query` case-insensitively by name or details,
+    /// best first, with the field that matched (`pctrl find`)
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>> {
+        let projects = self.list_projects().await?;
+        let project_names: HashMap<&str, &str> = projects
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect();
+
+        // Links store IDs or names; key them by both lowercase
+        let mut linked: HashMap<(EntityType, String), Vec<String>> = HashMap::new();
+        for link in self.list_all_project_resources().await? {
+            let entity_type = match link.resource_type {
+                ResourceType::Server => EntityType::Server,
+                ResourceType::Database => EntityType::Database,
+                ResourceType::Domain => EntityType::Domain,
+                ResourceType::Script => EntityType::Script,
+                _ => continue,
+            };
+            if let Some(name) = project_names.get(link.project_id.as_str()) {
+                let names = linked
+                    .entry((entity_type, link.resource_id.to_lowercase()))
+                    .or_default();
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        let projects_of = |entity_type: EntityType, id: &str, name: &str| -> Vec<String> {
+            let mut names: Vec<String> = [id, name]
+                .iter()
+                .filter_map(|key| linked.get(&(entity_type, key.to_lowercase())))
+                .flatten()
+                .cloned()
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        };
+
+        // Journal entries are matched as details of their entity
+        let mut journal: HashMap<(EntityType, String), Vec<(&'static str, String)>> =
+            HashMap::new();
+        for entry in self.list_all_journal_entries().await? {
+            journal
+                .entry((entry.entity_type, entry.entity_id))
+                .or_default()
+                .push(("journal", entry.text));
+        }
+        let mut journal_of = |entity_type: EntityType, id: &str| -> Vec<(&'static str, String)> {
+            journal
+                .remove(&(entity_type, id.to_string()))
+                .unwrap_or_default()
+        };
+
+        let mut candidates = Vec::new();
+        for project in &projects {
+            let mut candidate = Candidate::project(project);
+            candidate
+                .text
+                .extend(journal_of(EntityType::Project, &project.id));
+            candidates.push(candidate);
+        }
+        for server in self.list_servers().await? {
+            let mut candidate = Candidate::server(&server);
+            candidate.projects = projects_of(EntityType::Server, &server.id, &server.name);
+            candidate
+                .text
+                .extend(journal_of(EntityType::Server, &server.id));
+            candidates.push(candidate);
+        }
+        for domain in self.list_domains().await? {
+            let mut candidate = Candidate::domain(&domain);
+            candidate.projects = projects_of(EntityType::Domain, &domain.id, &domain.domain);
+            candidate
+                .text
+                .extend(journal_of(EntityType::Domain, &domain.id));
+            candidates.push(candidate);
+        }
+        for db in self.list_database_credentials().await? {
+            let mut candidate = Candidate::database(&db);
+            candidate.projects = projects_of(EntityType::Database, &db.id, &db.name);
+            candidates.push(candidate);
+        }
+        for script in self.list_scripts().await? {
+            let mut candidate = Candidate::script(&script);
+            candidate.projects = projects_of(EntityType::Script, &script.id, &script.name);
+            if let Some(name) = script
+                .project_id
+                .as_deref()
+                .and_then(|id| project_names.get(id))
+            {
+                if !candidate.projects.iter().any(|p| p == name) {
+                    candidate.projects.push(name.to_string());
+                }
+            }
+            candidates.push(candidate);
+        }
+        for credential in self.list_credentials().await? {
+            candidates.push(Candidate::credential(&credential));
+        }
+
+        Ok(search::rank(query, candidates, usize::MAX))
+    }
+
+    /// Any entity by type and ID or name, with its secrets redacted
+    pub async fn get_entity(&self, entity_type: EntityType, id: &str) -> Result<Option<Entity>> {
+        Ok(self
+            .find_entity(entity_type, id)
+            .await?
+            .map(Entity::redacted))
+    }
+
+    /// Any entity by type and ID or name, as stored
+    pub(crate) async fn find_entity(
+        &self,
+        entity_type: EntityType,
+        id: &str,
+    ) -> Result<Option<Entity>> {
+        let entity = match entity_type {
+            EntityType::Project => self
+                .get_project(id)
+                .await?
+                .or(self.get_project_by_name(id).await?)
+                .map(Entity::Project),
+            EntityType::Server => self
+                .get_server(id)
+                .await?
+                .or(self.get_server_by_name(id).await?)
+                .map(Entity::Server),
+            EntityType::Domain => self
+                .get_domain(id)
+                .await?
+                .or(self.get_domain_by_name(id).await?)
+                .map(Entity::Domain),
+            EntityType::Database => self
+                .get_database_credentials(id)
+                .await?
+                .or(self.get_database_credentials_by_name(id).await?)
+                .map(Entity::Database),
+            EntityType::Script => {
+                // An ID wins over another script's equal name
+                let scripts = self.get_scripts_by_ids(&[id]).await?;
+                let index = scripts.iter().position(|s| s.id == id).unwrap_or(0);
+                scripts.into_iter().nth(index).map(Entity::Script)
+            }
+            EntityType::Credential => self
+                .get_credential(id)
+                .await?
+                .or(self.get_credential_by_name(id).await?)
+                .map(Entity::Credential),
+        };
+        Ok(entity)
+    }
+}
@@ -1,19 +1,32 @@
 //! Server CRUD operations
 
+use super::{names, now_timestamp, placeholders};
 use crate::Database;
-use pctrl_core::Result;
+use pctrl_core::diff::diff;
+use pctrl_core::{AuditAction, EntityType, Result};
 
 impl Database {
-    /// Save a server
+    /// Save a server; a trashed one stays in the trash
     pub async fn save_server(&self, server: &pctrl_core::Server) -> Result<()> {
+        self.insert_server(server, false).await
+    }
+
+    /// Insert or replace; when `creating`, a taken ID is a conflict too
+    async fn insert_server(&self, server: &pctrl_core::Server, creating: bool) -> Result<()> {
+        self.check_lock(EntityType::Server, &server.id).await?;
+        self.check_name(names::SERVERS, &server.id, &server.name, creating)
+            .await?;
+        let previous = self.get_server(&server.id).await?;
+
         let specs = server
             .specs
             .as_ref()
             .map(|s| serde_json::to_string(s).unwrap_or_default());
 
         sqlx::query(
-            "INSERT OR REPLACE INTO servers (id, name, host, server_type, provider, credential_id, location, specs, notes)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT OR REPLACE INTO servers (id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated, short_ref, deleted_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                     (SELECT short_ref FROM servers WHERE id = ?), (SELECT deleted_at FROM servers WHERE id = ?))",
         )
         .bind(&server.id)
         .bind(&server.name)
@@ -24,27 +37,51 @@ impl Database {
         .bind(&server.location)
         .bind(&specs)
         .bind(&server.notes)
+        .bind(&server.requires_vpn)
+        .bind(server.max_containers.map(i64::from))
+        .bind(server.max_memory_mb_allocated.map(|mb| mb as i64))
+        .bind(&server.id)
+        .bind(&server.id)
         .execute(&self.pool)
         .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        .map_err(names::write_error(&server.name))?;
+        // The replaced row's ref carries over; new rows get one
+        self.assign_short_ref(EntityType::Server, &server.id)
+            .await?;
+
+        let changes = previous
+            .as_ref()
+            .map(|p| diff(p, server))
+            .unwrap_or_default();
+        self.record_audit_with_changes(
+            EntityType::Server,
+            &server.id,
+            AuditAction::for_save(previous.is_some()),
+            &server.name,
+            &changes,
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Add a server; fails with [`pctrl_core::Error::Conflict`] when its ID
+    /// or its name (ignoring case) is taken
+    pub async fn create_server(&self, server: &pctrl_core::Server) -> Result<()> {
+        self.insert_server(server, true).await
+    }
+
+    /// Change an existing server; fails with [`pctrl_core::Error::Conflict`]
+    /// when another server has its name (ignoring case)
+    pub async fn update_server(&self, server: &pctrl_core::Server) -> Result<()> {
+        self.check_exists(names::SERVERS, &server.id).await?;
+        self.save_server(server).await
+    }
+
     /// Get a server by ID
     pub async fn get_server(&self, id: &str) -> Result<Option<pctrl_core::Server>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes FROM servers WHERE id = ?",
+        let row: Option<ServerRow> = sqlx::query_as(
+            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers WHERE id = ? AND deleted_at IS NULL",
         )
         .bind(id)
         .fetch_optional(&self.pool)
@@ -56,18 +93,8 @@ impl Database {
 
     /// Get a server by name (case-insensitive)
     pub async fn get_server_by_name(&self, name: &str) -> Result<Option<pctrl_core::Server>> {
-        let row: Option<(
-            String,
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes FROM servers WHERE LOWER(name) = LOWER(?)",
+        let row: Option<ServerRow> = sqlx::query_as(
+            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers WHERE LOWER(name) = LOWER(?) AND deleted_at IS NULL",
         )
         .bind(name)
         .fetch_optional(&self.pool)
@@ -79,18 +106,8 @@ impl Database {
 
     /// List all servers
     pub async fn list_servers(&self) -> Result<Vec<pctrl_core::Server>> {
-        let rows: Vec<(
-            String,
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        )> = sqlx::query_as(
-            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes FROM servers ORDER BY name",
+        let rows: Vec<ServerRow> = sqlx::query_as(
+            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers WHERE deleted_at IS NULL ORDER BY name",
         )
         .fetch_all(&self.pool)
         .await
@@ -99,15 +116,136 @@ impl Database {
         Ok(rows.into_iter().map(Self::row_to_server).collect())
     }
 
+    /// Servers by ID or name (case-insensitive), in one query; links store
+    /// either. Unknown references are left out.
+    pub async fn get_servers_by_ids(&self, refs: &[&str]) -> Result<Vec<pctrl_core::Server>> {
+        if refs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list = placeholders(refs.len());
+        let sql = format!(
+            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers
+             WHERE (id IN ({list}) OR LOWER(name) IN ({list})) AND deleted_at IS NULL ORDER BY name"
+        );
+        let mut query = sqlx::query_as::<_, ServerRow>(&sql);
+        for r in refs {
+            query = query.bind(*r);
+        }
+        for r in refs {
+            query = query.bind(r.to_ascii_lowercase());
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_server).collect())
+    }
+
     /// Remove a server by ID
     pub async fn remove_server(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Server, id).await?;
+        let previous = self.get_server(id).await?;
+
+        // Dependent rows and the server go together or not at all
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        for sql in [
+            "DELETE FROM server_facts WHERE server_id = ?",
+            "DELETE FROM entity_metadata WHERE entity_type = 'server' AND entity_id = ?",
+            "DELETE FROM discovery_cache WHERE server_id = ?",
+            "DELETE FROM compose_projects WHERE server_id = ?",
+        ] {
+            sqlx::query(sql)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+        }
         let result = sqlx::query("DELETE FROM servers WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+
+        let removed = result.rows_affected() > 0;
+        if removed {
+            let summary = previous.map(|e| e.name).unwrap_or_else(|| id.to_string());
+            self.record_audit(EntityType::Server, id, AuditAction::Removed, &summary)
+                .await?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Move a server to the trash (soft delete, restorable)
+    pub async fn trash_server(&self, id: &str) -> Result<bool> {
+        self.check_lock(EntityType::Server, id).await?;
+        let Some(server) = self.get_server(id).await? else {
+            return Ok(false);
+        };
+
+        sqlx::query("UPDATE servers SET deleted_at = ? WHERE id = ?")
+            .bind(now_timestamp())
             .bind(id)
             .execute(&self.pool)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-        Ok(result.rows_affected() > 0)
+        self.record_audit(EntityType::Server, id, AuditAction::Removed, &server.name)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// List servers in the trash
+    pub async fn list_trashed_servers(&self) -> Result<Vec<pctrl_core::Server>> {
+        let rows: Vec<ServerRow> = sqlx::query_as(
+            "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers WHERE deleted_at IS NOT NULL ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_server).collect())
+    }
+
+    /// Restore a trashed server by ID or name; fails with
+    /// [`pctrl_core::Error::Conflict`] when a server has taken its name since
+    pub async fn restore_server(&self, id_or_name: &str) -> Result<Option<pctrl_core::Server>> {
+        let trashed = self.list_trashed_servers().await?;
+        let Some(server) = trashed
+            .iter()
+            .find(|s| s.id == id_or_name)
+            .or_else(|| {
+                trashed
+                    .iter()
+                    .find(|s| s.name.eq_ignore_ascii_case(id_or_name))
+            })
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        self.check_lock(EntityType::Server, &server.id).await?;
+        self.check_name(names::SERVERS, &server.id, &server.name, false)
+            .await?;
+        sqlx::query("UPDATE servers SET deleted_at = NULL WHERE id = ?")
+            .bind(&server.id)
+            .execute(&self.pool)
+            .await
+            .map_err(names::write_error(&server.name))?;
+
+        self.record_audit(
+            EntityType::Server,
+            &server.id,
+            AuditAction::Created,
+            &server.name,
+        )
+        .await?;
+
+        Ok(Some(server))
     }
 
     /// Check if a server exists
@@ -122,20 +260,21 @@ impl Database {
     }
 
     /// Helper to convert a row tuple to Server
-    fn row_to_server(
-        row: (
-            String,
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-        ),
-    ) -> pctrl_core::Server {
-        let (id, name, host, server_type, provider, credential_id, location, specs, notes) = row;
+    pub(super) fn row_to_server(row: ServerRow) -> pctrl_core::Server {
+        let (
+            id,
+            name,
+            host,
+            server_type,
+            provider,
+            credential_id,
+            location,
+            specs,
+            notes,
+            requires_vpn,
+            max_containers,
+            max_memory_mb_allocated,
+        ) = row;
         let server_type = server_type.parse().unwrap_or_default();
         let specs = specs.and_then(|s| serde_json::from_str(&s).ok());
 
@@ -149,6 +288,25 @@ impl Database {
             location,
             specs,
             notes,
+            requires_vpn,
+            max_containers: max_containers.map(|n| n as u32),
+            max_memory_mb_allocated: max_memory_mb_allocated.map(|n| n as u64),
         }
     }
 }
+
+/// Type alias for server row tuple
+pub(super) type ServerRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+    Option<i64>,
+);
@@ -0,0 +1,116 @@
+//! systemd service operations (`pctrl service`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::{Result, Service};
+
+/// services row
+type ServiceRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+const SERVICE_COLUMNS: &str = "id, name, server_id, unit, project_id, last_state, last_checked";
+
+impl Database {
+    /// Save a service, keeping its last known state
+    pub async fn save_service(&self, service: &Service) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO services (id, name, server_id, unit, project_id, last_state, last_checked)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&service.id)
+        .bind(&service.name)
+        .bind(&service.server_id)
+        .bind(&service.unit)
+        .bind(&service.project_id)
+        .bind(&service.last_state)
+        .bind(&service.last_checked)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Service by ID or name (case-insensitive)
+    pub async fn get_service(&self, reference: &str) -> Result<Option<Service>> {
+        let row: Option<ServiceRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM services WHERE id = ? OR LOWER(name) = LOWER(?)",
+            SERVICE_COLUMNS
+        ))
+        .bind(reference)
+        .bind(reference)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(row_to_service))
+    }
+
+    /// All services, by name
+    pub async fn list_services(&self) -> Result<Vec<Service>> {
+        let rows: Vec<ServiceRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM services ORDER BY name",
+            SERVICE_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_service).collect())
+    }
+
+    /// Services of a project, by name
+    pub async fn list_services_for_project(&self, project_id: &str) -> Result<Vec<Service>> {
+        let rows: Vec<ServiceRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM services WHERE project_id = ? ORDER BY name",
+            SERVICE_COLUMNS
+        ))
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows.into_iter().map(row_to_service).collect())
+    }
+
+    /// Remove a service from pctrl (the unit on the server is untouched)
+    pub async fn remove_service(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM services WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store the state summary of a status check, e.g. "active (running)"
+    pub async fn record_service_state(&self, id: &str, state: &str) -> Result<()> {
+        sqlx::query("UPDATE services SET last_state = ?, last_checked = ? WHERE id = ?")
+            .bind(state)
+            .bind(now_timestamp())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+}
+
+pub(super) fn row_to_service(row: ServiceRow) -> Service {
+    let (id, name, server_id, unit, project_id, last_state, last_checked) = row;
+    Service {
+        id,
+        name,
+        server_id,
+        unit,
+        project_id,
+        last_state,
+        last_checked,
+    }
+}
@@ -0,0 +1,84 @@
+//! User settings (`pctrl config`)
+
+use super::now_timestamp;
+use crate::Database;
+use pctrl_core::{settings, Result};
+
+impl Database {
+    /// Value of a setting, `None` if unset
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(|(v,)| v))
+    }
+
+    /// Set a known setting (validated, see [`settings::validate`])
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        settings::validate(key, value).map_err(pctrl_core::Error::Config)?;
+
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        sqlx::query("INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?, ?, ?)")
+            .bind(key)
+            .bind(value.trim())
+            .bind(now_timestamp())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        bump_generation(&mut tx).await.map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+
+        Ok(())
+    }
+
+    /// Reset a setting to its default. Returns false if it wasn't set.
+    pub async fn unset_setting(&self, key: &str) -> Result<bool> {
+        let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let result = sqlx::query("DELETE FROM settings WHERE key = ?")
+            .bind(key)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        if result.rows_affected() > 0 {
+            bump_generation(&mut tx).await.map_err(db_err)?;
+        }
+        tx.commit().await.map_err(db_err)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Counter bumped by every settings write; 0 before the first one.
+    /// Cheap enough to poll for changes made from another process.
+    pub async fn settings_generation(&self) -> Result<i64> {
+        let row: Option<(i64,)> =
+            sqlx::query_as("SELECT generation FROM settings_generation WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(|(g,)| g).unwrap_or(0))
+    }
+
+    /// All explicitly set settings, sorted by key
+    pub async fn list_settings(&self) -> Result<Vec<(String, String)>> {
+        sqlx::query_as("SELECT key, value FROM settings ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))
+    }
+}
+
+async fn bump_generation(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO settings_generation (id, generation) VALUES (1, 1)
+         ON CONFLICT(id) DO UPDATE SET generation = generation + 1",
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Deploy settings and run history for `project ship`
+
+use crate::Database;
+use pctrl_core::ship::{DeployConfig, ShipRun};
+use pctrl_core::{EntityType, Result};
+
+type ShipRunRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    bool,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    i64,
+    String,
+);
+
+impl Database {
+    /// Deploy settings of a project (empty if none were saved)
+    pub async fn get_deploy_config(&self, project_id: &str) -> Result<DeployConfig> {
+        let row: Option<(Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT path, branch, server_id FROM project_deploy WHERE project_id = ?",
+        )
+        .bind(project_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row
+            .map(|(path, branch, server_id)| DeployConfig {
+                path,
+                branch,
+                server_id,
+            })
+            .unwrap_or_default())
+    }
+
+    /// Save a project's deploy settings
+    pub async fn save_deploy_config(&self, project_id: &str, config: &DeployConfig) -> Result<()> {
+        self.check_lock(EntityType::Project, project_id).await?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO project_deploy (project_id, path, branch, server_id)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(project_id)
+        .bind(&config.path)
+        .bind(&config.branch)
+        .bind(&config.server_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Record a ship run
+    pub async fn record_ship_run(&self, run: &ShipRun) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO ship_runs
+             (project_id, server_id, branch, old_commit, new_commit, success, failed_phase,
+              error, output, duration_ms, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&run.project_id)
+        .bind(&run.server_id)
+        .bind(&run.branch)
+        .bind(&run.old_commit)
+        .bind(&run.new_commit)
+        .bind(run.success)
+        .bind(run.failed_phase.map(|p| p.to_string()))
+        .bind(&run.error)
+        .bind(&run.output)
+        .bind(run.duration_ms)
+        .bind(&run.ran_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Most recent ship runs of a project, newest first
+    pub async fn list_ship_runs(&self, project_id: &str, limit: i64) -> Result<Vec<ShipRun>> {
+        let rows: Vec<ShipRunRow> = sqlx::query_as(
+            "SELECT project_id, server_id, branch, old_commit, new_commit, success, failed_phase,
+                    error, output, duration_ms, created_at
+             FROM ship_runs WHERE project_id = ? ORDER BY id DESC LIMIT ?",
+        )
+        .bind(project_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(
+                    project_id,
+                    server_id,
+                    branch,
+                    old_commit,
+                    new_commit,
+                    success,
+                    failed_phase,
+                    error,
+                    output,
+                    duration_ms,
+                    ran_at,
+                )| ShipRun {
+                    project_id,
+                    server_id,
+                    branch,
+                    old_commit,
+                    new_commit,
+                    success,
+                    failed_phase: failed_phase.and_then(|p| p.parse().ok()),
+                    error,
+                    output,
+                    duration_ms,
+                    ran_at,
+                },
+            )
+            .collect())
+    }
+}
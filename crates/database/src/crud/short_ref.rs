@@ -0,0 +1,182 @@
+//! Short references (`srv-3fk2`), see [`pctrl_core::short_ref`]
+
+use super::references::entity_table;
+use crate::Database;
+use pctrl_core::short_ref::{self, EntityRef};
+use pctrl_core::{EntityType, Result};
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
+
+/// Trashed servers keep their ref but don't resolve
+fn live(entity_type: EntityType) -> &'static str {
+    match entity_type {
+        EntityType::Server => " AND deleted_at IS NULL",
+        _ => "",
+    }
+}
+
+fn db_err(e: sqlx::Error) -> pctrl_core::Error {
+    pctrl_core::Error::Database(e.to_string())
+}
+
+/// Give an entity without a ref the first free candidate; a no-op for
+/// entities that have one. Returns whether a ref was assigned.
+async fn assign(conn: &mut SqliteConnection, entity_type: EntityType, id: &str) -> Result<bool> {
+    let (table, _) = entity_table(entity_type);
+    let mut attempt = 0;
+    loop {
+        let candidate = short_ref::generate(entity_type, id, attempt);
+        let taken: Option<(String,)> =
+            sqlx::query_as(&format!("SELECT id FROM {} WHERE short_ref = ?", table))
+                .bind(&candidate)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(db_err)?;
+        match taken {
+            Some((owner,)) if owner == id => return Ok(false),
+            Some(_) => attempt += 1,
+            None => {
+                let assigned = sqlx::query(&format!(
+                    "UPDATE {} SET short_ref = ? WHERE id = ? AND short_ref IS NULL",
+                    table
+                ))
+                .bind(&candidate)
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .map_err(db_err)?;
+                return Ok(assigned.rows_affected() > 0);
+            }
+        }
+    }
+}
+
+/// Give every entity without a ref one, oldest first. Idempotent: entities
+/// that have a ref keep it. Returns how many got one.
+pub(crate) async fn backfill(conn: &mut SqliteConnection) -> Result<u64> {
+    let mut assigned = 0;
+    for entity_type in EntityType::ALL {
+        let (table, _) = entity_table(entity_type);
+        let ids: Vec<(String,)> = sqlx::query_as(&format!(
+            "SELECT id FROM {} WHERE short_ref IS NULL ORDER BY rowid",
+            table
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(db_err)?;
+        for (id,) in ids {
+            if assign(conn, entity_type, &id).await? {
+                assigned += 1;
+            }
+        }
+    }
+    Ok(assigned)
+}
+
+impl Database {
+    /// Give a just-written entity its short ref unless it has one
+    pub(crate) async fn assign_short_ref(&self, entity_type: EntityType, id: &str) -> Result<()> {
+        let mut conn = self.pool.acquire().await.map_err(db_err)?;
+        assign(&mut conn, entity_type, id).await?;
+        Ok(())
+    }
+
+    /// Give every entity without a short ref one; returns how many got one
+    pub async fn backfill_short_refs(&self) -> Result<u64> {
+        let mut conn = self.pool.acquire().await.map_err(db_err)?;
+        backfill(&mut conn).await
+    }
+
+    /// Short ref of an entity
+    pub async fn short_ref(&self, entity_type: EntityType, id: &str) -> Result<Option<String>> {
+        let (table, _) = entity_table(entity_type);
+        let row: Option<(Option<String>,)> =
+            sqlx::query_as(&format!("SELECT short_ref FROM {} WHERE id = ?", table))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(db_err)?;
+        Ok(row.and_then(|(r,)| r))
+    }
+
+    /// Short refs of all entities of a type, by ID
+    pub async fn short_refs(&self, entity_type: EntityType) -> Result<HashMap<String, String>> {
+        let (table, _) = entity_table(entity_type);
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, short_ref FROM {} WHERE short_ref IS NOT NULL",
+            table
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Entities an input names, of one type or of any: those with that ref,
+    /// else those with that ID, else those with that name (case-insensitive).
+    /// More than one match is ambiguous; none means not found.
+    pub async fn find_entities(
+        &self,
+        entity_type: Option<EntityType>,
+        input: &str,
+    ) -> Result<Vec<EntityRef>> {
+        let input = input.trim();
+        let types: Vec<EntityType> = match entity_type {
+            Some(t) => vec![t],
+            None => EntityType::ALL.to_vec(),
+        };
+
+        if let Some(t) = short_ref::parse(input).filter(|t| types.contains(t)) {
+            let found = self
+                .entities_where(t, "short_ref = ?", &input.to_ascii_lowercase())
+                .await?;
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+
+        let mut found = Vec::new();
+        for &t in &types {
+            found.extend(self.entities_where(t, "id = ?", input).await?);
+        }
+        if !found.is_empty() {
+            return Ok(found);
+        }
+
+        for &t in &types {
+            let (_, name_column) = entity_table(t);
+            let condition = format!("LOWER({}) = LOWER(?)", name_column);
+            found.extend(self.entities_where(t, &condition, input).await?);
+        }
+        Ok(found)
+    }
+
+    async fn entities_where(
+        &self,
+        entity_type: EntityType,
+        condition: &str,
+        value: &str,
+    ) -> Result<Vec<EntityRef>> {
+        let (table, name_column) = entity_table(entity_type);
+        let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(&format!(
+            "SELECT id, {}, short_ref FROM {} WHERE {}{} ORDER BY rowid",
+            name_column,
+            table,
+            condition,
+            live(entity_type)
+        ))
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(db_err)?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, short_ref)| EntityRef {
+                entity_type,
+                id,
+                name,
+                short_ref: short_ref.unwrap_or_default(),
+            })
+            .collect())
+    }
+}
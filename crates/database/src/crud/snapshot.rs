@@ -0,0 +1,160 @@
+//! Inventory snapshots (`pctrl snapshot`)
+//!
+//! A snapshot is the whole inventory as gzip-compressed JSON. Secrets are
+//! never stored (see [`Inventory`]). Restoring is up to the CLI, which
+//! writes a snapshot like an imported document without secrets.
+
+use super::now_timestamp;
+use crate::Database;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pctrl_core::snapshot::without_secrets;
+use pctrl_core::{CredentialInfo, Inventory, InventoryCounts, Result, SnapshotInfo};
+use std::io::{Read, Write};
+
+/// Largest compressed snapshot that is stored
+const MAX_SNAPSHOT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Largest inventory a snapshot may expand to when loaded
+const MAX_INVENTORY_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Snapshot row: name, created_at, counts (JSON), size_bytes
+type SnapshotRow = (String, String, String, i64);
+
+impl Database {
+    /// The current inventory, secrets excluded
+    pub async fn current_inventory(&self) -> Result<Inventory> {
+        Ok(Inventory {
+            projects: self.list_projects().await?,
+            servers: self.list_servers().await?,
+            domains: self.list_domains().await?,
+            databases: self
+                .list_database_credentials()
+                .await?
+                .into_iter()
+                .map(without_secrets)
+                .collect(),
+            scripts: self.list_scripts().await?,
+            credentials: self
+                .list_credentials()
+                .await?
+                .iter()
+                .map(CredentialInfo::from)
+                .collect(),
+            links: self.list_all_project_resources().await?,
+        })
+    }
+
+    /// Store the current inventory under `name` (which must be unused)
+    pub async fn create_snapshot(&self, name: &str) -> Result<SnapshotInfo> {
+        if self.load_snapshot(name).await?.is_some() {
+            return Err(pctrl_core::Error::Config(format!(
+                "Snapshot '{}' already exists",
+                name
+            )));
+        }
+
+        let inventory = self.current_inventory().await?;
+        let data = compress(&inventory)?;
+        if data.len() > MAX_SNAPSHOT_BYTES {
+            return Err(pctrl_core::Error::Config(format!(
+                "Snapshot is {} bytes compressed, more than the limit of {} bytes",
+                data.len(),
+                MAX_SNAPSHOT_BYTES
+            )));
+        }
+
+        let info = SnapshotInfo {
+            name: name.to_string(),
+            created_at: now_timestamp(),
+            counts: inventory.counts(),
+            size_bytes: data.len() as u64,
+        };
+        let counts = serde_json::to_string(&info.counts)
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO snapshots (name, created_at, counts, size_bytes, data) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&info.name)
+        .bind(&info.created_at)
+        .bind(counts)
+        .bind(info.size_bytes as i64)
+        .bind(data)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(info)
+    }
+
+    /// All snapshots, newest first
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        let rows: Vec<SnapshotRow> = sqlx::query_as(
+            "SELECT name, created_at, counts, size_bytes FROM snapshots ORDER BY created_at DESC, name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, created_at, counts, size_bytes)| SnapshotInfo {
+                name,
+                created_at,
+                counts: serde_json::from_str(&counts).unwrap_or(InventoryCounts::default()),
+                size_bytes: size_bytes as u64,
+            })
+            .collect())
+    }
+
+    /// Inventory stored in a snapshot
+    pub async fn load_snapshot(&self, name: &str) -> Result<Option<Inventory>> {
+        let row: Option<(Vec<u8>,)> = sqlx::query_as("SELECT data FROM snapshots WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        row.map(|(data,)| decompress(&data)).transpose()
+    }
+
+    /// Delete a snapshot
+    pub async fn remove_snapshot(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM snapshots WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn compress(inventory: &Inventory) -> Result<Vec<u8>> {
+    let json =
+        serde_json::to_vec(inventory).map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| pctrl_core::Error::Database(format!("Failed to compress snapshot: {}", e)))
+}
+
+fn decompress(data: &[u8]) -> Result<Inventory> {
+    let mut json = Vec::new();
+    GzDecoder::new(data)
+        .take(MAX_INVENTORY_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| pctrl_core::Error::Database(format!("Corrupt snapshot: {}", e)))?;
+    if json.len() as u64 > MAX_INVENTORY_BYTES {
+        return Err(pctrl_core::Error::Database(format!(
+            "Snapshot expands to more than {} bytes",
+            MAX_INVENTORY_BYTES
+        )));
+    }
+
+    serde_json::from_slice(&json)
+        .map_err(|e| pctrl_core::Error::Database(format!("Corrupt snapshot: {}", e)))
+}
@@ -0,0 +1,94 @@
+//! Streaming table rows for `pctrl export table`
+
+use super::service::row_to_service;
+use crate::Database;
+use futures_util::stream::{BoxStream, StreamExt};
+use pctrl_core::table_export::ExportTable;
+use pctrl_core::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+impl Database {
+    /// Rows of a table as serialized entities, in the order of the `list_*`
+    /// queries. Rows are read as the stream is polled, not loaded at once.
+    pub fn stream_table(&self, table: ExportTable) -> BoxStream<'_, Result<Value>> {
+        match table {
+            ExportTable::Projects => typed(
+                sqlx::query_as(
+                    "SELECT id, name, description, stack, status, color, icon, notes FROM projects ORDER BY name",
+                )
+                .fetch(&self.pool),
+                Self::row_to_project,
+            ),
+            ExportTable::Servers => typed(
+                sqlx::query_as(
+                    "SELECT id, name, host, server_type, provider, credential_id, location, specs, notes, requires_vpn, max_containers, max_memory_mb_allocated FROM servers WHERE deleted_at IS NULL ORDER BY name",
+                )
+                .fetch(&self.pool),
+                Self::row_to_server,
+            ),
+            ExportTable::Domains => typed(
+                sqlx::query_as(
+                    "SELECT id, domain, domain_type, ssl, ssl_expiry, cloudflare_zone_id, cloudflare_record_id, server_id, container_id, notes, superseded_by FROM domains ORDER BY domain",
+                )
+                .fetch(&self.pool),
+                Self::row_to_domain,
+            ),
+            ExportTable::Databases => sqlx::query_as(
+                "SELECT id, name, db_type, host, port, database_name, username, password, connection_string, server_id, container_id, notes, sealed FROM databases ORDER BY name",
+            )
+            .fetch(&self.pool)
+            .map(move |row| {
+                let row = row.map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+                to_value(self.row_to_database_credentials(row)?)
+            })
+            .boxed(),
+            ExportTable::Scripts => typed(
+                sqlx::query(
+                    "SELECT id, name, description, command, script_type, server_id, project_id, docker_host_id, container_id, dangerous, last_run, last_result, exit_code, last_output, working_dir, env, run_window FROM scripts ORDER BY name",
+                )
+                .fetch(&self.pool),
+                Self::row_to_script,
+            ),
+            ExportTable::Credentials => sqlx::query_as(
+                "SELECT id, name, credential_type, data, notes FROM credentials ORDER BY name",
+            )
+            .fetch(&self.pool)
+            .map(move |row| {
+                let row = row.map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+                to_value(self.row_to_credential(row)?)
+            })
+            .boxed(),
+            ExportTable::Services => typed(
+                sqlx::query_as(
+                    "SELECT id, name, server_id, unit, project_id, last_state, last_checked FROM services ORDER BY name",
+                )
+                .fetch(&self.pool),
+                row_to_service,
+            ),
+            ExportTable::Links => typed(
+                sqlx::query_as(
+                    "SELECT id, project_id, resource_type, resource_id, role, notes, start_order FROM project_resources ORDER BY project_id, id",
+                )
+                .fetch(&self.pool),
+                Self::row_to_resource,
+            ),
+        }
+    }
+}
+
+/// Convert the rows of a query stream to JSON values
+fn typed<'a, R: Send + 'a, T: Serialize>(
+    rows: BoxStream<'a, sqlx::Result<R>>,
+    convert: impl Fn(R) -> T + Send + 'a,
+) -> BoxStream<'a, Result<Value>> {
+    rows.map(move |row| {
+        let row = row.map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        to_value(convert(row))
+    })
+    .boxed()
+}
+
+fn to_value(row: impl Serialize) -> Result<Value> {
+    serde_json::to_value(row).map_err(|e| pctrl_core::Error::Database(e.to_string()))
+}
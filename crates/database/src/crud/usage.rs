@@ -0,0 +1,162 @@
+//! Local usage statistics (`pctrl stats`)
+//!
+//! Only subcommand paths and timings are stored, never arguments. Nothing
+//! leaves the machine.
+
+use super::format_timestamp;
+use crate::Database;
+use chrono::{DateTime, Utc};
+use pctrl_core::{CommandUsage, EntityUsage, Result, UsageSummary};
+
+/// Metadata key of the on/off setting
+const USAGE_STATS_KEY: &str = "usage_stats";
+
+impl Database {
+    /// Record one invocation, unless collection is disabled.
+    ///
+    /// A single statement, so the instrumentation stays cheap.
+    pub async fn record_usage(&self, command: &str, success: bool, duration_ms: i64) -> Result<()> {
+        self.record_usage_at(command, success, duration_ms, Utc::now())
+            .await
+    }
+
+    /// Record an invocation at a specific time (imports, tests)
+    pub async fn record_usage_at(
+        &self,
+        command: &str,
+        success: bool,
+        duration_ms: i64,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO usage_stats (command, success, duration_ms, created_at)
+             SELECT ?, ?, ?, ?
+             WHERE NOT EXISTS (SELECT 1 FROM metadata WHERE key = ? AND value = 'off')",
+        )
+        .bind(command)
+        .bind(success)
+        .bind(duration_ms)
+        .bind(format_timestamp(at))
+        .bind(USAGE_STATS_KEY)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Whether usage statistics are collected (on by default)
+    pub async fn usage_stats_enabled(&self) -> Result<bool> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT value FROM metadata WHERE key = ?")
+            .bind(USAGE_STATS_KEY)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(row.map(|(v,)| v != "off").unwrap_or(true))
+    }
+
+    /// Turn collection on or off
+    pub async fn set_usage_stats_enabled(&self, enabled: bool) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES (?, ?)")
+            .bind(USAGE_STATS_KEY)
+            .bind(if enabled { "on" } else { "off" })
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Delete all recorded usage. Returns the number of removed rows.
+    pub async fn reset_usage_stats(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM usage_stats")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Aggregate usage since `since` (all time for `None`), top `limit` per list
+    pub async fn usage_summary(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<UsageSummary> {
+        let since = since.map(format_timestamp).unwrap_or_default();
+
+        // Durations per command, sorted, for averages and percentiles
+        let rows: Vec<(String, bool, i64)> = sqlx::query_as(
+            "SELECT command, success, duration_ms FROM usage_stats
+             WHERE created_at >= ? ORDER BY command, duration_ms",
+        )
+        .bind(&since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let total = rows.len() as i64;
+        let mut commands = Vec::new();
+        let mut start = 0;
+        while start < rows.len() {
+            let command = &rows[start].0;
+            let end = rows[start..]
+                .iter()
+                .position(|(c, _, _)| c != command)
+                .map_or(rows.len(), |i| start + i);
+            let group = &rows[start..end];
+
+            let failures = group.iter().filter(|(_, ok, _)| !ok).count() as i64;
+            let durations: Vec<i64> = group.iter().map(|(_, _, d)| *d).collect();
+            commands.push(CommandUsage::from_durations(command, failures, &durations));
+            start = end;
+        }
+        commands.sort_by(|a, b| b.count.cmp(&a.count).then(a.command.cmp(&b.command)));
+        commands.truncate(limit);
+
+        let busiest_days: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT substr(created_at, 1, 10) AS day, COUNT(*) AS n FROM usage_stats
+             WHERE created_at >= ? GROUP BY day ORDER BY n DESC, day DESC LIMIT ?",
+        )
+        .bind(&since)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        // Most-touched entities come from the audit log
+        let entity_rows: Vec<(String, String, String, i64)> = sqlx::query_as(
+            "SELECT entity_type, entity_id,
+                    (SELECT summary FROM audit_log l WHERE l.entity_type = a.entity_type
+                       AND l.entity_id = a.entity_id ORDER BY id DESC LIMIT 1),
+                    COUNT(*) AS n
+             FROM audit_log a WHERE created_at >= ?
+             GROUP BY entity_type, entity_id ORDER BY n DESC, MAX(id) DESC LIMIT ?",
+        )
+        .bind(&since)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let entities = entity_rows
+            .into_iter()
+            .filter_map(|(entity_type, entity_id, summary, changes)| {
+                Some(EntityUsage {
+                    entity_type: entity_type.parse().ok()?,
+                    entity_id,
+                    summary,
+                    changes,
+                })
+            })
+            .collect();
+
+        Ok(UsageSummary {
+            total,
+            commands,
+            busiest_days,
+            entities,
+        })
+    }
+}
@@ -0,0 +1,212 @@
+//! What's at a database path before pctrl runs DDL against it
+//!
+//! `--db` takes any path, and opening a file runs the whole schema against
+//! it. A file that already holds another application's data is refused
+//! unless the caller adopts it deliberately ([`Database::adopt`]); a file
+//! written by a newer pctrl is refused because this version would work on a
+//! schema it doesn't know.
+//!
+//! [`Database::adopt`]: crate::Database::adopt
+
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+use pctrl_core::Result;
+use sqlx::sqlite::SqlitePool;
+use std::io::Read;
+use std::path::Path;
+
+/// Extensions a new database file may have (or none)
+pub const EXTENSIONS: &[&str] = &["db", "sqlite", "sqlite3", "db3"];
+
+/// First bytes of every SQLite database file
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Contents of a database path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbFileKind {
+    /// No file, an empty one or a SQLite database without tables
+    New,
+    /// A pctrl database at this schema version or older
+    Pctrl(i32),
+    /// A pctrl database from a newer pctrl
+    NewerPctrl(i32),
+    /// A SQLite database with someone else's tables
+    Foreign(Vec<String>),
+    /// A file that isn't a SQLite database at all
+    NotSqlite,
+}
+
+/// Classify the file at `path` without changing it
+pub async fn inspect(path: &Path) -> Result<DbFileKind> {
+    let mut header = Vec::with_capacity(SQLITE_HEADER.len());
+    match std::fs::File::open(path) {
+        Ok(file) => {
+            file.take(SQLITE_HEADER.len() as u64)
+                .read_to_end(&mut header)
+                .map_err(|e| io_error(path, e))?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(DbFileKind::New),
+        Err(e) => return Err(io_error(path, e)),
+    }
+    if header.is_empty() {
+        return Ok(DbFileKind::New);
+    }
+    if header.as_slice() != SQLITE_HEADER {
+        return Ok(DbFileKind::NotSqlite);
+    }
+
+    let url = format!("sqlite:{}?mode=ro", path.display());
+    let pool = SqlitePool::connect(&url)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let kind = classify(&pool).await;
+    pool.close().await;
+    kind
+}
+
+async fn classify(pool: &SqlitePool) -> Result<DbFileKind> {
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let tables: Vec<String> = tables.into_iter().map(|(name,)| name).collect();
+    if tables.is_empty() {
+        return Ok(DbFileKind::New);
+    }
+
+    let has = |name: &str| tables.iter().any(|t| t == name);
+    if !has("metadata") {
+        return Ok(DbFileKind::Foreign(tables));
+    }
+    let version: Option<(String,)> =
+        sqlx::query_as("SELECT CAST(value AS TEXT) FROM metadata WHERE key = 'schema_version'")
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+    let version = match version {
+        Some((value,)) => match value.parse::<i32>() {
+            Ok(version) => version,
+            Err(_) => return Ok(DbFileKind::Foreign(tables)),
+        },
+        // Databases from before schema versions have pctrl's first tables
+        None if has("credentials") && has("ssh_connections") => 1,
+        None => return Ok(DbFileKind::Foreign(tables)),
+    };
+    if version > CURRENT_SCHEMA_VERSION {
+        Ok(DbFileKind::NewerPctrl(version))
+    } else {
+        Ok(DbFileKind::Pctrl(version))
+    }
+}
+
+/// Run SQLite's integrity check on the file at `path` without changing it;
+/// the error lists the first problems found
+pub async fn integrity_check(path: &Path) -> Result<()> {
+    let url = format!("sqlite:{}?mode=ro", path.display());
+    let pool = SqlitePool::connect(&url)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let rows: std::result::Result<Vec<(String,)>, _> = sqlx::query_as("PRAGMA integrity_check(5)")
+        .fetch_all(&pool)
+        .await;
+    pool.close().await;
+    let problems: Vec<String> = rows
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?
+        .into_iter()
+        .map(|(row,)| row)
+        .filter(|row| row != "ok")
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(pctrl_core::Error::Database(format!(
+            "'{}' failed the integrity check: {}",
+            path.display(),
+            problems.join("; ")
+        )))
+    }
+}
+
+/// Whether the pctrl database at `path` has an encryption salt, i.e. was
+/// opened with a password and needs it to read its secrets. Files that
+/// can't be read count as unencrypted; opening them reports why.
+pub async fn is_encrypted(path: &Path) -> bool {
+    let url = format!("sqlite:{}?mode=ro", path.display());
+    let Ok(pool) = SqlitePool::connect(&url).await else {
+        return false;
+    };
+    let salt: Option<(i64,)> =
+        sqlx::query_as("SELECT 1 FROM metadata WHERE key = 'encryption_salt'")
+            .fetch_optional(&pool)
+            .await
+            .unwrap_or(None);
+    pool.close().await;
+    salt.is_some()
+}
+
+/// Refuse paths pctrl can't or shouldn't create a database at. `creating`
+/// is whether the file is missing or empty; other files are judged by their
+/// contents.
+pub fn check_path(path: &Path, creating: bool) -> Result<()> {
+    let shown = path.display().to_string();
+    if shown.contains("://") {
+        return Err(pctrl_core::Error::Config(format!(
+            "'{}' looks like a URL; pctrl only uses SQLite files, pass a file path like ~/pctrl.db",
+            shown
+        )));
+    }
+    if path.is_dir() {
+        return Err(pctrl_core::Error::Config(format!(
+            "'{}' is a directory; pass a file inside it, e.g. {}",
+            shown,
+            path.join("pctrl.db").display()
+        )));
+    }
+    if !creating {
+        return Ok(());
+    }
+
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        if !EXTENSIONS.contains(&ext.as_str()) {
+            return Err(pctrl_core::Error::Config(format!(
+                "Won't create a database named '{}': use one of the extensions .{}",
+                shown,
+                EXTENSIONS.join(", .")
+            )));
+        }
+    }
+
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    if !parent.is_dir() {
+        return Err(pctrl_core::Error::Config(format!(
+            "Directory '{}' doesn't exist; create it first",
+            parent.display()
+        )));
+    }
+    // SQLite also needs the directory for its journal, so probe by writing
+    let probe = parent.join(format!(".pctrl-write-check-{}", std::process::id()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => Err(pctrl_core::Error::Config(format!(
+            "Can't create a database in '{}' ({}); choose a writable directory with --db",
+            parent.display(),
+            e
+        ))),
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> pctrl_core::Error {
+    pctrl_core::Error::Config(format!("Can't read '{}': {}", path.display(), e))
+}
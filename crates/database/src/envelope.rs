@@ -0,0 +1,246 @@
+//! Secrets sent between pctrl installations (`pctrl secret send/receive`)
+//!
+//! Every installation can have an X25519 identity. A sender encrypts an
+//! entity to the recipient's public key: a fresh ephemeral key agrees on a
+//! shared secret with it, HKDF-SHA256 turns that into a ChaCha20-Poly1305
+//! key, and the header is authenticated along with the ciphertext.
+//!
+//! Envelope (`.pctrlsecret`):
+//!
+//! | bytes | field                                   |
+//! |-------|-----------------------------------------|
+//! | 8     | magic `PCTRLSEC`                        |
+//! | 1     | format version                          |
+//! | 32    | recipient public key                    |
+//! | 32    | ephemeral public key                    |
+//! | 12    | nonce                                   |
+//! | rest  | ciphertext of the JSON payload with tag |
+//!
+//! The recipient key is in the clear so a wrong recipient gets a clear error
+//! instead of a failed decryption.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use hkdf::Hkdf;
+use pctrl_core::{Credential, DatabaseCredentials};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+/// File extension of envelopes
+pub const EXTENSION: &str = "pctrlsecret";
+
+pub const MAGIC: &[u8; 8] = b"PCTRLSEC";
+
+/// Current envelope format
+pub const FORMAT_VERSION: u8 = 1;
+
+pub const HEADER_LEN: usize = 8 + 1 + 32 + 32 + 12;
+
+/// Prefix of public keys in text form
+pub const KEY_PREFIX: &str = "pctrl1";
+
+/// HKDF info, bound to the format version
+const KDF_INFO: &[u8] = b"pctrl secret v1";
+
+/// Why an envelope or key can't be used
+#[derive(Debug, thiserror::Error)]
+pub enum EnvelopeError {
+    #[error("Not a pctrl secret")]
+    NotASecret,
+
+    #[error("Unsupported secret format version {0} (this pctrl reads version {FORMAT_VERSION})")]
+    UnsupportedVersion(u8),
+
+    #[error("This secret was encrypted for {0}, not for this identity")]
+    WrongRecipient(RecipientKey),
+
+    #[error("Secret was modified or damaged: decryption failed")]
+    Tampered,
+
+    #[error("Invalid public key: {0}")]
+    InvalidKey(String),
+
+    #[error("Invalid secret contents: {0}")]
+    Payload(String),
+}
+
+impl From<EnvelopeError> for pctrl_core::Error {
+    fn from(e: EnvelopeError) -> Self {
+        pctrl_core::Error::Config(e.to_string())
+    }
+}
+
+/// A public key to send secrets to, shown as `pctrl1` and 64 hex digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecipientKey(pub [u8; 32]);
+
+impl fmt::Display for RecipientKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", KEY_PREFIX)?;
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl FromStr for RecipientKey {
+    type Err = EnvelopeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let hex = s
+            .strip_prefix(KEY_PREFIX)
+            .ok_or_else(|| EnvelopeError::InvalidKey(format!("must start with {}", KEY_PREFIX)))?;
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(EnvelopeError::InvalidKey(format!(
+                "expected {} and 64 hex digits",
+                KEY_PREFIX
+            )));
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|e| EnvelopeError::InvalidKey(e.to_string()))?;
+        }
+        Ok(Self(key))
+    }
+}
+
+/// This installation's keypair
+pub struct Identity {
+    secret: StaticSecret,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        Self {
+            secret: StaticSecret::random_from_rng(OsRng),
+        }
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self {
+            secret: StaticSecret::from(bytes),
+        }
+    }
+
+    /// Private key bytes, for storage
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> RecipientKey {
+        RecipientKey(PublicKey::from(&self.secret).to_bytes())
+    }
+}
+
+/// What an envelope carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "entity", rename_all = "snake_case")]
+pub enum SharedSecret {
+    Credential(Credential),
+    Database(DatabaseCredentials),
+}
+
+impl SharedSecret {
+    pub fn name(&self) -> &str {
+        match self {
+            SharedSecret::Credential(c) => &c.name,
+            SharedSecret::Database(d) => &d.name,
+        }
+    }
+
+    /// "credential" or "database"
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SharedSecret::Credential(_) => "credential",
+            SharedSecret::Database(_) => "database",
+        }
+    }
+}
+
+/// Encrypt `secret` to `recipient`
+pub fn seal(secret: &SharedSecret, recipient: &RecipientKey) -> Result<Vec<u8>, EnvelopeError> {
+    let plaintext =
+        serde_json::to_vec(secret).map_err(|e| EnvelopeError::Payload(e.to_string()))?;
+
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient.0));
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut envelope = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    envelope.extend_from_slice(MAGIC);
+    envelope.push(FORMAT_VERSION);
+    envelope.extend_from_slice(&recipient.0);
+    envelope.extend_from_slice(ephemeral_public.as_bytes());
+    envelope.extend_from_slice(&nonce);
+
+    let cipher = cipher(shared.as_bytes(), ephemeral_public.as_bytes(), &recipient.0);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &envelope,
+            },
+        )
+        .map_err(|_| EnvelopeError::Payload("encryption failed".to_string()))?;
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Decrypt an envelope with this installation's identity
+pub fn open(envelope: &[u8], identity: &Identity) -> Result<SharedSecret, EnvelopeError> {
+    let recipient = recipient_of(envelope)?;
+    if recipient != identity.public_key() {
+        return Err(EnvelopeError::WrongRecipient(recipient));
+    }
+    let (header, ciphertext) = envelope.split_at(HEADER_LEN);
+    let mut ephemeral = [0u8; 32];
+    ephemeral.copy_from_slice(&header[41..73]);
+    let nonce = &header[73..HEADER_LEN];
+
+    let shared = identity.secret.diffie_hellman(&PublicKey::from(ephemeral));
+    let plaintext = cipher(shared.as_bytes(), &ephemeral, &recipient.0)
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| EnvelopeError::Tampered)?;
+    serde_json::from_slice(&plaintext).map_err(|e| EnvelopeError::Payload(e.to_string()))
+}
+
+/// Recipient an envelope was sealed for
+pub fn recipient_of(envelope: &[u8]) -> Result<RecipientKey, EnvelopeError> {
+    if envelope.len() < 9 || &envelope[..8] != MAGIC {
+        return Err(EnvelopeError::NotASecret);
+    }
+    if envelope[8] != FORMAT_VERSION {
+        return Err(EnvelopeError::UnsupportedVersion(envelope[8]));
+    }
+    if envelope.len() < HEADER_LEN + 16 {
+        return Err(EnvelopeError::Tampered);
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&envelope[9..41]);
+    Ok(RecipientKey(key))
+}
+
+fn cipher(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> ChaCha20Poly1305 {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(ephemeral);
+    salt[32..].copy_from_slice(recipient);
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(KDF_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF output length");
+    ChaCha20Poly1305::new(&key.into())
+}
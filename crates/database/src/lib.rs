@@ -5,8 +5,15 @@
 
 #![allow(clippy::type_complexity)]
 
+pub mod backup;
+pub mod bundle_archive;
 mod crud;
+pub mod db_file;
+pub mod envelope;
 mod migrations;
+pub mod prompt;
+
+pub use migrations::CURRENT_SCHEMA_VERSION;
 
 use aes_gcm::{
     aead::{Aead, KeyInit},
@@ -14,25 +21,161 @@ use aes_gcm::{
 };
 use argon2::password_hash::SaltString;
 use argon2::Argon2;
+use pctrl_core::demo::RESET_CONFIRMATION;
+use pctrl_core::hooks::HookRunner;
+use pctrl_core::lease;
 use pctrl_core::Result;
 use sqlx::sqlite::SqlitePool;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
 
 /// Database manager with encryption support
 pub struct Database {
     pub(crate) pool: SqlitePool,
     cipher: Option<Aes256Gcm>,
-    #[allow(dead_code)]
     encryption_salt: Option<Vec<u8>>,
+    /// Ignore advisory locks held by others (`--override-lock`)
+    lock_override: AtomicBool,
+    /// Runs user hooks after mutations; unset means no hooks
+    hooks: OnceLock<HookRunner>,
+    /// Schema of a file written by a newer pctrl, opened read-only
+    newer_schema: Option<i32>,
 }
 
 impl Database {
     /// Create a new database connection
     /// Path kann ein Dateipfad oder eine SQLite-URL sein
+    ///
+    /// Files holding another application's tables are refused; see
+    /// [`db_file`]. A file written by a newer pctrl is opened read-only
+    /// (see [`newer_schema`](Self::newer_schema)), and an older schema is
+    /// migrated under the write lease (see [`pctrl_core::lease`]).
     pub async fn new(path: &str, password: Option<&str>) -> Result<Self> {
+        Self::open(path, password, false).await
+    }
+
+    /// Like [`new`](Self::new), but initialize a SQLite file that holds
+    /// another application's tables instead of refusing it (`--adopt-db`)
+    pub async fn adopt(path: &str, password: Option<&str>) -> Result<Self> {
+        Self::open(path, password, true).await
+    }
+
+    /// Open another pctrl database for reading only, e.g. the other side of
+    /// `pctrl merge`. Nothing is created, migrated or sealed, so the file
+    /// must be at this version's schema. An encrypted file (see
+    /// [`db_file::is_encrypted`]) needs the password its secrets were
+    /// written with; a wrong one is refused here rather than on first use.
+    pub async fn open_read_only(path: &str, password: Option<&str>) -> Result<Self> {
+        use db_file::DbFileKind;
+
+        let file = std::path::Path::new(path);
+        match db_file::inspect(file).await? {
+            DbFileKind::Pctrl(version) if version == migrations::CURRENT_SCHEMA_VERSION => {}
+            DbFileKind::Pctrl(version) => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "'{}' has schema v{}, this version expects v{}; open it with this pctrl once (pctrl --db {} project list) to upgrade it",
+                    path,
+                    version,
+                    migrations::CURRENT_SCHEMA_VERSION,
+                    path
+                )))
+            }
+            DbFileKind::New => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "'{}' is not a pctrl database (missing or empty)",
+                    path
+                )))
+            }
+            DbFileKind::NewerPctrl(version) => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "'{}' was written by a newer pctrl (schema v{}, this version knows v{}); upgrade pctrl to read it",
+                    path,
+                    version,
+                    migrations::CURRENT_SCHEMA_VERSION
+                )))
+            }
+            DbFileKind::Foreign(_) | DbFileKind::NotSqlite => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "'{}' is not a pctrl database",
+                    path
+                )))
+            }
+        }
+        Self::connect_read_only(path, password).await
+    }
+
+    /// Connect without writing anything, not even the salt
+    async fn connect_read_only(path: &str, password: Option<&str>) -> Result<Self> {
+        let file = std::path::Path::new(path);
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(file)
+            .read_only(true)
+            .create_if_missing(false);
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        let salt: Option<(Vec<u8>,)> =
+            sqlx::query_as("SELECT value FROM metadata WHERE key = 'encryption_salt'")
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let (cipher, salt) = match (salt, password) {
+            (None, _) => (None, None),
+            (Some(_), None) => {
+                return Err(pctrl_core::Error::Config(format!(
+                    "'{}' is encrypted, password required",
+                    path
+                )))
+            }
+            (Some((salt,)), Some(password)) => {
+                let cipher = Self::cipher(password, &salt)?;
+                if let Some(sample) = Self::encrypted_sample(&pool).await {
+                    if decrypt_with(&cipher, &sample).is_err() {
+                        return Err(pctrl_core::Error::Config(format!(
+                            "Wrong password for '{}'",
+                            path
+                        )));
+                    }
+                }
+                (Some(cipher), Some(salt))
+            }
+        };
+
+        Ok(Self {
+            pool,
+            cipher,
+            encryption_salt: salt,
+            lock_override: AtomicBool::new(false),
+            hooks: OnceLock::new(),
+            newer_schema: None,
+        })
+    }
+
+    /// Schema version of the file when it was written by a newer pctrl. Such
+    /// a file is opened read-only: rows of a schema this version doesn't
+    /// know would be damaged by saving them back.
+    pub fn newer_schema(&self) -> Option<i32> {
+        self.newer_schema
+    }
+
+    async fn open(path: &str, password: Option<&str>, adopt: bool) -> Result<Self> {
         // SQLite URL: mode=rwc erstellt die DB automatisch wenn sie nicht existiert
         let url = if path.starts_with("sqlite:") {
             path.to_string()
         } else {
+            let kind = Self::guard_file(std::path::Path::new(path), adopt).await?;
+            if let db_file::DbFileKind::NewerPctrl(version) = kind {
+                tracing::warn!(
+                    "{} has schema v{}, this pctrl knows v{}; opening it read-only",
+                    path,
+                    version,
+                    migrations::CURRENT_SCHEMA_VERSION
+                );
+                let mut db = Self::connect_read_only(path, password).await?;
+                db.newer_schema = Some(version);
+                return Ok(db);
+            }
             format!("sqlite:{}?mode=rwc", path)
         };
 
@@ -43,29 +186,152 @@ impl Database {
         // Initialize metadata table first (needed for salt storage)
         Self::init_metadata_table(&pool).await?;
 
-        let (cipher, salt) = if let Some(pwd) = password {
-            // Get or create a cryptographically secure random salt
-            let salt = Self::get_or_create_salt(&pool).await?;
-
-            let key = Self::derive_key(pwd, &salt)?;
-            (Some(Aes256Gcm::new(&key.into())), Some(salt.to_vec()))
-        } else {
-            (None, None)
+        let (cipher, salt, verified) = match password {
+            Some(pwd) => {
+                let (salt, cipher, verified) = Self::unlock(&pool, path, pwd).await?;
+                (Some(cipher), Some(salt), verified)
+            }
+            None => (None, None, false),
         };
 
         let db = Self {
             pool,
             cipher,
             encryption_salt: salt,
+            lock_override: AtomicBool::new(false),
+            hooks: OnceLock::new(),
+            newer_schema: None,
         };
+        // Not while another process is migrating the schema
+        let holder = lease::current_lease_holder();
+        db.check_write_lease(&holder).await?;
         db.init_schema().await?;
 
-        // Run any pending migrations
-        migrations::run_migrations(&db.pool).await?;
+        // Run any pending migrations, holding the write lease
+        if migrations::pending(&db.pool).await? {
+            db.acquire_write_lease(&holder, chrono::Utc::now()).await?;
+            let migrated = migrations::run_migrations(&db.pool, Some(&holder)).await;
+            db.release_write_lease(&holder).await?;
+            migrated?;
+        }
+        db.record_writer_version().await?;
+        // Database passwords and Coolify keys were stored in plain text
+        // before they were encrypted. Sealing them under a password nothing
+        // has confirmed yet could lock them away for good, so a new key
+        // waits for the next open, which checks it.
+        if verified {
+            db.seal_plaintext_database_secrets().await?;
+            db.seal_plaintext_coolify_keys().await?;
+        }
+        // Entities written by older builds or raw inserts get their refs
+        db.backfill_short_refs().await?;
 
         Ok(db)
     }
 
+    /// Check a database file before any DDL runs against it
+    async fn guard_file(path: &std::path::Path, adopt: bool) -> Result<db_file::DbFileKind> {
+        use db_file::DbFileKind;
+
+        let creating = std::fs::metadata(path).map_or(true, |m| m.len() == 0);
+        db_file::check_path(path, creating)?;
+        let kind = db_file::inspect(path).await?;
+        match kind {
+            DbFileKind::New | DbFileKind::Pctrl(_) | DbFileKind::NewerPctrl(_) => Ok(kind),
+            DbFileKind::NotSqlite => Err(pctrl_core::Error::Config(format!(
+                "'{}' is not a SQLite database; check the --db path",
+                path.display()
+            ))),
+            DbFileKind::Foreign(_) if adopt => {
+                tracing::info!("Adopting foreign database {}", path.display());
+                Ok(kind)
+            }
+            DbFileKind::Foreign(tables) => Err(pctrl_core::Error::Config(format!(
+                "'{}' is a SQLite database of another application (tables: {}); pctrl won't add its tables to it. Check the --db path, or pass --adopt-db to use this file anyway",
+                path.display(),
+                summarize_tables(&tables)
+            ))),
+        }
+    }
+
+    /// Drop every table and recreate the schema from scratch.
+    ///
+    /// `confirm` must be the literal [`RESET_CONFIRMATION`]. The encryption
+    /// salt survives, so this connection's key stays valid. Long-running
+    /// frontends should [`close`](Self::close) and reopen the database after.
+    pub async fn reset_database(&self, confirm: &str) -> Result<()> {
+        if confirm != RESET_CONFIRMATION {
+            return Err(pctrl_core::Error::Config(format!(
+                "Refusing to reset the database: confirm with {}",
+                RESET_CONFIRMATION
+            )));
+        }
+
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        // Tables reference each other, so drop them without foreign key checks
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let mut dropped = Ok(());
+        for (table,) in &tables {
+            let sql = format!("DROP TABLE IF EXISTS \"{}\"", table.replace('"', "\"\""));
+            if let Err(e) = sqlx::query(&sql).execute(&mut *conn).await {
+                dropped = Err(pctrl_core::Error::Database(e.to_string()));
+                break;
+            }
+        }
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        dropped?;
+        drop(conn);
+
+        // Recreate through a fresh pool: the other pooled connections may still
+        // see the dropped tables, which fools the migrations' column checks
+        let pool = SqlitePool::connect_with((*self.pool.connect_options()).clone())
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Self::init_metadata_table(&pool).await?;
+        if let (Some(salt), Some(cipher)) = (&self.encryption_salt, &self.cipher) {
+            Self::store_salt(&pool, salt).await?;
+            Self::store_key_check(&pool, cipher).await?;
+        }
+        sqlx::query(SCHEMA_SQL)
+            .execute(&pool)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        migrations::run_migrations(&pool, None).await?;
+        pool.close().await;
+
+        Ok(())
+    }
+
+    /// Path of the database file
+    pub fn path(&self) -> std::path::PathBuf {
+        (*self.pool.connect_options())
+            .clone()
+            .get_filename()
+            .into_owned()
+    }
+
+    /// Close all connections; the database can't be used afterwards
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
     /// Initialize metadata table for storing encryption salt
     async fn init_metadata_table(pool: &SqlitePool) -> Result<()> {
         sqlx::query(
@@ -84,37 +350,212 @@ impl Database {
         Ok(())
     }
 
-    /// Get existing salt or create a new random one
-    async fn get_or_create_salt(pool: &SqlitePool) -> Result<[u8; 16]> {
-        // Try to get existing salt
-        let row: Option<(Vec<u8>,)> =
+    /// The salt and key for `password`, checked before anything is written.
+    ///
+    /// With encrypted values in the file, the password must decrypt one of
+    /// them under the stored salt or, for files from before the salt was
+    /// stored, the legacy path-based one; otherwise it is refused and the
+    /// file stays as it is. Only a file without encrypted values gets a new
+    /// salt. Either way a key check is stored, so the next open can tell a
+    /// wrong password even before any secret is saved. The flag says
+    /// whether the password was checked against something encrypted.
+    async fn unlock(
+        pool: &SqlitePool,
+        path: &str,
+        password: &str,
+    ) -> Result<(Vec<u8>, Aes256Gcm, bool)> {
+        let stored: Option<(Vec<u8>,)> =
             sqlx::query_as("SELECT value FROM metadata WHERE key = 'encryption_salt'")
                 .fetch_optional(pool)
                 .await
                 .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let stored = stored.map(|(salt,)| salt).filter(|salt| salt.len() >= 16);
+
+        let Some(sample) = Self::encrypted_sample(pool).await else {
+            let salt = match stored {
+                Some(salt) => salt,
+                None => {
+                    let salt = random_salt().to_vec();
+                    Self::store_salt(pool, &salt).await?;
+                    salt
+                }
+            };
+            let cipher = Self::cipher(password, &salt)?;
+            Self::store_key_check(pool, &cipher).await?;
+            return Ok((salt, cipher, false));
+        };
 
-        if let Some((salt_bytes,)) = row {
-            // Salt exists, use it
-            if salt_bytes.len() >= 16 {
-                let mut salt = [0u8; 16];
-                salt.copy_from_slice(&salt_bytes[..16]);
-                return Ok(salt);
+        let legacy = format!("{}{}", LEGACY_SALT_PREFIX, path).into_bytes();
+        for salt in stored.iter().chain([&legacy]) {
+            let cipher = Self::cipher(password, salt)?;
+            if decrypt_with(&cipher, &sample).is_err() {
+                continue;
+            }
+            if stored.as_ref() != Some(salt) {
+                // Secrets written before the salt was stored used the
+                // path-based salt. Storing it keeps them readable after
+                // the file moves.
+                tracing::warn!(
+                    "{} uses the legacy path-based encryption salt; Database::migrate_salt replaces it",
+                    path
+                );
+                Self::store_salt(pool, salt).await?;
             }
+            Self::store_key_check(pool, &cipher).await?;
+            return Ok((salt.clone(), cipher, true));
         }
+        Err(pctrl_core::Error::Config(format!(
+            "Wrong password for '{}'",
+            path
+        )))
+    }
 
-        // Generate new random salt
-        use rand::RngCore;
-        let mut salt = [0u8; 16];
-        rand::rngs::OsRng.fill_bytes(&mut salt);
-
-        // Store the salt
+    async fn store_salt<'e>(executor: impl sqlx::SqliteExecutor<'e>, salt: &[u8]) -> Result<()> {
         sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES ('encryption_salt', ?)")
-            .bind(salt.as_slice())
-            .execute(pool)
+            .bind(salt)
+            .execute(executor)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
 
-        Ok(salt)
+    /// Encrypt [`KEY_CHECK`] with `cipher`, so a wrong password is refused
+    /// on open
+    async fn store_key_check<'e>(
+        executor: impl sqlx::SqliteExecutor<'e>,
+        cipher: &Aes256Gcm,
+    ) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES ('key_check', ?)")
+            .bind(encrypt_with(cipher, KEY_CHECK)?)
+            .execute(executor)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Any encrypted value, to tell which key fits: the key check, else a
+    /// secret; `None` on a fresh file
+    async fn encrypted_sample(pool: &SqlitePool) -> Option<Vec<u8>> {
+        let check: Option<(Vec<u8>,)> =
+            sqlx::query_as("SELECT value FROM metadata WHERE key = 'key_check'")
+                .fetch_optional(pool)
+                .await
+                .ok()
+                .flatten();
+        if let Some((check,)) = check {
+            return Some(check);
+        }
+        // The tables don't exist yet in a new database
+        sqlx::query_as::<_, (Vec<u8>,)>(
+            "SELECT data FROM credentials UNION ALL SELECT secret FROM identity LIMIT 1",
+        )
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|(value,)| value)
+    }
+
+    /// Whether secrets are still encrypted with the legacy salt derived from
+    /// the database path
+    pub fn uses_legacy_salt(&self) -> bool {
+        self.encryption_salt
+            .as_deref()
+            .is_some_and(|salt| salt.starts_with(LEGACY_SALT_PREFIX.as_bytes()))
+    }
+
+    /// Re-encrypt all secrets with a fresh random salt, replacing the legacy
+    /// path-based one. `password` must be the one the database was opened
+    /// with; nothing changes if it doesn't decrypt the secrets. Returns
+    /// `false` when the database already has a random salt.
+    pub async fn migrate_salt(&mut self, password: &str) -> Result<bool> {
+        let Some(old_salt) = self
+            .encryption_salt
+            .as_deref()
+            .filter(|_| self.uses_legacy_salt())
+        else {
+            return Ok(false);
+        };
+        let old = Self::cipher(password, old_salt)?;
+        let salt = random_salt();
+        let new = Self::cipher(password, &salt)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let credentials: Vec<(String, Vec<u8>, Option<Vec<u8>>)> =
+            sqlx::query_as("SELECT id, data, key_material FROM credentials")
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id, data, key_material) in credentials {
+            let data = encrypt_with(&new, &decrypt_with(&old, &data)?)?;
+            let key_material = key_material
+                .map(|key| encrypt_with(&new, &decrypt_with(&old, &key)?))
+                .transpose()?;
+            sqlx::query("UPDATE credentials SET data = ?, key_material = ? WHERE id = ?")
+                .bind(data)
+                .bind(key_material)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        let identity: Option<(Vec<u8>,)> =
+            sqlx::query_as("SELECT secret FROM identity WHERE id = 1")
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        let databases: Vec<(String, Option<String>, Option<String>)> =
+            sqlx::query_as("SELECT id, password, connection_string FROM databases WHERE sealed")
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id, password, connection_string) in databases {
+            let reseal = |value: Option<String>| {
+                value
+                    .map(|text| seal_text(&new, &open_text(&old, &text)?))
+                    .transpose()
+            };
+            sqlx::query("UPDATE databases SET password = ?, connection_string = ? WHERE id = ?")
+                .bind(reseal(password)?)
+                .bind(reseal(connection_string)?)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        let instances: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, api_key FROM coolify_instances WHERE sealed")
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        for (id, api_key) in instances {
+            sqlx::query("UPDATE coolify_instances SET api_key = ? WHERE id = ?")
+                .bind(seal_text(&new, &open_text(&old, &api_key)?)?)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        if let Some((secret,)) = identity {
+            sqlx::query("UPDATE identity SET secret = ? WHERE id = 1")
+                .bind(encrypt_with(&new, &decrypt_with(&old, &secret)?)?)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        Self::store_salt(&mut *tx, &salt).await?;
+        Self::store_key_check(&mut *tx, &new).await?;
+        tx.commit()
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        self.cipher = Some(new);
+        self.encryption_salt = Some(salt.to_vec());
+        Ok(true)
     }
 
     /// Initialize database schema
@@ -127,8 +568,13 @@ impl Database {
         Ok(())
     }
 
+    fn cipher(password: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+        let key = Self::derive_key(password, salt)?;
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+
     /// Derive encryption key from password using Argon2 with a fixed salt
-    fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    pub(crate) fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
         use argon2::password_hash::PasswordHasher;
 
         // Create a SaltString from the provided salt bytes
@@ -150,52 +596,138 @@ impl Database {
         Ok(key)
     }
 
+    /// Whether secrets are encrypted with a database key
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
     /// Encrypt data
     /// Returns nonce (12 bytes) prepended to ciphertext
     pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        if let Some(cipher) = &self.cipher {
-            use rand::RngCore;
-            // Generate a cryptographically secure random nonce
-            let mut nonce_bytes = [0u8; 12];
-            rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
-            let nonce = Nonce::from_slice(&nonce_bytes);
-
-            let ciphertext = cipher
-                .encrypt(nonce, data)
-                .map_err(|e| pctrl_core::Error::Database(format!("Encryption failed: {}", e)))?;
-
-            // Prepend nonce to ciphertext for storage
-            let mut result = nonce_bytes.to_vec();
-            result.extend_from_slice(&ciphertext);
-            Ok(result)
-        } else {
-            Ok(data.to_vec())
+        match &self.cipher {
+            Some(cipher) => encrypt_with(cipher, data),
+            None => Ok(data.to_vec()),
         }
     }
 
     /// Decrypt data
     /// Expects nonce (12 bytes) prepended to ciphertext
     pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        if let Some(cipher) = &self.cipher {
-            if data.len() < 12 {
-                return Err(pctrl_core::Error::Database(
-                    "Invalid encrypted data: too short".to_string(),
-                ));
-            }
-
-            // Extract nonce from the first 12 bytes
-            let nonce = Nonce::from_slice(&data[..12]);
-            let ciphertext = &data[12..];
+        match &self.cipher {
+            Some(cipher) => decrypt_with(cipher, data),
+            None => Ok(data.to_vec()),
+        }
+    }
+    /// Encrypt a secret text column (database password, Coolify API key)
+    /// when the database has a key
+    pub(crate) fn seal_secret(&self, value: Option<&str>) -> Result<Option<String>> {
+        match (value, &self.cipher) {
+            (Some(text), Some(cipher)) => seal_text(cipher, text).map(Some),
+            (value, _) => Ok(value.map(str::to_string)),
+        }
+    }
 
-            cipher
-                .decrypt(nonce, ciphertext)
-                .map_err(|e| pctrl_core::Error::Database(format!("Decryption failed: {}", e)))
-        } else {
-            Ok(data.to_vec())
+    /// Decrypt a secret text column of a row whose `sealed` flag is set;
+    /// plain text (from before encryption, or a database without a key)
+    /// passes through
+    pub(crate) fn open_secret(
+        &self,
+        value: Option<String>,
+        sealed: bool,
+    ) -> Result<Option<String>> {
+        match (value, &self.cipher) {
+            (Some(text), Some(cipher)) if sealed => open_text(cipher, &text).map(Some),
+            (Some(_), None) if sealed => Err(pctrl_core::Error::Database(
+                "database is encrypted, password required".to_string(),
+            )),
+            (value, _) => Ok(value),
         }
     }
 }
 
+/// Prefix of the salt databases got before it was stored in `metadata`:
+/// `pctrl-salt-{path}`, which broke them when the file moved
+const LEGACY_SALT_PREFIX: &str = "pctrl-salt-";
+
+/// Plain text of the `key_check` metadata value
+const KEY_CHECK: &[u8] = b"pctrl key check";
+
+fn random_salt() -> [u8; 16] {
+    use rand::RngCore;
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+fn encrypt_with(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>> {
+    use rand::RngCore;
+    // Generate a cryptographically secure random nonce
+    let mut nonce_bytes = [0u8; 12];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, data)
+        .map_err(|e| pctrl_core::Error::Database(format!("Encryption failed: {}", e)))?;
+
+    // Prepend nonce to ciphertext for storage
+    let mut result = nonce_bytes.to_vec();
+    result.extend_from_slice(&ciphertext);
+    Ok(result)
+}
+
+/// Prefix of encrypted text columns (database passwords and connection
+/// strings, Coolify API keys); the rest is base64 of nonce + ciphertext.
+/// Whether a value is encrypted is up to the row's `sealed` flag, not the
+/// prefix, so a plain text value may start with it too.
+const ENCRYPTED_TEXT_PREFIX: &str = "enc:v1:";
+
+fn seal_text(cipher: &Aes256Gcm, text: &str) -> Result<String> {
+    use base64::Engine;
+    let sealed = encrypt_with(cipher, text.as_bytes())?;
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_TEXT_PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(sealed)
+    ))
+}
+
+fn open_text(cipher: &Aes256Gcm, text: &str) -> Result<String> {
+    use base64::Engine;
+    let encoded = text.strip_prefix(ENCRYPTED_TEXT_PREFIX).unwrap_or(text);
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| pctrl_core::Error::Database(format!("Invalid encrypted value: {}", e)))?;
+    String::from_utf8(decrypt_with(cipher, &sealed)?)
+        .map_err(|e| pctrl_core::Error::Database(format!("Invalid UTF-8: {}", e)))
+}
+
+fn decrypt_with(cipher: &Aes256Gcm, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 12 {
+        return Err(pctrl_core::Error::Database(
+            "Invalid encrypted data: too short".to_string(),
+        ));
+    }
+
+    // Extract nonce from the first 12 bytes
+    let nonce = Nonce::from_slice(&data[..12]);
+    let ciphertext = &data[12..];
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| pctrl_core::Error::Database(format!("Decryption failed: {}", e)))
+}
+
+/// The first few table names, for error messages
+fn summarize_tables(tables: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut list = tables[..tables.len().min(SHOWN)].join(", ");
+    if tables.len() > SHOWN {
+        list.push_str(&format!(" and {} more", tables.len() - SHOWN));
+    }
+    list
+}
+
 /// Database schema SQL
 const SCHEMA_SQL: &str = r#"
 CREATE TABLE IF NOT EXISTS ssh_connections (
@@ -212,6 +744,9 @@ CREATE TABLE IF NOT EXISTS docker_hosts (
     id TEXT PRIMARY KEY,
     name TEXT NOT NULL,
     url TEXT NOT NULL,
+    tls_ca TEXT,
+    tls_cert TEXT,
+    tls_key TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -220,6 +755,8 @@ CREATE TABLE IF NOT EXISTS coolify_instances (
     name TEXT NOT NULL,
     url TEXT NOT NULL,
     api_key TEXT NOT NULL,
+    -- 1 when api_key is "enc:v1:<base64>"
+    sealed INTEGER NOT NULL DEFAULT 0,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -228,11 +765,13 @@ CREATE TABLE IF NOT EXISTS git_repos (
     name TEXT NOT NULL,
     path TEXT NOT NULL,
     remote_url TEXT,
+    credential_id TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
 CREATE TABLE IF NOT EXISTS changelog (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id TEXT,
     version TEXT NOT NULL,
     content TEXT NOT NULL,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
@@ -255,6 +794,8 @@ CREATE TABLE IF NOT EXISTS credentials (
     credential_type TEXT NOT NULL,
     data TEXT NOT NULL,
     notes TEXT,
+    key_material BLOB,
+    short_ref TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -268,6 +809,7 @@ CREATE TABLE IF NOT EXISTS projects (
     color TEXT,
     icon TEXT,
     notes TEXT,
+    short_ref TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
@@ -283,7 +825,12 @@ CREATE TABLE IF NOT EXISTS servers (
     location TEXT,
     specs TEXT,
     notes TEXT,
+    requires_vpn TEXT,
+    max_containers INTEGER,
+    max_memory_mb_allocated INTEGER,
+    short_ref TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TEXT,
     FOREIGN KEY (credential_id) REFERENCES credentials(id)
 );
 
@@ -299,6 +846,8 @@ CREATE TABLE IF NOT EXISTS domains (
     server_id TEXT,
     container_id TEXT,
     notes TEXT,
+    superseded_by TEXT,
+    short_ref TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (server_id) REFERENCES servers(id)
 );
@@ -312,11 +861,14 @@ CREATE TABLE IF NOT EXISTS databases (
     port INTEGER,
     database_name TEXT,
     username TEXT,
+    -- "enc:v1:<base64>" when sealed is 1
     password TEXT,
     connection_string TEXT,
     server_id TEXT,
     container_id TEXT,
     notes TEXT,
+    short_ref TEXT,
+    sealed INTEGER NOT NULL DEFAULT 0,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (server_id) REFERENCES servers(id)
 );
@@ -332,6 +884,7 @@ CREATE TABLE IF NOT EXISTS containers (
     ports TEXT,
     env_vars TEXT,
     labels TEXT,
+    memory_limit_mb INTEGER,
     created_at DATETIME,
     updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (server_id) REFERENCES servers(id),
@@ -354,6 +907,13 @@ CREATE TABLE IF NOT EXISTS scripts (
     last_result TEXT,
     exit_code INTEGER,
     last_output TEXT,
+    working_dir TEXT,
+    env TEXT,
+    run_window TEXT,
+    namespace TEXT,
+    last_cwd TEXT,
+    last_env TEXT,
+    short_ref TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (server_id) REFERENCES servers(id),
     FOREIGN KEY (project_id) REFERENCES projects(id),
@@ -368,6 +928,7 @@ CREATE TABLE IF NOT EXISTS project_resources (
     resource_id TEXT NOT NULL,
     role TEXT,
     notes TEXT,
+    start_order INTEGER,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (project_id) REFERENCES projects(id)
 );
@@ -382,4 +943,307 @@ CREATE TABLE IF NOT EXISTS discovery_cache (
     expires_at DATETIME,
     FOREIGN KEY (server_id) REFERENCES servers(id)
 );
+
+-- Advisory locks (one active lock per entity)
+CREATE TABLE IF NOT EXISTS entity_locks (
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    holder TEXT NOT NULL,
+    reason TEXT,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    PRIMARY KEY (entity_type, entity_id)
+);
+
+-- Audit log (written by every entity save/remove)
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    details TEXT,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log (entity_type, entity_id);
+
+-- CLI command history (subcommand path only, never arguments)
+CREATE TABLE IF NOT EXISTS command_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    created_at TEXT NOT NULL
+);
+
+-- Local usage statistics (subcommand path and timing only)
+CREATE TABLE IF NOT EXISTS usage_stats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Server metric samples (from `server status`), used for forecasts
+CREATE TABLE IF NOT EXISTS server_samples (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id TEXT NOT NULL,
+    disk_used_percent REAL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_server_samples_server ON server_samples (server_id, created_at);
+
+-- Hook executions (see pctrl_core::hooks)
+CREATE TABLE IF NOT EXISTS hook_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT NOT NULL,
+    hook TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    exit_code INTEGER,
+    timed_out BOOLEAN NOT NULL,
+    output TEXT,
+    duration_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_hook_runs_hook ON hook_runs (event, hook);
+
+-- Docker network topology per host (replaced by every `docker sync`)
+CREATE TABLE IF NOT EXISTS docker_networks (
+    host_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    driver TEXT,
+    synced_at TEXT NOT NULL,
+    PRIMARY KEY (host_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS container_networks (
+    host_id TEXT NOT NULL,
+    container TEXT NOT NULL,
+    network TEXT NOT NULL,
+    ip_address TEXT,
+    synced_at TEXT NOT NULL,
+    PRIMARY KEY (host_id, container, network)
+);
+
+CREATE TABLE IF NOT EXISTS container_ports (
+    host_id TEXT NOT NULL,
+    container TEXT NOT NULL,
+    host_port INTEGER NOT NULL,
+    container_port INTEGER NOT NULL,
+    protocol TEXT NOT NULL
+);
+
+-- Pre-flight settings for `project deploy` (one row per configured project)
+CREATE TABLE IF NOT EXISTS project_preflight (
+    project_id TEXT PRIMARY KEY,
+    health_url TEXT,
+    advisory TEXT NOT NULL DEFAULT '',
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+-- Deploy settings for `project ship` (one row per configured project)
+CREATE TABLE IF NOT EXISTS project_deploy (
+    project_id TEXT PRIMARY KEY,
+    path TEXT,
+    branch TEXT,
+    server_id TEXT,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+-- `project ship` runs
+CREATE TABLE IF NOT EXISTS ship_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id TEXT NOT NULL,
+    server_id TEXT NOT NULL,
+    branch TEXT NOT NULL,
+    old_commit TEXT,
+    new_commit TEXT,
+    success BOOLEAN NOT NULL,
+    failed_phase TEXT,
+    error TEXT,
+    output TEXT,
+    duration_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_ship_runs_project ON ship_runs (project_id, id);
+
+-- Open maintenance windows (`project maintenance`), one per project
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    project_id TEXT PRIMARY KEY,
+    previous_status TEXT NOT NULL,
+    reason TEXT,
+    started_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+-- DNS propagations the monitor checks until complete (`domain propagation --track`)
+CREATE TABLE IF NOT EXISTS dns_propagations (
+    domain TEXT PRIMARY KEY,
+    expected TEXT NOT NULL,
+    resolvers TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+-- Deploy keys on servers whose removal hasn't been confirmed yet
+-- (`server exec --with-deploy-key`); removed on the next run against the server
+CREATE TABLE IF NOT EXISTS pending_deploy_keys (
+    path TEXT PRIMARY KEY,
+    server_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Named inventory snapshots (gzip-compressed JSON, secrets excluded)
+CREATE TABLE IF NOT EXISTS snapshots (
+    name TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    counts TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+
+-- User settings (`pctrl config`), validated by pctrl_core::settings
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Bumped by every settings write; a running TUI polls it to notice changes
+CREATE TABLE IF NOT EXISTS settings_generation (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    generation INTEGER NOT NULL
+);
+
+-- Proposed changes to dangerous scripts awaiting approval (`script_approval`)
+CREATE TABLE IF NOT EXISTS script_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    script_id TEXT NOT NULL,
+    base_command TEXT NOT NULL,
+    base_dangerous INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    dangerous INTEGER NOT NULL,
+    author TEXT NOT NULL,
+    author_uid INTEGER,
+    created_at TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    decided_by TEXT,
+    decided_at TEXT
+);
+
+-- Every execution of a script; output capped at 256 KB
+CREATE TABLE IF NOT EXISTS script_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    script_id TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    exit_code INTEGER,
+    output TEXT,
+    triggered_by TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_script_runs_script ON script_runs (script_id, id);
+
+-- Last completed `pctrl monitor run` cycle (single row)
+CREATE TABLE IF NOT EXISTS monitor_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    last_cycle_at TEXT NOT NULL,
+    servers_checked INTEGER NOT NULL,
+    servers_down INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    interval_secs INTEGER NOT NULL,
+    heartbeat_error TEXT
+);
+
+-- Servers going down or coming back up, seen by `pctrl monitor run`
+CREATE TABLE IF NOT EXISTS monitor_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id TEXT NOT NULL,
+    server_name TEXT NOT NULL,
+    up BOOLEAN NOT NULL,
+    error TEXT,
+    created_at TEXT NOT NULL
+);
+
+-- Host facts (replaced by every `server facts` collection)
+CREATE TABLE IF NOT EXISTS server_facts (
+    server_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    collected_at TEXT NOT NULL,
+    PRIMARY KEY (server_id, key)
+);
+
+-- Free-form key/value metadata of entities (`pctrl server meta`)
+CREATE TABLE IF NOT EXISTS entity_metadata (
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (entity_type, entity_id, key)
+);
+
+-- This installation's X25519 identity for `pctrl secret` (single row);
+-- the private key is encrypted like credential data
+CREATE TABLE IF NOT EXISTS identity (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    public_key TEXT NOT NULL,
+    secret BLOB NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- systemd units on servers (`pctrl service`), with the state of the last status check
+CREATE TABLE IF NOT EXISTS services (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    server_id TEXT NOT NULL,
+    unit TEXT NOT NULL,
+    project_id TEXT,
+    last_state TEXT,
+    last_checked TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_services_server ON services (server_id);
+
+-- Compose stacks per server (`pctrl compose`), from the containers' labels
+CREATE TABLE IF NOT EXISTS compose_projects (
+    server_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    config_path TEXT,
+    services INTEGER NOT NULL,
+    running INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (server_id, name)
+);
+
+-- Report of the last run of each fan-out command (`pctrl last-run`), as JSON
+CREATE TABLE IF NOT EXISTS last_runs (
+    command TEXT PRIMARY KEY,
+    report TEXT NOT NULL,
+    finished_at TEXT NOT NULL
+);
+
+-- Write lease held while migrating the schema (single row, see pctrl_core::lease)
+CREATE TABLE IF NOT EXISTS write_lease (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    holder TEXT NOT NULL,
+    version TEXT NOT NULL,
+    schema_version INTEGER NOT NULL,
+    acquired_at TEXT NOT NULL,
+    heartbeat_at TEXT NOT NULL
+);
+
+-- Ops journal (`pctrl server log`); append-only, corrections amend an entry
+CREATE TABLE IF NOT EXISTS journal_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    author TEXT NOT NULL,
+    text TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    amends INTEGER REFERENCES journal_entries(id)
+);
+CREATE INDEX IF NOT EXISTS idx_journal_entries_entity ON journal_entries (entity_type, entity_id, id);
 "#;
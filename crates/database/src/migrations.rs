@@ -1,19 +1,59 @@
 //! Database schema migrations
 //!
-//! This module handles automatic schema migrations when the database
-//! schema version is outdated.
+//! `SCHEMA_SQL` only creates what's missing, so a table from an older
+//! version keeps its old columns. The schema version lives in the metadata
+//! table, and every version after the first has a migration here that
+//! brings such a table up to date. Each migration runs in its own
+//! transaction together with the version bump, so a failed one leaves the
+//! database at the version before it.
 
 use pctrl_core::Result;
-use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use sqlx::Connection;
 
 /// Current schema version
-pub const CURRENT_SCHEMA_VERSION: i32 = 4;
+pub const CURRENT_SCHEMA_VERSION: i32 = 22;
 
-/// Run all pending migrations
-pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    let current_version = get_schema_version(pool).await?;
+/// Whether the schema is older than this version's. A schema newer than
+/// this version's is an error: nothing here knows how to treat it.
+pub async fn pending(pool: &SqlitePool) -> Result<bool> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let version = get_schema_version(&mut conn).await?;
+    check_supported(version)?;
+    Ok(version < CURRENT_SCHEMA_VERSION)
+}
+
+/// Refuse a schema written by a newer pctrl
+fn check_supported(version: i32) -> Result<()> {
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(pctrl_core::Error::Database(format!(
+            "Database schema v{} is newer than this pctrl supports (v{}); upgrade pctrl",
+            version, CURRENT_SCHEMA_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Run all pending migrations, refreshing the heartbeat of `lease_holder`'s
+/// write lease after each one.
+///
+/// Everything runs on one connection: a pooled connection that didn't see a
+/// table being rebuilt may act on its old schema. Foreign keys are off while
+/// migrating, since rebuilding a table drops the one others reference and
+/// the pragma can't change inside a transaction.
+pub async fn run_migrations(pool: &SqlitePool, lease_holder: Option<&str>) -> Result<()> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    let conn = &mut *conn;
+    let current_version = get_schema_version(conn).await?;
+    check_supported(current_version)?;
 
-    if current_version >= CURRENT_SCHEMA_VERSION {
+    if current_version == CURRENT_SCHEMA_VERSION {
         return Ok(());
     }
 
@@ -23,21 +63,51 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         CURRENT_SCHEMA_VERSION
     );
 
-    // Run migrations sequentially
+    set_foreign_keys(conn, false).await?;
+    let migrated = run_pending(conn, current_version, lease_holder).await;
+    set_foreign_keys(conn, true).await?;
+    migrated
+}
+
+/// Migrate from `current_version` up, one transaction per version
+async fn run_pending(
+    conn: &mut SqliteConnection,
+    current_version: i32,
+    lease_holder: Option<&str>,
+) -> Result<()> {
+    let db_err = |e: sqlx::Error| pctrl_core::Error::Database(e.to_string());
     for version in (current_version + 1)..=CURRENT_SCHEMA_VERSION {
-        run_migration(pool, version).await?;
-        set_schema_version(pool, version).await?;
+        let mut tx = conn.begin().await.map_err(db_err)?;
+        run_migration(&mut tx, version).await.map_err(|e| {
+            pctrl_core::Error::Database(format!("Migration to schema v{} failed: {}", version, e))
+        })?;
+        set_schema_version(&mut tx, version).await?;
+        tx.commit().await.map_err(db_err)?;
+        if let Some(holder) = lease_holder {
+            crate::crud::lease::heartbeat(&mut *conn, holder, chrono::Utc::now()).await?;
+        }
         tracing::info!("Migration v{} completed", version);
     }
+    Ok(())
+}
 
+async fn set_foreign_keys(conn: &mut SqliteConnection, on: bool) -> Result<()> {
+    sqlx::query(if on {
+        "PRAGMA foreign_keys = ON"
+    } else {
+        "PRAGMA foreign_keys = OFF"
+    })
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
     Ok(())
 }
 
 /// Get current schema version from metadata table
-async fn get_schema_version(pool: &SqlitePool) -> Result<i32> {
+async fn get_schema_version(conn: &mut SqliteConnection) -> Result<i32> {
     let row: Option<(String,)> =
         sqlx::query_as("SELECT value FROM metadata WHERE key = 'schema_version'")
-            .fetch_optional(pool)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
@@ -50,10 +120,10 @@ async fn get_schema_version(pool: &SqlitePool) -> Result<i32> {
 }
 
 /// Set schema version in metadata table
-async fn set_schema_version(pool: &SqlitePool, version: i32) -> Result<()> {
+async fn set_schema_version(conn: &mut SqliteConnection, version: i32) -> Result<()> {
     sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES ('schema_version', ?)")
         .bind(version.to_string())
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
@@ -61,30 +131,48 @@ async fn set_schema_version(pool: &SqlitePool, version: i32) -> Result<()> {
 }
 
 /// Run a specific migration
-async fn run_migration(pool: &SqlitePool, version: i32) -> Result<()> {
+async fn run_migration(conn: &mut SqliteConnection, version: i32) -> Result<()> {
     match version {
-        2 => migrate_v2(pool).await,
-        3 => migrate_v3(pool).await,
-        4 => migrate_v4(pool).await,
+        2 => migrate_v2(conn).await,
+        3 => migrate_v3(conn).await,
+        4 => migrate_v4(conn).await,
+        5 => migrate_v5(conn).await,
+        6 => migrate_v6(conn).await,
+        7 => migrate_v7(conn).await,
+        8 => migrate_v8(conn).await,
+        9 => migrate_v9(conn).await,
+        10 => migrate_v10(conn).await,
+        11 => migrate_v11(conn).await,
+        12 => migrate_v12(conn).await,
+        13 => migrate_v13(conn).await,
+        14 => migrate_v14(conn).await,
+        15 => migrate_v15(conn).await,
+        16 => migrate_v16(conn).await,
+        17 => migrate_v17(conn).await,
+        18 => migrate_v18(conn).await,
+        19 => migrate_v19(conn).await,
+        20 => migrate_v20(conn).await,
+        21 => migrate_v21(conn).await,
+        22 => migrate_v22(conn).await,
         _ => Ok(()), // Unknown version, skip
     }
 }
 
 /// Migration v1 -> v2: Add missing columns to scripts table
-async fn migrate_v2(pool: &SqlitePool) -> Result<()> {
+async fn migrate_v2(conn: &mut SqliteConnection) -> Result<()> {
     // Check if columns exist before adding them
-    let columns = get_table_columns(pool, "scripts").await?;
+    let columns = get_table_columns(conn, "scripts").await?;
 
     if !columns.contains(&"exit_code".to_string()) {
         sqlx::query("ALTER TABLE scripts ADD COLUMN exit_code INTEGER")
-            .execute(pool)
+            .execute(&mut *conn)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
     }
 
     if !columns.contains(&"last_output".to_string()) {
         sqlx::query("ALTER TABLE scripts ADD COLUMN last_output TEXT")
-            .execute(pool)
+            .execute(&mut *conn)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
     }
@@ -93,10 +181,10 @@ async fn migrate_v2(pool: &SqlitePool) -> Result<()> {
 }
 
 /// Get list of column names for a table
-async fn get_table_columns(pool: &SqlitePool, table: &str) -> Result<Vec<String>> {
+async fn get_table_columns(conn: &mut SqliteConnection, table: &str) -> Result<Vec<String>> {
     let rows: Vec<(String,)> =
         sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
-            .fetch_all(pool)
+            .fetch_all(&mut *conn)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
@@ -104,8 +192,8 @@ async fn get_table_columns(pool: &SqlitePool, table: &str) -> Result<Vec<String>
 }
 
 /// Migration v2 -> v3: Rename ssh_connection_id to credential_id in servers
-async fn migrate_v3(pool: &SqlitePool) -> Result<()> {
-    let columns = get_table_columns(pool, "servers").await?;
+async fn migrate_v3(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "servers").await?;
 
     // Only migrate if old column exists and new one doesn't
     if columns.contains(&"ssh_connection_id".to_string())
@@ -113,7 +201,7 @@ async fn migrate_v3(pool: &SqlitePool) -> Result<()> {
     {
         // SQLite 3.25.0+ supports RENAME COLUMN
         sqlx::query("ALTER TABLE servers RENAME COLUMN ssh_connection_id TO credential_id")
-            .execute(pool)
+            .execute(&mut *conn)
             .await
             .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
     }
@@ -122,8 +210,9 @@ async fn migrate_v3(pool: &SqlitePool) -> Result<()> {
 }
 
 /// Migration v3 -> v4: Fix servers FK to reference credentials instead of ssh_connections
-async fn migrate_v4(pool: &SqlitePool) -> Result<()> {
+async fn migrate_v4(conn: &mut SqliteConnection) -> Result<()> {
     // SQLite doesn't support ALTER FK, so we need to recreate the table
+
     // First, clear invalid credential_id references
 
     // Set credential_id to NULL where it doesn't exist in credentials table
@@ -135,17 +224,11 @@ async fn migrate_v4(pool: &SqlitePool) -> Result<()> {
           AND credential_id NOT IN (SELECT id FROM credentials)
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-    // Disable FK checks temporarily
-    sqlx::query("PRAGMA foreign_keys = OFF")
-        .execute(pool)
-        .await
-        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
-
-    // Create new table with correct FK
+    // Create new table (foreign keys are off while migrating) with correct FK
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS servers_new (
@@ -163,7 +246,7 @@ async fn migrate_v4(pool: &SqlitePool) -> Result<()> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
@@ -175,27 +258,446 @@ async fn migrate_v4(pool: &SqlitePool) -> Result<()> {
         FROM servers
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
     // Drop old table
     sqlx::query("DROP TABLE servers")
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
     // Rename new table
     sqlx::query("ALTER TABLE servers_new RENAME TO servers")
-        .execute(pool)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Migration v4 -> v5: Add deleted_at to servers for soft delete (trash)
+async fn migrate_v5(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "servers").await?;
+
+    if !columns.contains(&"deleted_at".to_string()) {
+        sqlx::query("ALTER TABLE servers ADD COLUMN deleted_at TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v5 -> v6: Add start_order to project_resources
+async fn migrate_v6(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "project_resources").await?;
+
+    if !columns.contains(&"start_order".to_string()) {
+        sqlx::query("ALTER TABLE project_resources ADD COLUMN start_order INTEGER")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v6 -> v7: Add superseded_by to domains (`domain migrate-base`)
+async fn migrate_v7(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "domains").await?;
+
+    if !columns.contains(&"superseded_by".to_string()) {
+        sqlx::query("ALTER TABLE domains ADD COLUMN superseded_by TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v7 -> v8: Add requires_vpn to servers
+async fn migrate_v8(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "servers").await?;
+
+    if !columns.contains(&"requires_vpn".to_string()) {
+        sqlx::query("ALTER TABLE servers ADD COLUMN requires_vpn TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v8 -> v9: Working directory, environment and last run context
+/// of local scripts
+async fn migrate_v9(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "scripts").await?;
+
+    for column in ["working_dir", "env", "last_cwd", "last_env"] {
+        if !columns.contains(&column.to_string()) {
+            sqlx::query(&format!("ALTER TABLE scripts ADD COLUMN {} TEXT", column))
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Migration v9 -> v10: Quotas of servers and memory limits of synced containers
+async fn migrate_v10(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "servers").await?;
+
+    for column in ["max_containers", "max_memory_mb_allocated"] {
+        if !columns.contains(&column.to_string()) {
+            sqlx::query(&format!(
+                "ALTER TABLE servers ADD COLUMN {} INTEGER",
+                column
+            ))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+    }
+
+    let columns = get_table_columns(conn, "containers").await?;
+    if !columns.contains(&"memory_limit_mb".to_string()) {
+        sqlx::query("ALTER TABLE containers ADD COLUMN memory_limit_mb INTEGER")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v10 -> v11: Client certificate files of https:// Docker hosts
+async fn migrate_v11(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "docker_hosts").await?;
+
+    for column in ["tls_ca", "tls_cert", "tls_key"] {
+        if !columns.contains(&column.to_string()) {
+            sqlx::query(&format!(
+                "ALTER TABLE docker_hosts ADD COLUMN {} TEXT",
+                column
+            ))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Migration v11 -> v12: Short refs of entities (`srv-3fk2`), backfilled
+async fn migrate_v12(conn: &mut SqliteConnection) -> Result<()> {
+    for table in [
+        "projects",
+        "servers",
+        "domains",
+        "databases",
+        "scripts",
+        "credentials",
+    ] {
+        let columns = get_table_columns(conn, table).await?;
+        if !columns.contains(&"short_ref".to_string()) {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN short_ref TEXT", table))
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+        sqlx::query(&format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_{table}_short_ref ON {table} (short_ref)"
+        ))
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    crate::crud::short_ref::backfill(conn).await?;
+
+    Ok(())
+}
+
+/// Migration v12 -> v13: Docker host and container of docker scripts,
+/// missing from scripts tables created before they existed
+async fn migrate_v13(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "scripts").await?;
+
+    if !columns.contains(&"docker_host_id".to_string()) {
+        sqlx::query(
+            "ALTER TABLE scripts ADD COLUMN docker_host_id TEXT REFERENCES docker_hosts(id)",
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    if !columns.contains(&"container_id".to_string()) {
+        sqlx::query("ALTER TABLE scripts ADD COLUMN container_id TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v13 -> v14: Names unique ignoring case. Rows whose names only
+/// differ in case from an older row's are renamed "name (2)", "name (3)",
+/// ... first; trashed servers keep theirs. Domains are not renamed: DNS
+/// names ignore case, so such rows are the same domain twice and the
+/// migration stops with a list of them.
+async fn migrate_v14(conn: &mut SqliteConnection) -> Result<()> {
+    for (table, column, live) in [
+        ("projects", "name", ""),
+        ("servers", "name", " WHERE deleted_at IS NULL"),
+        ("domains", "domain", ""),
+        ("databases", "name", ""),
+        ("scripts", "name", ""),
+        ("credentials", "name", ""),
+    ] {
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, {column} FROM {table}{live} ORDER BY rowid"
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+        if table == "domains" {
+            refuse_case_duplicates(&rows)?;
+        }
+
+        // SQLite's NOCASE only folds ASCII
+        let mut taken: std::collections::HashSet<String> = std::collections::HashSet::new();
+        for (id, name) in rows {
+            if taken.insert(name.to_ascii_lowercase()) {
+                continue;
+            }
+            let renamed = (2..)
+                .map(|n| format!("{} ({})", name, n))
+                .find(|candidate| !taken.contains(&candidate.to_ascii_lowercase()))
+                .expect("some suffix is free");
+            tracing::warn!(
+                "Renaming {} '{}' ({}) to '{}': the name is taken ignoring case",
+                table,
+                name,
+                id,
+                renamed
+            );
+            taken.insert(renamed.to_ascii_lowercase());
+            sqlx::query(&format!("UPDATE {table} SET {column} = ? WHERE id = ?"))
+                .bind(&renamed)
+                .bind(&id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        }
+
+        sqlx::query(&format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_{table}_{column}_nocase ON {table} ({column} COLLATE NOCASE){live}"
+        ))
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Fail with every group of domains that only differ in case
+fn refuse_case_duplicates(rows: &[(String, String)]) -> Result<()> {
+    let mut groups: Vec<Vec<&(String, String)>> = Vec::new();
+    for row in rows {
+        let key = row.1.to_ascii_lowercase();
+        match groups
+            .iter_mut()
+            .find(|g| g[0].1.to_ascii_lowercase() == key)
+        {
+            Some(group) => group.push(row),
+            None => groups.push(vec![row]),
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    if groups.is_empty() {
+        return Ok(());
+    }
+
+    let mut report = "these domains only differ in case, and domain names ignore case:".to_string();
+    for group in groups {
+        let entries: Vec<String> = group
+            .iter()
+            .map(|(id, domain)| format!("{} (ID {})", domain, id))
+            .collect();
+        report.push_str(&format!("\n  {}", entries.join(", ")));
+    }
+    report.push_str(
+        "\nRemove all but one of each with the pctrl version that created this database \
+         (pctrl domain remove <ID>), then open it again. Nothing was changed.",
+    );
+    Err(pctrl_core::Error::Database(report))
+}
+
+/// Migration v14 -> v15: Run windows of dangerous scripts
+async fn migrate_v15(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "scripts").await?;
+
+    if !columns.contains(&"run_window".to_string()) {
+        sqlx::query("ALTER TABLE scripts ADD COLUMN run_window TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v15 -> v16: Private keys stored inline with SSH credentials
+async fn migrate_v16(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "credentials").await?;
+
+    if !columns.contains(&"key_material".to_string()) {
+        sqlx::query("ALTER TABLE credentials ADD COLUMN key_material BLOB")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v16 -> v17: Script namespaces, derived from the names
+async fn migrate_v17(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "scripts").await?;
+
+    if !columns.contains(&"namespace".to_string()) {
+        sqlx::query("ALTER TABLE scripts ADD COLUMN namespace TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_scripts_namespace ON scripts (namespace)")
+        .execute(&mut *conn)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
 
-    // Re-enable FK checks
-    sqlx::query("PRAGMA foreign_keys = ON")
-        .execute(pool)
+    let named: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, name FROM scripts WHERE name LIKE '%/%'")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    for (id, name) in named {
+        sqlx::query("UPDATE scripts SET namespace = ? WHERE id = ?")
+            .bind(pctrl_core::script_namespace::namespace(&name))
+            .bind(&id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v17 -> v18: Credential for pushing to a Git repository's remote
+async fn migrate_v18(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "git_repos").await?;
+
+    if !columns.contains(&"credential_id".to_string()) {
+        sqlx::query("ALTER TABLE git_repos ADD COLUMN credential_id TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v18 -> v19: Changelogs belong to a Git repository, one per
+/// version
+async fn migrate_v19(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "changelog").await?;
+
+    if !columns.contains(&"repo_id".to_string()) {
+        sqlx::query("ALTER TABLE changelog ADD COLUMN repo_id TEXT")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_changelog_repo_version ON changelog (repo_id, version)",
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Migration v19 -> v20: Flag encrypted database secrets and Coolify keys.
+/// Rows sealed before the flag existed are recognized by their prefix, once;
+/// from here on the flag decides.
+async fn migrate_v20(conn: &mut SqliteConnection) -> Result<()> {
+    for (table, sealed) in [
+        (
+            "databases",
+            "password LIKE 'enc:v1:%' OR connection_string LIKE 'enc:v1:%'",
+        ),
+        ("coolify_instances", "api_key LIKE 'enc:v1:%'"),
+    ] {
+        let columns = get_table_columns(conn, table).await?;
+        if columns.contains(&"sealed".to_string()) {
+            continue;
+        }
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN sealed INTEGER NOT NULL DEFAULT 0",
+            table
+        ))
+        .execute(&mut *conn)
         .await
         .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+        sqlx::query(&format!("UPDATE {} SET sealed = 1 WHERE {}", table, sealed))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Migration v20 -> v21: The activity feed reads script runs from
+/// `script_runs`; a script last run before that table existed gets its
+/// last run recorded there
+async fn migrate_v21(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO script_runs (script_id, started_at, finished_at, exit_code, output, triggered_by)
+         SELECT id, last_run, last_run, exit_code, last_output, 'unknown' FROM scripts
+         WHERE last_run IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM script_runs r WHERE r.script_id = scripts.id)",
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Migration v21 -> v22: Record the author's uid on script revisions, so
+/// two-person approval doesn't rest on `$USER` alone
+async fn migrate_v22(conn: &mut SqliteConnection) -> Result<()> {
+    let columns = get_table_columns(conn, "script_revisions").await?;
+    if !columns.contains(&"author_uid".to_string()) {
+        sqlx::query("ALTER TABLE script_revisions ADD COLUMN author_uid INTEGER")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| pctrl_core::Error::Database(e.to_string()))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,70 @@
+//! Time-boxed read for `pctrl prompt-segment`
+//!
+//! A prompt hook runs on every prompt, so this path skips everything
+//! [`Database::new`](crate::Database::new) does: no schema, no migrations,
+//! no pool. One read-only connection that never creates the file, and every
+//! step is cut off at the deadline; what didn't make it stays unknown.
+
+use crate::crud::monitor::{row_to_monitor_state, MonitorRow, MONITOR_STATE_SQL};
+use pctrl_core::monitor::MonitorState;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::ConnectOptions;
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// What the prompt could find out in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachedStatus {
+    /// Servers in the inventory (trash excluded)
+    pub servers: Option<u32>,
+    /// The last monitor cycle; `None` if it never ran or couldn't be read
+    pub monitor: Option<MonitorState>,
+}
+
+/// Read the server count and the last monitor cycle from the database at
+/// `path` within `budget`. Never fails: a missing file, a locked database
+/// or a timeout leave the fields empty.
+pub async fn read_cached_status(path: &Path, budget: Duration) -> CachedStatus {
+    let deadline = Instant::now() + budget;
+    let mut status = CachedStatus::default();
+
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .create_if_missing(false)
+        // A writer holding the lock shouldn't make the prompt wait
+        .busy_timeout(Duration::ZERO);
+    let Some(mut conn) = within(deadline, options.connect()).await else {
+        return status;
+    };
+
+    let servers: Option<(i64,)> = within(
+        deadline,
+        sqlx::query_as("SELECT COUNT(*) FROM servers WHERE deleted_at IS NULL")
+            .fetch_one(&mut conn),
+    )
+    .await;
+    status.servers = servers.map(|(count,)| count as u32);
+
+    let monitor: Option<Option<MonitorRow>> = within(
+        deadline,
+        sqlx::query_as(MONITOR_STATE_SQL).fetch_optional(&mut conn),
+    )
+    .await;
+    status.monitor = monitor.flatten().map(row_to_monitor_state);
+
+    close(conn, deadline).await;
+    status
+}
+
+/// The result of `step` if it succeeds before `deadline`
+async fn within<T, E>(deadline: Instant, step: impl Future<Output = Result<T, E>>) -> Option<T> {
+    tokio::time::timeout_at(deadline, step).await.ok()?.ok()
+}
+
+async fn close(conn: SqliteConnection, deadline: Instant) {
+    use sqlx::Connection;
+    let _ = tokio::time::timeout_at(deadline, conn.close()).await;
+}
@@ -0,0 +1,303 @@
+mod common;
+
+use common::{open_db, server};
+use pctrl_core::{ActivityFilter, ActivityKind, Script, ScriptType};
+use pctrl_database::Database;
+
+fn script(id: &str) -> Script {
+    Script {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        command: "echo hi".to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+/// Five audit entries, three commands and two historical script runs
+async fn seed(db: &Database) {
+    for name in ["web-1", "web-2", "web-3"] {
+        db.save_server(&server(name)).await.unwrap();
+    }
+    for (id, finished_at, output) in [
+        (
+            "backup",
+            "2024-01-02T10:00:00+00:00",
+            "DB_PASSWORD=hunter2 dump ok",
+        ),
+        ("deploy", "2024-01-01T09:30:00.123+00:00", "done"),
+    ] {
+        db.save_script(&script(id)).await.unwrap();
+        db.add_script_run(
+            id,
+            finished_at,
+            finished_at,
+            Some(0),
+            Some(output),
+            "alice@laptop",
+        )
+        .await
+        .unwrap();
+    }
+    for command in ["server list", "server add", "project show"] {
+        db.record_command(command, true, None).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_feed_is_reverse_chronological() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let feed = db
+        .activity_feed(None, &ActivityFilter::default(), 100)
+        .await
+        .unwrap();
+    assert_eq!(feed.len(), 10);
+
+    let keys: Vec<_> = feed
+        .iter()
+        .map(|e| (e.timestamp.clone(), e.kind.to_string(), e.id.clone()))
+        .collect();
+    let mut sorted = keys.clone();
+    sorted.sort_by(|a, b| b.cmp(a));
+    assert_eq!(keys, sorted);
+
+    // Script runs are oldest and normalized to second precision
+    assert_eq!(feed[8].timestamp, "2024-01-02T10:00:00Z");
+    assert_eq!(feed[9].timestamp, "2024-01-01T09:30:00Z");
+    assert_eq!(feed[9].kind, ActivityKind::Script);
+    assert_eq!(feed[9].title, "ran deploy (success)");
+}
+
+#[tokio::test]
+async fn test_every_script_run_is_in_the_feed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_script(&script("backup")).await.unwrap();
+    for (finished_at, exit_code) in [
+        ("2024-01-01T10:00:00+00:00", Some(0)),
+        ("2024-01-02T10:00:00+00:00", Some(2)),
+        ("2024-01-03T10:00:00+00:00", None),
+    ] {
+        db.add_script_run(
+            "backup",
+            finished_at,
+            finished_at,
+            exit_code,
+            None,
+            "alice@laptop",
+        )
+        .await
+        .unwrap();
+    }
+
+    let filter = ActivityFilter {
+        kinds: Some(vec![ActivityKind::Script]),
+        search: None,
+    };
+    let titles: Vec<_> = db
+        .activity_feed(None, &filter, 100)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.title)
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            "ran backup (unknown)",
+            "ran backup (exit 2)",
+            "ran backup (success)"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_monitor_transitions_are_in_the_feed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+    db.record_monitor_event("web-1", "web-1", Some("connection refused"))
+        .await
+        .unwrap();
+    db.record_monitor_event("web-1", "web-1", None)
+        .await
+        .unwrap();
+
+    let filter = ActivityFilter {
+        kinds: Some(vec![ActivityKind::Monitor]),
+        search: None,
+    };
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    let entries: Vec<_> = feed
+        .iter()
+        .map(|e| (e.title.as_str(), e.details.as_deref()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("server web-1 up", None),
+            ("server web-1 down", Some("connection refused"))
+        ]
+    );
+
+    // Toggled off, they leave the rest of the feed alone
+    let mut filter = ActivityFilter::default();
+    filter.toggle(ActivityKind::Monitor);
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    assert_eq!(feed.len(), 10);
+}
+
+#[tokio::test]
+async fn test_cursor_pages_cover_feed_exactly_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let filter = ActivityFilter::default();
+    let full = db.activity_feed(None, &filter, 100).await.unwrap();
+
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = db.activity_feed(cursor.as_ref(), &filter, 3).await.unwrap();
+        if page.is_empty() {
+            break;
+        }
+        cursor = page.last().map(|e| e.cursor());
+        paged.extend(page);
+    }
+
+    let full_ids: Vec<_> = full.iter().map(|e| (e.kind, e.id.clone())).collect();
+    let paged_ids: Vec<_> = paged.iter().map(|e| (e.kind, e.id.clone())).collect();
+    assert_eq!(full_ids, paged_ids);
+}
+
+#[tokio::test]
+async fn test_cursor_is_stable_when_new_rows_arrive() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let filter = ActivityFilter::default();
+    let first = db.activity_feed(None, &filter, 4).await.unwrap();
+    let before = db
+        .activity_feed(Some(&first[3].cursor()), &filter, 100)
+        .await
+        .unwrap();
+
+    // New activity lands at the head of the feed, not inside later pages
+    db.record_command("domain list", true, None).await.unwrap();
+    db.save_server(&server("web-4")).await.unwrap();
+
+    let after = db
+        .activity_feed(Some(&first[3].cursor()), &filter, 100)
+        .await
+        .unwrap();
+    let ids = |v: &[pctrl_core::ActivityEntry]| -> Vec<_> {
+        v.iter().map(|e| (e.kind, e.id.clone())).collect()
+    };
+    assert_eq!(ids(&before), ids(&after));
+}
+
+#[tokio::test]
+async fn test_kind_and_search_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let mut filter = ActivityFilter::default();
+    filter.toggle(ActivityKind::Audit);
+    filter.toggle(ActivityKind::Command);
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    assert_eq!(feed.len(), 2);
+    assert!(feed.iter().all(|e| e.kind == ActivityKind::Script));
+
+    let filter = ActivityFilter {
+        kinds: None,
+        search: Some("web-2".to_string()),
+    };
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    assert_eq!(feed.len(), 1);
+    assert_eq!(feed[0].title, "server created: web-2");
+
+    // LIKE wildcards in the search term match literally
+    let filter = ActivityFilter {
+        kinds: None,
+        search: Some("%".to_string()),
+    };
+    assert!(db
+        .activity_feed(None, &filter, 100)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_every_kind_toggled_off_shows_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let mut filter = ActivityFilter::default();
+    for kind in ActivityKind::ALL {
+        filter.toggle(kind);
+    }
+    assert!(ActivityKind::ALL.iter().all(|k| !filter.includes(*k)));
+    assert!(db
+        .activity_feed(None, &filter, 100)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Toggling one back on shows only that kind
+    filter.toggle(ActivityKind::Script);
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    assert_eq!(feed.len(), 2);
+    assert!(feed.iter().all(|e| e.kind == ActivityKind::Script));
+}
+
+#[tokio::test]
+async fn test_details_are_redacted() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let filter = ActivityFilter {
+        kinds: Some(vec![ActivityKind::Script]),
+        search: Some("backup".to_string()),
+    };
+    let feed = db.activity_feed(None, &filter, 100).await.unwrap();
+    let details = feed[0].details.as_deref().unwrap();
+    assert!(!details.contains("hunter2"));
+    assert!(details.contains("DB_PASSWORD=****"));
+}
+
+#[tokio::test]
+async fn test_audit_records_create_update_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.save_server(&server("web-1")).await.unwrap();
+    db.save_server(&server("web-1")).await.unwrap();
+    db.remove_server("web-1").await.unwrap();
+
+    let entries = db.list_audit_entries(None, 10).await.unwrap();
+    let actions: Vec<_> = entries.iter().map(|e| e.action.to_string()).collect();
+    assert_eq!(actions, ["removed", "updated", "created"]);
+    assert!(entries.iter().all(|e| e.summary == "web-1"));
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, Domain, DomainType, Project, ProjectResource,
+    ProjectStatus, ResourceType, Server, ServerType,
+};
+use pctrl_database::Database;
+
+/// Strings that must not survive anywhere in the anonymized file
+const ORIGINALS: &[&str] = &[
+    "acme-corp.de",
+    "shop.acme-corp.de",
+    "203.0.113.77",
+    "ops@acme-corp.de",
+    "Acme Webshop",
+    "acme-web-1",
+    "tok-s3cr3t-value",
+    "hetzner-rescue",
+];
+
+async fn seed(db: &Database) {
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "Acme Webshop".to_string(),
+        description: Some("Storefront on shop.acme-corp.de".to_string()),
+        stack: vec!["rust".to_string()],
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: Some(
+            "Escalate to ops@acme-corp.de; the rescue image is hetzner-rescue, \
+             reachable at 203.0.113.77 when https://panel.acme-corp.de/login is down"
+                .to_string(),
+        ),
+    })
+    .await
+    .unwrap();
+    db.save_credential(&Credential {
+        id: "cred-api".to_string(),
+        name: "hetzner-rescue".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "tok-s3cr3t-value".to_string(),
+            url: None,
+        },
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_server(&Server {
+        id: "srv-web".to_string(),
+        name: "acme-web-1".to_string(),
+        host: "203.0.113.77".to_string(),
+        server_type: ServerType::Vps,
+        provider: Some("hetzner".to_string()),
+        credential_id: Some("cred-api".to_string()),
+        location: None,
+        specs: None,
+        notes: Some("Serves acme-corp.de and its subdomains".to_string()),
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    for (id, name) in [
+        ("dom-apex", "acme-corp.de"),
+        ("dom-shop", "shop.acme-corp.de"),
+    ] {
+        db.save_domain(&Domain {
+            id: id.to_string(),
+            domain: name.to_string(),
+            domain_type: DomainType::Production,
+            ssl: true,
+            ssl_expiry: None,
+            cloudflare_zone_id: None,
+            cloudflare_record_id: None,
+            server_id: Some("srv-web".to_string()),
+            container_id: None,
+            notes: None,
+            superseded_by: None,
+        })
+        .await
+        .unwrap();
+    }
+    for (id, resource_type, resource_id) in [
+        ("link-srv", ResourceType::Server, "srv-web"),
+        ("link-dom", ResourceType::Domain, "dom-shop"),
+    ] {
+        db.link_project_resource(&ProjectResource {
+            id: id.to_string(),
+            project_id: "shop".to_string(),
+            resource_type,
+            resource_id: resource_id.to_string(),
+            role: Some("web".to_string()),
+            notes: None,
+            start_order: None,
+        })
+        .await
+        .unwrap();
+    }
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    let haystack = String::from_utf8_lossy(haystack).to_lowercase();
+    haystack.contains(&needle.to_lowercase())
+}
+
+#[tokio::test]
+async fn test_anonymized_copy_keeps_structure_and_drops_originals() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+    let out = dir.path().join("anon.db");
+
+    let reports = db.anonymize_to(&out).await.unwrap();
+    let servers = reports.iter().find(|r| r.table == "servers").unwrap();
+    assert_eq!(servers.rows, 1);
+    assert_eq!(servers.rewritten.get("hosts"), Some(&1));
+    assert_eq!(servers.rewritten.get("names"), Some(&1));
+    let credentials = reports.iter().find(|r| r.table == "credentials").unwrap();
+    assert_eq!(credentials.rewritten.get("secrets"), Some(&1));
+
+    // Full-file scan: no original survives in any page, free or not
+    let bytes = std::fs::read(&out).unwrap();
+    for original in ORIGINALS {
+        assert!(!contains(&bytes, original), "{} survived", original);
+    }
+    assert!(!out.with_extension("db-wal").exists());
+    // The source is untouched
+    assert_eq!(
+        db.get_server("srv-web").await.unwrap().unwrap().host,
+        "203.0.113.77"
+    );
+
+    let anon = Database::new(out.to_str().unwrap(), None).await.unwrap();
+    let projects = anon.list_projects().await.unwrap();
+    assert_eq!(projects.len(), 1);
+    let project = &projects[0];
+    assert_eq!(project.status, ProjectStatus::Live);
+    assert_eq!(project.stack, vec!["rust".to_string()]);
+    assert!(project.notes.as_ref().unwrap().chars().count() <= 41);
+
+    let server = &anon.list_servers().await.unwrap()[0];
+    assert!(server.host.starts_with("198.18."), "{}", server.host);
+    assert_eq!(server.server_type, ServerType::Vps);
+
+    // Same original, same fake: the subdomain hangs off the apex's fake
+    let mut domains: Vec<String> = anon
+        .list_domains()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|d| {
+            assert_eq!(d.server_id.as_deref(), Some(server.id.as_str()));
+            d.domain
+        })
+        .collect();
+    domains.sort();
+    assert!(domains[0].ends_with(".example.invalid"));
+    assert_eq!(domains[1], format!("sub-1.{}", domains[0]));
+
+    // Links still join
+    let links = anon.get_project_resources(&project.id).await.unwrap();
+    assert_eq!(links.len(), 2);
+    assert!(links
+        .iter()
+        .any(|l| l.resource_type == ResourceType::Server && l.resource_id == server.id));
+}
+
+#[tokio::test]
+async fn test_anonymize_refuses_an_existing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let out = dir.path().join("anon.db");
+    std::fs::write(&out, b"keep me").unwrap();
+
+    assert!(db.anonymize_to(&out).await.is_err());
+    assert_eq!(std::fs::read(&out).unwrap(), b"keep me");
+}
@@ -0,0 +1,383 @@
+use pctrl_core::{Project, ProjectStatus};
+use pctrl_database::backup::{
+    self, decrypt, encrypt, encrypt_chunked, BackupError, Header, CHUNK_SIZE, FORMAT_VERSION,
+    HEADER_LEN,
+};
+use pctrl_database::Database;
+use std::io::{self, Cursor, Read, Write};
+
+const PASSPHRASE: &str = "fixture passphrase";
+const PLAIN: &[u8] = include_bytes!("fixtures/backup_valid.txt");
+const VALID: &[u8] = include_bytes!("fixtures/backup_valid.pctrlbak");
+const TRUNCATED: &[u8] = include_bytes!("fixtures/backup_truncated.pctrlbak");
+const MISSING_LAST_FRAME: &[u8] = include_bytes!("fixtures/backup_missing_last_frame.pctrlbak");
+const TAMPERED: &[u8] = include_bytes!("fixtures/backup_tampered.pctrlbak");
+const BAD_CHECKSUM: &[u8] = include_bytes!("fixtures/backup_bad_checksum.pctrlbak");
+
+fn decrypt_bytes(data: &[u8], passphrase: &str) -> Result<Vec<u8>, BackupError> {
+    let mut out = Vec::new();
+    decrypt(&mut Cursor::new(data), &mut out, passphrase)?;
+    Ok(out)
+}
+
+/// Endless pseudo-random bytes, produced on demand
+struct Generated {
+    remaining: u64,
+    state: u32,
+}
+
+impl Read for Generated {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.remaining as usize);
+        for byte in &mut buf[..n] {
+            self.state = self
+                .state
+                .wrapping_mul(1_664_525)
+                .wrapping_add(1_013_904_223);
+            *byte = (self.state >> 24) as u8;
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Counts and compares output without keeping it
+struct Compare(Generated, u64);
+
+impl Write for Compare {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut expected = vec![0u8; buf.len()];
+        self.0.read_exact(&mut expected)?;
+        assert_eq!(buf, &expected[..]);
+        self.1 += buf.len() as u64;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_header_format() {
+    let header = Header::from_bytes(VALID).unwrap();
+    assert_eq!(&VALID[..8], b"PCTRLBAK");
+    assert_eq!(header.version, FORMAT_VERSION);
+    assert_eq!(header.chunk_size, 16);
+    assert_eq!(&header.to_bytes()[..], &VALID[..HEADER_LEN]);
+    // Four frames: three full ones of 16 bytes, one of 14
+    assert_eq!(VALID.len(), HEADER_LEN + 3 * (5 + 16 + 16) + (5 + 14 + 16));
+
+    for version in [1, FORMAT_VERSION + 1] {
+        let mut other = VALID.to_vec();
+        other[8] = version;
+        assert!(matches!(
+            Header::from_bytes(&other),
+            Err(BackupError::UnsupportedVersion(v)) if v == version
+        ));
+    }
+    assert!(matches!(
+        Header::from_bytes(b"SQLite format 3\0"),
+        Err(BackupError::NotABackup)
+    ));
+    assert!(matches!(
+        Header::from_bytes(&VALID[..40]),
+        Err(BackupError::Truncated)
+    ));
+}
+
+#[test]
+fn test_valid_fixture_decrypts() {
+    assert_eq!(decrypt_bytes(VALID, PASSPHRASE).unwrap(), PLAIN);
+    assert!(matches!(
+        decrypt_bytes(VALID, "wrong passphrase"),
+        Err(BackupError::WrongPassphrase)
+    ));
+}
+
+#[test]
+fn test_corrupted_fixtures_are_refused() {
+    assert!(matches!(
+        decrypt_bytes(TRUNCATED, PASSPHRASE),
+        Err(BackupError::Truncated)
+    ));
+    assert!(matches!(
+        decrypt_bytes(MISSING_LAST_FRAME, PASSPHRASE),
+        Err(BackupError::Truncated)
+    ));
+    assert!(matches!(
+        decrypt_bytes(TAMPERED, PASSPHRASE),
+        Err(BackupError::Tampered(1))
+    ));
+    assert!(matches!(
+        decrypt_bytes(BAD_CHECKSUM, PASSPHRASE),
+        Err(BackupError::ChecksumMismatch)
+    ));
+
+    let mut trailing = VALID.to_vec();
+    trailing.extend_from_slice(b"extra");
+    assert!(matches!(
+        decrypt_bytes(&trailing, PASSPHRASE),
+        Err(BackupError::TrailingData)
+    ));
+
+    // Swapping two frames breaks their nonces
+    let frame = 5 + 16 + 16;
+    let mut swapped = VALID.to_vec();
+    let (first, second) = (HEADER_LEN, HEADER_LEN + frame);
+    let copy = swapped[first..first + frame].to_vec();
+    swapped.copy_within(second..second + frame, first);
+    swapped[second..second + frame].copy_from_slice(&copy);
+    assert!(matches!(
+        decrypt_bytes(&swapped, PASSPHRASE),
+        Err(BackupError::Tampered(0))
+    ));
+}
+
+#[test]
+fn test_tampered_chunk_size_is_refused() {
+    let plain: Vec<u8> = (0..40).collect();
+    let mut out = Cursor::new(Vec::new());
+    encrypt_chunked(&mut Cursor::new(&plain), &mut out, "pw", 16).unwrap();
+    let written = out.into_inner();
+    assert_eq!(
+        Header::from_bytes(&written).unwrap().version,
+        FORMAT_VERSION
+    );
+
+    // Too large to allocate: refused before reading a frame
+    for backup in [&written[..], VALID] {
+        let mut huge = backup.to_vec();
+        huge[9..13].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            decrypt_bytes(&huge, "pw"),
+            Err(BackupError::ChunkTooLarge(u32::MAX))
+        ));
+    }
+
+    // Any other change fails the first frame, which authenticates the header
+    let mut changed = written.clone();
+    changed[9..13].copy_from_slice(&32u32.to_be_bytes());
+    assert!(matches!(
+        decrypt_bytes(&changed, "pw"),
+        Err(BackupError::Tampered(0))
+    ));
+
+    assert!(matches!(
+        encrypt_chunked(
+            &mut Cursor::new(&plain),
+            &mut Cursor::new(Vec::new()),
+            "pw",
+            backup::MAX_CHUNK_SIZE as usize + 1
+        ),
+        Err(BackupError::ChunkTooLarge(_))
+    ));
+}
+
+#[test]
+fn test_roundtrip_sizes() {
+    for size in [0, 16, 17] {
+        let plain: Vec<u8> = (0..size).map(|i| i as u8).collect();
+        let mut out = Cursor::new(Vec::new());
+        encrypt_chunked(&mut Cursor::new(&plain), &mut out, "pw", 16).unwrap();
+        assert_eq!(decrypt_bytes(out.get_ref(), "pw").unwrap(), plain);
+    }
+}
+
+#[test]
+fn test_streams_large_input_in_chunks() {
+    let size = 5 * CHUNK_SIZE as u64 + 123;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("large.pctrlbak");
+
+    let mut input = Generated {
+        remaining: size,
+        state: 7,
+    };
+    let mut file = io::BufWriter::new(std::fs::File::create(&path).unwrap());
+    assert_eq!(encrypt(&mut input, &mut file, "pw").unwrap(), size);
+    drop(file);
+
+    let frames = 6;
+    let written = std::fs::metadata(&path).unwrap().len();
+    assert_eq!(written, HEADER_LEN as u64 + size + frames * (5 + 16));
+
+    let mut compare = Compare(
+        Generated {
+            remaining: size,
+            state: 7,
+        },
+        0,
+    );
+    let mut reader = io::BufReader::new(std::fs::File::open(&path).unwrap());
+    assert_eq!(decrypt(&mut reader, &mut compare, "pw").unwrap(), size);
+    assert_eq!(compare.1, size);
+}
+
+fn project(id: &str) -> Project {
+    Project {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+#[tokio::test]
+async fn test_encrypted_backup_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("pctrl.db");
+    let db = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    db.save_project(&project("before")).await.unwrap();
+
+    let backup_path = dir.path().join("b.pctrlbak");
+    db.backup_encrypted_to(&backup_path, "pw").await.unwrap();
+    assert!(backup::is_encrypted_backup(&backup_path).unwrap());
+    // The staged plain snapshot is gone
+    let mut files: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["b.pctrlbak", "pctrl.db"]);
+
+    db.save_project(&project("after")).await.unwrap();
+    db.close().await;
+
+    // Tampered or wrongly unlocked backups leave the database alone
+    let mut tampered = std::fs::read(&backup_path).unwrap();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    let tampered_path = dir.path().join("t.pctrlbak");
+    std::fs::write(&tampered_path, tampered).unwrap();
+    assert!(
+        Database::restore_backup(&db_path, &tampered_path, Some("pw"), None)
+            .await
+            .is_err()
+    );
+    assert!(
+        Database::restore_backup(&db_path, &backup_path, Some("wrong"), None)
+            .await
+            .is_err()
+    );
+    assert!(Database::restore_backup(&db_path, &backup_path, None, None)
+        .await
+        .is_err());
+    let db = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    assert_eq!(db.list_projects().await.unwrap().len(), 2);
+    db.close().await;
+
+    Database::restore_backup(&db_path, &backup_path, Some("pw"), None)
+        .await
+        .unwrap();
+    let db = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    let projects = db.list_projects().await.unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].id, "before");
+}
+
+#[tokio::test]
+async fn test_restore_checks_the_backup_and_keeps_a_safety_copy() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("pctrl.db");
+    let db = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    db.save_project(&project("before")).await.unwrap();
+    let backup_path = dir.path().join("pctrl-20261016-090000.db");
+    db.backup_to(&backup_path).await.unwrap();
+    db.save_project(&project("after")).await.unwrap();
+    db.close().await;
+
+    // Someone else's SQLite file, a text file and a truncated backup
+    let foreign = dir.path().join("foreign.db");
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}?mode=rwc", foreign.display()))
+        .await
+        .unwrap();
+    sqlx::query("CREATE TABLE notes (body TEXT)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+    let text = dir.path().join("notes.txt");
+    std::fs::write(&text, "not a database").unwrap();
+    let bytes = std::fs::read(&backup_path).unwrap();
+    let truncated = dir.path().join("truncated.db");
+    std::fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+
+    let safety = dir.path().join("safety.db");
+    for bad in [&foreign, &text, &truncated] {
+        let result = Database::restore_backup(&db_path, bad, None, Some(&safety)).await;
+        assert!(result.is_err(), "{} was restored", bad.display());
+    }
+    let err = Database::restore_backup(&db_path, &foreign, None, Some(&safety))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not a pctrl database backup"));
+    assert!(!safety.exists());
+    assert!(!dir.path().join("pctrl.db.restore-tmp").exists());
+
+    Database::restore_backup(&db_path, &backup_path, None, Some(&safety))
+        .await
+        .unwrap();
+    let restored = Database::new(db_path.to_str().unwrap(), None)
+        .await
+        .unwrap();
+    assert_eq!(restored.list_projects().await.unwrap().len(), 1);
+    let previous = Database::new(safety.to_str().unwrap(), None).await.unwrap();
+    assert_eq!(previous.list_projects().await.unwrap().len(), 2);
+}
+
+#[test]
+fn test_prune_keeps_the_newest_timestamped_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let names = [
+        "pctrl-20261014-230000.db",
+        "pctrl-20261015-230000.pctrlbak",
+        "pctrl-20261016-090000.db",
+        "pctrl-20261016-230000.db",
+        "pctrl-pre-restore-20261001-120000.db",
+        "pctrl-latest.db",
+        "notes.txt",
+    ];
+    for name in names {
+        std::fs::write(dir.path().join(name), name).unwrap();
+    }
+
+    let removed = backup::prune_backups(dir.path(), 2).unwrap();
+    let removed: Vec<_> = removed
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        removed,
+        ["pctrl-20261014-230000.db", "pctrl-20261015-230000.pctrlbak"]
+    );
+    let mut left: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        [
+            "notes.txt",
+            "pctrl-20261016-090000.db",
+            "pctrl-20261016-230000.db",
+            "pctrl-latest.db",
+            "pctrl-pre-restore-20261001-120000.db",
+        ]
+    );
+
+    // Fewer backups than kept: nothing to do
+    assert!(backup::prune_backups(dir.path(), 5).unwrap().is_empty());
+}
@@ -0,0 +1,205 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pctrl_core::project_archive::{Manifest, Part, FORMAT_VERSION, MANIFEST};
+use pctrl_database::bundle_archive::{
+    verify_bundle, write_bundle, BundleEntry, EntrySource, STAGING_PREFIX,
+};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+fn manifest() -> Manifest {
+    Manifest {
+        format: FORMAT_VERSION,
+        pctrl_version: "0.1.0".to_string(),
+        created_at: "2026-04-01T10:00:00Z".to_string(),
+        project: "Shop".to_string(),
+        include_secrets: false,
+        include_artifacts: true,
+        files: Vec::new(),
+        skipped: Vec::new(),
+    }
+}
+
+fn entry(part: Part, path: &str, source: EntrySource) -> BundleEntry {
+    BundleEntry {
+        part,
+        path: path.to_string(),
+        source,
+    }
+}
+
+/// Every file of an archive by path, in archive order
+fn unpack(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let file = std::fs::File::open(path).unwrap();
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            (name, data)
+        })
+        .collect()
+}
+
+fn repack(path: &Path, files: &[(String, Vec<u8>)]) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, data.as_slice()).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+}
+
+fn leftovers(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(STAGING_PREFIX) || name.ends_with(".partial"))
+        .collect()
+}
+
+#[test]
+fn test_manifest_comes_first_and_matches_every_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let backup = dir.path().join("pctrl-20260401.db");
+    std::fs::write(&backup, b"SQLite format 3\0backup").unwrap();
+    let out = dir.path().join("shop.tar.gz");
+
+    let written = write_bundle(
+        &out,
+        &[
+            entry(
+                Part::Markdown,
+                "project.md",
+                EntrySource::Bytes(b"# Shop\n".to_vec()),
+            ),
+            entry(
+                Part::Backup,
+                "artifacts/pctrl-20260401.db",
+                EntrySource::File(backup),
+            ),
+        ],
+        manifest(),
+    )
+    .unwrap();
+    assert_eq!(written.files.len(), 2);
+
+    let files = unpack(&out);
+    assert_eq!(files[0].0, MANIFEST);
+    let stored: Manifest = serde_json::from_slice(&files[0].1).unwrap();
+    assert_eq!(stored.project, "Shop");
+    let contents: BTreeMap<_, _> = files[1..].iter().cloned().collect();
+    for file in &stored.files {
+        let data = &contents[&file.path];
+        assert_eq!(file.size, data.len() as u64);
+        assert_eq!(file.sha256, format!("{:x}", Sha256::digest(data)));
+    }
+    assert_eq!(contents["project.md"], b"# Shop\n");
+
+    assert!(verify_bundle(&out).unwrap().is_ok());
+    assert!(leftovers(dir.path()).is_empty());
+}
+
+#[test]
+fn test_verify_reports_changed_missing_and_unlisted_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("shop.tar.gz");
+    write_bundle(
+        &out,
+        &[
+            entry(
+                Part::Markdown,
+                "project.md",
+                EntrySource::Bytes(b"# Shop\n".to_vec()),
+            ),
+            entry(
+                Part::Proxy,
+                "proxy.toml",
+                EntrySource::Bytes(b"routes = []\n".to_vec()),
+            ),
+        ],
+        manifest(),
+    )
+    .unwrap();
+
+    let mut files = unpack(&out);
+    files.retain(|(name, _)| name != "proxy.toml");
+    for (name, data) in files.iter_mut() {
+        if name == "project.md" {
+            *data = b"# Sh0p\n".to_vec();
+        }
+    }
+    files.push(("extra.txt".to_string(), b"hi".to_vec()));
+    let tampered = dir.path().join("tampered.tar.gz");
+    repack(&tampered, &files);
+
+    let report = verify_bundle(&tampered).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.mismatched, vec!["project.md"]);
+    assert_eq!(report.missing, vec!["proxy.toml"]);
+    assert_eq!(report.unlisted, vec!["extra.txt"]);
+}
+
+#[test]
+fn test_failed_bundle_leaves_nothing_behind() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("shop.tar.gz");
+
+    let result = write_bundle(
+        &out,
+        &[
+            entry(
+                Part::Markdown,
+                "project.md",
+                EntrySource::Bytes(b"# Shop\n".to_vec()),
+            ),
+            entry(
+                Part::Backup,
+                "artifacts/gone.db",
+                EntrySource::File(dir.path().join("gone.db")),
+            ),
+        ],
+        manifest(),
+    );
+    assert!(result.is_err());
+    assert!(!out.exists());
+    assert!(leftovers(dir.path()).is_empty());
+}
+
+#[test]
+fn test_existing_output_and_unsafe_paths_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("shop.tar.gz");
+    std::fs::write(&out, b"keep me").unwrap();
+    let markdown = || {
+        entry(
+            Part::Markdown,
+            "project.md",
+            EntrySource::Bytes(b"# Shop\n".to_vec()),
+        )
+    };
+
+    assert!(write_bundle(&out, &[markdown()], manifest()).is_err());
+    assert_eq!(std::fs::read(&out).unwrap(), b"keep me");
+
+    let other = dir.path().join("other.tar.gz");
+    let escaping = entry(
+        Part::Markdown,
+        "../escape.md",
+        EntrySource::Bytes(Vec::new()),
+    );
+    assert!(write_bundle(&other, &[escaping], manifest()).is_err());
+    assert!(!other.exists());
+    assert!(!dir.path().parent().unwrap().join("escape.md").exists());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::open_db;
+
+#[tokio::test]
+async fn test_changelogs_are_kept_per_repo_and_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let first = db
+        .save_changelog("app", "v1.0.0", "## v1.0.0")
+        .await
+        .unwrap();
+    db.save_changelog("app", "v1.1.0", "## v1.1.0")
+        .await
+        .unwrap();
+    db.save_changelog("site", "v1.0.0", "## site v1.0.0")
+        .await
+        .unwrap();
+    // Regenerating replaces the text and keeps the place
+    let again = db
+        .save_changelog("app", "v1.0.0", "## v1.0.0 (fixed)")
+        .await
+        .unwrap();
+    assert_eq!(again.id, first.id);
+
+    let app = db.list_changelog("app").await.unwrap();
+    let versions: Vec<&str> = app.iter().map(|e| e.version.as_str()).collect();
+    assert_eq!(versions, ["v1.1.0", "v1.0.0"]);
+    assert_eq!(app[1].content, "## v1.0.0 (fixed)");
+    assert_eq!(db.list_changelog("site").await.unwrap().len(), 1);
+    assert!(db.list_changelog("other").await.unwrap().is_empty());
+}
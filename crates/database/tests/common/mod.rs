@@ -0,0 +1,32 @@
+//! Helpers shared by the database tests
+//!
+//! Every test file compiles this module on its own and uses only part of
+//! it.
+#![allow(dead_code)]
+
+use pctrl_core::{Server, ServerType};
+use pctrl_database::Database;
+
+/// A fresh database file in `dir`
+pub async fn open_db(dir: &tempfile::TempDir) -> Database {
+    let path = dir.path().join("pctrl.db");
+    Database::new(path.to_str().unwrap(), None).await.unwrap()
+}
+
+/// A VPS at 10.0.0.1, named after its ID, with nothing else set
+pub fn server(id: &str) -> Server {
+    Server {
+        id: id.to_string(),
+        name: id.to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    }
+}
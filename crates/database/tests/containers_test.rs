@@ -0,0 +1,242 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::open_db;
+use pctrl_core::compose::{self, ComposeProject, ComposeState};
+use pctrl_core::{Container, ContainerStatus, Project, Server};
+
+fn server(id: &str) -> Server {
+    Server {
+        host: "203.0.113.10".into(),
+        max_containers: Some(10),
+        max_memory_mb_allocated: Some(4096),
+        ..common::server(id)
+    }
+}
+
+fn container(id: &str, server_id: &str, memory: Option<u64>) -> Container {
+    Container {
+        id: id.into(),
+        name: format!("{}-name", id),
+        image: Some("nginx:1.27".into()),
+        server_id: server_id.into(),
+        project_id: None,
+        status: ContainerStatus::Running,
+        ports: Vec::new(),
+        env_vars: None,
+        labels: None,
+        memory_limit_mb: memory,
+    }
+}
+
+#[tokio::test]
+async fn test_server_quotas_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.save_server(&server("vps")).await.unwrap();
+    let loaded = db.get_server("vps").await.unwrap().unwrap();
+    assert_eq!(loaded.max_containers, Some(10));
+    assert_eq!(loaded.max_memory_mb_allocated, Some(4096));
+}
+
+#[tokio::test]
+async fn test_replace_server_containers() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("vps")).await.unwrap();
+    db.save_server(&server("other")).await.unwrap();
+
+    db.replace_server_containers(
+        "vps",
+        &[
+            container("a", "vps", Some(512)),
+            container("b", "vps", None),
+        ],
+    )
+    .await
+    .unwrap();
+    db.replace_server_containers("other", &[container("c", "other", Some(256))])
+        .await
+        .unwrap();
+
+    let loaded = db.list_containers_for_server("vps").await.unwrap();
+    let summary: Vec<(&str, Option<u64>)> = loaded
+        .iter()
+        .map(|c| (c.id.as_str(), c.memory_limit_mb))
+        .collect();
+    assert_eq!(summary, [("a", Some(512)), ("b", None)]);
+    assert_eq!(loaded[0].status, ContainerStatus::Running);
+    assert_eq!(loaded[0].image.as_deref(), Some("nginx:1.27"));
+
+    // A later sync drops containers that are gone and updates the rest
+    let mut a = container("a", "vps", Some(1024));
+    a.status = ContainerStatus::Exited;
+    db.replace_server_containers("vps", &[a]).await.unwrap();
+
+    let loaded = db.list_containers_for_server("vps").await.unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].memory_limit_mb, Some(1024));
+    assert_eq!(loaded[0].status, ContainerStatus::Exited);
+
+    // Other servers are untouched
+    let all = db.list_containers().await.unwrap();
+    let ids: Vec<&str> = all.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["c", "a"]);
+}
+
+#[tokio::test]
+async fn test_save_container_keeps_project_and_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("vps")).await.unwrap();
+    db.save_project(&Project {
+        id: "shop".into(),
+        name: "shop".into(),
+        description: None,
+        stack: Vec::new(),
+        status: Default::default(),
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_container(&container("c1", "vps", None))
+        .await
+        .unwrap();
+    sqlx_set_project(&dir, "c1").await;
+
+    let mut exited = container("c1", "vps", None);
+    exited.status = ContainerStatus::Exited;
+    exited.ports = vec!["0.0.0.0:80->80/tcp".into()];
+    db.save_container(&exited).await.unwrap();
+    let loaded = db.list_containers_for_server("vps").await.unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].status, ContainerStatus::Exited);
+    assert_eq!(loaded[0].ports, vec!["0.0.0.0:80->80/tcp"]);
+    assert_eq!(loaded[0].project_id.as_deref(), Some("shop"));
+
+    assert!(db.remove_container("c1").await.unwrap());
+    assert!(!db.remove_container("c1").await.unwrap());
+    assert!(db
+        .list_containers_for_server("vps")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_discovery_cache_upsert() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("vps")).await.unwrap();
+    assert!(db
+        .get_discovery_cache("vps", "containers")
+        .await
+        .unwrap()
+        .is_none());
+
+    db.upsert_discovery_cache("vps", "containers", "[]", Duration::minutes(5))
+        .await
+        .unwrap();
+    db.upsert_discovery_cache("vps", "containers", r#"["x"]"#, Duration::minutes(5))
+        .await
+        .unwrap();
+    let cached = db
+        .get_discovery_cache("vps", "containers")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cached.data, r#"["x"]"#);
+    assert!(cached.is_fresh(Utc::now()));
+    assert!(!cached.is_fresh(Utc::now() + Duration::minutes(6)));
+    assert_eq!(
+        db.list_discovery_cache("containers").await.unwrap().len(),
+        1
+    );
+
+    db.upsert_discovery_cache("vps", "containers", "[]", Duration::seconds(-1))
+        .await
+        .unwrap();
+    let cached = db.list_discovery_cache("containers").await.unwrap();
+    assert!(!cached[0].is_fresh(Utc::now()));
+    assert!(db.list_discovery_cache("ports").await.unwrap().is_empty());
+
+    // Removing the server drops its cache
+    db.remove_server("vps").await.unwrap();
+    assert!(db
+        .list_discovery_cache("containers")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Link a container to a project, as `project link` would
+async fn sqlx_set_project(dir: &tempfile::TempDir, id: &str) {
+    let path = dir.path().join("pctrl.db");
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    sqlx::query("UPDATE containers SET project_id = 'shop' WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+}
+
+fn stack(server_id: &str, name: &str, state: ComposeState) -> ComposeProject {
+    ComposeProject {
+        server_id: server_id.into(),
+        name: name.into(),
+        config_path: Some(format!("/srv/{}/compose.yml", name)),
+        services: 2,
+        running: 1,
+        state,
+    }
+}
+
+#[tokio::test]
+async fn test_compose_projects_replace_and_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("vps")).await.unwrap();
+    db.save_server(&server("edge")).await.unwrap();
+
+    db.replace_compose_projects(
+        "vps",
+        &[
+            stack("vps", compose::STANDALONE, ComposeState::Up),
+            stack("vps", "shop", ComposeState::Partial),
+            stack("vps", "blog", ComposeState::Gone),
+        ],
+    )
+    .await
+    .unwrap();
+    db.replace_compose_projects("edge", &[stack("edge", "proxy", ComposeState::Up)])
+        .await
+        .unwrap();
+
+    let vps = db.list_compose_projects(Some("vps")).await.unwrap();
+    let names: Vec<&str> = vps.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["blog", "shop", compose::STANDALONE]);
+    assert_eq!(vps[1], stack("vps", "shop", ComposeState::Partial));
+    assert_eq!(db.list_compose_projects(None).await.unwrap().len(), 4);
+
+    // Replacing drops the server's earlier stacks only
+    db.replace_compose_projects("vps", &[stack("vps", "shop", ComposeState::Up)])
+        .await
+        .unwrap();
+    assert_eq!(
+        db.list_compose_projects(Some("vps")).await.unwrap().len(),
+        1
+    );
+    assert_eq!(
+        db.list_compose_projects(Some("edge")).await.unwrap().len(),
+        1
+    );
+
+    db.remove_server("vps").await.unwrap();
+    assert_eq!(db.list_compose_projects(None).await.unwrap().len(), 1);
+}
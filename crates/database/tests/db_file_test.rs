@@ -0,0 +1,171 @@
+use pctrl_core::EntityType;
+use pctrl_database::db_file::{self, DbFileKind};
+use pctrl_database::Database;
+use sqlx::sqlite::SqlitePool;
+use std::path::Path;
+
+/// A SQLite file with the given statements run against it
+async fn sqlite_file(path: &Path, statements: &[&str]) {
+    let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    for statement in statements {
+        sqlx::query(statement).execute(&pool).await.unwrap();
+    }
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn missing_and_empty_files_are_new() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    assert_eq!(db_file::inspect(&path).await.unwrap(), DbFileKind::New);
+
+    std::fs::write(&path, b"").unwrap();
+    assert_eq!(db_file::inspect(&path).await.unwrap(), DbFileKind::New);
+    Database::new(path.to_str().unwrap(), None).await.unwrap();
+
+    let tableless = dir.path().join("tableless.db");
+    sqlite_file(&tableless, &["PRAGMA user_version = 1"]).await;
+    assert_eq!(db_file::inspect(&tableless).await.unwrap(), DbFileKind::New);
+}
+
+#[tokio::test]
+async fn pctrl_databases_are_recognized() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    db.close().await;
+
+    assert!(matches!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::Pctrl(v) if v >= 1
+    ));
+    // Opening again is fine
+    Database::new(path.to_str().unwrap(), None).await.unwrap();
+}
+
+#[tokio::test]
+async fn foreign_databases_are_refused_unless_adopted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("other-app.db");
+    sqlite_file(
+        &path,
+        &[
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+            "INSERT INTO notes (body) VALUES ('keep me')",
+        ],
+    )
+    .await;
+
+    assert_eq!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::Foreign(vec!["notes".to_string()])
+    );
+    let err = Database::new(path.to_str().unwrap(), None)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("--adopt-db"), "{}", err);
+    assert!(err.contains("notes"), "{}", err);
+    // Nothing was written
+    assert!(matches!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::Foreign(_)
+    ));
+
+    let db = Database::adopt(path.to_str().unwrap(), None).await.unwrap();
+    db.close().await;
+    assert!(matches!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::Pctrl(_)
+    ));
+    let pool = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let (body,): (String,) = sqlx::query_as("SELECT body FROM notes")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(body, "keep me");
+}
+
+#[tokio::test]
+async fn newer_pctrl_databases_open_read_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    db.close().await;
+    sqlite_file(
+        &path,
+        &["UPDATE metadata SET value = '999' WHERE key = 'schema_version'"],
+    )
+    .await;
+
+    assert_eq!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::NewerPctrl(999)
+    );
+    let db = Database::adopt(path.to_str().unwrap(), None).await.unwrap();
+    assert_eq!(db.newer_schema(), Some(999));
+    assert!(db.list_servers().await.unwrap().is_empty());
+
+    let err = db
+        .add_journal_entry(EntityType::Server, "srv", "alice@laptop", "Rebooted", None)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("readonly"), "{}", err);
+    assert_eq!(db.schema_version().await.unwrap(), 999);
+}
+
+#[tokio::test]
+async fn non_sqlite_files_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.db");
+    std::fs::write(&path, "[settings]\ntheme = dark\n").unwrap();
+
+    assert_eq!(
+        db_file::inspect(&path).await.unwrap(),
+        DbFileKind::NotSqlite
+    );
+    let err = Database::adopt(path.to_str().unwrap(), None)
+        .await
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(err.contains("not a SQLite database"), "{}", err);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "[settings]\ntheme = dark\n"
+    );
+}
+
+#[test]
+fn unusable_paths_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let err = db_file::check_path(dir.path(), true)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("is a directory"), "{}", err);
+
+    let url = Path::new("postgres://localhost/pctrl");
+    let err = db_file::check_path(url, true).unwrap_err().to_string();
+    assert!(err.contains("looks like a URL"), "{}", err);
+
+    let json = dir.path().join("settings.json");
+    let err = db_file::check_path(&json, true).unwrap_err().to_string();
+    assert!(err.contains(".db"), "{}", err);
+    // Existing files are judged by their contents instead
+    db_file::check_path(&json, false).unwrap();
+
+    let missing = dir.path().join("missing").join("pctrl.db");
+    let err = db_file::check_path(&missing, true).unwrap_err().to_string();
+    assert!(err.contains("doesn't exist"), "{}", err);
+
+    db_file::check_path(&dir.path().join("pctrl.sqlite3"), true).unwrap();
+    db_file::check_path(&dir.path().join("pctrl"), true).unwrap();
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::demo::{is_demo_id, DemoData};
+use pctrl_core::{Project, ProjectStatus};
+use sqlx::sqlite::SqlitePool;
+use std::collections::BTreeMap;
+
+/// Row count of every table, read through a separate connection
+async fn row_counts(dir: &tempfile::TempDir) -> BTreeMap<String, i64> {
+    let path = dir.path().join("pctrl.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+    let mut counts = BTreeMap::new();
+    for (table,) in tables {
+        let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM \"{}\"", table))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        counts.insert(table, count);
+    }
+    pool.close().await;
+    counts
+}
+
+fn project(id: &str, name: &str) -> Project {
+    Project {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+#[tokio::test]
+async fn test_seed_then_clear_restores_row_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&project("p1", "real")).await.unwrap();
+    let before = row_counts(&dir).await;
+
+    let demo = DemoData::new();
+    let added = db.seed_demo_data().await.unwrap();
+    assert_eq!(added, demo.ids().len());
+    assert_eq!(db.list_projects().await.unwrap().len(), 2);
+    assert_eq!(db.list_servers().await.unwrap().len(), demo.servers.len());
+    assert!(db
+        .list_servers()
+        .await
+        .unwrap()
+        .iter()
+        .all(|s| s.host.starts_with("203.0.113.") && is_demo_id(&s.id)));
+
+    let removed = db.clear_demo_data().await.unwrap();
+    assert_eq!(removed, added);
+    assert_eq!(row_counts(&dir).await, before);
+    let projects = db.list_projects().await.unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].id, "p1");
+}
+
+#[tokio::test]
+async fn test_seed_is_idempotent() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert!(db.seed_demo_data().await.unwrap() > 0);
+    let seeded = row_counts(&dir).await;
+    assert_eq!(db.seed_demo_data().await.unwrap(), 0);
+    assert_eq!(row_counts(&dir).await, seeded);
+
+    // Clearing twice is harmless too
+    db.clear_demo_data().await.unwrap();
+    assert_eq!(db.clear_demo_data().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_reset_requires_confirmation() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&project("p1", "real")).await.unwrap();
+
+    assert!(db.reset_database("reset").await.is_err());
+    assert!(db.reset_database("").await.is_err());
+    assert_eq!(db.list_projects().await.unwrap().len(), 1);
+
+    db.reset_database("RESET").await.unwrap();
+    assert!(db.list_projects().await.unwrap().is_empty());
+    assert!(db.list_audit_entries(None, 10).await.unwrap().is_empty());
+
+    // The recreated schema is fully usable, also after reopening
+    db.seed_demo_data().await.unwrap();
+    db.close().await;
+    let db = open_db(&dir).await;
+    assert_eq!(db.list_projects().await.unwrap().len(), 1);
+}
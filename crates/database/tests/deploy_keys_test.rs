@@ -0,0 +1,26 @@
+mod common;
+
+use common::open_db;
+
+#[tokio::test]
+async fn test_pending_deploy_keys_per_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.record_pending_deploy_key("srv-web", "$HOME/.pctrl/deploy-keys/a")
+        .await
+        .unwrap();
+    db.record_pending_deploy_key("srv-db", "$HOME/.pctrl/deploy-keys/b")
+        .await
+        .unwrap();
+    assert_eq!(
+        db.pending_deploy_keys("srv-web").await.unwrap(),
+        ["$HOME/.pctrl/deploy-keys/a"]
+    );
+
+    db.clear_pending_deploy_keys(&["$HOME/.pctrl/deploy-keys/a".to_string()])
+        .await
+        .unwrap();
+    assert!(db.pending_deploy_keys("srv-web").await.unwrap().is_empty());
+    assert_eq!(db.pending_deploy_keys("srv-db").await.unwrap().len(), 1);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::DockerHost;
+
+fn host(id: &str, url: &str) -> DockerHost {
+    DockerHost {
+        id: id.to_string(),
+        name: id.to_string(),
+        url: url.to_string(),
+        tls_ca: None,
+        tls_cert: None,
+        tls_key: None,
+    }
+}
+
+#[tokio::test]
+async fn test_hosts_keep_their_certificate_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.save_docker_host(&host("local", "/var/run/docker.sock"))
+        .await
+        .unwrap();
+    db.save_docker_host(&DockerHost {
+        tls_ca: Some("/certs/ca.pem".to_string()),
+        tls_cert: Some("/certs/cert.pem".to_string()),
+        tls_key: Some("/certs/key.pem".to_string()),
+        ..host("build", "https://build.internal:2376")
+    })
+    .await
+    .unwrap();
+
+    let hosts = db.load_config().await.unwrap().docker_hosts;
+    let build = hosts.iter().find(|h| h.id == "build").unwrap();
+    assert_eq!(build.tls_cert.as_deref(), Some("/certs/cert.pem"));
+    let local = hosts.iter().find(|h| h.id == "local").unwrap();
+    assert_eq!(local.tls_ca, None);
+}
+
+#[tokio::test]
+async fn test_unsupported_urls_are_rejected_when_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let err = db
+        .save_docker_host(&host("bad", "docker://1.2.3.4"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, pctrl_core::Error::Docker(_)), "{}", err);
+    assert!(!db.docker_host_exists("bad").await.unwrap());
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::doctor::{self, FindingKind, Repair};
+use pctrl_core::EntityType;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+
+/// Break references behind the database's back, like a database from
+/// before foreign keys were enforced or a hand-edited file
+async fn corrupt(dir: &tempfile::TempDir, statements: &[&str]) {
+    let options = SqliteConnectOptions::new()
+        .filename(dir.path().join("pctrl.db"))
+        .foreign_keys(false);
+    let pool = SqlitePool::connect_with(options).await.unwrap();
+    for statement in statements {
+        sqlx::query(statement).execute(&pool).await.unwrap();
+    }
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn finds_and_repairs_broken_references() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.seed_demo_data().await.unwrap();
+    assert!(db.integrity_findings().await.unwrap().is_empty());
+
+    corrupt(
+        &dir,
+        &[
+            "UPDATE domains SET server_id = 'DEMO-SERVER-WEB' WHERE id = 'demo-domain-shop'",
+            "UPDATE domains SET server_id = 'gone' WHERE id = 'demo-domain-staging'",
+            "INSERT INTO project_resources (id, project_id, resource_type, resource_id)
+             VALUES ('stale-link', 'demo-project', 'script', 'old-backup')",
+            "INSERT INTO containers (id, name, image, server_id) VALUES ('k1', 'nginx', 'nginx', 'vanished')",
+            // Names are unique ignoring case unless the index is gone too
+            "DROP INDEX idx_servers_name_nocase",
+            "INSERT INTO servers (id, name, host, server_type) VALUES ('demo-web-2', 'DEMO-WEB', '10.0.0.9', 'vps')",
+        ],
+    )
+    .await;
+
+    let steps = doctor::plan(db.integrity_findings().await.unwrap());
+    let summary: Vec<(FindingKind, &str)> = steps
+        .iter()
+        .map(|s| (s.finding.kind, s.finding.value.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (FindingKind::CaseDuplicate, "demo-web"),
+            (FindingKind::MissingParent, "vanished"),
+            (FindingKind::DanglingRef, "DEMO-SERVER-WEB"),
+            (FindingKind::DanglingRef, "gone"),
+            (FindingKind::OrphanedLink, "old-backup"),
+        ]
+    );
+    let container = &steps[1].finding;
+    assert!(container.site.as_ref().unwrap().required);
+    assert_eq!(steps[1].repair, None);
+    // The ID in another case is a clear match despite the duplicate names
+    assert_eq!(
+        steps[2].repair.as_ref().map(|r| r.to_string()),
+        Some("relink to 'demo-web'".to_string())
+    );
+
+    for step in &steps {
+        if let Some(repair) = &step.repair {
+            db.apply_repair(&step.finding, repair).await.unwrap();
+        }
+    }
+    let shop = db.get_domain("demo-domain-shop").await.unwrap().unwrap();
+    assert_eq!(shop.server_id.as_deref(), Some("demo-server-web"));
+    let staging = db.get_domain("demo-domain-staging").await.unwrap().unwrap();
+    assert_eq!(staging.server_id, None);
+
+    // Only the container and the duplicate names are left
+    let left: Vec<FindingKind> = doctor::plan(db.integrity_findings().await.unwrap())
+        .iter()
+        .map(|s| s.finding.kind)
+        .collect();
+    assert_eq!(
+        left,
+        vec![FindingKind::CaseDuplicate, FindingKind::MissingParent]
+    );
+
+    // A stale finding isn't applied twice
+    assert!(db
+        .apply_repair(&steps[3].finding, &Repair::Null)
+        .await
+        .is_err());
+
+    let audit = db
+        .list_audit_entries(Some((EntityType::Domain, "demo-domain-staging")), 1)
+        .await
+        .unwrap();
+    assert!(audit[0].summary.starts_with("doctor: clear the reference"));
+    let audit = db
+        .list_audit_entries(Some((EntityType::Project, "demo-project")), 1)
+        .await
+        .unwrap();
+    assert!(audit[0].summary.contains("old-backup"));
+}
+
+#[tokio::test]
+async fn refuses_repairs_the_column_does_not_allow() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.seed_demo_data().await.unwrap();
+    corrupt(
+        &dir,
+        &[
+            "INSERT INTO containers (id, name, image, server_id) VALUES ('k1', 'nginx', 'nginx', 'vanished')",
+            "UPDATE domains SET server_id = 'gone' WHERE id = 'demo-domain-staging'",
+        ],
+    )
+    .await;
+
+    let findings = db.integrity_findings().await.unwrap();
+    let container = findings
+        .iter()
+        .find(|f| f.kind == FindingKind::MissingParent)
+        .unwrap();
+    assert!(db.apply_repair(container, &Repair::Null).await.is_err());
+    let domain = findings
+        .iter()
+        .find(|f| f.kind == FindingKind::DanglingRef)
+        .unwrap();
+    assert!(db.apply_repair(domain, &Repair::Delete).await.is_err());
+    assert!(db
+        .get_domain("demo-domain-staging")
+        .await
+        .unwrap()
+        .is_some());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::domain_base::{BasePlan, HealthUrlChange};
+use pctrl_core::preflight::PreflightConfig;
+use pctrl_core::{
+    Domain, DomainType, EntityType, Project, ProjectResource, ProjectStatus, ResourceType,
+};
+use pctrl_database::Database;
+
+fn domain(name: &str) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Staging,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+        superseded_by: None,
+    }
+}
+
+async fn link(db: &Database, id: &str, resource_id: &str) {
+    db.link_project_resource(&ProjectResource {
+        id: id.to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Domain,
+        resource_id: resource_id.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+async fn plan(db: &Database) -> BasePlan {
+    BasePlan::new(
+        &db.list_domains().await.unwrap(),
+        "example.com",
+        "example.io",
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_migrate_base_supersedes_and_moves_references() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec![],
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    for name in [
+        "shop.example.com",
+        "api.example.com",
+        "api.example.io",
+        "other.example.org",
+    ] {
+        db.save_domain(&domain(name)).await.unwrap();
+    }
+    // Links by ID and by name
+    link(&db, "l1", "shop-example-com").await;
+    link(&db, "l2", "api.example.com").await;
+    link(&db, "l3", "other.example.org").await;
+    db.save_preflight_config(
+        "shop",
+        &PreflightConfig {
+            health_url: Some("https://shop.example.com/health".to_string()),
+            advisory: vec![],
+        },
+    )
+    .await
+    .unwrap();
+
+    let migration = db.migrate_domain_base(&plan(&db).await).await.unwrap();
+    assert_eq!(migration.domains, 1);
+    assert_eq!(migration.links, 1);
+    assert_eq!(
+        migration.health_urls,
+        [HealthUrlChange {
+            project_id: "shop".to_string(),
+            from: "https://shop.example.com/health".to_string(),
+            to: "https://shop.example.io/health".to_string(),
+        }]
+    );
+
+    // The old row stays and points to the new one, which copies its type
+    let old = db.get_domain("shop-example-com").await.unwrap().unwrap();
+    assert_eq!(old.superseded_by.as_deref(), Some("shop-example-io"));
+    let new = db
+        .get_domain_by_name("shop.example.io")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(new.domain_type, DomainType::Staging);
+    assert_eq!(new.superseded_by, None);
+
+    // The colliding rename was skipped
+    let api = db.get_domain("api-example-com").await.unwrap().unwrap();
+    assert_eq!(api.superseded_by, None);
+
+    let links: Vec<String> = db
+        .get_project_resources("shop")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|l| l.resource_id)
+        .collect();
+    assert!(links.contains(&"shop-example-io".to_string()));
+    assert!(links.contains(&"api.example.com".to_string()));
+    assert!(links.contains(&"other.example.org".to_string()));
+    assert_eq!(
+        db.get_preflight_config("shop")
+            .await
+            .unwrap()
+            .health_url
+            .as_deref(),
+        Some("https://shop.example.io/health")
+    );
+
+    // Both rows are in the audit log
+    let audit = db
+        .list_audit_entries(Some((EntityType::Domain, "shop-example-com")), 10)
+        .await
+        .unwrap();
+    assert_eq!(audit.len(), 2);
+    assert_eq!(
+        db.list_audit_entries(Some((EntityType::Domain, "shop-example-io")), 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Running it again only finds the collision
+    let again = plan(&db).await;
+    assert_eq!(again.applicable().count(), 0);
+    assert_eq!(again.collisions().count(), 1);
+}
+
+#[tokio::test]
+async fn test_superseded_by_survives_saves() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let mut old = domain("shop.example.com");
+    old.superseded_by = Some("shop-example-io".to_string());
+    db.save_domain(&old).await.unwrap();
+
+    let stored = db.get_domain("shop-example-com").await.unwrap().unwrap();
+    assert_eq!(stored.superseded_by.as_deref(), Some("shop-example-io"));
+    assert_eq!(
+        db.list_domains().await.unwrap()[0].superseded_by.as_deref(),
+        Some("shop-example-io")
+    );
+}
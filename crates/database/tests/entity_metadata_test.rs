@@ -0,0 +1,108 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::facts::Facts;
+use pctrl_core::{EntityType, Server};
+
+fn server(id: &str) -> Server {
+    Server {
+        host: format!("{}.example.com", id),
+        ..common::server(id)
+    }
+}
+
+#[tokio::test]
+async fn metadata_is_set_listed_and_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web-1")).await.unwrap();
+
+    db.set_entity_metadata(EntityType::Server, "web-1", "owner", "alice")
+        .await
+        .unwrap();
+    db.set_entity_metadata(EntityType::Server, "web-1", "owner", "bob")
+        .await
+        .unwrap();
+    db.set_entity_metadata(EntityType::Server, "web-1", "tier", "1")
+        .await
+        .unwrap();
+    let meta = db
+        .list_entity_metadata(EntityType::Server, "web-1")
+        .await
+        .unwrap();
+    assert_eq!(meta["owner"], "bob");
+    assert_eq!(meta.len(), 2);
+
+    let err = db
+        .set_entity_metadata(EntityType::Server, "web-1", "team.name", "ops")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid metadata key"), "{}", err);
+
+    assert!(db
+        .remove_entity_metadata(EntityType::Server, "web-1", "tier")
+        .await
+        .unwrap());
+    assert!(!db
+        .remove_entity_metadata(EntityType::Server, "web-1", "tier")
+        .await
+        .unwrap());
+
+    db.remove_server("web-1").await.unwrap();
+    assert!(db
+        .entity_metadata_keys(EntityType::Server)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn batched_lookups_cover_many_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    // More ids than one IN (...) batch holds
+    let ids: Vec<String> = (0..1200).map(|i| format!("srv-{:04}", i)).collect();
+    for id in ids.iter().step_by(100) {
+        db.set_entity_metadata(EntityType::Server, id, "owner", id)
+            .await
+            .unwrap();
+    }
+    db.set_entity_metadata(EntityType::Project, "srv-0000", "owner", "project")
+        .await
+        .unwrap();
+    let mut facts = Facts::new();
+    facts.insert("distro".into(), "debian".into());
+    db.replace_server_facts("srv-1100", &facts).await.unwrap();
+
+    let meta = db
+        .entity_metadata_for(EntityType::Server, &ids)
+        .await
+        .unwrap();
+    assert_eq!(meta.len(), 12);
+    assert_eq!(meta["srv-0000"]["owner"], "srv-0000");
+    assert_eq!(meta["srv-1100"]["owner"], "srv-1100");
+    assert!(!meta.contains_key("srv-0001"));
+
+    let facts = db.server_facts_for(&ids).await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts["srv-1100"]["distro"], "debian");
+    assert!(db.server_facts_for(&[]).await.unwrap().is_empty());
+
+    assert_eq!(
+        db.entity_metadata_keys(EntityType::Server)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        ["owner"]
+    );
+    assert_eq!(
+        db.server_fact_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        ["distro"]
+    );
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType};
+use pctrl_database::envelope::{
+    self, EnvelopeError, Identity, RecipientKey, SharedSecret, FORMAT_VERSION, HEADER_LEN,
+};
+use pctrl_database::Database;
+
+fn token() -> SharedSecret {
+    SharedSecret::Credential(Credential {
+        id: "cred-1".to_string(),
+        name: "hetzner-api".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "s3cr3t-token".to_string(),
+            url: Some("https://api.hetzner.cloud".to_string()),
+        },
+        notes: None,
+    })
+}
+
+fn database() -> SharedSecret {
+    SharedSecret::Database(DatabaseCredentials {
+        id: "shop-db".to_string(),
+        name: "shop-db".to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: Some("10.0.0.5".to_string()),
+        port: Some(5432),
+        database_name: Some("shop".to_string()),
+        username: Some("shop".to_string()),
+        password: Some("hunter2".to_string()),
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    })
+}
+
+#[test]
+fn test_round_trip() {
+    let alice = Identity::generate();
+    let sealed = envelope::seal(&token(), &alice.public_key()).unwrap();
+    assert_eq!(&sealed[..8], envelope::MAGIC);
+    assert_eq!(sealed[8], FORMAT_VERSION);
+    // The token isn't readable in the envelope
+    assert!(!sealed.windows(12).any(|w| w == b"s3cr3t-token"));
+
+    match envelope::open(&sealed, &alice).unwrap() {
+        SharedSecret::Credential(c) => {
+            assert_eq!(c.name, "hetzner-api");
+            assert!(
+                matches!(c.data, CredentialData::ApiToken { ref token, .. } if token == "s3cr3t-token")
+            );
+        }
+        other => panic!("unexpected {}", other.kind()),
+    }
+
+    let sealed = envelope::seal(&database(), &alice.public_key()).unwrap();
+    match envelope::open(&sealed, &alice).unwrap() {
+        SharedSecret::Database(d) => assert_eq!(d.password.as_deref(), Some("hunter2")),
+        other => panic!("unexpected {}", other.kind()),
+    }
+
+    // Every envelope uses a fresh ephemeral key and nonce
+    assert_ne!(
+        envelope::seal(&token(), &alice.public_key()).unwrap(),
+        envelope::seal(&token(), &alice.public_key()).unwrap()
+    );
+}
+
+#[test]
+fn test_wrong_recipient() {
+    let alice = Identity::generate();
+    let bob = Identity::generate();
+    let sealed = envelope::seal(&token(), &alice.public_key()).unwrap();
+    match envelope::open(&sealed, &bob) {
+        Err(EnvelopeError::WrongRecipient(key)) => assert_eq!(key, alice.public_key()),
+        other => panic!("expected WrongRecipient, got {:?}", other.map(|s| s.kind())),
+    }
+
+    // Claiming bob as recipient doesn't help: the key agreement differs
+    let mut readdressed = sealed.clone();
+    readdressed[9..41].copy_from_slice(&bob.public_key().0);
+    assert!(matches!(
+        envelope::open(&readdressed, &bob),
+        Err(EnvelopeError::Tampered)
+    ));
+}
+
+#[test]
+fn test_corrupted_envelopes() {
+    let alice = Identity::generate();
+    let sealed = envelope::seal(&token(), &alice.public_key()).unwrap();
+
+    // Any flipped bit after the recipient key fails authentication
+    for index in [45, HEADER_LEN - 1, HEADER_LEN + 3, sealed.len() - 1] {
+        let mut damaged = sealed.clone();
+        damaged[index] ^= 0x01;
+        assert!(
+            matches!(
+                envelope::open(&damaged, &alice),
+                Err(EnvelopeError::Tampered)
+            ),
+            "byte {}",
+            index
+        );
+    }
+
+    assert!(matches!(
+        envelope::open(&sealed[..sealed.len() - 1], &alice),
+        Err(EnvelopeError::Tampered)
+    ));
+    assert!(matches!(
+        envelope::open(&sealed[..HEADER_LEN], &alice),
+        Err(EnvelopeError::Tampered)
+    ));
+    assert!(matches!(
+        envelope::open(b"PCTRLBAK\x01", &alice),
+        Err(EnvelopeError::NotASecret)
+    ));
+    let mut future = sealed.clone();
+    future[8] = FORMAT_VERSION + 1;
+    assert!(matches!(
+        envelope::open(&future, &alice),
+        Err(EnvelopeError::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1
+    ));
+}
+
+#[test]
+fn test_public_key_text_form() {
+    let key = Identity::generate().public_key();
+    let text = key.to_string();
+    assert!(text.starts_with("pctrl1"));
+    assert_eq!(text.len(), 6 + 64);
+    assert_eq!(text.parse::<RecipientKey>().unwrap(), key);
+    assert_eq!(
+        format!("  {}\n", text).parse::<RecipientKey>().unwrap(),
+        key
+    );
+
+    assert!("age1qqqq".parse::<RecipientKey>().is_err());
+    assert!("pctrl1abcd".parse::<RecipientKey>().is_err());
+    assert!(format!("pctrl1{}", "zz".repeat(32))
+        .parse::<RecipientKey>()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_identity_is_stored() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    assert!(db.get_identity().await.unwrap().is_none());
+
+    let identity = Identity::generate();
+    db.save_identity(&identity).await.unwrap();
+    let stored = db.get_identity().await.unwrap().unwrap();
+    assert_eq!(stored.public_key(), identity.public_key());
+
+    // A secret sealed to the stored key opens with the loaded identity
+    let sealed = envelope::seal(&token(), &identity.public_key()).unwrap();
+    assert_eq!(
+        envelope::open(&sealed, &stored).unwrap().name(),
+        "hetzner-api"
+    );
+}
+
+#[tokio::test]
+async fn test_identity_key_is_encrypted_with_the_database_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let identity = Identity::generate();
+    {
+        let db = Database::new(path.to_str().unwrap(), Some("passphrase"))
+            .await
+            .unwrap();
+        assert!(db.is_encrypted());
+        db.save_identity(&identity).await.unwrap();
+        db.close().await;
+    }
+
+    let plain = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    assert!(plain.get_identity().await.is_err());
+    plain.close().await;
+
+    let db = Database::new(path.to_str().unwrap(), Some("passphrase"))
+        .await
+        .unwrap();
+    assert_eq!(
+        db.get_identity().await.unwrap().unwrap().public_key(),
+        identity.public_key()
+    );
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{open_db, server};
+use pctrl_core::facts::{self, Facts};
+
+fn facts_of(pairs: &[(&str, &str)]) -> Facts {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_facts_are_replaced_per_collection() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web-1")).await.unwrap();
+    db.save_server(&server("web-2")).await.unwrap();
+
+    db.replace_server_facts(
+        "web-1",
+        &facts_of(&[("distro", "debian"), ("kernel", "6.1")]),
+    )
+    .await
+    .unwrap();
+    db.replace_server_facts("web-2", &facts_of(&[("distro", "alpine")]))
+        .await
+        .unwrap();
+    db.replace_server_facts("web-1", &facts_of(&[("distro", "ubuntu"), ("gpu", "none")]))
+        .await
+        .unwrap();
+
+    let stored = db.list_server_facts("web-1").await.unwrap();
+    assert_eq!(
+        facts::to_map(&stored),
+        facts_of(&[("distro", "ubuntu"), ("gpu", "none")])
+    );
+    assert!(stored
+        .iter()
+        .all(|f| f.collected_at == stored[0].collected_at));
+    assert_eq!(db.list_all_server_facts().await.unwrap().len(), 3);
+
+    // Removing a server drops its facts
+    db.remove_server("web-1").await.unwrap();
+    assert!(db.list_server_facts("web-1").await.unwrap().is_empty());
+    assert_eq!(db.list_all_server_facts().await.unwrap().len(), 1);
+}
@@ -0,0 +1,132 @@
+-- A pctrl database from before schema versions: no schema_version in
+-- metadata, servers still point at ssh_connections, scripts lack the
+-- Docker and exit status columns
+CREATE TABLE metadata (
+    key TEXT PRIMARY KEY,
+    value BLOB NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE ssh_connections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    host TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    auth_method TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE docker_hosts (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE credentials (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    credential_type TEXT NOT NULL,
+    data TEXT NOT NULL,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE projects (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    stack TEXT,
+    status TEXT DEFAULT 'dev',
+    color TEXT,
+    icon TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE servers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    host TEXT NOT NULL,
+    server_type TEXT DEFAULT 'vps',
+    provider TEXT,
+    ssh_connection_id TEXT,
+    location TEXT,
+    specs TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (ssh_connection_id) REFERENCES ssh_connections(id)
+);
+
+CREATE TABLE domains (
+    id TEXT PRIMARY KEY,
+    domain TEXT NOT NULL UNIQUE,
+    domain_type TEXT DEFAULT 'production',
+    ssl INTEGER DEFAULT 1,
+    ssl_expiry DATETIME,
+    cloudflare_zone_id TEXT,
+    cloudflare_record_id TEXT,
+    server_id TEXT,
+    container_id TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE containers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    image TEXT,
+    server_id TEXT NOT NULL,
+    project_id TEXT,
+    status TEXT,
+    ports TEXT,
+    env_vars TEXT,
+    labels TEXT,
+    created_at DATETIME,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers(id),
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE TABLE scripts (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    command TEXT NOT NULL,
+    script_type TEXT DEFAULT 'ssh',
+    server_id TEXT,
+    project_id TEXT,
+    dangerous INTEGER DEFAULT 0,
+    last_run DATETIME,
+    last_result TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (server_id) REFERENCES servers(id),
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE TABLE project_resources (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    role TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+INSERT INTO ssh_connections (id, name, host, port, username, auth_method)
+    VALUES ('key-1', 'deploy', '203.0.113.10', 22, 'root', 'key');
+INSERT INTO credentials (id, name, credential_type, data)
+    VALUES ('key-1', 'deploy', 'ssh_key', '{"username":"root","port":22,"key_path":"~/.ssh/id_ed25519"}');
+INSERT INTO projects (id, name, status) VALUES ('shop', 'Shop', 'live');
+INSERT INTO servers (id, name, host, ssh_connection_id)
+    VALUES ('web-1', 'web-1', '203.0.113.10', 'key-1');
+INSERT INTO domains (id, domain, server_id) VALUES ('d-1', 'shop.example.com', 'web-1');
+INSERT INTO scripts (id, name, command, server_id, project_id)
+    VALUES ('s-1', 'deploy', './deploy.sh', 'web-1', 'shop');
+INSERT INTO project_resources (id, project_id, resource_type, resource_id, role)
+    VALUES ('r-1', 'shop', 'server', 'web-1', 'production');
@@ -0,0 +1,61 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::open_db;
+use pctrl_core::growth::{reconstruct, Granularity, Share};
+use pctrl_core::{EntityType, Server};
+
+fn server(id: &str, provider: Option<&str>) -> Server {
+    Server {
+        provider: provider.map(str::to_string),
+        ..common::server(id)
+    }
+}
+
+#[tokio::test]
+async fn test_trashed_and_purged_servers_count_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web", Some("Hetzner")))
+        .await
+        .unwrap();
+    db.save_server(&server("db", Some("hetzner")))
+        .await
+        .unwrap();
+    db.save_server(&server("old", None)).await.unwrap();
+    db.trash_server("old").await.unwrap();
+    db.remove_server("old").await.unwrap();
+
+    let counts = db.entity_counts().await.unwrap();
+    assert!(counts.contains(&(EntityType::Server, 2)));
+    assert!(counts.contains(&(EntityType::Project, 0)));
+
+    let events = db.growth_events().await.unwrap();
+    assert_eq!(events.len(), 5);
+    let now = Utc::now();
+    let growth = reconstruct(
+        &counts,
+        &events,
+        now - Duration::days(1),
+        now,
+        Granularity::Day,
+    );
+    let servers = growth
+        .series
+        .iter()
+        .find(|s| s.entity_type == EntityType::Server)
+        .unwrap();
+    assert_eq!(servers.baseline, 0);
+    assert_eq!(servers.counts.last(), Some(&2));
+    assert_eq!(servers.created.iter().sum::<u64>(), 3);
+    assert_eq!(servers.removed.iter().sum::<u64>(), 1);
+
+    let composition = db.composition().await.unwrap();
+    assert_eq!(
+        composition.servers_by_provider,
+        vec![Share {
+            name: "hetzner".to_string(),
+            count: 2
+        }]
+    );
+}
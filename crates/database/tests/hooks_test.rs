@@ -0,0 +1,53 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::hooks::HookRunner;
+use pctrl_core::Server;
+use std::os::unix::fs::PermissionsExt;
+
+fn server() -> Server {
+    common::server("web-1")
+}
+
+#[tokio::test]
+async fn test_mutations_fire_hooks_without_failing() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let hooks = dir.path().join("hooks");
+    let event_dir = hooks.join("server.created");
+    std::fs::create_dir_all(&event_dir).unwrap();
+    let out = dir.path().join("payload.json");
+    let ok = event_dir.join("10-capture");
+    std::fs::write(&ok, format!("#!/bin/sh\ncat > '{}'\n", out.display())).unwrap();
+    let broken = event_dir.join("20-broken");
+    std::fs::write(&broken, "#!/bin/sh\nexit 1\n").unwrap();
+    for path in [&ok, &broken] {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    db.set_hook_runner(HookRunner::new(&hooks));
+
+    // The failing hook doesn't fail the save
+    db.save_server(&server()).await.unwrap();
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    assert_eq!(payload["event"], "server.created");
+    assert_eq!(payload["entity_id"], "web-1");
+    assert_eq!(payload["data"]["host"], "10.0.0.1");
+
+    let last = db
+        .last_hook_run("server.created", "20-broken")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!last.success);
+    assert_eq!(last.exit_code, Some(1));
+    assert!(
+        db.last_hook_run("server.created", "10-capture")
+            .await
+            .unwrap()
+            .unwrap()
+            .success
+    );
+}
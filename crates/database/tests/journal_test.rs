@@ -0,0 +1,144 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::search::MatchKind;
+use pctrl_core::{EntityType, Server, ServerType};
+
+#[tokio::test]
+async fn test_amending_keeps_the_original() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let original = db
+        .add_journal_entry(
+            EntityType::Server,
+            "web",
+            "alice@laptop",
+            "  replaced failed disk, rebuilt raid \n",
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(original.text, "replaced failed disk, rebuilt raid");
+
+    let fix = db
+        .add_journal_entry(
+            EntityType::Server,
+            "web",
+            "bob@desk",
+            "it was sdb, raid1",
+            Some(original.id),
+        )
+        .await
+        .unwrap();
+    let fix_of_fix = db
+        .add_journal_entry(
+            EntityType::Server,
+            "web",
+            "alice@laptop",
+            "sdb, raid1, resync took 3h",
+            Some(fix.id),
+        )
+        .await
+        .unwrap();
+
+    // The original is still there as written; each correction points one back
+    assert_eq!(
+        db.get_journal_entry(original.id).await.unwrap(),
+        Some(original.clone())
+    );
+    let mut chain = vec![fix_of_fix.id];
+    let mut entry = fix_of_fix.clone();
+    while let Some(previous) = entry.amends {
+        chain.push(previous);
+        entry = db.get_journal_entry(previous).await.unwrap().unwrap();
+    }
+    assert_eq!(chain, vec![fix_of_fix.id, fix.id, original.id]);
+
+    // Newest first, limited
+    let listed = db
+        .list_journal_entries(EntityType::Server, "web", 2)
+        .await
+        .unwrap();
+    assert_eq!(listed, vec![fix_of_fix.clone(), fix]);
+    assert_eq!(
+        db.latest_journal_entry(EntityType::Server, "web")
+            .await
+            .unwrap(),
+        Some(fix_of_fix)
+    );
+    assert!(db
+        .latest_journal_entry(EntityType::Server, "db")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_amend_must_name_an_entry_of_the_same_entity() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let entry = db
+        .add_journal_entry(EntityType::Server, "web", "alice@laptop", "rebooted", None)
+        .await
+        .unwrap();
+
+    let other = db
+        .add_journal_entry(
+            EntityType::Domain,
+            "web",
+            "alice@laptop",
+            "x",
+            Some(entry.id),
+        )
+        .await
+        .unwrap_err();
+    assert!(other.to_string().contains("belongs to server"), "{}", other);
+    let missing = db
+        .add_journal_entry(EntityType::Server, "web", "alice@laptop", "x", Some(99))
+        .await
+        .unwrap_err();
+    assert!(missing.to_string().contains("#99 not found"), "{}", missing);
+    assert!(db
+        .add_journal_entry(EntityType::Server, "web", "alice@laptop", "  ", None)
+        .await
+        .is_err());
+
+    assert_eq!(db.list_all_journal_entries().await.unwrap(), vec![entry]);
+}
+
+#[tokio::test]
+async fn test_journal_text_is_searchable() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&Server {
+        id: "s-web".to_string(),
+        name: "web".to_string(),
+        host: "203.0.113.5".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    db.add_journal_entry(
+        EntityType::Server,
+        "s-web",
+        "alice@laptop",
+        "swapped the RAID controller",
+        None,
+    )
+    .await
+    .unwrap();
+
+    let hits = db.global_search("raid controller", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, "s-web");
+    assert_eq!(hits[0].matched, MatchKind::Detail);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::fanout::{FanoutReport, Outcome, TargetResult};
+
+fn report(command: &str, outcome: Outcome) -> FanoutReport {
+    let mut report = FanoutReport::new(command, "2024-06-01T12:00:00Z");
+    report.push(TargetResult {
+        id: "web".to_string(),
+        name: "web".to_string(),
+        outcome,
+        duration_ms: 250,
+        message: Some("ünïcødé message".to_string()),
+    });
+    report.push(TargetResult::skipped("db", "db", "an earlier phase failed"));
+    report.duration_ms = 260;
+    report
+}
+
+#[tokio::test]
+async fn test_last_run_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert_eq!(db.get_last_run("project start").await.unwrap(), None);
+    assert!(db.list_last_runs().await.unwrap().is_empty());
+
+    let first = report("project start", Outcome::Failed);
+    db.save_last_run(&first).await.unwrap();
+    assert_eq!(db.get_last_run("project start").await.unwrap(), Some(first));
+
+    // The next run of the same command replaces it; other commands are kept
+    let second = report("project start", Outcome::Ok);
+    let monitor = report("monitor run", Outcome::Ok);
+    db.save_last_run(&second).await.unwrap();
+    db.save_last_run(&monitor).await.unwrap();
+    assert_eq!(
+        db.get_last_run("project start").await.unwrap(),
+        Some(second.clone())
+    );
+
+    let all = db.list_last_runs().await.unwrap();
+    assert_eq!(all, vec![monitor, second]);
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{
+    Project, ProjectResource, ProjectStatus, ResourceType, Script, ScriptType, Server,
+};
+use pctrl_database::Database;
+
+async fn project(db: &Database, name: &str, status: ProjectStatus) {
+    db.save_project(&Project {
+        id: name.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+}
+
+async fn server(db: &Database, id: &str, name: &str) {
+    db.save_server(&Server {
+        name: name.to_string(),
+        ..common::server(id)
+    })
+    .await
+    .unwrap();
+}
+
+async fn link(db: &Database, project: &str, resource_type: ResourceType, resource_id: &str) {
+    db.link_project_resource(&ProjectResource {
+        id: format!("{}-{}", project, resource_id),
+        project_id: project.to_string(),
+        resource_type,
+        resource_id: resource_id.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+fn script(project_id: Option<&str>, server_id: Option<&str>) -> Script {
+    Script {
+        id: "wipe".to_string(),
+        name: "wipe-test-data".to_string(),
+        description: None,
+        command: "rm -rf /srv/app/test-data".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: server_id.map(String::from),
+        project_id: project_id.map(String::from),
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn names(projects: &[Project]) -> Vec<&str> {
+    projects.iter().map(|p| p.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_script_project_status_decides() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    project(&db, "shop", ProjectStatus::Live).await;
+    project(&db, "shop-test", ProjectStatus::Dev).await;
+
+    let live = db
+        .live_projects_for_script_target(&script(Some("shop"), None))
+        .await
+        .unwrap();
+    assert_eq!(names(&live), vec!["shop"]);
+
+    let dev = db
+        .live_projects_for_script_target(&script(Some("shop-test"), None))
+        .await
+        .unwrap();
+    assert!(dev.is_empty());
+}
+
+#[tokio::test]
+async fn test_server_shared_by_live_and_dev_counts_as_live() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    project(&db, "shop", ProjectStatus::Live).await;
+    project(&db, "shop-test", ProjectStatus::Dev).await;
+    server(&db, "srv-1", "web-1").await;
+    link(&db, "shop", ResourceType::Server, "srv-1").await;
+    link(&db, "shop-test", ResourceType::Server, "srv-1").await;
+
+    // The script itself belongs to the Dev project, the server makes it Live
+    let live = db
+        .live_projects_for_script_target(&script(Some("shop-test"), Some("srv-1")))
+        .await
+        .unwrap();
+    assert_eq!(names(&live), vec!["shop"]);
+}
+
+#[tokio::test]
+async fn test_server_linked_by_name_is_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    project(&db, "shop", ProjectStatus::Live).await;
+    server(&db, "srv-1", "web-1").await;
+    link(&db, "shop", ResourceType::Server, "web-1").await;
+
+    let live = db
+        .live_projects_for_script_target(&script(None, Some("srv-1")))
+        .await
+        .unwrap();
+    assert_eq!(names(&live), vec!["shop"]);
+}
+
+#[tokio::test]
+async fn test_dev_and_staging_only_targets_are_not_guarded() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    project(&db, "shop-test", ProjectStatus::Dev).await;
+    project(&db, "shop-staging", ProjectStatus::Staging).await;
+    server(&db, "srv-2", "test-1").await;
+    link(&db, "shop-test", ResourceType::Server, "srv-2").await;
+    link(&db, "shop-staging", ResourceType::Server, "srv-2").await;
+
+    let live = db
+        .live_projects_for_script_target(&script(None, Some("srv-2")))
+        .await
+        .unwrap();
+    assert!(live.is_empty());
+}
+
+#[tokio::test]
+async fn test_live_project_reached_twice_is_listed_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    project(&db, "shop", ProjectStatus::Live).await;
+    server(&db, "srv-1", "web-1").await;
+    link(&db, "shop", ResourceType::Server, "srv-1").await;
+    link(&db, "shop", ResourceType::Container, "shop-app").await;
+
+    let mut target = script(Some("shop"), Some("srv-1"));
+    target.container_id = Some("shop-app".to_string());
+    let live = db.live_projects_for_script_target(&target).await.unwrap();
+    assert_eq!(names(&live), vec!["shop"]);
+
+    let by_container = db
+        .live_projects_for_resource(&ResourceType::Container, &["shop-app"])
+        .await
+        .unwrap();
+    assert_eq!(names(&by_container), vec!["shop"]);
+}
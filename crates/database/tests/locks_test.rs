@@ -0,0 +1,213 @@
+mod common;
+
+use common::{open_db, server};
+use pctrl_core::{
+    EntityType, Error, Project, ProjectResource, ProjectStatus, ResourceType, Server,
+};
+
+#[tokio::test]
+async fn test_lock_by_other_holder_blocks_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web-1")).await.unwrap();
+
+    db.lock_entity(
+        EntityType::Server,
+        "web-1",
+        "someone-else@elsewhere",
+        Some("migrating"),
+        chrono::Duration::hours(8),
+    )
+    .await
+    .unwrap();
+
+    let err = db.save_server(&server("web-1")).await.unwrap_err();
+    assert!(matches!(err, Error::Locked(_)));
+    assert!(db.remove_server("web-1").await.is_err());
+    assert!(db.trash_server("web-1").await.is_err());
+
+    db.set_lock_override(true);
+    db.trash_server("web-1").await.unwrap();
+    db.set_lock_override(false);
+    // Restoring from the trash is a write like any other
+    let err = db.restore_server("web-1").await.unwrap_err();
+    assert!(matches!(err, Error::Locked(_)));
+
+    db.set_lock_override(true);
+    db.restore_server("web-1").await.unwrap().unwrap();
+    db.save_server(&server("web-1")).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_own_lock_does_not_block_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let holder = pctrl_core::current_holder();
+    db.lock_entity(
+        EntityType::Server,
+        "web-1",
+        &holder,
+        None,
+        chrono::Duration::hours(1),
+    )
+    .await
+    .unwrap();
+
+    db.save_server(&server("web-1")).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_lock_is_ignored_and_pruned() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.lock_entity(
+        EntityType::Server,
+        "web-1",
+        "someone-else@elsewhere",
+        None,
+        chrono::Duration::seconds(-1),
+    )
+    .await
+    .unwrap();
+
+    db.save_server(&server("web-1")).await.unwrap();
+    assert!(db
+        .get_entity_lock(EntityType::Server, "web-1")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db.list_entity_locks().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_active_lock_is_not_taken_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&Server {
+        name: "web-1".to_string(),
+        ..server("0b6f3c2e-srv")
+    })
+    .await
+    .unwrap();
+    let lock = |holder: &'static str| {
+        db.lock_entity(
+            EntityType::Server,
+            "0b6f3c2e-srv",
+            holder,
+            Some("migrating"),
+            chrono::Duration::hours(8),
+        )
+    };
+    lock("alice@laptop").await.unwrap();
+
+    // Someone else is refused and told who holds it, by the server's name
+    let err = lock("bob@desktop").await.unwrap_err();
+    let Error::Locked(message) = err else {
+        panic!("expected Locked, got {:?}", err);
+    };
+    assert!(
+        message.starts_with("server 'web-1' is locked by alice@laptop (migrating)"),
+        "{}",
+        message
+    );
+    let current = db
+        .get_entity_lock(EntityType::Server, "0b6f3c2e-srv")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(current.holder, "alice@laptop");
+    assert_eq!(current.entity_name, "web-1");
+
+    // The holder renews it
+    lock("alice@laptop").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_lock_is_taken_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.lock_entity(
+        EntityType::Server,
+        "web-1",
+        "alice@laptop",
+        None,
+        chrono::Duration::seconds(-1),
+    )
+    .await
+    .unwrap();
+
+    let lock = db
+        .lock_entity(
+            EntityType::Server,
+            "web-1",
+            "bob@desktop",
+            None,
+            chrono::Duration::hours(1),
+        )
+        .await
+        .unwrap();
+    // No such server: the lock names it by ID
+    assert_eq!(lock.entity_name, "web-1");
+    assert_eq!(
+        db.get_entity_lock(EntityType::Server, "web-1")
+            .await
+            .unwrap()
+            .unwrap()
+            .holder,
+        "bob@desktop"
+    );
+}
+
+#[tokio::test]
+async fn test_locked_project_refuses_link_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let link = |id: &str| ProjectResource {
+        id: id.to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: "web-1".to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    };
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec![],
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_server(&server("web-1")).await.unwrap();
+    db.link_project_resource(&link("link-1")).await.unwrap();
+    db.lock_entity(
+        EntityType::Project,
+        "shop",
+        "someone-else@elsewhere",
+        None,
+        chrono::Duration::hours(8),
+    )
+    .await
+    .unwrap();
+
+    let err = db.link_project_resource(&link("link-2")).await.unwrap_err();
+    assert!(matches!(err, Error::Locked(_)));
+    let err = db
+        .set_resource_start_order("link-1", Some(1))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Locked(_)));
+    let err = db.unlink_project_resource("link-1").await.unwrap_err();
+    assert!(matches!(err, Error::Locked(_)));
+    assert_eq!(db.get_project_resources("shop").await.unwrap().len(), 1);
+
+    db.set_lock_override(true);
+    assert!(db.unlink_project_resource("link-1").await.unwrap());
+}
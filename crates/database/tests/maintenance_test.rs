@@ -0,0 +1,282 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::open_db;
+use pctrl_core::maintenance::EndedWindow;
+use pctrl_core::ship::DeployConfig;
+use pctrl_core::{EntityType, Project, ProjectResource, ProjectStatus, ResourceType, Server};
+use pctrl_database::Database;
+
+fn project(name: &str, status: ProjectStatus) -> Project {
+    Project {
+        id: name.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+async fn server(db: &Database, id: &str, name: &str) {
+    db.save_server(&Server {
+        name: name.to_string(),
+        ..common::server(id)
+    })
+    .await
+    .unwrap();
+}
+
+async fn link_server(db: &Database, project: &str, server: &str) {
+    db.link_project_resource(&ProjectResource {
+        id: format!("{}-{}", project, server),
+        project_id: project.to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: server.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+async fn status(db: &Database, id: &str) -> ProjectStatus {
+    db.get_project(id).await.unwrap().unwrap().status
+}
+
+#[tokio::test]
+async fn test_expired_window_restores_previous_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    let window = db
+        .start_maintenance(
+            &shop,
+            Some("db migration"),
+            start,
+            start + Duration::hours(2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(window.previous_status, ProjectStatus::Live);
+    assert_eq!(window.expires_at, "2026-03-02T00:00:00Z");
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Maintenance);
+
+    // Still open an hour in
+    let during = start + Duration::hours(1);
+    assert!(db.end_expired_maintenance(during).await.unwrap().is_empty());
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Maintenance);
+
+    // Whoever runs first after the expiry (monitor cycle or any other
+    // invocation) restores the status, once
+    let after = start + Duration::hours(2);
+    assert_eq!(
+        db.end_expired_maintenance(after).await.unwrap(),
+        [EndedWindow {
+            project_id: "shop".to_string(),
+            project_name: "shop".to_string(),
+            restored: Some(ProjectStatus::Live),
+        }]
+    );
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Live);
+    assert!(db.get_maintenance_window("shop").await.unwrap().is_none());
+    assert!(db.end_expired_maintenance(after).await.unwrap().is_empty());
+
+    // Both transitions are in the audit log
+    let audit = db
+        .list_audit_entries(Some((EntityType::Project, "shop")), 10)
+        .await
+        .unwrap();
+    assert_eq!(audit.len(), 3);
+}
+
+#[tokio::test]
+async fn test_window_ends_even_when_project_is_locked() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Staging);
+    db.save_project(&shop).await.unwrap();
+
+    let start = Utc::now() - Duration::hours(3);
+    db.start_maintenance(&shop, None, start, start + Duration::hours(1))
+        .await
+        .unwrap();
+    db.lock_entity(
+        EntityType::Project,
+        "shop",
+        "someone-else@elsewhere",
+        None,
+        Duration::hours(8),
+    )
+    .await
+    .unwrap();
+
+    let ended = db.end_expired_maintenance(Utc::now()).await.unwrap();
+    assert_eq!(ended[0].restored, Some(ProjectStatus::Staging));
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Staging);
+}
+
+#[tokio::test]
+async fn test_locked_project_refuses_manual_maintenance_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+
+    let start = Utc::now();
+    db.start_maintenance(&shop, None, start, start + Duration::hours(1))
+        .await
+        .unwrap();
+    db.lock_entity(
+        EntityType::Project,
+        "shop",
+        "someone-else@elsewhere",
+        None,
+        Duration::hours(8),
+    )
+    .await
+    .unwrap();
+
+    assert!(db.end_maintenance("shop").await.is_err());
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Maintenance);
+    assert!(db.get_maintenance_window("shop").await.unwrap().is_some());
+
+    let shop = db.get_project("shop").await.unwrap().unwrap();
+    assert!(db
+        .start_maintenance(&shop, None, start, start + Duration::hours(2))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_status_changed_during_window_is_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    db.start_maintenance(&shop, None, start, start + Duration::hours(2))
+        .await
+        .unwrap();
+    db.save_project(&project("shop", ProjectStatus::Archived))
+        .await
+        .unwrap();
+
+    let ended = db
+        .end_expired_maintenance(start + Duration::hours(3))
+        .await
+        .unwrap();
+    assert_eq!(ended[0].restored, None);
+    assert_eq!(status(&db, "shop").await, ProjectStatus::Archived);
+}
+
+#[tokio::test]
+async fn test_extending_keeps_original_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    db.start_maintenance(&shop, None, start, start + Duration::hours(1))
+        .await
+        .unwrap();
+    let in_maintenance = db.get_project("shop").await.unwrap().unwrap();
+    let extended = db
+        .start_maintenance(
+            &in_maintenance,
+            Some("longer than planned"),
+            start,
+            start + Duration::hours(4),
+        )
+        .await
+        .unwrap();
+    assert_eq!(extended.previous_status, ProjectStatus::Live);
+
+    // The old expiry no longer ends it
+    let later = start + Duration::hours(2);
+    assert!(db.end_expired_maintenance(later).await.unwrap().is_empty());
+
+    // Ending it early restores the status too
+    let ended = db.end_maintenance("shop").await.unwrap().unwrap();
+    assert_eq!(ended.restored, Some(ProjectStatus::Live));
+    assert!(db.end_maintenance("shop").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_scope_covers_project_servers_while_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+    db.save_project(&project("blog", ProjectStatus::Live))
+        .await
+        .unwrap();
+    server(&db, "srv-web", "web-1").await;
+    server(&db, "srv-db", "db-1").await;
+    server(&db, "srv-deploy", "deploy-1").await;
+    server(&db, "srv-blog", "blog-1").await;
+    link_server(&db, "shop", "srv-web").await;
+    // Links may name the server instead of using its id
+    link_server(&db, "shop", "db-1").await;
+    link_server(&db, "blog", "srv-blog").await;
+    db.save_deploy_config(
+        "shop",
+        &DeployConfig {
+            path: Some("/srv/shop".to_string()),
+            branch: None,
+            server_id: Some("srv-deploy".to_string()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let start = Utc.with_ymd_and_hms(2026, 3, 1, 22, 0, 0).unwrap();
+    assert!(db.maintenance_scope(start).await.unwrap().is_empty());
+
+    db.start_maintenance(&shop, None, start, start + Duration::hours(2))
+        .await
+        .unwrap();
+    let scope = db
+        .maintenance_scope(start + Duration::hours(1))
+        .await
+        .unwrap();
+    assert_eq!(scope.covering("srv-web", "web-1"), Some("shop"));
+    assert_eq!(scope.covering("srv-db", "db-1"), Some("shop"));
+    assert_eq!(scope.covering("srv-deploy", "deploy-1"), Some("shop"));
+    assert_eq!(scope.covering("srv-blog", "blog-1"), None);
+
+    // An expired window no longer suppresses anything, even before it
+    // was cleaned up
+    let after = start + Duration::hours(2);
+    assert!(db.maintenance_scope(after).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_maintenance_counts_as_live_for_guards() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let shop = project("shop", ProjectStatus::Live);
+    db.save_project(&shop).await.unwrap();
+    server(&db, "srv-web", "web-1").await;
+    link_server(&db, "shop", "srv-web").await;
+
+    let now = Utc::now();
+    db.start_maintenance(&shop, None, now, now + Duration::hours(1))
+        .await
+        .unwrap();
+
+    let live = db
+        .live_projects_for_resource(&ResourceType::Server, &["srv-web"])
+        .await
+        .unwrap();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].status, ProjectStatus::Maintenance);
+}
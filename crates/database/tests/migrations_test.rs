@@ -0,0 +1,134 @@
+use pctrl_database::{Database, CURRENT_SCHEMA_VERSION};
+use sqlx::sqlite::SqlitePool;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const SCHEMA_V1: &str = include_str!("fixtures/schema_v1.sql");
+
+async fn raw_pool(path: &Path) -> SqlitePool {
+    SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap()
+}
+
+/// Columns of every table, by table
+async fn columns(path: &Path) -> BTreeMap<String, Vec<String>> {
+    let pool = raw_pool(path).await;
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let mut columns = BTreeMap::new();
+    for (table,) in tables {
+        let rows: Vec<(String,)> =
+            sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let mut names: Vec<String> = rows.into_iter().map(|(name,)| name).collect();
+        names.sort();
+        columns.insert(table, names);
+    }
+    pool.close().await;
+    columns
+}
+
+#[tokio::test]
+async fn test_v1_database_migrates_to_current_schema() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.db");
+    let pool = raw_pool(&old).await;
+    sqlx::query(SCHEMA_V1).execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let db = Database::new(old.to_str().unwrap(), None).await.unwrap();
+    assert_eq!(db.schema_version().await.unwrap(), CURRENT_SCHEMA_VERSION);
+
+    // Rows survive, the renamed column included
+    let server = db.get_server("web-1").await.unwrap().unwrap();
+    assert_eq!(server.credential_id.as_deref(), Some("key-1"));
+    let script = db.get_script("s-1").await.unwrap().unwrap();
+    assert_eq!(script.command, "./deploy.sh");
+    assert_eq!(script.docker_host_id, None);
+    assert_eq!(script.exit_code, None);
+    assert_eq!(db.list_domains().await.unwrap().len(), 1);
+    db.close().await;
+
+    // Same tables and columns as a database created at this version
+    let fresh = dir.path().join("fresh.db");
+    Database::new(fresh.to_str().unwrap(), None)
+        .await
+        .unwrap()
+        .close()
+        .await;
+    assert_eq!(columns(&old).await, columns(&fresh).await);
+}
+
+#[tokio::test]
+async fn test_last_script_run_moves_into_the_run_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.db");
+    let pool = raw_pool(&path).await;
+    sqlx::query(SCHEMA_V1).execute(&pool).await.unwrap();
+    sqlx::query("UPDATE scripts SET last_run = '2024-01-02 10:00:00', last_result = 'success'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    let runs = db.list_script_runs("s-1", 10).await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].finished_at, "2024-01-02 10:00:00");
+    assert_eq!(runs[0].triggered_by, "unknown");
+    db.close().await;
+}
+
+#[tokio::test]
+async fn test_migrated_database_opens_again_unchanged() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.db");
+    let pool = raw_pool(&path).await;
+    sqlx::query(SCHEMA_V1).execute(&pool).await.unwrap();
+    pool.close().await;
+
+    Database::new(path.to_str().unwrap(), None)
+        .await
+        .unwrap()
+        .close()
+        .await;
+    let migrated = columns(&path).await;
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    assert_eq!(db.schema_version().await.unwrap(), CURRENT_SCHEMA_VERSION);
+    db.close().await;
+    assert_eq!(columns(&path).await, migrated);
+}
+
+#[tokio::test]
+async fn test_newer_schema_is_refused_with_both_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("newer.db");
+    Database::new(path.to_str().unwrap(), None)
+        .await
+        .unwrap()
+        .close()
+        .await;
+    let pool = raw_pool(&path).await;
+    sqlx::query("UPDATE metadata SET value = '99' WHERE key = 'schema_version'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+
+    // A sqlite: URL skips the file checks that would open it read-only
+    let url = format!("sqlite:{}", path.display());
+    let err = Database::new(&url, None).await.err().unwrap().to_string();
+    assert!(err.contains("v99"), "{}", err);
+    assert!(
+        err.contains(&format!("v{}", CURRENT_SCHEMA_VERSION)),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::monitor::MonitorState;
+
+fn cycle(at: &str, down: u32, heartbeat_error: Option<&str>) -> MonitorState {
+    MonitorState {
+        last_cycle_at: at.to_string(),
+        servers_checked: 4,
+        servers_down: down,
+        duration_ms: 250,
+        interval_secs: 60,
+        heartbeat_error: heartbeat_error.map(String::from),
+    }
+}
+
+#[tokio::test]
+async fn test_monitor_cycles_replace_each_other() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert_eq!(db.get_monitor_state().await.unwrap(), None);
+
+    let first = cycle(
+        "2024-06-01T12:00:00Z",
+        1,
+        Some("HTTP 503 (after 3 attempts)"),
+    );
+    db.record_monitor_cycle(&first).await.unwrap();
+    assert_eq!(db.get_monitor_state().await.unwrap(), Some(first));
+
+    // The next cycle overwrites the row, including a cleared heartbeat error
+    let second = cycle("2024-06-01T12:01:00Z", 0, None);
+    db.record_monitor_cycle(&second).await.unwrap();
+    assert_eq!(db.get_monitor_state().await.unwrap(), Some(second));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{ContainerNetwork, DockerNetwork, PublishedPort};
+
+fn network(host: &str, name: &str) -> DockerNetwork {
+    DockerNetwork {
+        host_id: host.to_string(),
+        name: name.to_string(),
+        driver: Some("bridge".to_string()),
+    }
+}
+
+fn member(host: &str, container: &str, network: &str) -> ContainerNetwork {
+    ContainerNetwork {
+        host_id: host.to_string(),
+        container: container.to_string(),
+        network: network.to_string(),
+        ip_address: Some("172.18.0.2".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_sync_replaces_only_the_synced_host() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let port = PublishedPort {
+        host_id: "h1".to_string(),
+        container: "web".to_string(),
+        host_port: 8080,
+        container_port: 80,
+        protocol: "tcp".to_string(),
+    };
+    db.replace_network_topology(
+        "h1",
+        &[network("h1", "app"), network("h1", "old")],
+        &[member("h1", "web", "app")],
+        std::slice::from_ref(&port),
+    )
+    .await
+    .unwrap();
+    db.replace_network_topology(
+        "h2",
+        &[network("h2", "app")],
+        &[member("h2", "db", "app")],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Second sync of h1: "old" network and the port are gone
+    db.replace_network_topology(
+        "h1",
+        &[network("h1", "app")],
+        &[member("h1", "web", "app")],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let h1 = db.list_docker_networks("h1").await.unwrap();
+    assert_eq!(h1, vec![network("h1", "app")]);
+    assert_eq!(db.list_docker_networks("h2").await.unwrap().len(), 1);
+    assert_eq!(db.list_container_networks().await.unwrap().len(), 2);
+    assert!(db.list_published_ports().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_published_ports_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let port = PublishedPort {
+        host_id: "h1".to_string(),
+        container: "db".to_string(),
+        host_port: 65432,
+        container_port: 5432,
+        protocol: "tcp".to_string(),
+    };
+
+    db.replace_network_topology("h1", &[], &[], std::slice::from_ref(&port))
+        .await
+        .unwrap();
+    assert_eq!(db.list_published_ports().await.unwrap(), vec![port]);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::preflight::{CheckKind, PreflightConfig};
+use pctrl_core::{Project, ProjectStatus};
+
+#[tokio::test]
+async fn test_preflight_config_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec![],
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+
+    // Unconfigured projects block on everything
+    assert_eq!(
+        db.get_preflight_config("shop").await.unwrap(),
+        PreflightConfig::default()
+    );
+
+    let config = PreflightConfig {
+        health_url: Some("https://shop.example.com/health".to_string()),
+        advisory: vec![CheckKind::Database, CheckKind::Health],
+    };
+    db.save_preflight_config("shop", &config).await.unwrap();
+    assert_eq!(db.get_preflight_config("shop").await.unwrap(), config);
+
+    // Settings go with the project
+    db.remove_project("shop").await.unwrap();
+    assert_eq!(
+        db.get_preflight_config("shop").await.unwrap(),
+        PreflightConfig::default()
+    );
+}
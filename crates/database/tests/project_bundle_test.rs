@@ -0,0 +1,217 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{
+    DatabaseCredentials, DatabaseType, Domain, DomainType, Project, ProjectResource, ProjectStatus,
+    ResourceType, Script, ScriptType, Server,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+
+/// Statements sqlx has run, from its `sqlx::query` log events. sqlx runs
+/// SQLite statements on worker threads, so the counter is process-wide and
+/// this file holds a single test.
+static STATEMENTS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountStatements;
+
+/// Reads the statement summary, e.g. "SELECT id, name, host, …"
+#[derive(Default)]
+struct Summary(String);
+
+impl Visit for Summary {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "summary" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "summary" {
+            self.0 = value.to_string();
+        }
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for CountStatements {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != "sqlx::query" {
+            return;
+        }
+        // Connection setup (PRAGMAs) runs whenever the pool opens a connection
+        let mut summary = Summary::default();
+        event.record(&mut summary);
+        if !summary.0.starts_with("PRAGMA") {
+            STATEMENTS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn server(id: &str) -> Server {
+    Server {
+        name: id.to_string(),
+        host: format!("{}.example.com", id),
+        ..common::server(&format!("srv-{}", id))
+    }
+}
+
+fn domain(name: &str) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+        superseded_by: None,
+    }
+}
+
+fn database(id: &str) -> DatabaseCredentials {
+    DatabaseCredentials {
+        id: id.to_string(),
+        name: id.to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: None,
+        port: None,
+        database_name: None,
+        username: None,
+        password: None,
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    }
+}
+
+fn script(id: &str) -> Script {
+    Script {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        command: "uptime".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn link(n: usize, resource_type: ResourceType, resource_id: &str) -> ProjectResource {
+    ProjectResource {
+        id: format!("link-{}", n),
+        project_id: "shop".to_string(),
+        resource_type,
+        resource_id: resource_id.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    }
+}
+
+#[tokio::test]
+async fn test_bundle_loads_twenty_links_in_six_queries() {
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(CountStatements))
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "Shop".to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+
+    // 8 servers (half linked by name), 5 domains, 4 databases, 2 scripts
+    // and a container: 20 links, one of them to a server that is gone
+    let mut links = Vec::new();
+    for i in 0..8 {
+        let srv = server(&format!("web{}", i));
+        db.save_server(&srv).await.unwrap();
+        let reference = if i % 2 == 0 {
+            srv.id
+        } else {
+            srv.name.to_uppercase()
+        };
+        links.push((ResourceType::Server, reference));
+    }
+    links.pop();
+    links.push((ResourceType::Server, "srv-gone".to_string()));
+    for i in 0..5 {
+        let d = domain(&format!("app{}.example.com", i));
+        db.save_domain(&d).await.unwrap();
+        links.push((ResourceType::Domain, d.domain));
+    }
+    for i in 0..4 {
+        let d = database(&format!("db{}", i));
+        db.save_database_credentials(&d).await.unwrap();
+        links.push((ResourceType::Database, d.id));
+    }
+    for i in 0..2 {
+        let s = script(&format!("deploy{}", i));
+        db.save_script(&s).await.unwrap();
+        links.push((ResourceType::Script, s.name));
+    }
+    links.push((ResourceType::Container, "shop-web".to_string()));
+    assert_eq!(links.len(), 20);
+    for (n, (resource_type, reference)) in links.into_iter().enumerate() {
+        db.link_project_resource(&link(n, resource_type, &reference))
+            .await
+            .unwrap();
+    }
+
+    // One lookup per link, as before
+    let before = STATEMENTS.load(Ordering::SeqCst);
+    let project = db.get_project("shop").await.unwrap().unwrap();
+    for link in db.get_project_resources(&project.id).await.unwrap() {
+        let _ = db.get_server(&link.resource_id).await.unwrap();
+    }
+    let per_link = STATEMENTS.load(Ordering::SeqCst) - before;
+    assert!(per_link >= 20, "per-link lookups ran {} queries", per_link);
+
+    let before = STATEMENTS.load(Ordering::SeqCst);
+    let bundle = db.resolve_project_bundle("shop").await.unwrap().unwrap();
+    let queries = STATEMENTS.load(Ordering::SeqCst) - before;
+    assert!(queries <= 6, "bundle ran {} queries", queries);
+
+    assert_eq!(bundle.links.len(), 20);
+    assert_eq!(bundle.servers.len(), 7);
+    assert_eq!(bundle.domains.len(), 5);
+    assert_eq!(bundle.databases.len(), 4);
+    assert_eq!(bundle.scripts.len(), 2);
+
+    // Links by ID and by name resolve alike
+    assert_eq!(bundle.server("srv-web0").unwrap().name, "web0");
+    assert_eq!(bundle.server("WEB1").unwrap().id, "srv-web1");
+    let dangling: Vec<&str> = bundle
+        .links
+        .iter()
+        .filter(|l| bundle.is_dangling(l))
+        .map(|l| l.resource_id.as_str())
+        .collect();
+    assert_eq!(dangling, ["srv-gone"]);
+
+    assert!(db.resolve_project_bundle("nope").await.unwrap().is_none());
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::project_clone::{CloneOptions, ClonePlan};
+use pctrl_core::{
+    Domain, DomainType, Project, ProjectResource, ProjectStatus, ResourceType, Script, ScriptType,
+};
+use pctrl_database::Database;
+
+fn domain(name: &str) -> Domain {
+    Domain {
+        id: name.replace('.', "-"),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+        superseded_by: None,
+    }
+}
+
+/// "shop" with a script and a linked domain
+async fn seed(db: &Database) {
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec!["rust".to_string()],
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_script(&Script {
+        id: "shop-backup".to_string(),
+        name: "shop-backup".to_string(),
+        description: None,
+        command: "pg_dump shop".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: None,
+        project_id: Some("shop".to_string()),
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    })
+    .await
+    .unwrap();
+    db.save_domain(&domain("shop.example.com")).await.unwrap();
+    db.link_project_resource(&ProjectResource {
+        id: "shop-domain".to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Domain,
+        resource_id: "shop.example.com".to_string(),
+        role: Some("production".to_string()),
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+async fn plan(db: &Database) -> ClonePlan {
+    let bundle = db.resolve_project_bundle("shop").await.unwrap().unwrap();
+    let options = CloneOptions {
+        include_scripts: true,
+        domain_suffix: Some("acme".to_string()),
+        ..CloneOptions::default()
+    };
+    ClonePlan::new(
+        &bundle,
+        &db.list_scripts_for_project("shop").await.unwrap(),
+        "acme",
+        &options,
+        &db.list_projects().await.unwrap(),
+        &db.list_scripts().await.unwrap(),
+        &db.list_domains().await.unwrap(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_clone_creates_everything_in_the_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    db.clone_project(&plan(&db).await).await.unwrap();
+
+    let bundle = db.resolve_project_bundle("acme").await.unwrap().unwrap();
+    assert_eq!(bundle.project.status, ProjectStatus::Dev);
+    assert_eq!(bundle.project.stack, vec!["rust"]);
+    assert_eq!(bundle.domains.len(), 1);
+    assert_eq!(bundle.domains[0].domain, "shop-acme.example.com");
+    let scripts = db.list_scripts_for_project("acme").await.unwrap();
+    assert_eq!(scripts.len(), 1);
+    assert_eq!(scripts[0].id, "acme-backup");
+    assert_eq!(scripts[0].command, "pg_dump shop");
+    // The source is untouched
+    assert_eq!(db.list_scripts_for_project("shop").await.unwrap().len(), 1);
+    assert_eq!(db.get_project_resources("shop").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_failed_clone_leaves_nothing_behind() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    // The domain appears between planning and applying
+    let plan = plan(&db).await;
+    db.save_domain(&domain("shop-acme.example.com"))
+        .await
+        .unwrap();
+    assert!(db.clone_project(&plan).await.is_err());
+
+    assert!(db.get_project("acme").await.unwrap().is_none());
+    assert!(db.get_script("acme-backup").await.unwrap().is_none());
+    assert!(db.get_project_resources("acme").await.unwrap().is_empty());
+}
@@ -0,0 +1,83 @@
+use chrono::Utc;
+use pctrl_core::monitor::MonitorState;
+use pctrl_core::prompt::BUDGET;
+use pctrl_database::prompt::{read_cached_status, CachedStatus};
+use pctrl_database::Database;
+use sqlx::sqlite::SqlitePool;
+use std::time::{Duration, Instant};
+
+/// Scheduling noise allowed on top of the budget
+const SLACK: Duration = Duration::from_millis(25);
+
+#[tokio::test]
+async fn reads_a_cold_database_within_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    db.seed_demo_data().await.unwrap();
+    let state = MonitorState {
+        last_cycle_at: Utc::now().to_rfc3339(),
+        servers_checked: 2,
+        servers_down: 1,
+        duration_ms: 40,
+        interval_secs: 300,
+        heartbeat_error: None,
+    };
+    db.record_monitor_cycle(&state).await.unwrap();
+    db.close().await;
+
+    let started = Instant::now();
+    let status = read_cached_status(&path, BUDGET).await;
+    assert!(
+        started.elapsed() < BUDGET + SLACK,
+        "took {:?}",
+        started.elapsed()
+    );
+    assert_eq!(status.servers, Some(2));
+    assert_eq!(status.monitor, Some(state));
+}
+
+#[tokio::test]
+async fn missing_database_is_unknown_and_not_created() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+
+    let status = read_cached_status(&path, BUDGET).await;
+    assert_eq!(status, CachedStatus::default());
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn locked_database_gives_up_within_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    Database::new(path.to_str().unwrap(), None)
+        .await
+        .unwrap()
+        .close()
+        .await;
+
+    // A writer holding an exclusive lock blocks every reader
+    let pool = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let mut writer = pool.acquire().await.unwrap();
+    for statement in [
+        "PRAGMA locking_mode = EXCLUSIVE",
+        "BEGIN EXCLUSIVE",
+        "DELETE FROM monitor_state",
+    ] {
+        sqlx::query(statement).execute(&mut *writer).await.unwrap();
+    }
+
+    let started = Instant::now();
+    let status = read_cached_status(&path, BUDGET).await;
+    assert!(
+        started.elapsed() < BUDGET + SLACK,
+        "took {:?}",
+        started.elapsed()
+    );
+    assert_eq!(status, CachedStatus::default());
+
+    sqlx::query("ROLLBACK").execute(&mut *writer).await.unwrap();
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::propagation::TrackedPropagation;
+
+fn tracked(domain: &str, expected: &[&str], started_at: &str) -> TrackedPropagation {
+    TrackedPropagation {
+        domain: domain.to_string(),
+        expected: expected.iter().map(|ip| ip.parse().unwrap()).collect(),
+        resolvers: vec![
+            "1.1.1.1".parse().unwrap(),
+            "2606:4700::1111".parse().unwrap(),
+        ],
+        started_at: started_at.to_string(),
+        expires_at: "2026-03-01T13:00:00Z".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_track_replace_and_untrack() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let shop = tracked(
+        "shop.example.com",
+        &["203.0.113.10"],
+        "2026-03-01T12:00:00Z",
+    );
+    let blog = tracked(
+        "blog.example.com",
+        &["203.0.113.20", "2001:db8::20"],
+        "2026-03-01T12:10:00Z",
+    );
+    db.track_propagation(&blog).await.unwrap();
+    db.track_propagation(&shop).await.unwrap();
+    assert_eq!(
+        db.list_tracked_propagations().await.unwrap(),
+        [shop.clone(), blog.clone()]
+    );
+
+    // Tracking a domain again replaces the earlier check
+    let moved_again = tracked(
+        "shop.example.com",
+        &["203.0.113.11"],
+        "2026-03-01T12:20:00Z",
+    );
+    db.track_propagation(&moved_again).await.unwrap();
+    assert_eq!(
+        db.list_tracked_propagations().await.unwrap(),
+        [blog.clone(), moved_again]
+    );
+
+    assert!(db.untrack_propagation("shop.example.com").await.unwrap());
+    assert!(!db.untrack_propagation("shop.example.com").await.unwrap());
+    assert_eq!(db.list_tracked_propagations().await.unwrap(), [blog]);
+}
@@ -0,0 +1,255 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType, Domain,
+    DomainType, EntityType, Project, ProjectResource, ProjectStatus, Reference, ResourceType,
+    Script, ScriptType, Server,
+};
+use pctrl_database::Database;
+use sqlx::sqlite::SqlitePool;
+
+fn server(id: &str, credential_id: Option<&str>) -> Server {
+    Server {
+        host: format!("{}.example.com", id),
+        credential_id: credential_id.map(String::from),
+        ..common::server(id)
+    }
+}
+
+fn domain(id: &str, name: &str, server_id: &str) -> Domain {
+    Domain {
+        id: id.to_string(),
+        domain: name.to_string(),
+        domain_type: DomainType::Production,
+        ssl: true,
+        ssl_expiry: None,
+        cloudflare_zone_id: None,
+        cloudflare_record_id: None,
+        server_id: Some(server_id.to_string()),
+        container_id: None,
+        notes: None,
+        superseded_by: None,
+    }
+}
+
+fn database(id: &str, server_id: &str) -> DatabaseCredentials {
+    DatabaseCredentials {
+        id: id.to_string(),
+        name: id.to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: None,
+        port: None,
+        database_name: None,
+        username: None,
+        password: None,
+        connection_string: None,
+        server_id: Some(server_id.to_string()),
+        container_id: None,
+        notes: None,
+    }
+}
+
+fn script(id: &str, server_id: &str, project_id: &str) -> Script {
+    Script {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        command: "uptime".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: Some(server_id.to_string()),
+        project_id: Some(project_id.to_string()),
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn link(id: &str, resource_type: ResourceType, resource_id: &str) -> ProjectResource {
+    ProjectResource {
+        id: id.to_string(),
+        project_id: "shop".to_string(),
+        resource_type,
+        resource_id: resource_id.to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    }
+}
+
+fn relations(references: &[Reference]) -> Vec<(&str, &str)> {
+    references
+        .iter()
+        .map(|r| (r.relation.as_str(), r.name.as_str()))
+        .collect()
+}
+
+/// A project, two servers sharing a credential and everything around them
+async fn seed(db: &Database, dir: &tempfile::TempDir) {
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "Shop".to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_credential(&Credential {
+        id: "cred-deploy".to_string(),
+        name: "deploy".to_string(),
+        credential_type: CredentialType::SshKey,
+        data: CredentialData::SshKey {
+            username: "root".to_string(),
+            port: 22,
+            key_path: "/keys/id".to_string(),
+            passphrase: None,
+        },
+        notes: None,
+    })
+    .await
+    .unwrap();
+
+    db.save_server(&server("web", Some("cred-deploy")))
+        .await
+        .unwrap();
+    db.save_server(&server("old", Some("cred-deploy")))
+        .await
+        .unwrap();
+    db.trash_server("old").await.unwrap();
+    db.save_server(&server("spare", None)).await.unwrap();
+
+    db.save_domain(&domain("shop-com", "shop.com", "web"))
+        .await
+        .unwrap();
+    db.save_database_credentials(&database("shopdb", "web"))
+        .await
+        .unwrap();
+    db.save_script(&script("deploy-web", "web", "shop"))
+        .await
+        .unwrap();
+
+    // Links hold whatever was typed: an ID or a name
+    db.link_project_resource(&link("l1", ResourceType::Server, "web"))
+        .await
+        .unwrap();
+    db.link_project_resource(&link("l2", ResourceType::Domain, "shop.com"))
+        .await
+        .unwrap();
+    db.link_project_resource(&link("l3", ResourceType::Database, "shopdb"))
+        .await
+        .unwrap();
+    db.link_project_resource(&link("l4", ResourceType::Script, "deploy-web"))
+        .await
+        .unwrap();
+
+    // Containers have no CRUD yet; insert one directly
+    let pool = SqlitePool::connect(&format!("sqlite:{}", dir.path().join("pctrl.db").display()))
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO containers (id, name, server_id, project_id) VALUES (?, ?, ?, ?)")
+        .bind("c1")
+        .bind("shop-app")
+        .bind("web")
+        .bind("shop")
+        .execute(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_reverse_references_find_every_relationship_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db, &dir).await;
+
+    let server = db
+        .reverse_references(EntityType::Server, "web")
+        .await
+        .unwrap();
+    assert_eq!(
+        relations(&server),
+        [
+            ("project links", "Shop"),
+            ("domains", "shop.com"),
+            ("databases", "shopdb"),
+            ("containers", "shop-app"),
+            ("scripts", "deploy-web"),
+        ]
+    );
+    assert_eq!(server[0].id, "shop");
+
+    let credential = db
+        .reverse_references(EntityType::Credential, "cred-deploy")
+        .await
+        .unwrap();
+    assert_eq!(
+        relations(&credential),
+        [("servers", "old (trashed)"), ("servers", "web")]
+    );
+
+    // Linked by name, found by ID
+    let domain = db
+        .reverse_references(EntityType::Domain, "shop-com")
+        .await
+        .unwrap();
+    assert_eq!(relations(&domain), [("project links", "Shop")]);
+
+    let database = db
+        .reverse_references(EntityType::Database, "shopdb")
+        .await
+        .unwrap();
+    assert_eq!(relations(&database), [("project links", "Shop")]);
+
+    let script = db
+        .reverse_references(EntityType::Script, "deploy-web")
+        .await
+        .unwrap();
+    assert_eq!(relations(&script), [("project links", "Shop")]);
+
+    let project = db
+        .reverse_references(EntityType::Project, "shop")
+        .await
+        .unwrap();
+    assert_eq!(
+        relations(&project),
+        [("containers", "shop-app"), ("scripts", "deploy-web")]
+    );
+}
+
+#[tokio::test]
+async fn test_unreferenced_entities_have_no_references() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db, &dir).await;
+
+    assert!(db
+        .reverse_references(EntityType::Server, "spare")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .reverse_references(EntityType::Server, "missing")
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Unlinking removes the reference
+    db.unlink_project_resource("l3").await.unwrap();
+    assert!(db
+        .reverse_references(EntityType::Database, "shopdb")
+        .await
+        .unwrap()
+        .is_empty());
+}
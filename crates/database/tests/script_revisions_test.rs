@@ -0,0 +1,241 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::settings::SCRIPT_APPROVAL;
+use pctrl_core::{current_uid, RevisionStatus, Script, ScriptType, ScriptUpdate};
+use pctrl_database::Database;
+
+async fn add_script(db: &Database, id: &str, command: &str, dangerous: bool) {
+    db.save_script(&Script {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: None,
+        command: command.to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    })
+    .await
+    .unwrap();
+}
+
+async fn command_of(db: &Database, id: &str) -> String {
+    db.get_script(id).await.unwrap().unwrap().command
+}
+
+fn pending_id(update: ScriptUpdate) -> i64 {
+    match update {
+        ScriptUpdate::Pending(revision) => revision.id,
+        other => panic!("expected a pending revision, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_edits_apply_directly_when_approvals_are_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    add_script(&db, "restart-prod", "systemctl restart app", true).await;
+
+    let update = db
+        .update_script_command("restart-prod", "systemctl reload app", true, "alice@box")
+        .await
+        .unwrap();
+    assert!(matches!(update, ScriptUpdate::Applied(_)));
+    assert_eq!(
+        command_of(&db, "restart-prod").await,
+        "systemctl reload app"
+    );
+
+    let update = db
+        .update_script_command("restart-prod", "systemctl reload app", true, "alice@box")
+        .await
+        .unwrap();
+    assert!(matches!(update, ScriptUpdate::Unchanged));
+    assert!(db.list_script_revisions(None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_dangerous_edits_wait_for_approval() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.set_setting(SCRIPT_APPROVAL, "on").await.unwrap();
+    add_script(&db, "restart-prod", "systemctl restart app", true).await;
+    add_script(&db, "uptime", "uptime", false).await;
+
+    // Safe scripts, and marking a script dangerous, never need approval
+    let update = db
+        .update_script_command("uptime", "uptime -p", false, "alice@box")
+        .await
+        .unwrap();
+    assert!(matches!(update, ScriptUpdate::Applied(_)));
+    let update = db
+        .update_script_command("uptime", "uptime -p", true, "alice@box")
+        .await
+        .unwrap();
+    assert!(matches!(update, ScriptUpdate::Applied(ref s) if s.dangerous));
+
+    let id = pending_id(
+        db.update_script_command("restart-prod", "rm -rf /srv/app", true, "mallory@box")
+            .await
+            .unwrap(),
+    );
+    let flag_off = pending_id(
+        db.update_script_command(
+            "restart-prod",
+            "systemctl restart app",
+            false,
+            "mallory@box",
+        )
+        .await
+        .unwrap(),
+    );
+
+    // What a run would execute is still the approved version
+    let script = db.get_script("restart-prod").await.unwrap().unwrap();
+    assert_eq!(script.command, "systemctl restart app");
+    assert!(script.dangerous);
+
+    let pending = db
+        .list_script_revisions(Some(RevisionStatus::Pending))
+        .await
+        .unwrap();
+    assert_eq!(
+        pending.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![id, flag_off]
+    );
+    let changes = pending[0].changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, "command");
+    assert_eq!(changes[0].new, "rm -rf /srv/app");
+
+    // Rejected: discarded, the script is untouched
+    let rejected = db.reject_script_revision(id, "alice@box").await.unwrap();
+    assert_eq!(rejected.command, "rm -rf /srv/app");
+    assert_eq!(
+        command_of(&db, "restart-prod").await,
+        "systemctl restart app"
+    );
+    assert!(db.approve_script_revision(id, "alice@box").await.is_err());
+
+    // Approved: applied and recorded
+    let script = db
+        .approve_script_revision(flag_off, "alice@box")
+        .await
+        .unwrap();
+    assert!(!script.dangerous);
+    assert!(
+        !db.get_script("restart-prod")
+            .await
+            .unwrap()
+            .unwrap()
+            .dangerous
+    );
+
+    let decided = db.get_script_revision(flag_off).await.unwrap().unwrap();
+    assert_eq!(decided.status, RevisionStatus::Approved);
+    assert_eq!(decided.decided_by.as_deref(), Some("alice@box"));
+    assert!(decided.decided_at.is_some());
+    assert!(db.reject_script_revision(flag_off, "x").await.is_err());
+    assert!(db
+        .list_script_revisions(Some(RevisionStatus::Pending))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_two_person_approval() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.set_setting(SCRIPT_APPROVAL, "two-person").await.unwrap();
+    add_script(&db, "deploy", "./deploy.sh", true).await;
+
+    let id = pending_id(
+        db.update_script_command("deploy", "./deploy.sh --force", true, "alice@laptop")
+            .await
+            .unwrap(),
+    );
+
+    // Same OS user on another host is still the same person
+    assert!(db
+        .approve_script_revision(id, "alice@ops-box")
+        .await
+        .is_err());
+    assert_eq!(command_of(&db, "deploy").await, "./deploy.sh");
+
+    // Another $USER in the same account is still the same person
+    if current_uid().is_some() {
+        assert!(db.approve_script_revision(id, "bob@ops-box").await.is_err());
+    }
+
+    // As if alice had proposed it from her own account
+    let pool =
+        sqlx::SqlitePool::connect(&format!("sqlite:{}", dir.path().join("pctrl.db").display()))
+            .await
+            .unwrap();
+    sqlx::query("UPDATE script_revisions SET author_uid = author_uid + 1 WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    db.approve_script_revision(id, "bob@ops-box").await.unwrap();
+    assert_eq!(command_of(&db, "deploy").await, "./deploy.sh --force");
+}
+
+#[tokio::test]
+async fn test_stale_revisions_are_not_applied() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.set_setting(SCRIPT_APPROVAL, "on").await.unwrap();
+    add_script(&db, "deploy", "./deploy.sh", true).await;
+
+    let first = pending_id(
+        db.update_script_command("deploy", "./deploy.sh v1", true, "alice@box")
+            .await
+            .unwrap(),
+    );
+    let second = pending_id(
+        db.update_script_command("deploy", "./deploy.sh v2", true, "bob@box")
+            .await
+            .unwrap(),
+    );
+
+    db.approve_script_revision(second, "carol@box")
+        .await
+        .unwrap();
+    // `first` was proposed against the old command
+    assert!(db
+        .approve_script_revision(first, "carol@box")
+        .await
+        .is_err());
+    assert_eq!(command_of(&db, "deploy").await, "./deploy.sh v2");
+}
+
+#[tokio::test]
+async fn test_removing_a_script_drops_its_pending_revisions() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.set_setting(SCRIPT_APPROVAL, "on").await.unwrap();
+    add_script(&db, "deploy", "./deploy.sh", true).await;
+
+    let id = pending_id(
+        db.update_script_command("deploy", "./other.sh", true, "alice@box")
+            .await
+            .unwrap(),
+    );
+    db.remove_script("deploy").await.unwrap();
+
+    assert!(db.get_script_revision(id).await.unwrap().is_none());
+    assert!(db.approve_script_revision(id, "bob@box").await.is_err());
+}
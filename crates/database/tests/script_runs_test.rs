@@ -0,0 +1,84 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{ScriptRun, SCRIPT_RUN_OUTPUT_LIMIT};
+use pctrl_database::Database;
+
+async fn run(db: &Database, script_id: &str, exit_code: Option<i32>, output: &str) -> ScriptRun {
+    db.add_script_run(
+        script_id,
+        "2026-04-01T10:00:00Z",
+        "2026-04-01T10:00:05Z",
+        exit_code,
+        Some(output),
+        "alice@laptop",
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_runs_are_listed_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let first = run(&db, "deploy", Some(0), "ok\n").await;
+    let second = run(&db, "deploy", Some(2), "failed\n").await;
+    run(&db, "backup", None, "").await;
+
+    let runs = db.list_script_runs("deploy", 20).await.unwrap();
+    assert_eq!(runs, vec![second.clone(), first.clone()]);
+    assert!(first.succeeded() && !second.succeeded());
+    assert_eq!(
+        db.list_script_runs("deploy", 1).await.unwrap(),
+        vec![second]
+    );
+    assert_eq!(db.get_script_run(first.id).await.unwrap(), Some(first));
+    assert!(db.get_script_run(99).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_prune_keeps_latest_runs_per_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    for i in 0..5 {
+        run(&db, "deploy", Some(0), &format!("run {}", i)).await;
+    }
+    run(&db, "backup", Some(0), "only run").await;
+
+    assert_eq!(db.prune_script_runs(2).await.unwrap(), 3);
+    let outputs: Vec<String> = db
+        .list_script_runs("deploy", 20)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|r| r.output)
+        .collect();
+    assert_eq!(outputs, ["run 4", "run 3"]);
+    assert_eq!(db.list_script_runs("backup", 20).await.unwrap().len(), 1);
+    assert_eq!(db.script_run_history_limit().await.unwrap(), 100);
+}
+
+#[tokio::test]
+async fn test_large_output_is_truncated() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    // A multi-byte character straddles the limit
+    let output = format!(
+        "{}é{}",
+        "x".repeat(SCRIPT_RUN_OUTPUT_LIMIT - 1),
+        "y".repeat(999)
+    );
+    let stored = run(&db, "noisy", Some(0), &output).await;
+    let loaded = db.get_script_run(stored.id).await.unwrap().unwrap();
+    let text = loaded.output.unwrap();
+
+    assert!(text.starts_with(&"x".repeat(SCRIPT_RUN_OUTPUT_LIMIT - 1)));
+    assert!(
+        text.ends_with("\n...[truncated: 1001 more bytes not kept]"),
+        "{}",
+        &text[text.len() - 60..]
+    );
+    assert!(text.len() < SCRIPT_RUN_OUTPUT_LIMIT + 64);
+}
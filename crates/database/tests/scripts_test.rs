@@ -0,0 +1,160 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{Script, ScriptResult, ScriptRunContext, ScriptType};
+
+#[tokio::test]
+async fn test_multiline_command_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let command = "set -e\nDB_URL=\"postgres://app@db/app\"\ncat <<'EOF' | psql \"$DB_URL\"\nSELECT 'ünïcødé ✓', \"col\";\nEOF\necho '$HOME stays literal'\n\ttabbed\\\n  continued";
+    let script = Script {
+        id: "migrate".to_string(),
+        name: "migrate".to_string(),
+        description: None,
+        command: command.to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    };
+    db.save_script(&script).await.unwrap();
+
+    let loaded = db.get_script("migrate").await.unwrap().unwrap();
+    assert_eq!(loaded.command, command);
+}
+
+#[tokio::test]
+async fn test_environment_and_run_context() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let script = Script {
+        id: "build".to_string(),
+        name: "build".to_string(),
+        description: None,
+        command: "make".to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: Some("~/proj".to_string()),
+        env: [("API_TOKEN".to_string(), "t0k".to_string())].into(),
+        run_window: Some("Mon-Thu 08:00-18:00".to_string()),
+    };
+    db.save_script(&script).await.unwrap();
+
+    let loaded = db.get_script("build").await.unwrap().unwrap();
+    assert_eq!(loaded.working_dir.as_deref(), Some("~/proj"));
+    assert_eq!(loaded.env, script.env);
+    assert_eq!(loaded.run_window.as_deref(), Some("Mon-Thu 08:00-18:00"));
+    assert!(db.get_script_run_context("build").await.unwrap().is_none());
+
+    let context = ScriptRunContext {
+        cwd: "/home/me/proj".to_string(),
+        env_names: vec!["API_TOKEN".to_string(), "HOME".to_string()],
+    };
+    db.update_script_result(
+        "build",
+        ScriptResult::Success,
+        Some(0),
+        None,
+        Some(&context),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        db.get_script_run_context("build").await.unwrap(),
+        Some(context.clone())
+    );
+
+    // Editing the script keeps where it last ran
+    let edited = Script {
+        description: Some("Build everything".to_string()),
+        ..script
+    };
+    db.save_script(&edited).await.unwrap();
+    assert_eq!(
+        db.get_script_run_context("build").await.unwrap(),
+        Some(context)
+    );
+}
+
+fn named(name: &str) -> Script {
+    Script {
+        id: name.replace('/', "-"),
+        name: name.to_string(),
+        description: None,
+        command: "true".to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_list_scripts_in_namespace() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    for name in [
+        "backups/postgres-daily",
+        "Backups/db/redis",
+        "backups_old/mysql",
+        "deploy/frontend",
+        "backups",
+    ] {
+        db.create_script(&named(name)).await.unwrap();
+    }
+    let names =
+        |scripts: Vec<Script>| -> Vec<String> { scripts.into_iter().map(|s| s.name).collect() };
+
+    assert_eq!(
+        names(db.list_scripts_in_namespace("backups/").await.unwrap()),
+        vec!["Backups/db/redis", "backups/postgres-daily"]
+    );
+    assert_eq!(
+        names(db.list_scripts_in_namespace("backups/db").await.unwrap()),
+        vec!["Backups/db/redis"]
+    );
+    assert_eq!(db.list_scripts_in_namespace("/").await.unwrap().len(), 5);
+
+    // Renaming moves the script to its new namespace
+    let mut moved = db.get_script("deploy-frontend").await.unwrap().unwrap();
+    moved.name = "backups/frontend".to_string();
+    db.update_script(&moved).await.unwrap();
+    assert_eq!(
+        db.list_scripts_in_namespace("backups").await.unwrap().len(),
+        3
+    );
+    assert!(db
+        .list_scripts_in_namespace("deploy")
+        .await
+        .unwrap()
+        .is_empty());
+}
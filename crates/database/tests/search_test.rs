@@ -0,0 +1,177 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::search::{Entity, MatchKind};
+use pctrl_core::{
+    DatabaseCredentials, DatabaseType, EntityType, Project, ProjectResource, ProjectStatus,
+    ResourceType, Script, ScriptType, Server,
+};
+use pctrl_database::Database;
+
+fn server(id: &str, name: &str) -> Server {
+    Server {
+        name: name.to_string(),
+        host: format!("{}.example.com", name),
+        ..common::server(id)
+    }
+}
+
+async fn seed(db: &Database) {
+    db.save_project(&Project {
+        id: "p-shop".to_string(),
+        name: "shop".to_string(),
+        description: Some("Online store".to_string()),
+        stack: Vec::new(),
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_server(&server("s-web", "shop-web")).await.unwrap();
+    db.save_server(&server("s-ci", "ci")).await.unwrap();
+    // Linked by name rather than ID
+    db.link_project_resource(&ProjectResource {
+        id: "l-1".to_string(),
+        project_id: "p-shop".to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: "shop-web".to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+    db.save_script(&Script {
+        id: "sc-deploy".to_string(),
+        name: "deploy".to_string(),
+        description: Some("Ship the shop".to_string()),
+        command: "deploy --token=s3cret".to_string(),
+        script_type: ScriptType::Local,
+        server_id: None,
+        project_id: Some("p-shop".to_string()),
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    })
+    .await
+    .unwrap();
+    db.save_database_credentials(&DatabaseCredentials {
+        id: "d-shop".to_string(),
+        name: "shop-db".to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: Some("db.internal".to_string()),
+        port: None,
+        database_name: None,
+        username: Some("app".to_string()),
+        password: Some("hunter2".to_string()),
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_global_search_ranks_and_lists_projects() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let hits = db.global_search("shop", 10).await.unwrap();
+    let found: Vec<(EntityType, &str, MatchKind)> = hits
+        .iter()
+        .map(|h| (h.entity_type, h.name.as_str(), h.matched))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (EntityType::Project, "shop", MatchKind::Exact),
+            (EntityType::Server, "shop-web", MatchKind::Prefix),
+            (EntityType::Database, "shop-db", MatchKind::Prefix),
+            (EntityType::Script, "deploy", MatchKind::Detail),
+        ]
+    );
+    assert_eq!(hits[1].projects, vec!["shop"]);
+    assert_eq!(hits[1].detail.as_deref(), Some("shop-web.example.com"));
+    // From the script's own project
+    assert_eq!(hits[3].projects, vec!["shop"]);
+
+    // Secrets in commands don't match
+    assert!(db.global_search("s3cret", 10).await.unwrap().is_empty());
+    assert_eq!(db.global_search("shop", 2).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_search_reports_the_matched_field() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let fields = |hits: Vec<pctrl_core::search::SearchHit>| -> Vec<(String, String)> {
+        hits.into_iter().map(|h| (h.name, h.field)).collect()
+    };
+    assert_eq!(
+        fields(db.search("SHOP").await.unwrap()),
+        vec![
+            ("shop".to_string(), "name".to_string()),
+            ("shop-web".to_string(), "name".to_string()),
+            ("shop-db".to_string(), "name".to_string()),
+            ("deploy".to_string(), "description".to_string()),
+        ]
+    );
+    assert_eq!(
+        fields(db.search("ci.example").await.unwrap()),
+        vec![("ci".to_string(), "host".to_string())]
+    );
+    assert_eq!(
+        fields(db.search("db.internal").await.unwrap()),
+        vec![("shop-db".to_string(), "host".to_string())]
+    );
+    assert_eq!(
+        fields(db.search("--token").await.unwrap()),
+        vec![("deploy".to_string(), "command".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_get_entity_by_id_or_name_is_redacted() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let Some(Entity::Database(creds)) = db
+        .get_entity(EntityType::Database, "shop-db")
+        .await
+        .unwrap()
+    else {
+        panic!("database not found by name");
+    };
+    assert_eq!(creds.id, "d-shop");
+    assert_eq!(creds.password.as_deref(), Some("****"));
+
+    let Some(Entity::Script(script)) = db
+        .get_entity(EntityType::Script, "sc-deploy")
+        .await
+        .unwrap()
+    else {
+        panic!("script not found by ID");
+    };
+    assert_eq!(script.command, "deploy --token=****");
+
+    assert!(db
+        .get_entity(EntityType::Server, "missing")
+        .await
+        .unwrap()
+        .is_none());
+}
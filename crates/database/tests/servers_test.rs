@@ -0,0 +1,43 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{EntityType, Server};
+
+fn server(id: &str) -> Server {
+    Server {
+        host: "10.8.0.10".to_string(),
+        requires_vpn: Some("wg0".to_string()),
+        ..common::server(id)
+    }
+}
+
+#[tokio::test]
+async fn test_requires_vpn_is_stored_and_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let mut web = server("web-1");
+    db.save_server(&web).await.unwrap();
+
+    let stored = db.get_server_by_name("WEB-1").await.unwrap().unwrap();
+    assert_eq!(stored.requires_vpn.as_deref(), Some("wg0"));
+    assert_eq!(
+        db.list_servers().await.unwrap()[0].requires_vpn.as_deref(),
+        Some("wg0")
+    );
+
+    web.requires_vpn = None;
+    db.save_server(&web).await.unwrap();
+    assert_eq!(
+        db.get_server("web-1").await.unwrap().unwrap().requires_vpn,
+        None
+    );
+
+    let audit = db
+        .list_audit_entries(Some((EntityType::Server, "web-1")), 1)
+        .await
+        .unwrap();
+    let changes = audit[0].changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, "requires_vpn");
+    assert_eq!(changes[0].old, "wg0");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::Service;
+
+fn service(id: &str, name: &str, project_id: Option<&str>) -> Service {
+    Service {
+        id: id.to_string(),
+        name: name.to_string(),
+        server_id: "web-1".to_string(),
+        unit: format!("{}.service", name),
+        project_id: project_id.map(String::from),
+        last_state: None,
+        last_checked: None,
+    }
+}
+
+#[tokio::test]
+async fn test_service_lookup_and_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_service(&service("svc-1", "api", Some("shop")))
+        .await
+        .unwrap();
+    db.save_service(&service("svc-2", "worker", None))
+        .await
+        .unwrap();
+
+    // By ID or by name, ignoring case
+    assert_eq!(db.get_service("svc-1").await.unwrap().unwrap().name, "api");
+    assert_eq!(db.get_service("WORKER").await.unwrap().unwrap().id, "svc-2");
+    assert!(db.get_service("nope").await.unwrap().is_none());
+
+    let for_project = db.list_services_for_project("shop").await.unwrap();
+    assert_eq!(for_project.len(), 1);
+    assert_eq!(for_project[0].unit, "api.service");
+
+    db.record_service_state("svc-1", "active (running)")
+        .await
+        .unwrap();
+    let api = db.get_service("api").await.unwrap().unwrap();
+    assert_eq!(api.last_state.as_deref(), Some("active (running)"));
+    assert!(api.last_checked.is_some());
+
+    assert!(db.remove_service("svc-2").await.unwrap());
+    assert!(!db.remove_service("svc-2").await.unwrap());
+    let names: Vec<String> = db
+        .list_services()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["api"]);
+}
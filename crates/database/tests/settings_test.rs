@@ -0,0 +1,77 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::settings::{TUI_ACCENT, TUI_THEME};
+
+#[tokio::test]
+async fn test_settings_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert_eq!(db.get_setting(TUI_THEME).await.unwrap(), None);
+    assert!(db.list_settings().await.unwrap().is_empty());
+
+    db.set_setting(TUI_THEME, "light").await.unwrap();
+    db.set_setting(TUI_ACCENT, "#7aa2f7").await.unwrap();
+    db.set_setting(TUI_THEME, "dark").await.unwrap();
+
+    assert_eq!(
+        db.get_setting(TUI_THEME).await.unwrap().as_deref(),
+        Some("dark")
+    );
+    assert_eq!(
+        db.list_settings().await.unwrap(),
+        vec![
+            (TUI_ACCENT.to_string(), "#7aa2f7".to_string()),
+            (TUI_THEME.to_string(), "dark".to_string()),
+        ]
+    );
+
+    assert!(db.unset_setting(TUI_ACCENT).await.unwrap());
+    assert!(!db.unset_setting(TUI_ACCENT).await.unwrap());
+    assert_eq!(db.get_setting(TUI_ACCENT).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_invalid_settings_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert!(matches!(
+        db.set_setting("tui_colour", "blue").await,
+        Err(pctrl_core::Error::Config(_))
+    ));
+    assert!(matches!(
+        db.set_setting(TUI_THEME, "solarized").await,
+        Err(pctrl_core::Error::Config(_))
+    ));
+    assert!(matches!(
+        db.set_setting(TUI_ACCENT, "#12").await,
+        Err(pctrl_core::Error::Config(_))
+    ));
+    assert!(db.list_settings().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_every_write_bumps_the_generation() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    assert_eq!(db.settings_generation().await.unwrap(), 0);
+
+    db.set_setting(TUI_THEME, "light").await.unwrap();
+    assert_eq!(db.settings_generation().await.unwrap(), 1);
+    db.set_setting(TUI_THEME, "light").await.unwrap();
+    assert_eq!(db.settings_generation().await.unwrap(), 2);
+    assert!(db.unset_setting(TUI_THEME).await.unwrap());
+    assert_eq!(db.settings_generation().await.unwrap(), 3);
+
+    // Writes that change nothing or fail don't count
+    assert!(!db.unset_setting(TUI_THEME).await.unwrap());
+    assert!(db.set_setting(TUI_THEME, "solarized").await.is_err());
+    assert_eq!(db.settings_generation().await.unwrap(), 3);
+
+    // Another connection to the same file sees the same counter
+    let other = open_db(&dir).await;
+    other.set_setting(TUI_ACCENT, "magenta").await.unwrap();
+    assert_eq!(db.settings_generation().await.unwrap(), 4);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::ship::{DeployConfig, ShipPhase, ShipRun};
+use pctrl_core::{Project, ProjectStatus};
+use pctrl_database::Database;
+
+async fn save_project(db: &Database) {
+    db.save_project(&Project {
+        id: "acme".to_string(),
+        name: "acme".to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Live,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+}
+
+fn run(ran_at: &str, failed_phase: Option<ShipPhase>) -> ShipRun {
+    ShipRun {
+        project_id: "acme".to_string(),
+        server_id: "web-1".to_string(),
+        branch: "main".to_string(),
+        old_commit: Some("1111111".to_string()),
+        new_commit: failed_phase.is_none().then(|| "2222222".to_string()),
+        success: failed_phase.is_none(),
+        failed_phase,
+        error: failed_phase.map(|_| "exit code 1: boom".to_string()),
+        output: failed_phase.map(|_| "web-1 | boom".to_string()),
+        duration_ms: 4_200,
+        ran_at: ran_at.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_deploy_config_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    save_project(&db).await;
+
+    assert_eq!(
+        db.get_deploy_config("acme").await.unwrap(),
+        DeployConfig::default()
+    );
+
+    let config = DeployConfig {
+        path: Some("/srv/acme".to_string()),
+        branch: Some("release".to_string()),
+        server_id: None,
+    };
+    db.save_deploy_config("acme", &config).await.unwrap();
+    assert_eq!(db.get_deploy_config("acme").await.unwrap(), config);
+
+    let cleared = DeployConfig {
+        path: None,
+        ..config
+    };
+    db.save_deploy_config("acme", &cleared).await.unwrap();
+    assert_eq!(db.get_deploy_config("acme").await.unwrap(), cleared);
+}
+
+#[tokio::test]
+async fn test_ship_runs_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    save_project(&db).await;
+
+    db.record_ship_run(&run("2026-01-01T10:00:00Z", None))
+        .await
+        .unwrap();
+    db.record_ship_run(&run("2026-01-02T10:00:00Z", Some(ShipPhase::Build)))
+        .await
+        .unwrap();
+
+    let runs = db.list_ship_runs("acme", 10).await.unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0], run("2026-01-02T10:00:00Z", Some(ShipPhase::Build)));
+    assert_eq!(runs[1], run("2026-01-01T10:00:00Z", None));
+
+    assert_eq!(db.list_ship_runs("acme", 1).await.unwrap().len(), 1);
+    assert!(db.list_ship_runs("other", 10).await.unwrap().is_empty());
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::short_ref::{self, EntityRef};
+use pctrl_core::{EntityType, Project, ProjectStatus, Script, ScriptType, Server};
+use sqlx::sqlite::SqlitePool;
+
+/// Change the file behind the database's back
+async fn raw(dir: &tempfile::TempDir, statements: &[&str]) {
+    let pool = SqlitePool::connect(&format!("sqlite:{}", dir.path().join("pctrl.db").display()))
+        .await
+        .unwrap();
+    for statement in statements {
+        sqlx::query(statement).execute(&pool).await.unwrap();
+    }
+    pool.close().await;
+}
+
+fn project(id: &str, name: &str) -> Project {
+    Project {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+fn server(id: &str, name: &str) -> Server {
+    Server {
+        name: name.to_string(),
+        host: "203.0.113.5".to_string(),
+        ..common::server(id)
+    }
+}
+
+fn script(id: &str, name: &str) -> Script {
+    Script {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: None,
+        command: "uptime".to_string(),
+        script_type: ScriptType::Ssh,
+        server_id: None,
+        project_id: None,
+        docker_host_id: None,
+        container_id: None,
+        dangerous: false,
+        last_run: None,
+        last_result: None,
+        exit_code: None,
+        last_output: None,
+        working_dir: None,
+        run_window: None,
+        env: Default::default(),
+    }
+}
+
+fn ids(found: &[EntityRef]) -> Vec<(EntityType, &str)> {
+    found
+        .iter()
+        .map(|e| (e.entity_type, e.id.as_str()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_refs_are_assigned_on_save_and_kept_on_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&project("web", "Web")).await.unwrap();
+    db.save_server(&server("web", "web")).await.unwrap();
+
+    let project_ref = db.short_ref(EntityType::Project, "web").await.unwrap();
+    let server_ref = db.short_ref(EntityType::Server, "web").await.unwrap();
+    assert_eq!(
+        project_ref.as_deref(),
+        Some(short_ref::generate(EntityType::Project, "web", 0).as_str())
+    );
+    assert_eq!(
+        server_ref.as_deref().and_then(short_ref::parse),
+        Some(EntityType::Server)
+    );
+
+    // Saving replaces the row; the ref carries over
+    let mut renamed = server("web", "web-1");
+    renamed.host = "203.0.113.6".to_string();
+    db.save_server(&renamed).await.unwrap();
+    assert_eq!(
+        db.short_ref(EntityType::Server, "web").await.unwrap(),
+        server_ref
+    );
+    let refs = db.short_refs(EntityType::Server).await.unwrap();
+    assert_eq!(refs.get("web"), server_ref.as_ref());
+}
+
+#[tokio::test]
+async fn test_backfill_is_idempotent_and_collision_free() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.seed_demo_data().await.unwrap();
+    let before = db.short_refs(EntityType::Server).await.unwrap();
+    assert_eq!(before.len(), 2);
+    assert_eq!(db.backfill_short_refs().await.unwrap(), 0);
+    db.close().await;
+
+    // A database from before short refs: column, index and refs missing
+    raw(
+        &dir,
+        &[
+            "DROP INDEX idx_servers_short_ref",
+            "ALTER TABLE servers DROP COLUMN short_ref",
+            "UPDATE metadata SET value = '11' WHERE key = 'schema_version'",
+        ],
+    )
+    .await;
+    let db = open_db(&dir).await;
+    assert_eq!(db.short_refs(EntityType::Server).await.unwrap(), before);
+    assert_eq!(db.backfill_short_refs().await.unwrap(), 0);
+    db.close().await;
+
+    // Another entity already holds a script's first candidate
+    let taken = short_ref::generate(EntityType::Script, "backup", 0);
+    raw(
+        &dir,
+        &[
+            "INSERT INTO scripts (id, name, command) VALUES ('backup', 'backup', 'true')",
+            &format!(
+                "INSERT INTO scripts (id, name, command, short_ref) VALUES ('other', 'other', 'true', '{}')",
+                taken
+            ),
+        ],
+    )
+    .await;
+    let db = open_db(&dir).await;
+    let assigned = db
+        .short_ref(EntityType::Script, "backup")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        assigned,
+        short_ref::generate(EntityType::Script, "backup", 1)
+    );
+    assert_eq!(db.backfill_short_refs().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_resolver_precedence_ref_then_id_then_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_project(&project("web", "Storefront"))
+        .await
+        .unwrap();
+    db.save_server(&server("6f1c2b9e", "web")).await.unwrap();
+    let server_ref = db
+        .short_ref(EntityType::Server, "6f1c2b9e")
+        .await
+        .unwrap()
+        .unwrap();
+    // A project whose ID looks like the server's ref
+    db.save_project(&project(&server_ref, "odd")).await.unwrap();
+
+    // A ref beats an ID, in any case
+    let found = db
+        .find_entities(None, &server_ref.to_uppercase())
+        .await
+        .unwrap();
+    assert_eq!(ids(&found), vec![(EntityType::Server, "6f1c2b9e")]);
+    assert_eq!(found[0].short_ref, server_ref);
+    // Unless the ref is of another type than asked for
+    let found = db
+        .find_entities(Some(EntityType::Project), &server_ref)
+        .await
+        .unwrap();
+    assert_eq!(
+        ids(&found),
+        vec![(EntityType::Project, server_ref.as_str())]
+    );
+
+    // An ID beats a name
+    let found = db.find_entities(None, "web").await.unwrap();
+    assert_eq!(ids(&found), vec![(EntityType::Project, "web")]);
+    let found = db
+        .find_entities(Some(EntityType::Server), "WEB")
+        .await
+        .unwrap();
+    assert_eq!(ids(&found), vec![(EntityType::Server, "6f1c2b9e")]);
+
+    // Trashed servers don't resolve
+    db.trash_server("6f1c2b9e").await.unwrap();
+    assert!(db
+        .find_entities(Some(EntityType::Server), &server_ref)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_ambiguous_inputs_return_every_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    // The same slug as ID of two types
+    db.save_project(&project("cache", "Cache")).await.unwrap();
+    db.save_script(&script("cache", "flush cache"))
+        .await
+        .unwrap();
+    // The same name for two types (names are unique within a type)
+    db.save_project(&project("p-db", "DB")).await.unwrap();
+    db.save_server(&server("a1", "db")).await.unwrap();
+
+    let found = db.find_entities(None, "cache").await.unwrap();
+    assert_eq!(
+        ids(&found),
+        vec![
+            (EntityType::Project, "cache"),
+            (EntityType::Script, "cache")
+        ]
+    );
+    assert_ne!(found[0].short_ref, found[1].short_ref);
+    // A type or a ref makes them unique
+    let found = db
+        .find_entities(Some(EntityType::Script), "cache")
+        .await
+        .unwrap();
+    assert_eq!(ids(&found), vec![(EntityType::Script, "cache")]);
+
+    let found = db.find_entities(None, "db").await.unwrap();
+    assert_eq!(
+        ids(&found),
+        vec![(EntityType::Project, "p-db"), (EntityType::Server, "a1")]
+    );
+    let found = db.find_entities(None, &found[1].short_ref).await.unwrap();
+    assert_eq!(ids(&found), vec![(EntityType::Server, "a1")]);
+
+    assert!(db.find_entities(None, "nope").await.unwrap().is_empty());
+}
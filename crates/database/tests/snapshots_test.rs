@@ -0,0 +1,132 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::snapshot::{diff_inventories, ChangeKind};
+use pctrl_core::{
+    Credential, CredentialData, CredentialType, DatabaseCredentials, DatabaseType, Project,
+    ProjectResource, ProjectStatus, ResourceType, Server, ServerType,
+};
+use pctrl_database::Database;
+
+async fn seed(db: &Database) {
+    db.save_project(&Project {
+        id: "shop".to_string(),
+        name: "shop".to_string(),
+        description: None,
+        stack: vec!["rust".to_string()],
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_server(&Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    })
+    .await
+    .unwrap();
+    db.save_database_credentials(&DatabaseCredentials {
+        id: "main".to_string(),
+        name: "main".to_string(),
+        db_type: DatabaseType::PostgreSQL,
+        host: Some("db.internal".to_string()),
+        port: Some(5432),
+        database_name: Some("app".to_string()),
+        username: Some("app".to_string()),
+        password: Some("hunter2".to_string()),
+        connection_string: None,
+        server_id: None,
+        container_id: None,
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.save_credential(&Credential {
+        id: "deploy".to_string(),
+        name: "deploy".to_string(),
+        credential_type: CredentialType::ApiToken,
+        data: CredentialData::ApiToken {
+            token: "tok-secret".to_string(),
+            url: None,
+        },
+        notes: None,
+    })
+    .await
+    .unwrap();
+    db.link_project_resource(&ProjectResource {
+        id: "l1".to_string(),
+        project_id: "shop".to_string(),
+        resource_type: ResourceType::Server,
+        resource_id: "web-1".to_string(),
+        role: None,
+        notes: None,
+        start_order: None,
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_snapshot_excludes_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+
+    let info = db.create_snapshot("before").await.unwrap();
+    assert_eq!(info.counts.projects, 1);
+    assert_eq!(info.counts.credentials, 1);
+    assert_eq!(info.counts.links, 1);
+    assert!(info.size_bytes > 0);
+
+    let inventory = db.load_snapshot("before").await.unwrap().unwrap();
+    let json = serde_json::to_string(&inventory).unwrap();
+    assert!(!json.contains("hunter2"));
+    assert!(!json.contains("tok-secret"));
+
+    // Names are unique
+    assert!(db.create_snapshot("before").await.is_err());
+    assert_eq!(db.list_snapshots().await.unwrap(), vec![info]);
+}
+
+#[tokio::test]
+async fn test_diff_against_current() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    seed(&db).await;
+    db.create_snapshot("before").await.unwrap();
+
+    let mut server = db.get_server("web-1").await.unwrap().unwrap();
+    server.host = "10.0.0.2".to_string();
+    db.save_server(&server).await.unwrap();
+    db.remove_database_credentials("main").await.unwrap();
+
+    let before = db.load_snapshot("before").await.unwrap().unwrap();
+    let changes = diff_inventories(&before, &db.current_inventory().await.unwrap());
+    let summary: Vec<(&str, &str)> = changes
+        .iter()
+        .map(|c| {
+            let change = match c.change {
+                ChangeKind::Created => "created",
+                ChangeKind::Removed => "removed",
+                ChangeKind::Modified(_) => "modified",
+            };
+            (c.kind, change)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("server", "modified"), ("database", "removed")]
+    );
+}
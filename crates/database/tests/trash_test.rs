@@ -0,0 +1,53 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::Server;
+
+fn server(id: &str) -> Server {
+    Server {
+        provider: Some("hetzner".to_string()),
+        ..common::server(id)
+    }
+}
+
+#[tokio::test]
+async fn test_trashed_server_is_hidden_until_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web-1")).await.unwrap();
+
+    assert!(db.trash_server("web-1").await.unwrap());
+    assert!(db.get_server("web-1").await.unwrap().is_none());
+    assert!(db.list_servers().await.unwrap().is_empty());
+    assert_eq!(db.list_trashed_servers().await.unwrap().len(), 1);
+
+    let restored = db.restore_server("web-1").await.unwrap().unwrap();
+    assert_eq!(restored.id, "web-1");
+    assert_eq!(db.list_servers().await.unwrap().len(), 1);
+    assert!(db.list_trashed_servers().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_trash_unknown_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    assert!(!db.trash_server("missing").await.unwrap());
+    assert!(db.restore_server("missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_saving_trashed_server_keeps_it_trashed() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.save_server(&server("web-1")).await.unwrap();
+    db.trash_server("web-1").await.unwrap();
+
+    let mut edited = server("web-1");
+    edited.host = "10.0.0.9".to_string();
+    db.save_server(&edited).await.unwrap();
+    assert!(db.list_servers().await.unwrap().is_empty());
+
+    let restored = db.restore_server("web-1").await.unwrap().unwrap();
+    assert_eq!(restored.host, "10.0.0.9");
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::open_db;
+use pctrl_core::{Error, Project, ProjectStatus, Server};
+use pctrl_database::Database;
+use sqlx::sqlite::SqlitePool;
+
+fn project(id: &str, name: &str) -> Project {
+    Project {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: None,
+        stack: Vec::new(),
+        status: ProjectStatus::Dev,
+        color: None,
+        icon: None,
+        notes: None,
+    }
+}
+
+fn server(id: &str, name: &str) -> Server {
+    Server {
+        name: name.to_string(),
+        ..common::server(id)
+    }
+}
+
+fn conflict(result: pctrl_core::Result<()>) -> String {
+    match result {
+        Err(Error::Conflict(name)) => name,
+        other => panic!("expected a conflict, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_create_refuses_a_name_taken_ignoring_case() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    db.create_project(&project("p1", "Blog")).await.unwrap();
+    assert_eq!(
+        conflict(db.create_project(&project("p2", "blog")).await),
+        "Blog"
+    );
+    // The same ID is taken too, whatever the name
+    assert_eq!(
+        conflict(db.create_project(&project("p1", "Shop")).await),
+        "Blog"
+    );
+    assert_eq!(db.list_projects().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_update_and_save_refuse_another_rows_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.create_project(&project("p1", "Blog")).await.unwrap();
+    db.create_project(&project("p2", "Shop")).await.unwrap();
+
+    assert_eq!(
+        conflict(db.update_project(&project("p2", "BLOG")).await),
+        "Blog"
+    );
+    assert_eq!(
+        conflict(db.save_project(&project("p3", "blog")).await),
+        "Blog"
+    );
+    // Changing the case of its own name is fine
+    db.update_project(&project("p1", "blog")).await.unwrap();
+    assert_eq!(db.get_project("p1").await.unwrap().unwrap().name, "blog");
+
+    let err = db
+        .update_project(&project("p9", "New"))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not found"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_trashed_server_name_can_be_reused() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.create_server(&server("s1", "web-1")).await.unwrap();
+    db.trash_server("s1").await.unwrap();
+
+    db.create_server(&server("s2", "Web-1")).await.unwrap();
+    // Restoring the trashed one would clash now
+    assert_eq!(conflict(db.restore_server("s1").await.map(|_| ())), "Web-1");
+    assert!(matches!(
+        db.update_server(&server("s1", "web-1")).await,
+        Err(Error::Config(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_unique_index_backs_up_raw_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("pctrl.db");
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    db.create_project(&project("p1", "Blog")).await.unwrap();
+    db.close().await;
+
+    let pool = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let err = sqlx::query("INSERT INTO projects (id, name) VALUES ('p2', 'BLOG')")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("UNIQUE"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_migration_renames_names_that_differ_in_case() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    sqlx::query(include_str!("fixtures/schema_v1.sql"))
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO projects (id, name) VALUES ('p2', 'SHOP'), ('p3', 'shop'), ('p4', 'Shop (2)')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+
+    let db = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    let name = |id: &'static str| {
+        let db = &db;
+        async move { db.get_project(id).await.unwrap().unwrap().name }
+    };
+    assert_eq!(name("shop").await, "Shop");
+    assert_eq!(name("p2").await, "SHOP (2)");
+    assert_eq!(name("p3").await, "shop (3)");
+    assert_eq!(name("p4").await, "Shop (2) (2)");
+}
+
+#[tokio::test]
+async fn test_migration_refuses_domains_that_differ_in_case() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    sqlx::query(include_str!("fixtures/schema_v1.sql"))
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO domains (id, domain) VALUES ('d-2', 'Shop.Example.com'), ('d-3', 'blog.example.com')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+
+    let err = Database::new(path.to_str().unwrap(), None)
+        .await
+        .err()
+        .expect("migration fails")
+        .to_string();
+    assert!(err.contains("schema v14"), "{}", err);
+    assert!(
+        err.contains("shop.example.com (ID d-1), Shop.Example.com (ID d-2)"),
+        "{}",
+        err
+    );
+    assert!(!err.contains("blog.example.com"), "{}", err);
+
+    // Nothing was renamed
+    let pool = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    let (domain,): (String,) = sqlx::query_as("SELECT domain FROM domains WHERE id = 'd-2'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(domain, "Shop.Example.com");
+}
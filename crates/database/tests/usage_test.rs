@@ -0,0 +1,111 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::open_db;
+use pctrl_core::{EntityType, Server, ServerType};
+
+#[tokio::test]
+async fn test_usage_summary_aggregates_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    for ms in [100, 200, 300] {
+        db.record_usage("server list", true, ms).await.unwrap();
+    }
+    db.record_usage("server status", false, 5000).await.unwrap();
+
+    let summary = db.usage_summary(None, 10).await.unwrap();
+    assert_eq!(summary.total, 4);
+    assert_eq!(summary.commands.len(), 2);
+
+    let list = &summary.commands[0];
+    assert_eq!(list.command, "server list");
+    assert_eq!(list.count, 3);
+    assert_eq!(list.failures, 0);
+    assert_eq!(list.avg_ms, 200);
+    assert_eq!(list.p95_ms, 300);
+
+    let status = &summary.commands[1];
+    assert_eq!(status.failures, 1);
+    assert_eq!(status.failure_rate(), 1.0);
+
+    assert_eq!(summary.busiest_days.len(), 1);
+    assert_eq!(summary.busiest_days[0].1, 4);
+}
+
+#[tokio::test]
+async fn test_usage_summary_since_filters_old_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+
+    let old = Utc::now() - Duration::days(40);
+    db.record_usage_at("project list", true, 10, old)
+        .await
+        .unwrap();
+    db.record_usage("server list", true, 10).await.unwrap();
+
+    let recent = db
+        .usage_summary(Some(Utc::now() - Duration::days(30)), 10)
+        .await
+        .unwrap();
+    assert_eq!(recent.total, 1);
+    assert_eq!(recent.commands[0].command, "server list");
+
+    assert_eq!(db.usage_summary(None, 10).await.unwrap().total, 2);
+}
+
+#[tokio::test]
+async fn test_disabled_usage_stats_are_not_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    assert!(db.usage_stats_enabled().await.unwrap());
+
+    db.set_usage_stats_enabled(false).await.unwrap();
+    assert!(!db.usage_stats_enabled().await.unwrap());
+    db.record_usage("server list", true, 10).await.unwrap();
+    assert_eq!(db.usage_summary(None, 10).await.unwrap().total, 0);
+
+    db.set_usage_stats_enabled(true).await.unwrap();
+    db.record_usage("server list", true, 10).await.unwrap();
+    assert_eq!(db.usage_summary(None, 10).await.unwrap().total, 1);
+}
+
+#[tokio::test]
+async fn test_reset_usage_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    db.record_usage("server list", true, 10).await.unwrap();
+    db.record_usage("server list", true, 20).await.unwrap();
+
+    assert_eq!(db.reset_usage_stats().await.unwrap(), 2);
+    assert_eq!(db.usage_summary(None, 10).await.unwrap().total, 0);
+}
+
+#[tokio::test]
+async fn test_usage_summary_lists_most_changed_entities() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(&dir).await;
+    let mut server = Server {
+        id: "web-1".to_string(),
+        name: "web-1".to_string(),
+        host: "10.0.0.1".to_string(),
+        server_type: ServerType::Vps,
+        provider: None,
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    };
+    db.save_server(&server).await.unwrap();
+    server.notes = Some("primary".to_string());
+    db.save_server(&server).await.unwrap();
+
+    let summary = db.usage_summary(None, 10).await.unwrap();
+    let entity = &summary.entities[0];
+    assert_eq!(entity.entity_type, EntityType::Server);
+    assert_eq!(entity.entity_id, "web-1");
+    assert_eq!(entity.changes, 2);
+}
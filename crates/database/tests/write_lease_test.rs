@@ -0,0 +1,91 @@
+use chrono::{Duration, Utc};
+use pctrl_core::lease::{LeaseDecision, LEASE_TTL_SECS};
+use pctrl_core::Error;
+use pctrl_database::Database;
+
+/// Two handles on one file, standing in for two pctrl processes
+async fn two_processes(dir: &tempfile::TempDir) -> (Database, Database) {
+    let path = dir.path().join("pctrl.db");
+    let a = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    let b = Database::new(path.to_str().unwrap(), None).await.unwrap();
+    (a, b)
+}
+
+#[tokio::test]
+async fn a_live_lease_keeps_other_processes_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = two_processes(&dir).await;
+    let now = Utc::now();
+
+    assert!(a.write_lease().await.unwrap().is_none());
+    assert_eq!(
+        a.acquire_write_lease("a", now).await.unwrap(),
+        LeaseDecision::Acquire
+    );
+    assert_eq!(
+        a.acquire_write_lease("a", now + Duration::seconds(5))
+            .await
+            .unwrap(),
+        LeaseDecision::Renew
+    );
+
+    let err = b.acquire_write_lease("b", now).await.err().unwrap();
+    assert!(matches!(err, Error::Locked(_)), "{}", err);
+    assert!(err.to_string().contains("a (pctrl "), "{}", err);
+
+    // A fresh process refuses to open the file at all
+    let path = dir.path().join("pctrl.db");
+    let err = Database::new(path.to_str().unwrap(), None)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("upgrading the database"),
+        "{}",
+        err
+    );
+
+    assert!(!b.release_write_lease("b").await.unwrap());
+    assert!(a.release_write_lease("a").await.unwrap());
+    assert_eq!(
+        b.acquire_write_lease("b", now).await.unwrap(),
+        LeaseDecision::Acquire
+    );
+}
+
+#[tokio::test]
+async fn an_abandoned_lease_is_taken_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = two_processes(&dir).await;
+    let now = Utc::now();
+    a.acquire_write_lease("a", now).await.unwrap();
+    assert!(a.heartbeat_write_lease("a", now).await.unwrap());
+
+    let later = now + Duration::seconds(LEASE_TTL_SECS + 1);
+    match b.acquire_write_lease("b", later).await.unwrap() {
+        LeaseDecision::Steal(old) => assert_eq!(old.holder, "a"),
+        other => panic!("expected a takeover, got {:?}", other),
+    }
+
+    // The old holder finds out at its next heartbeat
+    assert!(!a.heartbeat_write_lease("a", later).await.unwrap());
+    let lease = a.write_lease().await.unwrap().unwrap();
+    assert_eq!(lease.holder, "b");
+    assert_eq!(lease.acquired_at.timestamp(), later.timestamp());
+}
+
+#[tokio::test]
+async fn opening_records_the_writer_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, _) = two_processes(&dir).await;
+
+    assert_eq!(
+        a.writer_version().await.unwrap().as_deref(),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(
+        a.schema_version().await.unwrap(),
+        pctrl_database::CURRENT_SCHEMA_VERSION
+    );
+    assert!(a.write_lease().await.unwrap().is_none());
+}
@@ -1,9 +1,28 @@
-use bollard::container::{ListContainersOptions, StartContainerOptions, StopContainerOptions};
+use bollard::container::{
+    CPUStats, ListContainersOptions, LogOutput, LogsOptions, MemoryStatsStats,
+    RemoveContainerOptions, RestartContainerOptions, StartContainerOptions, StatsOptions,
+    StopContainerOptions,
+};
 use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::network::ListNetworksOptions;
 use bollard::Docker;
-use futures_util::StreamExt;
-use pctrl_core::{DockerHost, Result};
+use futures_util::{Stream, StreamExt};
+use pctrl_core::container_stats::{self, CpuSample};
+use pctrl_core::discovery;
+use pctrl_core::docker_endpoint::{self, DockerEndpoint};
+use pctrl_core::log_tail::LineBuffer;
+use pctrl_core::proxy_labels::Labels;
+use pctrl_core::startup::ContainerState;
+use pctrl_core::{ContainerNetwork, DockerHost, DockerNetwork, PublishedPort, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tunnel::SshTunnel;
+
+mod tunnel;
+
+pub use pctrl_core::container_stats::ContainerStats;
 
 /// Container information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,33 +32,132 @@ pub struct ContainerInfo {
     pub image: String,
     pub state: String,
     pub status: String,
+    /// `HostConfig.Memory` from inspect; `None` when unlimited
+    pub memory_limit_mb: Option<u64>,
+    /// As `docker ps` shows them, e.g. "0.0.0.0:8080->80/tcp"
+    #[serde(default)]
+    pub ports: Vec<String>,
+    #[serde(default)]
+    pub labels: Labels,
 }
 
+/// Networks, memberships and published ports of one host
+#[derive(Debug, Clone, Default)]
+pub struct NetworkTopology {
+    pub networks: Vec<DockerNetwork>,
+    pub memberships: Vec<ContainerNetwork>,
+    pub ports: Vec<PublishedPort>,
+    /// Labels per container name, for reverse-proxy hostnames
+    pub labels: BTreeMap<String, Labels>,
+}
+
+/// Which output stream a log frame came from. TTY containers have only
+/// one, reported as stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A chunk of container output as Docker sends it, usually whole lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFrame {
+    pub stream: LogStream,
+    pub text: String,
+}
+
+/// Which logs [`DockerManager::container_logs`] returns
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    /// Only the last N lines; all when `None`
+    pub tail: Option<u32>,
+    /// Only lines since then, as a UNIX timestamp
+    pub since: Option<i64>,
+    /// Start every line with Docker's RFC 3339 timestamp
+    pub timestamps: bool,
+    /// Keep the stream open for new lines
+    pub follow: bool,
+}
+
+/// Start of stderr lines in [`DockerManager::container_log_lines`]
+pub const STDERR_PREFIX: &str = "stderr | ";
+
+/// Seconds a Docker API request may take
+const REQUEST_TIMEOUT: u64 = 120;
+
 /// Docker manager
 pub struct DockerManager {
     hosts: Vec<DockerHost>,
+    /// Open SSH tunnels of `ssh://` hosts, by host ID
+    tunnels: Mutex<HashMap<String, SshTunnel>>,
 }
 
 impl DockerManager {
     pub fn new() -> Self {
-        Self { hosts: Vec::new() }
+        Self {
+            hosts: Vec::new(),
+            tunnels: Mutex::new(HashMap::new()),
+        }
     }
 
-    /// Add a Docker host
-    pub fn add_host(&mut self, host: DockerHost) {
+    /// Add a Docker host; an unsupported URL is rejected here
+    pub fn add_host(&mut self, host: DockerHost) -> Result<()> {
+        docker_endpoint::validate(&host)?;
         self.hosts.push(host);
+        Ok(())
     }
 
-    /// Connect to a Docker host
+    /// Connect to a Docker host the way its URL says
     fn connect(&self, id: &str) -> Result<Docker> {
         let host = self
             .hosts
             .iter()
             .find(|h| h.id == id)
             .ok_or_else(|| pctrl_core::Error::Docker("Host not found".to_string()))?;
+        let failed = |e: bollard::errors::Error| {
+            pctrl_core::Error::Docker(format!("Connection to '{}' failed: {}", host.name, e))
+        };
+        let version = bollard::API_DEFAULT_VERSION;
 
-        Docker::connect_with_socket(&host.url, 120, bollard::API_DEFAULT_VERSION)
-            .map_err(|e| pctrl_core::Error::Docker(format!("Connection failed: {}", e)))
+        match docker_endpoint::validate(host)? {
+            DockerEndpoint::Unix(path) => {
+                Docker::connect_with_socket(&path, REQUEST_TIMEOUT, version).map_err(failed)
+            }
+            endpoint @ DockerEndpoint::Http { .. } => {
+                let address = endpoint.address().unwrap_or_default();
+                Docker::connect_with_http(&address, REQUEST_TIMEOUT, version).map_err(failed)
+            }
+            endpoint @ DockerEndpoint::Https { .. } => {
+                let address = endpoint.address().unwrap_or_default();
+                let (ca, cert, key) = tls_files(host);
+                Docker::connect_with_ssl(&address, &key, &cert, &ca, REQUEST_TIMEOUT, version)
+                    .map_err(failed)
+            }
+            DockerEndpoint::Ssh {
+                user,
+                host: ssh_host,
+                port,
+                socket,
+            } => {
+                let mut tunnels = self
+                    .tunnels
+                    .lock()
+                    .map_err(|_| pctrl_core::Error::Docker("Tunnel lock poisoned".to_string()))?;
+                if !tunnels.get_mut(id).is_some_and(SshTunnel::is_alive) {
+                    let destination = match user {
+                        Some(user) => format!("{}@{}", user, ssh_host),
+                        None => ssh_host,
+                    };
+                    tunnels.insert(
+                        id.to_string(),
+                        SshTunnel::open(id, &destination, port, &socket)?,
+                    );
+                }
+                let local = tunnels[id].socket().to_string_lossy().into_owned();
+                Docker::connect_with_socket(&local, REQUEST_TIMEOUT, version).map_err(failed)
+            }
+        }
     }
 
     /// List containers on a host
@@ -56,23 +174,149 @@ impl DockerManager {
 
         let mut result = Vec::new();
         for container in containers {
+            let id = container.id.unwrap_or_default();
+            // The list doesn't carry resource limits; 0 means unlimited
+            let memory_limit_mb = docker
+                .inspect_container(&id, None)
+                .await
+                .ok()
+                .and_then(|info| info.host_config)
+                .and_then(|config| config.memory)
+                .filter(|bytes| *bytes > 0)
+                .map(|bytes| bytes as u64 / (1024 * 1024));
             result.push(ContainerInfo {
-                id: container.id.unwrap_or_default(),
+                id,
                 name: container
                     .names
                     .unwrap_or_default()
                     .first()
-                    .cloned()
+                    .map(|n| n.trim_start_matches('/').to_string())
                     .unwrap_or_default(),
                 image: container.image.unwrap_or_default(),
                 state: container.state.unwrap_or_default(),
                 status: container.status.unwrap_or_default(),
+                memory_limit_mb,
+                ports: container
+                    .ports
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|port| {
+                        let protocol = port.typ.map(|t| t.to_string()).unwrap_or_default();
+                        discovery::port_text(
+                            port.ip.as_deref(),
+                            port.public_port,
+                            port.private_port,
+                            if protocol.is_empty() {
+                                "tcp"
+                            } else {
+                                &protocol
+                            },
+                        )
+                    })
+                    .collect(),
+                labels: container.labels.unwrap_or_default().into_iter().collect(),
             });
         }
 
         Ok(result)
     }
 
+    /// Fetch the host's networks and every container's attachments and
+    /// published ports.
+    ///
+    /// Containers sharing another container's network namespace
+    /// (`--network container:x`) have no attachments of their own and are
+    /// simply absent from the memberships.
+    pub async fn network_topology(&self, host_id: &str) -> Result<NetworkTopology> {
+        let docker = self.connect(host_id)?;
+
+        let networks = docker
+            .list_networks(None::<ListNetworksOptions<String>>)
+            .await
+            .map_err(|e| pctrl_core::Error::Docker(format!("Failed to list networks: {}", e)))?;
+
+        // The container list carries the same network settings as inspect
+        let containers = docker
+            .list_containers(Some(ListContainersOptions::<String> {
+                all: true,
+                ..Default::default()
+            }))
+            .await
+            .map_err(|e| pctrl_core::Error::Docker(format!("Failed to list containers: {}", e)))?;
+
+        let mut topology = NetworkTopology {
+            networks: networks
+                .into_iter()
+                .filter_map(|n| {
+                    Some(DockerNetwork {
+                        host_id: host_id.to_string(),
+                        name: n.name?,
+                        driver: n.driver.filter(|d| !d.is_empty()),
+                    })
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        for container in containers {
+            let Some(name) = container
+                .names
+                .unwrap_or_default()
+                .first()
+                .map(|n| n.trim_start_matches('/').to_string())
+            else {
+                continue;
+            };
+
+            let attached = container
+                .network_settings
+                .and_then(|s| s.networks)
+                .unwrap_or_default();
+            for (network, endpoint) in attached {
+                topology.memberships.push(ContainerNetwork {
+                    host_id: host_id.to_string(),
+                    container: name.clone(),
+                    network,
+                    // host/none mode and stopped containers have no address
+                    ip_address: endpoint.ip_address.filter(|ip| !ip.is_empty()),
+                });
+            }
+
+            for port in container.ports.unwrap_or_default() {
+                let Some(host_port) = port.public_port else {
+                    continue;
+                };
+                let published = PublishedPort {
+                    host_id: host_id.to_string(),
+                    container: name.clone(),
+                    host_port,
+                    container_port: port.private_port,
+                    protocol: port
+                        .typ
+                        .map(|t| t.to_string())
+                        .filter(|t| !t.is_empty())
+                        .unwrap_or_else(|| "tcp".to_string()),
+                };
+                // Docker lists IPv4 and IPv6 bindings separately
+                if !topology.ports.contains(&published) {
+                    topology.ports.push(published);
+                }
+            }
+
+            let labels = container.labels.unwrap_or_default();
+            if !labels.is_empty() {
+                topology
+                    .labels
+                    .insert(name.clone(), labels.into_iter().collect());
+            }
+        }
+
+        topology
+            .memberships
+            .sort_by(|a, b| (&a.container, &a.network).cmp(&(&b.container, &b.network)));
+        Ok(topology)
+    }
+
     /// Start a container
     pub async fn start_container(&self, host_id: &str, container_id: &str) -> Result<()> {
         let docker = self.connect(host_id)?;
@@ -80,7 +324,7 @@ impl DockerManager {
         docker
             .start_container(container_id, None::<StartContainerOptions<String>>)
             .await
-            .map_err(|e| pctrl_core::Error::Docker(format!("Failed to start container: {}", e)))?;
+            .map_err(|e| failed("start", container_id, e))?;
 
         Ok(())
     }
@@ -92,11 +336,284 @@ impl DockerManager {
         docker
             .stop_container(container_id, None::<StopContainerOptions>)
             .await
-            .map_err(|e| pctrl_core::Error::Docker(format!("Failed to stop container: {}", e)))?;
+            .map_err(|e| failed("stop", container_id, e))?;
+
+        Ok(())
+    }
+
+    /// Restart a container
+    pub async fn restart_container(&self, host_id: &str, container_id: &str) -> Result<()> {
+        let docker = self.connect(host_id)?;
+
+        docker
+            .restart_container(container_id, None::<RestartContainerOptions>)
+            .await
+            .map_err(|e| failed("restart", container_id, e))?;
+
+        Ok(())
+    }
+
+    /// Remove a container; a running (or paused) one only with `force`,
+    /// which kills it first
+    pub async fn remove_container(
+        &self,
+        host_id: &str,
+        container_id: &str,
+        force: bool,
+    ) -> Result<()> {
+        if !force {
+            let state = self.container_state(host_id, container_id).await?;
+            if matches!(state.status.as_str(), "running" | "paused" | "restarting") {
+                return Err(pctrl_core::Error::Docker(format!(
+                    "Container {} is {}; stop it first or force the removal",
+                    container_id, state.status
+                )));
+            }
+        }
+        let docker = self.connect(host_id)?;
+
+        docker
+            .remove_container(
+                container_id,
+                Some(RemoveContainerOptions {
+                    force,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .map_err(|e| failed("remove", container_id, e))?;
+
+        Ok(())
+    }
+
+    /// Pause all processes of a container
+    pub async fn pause_container(&self, host_id: &str, container_id: &str) -> Result<()> {
+        let docker = self.connect(host_id)?;
+
+        docker
+            .pause_container(container_id)
+            .await
+            .map_err(|e| failed("pause", container_id, e))?;
+
+        Ok(())
+    }
+
+    /// Resume a paused container
+    pub async fn unpause_container(&self, host_id: &str, container_id: &str) -> Result<()> {
+        let docker = self.connect(host_id)?;
+
+        docker
+            .unpause_container(container_id)
+            .await
+            .map_err(|e| failed("unpause", container_id, e))?;
 
         Ok(())
     }
 
+    /// Current state and health of a container
+    pub async fn container_state(
+        &self,
+        host_id: &str,
+        container_id: &str,
+    ) -> Result<ContainerState> {
+        let docker = self.connect(host_id)?;
+
+        let info = docker
+            .inspect_container(container_id, None)
+            .await
+            .map_err(|e| failed("inspect", container_id, e))?;
+
+        let state = info.state.unwrap_or_default();
+        let health = state
+            .health
+            .and_then(|h| h.status)
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty() && s != "none");
+
+        Ok(ContainerState {
+            status: state.status.map(|s| s.to_string()).unwrap_or_default(),
+            health,
+        })
+    }
+
+    /// Current CPU, memory, network and block I/O usage of a running
+    /// container. Docker waits for a second sample to report the CPU delta,
+    /// so this takes about a second.
+    pub async fn container_stats(
+        &self,
+        host_id: &str,
+        container_id: &str,
+    ) -> Result<ContainerStats> {
+        let docker = self.connect(host_id)?;
+
+        let stats = docker
+            .stats(
+                container_id,
+                Some(StatsOptions {
+                    stream: false,
+                    one_shot: false,
+                }),
+            )
+            .next()
+            .await
+            .ok_or_else(|| {
+                pctrl_core::Error::Docker(format!(
+                    "Failed to read stats of container {}: no sample",
+                    container_id
+                ))
+            })?
+            .map_err(|e| failed("read stats of", container_id, e))?;
+
+        let sample = |cpu: &CPUStats| CpuSample {
+            container: cpu.cpu_usage.total_usage,
+            system: cpu.system_cpu_usage,
+        };
+        let online_cpus = stats.cpu_stats.online_cpus.unwrap_or_else(|| {
+            stats
+                .cpu_stats
+                .cpu_usage
+                .percpu_usage
+                .as_ref()
+                .map_or(1, |cpus| cpus.len() as u64)
+        });
+        let inactive_file = match stats.memory_stats.stats {
+            Some(MemoryStatsStats::V1(v1)) => Some(v1.total_inactive_file),
+            Some(MemoryStatsStats::V2(v2)) => Some(v2.inactive_file),
+            None => None,
+        };
+        // Old daemons report a single `network` instead of one per network
+        let networks: Vec<_> = match &stats.networks {
+            Some(networks) => networks.values().collect(),
+            None => stats.network.iter().collect(),
+        };
+        let (net_rx, net_tx) = networks.into_iter().fold((0, 0), |(rx, tx), net| {
+            (rx + net.rx_bytes, tx + net.tx_bytes)
+        });
+        let (block_read, block_write) = container_stats::block_io(
+            stats
+                .blkio_stats
+                .io_service_bytes_recursive
+                .iter()
+                .flatten()
+                .map(|entry| (entry.op.as_str(), entry.value)),
+        );
+
+        Ok(ContainerStats {
+            cpu_percent: container_stats::cpu_percent(
+                sample(&stats.cpu_stats),
+                sample(&stats.precpu_stats),
+                online_cpus,
+            ),
+            mem_usage_bytes: container_stats::memory_usage(
+                stats.memory_stats.usage.unwrap_or(0),
+                inactive_file,
+            ),
+            mem_limit_bytes: stats.memory_stats.limit.unwrap_or(0),
+            net_rx,
+            net_tx,
+            block_read,
+            block_write,
+        })
+    }
+
+    /// A container's logs, stdout and stderr frames as they arrive. Without
+    /// `follow` the stream ends after the logs written so far.
+    pub fn container_logs(
+        &self,
+        host_id: &str,
+        container_id: &str,
+        options: &LogOptions,
+    ) -> Result<impl Stream<Item = Result<LogFrame>> + Send + 'static> {
+        let docker = self.connect(host_id)?;
+
+        let logs = docker.logs(
+            container_id,
+            Some(LogsOptions::<String> {
+                follow: options.follow,
+                stdout: true,
+                stderr: true,
+                since: options.since.unwrap_or(0),
+                timestamps: options.timestamps,
+                tail: options
+                    .tail
+                    .map_or_else(|| "all".to_string(), |n| n.to_string()),
+                ..Default::default()
+            }),
+        );
+        let container_id = container_id.to_string();
+        Ok(logs.map(move |chunk| {
+            let stream = match &chunk {
+                Ok(LogOutput::StdErr { .. }) => LogStream::Stderr,
+                _ => LogStream::Stdout,
+            };
+            chunk
+                .map(|output| LogFrame {
+                    stream,
+                    text: output.to_string(),
+                })
+                .map_err(|e| failed("read logs of", &container_id, e))
+        }))
+    }
+
+    /// A container's logs written so far, as lines; stderr lines start with
+    /// [`STDERR_PREFIX`]. `follow` is ignored.
+    pub async fn container_log_lines(
+        &self,
+        host_id: &str,
+        container_id: &str,
+        options: &LogOptions,
+    ) -> Result<Vec<String>> {
+        let options = LogOptions {
+            follow: false,
+            ..options.clone()
+        };
+        let mut frames = Box::pin(self.container_logs(host_id, container_id, &options)?);
+        let mut stdout = LineBuffer::default();
+        let mut stderr = LineBuffer::default();
+        let mut lines = Vec::new();
+        while let Some(frame) = frames.next().await {
+            let frame = frame?;
+            match frame.stream {
+                LogStream::Stdout => lines.extend(stdout.push(&frame.text)),
+                LogStream::Stderr => lines.extend(
+                    stderr
+                        .push(&frame.text)
+                        .into_iter()
+                        .map(|line| format!("{}{}", STDERR_PREFIX, line)),
+                ),
+            }
+        }
+        lines.extend(stdout.finish());
+        lines.extend(
+            stderr
+                .finish()
+                .map(|line| format!("{}{}", STDERR_PREFIX, line)),
+        );
+        Ok(lines)
+    }
+
+    /// Follow a container's logs, starting with its last `tail` lines. Every
+    /// line starts with Docker's RFC 3339 timestamp; items are chunks as
+    /// Docker sends them, usually whole lines.
+    pub fn follow_logs(
+        &self,
+        host_id: &str,
+        container_id: &str,
+        tail: u32,
+    ) -> Result<impl Stream<Item = Result<String>> + Send + 'static> {
+        let frames = self.container_logs(
+            host_id,
+            container_id,
+            &LogOptions {
+                tail: Some(tail),
+                timestamps: true,
+                follow: true,
+                ..Default::default()
+            },
+        )?;
+        Ok(frames.map(|frame| frame.map(|frame| frame.text)))
+    }
+
     /// List all hosts
     pub fn list_hosts(&self) -> &[DockerHost] {
         &self.hosts
@@ -170,6 +687,32 @@ impl DockerManager {
     }
 }
 
+/// A failed container operation, naming the container so batches can
+/// tell which one it was
+fn failed(action: &str, container_id: &str, e: bollard::errors::Error) -> pctrl_core::Error {
+    pctrl_core::Error::Docker(format!(
+        "Failed to {} container {}: {}",
+        action, container_id, e
+    ))
+}
+
+/// CA, certificate and key files of an `https://` host: its own, else
+/// `ca.pem`, `cert.pem` and `key.pem` in `DOCKER_CERT_PATH` or `~/.docker`
+fn tls_files(host: &DockerHost) -> (PathBuf, PathBuf, PathBuf) {
+    if let (Some(ca), Some(cert), Some(key)) = (&host.tls_ca, &host.tls_cert, &host.tls_key) {
+        return (ca.into(), cert.into(), key.into());
+    }
+    let dir = std::env::var_os("DOCKER_CERT_PATH")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".docker")))
+        .unwrap_or_default();
+    (
+        dir.join("ca.pem"),
+        dir.join("cert.pem"),
+        dir.join("key.pem"),
+    )
+}
+
 impl Default for DockerManager {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,124 @@
+//! `ssh://` Docker hosts: the remote socket forwarded to a local one by the
+//! system `ssh`, so the agent, `~/.ssh/config` and known hosts apply
+
+use pctrl_core::{Error, Result};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long `ssh` may take to log in and open the forward
+const OPEN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A running `ssh -L` forward; stopped when dropped
+pub(crate) struct SshTunnel {
+    child: Child,
+    socket: PathBuf,
+}
+
+impl SshTunnel {
+    /// Forward `remote_socket` on `destination` (`[user@]host`) to a local
+    /// socket named after `id`, waiting until it's there
+    pub(crate) fn open(
+        id: &str,
+        destination: &str,
+        port: Option<u16>,
+        remote_socket: &str,
+    ) -> Result<Self> {
+        if cfg!(not(unix)) {
+            return Err(Error::Docker(
+                "ssh:// Docker hosts need a local Unix socket, which this system lacks".to_string(),
+            ));
+        }
+        let socket = local_socket(id);
+        let _ = std::fs::remove_file(&socket);
+
+        let mut command = Command::new("ssh");
+        command
+            .args(forward_args(&socket, destination, port, remote_socket))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        let child = command
+            .spawn()
+            .map_err(|e| Error::Docker(format!("Failed to run ssh: {}", e)))?;
+        let mut tunnel = Self { child, socket };
+
+        let started = Instant::now();
+        while !tunnel.socket.exists() {
+            if let Ok(Some(status)) = tunnel.child.try_wait() {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = tunnel.child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr);
+                }
+                return Err(Error::Docker(format!(
+                    "SSH tunnel to {} failed ({}): {}",
+                    destination,
+                    status,
+                    stderr.trim()
+                )));
+            }
+            if started.elapsed() > OPEN_TIMEOUT {
+                return Err(Error::Docker(format!(
+                    "SSH tunnel to {} not open after {}s",
+                    destination,
+                    OPEN_TIMEOUT.as_secs()
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Ok(tunnel)
+    }
+
+    pub(crate) fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Whether `ssh` is still running
+    pub(crate) fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+/// Arguments of `ssh` forwarding `remote_socket` to `local`
+pub(crate) fn forward_args(
+    local: &Path,
+    destination: &str,
+    port: Option<u16>,
+    remote_socket: &str,
+) -> Vec<String> {
+    let mut args = vec![
+        "-nNT".to_string(),
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-o".to_string(),
+        "StreamLocalBindUnlink=yes".to_string(),
+        "-L".to_string(),
+        format!("{}:{}", local.display(), remote_socket),
+    ];
+    if let Some(port) = port {
+        args.push("-p".to_string());
+        args.push(port.to_string());
+    }
+    args.push(destination.to_string());
+    args
+}
+
+/// Local end of the forward, private to this process
+fn local_socket(id: &str) -> PathBuf {
+    let id: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join(format!("pctrl-docker-{}-{}.sock", std::process::id(), id))
+}
@@ -1,7 +1,8 @@
 use chrono::{TimeZone, Utc};
-use git2::Repository;
-use pctrl_core::{GitRepo, Result};
+use git2::{Cred, CredentialType, PushOptions, RemoteCallbacks, Repository};
+use pctrl_core::{AuthMethod, Credential, CredentialData, GitRepo, Result};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// Release information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,284 @@ pub struct Release {
     pub date: String,
 }
 
+/// What a push may authenticate with. The SSH agent is always tried
+/// first, then the SSH key, then username and password.
+#[derive(Debug, Clone, Default)]
+pub struct PushAuth {
+    /// SSH user when the remote URL doesn't name one (it usually does:
+    /// `git@github.com:...`)
+    pub ssh_username: Option<String>,
+    /// A key file or an inline key ([`AuthMethod::Key`] or
+    /// [`AuthMethod::InlineKey`])
+    pub ssh_key: Option<AuthMethod>,
+    /// Username and password, or a token as the password, for HTTPS
+    /// remotes; without a username the URL's is used
+    pub password: Option<(Option<String>, String)>,
+}
+
+impl PushAuth {
+    /// What a stored credential offers: SSH credentials their user and
+    /// key (`key_material` is the key stored inline with it), basic auth
+    /// its password, API tokens their token
+    pub fn from_credential(credential: &Credential, key_material: Option<String>) -> Self {
+        let mut auth = Self::default();
+        if let Some((username, _, method)) = credential.ssh_login(key_material) {
+            auth.ssh_username = Some(username);
+            if !matches!(method, AuthMethod::Agent) {
+                auth.ssh_key = Some(method);
+            }
+        }
+        match &credential.data {
+            CredentialData::BasicAuth {
+                username, password, ..
+            } => auth.password = Some((Some(username.clone()), password.clone())),
+            CredentialData::ApiToken { token, .. } => auth.password = Some((None, token.clone())),
+            _ => {}
+        }
+        auth
+    }
+}
+
+/// How far a push has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PushProgress {
+    pub objects: usize,
+    pub total: usize,
+    pub bytes: usize,
+}
+
+/// Where a repository stands: branch, upstream, uncommitted changes and
+/// the last commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoStatus {
+    /// The checked-out branch; `None` on a detached HEAD
+    pub branch: Option<String>,
+    /// The branch's upstream, e.g. `origin/main`; `None` without one
+    pub upstream: Option<String>,
+    /// Commits on the branch that the upstream doesn't have
+    pub ahead: usize,
+    /// Commits on the upstream that the branch doesn't have
+    pub behind: usize,
+    /// Files with changes in the index
+    pub staged: usize,
+    /// Tracked files with changes not in the index
+    pub unstaged: usize,
+    pub untracked: usize,
+    /// `None` before the first commit
+    pub last_commit: Option<CommitInfo>,
+}
+
+/// A commit as status output shows it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub summary: String,
+    pub author: String,
+    pub date: String,
+}
+
+impl From<&git2::Commit<'_>> for CommitInfo {
+    fn from(commit: &git2::Commit<'_>) -> Self {
+        let hash = commit.id().to_string();
+        CommitInfo {
+            short_hash: hash.chars().take(7).collect(),
+            hash,
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            date: format_timestamp(commit.time().seconds()),
+        }
+    }
+}
+
+impl RepoStatus {
+    /// Files with uncommitted changes; a file both staged and changed
+    /// again counts twice
+    pub fn changed_files(&self) -> usize {
+        self.staged + self.unstaged + self.untracked
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.changed_files() > 0
+    }
+
+    /// `main`, or `detached at a1b2c3d` without a branch
+    pub fn head(&self) -> String {
+        match (&self.branch, &self.last_commit) {
+            (Some(branch), _) => branch.clone(),
+            (None, Some(commit)) => format!("detached at {}", commit.short_hash),
+            (None, None) => "detached".to_string(),
+        }
+    }
+
+    /// `2 staged, 1 untracked`, or `clean`
+    pub fn changes(&self) -> String {
+        let parts: Vec<String> = [
+            (self.staged, "staged"),
+            (self.unstaged, "unstaged"),
+            (self.untracked, "untracked"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect();
+        if parts.is_empty() {
+            "clean".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// The commit a revision (tag, branch, hash, `HEAD`) points at
+fn resolve_commit(repo: &Repository, rev: &str) -> Result<git2::Oid> {
+    repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id())
+        .map_err(|_| pctrl_core::Error::Git(format!("Unknown revision '{}'", rev)))
+}
+
+/// Tag names by the commit they point at, sorted
+fn tags_by_commit(repo: &Repository) -> std::collections::HashMap<git2::Oid, Vec<String>> {
+    let mut tags: std::collections::HashMap<git2::Oid, Vec<String>> = Default::default();
+    if let Ok(names) = repo.tag_names(None) {
+        for name in names.iter().flatten() {
+            if let Ok(commit) = resolve_commit(repo, &format!("refs/tags/{}", name)) {
+                tags.entry(commit).or_default().push(name.to_string());
+            }
+        }
+    }
+    for names in tags.values_mut() {
+        names.sort();
+    }
+    tags
+}
+
+/// Push `refspecs` to origin, authenticating through `auth` and
+/// reporting transferred objects to `progress`; refs the remote refuses
+/// (e.g. not a fast-forward) are an error
+fn push(
+    repo: &Repository,
+    refspecs: &[String],
+    auth: &PushAuth,
+    mut progress: impl FnMut(PushProgress),
+) -> std::result::Result<(), String> {
+    let mut remote = repo
+        .find_remote("origin")
+        .map_err(|e| format!("no remote 'origin': {}", e.message()))?;
+    if refspecs.is_empty() {
+        return Ok(());
+    }
+
+    let mut attempts = Attempts::default();
+    let mut rejected = Vec::new();
+    {
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|url, username, allowed| attempts.next(auth, url, username, allowed));
+        callbacks.push_transfer_progress(|objects, total, bytes| {
+            progress(PushProgress {
+                objects,
+                total,
+                bytes,
+            })
+        });
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(reason) = status {
+                rejected.push(format!("{} ({})", refname, reason));
+            }
+            Ok(())
+        });
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+        remote
+            .push(refspecs, Some(&mut options))
+            .map_err(|e| e.message().to_string())?;
+    }
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("the remote rejected {}", rejected.join(", ")))
+    }
+}
+
+/// Authentication methods a push has tried. libgit2 asks again after
+/// every refused attempt, so each method is offered once and the last
+/// answer says what was tried.
+#[derive(Default)]
+struct Attempts {
+    agent: bool,
+    key: bool,
+    password: bool,
+    tried: Vec<String>,
+}
+
+impl Attempts {
+    fn next(
+        &mut self,
+        auth: &PushAuth,
+        url: &str,
+        username: Option<&str>,
+        allowed: CredentialType,
+    ) -> std::result::Result<Cred, git2::Error> {
+        let ssh_user = username.or(auth.ssh_username.as_deref()).unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(ssh_user);
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            if !self.agent {
+                self.agent = true;
+                self.tried.push("SSH agent".to_string());
+                return Cred::ssh_key_from_agent(ssh_user);
+            }
+            if !self.key {
+                self.key = true;
+                match &auth.ssh_key {
+                    Some(AuthMethod::Key { path, passphrase }) => {
+                        self.tried.push(format!("key {}", path));
+                        return Cred::ssh_key(
+                            ssh_user,
+                            None,
+                            Path::new(path),
+                            passphrase.as_deref(),
+                        );
+                    }
+                    Some(AuthMethod::InlineKey { key, passphrase }) => {
+                        self.tried.push("stored key".to_string());
+                        return Cred::ssh_key_from_memory(
+                            ssh_user,
+                            None,
+                            key,
+                            passphrase.as_deref(),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !self.password {
+            self.password = true;
+            if let Some((user, password)) = &auth.password {
+                let user = user.as_deref().or(username).unwrap_or("git");
+                self.tried.push(format!("password for {}", user));
+                return Cred::userpass_plaintext(user, password);
+            }
+        }
+
+        Err(git2::Error::from_str(&if self.tried.is_empty() {
+            format!(
+                "no credentials for {} (link a credential to the repository)",
+                url
+            )
+        } else {
+            format!(
+                "authentication to {} failed (tried {})",
+                url,
+                self.tried.join(", ")
+            )
+        }))
+    }
+}
+
 /// Format a Unix timestamp as a readable date string
 fn format_timestamp(seconds: i64) -> String {
     match Utc.timestamp_opt(seconds, 0) {
@@ -120,19 +399,201 @@ impl GitManager {
         Ok(())
     }
 
+    /// Commits reachable from `to` but not from `from`, oldest first;
+    /// without `from`, the whole history up to `to`. Both are revisions:
+    /// tags, branches, hashes or `HEAD`.
+    pub fn commits_between(
+        &self,
+        repo_id: &str,
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<Vec<CommitInfo>> {
+        let repo = self.open_repo(repo_id)?;
+        let git_error = |e: git2::Error| {
+            pctrl_core::Error::Git(format!("Failed to read history: {}", e.message()))
+        };
+
+        let mut walk = repo.revwalk().map_err(git_error)?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)
+            .map_err(git_error)?;
+        walk.push(resolve_commit(&repo, to)?).map_err(git_error)?;
+        if let Some(from) = from {
+            walk.hide(resolve_commit(&repo, from)?).map_err(git_error)?;
+        }
+        walk.map(|oid| {
+            let commit = repo
+                .find_commit(oid.map_err(git_error)?)
+                .map_err(git_error)?;
+            Ok(CommitInfo::from(&commit))
+        })
+        .collect()
+    }
+
+    /// The closest tag before `rev`: on one of its ancestors, not on `rev`
+    /// itself; `None` when the history before it has no tags
+    pub fn previous_tag(&self, repo_id: &str, rev: &str) -> Result<Option<String>> {
+        let repo = self.open_repo(repo_id)?;
+        let tags = tags_by_commit(&repo);
+        let start = resolve_commit(&repo, rev)?;
+        let mut walk = repo
+            .revwalk()
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to read history: {}", e)))?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)
+            .and_then(|()| walk.push(start))
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to read history: {}", e)))?;
+        let previous = walk
+            .flatten()
+            .filter(|oid| *oid != start)
+            .find_map(|oid| tags.get(&oid).and_then(|names| names.last().cloned()));
+        Ok(previous)
+    }
+
+    /// A tag on the commit `rev` points at (the last by name when there
+    /// are several)
+    pub fn tag_at(&self, repo_id: &str, rev: &str) -> Result<Option<String>> {
+        let repo = self.open_repo(repo_id)?;
+        let commit = resolve_commit(&repo, rev)?;
+        Ok(tags_by_commit(&repo)
+            .get(&commit)
+            .and_then(|names| names.last().cloned()))
+    }
+
     /// Push tags to remote
-    pub fn push_tags(&self, repo_id: &str) -> Result<()> {
+    pub fn push_tags(
+        &self,
+        repo_id: &str,
+        auth: &PushAuth,
+        progress: impl FnMut(PushProgress),
+    ) -> Result<()> {
         let repo = self.open_repo(repo_id)?;
+        let tags = repo
+            .tag_names(None)
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to list tags: {}", e)))?;
+        let refspecs: Vec<String> = tags
+            .iter()
+            .flatten()
+            .map(|tag| format!("refs/tags/{0}:refs/tags/{0}", tag))
+            .collect();
+        push(&repo, &refspecs, auth, progress)
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to push tags: {}", e)))
+    }
+
+    /// Push a local branch to the branch of the same name on origin
+    pub fn push_branch(
+        &self,
+        repo_id: &str,
+        branch: &str,
+        auth: &PushAuth,
+        progress: impl FnMut(PushProgress),
+    ) -> Result<()> {
+        let repo = self.open_repo(repo_id)?;
+        repo.find_branch(branch, git2::BranchType::Local)
+            .map_err(|_| pctrl_core::Error::Git(format!("No local branch '{}'", branch)))?;
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+        push(&repo, &[refspec], auth, progress)
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to push {}: {}", branch, e)))
+    }
 
-        let mut remote = repo
-            .find_remote("origin")
-            .map_err(|e| pctrl_core::Error::Git(format!("Failed to find remote: {}", e)))?;
+    /// Number of files with uncommitted changes, untracked ones included
+    pub fn changed_files(&self, repo_id: &str) -> Result<usize> {
+        let repo = self.open_repo(repo_id)?;
 
-        remote
-            .push(&["refs/tags/*:refs/tags/*"], None)
-            .map_err(|e| pctrl_core::Error::Git(format!("Failed to push tags: {}", e)))?;
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true).include_ignored(false);
+        let statuses = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| pctrl_core::Error::Git(format!("Failed to get status: {}", e)))?;
 
-        Ok(())
+        Ok(statuses.len())
+    }
+
+    /// Branch, upstream, uncommitted changes and last commit of a
+    /// repository; a detached HEAD, a missing upstream or an empty
+    /// repository are reported as such rather than as errors
+    pub fn repo_status(&self, repo_id: &str) -> Result<RepoStatus> {
+        let repo = self.open_repo(repo_id)?;
+        let git_error = |what: &str, e: git2::Error| {
+            pctrl_core::Error::Git(format!("Failed to get {}: {}", what, e))
+        };
+
+        let detached = repo.head_detached().map_err(|e| git_error("HEAD", e))?;
+        let branch = if detached {
+            None
+        } else {
+            // Also set before the first commit, when HEAD points nowhere yet
+            repo.find_reference("HEAD")
+                .ok()
+                .and_then(|head| head.symbolic_target().map(str::to_string))
+                .map(|target| {
+                    target
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(&target)
+                        .to_string()
+                })
+        };
+
+        let head_commit = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let last_commit = head_commit.as_ref().map(CommitInfo::from);
+
+        let (mut upstream, mut ahead, mut behind) = (None, 0, 0);
+        if let (Some(name), Some(local)) = (&branch, &head_commit) {
+            let tracking = repo
+                .find_branch(name, git2::BranchType::Local)
+                .and_then(|b| b.upstream());
+            if let Ok(tracking) = tracking {
+                upstream = tracking.name().ok().flatten().map(str::to_string);
+                if let Some(remote) = tracking.get().target() {
+                    (ahead, behind) = repo
+                        .graph_ahead_behind(local.id(), remote)
+                        .map_err(|e| git_error("ahead/behind counts", e))?;
+                }
+            }
+        }
+
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| git_error("status", e))?;
+        let (mut staged, mut unstaged, mut untracked) = (0, 0, 0);
+        for entry in statuses.iter() {
+            let status = entry.status();
+            if status.intersects(
+                git2::Status::INDEX_NEW
+                    | git2::Status::INDEX_MODIFIED
+                    | git2::Status::INDEX_DELETED
+                    | git2::Status::INDEX_RENAMED
+                    | git2::Status::INDEX_TYPECHANGE,
+            ) {
+                staged += 1;
+            }
+            if status.intersects(
+                git2::Status::WT_MODIFIED
+                    | git2::Status::WT_DELETED
+                    | git2::Status::WT_RENAMED
+                    | git2::Status::WT_TYPECHANGE
+                    | git2::Status::CONFLICTED,
+            ) {
+                unstaged += 1;
+            }
+            if status.contains(git2::Status::WT_NEW) {
+                untracked += 1;
+            }
+        }
+
+        Ok(RepoStatus {
+            branch,
+            upstream,
+            ahead,
+            behind,
+            staged,
+            unstaged,
+            untracked,
+            last_commit,
+        })
     }
 
     /// List all repositories
@@ -0,0 +1,102 @@
+//! `commits_between`, `previous_tag` and `tag_at`
+
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+use pctrl_core::GitRepo;
+use pctrl_git::GitManager;
+use std::path::Path;
+
+fn init(dir: &Path) -> Repository {
+    let mut options = RepositoryInitOptions::new();
+    options.initial_head("main");
+    Repository::init_opts(dir, &options).unwrap()
+}
+
+fn commit(repo: &Repository, message: &str) -> Oid {
+    std::fs::write(repo.workdir().unwrap().join("log.txt"), message).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("log.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &author, &author, message, &tree, &parents)
+        .unwrap()
+}
+
+fn tag(repo: &Repository, name: &str, target: Oid) {
+    let commit = repo.find_commit(target).unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    repo.tag(name, commit.as_object(), &author, name, false)
+        .unwrap();
+}
+
+fn manager(dir: &Path) -> GitManager {
+    let mut git = GitManager::new();
+    git.add_repo(GitRepo {
+        id: "app".to_string(),
+        name: "App".to_string(),
+        path: dir.to_string_lossy().to_string(),
+        remote_url: None,
+        credential_id: None,
+    });
+    git
+}
+
+fn summaries(git: &GitManager, from: Option<&str>, to: &str) -> Vec<String> {
+    git.commits_between("app", from, to)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.summary)
+        .collect()
+}
+
+#[test]
+fn test_commits_between_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    commit(&repo, "feat: one");
+    let v1 = commit(&repo, "fix: two");
+    tag(&repo, "v1.0.0", v1);
+    commit(&repo, "feat: three");
+    let v2 = commit(&repo, "chore: four");
+    tag(&repo, "v1.1.0", v2);
+    commit(&repo, "feat: five");
+    let git = manager(dir.path());
+
+    assert_eq!(
+        summaries(&git, Some("v1.0.0"), "v1.1.0"),
+        ["feat: three", "chore: four"]
+    );
+    assert_eq!(summaries(&git, Some("v1.1.0"), "HEAD"), ["feat: five"]);
+    assert_eq!(summaries(&git, None, "v1.0.0"), ["feat: one", "fix: two"]);
+
+    assert_eq!(
+        git.previous_tag("app", "HEAD").unwrap().as_deref(),
+        Some("v1.1.0")
+    );
+    assert_eq!(
+        git.previous_tag("app", "v1.1.0").unwrap().as_deref(),
+        Some("v1.0.0")
+    );
+    assert_eq!(git.previous_tag("app", "v1.0.0").unwrap(), None);
+    assert_eq!(
+        git.tag_at("app", &v2.to_string()).unwrap().as_deref(),
+        Some("v1.1.0")
+    );
+    assert_eq!(git.tag_at("app", "HEAD").unwrap(), None);
+}
+
+#[test]
+fn test_untagged_history_and_unknown_revisions() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    commit(&repo, "Initial commit");
+    commit(&repo, "Second");
+    let git = manager(dir.path());
+
+    assert_eq!(git.previous_tag("app", "HEAD").unwrap(), None);
+    assert_eq!(summaries(&git, None, "HEAD"), ["Initial commit", "Second"]);
+    let err = git.commits_between("app", Some("v9"), "HEAD").unwrap_err();
+    assert!(err.to_string().contains("Unknown revision 'v9'"), "{}", err);
+}
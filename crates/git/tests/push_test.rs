@@ -0,0 +1,194 @@
+//! `push_branch`/`push_tags` against a local bare remote, and the
+//! credential fallbacks against a remote that refuses everyone
+
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+use pctrl_core::{AuthMethod, Credential, GitRepo};
+use pctrl_git::{GitManager, PushAuth, PushProgress};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+
+fn init(dir: &Path) -> Repository {
+    let mut options = RepositoryInitOptions::new();
+    options.initial_head("main");
+    Repository::init_opts(dir, &options).unwrap()
+}
+
+fn commit(repo: &Repository, file: &str, content: &str, message: &str) -> Oid {
+    std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &author, &author, message, &tree, &parents)
+        .unwrap()
+}
+
+fn manager(dir: &Path) -> GitManager {
+    let mut git = GitManager::new();
+    git.add_repo(GitRepo {
+        id: "app".to_string(),
+        name: "App".to_string(),
+        path: dir.to_string_lossy().to_string(),
+        remote_url: None,
+        credential_id: None,
+    });
+    git
+}
+
+/// A working repository with one commit and a bare `origin` next to it
+fn with_origin(dir: &Path) -> (Repository, Repository) {
+    let repo = init(&dir.join("work"));
+    commit(&repo, "a.txt", "a", "First");
+    let origin = Repository::init_bare(dir.join("origin.git")).unwrap();
+    repo.remote("origin", dir.join("origin.git").to_str().unwrap())
+        .unwrap();
+    (repo, origin)
+}
+
+#[test]
+fn test_push_branch_reports_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let (repo, origin) = with_origin(dir.path());
+    let head = commit(&repo, "b.txt", "b", "Second");
+
+    let mut reports: Vec<PushProgress> = Vec::new();
+    manager(&dir.path().join("work"))
+        .push_branch("app", "main", &PushAuth::default(), |p| reports.push(p))
+        .unwrap();
+
+    let pushed = origin.find_reference("refs/heads/main").unwrap();
+    assert_eq!(pushed.target(), Some(head));
+    let last = reports.last().expect("progress was reported");
+    assert_eq!(last.objects, last.total);
+    assert!(last.total > 0);
+}
+
+#[test]
+fn test_push_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let (repo, origin) = with_origin(dir.path());
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let author = Signature::now("Alice", "alice@example.com").unwrap();
+    repo.tag("v1.0.0", head.as_object(), &author, "First release", false)
+        .unwrap();
+
+    manager(&dir.path().join("work"))
+        .push_tags("app", &PushAuth::default(), |_| {})
+        .unwrap();
+
+    assert!(origin.find_reference("refs/tags/v1.0.0").is_ok());
+}
+
+#[test]
+fn test_unknown_branch_and_missing_origin_are_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_repo, _origin) = with_origin(dir.path());
+    let git = manager(&dir.path().join("work"));
+    let err = git
+        .push_branch("app", "nope", &PushAuth::default(), |_| {})
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("No local branch 'nope'"),
+        "{}",
+        err
+    );
+
+    let lonely = dir.path().join("lonely");
+    commit(&init(&lonely), "a.txt", "a", "First");
+    let err = manager(&lonely)
+        .push_branch("app", "main", &PushAuth::default(), |_| {})
+        .unwrap_err();
+    assert!(err.to_string().contains("no remote 'origin'"), "{}", err);
+}
+
+/// An HTTP remote that answers every request with 401
+fn refusing_remote() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/app.git", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let _ = (&stream).write_all(
+                b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"git\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        }
+    });
+    url
+}
+
+#[test]
+fn test_refused_credentials_are_listed() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    commit(&repo, "a.txt", "a", "First");
+    repo.remote("origin", &refusing_remote()).unwrap();
+    let git = manager(dir.path());
+
+    let err = git
+        .push_branch("app", "main", &PushAuth::default(), |_| {})
+        .unwrap_err();
+    assert!(err.to_string().contains("no credentials for"), "{}", err);
+
+    let auth = PushAuth {
+        password: Some((Some("alice".to_string()), "wrong".to_string())),
+        ..PushAuth::default()
+    };
+    let err = git.push_branch("app", "main", &auth, |_| {}).unwrap_err();
+    assert!(
+        err.to_string().contains("tried password for alice"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_auth_from_credentials() {
+    let key = Credential::new_ssh(
+        "deploy".to_string(),
+        "Deploy".to_string(),
+        "git".to_string(),
+        "/keys/deploy".to_string(),
+        None,
+        Some("secret".to_string()),
+    );
+    let auth = PushAuth::from_credential(&key, None);
+    assert_eq!(auth.ssh_username.as_deref(), Some("git"));
+    assert!(matches!(
+        auth.ssh_key,
+        Some(AuthMethod::Key { ref path, .. }) if path == "/keys/deploy"
+    ));
+    let auth = PushAuth::from_credential(&key, Some("-----BEGIN KEY-----".to_string()));
+    assert!(matches!(auth.ssh_key, Some(AuthMethod::InlineKey { .. })));
+    assert!(auth.password.is_none());
+
+    let basic = Credential::new_basic_auth(
+        "gh".to_string(),
+        "GitHub".to_string(),
+        "alice".to_string(),
+        "pw".to_string(),
+        None,
+    );
+    let auth = PushAuth::from_credential(&basic, None);
+    assert_eq!(
+        auth.password,
+        Some((Some("alice".to_string()), "pw".to_string()))
+    );
+    assert!(auth.ssh_key.is_none());
+
+    let token = Credential::new_api_token(
+        "pat".to_string(),
+        "PAT".to_string(),
+        "ghp_x".to_string(),
+        None,
+    );
+    let auth = PushAuth::from_credential(&token, None);
+    assert_eq!(auth.password, Some((None, "ghp_x".to_string())));
+}
@@ -0,0 +1,153 @@
+//! `GitManager::repo_status` on throwaway repositories
+
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+use pctrl_core::GitRepo;
+use pctrl_git::GitManager;
+use std::path::Path;
+
+fn init(dir: &Path) -> Repository {
+    let mut options = RepositoryInitOptions::new();
+    options.initial_head("main");
+    Repository::init_opts(dir, &options).unwrap()
+}
+
+/// Commit `file` with `content` on top of HEAD
+fn commit(repo: &Repository, file: &str, content: &str, message: &str) -> Oid {
+    std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &author(), &author(), message, &tree, &parents)
+        .unwrap()
+}
+
+fn author() -> Signature<'static> {
+    Signature::now("Alice", "alice@example.com").unwrap()
+}
+
+fn manager(dir: &Path) -> GitManager {
+    let mut git = GitManager::new();
+    git.add_repo(GitRepo {
+        id: "app".to_string(),
+        name: "App".to_string(),
+        path: dir.to_string_lossy().to_string(),
+        remote_url: None,
+        credential_id: None,
+    });
+    git
+}
+
+#[test]
+fn test_clean_repo_without_remote() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    let first = commit(&repo, "README.md", "hi", "Initial commit");
+
+    let status = manager(dir.path()).repo_status("app").unwrap();
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert_eq!(status.upstream, None);
+    assert_eq!((status.ahead, status.behind), (0, 0));
+    assert!(!status.is_dirty());
+    assert_eq!(status.changes(), "clean");
+    let last = status.last_commit.unwrap();
+    assert_eq!(last.hash, first.to_string());
+    assert_eq!(last.short_hash, first.to_string()[..7]);
+    assert_eq!(last.summary, "Initial commit");
+    assert_eq!(last.author, "Alice");
+}
+
+#[test]
+fn test_changes_are_broken_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    commit(&repo, "a.txt", "a", "Initial commit");
+    std::fs::write(dir.path().join("a.txt"), "changed").unwrap();
+    std::fs::write(dir.path().join("b.txt"), "staged").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("b.txt")).unwrap();
+    index.write().unwrap();
+    std::fs::write(dir.path().join("c.txt"), "new").unwrap();
+    std::fs::write(dir.path().join("d.txt"), "new").unwrap();
+
+    let status = manager(dir.path()).repo_status("app").unwrap();
+    assert_eq!(
+        (status.staged, status.unstaged, status.untracked),
+        (1, 1, 2)
+    );
+    assert!(status.is_dirty());
+    assert_eq!(status.changes(), "1 staged, 1 unstaged, 2 untracked");
+}
+
+#[test]
+fn test_ahead_and_behind_the_upstream() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    let base = commit(&repo, "a.txt", "a", "Base");
+    repo.remote("origin", "https://example.invalid/app.git")
+        .unwrap();
+    repo.reference("refs/remotes/origin/main", base, false, "fetched")
+        .unwrap();
+    repo.find_branch("main", git2::BranchType::Local)
+        .unwrap()
+        .set_upstream(Some("origin/main"))
+        .unwrap();
+    commit(&repo, "a.txt", "b", "Local 1");
+    commit(&repo, "a.txt", "c", "Local 2");
+    // Someone else pushed on top of the base
+    let base = repo.find_commit(base).unwrap();
+    repo.commit(
+        Some("refs/remotes/origin/main"),
+        &author(),
+        &author(),
+        "Remote",
+        &base.tree().unwrap(),
+        &[&base],
+    )
+    .unwrap();
+
+    let status = manager(dir.path()).repo_status("app").unwrap();
+    assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+    assert_eq!((status.ahead, status.behind), (2, 1));
+    assert_eq!(status.last_commit.unwrap().summary, "Local 2");
+}
+
+#[test]
+fn test_detached_head_has_no_branch() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init(dir.path());
+    let first = commit(&repo, "a.txt", "a", "First");
+    commit(&repo, "a.txt", "b", "Second");
+    repo.set_head_detached(first).unwrap();
+
+    let status = manager(dir.path()).repo_status("app").unwrap();
+    assert_eq!(status.branch, None);
+    assert_eq!(status.upstream, None);
+    assert_eq!(
+        status.head(),
+        format!("detached at {}", &first.to_string()[..7])
+    );
+}
+
+#[test]
+fn test_repo_without_commits() {
+    let dir = tempfile::tempdir().unwrap();
+    init(dir.path());
+    std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+
+    let status = manager(dir.path()).repo_status("app").unwrap();
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert_eq!(status.last_commit, None);
+    assert_eq!(status.untracked, 1);
+}
+
+#[test]
+fn test_missing_repo_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(manager(&dir.path().join("gone"))
+        .repo_status("app")
+        .is_err());
+    assert!(manager(dir.path()).repo_status("other").is_err());
+}
@@ -0,0 +1,147 @@
+//! Hetzner Cloud API client
+
+use crate::CloudInstance;
+use pctrl_core::Result;
+use reqwest::Client;
+use serde::Deserialize;
+
+const API_URL: &str = "https://api.hetzner.cloud/v1";
+
+/// Hetzner Cloud client (token from an ApiToken credential)
+pub struct HetznerClient {
+    token: String,
+    base_url: String,
+    client: Client,
+}
+
+impl HetznerClient {
+    pub fn new(token: &str) -> Self {
+        Self::with_base_url(token, API_URL)
+    }
+
+    /// Client against a different API endpoint (e.g. a mock server)
+    pub fn with_base_url(token: &str, base_url: &str) -> Self {
+        Self {
+            token: token.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// List all servers in the project, following pagination
+    pub async fn list_servers(&self) -> Result<Vec<CloudInstance>> {
+        let mut instances = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!("{}/servers?page={}&per_page=50", self.base_url, page);
+            let response = self
+                .client
+                .get(&url)
+                .bearer_auth(&self.token)
+                .timeout(std::time::Duration::from_secs(15))
+                .send()
+                .await
+                .map_err(|e| pctrl_core::Error::Provider(format!("Request failed: {}", e)))?;
+
+            if !response.status().is_success() {
+                return Err(pctrl_core::Error::Provider(format!(
+                    "Hetzner API request failed with status: {}",
+                    response.status()
+                )));
+            }
+
+            let body: ServersResponse = response.json().await.map_err(|e| {
+                pctrl_core::Error::Provider(format!("Failed to parse response: {}", e))
+            })?;
+
+            instances.extend(body.servers.into_iter().map(HetznerServer::into_instance));
+
+            match body.meta.and_then(|m| m.pagination.next_page) {
+                Some(next) if next > page => page = next,
+                _ => break,
+            }
+        }
+
+        Ok(instances)
+    }
+}
+
+#[derive(Deserialize)]
+struct ServersResponse {
+    servers: Vec<HetznerServer>,
+    meta: Option<Meta>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    pagination: Pagination,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    next_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct HetznerServer {
+    id: u64,
+    name: String,
+    status: String,
+    public_net: PublicNet,
+    server_type: HetznerServerType,
+    datacenter: Option<Datacenter>,
+}
+
+#[derive(Deserialize)]
+struct PublicNet {
+    ipv4: Option<IpEntry>,
+    ipv6: Option<IpEntry>,
+}
+
+#[derive(Deserialize)]
+struct IpEntry {
+    ip: String,
+}
+
+#[derive(Deserialize)]
+struct HetznerServerType {
+    cores: Option<u8>,
+    memory: Option<f64>,
+    disk: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Datacenter {
+    location: Location,
+}
+
+#[derive(Deserialize)]
+struct Location {
+    city: Option<String>,
+    country: Option<String>,
+    name: String,
+}
+
+impl HetznerServer {
+    fn into_instance(self) -> CloudInstance {
+        let location = self
+            .datacenter
+            .map(|dc| match (dc.location.city, dc.location.country) {
+                (Some(city), Some(country)) => format!("{}, {}", city, country),
+                _ => dc.location.name,
+            });
+
+        CloudInstance {
+            id: self.id.to_string(),
+            name: self.name,
+            public_ipv4: self.public_net.ipv4.map(|i| i.ip),
+            public_ipv6: self.public_net.ipv6.map(|i| i.ip),
+            location,
+            status: self.status,
+            cpu_cores: self.server_type.cores,
+            ram_gb: self.server_type.memory.map(|m| m.round() as u16),
+            disk_gb: self.server_type.disk,
+        }
+    }
+}
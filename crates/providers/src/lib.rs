@@ -0,0 +1,88 @@
+//! pctrl-providers - Cloud provider API clients
+//!
+//! Lists the instances in a provider account so pctrl can compare them
+//! against its own server inventory.
+
+mod hetzner;
+mod reconcile;
+
+pub use hetzner::HetznerClient;
+pub use reconcile::{reconcile, MatchKind, ReconcileMatch, ReconcileReport};
+
+use pctrl_core::{Server, ServerSpecs, ServerType};
+use serde::{Deserialize, Serialize};
+
+/// A server instance as reported by a cloud provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudInstance {
+    /// Provider-side ID
+    pub id: String,
+    pub name: String,
+    pub public_ipv4: Option<String>,
+    /// IPv6 network or address (Hetzner reports a /64)
+    pub public_ipv6: Option<String>,
+    pub location: Option<String>,
+    pub status: String,
+    pub cpu_cores: Option<u8>,
+    pub ram_gb: Option<u16>,
+    pub disk_gb: Option<u32>,
+}
+
+impl CloudInstance {
+    /// Specs in pctrl's format
+    pub fn specs(&self) -> Option<ServerSpecs> {
+        if self.cpu_cores.is_none() && self.ram_gb.is_none() && self.disk_gb.is_none() {
+            return None;
+        }
+        Some(ServerSpecs {
+            cpu_cores: self.cpu_cores,
+            ram_gb: self.ram_gb,
+            disk_gb: self.disk_gb,
+        })
+    }
+
+    /// New pctrl server for this instance (used by `server reconcile --import`)
+    pub fn to_server(&self, provider: &str) -> Server {
+        Server {
+            id: self.name.to_lowercase().replace(' ', "-"),
+            name: self.name.clone(),
+            host: self
+                .public_ipv4
+                .clone()
+                .unwrap_or_else(|| self.name.clone()),
+            server_type: ServerType::Vps,
+            provider: Some(provider.to_string()),
+            credential_id: None,
+            location: self.location.clone(),
+            specs: self.specs(),
+            notes: None,
+            requires_vpn: None,
+            max_containers: None,
+            max_memory_mb_allocated: None,
+        }
+    }
+}
+
+/// Supported providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Hetzner,
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provider::Hetzner => write!(f, "hetzner"),
+        }
+    }
+}
+
+impl std::str::FromStr for Provider {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hetzner" | "hcloud" => Ok(Provider::Hetzner),
+            _ => Err(format!("Unsupported provider: {} (supported: hetzner)", s)),
+        }
+    }
+}
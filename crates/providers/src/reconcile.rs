@@ -0,0 +1,122 @@
+//! Match provider instances against pctrl servers
+//!
+//! Precedence: a public IP match always wins over a name match, and every
+//! server and instance is matched at most once.
+
+use crate::CloudInstance;
+use pctrl_core::Server;
+use std::net::Ipv6Addr;
+
+/// How a server was matched to an instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Ip,
+    Name,
+}
+
+/// A stored server paired with its cloud instance
+#[derive(Debug, Clone)]
+pub struct ReconcileMatch {
+    pub server: Server,
+    pub instance: CloudInstance,
+    pub by: MatchKind,
+}
+
+/// Result of comparing a provider account with the pctrl inventory
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileReport {
+    pub matched: Vec<ReconcileMatch>,
+    /// In the cloud, unknown to pctrl
+    pub unmanaged: Vec<CloudInstance>,
+    /// In pctrl with this provider, gone from the cloud
+    pub stale: Vec<Server>,
+}
+
+/// Compare `instances` from `provider` against the stored `servers`
+pub fn reconcile(
+    instances: &[CloudInstance],
+    servers: &[Server],
+    provider: &str,
+) -> ReconcileReport {
+    let mut server_used = vec![false; servers.len()];
+    let mut instance_match: Vec<Option<(usize, MatchKind)>> = vec![None; instances.len()];
+
+    // Pass 1: public IP
+    for (i, instance) in instances.iter().enumerate() {
+        if let Some(s) = servers
+            .iter()
+            .enumerate()
+            .position(|(s, server)| !server_used[s] && ip_matches(&server.host, instance))
+        {
+            server_used[s] = true;
+            instance_match[i] = Some((s, MatchKind::Ip));
+        }
+    }
+
+    // Pass 2: name (case-insensitive) for whatever is left
+    for (i, instance) in instances.iter().enumerate() {
+        if instance_match[i].is_some() {
+            continue;
+        }
+        if let Some(s) = servers.iter().enumerate().position(|(s, server)| {
+            !server_used[s] && server.name.eq_ignore_ascii_case(&instance.name)
+        }) {
+            server_used[s] = true;
+            instance_match[i] = Some((s, MatchKind::Name));
+        }
+    }
+
+    let mut report = ReconcileReport::default();
+    for (instance, matched) in instances.iter().zip(instance_match) {
+        match matched {
+            Some((s, by)) => report.matched.push(ReconcileMatch {
+                server: servers[s].clone(),
+                instance: instance.clone(),
+                by,
+            }),
+            None => report.unmanaged.push(instance.clone()),
+        }
+    }
+    report.stale = servers
+        .iter()
+        .zip(server_used)
+        .filter(|(server, used)| {
+            !used
+                && server
+                    .provider
+                    .as_deref()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(provider))
+        })
+        .map(|(server, _)| server.clone())
+        .collect();
+
+    report
+}
+
+/// Whether a server host is one of the instance's public addresses
+fn ip_matches(host: &str, instance: &CloudInstance) -> bool {
+    let host = host.trim();
+    if instance.public_ipv4.as_deref() == Some(host) {
+        return true;
+    }
+    let Some(net) = instance.public_ipv6.as_deref() else {
+        return false;
+    };
+    // "2a01:4f8:c17:1234::/64": any address in the /64 the instance got
+    let (net, whole_network) = match net.split_once('/') {
+        Some((addr, _)) => (addr, true),
+        None => (net, false),
+    };
+    let (Ok(net), Ok(host)) = (
+        net.parse::<Ipv6Addr>(),
+        host.trim_matches(|c| c == '[' || c == ']')
+            .parse::<Ipv6Addr>(),
+    ) else {
+        return false;
+    };
+    if whole_network {
+        u128::from(net) >> 64 == u128::from(host) >> 64
+    } else {
+        net == host
+    }
+}
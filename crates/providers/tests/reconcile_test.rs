@@ -0,0 +1,130 @@
+use pctrl_core::{Server, ServerType};
+use pctrl_providers::{reconcile, CloudInstance, MatchKind};
+
+fn instance(name: &str, ipv4: Option<&str>, ipv6: Option<&str>) -> CloudInstance {
+    CloudInstance {
+        id: name.to_string(),
+        name: name.to_string(),
+        public_ipv4: ipv4.map(String::from),
+        public_ipv6: ipv6.map(String::from),
+        location: Some("Falkenstein, DE".to_string()),
+        status: "running".to_string(),
+        cpu_cores: Some(2),
+        ram_gb: Some(4),
+        disk_gb: Some(40),
+    }
+}
+
+fn server(name: &str, host: &str, provider: Option<&str>) -> Server {
+    Server {
+        id: name.to_lowercase(),
+        name: name.to_string(),
+        host: host.to_string(),
+        server_type: ServerType::Vps,
+        provider: provider.map(String::from),
+        credential_id: None,
+        location: None,
+        specs: None,
+        notes: None,
+        requires_vpn: None,
+        max_containers: None,
+        max_memory_mb_allocated: None,
+    }
+}
+
+#[test]
+fn test_ip_match_wins_over_name() {
+    let instances = vec![instance("web", Some("1.2.3.4"), None)];
+    // "web" by name points elsewhere; "prod" holds the instance's IP
+    let servers = vec![
+        server("web", "9.9.9.9", Some("hetzner")),
+        server("prod", "1.2.3.4", Some("hetzner")),
+    ];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert_eq!(report.matched.len(), 1);
+    assert_eq!(report.matched[0].server.name, "prod");
+    assert_eq!(report.matched[0].by, MatchKind::Ip);
+    assert!(report.unmanaged.is_empty());
+    assert_eq!(report.stale.len(), 1);
+    assert_eq!(report.stale[0].name, "web");
+}
+
+#[test]
+fn test_name_match_is_case_insensitive() {
+    let instances = vec![instance("Web-1", Some("1.2.3.4"), None)];
+    let servers = vec![server("web-1", "web1.example.com", Some("hetzner"))];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert_eq!(report.matched.len(), 1);
+    assert_eq!(report.matched[0].by, MatchKind::Name);
+}
+
+#[test]
+fn test_ipv6_network_match() {
+    let instances = vec![instance("v6", None, Some("2a01:4f8:c17:1234::/64"))];
+    let servers = vec![server("other", "2a01:4f8:c17:1234::1", None)];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert_eq!(report.matched.len(), 1);
+    assert_eq!(report.matched[0].by, MatchKind::Ip);
+}
+
+#[test]
+fn test_ipv6_match_compares_addresses_not_text() {
+    let instances = vec![
+        instance("v6", None, Some("2a01:4f8:c17:1234::/64")),
+        instance("single", None, Some("2a01:4f8:c17:9::1")),
+    ];
+    let servers = vec![
+        // Shares the text prefix but lies in another /64
+        server("neighbour", "2a01:4f8:c17:12::1", None),
+        // Same network, written out in full and in brackets
+        server("full", "[2a01:04f8:0c17:1234:0000:0000:0000:0002]", None),
+        server("zeros", "2a01:4f8:c17:9:0:0:0:1", None),
+    ];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert!(report.matched.iter().all(|m| m.by == MatchKind::Ip));
+    let mut matched: Vec<_> = report
+        .matched
+        .iter()
+        .map(|m| (m.server.name.as_str(), m.instance.name.as_str()))
+        .collect();
+    matched.sort();
+    assert_eq!(matched, vec![("full", "v6"), ("zeros", "single")]);
+}
+
+#[test]
+fn test_each_server_matches_once() {
+    let instances = vec![
+        instance("db", Some("1.2.3.4"), None),
+        instance("db", Some("5.6.7.8"), None),
+    ];
+    let servers = vec![server("db", "10.0.0.1", Some("hetzner"))];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert_eq!(report.matched.len(), 1);
+    assert_eq!(report.unmanaged.len(), 1);
+    assert_eq!(report.unmanaged[0].public_ipv4.as_deref(), Some("5.6.7.8"));
+}
+
+#[test]
+fn test_stale_only_for_same_provider() {
+    let instances = vec![];
+    let servers = vec![
+        server("gone", "1.1.1.1", Some("Hetzner")),
+        server("aws-box", "2.2.2.2", Some("aws")),
+        server("home", "192.168.1.10", None),
+    ];
+
+    let report = reconcile(&instances, &servers, "hetzner");
+
+    assert_eq!(report.stale.len(), 1);
+    assert_eq!(report.stale[0].name, "gone");
+}
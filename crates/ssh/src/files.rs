@@ -0,0 +1,322 @@
+//! SFTP copies of files and directory trees (`ssh put/get`)
+//!
+//! Unlike [`SshManager::upload`] these don't resume or verify; they copy
+//! whole trees and keep permissions, through the same copy loop. Each file
+//! is written to a `.part` next to its destination and renamed into place,
+//! and nothing at the destination is replaced unless
+//! [`CopyOptions::overwrite`] says so. New directories stay writable while
+//! they're filled and get their own mode at the end, so read-only trees
+//! copy too.
+
+use crate::transfer::{rename_over, ssh_err, Copier};
+use crate::{OnProgress, SshManager};
+use pctrl_core::transfer;
+use pctrl_core::Result;
+use ssh2::{FileStat, OpenFlags, OpenType, Sftp};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// How a copy runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyOptions {
+    /// Copy a directory with everything in it; without this, directories
+    /// are refused
+    pub recursive: bool,
+    /// Replace files that already exist at the destination
+    pub overwrite: bool,
+}
+
+/// What a finished copy did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyReport {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Owner bits a directory keeps while the copy fills it
+const FILLING: i32 = 0o700;
+
+/// A file or directory of a copy, with where it goes
+struct Entry {
+    local: PathBuf,
+    remote: String,
+    dir: bool,
+    size: u64,
+    /// Permission bits
+    mode: i32,
+}
+
+impl SshManager {
+    /// Upload `local` to `remote` over SFTP. Files get `mode`, or keep
+    /// their local permissions when it's `None`; directories always keep
+    /// theirs. Links inside a directory are skipped.
+    pub fn upload_file(
+        &self,
+        id: &str,
+        local: &Path,
+        remote: &str,
+        mode: Option<i32>,
+        options: CopyOptions,
+        on_progress: &mut OnProgress,
+    ) -> Result<CopyReport> {
+        let meta = fs::metadata(local)
+            .map_err(|e| ssh_err(&format!("Can't read '{}'", local.display()), e))?;
+        let mut entries = vec![Entry {
+            local: local.to_path_buf(),
+            remote: remote.to_string(),
+            dir: meta.is_dir(),
+            size: meta.len(),
+            mode: local_mode(&meta),
+        }];
+        if meta.is_dir() {
+            if !options.recursive {
+                return Err(is_a_directory(&local.display().to_string()));
+            }
+            local_children(local, remote, &mut entries)?;
+        }
+        if let Some(mode) = mode {
+            for entry in entries.iter_mut().filter(|e| !e.dir) {
+                entry.mode = mode;
+            }
+        }
+
+        let session = self.connect(id)?;
+        let sftp = session.sftp().map_err(|e| ssh_err("SFTP unavailable", e))?;
+        if !options.overwrite {
+            let mut files = entries.iter().filter(|e| !e.dir);
+            if let Some(taken) = files.find(|e| sftp.stat(Path::new(&e.remote)).is_ok()) {
+                return Err(already_exists(&taken.remote));
+            }
+        }
+
+        let mut copier = Copier::new(total(&entries), 0, None, false, on_progress);
+        let mut created = Vec::new();
+        for entry in &entries {
+            let remote = Path::new(&entry.remote);
+            if entry.dir {
+                if !sftp.stat(remote).is_ok_and(|s| s.is_dir()) {
+                    sftp.mkdir(remote, entry.mode | FILLING)
+                        .map_err(|e| ssh_err(&format!("Can't create '{}'", entry.remote), e))?;
+                    // The server's umask applied here too
+                    set_remote_mode(&sftp, &entry.remote, entry.mode | FILLING)?;
+                    created.push(entry);
+                }
+                continue;
+            }
+
+            let part = transfer::partial_path(&entry.remote);
+            let mut source = File::open(&entry.local)?;
+            let flags = OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE;
+            let mut dest = sftp
+                .open_mode(Path::new(&part), flags, entry.mode, OpenType::File)
+                .map_err(|e| ssh_err(&format!("Can't write '{}'", part), e))?;
+            let copied = copier.copy(&mut source, &mut dest);
+            drop(dest);
+            if let Err(e) = copied {
+                let _ = sftp.unlink(Path::new(&part));
+                return Err(e);
+            }
+            // The server's umask applied when the file was created
+            set_remote_mode(&sftp, &part, entry.mode)?;
+            rename_over(&sftp, &part, &entry.remote)?;
+        }
+        // Innermost first, so a read-only parent doesn't get in the way
+        for entry in created.iter().rev() {
+            set_remote_mode(&sftp, &entry.remote, entry.mode)?;
+        }
+        Ok(report(&copier))
+    }
+
+    /// Download `remote` to `local` over SFTP, keeping the remote
+    /// permissions. Links inside a directory are skipped.
+    pub fn download_file(
+        &self,
+        id: &str,
+        remote: &str,
+        local: &Path,
+        options: CopyOptions,
+        on_progress: &mut OnProgress,
+    ) -> Result<CopyReport> {
+        let session = self.connect(id)?;
+        let sftp = session.sftp().map_err(|e| ssh_err("SFTP unavailable", e))?;
+        let stat = sftp
+            .stat(Path::new(remote))
+            .map_err(|e| ssh_err(&format!("Can't read '{}'", remote), e))?;
+        let mut entries = vec![remote_entry(local.to_path_buf(), remote.to_string(), &stat)];
+        if stat.is_dir() {
+            if !options.recursive {
+                return Err(is_a_directory(remote));
+            }
+            remote_children(&sftp, remote, local, &mut entries)?;
+        }
+        if !options.overwrite {
+            let mut files = entries.iter().filter(|e| !e.dir);
+            if let Some(taken) = files.find(|e| e.local.exists()) {
+                return Err(already_exists(&taken.local.display().to_string()));
+            }
+        }
+
+        let mut copier = Copier::new(total(&entries), 0, None, false, on_progress);
+        let mut created = Vec::new();
+        for entry in &entries {
+            if entry.dir {
+                if !entry.local.is_dir() {
+                    fs::create_dir(&entry.local)?;
+                    set_local_mode(&entry.local, entry.mode | FILLING)?;
+                    created.push(entry);
+                }
+                continue;
+            }
+
+            let part = PathBuf::from(transfer::partial_path(&entry.local.to_string_lossy()));
+            let mut source = sftp
+                .open(Path::new(&entry.remote))
+                .map_err(|e| ssh_err(&format!("Can't open '{}'", entry.remote), e))?;
+            let mut dest = File::create(&part)?;
+            if let Err(e) = copier.copy(&mut source, &mut dest) {
+                drop(dest);
+                let _ = fs::remove_file(&part);
+                return Err(e);
+            }
+            dest.sync_all()?;
+            set_local_mode(&part, entry.mode)?;
+            fs::rename(&part, &entry.local)?;
+        }
+        for entry in created.iter().rev() {
+            set_local_mode(&entry.local, entry.mode)?;
+        }
+        Ok(report(&copier))
+    }
+}
+
+/// Bytes of all files of a copy
+fn total(entries: &[Entry]) -> u64 {
+    entries.iter().map(|e| e.size).sum()
+}
+
+fn report(copier: &Copier) -> CopyReport {
+    CopyReport {
+        files: copier.files(),
+        bytes: copier.copied(),
+    }
+}
+
+/// The files and directories under local directory `dir`, depth first
+fn local_children(dir: &Path, remote: &str, entries: &mut Vec<Entry>) -> Result<()> {
+    let mut children = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    children.sort_by_key(|c| c.file_name());
+    for child in children {
+        // Not following links, so a link to a parent can't loop
+        let meta = child.metadata()?;
+        if !meta.is_dir() && !meta.is_file() {
+            continue;
+        }
+        let remote = join(remote, &child.file_name().to_string_lossy());
+        entries.push(Entry {
+            local: child.path(),
+            remote: remote.clone(),
+            dir: meta.is_dir(),
+            size: meta.len(),
+            mode: local_mode(&meta),
+        });
+        if meta.is_dir() {
+            local_children(&child.path(), &remote, entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// The files and directories under remote directory `dir`, depth first
+fn remote_children(sftp: &Sftp, dir: &str, local: &Path, entries: &mut Vec<Entry>) -> Result<()> {
+    let mut children = sftp
+        .readdir(Path::new(dir))
+        .map_err(|e| ssh_err(&format!("Can't list '{}'", dir), e))?;
+    children.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (path, stat) in children {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        // Listings carry the links themselves, which are skipped
+        if !stat.is_dir() && !stat.is_file() {
+            continue;
+        }
+        let entry = remote_entry(local.join(name), join(dir, &name.to_string_lossy()), &stat);
+        let (remote, local) = (entry.remote.clone(), entry.local.clone());
+        entries.push(entry);
+        if stat.is_dir() {
+            remote_children(sftp, &remote, &local, entries)?;
+        }
+    }
+    Ok(())
+}
+
+fn remote_entry(local: PathBuf, remote: String, stat: &FileStat) -> Entry {
+    let dir = stat.is_dir();
+    let fallback = if dir { 0o755 } else { 0o644 };
+    Entry {
+        local,
+        remote,
+        dir,
+        size: if dir { 0 } else { stat.size.unwrap_or(0) },
+        mode: stat.perm.map(|p| (p & 0o7777) as i32).unwrap_or(fallback),
+    }
+}
+
+/// `dir/name` on the server, which always uses `/`
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn set_remote_mode(sftp: &Sftp, path: &str, mode: i32) -> Result<()> {
+    let stat = FileStat {
+        size: None,
+        uid: None,
+        gid: None,
+        perm: Some(mode as u32),
+        atime: None,
+        mtime: None,
+    };
+    sftp.setstat(Path::new(path), stat)
+        .map_err(|e| ssh_err(&format!("Can't set the mode of '{}'", path), e))
+}
+
+#[cfg(unix)]
+fn local_mode(meta: &fs::Metadata) -> i32 {
+    use std::os::unix::fs::PermissionsExt;
+    (meta.permissions().mode() & 0o7777) as i32
+}
+
+/// Windows only knows read-only
+#[cfg(not(unix))]
+fn local_mode(meta: &fs::Metadata) -> i32 {
+    match (meta.is_dir(), meta.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    }
+}
+
+#[cfg(unix)]
+fn set_local_mode(path: &Path, mode: i32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode as u32))
+}
+
+#[cfg(not(unix))]
+fn set_local_mode(_path: &Path, _mode: i32) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn is_a_directory(path: &str) -> pctrl_core::Error {
+    pctrl_core::Error::Ssh(format!(
+        "'{}' is a directory; copy it recursively to include its contents",
+        path
+    ))
+}
+
+fn already_exists(path: &str) -> pctrl_core::Error {
+    pctrl_core::Error::Ssh(format!(
+        "'{}' already exists and overwriting wasn't asked for",
+        path
+    ))
+}
@@ -0,0 +1,47 @@
+//! Private keys stored inline in a credential, on disk for as long as
+//! something needs a path
+
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// A private key written to a temporary file only the current user can
+/// read, for the system `ssh` binary and libssh2 builds that can't take a
+/// key from memory. The file is deleted when this is dropped.
+pub struct KeyFile {
+    file: NamedTempFile,
+}
+
+impl KeyFile {
+    /// Write `key` (the text of a key file) to a new file with a unique name
+    pub fn write(key: &str) -> pctrl_core::Result<Self> {
+        let error = |e: std::io::Error| {
+            pctrl_core::Error::Ssh(format!(
+                "Failed to write the key to a temporary file: {}",
+                e
+            ))
+        };
+        let mut file = tempfile::Builder::new()
+            .prefix("pctrl-key-")
+            .tempfile()
+            .map_err(error)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.as_file()
+                .set_permissions(std::fs::Permissions::from_mode(0o600))
+                .map_err(error)?;
+        }
+        file.write_all(key.as_bytes()).map_err(error)?;
+        // OpenSSH refuses keys without a final newline
+        if !key.ends_with('\n') {
+            file.write_all(b"\n").map_err(error)?;
+        }
+        file.flush().map_err(error)?;
+        Ok(Self { file })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
@@ -1,21 +1,93 @@
+pub use files::{CopyOptions, CopyReport};
+pub use key_file::KeyFile;
 use pctrl_core::{AuthMethod, Result, ServerSpecs, SshConnection};
-use ssh2::Session;
-use std::net::TcpStream;
+pub use ssh2::Session;
+use std::net::{TcpStream, ToSocketAddrs};
 use std::path::Path;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+pub use transfer::{OnProgress, TransferOptions, TransferReport};
+
+mod files;
+mod key_file;
+mod transfer;
+
+/// How often a streamed command's silence is checked
+const STREAM_POLL: Duration = Duration::from_secs(1);
 
 /// SSH connection manager
 pub struct SshManager {
     connections: Vec<SshConnection>,
+    options: ConnectOptions,
+}
+
+/// How sessions are opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// Limit for the TCP connect (per resolved address) and for the
+    /// handshake and authentication
+    pub connect_timeout: Duration,
+    /// Limit for each blocking libssh2 call once connected, e.g. a read
+    /// waiting for command output; `None` waits indefinitely. Streamed
+    /// commands go by `stream_timeout` instead.
+    pub timeout: Option<Duration>,
+    /// How long a command run with [`SshManager::execute_streaming`] or a
+    /// script may go without output; `None` lets it run as long as it takes, with
+    /// keepalives noticing a dead server meanwhile
+    pub stream_timeout: Option<Duration>,
+    /// Send keepalives this often, so idle sessions aren't dropped by
+    /// firewalls and a dead server is noticed
+    pub keepalive_interval: Option<Duration>,
+    pub tcp_nodelay: bool,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Some(Duration::from_secs(300)),
+            stream_timeout: None,
+            keepalive_interval: Some(Duration::from_secs(30)),
+            tcp_nodelay: true,
+        }
+    }
+}
+
+/// Terminal requested for an interactive shell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtyRequest {
+    /// Terminal type, e.g. `xterm-256color`
+    pub term: String,
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// Input for an interactive shell, from the local terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellInput {
+    /// Keystrokes, as typed in raw mode
+    Data(Vec<u8>),
+    /// The local window was resized
+    Resize { cols: u32, rows: u32 },
 }
 
 impl SshManager {
     pub fn new() -> Self {
+        Self::with_options(ConnectOptions::default())
+    }
+
+    pub fn with_options(options: ConnectOptions) -> Self {
         Self {
             connections: Vec::new(),
+            options,
         }
     }
 
+    pub fn options(&self) -> &ConnectOptions {
+        &self.options
+    }
+
     /// Add a new SSH connection
     pub fn add_connection(&mut self, connection: SshConnection) {
         self.connections.push(connection);
@@ -34,169 +106,58 @@ impl SshManager {
     /// Connect to an SSH host with explicit password
     pub fn connect_with_password(&self, id: &str, password: Option<&str>) -> Result<Session> {
         let conn = self
-            .connections
-            .iter()
-            .find(|c| c.id == id)
+            .get_connection(id)
             .ok_or_else(|| pctrl_core::Error::Ssh("Connection not found".to_string()))?;
-
-        let tcp = TcpStream::connect(format!("{}:{}", conn.host, conn.port))
-            .map_err(|e| pctrl_core::Error::Ssh(format!("TCP connection failed: {}", e)))?;
-
-        let mut session = Session::new()
-            .map_err(|e| pctrl_core::Error::Ssh(format!("Session creation failed: {}", e)))?;
-
-        session.set_tcp_stream(tcp);
-        session
-            .handshake()
-            .map_err(|e| pctrl_core::Error::Ssh(format!("SSH handshake failed: {}", e)))?;
-
-        match &conn.auth_method {
-            AuthMethod::Password => {
-                let pw = password.ok_or_else(|| {
-                    pctrl_core::Error::Ssh("Password required for authentication".to_string())
-                })?;
-                session.userauth_password(&conn.username, pw).map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Password authentication failed: {}", e))
-                })?;
-            }
-            AuthMethod::PublicKey { key_path } => {
-                session
-                    .userauth_pubkey_file(&conn.username, None, Path::new(key_path), None)
-                    .map_err(|e| {
-                        pctrl_core::Error::Ssh(format!("Public key authentication failed: {}", e))
-                    })?;
-            }
-            AuthMethod::Key { path, passphrase } => {
-                session
-                    .userauth_pubkey_file(
-                        &conn.username,
-                        None,
-                        Path::new(path),
-                        passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        pctrl_core::Error::Ssh(format!("Key authentication failed: {}", e))
-                    })?;
-            }
-            AuthMethod::Agent => {
-                let mut agent = session.agent().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to get SSH agent: {}", e))
-                })?;
-
-                agent.connect().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to connect to SSH agent: {}", e))
-                })?;
-
-                agent.list_identities().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to list agent identities: {}", e))
-                })?;
-
-                // Try each identity until one works
-                let mut authenticated = false;
-                for identity in agent.identities().unwrap_or_default() {
-                    if agent.userauth(&conn.username, &identity).is_ok() {
-                        authenticated = true;
-                        break;
-                    }
-                }
-
-                if !authenticated {
-                    return Err(pctrl_core::Error::Ssh(
-                        "SSH agent authentication failed: no valid identity found".to_string(),
-                    ));
-                }
-            }
-        }
-
+        let session = self.open_session(conn)?;
+        let password = match &conn.auth_method {
+            AuthMethod::Password => Some(password.ok_or_else(|| {
+                pctrl_core::Error::Ssh("Password required for authentication".to_string())
+            })?),
+            _ => None,
+        };
+        authenticate(&session, conn, password)?;
+        self.set_session_timeout(&session, self.options.timeout);
         Ok(session)
     }
 
     /// Test if a connection can be established (for health checks)
     pub fn test_connection(&self, id: &str, password: Option<&str>) -> Result<()> {
         let conn = self
-            .connections
-            .iter()
-            .find(|c| c.id == id)
+            .get_connection(id)
             .ok_or_else(|| pctrl_core::Error::Ssh("Connection not found".to_string()))?;
+        let session = self.open_session(conn)?;
 
-        // Try TCP connection with timeout
-        let addr = format!("{}:{}", conn.host, conn.port);
-        let tcp = TcpStream::connect_timeout(
-            &addr
-                .parse()
-                .map_err(|e| pctrl_core::Error::Ssh(format!("Invalid address: {}", e)))?,
-            Duration::from_secs(5),
-        )
-        .map_err(|e| pctrl_core::Error::Ssh(format!("TCP connection failed: {}", e)))?;
+        // Password auth without a password: the handshake is all there is to check
+        if matches!(conn.auth_method, AuthMethod::Password) && password.is_none() {
+            return Ok(());
+        }
+        authenticate(&session, conn, password)
+    }
+
+    /// TCP connect and SSH handshake, bounded by the connect timeout. The
+    /// session keeps that timeout for the authentication that follows.
+    fn open_session(&self, conn: &SshConnection) -> Result<Session> {
+        let tcp = connect_tcp(&conn.host, conn.port, self.options.connect_timeout)?;
+        if self.options.tcp_nodelay {
+            tcp.set_nodelay(true)
+                .map_err(|e| pctrl_core::Error::Ssh(format!("TCP setup failed: {}", e)))?;
+        }
 
         let mut session = Session::new()
             .map_err(|e| pctrl_core::Error::Ssh(format!("Session creation failed: {}", e)))?;
-
         session.set_tcp_stream(tcp);
+        self.set_session_timeout(&session, Some(self.options.connect_timeout));
         session
             .handshake()
             .map_err(|e| pctrl_core::Error::Ssh(format!("SSH handshake failed: {}", e)))?;
-
-        // For public key auth, try to authenticate
-        // For password auth without password provided, just check handshake succeeded
-        match &conn.auth_method {
-            AuthMethod::Password => {
-                if let Some(pw) = password {
-                    session.userauth_password(&conn.username, pw).map_err(|e| {
-                        pctrl_core::Error::Ssh(format!("Password authentication failed: {}", e))
-                    })?;
-                }
-                // If no password, just verify TCP + handshake works
-            }
-            AuthMethod::PublicKey { key_path } => {
-                session
-                    .userauth_pubkey_file(&conn.username, None, Path::new(key_path), None)
-                    .map_err(|e| {
-                        pctrl_core::Error::Ssh(format!("Public key authentication failed: {}", e))
-                    })?;
-            }
-            AuthMethod::Key { path, passphrase } => {
-                session
-                    .userauth_pubkey_file(
-                        &conn.username,
-                        None,
-                        Path::new(path),
-                        passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        pctrl_core::Error::Ssh(format!("Key authentication failed: {}", e))
-                    })?;
-            }
-            AuthMethod::Agent => {
-                let mut agent = session.agent().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to get SSH agent: {}", e))
-                })?;
-
-                agent.connect().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to connect to SSH agent: {}", e))
-                })?;
-
-                agent.list_identities().map_err(|e| {
-                    pctrl_core::Error::Ssh(format!("Failed to list agent identities: {}", e))
-                })?;
-
-                let mut authenticated = false;
-                for identity in agent.identities().unwrap_or_default() {
-                    if agent.userauth(&conn.username, &identity).is_ok() {
-                        authenticated = true;
-                        break;
-                    }
-                }
-
-                if !authenticated {
-                    return Err(pctrl_core::Error::Ssh(
-                        "SSH agent authentication failed".to_string(),
-                    ));
-                }
-            }
+        if let Some(interval) = self.options.keepalive_interval {
+            session.set_keepalive(false, interval.as_secs().clamp(1, u32::MAX as u64) as u32);
         }
+        Ok(session)
+    }
 
-        Ok(())
+    fn set_session_timeout(&self, session: &Session, timeout: Option<Duration>) {
+        session.set_timeout(timeout.map_or(0, millis));
     }
 
     /// Execute a command on a remote host
@@ -232,6 +193,265 @@ impl SshManager {
         Ok(output)
     }
 
+    /// Run a script by piping it to `sh -s` on the remote host.
+    ///
+    /// Returns stdout and stderr merged in the order they arrived, and the
+    /// exit status. Like [`Self::execute_streaming`], only `stream_timeout`
+    /// of silence ends a long run, not the session timeout.
+    pub fn execute_script_with_password(
+        &self,
+        id: &str,
+        script: &str,
+        password: Option<&str>,
+    ) -> Result<(String, i32)> {
+        use std::io::Write;
+
+        let session = self.connect_with_password(id, password)?;
+
+        let mut channel = session
+            .channel_session()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel creation failed: {}", e)))?;
+        channel
+            .handle_extended_data(ssh2::ExtendedData::Merge)
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel setup failed: {}", e)))?;
+
+        let (shell, args) = pctrl_core::script_body::STDIN_SHELL;
+        channel
+            .exec(&format!("{} {}", shell, args.join(" ")))
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Command execution failed: {}", e)))?;
+
+        channel
+            .write_all(pctrl_core::script_body::stdin_payload(script).as_bytes())
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to send script: {}", e)))?;
+        channel
+            .send_eof()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to send script: {}", e)))?;
+
+        read_streaming(&session, channel, self.options.stream_timeout, &mut |_| {})
+    }
+
+    /// Run a command on an open session, passing its output (stdout and
+    /// stderr merged) to `on_output` as it arrives.
+    ///
+    /// The session's own timeout doesn't apply: a deploy may be silent for
+    /// longer. Only `stream_timeout` of silence (see [`ConnectOptions`])
+    /// ends it early. Returns the whole output and the exit status.
+    pub fn execute_streaming(
+        session: &Session,
+        command: &str,
+        stream_timeout: Option<Duration>,
+        on_output: &mut dyn FnMut(&str),
+    ) -> Result<(String, i32)> {
+        let mut channel = session
+            .channel_session()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel creation failed: {}", e)))?;
+        channel
+            .handle_extended_data(ssh2::ExtendedData::Merge)
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel setup failed: {}", e)))?;
+        channel
+            .exec(command)
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Command execution failed: {}", e)))?;
+
+        read_streaming(session, channel, stream_timeout, on_output)
+    }
+
+    /// Run a command that doesn't end on its own (`journalctl -f`), passing
+    /// its output to `on_output` as it arrives, until `stop` is set.
+    ///
+    /// While no output arrives, `stop` is checked every `poll`. Returns the
+    /// exit status if the command ended by itself, `None` if it was stopped.
+    pub fn follow(
+        session: &Session,
+        command: &str,
+        on_output: &mut dyn FnMut(&str),
+        stop: &AtomicBool,
+        poll: Duration,
+    ) -> Result<Option<i32>> {
+        use std::io::Read;
+
+        let mut channel = session
+            .channel_session()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel creation failed: {}", e)))?;
+        channel
+            .handle_extended_data(ssh2::ExtendedData::Merge)
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel setup failed: {}", e)))?;
+        channel
+            .exec(command)
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Command execution failed: {}", e)))?;
+
+        // Reads give up after `poll` so the stop flag gets checked
+        let timeout = session.timeout();
+        session.set_timeout(millis(poll));
+        let mut buf = [0u8; 4096];
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                session.set_timeout(timeout);
+                return Ok(None);
+            }
+            match channel.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => on_output(&String::from_utf8_lossy(&buf[..n])),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                    // Idle: keep the session alive if keepalives are on
+                    let _ = session.keepalive_send();
+                }
+                Err(e) => {
+                    return Err(pctrl_core::Error::Ssh(format!(
+                        "Failed to read output: {}",
+                        e
+                    )))
+                }
+            }
+        }
+        session.set_timeout(timeout);
+
+        channel
+            .wait_close()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel close failed: {}", e)))?;
+        let exit_code = channel
+            .exit_status()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to get exit status: {}", e)))?;
+        Ok(Some(exit_code))
+    }
+
+    /// Open a login shell on a PTY and pump it until the remote shell exits.
+    ///
+    /// `input` carries keystrokes and window resizes from the local terminal
+    /// (put it in raw mode first); the shell's output goes to `output`. The
+    /// shell also ends when `input` is disconnected. Returns the exit status.
+    pub fn interactive_shell(
+        &self,
+        id: &str,
+        password: Option<&str>,
+        pty: &PtyRequest,
+        input: &Receiver<ShellInput>,
+        output: &mut dyn std::io::Write,
+    ) -> Result<i32> {
+        use std::io::{ErrorKind, Read, Write};
+
+        let session = self.connect_with_password(id, password)?;
+        let mut channel = session
+            .channel_session()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel creation failed: {}", e)))?;
+        channel
+            .request_pty(&pty.term, None, Some((pty.cols, pty.rows, 0, 0)))
+            .map_err(|e| pctrl_core::Error::Ssh(format!("PTY request failed: {}", e)))?;
+        channel
+            .shell()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Shell request failed: {}", e)))?;
+
+        // Reads and writes return WouldBlock instead of waiting, so one
+        // thread can serve both directions
+        session.set_blocking(false);
+        let ssh_err =
+            |e: std::io::Error| pctrl_core::Error::Ssh(format!("Shell I/O failed: {}", e));
+        let mut pending: Vec<u8> = Vec::new();
+        let mut buf = [0u8; 4096];
+        let mut input_closed = false;
+        loop {
+            let mut busy = false;
+
+            while !input_closed {
+                match input.try_recv() {
+                    Ok(ShellInput::Data(data)) => pending.extend(data),
+                    Ok(ShellInput::Resize { cols, rows }) => loop {
+                        let resized = channel
+                            .request_pty_size(cols, rows, None, None)
+                            .map_err(std::io::Error::from);
+                        match resized {
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                                std::thread::sleep(Duration::from_millis(5))
+                            }
+                            // A server refusing the new size isn't fatal
+                            _ => break,
+                        }
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => input_closed = true,
+                }
+            }
+            if input_closed && pending.is_empty() {
+                break;
+            }
+
+            if !pending.is_empty() {
+                match channel.write(&pending) {
+                    Ok(n) => {
+                        pending.drain(..n);
+                        busy = true;
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(ssh_err(e)),
+                }
+            }
+
+            match channel.read(&mut buf) {
+                Ok(0) if channel.eof() => break,
+                Ok(0) => {}
+                Ok(n) => {
+                    output.write_all(&buf[..n]).map_err(ssh_err)?;
+                    output.flush().map_err(ssh_err)?;
+                    busy = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(ssh_err(e)),
+            }
+            if channel.eof() {
+                break;
+            }
+
+            if !busy {
+                let _ = session.keepalive_send();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        session.set_blocking(true);
+        channel
+            .close()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel close failed: {}", e)))?;
+        channel
+            .wait_close()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Channel close failed: {}", e)))?;
+        channel
+            .exit_status()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to get exit status: {}", e)))
+    }
+
+    /// Run a command through the system `ssh` binary, with the terminal
+    /// attached. Used where the library falls short: it can't forward the
+    /// local agent, so `forward_agent` needs this path.
+    ///
+    /// Returns the exit status.
+    pub fn execute_system(&self, id: &str, command: &str, forward_agent: bool) -> Result<i32> {
+        let conn = self
+            .get_connection(id)
+            .ok_or_else(|| pctrl_core::Error::Ssh("Connection not found".to_string()))?;
+        // ssh needs a key file; an inline key gets one for this run
+        let mut key_file = None;
+        let conn = match &conn.auth_method {
+            AuthMethod::InlineKey { key, passphrase } => {
+                let file = key_file.insert(KeyFile::write(key)?);
+                &SshConnection {
+                    auth_method: AuthMethod::Key {
+                        path: file.path().to_string_lossy().into_owned(),
+                        passphrase: passphrase.clone(),
+                    },
+                    ..conn.clone()
+                }
+            }
+            _ => conn,
+        };
+        let args = system_ssh_args(conn, command, forward_agent)?;
+
+        let status = std::process::Command::new("ssh")
+            .args(&args)
+            .status()
+            .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to run ssh: {}", e)))?;
+        // Killed by a signal: report like a shell would
+        Ok(status.code().unwrap_or(255))
+    }
+
     /// List all connections
     pub fn list_connections(&self) -> &[SshConnection] {
         &self.connections
@@ -302,3 +522,208 @@ impl Default for SshManager {
         Self::new()
     }
 }
+
+/// Read a started command's output until it ends, passing it to
+/// `on_output` as it arrives. Reads poll every [`STREAM_POLL`] so only
+/// `stream_timeout` of silence ends it early. Returns the whole output and
+/// the exit status.
+fn read_streaming(
+    session: &Session,
+    mut channel: ssh2::Channel,
+    stream_timeout: Option<Duration>,
+    on_output: &mut dyn FnMut(&str),
+) -> Result<(String, i32)> {
+    use std::io::Read;
+
+    // Reads give up after a short poll so silence can be measured
+    let timeout = session.timeout();
+    session.set_timeout(millis(STREAM_POLL));
+    let mut output = Vec::new();
+    let mut buf = [0u8; 4096];
+    let mut last_output = Instant::now();
+    let read = loop {
+        match channel.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(n) => {
+                on_output(&String::from_utf8_lossy(&buf[..n]));
+                output.extend_from_slice(&buf[..n]);
+                last_output = Instant::now();
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                if let Some(limit) = stream_timeout.filter(|l| last_output.elapsed() >= *l) {
+                    break Err(pctrl_core::Error::Ssh(format!(
+                        "No output for {}s, giving up",
+                        limit.as_secs()
+                    )));
+                }
+                // Idle: keep the session alive if keepalives are on
+                let _ = session.keepalive_send();
+            }
+            Err(e) => {
+                break Err(pctrl_core::Error::Ssh(format!(
+                    "Failed to read output: {}",
+                    e
+                )))
+            }
+        }
+    };
+    session.set_timeout(timeout);
+    read?;
+
+    channel
+        .wait_close()
+        .map_err(|e| pctrl_core::Error::Ssh(format!("Channel close failed: {}", e)))?;
+    let exit_code = channel
+        .exit_status()
+        .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to get exit status: {}", e)))?;
+
+    Ok((String::from_utf8_lossy(&output).into_owned(), exit_code))
+}
+
+/// Timeout in milliseconds for libssh2, where 0 means none
+fn millis(timeout: Duration) -> u32 {
+    timeout.as_millis().clamp(1, u32::MAX as u128) as u32
+}
+
+/// Connect to the first reachable address `host` resolves to, giving each
+/// one `timeout`
+pub fn connect_tcp(host: &str, port: u16, timeout: Duration) -> Result<TcpStream> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| pctrl_core::Error::Ssh(format!("Could not resolve {}: {}", host, e)))?;
+
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(pctrl_core::Error::Ssh(match last_error {
+        Some(e) => format!("TCP connection failed: {}", e),
+        None => format!("Could not resolve {}: no addresses", host),
+    }))
+}
+
+/// Authenticate `session` as configured for `conn`
+fn authenticate(session: &Session, conn: &SshConnection, password: Option<&str>) -> Result<()> {
+    match &conn.auth_method {
+        AuthMethod::Password => {
+            let pw = password.ok_or_else(|| {
+                pctrl_core::Error::Ssh("Password required for authentication".to_string())
+            })?;
+            session.userauth_password(&conn.username, pw).map_err(|e| {
+                pctrl_core::Error::Ssh(format!("Password authentication failed: {}", e))
+            })?;
+        }
+        AuthMethod::PublicKey { key_path } => {
+            session
+                .userauth_pubkey_file(&conn.username, None, Path::new(key_path), None)
+                .map_err(|e| {
+                    pctrl_core::Error::Ssh(format!("Public key authentication failed: {}", e))
+                })?;
+        }
+        AuthMethod::Key { path, passphrase } => {
+            session
+                .userauth_pubkey_file(&conn.username, None, Path::new(path), passphrase.as_deref())
+                .map_err(|e| pctrl_core::Error::Ssh(format!("Key authentication failed: {}", e)))?;
+        }
+        AuthMethod::InlineKey { key, passphrase } => {
+            inline_key_auth(session, &conn.username, key, passphrase.as_deref())?;
+        }
+        AuthMethod::Agent => {
+            let mut agent = session
+                .agent()
+                .map_err(|e| pctrl_core::Error::Ssh(format!("Failed to get SSH agent: {}", e)))?;
+
+            agent.connect().map_err(|e| {
+                pctrl_core::Error::Ssh(format!("Failed to connect to SSH agent: {}", e))
+            })?;
+
+            agent.list_identities().map_err(|e| {
+                pctrl_core::Error::Ssh(format!("Failed to list agent identities: {}", e))
+            })?;
+
+            // Try each identity until one works
+            let authenticated = agent
+                .identities()
+                .unwrap_or_default()
+                .iter()
+                .any(|identity| agent.userauth(&conn.username, identity).is_ok());
+            if !authenticated {
+                return Err(pctrl_core::Error::Ssh(
+                    "SSH agent authentication failed: no valid identity found".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Authenticate with a key held in memory
+#[cfg(unix)]
+fn inline_key_auth(
+    session: &Session,
+    username: &str,
+    key: &str,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    session
+        .userauth_pubkey_memory(username, None, key, passphrase)
+        .map_err(|e| pctrl_core::Error::Ssh(format!("Key authentication failed: {}", e)))
+}
+
+/// Authenticate with a key held in memory, through a key file that's gone
+/// again once authenticated
+#[cfg(not(unix))]
+fn inline_key_auth(
+    session: &Session,
+    username: &str,
+    key: &str,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let file = KeyFile::write(key)?;
+    session
+        .userauth_pubkey_file(username, None, file.path(), passphrase)
+        .map_err(|e| pctrl_core::Error::Ssh(format!("Key authentication failed: {}", e)))
+}
+
+/// Arguments for the system `ssh` binary running `command` on `conn`.
+/// `forward_agent` adds `-A`, so the command can use the local agent (e.g.
+/// for `git pull` on the server).
+pub fn system_ssh_args(
+    conn: &SshConnection,
+    command: &str,
+    forward_agent: bool,
+) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    if forward_agent {
+        args.push("-A".to_string());
+    }
+    args.extend(["-p".to_string(), conn.port.to_string()]);
+    match &conn.auth_method {
+        AuthMethod::PublicKey { key_path: path } | AuthMethod::Key { path, .. } => {
+            args.extend([
+                "-i".to_string(),
+                path.clone(),
+                "-o".to_string(),
+                "IdentitiesOnly=yes".to_string(),
+            ]);
+        }
+        AuthMethod::Agent => {}
+        AuthMethod::InlineKey { .. } => {
+            return Err(pctrl_core::Error::Ssh(
+                "Inline keys need a key file for the system ssh path".to_string(),
+            ))
+        }
+        AuthMethod::Password => {
+            return Err(pctrl_core::Error::Ssh(
+                "Password logins aren't supported by the system ssh path".to_string(),
+            ))
+        }
+    }
+    args.push(format!("{}@{}", conn.username, conn.host));
+    args.push("--".to_string());
+    args.push(command.to_string());
+    Ok(args)
+}
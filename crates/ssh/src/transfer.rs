@@ -0,0 +1,320 @@
+//! SFTP transfers that resume, throttle and verify; the decisions are in
+//! [`pctrl_core::transfer`]
+
+use crate::SshManager;
+use pctrl_core::transfer::{self, Bucket, Progress, Resume, Verified};
+use pctrl_core::{humanize, Result};
+use sha2::{Digest, Sha256};
+use ssh2::{OpenFlags, OpenType, RenameFlags, Session, Sftp};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::time::Instant;
+
+/// How a transfer runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferOptions {
+    /// Bandwidth cap in bytes per second
+    pub limit: Option<u64>,
+    /// Compare SHA-256 checksums when the server has `sha256sum`
+    pub checksum: bool,
+    /// Ignore a partial file from an earlier run and start over
+    pub fresh: bool,
+}
+
+/// What a finished transfer did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferReport {
+    /// Size of the file
+    pub total: u64,
+    /// Bytes an earlier run had already transferred
+    pub resumed_from: u64,
+    pub verified: Verified,
+}
+
+/// Called after every chunk; [`ControlFlow::Break`] stops the transfer
+/// and leaves the partial file to resume from
+pub type OnProgress<'a> = dyn FnMut(&Progress) -> ControlFlow<()> + 'a;
+
+pub(crate) fn ssh_err(what: &str, e: impl std::fmt::Display) -> pctrl_core::Error {
+    pctrl_core::Error::Ssh(format!("{}: {}", what, e))
+}
+
+impl SshManager {
+    /// Download `remote` to `local` over SFTP, continuing a `.part` file
+    /// left by an interrupted run
+    pub fn download(
+        &self,
+        id: &str,
+        remote: &str,
+        local: &Path,
+        options: TransferOptions,
+        on_progress: &mut OnProgress,
+    ) -> Result<TransferReport> {
+        let session = self.connect(id)?;
+        let sftp = session.sftp().map_err(|e| ssh_err("SFTP unavailable", e))?;
+        let stat = sftp
+            .stat(Path::new(remote))
+            .map_err(|e| ssh_err(&format!("Can't read '{}'", remote), e))?;
+        if stat.is_dir() {
+            return Err(pctrl_core::Error::Ssh(format!(
+                "'{}' is a directory",
+                remote
+            )));
+        }
+        let total = stat.size.unwrap_or(0);
+
+        let part = transfer::partial_path(&local.to_string_lossy());
+        let part = Path::new(&part);
+        if options.fresh {
+            let _ = fs::remove_file(part);
+        }
+        let partial = fs::metadata(part).ok().map(|m| m.len());
+        let resume = transfer::resume_offset(partial, total);
+        let offset = resume.offset(total);
+
+        if resume != Resume::Complete {
+            let mut source = sftp
+                .open(Path::new(remote))
+                .map_err(|e| ssh_err(&format!("Can't open '{}'", remote), e))?;
+            source
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| ssh_err("Seek failed", e))?;
+            let mut dest = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(offset > 0)
+                .truncate(offset == 0)
+                .open(part)?;
+            Copier::new(total, offset, options.limit, true, on_progress)
+                .copy(&mut source, &mut dest)?;
+            dest.sync_all()?;
+        }
+
+        let size = fs::metadata(part)?.len();
+        let checksums = if options.checksum {
+            remote_sha256(&session, remote).map(|expected| (file_sha256(part), expected))
+        } else {
+            None
+        };
+        let (actual, expected) = match &checksums {
+            Some((Ok(actual), expected)) => (Some(actual.as_str()), Some(expected.as_str())),
+            Some((Err(e), _)) => return Err(ssh_err("Can't read the download", e)),
+            None => (None, None),
+        };
+        let verified = transfer::verify(size, total, actual, expected).map_err(|e| {
+            // A corrupt partial file can't be resumed from
+            let _ = fs::remove_file(part);
+            pctrl_core::Error::Ssh(e)
+        })?;
+        fs::rename(part, local)?;
+
+        Ok(TransferReport {
+            total,
+            resumed_from: offset,
+            verified,
+        })
+    }
+
+    /// Upload `local` to `remote` over SFTP, continuing a remote `.part`
+    /// file left by an interrupted run
+    pub fn upload(
+        &self,
+        id: &str,
+        local: &Path,
+        remote: &str,
+        options: TransferOptions,
+        on_progress: &mut OnProgress,
+    ) -> Result<TransferReport> {
+        let mut source = File::open(local)?;
+        let total = source.metadata()?.len();
+
+        let session = self.connect(id)?;
+        let sftp = session.sftp().map_err(|e| ssh_err("SFTP unavailable", e))?;
+        let part = transfer::partial_path(remote);
+        if options.fresh {
+            let _ = sftp.unlink(Path::new(&part));
+        }
+        let partial = sftp.stat(Path::new(&part)).ok().and_then(|s| s.size);
+        let resume = transfer::resume_offset(partial, total);
+        let offset = resume.offset(total);
+
+        if resume != Resume::Complete {
+            let flags = if offset > 0 {
+                OpenFlags::WRITE
+            } else {
+                OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+            };
+            let mut dest = sftp
+                .open_mode(Path::new(&part), flags, 0o644, OpenType::File)
+                .map_err(|e| ssh_err(&format!("Can't write '{}'", part), e))?;
+            dest.seek(SeekFrom::Start(offset))
+                .map_err(|e| ssh_err("Seek failed", e))?;
+            source.seek(SeekFrom::Start(offset))?;
+            Copier::new(total, offset, options.limit, true, on_progress)
+                .copy(&mut source, &mut dest)?;
+            dest.fsync().ok();
+        }
+
+        let size = sftp
+            .stat(Path::new(&part))
+            .ok()
+            .and_then(|s| s.size)
+            .unwrap_or(0);
+        let checksums = if options.checksum {
+            remote_sha256(&session, &part).map(|actual| (actual, file_sha256(local)))
+        } else {
+            None
+        };
+        let (actual, expected) = match &checksums {
+            Some((actual, Ok(expected))) => (Some(actual.as_str()), Some(expected.as_str())),
+            Some((_, Err(e))) => return Err(ssh_err("Can't read the upload", e)),
+            None => (None, None),
+        };
+        let verified = transfer::verify(size, total, actual, expected).map_err(|e| {
+            let _ = sftp.unlink(Path::new(&part));
+            pctrl_core::Error::Ssh(e)
+        })?;
+        rename_over(&sftp, &part, remote)?;
+
+        Ok(TransferReport {
+            total,
+            resumed_from: offset,
+            verified,
+        })
+    }
+}
+
+/// The one copy loop of every SFTP transfer: bandwidth cap and progress
+/// across one or more files
+pub(crate) struct Copier<'a, 'b> {
+    total: u64,
+    done: u64,
+    resumed_from: u64,
+    files: usize,
+    /// Whether a stopped transfer can be resumed by running it again
+    resumable: bool,
+    started: Instant,
+    bucket: Option<Bucket>,
+    on_progress: &'a mut OnProgress<'b>,
+}
+
+impl<'a, 'b> Copier<'a, 'b> {
+    /// A copy of `total` bytes, `resumed_from` of them already there
+    pub(crate) fn new(
+        total: u64,
+        resumed_from: u64,
+        limit: Option<u64>,
+        resumable: bool,
+        on_progress: &'a mut OnProgress<'b>,
+    ) -> Self {
+        let started = Instant::now();
+        Self {
+            total,
+            done: resumed_from,
+            resumed_from,
+            files: 0,
+            resumable,
+            started,
+            bucket: limit.map(|limit| Bucket::new(limit, started)),
+            on_progress,
+        }
+    }
+
+    /// Copy one file to its end; [`ControlFlow::Break`] from the progress
+    /// callback stops the whole transfer
+    pub(crate) fn copy(&mut self, source: &mut impl Read, dest: &mut impl Write) -> Result<()> {
+        let mut buf = vec![0u8; transfer::CHUNK_SIZE];
+        loop {
+            let n = source.read(&mut buf).map_err(|e| self.failed(e))?;
+            if n == 0 {
+                break;
+            }
+            if let Some(bucket) = &mut self.bucket {
+                std::thread::sleep(bucket.take(n as u64, Instant::now()));
+            }
+            dest.write_all(&buf[..n]).map_err(|e| self.failed(e))?;
+            self.done += n as u64;
+
+            let progress = Progress {
+                done: self.done,
+                total: self.total,
+                resumed_from: self.resumed_from,
+                elapsed: self.started.elapsed(),
+            };
+            if (self.on_progress)(&progress).is_break() {
+                dest.flush()?;
+                return Err(pctrl_core::Error::Ssh(format!(
+                    "Transfer stopped at {} of {}{}",
+                    humanize::bytes(self.done),
+                    humanize::bytes(self.total),
+                    if self.resumable {
+                        "; run it again to resume"
+                    } else {
+                        ""
+                    }
+                )));
+            }
+        }
+        self.files += 1;
+        Ok(())
+    }
+
+    fn failed(&self, e: std::io::Error) -> pctrl_core::Error {
+        ssh_err(
+            &format!("Transfer failed at {}", humanize::bytes(self.done)),
+            e,
+        )
+    }
+
+    /// Files finished so far
+    pub(crate) fn files(&self) -> usize {
+        self.files
+    }
+
+    /// Bytes written by this run
+    pub(crate) fn copied(&self) -> u64 {
+        self.done - self.resumed_from
+    }
+}
+
+/// SHA-256 of a remote file, if the server can compute it
+fn remote_sha256(session: &Session, path: &str) -> Option<String> {
+    let mut channel = session.channel_session().ok()?;
+    channel.exec(&transfer::sha256sum_command(path)).ok()?;
+    let mut output = String::new();
+    channel.read_to_string(&mut output).ok()?;
+    channel.wait_close().ok()?;
+    transfer::parse_sha256sum(&output)
+}
+
+fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Move the finished `.part` file into place; servers without overwriting
+/// renames get the old file removed first
+pub(crate) fn rename_over(sftp: &Sftp, from: &str, to: &str) -> Result<()> {
+    let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+    if sftp.rename(Path::new(from), Path::new(to), flags).is_ok() {
+        return Ok(());
+    }
+    let _ = sftp.unlink(Path::new(to));
+    sftp.rename(Path::new(from), Path::new(to), None)
+        .map_err(|e| ssh_err(&format!("Can't move '{}' into place", from), e))
+}
@@ -0,0 +1,64 @@
+use pctrl_core::{AuthMethod, SshConnection};
+use pctrl_ssh::{connect_tcp, ConnectOptions, SshManager};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+fn connection(port: u16) -> SshConnection {
+    SshConnection {
+        id: "local".to_string(),
+        name: "local".to_string(),
+        host: "localhost".to_string(),
+        port,
+        username: "deploy".to_string(),
+        auth_method: AuthMethod::Agent,
+    }
+}
+
+#[test]
+fn test_connect_tcp_resolves_host_names() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    // "localhost" may resolve to ::1 first; the IPv4 address is tried next
+    let tcp = connect_tcp("localhost", port, Duration::from_secs(2)).unwrap();
+    assert_eq!(tcp.peer_addr().unwrap().port(), port);
+
+    drop(listener);
+    let err = connect_tcp("127.0.0.1", port, Duration::from_secs(2)).unwrap_err();
+    assert!(err.to_string().contains("TCP connection failed"), "{}", err);
+}
+
+#[test]
+fn test_silent_server_times_out_in_handshake() {
+    // Accepts connections but never speaks SSH
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let mut ssh = SshManager::with_options(ConnectOptions {
+        connect_timeout: Duration::from_millis(300),
+        ..ConnectOptions::default()
+    });
+    ssh.add_connection(connection(port));
+
+    let started = Instant::now();
+    let err = ssh.test_connection("local", None).unwrap_err();
+    assert!(err.to_string().contains("handshake"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let Err(err) = ssh.connect("local") else {
+        panic!("connected to a silent server");
+    };
+    assert!(err.to_string().contains("handshake"), "{}", err);
+    drop(listener);
+}
+
+#[test]
+fn test_default_options_bound_every_wait() {
+    let options = *SshManager::new().options();
+    assert_eq!(options, ConnectOptions::default());
+    assert!(options.connect_timeout <= Duration::from_secs(30));
+    assert!(options.timeout.is_some());
+    // Streamed commands may run long; keepalives notice a dead server
+    assert!(options.stream_timeout.is_none());
+    assert!(options.keepalive_interval.is_some());
+}
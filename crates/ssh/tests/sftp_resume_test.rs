@@ -0,0 +1,94 @@
+//! Interrupted and resumed transfers against a real sshd.
+//!
+//! Runs with `--features sshd-tests` and a server to write to:
+//!
+//! ```sh
+//! PCTRL_TEST_SSHD=user@127.0.0.1:2222 PCTRL_TEST_SSHD_KEY=~/.ssh/id_ed25519 \
+//!     cargo test -p pctrl-ssh --features sshd-tests
+//! ```
+#![cfg(feature = "sshd-tests")]
+
+use pctrl_core::transfer::{Progress, Verified};
+use pctrl_core::{AuthMethod, SshConnection};
+use pctrl_ssh::{SshManager, TransferOptions};
+use std::ops::ControlFlow;
+
+fn manager() -> SshManager {
+    let target = std::env::var("PCTRL_TEST_SSHD").expect("PCTRL_TEST_SSHD=user@host:port");
+    let key_path = std::env::var("PCTRL_TEST_SSHD_KEY").expect("PCTRL_TEST_SSHD_KEY=<key file>");
+    let (username, address) = target.split_once('@').expect("user@host:port");
+    let (host, port) = address.rsplit_once(':').unwrap_or((address, "22"));
+    let mut manager = SshManager::new();
+    manager.add_connection(SshConnection {
+        id: "sshd".to_string(),
+        name: "sshd".to_string(),
+        host: host.to_string(),
+        port: port.parse().expect("port"),
+        username: username.to_string(),
+        auth_method: AuthMethod::PublicKey { key_path },
+    });
+    manager
+}
+
+/// Stops the transfer once half of it is done
+fn stop_halfway(progress: &Progress) -> ControlFlow<()> {
+    if progress.done * 2 >= progress.total {
+        ControlFlow::Break(())
+    } else {
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_interrupted_transfers_resume() {
+    let ssh = manager();
+    let dir = tempfile::tempdir().unwrap();
+    let data: Vec<u8> = (0..3_000_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let local = dir.path().join("artifact.bin");
+    std::fs::write(&local, &data).unwrap();
+    let remote = format!("/tmp/pctrl-sftp-{}.bin", std::process::id());
+    let options = TransferOptions {
+        limit: None,
+        checksum: true,
+        fresh: true,
+    };
+
+    // Upload: stop halfway, then resume
+    let stopped = ssh.upload("sshd", &local, &remote, options, &mut stop_halfway);
+    assert!(stopped
+        .unwrap_err()
+        .to_string()
+        .contains("run it again to resume"));
+    let resume = TransferOptions {
+        fresh: false,
+        ..options
+    };
+    let report = ssh
+        .upload("sshd", &local, &remote, resume, &mut |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.total, data.len() as u64);
+    assert!(report.resumed_from >= data.len() as u64 / 2);
+    assert_eq!(report.verified, Verified::Checksum);
+
+    // Download: stop halfway, then resume at a capped rate
+    let copy = dir.path().join("copy.bin");
+    let stopped = ssh.download("sshd", &remote, &copy, options, &mut stop_halfway);
+    assert!(stopped.is_err());
+    assert!(!copy.exists());
+    let capped = TransferOptions {
+        limit: Some(8 << 20),
+        ..resume
+    };
+    let report = ssh
+        .download("sshd", &remote, &copy, capped, &mut |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert!(report.resumed_from >= data.len() as u64 / 2);
+    assert_eq!(std::fs::read(&copy).unwrap(), data);
+
+    ssh.execute_command("sshd", &format!("rm -f {}", remote))
+        .unwrap();
+}
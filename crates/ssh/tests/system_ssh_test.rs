@@ -0,0 +1,54 @@
+use pctrl_core::{AuthMethod, SshConnection};
+use pctrl_ssh::system_ssh_args;
+
+fn connection(auth_method: AuthMethod) -> SshConnection {
+    SshConnection {
+        id: "web".to_string(),
+        name: "web".to_string(),
+        host: "203.0.113.10".to_string(),
+        port: 2222,
+        username: "deploy".to_string(),
+        auth_method,
+    }
+}
+
+#[test]
+fn test_agent_forwarding_is_opt_in() {
+    let conn = connection(AuthMethod::Agent);
+    assert_eq!(
+        system_ssh_args(&conn, "git pull", true).unwrap(),
+        ["-A", "-p", "2222", "deploy@203.0.113.10", "--", "git pull"]
+    );
+    assert_eq!(
+        system_ssh_args(&conn, "git pull", false).unwrap(),
+        ["-p", "2222", "deploy@203.0.113.10", "--", "git pull"]
+    );
+}
+
+#[test]
+fn test_key_credentials_pin_the_identity() {
+    let conn = connection(AuthMethod::Key {
+        path: "/home/me/.ssh/web".to_string(),
+        passphrase: Some("secret".to_string()),
+    });
+    let args = system_ssh_args(&conn, "uptime", true).unwrap();
+    assert_eq!(
+        args,
+        [
+            "-A",
+            "-p",
+            "2222",
+            "-i",
+            "/home/me/.ssh/web",
+            "-o",
+            "IdentitiesOnly=yes",
+            "deploy@203.0.113.10",
+            "--",
+            "uptime"
+        ]
+    );
+    // The passphrase is asked by ssh itself, never passed on
+    assert!(!args.iter().any(|a| a.contains("secret")));
+
+    assert!(system_ssh_args(&connection(AuthMethod::Password), "uptime", false).is_err());
+}
@@ -0,0 +1,12 @@
+#!/bin/bash
+# Run the integration tests against throwaway containers (needs Docker).
+# Extra arguments go to cargo test, e.g. `--test ssh_test`.
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+cd "$(dirname "$SCRIPT_DIR")"
+
+# The CLI tests run the binary
+cargo build -p pctrl-cli
+cargo test -p pctrl-integration --features integration "$@"
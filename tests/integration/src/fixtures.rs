@@ -0,0 +1,243 @@
+//! Throwaway containers for the integration tests
+//!
+//! Containers are started through testcontainers, published on random
+//! ports and removed when their handle is dropped, so a failing test
+//! doesn't leave them running. Images are pinned, so a run behaves the same
+//! until someone bumps them here.
+
+use pctrl_core::{AuthMethod, SshConnection};
+use pctrl_ssh::SshManager;
+use std::io::Read;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use testcontainers::core::{ExecCommand, IntoContainerPort, WaitFor};
+use testcontainers::runners::SyncRunner;
+use testcontainers::{Container, GenericImage, ImageExt};
+
+/// Base of the sshd fixture; openssh is installed when it starts
+pub const SSHD_IMAGE: (&str, &str) = ("alpine", "3.20");
+
+/// Docker daemon for the Docker tests, serving plain HTTP on 2375
+pub const DIND_IMAGE: (&str, &str) = ("docker", "27.3-dind");
+
+/// Image of the containers started inside dind
+pub const PROBE_IMAGE: &str = "busybox:1.36";
+
+/// User the sshd fixture lets in with [`Sshd::key_path`]
+pub const SSH_USER: &str = "pctrl";
+
+/// Label on every fixture container, for cleaning up after a killed run
+pub const LABEL: &str = "pctrl-integration";
+
+/// How long a fixture may take to come up; the first run pulls images
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Port sshd listens on inside its container
+const SSHD_PORT: u16 = 2222;
+
+/// Installs openssh, lets [`SSH_USER`] in with `$AUTHORIZED_KEY` and runs
+/// sshd in the foreground, logging to stderr
+const SSHD_SETUP: &str = r#"set -e
+apk add --no-cache openssh-server >/dev/null
+ssh-keygen -A
+adduser -D -s /bin/sh pctrl
+echo 'pctrl:*' | chpasswd -e
+install -d -m 700 -o pctrl -g pctrl /home/pctrl/.ssh
+echo "$AUTHORIZED_KEY" > /home/pctrl/.ssh/authorized_keys
+chown pctrl:pctrl /home/pctrl/.ssh/authorized_keys
+chmod 600 /home/pctrl/.ssh/authorized_keys
+sed -i '/^Subsystem/d' /etc/ssh/sshd_config
+echo 'Subsystem sftp internal-sftp' >> /etc/ssh/sshd_config
+exec /usr/sbin/sshd -D -e -p 2222 -o PasswordAuthentication=no"#;
+
+/// Start `image` with the fixture label and startup timeout; panics if
+/// Docker isn't there, since nothing here works without it
+fn start(image: impl ImageExt<GenericImage>) -> Container<GenericImage> {
+    image
+        .with_label(LABEL, "1")
+        .with_startup_timeout(STARTUP_TIMEOUT)
+        .start()
+        .expect("the integration tests need a running Docker daemon")
+}
+
+/// Run a command in a container; its exit status and stdout
+fn exec(container: &Container<GenericImage>, command: &[&str]) -> (bool, String) {
+    let mut result = container
+        .exec(ExecCommand::new(command.iter().copied()))
+        .unwrap_or_else(|e| panic!("exec {:?} in {}: {}", command, container.id(), e));
+    // Reading stdout to the end waits for the command to finish
+    let stdout = result.stdout_to_vec().unwrap_or_default();
+    let ok = result.exit_code().ok().flatten() == Some(0);
+    (ok, String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Poll `ready` until it holds; panics naming `what` after
+/// [`STARTUP_TIMEOUT`]
+pub fn wait_until(what: &str, mut ready: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !ready() {
+        assert!(
+            started.elapsed() < STARTUP_TIMEOUT,
+            "timed out waiting for {}",
+            what
+        );
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// An ed25519 key pair made by `ssh-keygen` for one test, deleted with it
+pub struct KeyPair {
+    dir: TempDir,
+}
+
+impl KeyPair {
+    pub fn generate() -> Self {
+        let dir = tempfile::tempdir().expect("temp dir for the key pair");
+        let output = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", LABEL, "-f"])
+            .arg(dir.path().join("id_ed25519"))
+            .output()
+            .expect("the integration tests need ssh-keygen");
+        assert!(
+            output.status.success(),
+            "ssh-keygen failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Self { dir }
+    }
+
+    pub fn private_key(&self) -> PathBuf {
+        self.dir.path().join("id_ed25519")
+    }
+
+    /// The public key line, e.g. for `authorized_keys`
+    pub fn public_key(&self) -> String {
+        std::fs::read_to_string(self.dir.path().join("id_ed25519.pub"))
+            .expect("generated public key")
+            .trim()
+            .to_string()
+    }
+}
+
+/// An sshd that lets [`SSH_USER`] in with a key generated for it
+pub struct Sshd {
+    pub container: Container<GenericImage>,
+    pub host: String,
+    pub port: u16,
+    key: KeyPair,
+}
+
+impl Sshd {
+    pub fn start() -> Self {
+        let key = KeyPair::generate();
+        let container = start(
+            GenericImage::new(SSHD_IMAGE.0, SSHD_IMAGE.1)
+                .with_exposed_port(SSHD_PORT.tcp())
+                .with_wait_for(WaitFor::message_on_stderr("Server listening on"))
+                .with_env_var("AUTHORIZED_KEY", key.public_key())
+                .with_cmd(["sh", "-c", SSHD_SETUP]),
+        );
+        let host = container.get_host().expect("container host").to_string();
+        let port = container
+            .get_host_port_ipv4(SSHD_PORT)
+            .expect("sshd port is published");
+        // The port is published a moment before it reaches sshd
+        wait_until("the sshd banner", || banner(&host, port));
+        Self {
+            container,
+            host,
+            port,
+            key,
+        }
+    }
+
+    /// Private key the sshd accepts
+    pub fn key_path(&self) -> PathBuf {
+        self.key.private_key()
+    }
+
+    /// Key authentication as [`SSH_USER`], under the ID "sshd"
+    pub fn connection(&self) -> SshConnection {
+        SshConnection {
+            id: "sshd".to_string(),
+            name: "sshd".to_string(),
+            host: self.host.clone(),
+            port: self.port,
+            username: SSH_USER.to_string(),
+            auth_method: AuthMethod::PublicKey {
+                key_path: self.key_path().to_string_lossy().into_owned(),
+            },
+        }
+    }
+
+    /// A manager that knows [`Sshd::connection`]
+    pub fn manager(&self) -> SshManager {
+        let mut manager = SshManager::new();
+        manager.add_connection(self.connection());
+        manager
+    }
+}
+
+/// Whether an SSH server greets on `host:port`
+fn banner(host: &str, port: u16) -> bool {
+    let Ok(mut tcp) = TcpStream::connect((host, port)) else {
+        return false;
+    };
+    let _ = tcp.set_read_timeout(Some(Duration::from_secs(2)));
+    let mut greeting = [0u8; 4];
+    tcp.read_exact(&mut greeting).is_ok() && &greeting == b"SSH-"
+}
+
+/// A Docker daemon in a privileged container, reachable over plain HTTP
+pub struct Dind {
+    pub container: Container<GenericImage>,
+    /// Docker host URL, e.g. `tcp://127.0.0.1:49154`
+    pub url: String,
+}
+
+impl Dind {
+    pub fn start() -> Self {
+        let container = start(
+            GenericImage::new(DIND_IMAGE.0, DIND_IMAGE.1)
+                .with_exposed_port(2375.tcp())
+                .with_privileged(true)
+                // Without certificates dind serves plain HTTP on 2375
+                .with_env_var("DOCKER_TLS_CERTDIR", ""),
+        );
+        wait_until("dockerd inside dind", || {
+            exec(&container, &["docker", "version"]).0
+        });
+        let url = format!(
+            "tcp://{}:{}",
+            container.get_host().expect("container host"),
+            container
+                .get_host_port_ipv4(2375)
+                .expect("dind port is published")
+        );
+        Self { container, url }
+    }
+
+    /// Start a [`PROBE_IMAGE`] container named `name` inside dind that
+    /// sleeps until stopped; returns its ID
+    pub fn run_probe(&self, name: &str) -> String {
+        let (ok, id) = exec(
+            &self.container,
+            &[
+                "docker",
+                "run",
+                "-d",
+                "--name",
+                name,
+                PROBE_IMAGE,
+                "sleep",
+                "3600",
+            ],
+        );
+        assert!(ok, "starting {} inside dind failed", name);
+        id.trim().to_string()
+    }
+}
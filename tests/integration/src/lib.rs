@@ -0,0 +1,6 @@
+//! Fixtures for the integration tests in `tests/`
+//!
+//! The tests only build with `--features integration`; see README.md.
+
+#[cfg(feature = "integration")]
+pub mod fixtures;
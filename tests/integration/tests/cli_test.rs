@@ -0,0 +1,62 @@
+//! `pctrl server add → status → exec` against a real sshd
+#![cfg(feature = "integration")]
+
+use assert_cmd::Command;
+use pctrl_integration::fixtures::{Sshd, SSH_USER};
+use predicates::prelude::*;
+use std::path::Path;
+
+/// The `pctrl` binary as `cargo build -p pctrl-cli` leaves it
+fn pctrl(db: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    Command::cargo_bin("pctrl")
+        .expect("build it first with `cargo build -p pctrl-cli`")
+        .arg("--db")
+        .arg(db)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+}
+
+#[test]
+fn test_server_add_status_exec() {
+    let sshd = Sshd::start();
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("pctrl.db");
+    let port = sshd.port.to_string();
+    let key = sshd.key_path();
+
+    pctrl(
+        &db,
+        &[
+            "credential",
+            "add",
+            "sshd",
+            "-t",
+            "ssh",
+            "-u",
+            SSH_USER,
+            "-p",
+            &port,
+            "-k",
+            &key.to_string_lossy(),
+        ],
+    )
+    .success();
+
+    // Adding detects the specs over SSH
+    pctrl(&db, &["server", "add", "box", &sshd.host, "-c", "sshd"])
+        .success()
+        .stdout(predicate::str::contains("Could not detect specs").not())
+        .stderr(predicate::str::contains("Could not detect specs").not());
+
+    pctrl(&db, &["server", "status", "box"]).stdout(predicate::str::contains("Online"));
+
+    pctrl(&db, &["server", "exec", "box", "echo from-the-box"])
+        .success()
+        .stdout(predicate::str::contains("from-the-box"));
+
+    // Gone: the same commands fail cleanly
+    drop(sshd);
+    pctrl(&db, &["server", "exec", "box", "true"]).failure();
+}
@@ -0,0 +1,59 @@
+//! `DockerManager` against a Docker daemon in a container
+#![cfg(feature = "integration")]
+
+use pctrl_core::DockerHost;
+use pctrl_docker::DockerManager;
+use pctrl_integration::fixtures::Dind;
+
+fn manager(dind: &Dind) -> DockerManager {
+    let mut manager = DockerManager::new();
+    manager
+        .add_host(DockerHost {
+            id: "dind".to_string(),
+            name: "dind".to_string(),
+            url: dind.url.clone(),
+            tls_ca: None,
+            tls_cert: None,
+            tls_key: None,
+        })
+        .unwrap();
+    manager
+}
+
+#[tokio::test]
+async fn test_list_stop_start_and_exec() {
+    let dind = Dind::start();
+    let id = dind.run_probe("probe");
+    let docker = manager(&dind);
+    docker.health_check("dind").await.unwrap();
+
+    let containers = docker.list_containers("dind").await.unwrap();
+    let probe = containers
+        .iter()
+        .find(|c| c.name == "probe")
+        .expect("probe listed");
+    assert_eq!(probe.id, id);
+    assert_eq!(probe.state, "running");
+
+    docker.stop_container("dind", "probe").await.unwrap();
+    let state = docker.container_state("dind", "probe").await.unwrap();
+    assert_eq!(state.status, "exited");
+
+    docker.start_container("dind", "probe").await.unwrap();
+    let state = docker.container_state("dind", "probe").await.unwrap();
+    assert_eq!(state.status, "running");
+
+    let output = docker
+        .exec_in_container("dind", "probe", "echo hello")
+        .await
+        .unwrap();
+    assert_eq!(output.trim(), "hello");
+}
+
+#[tokio::test]
+async fn test_unknown_containers_are_errors() {
+    let dind = Dind::start();
+    let docker = manager(&dind);
+    assert!(docker.start_container("dind", "missing").await.is_err());
+    assert!(docker.container_state("dind", "missing").await.is_err());
+}
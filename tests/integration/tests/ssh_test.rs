@@ -0,0 +1,294 @@
+//! `SshManager` against a real sshd: key auth, exit codes, stderr, SFTP
+//! and spec detection
+#![cfg(feature = "integration")]
+
+use pctrl_core::transfer::Verified;
+use pctrl_integration::fixtures::Sshd;
+use pctrl_ssh::{ConnectOptions, CopyOptions, SshManager, TransferOptions};
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+#[test]
+fn test_key_auth_and_command_output() {
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+    ssh.test_connection("sshd", None).unwrap();
+    assert_eq!(ssh.execute_command("sshd", "whoami").unwrap(), "pctrl\n");
+
+    // A key the server doesn't know is refused
+    let mut stranger = sshd.connection();
+    stranger.username = "root".to_string();
+    let mut manager = SshManager::new();
+    manager.add_connection(stranger);
+    let Err(err) = manager.connect("sshd") else {
+        panic!("logged in as root with the fixture key");
+    };
+    assert!(err.to_string().contains("authentication failed"), "{}", err);
+}
+
+#[test]
+fn test_exit_codes_and_stderr_are_captured() {
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+
+    let (output, code) = ssh
+        .execute_script_with_password("sshd", "echo out\necho err >&2\nexit 3", None)
+        .unwrap();
+    assert_eq!(code, 3);
+    // Merged in the order it was written
+    assert_eq!(output, "out\nerr\n");
+
+    let session = ssh.connect("sshd").unwrap();
+    let mut streamed = String::new();
+    let (output, code) = SshManager::execute_streaming(
+        &session,
+        "echo first; ls /nonexistent",
+        None,
+        &mut |chunk| streamed.push_str(chunk),
+    )
+    .unwrap();
+    assert_ne!(code, 0);
+    assert_eq!(output, streamed);
+    assert!(output.contains("first"), "{}", output);
+    assert!(output.contains("nonexistent"), "{}", output);
+}
+
+#[test]
+fn test_streamed_command_outlives_session_timeout() {
+    let sshd = Sshd::start();
+    let mut ssh = SshManager::with_options(ConnectOptions {
+        timeout: Some(Duration::from_secs(1)),
+        ..ConnectOptions::default()
+    });
+    ssh.add_connection(sshd.connection());
+    let session = ssh.connect("sshd").unwrap();
+
+    // Silent for longer than the session timeout, like a slow build
+    let (output, code) =
+        SshManager::execute_streaming(&session, "sleep 3; echo built", None, &mut |_| {}).unwrap();
+    assert_eq!((output.as_str(), code), ("built\n", 0));
+
+    let err = SshManager::execute_streaming(
+        &session,
+        "sleep 5; echo late",
+        Some(Duration::from_secs(2)),
+        &mut |_| {},
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("No output for 2s"), "{}", err);
+}
+
+#[test]
+fn test_sftp_round_trip() {
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+    let dir = tempfile::tempdir().unwrap();
+    let data: Vec<u8> = (0..500_000u32).map(|i| (i * 13 % 251) as u8).collect();
+    let local = dir.path().join("upload.bin");
+    std::fs::write(&local, &data).unwrap();
+    let options = TransferOptions {
+        limit: None,
+        checksum: true,
+        fresh: true,
+    };
+
+    let report = ssh
+        .upload("sshd", &local, "/tmp/round-trip.bin", options, &mut |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.total, data.len() as u64);
+    assert_eq!(report.verified, Verified::Checksum);
+    assert_eq!(
+        ssh.execute_command("sshd", "wc -c < /tmp/round-trip.bin")
+            .unwrap()
+            .trim(),
+        data.len().to_string()
+    );
+
+    let copy = dir.path().join("download.bin");
+    ssh.download("sshd", "/tmp/round-trip.bin", &copy, options, &mut |_| {
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(std::fs::read(&copy).unwrap(), data);
+}
+
+#[test]
+fn test_sftp_copies_trees_with_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode =
+        |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+    let dir = tempfile::tempdir().unwrap();
+    let tree = dir.path().join("site");
+    std::fs::create_dir_all(tree.join("conf")).unwrap();
+    std::fs::write(tree.join("index.html"), "hello").unwrap();
+    std::fs::write(tree.join("conf/secret.env"), "TOKEN=1").unwrap();
+    std::fs::set_permissions(
+        tree.join("conf/secret.env"),
+        std::fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    let recursive = CopyOptions {
+        recursive: true,
+        overwrite: false,
+    };
+    let mut bytes = Vec::new();
+
+    // Directories need recursive
+    let err = ssh
+        .upload_file(
+            "sshd",
+            &tree,
+            "/tmp/site",
+            None,
+            CopyOptions::default(),
+            &mut |_| ControlFlow::Continue(()),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("is a directory"), "{}", err);
+
+    let report = ssh
+        .upload_file("sshd", &tree, "/tmp/site", None, recursive, &mut |p| {
+            bytes.push((p.done, p.total));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.files, 2);
+    assert_eq!(report.bytes, 12);
+    assert_eq!(bytes.last(), Some(&(12, 12)));
+    assert_eq!(
+        ssh.execute_command("sshd", "stat -c %a /tmp/site/conf/secret.env")
+            .unwrap()
+            .trim(),
+        "600"
+    );
+
+    // Nothing is replaced unless asked
+    let one = tree.join("index.html");
+    let err = ssh
+        .upload_file(
+            "sshd",
+            &one,
+            "/tmp/site/index.html",
+            Some(0o640),
+            CopyOptions::default(),
+            &mut |_| ControlFlow::Continue(()),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{}", err);
+    let overwrite = CopyOptions {
+        recursive: false,
+        overwrite: true,
+    };
+    ssh.upload_file(
+        "sshd",
+        &one,
+        "/tmp/site/index.html",
+        Some(0o640),
+        overwrite,
+        &mut |_| ControlFlow::Continue(()),
+    )
+    .unwrap();
+    assert_eq!(
+        ssh.execute_command("sshd", "stat -c %a /tmp/site/index.html")
+            .unwrap()
+            .trim(),
+        "640"
+    );
+
+    let copy = dir.path().join("copy");
+    ssh.download_file("sshd", "/tmp/site", &copy, recursive, &mut |_| {
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(copy.join("conf/secret.env")).unwrap(),
+        "TOKEN=1"
+    );
+    assert_eq!(mode(&copy.join("conf/secret.env")), 0o600);
+    assert_eq!(mode(&copy.join("index.html")), 0o640);
+    let again = ssh.download_file("sshd", "/tmp/site", &copy, recursive, &mut |_| {
+        ControlFlow::Continue(())
+    });
+    assert!(again.is_err());
+
+    ssh.execute_command("sshd", "rm -rf /tmp/site").unwrap();
+}
+
+#[test]
+fn test_sftp_copies_read_only_directories() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode =
+        |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let set_mode = |path: &std::path::Path, mode: u32| {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap()
+    };
+
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+    let dir = tempfile::tempdir().unwrap();
+    let tree = dir.path().join("release");
+    std::fs::create_dir_all(tree.join("static")).unwrap();
+    std::fs::write(tree.join("static/app.css"), "body{}").unwrap();
+    set_mode(&tree.join("static"), 0o555);
+    let recursive = CopyOptions {
+        recursive: true,
+        overwrite: false,
+    };
+
+    // The file lands in the directory before it turns read-only
+    let report = ssh
+        .upload_file("sshd", &tree, "/tmp/release", None, recursive, &mut |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.files, 1);
+    assert_eq!(
+        ssh.execute_command("sshd", "stat -c %a /tmp/release/static")
+            .unwrap()
+            .trim(),
+        "555"
+    );
+    assert_eq!(
+        ssh.execute_command("sshd", "cat /tmp/release/static/app.css")
+            .unwrap(),
+        "body{}"
+    );
+
+    let copy = dir.path().join("copy");
+    ssh.download_file("sshd", "/tmp/release", &copy, recursive, &mut |_| {
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(mode(&copy.join("static")), 0o555);
+    assert_eq!(
+        std::fs::read_to_string(copy.join("static/app.css")).unwrap(),
+        "body{}"
+    );
+
+    for path in [tree.join("static"), copy.join("static")] {
+        set_mode(&path, 0o755);
+    }
+    ssh.execute_command("sshd", "chmod -R u+w /tmp/release && rm -rf /tmp/release")
+        .unwrap();
+}
+
+#[test]
+fn test_detect_server_specs() {
+    let sshd = Sshd::start();
+    let ssh = sshd.manager();
+    let nproc: u8 = ssh
+        .execute_command("sshd", "nproc")
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap();
+
+    let specs = ssh.detect_server_specs("sshd", None).unwrap();
+    assert_eq!(specs.cpu_cores, Some(nproc));
+    assert!(specs.ram_gb.is_some(), "{:?}", specs);
+}